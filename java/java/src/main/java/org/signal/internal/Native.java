@@ -7,6 +7,8 @@
 
 package org.signal.client.internal;
 
+import org.signal.libsignal.AuditHook;
+import org.signal.libsignal.metadata.certificate.SenderCertificateSource;
 import org.whispersystems.libsignal.protocol.CiphertextMessage;
 import org.whispersystems.libsignal.state.IdentityKeyStore;
 import org.whispersystems.libsignal.state.SessionStore;
@@ -88,6 +90,62 @@ public final class Native {
   public static native byte[] Aes256GcmSiv_Encrypt(long aesGcmSiv, byte[] ptext, byte[] nonce, byte[] associatedData);
   public static native long Aes256GcmSiv_New(byte[] key);
 
+  public static native void Aes256KeyWrap_Destroy(long handle);
+  public static native long Aes256KeyWrap_New(byte[] key);
+  public static native byte[] Aes256KeyWrap_Unwrap(long kw, byte[] wrapped);
+  public static native byte[] Aes256KeyWrap_Wrap(long kw, byte[] key);
+
+  public static native long Argon2idParams_Calibrate(int targetDurationMs, int memoryKib, int parallelism);
+  public static native byte[] Argon2idParams_Derive(long params, byte[] password, byte[] salt, int outputLength);
+  public static native void Argon2idParams_Destroy(long handle);
+  public static native int Argon2idParams_GetIterations(long obj);
+  public static native int Argon2idParams_GetMemoryKib(long obj);
+  public static native int Argon2idParams_GetParallelism(long obj);
+  public static native long Argon2idParams_New(int memoryKib, int iterations, int parallelism);
+
+  public static native void AttachmentPointer_Destroy(long handle);
+  public static native long AttachmentPointer_New(int cdnNumber, String cdnKey, String contentType, byte[] key, byte[] digest, int size, String fileName, String caption, int width, int height, long uploadTimestamp);
+
+  public static native void AuditHook_Set(AuditHook hook);
+
+  public static native byte[] BackupBuilder_AddAccountData(long builder, byte[] profileKey, String username, String givenName, String familyName);
+  public static native byte[] BackupBuilder_AddChat(long builder, long id, long recipientId, boolean archived, int pinnedOrder, long expirationTimerMs);
+  public static native byte[] BackupBuilder_AddChatItem(long builder, long chatId, long authorId, long dateSent, String text);
+  public static native byte[] BackupBuilder_AddContact(long builder, long id, String uuid, String e164);
+  public static native byte[] BackupBuilder_AddGroup(long builder, long id, byte[] masterKey);
+  public static native byte[] BackupBuilder_AddSelfRecipient(long builder, long id);
+  public static native void BackupBuilder_Destroy(long handle);
+  public static native byte[] BackupBuilder_Finish(long builder);
+  public static native long BackupBuilder_New(byte[] backupKey, boolean compressed, long backupTimeMs, long rng);
+
+  public static native void BridgeContext_Destroy(long handle);
+  public static native void BridgeContext_Enter(long context);
+  public static native void BridgeContext_Exit(long context);
+  public static native String BridgeContext_GetMetrics(long context);
+  public static native long BridgeContext_New(String label);
+  public static native void BridgeContext_ResetMetrics(long context);
+  public static native void BridgeContext_SetMaxLogLevel(long context, int level);
+
+  public static native byte[] CallLinkRootKey_DeriveEraKey(long key, String eraId);
+  public static native byte[] CallLinkRootKey_DeriveRoomId(long key);
+  public static native void CallLinkRootKey_Destroy(long handle);
+  public static native long CallLinkRootKey_FromBytes(byte[] bytes);
+  public static native long CallLinkRootKey_Generate(long rng);
+  public static native byte[] CallLinkRootKey_GetBytes(long key);
+  public static native long CallLinkRootKey_Parse(String text);
+  public static native String CallLinkRootKey_ToString(long key);
+
+  public static native void Content_Destroy(long handle);
+  public static native byte[] Content_GetSerialized(long content);
+  public static native long Content_NewReceiptMessage(int receiptType, byte[] timestamps);
+  public static native long Content_NewTypingMessage(long timestamp, boolean started, byte[] groupId);
+
+  public static native void Crc32_Destroy(long handle);
+  public static native int Crc32_Digest(byte[] input);
+  public static native int Crc32_Finalize(long crc);
+  public static native long Crc32_New();
+  public static native void Crc32_Update(long crc, byte[] input);
+
   public static native void CryptographicHash_Destroy(long handle);
   public static native byte[] CryptographicHash_Finalize(long hash);
   public static native long CryptographicHash_New(String algo);
@@ -100,28 +158,64 @@ public final class Native {
   public static native void CryptographicMac_Update(long mac, byte[] input);
   public static native void CryptographicMac_UpdateWithOffset(long mac, byte[] input, int offset, int len);
 
-  public static native byte[] DeviceTransfer_GenerateCertificate(byte[] privateKey, String name, int daysToExpire);
+  public static native void DataMessageBuilder_AddAttachment(long builder, long attachment);
+  public static native long DataMessageBuilder_Build(long builder);
+  public static native void DataMessageBuilder_Destroy(long handle);
+  public static native long DataMessageBuilder_New(long timestamp);
+  public static native void DataMessageBuilder_SetBody(long builder, String body);
+  public static native void DataMessageBuilder_SetExpireTimer(long builder, int seconds);
+  public static native void DataMessageBuilder_SetProfileKey(long builder, byte[] profileKey);
+  public static native void DataMessageBuilder_SetQuote(long builder, long id, String authorUuid, String text);
+
+  public static native String DecryptionMetrics_Get();
+  public static native void DecryptionMetrics_Reset();
+  public static native void DecryptionMetrics_SetEnabled(boolean enabled);
+
+  public static native void DeferredDecryptionResult_Destroy(long handle);
+  public static native byte[] DeferredDecryptionResult_GetMutationLog(long obj);
+  public static native byte[] DeferredDecryptionResult_GetPlaintext(long obj);
+
+  public static native byte[] DeviceTransfer_GenerateCertificate(byte[] privateKey, String name, int daysToExpire, long now);
   public static native byte[] DeviceTransfer_GeneratePrivateKey();
 
   public static native byte[] ECPrivateKey_Agree(long privateKey, long publicKey);
   public static native long ECPrivateKey_Deserialize(byte[] data);
   public static native void ECPrivateKey_Destroy(long handle);
-  public static native long ECPrivateKey_Generate();
+  public static native long ECPrivateKey_Generate(long rng);
   public static native long ECPrivateKey_GetPublicKey(long k);
+  public static native byte[] ECPrivateKey_Open(long key, byte[] sealed);
   public static native byte[] ECPrivateKey_Serialize(long obj);
-  public static native byte[] ECPrivateKey_Sign(long key, byte[] message);
+  public static native byte[] ECPrivateKey_Sign(long key, byte[] message, long rng);
 
   public static native int ECPublicKey_Compare(long key1, long key2);
   public static native long ECPublicKey_Deserialize(byte[] data, int offset);
   public static native void ECPublicKey_Destroy(long handle);
   public static native byte[] ECPublicKey_GetPublicKeyBytes(long obj);
+  public static native byte[] ECPublicKey_Seal(long key, byte[] plaintext, long rng);
   public static native byte[] ECPublicKey_Serialize(long obj);
   public static native boolean ECPublicKey_Verify(long key, byte[] message, byte[] signature);
 
+  public static native void FrameReader_Destroy(long handle);
+  public static native void FrameReader_Finish(long reader);
+  public static native boolean FrameReader_IsFinished(long reader);
+  public static native long FrameReader_New(byte[] backupKey);
+  public static native byte[] FrameReader_NextFrame(long reader);
+  public static native void FrameReader_Push(long reader, byte[] chunk);
+
+  public static native byte[] FrameWriter_Append(long writer, byte[] frame);
+  public static native void FrameWriter_Destroy(long handle);
+  public static native byte[] FrameWriter_Finish(long writer);
+  public static native long FrameWriter_New(byte[] backupKey, boolean compressed, long rng);
+
+  public static native byte[] GroupCall_DeriveEraKey(byte[] groupMasterKey, String eraId);
+
+  public static native int GroupCipher_ChainPosition(long senderKeyName, int distributionId, SenderKeyStore store, Object ctx);
   public static native byte[] GroupCipher_DecryptMessage(long senderKeyName, byte[] message, SenderKeyStore store, Object ctx);
-  public static native byte[] GroupCipher_EncryptMessage(long senderKeyName, byte[] message, SenderKeyStore store, Object ctx);
+  public static native long GroupCipher_DecryptMessageDeferred(long senderKeyName, byte[] message, SenderKeyStore store, Object ctx);
+  public static native byte[] GroupCipher_EncryptMessage(long senderKeyName, byte[] message, SenderKeyStore store, long rng, Object ctx);
+  public static native void GroupCipher_FastForward(long senderKeyName, int distributionId, int toIteration, SenderKeyStore store, Object ctx);
 
-  public static native long GroupSessionBuilder_CreateSenderKeyDistributionMessage(long senderKeyName, SenderKeyStore store, Object ctx);
+  public static native long GroupSessionBuilder_CreateSenderKeyDistributionMessage(long senderKeyName, SenderKeyStore store, long rng, Object ctx);
   public static native void GroupSessionBuilder_ProcessSenderKeyDistributionMessage(long senderKeyName, long senderKeyDistributionMessage, SenderKeyStore store, Object ctx);
 
   public static native byte[] HKDF_DeriveSecrets(int outputLength, int version, byte[] ikm, byte[] label, byte[] salt);
@@ -129,17 +223,83 @@ public final class Native {
   public static native long[] IdentityKeyPair_Deserialize(byte[] data);
   public static native byte[] IdentityKeyPair_Serialize(long publicKey, long privateKey);
 
+  public static native void IncrementalMacValidator_Destroy(long handle);
+  public static native boolean IncrementalMacValidator_Finalize(long validator);
+  public static native long IncrementalMacValidator_GetValidatedLength(long obj);
+  public static native long IncrementalMacValidator_New(byte[] key, int chunkSize, byte[] digest);
+  public static native boolean IncrementalMacValidator_Update(long validator, byte[] data);
+
+  public static native void KeyTransparencyMonitorResult_Destroy(long handle);
+  public static native byte[] KeyTransparencyMonitorResult_GetMonitoringData(long obj);
+  public static native byte[] KeyTransparencyMonitorResult_GetTreeHead(long obj);
+  public static native boolean KeyTransparencyMonitorResult_IsUnchanged(long obj);
+
+  public static native void KeyTransparencySearchResult_Destroy(long handle);
+  public static native byte[] KeyTransparencySearchResult_GetMonitoringData(long obj);
+  public static native byte[] KeyTransparencySearchResult_GetTreeHead(long obj);
+  public static native byte[] KeyTransparencySearchResult_GetValue(long obj);
+
+  public static native byte[] KeyTransparency_VerifyDistinguished(long logKey, byte[] response, byte[] lastTreeHead);
+  public static native long KeyTransparency_VerifyMonitor(long logKey, byte[] searchKey, byte[] response, byte[] lastTreeHead, byte[] monitoringData);
+  public static native long KeyTransparency_VerifySearch(long logKey, byte[] searchKey, byte[] response, byte[] lastTreeHead);
+
+  public static native long Limits_GetMaxBufferLength();
+  public static native void Limits_SetMaxBufferLength(long limit);
+
   public static native void Logger_Initialize(int maxLevel, Class loggerClass);
   public static native void Logger_SetMaxLevel(int maxLevel);
 
+  public static native void MutationLog_Apply(byte[] mutationLog, SessionStore sessionStore, IdentityKeyStore identityStore, PreKeyStore prekeyStore, SignedPreKeyStore signedPrekeyStore, SenderKeyStore senderKeyStore, Object ctx);
+
+  public static native String Native_ListFunctions();
+  public static native void Native_RequireAtLeast(int abiRevision);
+  public static native String Native_SelfTest();
+  public static native void Native_TestPanic(String message);
+  public static native String Native_VersionInfo();
+
+  public static native void NotificationCommitSet_Apply(byte[] commitSet, SessionStore sessionStore, IdentityKeyStore identityStore, PreKeyStore prekeyStore, Object ctx);
+
+  public static native long NotificationDecrypt_Decrypt(byte[] message, long trustRoot, long timestamp, String localE164, String localUuid, int localDeviceId, SessionStore sessionStore, IdentityKeyStore identityStore, PreKeyStore prekeyStore, SignedPreKeyStore signedPrekeyStore, long rng, Object ctx);
+
+  public static native void NotificationDecryptionResult_Destroy(long handle);
+  public static native byte[] NotificationDecryptionResult_GetCommitSet(long result);
+  public static native int NotificationDecryptionResult_GetDeviceId(long result);
+  public static native byte[] NotificationDecryptionResult_GetMessage(long result);
+  public static native String NotificationDecryptionResult_GetSenderE164(long result);
+  public static native String NotificationDecryptionResult_GetSenderUuid(long result);
+
   public static native void NumericFingerprintGenerator_Destroy(long handle);
   public static native String NumericFingerprintGenerator_GetDisplayString(long obj);
   public static native byte[] NumericFingerprintGenerator_GetScannableEncoding(long obj);
   public static native long NumericFingerprintGenerator_New(int iterations, int version, byte[] localIdentifier, byte[] localKey, byte[] remoteIdentifier, byte[] remoteKey);
 
+  public static native long OutgoingEnvelope_Deserialize(byte[] data);
+  public static native void OutgoingEnvelope_Destroy(long handle);
+  public static native int OutgoingEnvelope_GetContentHint(long obj);
+  public static native long OutgoingEnvelope_GetDestination(long obj);
+  public static native byte[] OutgoingEnvelope_GetPayload(long obj);
+  public static native int OutgoingEnvelope_GetSerializedVersion(long obj);
+  public static native long OutgoingEnvelope_GetTimestamp(long obj);
+  public static native long OutgoingEnvelope_New(long destination, long timestamp, int contentHint, byte[] payload);
+  public static native byte[] OutgoingEnvelope_Serialize(long obj);
+
+  public static native long PaddingPolicy_Bucketed(int bucketSize);
+  public static native void PaddingPolicy_Destroy(long handle);
+  public static native long PaddingPolicy_None();
+  public static native byte[] PaddingPolicy_Pad(long policy, byte[] message);
+  public static native int PaddingPolicy_PaddedLength(long policy, int len);
+  public static native long PaddingPolicy_Padme();
+  public static native byte[] PaddingPolicy_Unpad(long policy, byte[] padded);
+
+  public static native byte[] PaymentAddress_Sign(long identityPublicKey, long identityPrivateKey, byte[] address, long rng);
+  public static native boolean PaymentAddress_Verify(long identityKey, byte[] address, byte[] signature);
+
+  public static native String PreKeyBundle_DebugDump(long obj, boolean includeSecrets);
   public static native void PreKeyBundle_Destroy(long handle);
   public static native int PreKeyBundle_GetDeviceId(long obj);
   public static native long PreKeyBundle_GetIdentityKey(long p);
+  public static native int PreKeyBundle_GetMaxSessionVersion(long obj);
+  public static native int PreKeyBundle_GetMinSessionVersion(long obj);
   public static native int PreKeyBundle_GetPreKeyId(long obj);
   public static native long PreKeyBundle_GetPreKeyPublic(long obj);
   public static native int PreKeyBundle_GetRegistrationId(long obj);
@@ -147,6 +307,7 @@ public final class Native {
   public static native long PreKeyBundle_GetSignedPreKeyPublic(long obj);
   public static native byte[] PreKeyBundle_GetSignedPreKeySignature(long obj);
   public static native long PreKeyBundle_New(int registrationId, int deviceId, int prekeyId, long prekey, int signedPrekeyId, long signedPrekey, byte[] signedPrekeySignature, long identityKey);
+  public static native long PreKeyBundle_WithSessionVersions(long bundle, int minVersion, int maxVersion);
 
   public static native long PreKeyRecord_Deserialize(byte[] data);
   public static native void PreKeyRecord_Destroy(long handle);
@@ -154,8 +315,10 @@ public final class Native {
   public static native long PreKeyRecord_GetPrivateKey(long obj);
   public static native long PreKeyRecord_GetPublicKey(long obj);
   public static native byte[] PreKeyRecord_GetSerialized(long obj);
+  public static native int PreKeyRecord_GetSerializedVersion(long obj);
   public static native long PreKeyRecord_New(int id, long pubKey, long privKey);
 
+  public static native String PreKeySignalMessage_DebugDump(long obj, boolean includeSecrets);
   public static native long PreKeySignalMessage_Deserialize(byte[] data);
   public static native void PreKeySignalMessage_Destroy(long handle);
   public static native byte[] PreKeySignalMessage_GetBaseKey(long m);
@@ -168,15 +331,80 @@ public final class Native {
   public static native int PreKeySignalMessage_GetVersion(long obj);
   public static native long PreKeySignalMessage_New(int messageVersion, int registrationId, int preKeyId, int signedPreKeyId, long baseKey, long identityKey, long signalMessage);
 
+  public static native byte[] ProfileKey_BlindServiceId(byte[] profileKey, String serviceId);
+  public static native byte[] ProfileKey_DeriveAccessKey(byte[] profileKey);
+
   public static native void ProtocolAddress_Destroy(long handle);
   public static native int ProtocolAddress_DeviceId(long obj);
   public static native String ProtocolAddress_Name(long obj);
   public static native long ProtocolAddress_New(String name, int deviceId);
 
+  public static native byte[] ProvisioningCipher_Decrypt(long cipher, byte[] envelope);
+  public static native void ProvisioningCipher_Destroy(long handle);
+  public static native byte[] ProvisioningCipher_Encrypt(long theirPublicKey, byte[] message, long rng);
+  public static native long ProvisioningCipher_GetPublicKey(long cipher);
+  public static native long ProvisioningCipher_New(long rng);
+
+  public static native long ProvisioningUuid_Deserialize(byte[] data);
+  public static native void ProvisioningUuid_Destroy(long handle);
+  public static native byte[] ProvisioningUuid_GetSerialized(long obj);
+  public static native String ProvisioningUuid_GetUuid(long obj);
+  public static native long ProvisioningUuid_New(String uuid);
+
+  public static native void RegistrationOutcome_Destroy(long handle);
+  public static native long RegistrationOutcome_FromResponse(int status, byte[] body, String retryAfter);
+  public static native String RegistrationOutcome_GetAci(long outcome);
+  public static native long RegistrationOutcome_GetLockTimeRemainingMs(long outcome);
+  public static native String RegistrationOutcome_GetNumber(long outcome);
+  public static native String RegistrationOutcome_GetPni(long outcome);
+  public static native String RegistrationOutcome_GetSvrPassword(long outcome);
+  public static native String RegistrationOutcome_GetSvrUsername(long outcome);
+  public static native boolean RegistrationOutcome_IsLocked(long outcome);
+
+  public static native long RegistrationRequest_CreateSession(String number, String pushToken, boolean pushTokenIsApn, String mcc, String mnc);
+  public static native void RegistrationRequest_Destroy(long handle);
+  public static native byte[] RegistrationRequest_GetBody(long obj);
+  public static native String RegistrationRequest_GetMethod(long obj);
+  public static native String RegistrationRequest_GetPath(long obj);
+  public static native long RegistrationRequest_GetSession(String sessionId);
+  public static native long RegistrationRequest_RegisterAccount(String sessionId, int registrationId, boolean fetchesMessages, String registrationLock);
+  public static native long RegistrationRequest_RequestVerificationCode(String sessionId, boolean voice, String client);
+  public static native long RegistrationRequest_SubmitVerificationCode(String sessionId, String code);
+  public static native long RegistrationRequest_UpdateSession(String sessionId, String pushChallenge, String captcha);
+
+  public static native void RegistrationSession_Destroy(long handle);
+  public static native long RegistrationSession_FromResponse(int status, byte[] body, String retryAfter);
+  public static native boolean RegistrationSession_GetAllowedToRequestCode(long obj);
+  public static native String RegistrationSession_GetId(long obj);
+  public static native int RegistrationSession_GetNextCallSeconds(long obj);
+  public static native int RegistrationSession_GetNextSmsSeconds(long obj);
+  public static native int RegistrationSession_GetNextVerificationAttemptSeconds(long obj);
+  public static native boolean RegistrationSession_GetVerified(long obj);
+  public static native boolean RegistrationSession_RequiresCaptcha(long session);
+  public static native boolean RegistrationSession_RequiresPushChallenge(long session);
+
+  public static native String Registration_LockToken(byte[] masterKey);
+
+  public static native long Rng_Clone(long handle);
+  public static native void Rng_Destroy(long handle);
+  public static native long Rng_NewSeeded(byte[] seed);
+  public static native long Rng_NewSystem();
+
   public static native boolean ScannableFingerprint_Compare(byte[] fprint1, byte[] fprint2);
 
+  public static native long ScryptParams_Calibrate(int targetDurationMs, int r, int p);
+  public static native byte[] ScryptParams_Derive(long params, byte[] password, byte[] salt, int outputLength);
+  public static native void ScryptParams_Destroy(long handle);
+  public static native int ScryptParams_GetLogN(long obj);
+  public static native int ScryptParams_GetP(long obj);
+  public static native int ScryptParams_GetR(long obj);
+  public static native long ScryptParams_New(int logN, int r, int p);
+
   public static native long SealedSessionCipher_DecryptToUsmc(byte[] ctext, IdentityKeyStore identityStore, Object ctx);
-  public static native byte[] SealedSessionCipher_Encrypt(long destination, long senderCert, byte[] ptext, SessionStore sessionStore, IdentityKeyStore identityKeyStore, Object ctx);
+  public static native byte[] SealedSessionCipher_Encrypt(long destination, long senderCert, byte[] ptext, SessionStore sessionStore, IdentityKeyStore identityKeyStore, long rng, Object ctx);
+  public static native byte[] SealedSessionCipher_EncryptWithProvider(long destination, long provider, SenderCertificateSource certificateSource, long timestamp, byte[] ptext, SessionStore sessionStore, IdentityKeyStore identityKeyStore, long rng, Object ctx);
+
+  public static native String SecuritySnapshot_Create(long address, long senderKeyName, SessionStore sessionStore, IdentityKeyStore identityKeyStore, SenderKeyStore senderKeyStore, Object ctx);
 
   public static native long SenderCertificate_Deserialize(byte[] data);
   public static native void SenderCertificate_Destroy(long handle);
@@ -189,9 +417,15 @@ public final class Native {
   public static native byte[] SenderCertificate_GetSerialized(long obj);
   public static native long SenderCertificate_GetServerCertificate(long cert);
   public static native byte[] SenderCertificate_GetSignature(long obj);
-  public static native long SenderCertificate_New(String senderUuid, String senderE164, int senderDeviceId, long senderKey, long expiration, long signerCert, long signerKey);
+  public static native long SenderCertificate_New(String senderUuid, String senderE164, int senderDeviceId, long senderKey, long expiration, long signerCert, long signerKey, long rng);
   public static native boolean SenderCertificate_Validate(long cert, long key, long time);
+  public static native boolean SenderCertificate_ValidateWithGracePeriod(long cert, long key, long time, long gracePeriod);
 
+  public static native void SenderCertificateProvider_Destroy(long handle);
+  public static native long SenderCertificateProvider_Get(long provider, long validUntil, SenderCertificateSource certificateSource, Object ctx);
+  public static native long SenderCertificateProvider_New(long certificate, long freshnessWindow);
+
+  public static native String SenderKeyDistributionMessage_DebugDump(long obj, boolean includeSecrets);
   public static native long SenderKeyDistributionMessage_Deserialize(byte[] data);
   public static native void SenderKeyDistributionMessage_Destroy(long handle);
   public static native byte[] SenderKeyDistributionMessage_GetChainKey(long obj);
@@ -201,13 +435,15 @@ public final class Native {
   public static native byte[] SenderKeyDistributionMessage_GetSignatureKey(long m);
   public static native long SenderKeyDistributionMessage_New(int keyId, int iteration, byte[] chainkey, long pk);
 
+  public static native String SenderKeyMessage_DebugDump(long obj, boolean includeSecrets);
   public static native long SenderKeyMessage_Deserialize(byte[] data);
   public static native void SenderKeyMessage_Destroy(long handle);
   public static native byte[] SenderKeyMessage_GetCipherText(long obj);
   public static native int SenderKeyMessage_GetIteration(long obj);
   public static native int SenderKeyMessage_GetKeyId(long obj);
+  public static native int SenderKeyMessage_GetMessageVersion(long obj);
   public static native byte[] SenderKeyMessage_GetSerialized(long obj);
-  public static native long SenderKeyMessage_New(int keyId, int iteration, byte[] ciphertext, long pk);
+  public static native long SenderKeyMessage_New(int keyId, int iteration, byte[] ciphertext, long pk, long rng);
   public static native boolean SenderKeyMessage_VerifySignature(long skm, long pubkey);
 
   public static native void SenderKeyName_Destroy(long handle);
@@ -216,9 +452,16 @@ public final class Native {
   public static native String SenderKeyName_GetSenderName(long obj);
   public static native long SenderKeyName_New(String groupId, String senderName, int senderDeviceId);
 
+  public static native String SenderKeyRecord_DebugDump(long obj, boolean includeSecrets);
   public static native long SenderKeyRecord_Deserialize(byte[] data);
   public static native void SenderKeyRecord_Destroy(long handle);
+  public static native boolean SenderKeyRecord_ForgetDistribution(long record, long member);
   public static native byte[] SenderKeyRecord_GetSerialized(long obj);
+  public static native int SenderKeyRecord_GetSerializedVersion(long obj);
+  public static native void SenderKeyRecord_MarkDistributed(long record, long member);
+  public static native int SenderKeyRecord_MaxMessageKeysPerState();
+  public static native int SenderKeyRecord_MaxStates();
+  public static native boolean SenderKeyRecord_NeedsDistribution(long record, long member);
   public static native long SenderKeyRecord_New();
 
   public static native long ServerCertificate_Deserialize(byte[] data);
@@ -228,15 +471,31 @@ public final class Native {
   public static native int ServerCertificate_GetKeyId(long obj);
   public static native byte[] ServerCertificate_GetSerialized(long obj);
   public static native byte[] ServerCertificate_GetSignature(long obj);
-  public static native long ServerCertificate_New(int keyId, long serverKey, long trustRoot);
-
-  public static native void SessionBuilder_ProcessPreKeyBundle(long bundle, long protocolAddress, SessionStore sessionStore, IdentityKeyStore identityKeyStore, Object ctx);
-
-  public static native byte[] SessionCipher_DecryptPreKeySignalMessage(long message, long protocolAddress, SessionStore sessionStore, IdentityKeyStore identityKeyStore, PreKeyStore prekeyStore, SignedPreKeyStore signedPrekeyStore, Object ctx);
-  public static native byte[] SessionCipher_DecryptSignalMessage(long message, long protocolAddress, SessionStore sessionStore, IdentityKeyStore identityKeyStore, Object ctx);
+  public static native long ServerCertificate_New(int keyId, long serverKey, long trustRoot, long rng);
+
+  public static native void ServiceIdSet_AddFixedWidthBinary(long set, byte[] list);
+  public static native boolean ServiceIdSet_Contains(long set, byte[] serviceId);
+  public static native void ServiceIdSet_Destroy(long handle);
+  public static native long ServiceIdSet_Difference(long set, long other);
+  public static native byte[] ServiceIdSet_GetPage(long set, int offset, int limit);
+  public static native long ServiceIdSet_Intersection(long set, long other);
+  public static native int ServiceIdSet_Len(long set);
+  public static native long ServiceIdSet_New();
+
+  public static native void SessionBuilder_ProcessPreKeyBundle(long bundle, long protocolAddress, SessionStore sessionStore, IdentityKeyStore identityKeyStore, long rng, Object ctx);
+
+  public static native byte[] SessionCipher_DecryptPreKeySignalMessage(long message, long protocolAddress, SessionStore sessionStore, IdentityKeyStore identityKeyStore, PreKeyStore prekeyStore, SignedPreKeyStore signedPrekeyStore, long rng, Object ctx);
+  public static native long SessionCipher_DecryptPreKeySignalMessageDeferred(long message, long protocolAddress, SessionStore sessionStore, IdentityKeyStore identityKeyStore, PreKeyStore prekeyStore, SignedPreKeyStore signedPrekeyStore, long rng, Object ctx);
+  public static native byte[] SessionCipher_DecryptPreKeySignalMessageWithIdentityOverride(long message, long protocolAddress, long approvedIdentityKey, SessionStore sessionStore, IdentityKeyStore identityKeyStore, PreKeyStore prekeyStore, SignedPreKeyStore signedPrekeyStore, long rng, Object ctx);
+  public static native byte[] SessionCipher_DecryptSignalMessage(long message, long protocolAddress, SessionStore sessionStore, IdentityKeyStore identityKeyStore, long rng, Object ctx);
+  public static native long SessionCipher_DecryptSignalMessageDeferred(long message, long protocolAddress, SessionStore sessionStore, IdentityKeyStore identityKeyStore, long rng, Object ctx);
+  public static native byte[] SessionCipher_DecryptSignalMessageWithIdentityOverride(long message, long protocolAddress, long approvedIdentityKey, SessionStore sessionStore, IdentityKeyStore identityKeyStore, long rng, Object ctx);
   public static native CiphertextMessage SessionCipher_EncryptMessage(byte[] message, long protocolAddress, SessionStore sessionStore, IdentityKeyStore identityKeyStore);
 
+  public static native Object[] SessionCipher_EncryptMessageBatch(byte[] message, byte[] recipients, SessionStore sessionStore, IdentityKeyStore identityKeyStore);
+  public static native CiphertextMessage SessionCipher_EncryptMessageWithIdentityOverride(byte[] message, long protocolAddress, long approvedIdentityKey, SessionStore sessionStore, IdentityKeyStore identityKeyStore);
   public static native void SessionRecord_ArchiveCurrentState(long sessionRecord);
+  public static native String SessionRecord_DebugDump(long obj, boolean includeSecrets);
   public static native long SessionRecord_Deserialize(byte[] data);
   public static native void SessionRecord_Destroy(long handle);
   public static native long SessionRecord_FromSingleSessionState(byte[] sessionState);
@@ -247,18 +506,36 @@ public final class Native {
   public static native byte[] SessionRecord_GetRemoteIdentityKeyPublic(long obj);
   public static native int SessionRecord_GetRemoteRegistrationId(long obj);
   public static native byte[] SessionRecord_GetSenderChainKeyValue(long obj);
+  public static native int SessionRecord_GetSerializedVersion(long obj);
   public static native int SessionRecord_GetSessionVersion(long s);
   public static native boolean SessionRecord_HasSenderChain(long obj);
-  public static native long SessionRecord_InitializeAliceSession(long identityKeyPrivate, long identityKeyPublic, long basePrivate, long basePublic, long theirIdentityKey, long theirSignedPrekey, long theirRatchetKey);
+  public static native long SessionRecord_InitializeAliceSession(long identityKeyPrivate, long identityKeyPublic, long basePrivate, long basePublic, long theirIdentityKey, long theirSignedPrekey, long theirRatchetKey, long rng);
   public static native long SessionRecord_InitializeBobSession(long identityKeyPrivate, long identityKeyPublic, long signedPrekeyPrivate, long signedPrekeyPublic, long ephPrivate, long ephPublic, long theirIdentityKey, long theirBaseKey);
+  public static native int SessionRecord_MaxArchivedStates();
+  public static native int SessionRecord_MaxMessageKeysPerChain();
+  public static native int SessionRecord_MaxReceiverChains();
   public static native long SessionRecord_NewFresh();
   public static native byte[] SessionRecord_Serialize(long obj);
 
+  public static native void Sha256_Destroy(long handle);
+  public static native byte[] Sha256_Digest(byte[] input);
+  public static native byte[] Sha256_Finalize(long sha256);
+  public static native long Sha256_New();
+  public static native void Sha256_Update(long sha256, byte[] input);
+
+  public static native void Sha512_Destroy(long handle);
+  public static native byte[] Sha512_Digest(byte[] input);
+  public static native byte[] Sha512_Finalize(long sha512);
+  public static native long Sha512_New();
+  public static native void Sha512_Update(long sha512, byte[] input);
+
+  public static native String SignalMessage_DebugDump(long obj, boolean includeSecrets);
   public static native long SignalMessage_Deserialize(byte[] data);
   public static native void SignalMessage_Destroy(long handle);
   public static native byte[] SignalMessage_GetBody(long obj);
   public static native int SignalMessage_GetCounter(long obj);
   public static native int SignalMessage_GetMessageVersion(long obj);
+  public static native int SignalMessage_GetPreviousCounter(long obj);
   public static native byte[] SignalMessage_GetSenderRatchetKey(long m);
   public static native byte[] SignalMessage_GetSerialized(long obj);
   public static native long SignalMessage_New(int messageVersion, byte[] macKey, long senderRatchetKey, int counter, int previousCounter, byte[] ciphertext, long senderIdentityKey, long receiverIdentityKey);
@@ -274,6 +551,28 @@ public final class Native {
   public static native long SignedPreKeyRecord_GetTimestamp(long obj);
   public static native long SignedPreKeyRecord_New(int id, long timestamp, long pubKey, long privKey, byte[] signature);
 
+  public static native byte[] StickerCipher_Decrypt(long cipher, byte[] data);
+  public static native void StickerCipher_Destroy(long handle);
+  public static native long StickerCipher_New(byte[] packKey);
+
+  public static native void StickerDecryption_Destroy(long handle);
+  public static native byte[] StickerDecryption_Finalize(long decryption);
+  public static native long StickerDecryption_New(long cipher);
+  public static native byte[] StickerDecryption_Update(long decryption, byte[] data);
+
+  public static native byte[] StorageServiceKey_DecryptManifest(long key, long version, byte[] ciphertext);
+  public static native byte[] StorageServiceKey_DecryptRecord(long key, byte[] rawId, byte[] ciphertext);
+  public static native void StorageServiceKey_Destroy(long handle);
+  public static native byte[] StorageServiceKey_EncryptManifest(long key, long version, byte[] plaintext, long rng);
+  public static native byte[] StorageServiceKey_EncryptRecord(long key, byte[] rawId, byte[] plaintext, long rng);
+  public static native long StorageServiceKey_FromMasterKey(byte[] masterKey);
+  public static native byte[] StorageServiceKey_GetStorageKey(long key);
+  public static native byte[] StorageServiceKey_ManifestKey(long key, long version);
+  public static native long StorageServiceKey_New(byte[] storageKey);
+  public static native byte[] StorageServiceKey_RecordKey(long key, byte[] rawId);
+
+  public static native void Tracing_InstallLogSubscriber();
+
   public static native long UnidentifiedSenderMessageContent_Deserialize(byte[] data);
   public static native void UnidentifiedSenderMessageContent_Destroy(long handle);
   public static native byte[] UnidentifiedSenderMessageContent_GetContents(long obj);
@@ -0,0 +1,35 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal;
+
+/**
+ * Receives a record of identity changes, session resets, and new or fast-forwarded sender keys.
+ * <p>
+ * Only one hook is installed for the whole process at a time; see {@link AuditLog#setHook}.
+ * {@link #record} may be called on any thread.
+ */
+public interface AuditHook {
+  /** A new identity key was saved for the address, replacing a different one. */
+  int IDENTITY_CHANGED = 1;
+  /** The current session with the address was archived to make way for a new one. */
+  int SESSION_ARCHIVED = 2;
+  /** A new sender key chain was created for sending to {@code groupId} from the address. */
+  int SENDER_KEY_CREATED = 3;
+  /**
+   * A sender key chain for {@code groupId} from the address was fast-forwarded past some messages.
+   */
+  int SENDER_KEY_FAST_FORWARDED = 4;
+
+  /**
+   * Called for each event.
+   *
+   * @param kind one of {@link #IDENTITY_CHANGED}, {@link #SESSION_ARCHIVED},
+   *     {@link #SENDER_KEY_CREATED}, or {@link #SENDER_KEY_FAST_FORWARDED}
+   * @param groupId the group for {@link #SENDER_KEY_CREATED} and {@link #SENDER_KEY_FAST_FORWARDED},
+   *     and {@code null} otherwise
+   */
+  void record(int kind, String addressName, int deviceId, String groupId);
+}
@@ -0,0 +1,20 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal;
+
+import org.signal.client.internal.Native;
+
+/**
+ * Process-wide reporting of identity changes, session resets, and new sender keys.
+ */
+public final class AuditLog {
+  private AuditLog() {}
+
+  /** Installs {@code hook}, replacing any hook installed earlier, or removes it if null. */
+  public static void setHook(AuditHook hook) {
+    Native.AuditHook_Set(hook);
+  }
+}
@@ -0,0 +1,74 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal;
+
+import org.signal.client.internal.Native;
+
+/**
+ * Per-account state, for apps that use libsignal on behalf of several accounts at once.
+ * <p>
+ * While a context is entered on a thread, log output from that thread is prefixed with the
+ * context's label and filtered by its level, and traced operations are counted in its metrics.
+ * Always pair {@link #enter} with {@link #exit}:
+ *
+ * <pre>
+ * context.enter();
+ * try {
+ *   // ...
+ * } finally {
+ *   context.exit();
+ * }
+ * </pre>
+ */
+public class BridgeContext {
+  private final long handle;
+
+  public BridgeContext(String label) {
+    this.handle = Native.BridgeContext_New(label);
+  }
+
+  @Override
+  protected void finalize() {
+    Native.BridgeContext_Destroy(this.handle);
+  }
+
+  /**
+   * Sets the most verbose level logged while this context is entered: 0 for none, then 1 (errors)
+   * through 5 (trace).
+   * <p>
+   * This can only make logging quieter than the level set with
+   * {@link org.whispersystems.libsignal.logging.SignalProtocolLoggerProvider}.
+   */
+  public void setMaxLogLevel(int level) {
+    Native.BridgeContext_SetMaxLogLevel(this.handle, level);
+  }
+
+  /** Makes this the current context on this thread, until {@link #exit} is called. */
+  public void enter() {
+    Native.BridgeContext_Enter(this.handle);
+  }
+
+  /** Undoes the innermost {@link #enter}, which must have been for this context. */
+  public void exit() {
+    Native.BridgeContext_Exit(this.handle);
+  }
+
+  /**
+   * Returns one {@code name count=N total_us=T} line per traced operation run while this context
+   * was entered.
+   * <p>
+   * Operations are only recorded once
+   * {@link org.whispersystems.libsignal.logging.SignalProtocolLoggerProvider#enableTracing} has
+   * been called.
+   */
+  public String getMetrics() {
+    return Native.BridgeContext_GetMetrics(this.handle);
+  }
+
+  public void resetMetrics() {
+    Native.BridgeContext_ResetMetrics(this.handle);
+  }
+}
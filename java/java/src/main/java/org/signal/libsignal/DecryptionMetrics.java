@@ -0,0 +1,37 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal;
+
+import org.signal.client.internal.Native;
+
+/**
+ * Process-wide counts of decryption results, for aggregate telemetry.
+ * <p>
+ * Counting is off until {@link #setEnabled} is called. While it is on, every decryption of an
+ * incoming message (1:1, sealed sender, or group) is counted by the class of its result, such as
+ * {@code success}, {@code untrusted_identity}, {@code no_session}, or {@code invalid_mac}. Nothing
+ * else about the message is recorded.
+ */
+public final class DecryptionMetrics {
+  private DecryptionMetrics() {}
+
+  /** Turns counting on or off. Counts already recorded are kept either way. */
+  public static void setEnabled(boolean enabled) {
+    Native.DecryptionMetrics_SetEnabled(enabled);
+  }
+
+  /**
+   * One {@code name count=N} line per result class, including classes with no decryptions, in a
+   * fixed order.
+   */
+  public static String get() {
+    return Native.DecryptionMetrics_Get();
+  }
+
+  public static void reset() {
+    Native.DecryptionMetrics_Reset();
+  }
+}
@@ -0,0 +1,42 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal;
+
+import org.signal.client.internal.Native;
+import org.whispersystems.libsignal.SignalProtocolAddress;
+import org.whispersystems.libsignal.groups.SenderKeyName;
+import org.whispersystems.libsignal.groups.state.SenderKeyStore;
+import org.whispersystems.libsignal.state.IdentityKeyStore;
+import org.whispersystems.libsignal.state.SessionStore;
+
+/**
+ * Summaries of the security state of a conversation, for attaching to bug reports.
+ * <p>
+ * A snapshot includes a fingerprint of the saved identity key, the session version, and when the
+ * ratchet last stepped, but never any secrets. It is not signed.
+ */
+public final class SecuritySnapshot {
+  private SecuritySnapshot() {}
+
+  /**
+   * Summarizes the conversation with {@code address} as JSON.
+   *
+   * @param senderKeyName one of our own sender keys, to also report which of our keys for that
+   *     group {@code address} was last sent; may be null
+   */
+  public static String create(SignalProtocolAddress address,
+                              SenderKeyName senderKeyName,
+                              SessionStore sessionStore,
+                              IdentityKeyStore identityKeyStore,
+                              SenderKeyStore senderKeyStore) {
+    return Native.SecuritySnapshot_Create(address.nativeHandle(),
+                                          senderKeyName != null ? senderKeyName.nativeHandle() : 0,
+                                          sessionStore,
+                                          identityKeyStore,
+                                          senderKeyStore,
+                                          null);
+  }
+}
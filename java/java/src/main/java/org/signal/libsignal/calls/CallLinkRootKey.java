@@ -0,0 +1,59 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.calls;
+
+import org.signal.client.internal.Native;
+import org.whispersystems.libsignal.InvalidKeyException;
+
+/**
+ * The secret shared by everyone with a call link.
+ *
+ * <p>Its text form ({@link #toString}) is what goes in the link itself.
+ */
+public class CallLinkRootKey {
+  private final long handle;
+
+  private CallLinkRootKey(long handle) {
+    this.handle = handle;
+  }
+
+  public CallLinkRootKey(byte[] bytes) throws InvalidKeyException {
+    this.handle = Native.CallLinkRootKey_FromBytes(bytes);
+  }
+
+  public static CallLinkRootKey generate() {
+    return new CallLinkRootKey(Native.CallLinkRootKey_Generate(0));
+  }
+
+  /** @throws InvalidKeyException if {@code text} isn't a root key in its text form */
+  public static CallLinkRootKey parse(String text) throws InvalidKeyException {
+    return new CallLinkRootKey(Native.CallLinkRootKey_Parse(text));
+  }
+
+  @Override
+  protected void finalize() {
+    Native.CallLinkRootKey_Destroy(this.handle);
+  }
+
+  public byte[] getBytes() {
+    return Native.CallLinkRootKey_GetBytes(this.handle);
+  }
+
+  @Override
+  public String toString() {
+    return Native.CallLinkRootKey_ToString(this.handle);
+  }
+
+  /** The ID the calling server uses for this call link's room. */
+  public byte[] deriveRoomId() {
+    return Native.CallLinkRootKey_DeriveRoomId(this.handle);
+  }
+
+  /** Derives the media key for one era of a call using this call link. */
+  public byte[] deriveEraKey(String eraId) {
+    return Native.CallLinkRootKey_DeriveEraKey(this.handle, eraId);
+  }
+}
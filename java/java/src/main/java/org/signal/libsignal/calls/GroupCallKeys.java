@@ -0,0 +1,19 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.calls;
+
+import org.signal.client.internal.Native;
+import org.whispersystems.libsignal.InvalidKeyException;
+
+public final class GroupCallKeys {
+  private GroupCallKeys() {}
+
+  /** Derives the media key for one era of a group call from the group's 32-byte master key. */
+  public static byte[] deriveEraKey(byte[] groupMasterKey, String eraId)
+      throws InvalidKeyException {
+    return Native.GroupCall_DeriveEraKey(groupMasterKey, eraId);
+  }
+}
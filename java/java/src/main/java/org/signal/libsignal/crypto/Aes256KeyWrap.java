@@ -0,0 +1,40 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.crypto;
+
+import org.signal.client.internal.Native;
+import org.whispersystems.libsignal.InvalidKeyException;
+
+/**
+ * AES key wrap with padding (RFC 5649), for storing keys encrypted under a 256-bit master key.
+ */
+public class Aes256KeyWrap {
+  private final long handle;
+
+  public Aes256KeyWrap(byte[] key) throws InvalidKeyException {
+    this.handle = Native.Aes256KeyWrap_New(key);
+  }
+
+  @Override
+  protected void finalize() {
+    Native.Aes256KeyWrap_Destroy(this.handle);
+  }
+
+  /** Wraps a non-empty key. The result is 8 to 15 bytes longer than the key. */
+  public byte[] wrap(byte[] key) {
+    return Native.Aes256KeyWrap_Wrap(this.handle, key);
+  }
+
+  /**
+   * Recovers a key from the output of {@link #wrap}.
+   *
+   * @throws InvalidWrappedKeyException if the data was wrapped under a different key or has been
+   *     modified
+   */
+  public byte[] unwrap(byte[] wrapped) throws InvalidWrappedKeyException {
+    return Native.Aes256KeyWrap_Unwrap(this.handle, wrapped);
+  }
+}
@@ -0,0 +1,62 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.crypto;
+
+import org.signal.client.internal.Native;
+
+/**
+ * Parameters for Argon2id (version 1.3), for deriving keys from PINs and other low-entropy
+ * secrets.
+ *
+ * A key can only be derived again with exactly the same parameters, so store them alongside the
+ * salt.
+ */
+public class Argon2idParams {
+  private final long handle;
+
+  private Argon2idParams(long handle) {
+    this.handle = handle;
+  }
+
+  /** @throws IllegalArgumentException if Argon2 doesn't allow the parameters */
+  public Argon2idParams(int memoryKib, int iterations, int parallelism) {
+    this(Native.Argon2idParams_New(memoryKib, iterations, parallelism));
+  }
+
+  /**
+   * Runs the KDF once to pick the iterations that take about {@code targetDurationMs} on this
+   * device.
+   */
+  public static Argon2idParams calibrate(int targetDurationMs, int memoryKib, int parallelism) {
+    return new Argon2idParams(
+        Native.Argon2idParams_Calibrate(targetDurationMs, memoryKib, parallelism));
+  }
+
+  @Override
+  protected void finalize() {
+    Native.Argon2idParams_Destroy(this.handle);
+  }
+
+  public int getMemoryKib() {
+    return Native.Argon2idParams_GetMemoryKib(this.handle);
+  }
+
+  public int getIterations() {
+    return Native.Argon2idParams_GetIterations(this.handle);
+  }
+
+  public int getParallelism() {
+    return Native.Argon2idParams_GetParallelism(this.handle);
+  }
+
+  /**
+   * @throws IllegalArgumentException if the salt is shorter than 8 bytes or the output is shorter
+   *     than 4 bytes
+   */
+  public byte[] derive(byte[] password, byte[] salt, int outputLength) {
+    return Native.Argon2idParams_Derive(this.handle, password, salt, outputLength);
+  }
+}
@@ -0,0 +1,43 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.crypto;
+
+import org.signal.client.internal.Native;
+
+/**
+ * A CRC-32 (IEEE) checksum over data supplied in pieces of any size.
+ *
+ * This detects corruption, not tampering.
+ */
+public class Crc32 {
+  private final long handle;
+
+  public Crc32() {
+    this.handle = Native.Crc32_New();
+  }
+
+  @Override
+  protected void finalize() {
+    Native.Crc32_Destroy(this.handle);
+  }
+
+  /** Returns the checksum as an unsigned value. */
+  public static long digest(byte[] input) {
+    return Native.Crc32_Digest(input) & 0xFFFFFFFFL;
+  }
+
+  public void update(byte[] input) {
+    Native.Crc32_Update(this.handle, input);
+  }
+
+  /**
+   * Returns the checksum of everything passed to {@link #update}, as an unsigned value, and
+   * starts over.
+   */
+  public long finish() {
+    return Native.Crc32_Finalize(this.handle) & 0xFFFFFFFFL;
+  }
+}
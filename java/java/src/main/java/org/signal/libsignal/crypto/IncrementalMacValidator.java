@@ -0,0 +1,46 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.crypto;
+
+import org.signal.client.internal.Native;
+
+/**
+ * Checks a download against its chunked HMAC-SHA256 digest as the data arrives.
+ *
+ * Data may be passed to {@link #update} in pieces of any size. Only the first
+ * {@link #getValidatedLength} bytes are known to be authentic and safe to use; once validation
+ * fails, that is the offset of the chunk that failed.
+ */
+public class IncrementalMacValidator {
+  private final long handle;
+
+  public IncrementalMacValidator(byte[] key, int chunkSize, byte[] digest) {
+    this.handle = Native.IncrementalMacValidator_New(key, chunkSize, digest);
+  }
+
+  @Override
+  protected void finalize() {
+    Native.IncrementalMacValidator_Destroy(this.handle);
+  }
+
+  /**
+   * Returns false as soon as a completed chunk doesn't match the digest.
+   *
+   * @throws IllegalStateException if validation has already failed or been finalized
+   */
+  public boolean update(byte[] data) {
+    return Native.IncrementalMacValidator_Update(this.handle, data);
+  }
+
+  /** Returns false if the data was truncated or its final chunk doesn't match the digest. */
+  public boolean finalizeAndValidate() {
+    return Native.IncrementalMacValidator_Finalize(this.handle);
+  }
+
+  public long getValidatedLength() {
+    return Native.IncrementalMacValidator_GetValidatedLength(this.handle);
+  }
+}
@@ -0,0 +1,16 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.crypto;
+
+/**
+ * Thrown when a wrapped key fails its integrity check, because it was wrapped under a different
+ * key or has been modified.
+ */
+public class InvalidWrappedKeyException extends Exception {
+  public InvalidWrappedKeyException(String message) {
+    super(message);
+  }
+}
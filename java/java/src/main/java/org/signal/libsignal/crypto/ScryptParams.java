@@ -0,0 +1,60 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.crypto;
+
+import org.signal.client.internal.Native;
+
+/**
+ * Parameters for scrypt, for deriving keys from PINs and other low-entropy secrets.
+ *
+ * A key can only be derived again with exactly the same parameters, so store them alongside the
+ * salt.
+ */
+public class ScryptParams {
+  private final long handle;
+
+  private ScryptParams(long handle) {
+    this.handle = handle;
+  }
+
+  /**
+   * Creates parameters for a cost of {@code N = 2^logN}, block size {@code r}, and parallelism
+   * {@code p}.
+   *
+   * @throws IllegalArgumentException if scrypt doesn't allow the parameters
+   */
+  public ScryptParams(int logN, int r, int p) {
+    this(Native.ScryptParams_New(logN, r, p));
+  }
+
+  /**
+   * Runs the KDF once to pick the cost that takes about {@code targetDurationMs} on this device.
+   */
+  public static ScryptParams calibrate(int targetDurationMs, int r, int p) {
+    return new ScryptParams(Native.ScryptParams_Calibrate(targetDurationMs, r, p));
+  }
+
+  @Override
+  protected void finalize() {
+    Native.ScryptParams_Destroy(this.handle);
+  }
+
+  public int getLogN() {
+    return Native.ScryptParams_GetLogN(this.handle);
+  }
+
+  public int getR() {
+    return Native.ScryptParams_GetR(this.handle);
+  }
+
+  public int getP() {
+    return Native.ScryptParams_GetP(this.handle);
+  }
+
+  public byte[] derive(byte[] password, byte[] salt, int outputLength) {
+    return Native.ScryptParams_Derive(this.handle, password, salt, outputLength);
+  }
+}
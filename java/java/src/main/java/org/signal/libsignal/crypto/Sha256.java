@@ -0,0 +1,35 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.crypto;
+
+import org.signal.client.internal.Native;
+
+/** A SHA-256 digest over data supplied in pieces of any size. */
+public class Sha256 {
+  private final long handle;
+
+  public Sha256() {
+    this.handle = Native.Sha256_New();
+  }
+
+  @Override
+  protected void finalize() {
+    Native.Sha256_Destroy(this.handle);
+  }
+
+  public static byte[] digest(byte[] input) {
+    return Native.Sha256_Digest(input);
+  }
+
+  public void update(byte[] input) {
+    Native.Sha256_Update(this.handle, input);
+  }
+
+  /** Returns the digest of everything passed to {@link #update}, and starts over. */
+  public byte[] finish() {
+    return Native.Sha256_Finalize(this.handle);
+  }
+}
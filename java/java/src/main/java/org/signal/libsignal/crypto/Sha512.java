@@ -0,0 +1,35 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.crypto;
+
+import org.signal.client.internal.Native;
+
+/** A SHA-512 digest over data supplied in pieces of any size. */
+public class Sha512 {
+  private final long handle;
+
+  public Sha512() {
+    this.handle = Native.Sha512_New();
+  }
+
+  @Override
+  protected void finalize() {
+    Native.Sha512_Destroy(this.handle);
+  }
+
+  public static byte[] digest(byte[] input) {
+    return Native.Sha512_Digest(input);
+  }
+
+  public void update(byte[] input) {
+    Native.Sha512_Update(this.handle, input);
+  }
+
+  /** Returns the digest of everything passed to {@link #update}, and starts over. */
+  public byte[] finish() {
+    return Native.Sha512_Finalize(this.handle);
+  }
+}
@@ -0,0 +1,41 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.crypto;
+
+import org.signal.client.internal.Native;
+import org.whispersystems.libsignal.InvalidKeyException;
+import org.whispersystems.libsignal.InvalidMessageException;
+
+/**
+ * Decrypts the manifest and stickers of a sticker pack, using keys derived from the pack key.
+ */
+public class StickerCipher {
+  private final long handle;
+
+  public StickerCipher(byte[] packKey) throws InvalidKeyException {
+    this.handle = Native.StickerCipher_New(packKey);
+  }
+
+  @Override
+  protected void finalize() {
+    Native.StickerCipher_Destroy(this.handle);
+  }
+
+  /**
+   * Decrypts a whole manifest or sticker.
+   *
+   * @throws InvalidMessageException if the data has been tampered with
+   * @throws IllegalArgumentException if the data is not a well-formed encryption
+   */
+  public byte[] decrypt(byte[] data) throws InvalidMessageException {
+    return Native.StickerCipher_Decrypt(this.handle, data);
+  }
+
+  /** Starts decrypting a manifest or sticker that arrives in pieces. */
+  public StickerDecryption newDecryption() {
+    return new StickerDecryption(Native.StickerDecryption_New(this.handle));
+  }
+}
@@ -0,0 +1,42 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.crypto;
+
+import org.signal.client.internal.Native;
+import org.whispersystems.libsignal.InvalidMessageException;
+
+/**
+ * Decrypts a manifest or sticker supplied in pieces of any size.
+ *
+ * Plaintext is returned as soon as it can be decrypted, but it is not authenticated until
+ * {@link #finalizeAndVerify} succeeds and must be discarded if it throws.
+ */
+public class StickerDecryption {
+  private final long handle;
+
+  StickerDecryption(long handle) {
+    this.handle = handle;
+  }
+
+  @Override
+  protected void finalize() {
+    Native.StickerDecryption_Destroy(this.handle);
+  }
+
+  public byte[] update(byte[] data) {
+    return Native.StickerDecryption_Update(this.handle, data);
+  }
+
+  /**
+   * Checks the MAC and returns the rest of the plaintext.
+   *
+   * @throws InvalidMessageException if the data has been tampered with
+   * @throws IllegalArgumentException if the data is not a well-formed encryption
+   */
+  public byte[] finalizeAndVerify() throws InvalidMessageException {
+    return Native.StickerDecryption_Finalize(this.handle);
+  }
+}
@@ -0,0 +1,74 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.crypto;
+
+import org.signal.client.internal.Native;
+import org.whispersystems.libsignal.InvalidKeyException;
+import org.whispersystems.libsignal.InvalidMessageException;
+
+/**
+ * An account's Storage Service key, from which the key for each manifest and record is derived.
+ *
+ * <p>Manifests and records are encrypted as {@code nonce || AES-256-GCM(data) || tag}.
+ */
+public class StorageServiceKey {
+  private final long handle;
+
+  private StorageServiceKey(long handle) {
+    this.handle = handle;
+  }
+
+  /** Wraps an existing 32-byte storage key. */
+  public StorageServiceKey(byte[] storageKey) throws InvalidKeyException {
+    this.handle = Native.StorageServiceKey_New(storageKey);
+  }
+
+  /** Derives the storage key from the account's 32-byte master key. */
+  public static StorageServiceKey fromMasterKey(byte[] masterKey) throws InvalidKeyException {
+    return new StorageServiceKey(Native.StorageServiceKey_FromMasterKey(masterKey));
+  }
+
+  @Override
+  protected void finalize() {
+    Native.StorageServiceKey_Destroy(this.handle);
+  }
+
+  public byte[] getStorageKey() {
+    return Native.StorageServiceKey_GetStorageKey(this.handle);
+  }
+
+  public byte[] getManifestKey(long version) {
+    return Native.StorageServiceKey_ManifestKey(this.handle, version);
+  }
+
+  public byte[] getRecordKey(byte[] rawId) {
+    return Native.StorageServiceKey_RecordKey(this.handle, rawId);
+  }
+
+  public byte[] encryptManifest(long version, byte[] plaintext) {
+    return Native.StorageServiceKey_EncryptManifest(this.handle, version, plaintext, 0);
+  }
+
+  /**
+   * @throws InvalidMessageException if the manifest was encrypted for a different version or under
+   *     a different key, or has been modified
+   */
+  public byte[] decryptManifest(long version, byte[] ciphertext) throws InvalidMessageException {
+    return Native.StorageServiceKey_DecryptManifest(this.handle, version, ciphertext);
+  }
+
+  public byte[] encryptRecord(byte[] rawId, byte[] plaintext) {
+    return Native.StorageServiceKey_EncryptRecord(this.handle, rawId, plaintext, 0);
+  }
+
+  /**
+   * @throws InvalidMessageException if the record was encrypted for a different ID or under a
+   *     different key, or has been modified
+   */
+  public byte[] decryptRecord(byte[] rawId, byte[] ciphertext) throws InvalidMessageException {
+    return Native.StorageServiceKey_DecryptRecord(this.handle, rawId, ciphertext);
+  }
+}
@@ -19,6 +19,14 @@ public class DeviceTransferKey {
   }
 
   public byte[] generateCertificate(String name, int daysTilExpires) {
-    return Native.DeviceTransfer_GenerateCertificate(this.keyMaterial, name, daysTilExpires);
+    return generateCertificate(name, daysTilExpires, System.currentTimeMillis());
+  }
+
+  /**
+   * Generates a certificate valid from the day containing {@code now}, given in milliseconds
+   * since the epoch, rather than the current day according to the system clock.
+   */
+  public byte[] generateCertificate(String name, int daysTilExpires, long now) {
+    return Native.DeviceTransfer_GenerateCertificate(this.keyMaterial, name, daysTilExpires, now);
   }
 }
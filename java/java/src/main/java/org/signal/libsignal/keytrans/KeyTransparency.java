@@ -0,0 +1,48 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.keytrans;
+
+import org.signal.client.internal.Native;
+import org.whispersystems.libsignal.ecc.ECPublicKey;
+
+/**
+ * Verifies responses from a key transparency log.
+ *
+ * The caller saves the tree head returned by each verification, and passes it back in as
+ * {@code lastTreeHead} next time, so that each new view of the log is checked for consistency
+ * with the last one.
+ */
+public class KeyTransparency {
+  private KeyTransparency() {}
+
+  /**
+   * @param lastTreeHead the tree head saved from the previous verification, or null if there was
+   *                     none
+   */
+  public static SearchResult verifySearch(ECPublicKey logKey, byte[] searchKey, byte[] response, byte[] lastTreeHead)
+      throws VerificationFailedException
+  {
+    return new SearchResult(Native.KeyTransparency_VerifySearch(logKey.nativeHandle(), searchKey, response, lastTreeHead));
+  }
+
+  /**
+   * @param monitoringData the state saved from the last search or monitor for {@code searchKey}
+   */
+  public static MonitorResult verifyMonitor(ECPublicKey logKey, byte[] searchKey, byte[] response, byte[] lastTreeHead, byte[] monitoringData)
+      throws VerificationFailedException
+  {
+    return new MonitorResult(Native.KeyTransparency_VerifyMonitor(logKey.nativeHandle(), searchKey, response, lastTreeHead, monitoringData));
+  }
+
+  /**
+   * @return the new tree head to save
+   */
+  public static byte[] verifyDistinguished(ECPublicKey logKey, byte[] response, byte[] lastTreeHead)
+      throws VerificationFailedException
+  {
+    return Native.KeyTransparency_VerifyDistinguished(logKey.nativeHandle(), response, lastTreeHead);
+  }
+}
@@ -0,0 +1,36 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.keytrans;
+
+import org.signal.client.internal.Native;
+
+public class MonitorResult {
+  private final long handle;
+
+  MonitorResult(long handle) {
+    this.handle = handle;
+  }
+
+  @Override
+  protected void finalize() {
+    Native.KeyTransparencyMonitorResult_Destroy(this.handle);
+  }
+
+  /**
+   * If false, the search key's value has changed and it should be searched for again.
+   */
+  public boolean isUnchanged() {
+    return Native.KeyTransparencyMonitorResult_IsUnchanged(this.handle);
+  }
+
+  public byte[] getTreeHead() {
+    return Native.KeyTransparencyMonitorResult_GetTreeHead(this.handle);
+  }
+
+  public byte[] getMonitoringData() {
+    return Native.KeyTransparencyMonitorResult_GetMonitoringData(this.handle);
+  }
+}
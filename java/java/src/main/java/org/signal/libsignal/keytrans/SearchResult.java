@@ -0,0 +1,40 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.keytrans;
+
+import org.signal.client.internal.Native;
+
+public class SearchResult {
+  private final long handle;
+
+  SearchResult(long handle) {
+    this.handle = handle;
+  }
+
+  @Override
+  protected void finalize() {
+    Native.KeyTransparencySearchResult_Destroy(this.handle);
+  }
+
+  public byte[] getValue() {
+    return Native.KeyTransparencySearchResult_GetValue(this.handle);
+  }
+
+  /**
+   * Returns the tree head to save and pass to the next verification.
+   */
+  public byte[] getTreeHead() {
+    return Native.KeyTransparencySearchResult_GetTreeHead(this.handle);
+  }
+
+  /**
+   * Returns the state to save and pass to {@link KeyTransparency#verifyMonitor} for this search
+   * key.
+   */
+  public byte[] getMonitoringData() {
+    return Native.KeyTransparencySearchResult_GetMonitoringData(this.handle);
+  }
+}
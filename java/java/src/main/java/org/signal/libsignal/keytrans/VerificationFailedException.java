@@ -0,0 +1,16 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.keytrans;
+
+/**
+ * Thrown when a key transparency log's response doesn't check out, which may mean the log is
+ * misbehaving.
+ */
+public class VerificationFailedException extends Exception {
+  public VerificationFailedException(String message) {
+    super(message);
+  }
+}
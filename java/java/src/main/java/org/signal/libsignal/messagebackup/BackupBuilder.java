@@ -0,0 +1,67 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.messagebackup;
+
+import org.signal.client.internal.Native;
+import org.whispersystems.libsignal.InvalidKeyException;
+
+/**
+ * Produces a complete, encrypted message backup from structured records.
+ *
+ * Records must be added in dependency order: the account data first, then each recipient before
+ * any chat with it, and each chat before its items. Each call returns the bytes to append to the
+ * backup; the backup is complete once the output of {@link #finish()} has been written.
+ *
+ * A record that is malformed or out of order is rejected with an {@link IllegalArgumentException}
+ * without affecting the backup, so the caller may skip it and carry on.
+ */
+public class BackupBuilder {
+  private final long handle;
+
+  public BackupBuilder(byte[] backupKey, boolean compressed, long backupTimeMs) throws InvalidKeyException {
+    this.handle = Native.BackupBuilder_New(backupKey, compressed, backupTimeMs, 0);
+  }
+
+  @Override
+  protected void finalize() {
+    Native.BackupBuilder_Destroy(this.handle);
+  }
+
+  /** @param username may be null */
+  public byte[] addAccountData(byte[] profileKey, String username, String givenName, String familyName) {
+    return Native.BackupBuilder_AddAccountData(this.handle, profileKey, username, givenName, familyName);
+  }
+
+  /** Adds the recipient representing the account the backup belongs to. */
+  public byte[] addSelfRecipient(long id) {
+    return Native.BackupBuilder_AddSelfRecipient(this.handle, id);
+  }
+
+  /** At least one of {@code uuid} and {@code e164} must be non-null. */
+  public byte[] addContact(long id, String uuid, String e164) {
+    return Native.BackupBuilder_AddContact(this.handle, id, uuid, e164);
+  }
+
+  public byte[] addGroup(long id, byte[] masterKey) {
+    return Native.BackupBuilder_AddGroup(this.handle, id, masterKey);
+  }
+
+  /**
+   * @param pinnedOrder 0 if the chat is not pinned
+   * @param expirationTimerMs 0 if messages don't expire
+   */
+  public byte[] addChat(long id, long recipientId, boolean archived, int pinnedOrder, long expirationTimerMs) {
+    return Native.BackupBuilder_AddChat(this.handle, id, recipientId, archived, pinnedOrder, expirationTimerMs);
+  }
+
+  public byte[] addChatItem(long chatId, long authorId, long dateSent, String text) {
+    return Native.BackupBuilder_AddChatItem(this.handle, chatId, authorId, dateSent, text);
+  }
+
+  public byte[] finish() {
+    return Native.BackupBuilder_Finish(this.handle);
+  }
+}
@@ -0,0 +1,50 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.messagebackup;
+
+import org.signal.client.internal.Native;
+import org.whispersystems.libsignal.InvalidKeyException;
+import org.whispersystems.libsignal.InvalidMessageException;
+
+/**
+ * Decrypts frames from a message backup stream that arrives in chunks of any size.
+ *
+ * Once a frame fails to decrypt, every later call fails with the same error.
+ */
+public class FrameReader {
+  private final long handle;
+
+  public FrameReader(byte[] backupKey) throws InvalidKeyException {
+    this.handle = Native.FrameReader_New(backupKey);
+  }
+
+  @Override
+  protected void finalize() {
+    Native.FrameReader_Destroy(this.handle);
+  }
+
+  public void push(byte[] chunk) throws InvalidMessageException {
+    Native.FrameReader_Push(this.handle, chunk);
+  }
+
+  /**
+   * Returns the next complete frame, or null if more input is needed or the stream has ended.
+   */
+  public byte[] nextFrame() throws InvalidMessageException {
+    return Native.FrameReader_NextFrame(this.handle);
+  }
+
+  public boolean isFinished() {
+    return Native.FrameReader_IsFinished(this.handle);
+  }
+
+  /**
+   * Checks that the whole stream was read, and fails if it was truncated.
+   */
+  public void finish() throws InvalidMessageException {
+    Native.FrameReader_Finish(this.handle);
+  }
+}
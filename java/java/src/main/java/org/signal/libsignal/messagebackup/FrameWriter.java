@@ -0,0 +1,36 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.messagebackup;
+
+import org.signal.client.internal.Native;
+import org.whispersystems.libsignal.InvalidKeyException;
+
+/**
+ * Encrypts frames into a message backup stream.
+ *
+ * Each call returns the bytes to append to the stream; the first one also carries the stream
+ * header. The stream is incomplete until the output of {@link #finish()} has been written.
+ */
+public class FrameWriter {
+  private final long handle;
+
+  public FrameWriter(byte[] backupKey, boolean compressed) throws InvalidKeyException {
+    this.handle = Native.FrameWriter_New(backupKey, compressed, 0);
+  }
+
+  @Override
+  protected void finalize() {
+    Native.FrameWriter_Destroy(this.handle);
+  }
+
+  public byte[] append(byte[] frame) {
+    return Native.FrameWriter_Append(this.handle, frame);
+  }
+
+  public byte[] finish() {
+    return Native.FrameWriter_Finish(this.handle);
+  }
+}
@@ -3,6 +3,7 @@ package org.signal.libsignal.metadata;
 import org.signal.libsignal.metadata.certificate.CertificateValidator;
 import org.signal.libsignal.metadata.certificate.InvalidCertificateException;
 import org.signal.libsignal.metadata.certificate.SenderCertificate;
+import org.signal.libsignal.metadata.certificate.SenderCertificateProvider;
 import org.signal.libsignal.metadata.protocol.UnidentifiedSenderMessage;
 import org.signal.libsignal.metadata.protocol.UnidentifiedSenderMessageContent;
 import org.whispersystems.libsignal.DuplicateMessageException;
@@ -56,6 +57,27 @@ public class SealedSessionCipher {
        paddedPlaintext,
        this.signalProtocolStore,
        this.signalProtocolStore,
+       0,
+       null);
+  }
+
+  /**
+   * Like {@link #encrypt(SignalProtocolAddress, SenderCertificate, byte[])}, but takes the sender
+   * certificate from {@code provider}, refreshing it first if it is close to expiring as of
+   * {@code timestamp}.
+   */
+  public byte[] encrypt(SignalProtocolAddress destinationAddress, SenderCertificateProvider provider, long timestamp, byte[] paddedPlaintext)
+      throws InvalidKeyException, UntrustedIdentityException
+  {
+    return Native.SealedSessionCipher_EncryptWithProvider(
+       destinationAddress.nativeHandle(),
+       provider.nativeHandle(),
+       provider.getSource(),
+       timestamp,
+       paddedPlaintext,
+       this.signalProtocolStore,
+       this.signalProtocolStore,
+       0,
        null);
   }
 
@@ -108,6 +130,56 @@ public class SealedSessionCipher {
     }
   }
 
+  /**
+   * Decrypts like {@link #decrypt}, but without writing to the store.
+   *
+   * Meant for processes such as notification extensions that may not be able
+   * to write to the app's database. The store changes are returned by
+   * {@link NotificationDecryptionResult#getCommitSet()} instead, to be passed
+   * to {@link #applyNotificationCommitSet(byte[])} later.
+   */
+  public NotificationDecryptionResult notificationDecrypt(CertificateValidator validator, byte[] ciphertext, long timestamp)
+      throws InvalidMessageException, InvalidKeyException, InvalidKeyIdException,
+             NoSessionException, DuplicateMessageException, UntrustedIdentityException
+  {
+    long handle = Native.NotificationDecrypt_Decrypt(ciphertext,
+                                                     validator.getTrustRoot().nativeHandle(),
+                                                     timestamp,
+                                                     this.localE164Address,
+                                                     this.localUuidAddress,
+                                                     this.localDeviceId,
+                                                     this.signalProtocolStore,
+                                                     this.signalProtocolStore,
+                                                     this.signalProtocolStore,
+                                                     this.signalProtocolStore,
+                                                     0,
+                                                     null);
+    try {
+      return new NotificationDecryptionResult(Native.NotificationDecryptionResult_GetSenderUuid(handle),
+                                              Optional.fromNullable(Native.NotificationDecryptionResult_GetSenderE164(handle)),
+                                              Native.NotificationDecryptionResult_GetDeviceId(handle),
+                                              Native.NotificationDecryptionResult_GetMessage(handle),
+                                              Native.NotificationDecryptionResult_GetCommitSet(handle));
+    } finally {
+      Native.NotificationDecryptionResult_Destroy(handle);
+    }
+  }
+
+  /**
+   * Makes the store changes deferred by {@link #notificationDecrypt}.
+   *
+   * @throws IllegalStateException without changing anything if one of the
+   *         affected sessions has changed since the notification was decrypted;
+   *         the message should then be decrypted again
+   */
+  public void applyNotificationCommitSet(byte[] commitSet) throws InvalidMessageException {
+    Native.NotificationCommitSet_Apply(commitSet,
+                                       this.signalProtocolStore,
+                                       this.signalProtocolStore,
+                                       this.signalProtocolStore,
+                                       null);
+  }
+
   public int getSessionVersion(SignalProtocolAddress remoteAddress) {
     return new SessionCipher(signalProtocolStore, remoteAddress).getSessionVersion();
   }
@@ -157,4 +229,20 @@ public class SealedSessionCipher {
       return paddedMessage;
     }
   }
+
+  public static class NotificationDecryptionResult extends DecryptionResult {
+    private final byte[] commitSet;
+
+    private NotificationDecryptionResult(String senderUuid, Optional<String> senderE164, int deviceId, byte[] paddedMessage, byte[] commitSet) {
+      super(senderUuid, senderE164, deviceId, paddedMessage);
+      this.commitSet = commitSet;
+    }
+
+    /**
+     * The serialized store changes, for {@link SealedSessionCipher#applyNotificationCommitSet(byte[])}.
+     */
+    public byte[] getCommitSet() {
+      return commitSet;
+    }
+  }
 }
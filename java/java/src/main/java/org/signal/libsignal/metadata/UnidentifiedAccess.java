@@ -0,0 +1,33 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.metadata;
+
+import org.signal.client.internal.Native;
+
+/** Values derived from a profile key that let others reach its owner without identifying them. */
+public final class UnidentifiedAccess {
+  private UnidentifiedAccess() {}
+
+  /**
+   * Derives the 16-byte unidentified-access key that senders present to deliver sealed sender
+   * messages to the owner of {@code profileKey}.
+   *
+   * @throws IllegalArgumentException if the profile key is not 32 bytes
+   */
+  public static byte[] deriveAccessKey(byte[] profileKey) {
+    return Native.ProfileKey_DeriveAccessKey(profileKey);
+  }
+
+  /**
+   * Blinds a service ID under a profile key, giving a 32-byte identifier that only those who know
+   * the key can link back to it.
+   *
+   * @throws IllegalArgumentException if the profile key is not 32 bytes
+   */
+  public static byte[] blindServiceId(byte[] profileKey, String serviceId) {
+    return Native.ProfileKey_BlindServiceId(profileKey, serviceId);
+  }
+}
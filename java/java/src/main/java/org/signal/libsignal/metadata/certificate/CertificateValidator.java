@@ -8,20 +8,39 @@ import org.whispersystems.libsignal.InvalidKeyException;
 
 public class CertificateValidator {
   private final ECPublicKey trustRoot;
+  private final long        gracePeriod;
 
   public CertificateValidator(ECPublicKey trustRoot) {
-    this.trustRoot = trustRoot;
+    this(trustRoot, 0);
+  }
+
+  /**
+   * Creates a validator that accepts sender certificates for {@code gracePeriod}
+   * past their expiration, to tolerate clock skew.
+   */
+  public CertificateValidator(ECPublicKey trustRoot, long gracePeriod) {
+    this.trustRoot   = trustRoot;
+    this.gracePeriod = gracePeriod;
   }
 
   public ECPublicKey getTrustRoot() {
     return this.trustRoot;
   }
 
+  public long getGracePeriod() {
+    return this.gracePeriod;
+  }
+
+  /**
+   * @throws ExpiredCertificateException if the certificate is otherwise valid but has expired
+   */
   public void validate(SenderCertificate certificate, long validationTime) throws InvalidCertificateException {
     try {
-       if (!Native.SenderCertificate_Validate(certificate.nativeHandle(), trustRoot.nativeHandle(), validationTime)) {
+       if (!Native.SenderCertificate_ValidateWithGracePeriod(certificate.nativeHandle(), trustRoot.nativeHandle(), validationTime, gracePeriod)) {
          throw new InvalidCertificateException("Validation failed");
        }
+    } catch (InvalidCertificateException e) {
+      throw e;
     } catch (Exception e) {
       throw new InvalidCertificateException(e);
     }
@@ -0,0 +1,15 @@
+package org.signal.libsignal.metadata.certificate;
+
+/**
+ * Thrown for a sender certificate that is correctly signed but has expired,
+ * even allowing for the validator's grace period.
+ *
+ * Unlike other {@link InvalidCertificateException}s, this usually means the
+ * certificate needs to be refreshed (or the clocks checked), not that it was
+ * forged.
+ */
+public class ExpiredCertificateException extends InvalidCertificateException {
+  public ExpiredCertificateException(String s) {
+    super(s);
+  }
+}
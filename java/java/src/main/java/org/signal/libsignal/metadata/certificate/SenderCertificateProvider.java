@@ -0,0 +1,58 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.metadata.certificate;
+
+import org.signal.client.internal.Native;
+
+/**
+ * Caches a sender certificate, fetching a new one from its {@link SenderCertificateSource} once
+ * the cached one is within the freshness window of expiring.
+ */
+public class SenderCertificateProvider {
+  private final long                    handle;
+  private final SenderCertificateSource source;
+
+  @Override
+  protected void finalize() {
+     Native.SenderCertificateProvider_Destroy(this.handle);
+  }
+
+  /**
+   * @param freshnessWindow how long before expiration, in milliseconds, to fetch a new certificate
+   */
+  public SenderCertificateProvider(SenderCertificateSource source, long freshnessWindow) {
+    this(source, freshnessWindow, null);
+  }
+
+  /**
+   * @param freshnessWindow how long before expiration, in milliseconds, to fetch a new certificate
+   * @param certificate a certificate to start with, such as one saved earlier
+   */
+  public SenderCertificateProvider(SenderCertificateSource source, long freshnessWindow, SenderCertificate certificate) {
+    this.source = source;
+    this.handle = Native.SenderCertificateProvider_New(certificate != null ? certificate.nativeHandle() : 0, freshnessWindow);
+  }
+
+  public long nativeHandle() {
+    return this.handle;
+  }
+
+  public SenderCertificateSource getSource() {
+    return this.source;
+  }
+
+  /**
+   * Returns a certificate that is still valid at {@code validUntil}, in milliseconds since the
+   * epoch.
+   *
+   * If the cached certificate expires within the freshness window, a new one is fetched first. If
+   * that fails, the cached certificate is still returned as long as it is valid at
+   * {@code validUntil}.
+   */
+  public SenderCertificate get(long validUntil) {
+    return new SenderCertificate(Native.SenderCertificateProvider_Get(this.handle, validUntil, this.source, null));
+  }
+}
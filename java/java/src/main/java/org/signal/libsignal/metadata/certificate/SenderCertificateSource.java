@@ -0,0 +1,16 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.metadata.certificate;
+
+import java.io.IOException;
+
+/**
+ * Fetches a new sender certificate from the service when a {@link SenderCertificateProvider}
+ * needs one.
+ */
+public interface SenderCertificateSource {
+  public SenderCertificate fetchSenderCertificate() throws IOException;
+}
@@ -0,0 +1,61 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.metadata.protocol;
+
+import org.signal.client.internal.Native;
+import org.whispersystems.libsignal.InvalidMessageException;
+import org.whispersystems.libsignal.SignalProtocolAddress;
+
+/**
+ * An already-encrypted message waiting to be sent, normally a sealed sender message.
+ *
+ * Serialize queued envelopes to keep them across restarts; the format is shared by every platform.
+ */
+public class OutgoingEnvelope {
+  public static final int CONTENT_HINT_DEFAULT    = 0;
+  public static final int CONTENT_HINT_RESENDABLE = 1;
+  public static final int CONTENT_HINT_IMPLICIT   = 2;
+
+  private final long handle;
+
+  public OutgoingEnvelope(SignalProtocolAddress destination, long timestamp, int contentHint, byte[] payload) {
+    this.handle = Native.OutgoingEnvelope_New(destination.nativeHandle(), timestamp, contentHint, payload);
+  }
+
+  public OutgoingEnvelope(byte[] serialized) throws InvalidMessageException {
+    this.handle = Native.OutgoingEnvelope_Deserialize(serialized);
+  }
+
+  @Override
+  protected void finalize() {
+    Native.OutgoingEnvelope_Destroy(this.handle);
+  }
+
+  public SignalProtocolAddress getDestination() {
+    return new SignalProtocolAddress(Native.OutgoingEnvelope_GetDestination(this.handle));
+  }
+
+  public long getTimestamp() {
+    return Native.OutgoingEnvelope_GetTimestamp(this.handle);
+  }
+
+  public int getContentHint() {
+    return Native.OutgoingEnvelope_GetContentHint(this.handle);
+  }
+
+  public byte[] getPayload() {
+    return Native.OutgoingEnvelope_GetPayload(this.handle);
+  }
+
+  /** The format version of the data this envelope was deserialized from. */
+  public int getSerializedVersion() {
+    return Native.OutgoingEnvelope_GetSerializedVersion(this.handle);
+  }
+
+  public byte[] serialize() {
+    return Native.OutgoingEnvelope_Serialize(this.handle);
+  }
+}
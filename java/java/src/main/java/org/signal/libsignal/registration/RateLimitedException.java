@@ -0,0 +1,22 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.registration;
+
+public class RateLimitedException extends RegistrationException {
+  private final long retryAfterSeconds;
+
+  public RateLimitedException(String message, long retryAfterSeconds) {
+    super(message);
+    this.retryAfterSeconds = retryAfterSeconds;
+  }
+
+  /**
+   * Returns how long to wait before trying again, or -1 if the server didn't say.
+   */
+  public long getRetryAfterSeconds() {
+    return retryAfterSeconds;
+  }
+}
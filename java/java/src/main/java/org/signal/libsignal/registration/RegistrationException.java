@@ -0,0 +1,15 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.registration;
+
+/**
+ * The server rejected a registration request, or its response could not be understood.
+ */
+public class RegistrationException extends Exception {
+  public RegistrationException(String message) {
+    super(message);
+  }
+}
@@ -0,0 +1,62 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.registration;
+
+import org.signal.client.internal.Native;
+
+/**
+ * The result of registering an account: either the new account's identifiers, or the details
+ * needed to get past its registration lock.
+ */
+public class RegistrationOutcome {
+  private final long handle;
+
+  /**
+   * Parses the response to {@link RegistrationRequest#registerAccount}.
+   *
+   * @param retryAfter the value of the response's Retry-After header, or null
+   */
+  public RegistrationOutcome(int status, byte[] body, String retryAfter) throws RegistrationException {
+    this.handle = Native.RegistrationOutcome_FromResponse(status, body, retryAfter);
+  }
+
+  @Override
+  protected void finalize() {
+    Native.RegistrationOutcome_Destroy(this.handle);
+  }
+
+  /**
+   * If true, register again with a registration lock token derived from the master key, which
+   * can be restored using the SVR credentials.
+   */
+  public boolean isLocked() {
+    return Native.RegistrationOutcome_IsLocked(this.handle);
+  }
+
+  public String getAci() {
+    return Native.RegistrationOutcome_GetAci(this.handle);
+  }
+
+  public String getPni() {
+    return Native.RegistrationOutcome_GetPni(this.handle);
+  }
+
+  public String getNumber() {
+    return Native.RegistrationOutcome_GetNumber(this.handle);
+  }
+
+  public long getLockTimeRemainingMs() {
+    return Native.RegistrationOutcome_GetLockTimeRemainingMs(this.handle);
+  }
+
+  public String getSvrUsername() {
+    return Native.RegistrationOutcome_GetSvrUsername(this.handle);
+  }
+
+  public String getSvrPassword() {
+    return Native.RegistrationOutcome_GetSvrPassword(this.handle);
+  }
+}
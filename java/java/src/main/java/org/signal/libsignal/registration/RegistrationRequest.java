@@ -0,0 +1,89 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.registration;
+
+import org.signal.client.internal.Native;
+
+/**
+ * An HTTP request to the chat server, built by libsignal so that the app only has to send it.
+ */
+public class RegistrationRequest {
+  public enum PushTokenType { FCM, APN }
+
+  public enum VerificationTransport { SMS, VOICE }
+
+  private final long handle;
+
+  private RegistrationRequest(long handle) {
+    this.handle = handle;
+  }
+
+  @Override
+  protected void finalize() {
+    Native.RegistrationRequest_Destroy(this.handle);
+  }
+
+  /**
+   * @param number the phone number to register, in E.164 format
+   * @param pushToken a token for receiving push challenges, or null
+   * @param mcc the mobile country code of the device's network, or null
+   * @param mnc the mobile network code of the device's network, or null
+   */
+  public static RegistrationRequest createSession(String number, String pushToken, PushTokenType pushTokenType, String mcc, String mnc) {
+    return new RegistrationRequest(Native.RegistrationRequest_CreateSession(number, pushToken, pushTokenType == PushTokenType.APN, mcc, mnc));
+  }
+
+  public static RegistrationRequest getSession(String sessionId) {
+    return new RegistrationRequest(Native.RegistrationRequest_GetSession(sessionId));
+  }
+
+  /**
+   * Answers the challenges the session asked for; either may be null.
+   */
+  public static RegistrationRequest updateSession(String sessionId, String pushChallenge, String captcha) {
+    return new RegistrationRequest(Native.RegistrationRequest_UpdateSession(sessionId, pushChallenge, captcha));
+  }
+
+  /**
+   * @param client the app's platform, such as "android"
+   */
+  public static RegistrationRequest requestVerificationCode(String sessionId, VerificationTransport transport, String client) {
+    return new RegistrationRequest(Native.RegistrationRequest_RequestVerificationCode(sessionId, transport == VerificationTransport.VOICE, client));
+  }
+
+  public static RegistrationRequest submitVerificationCode(String sessionId, String code) {
+    return new RegistrationRequest(Native.RegistrationRequest_SubmitVerificationCode(sessionId, code));
+  }
+
+  /**
+   * @param registrationLock the token from {@link #registrationLockToken}, or null
+   */
+  public static RegistrationRequest registerAccount(String sessionId, int registrationId, boolean fetchesMessages, String registrationLock) {
+    return new RegistrationRequest(Native.RegistrationRequest_RegisterAccount(sessionId, registrationId, fetchesMessages, registrationLock));
+  }
+
+  /**
+   * Derives the registration lock token from the master key restored from secure value recovery.
+   */
+  public static String registrationLockToken(byte[] masterKey) {
+    return Native.Registration_LockToken(masterKey);
+  }
+
+  public String getMethod() {
+    return Native.RegistrationRequest_GetMethod(this.handle);
+  }
+
+  public String getPath() {
+    return Native.RegistrationRequest_GetPath(this.handle);
+  }
+
+  /**
+   * Returns the JSON body, or null if the request has none.
+   */
+  public byte[] getBody() {
+    return Native.RegistrationRequest_GetBody(this.handle);
+  }
+}
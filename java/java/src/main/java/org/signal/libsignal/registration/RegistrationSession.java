@@ -0,0 +1,64 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.registration;
+
+import org.signal.client.internal.Native;
+
+/**
+ * The server's view of a verification session, as of the last response.
+ *
+ * Wait times are in seconds from when the response was received, or -1 if the action isn't
+ * available at all.
+ */
+public class RegistrationSession {
+  private final long handle;
+
+  /**
+   * Parses the response to any of the session requests.
+   *
+   * @param retryAfter the value of the response's Retry-After header, or null
+   */
+  public RegistrationSession(int status, byte[] body, String retryAfter) throws RegistrationException {
+    this.handle = Native.RegistrationSession_FromResponse(status, body, retryAfter);
+  }
+
+  @Override
+  protected void finalize() {
+    Native.RegistrationSession_Destroy(this.handle);
+  }
+
+  public String getId() {
+    return Native.RegistrationSession_GetId(this.handle);
+  }
+
+  public int getNextSmsSeconds() {
+    return Native.RegistrationSession_GetNextSmsSeconds(this.handle);
+  }
+
+  public int getNextCallSeconds() {
+    return Native.RegistrationSession_GetNextCallSeconds(this.handle);
+  }
+
+  public int getNextVerificationAttemptSeconds() {
+    return Native.RegistrationSession_GetNextVerificationAttemptSeconds(this.handle);
+  }
+
+  public boolean isAllowedToRequestCode() {
+    return Native.RegistrationSession_GetAllowedToRequestCode(this.handle);
+  }
+
+  public boolean requiresPushChallenge() {
+    return Native.RegistrationSession_RequiresPushChallenge(this.handle);
+  }
+
+  public boolean requiresCaptcha() {
+    return Native.RegistrationSession_RequiresCaptcha(this.handle);
+  }
+
+  public boolean isVerified() {
+    return Native.RegistrationSession_GetVerified(this.handle);
+  }
+}
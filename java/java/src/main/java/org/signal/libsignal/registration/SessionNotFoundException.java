@@ -0,0 +1,15 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.registration;
+
+/**
+ * The registration session has expired or never existed; a new one must be created.
+ */
+public class SessionNotFoundException extends RegistrationException {
+  public SessionNotFoundException(String message) {
+    super(message);
+  }
+}
@@ -0,0 +1,17 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal;
+
+/**
+ * Thrown when a byte array passed to the native library is longer than the configured limit.
+ *
+ * See {@code Native.Limits_SetMaxBufferLength}.
+ */
+public class BufferTooLargeException extends IllegalArgumentException {
+  public BufferTooLargeException(String message) {
+    super(message);
+  }
+}
@@ -0,0 +1,40 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal;
+
+import org.signal.client.internal.Native;
+
+/**
+ * The result of a decryption that only read from the stores.
+ *
+ * @see SessionCipher#decryptDeferred(org.whispersystems.libsignal.protocol.SignalMessage)
+ * @see org.whispersystems.libsignal.groups.GroupCipher#decryptDeferred(byte[])
+ */
+public class DeferredDecryptionResult {
+  private final long handle;
+
+  @Override
+  protected void finalize() {
+    Native.DeferredDecryptionResult_Destroy(this.handle);
+  }
+
+  public DeferredDecryptionResult(long nativeHandle) {
+    this.handle = nativeHandle;
+  }
+
+  public byte[] getPlaintext() {
+    return Native.DeferredDecryptionResult_GetPlaintext(this.handle);
+  }
+
+  /**
+   * The store writes the decryption would have made, to be replayed with
+   * {@link org.whispersystems.libsignal.state.MutationLog#apply} by a process
+   * that can write to the stores.
+   */
+  public byte[] getMutationLog() {
+    return Native.DeferredDecryptionResult_GetMutationLog(this.handle);
+  }
+}
@@ -6,7 +6,32 @@
 package org.whispersystems.libsignal;
 
 public class DuplicateMessageException extends Exception {
+  private final int chainIndex;
+  private final int counter;
+
   public DuplicateMessageException(String s) {
+    this(s, -1, -1);
+  }
+
+  public DuplicateMessageException(String s, int chainIndex, int counter) {
     super(s);
+    this.chainIndex = chainIndex;
+    this.counter    = counter;
+  }
+
+  /**
+   * The next index the receiving chain expects, or -1 if the duplicate was not
+   * detected by a chain.
+   */
+  public int getChainIndex() {
+    return chainIndex;
+  }
+
+  /**
+   * The index of the duplicated message, or -1 if the duplicate was not
+   * detected by a chain.
+   */
+  public int getCounter() {
+    return counter;
   }
 }
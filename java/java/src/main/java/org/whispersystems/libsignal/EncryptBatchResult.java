@@ -0,0 +1,49 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal;
+
+import org.whispersystems.libsignal.protocol.CiphertextMessage;
+
+/**
+ * The outcome of encrypting a message for one recipient of a batch.
+ *
+ * Exactly one of {@link #getMessage()} and {@link #getError()} is non-null.
+ *
+ * @see SessionCipher#encrypt(org.whispersystems.libsignal.state.SessionStore, org.whispersystems.libsignal.state.IdentityKeyStore, java.util.List, byte[])
+ */
+public class EncryptBatchResult {
+  private final CiphertextMessage message;
+  private final Throwable         error;
+
+  EncryptBatchResult(Object nativeResult) {
+    if (nativeResult instanceof CiphertextMessage) {
+      this.message = (CiphertextMessage)nativeResult;
+      this.error   = null;
+    } else {
+      this.message = null;
+      this.error   = (Throwable)nativeResult;
+    }
+  }
+
+  /** The encrypted message, or null if encryption failed for this recipient. */
+  public CiphertextMessage getMessage() {
+    return message;
+  }
+
+  /**
+   * Why encryption failed for this recipient, or null if it succeeded.
+   *
+   * This is the exception the single-recipient {@link SessionCipher#encrypt(byte[])} would have
+   * thrown, such as {@link UntrustedIdentityException} or {@link NoSessionException}.
+   */
+  public Throwable getError() {
+    return error;
+  }
+
+  public boolean isSuccess() {
+    return message != null;
+  }
+}
@@ -6,6 +6,7 @@
 package org.whispersystems.libsignal;
 
 
+import org.signal.client.internal.Native;
 import org.whispersystems.libsignal.ecc.Curve;
 import org.whispersystems.libsignal.ecc.ECPublicKey;
 import org.whispersystems.libsignal.util.Hex;
@@ -47,6 +48,13 @@ public class IdentityKey {
   public String getFingerprint() {
     return Hex.toString(publicKey.serialize());
   }
+
+  /**
+   * Checks that a payment address from a profile was signed by this identity key.
+   */
+  public boolean verifyPaymentAddress(byte[] address, byte[] signature) {
+    return Native.PaymentAddress_Verify(this.nativeHandle(), address, signature);
+  }
 	
   @Override
   public boolean equals(Object other) {
@@ -43,4 +43,13 @@ public class IdentityKeyPair {
   public byte[] serialize() {
     return Native.IdentityKeyPair_Serialize(this.publicKey.nativeHandle(), this.privateKey.nativeHandle());
   }
+
+  /**
+   * Signs a payment address to be published in this account's profile.
+   *
+   * @see IdentityKey#verifyPaymentAddress
+   */
+  public byte[] signPaymentAddress(byte[] address) {
+    return Native.PaymentAddress_Sign(this.publicKey.nativeHandle(), this.privateKey.nativeHandle(), address, 0);
+  }
 }
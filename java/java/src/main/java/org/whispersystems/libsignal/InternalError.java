@@ -0,0 +1,26 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal;
+
+/**
+ * Thrown when the native library hits a bug it cannot recover from, such as a panic.
+ *
+ * This extends {@link AssertionError} because that is what such failures were reported as
+ * previously.
+ */
+public class InternalError extends AssertionError {
+  private final String functionName;
+
+  public InternalError(String message, String functionName) {
+    super(message);
+    this.functionName = functionName;
+  }
+
+  /** The name of the native method that failed. */
+  public String getFunctionName() {
+    return functionName;
+  }
+}
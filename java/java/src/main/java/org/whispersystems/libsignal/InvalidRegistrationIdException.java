@@ -0,0 +1,29 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal;
+
+/**
+ * Thrown when a remote client's registration ID is out of range, which means its session state
+ * cannot be trusted.
+ */
+public class InvalidRegistrationIdException extends InvalidKeyException {
+  private final SignalProtocolAddress address;
+  private final int registrationId;
+
+  public InvalidRegistrationIdException(SignalProtocolAddress address, int registrationId, String message) {
+    super(message);
+    this.address        = address;
+    this.registrationId = registrationId;
+  }
+
+  public SignalProtocolAddress getAddress() {
+    return address;
+  }
+
+  public int getRegistrationId() {
+    return registrationId;
+  }
+}
@@ -0,0 +1,62 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal;
+
+import org.signal.client.internal.Native;
+
+/**
+ * How far to pad a plaintext before encrypting it, so that ciphertext lengths reveal less.
+ *
+ * A padded message is the original message, a {@code 0x80} terminator byte, and zeros, so any
+ * policy other than {@link #none()} can remove padding added under any other.
+ */
+public class PaddingPolicy {
+  private final long handle;
+
+  private PaddingPolicy(long handle) {
+    this.handle = handle;
+  }
+
+  /** Messages are sent as-is. */
+  public static PaddingPolicy none() {
+    return new PaddingPolicy(Native.PaddingPolicy_None());
+  }
+
+  /** Messages are padded up to the next multiple of {@code bucketSize}. */
+  public static PaddingPolicy bucketed(int bucketSize) {
+    return new PaddingPolicy(Native.PaddingPolicy_Bucketed(bucketSize));
+  }
+
+  /** Messages are padded with the Padmé scheme, which adds at most about 12%. */
+  public static PaddingPolicy padme() {
+    return new PaddingPolicy(Native.PaddingPolicy_Padme());
+  }
+
+  @Override
+  protected void finalize() {
+    Native.PaddingPolicy_Destroy(this.handle);
+  }
+
+  /** The length of a message of {@code length} bytes after {@link #pad}. */
+  public int getPaddedLength(int length) {
+    return Native.PaddingPolicy_PaddedLength(this.handle, length);
+  }
+
+  public byte[] pad(byte[] message) {
+    return Native.PaddingPolicy_Pad(this.handle, message);
+  }
+
+  /**
+   * @throws InvalidMessageException if the padding is malformed
+   */
+  public byte[] unpad(byte[] paddedMessage) throws InvalidMessageException {
+    return Native.PaddingPolicy_Unpad(this.handle, paddedMessage);
+  }
+
+  public long nativeHandle() {
+    return this.handle;
+  }
+}
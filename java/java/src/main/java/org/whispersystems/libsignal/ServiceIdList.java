@@ -0,0 +1,54 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal;
+
+import org.whispersystems.libsignal.state.IdentityKeyStore;
+import org.whispersystems.libsignal.state.SessionStore;
+
+import java.io.ByteArrayOutputStream;
+import java.nio.charset.StandardCharsets;
+import java.util.List;
+
+/**
+ * The compact recipient list accepted by multi-recipient operations such as
+ * {@link SessionCipher#encrypt(SessionStore, IdentityKeyStore, byte[], byte[])}.
+ *
+ * Each recipient is a one-byte length, the UTF-8 service ID (the address name), and the device ID
+ * as a big-endian 32-bit integer. Building the list in Java avoids creating a native
+ * {@link SignalProtocolAddress} for every recipient of a large send.
+ */
+public final class ServiceIdList {
+  private final ByteArrayOutputStream serialized = new ByteArrayOutputStream();
+
+  public ServiceIdList() {}
+
+  public ServiceIdList(List<SignalProtocolAddress> addresses) {
+    for (SignalProtocolAddress address : addresses) {
+      add(address.getName(), address.getDeviceId());
+    }
+  }
+
+  /**
+   * @throws IllegalArgumentException if {@code serviceId} is longer than 255 bytes as UTF-8
+   */
+  public ServiceIdList add(String serviceId, int deviceId) {
+    byte[] name = serviceId.getBytes(StandardCharsets.UTF_8);
+    if (name.length > 255) {
+      throw new IllegalArgumentException("service ID is longer than 255 bytes");
+    }
+    serialized.write(name.length);
+    serialized.write(name, 0, name.length);
+    serialized.write(deviceId >>> 24);
+    serialized.write(deviceId >>> 16);
+    serialized.write(deviceId >>> 8);
+    serialized.write(deviceId);
+    return this;
+  }
+
+  public byte[] serialize() {
+    return serialized.toByteArray();
+  }
+}
@@ -0,0 +1,68 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal;
+
+import org.signal.client.internal.Native;
+
+/**
+ * A sorted set of service IDs, kept in native memory so that large sets (such as contact discovery
+ * results) can be compared without copying them back and forth.
+ *
+ * <p>IDs are passed in their fixed-width binary form: a kind byte (0 for an ACI, 1 for a PNI)
+ * followed by the 16 bytes of the UUID. Lists of IDs are these 17-byte entries concatenated.
+ */
+public final class ServiceIdSet {
+  public static final int FIXED_WIDTH_LENGTH = 17;
+
+  private final long handle;
+
+  public ServiceIdSet() {
+    this(Native.ServiceIdSet_New());
+  }
+
+  private ServiceIdSet(long handle) {
+    this.handle = handle;
+  }
+
+  @Override
+  protected void finalize() {
+    Native.ServiceIdSet_Destroy(this.handle);
+  }
+
+  /**
+   * Adds a list of fixed-width binary IDs. Nothing is added if any entry is invalid.
+   *
+   * @throws IllegalArgumentException if the list is malformed
+   */
+  public void add(byte[] fixedWidthBinaryList) {
+    Native.ServiceIdSet_AddFixedWidthBinary(this.handle, fixedWidthBinaryList);
+  }
+
+  public int size() {
+    return Native.ServiceIdSet_Len(this.handle);
+  }
+
+  public boolean contains(byte[] fixedWidthBinaryServiceId) {
+    return Native.ServiceIdSet_Contains(this.handle, fixedWidthBinaryServiceId);
+  }
+
+  public ServiceIdSet intersection(ServiceIdSet other) {
+    return new ServiceIdSet(Native.ServiceIdSet_Intersection(this.handle, other.handle));
+  }
+
+  /** The IDs in this set that are not in {@code other}. */
+  public ServiceIdSet difference(ServiceIdSet other) {
+    return new ServiceIdSet(Native.ServiceIdSet_Difference(this.handle, other.handle));
+  }
+
+  /**
+   * Up to {@code limit} IDs in fixed-width binary form, in sorted order, skipping the first {@code
+   * offset}.
+   */
+  public byte[] getPage(int offset, int limit) {
+    return Native.ServiceIdSet_GetPage(this.handle, offset, limit);
+  }
+}
@@ -89,6 +89,7 @@ public class SessionBuilder {
                                               remoteAddress.nativeHandle(),
                                               sessionStore,
                                               identityKeyStore,
+                                              0,
                                               null);
   }
 }
@@ -9,6 +9,7 @@ import org.signal.client.internal.Native;
 
 import org.whispersystems.libsignal.ecc.ECPublicKey;
 import org.whispersystems.libsignal.protocol.CiphertextMessage;
+import org.whispersystems.libsignal.protocol.Content;
 import org.whispersystems.libsignal.protocol.PreKeySignalMessage;
 import org.whispersystems.libsignal.protocol.SignalMessage;
 import org.whispersystems.libsignal.state.SignalProtocolStore;
@@ -20,6 +21,8 @@ import org.whispersystems.libsignal.state.SignedPreKeyStore;
 
 import java.security.InvalidAlgorithmParameterException;
 import java.security.NoSuchAlgorithmException;
+import java.util.ArrayList;
+import java.util.List;
 
 /**
  * The main entry point for Signal Protocol encrypt/decrypt operations.
@@ -76,6 +79,90 @@ public class SessionCipher {
                            identityKeyStore);
   }
 
+  /**
+   * Pad a message according to {@code padding}, then encrypt it.
+   *
+   * @param  message The unpadded plaintext message bytes.
+   * @return A ciphertext message encrypted to the recipient+device tuple.
+   */
+  public CiphertextMessage encrypt(byte[] message, PaddingPolicy padding) throws UntrustedIdentityException {
+    return encrypt(padding.pad(message));
+  }
+
+  /**
+   * Serialize {@code content}, pad it according to {@code padding}, and encrypt it.
+   *
+   * @param  content The message content to send.
+   * @return A ciphertext message encrypted to the recipient+device tuple.
+   */
+  public CiphertextMessage encrypt(Content content, PaddingPolicy padding) throws UntrustedIdentityException {
+    return encrypt(content.serialize(), padding);
+  }
+
+  /**
+   * Encrypt a message, trusting {@code approvedIdentity} even if the {@link IdentityKeyStore}
+   * does not.
+   *
+   * Use this to retry after an {@link UntrustedIdentityException} once the user has accepted the
+   * recipient's new identity. If the session's identity is {@code approvedIdentity}, it is saved to
+   * the identity store as part of the same operation; otherwise the store decides as usual.
+   *
+   * @param  paddedMessage The plaintext message bytes, optionally padded to a constant multiple.
+   * @param  approvedIdentity The identity the user has decided to trust for this recipient.
+   * @return A ciphertext message encrypted to the recipient+device tuple.
+   */
+  public CiphertextMessage encrypt(byte[] paddedMessage, IdentityKey approvedIdentity)
+      throws UntrustedIdentityException
+  {
+    return Native.SessionCipher_EncryptMessageWithIdentityOverride(paddedMessage,
+                                                                   this.remoteAddress.nativeHandle(),
+                                                                   approvedIdentity.nativeHandle(),
+                                                                   sessionStore,
+                                                                   identityKeyStore);
+  }
+
+  /**
+   * Encrypt a message to several recipients at once.
+   *
+   * Each session is loaded from and stored to {@code sessionStore} only once. A recipient whose
+   * session is missing or whose identity is untrusted does not stop the others from being
+   * encrypted; its result carries the error instead, and its session is left untouched.
+   *
+   * @param  remoteAddresses The recipient+device tuples to encrypt to.
+   * @param  paddedMessage The plaintext message bytes, optionally padded to a constant multiple.
+   * @return One result per address, in the same order as {@code remoteAddresses}.
+   */
+  public static List<EncryptBatchResult> encrypt(SessionStore sessionStore,
+                                                 IdentityKeyStore identityKeyStore,
+                                                 List<SignalProtocolAddress> remoteAddresses,
+                                                 byte[] paddedMessage)
+  {
+    return encrypt(sessionStore, identityKeyStore, new ServiceIdList(remoteAddresses).serialize(), paddedMessage);
+  }
+
+  /**
+   * Encrypt a message to several recipients at once, as above.
+   *
+   * @param  recipients The recipient+device tuples to encrypt to, as a serialized {@link ServiceIdList}.
+   * @param  paddedMessage The plaintext message bytes, optionally padded to a constant multiple.
+   * @return One result per recipient, in the same order as {@code recipients}.
+   */
+  public static List<EncryptBatchResult> encrypt(SessionStore sessionStore,
+                                                 IdentityKeyStore identityKeyStore,
+                                                 byte[] recipients,
+                                                 byte[] paddedMessage)
+  {
+    Object[] nativeResults = Native.SessionCipher_EncryptMessageBatch(paddedMessage,
+                                                                      recipients,
+                                                                      sessionStore,
+                                                                      identityKeyStore);
+    List<EncryptBatchResult> results = new ArrayList<>(nativeResults.length);
+    for (Object nativeResult : nativeResults) {
+      results.add(new EncryptBatchResult(nativeResult));
+    }
+    return results;
+  }
+
   /**
    * Decrypt a message.
    *
@@ -101,6 +188,7 @@ public class SessionCipher {
                                                            identityKeyStore,
                                                            preKeyStore,
                                                            signedPreKeyStore,
+                                                           0,
                                                            null);
   }
 
@@ -124,9 +212,97 @@ public class SessionCipher {
                                                       remoteAddress.nativeHandle(),
                                                       sessionStore,
                                                       identityKeyStore,
+                                                      0,
                                                       null);
   }
 
+  /**
+   * Decrypt a message like {@link #decrypt(PreKeySignalMessage)}, but without writing to any of
+   * the stores.
+   *
+   * Meant for processes that can read the stores but must not write to them. The writes are
+   * returned as {@link DeferredDecryptionResult#getMutationLog()} instead, to be replayed later
+   * with {@link org.whispersystems.libsignal.state.MutationLog#apply}.
+   */
+  public DeferredDecryptionResult decryptDeferred(PreKeySignalMessage ciphertext)
+      throws DuplicateMessageException, LegacyMessageException, InvalidMessageException,
+             InvalidKeyIdException, InvalidKeyException, UntrustedIdentityException
+  {
+    return new DeferredDecryptionResult(
+        Native.SessionCipher_DecryptPreKeySignalMessageDeferred(ciphertext.nativeHandle(),
+                                                                remoteAddress.nativeHandle(),
+                                                                sessionStore,
+                                                                identityKeyStore,
+                                                                preKeyStore,
+                                                                signedPreKeyStore,
+                                                                0,
+                                                                null));
+  }
+
+  /**
+   * Decrypt a message like {@link #decrypt(SignalMessage)}, but without writing to any of the
+   * stores.
+   *
+   * @see #decryptDeferred(PreKeySignalMessage)
+   */
+  public DeferredDecryptionResult decryptDeferred(SignalMessage ciphertext)
+      throws InvalidMessageException, DuplicateMessageException, LegacyMessageException,
+      NoSessionException, UntrustedIdentityException
+  {
+    return new DeferredDecryptionResult(
+        Native.SessionCipher_DecryptSignalMessageDeferred(ciphertext.nativeHandle(),
+                                                          remoteAddress.nativeHandle(),
+                                                          sessionStore,
+                                                          identityKeyStore,
+                                                          0,
+                                                          null));
+  }
+
+  /**
+   * Decrypt a message, trusting {@code approvedIdentity} even if the {@link IdentityKeyStore}
+   * does not.
+   *
+   * Use this to retry after an {@link UntrustedIdentityException} once the user has accepted the
+   * sender's new identity. If the sender's identity is {@code approvedIdentity}, it is saved to the
+   * identity store as part of the same operation; otherwise the store decides as usual.
+   *
+   * @see #decrypt(PreKeySignalMessage)
+   */
+  public byte[] decrypt(PreKeySignalMessage ciphertext, IdentityKey approvedIdentity)
+      throws DuplicateMessageException, LegacyMessageException, InvalidMessageException,
+             InvalidKeyIdException, InvalidKeyException, UntrustedIdentityException
+  {
+    return Native.SessionCipher_DecryptPreKeySignalMessageWithIdentityOverride(ciphertext.nativeHandle(),
+                                                                               remoteAddress.nativeHandle(),
+                                                                               approvedIdentity.nativeHandle(),
+                                                                               sessionStore,
+                                                                               identityKeyStore,
+                                                                               preKeyStore,
+                                                                               signedPreKeyStore,
+                                                                               0,
+                                                                               null);
+  }
+
+  /**
+   * Decrypt a message, trusting {@code approvedIdentity} even if the {@link IdentityKeyStore}
+   * does not.
+   *
+   * @see #decrypt(PreKeySignalMessage, IdentityKey)
+   * @see #decrypt(SignalMessage)
+   */
+  public byte[] decrypt(SignalMessage ciphertext, IdentityKey approvedIdentity)
+      throws InvalidMessageException, DuplicateMessageException, LegacyMessageException,
+      NoSessionException, UntrustedIdentityException
+  {
+    return Native.SessionCipher_DecryptSignalMessageWithIdentityOverride(ciphertext.nativeHandle(),
+                                                                         remoteAddress.nativeHandle(),
+                                                                         approvedIdentity.nativeHandle(),
+                                                                         sessionStore,
+                                                                         identityKeyStore,
+                                                                         0,
+                                                                         null);
+  }
+
   public int getRemoteRegistrationId() {
     if (!sessionStore.containsSession(remoteAddress)) {
       throw new IllegalStateException(String.format("No session for (%s)!", remoteAddress));
@@ -10,8 +10,15 @@ import org.signal.client.internal.Native;
 public class SignalProtocolAddress {
   private final long handle;
 
+  // Filled in on first use, so that stores keyed by address don't cross into native code for
+  // every lookup.
+  private String  name;
+  private Integer deviceId;
+
   public SignalProtocolAddress(String name, int deviceId) {
-    this.handle = Native.ProtocolAddress_New(name, deviceId);
+    this.handle   = Native.ProtocolAddress_New(name, deviceId);
+    this.name     = name;
+    this.deviceId = deviceId;
   }
 
   public SignalProtocolAddress(long handle) {
@@ -24,11 +31,17 @@ public class SignalProtocolAddress {
   }
 
   public String getName() {
-    return Native.ProtocolAddress_Name(this.handle);
+    if (this.name == null) {
+      this.name = Native.ProtocolAddress_Name(this.handle);
+    }
+    return this.name;
   }
 
   public int getDeviceId() {
-    return Native.ProtocolAddress_DeviceId(this.handle);
+    if (this.deviceId == null) {
+      this.deviceId = Native.ProtocolAddress_DeviceId(this.handle);
+    }
+    return this.deviceId;
   }
 
   @Override
@@ -6,12 +6,13 @@
 
 package org.whispersystems.libsignal.ecc;
 import org.signal.client.internal.Native;
+import org.whispersystems.libsignal.InvalidMessageException;
 
 public class ECPrivateKey {
   private long handle;
 
   static ECPrivateKey generate() {
-    return new ECPrivateKey(Native.ECPrivateKey_Generate());
+    return new ECPrivateKey(Native.ECPrivateKey_Generate(0));
   }
 
   ECPrivateKey(byte[] privateKey) {
@@ -35,13 +36,23 @@ public class ECPrivateKey {
   }
 
   public byte[] calculateSignature(byte[] message) {
-     return Native.ECPrivateKey_Sign(this.handle, message);
+     return Native.ECPrivateKey_Sign(this.handle, message, 0);
   }
 
   public byte[] calculateAgreement(ECPublicKey other) {
     return Native.ECPrivateKey_Agree(this.handle, other.nativeHandle());
   }
 
+  /**
+   * Decrypts a message from {@link ECPublicKey#seal} for this key's public key.
+   *
+   * @throws InvalidMessageException if the message was sealed for a different key or has been
+   *     modified
+   */
+  public byte[] open(byte[] sealed) throws InvalidMessageException {
+    return Native.ECPrivateKey_Open(this.handle, sealed);
+  }
+
   public long nativeHandle() {
     return this.handle;
   }
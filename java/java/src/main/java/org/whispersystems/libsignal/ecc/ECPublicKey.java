@@ -46,6 +46,16 @@ public class ECPublicKey implements Comparable<ECPublicKey> {
     return Native.ECPublicKey_Verify(this.handle, message, signature);
   }
 
+  /**
+   * Encrypts a message that only the holder of the matching private key can read, without
+   * identifying the sender.
+   *
+   * @see ECPrivateKey#open
+   */
+  public byte[] seal(byte[] plaintext) {
+    return Native.ECPublicKey_Seal(this.handle, plaintext, 0);
+  }
+
   public byte[] serialize() {
     return Native.ECPublicKey_Serialize(this.handle);
   }
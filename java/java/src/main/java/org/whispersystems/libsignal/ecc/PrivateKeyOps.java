@@ -0,0 +1,20 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal.ecc;
+
+/**
+ * Operations on a private key that may not be available in memory, such as one held by the
+ * Android keystore.
+ */
+public interface PrivateKeyOps {
+  /**
+   * Calculate a Diffie-Hellman shared secret with another party's public key.
+   *
+   * @param theirKey The other party's public key.
+   * @return The 32-byte shared secret.
+   */
+  public byte[] calculateAgreement(ECPublicKey theirKey);
+}
@@ -6,11 +6,13 @@
 package org.whispersystems.libsignal.groups;
 
 import org.signal.client.internal.Native;
+import org.whispersystems.libsignal.DeferredDecryptionResult;
 import org.whispersystems.libsignal.DuplicateMessageException;
 import org.whispersystems.libsignal.InvalidKeyIdException;
 import org.whispersystems.libsignal.InvalidMessageException;
 import org.whispersystems.libsignal.LegacyMessageException;
 import org.whispersystems.libsignal.NoSessionException;
+import org.whispersystems.libsignal.PaddingPolicy;
 import org.whispersystems.libsignal.groups.state.SenderKeyStore;
 
 import java.security.InvalidAlgorithmParameterException;
@@ -47,12 +49,23 @@ public class GroupCipher {
    */
   public byte[] encrypt(byte[] paddedPlaintext) throws NoSessionException {
     try {
-      return Native.GroupCipher_EncryptMessage(this.senderKeyId.nativeHandle(), paddedPlaintext, this.senderKeyStore, null);
+      return Native.GroupCipher_EncryptMessage(this.senderKeyId.nativeHandle(), paddedPlaintext, this.senderKeyStore, 0, null);
     } catch (IllegalStateException e) {
       throw new NoSessionException(e);
     }
   }
 
+  /**
+   * Pad a message according to {@code padding}, then encrypt it.
+   *
+   * @param plaintext The unpadded plaintext message bytes.
+   * @return Ciphertext.
+   * @throws NoSessionException
+   */
+  public byte[] encrypt(byte[] plaintext, PaddingPolicy padding) throws NoSessionException {
+    return encrypt(padding.pad(plaintext));
+  }
+
   /**
    * Decrypt a SenderKey group message.
    *
@@ -71,4 +84,56 @@ public class GroupCipher {
       throw new NoSessionException(e);
     }
   }
+
+  /**
+   * Decrypt a SenderKey group message like {@link #decrypt(byte[])}, but without writing to the
+   * sender key store.
+   *
+   * The writes are returned as {@link DeferredDecryptionResult#getMutationLog()} instead, to be
+   * replayed later with {@link org.whispersystems.libsignal.state.MutationLog#apply}.
+   */
+  public DeferredDecryptionResult decryptDeferred(byte[] senderKeyMessageBytes)
+      throws LegacyMessageException, DuplicateMessageException, InvalidMessageException, NoSessionException
+  {
+    try {
+      return new DeferredDecryptionResult(
+          Native.GroupCipher_DecryptMessageDeferred(this.senderKeyId.nativeHandle(), senderKeyMessageBytes, this.senderKeyStore, null));
+    } catch (IllegalStateException e) {
+      throw new NoSessionException(e);
+    }
+  }
+
+  /**
+   * Get the next iteration expected on a sender key chain.
+   *
+   * @param distributionId The ID of the sender key, as found in its distribution message.
+   * @return The next iteration that can be decrypted without skipping any messages.
+   * @throws NoSessionException
+   */
+  public int getChainPosition(int distributionId) throws NoSessionException {
+    try {
+      return Native.GroupCipher_ChainPosition(this.senderKeyId.nativeHandle(), distributionId, this.senderKeyStore, null);
+    } catch (IllegalStateException e) {
+      throw new NoSessionException(e);
+    }
+  }
+
+  /**
+   * Advance a sender key chain past messages that are too far ahead to decrypt normally.
+   *
+   * Messages from the skipped iterations can no longer be decrypted, so only use this once the
+   * sender's position has been verified out of band. Moving the chain backwards, or forward by more
+   * than 1,000,000 iterations at once, is an error.
+   *
+   * @param distributionId The ID of the sender key, as found in its distribution message.
+   * @param toIteration The next iteration to expect.
+   * @throws NoSessionException
+   */
+  public void fastForward(int distributionId, int toIteration) throws NoSessionException {
+    try {
+      Native.GroupCipher_FastForward(this.senderKeyId.nativeHandle(), distributionId, toIteration, this.senderKeyStore, null);
+    } catch (IllegalStateException e) {
+      throw new NoSessionException(e);
+    }
+  }
 }
@@ -53,6 +53,6 @@ public class GroupSessionBuilder {
    * @return A SenderKeyDistributionMessage that is individually distributed to each member of the group.
    */
   public SenderKeyDistributionMessage create(SenderKeyName senderKeyName) {
-    return new SenderKeyDistributionMessage(Native.GroupSessionBuilder_CreateSenderKeyDistributionMessage(senderKeyName.nativeHandle(), senderKeyStore, null));
+    return new SenderKeyDistributionMessage(Native.GroupSessionBuilder_CreateSenderKeyDistributionMessage(senderKeyName.nativeHandle(), senderKeyStore, 0, null));
   }
 }
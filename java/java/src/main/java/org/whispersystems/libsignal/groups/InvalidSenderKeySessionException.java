@@ -0,0 +1,31 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal.groups;
+
+import org.whispersystems.libsignal.NoSessionException;
+import org.whispersystems.libsignal.SignalProtocolAddress;
+
+/**
+ * Thrown when there is no sender key session for a particular sender in a group.
+ */
+public class InvalidSenderKeySessionException extends NoSessionException {
+  private final String groupId;
+  private final SignalProtocolAddress sender;
+
+  public InvalidSenderKeySessionException(String groupId, SignalProtocolAddress sender, String message) {
+    super(message);
+    this.groupId = groupId;
+    this.sender  = sender;
+  }
+
+  public String getGroupId() {
+    return groupId;
+  }
+
+  public SignalProtocolAddress getSender() {
+    return sender;
+  }
+}
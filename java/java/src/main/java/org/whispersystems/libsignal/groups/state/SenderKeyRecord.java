@@ -6,6 +6,7 @@
 package org.whispersystems.libsignal.groups.state;
 
 import org.signal.client.internal.Native;
+import org.whispersystems.libsignal.SignalProtocolAddress;
 import java.io.IOException;
 
 /**
@@ -34,10 +35,50 @@ public class SenderKeyRecord {
     handle = Native.SenderKeyRecord_Deserialize(serialized);
   }
 
+  /** The most states a record may have when deserialized. */
+  public static int getMaxStates() {
+    return Native.SenderKeyRecord_MaxStates();
+  }
+
+  /** The most skipped-message keys each state in a record may have. */
+  public static int getMaxMessageKeysPerState() {
+    return Native.SenderKeyRecord_MaxMessageKeysPerState();
+  }
+
   public byte[] serialize() {
     return Native.SenderKeyRecord_GetSerialized(this.handle);
   }
 
+  public int getSerializedVersion() {
+    return Native.SenderKeyRecord_GetSerializedVersion(this.handle);
+  }
+
+  /**
+   * Records that {@code member} has been sent a distribution message for the current sender key.
+   *
+   * @throws IllegalStateException if there is no sender key yet
+   */
+  public void markDistributed(SignalProtocolAddress member) {
+    Native.SenderKeyRecord_MarkDistributed(this.handle, member.nativeHandle());
+  }
+
+  /**
+   * Returns true if {@code member} has not been sent the current sender key.
+   */
+  public boolean needsDistribution(SignalProtocolAddress member) {
+    return Native.SenderKeyRecord_NeedsDistribution(this.handle, member.nativeHandle());
+  }
+
+  /**
+   * Stops tracking a member who has left the group.
+   *
+   * @return true if the member holds the current sender key, which should then be replaced
+   *         before the next message is sent to the group
+   */
+  public boolean forgetDistribution(SignalProtocolAddress member) {
+    return Native.SenderKeyRecord_ForgetDistribution(this.handle, member.nativeHandle());
+  }
+
   public long nativeHandle() {
     return this.handle;
   }
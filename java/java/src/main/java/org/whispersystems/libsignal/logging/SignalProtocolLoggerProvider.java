@@ -5,6 +5,8 @@
  */
 package org.whispersystems.libsignal.logging;
 
+import org.signal.internal.Native;
+
 public class SignalProtocolLoggerProvider {
 
   private static SignalProtocolLogger provider;
@@ -16,4 +18,14 @@ public class SignalProtocolLoggerProvider {
   public static void setProvider(SignalProtocolLogger provider) {
     SignalProtocolLoggerProvider.provider = provider;
   }
+
+  /**
+   * Also forward spans and events from the native protocol code to the provider.
+   *
+   * Spans are logged at {@link SignalProtocolLogger#DEBUG} and each store callback at
+   * {@link SignalProtocolLogger#VERBOSE}, so the native log level must allow those to see them.
+   */
+  public static void enableTracing() {
+    Native.Tracing_InstallLogSubscriber();
+  }
 }
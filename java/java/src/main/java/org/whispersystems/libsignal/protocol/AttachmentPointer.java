@@ -0,0 +1,43 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal.protocol;
+
+import org.signal.client.internal.Native;
+
+/** A reference to an encrypted attachment that has been uploaded to a CDN. */
+public final class AttachmentPointer {
+  private final long handle;
+
+  /**
+   * @param key the attachment's 64-byte AES and HMAC key
+   * @param digest the SHA-256 of the uploaded ciphertext
+   * @param size the length of the plaintext
+   * @throws IllegalArgumentException if the key or digest is the wrong size, or the CDN key is empty
+   */
+  public AttachmentPointer(int cdnNumber, String cdnKey, String contentType, byte[] key, byte[] digest, int size) {
+    this(cdnNumber, cdnKey, contentType, key, digest, size, null, null, 0, 0, 0);
+  }
+
+  /**
+   * Like {@link #AttachmentPointer(int, String, String, byte[], byte[], int)}, with the optional
+   * fields too. {@code width}, {@code height}, and {@code uploadTimestamp} are 0 if unknown.
+   */
+  public AttachmentPointer(int cdnNumber, String cdnKey, String contentType, byte[] key, byte[] digest, int size,
+                           String fileName, String caption, int width, int height, long uploadTimestamp)
+  {
+    this.handle = Native.AttachmentPointer_New(cdnNumber, cdnKey, contentType, key, digest, size,
+                                               fileName, caption, width, height, uploadTimestamp);
+  }
+
+  @Override
+  protected void finalize() {
+    Native.AttachmentPointer_Destroy(this.handle);
+  }
+
+  public long nativeHandle() {
+    return this.handle;
+  }
+}
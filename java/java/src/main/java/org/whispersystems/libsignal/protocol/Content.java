@@ -0,0 +1,59 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal.protocol;
+
+import org.signal.client.internal.Native;
+
+import java.nio.ByteBuffer;
+
+/**
+ * A message ready to be padded and encrypted, such as with
+ * {@link org.whispersystems.libsignal.SessionCipher#encrypt(Content, org.whispersystems.libsignal.PaddingPolicy)}.
+ *
+ * Build data messages with {@link DataMessageBuilder}.
+ */
+public final class Content {
+  public enum ReceiptType {
+    DELIVERY, READ, VIEWED
+  }
+
+  private final long handle;
+
+  Content(long handle) {
+    this.handle = handle;
+  }
+
+  /**
+   * Acknowledges the messages sent at each of {@code timestamps}.
+   *
+   * @throws IllegalArgumentException if {@code timestamps} is empty
+   */
+  public static Content receipt(ReceiptType type, long[] timestamps) {
+    ByteBuffer packed = ByteBuffer.allocate(timestamps.length * 8);
+    for (long timestamp : timestamps) {
+      packed.putLong(timestamp);
+    }
+    return new Content(Native.Content_NewReceiptMessage(type.ordinal(), packed.array()));
+  }
+
+  /** A typing indicator, in the group with ID {@code groupId} if not null. */
+  public static Content typing(long timestamp, boolean started, byte[] groupId) {
+    return new Content(Native.Content_NewTypingMessage(timestamp, started, groupId == null ? new byte[0] : groupId));
+  }
+
+  @Override
+  protected void finalize() {
+    Native.Content_Destroy(this.handle);
+  }
+
+  public byte[] serialize() {
+    return Native.Content_GetSerialized(this.handle);
+  }
+
+  public long nativeHandle() {
+    return this.handle;
+  }
+}
@@ -0,0 +1,62 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal.protocol;
+
+import org.signal.client.internal.Native;
+
+/**
+ * Assembles a data message, checking each part as it is added.
+ *
+ * Each setter throws {@link IllegalArgumentException} if its argument would make the message
+ * invalid, and leaves the builder unchanged.
+ */
+public final class DataMessageBuilder {
+  private final long handle;
+
+  public DataMessageBuilder(long timestamp) {
+    this.handle = Native.DataMessageBuilder_New(timestamp);
+  }
+
+  @Override
+  protected void finalize() {
+    Native.DataMessageBuilder_Destroy(this.handle);
+  }
+
+  /** Longer text should be sent as a "long text" attachment instead. */
+  public DataMessageBuilder setBody(String body) {
+    Native.DataMessageBuilder_SetBody(this.handle, body);
+    return this;
+  }
+
+  public DataMessageBuilder addAttachment(AttachmentPointer attachment) {
+    Native.DataMessageBuilder_AddAttachment(this.handle, attachment.nativeHandle());
+    return this;
+  }
+
+  /** Quotes the message sent at {@code id} (its timestamp) by {@code authorUuid}. */
+  public DataMessageBuilder setQuote(long id, String authorUuid, String text) {
+    Native.DataMessageBuilder_SetQuote(this.handle, id, authorUuid, text);
+    return this;
+  }
+
+  /** The disappearing message timer, in seconds; 0 turns it off. */
+  public DataMessageBuilder setExpireTimer(int seconds) {
+    Native.DataMessageBuilder_SetExpireTimer(this.handle, seconds);
+    return this;
+  }
+
+  public DataMessageBuilder setProfileKey(byte[] profileKey) {
+    Native.DataMessageBuilder_SetProfileKey(this.handle, profileKey);
+    return this;
+  }
+
+  /**
+   * @throws IllegalArgumentException if the message has neither a body nor any attachments
+   */
+  public Content build() {
+    return new Content(Native.DataMessageBuilder_Build(this.handle));
+  }
+}
@@ -31,7 +31,7 @@ public class SenderKeyMessage implements CiphertextMessage {
   }
 
   public SenderKeyMessage(int keyId, int iteration, byte[] ciphertext, ECPrivateKey signatureKey) {
-    handle = Native.SenderKeyMessage_New(keyId, iteration, ciphertext, signatureKey.nativeHandle());
+    handle = Native.SenderKeyMessage_New(keyId, iteration, ciphertext, signatureKey.nativeHandle(), 0);
   }
 
   public int getKeyId() {
@@ -42,6 +42,10 @@ public class SenderKeyMessage implements CiphertextMessage {
     return Native.SenderKeyMessage_GetIteration(this.handle);
   }
 
+  public int getMessageVersion() {
+    return Native.SenderKeyMessage_GetMessageVersion(this.handle);
+  }
+
   public byte[] getCipherText() {
     return Native.SenderKeyMessage_GetCipherText(this.handle);
   }
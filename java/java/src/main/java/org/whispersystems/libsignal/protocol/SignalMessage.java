@@ -54,6 +54,10 @@ public class SignalMessage implements CiphertextMessage {
     return Native.SignalMessage_GetCounter(this.handle);
   }
 
+  public int getPreviousCounter() {
+    return Native.SignalMessage_GetPreviousCounter(this.handle);
+  }
+
   public byte[] getBody() {
     return Native.SignalMessage_GetBody(this.handle);
   }
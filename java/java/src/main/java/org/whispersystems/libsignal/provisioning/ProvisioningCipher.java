@@ -0,0 +1,51 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal.provisioning;
+
+import org.signal.client.internal.Native;
+import org.whispersystems.libsignal.InvalidKeyException;
+import org.whispersystems.libsignal.InvalidMessageException;
+import org.whispersystems.libsignal.InvalidVersionException;
+import org.whispersystems.libsignal.ecc.ECPublicKey;
+
+/**
+ * Encrypts the provisioning message a primary device sends to a newly linked device.
+ *
+ * The linked device creates a ProvisioningCipher and shows its public key to the primary device,
+ * which passes that key to {@link #encrypt}. The cipher's key pair should be discarded once the
+ * provisioning message has been decrypted.
+ */
+public class ProvisioningCipher {
+  private final long handle;
+
+  public ProvisioningCipher() {
+    this.handle = Native.ProvisioningCipher_New(0);
+  }
+
+  @Override
+  protected void finalize() {
+    Native.ProvisioningCipher_Destroy(this.handle);
+  }
+
+  public ECPublicKey getPublicKey() {
+    return new ECPublicKey(Native.ProvisioningCipher_GetPublicKey(this.handle));
+  }
+
+  /**
+   * Decrypts a serialized ProvisionEnvelope sent by the primary device.
+   */
+  public byte[] decrypt(byte[] envelope) throws InvalidMessageException, InvalidVersionException, InvalidKeyException {
+    return Native.ProvisioningCipher_Decrypt(this.handle, envelope);
+  }
+
+  /**
+   * Encrypts {@code message} for the linked device that showed {@code theirPublicKey}, returning a
+   * serialized ProvisionEnvelope.
+   */
+  public static byte[] encrypt(ECPublicKey theirPublicKey, byte[] message) {
+    return Native.ProvisioningCipher_Encrypt(theirPublicKey.nativeHandle(), message, 0);
+  }
+}
@@ -0,0 +1,38 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal.provisioning;
+
+import org.signal.client.internal.Native;
+import org.whispersystems.libsignal.InvalidMessageException;
+
+/**
+ * The first message the server sends on a provisioning connection, identifying the address the
+ * primary device should send the provisioning message to.
+ */
+public class ProvisioningUuid {
+  private final long handle;
+
+  public ProvisioningUuid(String uuid) {
+    this.handle = Native.ProvisioningUuid_New(uuid);
+  }
+
+  public ProvisioningUuid(byte[] serialized) throws InvalidMessageException {
+    this.handle = Native.ProvisioningUuid_Deserialize(serialized);
+  }
+
+  @Override
+  protected void finalize() {
+    Native.ProvisioningUuid_Destroy(this.handle);
+  }
+
+  public String getUuid() {
+    return Native.ProvisioningUuid_GetUuid(this.handle);
+  }
+
+  public byte[] serialize() {
+    return Native.ProvisioningUuid_GetSerialized(this.handle);
+  }
+}
@@ -0,0 +1,31 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal.state;
+
+import org.whispersystems.libsignal.IdentityKey;
+import org.whispersystems.libsignal.ecc.PrivateKeyOps;
+
+/**
+ * An {@link IdentityKeyStore} whose identity private key need not leave the store.
+ * <p>
+ * Session setup uses these methods instead of {@link #getIdentityKeyPair()}, so the private key
+ * can be kept in a hardware-backed keystore. Other operations still use the key pair.
+ */
+public interface IdentityKeyOpsStore extends IdentityKeyStore {
+  /**
+   * Get the local client's public identity key.
+   *
+   * @return The public half of the local client's identity key pair.
+   */
+  public IdentityKey getIdentityKey();
+
+  /**
+   * Get operations backed by the local client's identity private key.
+   *
+   * @return Operations using the private half of the local client's identity key pair.
+   */
+  public PrivateKeyOps getIdentityPrivateKeyOps();
+}
@@ -0,0 +1,33 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal.state;
+
+import org.signal.client.internal.Native;
+
+import org.whispersystems.libsignal.InvalidMessageException;
+import org.whispersystems.libsignal.groups.state.SenderKeyStore;
+
+/**
+ * Replays store writes recorded by a deferred decryption, such as
+ * {@link org.whispersystems.libsignal.SessionCipher#decryptDeferred(org.whispersystems.libsignal.protocol.SignalMessage)}.
+ */
+public final class MutationLog {
+  private MutationLog() {}
+
+  /**
+   * Makes the recorded writes, in order.
+   *
+   * @throws IllegalStateException without writing anything if a session or
+   *         sender key the log replaces has changed since it was recorded; the
+   *         message should then be decrypted again
+   * @throws InvalidMessageException if {@code mutationLog} is malformed
+   */
+  public static void apply(byte[] mutationLog, SignalProtocolStore store, SenderKeyStore senderKeyStore)
+      throws InvalidMessageException
+  {
+    Native.MutationLog_Apply(mutationLog, store, store, store, store, senderKeyStore, null);
+  }
+}
@@ -43,6 +43,10 @@ public class PreKeyBundle {
   /**
    * @return the device ID this PreKey belongs to.
    */
+  private PreKeyBundle(long handle) {
+    this.handle = handle;
+  }
+
   public int getDeviceId() {
     return Native.PreKeyBundle_GetDeviceId(this.handle);
   }
@@ -100,6 +104,29 @@ public class PreKeyBundle {
     return Native.PreKeyBundle_GetRegistrationId(this.handle);
   }
 
+  /**
+   * @return the oldest session version the bundle's owner advertises support for.
+   */
+  public int getMinSessionVersion() {
+    return Native.PreKeyBundle_GetMinSessionVersion(this.handle);
+  }
+
+  /**
+   * @return the newest session version the bundle's owner advertises support for.
+   */
+  public int getMaxSessionVersion() {
+    return Native.PreKeyBundle_GetMaxSessionVersion(this.handle);
+  }
+
+  /**
+   * Returns a copy of this bundle advertising support for session versions {@code min} through
+   * {@code max}. A session established from the bundle uses the newest version supported by both
+   * sides.
+   */
+  public PreKeyBundle withSessionVersions(int min, int max) {
+    return new PreKeyBundle(Native.PreKeyBundle_WithSessionVersions(this.handle, min, max));
+  }
+
   public long nativeHandle() {
     return this.handle;
   }
@@ -43,6 +43,10 @@ public class PreKeyRecord {
     return Native.PreKeyRecord_GetSerialized(this.handle);
   }
 
+  public int getSerializedVersion() {
+    return Native.PreKeyRecord_GetSerializedVersion(this.handle);
+  }
+
   public long nativeHandle() {
     return this.handle;
   }
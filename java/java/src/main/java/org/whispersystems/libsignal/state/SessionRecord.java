@@ -43,6 +43,21 @@ public class SessionRecord {
     this.handle = Native.SessionRecord_Deserialize(serialized);
   }
 
+  /** The most archived states a record may have when deserialized. */
+  public static int getMaxArchivedStates() {
+    return Native.SessionRecord_MaxArchivedStates();
+  }
+
+  /** The most receiver chains each state in a record may have. */
+  public static int getMaxReceiverChains() {
+    return Native.SessionRecord_MaxReceiverChains();
+  }
+
+  /** The most skipped-message keys each chain in a record may have. */
+  public static int getMaxMessageKeysPerChain() {
+    return Native.SessionRecord_MaxMessageKeysPerChain();
+  }
+
   /**
    * Move the current SessionState into the list of "previous" session states, and replace
    * the current SessionState with a fresh reset instance.
@@ -95,6 +110,10 @@ public class SessionRecord {
     return Native.SessionRecord_Serialize(this.handle);
   }
 
+  public int getSerializedVersion() {
+    return Native.SessionRecord_GetSerializedVersion(this.handle);
+  }
+
   // Following functions are for internal or testing use and may be removed in the future:
 
   public byte[] getReceiverChainKeyValue(ECPublicKey senderEphemeral) {
@@ -124,7 +143,8 @@ public class SessionRecord {
             baseKey.getPublicKey().nativeHandle(),
             theirIdentityKey.getPublicKey().nativeHandle(),
             theirSignedPreKey.nativeHandle(),
-            theirRatchetKey.nativeHandle()));
+            theirRatchetKey.nativeHandle(),
+            0));
   }
 
   public static SessionRecord initializeBobSession(
@@ -15,4 +15,16 @@ public class DeviceTransferKeyTest extends TestCase {
 
     X509Certificate cert = X509Certificate.getInstance(certBytes);
   }
+
+  public void testCertificateValidityUsesGivenTime() throws Exception {
+    DeviceTransferKey key = new DeviceTransferKey();
+    // 2021-03-15T12:00:00Z
+    long now = 1615809600000L;
+    byte[] certBytes = key.generateCertificate("name", 10, now);
+
+    X509Certificate cert = X509Certificate.getInstance(certBytes);
+    long dayStart = now - now % (24 * 60 * 60 * 1000);
+    assertEquals(dayStart, cert.getNotBefore().getTime());
+    assertEquals(dayStart + 10L * 24 * 60 * 60 * 1000, cert.getNotAfter().getTime());
+  }
 }
@@ -0,0 +1,54 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.calls;
+
+import java.util.Arrays;
+import junit.framework.TestCase;
+import org.whispersystems.libsignal.InvalidKeyException;
+import org.whispersystems.libsignal.util.Hex;
+
+public class CallLinkRootKeyTests extends TestCase {
+  private static final String ROOT_KEY_TEXT = "bbbc-bdbf-bgbh-bkbm-bnbp-bqbr-bsbt-bxbz";
+
+  private static byte[] bytes(int length) {
+    byte[] result = new byte[length];
+    for (int i = 0; i < length; i++) {
+      result[i] = (byte) i;
+    }
+    return result;
+  }
+
+  public void testTextForm() throws Exception {
+    CallLinkRootKey key = new CallLinkRootKey(bytes(16));
+    assertEquals(ROOT_KEY_TEXT, key.toString());
+    assertTrue(Arrays.equals(key.getBytes(), CallLinkRootKey.parse(ROOT_KEY_TEXT).getBytes()));
+
+    CallLinkRootKey generated = CallLinkRootKey.generate();
+    assertTrue(
+        Arrays.equals(
+            generated.getBytes(), CallLinkRootKey.parse(generated.toString()).getBytes()));
+
+    try {
+      CallLinkRootKey.parse("abbc-bdbf-bgbh-bkbm-bnbp-bqbr-bsbt-bxbz");
+      fail("vowels are not in the alphabet");
+    } catch (InvalidKeyException e) {
+      // expected
+    }
+  }
+
+  public void testDerivations() throws Exception {
+    CallLinkRootKey key = new CallLinkRootKey(bytes(16));
+    assertEquals(
+        "cd415ffc58771108cdbf029b8d86ee857be167bf28dda87e3e592e3f10eb1c9b",
+        Hex.toHexString(key.deriveRoomId()));
+    assertEquals(
+        "ccd3b57831cf1733774ae4a6ae24eee4d88184c21b46aac04e5983a7c2990845",
+        Hex.toHexString(key.deriveEraKey("era-1")));
+    assertEquals(
+        "ddb3cc5d658418708592e5814e848d25601c9ead04dd40538cdcf82e951a8980",
+        Hex.toHexString(GroupCallKeys.deriveEraKey(bytes(32), "era-1")));
+  }
+}
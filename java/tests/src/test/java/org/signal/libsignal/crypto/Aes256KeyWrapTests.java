@@ -0,0 +1,42 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.crypto;
+
+import java.util.Arrays;
+import junit.framework.TestCase;
+import org.whispersystems.libsignal.util.Hex;
+
+public class Aes256KeyWrapTests extends TestCase {
+  private static final String KEY = "c37b7e6492584340bed12207808941155068f738";
+  private static final String WRAPPED =
+      "29b7fa191c2165684374eee9f74595e2a42bace75c425b3053efa26ffe1bb32f";
+
+  private static Aes256KeyWrap newKeyWrap() throws Exception {
+    byte[] kek = new byte[32];
+    for (int i = 0; i < kek.length; i++) {
+      kek[i] = (byte) i;
+    }
+    return new Aes256KeyWrap(kek);
+  }
+
+  public void testWrapAndUnwrap() throws Exception {
+    Aes256KeyWrap kw = newKeyWrap();
+    byte[] wrapped = kw.wrap(Hex.fromStringCondensed(KEY));
+    assertEquals(WRAPPED, Hex.toHexString(wrapped));
+    assertTrue(Arrays.equals(Hex.fromStringCondensed(KEY), kw.unwrap(wrapped)));
+  }
+
+  public void testUnwrapRejectsTampering() throws Exception {
+    byte[] tampered = Hex.fromStringCondensed(WRAPPED);
+    tampered[tampered.length - 1] ^= 1;
+    try {
+      newKeyWrap().unwrap(tampered);
+      fail("tampered key should not unwrap");
+    } catch (InvalidWrappedKeyException e) {
+      // expected
+    }
+  }
+}
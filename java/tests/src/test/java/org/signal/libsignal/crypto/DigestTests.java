@@ -0,0 +1,42 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.crypto;
+import junit.framework.TestCase;
+import org.whispersystems.libsignal.util.Hex;
+
+public class DigestTests extends TestCase {
+  public void testCrc32() {
+    byte[] input = "123456789".getBytes();
+    assertEquals(0xcbf43926L, Crc32.digest(input));
+
+    Crc32 crc = new Crc32();
+    crc.update("1234".getBytes());
+    crc.update("56789".getBytes());
+    assertEquals(0xcbf43926L, crc.finish());
+    assertEquals(0L, crc.finish());
+  }
+
+  public void testSha256() {
+    String expected = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    assertEquals(expected, Hex.toHexString(Sha256.digest("abc".getBytes())));
+
+    Sha256 hash = new Sha256();
+    hash.update("a".getBytes());
+    hash.update("bc".getBytes());
+    assertEquals(expected, Hex.toHexString(hash.finish()));
+  }
+
+  public void testSha512() {
+    String expected = "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a" +
+                      "2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f";
+    assertEquals(expected, Hex.toHexString(Sha512.digest("abc".getBytes())));
+
+    Sha512 hash = new Sha512();
+    hash.update("ab".getBytes());
+    hash.update("c".getBytes());
+    assertEquals(expected, Hex.toHexString(hash.finish()));
+  }
+}
@@ -0,0 +1,62 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.crypto;
+
+import java.io.IOException;
+import java.util.Arrays;
+import junit.framework.TestCase;
+import org.whispersystems.libsignal.util.Hex;
+
+public class IncrementalMacValidatorTests extends TestCase {
+  private static final int CHUNK_SIZE = 4;
+  private static final String DIGEST =
+      "52fa805b50abf8dfcab98df509bb333be21ae17d2014a42eb8d477c171e65473" +
+      "97c6323b0c0ee26c6a637ad3f16be38dd3cff2dbcef3fdf0a61d01110cdc3b17" +
+      "0e8ff8cf15d36c44df1fb5ada0acaaf9f7e149f805b7533ad50344fd82c1f9b9";
+
+  private static byte[] sequence(int length) {
+    byte[] result = new byte[length];
+    for (int i = 0; i < length; i++) {
+      result[i] = (byte) i;
+    }
+    return result;
+  }
+
+  private static IncrementalMacValidator newValidator() throws IOException {
+    return new IncrementalMacValidator(sequence(32), CHUNK_SIZE, Hex.fromStringCondensed(DIGEST));
+  }
+
+  public void testValidData() throws Exception {
+    byte[] data = sequence(10);
+    IncrementalMacValidator validator = newValidator();
+    assertTrue(validator.update(Arrays.copyOfRange(data, 0, 5)));
+    assertEquals(4, validator.getValidatedLength());
+    assertTrue(validator.update(Arrays.copyOfRange(data, 5, 10)));
+    assertEquals(8, validator.getValidatedLength());
+    assertTrue(validator.finalizeAndValidate());
+    assertEquals(10, validator.getValidatedLength());
+  }
+
+  public void testCorruptedData() throws Exception {
+    byte[] data = sequence(10);
+    data[6] ^= 1;
+    IncrementalMacValidator validator = newValidator();
+    assertFalse(validator.update(data));
+    assertEquals(4, validator.getValidatedLength());
+    try {
+      validator.update(data);
+      fail("should have thrown");
+    } catch (IllegalStateException e) {
+      // expected
+    }
+  }
+
+  public void testTruncatedData() throws Exception {
+    IncrementalMacValidator validator = newValidator();
+    assertTrue(validator.update(sequence(8)));
+    assertFalse(validator.finalizeAndValidate());
+  }
+}
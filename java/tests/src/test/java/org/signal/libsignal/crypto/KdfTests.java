@@ -0,0 +1,40 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.crypto;
+import junit.framework.TestCase;
+import org.whispersystems.libsignal.util.Hex;
+
+public class KdfTests extends TestCase {
+  public void testArgon2id() {
+    Argon2idParams params = new Argon2idParams(65536, 2, 1);
+    assertEquals(65536, params.getMemoryKib());
+    assertEquals(2, params.getIterations());
+    assertEquals(1, params.getParallelism());
+
+    byte[] key = params.derive("password".getBytes(), "somesalt".getBytes(), 32);
+    assertEquals("09316115d5cf24ed5a15a31a3ba326e5cf32edc24702987c02b6566f61913cf7",
+                 Hex.toHexString(key));
+
+    try {
+      new Argon2idParams(4, 1, 1);
+      fail();
+    } catch (IllegalArgumentException e) {
+      // expected
+    }
+
+    assertEquals(1, Argon2idParams.calibrate(0, 64, 1).getIterations());
+  }
+
+  public void testScrypt() {
+    ScryptParams params = new ScryptParams(10, 8, 16);
+    byte[] key = params.derive("password".getBytes(), "NaCl".getBytes(), 64);
+    assertEquals("fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162" +
+                 "2eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640",
+                 Hex.toHexString(key));
+
+    assertEquals(10, ScryptParams.calibrate(0, 8, 1).getLogN());
+  }
+}
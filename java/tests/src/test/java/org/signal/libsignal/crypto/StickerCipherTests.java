@@ -0,0 +1,56 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.crypto;
+
+import java.io.ByteArrayOutputStream;
+import java.util.Arrays;
+import junit.framework.TestCase;
+import org.whispersystems.libsignal.InvalidMessageException;
+import org.whispersystems.libsignal.util.Hex;
+
+public class StickerCipherTests extends TestCase {
+  private static final String CIPHERTEXT =
+      "6465666768696a6b6c6d6e6f707172731e07a999cd11729bcee7b16cc7aa710d" +
+      "ce938f78a947d0f8de052db72443aedf6050f41d82c3b5f23bcfdd8fd27469cf" +
+      "0178d0de4ac48f383508dccd00bb88c54e6a0cbfeb861694da6dfc1870b648d6";
+  private static final String PLAINTEXT = "sticker pack manifest for testing";
+
+  private static StickerCipher newCipher() throws Exception {
+    byte[] packKey = new byte[32];
+    for (int i = 0; i < packKey.length; i++) {
+      packKey[i] = (byte) i;
+    }
+    return new StickerCipher(packKey);
+  }
+
+  public void testDecrypt() throws Exception {
+    byte[] plaintext = newCipher().decrypt(Hex.fromStringCondensed(CIPHERTEXT));
+    assertEquals(PLAINTEXT, new String(plaintext, "UTF-8"));
+  }
+
+  public void testStreamingDecrypt() throws Exception {
+    byte[] ciphertext = Hex.fromStringCondensed(CIPHERTEXT);
+    StickerDecryption decryption = newCipher().newDecryption();
+    ByteArrayOutputStream plaintext = new ByteArrayOutputStream();
+    for (int offset = 0; offset < ciphertext.length; offset += 7) {
+      int end = Math.min(offset + 7, ciphertext.length);
+      plaintext.write(decryption.update(Arrays.copyOfRange(ciphertext, offset, end)));
+    }
+    plaintext.write(decryption.finalizeAndVerify());
+    assertEquals(PLAINTEXT, new String(plaintext.toByteArray(), "UTF-8"));
+  }
+
+  public void testTamperedData() throws Exception {
+    byte[] ciphertext = Hex.fromStringCondensed(CIPHERTEXT);
+    ciphertext[20] ^= 1;
+    try {
+      newCipher().decrypt(ciphertext);
+      fail("should have thrown");
+    } catch (InvalidMessageException e) {
+      // expected
+    }
+  }
+}
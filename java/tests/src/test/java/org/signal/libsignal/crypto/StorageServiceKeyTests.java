@@ -0,0 +1,67 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.crypto;
+
+import java.util.Arrays;
+import junit.framework.TestCase;
+import org.whispersystems.libsignal.InvalidMessageException;
+import org.whispersystems.libsignal.util.Hex;
+
+public class StorageServiceKeyTests extends TestCase {
+  private static final byte[] PLAINTEXT = "storage record for testing".getBytes();
+  private static final String RECORD_CIPHERTEXT =
+      "6465666768696a6b6c6d6e6fe05a971f4a5a0b590212cc8bd2172baaf28790fa"
+          + "4f7ea8cd1eafe5898c431608052773941c26c5bb025a";
+
+  private static byte[] bytes(int length) {
+    byte[] result = new byte[length];
+    for (int i = 0; i < length; i++) {
+      result[i] = (byte) i;
+    }
+    return result;
+  }
+
+  public void testKeyDerivation() throws Exception {
+    StorageServiceKey key = StorageServiceKey.fromMasterKey(bytes(32));
+    assertEquals(
+        "d9fd23ee99c148ad879f62609297139e6a36f415b1ad779d23c687b1fa6d36d9",
+        Hex.toHexString(key.getStorageKey()));
+    assertEquals(
+        "6030bc36a437e3bbdf288720487ca3293fe7eb1914819ef235f2532ff85fee6f",
+        Hex.toHexString(key.getManifestKey(42)));
+    assertEquals(
+        "93e3f1cee062cb39adbef00589a172248106ff336017c853154062453939294e",
+        Hex.toHexString(key.getRecordKey(bytes(16))));
+  }
+
+  public void testDecryptKnownRecord() throws Exception {
+    byte[] storageKey = StorageServiceKey.fromMasterKey(bytes(32)).getStorageKey();
+    StorageServiceKey key = new StorageServiceKey(storageKey);
+    byte[] plaintext = key.decryptRecord(bytes(16), Hex.fromStringCondensed(RECORD_CIPHERTEXT));
+    assertTrue(Arrays.equals(PLAINTEXT, plaintext));
+  }
+
+  public void testRoundTrip() throws Exception {
+    StorageServiceKey key = StorageServiceKey.fromMasterKey(bytes(32));
+    byte[] manifest = key.encryptManifest(7, PLAINTEXT);
+    assertTrue(Arrays.equals(PLAINTEXT, key.decryptManifest(7, manifest)));
+    try {
+      key.decryptManifest(8, manifest);
+      fail("manifest should not decrypt under a different version");
+    } catch (InvalidMessageException e) {
+      // expected
+    }
+
+    byte[] record = key.encryptRecord(bytes(16), PLAINTEXT);
+    assertTrue(Arrays.equals(PLAINTEXT, key.decryptRecord(bytes(16), record)));
+    try {
+      key.decryptRecord(bytes(3), record);
+      fail("record should not decrypt under a different ID");
+    } catch (InvalidMessageException e) {
+      // expected
+    }
+  }
+}
@@ -0,0 +1,31 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.keytrans;
+
+import junit.framework.TestCase;
+import org.whispersystems.libsignal.ecc.Curve;
+import org.whispersystems.libsignal.ecc.ECPublicKey;
+
+public class KeyTransparencyTests extends TestCase {
+  public void testBadResponsesAreRejected() throws Exception {
+    ECPublicKey logKey = Curve.generateKeyPair().getPublicKey();
+    byte[][] responses = { new byte[0], new byte[] { (byte) 0xff, (byte) 0xff } };
+    for (byte[] response : responses) {
+      try {
+        KeyTransparency.verifySearch(logKey, "alice".getBytes("UTF-8"), response, null);
+        fail("should have thrown");
+      } catch (VerificationFailedException e) {
+        // expected
+      }
+      try {
+        KeyTransparency.verifyDistinguished(logKey, response, null);
+        fail("should have thrown");
+      } catch (VerificationFailedException e) {
+        // expected
+      }
+    }
+  }
+}
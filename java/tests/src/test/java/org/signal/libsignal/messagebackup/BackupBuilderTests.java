@@ -0,0 +1,42 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.messagebackup;
+
+import java.io.ByteArrayOutputStream;
+import junit.framework.TestCase;
+
+public class BackupBuilderTests extends TestCase {
+  private static final byte[] BACKUP_KEY = new byte[32];
+
+  public void testBuildAndRead() throws Exception {
+    BackupBuilder builder = new BackupBuilder(BACKUP_KEY, true, System.currentTimeMillis());
+    ByteArrayOutputStream stream = new ByteArrayOutputStream();
+    stream.write(builder.addAccountData(new byte[32], null, "Alice", ""));
+    stream.write(builder.addSelfRecipient(1));
+    stream.write(builder.addContact(2, null, "+14155550100"));
+    stream.write(builder.addChat(10, 2, false, 0, 0));
+    stream.write(builder.addChatItem(10, 1, System.currentTimeMillis(), "hello"));
+
+    try {
+      builder.addChatItem(11, 1, 0, "no such chat");
+      fail("should have thrown");
+    } catch (IllegalArgumentException e) {
+      // expected
+    }
+
+    stream.write(builder.finish());
+
+    FrameReader reader = new FrameReader(BACKUP_KEY);
+    reader.push(stream.toByteArray());
+    int frameCount = 0;
+    while (reader.nextFrame() != null) {
+      frameCount++;
+    }
+    reader.finish();
+    // The backup info frame, followed by one frame per record.
+    assertEquals(6, frameCount);
+  }
+}
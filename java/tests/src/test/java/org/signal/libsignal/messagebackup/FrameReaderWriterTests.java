@@ -0,0 +1,68 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.messagebackup;
+
+import java.io.ByteArrayOutputStream;
+import java.util.ArrayList;
+import java.util.Arrays;
+import java.util.List;
+import junit.framework.TestCase;
+import org.whispersystems.libsignal.InvalidMessageException;
+
+public class FrameReaderWriterTests extends TestCase {
+  private static final byte[] BACKUP_KEY = new byte[32];
+  private static final byte[][] FRAMES = {
+    "account data".getBytes(), new byte[0], new byte[10000], "last chat item".getBytes()
+  };
+
+  private static byte[] writeStream(boolean compressed) throws Exception {
+    FrameWriter writer = new FrameWriter(BACKUP_KEY, compressed);
+    ByteArrayOutputStream stream = new ByteArrayOutputStream();
+    for (byte[] frame : FRAMES) {
+      stream.write(writer.append(frame));
+    }
+    stream.write(writer.finish());
+    return stream.toByteArray();
+  }
+
+  private static List<byte[]> readStream(byte[] stream, int chunkSize) throws Exception {
+    FrameReader reader = new FrameReader(BACKUP_KEY);
+    List<byte[]> frames = new ArrayList<>();
+    for (int offset = 0; offset < stream.length; offset += chunkSize) {
+      reader.push(Arrays.copyOfRange(stream, offset, Math.min(offset + chunkSize, stream.length)));
+      byte[] frame;
+      while ((frame = reader.nextFrame()) != null) {
+        frames.add(frame);
+      }
+    }
+    reader.finish();
+    assertTrue(reader.isFinished());
+    return frames;
+  }
+
+  public void testRoundTrip() throws Exception {
+    for (boolean compressed : new boolean[] {false, true}) {
+      byte[] stream = writeStream(compressed);
+      for (int chunkSize : new int[] {1, 100, stream.length}) {
+        List<byte[]> frames = readStream(stream, chunkSize);
+        assertEquals(FRAMES.length, frames.size());
+        for (int i = 0; i < FRAMES.length; i++) {
+          assertTrue(Arrays.equals(FRAMES[i], frames.get(i)));
+        }
+      }
+    }
+  }
+
+  public void testTruncation() throws Exception {
+    byte[] stream = writeStream(false);
+    try {
+      readStream(Arrays.copyOf(stream, stream.length - 1), 100);
+      fail("should have thrown");
+    } catch (InvalidMessageException e) {
+      // expected
+    }
+  }
+}
@@ -3,9 +3,12 @@ package org.signal.libsignal.metadata;
 import junit.framework.TestCase;
 
 import org.signal.libsignal.metadata.SealedSessionCipher.DecryptionResult;
+import org.signal.libsignal.metadata.SealedSessionCipher.NotificationDecryptionResult;
 import org.signal.libsignal.metadata.certificate.CertificateValidator;
 import org.signal.libsignal.metadata.certificate.InvalidCertificateException;
 import org.signal.libsignal.metadata.certificate.SenderCertificate;
+import org.signal.libsignal.metadata.certificate.SenderCertificateProvider;
+import org.signal.libsignal.metadata.certificate.SenderCertificateSource;
 import org.signal.libsignal.metadata.certificate.ServerCertificate;
 import org.whispersystems.libsignal.IdentityKeyPair;
 import org.whispersystems.libsignal.InvalidKeyException;
@@ -131,16 +134,86 @@ public class SealedSessionCipherTest extends TestCase {
     }
   }
 
+  public void testNotificationDecrypt() throws Exception {
+    TestInMemorySignalProtocolStore aliceStore = new TestInMemorySignalProtocolStore();
+    TestInMemorySignalProtocolStore bobStore   = new TestInMemorySignalProtocolStore();
+
+    initializeSessions(aliceStore, bobStore);
+
+    ECKeyPair           trustRoot         = Curve.generateKeyPair();
+    SenderCertificate   senderCertificate = createCertificateFor(trustRoot, UUID.fromString("9d0652a3-dcc3-4d11-975f-74d61598733f"), "+14151111111", 1, aliceStore.getIdentityKeyPair().getPublicKey().getPublicKey(), 31337);
+    SealedSessionCipher aliceCipher       = new SealedSessionCipher(aliceStore, UUID.fromString("9d0652a3-dcc3-4d11-975f-74d61598733f"), "+14151111111", 1);
+
+    byte[] ciphertext = aliceCipher.encrypt(new SignalProtocolAddress("+14152222222", 1),
+                                            senderCertificate, "smert za smert".getBytes());
+
+    SealedSessionCipher   bobCipher     = new SealedSessionCipher(bobStore, UUID.fromString("e80f7bbe-5b94-471e-bd8c-2173654ea3d1"), "+14152222222", 1);
+    SignalProtocolAddress aliceAddress  = new SignalProtocolAddress("9d0652a3-dcc3-4d11-975f-74d61598733f", 1);
+
+    NotificationDecryptionResult plaintext = bobCipher.notificationDecrypt(new CertificateValidator(trustRoot.getPublicKey()), ciphertext, 31335);
+
+    assertEquals(new String(plaintext.getPaddedMessage()), "smert za smert");
+    assertEquals(plaintext.getSenderUuid(), "9d0652a3-dcc3-4d11-975f-74d61598733f");
+    assertEquals(plaintext.getSenderE164().get(), "+14151111111");
+    assertEquals(plaintext.getDeviceId(), 1);
+    assertFalse(bobStore.containsSession(aliceAddress));
+    assertTrue(bobStore.containsPreKey(1));
+
+    bobCipher.applyNotificationCommitSet(plaintext.getCommitSet());
 
+    assertTrue(bobStore.containsSession(aliceAddress));
+    assertFalse(bobStore.containsPreKey(1));
+  }
+
+  public void testEncryptWithCertificateProvider() throws Exception {
+    TestInMemorySignalProtocolStore aliceStore = new TestInMemorySignalProtocolStore();
+    TestInMemorySignalProtocolStore bobStore   = new TestInMemorySignalProtocolStore();
+
+    initializeSessions(aliceStore, bobStore);
+
+    final ECKeyPair   trustRoot   = Curve.generateKeyPair();
+    final UUID        aliceUuid   = UUID.fromString("9d0652a3-dcc3-4d11-975f-74d61598733f");
+    final ECPublicKey aliceKey    = aliceStore.getIdentityKeyPair().getPublicKey().getPublicKey();
+    final int[]       fetches     = new int[1];
+
+    SenderCertificateSource source = new SenderCertificateSource() {
+      public SenderCertificate fetchSenderCertificate() {
+        fetches[0]++;
+        try {
+          return createCertificateFor(trustRoot, aliceUuid, "+14151111111", 1, aliceKey, 20000);
+        } catch (Exception e) {
+          throw new AssertionError(e);
+        }
+      }
+    };
+
+    SenderCertificateProvider provider    = new SenderCertificateProvider(source, 1000, createCertificateFor(trustRoot, aliceUuid, "+14151111111", 1, aliceKey, 10000));
+    SealedSessionCipher       aliceCipher = new SealedSessionCipher(aliceStore, aliceUuid, "+14151111111", 1);
+
+    assertEquals(provider.get(5000).getExpiration(), 10000);
+    assertEquals(fetches[0], 0);
+
+    byte[] ciphertext = aliceCipher.encrypt(new SignalProtocolAddress("+14152222222", 1),
+                                            provider, 9500, "smert za smert".getBytes());
+    assertEquals(fetches[0], 1);
+
+    SealedSessionCipher bobCipher = new SealedSessionCipher(bobStore, UUID.fromString("e80f7bbe-5b94-471e-bd8c-2173654ea3d1"), "+14152222222", 1);
+
+    DecryptionResult plaintext = bobCipher.decrypt(new CertificateValidator(trustRoot.getPublicKey()), ciphertext, 15000);
+
+    assertEquals(new String(plaintext.getPaddedMessage()), "smert za smert");
+    assertEquals(provider.get(15000).getExpiration(), 20000);
+    assertEquals(fetches[0], 1);
+  }
 
   private SenderCertificate createCertificateFor(ECKeyPair trustRoot, UUID uuid, String e164, int deviceId, ECPublicKey identityKey, long expires)
       throws InvalidKeyException, InvalidCertificateException {
     ECKeyPair serverKey = Curve.generateKeyPair();
 
-    ServerCertificate serverCertificate = new ServerCertificate(Native.ServerCertificate_New(1, serverKey.getPublicKey().nativeHandle(), trustRoot.getPrivateKey().nativeHandle()));
+    ServerCertificate serverCertificate = new ServerCertificate(Native.ServerCertificate_New(1, serverKey.getPublicKey().nativeHandle(), trustRoot.getPrivateKey().nativeHandle(), 0));
 
     return new SenderCertificate(Native.SenderCertificate_New(uuid.toString(), e164, deviceId, identityKey.nativeHandle(), expires,
-                                                              serverCertificate.nativeHandle(), serverKey.getPrivateKey().nativeHandle()));
+                                                              serverCertificate.nativeHandle(), serverKey.getPrivateKey().nativeHandle(), 0));
   }
 
   private void initializeSessions(TestInMemorySignalProtocolStore aliceStore, TestInMemorySignalProtocolStore bobStore)
@@ -0,0 +1,36 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.metadata;
+
+import junit.framework.TestCase;
+import org.whispersystems.libsignal.util.Hex;
+
+public class UnidentifiedAccessTest extends TestCase {
+  private static byte[] profileKey() {
+    byte[] profileKey = new byte[32];
+    for (int i = 0; i < profileKey.length; i++) {
+      profileKey[i] = (byte) i;
+    }
+    return profileKey;
+  }
+
+  public void testDeriveAccessKey() {
+    assertEquals("0ebcb5deb52c83bd08a8a935182c9199",
+                 Hex.toHexString(UnidentifiedAccess.deriveAccessKey(profileKey())));
+    try {
+      UnidentifiedAccess.deriveAccessKey(new byte[16]);
+      fail("short profile key should be rejected");
+    } catch (IllegalArgumentException e) {
+      // expected
+    }
+  }
+
+  public void testBlindServiceId() {
+    byte[] blinded = UnidentifiedAccess.blindServiceId(profileKey(), "9d0652a3-dcc3-4d11-975f-74d61598733f");
+    assertEquals("81be8025bf77f56435df7ab967647c5e0ce8c92232483d053ac1e0fc3fb6e81b",
+                 Hex.toHexString(blinded));
+  }
+}
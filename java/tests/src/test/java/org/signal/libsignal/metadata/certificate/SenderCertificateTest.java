@@ -30,11 +30,40 @@ public class SenderCertificateTest extends TestCase {
     try {
       new CertificateValidator(trustRoot.getPublicKey()).validate(senderCertificate, 31338);
       throw new AssertionError();
-    } catch (InvalidCertificateException e) {
+    } catch (ExpiredCertificateException e) {
       // good
     }
   }
 
+  public void testExpiredWithinGracePeriod() throws InvalidCertificateException, InvalidKeyException {
+    ECKeyPair key       = Curve.generateKeyPair();
+
+    SenderCertificate   senderCertificate = createCertificateFor(trustRoot, UUID.fromString("9d0652a3-dcc3-4d11-975f-74d61598733f"), "+14151111111", 31338, key.getPublicKey(), 31337);
+    new CertificateValidator(trustRoot.getPublicKey(), 1).validate(senderCertificate, 31338);
+
+    try {
+      new CertificateValidator(trustRoot.getPublicKey(), 1).validate(senderCertificate, 31339);
+      throw new AssertionError();
+    } catch (ExpiredCertificateException e) {
+      // good
+    }
+  }
+
+  public void testImplausibleValidationTime() throws InvalidCertificateException, InvalidKeyException {
+    ECKeyPair key = Curve.generateKeyPair();
+    SenderCertificate senderCertificate = createCertificateFor(trustRoot, UUID.fromString("9d0652a3-dcc3-4d11-975f-74d61598733f"), "+14151111111", 31337, key.getPublicKey(), 31337);
+
+    // A time in milliseconds mistakenly scaled by 1000 again.
+    try {
+      new CertificateValidator(trustRoot.getPublicKey()).validate(senderCertificate, System.currentTimeMillis() * 1000);
+      throw new AssertionError();
+    } catch (ExpiredCertificateException e) {
+      throw new AssertionError(e);
+    } catch (InvalidCertificateException e) {
+      assertTrue(e.getCause() instanceof IllegalArgumentException);
+    }
+  }
+
   public void testBadSignature() throws InvalidCertificateException, InvalidKeyException {
     ECKeyPair key       = Curve.generateKeyPair();
 
@@ -58,9 +87,9 @@ public class SenderCertificateTest extends TestCase {
       throws InvalidKeyException, InvalidCertificateException {
     ECKeyPair serverKey = Curve.generateKeyPair();
 
-    ServerCertificate serverCertificate = new ServerCertificate(Native.ServerCertificate_New(1, serverKey.getPublicKey().nativeHandle(), trustRoot.getPrivateKey().nativeHandle()));
+    ServerCertificate serverCertificate = new ServerCertificate(Native.ServerCertificate_New(1, serverKey.getPublicKey().nativeHandle(), trustRoot.getPrivateKey().nativeHandle(), 0));
 
     return new SenderCertificate(Native.SenderCertificate_New(uuid.toString(), e164, deviceId, identityKey.nativeHandle(), expires,
-                                                              serverCertificate.nativeHandle(), serverKey.getPrivateKey().nativeHandle()));
+                                                              serverCertificate.nativeHandle(), serverKey.getPrivateKey().nativeHandle(), 0));
   }
 }
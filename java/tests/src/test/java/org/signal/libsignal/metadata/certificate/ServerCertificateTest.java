@@ -15,7 +15,7 @@ public class ServerCertificateTest extends TestCase {
     ECKeyPair keyPair   = Curve.generateKeyPair();
 
     ServerCertificate certificate = new ServerCertificate(
-       Native.ServerCertificate_New(1, keyPair.getPublicKey().nativeHandle(), trustRoot.getPrivateKey().nativeHandle()));
+       Native.ServerCertificate_New(1, keyPair.getPublicKey().nativeHandle(), trustRoot.getPrivateKey().nativeHandle(), 0));
 
     new CertificateValidator(trustRoot.getPublicKey()).validate(certificate);
 
@@ -28,7 +28,7 @@ public class ServerCertificateTest extends TestCase {
     ECKeyPair keyPair   = Curve.generateKeyPair();
 
     ServerCertificate certificate = new ServerCertificate(
-       Native.ServerCertificate_New(1, keyPair.getPublicKey().nativeHandle(), trustRoot.getPrivateKey().nativeHandle()));
+       Native.ServerCertificate_New(1, keyPair.getPublicKey().nativeHandle(), trustRoot.getPrivateKey().nativeHandle(), 0));
 
     byte[] badSignature = certificate.getSerialized();
 
@@ -0,0 +1,33 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.metadata.protocol;
+
+import junit.framework.TestCase;
+import org.whispersystems.libsignal.InvalidMessageException;
+import org.whispersystems.libsignal.SignalProtocolAddress;
+
+import java.util.Arrays;
+
+public class OutgoingEnvelopeTest extends TestCase {
+  public void testRoundTrip() throws Exception {
+    SignalProtocolAddress destination = new SignalProtocolAddress("+14151111111", 2);
+    byte[] payload = "sealed sender ciphertext".getBytes("UTF-8");
+    OutgoingEnvelope envelope = new OutgoingEnvelope(destination, 1600000000000L, OutgoingEnvelope.CONTENT_HINT_RESENDABLE, payload);
+
+    OutgoingEnvelope parsed = new OutgoingEnvelope(envelope.serialize());
+    assertEquals(destination, parsed.getDestination());
+    assertEquals(1600000000000L, parsed.getTimestamp());
+    assertEquals(OutgoingEnvelope.CONTENT_HINT_RESENDABLE, parsed.getContentHint());
+    assertTrue(Arrays.equals(payload, parsed.getPayload()));
+
+    try {
+      new OutgoingEnvelope(new byte[] { 0x0a, 0x05 });
+      fail("parsed a truncated envelope");
+    } catch (InvalidMessageException e) {
+      // expected
+    }
+  }
+}
@@ -0,0 +1,69 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.registration;
+
+import junit.framework.TestCase;
+
+public class RegistrationTests extends TestCase {
+  public void testRequests() throws Exception {
+    RegistrationRequest request = RegistrationRequest.createSession("+14155550100", null, RegistrationRequest.PushTokenType.FCM, null, null);
+    assertEquals("POST", request.getMethod());
+    assertEquals("/v1/verification/session", request.getPath());
+    assertEquals("{\"number\":\"+14155550100\"}", new String(request.getBody(), "UTF-8"));
+
+    request = RegistrationRequest.getSession("abc");
+    assertEquals("GET", request.getMethod());
+    assertEquals("/v1/verification/session/abc", request.getPath());
+    assertNull(request.getBody());
+
+    try {
+      RegistrationRequest.getSession("../abc");
+      fail("should have thrown");
+    } catch (IllegalArgumentException e) {
+      // expected
+    }
+  }
+
+  public void testSession() throws Exception {
+    String body = "{\"id\":\"abc\",\"nextSms\":30,\"nextCall\":null,\"nextVerificationAttempt\":null,"
+        + "\"allowedToRequestCode\":true,\"requestedInformation\":[\"captcha\"],\"verified\":false}";
+    RegistrationSession session = new RegistrationSession(200, body.getBytes("UTF-8"), null);
+    assertEquals("abc", session.getId());
+    assertEquals(30, session.getNextSmsSeconds());
+    assertEquals(-1, session.getNextCallSeconds());
+    assertTrue(session.isAllowedToRequestCode());
+    assertTrue(session.requiresCaptcha());
+    assertFalse(session.requiresPushChallenge());
+    assertFalse(session.isVerified());
+
+    try {
+      new RegistrationSession(429, new byte[0], "60");
+      fail("should have thrown");
+    } catch (RateLimitedException e) {
+      assertEquals(60, e.getRetryAfterSeconds());
+    }
+
+    try {
+      new RegistrationSession(404, new byte[0], null);
+      fail("should have thrown");
+    } catch (SessionNotFoundException e) {
+      // expected
+    }
+  }
+
+  public void testLockedOutcome() throws Exception {
+    String body = "{\"timeRemaining\":1000,\"svr2Credentials\":{\"username\":\"user\",\"password\":\"pass\"}}";
+    RegistrationOutcome outcome = new RegistrationOutcome(423, body.getBytes("UTF-8"), null);
+    assertTrue(outcome.isLocked());
+    assertNull(outcome.getAci());
+    assertEquals(1000, outcome.getLockTimeRemainingMs());
+    assertEquals("user", outcome.getSvrUsername());
+    assertEquals("pass", outcome.getSvrPassword());
+
+    String token = RegistrationRequest.registrationLockToken(new byte[32]);
+    assertEquals(64, token.length());
+  }
+}
@@ -0,0 +1,26 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal;
+
+import junit.framework.TestCase;
+import org.whispersystems.libsignal.ecc.Curve;
+import org.whispersystems.libsignal.ecc.ECKeyPair;
+
+public class IdentityKeyPairTest extends TestCase {
+  public void testPaymentAddressSignature() throws Exception {
+    ECKeyPair keyPair = Curve.generateKeyPair();
+    IdentityKeyPair identityKeyPair = new IdentityKeyPair(new IdentityKey(keyPair.getPublicKey()), keyPair.getPrivateKey());
+    byte[] address = "mobilecoin public address".getBytes("UTF-8");
+
+    byte[] signature = identityKeyPair.signPaymentAddress(address);
+    IdentityKey identityKey = identityKeyPair.getPublicKey();
+    assertTrue(identityKey.verifyPaymentAddress(address, signature));
+    assertFalse(identityKey.verifyPaymentAddress("another address".getBytes("UTF-8"), signature));
+
+    byte[] plainSignature = Curve.calculateSignature(keyPair.getPrivateKey(), address);
+    assertFalse(identityKey.verifyPaymentAddress(address, plainSignature));
+  }
+}
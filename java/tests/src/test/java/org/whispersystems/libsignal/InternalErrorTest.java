@@ -0,0 +1,22 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal;
+
+import junit.framework.TestCase;
+
+import org.signal.client.internal.Native;
+
+public class InternalErrorTest extends TestCase {
+  public void testPanicIsThrownAsInternalError() {
+    try {
+      Native.Native_TestPanic("contained panic");
+      fail("should have thrown");
+    } catch (InternalError e) {
+      assertEquals("Native_TestPanic", e.getFunctionName());
+      assertTrue(e.getMessage(), e.getMessage().contains("contained panic"));
+    }
+  }
+}
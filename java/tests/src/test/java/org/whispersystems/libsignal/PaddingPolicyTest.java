@@ -0,0 +1,35 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal;
+
+import junit.framework.TestCase;
+
+import java.util.Arrays;
+
+public class PaddingPolicyTest extends TestCase {
+  public void testPaddedLengths() {
+    assertEquals(160, PaddingPolicy.bucketed(160).getPaddedLength(159));
+    assertEquals(320, PaddingPolicy.bucketed(160).getPaddedLength(160));
+    assertEquals(1024, PaddingPolicy.padme().getPaddedLength(1000));
+    assertEquals(17, PaddingPolicy.none().getPaddedLength(17));
+  }
+
+  public void testRoundTrip() throws Exception {
+    byte[] message = "padded message".getBytes("UTF-8");
+    for (PaddingPolicy policy : Arrays.asList(PaddingPolicy.none(), PaddingPolicy.bucketed(160), PaddingPolicy.padme())) {
+      byte[] padded = policy.pad(message);
+      assertEquals(policy.getPaddedLength(message.length), padded.length);
+      assertTrue(Arrays.equals(message, policy.unpad(padded)));
+    }
+
+    try {
+      PaddingPolicy.padme().unpad(new byte[] { 1, 2, 0, 0 });
+      fail("unpadded a message with no terminator");
+    } catch (InvalidMessageException e) {
+      // expected
+    }
+  }
+}
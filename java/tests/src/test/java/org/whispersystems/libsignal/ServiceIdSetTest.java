@@ -0,0 +1,63 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal;
+
+import junit.framework.TestCase;
+
+import java.io.ByteArrayOutputStream;
+import java.util.Arrays;
+
+public class ServiceIdSetTest extends TestCase {
+  private static byte[] serviceId(int kind, int last) {
+    byte[] result = new byte[ServiceIdSet.FIXED_WIDTH_LENGTH];
+    result[0] = (byte) kind;
+    result[result.length - 1] = (byte) last;
+    return result;
+  }
+
+  private static byte[] list(byte[]... ids) {
+    ByteArrayOutputStream result = new ByteArrayOutputStream();
+    for (byte[] id : ids) {
+      result.write(id, 0, id.length);
+    }
+    return result.toByteArray();
+  }
+
+  public void testSetOperations() {
+    byte[] aci1 = serviceId(0, 1);
+    byte[] aci2 = serviceId(0, 2);
+    byte[] pni1 = serviceId(1, 1);
+
+    ServiceIdSet contacts = new ServiceIdSet();
+    contacts.add(list(pni1, aci2, aci1));
+    assertEquals(3, contacts.size());
+    assertTrue(contacts.contains(aci2));
+    assertTrue(Arrays.equals(list(aci1, aci2), contacts.getPage(0, 2)));
+    assertTrue(Arrays.equals(pni1, contacts.getPage(2, 2)));
+
+    ServiceIdSet discovered = new ServiceIdSet();
+    discovered.add(list(aci2, pni1));
+    assertTrue(Arrays.equals(list(aci2, pni1), contacts.intersection(discovered).getPage(0, 10)));
+    assertTrue(Arrays.equals(aci1, contacts.difference(discovered).getPage(0, 10)));
+  }
+
+  public void testRejectsMalformedLists() {
+    ServiceIdSet set = new ServiceIdSet();
+    try {
+      set.add(list(serviceId(0, 1), serviceId(2, 3)));
+      fail("kind 2 is not a service ID kind");
+    } catch (IllegalArgumentException e) {
+      // expected
+    }
+    try {
+      set.add(new byte[16]);
+      fail("list length is not a multiple of 17");
+    } catch (IllegalArgumentException e) {
+      // expected
+    }
+    assertEquals(0, set.size());
+  }
+}
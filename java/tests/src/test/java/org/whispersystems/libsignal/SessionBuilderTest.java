@@ -381,6 +381,39 @@ public class SessionBuilderTest extends TestCase {
     assertTrue(originalMessage.equals(new String(plaintext)));
   }
 
+  public void testSessionVersionNegotiation() throws Exception {
+    SignalProtocolStore aliceStore          = new TestInMemorySignalProtocolStore();
+    SessionBuilder      aliceSessionBuilder = new SessionBuilder(aliceStore, BOB_ADDRESS);
+
+    SignalProtocolStore bobStore = new TestInMemorySignalProtocolStore();
+
+    ECKeyPair bobSignedPreKeyPair      = Curve.generateKeyPair();
+    byte[]    bobSignedPreKeySignature = Curve.calculateSignature(bobStore.getIdentityKeyPair().getPrivateKey(),
+                                                                  bobSignedPreKeyPair.getPublicKey().serialize());
+
+    PreKeyBundle bobPreKey = new PreKeyBundle(bobStore.getLocalRegistrationId(), 1,
+                                              -1, null,
+                                              22, bobSignedPreKeyPair.getPublicKey(),
+                                              bobSignedPreKeySignature,
+                                              bobStore.getIdentityKeyPair().getPublicKey());
+    assertEquals(3, bobPreKey.getMinSessionVersion());
+    assertEquals(3, bobPreKey.getMaxSessionVersion());
+
+    try {
+      bobPreKey.withSessionVersions(4, 3);
+      fail();
+    } catch (IllegalArgumentException e) {
+      // good
+    }
+
+    PreKeyBundle advertised = bobPreKey.withSessionVersions(3, 4);
+    assertEquals(3, advertised.getMinSessionVersion());
+    assertEquals(4, advertised.getMaxSessionVersion());
+
+    aliceSessionBuilder.process(advertised);
+    assertEquals(3, aliceStore.loadSession(BOB_ADDRESS).getSessionVersion());
+  }
+
 
   private void runInteraction(SignalProtocolStore aliceStore, SignalProtocolStore bobStore)
       throws DuplicateMessageException, LegacyMessageException, InvalidMessageException, NoSessionException, UntrustedIdentityException
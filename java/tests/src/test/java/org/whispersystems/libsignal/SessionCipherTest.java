@@ -3,11 +3,13 @@ package org.whispersystems.libsignal;
 import junit.framework.TestCase;
 
 import org.whispersystems.libsignal.ecc.Curve;
+import org.whispersystems.libsignal.groups.InMemorySenderKeyStore;
 import org.whispersystems.libsignal.ecc.ECKeyPair;
 import org.whispersystems.libsignal.ecc.ECPublicKey;
 import org.whispersystems.libsignal.ecc.ECPrivateKey;
 import org.whispersystems.libsignal.protocol.CiphertextMessage;
 import org.whispersystems.libsignal.protocol.SignalMessage;
+import org.whispersystems.libsignal.state.MutationLog;
 import org.whispersystems.libsignal.state.SignalProtocolStore;
 import org.whispersystems.libsignal.state.SessionRecord;
 import org.whispersystems.libsignal.util.guava.Optional;
@@ -67,7 +69,8 @@ public class SessionCipherTest extends TestCase {
       bobCipher.decrypt(new SignalMessage(inflight.get(0).serialize()));
       throw new AssertionError("Should have failed!");
     } catch (DuplicateMessageException dme) {
-      // good
+      assertEquals(2010, dme.getChainIndex());
+      assertEquals(0, dme.getCounter());
     }
   }
 
@@ -102,6 +105,128 @@ public class SessionCipherTest extends TestCase {
     assertTrue(Arrays.equals(alicePlaintext, bobPlaintext2));
   }
 
+  public void testDecryptDeferred() throws Exception {
+    PairOfSessions sessions = initializeSessionsV3();
+
+    SignalProtocolStore aliceStore = new TestInMemorySignalProtocolStore();
+    SignalProtocolStore bobStore   = new TestInMemorySignalProtocolStore();
+
+    SignalProtocolAddress aliceAddress = new SignalProtocolAddress("+14159999999", 1);
+    SignalProtocolAddress bobAddress   = new SignalProtocolAddress("+14158888888", 1);
+
+    aliceStore.storeSession(bobAddress, sessions.aliceSession);
+    bobStore.storeSession(aliceAddress, sessions.bobSession);
+
+    SessionCipher aliceCipher = new SessionCipher(aliceStore, bobAddress);
+    SessionCipher bobCipher   = new SessionCipher(bobStore, aliceAddress);
+
+    byte[]            alicePlaintext   = "This is a plaintext message.".getBytes();
+    CiphertextMessage message          = aliceCipher.encrypt(alicePlaintext);
+    byte[]            bobSessionBefore = bobStore.loadSession(aliceAddress).serialize();
+
+    DeferredDecryptionResult result = bobCipher.decryptDeferred(new SignalMessage(message.serialize()));
+    assertTrue(Arrays.equals(alicePlaintext, result.getPlaintext()));
+    assertTrue(Arrays.equals(bobSessionBefore, bobStore.loadSession(aliceAddress).serialize()));
+
+    MutationLog.apply(result.getMutationLog(), bobStore, new InMemorySenderKeyStore());
+
+    try {
+      bobCipher.decrypt(new SignalMessage(message.serialize()));
+      fail("should have been marked as received");
+    } catch (DuplicateMessageException e) {
+      // good
+    }
+
+    try {
+      MutationLog.apply(result.getMutationLog(), bobStore, new InMemorySenderKeyStore());
+      fail("should have rejected a stale log");
+    } catch (IllegalStateException e) {
+      // good
+    }
+  }
+
+  public void testEncryptBatch() throws Exception {
+    PairOfSessions sessions = initializeSessionsV3();
+
+    SignalProtocolStore aliceStore = new TestInMemorySignalProtocolStore();
+    SignalProtocolStore bobStore   = new TestInMemorySignalProtocolStore();
+
+    SignalProtocolAddress aliceAddress = new SignalProtocolAddress("+14159999999", 1);
+    SignalProtocolAddress bobAddress   = new SignalProtocolAddress("+14158888888", 1);
+
+    aliceStore.storeSession(bobAddress, sessions.aliceSession);
+    bobStore.storeSession(aliceAddress, sessions.bobSession);
+
+    SessionCipher bobCipher = new SessionCipher(bobStore, aliceAddress);
+
+    byte[]                   alicePlaintext = "This is a plaintext message.".getBytes();
+    List<EncryptBatchResult> results        = SessionCipher.encrypt(aliceStore, aliceStore,
+                                                                    Arrays.asList(bobAddress, bobAddress),
+                                                                    alicePlaintext);
+
+    assertEquals(2, results.size());
+    for (EncryptBatchResult result : results) {
+      assertTrue(result.isSuccess());
+      byte[] bobPlaintext = bobCipher.decrypt(new SignalMessage(result.getMessage().serialize()));
+      assertTrue(Arrays.equals(alicePlaintext, bobPlaintext));
+    }
+  }
+
+  public void testEncryptBatchToServiceIdList() throws Exception {
+    PairOfSessions sessions = initializeSessionsV3();
+
+    SignalProtocolStore aliceStore = new TestInMemorySignalProtocolStore();
+    SignalProtocolStore bobStore   = new TestInMemorySignalProtocolStore();
+
+    SignalProtocolAddress aliceAddress = new SignalProtocolAddress("+14159999999", 1);
+    SignalProtocolAddress bobAddress   = new SignalProtocolAddress("+14158888888", 1);
+
+    aliceStore.storeSession(bobAddress, sessions.aliceSession);
+    bobStore.storeSession(aliceAddress, sessions.bobSession);
+
+    SessionCipher bobCipher = new SessionCipher(bobStore, aliceAddress);
+
+    byte[]                   recipients     = new ServiceIdList().add("+14157777777", 1)
+                                                                 .add("+14158888888", 1)
+                                                                 .serialize();
+    byte[]                   alicePlaintext = "This is a plaintext message.".getBytes();
+    List<EncryptBatchResult> results        = SessionCipher.encrypt(aliceStore, aliceStore, recipients, alicePlaintext);
+
+    assertEquals(2, results.size());
+    assertTrue(results.get(0).getError() instanceof NoSessionException);
+
+    byte[] bobPlaintext = bobCipher.decrypt(new SignalMessage(results.get(1).getMessage().serialize()));
+    assertTrue(Arrays.equals(alicePlaintext, bobPlaintext));
+  }
+
+  public void testEncryptBatchIsolatesFailures() throws Exception {
+    PairOfSessions sessions = initializeSessionsV3();
+
+    SignalProtocolStore aliceStore = new TestInMemorySignalProtocolStore();
+    SignalProtocolStore bobStore   = new TestInMemorySignalProtocolStore();
+
+    SignalProtocolAddress aliceAddress   = new SignalProtocolAddress("+14159999999", 1);
+    SignalProtocolAddress bobAddress     = new SignalProtocolAddress("+14158888888", 1);
+    SignalProtocolAddress unknownAddress = new SignalProtocolAddress("+14157777777", 1);
+
+    aliceStore.storeSession(bobAddress, sessions.aliceSession);
+    bobStore.storeSession(aliceAddress, sessions.bobSession);
+
+    SessionCipher bobCipher = new SessionCipher(bobStore, aliceAddress);
+
+    byte[]                   alicePlaintext = "This is a plaintext message.".getBytes();
+    List<EncryptBatchResult> results        = SessionCipher.encrypt(aliceStore, aliceStore,
+                                                                    Arrays.asList(unknownAddress, bobAddress),
+                                                                    alicePlaintext);
+
+    assertEquals(2, results.size());
+    assertFalse(results.get(0).isSuccess());
+    assertTrue(results.get(0).getError() instanceof NoSessionException);
+
+    byte[] bobPlaintext = bobCipher.decrypt(new SignalMessage(results.get(1).getMessage().serialize()));
+    assertTrue(Arrays.equals(alicePlaintext, bobPlaintext));
+  }
+
   private void runInteraction(SessionRecord aliceSessionRecord, SessionRecord bobSessionRecord)
       throws DuplicateMessageException, LegacyMessageException, InvalidMessageException, NoSuchAlgorithmException, NoSessionException, UntrustedIdentityException {
     SignalProtocolStore aliceStore = new TestInMemorySignalProtocolStore();
@@ -3,6 +3,7 @@ package org.whispersystems.libsignal.ecc;
 import junit.framework.TestCase;
 
 import org.whispersystems.libsignal.InvalidKeyException;
+import org.whispersystems.libsignal.InvalidMessageException;
 
 import java.util.Arrays;
 
@@ -75,6 +76,23 @@ public class Curve25519Test extends TestCase {
     }
   }
 
+  public void testSealedBox() throws InvalidMessageException {
+    ECKeyPair keyPair = Curve.generateKeyPair();
+    byte[]    message = {1, 2, 3};
+
+    byte[] sealed = keyPair.getPublicKey().seal(message);
+    assertEquals(message.length + 65, sealed.length);
+    assertTrue(Arrays.equals(message, keyPair.getPrivateKey().open(sealed)));
+
+    sealed[40] ^= 1;
+    try {
+      keyPair.getPrivateKey().open(sealed);
+      throw new AssertionError("Shouldn't open tampered message");
+    } catch (InvalidMessageException e) {
+      // good
+    }
+  }
+
   public void testSignature() throws InvalidKeyException {
     byte[] aliceIdentityPrivate = {(byte)0xc0, (byte)0x97, (byte)0x24, (byte)0x84, (byte)0x12,
                                    (byte)0xe5, (byte)0x8b, (byte)0xf0, (byte)0x5d, (byte)0xf4,
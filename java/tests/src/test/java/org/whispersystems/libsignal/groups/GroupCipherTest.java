@@ -7,6 +7,7 @@ import org.whispersystems.libsignal.DuplicateMessageException;
 import org.whispersystems.libsignal.InvalidMessageException;
 import org.whispersystems.libsignal.LegacyMessageException;
 import org.whispersystems.libsignal.NoSessionException;
+import org.whispersystems.libsignal.groups.state.SenderKeyRecord;
 import org.whispersystems.libsignal.protocol.SenderKeyDistributionMessage;
 
 import java.security.NoSuchAlgorithmException;
@@ -201,6 +202,33 @@ public class GroupCipherTest extends TestCase {
     }
   }
 
+  public void testDistributionTracking() throws Exception {
+    InMemorySenderKeyStore aliceStore = new InMemorySenderKeyStore();
+    new GroupSessionBuilder(aliceStore).create(GROUP_SENDER);
+
+    SignalProtocolAddress bob   = new SignalProtocolAddress("+14150002222", 1);
+    SignalProtocolAddress carol = new SignalProtocolAddress("+14150003333", 2);
+
+    SenderKeyRecord record = aliceStore.loadSenderKey(GROUP_SENDER);
+    assertTrue(record.needsDistribution(bob));
+    record.markDistributed(bob);
+    record.markDistributed(carol);
+    assertFalse(record.needsDistribution(bob));
+
+    SenderKeyRecord reloaded = new SenderKeyRecord(record.serialize());
+    assertFalse(reloaded.needsDistribution(carol));
+    assertTrue(reloaded.forgetDistribution(carol));
+    assertTrue(reloaded.needsDistribution(carol));
+    assertFalse(reloaded.forgetDistribution(carol));
+
+    try {
+      new SenderKeyRecord().markDistributed(bob);
+      fail("no sender key to distribute");
+    } catch (IllegalStateException e) {
+      // good
+    }
+  }
+
   public void testEncryptNoSession() {
     InMemorySenderKeyStore aliceStore = new InMemorySenderKeyStore();
     GroupCipher aliceGroupCipher = new GroupCipher(aliceStore, new SenderKeyName("coolio groupio", new SignalProtocolAddress("+10002223333", 1)));
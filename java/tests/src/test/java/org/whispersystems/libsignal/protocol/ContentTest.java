@@ -0,0 +1,63 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal.protocol;
+
+import junit.framework.TestCase;
+
+import java.util.Arrays;
+
+public class ContentTest extends TestCase {
+  private static byte[] filled(int length, int value) {
+    byte[] result = new byte[length];
+    Arrays.fill(result, (byte) value);
+    return result;
+  }
+
+  public void testDataMessage() {
+    AttachmentPointer attachment =
+        new AttachmentPointer(2, "cdn-key", "image/jpeg", filled(64, 1), filled(32, 2), 1000, null, null, 640, 480, 0);
+
+    DataMessageBuilder builder = new DataMessageBuilder(1234);
+    try {
+      builder.build();
+      fail("built an empty message");
+    } catch (IllegalArgumentException expected) {
+    }
+
+    Content content = builder.setBody("hello")
+                             .addAttachment(attachment)
+                             .setQuote(1000, "9d0652a3-dcc3-4d11-975f-74d61598733f", null)
+                             .setExpireTimer(60)
+                             .build();
+    assertTrue(content.serialize().length > 0);
+  }
+
+  public void testInvalidParts() {
+    try {
+      new AttachmentPointer(2, "cdn-key", "image/jpeg", new byte[3], filled(32, 2), 1000);
+      fail("accepted a short key");
+    } catch (IllegalArgumentException expected) {
+    }
+
+    try {
+      new DataMessageBuilder(1234).setProfileKey(new byte[3]);
+      fail("accepted a short profile key");
+    } catch (IllegalArgumentException expected) {
+    }
+  }
+
+  public void testReceiptAndTyping() {
+    assertTrue(Content.receipt(Content.ReceiptType.READ, new long[] { 1000, 1001 }).serialize().length > 0);
+    try {
+      Content.receipt(Content.ReceiptType.DELIVERY, new long[0]);
+      fail("accepted a receipt for no messages");
+    } catch (IllegalArgumentException expected) {
+    }
+
+    assertTrue(Content.typing(1234, true, new byte[] { 1, 2, 3 }).serialize().length > 0);
+    assertTrue(Content.typing(1234, false, null).serialize().length > 0);
+  }
+}
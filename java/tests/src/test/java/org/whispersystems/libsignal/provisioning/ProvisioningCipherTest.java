@@ -0,0 +1,34 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.whispersystems.libsignal.provisioning;
+
+import junit.framework.TestCase;
+import org.whispersystems.libsignal.InvalidMessageException;
+
+import java.util.Arrays;
+
+public class ProvisioningCipherTest extends TestCase {
+  public void testRoundTrip() throws Exception {
+    ProvisioningCipher linkedDevice = new ProvisioningCipher();
+    byte[] message = "account details for the new device".getBytes("UTF-8");
+
+    byte[] envelope = ProvisioningCipher.encrypt(linkedDevice.getPublicKey(), message);
+    assertTrue(Arrays.equals(linkedDevice.decrypt(envelope), message));
+
+    try {
+      new ProvisioningCipher().decrypt(envelope);
+      fail("decrypted with the wrong key");
+    } catch (InvalidMessageException e) {
+      // expected
+    }
+  }
+
+  public void testProvisioningUuid() throws Exception {
+    ProvisioningUuid uuid = new ProvisioningUuid("b7f8d8a0-5f1e-4b5e-9a5c-2e3c5d7f9a1b");
+    ProvisioningUuid parsed = new ProvisioningUuid(uuid.serialize());
+    assertEquals(uuid.getUuid(), parsed.getUuid());
+  }
+}
@@ -0,0 +1,173 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+// The classes here are registered with the native module when it is loaded (see index.ts), and
+// instances are created on the Rust side by name. Their structured fields are set by the native
+// code after construction, which is why they are declared but never assigned here.
+
+export class LibSignalErrorBase extends Error {
+  constructor(message: string) {
+    super(message);
+    this.name = new.target.name;
+  }
+}
+
+export class InternalError extends LibSignalErrorBase {
+  readonly functionName!: string;
+}
+
+export class InvalidRegistrationIdError extends LibSignalErrorBase {
+  readonly addressName!: string;
+  readonly deviceId!: number;
+  readonly registrationId!: number;
+}
+
+export class InvalidSenderKeySessionError extends LibSignalErrorBase {
+  readonly groupId!: string;
+  readonly addressName!: string;
+  readonly deviceId!: number;
+}
+
+/** The message was already decrypted; its keys have been used up. */
+export class DuplicatedMessageError extends LibSignalErrorBase {
+  /** The next index the receiving chain expects. */
+  readonly chainIndex!: number;
+  /** The index of the duplicated message. */
+  readonly counter!: number;
+}
+
+/** The sender certificate was valid but has expired, even allowing for any grace period. */
+export class SealedSenderCertificateExpiredError extends LibSignalErrorBase {
+  readonly expiration!: number;
+}
+
+/** The sealed sender message was already received, according to the replay cache. */
+export class SealedSenderReplayError extends LibSignalErrorBase {}
+
+/** A stored session or sender key record is past its structural limits. */
+export class RecordLimitExceededError extends LibSignalErrorBase {
+  readonly count!: number;
+  readonly limit!: number;
+}
+
+/** A Buffer argument was longer than the limit set with `setMaxBufferLength`. */
+export class BufferTooLargeError extends LibSignalErrorBase {
+  readonly length!: number;
+  readonly limit!: number;
+}
+
+export class UnknownAlgorithmError extends LibSignalErrorBase {
+  readonly algorithmType!: string;
+  readonly algorithm!: string;
+}
+
+export class InvalidKeySizeError extends LibSignalErrorBase {
+  readonly expectedLength!: number;
+  readonly actualLength!: number;
+}
+
+export class InvalidNonceSizeError extends LibSignalErrorBase {
+  readonly expectedLength!: number;
+  readonly actualLength!: number;
+}
+
+export class InvalidInputSizeError extends LibSignalErrorBase {}
+
+export class InvalidTagError extends LibSignalErrorBase {}
+
+export class InvalidCryptoStateError extends LibSignalErrorBase {}
+
+export class InvalidKdfParametersError extends LibSignalErrorBase {}
+
+export class InvalidWrappedKeyError extends LibSignalErrorBase {}
+
+export class InvalidKeyEncodingError extends LibSignalErrorBase {}
+
+export class KeyDecodingFailedError extends LibSignalErrorBase {}
+
+export class DeviceTransferInternalError extends LibSignalErrorBase {}
+
+export class BackupTruncatedError extends LibSignalErrorBase {}
+
+export class InvalidBackupError extends LibSignalErrorBase {
+  /** The index of the frame that was rejected, if the problem was with a single frame. */
+  readonly frameIndex?: number;
+}
+
+export class InvalidBackupRecordError extends LibSignalErrorBase {}
+
+/** The key transparency log's response didn't check out; the log may be misbehaving. */
+export class KeyTransparencyVerificationError extends LibSignalErrorBase {}
+
+export class KeyNotMonitoredError extends LibSignalErrorBase {}
+
+export class RegistrationError extends LibSignalErrorBase {}
+
+export class RateLimitedError extends RegistrationError {
+  /** How long to wait before trying again, if the server said. */
+  readonly retryAfterSecs?: number;
+}
+
+/** The registration session has expired or never existed; create a new one. */
+export class RegistrationSessionNotFoundError extends RegistrationError {}
+
+export const errorClasses = {
+  InternalError,
+  InvalidRegistrationIdError,
+  InvalidSenderKeySessionError,
+  DuplicatedMessageError,
+  SealedSenderCertificateExpiredError,
+  SealedSenderReplayError,
+  RecordLimitExceededError,
+  BufferTooLargeError,
+  UnknownAlgorithmError,
+  InvalidKeySizeError,
+  InvalidNonceSizeError,
+  InvalidInputSizeError,
+  InvalidTagError,
+  InvalidCryptoStateError,
+  InvalidKdfParametersError,
+  InvalidWrappedKeyError,
+  InvalidKeyEncodingError,
+  KeyDecodingFailedError,
+  DeviceTransferInternalError,
+  BackupTruncatedError,
+  InvalidBackupError,
+  InvalidBackupRecordError,
+  KeyTransparencyVerificationError,
+  KeyNotMonitoredError,
+  RegistrationError,
+  RateLimitedError,
+  RegistrationSessionNotFoundError,
+};
+
+/**
+ * What to do about a message that failed to decrypt.
+ *
+ * - `Retryable`: keep the message and try again later, e.g. after approving a changed identity.
+ * - `NeedsNewSession`: the session is missing or out of sync; the sender should start a new one.
+ * - `PermanentlyInvalid`: the message can never be decrypted; drop it.
+ * - `StoreError`: one of the application's stores failed.
+ */
+export type DecryptionFailureAction =
+  | 'Retryable'
+  | 'NeedsNewSession'
+  | 'PermanentlyInvalid'
+  | 'StoreError';
+
+/**
+ * Classifies an error thrown while decrypting a message.
+ *
+ * Returns `undefined` for errors that didn't come from the protocol layer.
+ */
+export function classifyDecryptionFailure(
+  error: unknown
+): DecryptionFailureAction | undefined {
+  if (error instanceof Error) {
+    return (error as { decryptionFailureAction?: DecryptionFailureAction })
+      .decryptionFailureAction;
+  }
+  return undefined;
+}
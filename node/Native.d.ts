@@ -11,22 +11,25 @@ export abstract class IdentityKeyStore {
   _saveIdentity(name: ProtocolAddress, key: PublicKey): Promise<boolean>;
   _isTrustedIdentity(name: ProtocolAddress, key: PublicKey, sending: boolean): Promise<boolean>;
   _getIdentity(name: ProtocolAddress): Promise<PublicKey | null>;
+  _getIdentities?(names: ProtocolAddress[]): Promise<(PublicKey | null)[]>;
 }
 
 export abstract class SessionStore {
   _saveSession(addr: ProtocolAddress, record: SessionRecord): Promise<void>;
   _getSession(addr: ProtocolAddress): Promise<SessionRecord | null>;
+  _getSessions?(addrs: ProtocolAddress[]): Promise<(SessionRecord | null)[]>;
+  _sessionCache?: Wrapper<SessionCache> | null;
 }
 
 export abstract class PreKeyStore {
   _savePreKey(preKeyId: number, record: PreKeyRecord): Promise<void>;
-  _getPreKey(preKeyId: number): Promise<PreKeyRecord>;
+  _getPreKey(preKeyId: number): Promise<PreKeyRecord | null>;
   _removePreKey(preKeyId: number): Promise<void>;
 }
 
 export abstract class SignedPreKeyStore {
   _saveSignedPreKey(signedPreKeyId: number, record: SignedPreKeyRecord): Promise<void>;
-  _getSignedPreKey(signedPreKeyId: number): Promise<SignedPreKeyRecord>;
+  _getSignedPreKey(signedPreKeyId: number): Promise<SignedPreKeyRecord | null>;
 }
 
 export abstract class SenderKeyStore {
@@ -34,26 +37,189 @@ export abstract class SenderKeyStore {
   _getSenderKey(name: SenderKeyName): Promise<SenderKeyRecord | null>;
 }
 
+export abstract class ReplayCache {
+  _containsMessage(messageId: Buffer, now: number): Promise<boolean>;
+  _recordMessage(messageId: Buffer, expiration: number): Promise<void>;
+}
+
+export abstract class SenderCertificateSource {
+  _fetchSenderCertificate(): Promise<SenderCertificate>;
+}
+
 interface Wrapper<T> {
   readonly _nativeHandle: T
 }
 
+// A handle copied out of one JavaScript context, to be received in another (such as a worker).
+type Transferred<T> = number & { readonly __transferred: T };
+
+interface EncryptBatchResult {
+  message?: CiphertextMessage;
+  error?: Error;
+}
+
 
+export const enum AuditEventKind { IdentityChanged = 1, SessionArchived, SenderKeyCreated, SenderKeyFastForwarded }
+export const enum BufferChecksumPolicy { Off = 0, Prefix, Full, FullWithPanic, Copy }
 export const enum LogLevel { Error = 1, Warn, Info, Debug, Trace }
 export function Aes256GcmSiv_Decrypt(aesGcmSiv: Wrapper<Aes256GcmSiv>, ctext: Buffer, nonce: Buffer, associatedData: Buffer): Buffer;
 export function Aes256GcmSiv_Encrypt(aesGcmSiv: Wrapper<Aes256GcmSiv>, ptext: Buffer, nonce: Buffer, associatedData: Buffer): Buffer;
 export function Aes256GcmSiv_New(key: Buffer): Aes256GcmSiv;
+export function Aes256KeyWrap_New(key: Buffer): Aes256KeyWrap;
+export function Aes256KeyWrap_TransferToWorker(obj: Wrapper<Aes256KeyWrap>): Transferred<Aes256KeyWrap>;
+export function Aes256KeyWrap_Unwrap(kw: Wrapper<Aes256KeyWrap>, wrapped: Buffer): Buffer;
+export function Aes256KeyWrap_Wrap(kw: Wrapper<Aes256KeyWrap>, key: Buffer): Buffer;
+export function Argon2idParams_Calibrate(targetDurationMs: number, memoryKib: number, parallelism: number): Argon2idParams;
+export function Argon2idParams_Derive(params: Wrapper<Argon2idParams>, password: Buffer, salt: Buffer, outputLength: number): Buffer;
+export function Argon2idParams_GetIterations(obj: Wrapper<Argon2idParams>): number;
+export function Argon2idParams_GetMemoryKib(obj: Wrapper<Argon2idParams>): number;
+export function Argon2idParams_GetParallelism(obj: Wrapper<Argon2idParams>): number;
+export function Argon2idParams_New(memoryKib: number, iterations: number, parallelism: number): Argon2idParams;
+export function Argon2idParams_TransferToWorker(obj: Wrapper<Argon2idParams>): Transferred<Argon2idParams>;
+export function AttachmentPointer_New(cdnNumber: number, cdnKey: string, contentType: string, key: Buffer, digest: Buffer, size: number, fileName: string | null, caption: string | null, width: number, height: number, uploadTimestamp: number): AttachmentPointer;
+export function AttachmentPointer_TransferToWorker(obj: Wrapper<AttachmentPointer>): Transferred<AttachmentPointer>;
+export function BacklogDecryptor_Decrypt(decryptor: Wrapper<BacklogDecryptor>, messages: Wrapper<CiphertextMessage>[], protocolAddresses: Wrapper<ProtocolAddress>[], sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, prekeyStore: PreKeyStore, signedPrekeyStore: SignedPreKeyStore, rng: Wrapper<Rng> | null, ctx: null): Promise<(Buffer | Error)[]>;
+export function BacklogDecryptor_New(threads: number): BacklogDecryptor;
+export function BackupBuilder_AddAccountData(builder: Wrapper<BackupBuilder>, profileKey: Buffer, username: string | null, givenName: string, familyName: string): Buffer;
+export function BackupBuilder_AddChat(builder: Wrapper<BackupBuilder>, id: number, recipientId: number, archived: boolean, pinnedOrder: number, expirationTimerMs: number): Buffer;
+export function BackupBuilder_AddChatItem(builder: Wrapper<BackupBuilder>, chatId: number, authorId: number, dateSent: number, text: string): Buffer;
+export function BackupBuilder_AddContact(builder: Wrapper<BackupBuilder>, id: number, uuid: string | null, e164: string | null): Buffer;
+export function BackupBuilder_AddGroup(builder: Wrapper<BackupBuilder>, id: number, masterKey: Buffer): Buffer;
+export function BackupBuilder_AddSelfRecipient(builder: Wrapper<BackupBuilder>, id: number): Buffer;
+export function BackupBuilder_Finish(builder: Wrapper<BackupBuilder>): Buffer;
+export function BackupBuilder_New(backupKey: Buffer, compressed: boolean, backupTimeMs: number, rng: Wrapper<Rng> | null): BackupBuilder;
+export function Base64Decoder_Finalize(decoder: Wrapper<Base64Decoder>): Buffer;
+export function Base64Decoder_New(): Base64Decoder;
+export function Base64Decoder_TransferToWorker(obj: Wrapper<Base64Decoder>): Transferred<Base64Decoder>;
+export function Base64Decoder_Update(decoder: Wrapper<Base64Decoder>, encoded: string): Buffer;
+export function Base64Encoder_Finalize(encoder: Wrapper<Base64Encoder>): string;
+export function Base64Encoder_New(): Base64Encoder;
+export function Base64Encoder_TransferToWorker(obj: Wrapper<Base64Encoder>): Transferred<Base64Encoder>;
+export function Base64Encoder_Update(encoder: Wrapper<Base64Encoder>, data: Buffer): string;
+export function Base64_Decode(encoded: string): Buffer;
+export function Base64_Encode(data: Buffer): string;
+export function BridgeContext_Enter(context: Wrapper<BridgeContext>): void;
+export function BridgeContext_Exit(context: Wrapper<BridgeContext>): void;
+export function BridgeContext_GetMetrics(context: Wrapper<BridgeContext>): string;
+export function BridgeContext_New(label: string): BridgeContext;
+export function BridgeContext_ResetMetrics(context: Wrapper<BridgeContext>): void;
+export function BridgeContext_SetMaxLogLevel(context: Wrapper<BridgeContext>, level: number): void;
+export function BridgeContext_TransferToWorker(obj: Wrapper<BridgeContext>): Transferred<BridgeContext>;
+export function CallLinkRootKey_DeriveEraKey(key: Wrapper<CallLinkRootKey>, eraId: string): Buffer;
+export function CallLinkRootKey_DeriveRoomId(key: Wrapper<CallLinkRootKey>): Buffer;
+export function CallLinkRootKey_FromBytes(bytes: Buffer): CallLinkRootKey;
+export function CallLinkRootKey_Generate(rng: Wrapper<Rng> | null): CallLinkRootKey;
+export function CallLinkRootKey_GetBytes(key: Wrapper<CallLinkRootKey>): Buffer;
+export function CallLinkRootKey_Parse(text: string): CallLinkRootKey;
+export function CallLinkRootKey_ToString(key: Wrapper<CallLinkRootKey>): string;
+export function CallLinkRootKey_TransferToWorker(obj: Wrapper<CallLinkRootKey>): Transferred<CallLinkRootKey>;
+export function CiphertextMessage_Deserialize(messageType: number, data: Buffer): CiphertextMessage;
+export function CiphertextMessage_DeserializeFromTransport(data: Buffer): CiphertextMessage;
+export function CiphertextMessage_GetCounter(obj: Wrapper<CiphertextMessage>): number | null;
+export function CiphertextMessage_GetMessageVersion(obj: Wrapper<CiphertextMessage>): number;
+export function CiphertextMessage_GetPreviousCounter(obj: Wrapper<CiphertextMessage>): number | null;
 export function CiphertextMessage_Serialize(obj: Wrapper<CiphertextMessage>): Buffer;
+export function CiphertextMessage_SerializeForTransport(obj: Wrapper<CiphertextMessage>): Buffer;
 export function CiphertextMessage_Type(msg: Wrapper<CiphertextMessage>): number;
+export function Content_GetSerialized(content: Wrapper<Content>): Buffer;
+export function Content_NewReceiptMessage(receiptType: number, timestamps: Buffer): Content;
+export function Content_NewTypingMessage(timestamp: number, started: boolean, groupId: Buffer): Content;
+export function Content_TransferToWorker(obj: Wrapper<Content>): Transferred<Content>;
+export function Crc32_Digest(input: Buffer): number;
+export function Crc32_Finalize(crc: Wrapper<Crc32>): number;
+export function Crc32_New(): Crc32;
+export function Crc32_TransferToWorker(obj: Wrapper<Crc32>): Transferred<Crc32>;
+export function Crc32_Update(crc: Wrapper<Crc32>, input: Buffer): void;
+export function DataMessageBuilder_AddAttachment(builder: Wrapper<DataMessageBuilder>, attachment: Wrapper<AttachmentPointer>): void;
+export function DataMessageBuilder_Build(builder: Wrapper<DataMessageBuilder>): Content;
+export function DataMessageBuilder_New(timestamp: number): DataMessageBuilder;
+export function DataMessageBuilder_SetBody(builder: Wrapper<DataMessageBuilder>, body: string): void;
+export function DataMessageBuilder_SetExpireTimer(builder: Wrapper<DataMessageBuilder>, seconds: number): void;
+export function DataMessageBuilder_SetProfileKey(builder: Wrapper<DataMessageBuilder>, profileKey: Buffer): void;
+export function DataMessageBuilder_SetQuote(builder: Wrapper<DataMessageBuilder>, id: number, authorUuid: string, text: string | null): void;
+export function DecryptionMetrics_Get(): string;
+export function DecryptionMetrics_Reset(): void;
+export function DecryptionMetrics_SetEnabled(enabled: boolean): void;
+export function DeferredDecryptionResult_GetMutationLog(obj: Wrapper<DeferredDecryptionResult>): Buffer;
+export function DeferredDecryptionResult_GetPlaintext(obj: Wrapper<DeferredDecryptionResult>): Buffer;
 export function Fingerprint_DisplayString(obj: Wrapper<Fingerprint>): string;
 export function Fingerprint_New(iterations: number, version: number, localIdentifier: Buffer, localKey: Wrapper<PublicKey>, remoteIdentifier: Buffer, remoteKey: Wrapper<PublicKey>): Fingerprint;
 export function Fingerprint_ScannableEncoding(obj: Wrapper<Fingerprint>): Buffer;
+export function Fingerprint_TransferToWorker(obj: Wrapper<Fingerprint>): Transferred<Fingerprint>;
+export function FrameReader_Finish(reader: Wrapper<FrameReader>): void;
+export function FrameReader_IsFinished(reader: Wrapper<FrameReader>): boolean;
+export function FrameReader_New(backupKey: Buffer): FrameReader;
+export function FrameReader_NextFrame(reader: Wrapper<FrameReader>): Buffer | null;
+export function FrameReader_Push(reader: Wrapper<FrameReader>, chunk: Buffer): void;
+export function FrameReader_TransferToWorker(obj: Wrapper<FrameReader>): Transferred<FrameReader>;
+export function FrameWriter_Append(writer: Wrapper<FrameWriter>, frame: Buffer): Buffer;
+export function FrameWriter_Finish(writer: Wrapper<FrameWriter>): Buffer;
+export function FrameWriter_New(backupKey: Buffer, compressed: boolean, rng: Wrapper<Rng> | null): FrameWriter;
+export function FrameWriter_TransferToWorker(obj: Wrapper<FrameWriter>): Transferred<FrameWriter>;
+export function GroupCall_DeriveEraKey(groupMasterKey: Buffer, eraId: string): Buffer;
+export function GroupCipher_ChainPosition(senderKeyName: Wrapper<SenderKeyName>, distributionId: number, store: SenderKeyStore, ctx: null): Promise<number>;
 export function GroupCipher_DecryptMessage(senderKeyName: Wrapper<SenderKeyName>, message: Buffer, store: SenderKeyStore, ctx: null): Promise<Buffer>;
-export function GroupCipher_EncryptMessage(senderKeyName: Wrapper<SenderKeyName>, message: Buffer, store: SenderKeyStore, ctx: null): Promise<Buffer>;
+export function GroupCipher_DecryptMessageDeferred(senderKeyName: Wrapper<SenderKeyName>, message: Buffer, store: SenderKeyStore, ctx: null): Promise<DeferredDecryptionResult>;
+export function GroupCipher_EncryptMessage(senderKeyName: Wrapper<SenderKeyName>, message: Buffer, store: SenderKeyStore, rng: Wrapper<Rng> | null, ctx: null): Promise<Buffer>;
+export function GroupCipher_FastForward(senderKeyName: Wrapper<SenderKeyName>, distributionId: number, toIteration: number, store: SenderKeyStore, ctx: null): Promise<void>;
 export function HKDF_DeriveSecrets(outputLength: number, version: number, ikm: Buffer, label: Buffer, salt: Buffer | null): Buffer;
+export function Hex_Decode(encoded: string): Buffer;
+export function Hex_Encode(data: Buffer): string;
 export function IdentityKeyPair_Serialize(publicKey: Wrapper<PublicKey>, privateKey: Wrapper<PrivateKey>): Buffer;
+export function IncrementalMacValidator_Finalize(validator: Wrapper<IncrementalMacValidator>): boolean;
+export function IncrementalMacValidator_GetValidatedLength(obj: Wrapper<IncrementalMacValidator>): number;
+export function IncrementalMacValidator_New(key: Buffer, chunkSize: number, digest: Buffer): IncrementalMacValidator;
+export function IncrementalMacValidator_Update(validator: Wrapper<IncrementalMacValidator>, data: Buffer): boolean;
+export function KeyTransparencyMonitorResult_GetMonitoringData(obj: Wrapper<KeyTransparencyMonitorResult>): Buffer;
+export function KeyTransparencyMonitorResult_GetTreeHead(obj: Wrapper<KeyTransparencyMonitorResult>): Buffer;
+export function KeyTransparencyMonitorResult_IsUnchanged(obj: Wrapper<KeyTransparencyMonitorResult>): boolean;
+export function KeyTransparencySearchResult_GetMonitoringData(obj: Wrapper<KeyTransparencySearchResult>): Buffer;
+export function KeyTransparencySearchResult_GetTreeHead(obj: Wrapper<KeyTransparencySearchResult>): Buffer;
+export function KeyTransparencySearchResult_GetValue(obj: Wrapper<KeyTransparencySearchResult>): Buffer;
+export function KeyTransparency_VerifyDistinguished(logKey: Wrapper<PublicKey>, response: Buffer, lastTreeHead: Buffer | null): Buffer;
+export function KeyTransparency_VerifyMonitor(logKey: Wrapper<PublicKey>, searchKey: Buffer, response: Buffer, lastTreeHead: Buffer | null, monitoringData: Buffer): KeyTransparencyMonitorResult;
+export function KeyTransparency_VerifySearch(logKey: Wrapper<PublicKey>, searchKey: Buffer, response: Buffer, lastTreeHead: Buffer | null): KeyTransparencySearchResult;
+export function Limits_GetMaxBufferLength(): number;
+export function Limits_SetMaxBufferLength(limit: number): void;
+export function MutationLog_Apply(mutationLog: Buffer, sessionStore: SessionStore, identityStore: IdentityKeyStore, prekeyStore: PreKeyStore, signedPrekeyStore: SignedPreKeyStore, senderKeyStore: SenderKeyStore, ctx: null): Promise<void>;
+export function Native_InitRuntime(maxInFlight: number, maxQueued: number, queueWhenBusy: boolean): void;
+export function Native_ListFunctions(): string;
+export function Native_RequireAtLeast(abiRevision: number): void;
+export function Native_SelfTest(): string;
+export function Native_Shutdown(timeoutMs: number): Promise<boolean>;
+export function Native_TestPanic(message: string): void;
+export function Native_VersionInfo(): string;
+export function NotificationCommitSet_Apply(commitSet: Buffer, sessionStore: SessionStore, identityStore: IdentityKeyStore, prekeyStore: PreKeyStore, ctx: null): Promise<void>;
+export function NotificationDecrypt_Decrypt(message: Buffer, trustRoot: Wrapper<PublicKey>, timestamp: number, localE164: string | null, localUuid: string, localDeviceId: number, sessionStore: SessionStore, identityStore: IdentityKeyStore, prekeyStore: PreKeyStore, signedPrekeyStore: SignedPreKeyStore, rng: Wrapper<Rng> | null, ctx: null): Promise<NotificationDecryptionResult>;
+export function NotificationDecryptionResult_GetCommitSet(result: Wrapper<NotificationDecryptionResult>): Buffer;
+export function NotificationDecryptionResult_GetDeviceId(result: Wrapper<NotificationDecryptionResult>): number;
+export function NotificationDecryptionResult_GetMessage(result: Wrapper<NotificationDecryptionResult>): Buffer;
+export function NotificationDecryptionResult_GetSenderE164(result: Wrapper<NotificationDecryptionResult>): string | null;
+export function NotificationDecryptionResult_GetSenderUuid(result: Wrapper<NotificationDecryptionResult>): string;
+export function OutgoingEnvelope_Deserialize(buffer: Buffer): OutgoingEnvelope;
+export function OutgoingEnvelope_GetContentHint(obj: Wrapper<OutgoingEnvelope>): number;
+export function OutgoingEnvelope_GetDestination(obj: Wrapper<OutgoingEnvelope>): ProtocolAddress;
+export function OutgoingEnvelope_GetPayload(obj: Wrapper<OutgoingEnvelope>): Buffer;
+export function OutgoingEnvelope_GetSerializedVersion(obj: Wrapper<OutgoingEnvelope>): number;
+export function OutgoingEnvelope_GetTimestamp(obj: Wrapper<OutgoingEnvelope>): number;
+export function OutgoingEnvelope_New(destination: Wrapper<ProtocolAddress>, timestamp: number, contentHint: number, payload: Buffer): OutgoingEnvelope;
+export function OutgoingEnvelope_Serialize(obj: Wrapper<OutgoingEnvelope>): Buffer;
+export function OutgoingEnvelope_TransferToWorker(obj: Wrapper<OutgoingEnvelope>): Transferred<OutgoingEnvelope>;
+export function PaddingPolicy_Bucketed(bucketSize: number): PaddingPolicy;
+export function PaddingPolicy_None(): PaddingPolicy;
+export function PaddingPolicy_Pad(policy: Wrapper<PaddingPolicy>, message: Buffer): Buffer;
+export function PaddingPolicy_PaddedLength(policy: Wrapper<PaddingPolicy>, len: number): number;
+export function PaddingPolicy_Padme(): PaddingPolicy;
+export function PaddingPolicy_TransferToWorker(obj: Wrapper<PaddingPolicy>): Transferred<PaddingPolicy>;
+export function PaddingPolicy_Unpad(policy: Wrapper<PaddingPolicy>, padded: Buffer): Buffer;
+export function PaymentAddress_Sign(identityPublicKey: Wrapper<PublicKey>, identityPrivateKey: Wrapper<PrivateKey>, address: Buffer, rng: Wrapper<Rng> | null): Buffer;
+export function PaymentAddress_Verify(identityKey: Wrapper<PublicKey>, address: Buffer, signature: Buffer): boolean;
+export function PreKeyBundle_DebugDump(obj: Wrapper<PreKeyBundle>, includeSecrets: boolean): string;
+export function PreKeyBundle_DeserializeFromTransport(data: Buffer): PreKeyBundle;
 export function PreKeyBundle_GetDeviceId(obj: Wrapper<PreKeyBundle>): number;
 export function PreKeyBundle_GetIdentityKey(p: Wrapper<PreKeyBundle>): PublicKey;
+export function PreKeyBundle_GetMaxSessionVersion(obj: Wrapper<PreKeyBundle>): number;
+export function PreKeyBundle_GetMinSessionVersion(obj: Wrapper<PreKeyBundle>): number;
 export function PreKeyBundle_GetPreKeyId(obj: Wrapper<PreKeyBundle>): number | null;
 export function PreKeyBundle_GetPreKeyPublic(obj: Wrapper<PreKeyBundle>): PublicKey | null;
 export function PreKeyBundle_GetRegistrationId(obj: Wrapper<PreKeyBundle>): number;
@@ -61,98 +227,232 @@ export function PreKeyBundle_GetSignedPreKeyId(obj: Wrapper<PreKeyBundle>): numb
 export function PreKeyBundle_GetSignedPreKeyPublic(obj: Wrapper<PreKeyBundle>): PublicKey;
 export function PreKeyBundle_GetSignedPreKeySignature(obj: Wrapper<PreKeyBundle>): Buffer;
 export function PreKeyBundle_New(registrationId: number, deviceId: number, prekeyId: number | null, prekey: Wrapper<PublicKey> | null, signedPrekeyId: number, signedPrekey: Wrapper<PublicKey>, signedPrekeySignature: Buffer, identityKey: Wrapper<PublicKey>): PreKeyBundle;
+export function PreKeyBundle_SerializeForTransport(obj: Wrapper<PreKeyBundle>): Buffer;
+export function PreKeyBundle_TransferToWorker(obj: Wrapper<PreKeyBundle>): Transferred<PreKeyBundle>;
+export function PreKeyBundle_WithSessionVersions(bundle: Wrapper<PreKeyBundle>, minVersion: number, maxVersion: number): PreKeyBundle;
 export function PreKeyRecord_Deserialize(buffer: Buffer): PreKeyRecord;
 export function PreKeyRecord_GetId(obj: Wrapper<PreKeyRecord>): number;
 export function PreKeyRecord_GetPrivateKey(obj: Wrapper<PreKeyRecord>): PrivateKey;
 export function PreKeyRecord_GetPublicKey(obj: Wrapper<PreKeyRecord>): PublicKey;
+export function PreKeyRecord_GetSerializedVersion(obj: Wrapper<PreKeyRecord>): number;
 export function PreKeyRecord_New(id: number, pubKey: Wrapper<PublicKey>, privKey: Wrapper<PrivateKey>): PreKeyRecord;
 export function PreKeyRecord_Serialize(obj: Wrapper<PreKeyRecord>): Buffer;
+export function PreKeyRecord_TransferToWorker(obj: Wrapper<PreKeyRecord>): Transferred<PreKeyRecord>;
+export function PreKeySignalMessage_DebugDump(obj: Wrapper<PreKeySignalMessage>, includeSecrets: boolean): string;
 export function PreKeySignalMessage_Deserialize(buffer: Buffer): PreKeySignalMessage;
+export function PreKeySignalMessage_DeserializeFromTransport(data: Buffer): PreKeySignalMessage;
+export function PreKeySignalMessage_GetBaseKey(m: Wrapper<PreKeySignalMessage>): PublicKey;
+export function PreKeySignalMessage_GetIdentityKey(m: Wrapper<PreKeySignalMessage>): PublicKey;
 export function PreKeySignalMessage_GetPreKeyId(obj: Wrapper<PreKeySignalMessage>): number | null;
 export function PreKeySignalMessage_GetRegistrationId(obj: Wrapper<PreKeySignalMessage>): number;
+export function PreKeySignalMessage_GetSignalMessage(m: Wrapper<PreKeySignalMessage>): SignalMessage;
 export function PreKeySignalMessage_GetSignedPreKeyId(obj: Wrapper<PreKeySignalMessage>): number;
 export function PreKeySignalMessage_GetVersion(obj: Wrapper<PreKeySignalMessage>): number;
 export function PreKeySignalMessage_New(messageVersion: number, registrationId: number, preKeyId: number | null, signedPreKeyId: number, baseKey: Wrapper<PublicKey>, identityKey: Wrapper<PublicKey>, signalMessage: Wrapper<SignalMessage>): PreKeySignalMessage;
 export function PreKeySignalMessage_Serialize(obj: Wrapper<PreKeySignalMessage>): Buffer;
+export function PreKeySignalMessage_SerializeForTransport(obj: Wrapper<PreKeySignalMessage>): Buffer;
+export function PreKeySignalMessage_TransferToWorker(obj: Wrapper<PreKeySignalMessage>): Transferred<PreKeySignalMessage>;
 export function PrivateKey_Agree(privateKey: Wrapper<PrivateKey>, publicKey: Wrapper<PublicKey>): Buffer;
 export function PrivateKey_Deserialize(buffer: Buffer): PrivateKey;
-export function PrivateKey_Generate(): PrivateKey;
+export function PrivateKey_Generate(rng: Wrapper<Rng> | null): PrivateKey;
 export function PrivateKey_GetPublicKey(k: Wrapper<PrivateKey>): PublicKey;
+export function PrivateKey_Open(key: Wrapper<PrivateKey>, sealed: Buffer): Buffer;
 export function PrivateKey_Serialize(obj: Wrapper<PrivateKey>): Buffer;
-export function PrivateKey_Sign(key: Wrapper<PrivateKey>, message: Buffer): Buffer;
+export function PrivateKey_Sign(key: Wrapper<PrivateKey>, message: Buffer, rng: Wrapper<Rng> | null): Buffer;
+export function PrivateKey_TransferToWorker(obj: Wrapper<PrivateKey>): Transferred<PrivateKey>;
+export function ProfileKey_BlindServiceId(profileKey: Buffer, serviceId: string): Buffer;
+export function ProfileKey_DeriveAccessKey(profileKey: Buffer): Buffer;
 export function ProtocolAddress_DeviceId(obj: Wrapper<ProtocolAddress>): number;
 export function ProtocolAddress_Name(obj: Wrapper<ProtocolAddress>): string;
 export function ProtocolAddress_New(name: string, deviceId: number): ProtocolAddress;
+export function ProtocolAddress_TransferToWorker(obj: Wrapper<ProtocolAddress>): Transferred<ProtocolAddress>;
+export function ProvisioningCipher_Decrypt(cipher: Wrapper<ProvisioningCipher>, envelope: Buffer): Buffer;
+export function ProvisioningCipher_Encrypt(theirPublicKey: Wrapper<PublicKey>, message: Buffer, rng: Wrapper<Rng> | null): Buffer;
+export function ProvisioningCipher_GetPublicKey(cipher: Wrapper<ProvisioningCipher>): PublicKey;
+export function ProvisioningCipher_New(rng: Wrapper<Rng> | null): ProvisioningCipher;
+export function ProvisioningUuid_Deserialize(buffer: Buffer): ProvisioningUuid;
+export function ProvisioningUuid_GetSerialized(obj: Wrapper<ProvisioningUuid>): Buffer;
+export function ProvisioningUuid_GetUuid(obj: Wrapper<ProvisioningUuid>): string;
+export function ProvisioningUuid_New(uuid: string): ProvisioningUuid;
+export function ProvisioningUuid_TransferToWorker(obj: Wrapper<ProvisioningUuid>): Transferred<ProvisioningUuid>;
 export function PublicKey_Compare(key1: Wrapper<PublicKey>, key2: Wrapper<PublicKey>): number;
 export function PublicKey_Deserialize(buffer: Buffer): PublicKey;
 export function PublicKey_GetPublicKeyBytes(obj: Wrapper<PublicKey>): Buffer;
+export function PublicKey_Seal(key: Wrapper<PublicKey>, plaintext: Buffer, rng: Wrapper<Rng> | null): Buffer;
 export function PublicKey_Serialize(obj: Wrapper<PublicKey>): Buffer;
+export function PublicKey_TransferToWorker(obj: Wrapper<PublicKey>): Transferred<PublicKey>;
 export function PublicKey_Verify(key: Wrapper<PublicKey>, message: Buffer, signature: Buffer): boolean;
+export function ReceiveTransferredHandle<T>(token: Transferred<T>): T;
+export function RegistrationOutcome_FromResponse(status: number, body: Buffer, retryAfter: string | null): RegistrationOutcome;
+export function RegistrationOutcome_GetAci(outcome: Wrapper<RegistrationOutcome>): string | null;
+export function RegistrationOutcome_GetLockTimeRemainingMs(outcome: Wrapper<RegistrationOutcome>): number;
+export function RegistrationOutcome_GetNumber(outcome: Wrapper<RegistrationOutcome>): string | null;
+export function RegistrationOutcome_GetPni(outcome: Wrapper<RegistrationOutcome>): string | null;
+export function RegistrationOutcome_GetSvrPassword(outcome: Wrapper<RegistrationOutcome>): string | null;
+export function RegistrationOutcome_GetSvrUsername(outcome: Wrapper<RegistrationOutcome>): string | null;
+export function RegistrationOutcome_IsLocked(outcome: Wrapper<RegistrationOutcome>): boolean;
+export function RegistrationRequest_CreateSession(number: string, pushToken: string | null, pushTokenIsApn: boolean, mcc: string | null, mnc: string | null): RegistrationRequest;
+export function RegistrationRequest_GetBody(obj: Wrapper<RegistrationRequest>): Buffer | null;
+export function RegistrationRequest_GetMethod(obj: Wrapper<RegistrationRequest>): string;
+export function RegistrationRequest_GetPath(obj: Wrapper<RegistrationRequest>): string;
+export function RegistrationRequest_GetSession(sessionId: string): RegistrationRequest;
+export function RegistrationRequest_RegisterAccount(sessionId: string, registrationId: number, fetchesMessages: boolean, registrationLock: string | null): RegistrationRequest;
+export function RegistrationRequest_RequestVerificationCode(sessionId: string, voice: boolean, client: string): RegistrationRequest;
+export function RegistrationRequest_SubmitVerificationCode(sessionId: string, code: string): RegistrationRequest;
+export function RegistrationRequest_UpdateSession(sessionId: string, pushChallenge: string | null, captcha: string | null): RegistrationRequest;
+export function RegistrationSession_FromResponse(status: number, body: Buffer, retryAfter: string | null): RegistrationSession;
+export function RegistrationSession_GetAllowedToRequestCode(obj: Wrapper<RegistrationSession>): boolean;
+export function RegistrationSession_GetId(obj: Wrapper<RegistrationSession>): string;
+export function RegistrationSession_GetNextCallSeconds(obj: Wrapper<RegistrationSession>): number | null;
+export function RegistrationSession_GetNextSmsSeconds(obj: Wrapper<RegistrationSession>): number | null;
+export function RegistrationSession_GetNextVerificationAttemptSeconds(obj: Wrapper<RegistrationSession>): number | null;
+export function RegistrationSession_GetVerified(obj: Wrapper<RegistrationSession>): boolean;
+export function RegistrationSession_RequiresCaptcha(session: Wrapper<RegistrationSession>): boolean;
+export function RegistrationSession_RequiresPushChallenge(session: Wrapper<RegistrationSession>): boolean;
+export function Registration_LockToken(masterKey: Buffer): string;
+export function Rng_Clone(obj: Wrapper<Rng>): Rng;
+export function Rng_NewSeeded(seed: Buffer): Rng;
+export function Rng_NewSystem(): Rng;
+export function Rng_TransferToWorker(obj: Wrapper<Rng>): Transferred<Rng>;
 export function ScannableFingerprint_Compare(fprint1: Buffer, fprint2: Buffer): boolean;
+export function ScryptParams_Calibrate(targetDurationMs: number, r: number, p: number): ScryptParams;
+export function ScryptParams_Derive(params: Wrapper<ScryptParams>, password: Buffer, salt: Buffer, outputLength: number): Buffer;
+export function ScryptParams_GetLogN(obj: Wrapper<ScryptParams>): number;
+export function ScryptParams_GetP(obj: Wrapper<ScryptParams>): number;
+export function ScryptParams_GetR(obj: Wrapper<ScryptParams>): number;
+export function ScryptParams_New(logN: number, r: number, p: number): ScryptParams;
+export function ScryptParams_TransferToWorker(obj: Wrapper<ScryptParams>): Transferred<ScryptParams>;
 export function SealedSenderDecryptionResult_GetDeviceId(obj: Wrapper<SealedSenderDecryptionResult>): number;
 export function SealedSenderDecryptionResult_GetSenderE164(obj: Wrapper<SealedSenderDecryptionResult>): string | null;
 export function SealedSenderDecryptionResult_GetSenderUuid(obj: Wrapper<SealedSenderDecryptionResult>): string;
 export function SealedSenderDecryptionResult_Message(obj: Wrapper<SealedSenderDecryptionResult>): Buffer;
-export function SealedSender_DecryptMessage(message: Buffer, trustRoot: Wrapper<PublicKey>, timestamp: number, localE164: string | null, localUuid: string, localDeviceId: number, sessionStore: SessionStore, identityStore: IdentityKeyStore, prekeyStore: PreKeyStore, signedPrekeyStore: SignedPreKeyStore): Promise<SealedSenderDecryptionResult | null>;
+export function SealedSender_DecryptMessage(message: Buffer, trustRoot: Wrapper<PublicKey>, timestamp: number, gracePeriod: number, localE164: string | null, localUuid: string, localDeviceId: number, sessionStore: SessionStore, identityStore: IdentityKeyStore, prekeyStore: PreKeyStore, signedPrekeyStore: SignedPreKeyStore, replayCache: ReplayCache | null, rng: Wrapper<Rng> | null): Promise<SealedSenderDecryptionResult | null>;
 export function SealedSender_DecryptToUsmc(ctext: Buffer, identityStore: IdentityKeyStore, ctx: null): Promise<UnidentifiedSenderMessageContent>;
-export function SealedSender_EncryptMessage(destination: Wrapper<ProtocolAddress>, senderCert: Wrapper<SenderCertificate>, ptext: Buffer, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, ctx: null): Promise<Buffer>;
+export function SealedSender_EncryptMessage(destination: Wrapper<ProtocolAddress>, senderCert: Wrapper<SenderCertificate>, ptext: Buffer, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, rng: Wrapper<Rng> | null, ctx: null): Promise<Buffer>;
+export function SealedSender_EncryptMessageWithProvider(destination: Wrapper<ProtocolAddress>, provider: Wrapper<SenderCertificateProvider>, certificateSource: SenderCertificateSource, timestamp: number, ptext: Buffer, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, rng: Wrapper<Rng> | null, ctx: null): Promise<Buffer>;
+export function SecuritySnapshot_Create(address: Wrapper<ProtocolAddress>, senderKeyName: Wrapper<SenderKeyName> | null, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, senderKeyStore: SenderKeyStore, ctx: null): Promise<string>;
+export function SenderCertificateProvider_Get(provider: Wrapper<SenderCertificateProvider>, validUntil: number, certificateSource: SenderCertificateSource, ctx: null): Promise<SenderCertificate>;
+export function SenderCertificateProvider_New(certificate: Wrapper<SenderCertificate> | null, freshnessWindow: number): SenderCertificateProvider;
 export function SenderCertificate_Deserialize(buffer: Buffer): SenderCertificate;
 export function SenderCertificate_GetCertificate(obj: Wrapper<SenderCertificate>): Buffer;
 export function SenderCertificate_GetDeviceId(obj: Wrapper<SenderCertificate>): number;
-export function SenderCertificate_GetExpiration(obj: Wrapper<SenderCertificate>): number;
+export function SenderCertificate_GetExpiration(obj: Wrapper<SenderCertificate>): Date;
 export function SenderCertificate_GetKey(obj: Wrapper<SenderCertificate>): PublicKey;
 export function SenderCertificate_GetSenderE164(obj: Wrapper<SenderCertificate>): string | null;
 export function SenderCertificate_GetSenderUuid(obj: Wrapper<SenderCertificate>): string;
 export function SenderCertificate_GetSerialized(obj: Wrapper<SenderCertificate>): Buffer;
 export function SenderCertificate_GetServerCertificate(cert: Wrapper<SenderCertificate>): ServerCertificate;
 export function SenderCertificate_GetSignature(obj: Wrapper<SenderCertificate>): Buffer;
-export function SenderCertificate_New(senderUuid: string, senderE164: string | null, senderDeviceId: number, senderKey: Wrapper<PublicKey>, expiration: number, signerCert: Wrapper<ServerCertificate>, signerKey: Wrapper<PrivateKey>): SenderCertificate;
+export function SenderCertificate_New(senderUuid: string, senderE164: string | null, senderDeviceId: number, senderKey: Wrapper<PublicKey>, expiration: number, signerCert: Wrapper<ServerCertificate>, signerKey: Wrapper<PrivateKey>, rng: Wrapper<Rng> | null): SenderCertificate;
+export function SenderCertificate_TransferToWorker(obj: Wrapper<SenderCertificate>): Transferred<SenderCertificate>;
 export function SenderCertificate_Validate(cert: Wrapper<SenderCertificate>, key: Wrapper<PublicKey>, time: number): boolean;
-export function SenderKeyDistributionMessage_Create(senderKeyName: Wrapper<SenderKeyName>, store: SenderKeyStore, ctx: null): Promise<SenderKeyDistributionMessage>;
+export function SenderCertificate_ValidateWithGracePeriod(cert: Wrapper<SenderCertificate>, key: Wrapper<PublicKey>, time: number, gracePeriod: number): boolean;
+export function SenderKeyDistributionMessage_Create(senderKeyName: Wrapper<SenderKeyName>, store: SenderKeyStore, rng: Wrapper<Rng> | null, ctx: null): Promise<SenderKeyDistributionMessage>;
+export function SenderKeyDistributionMessage_DebugDump(obj: Wrapper<SenderKeyDistributionMessage>, includeSecrets: boolean): string;
 export function SenderKeyDistributionMessage_Deserialize(buffer: Buffer): SenderKeyDistributionMessage;
+export function SenderKeyDistributionMessage_DeserializeFromTransport(data: Buffer): SenderKeyDistributionMessage;
 export function SenderKeyDistributionMessage_GetChainKey(obj: Wrapper<SenderKeyDistributionMessage>): Buffer;
 export function SenderKeyDistributionMessage_GetId(obj: Wrapper<SenderKeyDistributionMessage>): number;
 export function SenderKeyDistributionMessage_GetIteration(obj: Wrapper<SenderKeyDistributionMessage>): number;
 export function SenderKeyDistributionMessage_New(keyId: number, iteration: number, chainkey: Buffer, pk: Wrapper<PublicKey>): SenderKeyDistributionMessage;
 export function SenderKeyDistributionMessage_Process(senderKeyName: Wrapper<SenderKeyName>, senderKeyDistributionMessage: Wrapper<SenderKeyDistributionMessage>, store: SenderKeyStore, ctx: null): Promise<void>;
 export function SenderKeyDistributionMessage_Serialize(obj: Wrapper<SenderKeyDistributionMessage>): Buffer;
+export function SenderKeyDistributionMessage_SerializeForTransport(obj: Wrapper<SenderKeyDistributionMessage>): Buffer;
+export function SenderKeyDistributionMessage_TransferToWorker(obj: Wrapper<SenderKeyDistributionMessage>): Transferred<SenderKeyDistributionMessage>;
+export function SenderKeyMessage_DebugDump(obj: Wrapper<SenderKeyMessage>, includeSecrets: boolean): string;
 export function SenderKeyMessage_Deserialize(buffer: Buffer): SenderKeyMessage;
+export function SenderKeyMessage_DeserializeFromTransport(data: Buffer): SenderKeyMessage;
 export function SenderKeyMessage_GetCipherText(obj: Wrapper<SenderKeyMessage>): Buffer;
 export function SenderKeyMessage_GetIteration(obj: Wrapper<SenderKeyMessage>): number;
 export function SenderKeyMessage_GetKeyId(obj: Wrapper<SenderKeyMessage>): number;
-export function SenderKeyMessage_New(keyId: number, iteration: number, ciphertext: Buffer, pk: Wrapper<PrivateKey>): SenderKeyMessage;
+export function SenderKeyMessage_GetMessageVersion(obj: Wrapper<SenderKeyMessage>): number;
+export function SenderKeyMessage_New(keyId: number, iteration: number, ciphertext: Buffer, pk: Wrapper<PrivateKey>, rng: Wrapper<Rng> | null): SenderKeyMessage;
 export function SenderKeyMessage_Serialize(obj: Wrapper<SenderKeyMessage>): Buffer;
+export function SenderKeyMessage_SerializeForTransport(obj: Wrapper<SenderKeyMessage>): Buffer;
+export function SenderKeyMessage_TransferToWorker(obj: Wrapper<SenderKeyMessage>): Transferred<SenderKeyMessage>;
 export function SenderKeyMessage_VerifySignature(skm: Wrapper<SenderKeyMessage>, pubkey: Wrapper<PublicKey>): boolean;
 export function SenderKeyName_GetGroupId(obj: Wrapper<SenderKeyName>): string;
 export function SenderKeyName_GetSenderDeviceId(skn: Wrapper<SenderKeyName>): number;
 export function SenderKeyName_GetSenderName(obj: Wrapper<SenderKeyName>): string;
 export function SenderKeyName_New(groupId: string, senderName: string, senderDeviceId: number): SenderKeyName;
+export function SenderKeyName_TransferToWorker(obj: Wrapper<SenderKeyName>): Transferred<SenderKeyName>;
+export function SenderKeyRecord_DebugDump(obj: Wrapper<SenderKeyRecord>, includeSecrets: boolean): string;
 export function SenderKeyRecord_Deserialize(buffer: Buffer): SenderKeyRecord;
+export function SenderKeyRecord_ForgetDistribution(record: Wrapper<SenderKeyRecord>, member: Wrapper<ProtocolAddress>): boolean;
+export function SenderKeyRecord_GetSerializedVersion(obj: Wrapper<SenderKeyRecord>): number;
+export function SenderKeyRecord_MarkDistributed(record: Wrapper<SenderKeyRecord>, member: Wrapper<ProtocolAddress>): void;
+export function SenderKeyRecord_MaxMessageKeysPerState(): number;
+export function SenderKeyRecord_MaxStates(): number;
+export function SenderKeyRecord_NeedsDistribution(record: Wrapper<SenderKeyRecord>, member: Wrapper<ProtocolAddress>): boolean;
 export function SenderKeyRecord_New(): SenderKeyRecord;
 export function SenderKeyRecord_Serialize(obj: Wrapper<SenderKeyRecord>): Buffer;
+export function SenderKeyRecord_TransferToWorker(obj: Wrapper<SenderKeyRecord>): Transferred<SenderKeyRecord>;
 export function ServerCertificate_Deserialize(buffer: Buffer): ServerCertificate;
 export function ServerCertificate_GetCertificate(obj: Wrapper<ServerCertificate>): Buffer;
 export function ServerCertificate_GetKey(obj: Wrapper<ServerCertificate>): PublicKey;
 export function ServerCertificate_GetKeyId(obj: Wrapper<ServerCertificate>): number;
 export function ServerCertificate_GetSerialized(obj: Wrapper<ServerCertificate>): Buffer;
 export function ServerCertificate_GetSignature(obj: Wrapper<ServerCertificate>): Buffer;
-export function ServerCertificate_New(keyId: number, serverKey: Wrapper<PublicKey>, trustRoot: Wrapper<PrivateKey>): ServerCertificate;
-export function SessionBuilder_ProcessPreKeyBundle(bundle: Wrapper<PreKeyBundle>, protocolAddress: Wrapper<ProtocolAddress>, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, ctx: null): Promise<void>;
-export function SessionCipher_DecryptPreKeySignalMessage(message: Wrapper<PreKeySignalMessage>, protocolAddress: Wrapper<ProtocolAddress>, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, prekeyStore: PreKeyStore, signedPrekeyStore: SignedPreKeyStore, ctx: null): Promise<Buffer>;
-export function SessionCipher_DecryptSignalMessage(message: Wrapper<SignalMessage>, protocolAddress: Wrapper<ProtocolAddress>, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, ctx: null): Promise<Buffer>;
+export function ServerCertificate_New(keyId: number, serverKey: Wrapper<PublicKey>, trustRoot: Wrapper<PrivateKey>, rng: Wrapper<Rng> | null): ServerCertificate;
+export function ServerCertificate_TransferToWorker(obj: Wrapper<ServerCertificate>): Transferred<ServerCertificate>;
+export function ServiceIdSet_AddFixedWidthBinary(set: Wrapper<ServiceIdSet>, list: Buffer): void;
+export function ServiceIdSet_Contains(set: Wrapper<ServiceIdSet>, serviceId: Buffer): boolean;
+export function ServiceIdSet_Difference(set: Wrapper<ServiceIdSet>, other: Wrapper<ServiceIdSet>): ServiceIdSet;
+export function ServiceIdSet_GetPage(set: Wrapper<ServiceIdSet>, offset: number, limit: number): Buffer;
+export function ServiceIdSet_Intersection(set: Wrapper<ServiceIdSet>, other: Wrapper<ServiceIdSet>): ServiceIdSet;
+export function ServiceIdSet_Len(set: Wrapper<ServiceIdSet>): number;
+export function ServiceIdSet_New(): ServiceIdSet;
+export function ServiceIdSet_TransferToWorker(obj: Wrapper<ServiceIdSet>): Transferred<ServiceIdSet>;
+export function SessionBuilder_ProcessPreKeyBundle(bundle: Wrapper<PreKeyBundle>, protocolAddress: Wrapper<ProtocolAddress>, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, rng: Wrapper<Rng> | null, ctx: null): Promise<void>;
+export function SessionCache_Clear(cache: Wrapper<SessionCache>): void;
+export function SessionCache_Invalidate(cache: Wrapper<SessionCache>, address: Wrapper<ProtocolAddress>): void;
+export function SessionCache_New(capacity: number): SessionCache;
+export function SessionCache_SetCapacity(cache: Wrapper<SessionCache>, capacity: number): void;
+export function SessionCipher_DecryptPreKeySignalMessage(message: Wrapper<PreKeySignalMessage>, protocolAddress: Wrapper<ProtocolAddress>, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, prekeyStore: PreKeyStore, signedPrekeyStore: SignedPreKeyStore, rng: Wrapper<Rng> | null, ctx: null): Promise<Buffer>;
+export function SessionCipher_DecryptPreKeySignalMessageDeferred(message: Wrapper<PreKeySignalMessage>, protocolAddress: Wrapper<ProtocolAddress>, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, prekeyStore: PreKeyStore, signedPrekeyStore: SignedPreKeyStore, rng: Wrapper<Rng> | null, ctx: null): Promise<DeferredDecryptionResult>;
+export function SessionCipher_DecryptPreKeySignalMessageWithIdentityOverride(message: Wrapper<PreKeySignalMessage>, protocolAddress: Wrapper<ProtocolAddress>, approvedIdentityKey: Wrapper<PublicKey>, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, prekeyStore: PreKeyStore, signedPrekeyStore: SignedPreKeyStore, rng: Wrapper<Rng> | null, ctx: null): Promise<Buffer>;
+export function SessionCipher_DecryptSignalMessage(message: Wrapper<SignalMessage>, protocolAddress: Wrapper<ProtocolAddress>, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, rng: Wrapper<Rng> | null, ctx: null): Promise<Buffer>;
+export function SessionCipher_DecryptSignalMessageDeferred(message: Wrapper<SignalMessage>, protocolAddress: Wrapper<ProtocolAddress>, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, rng: Wrapper<Rng> | null, ctx: null): Promise<DeferredDecryptionResult>;
+export function SessionCipher_DecryptSignalMessageWithIdentityOverride(message: Wrapper<SignalMessage>, protocolAddress: Wrapper<ProtocolAddress>, approvedIdentityKey: Wrapper<PublicKey>, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, rng: Wrapper<Rng> | null, ctx: null): Promise<Buffer>;
+export function SessionCipher_EncryptContent(content: Wrapper<Content>, padding: Wrapper<PaddingPolicy>, protocolAddress: Wrapper<ProtocolAddress>, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, ctx: null): Promise<CiphertextMessage>;
 export function SessionCipher_EncryptMessage(ptext: Buffer, protocolAddress: Wrapper<ProtocolAddress>, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, ctx: null): Promise<CiphertextMessage>;
+export function SessionCipher_EncryptMessageBatch(ptext: Buffer, protocolAddresses: Wrapper<ProtocolAddress>[], sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, ctx: null): Promise<EncryptBatchResult[]>;
+export function SessionCipher_EncryptMessageWithIdentityOverride(ptext: Buffer, protocolAddress: Wrapper<ProtocolAddress>, approvedIdentityKey: Wrapper<PublicKey>, sessionStore: SessionStore, identityKeyStore: IdentityKeyStore, ctx: null): Promise<CiphertextMessage>;
 export function SessionRecord_ArchiveCurrentState(sessionRecord: Wrapper<SessionRecord>): void;
+export function SessionRecord_DebugDump(obj: Wrapper<SessionRecord>, includeSecrets: boolean): string;
 export function SessionRecord_Deserialize(buffer: Buffer): SessionRecord;
 export function SessionRecord_GetLocalRegistrationId(obj: Wrapper<SessionRecord>): number;
 export function SessionRecord_GetRemoteRegistrationId(obj: Wrapper<SessionRecord>): number;
+export function SessionRecord_GetSerializedVersion(obj: Wrapper<SessionRecord>): number;
+export function SessionRecord_GetSessionVersion(s: Wrapper<SessionRecord>): number;
 export function SessionRecord_HasCurrentState(obj: Wrapper<SessionRecord>): boolean;
+export function SessionRecord_MaxArchivedStates(): number;
+export function SessionRecord_MaxMessageKeysPerChain(): number;
+export function SessionRecord_MaxReceiverChains(): number;
 export function SessionRecord_Serialize(obj: Wrapper<SessionRecord>): Buffer;
+export function SessionRecord_TransferToWorker(obj: Wrapper<SessionRecord>): Transferred<SessionRecord>;
+export function Sha256_Digest(input: Buffer): Buffer;
+export function Sha256_Finalize(sha256: Wrapper<Sha256>): Buffer;
+export function Sha256_New(): Sha256;
+export function Sha256_TransferToWorker(obj: Wrapper<Sha256>): Transferred<Sha256>;
+export function Sha256_Update(sha256: Wrapper<Sha256>, input: Buffer): void;
+export function Sha512_Digest(input: Buffer): Buffer;
+export function Sha512_Finalize(sha512: Wrapper<Sha512>): Buffer;
+export function Sha512_New(): Sha512;
+export function Sha512_TransferToWorker(obj: Wrapper<Sha512>): Transferred<Sha512>;
+export function Sha512_Update(sha512: Wrapper<Sha512>, input: Buffer): void;
+export function SignalMessage_DebugDump(obj: Wrapper<SignalMessage>, includeSecrets: boolean): string;
 export function SignalMessage_Deserialize(buffer: Buffer): SignalMessage;
+export function SignalMessage_DeserializeFromTransport(data: Buffer): SignalMessage;
 export function SignalMessage_GetBody(obj: Wrapper<SignalMessage>): Buffer;
 export function SignalMessage_GetCounter(obj: Wrapper<SignalMessage>): number;
 export function SignalMessage_GetMessageVersion(obj: Wrapper<SignalMessage>): number;
+export function SignalMessage_GetPreviousCounter(obj: Wrapper<SignalMessage>): number;
+export function SignalMessage_GetSenderRatchetKey(m: Wrapper<SignalMessage>): PublicKey;
 export function SignalMessage_GetSerialized(obj: Wrapper<SignalMessage>): Buffer;
 export function SignalMessage_New(messageVersion: number, macKey: Buffer, senderRatchetKey: Wrapper<PublicKey>, counter: number, previousCounter: number, ciphertext: Buffer, senderIdentityKey: Wrapper<PublicKey>, receiverIdentityKey: Wrapper<PublicKey>): SignalMessage;
+export function SignalMessage_SerializeForTransport(obj: Wrapper<SignalMessage>): Buffer;
+export function SignalMessage_TransferToWorker(obj: Wrapper<SignalMessage>): Transferred<SignalMessage>;
 export function SignalMessage_VerifyMac(msg: Wrapper<SignalMessage>, senderIdentityKey: Wrapper<PublicKey>, receiverIdentityKey: Wrapper<PublicKey>, macKey: Buffer): boolean;
 export function SignedPreKeyRecord_Deserialize(buffer: Buffer): SignedPreKeyRecord;
 export function SignedPreKeyRecord_GetId(obj: Wrapper<SignedPreKeyRecord>): number;
@@ -162,29 +462,85 @@ export function SignedPreKeyRecord_GetSignature(obj: Wrapper<SignedPreKeyRecord>
 export function SignedPreKeyRecord_GetTimestamp(obj: Wrapper<SignedPreKeyRecord>): number;
 export function SignedPreKeyRecord_New(id: number, timestamp: number, pubKey: Wrapper<PublicKey>, privKey: Wrapper<PrivateKey>, signature: Buffer): SignedPreKeyRecord;
 export function SignedPreKeyRecord_Serialize(obj: Wrapper<SignedPreKeyRecord>): Buffer;
+export function SignedPreKeyRecord_TransferToWorker(obj: Wrapper<SignedPreKeyRecord>): Transferred<SignedPreKeyRecord>;
+export function StickerCipher_Decrypt(cipher: Wrapper<StickerCipher>, data: Buffer): Buffer;
+export function StickerCipher_New(packKey: Buffer): StickerCipher;
+export function StickerDecryption_Finalize(decryption: Wrapper<StickerDecryption>): Buffer;
+export function StickerDecryption_New(cipher: Wrapper<StickerCipher>): StickerDecryption;
+export function StickerDecryption_Update(decryption: Wrapper<StickerDecryption>, data: Buffer): Buffer;
+export function StorageServiceKey_DecryptManifest(key: Wrapper<StorageServiceKey>, version: number, ciphertext: Buffer): Buffer;
+export function StorageServiceKey_DecryptRecord(key: Wrapper<StorageServiceKey>, rawId: Buffer, ciphertext: Buffer): Buffer;
+export function StorageServiceKey_EncryptManifest(key: Wrapper<StorageServiceKey>, version: number, plaintext: Buffer, rng: Wrapper<Rng> | null): Buffer;
+export function StorageServiceKey_EncryptRecord(key: Wrapper<StorageServiceKey>, rawId: Buffer, plaintext: Buffer, rng: Wrapper<Rng> | null): Buffer;
+export function StorageServiceKey_FromMasterKey(masterKey: Buffer): StorageServiceKey;
+export function StorageServiceKey_GetStorageKey(key: Wrapper<StorageServiceKey>): Buffer;
+export function StorageServiceKey_ManifestKey(key: Wrapper<StorageServiceKey>, version: number): Buffer;
+export function StorageServiceKey_New(storageKey: Buffer): StorageServiceKey;
+export function StorageServiceKey_RecordKey(key: Wrapper<StorageServiceKey>, rawId: Buffer): Buffer;
+export function StorageServiceKey_TransferToWorker(obj: Wrapper<StorageServiceKey>): Transferred<StorageServiceKey>;
+export function Tracing_InstallLogSubscriber(): void;
 export function UnidentifiedSenderMessageContent_Deserialize(buffer: Buffer): UnidentifiedSenderMessageContent;
 export function UnidentifiedSenderMessageContent_GetContents(obj: Wrapper<UnidentifiedSenderMessageContent>): Buffer;
 export function UnidentifiedSenderMessageContent_GetMsgType(m: Wrapper<UnidentifiedSenderMessageContent>): number;
 export function UnidentifiedSenderMessageContent_GetSenderCert(m: Wrapper<UnidentifiedSenderMessageContent>): SenderCertificate;
 export function UnidentifiedSenderMessageContent_Serialize(obj: Wrapper<UnidentifiedSenderMessageContent>): Buffer;
 export function initLogger(maxLevel: LogLevel, callback: (level: LogLevel, target: string, file: string | null, line: number | null, message: string) => void): void
+export function registerErrorClasses(classes: Record<string, new (message: string) => Error>): void
+export function setAuditHook(callback: ((kind: AuditEventKind, addressName: string, deviceId: number, groupId: string | null) => void) | null): void
+export function setBufferChecksumPolicy(policy: BufferChecksumPolicy): void
 interface Aes256GcmSiv { readonly __type: unique symbol; }
+interface Aes256KeyWrap { readonly __type: unique symbol; }
+interface Argon2idParams { readonly __type: unique symbol; }
+interface AttachmentPointer { readonly __type: unique symbol; }
+interface BacklogDecryptor { readonly __type: unique symbol; }
+interface BackupBuilder { readonly __type: unique symbol; }
+interface Base64Decoder { readonly __type: unique symbol; }
+interface Base64Encoder { readonly __type: unique symbol; }
+interface BridgeContext { readonly __type: unique symbol; }
+interface CallLinkRootKey { readonly __type: unique symbol; }
 interface CiphertextMessage { readonly __type: unique symbol; }
+interface Content { readonly __type: unique symbol; }
+interface Crc32 { readonly __type: unique symbol; }
+interface DataMessageBuilder { readonly __type: unique symbol; }
+interface DeferredDecryptionResult { readonly __type: unique symbol; }
 interface Fingerprint { readonly __type: unique symbol; }
+interface FrameReader { readonly __type: unique symbol; }
+interface FrameWriter { readonly __type: unique symbol; }
+interface IncrementalMacValidator { readonly __type: unique symbol; }
+interface KeyTransparencyMonitorResult { readonly __type: unique symbol; }
+interface KeyTransparencySearchResult { readonly __type: unique symbol; }
+interface NotificationDecryptionResult { readonly __type: unique symbol; }
+interface OutgoingEnvelope { readonly __type: unique symbol; }
+interface PaddingPolicy { readonly __type: unique symbol; }
 interface PreKeyBundle { readonly __type: unique symbol; }
 interface PreKeyRecord { readonly __type: unique symbol; }
 interface PreKeySignalMessage { readonly __type: unique symbol; }
 interface PrivateKey { readonly __type: unique symbol; }
 interface ProtocolAddress { readonly __type: unique symbol; }
+interface ProvisioningCipher { readonly __type: unique symbol; }
+interface ProvisioningUuid { readonly __type: unique symbol; }
 interface PublicKey { readonly __type: unique symbol; }
+interface RegistrationOutcome { readonly __type: unique symbol; }
+interface RegistrationRequest { readonly __type: unique symbol; }
+interface RegistrationSession { readonly __type: unique symbol; }
+interface Rng { readonly __type: unique symbol; }
+interface ScryptParams { readonly __type: unique symbol; }
 interface SealedSenderDecryptionResult { readonly __type: unique symbol; }
 interface SenderCertificate { readonly __type: unique symbol; }
+interface SenderCertificateProvider { readonly __type: unique symbol; }
 interface SenderKeyDistributionMessage { readonly __type: unique symbol; }
 interface SenderKeyMessage { readonly __type: unique symbol; }
 interface SenderKeyName { readonly __type: unique symbol; }
 interface SenderKeyRecord { readonly __type: unique symbol; }
 interface ServerCertificate { readonly __type: unique symbol; }
+interface ServiceIdSet { readonly __type: unique symbol; }
+interface SessionCache { readonly __type: unique symbol; }
 interface SessionRecord { readonly __type: unique symbol; }
+interface Sha256 { readonly __type: unique symbol; }
+interface Sha512 { readonly __type: unique symbol; }
 interface SignalMessage { readonly __type: unique symbol; }
 interface SignedPreKeyRecord { readonly __type: unique symbol; }
+interface StickerCipher { readonly __type: unique symbol; }
+interface StickerDecryption { readonly __type: unique symbol; }
+interface StorageServiceKey { readonly __type: unique symbol; }
 interface UnidentifiedSenderMessageContent { readonly __type: unique symbol; }
@@ -6,12 +6,276 @@
 import * as os from 'os';
 import bindings = require('bindings'); // eslint-disable-line @typescript-eslint/no-require-imports
 import * as Native from './Native';
+import { errorClasses } from './Errors';
+
+export * from './Errors';
 
 const NativeImpl = bindings(
   'libsignal_client_' + os.platform() + '_' + process.arch
 ) as typeof Native;
 
-export const { initLogger, LogLevel } = NativeImpl;
+NativeImpl.registerErrorClasses(errorClasses);
+
+export const {
+  initLogger,
+  LogLevel,
+  setBufferChecksumPolicy,
+  BufferChecksumPolicy,
+  setAuditHook,
+  AuditEventKind,
+} = NativeImpl;
+
+// Forwards spans and events from the protocol code to the logger set up by initLogger. Spans are
+// logged at Debug level, and each store callback at Trace level.
+export function enableTracing(): void {
+  NativeImpl.Tracing_InstallLogSubscriber();
+}
+
+export interface SelfTestCheck {
+  name: string;
+  passed: boolean;
+  durationUs: number;
+  error: string | null;
+}
+
+export interface SelfTestReport {
+  passed: boolean;
+  checks: SelfTestCheck[];
+}
+
+// Runs a fixed set of known-answer checks (curve agreement, AES-GCM, HKDF, and a full session
+// exchange against in-memory stores) to confirm the native library is working.
+export function selfTest(): SelfTestReport {
+  const report = JSON.parse(NativeImpl.Native_SelfTest());
+  return {
+    passed: report.passed,
+    checks: report.checks.map(
+      (check: {
+        name: string;
+        passed: boolean;
+        duration_us: number;
+        error: string | null;
+      }) => ({
+        name: check.name,
+        passed: check.passed,
+        durationUs: check.duration_us,
+        error: check.error,
+      })
+    ),
+  };
+}
+
+export interface NativeFunctionInfo {
+  name: string;
+  args: string[];
+  result: string;
+  isAsync: boolean;
+}
+
+// Lists the entry points exported by the native library, with their argument and result types as
+// declared in Native.d.ts, so that a mismatched build can be detected before calling into it.
+export function listNativeFunctions(): NativeFunctionInfo[] {
+  return JSON.parse(NativeImpl.Native_ListFunctions())
+    .filter((info: { bridge: string }) => info.bridge == 'node')
+    .map(
+      (info: {
+        name: string;
+        args: string[];
+        result: string;
+        async: boolean;
+      }) => ({
+        name: info.name,
+        args: info.args,
+        result: info.result,
+        isAsync: info.async,
+      })
+    );
+}
+
+export interface NativeVersionInfo {
+  version: string;
+  abiRevision: number;
+  features: string[];
+  ciphertextMessageVersion: number;
+  sealedSenderVersion: number;
+}
+
+// Describes the native library, for diagnosing mismatches with this wrapper.
+export function versionInfo(): NativeVersionInfo {
+  const info = JSON.parse(NativeImpl.Native_VersionInfo());
+  return {
+    version: info.version,
+    abiRevision: info.abi_revision,
+    features: info.features,
+    ciphertextMessageVersion: info.ciphertext_message_version,
+    sealedSenderVersion: info.sealed_sender_version,
+  };
+}
+
+// Throws if the native library's ABI revision is older than `abiRevision`.
+export function requireAtLeast(abiRevision: number): void {
+  NativeImpl.Native_RequireAtLeast(abiRevision);
+}
+
+export interface RuntimeOptions {
+  // The most async operations that may run at once, or 0 (the default) for no
+  // limit.
+  maxInFlight?: number;
+  // Whether to queue operations beyond the limit instead of rejecting them.
+  queueWhenBusy?: boolean;
+  // The most operations that may be queued; further operations are rejected.
+  maxQueued?: number;
+}
+
+// Limits how many async operations may run at once, e.g. to save battery. The
+// options replace any set previously; omitted options take their defaults.
+export function initRuntime(options: RuntimeOptions): void {
+  NativeImpl.Native_InitRuntime(
+    options.maxInFlight ?? 0,
+    options.maxQueued ?? 0,
+    options.queueWhenBusy ?? false
+  );
+}
+
+// Stops new async operations from starting and waits up to `timeoutMs` for the
+// ones already running to finish. Resolves to false if they did not finish in
+// time. Call this right before the process exits (such as during Electron
+// teardown); async operations throw once it has been called.
+export function shutdown(timeoutMs: number): Promise<boolean> {
+  return NativeImpl.Native_Shutdown(timeoutMs);
+}
+
+// Sets the longest Buffer, in bytes, that will be accepted as an argument.
+// Longer Buffers are rejected with a BufferTooLargeError. The default is 256 MiB.
+export function setMaxBufferLength(limit: number): void {
+  NativeImpl.Limits_SetMaxBufferLength(limit);
+}
+
+export function getMaxBufferLength(): number {
+  return NativeImpl.Limits_GetMaxBufferLength();
+}
+
+// Turns the process-wide decryption counters on or off. While counting is on,
+// every decryption of an incoming message (1:1, sealed sender, or group) is
+// counted by the class of its result, such as `success`, `untrusted_identity`,
+// `no_session`, or `invalid_mac`. Nothing else about the message is recorded.
+export function setDecryptionMetricsEnabled(enabled: boolean): void {
+  NativeImpl.DecryptionMetrics_SetEnabled(enabled);
+}
+
+// One `name count=N` line per result class, including classes with no
+// decryptions, in a fixed order.
+export function decryptionMetrics(): string {
+  return NativeImpl.DecryptionMetrics_Get();
+}
+
+export function resetDecryptionMetrics(): void {
+  NativeImpl.DecryptionMetrics_Reset();
+}
+
+// Per-account state, for apps that use libsignal on behalf of several accounts
+// at once. While a context is entered (see `withEntered()`), log output is
+// prefixed with its label and filtered by its level, and traced operations are
+// counted in its metrics. Async operations only see the context if it is still
+// entered whenever they run.
+export class BridgeContext {
+  readonly _nativeHandle: Native.BridgeContext;
+
+  private constructor(handle: Native.BridgeContext) {
+    this._nativeHandle = handle;
+  }
+
+  static new(label: string): BridgeContext {
+    return new BridgeContext(NativeImpl.BridgeContext_New(label));
+  }
+
+  // Sets the most verbose level logged while this context is entered; 0 turns
+  // logging off. This can only make logging quieter than initLogger's level.
+  setMaxLogLevel(level: Native.LogLevel | 0): void {
+    NativeImpl.BridgeContext_SetMaxLogLevel(this, level);
+  }
+
+  withEntered<T>(callback: () => T): T {
+    NativeImpl.BridgeContext_Enter(this);
+    try {
+      return callback();
+    } finally {
+      NativeImpl.BridgeContext_Exit(this);
+    }
+  }
+
+  // One `name count=N total_us=T` line per traced operation run while this
+  // context was entered. Only recorded once `enableTracing()` has been called.
+  metrics(): string {
+    return NativeImpl.BridgeContext_GetMetrics(this);
+  }
+
+  resetMetrics(): void {
+    NativeImpl.BridgeContext_ResetMetrics(this);
+  }
+}
+
+// Encodes `data` as base64, with the standard alphabet and padding.
+export function base64Encode(data: Buffer): string {
+  return NativeImpl.Base64_Encode(data);
+}
+
+export function base64Decode(encoded: string): Buffer {
+  return NativeImpl.Base64_Decode(encoded);
+}
+
+export function hexEncode(data: Buffer): string {
+  return NativeImpl.Hex_Encode(data);
+}
+
+export function hexDecode(encoded: string): Buffer {
+  return NativeImpl.Hex_Decode(encoded);
+}
+
+// Encodes data supplied in pieces of any size as base64.
+//
+// Concatenating the results of every `update()` and the final `finalize()` gives
+// the same text as `base64Encode` on the whole input.
+export class Base64Encoder {
+  readonly _nativeHandle: Native.Base64Encoder;
+
+  private constructor(handle: Native.Base64Encoder) {
+    this._nativeHandle = handle;
+  }
+
+  static new(): Base64Encoder {
+    return new Base64Encoder(NativeImpl.Base64Encoder_New());
+  }
+
+  update(data: Buffer): string {
+    return NativeImpl.Base64Encoder_Update(this, data);
+  }
+
+  finalize(): string {
+    return NativeImpl.Base64Encoder_Finalize(this);
+  }
+}
+
+// Decodes base64 text supplied in pieces of any size.
+export class Base64Decoder {
+  readonly _nativeHandle: Native.Base64Decoder;
+
+  private constructor(handle: Native.Base64Decoder) {
+    this._nativeHandle = handle;
+  }
+
+  static new(): Base64Decoder {
+    return new Base64Decoder(NativeImpl.Base64Decoder_New());
+  }
+
+  update(encoded: string): Buffer {
+    return NativeImpl.Base64Decoder_Update(this, encoded);
+  }
+
+  finalize(): Buffer {
+    return NativeImpl.Base64Decoder_Finalize(this);
+  }
+}
 
 export const enum CiphertextMessageType {
   Whisper = 2,
@@ -25,6 +289,13 @@ export const enum Direction {
   Receiving,
 }
 
+/// How a recipient should treat a message it fails to decrypt.
+export const enum ContentHint {
+  Default = 0,
+  Resendable = 1,
+  Implicit = 2,
+}
+
 export class HKDF {
   private readonly version: number;
 
@@ -161,563 +432,1759 @@ export class Aes256GcmSiv {
   }
 }
 
-export class ProtocolAddress {
-  readonly _nativeHandle: Native.ProtocolAddress;
+/// AES key wrap with padding (RFC 5649), for storing keys encrypted under a
+/// 256-bit master key.
+export class Aes256KeyWrap {
+  readonly _nativeHandle: Native.Aes256KeyWrap;
 
-  private constructor(handle: Native.ProtocolAddress) {
+  private constructor(handle: Native.Aes256KeyWrap) {
     this._nativeHandle = handle;
   }
 
-  static _fromNativeHandle(handle: Native.ProtocolAddress): ProtocolAddress {
-    return new ProtocolAddress(handle);
-  }
-
-  static new(name: string, deviceId: number): ProtocolAddress {
-    return new ProtocolAddress(NativeImpl.ProtocolAddress_New(name, deviceId));
+  static new(key: Buffer): Aes256KeyWrap {
+    return new Aes256KeyWrap(NativeImpl.Aes256KeyWrap_New(key));
   }
 
-  name(): string {
-    return NativeImpl.ProtocolAddress_Name(this);
+  /// The result is 8 to 15 bytes longer than the (non-empty) key.
+  wrap(key: Buffer): Buffer {
+    return NativeImpl.Aes256KeyWrap_Wrap(this, key);
   }
 
-  deviceId(): number {
-    return NativeImpl.ProtocolAddress_DeviceId(this);
+  /// Throws `InvalidWrappedKeyError` if the data was wrapped under a different
+  /// key or has been modified.
+  unwrap(wrapped: Buffer): Buffer {
+    return NativeImpl.Aes256KeyWrap_Unwrap(this, wrapped);
   }
 }
 
-export class PublicKey {
-  readonly _nativeHandle: Native.PublicKey;
+/// An account's Storage Service key, from which the key for each manifest and
+/// record is derived.
+///
+/// Manifests and records are encrypted as `nonce || AES-256-GCM(data) || tag`.
+export class StorageServiceKey {
+  readonly _nativeHandle: Native.StorageServiceKey;
 
-  private constructor(handle: Native.PublicKey) {
+  private constructor(handle: Native.StorageServiceKey) {
     this._nativeHandle = handle;
   }
 
-  static _fromNativeHandle(handle: Native.PublicKey): PublicKey {
-    return new PublicKey(handle);
+  /// Wraps an existing 32-byte storage key.
+  static new(storageKey: Buffer): StorageServiceKey {
+    return new StorageServiceKey(NativeImpl.StorageServiceKey_New(storageKey));
   }
 
-  static deserialize(buf: Buffer): PublicKey {
-    return new PublicKey(NativeImpl.PublicKey_Deserialize(buf));
+  /// Derives the storage key from the account's 32-byte master key.
+  static fromMasterKey(masterKey: Buffer): StorageServiceKey {
+    return new StorageServiceKey(
+      NativeImpl.StorageServiceKey_FromMasterKey(masterKey)
+    );
   }
 
-  /// Returns -1, 0, or 1
-  compare(other: PublicKey): number {
-    return NativeImpl.PublicKey_Compare(this, other);
+  storageKey(): Buffer {
+    return NativeImpl.StorageServiceKey_GetStorageKey(this);
   }
 
-  serialize(): Buffer {
-    return NativeImpl.PublicKey_Serialize(this);
+  manifestKey(version: number): Buffer {
+    return NativeImpl.StorageServiceKey_ManifestKey(this, version);
   }
 
-  getPublicKeyBytes(): Buffer {
-    return NativeImpl.PublicKey_GetPublicKeyBytes(this);
+  recordKey(rawId: Buffer): Buffer {
+    return NativeImpl.StorageServiceKey_RecordKey(this, rawId);
   }
 
-  verify(msg: Buffer, sig: Buffer): boolean {
-    return NativeImpl.PublicKey_Verify(this, msg, sig);
+  encryptManifest(version: number, plaintext: Buffer): Buffer {
+    return NativeImpl.StorageServiceKey_EncryptManifest(
+      this,
+      version,
+      plaintext,
+      null
+    );
+  }
+
+  /// Throws `InvalidTagError` if the manifest was encrypted for a different
+  /// version or under a different key, or has been modified.
+  decryptManifest(version: number, ciphertext: Buffer): Buffer {
+    return NativeImpl.StorageServiceKey_DecryptManifest(
+      this,
+      version,
+      ciphertext
+    );
+  }
+
+  encryptRecord(rawId: Buffer, plaintext: Buffer): Buffer {
+    return NativeImpl.StorageServiceKey_EncryptRecord(
+      this,
+      rawId,
+      plaintext,
+      null
+    );
+  }
+
+  /// Throws `InvalidTagError` if the record was encrypted for a different ID or
+  /// under a different key, or has been modified.
+  decryptRecord(rawId: Buffer, ciphertext: Buffer): Buffer {
+    return NativeImpl.StorageServiceKey_DecryptRecord(this, rawId, ciphertext);
   }
 }
 
-export class PrivateKey {
-  readonly _nativeHandle: Native.PrivateKey;
+/// The secret shared by everyone with a call link.
+///
+/// Its text form (`toString()`) is what goes in the link itself.
+export class CallLinkRootKey {
+  readonly _nativeHandle: Native.CallLinkRootKey;
 
-  private constructor(handle: Native.PrivateKey) {
+  private constructor(handle: Native.CallLinkRootKey) {
     this._nativeHandle = handle;
   }
 
-  static _fromNativeHandle(handle: Native.PrivateKey): PrivateKey {
-    return new PrivateKey(handle);
+  static generate(): CallLinkRootKey {
+    return new CallLinkRootKey(NativeImpl.CallLinkRootKey_Generate(null));
   }
 
-  static generate(): PrivateKey {
-    return new PrivateKey(NativeImpl.PrivateKey_Generate());
+  static fromBytes(bytes: Buffer): CallLinkRootKey {
+    return new CallLinkRootKey(NativeImpl.CallLinkRootKey_FromBytes(bytes));
   }
 
-  static deserialize(buf: Buffer): PrivateKey {
-    return new PrivateKey(NativeImpl.PrivateKey_Deserialize(buf));
+  /// Throws `InvalidKeyEncodingError` if `text` isn't a root key in its text
+  /// form.
+  static parse(text: string): CallLinkRootKey {
+    return new CallLinkRootKey(NativeImpl.CallLinkRootKey_Parse(text));
   }
 
-  serialize(): Buffer {
-    return NativeImpl.PrivateKey_Serialize(this);
+  bytes(): Buffer {
+    return NativeImpl.CallLinkRootKey_GetBytes(this);
   }
 
-  sign(msg: Buffer): Buffer {
-    return NativeImpl.PrivateKey_Sign(this, msg);
+  toString(): string {
+    return NativeImpl.CallLinkRootKey_ToString(this);
   }
 
-  agree(other_key: PublicKey): Buffer {
-    return NativeImpl.PrivateKey_Agree(this, other_key);
+  /// The ID the calling server uses for this call link's room.
+  deriveRoomId(): Buffer {
+    return NativeImpl.CallLinkRootKey_DeriveRoomId(this);
   }
 
-  getPublicKey(): PublicKey {
-    return PublicKey._fromNativeHandle(
-      NativeImpl.PrivateKey_GetPublicKey(this)
-    );
+  /// Derives the media key for one era of a call using this call link.
+  deriveEraKey(eraId: string): Buffer {
+    return NativeImpl.CallLinkRootKey_DeriveEraKey(this, eraId);
   }
 }
 
-export class IdentityKeyPair {
-  private readonly publicKey: PublicKey;
-  private readonly privateKey: PrivateKey;
+/// Derives the media key for one era of a group call from the group's 32-byte
+/// master key.
+export function deriveGroupCallEraKey(
+  groupMasterKey: Buffer,
+  eraId: string
+): Buffer {
+  return NativeImpl.GroupCall_DeriveEraKey(groupMasterKey, eraId);
+}
 
-  constructor(publicKey: PublicKey, privateKey: PrivateKey) {
-    this.publicKey = publicKey;
-    this.privateKey = privateKey;
+/// Decrypts the manifest and stickers of a sticker pack, using keys derived from the pack key.
+export class StickerCipher {
+  readonly _nativeHandle: Native.StickerCipher;
+
+  private constructor(handle: Native.StickerCipher) {
+    this._nativeHandle = handle;
   }
 
-  static new(publicKey: PublicKey, privateKey: PrivateKey): IdentityKeyPair {
-    return new IdentityKeyPair(publicKey, privateKey);
+  static new(packKey: Buffer): StickerCipher {
+    return new StickerCipher(NativeImpl.StickerCipher_New(packKey));
   }
 
-  serialize(): Buffer {
-    return NativeImpl.IdentityKeyPair_Serialize(
-      this.publicKey,
-      this.privateKey
+  /// Throws `InvalidTagError` if the data has been tampered with.
+  decrypt(data: Buffer): Buffer {
+    return NativeImpl.StickerCipher_Decrypt(this, data);
+  }
+
+  /// Starts decrypting a manifest or sticker that arrives in pieces.
+  newDecryption(): StickerDecryption {
+    return StickerDecryption._fromNativeHandle(
+      NativeImpl.StickerDecryption_New(this)
     );
   }
 }
 
-export class PreKeyBundle {
-  readonly _nativeHandle: Native.PreKeyBundle;
+/// Decrypts a manifest or sticker supplied in pieces of any size.
+///
+/// Plaintext is returned as soon as it can be decrypted, but it is not authenticated until
+/// `finalize()` succeeds and must be discarded if it throws.
+export class StickerDecryption {
+  readonly _nativeHandle: Native.StickerDecryption;
 
-  private constructor(handle: Native.PreKeyBundle) {
+  private constructor(handle: Native.StickerDecryption) {
     this._nativeHandle = handle;
   }
 
-  static new(
-    registration_id: number,
-    device_id: number,
-    prekey_id: number | null,
-    prekey: PublicKey | null,
-    signed_prekey_id: number,
-    signed_prekey: PublicKey,
-    signed_prekey_signature: Buffer,
-    identity_key: PublicKey
-  ): PreKeyBundle {
-    return new PreKeyBundle(
-      NativeImpl.PreKeyBundle_New(
-        registration_id,
-        device_id,
-        prekey_id,
-        prekey != null ? prekey : null,
-        //prekey?,
-        signed_prekey_id,
-        signed_prekey,
-        signed_prekey_signature,
-        identity_key
-      )
-    );
+  static _fromNativeHandle(
+    handle: Native.StickerDecryption
+  ): StickerDecryption {
+    return new StickerDecryption(handle);
   }
 
-  deviceId(): number {
-    return NativeImpl.PreKeyBundle_GetDeviceId(this);
-  }
-  identityKey(): PublicKey {
-    return PublicKey._fromNativeHandle(
-      NativeImpl.PreKeyBundle_GetIdentityKey(this)
-    );
+  update(data: Buffer): Buffer {
+    return NativeImpl.StickerDecryption_Update(this, data);
   }
-  preKeyId(): number | null {
-    return NativeImpl.PreKeyBundle_GetPreKeyId(this);
+
+  /// Checks the MAC and returns the rest of the plaintext.
+  finalize(): Buffer {
+    return NativeImpl.StickerDecryption_Finalize(this);
   }
-  preKeyPublic(): PublicKey | null {
-    const handle = NativeImpl.PreKeyBundle_GetPreKeyPublic(this);
+}
 
-    if (handle == null) {
-      return null;
-    } else {
-      return PublicKey._fromNativeHandle(handle);
-    }
+/// Checks a download against its chunked HMAC-SHA256 digest as the data arrives.
+///
+/// Data may be passed to `update` in pieces of any size. Only the first `validatedLength()` bytes
+/// are known to be authentic and safe to use; once validation fails, that is the offset of the
+/// chunk that failed.
+export class IncrementalMacValidator {
+  readonly _nativeHandle: Native.IncrementalMacValidator;
+
+  private constructor(handle: Native.IncrementalMacValidator) {
+    this._nativeHandle = handle;
   }
-  registrationId(): number {
-    return NativeImpl.PreKeyBundle_GetRegistrationId(this);
+
+  static new(
+    key: Buffer,
+    chunkSize: number,
+    digest: Buffer
+  ): IncrementalMacValidator {
+    return new IncrementalMacValidator(
+      NativeImpl.IncrementalMacValidator_New(key, chunkSize, digest)
+    );
   }
-  signedPreKeyId(): number {
-    return NativeImpl.PreKeyBundle_GetSignedPreKeyId(this);
+
+  /// Returns false as soon as a completed chunk doesn't match the digest.
+  update(data: Buffer): boolean {
+    return NativeImpl.IncrementalMacValidator_Update(this, data);
   }
-  signedPreKeyPublic(): PublicKey {
-    return PublicKey._fromNativeHandle(
-      NativeImpl.PreKeyBundle_GetSignedPreKeyPublic(this)
-    );
+
+  /// Returns false if the data was truncated or its final chunk doesn't match the digest.
+  finalize(): boolean {
+    return NativeImpl.IncrementalMacValidator_Finalize(this);
   }
-  signedPreKeySignature(): Buffer {
-    return NativeImpl.PreKeyBundle_GetSignedPreKeySignature(this);
+
+  validatedLength(): number {
+    return NativeImpl.IncrementalMacValidator_GetValidatedLength(this);
   }
 }
 
-export class PreKeyRecord {
-  readonly _nativeHandle: Native.PreKeyRecord;
+/// A CRC-32 (IEEE) checksum over data supplied in pieces of any size.
+///
+/// This detects corruption, not tampering.
+export class Crc32 {
+  readonly _nativeHandle: Native.Crc32;
 
-  private constructor(handle: Native.PreKeyRecord) {
+  private constructor(handle: Native.Crc32) {
     this._nativeHandle = handle;
   }
 
-  static _fromNativeHandle(nativeHandle: Native.PreKeyRecord): PreKeyRecord {
-    return new PreKeyRecord(nativeHandle);
+  static new(): Crc32 {
+    return new Crc32(NativeImpl.Crc32_New());
   }
 
-  static new(id: number, pubKey: PublicKey, privKey: PrivateKey): PreKeyRecord {
-    return new PreKeyRecord(NativeImpl.PreKeyRecord_New(id, pubKey, privKey));
+  static digest(data: Buffer): number {
+    return NativeImpl.Crc32_Digest(data);
   }
 
-  static deserialize(buffer: Buffer): PreKeyRecord {
-    return new PreKeyRecord(NativeImpl.PreKeyRecord_Deserialize(buffer));
+  update(data: Buffer): void {
+    NativeImpl.Crc32_Update(this, data);
   }
 
-  id(): number {
-    return NativeImpl.PreKeyRecord_GetId(this);
+  /// Returns the checksum of everything passed to `update`, and starts over.
+  finalize(): number {
+    return NativeImpl.Crc32_Finalize(this);
   }
+}
 
-  privateKey(): PrivateKey {
-    return PrivateKey._fromNativeHandle(
-      NativeImpl.PreKeyRecord_GetPrivateKey(this)
-    );
+/// A SHA-256 digest over data supplied in pieces of any size.
+export class Sha256 {
+  readonly _nativeHandle: Native.Sha256;
+
+  private constructor(handle: Native.Sha256) {
+    this._nativeHandle = handle;
   }
 
-  publicKey(): PublicKey {
-    return PublicKey._fromNativeHandle(
-      NativeImpl.PreKeyRecord_GetPublicKey(this)
-    );
+  static new(): Sha256 {
+    return new Sha256(NativeImpl.Sha256_New());
   }
 
-  serialize(): Buffer {
-    return NativeImpl.PreKeyRecord_Serialize(this);
+  static digest(data: Buffer): Buffer {
+    return NativeImpl.Sha256_Digest(data);
+  }
+
+  update(data: Buffer): void {
+    NativeImpl.Sha256_Update(this, data);
+  }
+
+  /// Returns the digest of everything passed to `update`, and starts over.
+  finalize(): Buffer {
+    return NativeImpl.Sha256_Finalize(this);
   }
 }
 
-export class SignedPreKeyRecord {
-  readonly _nativeHandle: Native.SignedPreKeyRecord;
+/// A SHA-512 digest over data supplied in pieces of any size.
+export class Sha512 {
+  readonly _nativeHandle: Native.Sha512;
 
-  private constructor(handle: Native.SignedPreKeyRecord) {
+  private constructor(handle: Native.Sha512) {
     this._nativeHandle = handle;
   }
 
-  static _fromNativeHandle(
-    nativeHandle: Native.SignedPreKeyRecord
-  ): SignedPreKeyRecord {
-    return new SignedPreKeyRecord(nativeHandle);
+  static new(): Sha512 {
+    return new Sha512(NativeImpl.Sha512_New());
   }
 
-  static new(
-    id: number,
-    timestamp: number,
-    pubKey: PublicKey,
-    privKey: PrivateKey,
-    signature: Buffer
-  ): SignedPreKeyRecord {
-    return new SignedPreKeyRecord(
-      NativeImpl.SignedPreKeyRecord_New(
-        id,
-        timestamp,
-        pubKey,
-        privKey,
-        signature
-      )
-    );
+  static digest(data: Buffer): Buffer {
+    return NativeImpl.Sha512_Digest(data);
   }
 
-  static deserialize(buffer: Buffer): SignedPreKeyRecord {
-    return new SignedPreKeyRecord(
-      NativeImpl.SignedPreKeyRecord_Deserialize(buffer)
-    );
+  update(data: Buffer): void {
+    NativeImpl.Sha512_Update(this, data);
   }
 
-  id(): number {
-    return NativeImpl.SignedPreKeyRecord_GetId(this);
+  /// Returns the digest of everything passed to `update`, and starts over.
+  finalize(): Buffer {
+    return NativeImpl.Sha512_Finalize(this);
   }
+}
 
-  privateKey(): PrivateKey {
-    return PrivateKey._fromNativeHandle(
-      NativeImpl.SignedPreKeyRecord_GetPrivateKey(this)
+/// Parameters for Argon2id (version 1.3), for deriving keys from PINs and
+/// other low-entropy secrets.
+///
+/// A key can only be derived again with exactly the same parameters, so store
+/// them alongside the salt.
+export class Argon2idParams {
+  readonly _nativeHandle: Native.Argon2idParams;
+
+  private constructor(handle: Native.Argon2idParams) {
+    this._nativeHandle = handle;
+  }
+
+  static new(
+    memoryKib: number,
+    iterations: number,
+    parallelism: number
+  ): Argon2idParams {
+    return new Argon2idParams(
+      NativeImpl.Argon2idParams_New(memoryKib, iterations, parallelism)
     );
   }
 
-  publicKey(): PublicKey {
-    return PublicKey._fromNativeHandle(
-      NativeImpl.SignedPreKeyRecord_GetPublicKey(this)
+  /// Runs the KDF once to pick the iterations that take about
+  /// `targetDurationMs` on this device.
+  static calibrate(
+    targetDurationMs: number,
+    memoryKib: number,
+    parallelism: number
+  ): Argon2idParams {
+    return new Argon2idParams(
+      NativeImpl.Argon2idParams_Calibrate(
+        targetDurationMs,
+        memoryKib,
+        parallelism
+      )
     );
   }
 
-  serialize(): Buffer {
-    return NativeImpl.SignedPreKeyRecord_Serialize(this);
+  memoryKib(): number {
+    return NativeImpl.Argon2idParams_GetMemoryKib(this);
   }
 
-  signature(): Buffer {
-    return NativeImpl.SignedPreKeyRecord_GetSignature(this);
+  iterations(): number {
+    return NativeImpl.Argon2idParams_GetIterations(this);
   }
 
-  timestamp(): number {
-    return NativeImpl.SignedPreKeyRecord_GetTimestamp(this);
+  parallelism(): number {
+    return NativeImpl.Argon2idParams_GetParallelism(this);
+  }
+
+  derive(password: Buffer, salt: Buffer, outputLength: number): Buffer {
+    return NativeImpl.Argon2idParams_Derive(
+      this,
+      password,
+      salt,
+      outputLength
+    );
   }
 }
 
-export class SignalMessage {
-  readonly _nativeHandle: Native.SignalMessage;
+/// Parameters for scrypt, for deriving keys from PINs and other low-entropy
+/// secrets.
+///
+/// A key can only be derived again with exactly the same parameters, so store
+/// them alongside the salt.
+export class ScryptParams {
+  readonly _nativeHandle: Native.ScryptParams;
 
-  private constructor(handle: Native.SignalMessage) {
+  private constructor(handle: Native.ScryptParams) {
     this._nativeHandle = handle;
   }
 
-  static new(
-    messageVersion: number,
-    macKey: Buffer,
-    senderRatchetKey: PublicKey,
-    counter: number,
-    previousCounter: number,
-    ciphertext: Buffer,
-    senderIdentityKey: PublicKey,
-    receiverIdentityKey: PublicKey
-  ): SignalMessage {
-    return new SignalMessage(
-      NativeImpl.SignalMessage_New(
-        messageVersion,
-        macKey,
-        senderRatchetKey,
-        counter,
-        previousCounter,
-        ciphertext,
-        senderIdentityKey,
-        receiverIdentityKey
-      )
-    );
-  }
-
-  static deserialize(buffer: Buffer): SignalMessage {
-    return new SignalMessage(NativeImpl.SignalMessage_Deserialize(buffer));
+  /// Creates parameters for a cost of `N = 2^logN`, block size `r`, and
+  /// parallelism `p`.
+  static new(logN: number, r: number, p: number): ScryptParams {
+    return new ScryptParams(NativeImpl.ScryptParams_New(logN, r, p));
   }
 
-  body(): Buffer {
-    return NativeImpl.SignalMessage_GetBody(this);
+  /// Runs the KDF once to pick the cost that takes about `targetDurationMs` on
+  /// this device.
+  static calibrate(
+    targetDurationMs: number,
+    r: number,
+    p: number
+  ): ScryptParams {
+    return new ScryptParams(
+      NativeImpl.ScryptParams_Calibrate(targetDurationMs, r, p)
+    );
   }
 
-  counter(): number {
-    return NativeImpl.SignalMessage_GetCounter(this);
+  logN(): number {
+    return NativeImpl.ScryptParams_GetLogN(this);
   }
 
-  messageVersion(): number {
-    return NativeImpl.SignalMessage_GetMessageVersion(this);
+  r(): number {
+    return NativeImpl.ScryptParams_GetR(this);
   }
 
-  serialize(): Buffer {
-    return NativeImpl.SignalMessage_GetSerialized(this);
+  p(): number {
+    return NativeImpl.ScryptParams_GetP(this);
   }
 
-  verifyMac(
-    senderIdentityKey: PublicKey,
-    recevierIdentityKey: PublicKey,
-    macKey: Buffer
-  ): boolean {
-    return NativeImpl.SignalMessage_VerifyMac(
-      this,
-      senderIdentityKey,
-      recevierIdentityKey,
-      macKey
-    );
+  derive(password: Buffer, salt: Buffer, outputLength: number): Buffer {
+    return NativeImpl.ScryptParams_Derive(this, password, salt, outputLength);
   }
 }
 
-export class PreKeySignalMessage {
-  readonly _nativeHandle: Native.PreKeySignalMessage;
+/// Encrypts frames into a message backup stream.
+///
+/// Each call returns the bytes to append to the stream; the first one also carries the stream
+/// header. The stream is incomplete until the output of `finish()` has been written.
+export class BackupFrameWriter {
+  readonly _nativeHandle: Native.FrameWriter;
 
-  private constructor(handle: Native.PreKeySignalMessage) {
+  private constructor(handle: Native.FrameWriter) {
     this._nativeHandle = handle;
   }
 
-  static new(
-    messageVersion: number,
-    registrationId: number,
-    preKeyId: number | null,
-    signedPreKeyId: number,
-    baseKey: PublicKey,
-    identityKey: PublicKey,
-    signalMessage: SignalMessage
-  ): PreKeySignalMessage {
-    return new PreKeySignalMessage(
-      NativeImpl.PreKeySignalMessage_New(
-        messageVersion,
-        registrationId,
-        preKeyId,
-        signedPreKeyId,
-        baseKey,
-        identityKey,
-        signalMessage
-      )
+  static new(backupKey: Buffer, compressed: boolean, rng?: Rng): BackupFrameWriter {
+    return new BackupFrameWriter(
+      NativeImpl.FrameWriter_New(backupKey, compressed, rng ?? null)
     );
   }
 
-  static deserialize(buffer: Buffer): PreKeySignalMessage {
-    return new PreKeySignalMessage(
-      NativeImpl.PreKeySignalMessage_Deserialize(buffer)
-    );
+  append(frame: Buffer): Buffer {
+    return NativeImpl.FrameWriter_Append(this, frame);
   }
 
-  preKeyId(): number | null {
-    return NativeImpl.PreKeySignalMessage_GetPreKeyId(this);
+  finish(): Buffer {
+    return NativeImpl.FrameWriter_Finish(this);
+  }
+}
+
+/// Decrypts frames from a message backup stream that arrives in chunks of any size.
+///
+/// Once a frame fails to decrypt, every later call throws the same error.
+export class BackupFrameReader {
+  readonly _nativeHandle: Native.FrameReader;
+
+  private constructor(handle: Native.FrameReader) {
+    this._nativeHandle = handle;
   }
 
-  registrationId(): number {
-    return NativeImpl.PreKeySignalMessage_GetRegistrationId(this);
+  static new(backupKey: Buffer): BackupFrameReader {
+    return new BackupFrameReader(NativeImpl.FrameReader_New(backupKey));
   }
 
-  signedPreKeyId(): number {
-    return NativeImpl.PreKeySignalMessage_GetSignedPreKeyId(this);
+  push(chunk: Buffer): void {
+    NativeImpl.FrameReader_Push(this, chunk);
   }
 
-  version(): number {
-    return NativeImpl.PreKeySignalMessage_GetVersion(this);
+  /// Returns the next complete frame, or null if more input is needed or the stream has ended.
+  nextFrame(): Buffer | null {
+    return NativeImpl.FrameReader_NextFrame(this);
   }
 
-  serialize(): Buffer {
-    return NativeImpl.PreKeySignalMessage_Serialize(this);
+  isFinished(): boolean {
+    return NativeImpl.FrameReader_IsFinished(this);
+  }
+
+  /// Throws `BackupTruncatedError` if the stream ended early.
+  finish(): void {
+    NativeImpl.FrameReader_Finish(this);
   }
 }
 
-export class SessionRecord {
-  readonly _nativeHandle: Native.SessionRecord;
+export interface BackupAccountData {
+  profileKey: Buffer;
+  username?: string;
+  givenName: string;
+  familyName: string;
+}
 
-  private constructor(nativeHandle: Native.SessionRecord) {
-    this._nativeHandle = nativeHandle;
-  }
+/// Exactly one of `self`, `group`, or a contact's `uuid`/`e164` must be given.
+export type BackupRecipient =
+  | { id: number; self: true }
+  | { id: number; uuid?: string; e164?: string }
+  | { id: number; group: { masterKey: Buffer } };
+
+export interface BackupChat {
+  id: number;
+  recipientId: number;
+  archived?: boolean;
+  /// Omitted or 0 if the chat is not pinned.
+  pinnedOrder?: number;
+  /// Omitted or 0 if messages don't expire.
+  expirationTimerMs?: number;
+}
 
-  static _fromNativeHandle(nativeHandle: Native.SessionRecord): SessionRecord {
-    return new SessionRecord(nativeHandle);
+export interface BackupChatItem {
+  chatId: number;
+  authorId: number;
+  dateSent: number;
+  text: string;
+}
+
+/// Produces a complete, encrypted message backup from structured records.
+///
+/// Records must be added in dependency order: the account data first, then each recipient before
+/// any chat with it, and each chat before its items. Each call returns the bytes to append to the
+/// backup; the backup is complete once the output of `finish()` has been written.
+///
+/// A record that is malformed or out of order is rejected with `InvalidBackupRecordError` without
+/// affecting the backup, so the caller may skip it and carry on.
+export class BackupBuilder {
+  readonly _nativeHandle: Native.BackupBuilder;
+
+  private constructor(handle: Native.BackupBuilder) {
+    this._nativeHandle = handle;
   }
 
-  static deserialize(buffer: Buffer): SessionRecord {
-    return new SessionRecord(NativeImpl.SessionRecord_Deserialize(buffer));
+  static new(
+    backupKey: Buffer,
+    compressed: boolean,
+    backupTime: Date,
+    rng?: Rng
+  ): BackupBuilder {
+    return new BackupBuilder(
+      NativeImpl.BackupBuilder_New(
+        backupKey,
+        compressed,
+        backupTime.getTime(),
+        rng ?? null
+      )
+    );
   }
 
-  serialize(): Buffer {
-    return NativeImpl.SessionRecord_Serialize(this);
+  addAccountData(account: BackupAccountData): Buffer {
+    return NativeImpl.BackupBuilder_AddAccountData(
+      this,
+      account.profileKey,
+      account.username ?? null,
+      account.givenName,
+      account.familyName
+    );
   }
 
-  archiveCurrentState(): void {
-    NativeImpl.SessionRecord_ArchiveCurrentState(this);
+  addRecipient(recipient: BackupRecipient): Buffer {
+    if ('self' in recipient) {
+      return NativeImpl.BackupBuilder_AddSelfRecipient(this, recipient.id);
+    }
+    if ('group' in recipient) {
+      return NativeImpl.BackupBuilder_AddGroup(
+        this,
+        recipient.id,
+        recipient.group.masterKey
+      );
+    }
+    return NativeImpl.BackupBuilder_AddContact(
+      this,
+      recipient.id,
+      recipient.uuid ?? null,
+      recipient.e164 ?? null
+    );
   }
 
-  localRegistrationId(): number {
-    return NativeImpl.SessionRecord_GetLocalRegistrationId(this);
+  addChat(chat: BackupChat): Buffer {
+    return NativeImpl.BackupBuilder_AddChat(
+      this,
+      chat.id,
+      chat.recipientId,
+      chat.archived ?? false,
+      chat.pinnedOrder ?? 0,
+      chat.expirationTimerMs ?? 0
+    );
   }
 
-  remoteRegistrationId(): number {
-    return NativeImpl.SessionRecord_GetRemoteRegistrationId(this);
+  addChatItem(item: BackupChatItem): Buffer {
+    return NativeImpl.BackupBuilder_AddChatItem(
+      this,
+      item.chatId,
+      item.authorId,
+      item.dateSent,
+      item.text
+    );
   }
 
-  hasCurrentState(): boolean {
-    return NativeImpl.SessionRecord_HasCurrentState(this);
+  finish(): Buffer {
+    return NativeImpl.BackupBuilder_Finish(this);
   }
 }
 
-export class SenderKeyName {
-  readonly _nativeHandle: Native.SenderKeyName;
-
-  private constructor(nativeHandle: Native.SenderKeyName) {
-    this._nativeHandle = nativeHandle;
-  }
+export class ProtocolAddress {
+  readonly _nativeHandle: Native.ProtocolAddress;
 
-  static _fromNativeHandle(nativeHandle: Native.SenderKeyName): SenderKeyName {
-    return new SenderKeyName(nativeHandle);
+  private constructor(handle: Native.ProtocolAddress) {
+    this._nativeHandle = handle;
   }
 
-  static new(
-    groupId: string,
-    senderName: string,
-    senderDeviceId: number
-  ): SenderKeyName {
-    return new SenderKeyName(
-      NativeImpl.SenderKeyName_New(groupId, senderName, senderDeviceId)
-    );
+  static _fromNativeHandle(handle: Native.ProtocolAddress): ProtocolAddress {
+    return new ProtocolAddress(handle);
   }
 
-  groupId(): string {
-    return NativeImpl.SenderKeyName_GetGroupId(this);
+  static new(name: string, deviceId: number): ProtocolAddress {
+    return new ProtocolAddress(NativeImpl.ProtocolAddress_New(name, deviceId));
   }
 
-  senderName(): string {
-    return NativeImpl.SenderKeyName_GetSenderName(this);
+  name(): string {
+    return NativeImpl.ProtocolAddress_Name(this);
   }
 
-  senderDeviceId(): number {
-    return NativeImpl.SenderKeyName_GetSenderDeviceId(this);
+  deviceId(): number {
+    return NativeImpl.ProtocolAddress_DeviceId(this);
   }
 }
 
-export class ServerCertificate {
-  readonly _nativeHandle: Native.ServerCertificate;
+/// A sorted set of service IDs, kept in native memory so that large sets (such
+/// as contact discovery results) can be compared without copying them back and
+/// forth.
+///
+/// IDs are passed in their fixed-width binary form: a kind byte (0 for an ACI, 1
+/// for a PNI) followed by the 16 bytes of the UUID. Lists of IDs are these
+/// 17-byte entries concatenated.
+export class ServiceIdSet {
+  static readonly FIXED_WIDTH_LENGTH = 17;
 
-  static _fromNativeHandle(
-    nativeHandle: Native.ServerCertificate
-  ): ServerCertificate {
-    return new ServerCertificate(nativeHandle);
-  }
+  readonly _nativeHandle: Native.ServiceIdSet;
 
-  private constructor(nativeHandle: Native.ServerCertificate) {
-    this._nativeHandle = nativeHandle;
+  private constructor(handle: Native.ServiceIdSet) {
+    this._nativeHandle = handle;
   }
 
-  static new(
-    keyId: number,
-    serverKey: PublicKey,
-    trustRoot: PrivateKey
-  ): ServerCertificate {
-    return new ServerCertificate(
-      NativeImpl.ServerCertificate_New(keyId, serverKey, trustRoot)
-    );
+  static new(): ServiceIdSet {
+    return new ServiceIdSet(NativeImpl.ServiceIdSet_New());
   }
 
-  static deserialize(buffer: Buffer): ServerCertificate {
-    return new ServerCertificate(
-      NativeImpl.ServerCertificate_Deserialize(buffer)
-    );
+  /// Adds a list of fixed-width binary IDs. Nothing is added if any entry is
+  /// invalid.
+  add(fixedWidthBinaryList: Buffer): void {
+    NativeImpl.ServiceIdSet_AddFixedWidthBinary(this, fixedWidthBinaryList);
   }
 
-  certificateData(): Buffer {
-    return NativeImpl.ServerCertificate_GetCertificate(this);
+  size(): number {
+    return NativeImpl.ServiceIdSet_Len(this);
   }
 
-  key(): PublicKey {
-    return PublicKey._fromNativeHandle(
-      NativeImpl.ServerCertificate_GetKey(this)
-    );
+  has(fixedWidthBinaryServiceId: Buffer): boolean {
+    return NativeImpl.ServiceIdSet_Contains(this, fixedWidthBinaryServiceId);
   }
 
-  keyId(): number {
-    return NativeImpl.ServerCertificate_GetKeyId(this);
+  intersection(other: ServiceIdSet): ServiceIdSet {
+    return new ServiceIdSet(NativeImpl.ServiceIdSet_Intersection(this, other));
   }
 
-  serialize(): Buffer {
-    return NativeImpl.ServerCertificate_GetSerialized(this);
+  /// The IDs in this set that are not in `other`.
+  difference(other: ServiceIdSet): ServiceIdSet {
+    return new ServiceIdSet(NativeImpl.ServiceIdSet_Difference(this, other));
   }
 
-  signature(): Buffer {
-    return NativeImpl.ServerCertificate_GetSignature(this);
+  /// Up to `limit` IDs in fixed-width binary form, in sorted order, skipping the
+  /// first `offset`.
+  getPage(offset: number, limit: number): Buffer {
+    return NativeImpl.ServiceIdSet_GetPage(this, offset, limit);
   }
 }
 
-export class SenderKeyRecord {
-  readonly _nativeHandle: Native.SenderKeyRecord;
+/// A source of randomness for APIs that generate keys or signatures.
+///
+/// Functions that take an optional `rng` use the system RNG when it is omitted.
+export class Rng {
+  readonly _nativeHandle: Native.Rng;
 
-  static _fromNativeHandle(
-    nativeHandle: Native.SenderKeyRecord
-  ): SenderKeyRecord {
-    return new SenderKeyRecord(nativeHandle);
+  private constructor(handle: Native.Rng) {
+    this._nativeHandle = handle;
   }
 
-  private constructor(nativeHandle: Native.SenderKeyRecord) {
-    this._nativeHandle = nativeHandle;
+  static system(): Rng {
+    return new Rng(NativeImpl.Rng_NewSystem());
   }
 
-  static new(): SenderKeyRecord {
-    return new SenderKeyRecord(NativeImpl.SenderKeyRecord_New());
+  /// Produces a deterministic sequence from a 32-byte seed. For testing only.
+  static seeded(seed: Buffer): Rng {
+    return new Rng(NativeImpl.Rng_NewSeeded(seed));
+  }
+
+  /// Returns another reference to the same underlying generator, which can be used and released
+  /// independently of this one.
+  clone(): Rng {
+    return new Rng(NativeImpl.Rng_Clone(this));
+  }
+
+  /// Prepares this generator to be used from another JavaScript context, such as a worker thread.
+  /// Post the returned token there and pass it to `Rng.receiveTransferred`. Like `clone`, the
+  /// result refers to the same underlying generator. The token expires if it isn't received within
+  /// a minute.
+  transferToWorker(): Native.Transferred<Native.Rng> {
+    return NativeImpl.Rng_TransferToWorker(this);
+  }
+
+  static receiveTransferred(token: Native.Transferred<Native.Rng>): Rng {
+    return new Rng(NativeImpl.ReceiveTransferredHandle(token));
+  }
+}
+
+export class PublicKey {
+  readonly _nativeHandle: Native.PublicKey;
+
+  private constructor(handle: Native.PublicKey) {
+    this._nativeHandle = handle;
+  }
+
+  static _fromNativeHandle(handle: Native.PublicKey): PublicKey {
+    return new PublicKey(handle);
+  }
+
+  static deserialize(buf: Buffer): PublicKey {
+    return new PublicKey(NativeImpl.PublicKey_Deserialize(buf));
+  }
+
+  /// Returns -1, 0, or 1
+  compare(other: PublicKey): number {
+    return NativeImpl.PublicKey_Compare(this, other);
+  }
+
+  serialize(): Buffer {
+    return NativeImpl.PublicKey_Serialize(this);
+  }
+
+  getPublicKeyBytes(): Buffer {
+    return NativeImpl.PublicKey_GetPublicKeyBytes(this);
+  }
+
+  verify(msg: Buffer, sig: Buffer): boolean {
+    return NativeImpl.PublicKey_Verify(this, msg, sig);
+  }
+
+  /// Encrypts a message that only the holder of the matching private key can
+  /// read, without identifying the sender. See `PrivateKey.open`.
+  seal(msg: Buffer, rng?: Rng): Buffer {
+    return NativeImpl.PublicKey_Seal(this, msg, rng ?? null);
+  }
+
+  /// Copies this key for use in another JavaScript context, such as a worker thread. Post the
+  /// returned token there and pass it to `PublicKey.receiveTransferred`
+  /// within a minute, after which it expires.
+  transferToWorker(): Native.Transferred<Native.PublicKey> {
+    return NativeImpl.PublicKey_TransferToWorker(this);
+  }
+
+  static receiveTransferred(
+    token: Native.Transferred<Native.PublicKey>
+  ): PublicKey {
+    return new PublicKey(NativeImpl.ReceiveTransferredHandle(token));
+  }
+}
+
+export class PrivateKey {
+  readonly _nativeHandle: Native.PrivateKey;
+
+  private constructor(handle: Native.PrivateKey) {
+    this._nativeHandle = handle;
+  }
+
+  static _fromNativeHandle(handle: Native.PrivateKey): PrivateKey {
+    return new PrivateKey(handle);
+  }
+
+  static generate(rng?: Rng): PrivateKey {
+    return new PrivateKey(NativeImpl.PrivateKey_Generate(rng ?? null));
+  }
+
+  static deserialize(buf: Buffer): PrivateKey {
+    return new PrivateKey(NativeImpl.PrivateKey_Deserialize(buf));
+  }
+
+  serialize(): Buffer {
+    return NativeImpl.PrivateKey_Serialize(this);
+  }
+
+  sign(msg: Buffer, rng?: Rng): Buffer {
+    return NativeImpl.PrivateKey_Sign(this, msg, rng ?? null);
+  }
+
+  agree(other_key: PublicKey): Buffer {
+    return NativeImpl.PrivateKey_Agree(this, other_key);
+  }
+
+  /// Decrypts a message from `PublicKey.seal`. Throws if the message was sealed
+  /// for a different key or has been modified.
+  open(sealed: Buffer): Buffer {
+    return NativeImpl.PrivateKey_Open(this, sealed);
+  }
+
+  getPublicKey(): PublicKey {
+    return PublicKey._fromNativeHandle(
+      NativeImpl.PrivateKey_GetPublicKey(this)
+    );
+  }
+
+  /// Copies this key for use in another JavaScript context, such as a worker thread. Post the
+  /// returned token there and pass it to `PrivateKey.receiveTransferred`
+  /// within a minute, after which it expires.
+  transferToWorker(): Native.Transferred<Native.PrivateKey> {
+    return NativeImpl.PrivateKey_TransferToWorker(this);
+  }
+
+  static receiveTransferred(
+    token: Native.Transferred<Native.PrivateKey>
+  ): PrivateKey {
+    return new PrivateKey(NativeImpl.ReceiveTransferredHandle(token));
+  }
+}
+
+export class IdentityKeyPair {
+  private readonly publicKey: PublicKey;
+  private readonly privateKey: PrivateKey;
+
+  constructor(publicKey: PublicKey, privateKey: PrivateKey) {
+    this.publicKey = publicKey;
+    this.privateKey = privateKey;
+  }
+
+  static new(publicKey: PublicKey, privateKey: PrivateKey): IdentityKeyPair {
+    return new IdentityKeyPair(publicKey, privateKey);
+  }
+
+  serialize(): Buffer {
+    return NativeImpl.IdentityKeyPair_Serialize(
+      this.publicKey,
+      this.privateKey
+    );
+  }
+
+  /// Signs a payment address to be published in this account's profile.
+  signPaymentAddress(address: Buffer, rng?: Rng): Buffer {
+    return NativeImpl.PaymentAddress_Sign(
+      this.publicKey,
+      this.privateKey,
+      address,
+      rng ?? null
+    );
+  }
+}
+
+/// Checks that a payment address from a profile was signed by the account's identity key.
+export function verifyPaymentAddress(
+  identityKey: PublicKey,
+  address: Buffer,
+  signature: Buffer
+): boolean {
+  return NativeImpl.PaymentAddress_Verify(identityKey, address, signature);
+}
+
+/// The linked device's side of device provisioning.
+///
+/// The linked device shows `publicKey()` to the primary device, which encrypts the provisioning
+/// message with `ProvisioningCipher.encrypt`. The cipher should be discarded once that message has
+/// been decrypted.
+export class ProvisioningCipher {
+  readonly _nativeHandle: Native.ProvisioningCipher;
+
+  private constructor(nativeHandle: Native.ProvisioningCipher) {
+    this._nativeHandle = nativeHandle;
+  }
+
+  static new(rng?: Rng): ProvisioningCipher {
+    return new ProvisioningCipher(
+      NativeImpl.ProvisioningCipher_New(rng ?? null)
+    );
+  }
+
+  /// Encrypts `message` for the linked device that showed `theirPublicKey`, returning a serialized
+  /// ProvisionEnvelope.
+  static encrypt(
+    theirPublicKey: PublicKey,
+    message: Buffer,
+    rng?: Rng
+  ): Buffer {
+    return NativeImpl.ProvisioningCipher_Encrypt(
+      theirPublicKey,
+      message,
+      rng ?? null
+    );
+  }
+
+  publicKey(): PublicKey {
+    return PublicKey._fromNativeHandle(
+      NativeImpl.ProvisioningCipher_GetPublicKey(this)
+    );
+  }
+
+  decrypt(envelope: Buffer): Buffer {
+    return NativeImpl.ProvisioningCipher_Decrypt(this, envelope);
+  }
+}
+
+/// The first message the server sends on a provisioning connection.
+export class ProvisioningUuid {
+  readonly _nativeHandle: Native.ProvisioningUuid;
+
+  private constructor(nativeHandle: Native.ProvisioningUuid) {
+    this._nativeHandle = nativeHandle;
+  }
+
+  static new(uuid: string): ProvisioningUuid {
+    return new ProvisioningUuid(NativeImpl.ProvisioningUuid_New(uuid));
+  }
+
+  static deserialize(buffer: Buffer): ProvisioningUuid {
+    return new ProvisioningUuid(
+      NativeImpl.ProvisioningUuid_Deserialize(buffer)
+    );
+  }
+
+  uuid(): string {
+    return NativeImpl.ProvisioningUuid_GetUuid(this);
+  }
+
+  serialize(): Buffer {
+    return NativeImpl.ProvisioningUuid_GetSerialized(this);
+  }
+}
+
+/// An already-encrypted message waiting to be sent, normally a sealed sender
+/// message.
+///
+/// Serialize queued envelopes to keep them across restarts; the format is shared
+/// by every platform.
+export class OutgoingEnvelope {
+  readonly _nativeHandle: Native.OutgoingEnvelope;
+
+  private constructor(nativeHandle: Native.OutgoingEnvelope) {
+    this._nativeHandle = nativeHandle;
+  }
+
+  static new(
+    destination: ProtocolAddress,
+    timestamp: number,
+    contentHint: ContentHint,
+    payload: Buffer
+  ): OutgoingEnvelope {
+    return new OutgoingEnvelope(
+      NativeImpl.OutgoingEnvelope_New(
+        destination,
+        timestamp,
+        contentHint,
+        payload
+      )
+    );
+  }
+
+  static deserialize(buffer: Buffer): OutgoingEnvelope {
+    return new OutgoingEnvelope(NativeImpl.OutgoingEnvelope_Deserialize(buffer));
+  }
+
+  serialize(): Buffer {
+    return NativeImpl.OutgoingEnvelope_Serialize(this);
+  }
+
+  destination(): ProtocolAddress {
+    return ProtocolAddress._fromNativeHandle(
+      NativeImpl.OutgoingEnvelope_GetDestination(this)
+    );
+  }
+
+  timestamp(): number {
+    return NativeImpl.OutgoingEnvelope_GetTimestamp(this);
+  }
+
+  contentHint(): ContentHint {
+    return NativeImpl.OutgoingEnvelope_GetContentHint(this);
+  }
+
+  payload(): Buffer {
+    return NativeImpl.OutgoingEnvelope_GetPayload(this);
+  }
+
+  /// The format version of the data this envelope was deserialized from.
+  serializedVersion(): number {
+    return NativeImpl.OutgoingEnvelope_GetSerializedVersion(this);
+  }
+}
+
+/// How far to pad a plaintext before encrypting it, so that ciphertext lengths
+/// reveal less.
+///
+/// A padded message is the original message, a 0x80 terminator byte, and zeros,
+/// so any policy other than `none()` can remove padding added under any other.
+export class PaddingPolicy {
+  readonly _nativeHandle: Native.PaddingPolicy;
+
+  private constructor(nativeHandle: Native.PaddingPolicy) {
+    this._nativeHandle = nativeHandle;
+  }
+
+  /// Messages are sent as-is.
+  static none(): PaddingPolicy {
+    return new PaddingPolicy(NativeImpl.PaddingPolicy_None());
+  }
+
+  /// Messages are padded up to the next multiple of `bucketSize`.
+  static bucketed(bucketSize: number): PaddingPolicy {
+    return new PaddingPolicy(NativeImpl.PaddingPolicy_Bucketed(bucketSize));
+  }
+
+  /// Messages are padded with the Padmé scheme, which adds at most about 12%.
+  static padme(): PaddingPolicy {
+    return new PaddingPolicy(NativeImpl.PaddingPolicy_Padme());
+  }
+
+  paddedLength(length: number): number {
+    return NativeImpl.PaddingPolicy_PaddedLength(this, length);
+  }
+
+  pad(message: Buffer): Buffer {
+    return NativeImpl.PaddingPolicy_Pad(this, message);
+  }
+
+  unpad(paddedMessage: Buffer): Buffer {
+    return NativeImpl.PaddingPolicy_Unpad(this, paddedMessage);
+  }
+}
+
+/// A reference to an encrypted attachment that has been uploaded to a CDN.
+export class AttachmentPointer {
+  readonly _nativeHandle: Native.AttachmentPointer;
+
+  private constructor(handle: Native.AttachmentPointer) {
+    this._nativeHandle = handle;
+  }
+
+  /// `key` is the attachment's 64-byte AES and HMAC key, and `digest` the
+  /// SHA-256 of the uploaded ciphertext; `size` is the length of the plaintext.
+  static new(
+    cdnNumber: number,
+    cdnKey: string,
+    contentType: string,
+    key: Buffer,
+    digest: Buffer,
+    size: number,
+    options?: {
+      fileName?: string;
+      caption?: string;
+      width?: number;
+      height?: number;
+      uploadTimestamp?: number;
+    }
+  ): AttachmentPointer {
+    return new AttachmentPointer(
+      NativeImpl.AttachmentPointer_New(
+        cdnNumber,
+        cdnKey,
+        contentType,
+        key,
+        digest,
+        size,
+        options?.fileName ?? null,
+        options?.caption ?? null,
+        options?.width ?? 0,
+        options?.height ?? 0,
+        options?.uploadTimestamp ?? 0
+      )
+    );
+  }
+}
+
+/// Assembles a data message, checking each part as it is added.
+export class DataMessageBuilder {
+  readonly _nativeHandle: Native.DataMessageBuilder;
+
+  private constructor(handle: Native.DataMessageBuilder) {
+    this._nativeHandle = handle;
+  }
+
+  static new(timestamp: number): DataMessageBuilder {
+    return new DataMessageBuilder(NativeImpl.DataMessageBuilder_New(timestamp));
+  }
+
+  /// Throws if `body` is longer than the limit for inline text.
+  setBody(body: string): this {
+    NativeImpl.DataMessageBuilder_SetBody(this, body);
+    return this;
+  }
+
+  addAttachment(attachment: AttachmentPointer): this {
+    NativeImpl.DataMessageBuilder_AddAttachment(this, attachment);
+    return this;
+  }
+
+  /// Quotes the message sent at `id` (its timestamp) by `authorUuid`.
+  setQuote(id: number, authorUuid: string, text?: string): this {
+    NativeImpl.DataMessageBuilder_SetQuote(this, id, authorUuid, text ?? null);
+    return this;
+  }
+
+  /// The disappearing message timer, in seconds; 0 turns it off.
+  setExpireTimer(seconds: number): this {
+    NativeImpl.DataMessageBuilder_SetExpireTimer(this, seconds);
+    return this;
+  }
+
+  setProfileKey(profileKey: Buffer): this {
+    NativeImpl.DataMessageBuilder_SetProfileKey(this, profileKey);
+    return this;
+  }
+
+  /// Throws if the message has neither a body nor any attachments.
+  build(): Content {
+    return Content._fromNativeHandle(NativeImpl.DataMessageBuilder_Build(this));
+  }
+}
+
+export const enum ReceiptType {
+  Delivery = 0,
+  Read = 1,
+  Viewed = 2,
+}
+
+/// A message ready to be padded and encrypted with `signalEncryptContent`.
+export class Content {
+  readonly _nativeHandle: Native.Content;
+
+  private constructor(handle: Native.Content) {
+    this._nativeHandle = handle;
+  }
+
+  static _fromNativeHandle(handle: Native.Content): Content {
+    return new Content(handle);
+  }
+
+  /// Acknowledges the messages sent at each of `timestamps`.
+  static receipt(type: ReceiptType, timestamps: number[]): Content {
+    const packed = Buffer.alloc(timestamps.length * 8);
+    timestamps.forEach((timestamp, i) => {
+      packed.writeUInt32BE(Math.floor(timestamp / 0x100000000), i * 8);
+      packed.writeUInt32BE(timestamp % 0x100000000, i * 8 + 4);
+    });
+    return new Content(NativeImpl.Content_NewReceiptMessage(type, packed));
+  }
+
+  /// A typing indicator, in the group with ID `groupId` if given.
+  static typing(
+    timestamp: number,
+    started: boolean,
+    groupId?: Buffer
+  ): Content {
+    return new Content(
+      NativeImpl.Content_NewTypingMessage(
+        timestamp,
+        started,
+        groupId ?? Buffer.alloc(0)
+      )
+    );
+  }
+
+  serialize(): Buffer {
+    return NativeImpl.Content_GetSerialized(this);
+  }
+}
+
+export class PreKeyBundle {
+  readonly _nativeHandle: Native.PreKeyBundle;
+
+  private constructor(handle: Native.PreKeyBundle) {
+    this._nativeHandle = handle;
+  }
+
+  static new(
+    registration_id: number,
+    device_id: number,
+    prekey_id: number | null,
+    prekey: PublicKey | null,
+    signed_prekey_id: number,
+    signed_prekey: PublicKey,
+    signed_prekey_signature: Buffer,
+    identity_key: PublicKey
+  ): PreKeyBundle {
+    return new PreKeyBundle(
+      NativeImpl.PreKeyBundle_New(
+        registration_id,
+        device_id,
+        prekey_id,
+        prekey != null ? prekey : null,
+        //prekey?,
+        signed_prekey_id,
+        signed_prekey,
+        signed_prekey_signature,
+        identity_key
+      )
+    );
+  }
+
+  deviceId(): number {
+    return NativeImpl.PreKeyBundle_GetDeviceId(this);
+  }
+  identityKey(): PublicKey {
+    return PublicKey._fromNativeHandle(
+      NativeImpl.PreKeyBundle_GetIdentityKey(this)
+    );
+  }
+  preKeyId(): number | null {
+    return NativeImpl.PreKeyBundle_GetPreKeyId(this);
+  }
+  preKeyPublic(): PublicKey | null {
+    const handle = NativeImpl.PreKeyBundle_GetPreKeyPublic(this);
+
+    if (handle == null) {
+      return null;
+    } else {
+      return PublicKey._fromNativeHandle(handle);
+    }
+  }
+  registrationId(): number {
+    return NativeImpl.PreKeyBundle_GetRegistrationId(this);
+  }
+  signedPreKeyId(): number {
+    return NativeImpl.PreKeyBundle_GetSignedPreKeyId(this);
+  }
+  signedPreKeyPublic(): PublicKey {
+    return PublicKey._fromNativeHandle(
+      NativeImpl.PreKeyBundle_GetSignedPreKeyPublic(this)
+    );
+  }
+  signedPreKeySignature(): Buffer {
+    return NativeImpl.PreKeyBundle_GetSignedPreKeySignature(this);
+  }
+  minSessionVersion(): number {
+    return NativeImpl.PreKeyBundle_GetMinSessionVersion(this);
+  }
+  maxSessionVersion(): number {
+    return NativeImpl.PreKeyBundle_GetMaxSessionVersion(this);
+  }
+
+  /// Returns a copy of this bundle advertising support for session versions
+  /// `min` through `max`.
+  ///
+  /// A session established from the bundle uses the newest version supported
+  /// by both sides.
+  withSessionVersions(min: number, max: number): PreKeyBundle {
+    return new PreKeyBundle(
+      NativeImpl.PreKeyBundle_WithSessionVersions(this, min, max)
+    );
+  }
+
+  /// Encodes this bundle as bytes for passing to another process, such
+  /// as over Electron IPC. Decode it there with `deserializeFromTransport`.
+  serializeForTransport(): Buffer {
+    return NativeImpl.PreKeyBundle_SerializeForTransport(this);
+  }
+
+  static deserializeFromTransport(buffer: Buffer): PreKeyBundle {
+    return new PreKeyBundle(
+      NativeImpl.PreKeyBundle_DeserializeFromTransport(buffer)
+    );
+  }
+}
+
+export class PreKeyRecord {
+  readonly _nativeHandle: Native.PreKeyRecord;
+
+  private constructor(handle: Native.PreKeyRecord) {
+    this._nativeHandle = handle;
+  }
+
+  static _fromNativeHandle(nativeHandle: Native.PreKeyRecord): PreKeyRecord {
+    return new PreKeyRecord(nativeHandle);
+  }
+
+  static new(id: number, pubKey: PublicKey, privKey: PrivateKey): PreKeyRecord {
+    return new PreKeyRecord(NativeImpl.PreKeyRecord_New(id, pubKey, privKey));
+  }
+
+  static deserialize(buffer: Buffer): PreKeyRecord {
+    return new PreKeyRecord(NativeImpl.PreKeyRecord_Deserialize(buffer));
+  }
+
+  id(): number {
+    return NativeImpl.PreKeyRecord_GetId(this);
+  }
+
+  privateKey(): PrivateKey {
+    return PrivateKey._fromNativeHandle(
+      NativeImpl.PreKeyRecord_GetPrivateKey(this)
+    );
+  }
+
+  publicKey(): PublicKey {
+    return PublicKey._fromNativeHandle(
+      NativeImpl.PreKeyRecord_GetPublicKey(this)
+    );
+  }
+
+  serializedVersion(): number {
+    return NativeImpl.PreKeyRecord_GetSerializedVersion(this);
+  }
+
+  serialize(): Buffer {
+    return NativeImpl.PreKeyRecord_Serialize(this);
+  }
+}
+
+export class SignedPreKeyRecord {
+  readonly _nativeHandle: Native.SignedPreKeyRecord;
+
+  private constructor(handle: Native.SignedPreKeyRecord) {
+    this._nativeHandle = handle;
+  }
+
+  static _fromNativeHandle(
+    nativeHandle: Native.SignedPreKeyRecord
+  ): SignedPreKeyRecord {
+    return new SignedPreKeyRecord(nativeHandle);
+  }
+
+  static new(
+    id: number,
+    timestamp: number,
+    pubKey: PublicKey,
+    privKey: PrivateKey,
+    signature: Buffer
+  ): SignedPreKeyRecord {
+    return new SignedPreKeyRecord(
+      NativeImpl.SignedPreKeyRecord_New(
+        id,
+        timestamp,
+        pubKey,
+        privKey,
+        signature
+      )
+    );
+  }
+
+  static deserialize(buffer: Buffer): SignedPreKeyRecord {
+    return new SignedPreKeyRecord(
+      NativeImpl.SignedPreKeyRecord_Deserialize(buffer)
+    );
+  }
+
+  id(): number {
+    return NativeImpl.SignedPreKeyRecord_GetId(this);
+  }
+
+  privateKey(): PrivateKey {
+    return PrivateKey._fromNativeHandle(
+      NativeImpl.SignedPreKeyRecord_GetPrivateKey(this)
+    );
+  }
+
+  publicKey(): PublicKey {
+    return PublicKey._fromNativeHandle(
+      NativeImpl.SignedPreKeyRecord_GetPublicKey(this)
+    );
+  }
+
+  serialize(): Buffer {
+    return NativeImpl.SignedPreKeyRecord_Serialize(this);
+  }
+
+  signature(): Buffer {
+    return NativeImpl.SignedPreKeyRecord_GetSignature(this);
+  }
+
+  timestamp(): number {
+    return NativeImpl.SignedPreKeyRecord_GetTimestamp(this);
+  }
+}
+
+export class SignalMessage {
+  readonly _nativeHandle: Native.SignalMessage;
+
+  private constructor(handle: Native.SignalMessage) {
+    this._nativeHandle = handle;
+  }
+
+  static new(
+    messageVersion: number,
+    macKey: Buffer,
+    senderRatchetKey: PublicKey,
+    counter: number,
+    previousCounter: number,
+    ciphertext: Buffer,
+    senderIdentityKey: PublicKey,
+    receiverIdentityKey: PublicKey
+  ): SignalMessage {
+    return new SignalMessage(
+      NativeImpl.SignalMessage_New(
+        messageVersion,
+        macKey,
+        senderRatchetKey,
+        counter,
+        previousCounter,
+        ciphertext,
+        senderIdentityKey,
+        receiverIdentityKey
+      )
+    );
+  }
+
+  static _fromNativeHandle(handle: Native.SignalMessage): SignalMessage {
+    return new SignalMessage(handle);
+  }
+
+  static deserialize(buffer: Buffer): SignalMessage {
+    return new SignalMessage(NativeImpl.SignalMessage_Deserialize(buffer));
+  }
+
+  body(): Buffer {
+    return NativeImpl.SignalMessage_GetBody(this);
+  }
+
+  counter(): number {
+    return NativeImpl.SignalMessage_GetCounter(this);
+  }
+
+  messageVersion(): number {
+    return NativeImpl.SignalMessage_GetMessageVersion(this);
+  }
+
+  previousCounter(): number {
+    return NativeImpl.SignalMessage_GetPreviousCounter(this);
+  }
+
+  senderRatchetKey(): PublicKey {
+    return PublicKey._fromNativeHandle(
+      NativeImpl.SignalMessage_GetSenderRatchetKey(this)
+    );
+  }
+
+  serialize(): Buffer {
+    return NativeImpl.SignalMessage_GetSerialized(this);
+  }
+
+  /// Encodes this message as bytes for passing to another process, such
+  /// as over Electron IPC. Decode it there with `deserializeFromTransport`.
+  serializeForTransport(): Buffer {
+    return NativeImpl.SignalMessage_SerializeForTransport(this);
+  }
+
+  static deserializeFromTransport(buffer: Buffer): SignalMessage {
+    return new SignalMessage(
+      NativeImpl.SignalMessage_DeserializeFromTransport(buffer)
+    );
+  }
+
+  verifyMac(
+    senderIdentityKey: PublicKey,
+    recevierIdentityKey: PublicKey,
+    macKey: Buffer
+  ): boolean {
+    return NativeImpl.SignalMessage_VerifyMac(
+      this,
+      senderIdentityKey,
+      recevierIdentityKey,
+      macKey
+    );
+  }
+}
+
+export class PreKeySignalMessage {
+  readonly _nativeHandle: Native.PreKeySignalMessage;
+
+  private constructor(handle: Native.PreKeySignalMessage) {
+    this._nativeHandle = handle;
+  }
+
+  static new(
+    messageVersion: number,
+    registrationId: number,
+    preKeyId: number | null,
+    signedPreKeyId: number,
+    baseKey: PublicKey,
+    identityKey: PublicKey,
+    signalMessage: SignalMessage
+  ): PreKeySignalMessage {
+    return new PreKeySignalMessage(
+      NativeImpl.PreKeySignalMessage_New(
+        messageVersion,
+        registrationId,
+        preKeyId,
+        signedPreKeyId,
+        baseKey,
+        identityKey,
+        signalMessage
+      )
+    );
+  }
+
+  static deserialize(buffer: Buffer): PreKeySignalMessage {
+    return new PreKeySignalMessage(
+      NativeImpl.PreKeySignalMessage_Deserialize(buffer)
+    );
+  }
+
+  baseKey(): PublicKey {
+    return PublicKey._fromNativeHandle(
+      NativeImpl.PreKeySignalMessage_GetBaseKey(this)
+    );
+  }
+
+  identityKey(): PublicKey {
+    return PublicKey._fromNativeHandle(
+      NativeImpl.PreKeySignalMessage_GetIdentityKey(this)
+    );
+  }
+
+  preKeyId(): number | null {
+    return NativeImpl.PreKeySignalMessage_GetPreKeyId(this);
+  }
+
+  registrationId(): number {
+    return NativeImpl.PreKeySignalMessage_GetRegistrationId(this);
+  }
+
+  signalMessage(): SignalMessage {
+    return SignalMessage._fromNativeHandle(
+      NativeImpl.PreKeySignalMessage_GetSignalMessage(this)
+    );
+  }
+
+  signedPreKeyId(): number {
+    return NativeImpl.PreKeySignalMessage_GetSignedPreKeyId(this);
+  }
+
+  version(): number {
+    return NativeImpl.PreKeySignalMessage_GetVersion(this);
+  }
+
+  serialize(): Buffer {
+    return NativeImpl.PreKeySignalMessage_Serialize(this);
+  }
+
+  /// Encodes this message as bytes for passing to another process, such
+  /// as over Electron IPC. Decode it there with `deserializeFromTransport`.
+  serializeForTransport(): Buffer {
+    return NativeImpl.PreKeySignalMessage_SerializeForTransport(this);
+  }
+
+  static deserializeFromTransport(buffer: Buffer): PreKeySignalMessage {
+    return new PreKeySignalMessage(
+      NativeImpl.PreKeySignalMessage_DeserializeFromTransport(buffer)
+    );
+  }
+}
+
+export class SessionRecord {
+  readonly _nativeHandle: Native.SessionRecord;
+
+  private constructor(nativeHandle: Native.SessionRecord) {
+    this._nativeHandle = nativeHandle;
+  }
+
+  static _fromNativeHandle(nativeHandle: Native.SessionRecord): SessionRecord {
+    return new SessionRecord(nativeHandle);
+  }
+
+  static deserialize(buffer: Buffer): SessionRecord {
+    return new SessionRecord(NativeImpl.SessionRecord_Deserialize(buffer));
+  }
+
+  /// The most archived states a record may have when deserialized.
+  static maxArchivedStates(): number {
+    return NativeImpl.SessionRecord_MaxArchivedStates();
+  }
+
+  /// The most receiver chains each state in a record may have.
+  static maxReceiverChains(): number {
+    return NativeImpl.SessionRecord_MaxReceiverChains();
+  }
+
+  /// The most skipped-message keys each chain in a record may have.
+  static maxMessageKeysPerChain(): number {
+    return NativeImpl.SessionRecord_MaxMessageKeysPerChain();
+  }
+
+  serialize(): Buffer {
+    return NativeImpl.SessionRecord_Serialize(this);
+  }
+
+  archiveCurrentState(): void {
+    NativeImpl.SessionRecord_ArchiveCurrentState(this);
+  }
+
+  localRegistrationId(): number {
+    return NativeImpl.SessionRecord_GetLocalRegistrationId(this);
+  }
+
+  remoteRegistrationId(): number {
+    return NativeImpl.SessionRecord_GetRemoteRegistrationId(this);
+  }
+
+  hasCurrentState(): boolean {
+    return NativeImpl.SessionRecord_HasCurrentState(this);
+  }
+
+  serializedVersion(): number {
+    return NativeImpl.SessionRecord_GetSerializedVersion(this);
+  }
+
+  /// The version of the current session, or 0 if there is no current session.
+  sessionVersion(): number {
+    return NativeImpl.SessionRecord_GetSessionVersion(this);
+  }
+}
+
+export class SenderKeyName {
+  readonly _nativeHandle: Native.SenderKeyName;
+
+  private constructor(nativeHandle: Native.SenderKeyName) {
+    this._nativeHandle = nativeHandle;
+  }
+
+  static _fromNativeHandle(nativeHandle: Native.SenderKeyName): SenderKeyName {
+    return new SenderKeyName(nativeHandle);
+  }
+
+  static new(
+    groupId: string,
+    senderName: string,
+    senderDeviceId: number
+  ): SenderKeyName {
+    return new SenderKeyName(
+      NativeImpl.SenderKeyName_New(groupId, senderName, senderDeviceId)
+    );
+  }
+
+  groupId(): string {
+    return NativeImpl.SenderKeyName_GetGroupId(this);
+  }
+
+  senderName(): string {
+    return NativeImpl.SenderKeyName_GetSenderName(this);
+  }
+
+  senderDeviceId(): number {
+    return NativeImpl.SenderKeyName_GetSenderDeviceId(this);
+  }
+}
+
+export class ServerCertificate {
+  readonly _nativeHandle: Native.ServerCertificate;
+
+  static _fromNativeHandle(
+    nativeHandle: Native.ServerCertificate
+  ): ServerCertificate {
+    return new ServerCertificate(nativeHandle);
+  }
+
+  private constructor(nativeHandle: Native.ServerCertificate) {
+    this._nativeHandle = nativeHandle;
+  }
+
+  static new(
+    keyId: number,
+    serverKey: PublicKey,
+    trustRoot: PrivateKey,
+    rng?: Rng
+  ): ServerCertificate {
+    return new ServerCertificate(
+      NativeImpl.ServerCertificate_New(keyId, serverKey, trustRoot, rng ?? null)
+    );
+  }
+
+  static deserialize(buffer: Buffer): ServerCertificate {
+    return new ServerCertificate(
+      NativeImpl.ServerCertificate_Deserialize(buffer)
+    );
+  }
+
+  certificateData(): Buffer {
+    return NativeImpl.ServerCertificate_GetCertificate(this);
+  }
+
+  key(): PublicKey {
+    return PublicKey._fromNativeHandle(
+      NativeImpl.ServerCertificate_GetKey(this)
+    );
+  }
+
+  keyId(): number {
+    return NativeImpl.ServerCertificate_GetKeyId(this);
+  }
+
+  serialize(): Buffer {
+    return NativeImpl.ServerCertificate_GetSerialized(this);
+  }
+
+  signature(): Buffer {
+    return NativeImpl.ServerCertificate_GetSignature(this);
+  }
+}
+
+export class SenderKeyRecord {
+  readonly _nativeHandle: Native.SenderKeyRecord;
+
+  static _fromNativeHandle(
+    nativeHandle: Native.SenderKeyRecord
+  ): SenderKeyRecord {
+    return new SenderKeyRecord(nativeHandle);
+  }
+
+  private constructor(nativeHandle: Native.SenderKeyRecord) {
+    this._nativeHandle = nativeHandle;
+  }
+
+  static new(): SenderKeyRecord {
+    return new SenderKeyRecord(NativeImpl.SenderKeyRecord_New());
   }
 
   static deserialize(buffer: Buffer): SenderKeyRecord {
     return new SenderKeyRecord(NativeImpl.SenderKeyRecord_Deserialize(buffer));
   }
 
+  /// The most states a record may have when deserialized.
+  static maxStates(): number {
+    return NativeImpl.SenderKeyRecord_MaxStates();
+  }
+
+  /// The most skipped-message keys each state in a record may have.
+  static maxMessageKeysPerState(): number {
+    return NativeImpl.SenderKeyRecord_MaxMessageKeysPerState();
+  }
+
   serialize(): Buffer {
     return NativeImpl.SenderKeyRecord_Serialize(this);
   }
+
+  serializedVersion(): number {
+    return NativeImpl.SenderKeyRecord_GetSerializedVersion(this);
+  }
+
+  /// Records that `member` has been sent a distribution message for the current sender key.
+  markDistributed(member: ProtocolAddress): void {
+    NativeImpl.SenderKeyRecord_MarkDistributed(this, member);
+  }
+
+  /// Returns true if `member` has not been sent the current sender key.
+  needsDistribution(member: ProtocolAddress): boolean {
+    return NativeImpl.SenderKeyRecord_NeedsDistribution(this, member);
+  }
+
+  /// Stops tracking a member who has left the group.
+  ///
+  /// Returns true if the member holds the current sender key, which should then be replaced before
+  /// the next message is sent to the group.
+  forgetDistribution(member: ProtocolAddress): boolean {
+    return NativeImpl.SenderKeyRecord_ForgetDistribution(this, member);
+  }
 }
 
 export class SenderCertificate {
@@ -740,7 +2207,8 @@ export class SenderCertificate {
     senderKey: PublicKey,
     expiration: number,
     signerCert: ServerCertificate,
-    signerKey: PrivateKey
+    signerKey: PrivateKey,
+    rng?: Rng
   ): SenderCertificate {
     return new SenderCertificate(
       NativeImpl.SenderCertificate_New(
@@ -750,7 +2218,8 @@ export class SenderCertificate {
         senderKey,
         expiration,
         signerCert,
-        signerKey
+        signerKey,
+        rng ?? null
       )
     );
   }
@@ -768,7 +2237,7 @@ export class SenderCertificate {
   certificate(): Buffer {
     return NativeImpl.SenderCertificate_GetCertificate(this);
   }
-  expiration(): number {
+  expiration(): Date {
     return NativeImpl.SenderCertificate_GetExpiration(this);
   }
   key(): PublicKey {
@@ -796,6 +2265,75 @@ export class SenderCertificate {
   validate(trustRoot: PublicKey, time: number): boolean {
     return NativeImpl.SenderCertificate_Validate(this, trustRoot, time);
   }
+  // Accepts the certificate for `gracePeriod` past its expiration. A certificate
+  // that is still expired throws SealedSenderCertificateExpiredError instead of
+  // returning false.
+  validateWithGracePeriod(
+    trustRoot: PublicKey,
+    time: number,
+    gracePeriod: number
+  ): boolean {
+    return NativeImpl.SenderCertificate_ValidateWithGracePeriod(
+      this,
+      trustRoot,
+      time,
+      gracePeriod
+    );
+  }
+}
+
+/**
+ * Caches a sender certificate, fetching a new one from its source once the
+ * cached one is within the freshness window of expiring.
+ */
+export class SenderCertificateProvider {
+  readonly _nativeHandle: Native.SenderCertificateProvider;
+  readonly source: SenderCertificateSource;
+
+  private constructor(
+    nativeHandle: Native.SenderCertificateProvider,
+    source: SenderCertificateSource
+  ) {
+    this._nativeHandle = nativeHandle;
+    this.source = source;
+  }
+
+  /**
+   * @param freshnessWindow how long before expiration, in milliseconds, to
+   *   fetch a new certificate
+   * @param certificate a certificate to start with, such as one saved earlier
+   */
+  static new(
+    source: SenderCertificateSource,
+    freshnessWindow: number,
+    certificate?: SenderCertificate
+  ): SenderCertificateProvider {
+    return new SenderCertificateProvider(
+      NativeImpl.SenderCertificateProvider_New(
+        certificate ?? null,
+        freshnessWindow
+      ),
+      source
+    );
+  }
+
+  /**
+   * Returns a certificate that is still valid at `validUntil`.
+   *
+   * If the cached certificate expires within the freshness window, a new one is
+   * fetched first. If that fails, the cached certificate is still returned as
+   * long as it is valid at `validUntil`.
+   */
+  async get(validUntil: number): Promise<SenderCertificate> {
+    return SenderCertificate._fromNativeHandle(
+      await NativeImpl.SenderCertificateProvider_Get(
+        this,
+        validUntil,
+        this.source,
+        null
+      )
+    );
+  }
 }
 
 export class SenderKeyDistributionMessage {
@@ -807,11 +2345,13 @@ export class SenderKeyDistributionMessage {
 
   static async create(
     name: SenderKeyName,
-    store: SenderKeyStore
+    store: SenderKeyStore,
+    rng?: Rng
   ): Promise<SenderKeyDistributionMessage> {
     const handle = await NativeImpl.SenderKeyDistributionMessage_Create(
       name,
       store,
+      rng ?? null,
       null
     );
     return new SenderKeyDistributionMessage(handle);
@@ -843,6 +2383,20 @@ export class SenderKeyDistributionMessage {
     return NativeImpl.SenderKeyDistributionMessage_Serialize(this);
   }
 
+  /// Encodes this message as bytes for passing to another process, such
+  /// as over Electron IPC. Decode it there with `deserializeFromTransport`.
+  serializeForTransport(): Buffer {
+    return NativeImpl.SenderKeyDistributionMessage_SerializeForTransport(this);
+  }
+
+  static deserializeFromTransport(
+    buffer: Buffer
+  ): SenderKeyDistributionMessage {
+    return new SenderKeyDistributionMessage(
+      NativeImpl.SenderKeyDistributionMessage_DeserializeFromTransport(buffer)
+    );
+  }
+
   chainKey(): Buffer {
     return NativeImpl.SenderKeyDistributionMessage_GetChainKey(this);
   }
@@ -880,10 +2434,17 @@ export class SenderKeyMessage {
     keyId: number,
     iteration: number,
     ciphertext: Buffer,
-    pk: PrivateKey
+    pk: PrivateKey,
+    rng?: Rng
   ): SenderKeyMessage {
     return new SenderKeyMessage(
-      NativeImpl.SenderKeyMessage_New(keyId, iteration, ciphertext, pk)
+      NativeImpl.SenderKeyMessage_New(
+        keyId,
+        iteration,
+        ciphertext,
+        pk,
+        rng ?? null
+      )
     );
   }
 
@@ -897,6 +2458,18 @@ export class SenderKeyMessage {
     return NativeImpl.SenderKeyMessage_Serialize(this);
   }
 
+  /// Encodes this message as bytes for passing to another process, such
+  /// as over Electron IPC. Decode it there with `deserializeFromTransport`.
+  serializeForTransport(): Buffer {
+    return NativeImpl.SenderKeyMessage_SerializeForTransport(this);
+  }
+
+  static deserializeFromTransport(buffer: Buffer): SenderKeyMessage {
+    return new SenderKeyMessage(
+      NativeImpl.SenderKeyMessage_DeserializeFromTransport(buffer)
+    );
+  }
+
   ciphertext(): Buffer {
     return NativeImpl.SenderKeyMessage_GetCipherText(this);
   }
@@ -909,6 +2482,10 @@ export class SenderKeyMessage {
     return NativeImpl.SenderKeyMessage_GetKeyId(this);
   }
 
+  messageVersion(): number {
+    return NativeImpl.SenderKeyMessage_GetMessageVersion(this);
+  }
+
   verifySignature(key: PublicKey): boolean {
     return NativeImpl.SenderKeyMessage_VerifySignature(this, key);
   }
@@ -952,7 +2529,17 @@ export class UnidentifiedSenderMessageContent {
   }
 }
 
+class SessionCache {
+  readonly _nativeHandle: Native.SessionCache;
+
+  constructor(capacity: number) {
+    this._nativeHandle = NativeImpl.SessionCache_New(capacity);
+  }
+}
+
 export abstract class SessionStore implements Native.SessionStore {
+  _sessionCache: SessionCache | null = null;
+
   async _saveSession(
     name: Native.ProtocolAddress,
     record: Native.SessionRecord
@@ -972,12 +2559,56 @@ export abstract class SessionStore implements Native.SessionStore {
       return sess._nativeHandle;
     }
   }
+  async _getSessions(
+    names: Native.ProtocolAddress[]
+  ): Promise<(Native.SessionRecord | null)[]> {
+    const addresses = names.map(name =>
+      ProtocolAddress._fromNativeHandle(name)
+    );
+    const sessions = this.getSessions
+      ? await this.getSessions(addresses)
+      : await Promise.all(addresses.map(address => this.getSession(address)));
+    return sessions.map(sess => (sess == null ? null : sess._nativeHandle));
+  }
+
+  /**
+   * Loads several sessions at once.
+   *
+   * Lookups made by concurrent operations are grouped and passed here,
+   * if implemented. Otherwise, each one goes to `getSession` individually.
+   */
+  getSessions?(names: ProtocolAddress[]): Promise<(SessionRecord | null)[]>;
 
   abstract saveSession(
     name: ProtocolAddress,
     record: SessionRecord
   ): Promise<void>;
   abstract getSession(name: ProtocolAddress): Promise<SessionRecord | null>;
+
+  // Keeps up to `capacity` of this store's session records in native memory
+  // after they are loaded or saved, to save round trips through JavaScript.
+  // Saves still always go to the store first. A capacity of 0 (the default)
+  // disables the cache. Use invalidateCachedSession() or clearSessionCache()
+  // whenever this store's sessions are changed any other way.
+  enableSessionCache(capacity: number): void {
+    if (this._sessionCache == null) {
+      this._sessionCache = new SessionCache(capacity);
+    } else {
+      NativeImpl.SessionCache_SetCapacity(this._sessionCache, capacity);
+    }
+  }
+
+  invalidateCachedSession(address: ProtocolAddress): void {
+    if (this._sessionCache != null) {
+      NativeImpl.SessionCache_Invalidate(this._sessionCache, address);
+    }
+  }
+
+  clearSessionCache(): void {
+    if (this._sessionCache != null) {
+      NativeImpl.SessionCache_Clear(this._sessionCache);
+    }
+  }
 }
 
 export abstract class IdentityKeyStore implements Native.IdentityKeyStore {
@@ -1021,6 +2652,25 @@ export abstract class IdentityKeyStore implements Native.IdentityKeyStore {
       return key._nativeHandle;
     }
   }
+  async _getIdentities(
+    names: Native.ProtocolAddress[]
+  ): Promise<(Native.PublicKey | null)[]> {
+    const addresses = names.map(name =>
+      ProtocolAddress._fromNativeHandle(name)
+    );
+    const keys = this.getIdentities
+      ? await this.getIdentities(addresses)
+      : await Promise.all(addresses.map(address => this.getIdentity(address)));
+    return keys.map(key => (key == null ? null : key._nativeHandle));
+  }
+
+  /**
+   * Looks up several identities at once.
+   *
+   * Lookups made by concurrent operations are grouped and passed here,
+   * if implemented. Otherwise, each one goes to `getIdentity` individually.
+   */
+  getIdentities?(names: ProtocolAddress[]): Promise<(PublicKey | null)[]>;
 
   abstract getIdentityKey(): Promise<PrivateKey>;
   abstract getLocalRegistrationId(): Promise<number>;
@@ -1040,16 +2690,20 @@ export abstract class PreKeyStore implements Native.PreKeyStore {
   async _savePreKey(id: number, record: Native.PreKeyRecord): Promise<void> {
     return this.savePreKey(id, PreKeyRecord._fromNativeHandle(record));
   }
-  async _getPreKey(id: number): Promise<Native.PreKeyRecord> {
+  async _getPreKey(id: number): Promise<Native.PreKeyRecord | null> {
     const pk = await this.getPreKey(id);
-    return pk._nativeHandle;
+    if (pk == null) {
+      return null;
+    } else {
+      return pk._nativeHandle;
+    }
   }
   async _removePreKey(id: number): Promise<void> {
     return this.removePreKey(id);
   }
 
   abstract savePreKey(id: number, record: PreKeyRecord): Promise<void>;
-  abstract getPreKey(id: number): Promise<PreKeyRecord>;
+  abstract getPreKey(id: number): Promise<PreKeyRecord | null>;
   abstract removePreKey(id: number): Promise<void>;
 }
 
@@ -1063,16 +2717,22 @@ export abstract class SignedPreKeyStore implements Native.SignedPreKeyStore {
       SignedPreKeyRecord._fromNativeHandle(record)
     );
   }
-  async _getSignedPreKey(id: number): Promise<Native.SignedPreKeyRecord> {
+  async _getSignedPreKey(
+    id: number
+  ): Promise<Native.SignedPreKeyRecord | null> {
     const pk = await this.getSignedPreKey(id);
-    return pk._nativeHandle;
+    if (pk == null) {
+      return null;
+    } else {
+      return pk._nativeHandle;
+    }
   }
 
   abstract saveSignedPreKey(
     id: number,
     record: SignedPreKeyRecord
   ): Promise<void>;
-  abstract getSignedPreKey(id: number): Promise<SignedPreKeyRecord>;
+  abstract getSignedPreKey(id: number): Promise<SignedPreKeyRecord | null>;
 }
 
 export abstract class SenderKeyStore implements Native.SenderKeyStore {
@@ -1103,12 +2763,51 @@ export abstract class SenderKeyStore implements Native.SenderKeyStore {
   abstract getSenderKey(name: SenderKeyName): Promise<SenderKeyRecord | null>;
 }
 
+/**
+ * Remembers which sealed sender messages have already been received.
+ *
+ * Each message is identified by the SHA-256 digest of its ciphertext. An
+ * entry only needs to be kept until its expiration, after which the sender
+ * certificate no longer validates anyway.
+ */
+export abstract class ReplayCache implements Native.ReplayCache {
+  async _containsMessage(messageId: Buffer, now: number): Promise<boolean> {
+    return this.containsMessage(messageId, now);
+  }
+  async _recordMessage(messageId: Buffer, expiration: number): Promise<void> {
+    return this.recordMessage(messageId, expiration);
+  }
+
+  /** Returns true if `messageId` was recorded and has not expired as of `now`. */
+  abstract containsMessage(messageId: Buffer, now: number): Promise<boolean>;
+  abstract recordMessage(messageId: Buffer, expiration: number): Promise<void>;
+}
+
+export abstract class SenderCertificateSource
+  implements Native.SenderCertificateSource {
+  async _fetchSenderCertificate(): Promise<Native.SenderCertificate> {
+    const certificate = await this.fetchSenderCertificate();
+    return certificate._nativeHandle;
+  }
+
+  /** Fetches a new sender certificate from the service. */
+  abstract fetchSenderCertificate(): Promise<SenderCertificate>;
+}
+
 export async function groupEncrypt(
   name: SenderKeyName,
   store: SenderKeyStore,
-  message: Buffer
+  message: Buffer,
+  rng?: Rng,
+  padding?: PaddingPolicy
 ): Promise<Buffer> {
-  return NativeImpl.GroupCipher_EncryptMessage(name, message, store, null);
+  return NativeImpl.GroupCipher_EncryptMessage(
+    name,
+    padding?.pad(message) ?? message,
+    store,
+    rng ?? null,
+    null
+  );
 }
 
 export async function groupDecrypt(
@@ -1119,6 +2818,77 @@ export async function groupDecrypt(
   return NativeImpl.GroupCipher_DecryptMessage(name, message, store, null);
 }
 
+// Like groupDecrypt, but records the sender key write in the result's mutation
+// log instead of making it.
+export async function groupDecryptDeferred(
+  name: SenderKeyName,
+  store: SenderKeyStore,
+  message: Buffer
+): Promise<DeferredDecryptionResult> {
+  const result = await NativeImpl.GroupCipher_DecryptMessageDeferred(
+    name,
+    message,
+    store,
+    null
+  );
+  return DeferredDecryptionResult._fromNativeHandle(result);
+}
+
+// Returns the next iteration expected on the given sender key chain.
+export async function groupChainPosition(
+  name: SenderKeyName,
+  store: SenderKeyStore,
+  distributionId: number
+): Promise<number> {
+  return NativeImpl.GroupCipher_ChainPosition(
+    name,
+    distributionId,
+    store,
+    null
+  );
+}
+
+// Advances a sender key chain past messages that are too far ahead to decrypt normally.
+// Messages from the skipped iterations can no longer be decrypted, so only use this once the
+// sender's position has been verified out of band. The chain can move forward by at most 1,000,000
+// iterations at once.
+export async function groupFastForward(
+  name: SenderKeyName,
+  store: SenderKeyStore,
+  distributionId: number,
+  toIteration: number
+): Promise<void> {
+  return NativeImpl.GroupCipher_FastForward(
+    name,
+    distributionId,
+    toIteration,
+    store,
+    null
+  );
+}
+
+// Summarizes the security state of the conversation with `address` as JSON,
+// for attaching to a bug report. The summary includes a fingerprint of the
+// saved identity key, the session version, and when the ratchet last stepped,
+// but never any secrets. If `senderKeyName` (one of our own) is given, it also
+// says which of our sender keys for that group `address` was last sent.
+export async function securitySnapshot(
+  address: ProtocolAddress,
+  senderKeyName: SenderKeyName | null,
+  sessionStore: SessionStore,
+  identityStore: IdentityKeyStore,
+  senderKeyStore: SenderKeyStore
+): Promise<string> {
+  return NativeImpl.SecuritySnapshot_Create(
+    address,
+    senderKeyName,
+    sessionStore,
+    identityStore,
+    senderKeyStore,
+    null
+  );
+}
+
 export class SealedSenderDecryptionResult {
   readonly _nativeHandle: Native.SealedSenderDecryptionResult;
 
@@ -1149,6 +2919,73 @@ export class SealedSenderDecryptionResult {
   }
 }
 
+/**
+ * The result of a decryption that only read from the stores.
+ */
+export class DeferredDecryptionResult {
+  readonly _nativeHandle: Native.DeferredDecryptionResult;
+
+  private constructor(nativeHandle: Native.DeferredDecryptionResult) {
+    this._nativeHandle = nativeHandle;
+  }
+
+  static _fromNativeHandle(
+    nativeHandle: Native.DeferredDecryptionResult
+  ): DeferredDecryptionResult {
+    return new DeferredDecryptionResult(nativeHandle);
+  }
+
+  plaintext(): Buffer {
+    return NativeImpl.DeferredDecryptionResult_GetPlaintext(this);
+  }
+
+  /**
+   * The store writes the decryption would have made, to be replayed with
+   * {@link applyMutationLog} by a process that can write to the stores.
+   */
+  mutationLog(): Buffer {
+    return NativeImpl.DeferredDecryptionResult_GetMutationLog(this);
+  }
+}
+
+export class NotificationDecryptionResult {
+  readonly _nativeHandle: Native.NotificationDecryptionResult;
+
+  private constructor(nativeHandle: Native.NotificationDecryptionResult) {
+    this._nativeHandle = nativeHandle;
+  }
+
+  static _fromNativeHandle(
+    nativeHandle: Native.NotificationDecryptionResult
+  ): NotificationDecryptionResult {
+    return new NotificationDecryptionResult(nativeHandle);
+  }
+
+  message(): Buffer {
+    return NativeImpl.NotificationDecryptionResult_GetMessage(this);
+  }
+
+  senderE164(): string | null {
+    return NativeImpl.NotificationDecryptionResult_GetSenderE164(this);
+  }
+
+  senderUuid(): string {
+    return NativeImpl.NotificationDecryptionResult_GetSenderUuid(this);
+  }
+
+  deviceId(): number {
+    return NativeImpl.NotificationDecryptionResult_GetDeviceId(this);
+  }
+
+  /**
+   * The store changes a normal decryption would have made, serialized to be
+   * passed to {@link applyNotificationCommitSet} later.
+   */
+  commitSet(): Buffer {
+    return NativeImpl.NotificationDecryptionResult_GetCommitSet(this);
+  }
+}
+
 export class CiphertextMessage {
   readonly _nativeHandle: Native.CiphertextMessage;
 
@@ -1162,40 +2999,271 @@ export class CiphertextMessage {
     return new CiphertextMessage(nativeHandle);
   }
 
-  serialize(): Buffer {
-    return NativeImpl.CiphertextMessage_Serialize(this);
-  }
+  static deserialize(type: number, buffer: Buffer): CiphertextMessage {
+    return new CiphertextMessage(
+      NativeImpl.CiphertextMessage_Deserialize(type, buffer)
+    );
+  }
+
+  serialize(): Buffer {
+    return NativeImpl.CiphertextMessage_Serialize(this);
+  }
+
+  /// Encodes this message as bytes for passing to another process, such
+  /// as over Electron IPC. Decode it there with `deserializeFromTransport`.
+  serializeForTransport(): Buffer {
+    return NativeImpl.CiphertextMessage_SerializeForTransport(this);
+  }
+
+  static deserializeFromTransport(buffer: Buffer): CiphertextMessage {
+    return new CiphertextMessage(
+      NativeImpl.CiphertextMessage_DeserializeFromTransport(buffer)
+    );
+  }
+
+  messageVersion(): number {
+    return NativeImpl.CiphertextMessage_GetMessageVersion(this);
+  }
+
+  // The position of this message in the sender's current chain, or null for
+  // sender key messages.
+  counter(): number | null {
+    return NativeImpl.CiphertextMessage_GetCounter(this);
+  }
+
+  // The length of the sender's previous chain, or null for sender key messages.
+  previousCounter(): number | null {
+    return NativeImpl.CiphertextMessage_GetPreviousCounter(this);
+  }
+
+  type(): number {
+    return NativeImpl.CiphertextMessage_Type(this);
+  }
+}
+
+export function processPreKeyBundle(
+  bundle: PreKeyBundle,
+  address: ProtocolAddress,
+  sessionStore: SessionStore,
+  identityStore: IdentityKeyStore,
+  rng?: Rng
+): Promise<void> {
+  return NativeImpl.SessionBuilder_ProcessPreKeyBundle(
+    bundle,
+    address,
+    sessionStore,
+    identityStore,
+    rng ?? null,
+    null
+  );
+}
+
+export async function signalEncrypt(
+  message: Buffer,
+  address: ProtocolAddress,
+  sessionStore: SessionStore,
+  identityStore: IdentityKeyStore,
+  padding?: PaddingPolicy
+): Promise<CiphertextMessage> {
+  return CiphertextMessage._fromNativeHandle(
+    await NativeImpl.SessionCipher_EncryptMessage(
+      padding?.pad(message) ?? message,
+      address,
+      sessionStore,
+      identityStore,
+      null
+    )
+  );
+}
+
+/// Serializes `content`, pads it according to `padding`, and encrypts it for
+/// `address`.
+export async function signalEncryptContent(
+  content: Content,
+  address: ProtocolAddress,
+  sessionStore: SessionStore,
+  identityStore: IdentityKeyStore,
+  padding: PaddingPolicy
+): Promise<CiphertextMessage> {
+  return CiphertextMessage._fromNativeHandle(
+    await NativeImpl.SessionCipher_EncryptContent(
+      content,
+      padding,
+      address,
+      sessionStore,
+      identityStore,
+      null
+    )
+  );
+}
+
+export interface EncryptBatchResult {
+  message: CiphertextMessage | null;
+  error: Error | null;
+}
+
+// Encrypts `message` for each of `addresses`, returning a result for each in the same order.
+// A failure for one address doesn't prevent sending to the others.
+export async function signalEncryptBatch(
+  message: Buffer,
+  addresses: ProtocolAddress[],
+  sessionStore: SessionStore,
+  identityStore: IdentityKeyStore
+): Promise<EncryptBatchResult[]> {
+  const results = await NativeImpl.SessionCipher_EncryptMessageBatch(
+    message,
+    addresses,
+    sessionStore,
+    identityStore,
+    null
+  );
+  return results.map(result => {
+    if (result.message == null) {
+      return { message: null, error: result.error || null };
+    }
+    return {
+      message: CiphertextMessage._fromNativeHandle(result.message),
+      error: null,
+    };
+  });
+}
+
+export function signalDecrypt(
+  message: SignalMessage,
+  address: ProtocolAddress,
+  sessionStore: SessionStore,
+  identityStore: IdentityKeyStore,
+  rng?: Rng
+): Promise<Buffer> {
+  return NativeImpl.SessionCipher_DecryptSignalMessage(
+    message,
+    address,
+    sessionStore,
+    identityStore,
+    rng ?? null,
+    null
+  );
+}
+
+export function signalDecryptPreKey(
+  message: PreKeySignalMessage,
+  address: ProtocolAddress,
+  sessionStore: SessionStore,
+  identityStore: IdentityKeyStore,
+  prekeyStore: PreKeyStore,
+  signedPrekeyStore: SignedPreKeyStore,
+  rng?: Rng
+): Promise<Buffer> {
+  return NativeImpl.SessionCipher_DecryptPreKeySignalMessage(
+    message,
+    address,
+    sessionStore,
+    identityStore,
+    prekeyStore,
+    signedPrekeyStore,
+    rng ?? null,
+    null
+  );
+}
 
-  type(): number {
-    return NativeImpl.CiphertextMessage_Type(this);
-  }
+/**
+ * Like {@link signalDecrypt}, but never writes to the stores.
+ *
+ * Meant for processes that can read the stores but must not write to them. The
+ * writes are returned in the result's mutation log instead, to be replayed
+ * later with {@link applyMutationLog}.
+ */
+export async function signalDecryptDeferred(
+  message: SignalMessage,
+  address: ProtocolAddress,
+  sessionStore: SessionStore,
+  identityStore: IdentityKeyStore,
+  rng?: Rng
+): Promise<DeferredDecryptionResult> {
+  const result = await NativeImpl.SessionCipher_DecryptSignalMessageDeferred(
+    message,
+    address,
+    sessionStore,
+    identityStore,
+    rng ?? null,
+    null
+  );
+  return DeferredDecryptionResult._fromNativeHandle(result);
 }
 
-export function processPreKeyBundle(
-  bundle: PreKeyBundle,
+/**
+ * Like {@link signalDecryptPreKey}, but never writes to the stores.
+ *
+ * @see signalDecryptDeferred
+ */
+export async function signalDecryptPreKeyDeferred(
+  message: PreKeySignalMessage,
   address: ProtocolAddress,
   sessionStore: SessionStore,
-  identityStore: IdentityKeyStore
-): Promise<void> {
-  return NativeImpl.SessionBuilder_ProcessPreKeyBundle(
-    bundle,
+  identityStore: IdentityKeyStore,
+  prekeyStore: PreKeyStore,
+  signedPrekeyStore: SignedPreKeyStore,
+  rng?: Rng
+): Promise<DeferredDecryptionResult> {
+  const result = await NativeImpl.SessionCipher_DecryptPreKeySignalMessageDeferred(
+    message,
     address,
     sessionStore,
     identityStore,
+    prekeyStore,
+    signedPrekeyStore,
+    rng ?? null,
+    null
+  );
+  return DeferredDecryptionResult._fromNativeHandle(result);
+}
+
+/**
+ * Makes the store writes recorded in a {@link DeferredDecryptionResult}'s
+ * mutation log, in order.
+ *
+ * Throws without writing anything if a session or sender key the log replaces
+ * has changed since it was recorded; the message should then be decrypted
+ * again.
+ */
+export function applyMutationLog(
+  mutationLog: Buffer,
+  sessionStore: SessionStore,
+  identityStore: IdentityKeyStore,
+  prekeyStore: PreKeyStore,
+  signedPrekeyStore: SignedPreKeyStore,
+  senderKeyStore: SenderKeyStore
+): Promise<void> {
+  return NativeImpl.MutationLog_Apply(
+    mutationLog,
+    sessionStore,
+    identityStore,
+    prekeyStore,
+    signedPrekeyStore,
+    senderKeyStore,
     null
   );
 }
 
-export async function signalEncrypt(
+/**
+ * Like `signalEncrypt`, but trusts `approvedIdentity` even if `identityStore` doesn't.
+ *
+ * Use this to retry after an untrusted identity error once the user has accepted the recipient's
+ * new identity. If the session's identity is `approvedIdentity`, it is saved to `identityStore` as
+ * part of the same operation; otherwise the store decides as usual.
+ */
+export async function signalEncryptWithIdentityOverride(
   message: Buffer,
   address: ProtocolAddress,
+  approvedIdentity: PublicKey,
   sessionStore: SessionStore,
   identityStore: IdentityKeyStore
 ): Promise<CiphertextMessage> {
   return CiphertextMessage._fromNativeHandle(
-    await NativeImpl.SessionCipher_EncryptMessage(
+    await NativeImpl.SessionCipher_EncryptMessageWithIdentityOverride(
       message,
       address,
+      approvedIdentity,
       sessionStore,
       identityStore,
       null
@@ -1203,53 +3271,149 @@ export async function signalEncrypt(
   );
 }
 
-export function signalDecrypt(
+/**
+ * Like `signalDecrypt`, but trusts `approvedIdentity` even if `identityStore` doesn't.
+ *
+ * See `signalEncryptWithIdentityOverride`.
+ */
+export function signalDecryptWithIdentityOverride(
   message: SignalMessage,
   address: ProtocolAddress,
+  approvedIdentity: PublicKey,
   sessionStore: SessionStore,
-  identityStore: IdentityKeyStore
+  identityStore: IdentityKeyStore,
+  rng?: Rng
 ): Promise<Buffer> {
-  return NativeImpl.SessionCipher_DecryptSignalMessage(
+  return NativeImpl.SessionCipher_DecryptSignalMessageWithIdentityOverride(
     message,
     address,
+    approvedIdentity,
     sessionStore,
     identityStore,
+    rng ?? null,
     null
   );
 }
 
-export function signalDecryptPreKey(
+/**
+ * Like `signalDecryptPreKey`, but trusts `approvedIdentity` even if `identityStore` doesn't.
+ *
+ * See `signalEncryptWithIdentityOverride`.
+ */
+export function signalDecryptPreKeyWithIdentityOverride(
   message: PreKeySignalMessage,
   address: ProtocolAddress,
+  approvedIdentity: PublicKey,
   sessionStore: SessionStore,
   identityStore: IdentityKeyStore,
   prekeyStore: PreKeyStore,
-  signedPrekeyStore: SignedPreKeyStore
+  signedPrekeyStore: SignedPreKeyStore,
+  rng?: Rng
 ): Promise<Buffer> {
-  return NativeImpl.SessionCipher_DecryptPreKeySignalMessage(
+  return NativeImpl.SessionCipher_DecryptPreKeySignalMessageWithIdentityOverride(
     message,
     address,
+    approvedIdentity,
     sessionStore,
     identityStore,
     prekeyStore,
     signedPrekeyStore,
+    rng ?? null,
     null
   );
 }
 
+export class BacklogDecryptor {
+  readonly _nativeHandle: Native.BacklogDecryptor;
+
+  private constructor(nativeHandle: Native.BacklogDecryptor) {
+    this._nativeHandle = nativeHandle;
+  }
+
+  /**
+   * Creates a decryptor with its own pool of `threads` threads, or one per CPU if `threads` is 0.
+   */
+  static new(threads = 0): BacklogDecryptor {
+    return new BacklogDecryptor(NativeImpl.BacklogDecryptor_New(threads));
+  }
+
+  /**
+   * Decrypts `messages[i]`, sent by `addresses[i]`, for every `i`.
+   *
+   * Messages from different senders are decrypted in parallel, but messages from the same sender
+   * are decrypted in order. Each element of the result is either the plaintext or the error for the
+   * message at the same position.
+   */
+  decrypt(
+    messages: CiphertextMessage[],
+    addresses: ProtocolAddress[],
+    sessionStore: SessionStore,
+    identityStore: IdentityKeyStore,
+    prekeyStore: PreKeyStore,
+    signedPrekeyStore: SignedPreKeyStore,
+    rng?: Rng
+  ): Promise<(Buffer | Error)[]> {
+    return NativeImpl.BacklogDecryptor_Decrypt(
+      this,
+      messages,
+      addresses,
+      sessionStore,
+      identityStore,
+      prekeyStore,
+      signedPrekeyStore,
+      rng ?? null,
+      null
+    );
+  }
+}
+
+/// Derives the 16-byte unidentified-access key that senders present to deliver
+/// sealed sender messages to the owner of `profileKey`.
+export function deriveAccessKey(profileKey: Buffer): Buffer {
+  return NativeImpl.ProfileKey_DeriveAccessKey(profileKey);
+}
+
+/// Blinds `serviceId` under a profile key, giving a 32-byte identifier that
+/// only those who know the key can link back to it.
+export function blindServiceId(profileKey: Buffer, serviceId: string): Buffer {
+  return NativeImpl.ProfileKey_BlindServiceId(profileKey, serviceId);
+}
+
+/**
+ * Encrypts `message` for `address` using sealed sender.
+ *
+ * If `senderCert` is a {@link SenderCertificateProvider}, its certificate is
+ * used, after refreshing it if it expires within the provider's freshness
+ * window from now.
+ */
 export function sealedSenderEncryptMessage(
   message: Buffer,
   address: ProtocolAddress,
-  senderCert: SenderCertificate,
+  senderCert: SenderCertificate | SenderCertificateProvider,
   sessionStore: SessionStore,
-  identityStore: IdentityKeyStore
+  identityStore: IdentityKeyStore,
+  rng?: Rng
 ): Promise<Buffer> {
+  if (senderCert instanceof SenderCertificateProvider) {
+    return NativeImpl.SealedSender_EncryptMessageWithProvider(
+      address,
+      senderCert,
+      senderCert.source,
+      Date.now(),
+      message,
+      sessionStore,
+      identityStore,
+      rng ?? null,
+      null
+    );
+  }
   return NativeImpl.SealedSender_EncryptMessage(
     address,
     senderCert,
     message,
     sessionStore,
     identityStore,
+    rng ?? null,
     null
   );
 }
@@ -1264,19 +3428,25 @@ export async function sealedSenderDecryptMessage(
   sessionStore: SessionStore,
   identityStore: IdentityKeyStore,
   prekeyStore: PreKeyStore,
-  signedPrekeyStore: SignedPreKeyStore
+  signedPrekeyStore: SignedPreKeyStore,
+  rng?: Rng,
+  gracePeriod = 0,
+  replayCache?: ReplayCache
 ): Promise<SealedSenderDecryptionResult | null> {
   const ssdr = await NativeImpl.SealedSender_DecryptMessage(
     message,
     trustRoot,
     timestamp,
+    gracePeriod,
     localE164,
     localUuid,
     localDeviceId,
     sessionStore,
     identityStore,
     prekeyStore,
-    signedPrekeyStore
+    signedPrekeyStore,
+    replayCache ?? null,
+    rng ?? null
   );
   if (ssdr == null) {
     return null;
@@ -1295,3 +3465,403 @@ export async function sealedSenderDecryptToUsmc(
   );
   return UnidentifiedSenderMessageContent._fromNativeHandle(usmc);
 }
+
+/**
+ * Decrypts like {@link sealedSenderDecryptMessage}, but never writes to the
+ * stores.
+ *
+ * Meant for processes that may not be able to write to the app's database,
+ * such as notification handlers. The store changes are returned in the
+ * result's {@link NotificationDecryptionResult.commitSet} instead.
+ */
+export async function notificationDecrypt(
+  message: Buffer,
+  trustRoot: PublicKey,
+  timestamp: number,
+  localE164: string | null,
+  localUuid: string,
+  localDeviceId: number,
+  sessionStore: SessionStore,
+  identityStore: IdentityKeyStore,
+  prekeyStore: PreKeyStore,
+  signedPrekeyStore: SignedPreKeyStore,
+  rng?: Rng
+): Promise<NotificationDecryptionResult> {
+  const result = await NativeImpl.NotificationDecrypt_Decrypt(
+    message,
+    trustRoot,
+    timestamp,
+    localE164,
+    localUuid,
+    localDeviceId,
+    sessionStore,
+    identityStore,
+    prekeyStore,
+    signedPrekeyStore,
+    rng ?? null,
+    null
+  );
+  return NotificationDecryptionResult._fromNativeHandle(result);
+}
+
+/**
+ * Makes the store changes deferred by {@link notificationDecrypt}.
+ *
+ * Throws without changing anything if one of the affected sessions has changed
+ * since the notification was decrypted; the message should then be decrypted
+ * again.
+ */
+export function applyNotificationCommitSet(
+  commitSet: Buffer,
+  sessionStore: SessionStore,
+  identityStore: IdentityKeyStore,
+  prekeyStore: PreKeyStore
+): Promise<void> {
+  return NativeImpl.NotificationCommitSet_Apply(
+    commitSet,
+    sessionStore,
+    identityStore,
+    prekeyStore,
+    null
+  );
+}
+
+export class KeyTransparencySearchResult {
+  readonly _nativeHandle: Native.KeyTransparencySearchResult;
+
+  private constructor(nativeHandle: Native.KeyTransparencySearchResult) {
+    this._nativeHandle = nativeHandle;
+  }
+
+  static _fromNativeHandle(
+    nativeHandle: Native.KeyTransparencySearchResult
+  ): KeyTransparencySearchResult {
+    return new KeyTransparencySearchResult(nativeHandle);
+  }
+
+  value(): Buffer {
+    return NativeImpl.KeyTransparencySearchResult_GetValue(this);
+  }
+
+  /// The tree head to save and pass to the next verification.
+  treeHead(): Buffer {
+    return NativeImpl.KeyTransparencySearchResult_GetTreeHead(this);
+  }
+
+  /// The state to save and pass to `keyTransparencyVerifyMonitor` for this search key.
+  monitoringData(): Buffer {
+    return NativeImpl.KeyTransparencySearchResult_GetMonitoringData(this);
+  }
+}
+
+export class KeyTransparencyMonitorResult {
+  readonly _nativeHandle: Native.KeyTransparencyMonitorResult;
+
+  private constructor(nativeHandle: Native.KeyTransparencyMonitorResult) {
+    this._nativeHandle = nativeHandle;
+  }
+
+  static _fromNativeHandle(
+    nativeHandle: Native.KeyTransparencyMonitorResult
+  ): KeyTransparencyMonitorResult {
+    return new KeyTransparencyMonitorResult(nativeHandle);
+  }
+
+  /// If false, the search key's value has changed and it should be searched for again.
+  isUnchanged(): boolean {
+    return NativeImpl.KeyTransparencyMonitorResult_IsUnchanged(this);
+  }
+
+  treeHead(): Buffer {
+    return NativeImpl.KeyTransparencyMonitorResult_GetTreeHead(this);
+  }
+
+  monitoringData(): Buffer {
+    return NativeImpl.KeyTransparencyMonitorResult_GetMonitoringData(this);
+  }
+}
+
+/// Verifies a key transparency log's response to a search for `searchKey`.
+///
+/// `lastTreeHead` is the tree head saved from the previous verification, if any. Throws
+/// `KeyTransparencyVerificationError` if the response doesn't check out.
+export function keyTransparencyVerifySearch(
+  logKey: PublicKey,
+  searchKey: Buffer,
+  response: Buffer,
+  lastTreeHead: Buffer | null
+): KeyTransparencySearchResult {
+  return KeyTransparencySearchResult._fromNativeHandle(
+    NativeImpl.KeyTransparency_VerifySearch(
+      logKey,
+      searchKey,
+      response,
+      lastTreeHead
+    )
+  );
+}
+
+export function keyTransparencyVerifyMonitor(
+  logKey: PublicKey,
+  searchKey: Buffer,
+  response: Buffer,
+  lastTreeHead: Buffer | null,
+  monitoringData: Buffer
+): KeyTransparencyMonitorResult {
+  return KeyTransparencyMonitorResult._fromNativeHandle(
+    NativeImpl.KeyTransparency_VerifyMonitor(
+      logKey,
+      searchKey,
+      response,
+      lastTreeHead,
+      monitoringData
+    )
+  );
+}
+
+/// Returns the new tree head to save.
+export function keyTransparencyVerifyDistinguished(
+  logKey: PublicKey,
+  response: Buffer,
+  lastTreeHead: Buffer | null
+): Buffer {
+  return NativeImpl.KeyTransparency_VerifyDistinguished(
+    logKey,
+    response,
+    lastTreeHead
+  );
+}
+
+export const enum PushTokenType {
+  Fcm,
+  Apn,
+}
+
+export const enum VerificationTransport {
+  Sms,
+  Voice,
+}
+
+/// An HTTP request to the chat server, built here so the app only has to send it.
+export class RegistrationRequest {
+  readonly _nativeHandle: Native.RegistrationRequest;
+
+  private constructor(nativeHandle: Native.RegistrationRequest) {
+    this._nativeHandle = nativeHandle;
+  }
+
+  /// `number` is in E.164 format; `mcc` and `mnc` identify the device's network, if known.
+  static createSession(
+    number: string,
+    pushToken?: { token: string; type: PushTokenType },
+    mcc?: string,
+    mnc?: string
+  ): RegistrationRequest {
+    return new RegistrationRequest(
+      NativeImpl.RegistrationRequest_CreateSession(
+        number,
+        pushToken?.token ?? null,
+        pushToken?.type === PushTokenType.Apn,
+        mcc ?? null,
+        mnc ?? null
+      )
+    );
+  }
+
+  static getSession(sessionId: string): RegistrationRequest {
+    return new RegistrationRequest(
+      NativeImpl.RegistrationRequest_GetSession(sessionId)
+    );
+  }
+
+  /// Answers the challenges the session asked for.
+  static updateSession(
+    sessionId: string,
+    answers: { pushChallenge?: string; captcha?: string }
+  ): RegistrationRequest {
+    return new RegistrationRequest(
+      NativeImpl.RegistrationRequest_UpdateSession(
+        sessionId,
+        answers.pushChallenge ?? null,
+        answers.captcha ?? null
+      )
+    );
+  }
+
+  /// `client` names the app's platform, such as "desktop".
+  static requestVerificationCode(
+    sessionId: string,
+    transport: VerificationTransport,
+    client: string
+  ): RegistrationRequest {
+    return new RegistrationRequest(
+      NativeImpl.RegistrationRequest_RequestVerificationCode(
+        sessionId,
+        transport === VerificationTransport.Voice,
+        client
+      )
+    );
+  }
+
+  static submitVerificationCode(
+    sessionId: string,
+    code: string
+  ): RegistrationRequest {
+    return new RegistrationRequest(
+      NativeImpl.RegistrationRequest_SubmitVerificationCode(sessionId, code)
+    );
+  }
+
+  /// `registrationLock` comes from `registrationLockToken`, if the account is locked.
+  static registerAccount(
+    sessionId: string,
+    registrationId: number,
+    fetchesMessages: boolean,
+    registrationLock?: string
+  ): RegistrationRequest {
+    return new RegistrationRequest(
+      NativeImpl.RegistrationRequest_RegisterAccount(
+        sessionId,
+        registrationId,
+        fetchesMessages,
+        registrationLock ?? null
+      )
+    );
+  }
+
+  method(): string {
+    return NativeImpl.RegistrationRequest_GetMethod(this);
+  }
+
+  path(): string {
+    return NativeImpl.RegistrationRequest_GetPath(this);
+  }
+
+  /// The JSON body, if the request has one.
+  body(): Buffer | null {
+    return NativeImpl.RegistrationRequest_GetBody(this);
+  }
+}
+
+/// The server's view of a verification session, as of the last response.
+///
+/// Wait times are in seconds from when the response was received, or null if the action isn't
+/// available at all.
+export class RegistrationSession {
+  readonly _nativeHandle: Native.RegistrationSession;
+
+  private constructor(nativeHandle: Native.RegistrationSession) {
+    this._nativeHandle = nativeHandle;
+  }
+
+  /// Parses the response to any of the session requests.
+  ///
+  /// `retryAfter` is the value of the response's Retry-After header, if any.
+  static fromResponse(
+    status: number,
+    body: Buffer,
+    retryAfter?: string
+  ): RegistrationSession {
+    return new RegistrationSession(
+      NativeImpl.RegistrationSession_FromResponse(
+        status,
+        body,
+        retryAfter ?? null
+      )
+    );
+  }
+
+  id(): string {
+    return NativeImpl.RegistrationSession_GetId(this);
+  }
+
+  nextSmsSeconds(): number | null {
+    return NativeImpl.RegistrationSession_GetNextSmsSeconds(this);
+  }
+
+  nextCallSeconds(): number | null {
+    return NativeImpl.RegistrationSession_GetNextCallSeconds(this);
+  }
+
+  nextVerificationAttemptSeconds(): number | null {
+    return NativeImpl.RegistrationSession_GetNextVerificationAttemptSeconds(
+      this
+    );
+  }
+
+  allowedToRequestCode(): boolean {
+    return NativeImpl.RegistrationSession_GetAllowedToRequestCode(this);
+  }
+
+  requiresPushChallenge(): boolean {
+    return NativeImpl.RegistrationSession_RequiresPushChallenge(this);
+  }
+
+  requiresCaptcha(): boolean {
+    return NativeImpl.RegistrationSession_RequiresCaptcha(this);
+  }
+
+  verified(): boolean {
+    return NativeImpl.RegistrationSession_GetVerified(this);
+  }
+}
+
+/// The result of registering an account: either the new account's identifiers, or the details
+/// needed to get past its registration lock.
+export class RegistrationOutcome {
+  readonly _nativeHandle: Native.RegistrationOutcome;
+
+  private constructor(nativeHandle: Native.RegistrationOutcome) {
+    this._nativeHandle = nativeHandle;
+  }
+
+  /// Parses the response to `RegistrationRequest.registerAccount`.
+  static fromResponse(
+    status: number,
+    body: Buffer,
+    retryAfter?: string
+  ): RegistrationOutcome {
+    return new RegistrationOutcome(
+      NativeImpl.RegistrationOutcome_FromResponse(
+        status,
+        body,
+        retryAfter ?? null
+      )
+    );
+  }
+
+  /// If true, register again with a `registrationLockToken` derived from the master key, which
+  /// can be restored using the SVR credentials.
+  isLocked(): boolean {
+    return NativeImpl.RegistrationOutcome_IsLocked(this);
+  }
+
+  aci(): string | null {
+    return NativeImpl.RegistrationOutcome_GetAci(this);
+  }
+
+  pni(): string | null {
+    return NativeImpl.RegistrationOutcome_GetPni(this);
+  }
+
+  number(): string | null {
+    return NativeImpl.RegistrationOutcome_GetNumber(this);
+  }
+
+  lockTimeRemainingMs(): number {
+    return NativeImpl.RegistrationOutcome_GetLockTimeRemainingMs(this);
+  }
+
+  svrUsername(): string | null {
+    return NativeImpl.RegistrationOutcome_GetSvrUsername(this);
+  }
+
+  svrPassword(): string | null {
+    return NativeImpl.RegistrationOutcome_GetSvrPassword(this);
+  }
+}
+
+/// Derives the registration lock token from the master key restored from secure value recovery.
+export function registrationLockToken(masterKey: Buffer): string {
+  return NativeImpl.Registration_LockToken(masterKey);
+}
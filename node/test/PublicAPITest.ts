@@ -5,10 +5,18 @@
 
 import { assert, use } from 'chai';
 import * as chaiAsPromised from 'chai-as-promised';
+import * as os from 'os';
+import bindings = require('bindings'); // eslint-disable-line @typescript-eslint/no-require-imports
+import * as Native from '../Native';
 import * as SignalClient from '../index';
 
 use(chaiAsPromised);
 
+// For entry points that are only meant for tests.
+const NativeImpl = bindings(
+  'libsignal_client_' + os.platform() + '_' + process.arch
+) as typeof Native;
+
 SignalClient.initLogger(
   SignalClient.LogLevel.Trace,
   (level, target, fileOrNull, lineOrNull, message) => {
@@ -111,10 +119,12 @@ class InMemoryPreKeyStore extends SignalClient.PreKeyStore {
   ): Promise<void> {
     Promise.resolve(this.state.set(id, record.serialize()));
   }
-  async getPreKey(id: number): Promise<SignalClient.PreKeyRecord> {
-    return Promise.resolve(
-      SignalClient.PreKeyRecord.deserialize(this.state.get(id))
-    );
+  async getPreKey(id: number): Promise<SignalClient.PreKeyRecord | null> {
+    const record = this.state.get(id);
+    if (record == null) {
+      return null;
+    }
+    return SignalClient.PreKeyRecord.deserialize(record);
   }
   async removePreKey(id: number): Promise<void> {
     this.state.delete(id);
@@ -130,10 +140,14 @@ class InMemorySignedPreKeyStore extends SignalClient.SignedPreKeyStore {
   ): Promise<void> {
     Promise.resolve(this.state.set(id, record.serialize()));
   }
-  async getSignedPreKey(id: number): Promise<SignalClient.SignedPreKeyRecord> {
-    return Promise.resolve(
-      SignalClient.SignedPreKeyRecord.deserialize(this.state.get(id))
-    );
+  async getSignedPreKey(
+    id: number
+  ): Promise<SignalClient.SignedPreKeyRecord | null> {
+    const record = this.state.get(id);
+    if (record == null) {
+      return null;
+    }
+    return SignalClient.SignedPreKeyRecord.deserialize(record);
   }
 }
 
@@ -160,7 +174,107 @@ class InMemorySenderKeyStore extends SignalClient.SenderKeyStore {
   }
 }
 
+class InMemoryReplayCache extends SignalClient.ReplayCache {
+  private state = new Map<string, number>();
+  async containsMessage(messageId: Buffer, now: number): Promise<boolean> {
+    const expiration = this.state.get(messageId.toString('hex'));
+    return Promise.resolve(expiration != null && expiration >= now);
+  }
+  async recordMessage(messageId: Buffer, expiration: number): Promise<void> {
+    Promise.resolve(this.state.set(messageId.toString('hex'), expiration));
+  }
+}
+
 describe('SignalClient', () => {
+  it('passes the native self-test', () => {
+    const report = SignalClient.selfTest();
+    assert.isTrue(report.passed, JSON.stringify(report));
+    assert.deepEqual(
+      report.checks.map(check => check.name),
+      ['curve_agreement', 'aes_gcm', 'hkdf', 'ratchet_exchange']
+    );
+  });
+  it('throws native panics as InternalErrors', () => {
+    try {
+      NativeImpl.Native_TestPanic('contained panic');
+      assert.fail('should have thrown');
+    } catch (e) {
+      assert.instanceOf(e, Error);
+      const err = e as Error & { functionName: string };
+      assert.equal(err.name, 'InternalError');
+      assert.equal(err.functionName, 'Native_TestPanic');
+      assert.include(err.message, 'contained panic');
+    }
+  });
+  it('lists the native functions', () => {
+    const functions = SignalClient.listNativeFunctions();
+    const byName = new Map(functions.map(info => [info.name, info]));
+    assert.deepEqual(byName.get('Rng_NewSeeded'), {
+      name: 'Rng_NewSeeded',
+      args: ['Buffer'],
+      result: 'Rng',
+      isAsync: false,
+    });
+    assert.include(
+      functions.filter(info => info.isAsync).map(info => info.name),
+      'SessionCipher_EncryptMessage'
+    );
+    assert.isTrue(byName.has('Native_ListFunctions'));
+    assert.deepEqual(byName.get('PrivateKey_Deserialize'), {
+      name: 'PrivateKey_Deserialize',
+      args: ['Buffer'],
+      result: 'PrivateKey',
+      isAsync: false,
+    });
+  });
+  it('reports the native library version', () => {
+    const info = SignalClient.versionInfo();
+    assert.include(info.features, 'node');
+    assert.equal(info.ciphertextMessageVersion, 3);
+    assert.equal(info.sealedSenderVersion, 1);
+    SignalClient.requireAtLeast(info.abiRevision);
+    assert.throws(
+      () => SignalClient.requireAtLeast(info.abiRevision + 1),
+      `is older than the required revision ${info.abiRevision + 1}`
+    );
+  });
+  it('rejects buffers over the configured limit', () => {
+    const previousLimit = SignalClient.getMaxBufferLength();
+    assert.equal(previousLimit, 256 * 1024 * 1024);
+    try {
+      SignalClient.setMaxBufferLength(16);
+      assert.equal(SignalClient.getMaxBufferLength(), 16);
+      try {
+        SignalClient.PublicKey.deserialize(Buffer.alloc(33));
+        assert.fail('should have thrown');
+      } catch (e) {
+        assert.instanceOf(e, SignalClient.BufferTooLargeError);
+        const err = e as SignalClient.BufferTooLargeError;
+        assert.equal(err.length, 33);
+        assert.equal(err.limit, 16);
+      }
+    } finally {
+      SignalClient.setMaxBufferLength(previousLimit);
+    }
+  });
+  it('runs code with a BridgeContext entered', () => {
+    const alice = SignalClient.BridgeContext.new('alice');
+    const bob = SignalClient.BridgeContext.new('bob');
+    alice.setMaxLogLevel(SignalClient.LogLevel.Warn);
+    const key = alice.withEntered(() =>
+      bob.withEntered(() => SignalClient.PrivateKey.generate().getPublicKey())
+    );
+    assert.lengthOf(key.serialize(), 33);
+    assert.throws(
+      () =>
+        alice.withEntered(() => {
+          throw new Error('inner failure');
+        }),
+      'inner failure'
+    );
+    // Tracing isn't enabled, so nothing is recorded.
+    assert.equal(alice.metrics(), '');
+  });
   it('HKDF test vector', () => {
     const hkdf = SignalClient.HKDF.new(3);
 
@@ -193,6 +307,89 @@ describe('SignalClient', () => {
     assert.deepEqual(addr.name(), 'name');
     assert.deepEqual(addr.deviceId(), 42);
   });
+  it('seeded Rng is deterministic', () => {
+    const seed = Buffer.alloc(32, 7);
+    const key1 = SignalClient.PrivateKey.generate(
+      SignalClient.Rng.seeded(seed)
+    );
+    const key2 = SignalClient.PrivateKey.generate(
+      SignalClient.Rng.seeded(seed)
+    );
+    assert.deepEqual(key1.serialize(), key2.serialize());
+
+    const rng = SignalClient.Rng.seeded(seed);
+    const key3 = SignalClient.PrivateKey.generate(rng);
+    const key4 = SignalClient.PrivateKey.generate(rng);
+    assert.deepEqual(key3.serialize(), key1.serialize());
+    assert.notDeepEqual(key4.serialize(), key1.serialize());
+
+    assert.throws(
+      () => SignalClient.Rng.seeded(Buffer.alloc(16)),
+      'seed must be 32 bytes (got 16)'
+    );
+  });
+  it('cloned Rng shares state with the original', () => {
+    const seed = Buffer.alloc(32, 7);
+    const expected = SignalClient.Rng.seeded(seed);
+    const key1 = SignalClient.PrivateKey.generate(expected);
+    const key2 = SignalClient.PrivateKey.generate(expected);
+
+    const rng = SignalClient.Rng.seeded(seed);
+    const clone = rng.clone();
+    assert.deepEqual(
+      SignalClient.PrivateKey.generate(rng).serialize(),
+      key1.serialize()
+    );
+    assert.deepEqual(
+      SignalClient.PrivateKey.generate(clone).serialize(),
+      key2.serialize()
+    );
+  });
+  it('transfers keys between contexts', () => {
+    const key = SignalClient.PrivateKey.generate();
+    const token = key.transferToWorker();
+    assert.typeOf(token, 'number');
+    const received = SignalClient.PrivateKey.receiveTransferred(token);
+    assert.deepEqual(received.serialize(), key.serialize());
+    assert.deepEqual(
+      SignalClient.PublicKey.receiveTransferred(
+        key.getPublicKey().transferToWorker()
+      ).serialize(),
+      key.getPublicKey().serialize()
+    );
+    assert.throws(
+      () => SignalClient.PrivateKey.receiveTransferred(token),
+      'no handle is waiting to be received'
+    );
+  });
+  it('derives access keys and blinded service IDs', () => {
+    const profileKey = Buffer.from(Array.from({ length: 32 }, (_, i) => i));
+    assert.equal(
+      SignalClient.deriveAccessKey(profileKey).toString('hex'),
+      '0ebcb5deb52c83bd08a8a935182c9199'
+    );
+    assert.equal(
+      SignalClient.blindServiceId(
+        profileKey,
+        '9d0652a3-dcc3-4d11-975f-74d61598733f'
+      ).toString('hex'),
+      '81be8025bf77f56435df7ab967647c5e0ce8c92232483d053ac1e0fc3fb6e81b'
+    );
+    assert.throws(() => SignalClient.deriveAccessKey(Buffer.alloc(16)));
+  });
+  it('sealed box', () => {
+    const key = SignalClient.PrivateKey.generate();
+    const message = Buffer.from('transfer archive key');
+
+    const sealed = key.getPublicKey().seal(message);
+    assert.equal(sealed.length, message.length + 65);
+    assert.deepEqual(key.open(sealed), message);
+
+    const tampered = Buffer.from(sealed);
+    tampered[40] ^= 1;
+    assert.throws(() => key.open(tampered));
+    assert.throws(() => SignalClient.PrivateKey.generate().open(sealed));
+  });
   it('SenderKeyName', () => {
     const addr = SignalClient.SenderKeyName.new('group', 'sender', 42);
     assert.deepEqual(addr.groupId(), 'group');
@@ -311,6 +508,7 @@ describe('SignalClient', () => {
     assert.deepEqual(senderCert.senderUuid(), senderUuid);
     assert.deepEqual(senderCert.senderE164(), senderE164);
     assert.deepEqual(senderCert.senderDeviceId(), senderDeviceId);
+    assert.deepEqual(senderCert.expiration(), new Date(expiration));
 
     const senderCertFromBytes = SignalClient.SenderCertificate.deserialize(
       senderCert.serialize()
@@ -319,6 +517,22 @@ describe('SignalClient', () => {
 
     assert(senderCert.validate(trustRoot.getPublicKey(), expiration - 1000));
     assert(!senderCert.validate(trustRoot.getPublicKey(), expiration + 10)); // expired
+    assert(
+      senderCert.validateWithGracePeriod(
+        trustRoot.getPublicKey(),
+        expiration + 10,
+        10
+      )
+    );
+    assert.throws(
+      () =>
+        senderCert.validateWithGracePeriod(
+          trustRoot.getPublicKey(),
+          expiration + 11,
+          10
+        ),
+      SignalClient.SealedSenderCertificateExpiredError
+    );
   });
   it('SenderKeyMessage', () => {
     const keyId = 9;
@@ -400,6 +614,218 @@ describe('SignalClient', () => {
 
       assert.deepEqual(message, bPtext);
     });
+    it('tracks which members have the sender key', async () => {
+      const senderKeyName = SignalClient.SenderKeyName.new(
+        'group',
+        'sender',
+        1
+      );
+      const bob = SignalClient.ProtocolAddress.new('bob', 1);
+      const carol = SignalClient.ProtocolAddress.new('carol', 2);
+      const aSenderKeyStore = new InMemorySenderKeyStore();
+      await SignalClient.SenderKeyDistributionMessage.create(
+        senderKeyName,
+        aSenderKeyStore
+      );
+      const record = await aSenderKeyStore.getSenderKey(senderKeyName);
+      assert(record !== null);
+
+      assert(record.needsDistribution(bob));
+      record.markDistributed(bob);
+      record.markDistributed(carol);
+      assert(!record.needsDistribution(bob));
+
+      const reloaded = SignalClient.SenderKeyRecord.deserialize(
+        record.serialize()
+      );
+      assert(!reloaded.needsDistribution(carol));
+      assert(reloaded.forgetDistribution(carol));
+      assert(reloaded.needsDistribution(carol));
+      assert(!reloaded.forgetDistribution(carol));
+
+      assert.throws(() =>
+        SignalClient.SenderKeyRecord.new().markDistributed(bob)
+      );
+    });
+    it('can fast-forward past skipped messages', async () => {
+      const senderKeyName = SignalClient.SenderKeyName.new(
+        'group',
+        'sender',
+        1
+      );
+      const aSenderKeyStore = new InMemorySenderKeyStore();
+      const skdm = await SignalClient.SenderKeyDistributionMessage.create(
+        senderKeyName,
+        aSenderKeyStore
+      );
+
+      const bSenderKeyStore = new InMemorySenderKeyStore();
+      await SignalClient.processSenderKeyDistributionMessage(
+        senderKeyName,
+        skdm,
+        bSenderKeyStore
+      );
+
+      const message = Buffer.from('0a0b0c', 'hex');
+      const aCtexts: Buffer[] = [];
+      for (let i = 0; i < 3; i++) {
+        aCtexts.push(
+          await SignalClient.groupEncrypt(
+            senderKeyName,
+            aSenderKeyStore,
+            message
+          )
+        );
+      }
+
+      assert.equal(
+        await SignalClient.groupChainPosition(
+          senderKeyName,
+          bSenderKeyStore,
+          skdm.id()
+        ),
+        0
+      );
+      await SignalClient.groupFastForward(
+        senderKeyName,
+        bSenderKeyStore,
+        skdm.id(),
+        2
+      );
+      assert.equal(
+        await SignalClient.groupChainPosition(
+          senderKeyName,
+          bSenderKeyStore,
+          skdm.id()
+        ),
+        2
+      );
+
+      const bPtext = await SignalClient.groupDecrypt(
+        senderKeyName,
+        bSenderKeyStore,
+        aCtexts[2]
+      );
+      assert.deepEqual(message, bPtext);
+    });
+    it('reports a missing session with the group and sender', async () => {
+      const senderKeyName = SignalClient.SenderKeyName.new(
+        'group',
+        'sender',
+        1
+      );
+      const aSenderKeyStore = new InMemorySenderKeyStore();
+      await SignalClient.SenderKeyDistributionMessage.create(
+        senderKeyName,
+        aSenderKeyStore
+      );
+      const aCtext = await SignalClient.groupEncrypt(
+        senderKeyName,
+        aSenderKeyStore,
+        Buffer.from('0a0b0c', 'hex')
+      );
+
+      try {
+        await SignalClient.groupDecrypt(
+          senderKeyName,
+          new InMemorySenderKeyStore(),
+          aCtext
+        );
+        assert.fail('should have thrown');
+      } catch (e) {
+        assert.instanceOf(e, SignalClient.InvalidSenderKeySessionError);
+        assert.equal(e.groupId, 'group');
+        assert.equal(e.addressName, 'sender');
+        assert.equal(e.deviceId, 1);
+        assert.equal(
+          SignalClient.classifyDecryptionFailure(e),
+          'NeedsNewSession'
+        );
+      }
+    });
+    it('counts decryptions by result class', async () => {
+      const senderKeyName = SignalClient.SenderKeyName.new(
+        'group',
+        'sender',
+        1
+      );
+      const aSenderKeyStore = new InMemorySenderKeyStore();
+      const skdm = await SignalClient.SenderKeyDistributionMessage.create(
+        senderKeyName,
+        aSenderKeyStore
+      );
+      const bSenderKeyStore = new InMemorySenderKeyStore();
+      await SignalClient.processSenderKeyDistributionMessage(
+        senderKeyName,
+        skdm,
+        bSenderKeyStore
+      );
+      const aCtext = await SignalClient.groupEncrypt(
+        senderKeyName,
+        aSenderKeyStore,
+        Buffer.from('0a0b0c', 'hex')
+      );
+
+      SignalClient.resetDecryptionMetrics();
+      SignalClient.setDecryptionMetricsEnabled(true);
+      try {
+        await SignalClient.groupDecrypt(senderKeyName, bSenderKeyStore, aCtext);
+        try {
+          await SignalClient.groupDecrypt(
+            senderKeyName,
+            new InMemorySenderKeyStore(),
+            aCtext
+          );
+          assert.fail('should have thrown');
+        } catch (e) {
+          assert.instanceOf(e, SignalClient.InvalidSenderKeySessionError);
+        }
+      } finally {
+        SignalClient.setDecryptionMetricsEnabled(false);
+      }
+
+      const metrics = SignalClient.decryptionMetrics().split('\n');
+      assert.include(metrics, 'success count=1');
+      assert.include(metrics, 'no_session count=1');
+      assert.include(metrics, 'invalid_mac count=0');
+
+      SignalClient.resetDecryptionMetrics();
+      assert.include(
+        SignalClient.decryptionMetrics().split('\n'),
+        'success count=0'
+      );
+    });
+    it('reports new sender keys to the audit hook', async () => {
+      const senderKeyName = SignalClient.SenderKeyName.new(
+        'group',
+        'sender',
+        1
+      );
+      const aSenderKeyStore = new InMemorySenderKeyStore();
+
+      const events: [number, string, number, string | null][] = [];
+      SignalClient.setAuditHook((kind, addressName, deviceId, groupId) => {
+        events.push([kind, addressName, deviceId, groupId]);
+      });
+      try {
+        await SignalClient.SenderKeyDistributionMessage.create(
+          senderKeyName,
+          aSenderKeyStore
+        );
+        await SignalClient.SenderKeyDistributionMessage.create(
+          senderKeyName,
+          aSenderKeyStore
+        );
+        // Events are delivered on a later turn of the event loop.
+        await new Promise(resolve => setImmediate(resolve));
+      } finally {
+        SignalClient.setAuditHook(null);
+      }
+
+      assert.deepEqual(events, [
+        [SignalClient.AuditEventKind.SenderKeyCreated, 'sender', 1, 'group'],
+      ]);
+    });
     it("does not panic if there's an error", async () => {
       const senderKeyName = SignalClient.SenderKeyName.new(
         'group',
@@ -420,6 +846,37 @@ describe('SignalClient', () => {
       );
       await assert.isRejected(messagePromise2, TypeError);
     });
+    it('limits concurrent operations', async () => {
+      const senderKeyName = SignalClient.SenderKeyName.new(
+        'group',
+        'sender',
+        1
+      );
+      const aSenderKeyStore = new InMemorySenderKeyStore();
+      const create = () =>
+        SignalClient.SenderKeyDistributionMessage.create(
+          senderKeyName,
+          aSenderKeyStore
+        );
+
+      try {
+        SignalClient.initRuntime({ maxInFlight: 1 });
+        const first = create();
+        await assert.isRejected(create(), 'too many async operations');
+        await first;
+
+        SignalClient.initRuntime({
+          maxInFlight: 1,
+          queueWhenBusy: true,
+          maxQueued: 1,
+        });
+        const queued = [create(), create()];
+        await assert.isRejected(create(), 'too many async operations');
+        await Promise.all(queued);
+      } finally {
+        SignalClient.initRuntime({});
+      }
+    });
   });
   it('PublicKeyBundle', () => {
     const registrationId = 5;
@@ -473,6 +930,33 @@ describe('SignalClient', () => {
     assert.deepEqual(pkb2.signedPreKeyPublic(), signedPrekey);
     assert.deepEqual(pkb2.signedPreKeySignature(), signedPrekeySignature);
     assert.deepEqual(pkb2.identityKey(), identityKey);
+
+    for (const bundle of [pkb, pkb2.withSessionVersions(3, 4)]) {
+      const transported = SignalClient.PreKeyBundle.deserializeFromTransport(
+        bundle.serializeForTransport()
+      );
+      assert.deepEqual(transported.registrationId(), bundle.registrationId());
+      assert.deepEqual(transported.deviceId(), bundle.deviceId());
+      assert.deepEqual(transported.preKeyId(), bundle.preKeyId());
+      assert.deepEqual(transported.preKeyPublic(), bundle.preKeyPublic());
+      assert.deepEqual(transported.signedPreKeyId(), bundle.signedPreKeyId());
+      assert.deepEqual(
+        transported.signedPreKeySignature(),
+        bundle.signedPreKeySignature()
+      );
+      assert.deepEqual(transported.identityKey(), bundle.identityKey());
+      assert.deepEqual(
+        transported.maxSessionVersion(),
+        bundle.maxSessionVersion()
+      );
+    }
+    assert.throws(
+      () =>
+        SignalClient.PreKeySignalMessage.deserializeFromTransport(
+          pkb.serializeForTransport()
+        ),
+      'expected PreKeySignalMessage transport data'
+    );
   });
   it('PreKeyRecord', () => {
     const privKey = SignalClient.PrivateKey.generate();
@@ -512,6 +996,19 @@ describe('SignalClient', () => {
       spkr.serialize()
     );
     assert.deepEqual(spkrFromBytes, spkr);
+
+    // A timestamp in milliseconds mistakenly scaled by 1000 again.
+    assert.throws(
+      () =>
+        SignalClient.SignedPreKeyRecord.new(
+          keyId,
+          Date.now() * 1000,
+          pubKey,
+          privKey,
+          signature
+        ),
+      RangeError
+    );
   });
   it('SenderKeyRecord', () => {
     const skr = SignalClient.SenderKeyRecord.new();
@@ -520,6 +1017,16 @@ describe('SignalClient', () => {
     );
     assert.deepEqual(skr, skrFromBytes);
   });
+  it('exposes record limits', () => {
+    assert.isAbove(SignalClient.SessionRecord.maxArchivedStates(), 0);
+    assert.isAbove(SignalClient.SessionRecord.maxReceiverChains(), 0);
+    assert.isAbove(SignalClient.SessionRecord.maxMessageKeysPerChain(), 0);
+    assert.isAbove(SignalClient.SenderKeyRecord.maxStates(), 0);
+    assert.equal(
+      SignalClient.SenderKeyRecord.maxMessageKeysPerState(),
+      SignalClient.SessionRecord.maxMessageKeysPerChain()
+    );
+  });
   it('SignalMessage and PreKeySignalMessage', () => {
     const messageVersion = 2;
     const macKey = Buffer.alloc(32, 0xab);
@@ -576,6 +1083,28 @@ describe('SignalClient', () => {
 
     assert.deepEqual(pkm2.serialize(), pkm_bytes);
   });
+  it('summarizes a conversation with no session', async () => {
+    const address = SignalClient.ProtocolAddress.new('+19192222222', 1);
+    const senderKeyName = SignalClient.SenderKeyName.new(
+      'group',
+      '+14151111111',
+      1
+    );
+    const snapshot = JSON.parse(
+      await SignalClient.securitySnapshot(
+        address,
+        senderKeyName,
+        new InMemorySessionStore(),
+        new InMemoryIdentityKeyStore(),
+        new InMemorySenderKeyStore()
+      )
+    );
+    assert.equal(snapshot.name, '+19192222222');
+    assert.equal(snapshot.device_id, 1);
+    assert.isNull(snapshot.identity_fingerprint);
+    assert.isNull(snapshot.session);
+    assert.deepEqual(snapshot.sender_key_distributions, []);
+  });
   it('BasicPreKeyMessaging', async () => {
     // basic_prekey_v3 in Rust
     const aKeys = new InMemoryIdentityKeyStore();
@@ -648,6 +1177,9 @@ describe('SignalClient', () => {
       aCiphertext.type(),
       SignalClient.CiphertextMessageType.PreKey
     );
+    assert.deepEqual(aCiphertext.counter(), 0);
+    assert.deepEqual(aCiphertext.previousCounter(), 0);
+    assert.deepEqual(aCiphertext.messageVersion(), 3);
 
     const aCiphertextR = SignalClient.PreKeySignalMessage.deserialize(
       aCiphertext.serialize()
@@ -693,6 +1225,75 @@ describe('SignalClient', () => {
 
     assert.deepEqual(aDPlaintext, bMessage);
 
+    try {
+      await SignalClient.signalDecrypt(bCiphertextR, bAddress, aSess, aKeys);
+      assert.fail('should have thrown');
+    } catch (e) {
+      assert.instanceOf(e, SignalClient.DuplicatedMessageError);
+      const err = e as SignalClient.DuplicatedMessageError;
+      assert.equal(err.chainIndex, 1);
+      assert.equal(err.counter, 0);
+    }
+
+    const encryptBatch = async (addresses: SignalClient.ProtocolAddress[]) =>
+      (
+        await SignalClient.signalEncryptBatch(bMessage, addresses, bSess, bKeys)
+      ).map(result => {
+        if (result.message == null) {
+          throw result.error;
+        }
+        return result.message;
+      });
+
+    const bBatchCiphertexts = await encryptBatch([aAddress, aAddress]);
+    assert.deepEqual(bBatchCiphertexts.length, 2);
+    for (const ciphertext of bBatchCiphertexts) {
+      const plaintext = await SignalClient.signalDecrypt(
+        SignalClient.SignalMessage.deserialize(ciphertext.serialize()),
+        bAddress,
+        aSess,
+        aKeys
+      );
+      assert.deepEqual(plaintext, bMessage);
+    }
+
+    const unknownAddress = SignalClient.ProtocolAddress.new('+19195550123', 1);
+    const [bIsolated, bMissing] = await SignalClient.signalEncryptBatch(
+      bMessage,
+      [aAddress, unknownAddress],
+      bSess,
+      bKeys
+    );
+    assert.isNull(bIsolated.error);
+    assert.isNull(bMissing.message);
+    assert.instanceOf(bMissing.error, Error);
+    if (bIsolated.message != null) {
+      const plaintext = await SignalClient.signalDecrypt(
+        SignalClient.SignalMessage.deserialize(bIsolated.message.serialize()),
+        bAddress,
+        aSess,
+        aKeys
+      );
+      assert.deepEqual(plaintext, bMessage);
+    }
+
+    const bBacklog = await encryptBatch([aAddress, aAddress, aAddress]);
+    const decryptor = SignalClient.BacklogDecryptor.new(2);
+    const aBacklogPlaintexts = await decryptor.decrypt(
+      [...bBacklog, bBacklog[0]],
+      [bAddress, bAddress, bAddress, bAddress],
+      aSess,
+      aKeys,
+      new InMemoryPreKeyStore(),
+      new InMemorySignedPreKeyStore()
+    );
+    assert.deepEqual(aBacklogPlaintexts.slice(0, 3), [
+      bMessage,
+      bMessage,
+      bMessage,
+    ]);
+    assert.instanceOf(aBacklogPlaintexts[3], Error);
+
     const session = await bSess.getSession(aAddress);
 
     if (session != null) {
@@ -704,6 +1305,114 @@ describe('SignalClient', () => {
       assert(!session.hasCurrentState());
     }
   });
+  it('negotiates the session version from the pre-key bundle', async () => {
+    const aKeys = new InMemoryIdentityKeyStore();
+    const bKeys = new InMemoryIdentityKeyStore();
+
+    const bSPreKey = SignalClient.PrivateKey.generate();
+    const bIdentityKey = await bKeys.getIdentityKey();
+    const bAddress = SignalClient.ProtocolAddress.new('+19192222222', 1);
+    const bPreKeyBundle = SignalClient.PreKeyBundle.new(
+      await bKeys.getLocalRegistrationId(),
+      bAddress.deviceId(),
+      null,
+      null,
+      22,
+      bSPreKey.getPublicKey(),
+      bIdentityKey.sign(bSPreKey.getPublicKey().serialize()),
+      bIdentityKey.getPublicKey()
+    );
+    assert.deepEqual(bPreKeyBundle.minSessionVersion(), 3);
+    assert.deepEqual(bPreKeyBundle.maxSessionVersion(), 3);
+    assert.throws(() => bPreKeyBundle.withSessionVersions(4, 3));
+
+    // A peer that also supports a newer version gets the newest one we support.
+    const newerBundle = bPreKeyBundle.withSessionVersions(3, 4);
+    assert.deepEqual(newerBundle.minSessionVersion(), 3);
+    assert.deepEqual(newerBundle.maxSessionVersion(), 4);
+
+    const aSess = new InMemorySessionStore();
+    await SignalClient.processPreKeyBundle(newerBundle, bAddress, aSess, aKeys);
+    const session = await aSess.getSession(bAddress);
+    assert.isNotNull(session);
+    assert.deepEqual(session?.sessionVersion(), 3);
+    session?.archiveCurrentState();
+    assert.deepEqual(session?.sessionVersion(), 0);
+
+    // A peer that only supports newer versions is rejected.
+    const tooNewSess = new InMemorySessionStore();
+    await assert.isRejected(
+      SignalClient.processPreKeyBundle(
+        bPreKeyBundle.withSessionVersions(4, 5),
+        bAddress,
+        tooNewSess,
+        aKeys
+      )
+    );
+    assert.isNull(await tooNewSess.getSession(bAddress));
+  });
+  it('caches sessions natively when enabled', async () => {
+    class CountingSessionStore extends InMemorySessionStore {
+      loads = 0;
+      async getSession(
+        name: SignalClient.ProtocolAddress
+      ): Promise<SignalClient.SessionRecord | null> {
+        this.loads += 1;
+        return super.getSession(name);
+      }
+    }
+
+    const aKeys = new InMemoryIdentityKeyStore();
+    const bKeys = new InMemoryIdentityKeyStore();
+    const aSess = new CountingSessionStore();
+
+    const bPreKey = SignalClient.PrivateKey.generate();
+    const bSPreKey = SignalClient.PrivateKey.generate();
+    const bIdentityKey = await bKeys.getIdentityKey();
+    const bAddress = SignalClient.ProtocolAddress.new('+19192222222', 1);
+    const bPreKeyBundle = SignalClient.PreKeyBundle.new(
+      await bKeys.getLocalRegistrationId(),
+      bAddress.deviceId(),
+      31337,
+      bPreKey.getPublicKey(),
+      22,
+      bSPreKey.getPublicKey(),
+      bIdentityKey.sign(bSPreKey.getPublicKey().serialize()),
+      bIdentityKey.getPublicKey()
+    );
+    const message = Buffer.from('cached', 'utf8');
+
+    aSess.enableSessionCache(10);
+    await SignalClient.processPreKeyBundle(
+      bPreKeyBundle,
+      bAddress,
+      aSess,
+      aKeys
+    );
+    const loadsAfterSetup = aSess.loads;
+
+    // The session saved by processPreKeyBundle is served from the cache.
+    await SignalClient.signalEncrypt(message, bAddress, aSess, aKeys);
+    await SignalClient.signalEncrypt(message, bAddress, aSess, aKeys);
+    assert.equal(aSess.loads, loadsAfterSetup);
+
+    aSess.invalidateCachedSession(bAddress);
+    await SignalClient.signalEncrypt(message, bAddress, aSess, aKeys);
+    assert.equal(aSess.loads, loadsAfterSetup + 1);
+
+    // Another store doesn't see this store's cached session.
+    const otherSess = new CountingSessionStore();
+    otherSess.enableSessionCache(10);
+    await assert.isRejected(
+      SignalClient.signalEncrypt(message, bAddress, otherSess, aKeys),
+      'not found'
+    );
+    assert.equal(otherSess.loads, 1);
+
+    aSess.clearSessionCache();
+    await SignalClient.signalEncrypt(message, bAddress, aSess, aKeys);
+    assert.equal(aSess.loads, loadsAfterSetup + 2);
+  });
   it('SealedSender', async () => {
     const aKeys = new InMemoryIdentityKeyStore();
     const bKeys = new InMemoryIdentityKeyStore();
@@ -802,6 +1511,7 @@ describe('SignalClient', () => {
       aKeys
     );
 
+    const bReplayCache = new InMemoryReplayCache();
     const bPlaintext = await SignalClient.sealedSenderDecryptMessage(
       aCiphertext,
       trustRoot.getPublicKey(),
@@ -812,7 +1522,10 @@ describe('SignalClient', () => {
       bSess,
       bKeys,
       bPreK,
-      bSPreK
+      bSPreK,
+      undefined,
+      0,
+      bReplayCache
     );
 
     assert(bPlaintext != null);
@@ -823,30 +1536,827 @@ describe('SignalClient', () => {
       assert.deepEqual(bPlaintext.senderUuid(), aUuid);
       assert.deepEqual(bPlaintext.deviceId(), aDeviceId);
     }
-  });
-  it('AES-GCM-SIV test vector', () => {
-    // RFC 8452, appendix C.2
-    const key = Buffer.from(
-      '0100000000000000000000000000000000000000000000000000000000000000',
-      'hex'
+
+    await assert.isRejected(
+      SignalClient.sealedSenderDecryptMessage(
+        aCiphertext,
+        trustRoot.getPublicKey(),
+        43, // timestamp,
+        bE164,
+        bUuid,
+        bDeviceId,
+        bSess,
+        bKeys,
+        bPreK,
+        bSPreK,
+        undefined,
+        0,
+        bReplayCache
+      ),
+      SignalClient.SealedSenderReplayError
     );
+  });
+  it('SenderCertificateProvider refreshes before expiration', async () => {
+    const aKeys = new InMemoryIdentityKeyStore();
+    const bKeys = new InMemoryIdentityKeyStore();
 
-    const aes_gcm_siv = SignalClient.Aes256GcmSiv.new(key);
+    const aSess = new InMemorySessionStore();
+    const bSess = new InMemorySessionStore();
 
-    const nonce = Buffer.from('030000000000000000000000', 'hex');
-    const aad = Buffer.from('010000000000000000000000', 'hex');
-    const ptext = Buffer.from('02000000', 'hex');
+    const bPreK = new InMemoryPreKeyStore();
+    const bSPreK = new InMemorySignedPreKeyStore();
 
-    const ctext = aes_gcm_siv.encrypt(ptext, nonce, aad);
+    const bPreKey = SignalClient.PrivateKey.generate();
+    const bSPreKey = SignalClient.PrivateKey.generate();
 
-    assert.deepEqual(
-      ctext.toString('hex'),
-      '22b3f4cd1835e517741dfddccfa07fa4661b74cf'
-    );
+    const aIdentityKey = await aKeys.getIdentityKey();
+    const bIdentityKey = await bKeys.getIdentityKey();
 
-    const decrypted = aes_gcm_siv.decrypt(ctext, nonce, aad);
+    const aDeviceId = 1;
+    const bDeviceId = 3;
 
-    assert.deepEqual(decrypted.toString('hex'), '02000000');
+    const aUuid = '9d0652a3-dcc3-4d11-975f-74d61598733f';
+    const bUuid = '796abedb-ca4e-4f18-8803-1fde5b921f9f';
+
+    const trustRoot = SignalClient.PrivateKey.generate();
+    const serverKey = SignalClient.PrivateKey.generate();
+
+    const serverCert = SignalClient.ServerCertificate.new(
+      1,
+      serverKey.getPublicKey(),
+      trustRoot
+    );
+    const makeSenderCert = (expires: number) =>
+      SignalClient.SenderCertificate.new(
+        aUuid,
+        null,
+        aDeviceId,
+        aIdentityKey.getPublicKey(),
+        expires,
+        serverCert,
+        serverKey
+      );
+
+    class QueuedCertificateSource extends SignalClient.SenderCertificateSource {
+      fetches = 0;
+      constructor(private certificates: SignalClient.SenderCertificate[]) {
+        super();
+      }
+      async fetchSenderCertificate(): Promise<SignalClient.SenderCertificate> {
+        this.fetches += 1;
+        const certificate = this.certificates.shift();
+        if (certificate == null) {
+          throw new Error('no certificates left');
+        }
+        return certificate;
+      }
+    }
+
+    const now = Date.now();
+    const hour = 60 * 60 * 1000;
+    const source = new QueuedCertificateSource([
+      makeSenderCert(now + 24 * hour),
+    ]);
+    const provider = SignalClient.SenderCertificateProvider.new(
+      source,
+      hour,
+      makeSenderCert(now + 30 * 60 * 1000)
+    );
+
+    // Well before expiration, the cached certificate is used as is.
+    assert.deepEqual(
+      (await provider.get(now - hour)).expiration(),
+      new Date(now + 30 * 60 * 1000)
+    );
+    assert.equal(source.fetches, 0);
+
+    const bPreKeyId = 31337;
+    const bSignedPreKeyId = 22;
+    const bSignedPreKeySig = bIdentityKey.sign(
+      bSPreKey.getPublicKey().serialize()
+    );
+
+    const bPreKeyBundle = SignalClient.PreKeyBundle.new(
+      await bKeys.getLocalRegistrationId(),
+      bDeviceId,
+      bPreKeyId,
+      bPreKey.getPublicKey(),
+      bSignedPreKeyId,
+      bSPreKey.getPublicKey(),
+      bSignedPreKeySig,
+      bIdentityKey.getPublicKey()
+    );
+    await bPreK.savePreKey(
+      bPreKeyId,
+      SignalClient.PreKeyRecord.new(bPreKeyId, bPreKey.getPublicKey(), bPreKey)
+    );
+    await bSPreK.saveSignedPreKey(
+      bSignedPreKeyId,
+      SignalClient.SignedPreKeyRecord.new(
+        bSignedPreKeyId,
+        42, // timestamp
+        bSPreKey.getPublicKey(),
+        bSPreKey,
+        bSignedPreKeySig
+      )
+    );
+
+    const bAddress = SignalClient.ProtocolAddress.new(bUuid, bDeviceId);
+    await SignalClient.processPreKeyBundle(
+      bPreKeyBundle,
+      bAddress,
+      aSess,
+      aKeys
+    );
+
+    // Within the freshness window, encrypting fetches a new certificate first.
+    const aPlaintext = Buffer.from('hi there', 'utf8');
+    const aCiphertext = await SignalClient.sealedSenderEncryptMessage(
+      aPlaintext,
+      bAddress,
+      provider,
+      aSess,
+      aKeys
+    );
+    assert.equal(source.fetches, 1);
+
+    const bPlaintext = await SignalClient.sealedSenderDecryptMessage(
+      aCiphertext,
+      trustRoot.getPublicKey(),
+      now + 12 * hour,
+      null,
+      bUuid,
+      bDeviceId,
+      bSess,
+      bKeys,
+      bPreK,
+      bSPreK
+    );
+    assert(bPlaintext != null);
+    assert.deepEqual(bPlaintext?.message(), aPlaintext);
+  });
+  it('notification decryption defers store changes', async () => {
+    const aKeys = new InMemoryIdentityKeyStore();
+    const bKeys = new InMemoryIdentityKeyStore();
+
+    const aSess = new InMemorySessionStore();
+    const bSess = new InMemorySessionStore();
+
+    const bPreK = new InMemoryPreKeyStore();
+    const bSPreK = new InMemorySignedPreKeyStore();
+
+    const bPreKey = SignalClient.PrivateKey.generate();
+    const bSPreKey = SignalClient.PrivateKey.generate();
+
+    const aIdentityKey = await aKeys.getIdentityKey();
+    const bIdentityKey = await bKeys.getIdentityKey();
+
+    const aDeviceId = 1;
+    const bDeviceId = 3;
+
+    const aUuid = '9d0652a3-dcc3-4d11-975f-74d61598733f';
+    const bUuid = '796abedb-ca4e-4f18-8803-1fde5b921f9f';
+
+    const trustRoot = SignalClient.PrivateKey.generate();
+    const serverKey = SignalClient.PrivateKey.generate();
+
+    const serverCert = SignalClient.ServerCertificate.new(
+      1,
+      serverKey.getPublicKey(),
+      trustRoot
+    );
+    const senderCert = SignalClient.SenderCertificate.new(
+      aUuid,
+      null,
+      aDeviceId,
+      aIdentityKey.getPublicKey(),
+      1605722925, // expires
+      serverCert,
+      serverKey
+    );
+
+    const bPreKeyId = 31337;
+    const bSignedPreKeyId = 22;
+    const bSignedPreKeySig = bIdentityKey.sign(
+      bSPreKey.getPublicKey().serialize()
+    );
+
+    const bPreKeyBundle = SignalClient.PreKeyBundle.new(
+      await bKeys.getLocalRegistrationId(),
+      bDeviceId,
+      bPreKeyId,
+      bPreKey.getPublicKey(),
+      bSignedPreKeyId,
+      bSPreKey.getPublicKey(),
+      bSignedPreKeySig,
+      bIdentityKey.getPublicKey()
+    );
+    await bPreK.savePreKey(
+      bPreKeyId,
+      SignalClient.PreKeyRecord.new(bPreKeyId, bPreKey.getPublicKey(), bPreKey)
+    );
+    await bSPreK.saveSignedPreKey(
+      bSignedPreKeyId,
+      SignalClient.SignedPreKeyRecord.new(
+        bSignedPreKeyId,
+        42, // timestamp
+        bSPreKey.getPublicKey(),
+        bSPreKey,
+        bSignedPreKeySig
+      )
+    );
+
+    const aAddress = SignalClient.ProtocolAddress.new(aUuid, aDeviceId);
+    const bAddress = SignalClient.ProtocolAddress.new(bUuid, bDeviceId);
+    await SignalClient.processPreKeyBundle(
+      bPreKeyBundle,
+      bAddress,
+      aSess,
+      aKeys
+    );
+
+    const aPlaintext = Buffer.from('hi there', 'utf8');
+    const aCiphertext = await SignalClient.sealedSenderEncryptMessage(
+      aPlaintext,
+      bAddress,
+      senderCert,
+      aSess,
+      aKeys
+    );
+
+    const result = await SignalClient.notificationDecrypt(
+      aCiphertext,
+      trustRoot.getPublicKey(),
+      43, // timestamp
+      null,
+      bUuid,
+      bDeviceId,
+      bSess,
+      bKeys,
+      bPreK,
+      bSPreK
+    );
+    assert.deepEqual(result.message(), aPlaintext);
+    assert.isNull(result.senderE164());
+    assert.deepEqual(result.senderUuid(), aUuid);
+    assert.deepEqual(result.deviceId(), aDeviceId);
+
+    assert.isNull(await bSess.getSession(aAddress));
+    assert.isNotNull(await bPreK.getPreKey(bPreKeyId));
+
+    await SignalClient.applyNotificationCommitSet(
+      result.commitSet(),
+      bSess,
+      bKeys,
+      bPreK
+    );
+    assert.isNotNull(await bSess.getSession(aAddress));
+    assert.isNull(await bPreK.getPreKey(bPreKeyId));
+  });
+  it('deferred decryption records a mutation log', async () => {
+    const aKeys = new InMemoryIdentityKeyStore();
+    const bKeys = new InMemoryIdentityKeyStore();
+
+    const aSess = new InMemorySessionStore();
+    const bSess = new InMemorySessionStore();
+
+    const bPreK = new InMemoryPreKeyStore();
+    const bSPreK = new InMemorySignedPreKeyStore();
+
+    const bPreKey = SignalClient.PrivateKey.generate();
+    const bSPreKey = SignalClient.PrivateKey.generate();
+
+    const bIdentityKey = await bKeys.getIdentityKey();
+    const bSignedPreKeySig = bIdentityKey.sign(
+      bSPreKey.getPublicKey().serialize()
+    );
+
+    const aAddress = SignalClient.ProtocolAddress.new('+14151111111', 1);
+    const bAddress = SignalClient.ProtocolAddress.new('+19192222222', 1);
+
+    const bPreKeyId = 31337;
+    const bSignedPreKeyId = 22;
+
+    const bPreKeyBundle = SignalClient.PreKeyBundle.new(
+      await bKeys.getLocalRegistrationId(),
+      bAddress.deviceId(),
+      bPreKeyId,
+      bPreKey.getPublicKey(),
+      bSignedPreKeyId,
+      bSPreKey.getPublicKey(),
+      bSignedPreKeySig,
+      bIdentityKey.getPublicKey()
+    );
+    await bPreK.savePreKey(
+      bPreKeyId,
+      SignalClient.PreKeyRecord.new(bPreKeyId, bPreKey.getPublicKey(), bPreKey)
+    );
+    await bSPreK.saveSignedPreKey(
+      bSignedPreKeyId,
+      SignalClient.SignedPreKeyRecord.new(
+        bSignedPreKeyId,
+        42, // timestamp
+        bSPreKey.getPublicKey(),
+        bSPreKey,
+        bSignedPreKeySig
+      )
+    );
+
+    await SignalClient.processPreKeyBundle(
+      bPreKeyBundle,
+      bAddress,
+      aSess,
+      aKeys
+    );
+    const aMessage = Buffer.from('Greetings hoo-man', 'utf8');
+    const aCiphertext = await SignalClient.signalEncrypt(
+      aMessage,
+      bAddress,
+      aSess,
+      aKeys
+    );
+
+    const result = await SignalClient.signalDecryptPreKeyDeferred(
+      SignalClient.PreKeySignalMessage.deserialize(aCiphertext.serialize()),
+      aAddress,
+      bSess,
+      bKeys,
+      bPreK,
+      bSPreK
+    );
+    assert.deepEqual(result.plaintext(), aMessage);
+
+    assert.isNull(await bSess.getSession(aAddress));
+    assert.isNotNull(await bPreK.getPreKey(bPreKeyId));
+
+    await SignalClient.applyMutationLog(
+      result.mutationLog(),
+      bSess,
+      bKeys,
+      bPreK,
+      bSPreK,
+      new InMemorySenderKeyStore()
+    );
+    assert.isNotNull(await bSess.getSession(aAddress));
+    assert.isNull(await bPreK.getPreKey(bPreKeyId));
+
+    // The session has moved on, so the same log no longer applies.
+    try {
+      await SignalClient.applyMutationLog(
+        result.mutationLog(),
+        bSess,
+        bKeys,
+        bPreK,
+        bSPreK,
+        new InMemorySenderKeyStore()
+      );
+      assert.fail('should have thrown');
+    } catch (e) {
+      assert.instanceOf(e, SignalClient.InvalidCryptoStateError);
+    }
+  });
+  it('AES-GCM-SIV test vector', () => {
+    // RFC 8452, appendix C.2
+    const key = Buffer.from(
+      '0100000000000000000000000000000000000000000000000000000000000000',
+      'hex'
+    );
+
+    const aes_gcm_siv = SignalClient.Aes256GcmSiv.new(key);
+
+    const nonce = Buffer.from('030000000000000000000000', 'hex');
+    const aad = Buffer.from('010000000000000000000000', 'hex');
+    const ptext = Buffer.from('02000000', 'hex');
+
+    const ctext = aes_gcm_siv.encrypt(ptext, nonce, aad);
+
+    assert.deepEqual(
+      ctext.toString('hex'),
+      '22b3f4cd1835e517741dfddccfa07fa4661b74cf'
+    );
+
+    const decrypted = aes_gcm_siv.decrypt(ctext, nonce, aad);
+
+    assert.deepEqual(decrypted.toString('hex'), '02000000');
+  });
+  it('AES-GCM-SIV errors carry their details', () => {
+    try {
+      SignalClient.Aes256GcmSiv.new(Buffer.alloc(16));
+      assert.fail('should have thrown');
+    } catch (e) {
+      assert.instanceOf(e, SignalClient.InvalidKeySizeError);
+      assert.equal(e.name, 'InvalidKeySizeError');
+      assert.equal(e.expectedLength, 32);
+      assert.equal(e.actualLength, 16);
+    }
+
+    const aes_gcm_siv = SignalClient.Aes256GcmSiv.new(Buffer.alloc(32));
+    const nonce = Buffer.alloc(12);
+    const ctext = aes_gcm_siv.encrypt(Buffer.from('hello'), nonce, Buffer.of());
+    ctext[0] ^= 1;
+    assert.throws(
+      () => aes_gcm_siv.decrypt(ctext, nonce, Buffer.of()),
+      SignalClient.InvalidTagError
+    );
+  });
+  it('IncrementalMacValidator reports where validation failed', () => {
+    const key = Buffer.from(Array.from({ length: 32 }, (_, i) => i));
+    const data = Buffer.from(Array.from({ length: 10 }, (_, i) => i));
+    const digest = Buffer.from(
+      '52fa805b50abf8dfcab98df509bb333be21ae17d2014a42eb8d477c171e65473' +
+        '97c6323b0c0ee26c6a637ad3f16be38dd3cff2dbcef3fdf0a61d01110cdc3b17' +
+        '0e8ff8cf15d36c44df1fb5ada0acaaf9f7e149f805b7533ad50344fd82c1f9b9',
+      'hex'
+    );
+
+    const validator = SignalClient.IncrementalMacValidator.new(key, 4, digest);
+    assert.isTrue(validator.update(data.slice(0, 5)));
+    assert.equal(validator.validatedLength(), 4);
+    assert.isTrue(validator.update(data.slice(5)));
+    assert.isTrue(validator.finalize());
+    assert.equal(validator.validatedLength(), 10);
+
+    const corrupted = Buffer.from(data);
+    corrupted[6] ^= 1;
+    const failing = SignalClient.IncrementalMacValidator.new(key, 4, digest);
+    assert.isFalse(failing.update(corrupted));
+    assert.equal(failing.validatedLength(), 4);
+  });
+  it('StickerCipher decrypts whole and in pieces', () => {
+    const packKey = Buffer.from(Array.from({ length: 32 }, (_, i) => i));
+    const ciphertext = Buffer.from(
+      '6465666768696a6b6c6d6e6f707172731e07a999cd11729bcee7b16cc7aa710d' +
+        'ce938f78a947d0f8de052db72443aedf6050f41d82c3b5f23bcfdd8fd27469cf' +
+        '0178d0de4ac48f383508dccd00bb88c54e6a0cbfeb861694da6dfc1870b648d6',
+      'hex'
+    );
+    const plaintext = 'sticker pack manifest for testing';
+
+    const cipher = SignalClient.StickerCipher.new(packKey);
+    assert.equal(cipher.decrypt(ciphertext).toString(), plaintext);
+
+    const decryption = cipher.newDecryption();
+    const pieces = [];
+    for (let offset = 0; offset < ciphertext.length; offset += 7) {
+      pieces.push(decryption.update(ciphertext.slice(offset, offset + 7)));
+    }
+    pieces.push(decryption.finalize());
+    assert.equal(Buffer.concat(pieces).toString(), plaintext);
+
+    const tampered = Buffer.from(ciphertext);
+    tampered[20] ^= 1;
+    assert.throws(() => cipher.decrypt(tampered), SignalClient.InvalidTagError);
+  });
+  it('Aes256KeyWrap wraps and unwraps keys', () => {
+    const kek = Buffer.from(Array.from({ length: 32 }, (_, i) => i));
+    const key = Buffer.from('c37b7e6492584340bed12207808941155068f738', 'hex');
+    const kw = SignalClient.Aes256KeyWrap.new(kek);
+
+    const wrapped = kw.wrap(key);
+    assert.equal(
+      wrapped.toString('hex'),
+      '29b7fa191c2165684374eee9f74595e2a42bace75c425b3053efa26ffe1bb32f'
+    );
+    assert.deepEqual(kw.unwrap(wrapped), key);
+
+    const tampered = Buffer.from(wrapped);
+    tampered[3] ^= 1;
+    assert.throws(
+      () => kw.unwrap(tampered),
+      SignalClient.InvalidWrappedKeyError
+    );
+  });
+  it('StorageServiceKey encrypts manifests and records', () => {
+    const masterKey = Buffer.from(Array.from({ length: 32 }, (_, i) => i));
+    const rawId = Buffer.from(Array.from({ length: 16 }, (_, i) => i));
+    const plaintext = Buffer.from('storage record for testing');
+    const key = SignalClient.StorageServiceKey.fromMasterKey(masterKey);
+    assert.equal(
+      key.storageKey().toString('hex'),
+      'd9fd23ee99c148ad879f62609297139e6a36f415b1ad779d23c687b1fa6d36d9'
+    );
+    assert.equal(
+      key.manifestKey(42).toString('hex'),
+      '6030bc36a437e3bbdf288720487ca3293fe7eb1914819ef235f2532ff85fee6f'
+    );
+
+    const rewrapped = SignalClient.StorageServiceKey.new(key.storageKey());
+    const knownRecord = Buffer.from(
+      '6465666768696a6b6c6d6e6fe05a971f4a5a0b590212cc8bd2172baaf28790fa' +
+        '4f7ea8cd1eafe5898c431608052773941c26c5bb025a',
+      'hex'
+    );
+    assert.deepEqual(rewrapped.decryptRecord(rawId, knownRecord), plaintext);
+
+    const manifest = key.encryptManifest(7, plaintext);
+    assert.deepEqual(key.decryptManifest(7, manifest), plaintext);
+    assert.throws(
+      () => key.decryptManifest(8, manifest),
+      SignalClient.InvalidTagError
+    );
+
+    const record = key.encryptRecord(rawId, plaintext);
+    assert.deepEqual(key.decryptRecord(rawId, record), plaintext);
+    assert.throws(
+      () => key.decryptRecord(Buffer.of(1, 2, 3), record),
+      SignalClient.InvalidTagError
+    );
+  });
+  it('CallLinkRootKey has a text form and derives keys', () => {
+    const bytes = Buffer.from(Array.from({ length: 16 }, (_, i) => i));
+    const text = 'bbbc-bdbf-bgbh-bkbm-bnbp-bqbr-bsbt-bxbz';
+    const key = SignalClient.CallLinkRootKey.fromBytes(bytes);
+    assert.equal(key.toString(), text);
+    assert.deepEqual(SignalClient.CallLinkRootKey.parse(text).bytes(), bytes);
+    assert.equal(
+      key.deriveRoomId().toString('hex'),
+      'cd415ffc58771108cdbf029b8d86ee857be167bf28dda87e3e592e3f10eb1c9b'
+    );
+    assert.equal(
+      key.deriveEraKey('era-1').toString('hex'),
+      'ccd3b57831cf1733774ae4a6ae24eee4d88184c21b46aac04e5983a7c2990845'
+    );
+
+    const generated = SignalClient.CallLinkRootKey.generate();
+    assert.deepEqual(
+      SignalClient.CallLinkRootKey.parse(generated.toString()).bytes(),
+      generated.bytes()
+    );
+
+    assert.throws(
+      () =>
+        SignalClient.CallLinkRootKey.parse(
+          'abbc-bdbf-bgbh-bkbm-bnbp-bqbr-bsbt-bxbz'
+        ),
+      SignalClient.InvalidKeyEncodingError
+    );
+  });
+  it('derives group call era keys', () => {
+    const masterKey = Buffer.from(Array.from({ length: 32 }, (_, i) => i));
+    assert.equal(
+      SignalClient.deriveGroupCallEraKey(masterKey, 'era-1').toString('hex'),
+      'ddb3cc5d658418708592e5814e848d25601c9ead04dd40538cdcf82e951a8980'
+    );
+    assert.throws(
+      () =>
+        SignalClient.deriveGroupCallEraKey(masterKey.slice(16), 'era-1'),
+      SignalClient.InvalidKeySizeError
+    );
+  });
+  it('ServiceIdSet intersects and pages through service IDs', () => {
+    const serviceId = (kind: number, last: number) => {
+      const result = Buffer.alloc(SignalClient.ServiceIdSet.FIXED_WIDTH_LENGTH);
+      result[0] = kind;
+      result[result.length - 1] = last;
+      return result;
+    };
+    const aci1 = serviceId(0, 1);
+    const aci2 = serviceId(0, 2);
+    const pni1 = serviceId(1, 1);
+
+    const contacts = SignalClient.ServiceIdSet.new();
+    contacts.add(Buffer.concat([pni1, aci2, aci1]));
+    assert.equal(contacts.size(), 3);
+    assert.isTrue(contacts.has(aci2));
+    assert.deepEqual(contacts.getPage(0, 2), Buffer.concat([aci1, aci2]));
+    assert.deepEqual(contacts.getPage(2, 2), pni1);
+
+    const discovered = SignalClient.ServiceIdSet.new();
+    discovered.add(Buffer.concat([aci2, pni1]));
+    assert.deepEqual(
+      contacts.intersection(discovered).getPage(0, 10),
+      Buffer.concat([aci2, pni1])
+    );
+    assert.deepEqual(contacts.difference(discovered).getPage(0, 10), aci1);
+
+    assert.throws(() => contacts.add(serviceId(2, 3)));
+    assert.throws(() => contacts.add(aci1.slice(1)));
+    assert.equal(contacts.size(), 3);
+  });
+  it('builds message content', () => {
+    const attachment = SignalClient.AttachmentPointer.new(
+      2,
+      'cdn-key',
+      'image/jpeg',
+      Buffer.alloc(64, 1),
+      Buffer.alloc(32, 2),
+      1000,
+      { width: 640, height: 480 }
+    );
+    assert.throws(() =>
+      SignalClient.AttachmentPointer.new(
+        2,
+        'cdn-key',
+        'image/jpeg',
+        Buffer.of(1, 2, 3),
+        Buffer.alloc(32, 2),
+        1000
+      )
+    );
+
+    const builder = SignalClient.DataMessageBuilder.new(1234);
+    assert.throws(() => builder.build());
+    assert.throws(() => builder.setBody('x'.repeat(2049)));
+    assert.throws(() => builder.setProfileKey(Buffer.of(1, 2, 3)));
+    const content = builder
+      .setBody('hello')
+      .addAttachment(attachment)
+      .setQuote(1000, '9d0652a3-dcc3-4d11-975f-74d61598733f')
+      .setExpireTimer(60)
+      .build();
+    assert.isAbove(content.serialize().length, 0);
+
+    const receipt = SignalClient.Content.receipt(
+      SignalClient.ReceiptType.Read,
+      [1000, 1001]
+    );
+    assert.isAbove(receipt.serialize().length, 0);
+    assert.throws(() =>
+      SignalClient.Content.receipt(SignalClient.ReceiptType.Read, [])
+    );
+
+    const typing = SignalClient.Content.typing(1234, true, Buffer.of(1, 2, 3));
+    assert.isAbove(typing.serialize().length, 0);
+    assert.throws(() => SignalClient.Content.typing(0, false));
+  });
+  it('backup frames round-trip in chunks', () => {
+    const key = Buffer.alloc(32, 1);
+    const frames = [
+      Buffer.from('account data'),
+      Buffer.of(),
+      Buffer.alloc(10000, 7),
+      Buffer.from('last chat item'),
+    ];
+
+    const writer = SignalClient.BackupFrameWriter.new(key, true);
+    const stream = Buffer.concat([
+      ...frames.map(frame => writer.append(frame)),
+      writer.finish(),
+    ]);
+
+    const readAll = (input: Buffer): Buffer[] => {
+      const reader = SignalClient.BackupFrameReader.new(key);
+      const result = [];
+      for (let offset = 0; offset < input.length; offset += 100) {
+        reader.push(input.slice(offset, offset + 100));
+        for (let frame = reader.nextFrame(); frame; frame = reader.nextFrame()) {
+          result.push(frame);
+        }
+      }
+      reader.finish();
+      return result;
+    };
+
+    assert.deepEqual(readAll(stream), frames);
+    assert.throws(
+      () => readAll(stream.slice(0, stream.length - 1)),
+      SignalClient.BackupTruncatedError
+    );
+
+    const corrupted = Buffer.from(stream);
+    corrupted[30] ^= 1;
+    try {
+      readAll(corrupted);
+      assert.fail('should have thrown');
+    } catch (e) {
+      assert.instanceOf(e, SignalClient.InvalidBackupError);
+      assert.equal(e.frameIndex, 0);
+    }
+  });
+  it('BackupBuilder writes records as frames', () => {
+    const key = Buffer.alloc(32, 1);
+    const builder = SignalClient.BackupBuilder.new(key, true, new Date());
+    const parts = [
+      builder.addAccountData({
+        profileKey: Buffer.alloc(32),
+        givenName: 'Alice',
+        familyName: '',
+      }),
+      builder.addRecipient({ id: 1, self: true }),
+      builder.addRecipient({ id: 2, e164: '+14155550100' }),
+      builder.addRecipient({ id: 3, group: { masterKey: Buffer.alloc(32) } }),
+      builder.addChat({ id: 10, recipientId: 3 }),
+      builder.addChatItem({ chatId: 10, authorId: 2, dateSent: 1, text: 'hi' }),
+    ];
+    assert.throws(
+      () => builder.addChat({ id: 11, recipientId: 4 }),
+      SignalClient.InvalidBackupRecordError
+    );
+    parts.push(builder.finish());
+
+    const reader = SignalClient.BackupFrameReader.new(key);
+    reader.push(Buffer.concat(parts));
+    let frameCount = 0;
+    while (reader.nextFrame()) {
+      frameCount += 1;
+    }
+    reader.finish();
+    // The backup info frame, followed by one frame per record.
+    assert.equal(frameCount, 7);
+  });
+  it('registration requests and responses', () => {
+    const request = SignalClient.RegistrationRequest.submitVerificationCode(
+      'abc',
+      '123456'
+    );
+    assert.equal(request.method(), 'PUT');
+    assert.equal(request.path(), '/v1/verification/session/abc/code');
+    assert.deepEqual(JSON.parse(request.body()?.toString() ?? ''), {
+      code: '123456',
+    });
+
+    const session = SignalClient.RegistrationSession.fromResponse(
+      200,
+      Buffer.from(
+        JSON.stringify({
+          id: 'abc',
+          nextSms: 30,
+          nextCall: null,
+          nextVerificationAttempt: null,
+          allowedToRequestCode: true,
+          requestedInformation: ['pushChallenge'],
+          verified: false,
+        })
+      )
+    );
+    assert.equal(session.id(), 'abc');
+    assert.equal(session.nextSmsSeconds(), 30);
+    assert.isNull(session.nextCallSeconds());
+    assert(session.requiresPushChallenge());
+    assert(!session.requiresCaptcha());
+
+    try {
+      SignalClient.RegistrationSession.fromResponse(429, Buffer.of(), '60');
+      assert.fail('should have thrown');
+    } catch (e) {
+      assert.instanceOf(e, SignalClient.RateLimitedError);
+      assert.equal(e.retryAfterSecs, 60);
+    }
+    assert.throws(
+      () => SignalClient.RegistrationSession.fromResponse(404, Buffer.of()),
+      SignalClient.RegistrationSessionNotFoundError
+    );
+
+    const outcome = SignalClient.RegistrationOutcome.fromResponse(
+      200,
+      Buffer.from(
+        JSON.stringify({ uuid: 'aci', pni: 'pni', number: '+14155550100' })
+      )
+    );
+    assert(!outcome.isLocked());
+    assert.equal(outcome.aci(), 'aci');
+    assert.isNull(outcome.svrUsername());
+    assert.lengthOf(SignalClient.registrationLockToken(Buffer.alloc(32)), 64);
+  });
+  it('key transparency rejects bad responses', () => {
+    const logKey = SignalClient.PrivateKey.generate().getPublicKey();
+    const searchKey = Buffer.from('alice');
+    for (const response of [Buffer.alloc(0), Buffer.from([0xff, 0xff])]) {
+      assert.throws(
+        () =>
+          SignalClient.keyTransparencyVerifySearch(
+            logKey,
+            searchKey,
+            response,
+            null
+          ),
+        SignalClient.KeyTransparencyVerificationError
+      );
+      assert.throws(
+        () =>
+          SignalClient.keyTransparencyVerifyDistinguished(
+            logKey,
+            response,
+            null
+          ),
+        SignalClient.KeyTransparencyVerificationError
+      );
+    }
+  });
+  it('buffer checksum policies', () => {
+    const key = Buffer.alloc(32, 1);
+    const nonce = Buffer.alloc(12, 3);
+    const aad = Buffer.alloc(2048, 5);
+    const ptext = Buffer.from('02000000', 'hex');
+
+    const policies = [
+      SignalClient.BufferChecksumPolicy.Off,
+      SignalClient.BufferChecksumPolicy.Full,
+      SignalClient.BufferChecksumPolicy.FullWithPanic,
+      SignalClient.BufferChecksumPolicy.Copy,
+    ];
+    try {
+      for (const policy of policies) {
+        SignalClient.setBufferChecksumPolicy(policy);
+        const aes_gcm_siv = SignalClient.Aes256GcmSiv.new(key);
+        const ctext = aes_gcm_siv.encrypt(ptext, nonce, aad);
+        assert.deepEqual(aes_gcm_siv.decrypt(ctext, nonce, aad), ptext);
+      }
+    } finally {
+      SignalClient.setBufferChecksumPolicy(
+        SignalClient.BufferChecksumPolicy.Prefix
+      );
+    }
   });
   it('ECC signatures work', () => {
     const priv_a = SignalClient.PrivateKey.generate();
@@ -877,6 +2387,191 @@ describe('SignalClient', () => {
     assert(!pub_a.verify(msg, sig_b));
   });
 
+  it('payment addresses are signed by the identity key', () => {
+    const privateKey = SignalClient.PrivateKey.generate();
+    const publicKey = privateKey.getPublicKey();
+    const identity = SignalClient.IdentityKeyPair.new(publicKey, privateKey);
+    const address = Buffer.from('mobilecoin public address');
+
+    const signature = identity.signPaymentAddress(address);
+    assert(SignalClient.verifyPaymentAddress(publicKey, address, signature));
+    assert(
+      !SignalClient.verifyPaymentAddress(
+        publicKey,
+        Buffer.from('another address'),
+        signature
+      )
+    );
+    // A plain signature over the address isn't accepted.
+    assert(
+      !SignalClient.verifyPaymentAddress(
+        publicKey,
+        address,
+        privateKey.sign(address)
+      )
+    );
+  });
+  it('Argon2id', () => {
+    const params = SignalClient.Argon2idParams.new(65536, 2, 1);
+    assert.equal(params.memoryKib(), 65536);
+    assert.equal(params.iterations(), 2);
+    assert.equal(params.parallelism(), 1);
+    const key = params.derive(
+      Buffer.from('password'),
+      Buffer.from('somesalt'),
+      32
+    );
+    assert.equal(
+      key.toString('hex'),
+      '09316115d5cf24ed5a15a31a3ba326e5cf32edc24702987c02b6566f61913cf7'
+    );
+    assert.throws(
+      () => SignalClient.Argon2idParams.new(4, 1, 1),
+      SignalClient.InvalidKdfParametersError
+    );
+
+    const calibrated = SignalClient.Argon2idParams.calibrate(0, 64, 1);
+    assert.equal(calibrated.iterations(), 1);
+  });
+
+  it('scrypt', () => {
+    const params = SignalClient.ScryptParams.new(10, 8, 16);
+    const key = params.derive(Buffer.from('password'), Buffer.from('NaCl'), 64);
+    assert.equal(
+      key.toString('hex'),
+      'fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162' +
+        '2eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640'
+    );
+
+    const calibrated = SignalClient.ScryptParams.calibrate(0, 8, 1);
+    assert.equal(calibrated.logN(), 10);
+  });
+
+  it('digests', () => {
+    assert.equal(
+      SignalClient.Crc32.digest(Buffer.from('123456789')),
+      0xcbf43926
+    );
+    const crc = SignalClient.Crc32.new();
+    crc.update(Buffer.from('1234'));
+    crc.update(Buffer.from('56789'));
+    assert.equal(crc.finalize(), 0xcbf43926);
+
+    const abc = Buffer.from('abc');
+    const sha256 =
+      'ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad';
+    assert.equal(SignalClient.Sha256.digest(abc).toString('hex'), sha256);
+    const hash256 = SignalClient.Sha256.new();
+    hash256.update(Buffer.from('a'));
+    hash256.update(Buffer.from('bc'));
+    assert.equal(hash256.finalize().toString('hex'), sha256);
+
+    const sha512 =
+      'ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a' +
+      '2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f';
+    assert.equal(SignalClient.Sha512.digest(abc).toString('hex'), sha512);
+    const hash512 = SignalClient.Sha512.new();
+    hash512.update(Buffer.from('ab'));
+    hash512.update(Buffer.from('c'));
+    assert.equal(hash512.finalize().toString('hex'), sha512);
+  });
+
+  it('base64 and hex', () => {
+    const data = Buffer.from('streaming attachment data');
+    const encoded = data.toString('base64');
+    assert.equal(SignalClient.base64Encode(data), encoded);
+    assert.deepEqual(SignalClient.base64Decode(encoded), data);
+    assert.equal(SignalClient.hexEncode(data), data.toString('hex'));
+    assert.deepEqual(SignalClient.hexDecode(data.toString('hex')), data);
+    assert.throws(() => SignalClient.base64Decode('not base64!'));
+    assert.throws(() => SignalClient.hexDecode('abc'));
+
+    const encoder = SignalClient.Base64Encoder.new();
+    const encodedPieces = [
+      encoder.update(data.slice(0, 1)),
+      encoder.update(data.slice(1, 8)),
+      encoder.update(data.slice(8)),
+      encoder.finalize(),
+    ];
+    assert.equal(encodedPieces.join(''), encoded);
+
+    const decoder = SignalClient.Base64Decoder.new();
+    const decodedPieces = [
+      decoder.update(encoded.slice(0, 3)),
+      decoder.update(encoded.slice(3, 10)),
+      decoder.update(encoded.slice(10)),
+      decoder.finalize(),
+    ];
+    assert.deepEqual(Buffer.concat(decodedPieces), data);
+
+    const padded = SignalClient.Base64Decoder.new();
+    padded.update('QQ==');
+    assert.throws(() => padded.update('QUJD'));
+  });
+
+  it('PaddingPolicy', () => {
+    const bucketed = SignalClient.PaddingPolicy.bucketed(160);
+    assert.equal(bucketed.paddedLength(159), 160);
+    assert.equal(bucketed.paddedLength(160), 320);
+    assert.equal(SignalClient.PaddingPolicy.padme().paddedLength(1000), 1024);
+
+    const message = Buffer.from('padded message');
+    for (const policy of [
+      SignalClient.PaddingPolicy.none(),
+      bucketed,
+      SignalClient.PaddingPolicy.padme(),
+    ]) {
+      const padded = policy.pad(message);
+      assert.equal(padded.length, policy.paddedLength(message.length));
+      assert.deepEqual(policy.unpad(padded), message);
+    }
+    assert.throws(() =>
+      SignalClient.PaddingPolicy.padme().unpad(Buffer.from([1, 2, 0, 0]))
+    );
+  });
+  it('OutgoingEnvelope', () => {
+    const destination = SignalClient.ProtocolAddress.new('+14151111111', 2);
+    const payload = Buffer.from('sealed sender ciphertext');
+    const envelope = SignalClient.OutgoingEnvelope.new(
+      destination,
+      1600000000000,
+      SignalClient.ContentHint.Resendable,
+      payload
+    );
+
+    const parsed = SignalClient.OutgoingEnvelope.deserialize(
+      envelope.serialize()
+    );
+    assert.equal(parsed.destination().name(), '+14151111111');
+    assert.equal(parsed.destination().deviceId(), 2);
+    assert.equal(parsed.timestamp(), 1600000000000);
+    assert.equal(parsed.contentHint(), SignalClient.ContentHint.Resendable);
+    assert.deepEqual(parsed.payload(), payload);
+    assert.equal(parsed.serializedVersion(), 1);
+
+    assert.throws(() =>
+      SignalClient.OutgoingEnvelope.deserialize(Buffer.from([0x0a, 0x05]))
+    );
+  });
+  it('provisioning messages can be decrypted by the linked device', () => {
+    const linkedDevice = SignalClient.ProvisioningCipher.new();
+    const message = Buffer.from('account details for the new device');
+
+    const envelope = SignalClient.ProvisioningCipher.encrypt(
+      linkedDevice.publicKey(),
+      message
+    );
+    assert.deepEqual(linkedDevice.decrypt(envelope), message);
+    assert.throws(() =>
+      SignalClient.ProvisioningCipher.new().decrypt(envelope)
+    );
+
+    const uuid = SignalClient.ProvisioningUuid.new(
+      'b7f8d8a0-5f1e-4b5e-9a5c-2e3c5d7f9a1b'
+    );
+    const parsed = SignalClient.ProvisioningUuid.deserialize(uuid.serialize());
+    assert.equal(parsed.uuid(), uuid.uuid());
+  });
   it('ECC key agreement work', () => {
     const priv_a = SignalClient.PrivateKey.generate();
     const priv_b = SignalClient.PrivateKey.generate();
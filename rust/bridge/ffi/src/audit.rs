@@ -0,0 +1,85 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use libc::{c_char, c_void};
+use libsignal_bridge::audit::{AuditEventKind, BridgedAuditEvent};
+use libsignal_protocol::{set_audit_hook, AuditEvent, AuditHook};
+use std::ffi::CString;
+
+pub type AuditCallback = extern "C" fn(
+    ctx: *mut c_void,
+    kind: AuditEventKind,
+    address_name: *const c_char,
+    device_id: u32,
+    group_id: *const c_char,
+);
+
+pub type AuditDestroyCallback = extern "C" fn(ctx: *mut c_void);
+
+/// An audit hook implemented by the app.
+///
+/// `record` may be called from any thread. `destroy` is called once the hook has been replaced
+/// and will not be called again.
+#[repr(C)]
+pub struct FfiAuditHook {
+    ctx: *mut c_void,
+    record: AuditCallback,
+    destroy: AuditDestroyCallback,
+}
+
+// The app promises that the callbacks can be called from any thread.
+unsafe impl Send for FfiAuditHook {}
+unsafe impl Sync for FfiAuditHook {}
+
+impl AuditHook for FfiAuditHook {
+    fn record(&self, event: &AuditEvent) {
+        let event = match BridgedAuditEvent::new(event) {
+            Ok(event) => event,
+            Err(e) => {
+                log::error!("failed to report audit event: {}", e);
+                return;
+            }
+        };
+        let (address_name, group_id) = match (
+            CString::new(event.address_name),
+            event.group_id.map(CString::new).transpose(),
+        ) {
+            (Ok(address_name), Ok(group_id)) => (address_name, group_id),
+            _ => {
+                log::error!("failed to report audit event: name contains a 0 byte");
+                return;
+            }
+        };
+        (self.record)(
+            self.ctx,
+            event.kind,
+            address_name.as_ptr(),
+            event.device_id,
+            group_id
+                .as_ref()
+                .map(|group_id| group_id.as_ptr())
+                .unwrap_or(std::ptr::null()),
+        );
+    }
+}
+
+impl Drop for FfiAuditHook {
+    fn drop(&mut self) {
+        (self.destroy)(self.ctx)
+    }
+}
+
+/// Installs `hook` to be told about identity changes, session resets, and new sender keys,
+/// replacing any hook installed earlier.
+#[no_mangle]
+pub unsafe extern "C" fn signal_set_audit_hook(hook: FfiAuditHook) {
+    set_audit_hook(Some(Box::new(hook)))
+}
+
+/// Removes the current audit hook, if any.
+#[no_mangle]
+pub unsafe extern "C" fn signal_clear_audit_hook() {
+    set_audit_hook(None)
+}
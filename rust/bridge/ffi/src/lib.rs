@@ -8,11 +8,14 @@
 
 use libc::{c_char, c_uchar, c_uint, size_t};
 use libsignal_bridge::ffi::*;
+use libsignal_bridge::rng::{CallRng, Rng};
 use libsignal_protocol::*;
 use std::convert::TryFrom;
 use std::ffi::{c_void, CString};
 
+pub mod audit;
 pub mod logging;
+pub mod memory;
 mod util;
 
 use crate::util::*;
@@ -38,6 +41,15 @@ pub unsafe extern "C" fn signal_free_buffer(buf: *const c_uchar, buf_len: size_t
     Box::from_raw(std::slice::from_raw_parts_mut(buf as *mut c_uchar, buf_len));
 }
 
+/// Frees an array of handles returned by a function, but not the handles themselves.
+#[no_mangle]
+pub unsafe extern "C" fn signal_free_handle_array(handles: *mut *mut c_void, handles_len: size_t) {
+    if handles.is_null() {
+        return;
+    }
+    Box::from_raw(std::slice::from_raw_parts_mut(handles, handles_len));
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn signal_error_get_message(
     err: *const SignalFfiError,
@@ -68,6 +80,50 @@ pub unsafe extern "C" fn signal_error_get_type(err: *const SignalFfiError) -> u3
     }
 }
 
+/// Classifies an error returned while decrypting a message as a `SignalDecryptionFailureAction`.
+///
+/// Errors that don't come from the protocol layer are treated as `PermanentlyInvalid`.
+#[no_mangle]
+pub unsafe extern "C" fn signal_error_get_decryption_failure_action(
+    err: *const SignalFfiError,
+) -> u32 {
+    match err.as_ref() {
+        Some(err) => {
+            let action: SignalDecryptionFailureAction = err.into();
+            action as u32
+        }
+        None => 0,
+    }
+}
+
+/// Returns the address carried by an `InvalidRegistrationId` or `InvalidSenderKeySession` error.
+#[no_mangle]
+pub unsafe extern "C" fn signal_error_get_address(
+    err: *const SignalFfiError,
+    out: *mut *mut ProtocolAddress,
+) -> *mut SignalFfiError {
+    let result = (|| {
+        let mut err = err.as_ref().ok_or(SignalFfiError::NullPointer)?;
+        while let SignalFfiError::WithContext(_, inner) = err {
+            err = inner.as_ref();
+        }
+        match err {
+            SignalFfiError::Signal(SignalProtocolError::InvalidRegistrationId(address, _)) => {
+                box_object(out, Ok(address.clone()))
+            }
+            SignalFfiError::Signal(SignalProtocolError::InvalidSenderKeySession(name)) => {
+                box_object(out, name.sender())
+            }
+            _ => Err(SignalFfiError::InvalidType),
+        }
+    })();
+
+    match result {
+        Ok(()) => std::ptr::null_mut(),
+        Err(e) => Box::into_raw(Box::new(e)),
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn signal_error_free(err: *mut SignalFfiError) {
     if !err.is_null() {
@@ -82,7 +138,7 @@ pub unsafe extern "C" fn signal_identitykeypair_deserialize(
     input: *const c_uchar,
     input_len: size_t,
 ) -> *mut SignalFfiError {
-    run_ffi_safe(|| {
+    run_ffi_safe("signal_identitykeypair_deserialize", || {
         let input = as_slice(input, input_len)?;
         let identity_key_pair = IdentityKeyPair::try_from(input)?;
         box_object::<PublicKey>(public_key, Ok(*identity_key_pair.public_key()))?;
@@ -108,9 +164,10 @@ pub unsafe extern "C" fn signal_sealed_session_cipher_decrypt(
     identity_store: *const FfiIdentityKeyStoreStruct,
     prekey_store: *const FfiPreKeyStoreStruct,
     signed_prekey_store: *const FfiSignedPreKeyStoreStruct,
+    rng: *const Rng,
     ctx: *mut c_void,
 ) -> *mut SignalFfiError {
-    run_ffi_safe(|| {
+    run_ffi_safe("signal_sealed_session_cipher_decrypt", || {
         let ctext = as_slice(ctext, ctext_len)?;
         let trust_root = native_handle_cast::<PublicKey>(trust_root)?;
         let mut identity_store = identity_store.as_ref().ok_or(SignalFfiError::NullPointer)?;
@@ -120,8 +177,10 @@ pub unsafe extern "C" fn signal_sealed_session_cipher_decrypt(
             .as_ref()
             .ok_or(SignalFfiError::NullPointer)?;
 
-        let local_e164 = Option::convert_from(local_e164)?;
-        let local_uuid = Option::convert_from(local_uuid)?.ok_or(SignalFfiError::NullPointer)?;
+        let local_e164 = Option::<String>::convert_from(local_e164)?;
+        let local_uuid =
+            Option::<String>::convert_from(local_uuid)?.ok_or(SignalFfiError::NullPointer)?;
+        let rng = Option::<&Rng>::convert_from(rng)?;
 
         let decrypted = expect_ready(sealed_sender_decrypt(
             &ctext,
@@ -135,6 +194,7 @@ pub unsafe extern "C" fn signal_sealed_session_cipher_decrypt(
             &mut prekey_store,
             &mut signed_prekey_store,
             Some(ctx),
+            &mut CallRng::new(rng),
         ))?;
 
         write_optional_cstr_to(sender_e164, Ok(decrypted.sender_e164))?;
@@ -143,3 +203,71 @@ pub unsafe extern "C" fn signal_sealed_session_cipher_decrypt(
         write_bytearray_to(out, out_len, decrypted.message)
     })
 }
+
+/// Encrypts `ptext` for each of `protocol_addresses`.
+///
+/// `out` and `errors` must each have room for `protocol_addresses_len` entries, which are written in
+/// the same order as the addresses. For each address, either the message is written to `out` and
+/// `errors` gets null, or the reason encryption failed is written to `errors` and `out` gets null.
+/// Each error must be freed with `signal_error_free`.
+///
+/// An error is returned (and nothing is written) only if the batch as a whole could not be
+/// processed, such as when the session store fails.
+#[no_mangle]
+pub unsafe extern "C" fn signal_encrypt_message_batch(
+    out: *mut *mut CiphertextMessage,
+    errors: *mut *mut SignalFfiError,
+    ptext: *const c_uchar,
+    ptext_len: size_t,
+    protocol_addresses: *const *const ProtocolAddress,
+    protocol_addresses_len: size_t,
+    session_store: *const FfiSessionStoreStruct,
+    identity_key_store: *const FfiIdentityKeyStoreStruct,
+    ctx: *mut c_void,
+) -> *mut SignalFfiError {
+    // SignalFfiError isn't RefUnwindSafe, but the closure only writes through this pointer.
+    let errors = std::panic::AssertUnwindSafe(errors);
+    run_ffi_safe("signal_encrypt_message_batch", || {
+        if out.is_null()
+            || errors.is_null()
+            || (protocol_addresses.is_null() && protocol_addresses_len != 0)
+        {
+            return Err(SignalFfiError::NullPointer);
+        }
+        let ptext = as_slice(ptext, ptext_len)?;
+        let protocol_addresses = if protocol_addresses_len == 0 {
+            Vec::new()
+        } else {
+            std::slice::from_raw_parts(protocol_addresses, protocol_addresses_len)
+                .iter()
+                .map(|address| Ok(native_handle_cast::<ProtocolAddress>(*address)?.clone()))
+                .collect::<Result<Vec<_>, SignalFfiError>>()?
+        };
+        let mut session_store = session_store.as_ref().ok_or(SignalFfiError::NullPointer)?;
+        let mut identity_key_store = identity_key_store
+            .as_ref()
+            .ok_or(SignalFfiError::NullPointer)?;
+
+        let messages = expect_ready(message_encrypt_batch(
+            ptext,
+            &protocol_addresses,
+            &mut session_store,
+            &mut identity_key_store,
+            Some(ctx),
+        ))?;
+
+        for (i, message) in messages.into_iter().enumerate() {
+            match message {
+                Ok(message) => {
+                    box_object(out.add(i), Ok(message))?;
+                    *errors.add(i) = std::ptr::null_mut();
+                }
+                Err(e) => {
+                    *out.add(i) = std::ptr::null_mut();
+                    *errors.add(i) = Box::into_raw(Box::new(SignalFfiError::from(e)));
+                }
+            }
+        }
+        Ok(())
+    })
+}
@@ -4,6 +4,7 @@
 //
 
 use libc::c_char;
+use libsignal_bridge::context::ContextLogger;
 use std::ffi::CString;
 
 #[repr(C)]
@@ -94,7 +95,7 @@ impl log::Log for FfiLogger {
 
 #[no_mangle]
 pub unsafe extern "C" fn signal_init_logger(max_level: LogLevel, logger: FfiLogger) {
-    match log::set_logger(Box::leak(Box::new(logger))) {
+    match log::set_logger(Box::leak(Box::new(ContextLogger(logger)))) {
         Ok(_) => {
             log::set_max_level(log::Level::from(max_level).to_level_filter());
             log::info!(
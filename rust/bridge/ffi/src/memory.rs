@@ -0,0 +1,227 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Optional instrumentation and redirection of native allocations.
+//!
+//! - With the `alloc-stats` feature, every allocation is counted, both in total and against the
+//!   entry point that made it. `signal_memory_stats` reports the counts as JSON.
+//! - With the `external-alloc` feature, memory is obtained from `signal_host_alloc` and returned
+//!   to `signal_host_dealloc`, which the embedding application must provide at link time.
+//!
+//! The two features can be combined. Without either, the system allocator is used directly and
+//! `signal_memory_stats` reports `{"enabled":false}`.
+
+use libc::c_char;
+use libsignal_bridge::ffi::*;
+
+use crate::util::*;
+
+#[cfg(feature = "external-alloc")]
+mod host {
+    use libc::{c_void, size_t};
+    use std::alloc::{GlobalAlloc, Layout};
+
+    extern "C" {
+        fn signal_host_alloc(size: size_t, align: size_t) -> *mut c_void;
+        fn signal_host_dealloc(ptr: *mut c_void, size: size_t, align: size_t);
+    }
+
+    /// Serves allocations from the functions provided by the embedding application.
+    pub struct HostAllocator;
+
+    unsafe impl GlobalAlloc for HostAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            signal_host_alloc(layout.size(), layout.align()) as *mut u8
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            signal_host_dealloc(ptr as *mut c_void, layout.size(), layout.align())
+        }
+    }
+}
+
+#[cfg(feature = "alloc-stats")]
+mod stats {
+    use libsignal_bridge::ffi::current_entry_point;
+    use std::alloc::{GlobalAlloc, Layout};
+    use std::fmt::Write;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static BYTES_IN_USE: AtomicUsize = AtomicUsize::new(0);
+    static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+    static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+    static DEALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+    /// Allocations made by a single entry point.
+    ///
+    /// Deallocations can't be attributed (memory is often freed by a different call than the one
+    /// that allocated it), so only allocations are counted here.
+    struct EntryPointStats {
+        /// The address of the entry point's name, or 0 if this slot is unused.
+        name_ptr: AtomicUsize,
+        /// The length of the entry point's name, or 0 if the slot is still being claimed.
+        name_len: AtomicUsize,
+        allocations: AtomicUsize,
+        bytes: AtomicUsize,
+    }
+
+    const UNUSED: EntryPointStats = EntryPointStats {
+        name_ptr: AtomicUsize::new(0),
+        name_len: AtomicUsize::new(0),
+        allocations: AtomicUsize::new(0),
+        bytes: AtomicUsize::new(0),
+    };
+
+    /// Entry points beyond this many are only counted in the totals.
+    const MAX_ENTRY_POINTS: usize = 256;
+
+    // The allocator can't allocate, so the table has a fixed size.
+    static ENTRY_POINTS: [EntryPointStats; MAX_ENTRY_POINTS] = [UNUSED; MAX_ENTRY_POINTS];
+
+    fn stats_for(name: &'static str) -> Option<&'static EntryPointStats> {
+        let key = name.as_ptr() as usize;
+        for entry in ENTRY_POINTS.iter() {
+            match entry
+                .name_ptr
+                .compare_exchange(0, key, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => {
+                    entry.name_len.store(name.len(), Ordering::Release);
+                    return Some(entry);
+                }
+                Err(existing) if existing == key => return Some(entry),
+                Err(_) => {}
+            }
+        }
+        None
+    }
+
+    fn record_allocation(size: usize) {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        let in_use = BYTES_IN_USE.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK_BYTES.fetch_max(in_use, Ordering::Relaxed);
+        if let Some(entry) = current_entry_point().and_then(stats_for) {
+            entry.allocations.fetch_add(1, Ordering::Relaxed);
+            entry.bytes.fetch_add(size, Ordering::Relaxed);
+        }
+    }
+
+    fn record_deallocation(size: usize) {
+        DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES_IN_USE.fetch_sub(size, Ordering::Relaxed);
+    }
+
+    /// Wraps another allocator to count what passes through it.
+    pub struct CountingAllocator<A>(pub A);
+
+    unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = self.0.alloc(layout);
+            if !ptr.is_null() {
+                record_allocation(layout.size());
+            }
+            ptr
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let ptr = self.0.alloc_zeroed(layout);
+            if !ptr.is_null() {
+                record_allocation(layout.size());
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            self.0.dealloc(ptr, layout);
+            record_deallocation(layout.size());
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_ptr = self.0.realloc(ptr, layout, new_size);
+            if !new_ptr.is_null() {
+                record_deallocation(layout.size());
+                record_allocation(new_size);
+            }
+            new_ptr
+        }
+    }
+
+    pub fn to_json() -> String {
+        let mut out = String::new();
+        write!(
+            out,
+            concat!(
+                "{{\"enabled\":true,\"bytes_in_use\":{},\"peak_bytes\":{},",
+                "\"allocations\":{},\"deallocations\":{},\"entry_points\":["
+            ),
+            BYTES_IN_USE.load(Ordering::Relaxed),
+            PEAK_BYTES.load(Ordering::Relaxed),
+            ALLOCATIONS.load(Ordering::Relaxed),
+            DEALLOCATIONS.load(Ordering::Relaxed),
+        )
+        .expect("writing to a String cannot fail");
+        let mut first = true;
+        for entry in ENTRY_POINTS.iter() {
+            let name_len = entry.name_len.load(Ordering::Acquire);
+            if name_len == 0 {
+                continue;
+            }
+            let name_ptr = entry.name_ptr.load(Ordering::Acquire) as *const u8;
+            // Safety: the slot was claimed using the address and length of a `&'static str`.
+            let name = unsafe {
+                std::str::from_utf8_unchecked(std::slice::from_raw_parts(name_ptr, name_len))
+            };
+            if !first {
+                out.push(',');
+            }
+            first = false;
+            // Entry point names are C identifiers, so they never need escaping.
+            write!(
+                out,
+                "{{\"name\":\"{}\",\"allocations\":{},\"bytes_allocated\":{}}}",
+                name,
+                entry.allocations.load(Ordering::Relaxed),
+                entry.bytes.load(Ordering::Relaxed),
+            )
+            .expect("writing to a String cannot fail");
+        }
+        out.push_str("]}");
+        out
+    }
+}
+
+#[cfg(all(feature = "alloc-stats", feature = "external-alloc"))]
+#[global_allocator]
+static ALLOCATOR: stats::CountingAllocator<host::HostAllocator> =
+    stats::CountingAllocator(host::HostAllocator);
+
+#[cfg(all(feature = "alloc-stats", not(feature = "external-alloc")))]
+#[global_allocator]
+static ALLOCATOR: stats::CountingAllocator<std::alloc::System> =
+    stats::CountingAllocator(std::alloc::System);
+
+#[cfg(all(not(feature = "alloc-stats"), feature = "external-alloc"))]
+#[global_allocator]
+static ALLOCATOR: host::HostAllocator = host::HostAllocator;
+
+#[cfg(feature = "alloc-stats")]
+fn memory_stats_to_json() -> String {
+    stats::to_json()
+}
+
+#[cfg(not(feature = "alloc-stats"))]
+fn memory_stats_to_json() -> String {
+    "{\"enabled\":false}".to_string()
+}
+
+/// Reports allocation statistics as JSON.
+///
+/// Allocations are attributed to the entry point that was running when they were made.
+#[no_mangle]
+pub unsafe extern "C" fn signal_memory_stats(out: *mut *const c_char) -> *mut SignalFfiError {
+    run_ffi_safe("signal_memory_stats", || {
+        write_cstr_to(out, Ok(memory_stats_to_json()))
+    })
+}
@@ -4,9 +4,12 @@
 //
 
 use device_transfer::Error as DeviceTransferError;
+use keytrans::Error as KeyTransparencyError;
 use libc::{c_char, c_uchar, size_t};
 use libsignal_bridge::ffi::*;
 use libsignal_protocol::*;
+use message_backup::Error as MessageBackupError;
+use registration::Error as RegistrationError;
 use signal_crypto::Error as SignalCryptoError;
 use std::ffi::CString;
 
@@ -22,6 +25,7 @@ pub enum SignalErrorCode {
     InvalidType = 6,
     InvalidUtf8String = 7,
     InsufficientOutputSize = 8,
+    BufferTooLarge = 9,
 
     ProtobufError = 10,
 
@@ -31,9 +35,12 @@ pub enum SignalErrorCode {
     UnrecognizedMessageVersion = 23,
     InvalidMessage = 30,
     SealedSenderSelfSend = 31,
+    SealedSenderCertificateExpired = 32,
+    SealedSenderReplay = 33,
 
     InvalidKey = 40,
     InvalidSignature = 41,
+    InvalidWrappedKey = 42,
 
     FingerprintIdentifierMismatch = 50,
     FingerprintVersionMismatch = 51,
@@ -44,19 +51,57 @@ pub enum SignalErrorCode {
     InvalidKeyIdentifier = 70,
 
     SessionNotFound = 80,
+    InvalidRegistrationId = 81,
+    InvalidSenderKeySession = 82,
 
     DuplicatedMessage = 90,
 
     CallbackError = 100,
+
+    TruncatedBackup = 110,
+    InvalidBackup = 111,
+
+    KeyTransparencyVerificationFailed = 120,
+
+    RateLimited = 130,
+    RegistrationSessionNotFound = 131,
+    RegistrationFailed = 132,
+}
+
+#[derive(Debug)]
+#[repr(C)]
+pub enum SignalDecryptionFailureAction {
+    Retryable = 1,
+    NeedsNewSession = 2,
+    PermanentlyInvalid = 3,
+    StoreError = 4,
+}
+
+impl From<&SignalFfiError> for SignalDecryptionFailureAction {
+    fn from(err: &SignalFfiError) -> Self {
+        let action = match err {
+            SignalFfiError::WithContext(_, e) => return Self::from(e.as_ref()),
+            SignalFfiError::Signal(e) => classify_decryption_failure(e),
+            _ => DecryptionFailureAction::PermanentlyInvalid,
+        };
+        match action {
+            DecryptionFailureAction::Retryable => Self::Retryable,
+            DecryptionFailureAction::NeedsNewSession => Self::NeedsNewSession,
+            DecryptionFailureAction::PermanentlyInvalid => Self::PermanentlyInvalid,
+            DecryptionFailureAction::StoreError => Self::StoreError,
+        }
+    }
 }
 
 impl From<&SignalFfiError> for SignalErrorCode {
     fn from(err: &SignalFfiError) -> Self {
         match err {
+            SignalFfiError::WithContext(_, e) => SignalErrorCode::from(e.as_ref()),
+
             SignalFfiError::NullPointer => SignalErrorCode::NullParameter,
             SignalFfiError::InvalidType => SignalErrorCode::InvalidType,
 
-            SignalFfiError::UnexpectedPanic(_)
+            SignalFfiError::UnexpectedPanic(_, _)
             | SignalFfiError::Signal(SignalProtocolError::InternalError(_))
             | SignalFfiError::DeviceTransfer(DeviceTransferError::InternalError(_))
             | SignalFfiError::Signal(SignalProtocolError::FfiBindingError(_))
@@ -72,6 +117,7 @@ impl From<&SignalFfiError> for SignalErrorCode {
 
             SignalFfiError::InvalidUtf8String => SignalErrorCode::InvalidUtf8String,
             SignalFfiError::InsufficientOutputSize(_, _) => SignalErrorCode::InsufficientOutputSize,
+            SignalFfiError::BufferTooLarge(_) => SignalErrorCode::BufferTooLarge,
 
             SignalFfiError::Signal(SignalProtocolError::ProtobufEncodingError(_))
             | SignalFfiError::Signal(SignalProtocolError::ProtobufDecodingError(_)) => {
@@ -92,6 +138,14 @@ impl From<&SignalFfiError> for SignalErrorCode {
                 SignalErrorCode::SealedSenderSelfSend
             }
 
+            SignalFfiError::Signal(SignalProtocolError::SealedSenderCertificateExpired(_)) => {
+                SignalErrorCode::SealedSenderCertificateExpired
+            }
+
+            SignalFfiError::Signal(SignalProtocolError::SealedSenderReplay) => {
+                SignalErrorCode::SealedSenderReplay
+            }
+
             SignalFfiError::Signal(SignalProtocolError::SignatureValidationFailed) => {
                 SignalErrorCode::InvalidSignature
             }
@@ -100,14 +154,28 @@ impl From<&SignalFfiError> for SignalErrorCode {
             | SignalFfiError::Signal(SignalProtocolError::BadKeyType(_))
             | SignalFfiError::Signal(SignalProtocolError::BadKeyLength(_, _))
             | SignalFfiError::DeviceTransfer(DeviceTransferError::KeyDecodingFailed)
-            | SignalFfiError::SignalCrypto(SignalCryptoError::InvalidKeySize) => {
+            | SignalFfiError::SignalCrypto(SignalCryptoError::InvalidKeySize(_, _))
+            | SignalFfiError::SignalCrypto(SignalCryptoError::InvalidKeyEncoding)
+            | SignalFfiError::MessageBackup(MessageBackupError::InvalidKeySize(_, _)) => {
                 SignalErrorCode::InvalidKey
             }
 
+            SignalFfiError::SignalCrypto(SignalCryptoError::InvalidWrappedKey) => {
+                SignalErrorCode::InvalidWrappedKey
+            }
+
             SignalFfiError::Signal(SignalProtocolError::SessionNotFound(_)) => {
                 SignalErrorCode::SessionNotFound
             }
 
+            SignalFfiError::Signal(SignalProtocolError::InvalidRegistrationId(_, _)) => {
+                SignalErrorCode::InvalidRegistrationId
+            }
+
+            SignalFfiError::Signal(SignalProtocolError::InvalidSenderKeySession(_)) => {
+                SignalErrorCode::InvalidSenderKeySession
+            }
+
             SignalFfiError::Signal(SignalProtocolError::FingerprintIdentifierMismatch) => {
                 SignalErrorCode::FingerprintIdentifierMismatch
             }
@@ -137,6 +205,7 @@ impl From<&SignalFfiError> for SignalErrorCode {
 
             SignalFfiError::Signal(SignalProtocolError::InvalidMessage(_))
             | SignalFfiError::Signal(SignalProtocolError::InvalidProtobufEncoding)
+            | SignalFfiError::Signal(SignalProtocolError::RecordLimitExceeded(_, _))
             | SignalFfiError::Signal(SignalProtocolError::InvalidSealedSenderMessage(_)) => {
                 SignalErrorCode::InvalidMessage
             }
@@ -151,16 +220,47 @@ impl From<&SignalFfiError> for SignalErrorCode {
 
             SignalFfiError::Signal(SignalProtocolError::InvalidState(_, _))
             | SignalFfiError::Signal(SignalProtocolError::NoSenderKeyState)
-            | SignalFfiError::Signal(SignalProtocolError::InvalidSessionStructure) => {
+            | SignalFfiError::Signal(SignalProtocolError::InvalidSessionStructure)
+            | SignalFfiError::MessageBackup(MessageBackupError::InvalidState)
+            | SignalFfiError::KeyTransparency(KeyTransparencyError::NotMonitored) => {
                 SignalErrorCode::InvalidState
             }
 
             SignalFfiError::Signal(SignalProtocolError::InvalidArgument(_))
-            | SignalFfiError::SignalCrypto(_) => SignalErrorCode::InvalidArgument,
+            | SignalFfiError::SignalCrypto(_)
+            | SignalFfiError::MessageBackup(MessageBackupError::InvalidRecord(_))
+            | SignalFfiError::Registration(RegistrationError::InvalidSessionId)
+            | SignalFfiError::Registration(RegistrationError::InvalidMasterKeySize(_)) => {
+                SignalErrorCode::InvalidArgument
+            }
 
             SignalFfiError::Signal(SignalProtocolError::ApplicationCallbackError(_, _)) => {
                 SignalErrorCode::CallbackError
             }
+
+            SignalFfiError::MessageBackup(MessageBackupError::Truncated) => {
+                SignalErrorCode::TruncatedBackup
+            }
+
+            SignalFfiError::MessageBackup(_) => SignalErrorCode::InvalidBackup,
+
+            SignalFfiError::KeyTransparency(_) => {
+                SignalErrorCode::KeyTransparencyVerificationFailed
+            }
+
+            SignalFfiError::Registration(RegistrationError::RateLimited { .. }) => {
+                SignalErrorCode::RateLimited
+            }
+
+            SignalFfiError::Registration(RegistrationError::SessionNotFound) => {
+                SignalErrorCode::RegistrationSessionNotFound
+            }
+
+            SignalFfiError::Registration(_) => SignalErrorCode::RegistrationFailed,
+
+            SignalFfiError::Signal(SignalProtocolError::WithContext(_, _)) => {
+                unreachable!("converted to SignalFfiError::WithContext")
+            }
         }
     }
 }
@@ -198,6 +298,6 @@ pub(crate) unsafe fn write_optional_cstr_to(
             *out = std::ptr::null();
             Ok(())
         }
-        Err(e) => Err(SignalFfiError::Signal(e)),
+        Err(e) => Err(e.into()),
     }
 }
@@ -69,6 +69,8 @@ def translate_to_java(typ):
         "JObject": "Object",
         "JClass": "Class",
         "jbyteArray": "byte[]",
+        # Only used for batches of buffers.
+        "jobjectArray": "byte[][]",
         "jlongArray": "long[]",
         "ObjectHandle": "long",
         "jint": "int",
@@ -0,0 +1,87 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use jni::objects::{GlobalRef, JClass, JObject, JValue};
+use jni::sys::jint;
+use jni::{JNIEnv, JavaVM};
+use libsignal_bridge::audit::BridgedAuditEvent;
+use libsignal_bridge::jni::*;
+use libsignal_bridge::jni_signature;
+use libsignal_protocol::{set_audit_hook, AuditEvent, AuditHook};
+
+pub type JavaAuditHook<'a> = JObject<'a>;
+
+struct JniAuditHook {
+    vm: JavaVM,
+    hook: GlobalRef,
+}
+
+impl JniAuditHook {
+    fn new(env: &JNIEnv, hook: JObject) -> jni::errors::Result<Self> {
+        Ok(Self {
+            vm: env.get_java_vm()?,
+            hook: env.new_global_ref(hook)?,
+        })
+    }
+
+    fn record_impl(&self, event: BridgedAuditEvent) -> jni::errors::Result<()> {
+        let env = self.vm.attach_current_thread()?;
+        let group_id = match event.group_id {
+            Some(group_id) => env.new_string(group_id)?.into(),
+            None => JObject::null(),
+        };
+        let args: [JValue; 4] = [
+            (event.kind as jint).into(),
+            env.new_string(event.address_name)?.into(),
+            (event.device_id as jint).into(),
+            group_id.into(),
+        ];
+        let result = env.call_method(
+            self.hook.as_obj(),
+            "record",
+            jni_signature!((int, java.lang.String, int, java.lang.String) -> void),
+            &args,
+        );
+
+        let throwable = env.exception_occurred()?;
+        if **throwable == *JObject::null() {
+            result?;
+        } else {
+            env.exception_clear()?;
+        }
+        Ok(())
+    }
+}
+
+impl AuditHook for JniAuditHook {
+    fn record(&self, event: &AuditEvent) {
+        let result = match BridgedAuditEvent::new(event) {
+            Ok(event) => self.record_impl(event).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = result {
+            log::error!("failed to report audit event: {}", e);
+        }
+    }
+}
+
+/// Installs `hook` (an `org.signal.libsignal.AuditHook`), or removes the current hook if `hook` is
+/// null.
+#[no_mangle]
+pub unsafe extern "C" fn Java_org_signal_client_internal_Native_AuditHook_1Set(
+    env: JNIEnv,
+    _class: JClass,
+    hook: JavaAuditHook,
+) {
+    run_ffi_safe(&env, "AuditHook_Set", || {
+        if hook.is_null() {
+            set_audit_hook(None);
+        } else {
+            check_jobject_type(&env, hook, "org/signal/libsignal/AuditHook")?;
+            set_audit_hook(Some(Box::new(JniAuditHook::new(&env, hook)?)));
+        }
+        Ok(())
+    })
+}
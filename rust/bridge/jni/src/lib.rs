@@ -6,14 +6,15 @@
 #![allow(clippy::missing_safety_doc)]
 #![deny(clippy::unwrap_used)]
 
-use jni::objects::JClass;
-use jni::sys::{jbyteArray, jlongArray, jobject};
+use jni::objects::{JClass, JObject};
+use jni::sys::{jbyteArray, jlongArray, jobject, jobjectArray, jsize};
 use jni::JNIEnv;
 use std::convert::TryFrom;
 
 use libsignal_bridge::jni::*;
 use libsignal_protocol::*;
 
+pub mod audit;
 pub mod logging;
 
 type JavaCiphertextMessage = jobject;
@@ -24,7 +25,7 @@ pub unsafe extern "C" fn Java_org_signal_client_internal_Native_IdentityKeyPair_
     _class: JClass,
     data: jbyteArray,
 ) -> jlongArray {
-    run_ffi_safe(&env, || {
+    run_ffi_safe(&env, "IdentityKeyPair_Deserialize", || {
         let data = env.convert_byte_array(data)?;
         let key = IdentityKeyPair::try_from(data.as_ref())?;
 
@@ -47,7 +48,7 @@ pub unsafe extern "C" fn Java_org_signal_client_internal_Native_SessionCipher_1E
     session_store: JavaSessionStore,
     identity_key_store: JavaIdentityKeyStore,
 ) -> JavaCiphertextMessage {
-    run_ffi_safe(&env, || {
+    run_ffi_safe(&env, "SessionCipher_EncryptMessage", || {
         let message = env.convert_byte_array(message)?;
         let protocol_address = native_handle_cast::<ProtocolAddress>(protocol_address)?;
 
@@ -62,22 +63,104 @@ pub unsafe extern "C" fn Java_org_signal_client_internal_Native_SessionCipher_1E
             None,
         ))?;
 
-        let obj = match ctext {
-            CiphertextMessage::SignalMessage(m) => jobject_from_native_handle(
-                &env,
-                "org/whispersystems/libsignal/protocol/SignalMessage",
-                box_object::<SignalMessage>(Ok(m))?,
-            ),
-            CiphertextMessage::PreKeySignalMessage(m) => jobject_from_native_handle(
-                &env,
-                "org/whispersystems/libsignal/protocol/PreKeySignalMessage",
-                box_object::<PreKeySignalMessage>(Ok(m))?,
-            ),
-            _ => Err(SignalJniError::Signal(SignalProtocolError::InternalError(
-                "Unexpected result type from message_encrypt",
-            ))),
-        };
-
-        Ok(obj?.into_inner())
+        Ok(ciphertext_message_to_jobject(&env, ctext)?.into_inner())
     })
 }
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_org_signal_client_internal_Native_SessionCipher_1EncryptMessageWithIdentityOverride(
+    env: JNIEnv,
+    _class: JClass,
+    message: jbyteArray,
+    protocol_address: ObjectHandle,
+    approved_identity_key: ObjectHandle,
+    session_store: JavaSessionStore,
+    identity_key_store: JavaIdentityKeyStore,
+) -> JavaCiphertextMessage {
+    run_ffi_safe(
+        &env,
+        "SessionCipher_EncryptMessageWithIdentityOverride",
+        || {
+            let message = env.convert_byte_array(message)?;
+            let protocol_address = native_handle_cast::<ProtocolAddress>(protocol_address)?;
+            let approved_identity_key = native_handle_cast::<PublicKey>(approved_identity_key)?;
+
+            let mut identity_key_store = JniIdentityKeyStore::new(&env, identity_key_store)?;
+            let mut session_store = JniSessionStore::new(&env, session_store)?;
+
+            let ctext = expect_ready(message_encrypt_with_identity_override(
+                &message,
+                &protocol_address,
+                &IdentityKey::new(*approved_identity_key),
+                &mut session_store,
+                &mut identity_key_store,
+                None,
+            ))?;
+
+            Ok(ciphertext_message_to_jobject(&env, ctext)?.into_inner())
+        },
+    )
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_org_signal_client_internal_Native_SessionCipher_1EncryptMessageBatch(
+    env: JNIEnv,
+    _class: JClass,
+    message: jbyteArray,
+    recipients: jbyteArray,
+    session_store: JavaSessionStore,
+    identity_key_store: JavaIdentityKeyStore,
+) -> jobjectArray {
+    run_ffi_safe(&env, "SessionCipher_EncryptMessageBatch", || {
+        let message = env.convert_byte_array(message)?;
+
+        // Recipients come as a serialized service ID list rather than an array of
+        // ProtocolAddress handles, so that Java doesn't have to create a native object for each.
+        let protocol_addresses = parse_service_id_list(&env.convert_byte_array(recipients)?)?;
+
+        let mut identity_key_store = JniIdentityKeyStore::new(&env, identity_key_store)?;
+        let mut session_store = JniSessionStore::new(&env, session_store)?;
+
+        let ctexts = expect_ready(message_encrypt_batch(
+            &message,
+            &protocol_addresses,
+            &mut session_store,
+            &mut identity_key_store,
+            None,
+        ))?;
+
+        // Each element is either a CiphertextMessage or the Throwable explaining why that
+        // recipient's message could not be encrypted.
+        let result =
+            env.new_object_array(ctexts.len() as jsize, "java/lang/Object", JObject::null())?;
+        for (i, ctext) in ctexts.into_iter().enumerate() {
+            let obj = match ctext {
+                Ok(ctext) => ciphertext_message_to_jobject(&env, ctext)?,
+                Err(e) => error_to_throwable(&env, e.into())?,
+            };
+            env.set_object_array_element(result, i as jsize, obj)?;
+        }
+        Ok(result)
+    })
+}
+
+fn ciphertext_message_to_jobject<'a>(
+    env: &'a JNIEnv,
+    ctext: CiphertextMessage,
+) -> Result<JObject<'a>, SignalJniError> {
+    match ctext {
+        CiphertextMessage::SignalMessage(m) => jobject_from_native_handle(
+            env,
+            "org/whispersystems/libsignal/protocol/SignalMessage",
+            box_object::<SignalMessage>(Ok(m))?,
+        ),
+        CiphertextMessage::PreKeySignalMessage(m) => jobject_from_native_handle(
+            env,
+            "org/whispersystems/libsignal/protocol/PreKeySignalMessage",
+            box_object::<PreKeySignalMessage>(Ok(m))?,
+        ),
+        _ => Err(SignalJniError::Signal(SignalProtocolError::InternalError(
+            "Unexpected result type from message_encrypt",
+        ))),
+    }
+}
@@ -6,6 +6,7 @@
 use jni::objects::{GlobalRef, JClass, JObject, JValue};
 use jni::sys::jint;
 use jni::{JNIEnv, JavaVM};
+use libsignal_bridge::context::ContextLogger;
 use libsignal_bridge::jni_signature;
 use std::any::Any;
 use std::panic::{catch_unwind, AssertUnwindSafe};
@@ -167,7 +168,7 @@ pub unsafe extern "C" fn Java_org_signal_client_internal_Native_Logger_1Initiali
     abort_on_panic(|| {
         let logger = JniLogger::new(env, logger_class).expect("could not initialize logging");
 
-        match log::set_logger(Box::leak(Box::new(logger))) {
+        match log::set_logger(Box::leak(Box::new(ContextLogger(logger)))) {
             Ok(_) => {
                 set_max_level_from_java_level(max_level);
                 log::info!(
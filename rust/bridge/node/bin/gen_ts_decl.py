@@ -19,9 +19,13 @@ def print_usage_and_exit():
     sys.exit(2)
 
 
-def translate_to_ts(typ):
+def translate_to_ts(typ, is_result=False):
     typ = typ.replace(' ', '')
 
+    # Timestamps are passed in as numbers but returned as Dates.
+    if is_result and typ == 'Timestamp':
+        return 'Date'
+
     type_map = {
         "()": "void",
         "&[u8]": "Buffer",
@@ -29,16 +33,30 @@ def translate_to_ts(typ):
         "u8": "number",
         "u32": "number",
         "u64": "number",
+        "Timestamp": "number",
         "bool": "boolean",
         "String": "string",
         "&str": "string",
         "Vec<u8>": "Buffer",
         "Context": "null",
+        "Vec<Result<CiphertextMessage>>": "EncryptBatchResult[]",
     }
 
     if typ in type_map:
         return type_map[typ]
 
+    if typ.startswith('&['):
+        assert(typ.endswith(']'))
+        return translate_to_ts('&' + typ[2:-1], is_result) + '[]'
+
+    if typ.startswith('Vec<Result<'):
+        # Errors inside a returned array are returned, not thrown.
+        return '(' + translate_to_ts(typ[4:-1], is_result) + ' | Error)[]'
+
+    if typ.startswith('Vec<'):
+        assert(typ.endswith('>'))
+        return translate_to_ts(typ[4:-1], is_result) + '[]'
+
     if typ.startswith('&mutdyn'):
         return typ[7:]
 
@@ -50,7 +68,7 @@ def translate_to_ts(typ):
 
     if typ.startswith('Option<'):
         assert(typ.endswith('>'))
-        return translate_to_ts(typ[7:-1]) + ' | null'
+        return translate_to_ts(typ[7:-1], is_result) + ' | null'
 
     if typ.startswith('Result<'):
         assert(typ.endswith('>'))
@@ -58,11 +76,11 @@ def translate_to_ts(typ):
             success_type = typ[7:].split(',')[0]
         else:
             success_type = typ[7:-1]
-        return translate_to_ts(success_type)
+        return translate_to_ts(success_type, is_result)
 
     if typ.startswith('Promise<'):
         assert(typ.endswith('>'))
-        return 'Promise<' + translate_to_ts(typ[8:-1]) + '>'
+        return 'Promise<' + translate_to_ts(typ[8:-1], is_result) + '>'
 
     return typ
 
@@ -119,6 +137,9 @@ def collect_decls(crate_dir, features=''):
     # which won't survive textual splitting below.
     function_sig = re.compile(r'(.+)\(([^()]*)\): (.+);?')
 
+    # Structs deriving BridgeStruct are passed as plain objects.
+    struct_decl = re.compile(r'struct (\w+) \{ (.*) \}')
+
     for line in stdout.split('\n'):
         match = comment_decl.match(line) or attr_decl.match(line)
         if match is None:
@@ -126,6 +147,16 @@ def collect_decls(crate_dir, features=''):
 
         (decl,) = match.groups()
 
+        struct_match = struct_decl.match(decl)
+        if struct_match is not None:
+            (struct_name, fields) = struct_match.groups()
+            ts_fields = []
+            for field in fields.split('; ') if fields else []:
+                (field_name, field_type) = field.split(': ')
+                ts_fields.append('%s: %s;' % (field_name, translate_to_ts(field_type)))
+            yield 'interface %s { %s }' % (struct_name, ' '.join(ts_fields))
+            continue
+
         function_match = function_sig.match(decl)
         if function_match is None:
             yield decl
@@ -133,7 +164,7 @@ def collect_decls(crate_dir, features=''):
 
         (prefix, args, ret_type) = function_match.groups()
 
-        ts_ret_type = translate_to_ts(ret_type)
+        ts_ret_type = translate_to_ts(ret_type, is_result=True)
         ts_args = []
         if args:
             for arg in args.split(', '):
@@ -15,6 +15,9 @@ use std::task::{Poll, Waker};
 use crate::result::*;
 use crate::util::call_method;
 
+mod batch;
+pub use batch::JsCallBatcher;
+
 mod builder;
 pub use builder::JsFutureBuilder;
 
@@ -0,0 +1,239 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use neon::prelude::*;
+use std::sync::{Arc, Mutex};
+
+use crate::future::*;
+
+/// Produces the argument for a single call, in the JavaScript context where the call is made.
+type MakeArgument = Box<dyn for<'a> FnOnce(&mut TaskContext<'a>) -> JsResult<'a, JsValue> + Send>;
+
+/// Connects the promise produced for a single call (or the exception thrown while making it)
+/// to the [JsFuture] waiting on it.
+trait SettleCall: Send {
+    fn settle<'a>(
+        self: Box<Self>,
+        cx: &mut TaskContext<'a>,
+        promise: Result<Handle<'a, JsObject>, Handle<'a, JsValue>>,
+    ) -> NeonResult<()>;
+}
+
+impl<T: Send + 'static> SettleCall for WeakFutureToken<T> {
+    fn settle<'a>(
+        self: Box<Self>,
+        cx: &mut TaskContext<'a>,
+        promise: Result<Handle<'a, JsObject>, Handle<'a, JsValue>>,
+    ) -> NeonResult<()> {
+        let bound_reject = self.bind_settle_promise::<_, JsRejectedResult>(cx)?;
+        match promise {
+            Ok(promise) => {
+                let bound_fulfill = self.bind_settle_promise::<_, JsFulfilledResult>(cx)?;
+                call_method(cx, promise, "then", vec![bound_fulfill, bound_reject])?;
+            }
+            Err(exception) => {
+                let undef = cx.undefined();
+                bound_reject.downcast_or_throw::<JsFunction, _>(cx)?.call(
+                    cx,
+                    undef,
+                    vec![exception],
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct PendingCall {
+    object: Arc<Root<JsObject>>,
+    method: &'static str,
+    batch_method: &'static str,
+    argument: MakeArgument,
+    settle: Box<dyn SettleCall>,
+}
+
+/// Calls on the same object and method collected during a single flush.
+struct CallGroup<'a> {
+    object: Handle<'a, JsObject>,
+    method: &'static str,
+    batch_method: &'static str,
+    calls: Vec<(MakeArgument, Box<dyn SettleCall>)>,
+}
+
+/// Coalesces JavaScript method calls that are requested before the JavaScript thread gets a chance to run them.
+///
+/// Each call made through [JsCallBatcher::call] names both a single-argument method and a batched variant.
+/// All calls pending when the JavaScript thread next runs are grouped by object and method.
+/// If a group has more than one call and the object has a function for the batched method,
+/// that function is invoked once with an array of all the arguments, and is expected to produce a promise
+/// for an array of results in the same order. Otherwise, the single-argument method is called once per argument.
+///
+/// Either way, each call gets its own [JsFuture], so callers can't tell whether their call was batched.
+#[derive(Clone)]
+pub struct JsCallBatcher {
+    queue: Arc<EventQueue>,
+    pending: Arc<Mutex<Vec<PendingCall>>>,
+}
+
+impl JsCallBatcher {
+    /// Creates a batcher that will make its calls on `queue`.
+    pub fn new(queue: EventQueue) -> Self {
+        Self {
+            queue: Arc::new(queue),
+            pending: Default::default(),
+        }
+    }
+
+    /// Schedules a call to `object[method](argument)`, or a batched call to `object[batch_method]([argument, ...])`.
+    ///
+    /// `argument` will be run in the JavaScript context where the call is made.
+    /// When the call's promise settles, `transform` will be invoked to produce the result of the Rust future,
+    /// as in [JsFutureBuilder::then]. If the call throws, `transform` is given the exception as a rejection.
+    pub fn call<T, A, XF>(
+        &self,
+        object: Arc<Root<JsObject>>,
+        method: &'static str,
+        batch_method: &'static str,
+        argument: A,
+        transform: XF,
+    ) -> JsFuture<T>
+    where
+        T: 'static + Send,
+        A: for<'a> FnOnce(&mut TaskContext<'a>) -> JsResult<'a, JsValue> + 'static + Send,
+        XF: for<'b> FnOnce(&mut FunctionContext<'b>, JsPromiseResult<'b>) -> T + 'static + Send,
+    {
+        let future = JsFuture::new(transform);
+        let settle_token = WeakFutureToken::new(&future);
+
+        let is_first_pending_call = {
+            let mut pending = self.pending.lock().expect("Lock can be taken");
+            pending.push(PendingCall {
+                object,
+                method,
+                batch_method,
+                argument: Box::new(argument),
+                settle: Box::new(settle_token),
+            });
+            pending.len() == 1
+        };
+
+        // Only the first call needs to schedule a flush; later calls will be picked up by the same one.
+        if is_first_pending_call {
+            let pending = self.pending.clone();
+            self.queue.send(move |mut cx| flush(&mut cx, &pending));
+        }
+
+        future
+    }
+}
+
+/// Runs every call pending in `pending`, batching where possible.
+fn flush(cx: &mut TaskContext, pending: &Mutex<Vec<PendingCall>>) -> NeonResult<()> {
+    let calls = std::mem::take(&mut *pending.lock().expect("Lock can be taken"));
+
+    let mut groups: Vec<CallGroup> = Vec::new();
+    for call in calls {
+        let PendingCall {
+            object,
+            method,
+            batch_method,
+            argument,
+            settle,
+        } = call;
+        let object_handle = object.to_inner(cx);
+        object.finalize(cx);
+
+        let mut existing_group = None;
+        for (i, group) in groups.iter().enumerate() {
+            if group.method == method && group.object.strict_equals(cx, object_handle) {
+                existing_group = Some(i);
+                break;
+            }
+        }
+        match existing_group {
+            Some(i) => groups[i].calls.push((argument, settle)),
+            None => groups.push(CallGroup {
+                object: object_handle,
+                method,
+                batch_method,
+                calls: vec![(argument, settle)],
+            }),
+        }
+    }
+
+    for group in groups {
+        flush_group(cx, group)?;
+    }
+    Ok(())
+}
+
+fn flush_group<'a>(cx: &mut TaskContext<'a>, group: CallGroup<'a>) -> NeonResult<()> {
+    let CallGroup {
+        object,
+        method,
+        batch_method,
+        calls,
+    } = group;
+
+    if calls.len() > 1 {
+        let batch_fn = object.get(cx, batch_method)?;
+        if batch_fn.is_a::<JsFunction, _>(cx) {
+            return flush_group_as_batch(cx, object, batch_method, calls);
+        }
+    }
+
+    for (argument, settle) in calls {
+        let promise = cx.try_catch(|cx| {
+            let argument = argument(cx)?;
+            call_method(cx, object, method, vec![argument])?.downcast_or_throw(cx)
+        });
+        settle.settle(cx, promise)?;
+    }
+    Ok(())
+}
+
+fn flush_group_as_batch<'a>(
+    cx: &mut TaskContext<'a>,
+    object: Handle<'a, JsObject>,
+    batch_method: &'static str,
+    calls: Vec<(MakeArgument, Box<dyn SettleCall>)>,
+) -> NeonResult<()> {
+    let (arguments, settles): (Vec<_>, Vec<_>) = calls.into_iter().unzip();
+
+    let batch_promise = cx.try_catch(|cx| {
+        let arguments_array = JsArray::new(cx, arguments.len() as u32);
+        for (i, argument) in arguments.into_iter().enumerate() {
+            let argument = argument(cx)?;
+            arguments_array.set(cx, i as u32, argument)?;
+        }
+        call_method(cx, object, batch_method, vec![arguments_array.upcast()])?
+            .downcast_or_throw::<JsObject, _>(cx)
+    });
+
+    for (i, settle) in settles.into_iter().enumerate() {
+        let promise = batch_promise.and_then(|batch_promise| {
+            cx.try_catch(|cx| {
+                let select = JsFunction::new(cx, select_batch_result)?;
+                let bind_args = vec![cx.undefined().upcast(), cx.number(i as f64).upcast()];
+                let bound_select = call_method(cx, select, "bind", bind_args)?;
+                call_method(cx, batch_promise, "then", vec![bound_select])?.downcast_or_throw(cx)
+            })
+        });
+        settle.settle(cx, promise)?;
+    }
+    Ok(())
+}
+
+/// Registered as the fulfillment callback on a batched call's promise.
+///
+/// This callback assumes its first (bound) argument is the index of the result to extract from the array of results.
+fn select_batch_result(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let index = cx.argument::<JsNumber>(0)?.value(&mut cx) as u32;
+    let results = cx.argument::<JsArray>(1)?;
+    if index >= results.len(&mut cx) {
+        return cx.throw_error("batched call returned too few results");
+    }
+    results.get(&mut cx, index)
+}
@@ -30,7 +30,7 @@ mod exception;
 pub use exception::PersistentException;
 
 mod future;
-pub use future::{JsFuture, JsFutureBuilder};
+pub use future::{JsCallBatcher, JsFuture, JsFutureBuilder};
 
 mod promise;
 pub use promise::{promise, settle_promise};
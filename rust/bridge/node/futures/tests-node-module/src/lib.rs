@@ -73,6 +73,7 @@ register_module!(mut cx, {
         "doubleNameFromStoreUsingJoin",
         double_name_from_store_using_join,
     )?;
+    cx.export_function("twoNamesFromBatchedStore", two_names_from_batched_store)?;
 
     cx.export_function("panicPreAwait", panic_pre_await)?;
     cx.export_function("panicDuringCallback", panic_during_callback)?;
@@ -100,3 +100,70 @@ pub fn double_name_from_store_using_join(mut cx: FunctionContext) -> JsResult<Js
         })
     })
 }
+
+struct BatchedNameStore {
+    batcher: JsCallBatcher,
+    store_object: Arc<Root<JsObject>>,
+}
+
+impl BatchedNameStore {
+    fn new<'a>(cx: &mut FunctionContext<'a>, store: Handle<'a, JsObject>) -> Self {
+        Self {
+            batcher: JsCallBatcher::new(cx.queue()),
+            store_object: Arc::new(store.root(cx)),
+        }
+    }
+
+    async fn get_name(&self, id: u32) -> Result<String, String> {
+        self.batcher
+            .call(
+                self.store_object.clone(),
+                "getName",
+                "getNames",
+                move |cx| Ok(cx.number(id).upcast()),
+                |cx, result| match result {
+                    Ok(value) => match value.downcast::<JsString, _>(cx) {
+                        Ok(s) => Ok(s.value(cx)),
+                        Err(_) => Err("name must be a string".into()),
+                    },
+                    Err(error) => Err(error
+                        .to_string(cx)
+                        .expect("can convert to string")
+                        .value(cx)),
+                },
+            )
+            .await
+    }
+}
+
+impl Finalize for BatchedNameStore {
+    fn finalize<'a, C: Context<'a>>(self, cx: &mut C) {
+        self.store_object.finalize(cx)
+    }
+}
+
+async fn two_names_from_batched_store_impl(store: &BatchedNameStore) -> Result<String, String> {
+    let names = try_join!(store.get_name(1), store.get_name(2))?;
+    Ok(format!("{0} {1}", names.0, names.1))
+}
+
+// function twoNamesFromBatchedStore(store: {
+//   getName: (id: number) => Promise<string>,
+//   getNames?: (ids: number[]) => Promise<string[]>,
+// }): Promise<string>
+pub fn two_names_from_batched_store(mut cx: FunctionContext) -> JsResult<JsObject> {
+    let js_store = cx.argument(0)?;
+    let store = BatchedNameStore::new(&mut cx, js_store);
+
+    promise(&mut cx, async move {
+        let future = AssertUnwindSafe(two_names_from_batched_store_impl(&store));
+        let result = future.await;
+        settle_promise(move |cx| {
+            store.finalize(cx);
+            match result {
+                Ok(names) => Ok(cx.string(names)),
+                Err(message) => cx.throw_error(format!("rejected: {}", message)),
+            }
+        })
+    })
+}
@@ -78,6 +78,46 @@ describe('native', () => {
     await assert.isRejected(promise, /rejected: uh oh/);
   });
 
+  describe('batching', () => {
+    it('batches parallel store-like callbacks', async () => {
+      const calls = [];
+      const result = await native.twoNamesFromBatchedStore({
+        getName: id => Promise.reject(`unbatched ${id}`),
+        getNames: ids => {
+          calls.push(ids);
+          return Promise.resolve(ids.map(id => `Moxie ${id}`));
+        },
+      });
+      assert.equal(result, 'Moxie 1 Moxie 2');
+      assert.deepEqual(calls, [[1, 2]]);
+    });
+
+    it('falls back to individual callbacks', async () => {
+      const result = await native.twoNamesFromBatchedStore({
+        getName: id => Promise.resolve(`Moxie ${id}`),
+      });
+      assert.equal(result, 'Moxie 1 Moxie 2');
+    });
+
+    it('can handle batched callbacks that fail', async () => {
+      const promise = native.twoNamesFromBatchedStore({
+        getName: id => Promise.resolve(`Moxie ${id}`),
+        getNames: () => Promise.reject('uh oh'),
+      });
+      await assert.isRejected(promise, /rejected: uh oh/);
+    });
+
+    it('can handle batched callbacks that throw', async () => {
+      const promise = native.twoNamesFromBatchedStore({
+        getName: id => Promise.resolve(`Moxie ${id}`),
+        getNames: () => {
+          throw 'uh oh';
+        },
+      });
+      await assert.isRejected(promise, /rejected: uh oh/);
+    });
+  });
+
   describe('promises', () => {
     it('can fulfill promises', async () => {
       const result = await native.incrementPromise(Promise.resolve(5));
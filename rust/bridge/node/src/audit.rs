@@ -0,0 +1,80 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use libsignal_bridge::audit::BridgedAuditEvent;
+use libsignal_protocol::{AuditEvent, AuditHook};
+use neon::prelude::*;
+
+struct NodeAuditHook {
+    queue: EventQueue,
+}
+
+const GLOBAL_AUDIT_FN_KEY: &str = "__libsignal_audit_fn";
+
+impl AuditHook for NodeAuditHook {
+    fn record(&self, event: &AuditEvent) {
+        let event = match BridgedAuditEvent::new(event) {
+            Ok(event) => event,
+            Err(e) => {
+                log::error!("failed to report audit event: {}", e);
+                return;
+            }
+        };
+        self.queue
+            .try_send(move |mut cx| {
+                let kind_arg: Handle<JsValue> = cx.number(event.kind as u32).upcast();
+                let address_name_arg: Handle<JsValue> = cx.string(event.address_name).upcast();
+                let device_id_arg: Handle<JsValue> = cx.number(event.device_id).upcast();
+                let group_id_arg: Handle<JsValue> = match event.group_id {
+                    Some(group_id) => cx.string(group_id).upcast(),
+                    None => cx.null().upcast(),
+                };
+
+                // The hook may have been removed since the event was queued.
+                let global_obj = cx.global();
+                let audit_fn = match global_obj
+                    .get(&mut cx, GLOBAL_AUDIT_FN_KEY)?
+                    .downcast::<JsFunction, _>(&mut cx)
+                {
+                    Ok(audit_fn) => audit_fn,
+                    Err(_) => return Ok(()),
+                };
+                let undef = cx.undefined();
+                audit_fn.call(
+                    &mut cx,
+                    undef,
+                    vec![kind_arg, address_name_arg, device_id_arg, group_id_arg],
+                )?;
+                Ok(())
+            })
+            .unwrap_or_else(|_| {
+                // Most likely the Node event loop has already shut down.
+            });
+    }
+}
+
+// Keep AuditEventKind in sync with libsignal_bridge::audit::AuditEventKind.
+/// ts: export const enum AuditEventKind { IdentityChanged = 1, SessionArchived, SenderKeyCreated, SenderKeyFastForwarded }
+/// ts: export function setAuditHook(callback: ((kind: AuditEventKind, addressName: string, deviceId: number, groupId: string | null) => void) | null): void
+pub(crate) fn set_audit_hook(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+    let callback = cx.argument::<JsValue>(0)?;
+    let global = cx.global();
+
+    match callback.downcast::<JsFunction, _>(&mut cx) {
+        Ok(callback) => {
+            global.set(&mut cx, GLOBAL_AUDIT_FN_KEY, callback)?;
+            let mut queue = cx.queue();
+            queue.unref(&mut cx);
+            libsignal_protocol::set_audit_hook(Some(Box::new(NodeAuditHook { queue })));
+        }
+        Err(_) => {
+            libsignal_protocol::set_audit_hook(None);
+            let undef = cx.undefined();
+            global.set(&mut cx, GLOBAL_AUDIT_FN_KEY, undef)?;
+        }
+    }
+
+    Ok(cx.undefined())
+}
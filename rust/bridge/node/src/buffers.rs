@@ -0,0 +1,22 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use libsignal_bridge::node::{set_buffer_checksum_policy, BufferChecksumPolicy, SimpleArgTypeInfo};
+use neon::prelude::*;
+use std::convert::TryFrom;
+
+// The values here must match libsignal_bridge::node::BufferChecksumPolicy.
+/// ts: export const enum BufferChecksumPolicy { Off = 0, Prefix, Full, FullWithPanic, Copy }
+///
+/// ts: export function setBufferChecksumPolicy(policy: BufferChecksumPolicy): void
+pub(crate) fn set_checksum_policy(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+    let policy_arg = cx.argument::<JsNumber>(0)?;
+    let policy = u32::convert_from(&mut cx, policy_arg)?;
+    match BufferChecksumPolicy::try_from(policy) {
+        Ok(policy) => set_buffer_checksum_policy(policy),
+        Err(value) => return cx.throw_range_error(format!("invalid checksum policy {}", value)),
+    }
+    Ok(cx.undefined())
+}
@@ -0,0 +1,13 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use neon::prelude::*;
+
+/// ts: export function registerErrorClasses(classes: Record<string, new (message: string) => Error>): void
+pub(crate) fn register_error_classes(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+    let classes = cx.argument::<JsObject>(0)?;
+    libsignal_bridge::node::register_error_classes(&mut cx, classes);
+    Ok(cx.undefined())
+}
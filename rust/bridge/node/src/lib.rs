@@ -7,11 +7,17 @@
 
 use neon::prelude::*;
 
+pub mod audit;
+pub mod buffers;
+pub mod errors;
 pub mod logging;
 
 #[neon::main]
 fn main(mut cx: ModuleContext) -> NeonResult<()> {
     libsignal_bridge::node::register(&mut cx)?;
     cx.export_function("initLogger", logging::init_logger)?;
+    cx.export_function("registerErrorClasses", errors::register_error_classes)?;
+    cx.export_function("setBufferChecksumPolicy", buffers::set_checksum_policy)?;
+    cx.export_function("setAuditHook", audit::set_audit_hook)?;
     Ok(())
 }
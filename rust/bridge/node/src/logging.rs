@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use libsignal_bridge::context::ContextLogger;
 use libsignal_bridge::node::SimpleArgTypeInfo;
 use neon::prelude::*;
 
@@ -133,7 +134,7 @@ pub(crate) fn init_logger(mut cx: FunctionContext) -> JsResult<JsUndefined> {
     global.set(&mut cx, GLOBAL_LOG_FN_KEY, callback)?;
 
     let logger = NodeLogger::new(&mut cx);
-    match log::set_logger(Box::leak(Box::new(logger))) {
+    match log::set_logger(Box::leak(Box::new(ContextLogger(logger)))) {
         Ok(_) => {
             set_max_level_from_js_level(max_level);
             log::info!(
@@ -0,0 +1,252 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use heck::MixedCase;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::*;
+use syn::spanned::Spanned;
+use syn::*;
+
+/// A named field of a `#[derive(BridgeStruct)]` type.
+struct StructField<'a> {
+    ident: &'a Ident,
+    ty: &'a Type,
+    /// The name of the corresponding JavaScript property or Java field.
+    foreign_name: String,
+}
+
+/// Checks whether a field type is syntactically `Option<_>`.
+fn is_optional(ty: &Type) -> bool {
+    match ty {
+        Type::Path(TypePath { qself: None, path }) => path
+            .segments
+            .last()
+            .map_or(false, |segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+/// Checks for `#[repr(C)]`, which is required to pass the struct to C by pointer.
+fn is_repr_c(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path.is_ident("repr") && attr.parse_args::<Ident>().map_or(false, |repr| repr == "C")
+    })
+}
+
+/// Finds the `jni = "org/signal/..."` class name in a `#[bridge_struct(...)]` attribute, if any.
+fn jni_class_name(attrs: &[Attribute]) -> Result<Option<LitStr>> {
+    let mut class_name = None;
+    for attr in attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("bridge_struct"))
+    {
+        match attr.parse_meta()? {
+            Meta::List(list) => {
+                for nested in list.nested {
+                    match nested {
+                        NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                            path,
+                            lit: Lit::Str(name),
+                            ..
+                        })) if path.is_ident("jni") => class_name = Some(name),
+                        other => {
+                            return Err(Error::new(
+                                other.span(),
+                                "expected `jni = \"path/to/JavaClass\"`",
+                            ))
+                        }
+                    }
+                }
+            }
+            other => return Err(Error::new(other.span(), "expected `bridge_struct(...)`")),
+        }
+    }
+    Ok(class_name)
+}
+
+fn node_impls(name: &Ident, fields: &[StructField]) -> TokenStream2 {
+    let field_loading = fields.iter().map(|field| {
+        let ident = field.ident;
+        let foreign_name = &field.foreign_name;
+        if is_optional(field.ty) {
+            quote!(#ident: node::get_optional_struct_field(cx, foreign, #foreign_name)?)
+        } else {
+            quote!(#ident: node::get_struct_field(cx, foreign, #foreign_name)?)
+        }
+    });
+    let field_storing = fields.iter().map(|field| {
+        let ident = field.ident;
+        let foreign_name = &field.foreign_name;
+        quote! {
+            let value = node::ResultTypeInfo::convert_into(self.#ident, cx)?;
+            node::Object::set(*object, cx, #foreign_name, value)?;
+        }
+    });
+
+    // Picked up by gen_ts_decl.py, which translates the field types.
+    let ts_fields = fields
+        .iter()
+        .map(|field| {
+            let ty = field.ty;
+            format!("{}: {}", field.foreign_name, quote!(#ty))
+        })
+        .collect::<Vec<_>>();
+    let ts_decl = format!("ts: struct {} {{ {} }}", name, ts_fields.join("; "));
+
+    quote! {
+        #[cfg(feature = "node")]
+        #[doc = #ts_decl]
+        impl node::SimpleArgTypeInfo for #name {
+            type ArgType = node::JsObject;
+            fn convert_from(
+                cx: &mut node::FunctionContext,
+                foreign: node::Handle<Self::ArgType>,
+            ) -> node::NeonResult<Self> {
+                Ok(Self {
+                    #(#field_loading),*
+                })
+            }
+        }
+
+        #[cfg(feature = "node")]
+        impl<'a> node::ResultTypeInfo<'a> for #name {
+            type ResultType = node::JsObject;
+            fn convert_into(
+                self,
+                cx: &mut impl node::Context<'a>,
+            ) -> node::NeonResult<node::Handle<'a, Self::ResultType>> {
+                let object = cx.empty_object();
+                #(#field_storing)*
+                Ok(object)
+            }
+        }
+    }
+}
+
+fn jni_impls(name: &Ident, fields: &[StructField], class_name: Option<LitStr>) -> TokenStream2 {
+    let field_loading = fields.iter().map(|field| {
+        let ident = field.ident;
+        let foreign_name = &field.foreign_name;
+        quote!(#ident: jni::get_struct_field(env, foreign, #foreign_name)?)
+    });
+
+    let result_impl = class_name.map(|class_name| {
+        let field_types = fields.iter().map(|field| field.ty);
+        let field_values = fields.iter().map(|field| {
+            let ident = field.ident;
+            let ty = field.ty;
+            quote! {
+                <#ty as jni::StructFieldTypeInfo>::result_into_value(
+                    jni::ResultTypeInfo::convert_into(self.#ident, env)?,
+                )
+            }
+        });
+        quote! {
+            #[cfg(feature = "jni")]
+            impl jni::ResultTypeInfo for #name {
+                type ResultType = jni::jobject;
+                fn convert_into(self, env: &jni::JNIEnv) -> jni::SignalJniResult<Self::ResultType> {
+                    let ctor_sig = [
+                        "(",
+                        #(<#field_types as jni::StructFieldTypeInfo>::SIGNATURE,)*
+                        ")V",
+                    ]
+                    .concat();
+                    let ctor_args = [#(#field_values),*];
+                    Ok(env.new_object(#class_name, ctor_sig, &ctor_args)?.into_inner())
+                }
+            }
+        }
+    });
+
+    quote! {
+        #[cfg(feature = "jni")]
+        impl<'a> jni::SimpleArgTypeInfo<'a> for #name {
+            type ArgType = jni::JObject<'a>;
+            fn convert_from(
+                env: &jni::JNIEnv,
+                foreign: Self::ArgType,
+            ) -> jni::SignalJniResult<Self> {
+                if foreign.is_null() {
+                    return Err(jni::SignalJniError::NullHandle);
+                }
+                Ok(Self {
+                    #(#field_loading),*
+                })
+            }
+        }
+
+        #result_impl
+    }
+}
+
+fn ffi_impls(name: &Ident) -> TokenStream2 {
+    quote! {
+        #[cfg(feature = "ffi")]
+        impl ffi::SimpleArgTypeInfo for #name {
+            type ArgType = *const Self;
+            #[allow(clippy::not_unsafe_ptr_arg_deref)]
+            fn convert_from(foreign: *const Self) -> ffi::SignalFfiResult<Self> {
+                Ok(unsafe { ffi::native_handle_cast(foreign) }?.clone())
+            }
+        }
+
+        #[cfg(feature = "ffi")]
+        impl ffi::ResultTypeInfo for #name {
+            type ResultType = Self;
+            fn convert_into(self) -> ffi::SignalFfiResult<Self> {
+                Ok(self)
+            }
+        }
+    }
+}
+
+pub(crate) fn derive_bridge_struct(input: DeriveInput) -> Result<TokenStream2> {
+    let named_fields = match &input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => &fields.named,
+        _ => {
+            return Err(Error::new(
+                input.ident.span(),
+                "BridgeStruct can only be derived for structs with named fields",
+            ))
+        }
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "BridgeStruct cannot be derived for generic structs",
+        ));
+    }
+
+    let fields: Vec<StructField> = named_fields
+        .iter()
+        .map(|field| {
+            let ident = field.ident.as_ref().expect("named field");
+            StructField {
+                ident,
+                ty: &field.ty,
+                foreign_name: ident.to_string().to_mixed_case(),
+            }
+        })
+        .collect();
+
+    let name = &input.ident;
+    let node_impls = node_impls(name, &fields);
+    let jni_impls = jni_impls(name, &fields, jni_class_name(&input.attrs)?);
+    let ffi_impls = if is_repr_c(&input.attrs) {
+        Some(ffi_impls(name))
+    } else {
+        None
+    };
+
+    Ok(quote! {
+        #node_impls
+        #jni_impls
+        #ffi_impls
+    })
+}
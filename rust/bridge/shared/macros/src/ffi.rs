@@ -15,9 +15,18 @@ use crate::ResultKind;
 
 pub(crate) fn bridge_fn(name: String, sig: &Signature, result_kind: ResultKind) -> TokenStream2 {
     let name = format_ident!("signal_{}", name);
+    let name_str = name.to_string();
 
     let (output_args, env_arg, output_processing) = match (result_kind, &sig.output) {
         (ResultKind::Regular, ReturnType::Default) => (quote!(), quote!(), quote!()),
+        (ResultKind::Regular, ReturnType::Type(_, ref ty)) if is_sized_result(ty) => (
+            quote!(
+                out: *mut ffi_result_type!(#ty),
+                out_len: *mut libc::size_t, // note the trailing comma
+            ),
+            quote!(),
+            quote!(ffi::write_array_result_to(out, out_len, __result)?),
+        ),
         (ResultKind::Regular, ReturnType::Type(_, ref ty)) => (
             quote!(out: *mut ffi_result_type!(#ty),), // note the trailing comma
             quote!(),
@@ -31,7 +40,7 @@ pub(crate) fn bridge_fn(name: String, sig: &Signature, result_kind: ResultKind)
                 out_len: *mut libc::size_t, // note the trailing comma
             ),
             quote!(ffi::Env,), // note the trailing comma
-            quote!(ffi::write_bytearray_to(out, out_len, __result?)?),
+            quote!(ffi::write_buffer_result_to(out, out_len, __result?)?),
         ),
         (ResultKind::Buffer, ReturnType::Default) => {
             return Error::new(
@@ -63,14 +72,8 @@ pub(crate) fn bridge_fn(name: String, sig: &Signature, result_kind: ResultKind)
                 attrs,
                 pat: box Pat::Ident(name),
                 colon_token,
-                ty:
-                    ty
-                    @
-                    box Type::Reference(TypeReference {
-                        elem: box Type::Slice(_),
-                        ..
-                    }),
-            }) => {
+                ty,
+            }) if is_sized_arg(ty) => {
                 let size_arg = format_ident!("{}_len", name.ident);
                 (
                     name.ident.clone(),
@@ -112,7 +115,7 @@ pub(crate) fn bridge_fn(name: String, sig: &Signature, result_kind: ResultKind)
             #output_args
             #(#input_args),*
         ) -> *mut ffi::SignalFfiError {
-            ffi::run_ffi_safe(|| {
+            ffi::run_ffi_safe(#name_str, || {
                 #(#input_processing);*;
                 let __result = #orig_name(#env_arg #(#input_names),*);
                 #await_if_needed;
@@ -123,6 +126,221 @@ pub(crate) fn bridge_fn(name: String, sig: &Signature, result_kind: ResultKind)
     }
 }
 
+/// The last segment of `ty`'s path, if it is a plain path type.
+fn last_path_segment(ty: &Type) -> Option<&PathSegment> {
+    match ty {
+        Type::Path(TypePath { qself: None, path }) => path.segments.last(),
+        _ => None,
+    }
+}
+
+/// Whether `ty` is passed as a base pointer and a length, using `SizedArgTypeInfo`.
+fn is_sized_arg(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(TypeReference {
+            elem: box Type::Slice(_),
+            ..
+        }) => true,
+        _ => match generic_argument(ty, "Option") {
+            Some(inner) => is_sized_arg(inner),
+            None => last_path_segment(ty).map_or(false, |segment| segment.ident == "Vec"),
+        },
+    }
+}
+
+/// Whether `ty` (or the success type of a `Result`) is returned as a base pointer and a length,
+/// using `SizedResultTypeInfo`.
+fn is_sized_result(ty: &Type) -> bool {
+    let segment = match last_path_segment(ty) {
+        Some(segment) => segment,
+        None => return false,
+    };
+    if segment.ident == "Vec" {
+        return true;
+    }
+    if segment.ident != "Result" {
+        return false;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) => match arguments.args.first() {
+            Some(GenericArgument::Type(success_type)) => {
+                last_path_segment(success_type).map_or(false, |segment| segment.ident == "Vec")
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
 pub(crate) fn name_from_ident(ident: &Ident) -> String {
     ident.to_string().to_snake_case()
 }
+
+/// The single generic argument of `ty` if it is `name<T>`, such as `T` for `Option<T>`.
+fn generic_argument<'a>(ty: &'a Type, name: &str) -> Option<&'a Type> {
+    let segment = last_path_segment(ty).filter(|segment| segment.ident == name)?;
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) => match arguments.args.first() {
+            Some(GenericArgument::Type(argument)) => Some(argument),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Mirrors `ffi_arg_type!`, including the length parameter for sized arguments.
+fn ffi_arg_types(ty: &Type) -> Vec<Type> {
+    let ffi_type: Type = match quote!(#ty).to_string().replace(' ', "").as_str() {
+        "bool" => parse_quote!(bool),
+        "u8" => parse_quote!(u8),
+        "u32" | "Option<u32>" => parse_quote!(u32),
+        "u64" | "Timestamp" => parse_quote!(u64),
+        "usize" => parse_quote!(libc::size_t),
+        "&[u8]" | "Option<&[u8]>" => parse_quote!(*const libc::c_uchar),
+        "&mut[u8]" => parse_quote!(*mut libc::c_uchar),
+        "String" | "Option<String>" | "Option<&str>" => parse_quote!(*const libc::c_char),
+        "Context" => parse_quote!(*mut libc::c_void),
+        _ => match ty {
+            Type::Reference(TypeReference {
+                elem: box Type::TraitObject(TypeTraitObject { bounds, .. }),
+                ..
+            }) => {
+                let name = format_ident!("Ffi{}Struct", quote!(#bounds).to_string());
+                parse_quote!(*const #name)
+            }
+            Type::Reference(TypeReference {
+                mutability: Some(_),
+                elem,
+                ..
+            }) => parse_quote!(*mut #elem),
+            Type::Reference(TypeReference { elem, .. }) => parse_quote!(*const #elem),
+            _ => match (generic_argument(ty, "Option"), generic_argument(ty, "Vec")) {
+                (Some(Type::Reference(TypeReference { elem, .. })), _) => {
+                    parse_quote!(*const #elem)
+                }
+                (_, Some(Type::Reference(TypeReference { elem, .. }))) => {
+                    parse_quote!(*const *const #elem)
+                }
+                _ => ty.clone(),
+            },
+        },
+    };
+    if is_sized_arg(ty) {
+        vec![ffi_type, parse_quote!(libc::size_t)]
+    } else {
+        vec![ffi_type]
+    }
+}
+
+/// Mirrors `ffi_result_type!`.
+fn ffi_result_type(ty: &Type) -> Type {
+    // Results of `&T` and `Option<&T>` are returned the same way as `T`.
+    fn strip_reference(ty: &Type) -> &Type {
+        match ty {
+            Type::Reference(TypeReference { elem, .. }) if quote!(#elem).to_string() != "str" => {
+                elem
+            }
+            _ => ty,
+        }
+    }
+    let ty = strip_reference(generic_argument(ty, "Result").unwrap_or(ty));
+    match quote!(#ty).to_string().replace(' ', "").as_str() {
+        "u8" => parse_quote!(u8),
+        "i32" => parse_quote!(i32),
+        "u32" | "Option<u32>" => parse_quote!(u32),
+        "u64" | "Timestamp" => parse_quote!(u64),
+        "bool" => parse_quote!(bool),
+        "&str" | "String" | "Option<String>" | "Option<&str>" => {
+            parse_quote!(*const libc::c_char)
+        }
+        _ => match (generic_argument(ty, "Option"), generic_argument(ty, "Vec")) {
+            (Some(elem), _) => {
+                let elem = strip_reference(elem);
+                parse_quote!(*mut #elem)
+            }
+            (_, Some(elem)) => parse_quote!(*mut *mut #elem),
+            _ => parse_quote!(*mut #ty),
+        },
+    }
+}
+
+/// The C parameter types of the entry point generated for `sig`, as written by cbindgen.
+///
+/// Output parameters come first, as they do in the generated function. Every entry point returns
+/// `SignalFfiError *`.
+pub(crate) fn registry_types(sig: &Signature, result_kind: ResultKind) -> (Vec<String>, String) {
+    let output_args: Vec<Type> = match (result_kind, &sig.output) {
+        (ResultKind::Regular, ReturnType::Type(_, ty)) if is_sized_result(ty) => {
+            let result_type = ffi_result_type(ty);
+            vec![
+                parse_quote!(*mut #result_type),
+                parse_quote!(*mut libc::size_t),
+            ]
+        }
+        (ResultKind::Regular, ReturnType::Type(_, ty)) => {
+            let result_type = ffi_result_type(ty);
+            vec![parse_quote!(*mut #result_type)]
+        }
+        (ResultKind::Buffer, _) => vec![
+            parse_quote!(*mut *const libc::c_uchar),
+            parse_quote!(*mut libc::size_t),
+        ],
+        (ResultKind::Regular, ReturnType::Default) | (ResultKind::Void, _) => vec![],
+    };
+    let input_args = sig
+        .inputs
+        .iter()
+        .skip(if result_kind.has_env() { 1 } else { 0 })
+        .flat_map(|arg| match arg {
+            FnArg::Typed(PatType { ty, .. }) => ffi_arg_types(ty),
+            FnArg::Receiver(_) => vec![],
+        });
+    let args = output_args
+        .iter()
+        .chain(&input_args.collect::<Vec<_>>())
+        .map(c_type)
+        .collect();
+    (args, "SignalFfiError *".to_string())
+}
+
+/// Spells an FFI type the way cbindgen does, such as `const SignalPrivateKey *` for
+/// `*const PrivateKey`.
+pub(crate) fn c_type(ty: &Type) -> String {
+    match ty {
+        Type::Ptr(TypePtr {
+            const_token, elem, ..
+        }) => {
+            let pointee = c_type(elem);
+            match (const_token, &**elem) {
+                (Some(_), Type::Ptr(_)) => format!("{}const *", pointee),
+                (Some(_), _) => format!("const {} *", pointee),
+                (None, Type::Ptr(_)) => format!("{}*", pointee),
+                (None, _) => format!("{} *", pointee),
+            }
+        }
+        Type::Group(TypeGroup { elem, .. }) | Type::Paren(TypeParen { elem, .. }) => c_type(elem),
+        _ => {
+            let name = match last_path_segment(ty) {
+                Some(segment) => segment.ident.to_string(),
+                None => return quote!(#ty).to_string(),
+            };
+            match name.as_str() {
+                "bool" | "size_t" => name,
+                "u8" => "uint8_t".to_string(),
+                "u32" => "uint32_t".to_string(),
+                "u64" => "uint64_t".to_string(),
+                "i32" => "int32_t".to_string(),
+                "c_uchar" => "unsigned char".to_string(),
+                "c_char" => "char".to_string(),
+                "c_void" => "void".to_string(),
+                // Store structs are renamed in cbindgen.toml.
+                _ if name.starts_with("Ffi") && name.ends_with("Struct") => {
+                    format!("Signal{}", &name[3..name.len() - 6])
+                }
+                // Avoid double-prefixing, as in cbindgen.toml.
+                _ if name.starts_with("Signal") => name,
+                _ => format!("Signal{}", name),
+            }
+        }
+    }
+}
@@ -13,6 +13,8 @@ use unzip3::Unzip3;
 use crate::ResultKind;
 
 pub(crate) fn bridge_fn(name: String, sig: &Signature, result_kind: ResultKind) -> TokenStream2 {
+    // Undo the JNI escaping of underscores to get the name of the Java method.
+    let method_name = name.replace("_1", "_");
     let name = format_ident!("Java_org_signal_client_internal_Native_{}", name);
 
     let (env_arg, output) = match (result_kind, &sig.output) {
@@ -78,7 +80,7 @@ pub(crate) fn bridge_fn(name: String, sig: &Signature, result_kind: ResultKind)
             _class: jni::JClass,
             #(#input_args),*
         ) #output {
-            jni::run_ffi_safe(&env, || {
+            jni::run_ffi_safe(&env, #method_name, || {
                 #(#input_processing);*;
                 let __result = #orig_name(#env_arg #(#input_names),*);
                 #await_if_needed;
@@ -91,3 +93,65 @@ pub(crate) fn bridge_fn(name: String, sig: &Signature, result_kind: ResultKind)
 pub(crate) fn name_from_ident(ident: &Ident) -> String {
     ident.to_string().replace("_", "_1")
 }
+
+/// Mirrors `jni_arg_type!`, spelled as in the Java declaration from `gen_java_decl.py`.
+fn java_arg_type(ty: &Type) -> String {
+    let ty = quote!(#ty).to_string().replace(' ', "");
+    match ty.as_str() {
+        "bool" => "boolean".to_string(),
+        "u8" | "u32" | "Option<u32>" => "int".to_string(),
+        "u64" | "Timestamp" => "long".to_string(),
+        "String" | "Option<String>" => "String".to_string(),
+        "&[u8]" | "Option<&[u8]>" | "&mut[u8]" => "byte[]".to_string(),
+        "Context" => "Object".to_string(),
+        _ if ty.starts_with("&mutdyn") => ty["&mutdyn".len()..].to_string(),
+        _ if ty.starts_with("Vec<&") => "long[]".to_string(),
+        _ => "long".to_string(),
+    }
+}
+
+/// Mirrors `jni_result_type!`, spelled as in the Java declaration from `gen_java_decl.py`.
+fn java_result_type(ty: &Type) -> String {
+    let ty = quote!(#ty).to_string().replace(' ', "");
+    let ty = match ty.strip_prefix("Result<") {
+        Some(rest) => rest
+            .strip_suffix('>')
+            .and_then(|success| success.split(',').next())
+            .unwrap_or(rest),
+        None => &ty,
+    };
+    let ty = match ty.strip_prefix("Option<&") {
+        Some(rest) => rest.strip_suffix('>').unwrap_or(rest),
+        None => ty,
+    };
+    match ty {
+        "bool" => "boolean",
+        "u8" | "i32" | "u32" | "Option<u32>" => "int",
+        "u64" | "Timestamp" => "long",
+        "&str" | "String" | "Option<String>" | "Option<&str>" => "String",
+        "Vec<u8>" => "byte[]",
+        "Vec<Vec<u8>>" => "byte[][]",
+        _ if ty.starts_with("Vec<") => "long[]",
+        _ => "long",
+    }
+    .to_string()
+}
+
+/// The Java parameter and result types of the native method generated for `sig`.
+pub(crate) fn registry_types(sig: &Signature, result_kind: ResultKind) -> (Vec<String>, String) {
+    let args = sig
+        .inputs
+        .iter()
+        .skip(if result_kind.has_env() { 1 } else { 0 })
+        .filter_map(|arg| match arg {
+            FnArg::Typed(PatType { ty, .. }) => Some(java_arg_type(ty)),
+            FnArg::Receiver(_) => None,
+        })
+        .collect();
+    let result = match (result_kind, &sig.output) {
+        (ResultKind::Regular, ReturnType::Type(_, ty)) => java_result_type(ty),
+        (ResultKind::Buffer, _) => "byte[]".to_string(),
+        (ResultKind::Regular, ReturnType::Default) | (ResultKind::Void, _) => "void".to_string(),
+    };
+    (args, result)
+}
@@ -65,6 +65,11 @@
 //! defined using the [`async-trait`][] crate. Like the synchronous implementations of all three
 //! bridges, **panics will be caught** and translated to JavaScript exceptions.
 //!
+//! Each call counts as an in-flight operation until its arguments have been finalized. Calls beyond
+//! the limit set with `Native_InitRuntime` are rejected or queued, and `Native_Shutdown` waits for
+//! all in-flight operations. Once shutdown has started, `async` entry points throw instead of
+//! starting new work.
+//!
 //! The FFI and JNI bridges do not support asynchronous execution; an `async` function is invoked
 //! and `expect`ed to complete immediately without blocking.
 //!
@@ -129,6 +134,88 @@
 //!    These traits define how to convert between the bridge type and the Rust type used in the
 //!    function as written. See each individual trait for more info on how to add a new type.
 //!
+//! # Passing structs as plain objects
+//!
+//! A simple options or result struct doesn't need to be a handle. `#[derive(BridgeStruct)]`
+//! implements the argument and result traits for a struct with named fields, each of which must
+//! itself be a supported type:
+//!
+//! ```ignore
+//! # #[cfg(ignore_even_when_running_all_tests)]
+//! #[derive(BridgeStruct, Clone)]
+//! #[bridge_struct(jni = "org/signal/client/backup/ExportOptions")]
+//! #[repr(C)]
+//! struct ExportOptions {
+//!   chunk_size: u32,
+//!   include_attachments: bool,
+//! }
+//! ```
+//!
+//! - For Node, the struct is a plain object whose properties are the camelCased field names
+//!   (`chunkSize`). `Option` fields may be missing, `undefined`, or `null`. A TypeScript interface
+//!   is generated for `Native.d.ts`.
+//!
+//! - For JNI, arguments are read from the Java object's fields of the same camelCased names.
+//!   Results are only supported when a class is named with `#[bridge_struct(jni = "...")]`; they
+//!   are constructed by passing every field, in order, to its constructor.
+//!
+//! - For FFI, the struct must be `#[repr(C)]` and `Clone`. Arguments are passed by pointer and
+//!   results by value. Without `#[repr(C)]`, the struct is not available to C at all.
+//!
+//! As with any other type, the struct needs entries in `ffi_arg_type` (`*const ExportOptions`),
+//! `ffi_result_type` (`ExportOptions`), `jni_arg_type` (`jni::JObject`), and `jni_result_type`.
+//! For the last, declare a `jni::JavaExportOptions` alias of `jobject` so that `gen_java_decl.py`
+//! can name the class.
+//!
+//! # Argument validation
+//!
+//! Buffer and string arguments that must have a fixed length can declare it in the attribute:
+//!
+//! ```ignore
+//! # #[cfg(ignore_even_when_running_all_tests)]
+//! #[bridge_fn(len(key = 32, nonce = 12))]
+//! fn Cipher_New(key: &[u8], nonce: &[u8]) -> Result<Cipher> {
+//!   // ...
+//! }
+//! ```
+//!
+//! The check is inserted at the start of the Rust function, so it produces the same
+//! `SignalProtocolError::InvalidArgument` message for every bridge. The function must therefore
+//! return a `Result` whose error type can be converted from `SignalProtocolError`.
+//!
+//! # Fire-and-forget calls for Node
+//!
+//! Some operations, such as store maintenance, have no result worth waiting for. An `async`
+//! `bridge_fn_void` can be marked `fire_and_forget` to have the Node entry point start the
+//! operation on the JavaScript event loop and return `undefined` immediately, without allocating a
+//! Promise:
+//!
+//! ```ignore
+//! # #[cfg(ignore_even_when_running_all_tests)]
+//! #[bridge_fn_void(fire_and_forget)]
+//! async fn Store_Compact(store: &mut dyn SessionStore, ctx: Context) -> Result<()> {
+//!   // ...
+//! }
+//! ```
+//!
+//! Errors and panics are reported through the `log` crate instead of being thrown. The FFI and
+//! JNI entry points are unaffected, since those bridges already run `async` functions to completion
+//! before returning.
+//!
+//! # Optional results
+//!
+//! A result of `Option<T>` or `Result<Option<T>, E>` represents "no value" without being an error.
+//! `None` is returned as `NULL` (with a length of 0 for buffers) for FFI, `null` (or a handle of 0)
+//! for JNI, and `null` for Node. This also applies to `bridge_fn_buffer` functions returning
+//! `Result<Option<E::Buffer>>`.
+//!
+//! # Introspection
+//!
+//! With the "introspection" feature, every generated entry point is also recorded in
+//! `introspection::BRIDGE_FUNCTIONS`, along with the C, Java, or TypeScript types of its arguments
+//! and result. This is exposed to all bridges as `Native_ListFunctions`, so that wrappers can check
+//! at startup that the native library matches their bindings.
+//!
 //! # Limitations
 //!
 //! - Input buffers require special treatment for FFI so that their size can be passed in.
@@ -146,17 +233,16 @@
 use proc_macro::TokenStream;
 use quote::*;
 use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
 use syn::*;
 use syn_mid::ItemFn;
 
+mod bridge_struct;
 mod ffi;
 mod jni;
 mod node;
 
-fn value_for_meta_key<'a>(
-    meta_values: &'a Punctuated<MetaNameValue, Token![,]>,
-    key: &str,
-) -> Option<&'a Lit> {
+fn value_for_meta_key<'a>(meta_values: &'a [MetaNameValue], key: &str) -> Option<&'a Lit> {
     meta_values
         .iter()
         .find(|meta| meta.path.get_ident().map_or(false, |ident| ident == key))
@@ -164,7 +250,7 @@ fn value_for_meta_key<'a>(
 }
 
 fn name_for_meta_key(
-    meta_values: &Punctuated<MetaNameValue, Token![,]>,
+    meta_values: &[MetaNameValue],
     key: &str,
     enabled: bool,
     default: impl FnOnce() -> String,
@@ -196,11 +282,159 @@ impl ResultKind {
     }
 }
 
+/// A `len(arg = N)` requirement on a buffer or string argument.
+struct LengthRequirement {
+    arg: Ident,
+    expected: LitInt,
+}
+
+/// The parsed arguments of a `bridge_fn` attribute.
+struct AttrArgs {
+    names: Vec<MetaNameValue>,
+    lengths: Vec<LengthRequirement>,
+    fire_and_forget: Option<Path>,
+}
+
+/// Splits the attribute arguments into entry point names (`ffi = "..."`), argument
+/// requirements (`len(key = 32)`), and flags (`fire_and_forget`).
+fn parse_attr_args(args: Punctuated<NestedMeta, Token![,]>) -> Result<AttrArgs> {
+    let mut names = Vec::new();
+    let mut lengths = Vec::new();
+    let mut fire_and_forget = None;
+    for arg in args {
+        match arg {
+            NestedMeta::Meta(Meta::NameValue(name)) => names.push(name),
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("fire_and_forget") => {
+                fire_and_forget = Some(path)
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("len") => {
+                for nested in list.nested {
+                    match nested {
+                        NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                            path,
+                            lit: Lit::Int(expected),
+                            ..
+                        })) if path.get_ident().is_some() => lengths.push(LengthRequirement {
+                            arg: path.get_ident().expect("checked above").clone(),
+                            expected,
+                        }),
+                        other => {
+                            return Err(Error::new(
+                                other.span(),
+                                "expected `argument = length`, e.g. `len(key = 32)`",
+                            ))
+                        }
+                    }
+                }
+            }
+            other => return Err(Error::new(other.span(), "unexpected bridge_fn argument")),
+        }
+    }
+    Ok(AttrArgs {
+        names,
+        lengths,
+        fire_and_forget,
+    })
+}
+
+/// Checks that `fire_and_forget` is only used where there is no result to discard.
+fn check_fire_and_forget(
+    function: &ItemFn,
+    flag: &Option<Path>,
+    result_kind: ResultKind,
+) -> Result<bool> {
+    let flag = match flag {
+        Some(flag) => flag,
+        None => return Ok(false),
+    };
+    if !matches!(result_kind, ResultKind::Void) {
+        return Err(Error::new(
+            flag.span(),
+            "fire_and_forget is only supported for bridge_fn_void",
+        ));
+    }
+    if function.sig.asyncness.is_none() {
+        return Err(Error::new(
+            flag.span(),
+            "fire_and_forget requires an async function",
+        ));
+    }
+    Ok(true)
+}
+
+/// Prepends a length check for each `len(...)` requirement to the body of `function`.
+///
+/// The checks run before any bridge-specific code sees the result, so every bridge reports the
+/// same `SignalProtocolError::InvalidArgument` message.
+fn add_length_checks(function: &mut ItemFn, lengths: &[LengthRequirement]) -> Result<()> {
+    if lengths.is_empty() {
+        return Ok(());
+    }
+    let checks = lengths
+        .iter()
+        .map(|LengthRequirement { arg, expected }| {
+            let is_parameter = function.sig.inputs.iter().any(|input| match input {
+                syn_mid::FnArg::Typed(syn_mid::PatType {
+                    pat: box syn_mid::Pat::Ident(name),
+                    ..
+                }) => name.ident == *arg,
+                _ => false,
+            });
+            if !is_parameter {
+                return Err(Error::new(
+                    arg.span(),
+                    format!("no parameter named `{}`", arg),
+                ));
+            }
+            let arg_name = arg.to_string();
+            Ok(quote! {
+                crate::support::check_argument_length(#arg_name, #arg.len(), #expected)?;
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let body = &function.block.stmts;
+    function.block.stmts = quote!(#(#checks)* #body);
+    Ok(())
+}
+
+/// Records a generated entry point in `introspection::BRIDGE_FUNCTIONS`, with the argument and
+/// result types spelled in the bridge's own language.
+///
+/// JNI and Node entry points live in a cdylib, so their entries are named (and exported) using
+/// `static_name`; FFI entries are anonymous.
+fn registry_entry(
+    bridge: &str,
+    name: &str,
+    static_name: Option<Ident>,
+    (args, result): (Vec<String>, String),
+    is_async: bool,
+) -> proc_macro2::TokenStream {
+    let static_name = static_name.map(|static_name| quote!(#static_name:));
+    quote! {
+        bridge_registry_entry!(#static_name #bridge, #name, [#(#args),*] -> #result, #is_async);
+    }
+}
+
 fn bridge_fn_impl(attr: TokenStream, item: TokenStream, result_kind: ResultKind) -> TokenStream {
-    let function = parse_macro_input!(item as ItemFn);
+    let mut function = parse_macro_input!(item as ItemFn);
 
-    let item_names =
-        parse_macro_input!(attr with Punctuated<MetaNameValue, Token![,]>::parse_terminated);
+    let attr_args =
+        parse_macro_input!(attr with Punctuated<NestedMeta, Token![,]>::parse_terminated);
+    let AttrArgs {
+        names: item_names,
+        lengths,
+        fire_and_forget,
+    } = match parse_attr_args(attr_args) {
+        Ok(parsed) => parsed,
+        Err(error) => return error.to_compile_error().into(),
+    };
+    if let Err(error) = add_length_checks(&mut function, &lengths) {
+        return error.to_compile_error().into();
+    }
+    let fire_and_forget = match check_fire_and_forget(&function, &fire_and_forget, result_kind) {
+        Ok(fire_and_forget) => fire_and_forget,
+        Err(error) => return error.to_compile_error().into(),
+    };
     let ffi_name = match name_for_meta_key(&item_names, "ffi", cfg!(feature = "ffi"), || {
         ffi::name_from_ident(&function.sig.ident)
     }) {
@@ -226,9 +460,40 @@ fn bridge_fn_impl(attr: TokenStream, item: TokenStream, result_kind: ResultKind)
     let maybe_features = [ffi_feature, jni_feature, node_feature];
     let feature_list = maybe_features.iter().flatten();
 
+    let is_async = function.sig.asyncness.is_some();
+    let ffi_info = ffi_name.as_ref().map(|name| {
+        registry_entry(
+            "ffi",
+            &format!("signal_{}", name),
+            None,
+            ffi::registry_types(&function.sig, result_kind),
+            is_async,
+        )
+    });
+    let jni_info = jni_name.as_ref().map(|name| {
+        registry_entry(
+            "jni",
+            // Undo the JNI escaping of underscores to get the name of the Java method.
+            &name.replace("_1", "_"),
+            Some(format_ident!("signal_bridge_info_jni_{}", name)),
+            jni::registry_types(&function.sig, result_kind),
+            is_async,
+        )
+    });
+    let node_info = node_name.as_ref().map(|name| {
+        registry_entry(
+            "node",
+            name,
+            Some(format_ident!("signal_bridge_info_node_{}", name)),
+            node::registry_types(&function.sig, result_kind, fire_and_forget),
+            is_async,
+        )
+    });
+
     let ffi_fn = ffi_name.map(|name| ffi::bridge_fn(name, &function.sig, result_kind));
     let jni_fn = jni_name.map(|name| jni::bridge_fn(name, &function.sig, result_kind));
-    let node_fn = node_name.map(|name| node::bridge_fn(name, &function.sig, result_kind));
+    let node_fn =
+        node_name.map(|name| node::bridge_fn(name, &function.sig, result_kind, fire_and_forget));
 
     quote!(
         #[allow(non_snake_case)]
@@ -236,10 +501,13 @@ fn bridge_fn_impl(attr: TokenStream, item: TokenStream, result_kind: ResultKind)
         #function
 
         #ffi_fn
+        #ffi_info
 
         #jni_fn
+        #jni_info
 
         #node_fn
+        #node_info
     )
     .into()
 }
@@ -315,3 +583,33 @@ pub fn bridge_fn_buffer(attr: TokenStream, item: TokenStream) -> TokenStream {
 pub fn bridge_fn_void(attr: TokenStream, item: TokenStream) -> TokenStream {
     bridge_fn_impl(attr, item, ResultKind::Void)
 }
+
+/// Passes a struct across the bridge as a plain object, field by field.
+///
+/// See the [crate-level documentation](crate#passing-structs-as-plain-objects) for more
+/// information.
+#[proc_macro_derive(BridgeStruct, attributes(bridge_struct))]
+pub fn derive_bridge_struct(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    match bridge_struct::derive_bridge_struct(input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Spells an FFI type the way cbindgen does, as a string literal.
+///
+/// Used to record the entry points written by the `bridge_handle!` family of macros in
+/// `introspection::BRIDGE_FUNCTIONS`; not intended to be invoked directly.
+///
+/// ```ignore
+/// # #[cfg(ignore_even_when_running_all_tests)]
+/// ffi_c_type!(*const PrivateKey) // "const SignalPrivateKey *"
+/// ```
+#[doc(hidden)]
+#[proc_macro]
+pub fn ffi_c_type(item: TokenStream) -> TokenStream {
+    let ty = parse_macro_input!(item as Type);
+    let c_type = ffi::c_type(&ty);
+    quote!(#c_type).into()
+}
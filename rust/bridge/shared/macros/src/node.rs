@@ -14,6 +14,7 @@ use syn_mid::{FnArg, Pat, PatType, Signature};
 use crate::ResultKind;
 
 fn bridge_fn_body(
+    name: &str,
     orig_name: &Ident,
     input_args: &[(&Ident, &Type)],
     result_kind: ResultKind,
@@ -44,17 +45,28 @@ fn bridge_fn_body(
     let input_names = input_args.iter().map(|(name, _ty)| name);
 
     quote! {
-        #(#input_borrowing)*
-        #(#input_loading)*
-        let __result = #orig_name(#env_arg #(#input_names),*);
-        Ok(node::ResultTypeInfo::convert_into(__result, &mut cx)?.upcast())
+        // Panics must not unwind into Node, so catch them and throw them as JS errors instead.
+        let __result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(
+            || -> node::JsResult<node::JsValue> {
+                #(#input_borrowing)*
+                #(#input_loading)*
+                let __result = #orig_name(#env_arg #(#input_names),*);
+                Ok(node::ResultTypeInfo::convert_into(__result, &mut cx)?.upcast())
+            }
+        ));
+        match __result {
+            Ok(result) => result,
+            Err(panic) => node::throw_panic(&mut cx, #name, &node::describe_panic(&panic)),
+        }
     }
 }
 
 fn bridge_fn_async_body(
+    name: &str,
     orig_name: &Ident,
     input_args: &[(&Ident, &Type)],
     result_kind: ResultKind,
+    fire_and_forget: bool,
 ) -> TokenStream2 {
     let input_saving = input_args.iter().zip(0..).map(|((name, ty), i)| {
         let name_arg = format_ident!("{}_arg", name);
@@ -103,7 +115,38 @@ fn bridge_fn_async_body(
         }
     });
 
+    if fire_and_forget {
+        return quote! {
+            // Counts towards the runtime limits, and keeps `Native_Shutdown` from completing,
+            // until the arguments have been finalized.
+            let mut __operation = node::AsyncOperation::begin(&mut cx, #name)?;
+            // Use a RefCell so that the early-exit cleanup functions can reference the context
+            // without taking ownership.
+            let cx = std::cell::RefCell::new(cx);
+            #(#input_saving)*
+            #(#input_unwrapping)*
+            let mut cx = cx.into_inner();
+            node::run_detached(&mut cx, #name, async move {
+                __operation.admitted().await;
+                let __result = futures::FutureExt::catch_unwind(std::panic::AssertUnwindSafe(async {
+                    #(#input_loading)*
+                    #orig_name(#env_arg #(#input_names),*).await
+                }))
+                .await;
+                (__result, move |cx: &mut node::TaskContext| {
+                    // Declared first so that it is dropped last, after finalization.
+                    let _operation = __operation;
+                    #(#input_finalization)*
+                })
+            });
+            Ok(cx.undefined().upcast())
+        };
+    }
+
     quote! {
+        // Counts towards the runtime limits, and keeps `Native_Shutdown` from completing, until
+        // the promise is settled.
+        let mut __operation = node::AsyncOperation::begin(&mut cx, #name)?;
         // Use a RefCell so that the early-exit cleanup functions can reference the context
         // without taking ownership.
         let cx = std::cell::RefCell::new(cx);
@@ -112,40 +155,93 @@ fn bridge_fn_async_body(
         Ok(signal_neon_futures::promise(
             &mut cx.into_inner(),
             std::panic::AssertUnwindSafe(async move {
-                #(#input_loading)*
-                let __result = #orig_name(#env_arg #(#input_names),*).await;
+                __operation.admitted().await;
+                // Catch panics here rather than leaving them to the promise, so that the stored
+                // arguments still get finalized and the rejection says which function panicked.
+                let __result = futures::FutureExt::catch_unwind(std::panic::AssertUnwindSafe(async {
+                    #(#input_loading)*
+                    #orig_name(#env_arg #(#input_names),*).await
+                }))
+                .await
+                .map_err(|panic| node::describe_panic(&panic));
                 signal_neon_futures::settle_promise(move |cx| {
+                    // Declared first so that it is dropped last, after finalization.
+                    let _operation = __operation;
                     let mut cx = scopeguard::guard(cx, |cx| {
                         #(#input_finalization)*
                     });
-                    node::ResultTypeInfo::convert_into(__result, *cx)
+                    match __result {
+                        Ok(__result) => node::ResultTypeInfo::convert_into(__result, *cx),
+                        Err(message) => node::throw_panic(*cx, #name, &message),
+                    }
                 })
             })
         )?.upcast())
     }
 }
 
-pub(crate) fn bridge_fn(name: String, sig: &Signature, result_kind: ResultKind) -> TokenStream2 {
-    let name_with_prefix = format_ident!("node_{}", name);
-    let name_without_prefix = Ident::new(&name, Span::call_site());
+/// Checks whether a result type is syntactically `Option<_>` or `Result<Option<_>, ...>`.
+fn is_optional_result(ty: &Type) -> bool {
+    let last_segment = |ty: &Type| match ty {
+        Type::Path(TypePath { qself: None, path }) => path.segments.last().cloned(),
+        _ => None,
+    };
+    match last_segment(ty) {
+        Some(segment) if segment.ident == "Option" => true,
+        Some(segment) if segment.ident == "Result" => match segment.arguments {
+            PathArguments::AngleBracketed(args) => match args.args.first() {
+                Some(GenericArgument::Type(success)) => {
+                    last_segment(success).map_or(false, |segment| segment.ident == "Option")
+                }
+                _ => false,
+            },
+            _ => false,
+        },
+        _ => false,
+    }
+}
 
-    let result_type_format = if sig.asyncness.is_some() {
+/// The result type of the entry point generated for `sig`, as written in its `ts:` annotation.
+fn result_type_str(
+    sig: &Signature,
+    result_kind: ResultKind,
+    fire_and_forget: bool,
+) -> Result<String> {
+    // Fire-and-forget functions return immediately rather than producing a Promise.
+    let result_type_format = if sig.asyncness.is_some() && !fire_and_forget {
         |ty: &dyn Display| format!("Promise<{}>", ty)
     } else {
         |ty: &dyn Display| format!("{}", ty)
     };
-    let result_type_str = match (result_kind, &sig.output) {
+    Ok(match (result_kind, &sig.output) {
         (ResultKind::Regular, ReturnType::Default) => result_type_format(&"()"),
         (ResultKind::Regular, ReturnType::Type(_, ty)) => result_type_format(&quote!(#ty)),
         (ResultKind::Void, _) => result_type_format(&"()"),
+        (ResultKind::Buffer, ReturnType::Type(_, ty)) if is_optional_result(ty) => {
+            result_type_format(&"Option<Buffer>")
+        }
         (ResultKind::Buffer, ReturnType::Type(_, _)) => result_type_format(&"Buffer"),
         (ResultKind::Buffer, ReturnType::Default) => {
-            return Error::new(
+            return Err(Error::new(
                 sig.paren_token.span,
                 "missing result type for bridge_fn_buffer",
-            )
-            .to_compile_error()
+            ))
         }
+    })
+}
+
+pub(crate) fn bridge_fn(
+    name: String,
+    sig: &Signature,
+    result_kind: ResultKind,
+    fire_and_forget: bool,
+) -> TokenStream2 {
+    let name_with_prefix = format_ident!("node_{}", name);
+    let name_without_prefix = Ident::new(&name, Span::call_site());
+
+    let result_type_str = match result_type_str(sig, result_kind, fire_and_forget) {
+        Ok(result_type_str) => result_type_str,
+        Err(error) => return error.to_compile_error(),
     };
 
     let input_args: Result<Vec<_>> = sig
@@ -175,8 +271,10 @@ pub(crate) fn bridge_fn(name: String, sig: &Signature, result_kind: ResultKind)
     };
 
     let body = match sig.asyncness {
-        Some(_) => bridge_fn_async_body(&sig.ident, &input_args, result_kind),
-        None => bridge_fn_body(&sig.ident, &input_args, result_kind),
+        Some(_) => {
+            bridge_fn_async_body(&name, &sig.ident, &input_args, result_kind, fire_and_forget)
+        }
+        None => bridge_fn_body(&name, &sig.ident, &input_args, result_kind),
     };
 
     let node_annotation = format!(
@@ -207,3 +305,67 @@ pub(crate) fn bridge_fn(name: String, sig: &Signature, result_kind: ResultKind)
 pub(crate) fn name_from_ident(ident: &Ident) -> String {
     ident.to_string()
 }
+
+/// Translates a Rust type to TypeScript the same way `gen_ts_decl.py` does.
+fn translate_to_ts(ty: &str, is_result: bool) -> String {
+    let ty = ty.replace(' ', "");
+
+    // Timestamps are passed in as numbers but returned as Dates.
+    if is_result && ty == "Timestamp" {
+        return "Date".to_string();
+    }
+
+    let inner = |prefix: &str, suffix: &str| &ty[prefix.len()..ty.len() - suffix.len()];
+    let translate_inner = |prefix, suffix| translate_to_ts(inner(prefix, suffix), is_result);
+    match ty.as_str() {
+        "()" => "void".to_string(),
+        "&[u8]" | "Vec<u8>" => "Buffer".to_string(),
+        "i32" | "u8" | "u32" | "u64" | "Timestamp" => "number".to_string(),
+        "bool" => "boolean".to_string(),
+        "String" | "&str" => "string".to_string(),
+        "Context" => "null".to_string(),
+        "Vec<Result<CiphertextMessage>>" => "EncryptBatchResult[]".to_string(),
+        _ if ty.starts_with("&[") => {
+            let element = format!("&{}", inner("&[", "]"));
+            format!("{}[]", translate_to_ts(&element, is_result))
+        }
+        // Errors inside a returned array are returned, not thrown.
+        _ if ty.starts_with("Vec<Result<") => {
+            format!("({} | Error)[]", translate_inner("Vec<", ">"))
+        }
+        _ if ty.starts_with("Vec<") => format!("{}[]", translate_inner("Vec<", ">")),
+        _ if ty.starts_with("&mutdyn") => inner("&mutdyn", "").to_string(),
+        _ if ty.starts_with("&mut") => format!("Wrapper<{}>", inner("&mut", "")),
+        _ if ty.starts_with('&') => format!("Wrapper<{}>", inner("&", "")),
+        _ if ty.starts_with("Option<") => format!("{} | null", translate_inner("Option<", ">")),
+        _ if ty.starts_with("Result<") => {
+            let success = inner("Result<", ">").split(',').next().unwrap_or_default();
+            translate_to_ts(success, is_result)
+        }
+        _ if ty.starts_with("Promise<") => format!("Promise<{}>", translate_inner("Promise<", ">")),
+        _ => ty,
+    }
+}
+
+/// The TypeScript parameter and result types of the function generated for `sig`, as they appear
+/// in `Native.d.ts`.
+pub(crate) fn registry_types(
+    sig: &Signature,
+    result_kind: ResultKind,
+    fire_and_forget: bool,
+) -> (Vec<String>, String) {
+    let args = sig
+        .inputs
+        .iter()
+        .skip(if result_kind.has_env() { 1 } else { 0 })
+        .filter_map(|arg| match arg {
+            FnArg::Typed(PatType { ty, .. }) => {
+                Some(translate_to_ts(&quote!(#ty).to_string(), false))
+            }
+            FnArg::Receiver(_) => None,
+        })
+        .collect();
+    let result = result_type_str(sig, result_kind, fire_and_forget)
+        .map_or_else(|_| String::new(), |result| translate_to_ts(&result, true));
+    (args, result)
+}
@@ -0,0 +1,71 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! The form in which [`AuditEvent`]s are passed to the platforms.
+//!
+//! Each bridge crate installs its own [`AuditHook`](libsignal_protocol::AuditHook), next to its
+//! logger, and uses [`BridgedAuditEvent`] so that all the platforms see the same fields.
+
+use libsignal_protocol::error::Result;
+use libsignal_protocol::AuditEvent;
+
+/// Which kind of [`AuditEvent`] a [`BridgedAuditEvent`] describes.
+///
+/// Keep the values in sync with each platform's copy of this enum.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditEventKind {
+    IdentityChanged = 1,
+    SessionArchived,
+    SenderKeyCreated,
+    SenderKeyFastForwarded,
+}
+
+/// An [`AuditEvent`] flattened into fields that are easy to pass to platform callbacks.
+#[derive(Debug)]
+pub struct BridgedAuditEvent {
+    pub kind: AuditEventKind,
+    /// The remote address for identity and session events, or the sender's for sender key events
+    /// (our own, for [`AuditEventKind::SenderKeyCreated`]).
+    pub address_name: String,
+    pub device_id: u32,
+    /// Only present for sender key events.
+    pub group_id: Option<String>,
+}
+
+impl BridgedAuditEvent {
+    pub fn new(event: &AuditEvent) -> Result<Self> {
+        Ok(match event {
+            AuditEvent::IdentityChanged { address } => Self {
+                kind: AuditEventKind::IdentityChanged,
+                address_name: address.name().to_string(),
+                device_id: address.device_id(),
+                group_id: None,
+            },
+            AuditEvent::SessionArchived { address } => Self {
+                kind: AuditEventKind::SessionArchived,
+                address_name: address.name().to_string(),
+                device_id: address.device_id(),
+                group_id: None,
+            },
+            AuditEvent::SenderKeyCreated {
+                sender_key_name, ..
+            } => Self {
+                kind: AuditEventKind::SenderKeyCreated,
+                address_name: sender_key_name.sender_name()?,
+                device_id: sender_key_name.sender_device_id()?,
+                group_id: Some(sender_key_name.group_id()?),
+            },
+            AuditEvent::SenderKeyFastForwarded {
+                sender_key_name, ..
+            } => Self {
+                kind: AuditEventKind::SenderKeyFastForwarded,
+                address_name: sender_key_name.sender_name()?,
+                device_id: sender_key_name.sender_device_id()?,
+                group_id: Some(sender_key_name.group_id()?),
+            },
+        })
+    }
+}
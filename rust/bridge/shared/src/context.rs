@@ -0,0 +1,230 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Per-account state, for apps that use the library on behalf of several accounts at once.
+//!
+//! The logger and the `tracing` subscriber are process-wide. A [`BridgeContext`] holds the state
+//! that should instead be kept separately for each account: a label and level filter for log
+//! output, and metrics for traced operations. A context is *entered* on a thread, and applies to
+//! everything the library does on that thread until it is exited. Subsystems look up the
+//! [current](BridgeContext::current) context, and fall back to process-wide behavior if
+//! there is none.
+//!
+//! Contexts are tracked per thread, so async Node operations only see a context if it is still
+//! entered whenever they run.
+
+use libsignal_bridge_macros::*;
+use libsignal_protocol::error::{Result, SignalProtocolError};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::support::*;
+use crate::*;
+
+/// Totals for all the traced operations with a particular name.
+#[derive(Default, Clone, Copy)]
+struct OperationMetrics {
+    count: u64,
+    total_micros: u64,
+}
+
+struct ContextState {
+    label: String,
+    /// A [`log::LevelFilter`] as `usize`.
+    max_log_level: AtomicUsize,
+    operations: Mutex<BTreeMap<&'static str, OperationMetrics>>,
+}
+
+/// Per-account state; see the [module documentation](self).
+#[derive(Clone)]
+pub struct BridgeContext {
+    state: Arc<ContextState>,
+}
+
+thread_local! {
+    /// Contexts entered on this thread, innermost last.
+    static ENTERED_CONTEXTS: RefCell<Vec<BridgeContext>> = RefCell::new(Vec::new());
+}
+
+fn level_filter_from_usize(level: usize) -> log::LevelFilter {
+    [
+        log::LevelFilter::Off,
+        log::LevelFilter::Error,
+        log::LevelFilter::Warn,
+        log::LevelFilter::Info,
+        log::LevelFilter::Debug,
+        log::LevelFilter::Trace,
+    ]
+    .get(level)
+    .copied()
+    .unwrap_or(log::LevelFilter::Trace)
+}
+
+impl BridgeContext {
+    /// Creates a context whose log output is prefixed with `[label]`.
+    pub fn new(label: String) -> Self {
+        Self {
+            state: Arc::new(ContextState {
+                label,
+                max_log_level: AtomicUsize::new(log::LevelFilter::Trace as usize),
+                operations: Mutex::new(BTreeMap::new()),
+            }),
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        &self.state.label
+    }
+
+    /// Limits log output produced while this context is current.
+    ///
+    /// This can only make logging quieter than the process-wide maximum level, not louder.
+    pub fn set_max_log_level(&self, level: log::LevelFilter) {
+        self.state
+            .max_log_level
+            .store(level as usize, Ordering::Relaxed);
+    }
+
+    pub fn max_log_level(&self) -> log::LevelFilter {
+        level_filter_from_usize(self.state.max_log_level.load(Ordering::Relaxed))
+    }
+
+    /// Makes this the current context on this thread, until [`exit`](Self::exit) is called.
+    pub fn enter(&self) {
+        ENTERED_CONTEXTS.with(|entered| entered.borrow_mut().push(self.clone()));
+    }
+
+    /// Undoes the innermost call to [`enter`](Self::enter).
+    ///
+    /// Fails if this is not the current context on this thread.
+    pub fn exit(&self) -> Result<()> {
+        ENTERED_CONTEXTS.with(|entered| {
+            let mut entered = entered.borrow_mut();
+            match entered.last() {
+                Some(innermost) if Arc::ptr_eq(&innermost.state, &self.state) => {
+                    entered.pop();
+                    Ok(())
+                }
+                _ => Err(SignalProtocolError::InvalidState(
+                    "BridgeContext_Exit",
+                    format!("context '{}' is not the current context", self.label()),
+                )),
+            }
+        })
+    }
+
+    /// Returns the innermost context entered on this thread, if any.
+    pub fn current() -> Option<BridgeContext> {
+        ENTERED_CONTEXTS.with(|entered| entered.borrow().last().cloned())
+    }
+
+    /// Adds one run of the traced operation `name` to this context's metrics.
+    pub fn record_operation(&self, name: &'static str, duration: Duration) {
+        let mut operations = self.state.operations.lock().expect("not poisoned");
+        let metrics = operations.entry(name).or_default();
+        metrics.count += 1;
+        metrics.total_micros += duration.as_micros() as u64;
+    }
+
+    /// Formats this context's metrics as one `name count=N total_us=T` line per operation, sorted
+    /// by name.
+    pub fn metrics_report(&self) -> String {
+        let operations = self.state.operations.lock().expect("not poisoned");
+        let mut report = String::new();
+        for (name, metrics) in operations.iter() {
+            let _ = writeln!(
+                report,
+                "{} count={} total_us={}",
+                name, metrics.count, metrics.total_micros
+            );
+        }
+        report
+    }
+
+    pub fn reset_metrics(&self) {
+        self.state.operations.lock().expect("not poisoned").clear();
+    }
+}
+
+/// Wraps a platform logger so that output is filtered and labeled by the current
+/// [`BridgeContext`].
+pub struct ContextLogger<L>(pub L);
+
+impl<L: log::Log> log::Log for ContextLogger<L> {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        BridgeContext::current().map_or(true, |context| metadata.level() <= context.max_log_level())
+            && self.0.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        // The platform logger may call back into the library, so don't hold on to the thread's
+        // context stack while it runs.
+        match BridgeContext::current() {
+            None => self.0.log(record),
+            Some(context) => {
+                if record.level() > context.max_log_level() {
+                    return;
+                }
+                self.0.log(
+                    &log::Record::builder()
+                        .level(record.level())
+                        .target(record.target())
+                        .module_path(record.module_path())
+                        .file(record.file())
+                        .line(record.line())
+                        .args(format_args!("[{}] {}", context.label(), record.args()))
+                        .build(),
+                )
+            }
+        }
+    }
+
+    fn flush(&self) {
+        self.0.flush()
+    }
+}
+
+bridge_handle!(BridgeContext);
+
+#[bridge_fn]
+fn BridgeContext_New(label: String) -> BridgeContext {
+    BridgeContext::new(label)
+}
+
+/// Sets the most verbose level logged while `context` is current: 0 for none, then 1 (errors)
+/// through 5 (trace).
+#[bridge_fn_void]
+fn BridgeContext_SetMaxLogLevel(context: &BridgeContext, level: u32) {
+    context.set_max_log_level(level_filter_from_usize(level as usize))
+}
+
+#[bridge_fn_void]
+fn BridgeContext_Enter(context: &BridgeContext) {
+    context.enter()
+}
+
+#[bridge_fn_void]
+fn BridgeContext_Exit(context: &BridgeContext) -> Result<()> {
+    context.exit()
+}
+
+/// Returns the metrics recorded for `context`, as formatted by [`BridgeContext::metrics_report`].
+///
+/// Operations are only recorded while a tracing subscriber is installed (see
+/// `Tracing_InstallLogSubscriber`, which needs the bridge's `tracing` feature), and only if their
+/// spans are enabled by the process-wide log level.
+#[bridge_fn]
+fn BridgeContext_GetMetrics(context: &BridgeContext) -> String {
+    context.metrics_report()
+}
+
+#[bridge_fn_void]
+fn BridgeContext_ResetMetrics(context: &BridgeContext) {
+    context.reset_metrics()
+}
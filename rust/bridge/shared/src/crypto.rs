@@ -6,7 +6,9 @@
 use ::signal_crypto;
 use libsignal_bridge_macros::*;
 use signal_crypto::*;
+use std::time::Duration;
 
+use crate::rng::{CallRng, Rng};
 use crate::support::*;
 use crate::*;
 
@@ -77,6 +79,17 @@ bridge_handle!(Aes256GcmSiv, clone = false);
 bridge_handle!(Aes256Ctr32, mut = true, node = false);
 bridge_handle!(Aes256GcmEncryption, mut = true, node = false);
 bridge_handle!(Aes256GcmDecryption, mut = true, node = false);
+bridge_handle!(Aes256KeyWrap);
+bridge_handle!(StorageServiceKey);
+bridge_handle!(CallLinkRootKey);
+bridge_handle!(Argon2idParams);
+bridge_handle!(Crc32, mut = true);
+bridge_handle!(IncrementalMacValidator, clone = false, mut = true);
+bridge_handle!(ScryptParams);
+bridge_handle!(Sha256, mut = true);
+bridge_handle!(Sha512, mut = true);
+bridge_handle!(StickerCipher, clone = false);
+bridge_handle!(StickerDecryption, clone = false, mut = true);
 
 #[bridge_fn(node = false)]
 fn Aes256Ctr32_New(key: &[u8], nonce: &[u8], initial_ctr: u32) -> Result<Aes256Ctr32> {
@@ -244,3 +257,359 @@ fn CryptographicMac_Finalize<T: Env>(env: T, mac: &mut CryptographicMac) -> Resu
     let digest = mac.finalize()?;
     Ok(env.buffer(digest))
 }
+
+#[bridge_fn]
+fn Crc32_New() -> Crc32 {
+    Crc32::new()
+}
+
+#[bridge_fn_void]
+fn Crc32_Update(crc: &mut Crc32, input: &[u8]) {
+    crc.update(input)
+}
+
+/// Returns the checksum of everything passed to `Crc32_Update`, and starts over.
+#[bridge_fn]
+fn Crc32_Finalize(crc: &mut Crc32) -> u32 {
+    crc.finalize()
+}
+
+#[bridge_fn]
+fn Crc32_Digest(input: &[u8]) -> u32 {
+    Crc32::digest(input)
+}
+
+#[bridge_fn]
+fn IncrementalMacValidator_New(
+    key: &[u8],
+    chunk_size: u32,
+    digest: &[u8],
+) -> Result<IncrementalMacValidator> {
+    IncrementalMacValidator::new(key, chunk_size as usize, digest)
+}
+
+/// Returns false once the input is known to be invalid, at which point
+/// `IncrementalMacValidator_GetValidatedLength` gives the offset of the bad chunk.
+#[bridge_fn]
+fn IncrementalMacValidator_Update(
+    validator: &mut IncrementalMacValidator,
+    data: &[u8],
+) -> Result<bool> {
+    match validator.update(data) {
+        Ok(()) => Ok(true),
+        Err(Error::InvalidTag) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+#[bridge_fn]
+fn IncrementalMacValidator_Finalize(validator: &mut IncrementalMacValidator) -> Result<bool> {
+    match validator.finalize() {
+        Ok(()) => Ok(true),
+        Err(Error::InvalidTag) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+#[bridge_fn]
+fn IncrementalMacValidator_GetValidatedLength(validator: &IncrementalMacValidator) -> u64 {
+    validator.validated_length()
+}
+
+#[bridge_fn]
+fn StickerCipher_New(pack_key: &[u8]) -> Result<StickerCipher> {
+    StickerCipher::new(pack_key)
+}
+
+#[bridge_fn_buffer]
+fn StickerCipher_Decrypt<E: Env>(env: E, cipher: &StickerCipher, data: &[u8]) -> Result<E::Buffer> {
+    Ok(env.buffer(cipher.decrypt(data)?))
+}
+
+#[bridge_fn]
+fn StickerDecryption_New(cipher: &StickerCipher) -> StickerDecryption {
+    cipher.decryption()
+}
+
+#[bridge_fn_buffer]
+fn StickerDecryption_Update<E: Env>(
+    env: E,
+    decryption: &mut StickerDecryption,
+    data: &[u8],
+) -> Result<E::Buffer> {
+    Ok(env.buffer(decryption.update(data)?))
+}
+
+#[bridge_fn_buffer]
+fn StickerDecryption_Finalize<E: Env>(
+    env: E,
+    decryption: &mut StickerDecryption,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(decryption.finalize()?))
+}
+
+#[bridge_fn]
+fn Sha256_New() -> Sha256 {
+    Sha256::new()
+}
+
+#[bridge_fn_void]
+fn Sha256_Update(sha256: &mut Sha256, input: &[u8]) {
+    sha256.update(input)
+}
+
+/// Returns the digest of everything passed to `Sha256_Update`, and starts over.
+#[bridge_fn_buffer]
+fn Sha256_Finalize<E: Env>(env: E, sha256: &mut Sha256) -> Result<E::Buffer> {
+    Ok(env.buffer(sha256.finalize()))
+}
+
+#[bridge_fn_buffer]
+fn Sha256_Digest<E: Env>(env: E, input: &[u8]) -> Result<E::Buffer> {
+    Ok(env.buffer(Sha256::digest(input)))
+}
+
+#[bridge_fn]
+fn Sha512_New() -> Sha512 {
+    Sha512::new()
+}
+
+#[bridge_fn_void]
+fn Sha512_Update(sha512: &mut Sha512, input: &[u8]) {
+    sha512.update(input)
+}
+
+/// Returns the digest of everything passed to `Sha512_Update`, and starts over.
+#[bridge_fn_buffer]
+fn Sha512_Finalize<E: Env>(env: E, sha512: &mut Sha512) -> Result<E::Buffer> {
+    Ok(env.buffer(sha512.finalize()))
+}
+
+#[bridge_fn_buffer]
+fn Sha512_Digest<E: Env>(env: E, input: &[u8]) -> Result<E::Buffer> {
+    Ok(env.buffer(Sha512::digest(input)))
+}
+
+#[bridge_fn]
+fn Argon2idParams_New(
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+) -> Result<Argon2idParams> {
+    Argon2idParams::new(memory_kib, iterations, parallelism)
+}
+
+/// Runs the KDF once to pick the iterations that take about `target_duration_ms` on this device.
+#[bridge_fn]
+fn Argon2idParams_Calibrate(
+    target_duration_ms: u32,
+    memory_kib: u32,
+    parallelism: u32,
+) -> Result<Argon2idParams> {
+    Argon2idParams::calibrate(
+        Duration::from_millis(target_duration_ms.into()),
+        memory_kib,
+        parallelism,
+    )
+}
+
+#[bridge_fn]
+fn Argon2idParams_GetMemoryKib(params: &Argon2idParams) -> u32 {
+    params.memory_kib()
+}
+
+#[bridge_fn]
+fn Argon2idParams_GetIterations(params: &Argon2idParams) -> u32 {
+    params.iterations()
+}
+
+#[bridge_fn]
+fn Argon2idParams_GetParallelism(params: &Argon2idParams) -> u32 {
+    params.parallelism()
+}
+
+#[bridge_fn_buffer]
+fn Argon2idParams_Derive<E: Env>(
+    env: E,
+    params: &Argon2idParams,
+    password: &[u8],
+    salt: &[u8],
+    output_length: u32,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(params.derive(password, salt, output_length as usize)?))
+}
+
+#[bridge_fn]
+fn ScryptParams_New(log_n: u8, r: u32, p: u32) -> Result<ScryptParams> {
+    ScryptParams::new(log_n, r, p)
+}
+
+/// Runs the KDF once to pick the cost that takes about `target_duration_ms` on this device.
+#[bridge_fn]
+fn ScryptParams_Calibrate(target_duration_ms: u32, r: u32, p: u32) -> Result<ScryptParams> {
+    ScryptParams::calibrate(Duration::from_millis(target_duration_ms.into()), r, p)
+}
+
+#[bridge_fn]
+fn ScryptParams_GetLogN(params: &ScryptParams) -> u8 {
+    params.log_n()
+}
+
+#[bridge_fn]
+fn ScryptParams_GetR(params: &ScryptParams) -> u32 {
+    params.r()
+}
+
+#[bridge_fn]
+fn ScryptParams_GetP(params: &ScryptParams) -> u32 {
+    params.p()
+}
+
+#[bridge_fn_buffer]
+fn ScryptParams_Derive<E: Env>(
+    env: E,
+    params: &ScryptParams,
+    password: &[u8],
+    salt: &[u8],
+    output_length: u32,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(params.derive(password, salt, output_length as usize)?))
+}
+
+#[bridge_fn]
+fn Aes256KeyWrap_New(key: &[u8]) -> Result<Aes256KeyWrap> {
+    Aes256KeyWrap::new(key)
+}
+
+#[bridge_fn_buffer]
+fn Aes256KeyWrap_Wrap<E: Env>(env: E, kw: &Aes256KeyWrap, key: &[u8]) -> Result<E::Buffer> {
+    Ok(env.buffer(kw.wrap(key)?))
+}
+
+#[bridge_fn_buffer]
+fn Aes256KeyWrap_Unwrap<E: Env>(env: E, kw: &Aes256KeyWrap, wrapped: &[u8]) -> Result<E::Buffer> {
+    Ok(env.buffer(kw.unwrap(wrapped)?))
+}
+
+#[bridge_fn]
+fn StorageServiceKey_New(storage_key: &[u8]) -> Result<StorageServiceKey> {
+    StorageServiceKey::new(storage_key)
+}
+
+#[bridge_fn]
+fn StorageServiceKey_FromMasterKey(master_key: &[u8]) -> Result<StorageServiceKey> {
+    StorageServiceKey::from_master_key(master_key)
+}
+
+#[bridge_fn_buffer]
+fn StorageServiceKey_GetStorageKey<E: Env>(env: E, key: &StorageServiceKey) -> Result<E::Buffer> {
+    Ok(env.buffer(key.storage_key().to_vec()))
+}
+
+#[bridge_fn_buffer]
+fn StorageServiceKey_ManifestKey<E: Env>(
+    env: E,
+    key: &StorageServiceKey,
+    version: u64,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(key.manifest_key(version).to_vec()))
+}
+
+#[bridge_fn_buffer]
+fn StorageServiceKey_RecordKey<E: Env>(
+    env: E,
+    key: &StorageServiceKey,
+    raw_id: &[u8],
+) -> Result<E::Buffer> {
+    Ok(env.buffer(key.record_key(raw_id).to_vec()))
+}
+
+#[bridge_fn_buffer]
+fn StorageServiceKey_EncryptManifest<E: Env>(
+    env: E,
+    key: &StorageServiceKey,
+    version: u64,
+    plaintext: &[u8],
+    rng: Option<&Rng>,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(key.encrypt_manifest(version, plaintext, &mut CallRng::new(rng))?))
+}
+
+#[bridge_fn_buffer]
+fn StorageServiceKey_DecryptManifest<E: Env>(
+    env: E,
+    key: &StorageServiceKey,
+    version: u64,
+    ciphertext: &[u8],
+) -> Result<E::Buffer> {
+    Ok(env.buffer(key.decrypt_manifest(version, ciphertext)?))
+}
+
+#[bridge_fn_buffer]
+fn StorageServiceKey_EncryptRecord<E: Env>(
+    env: E,
+    key: &StorageServiceKey,
+    raw_id: &[u8],
+    plaintext: &[u8],
+    rng: Option<&Rng>,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(key.encrypt_record(raw_id, plaintext, &mut CallRng::new(rng))?))
+}
+
+#[bridge_fn_buffer]
+fn StorageServiceKey_DecryptRecord<E: Env>(
+    env: E,
+    key: &StorageServiceKey,
+    raw_id: &[u8],
+    ciphertext: &[u8],
+) -> Result<E::Buffer> {
+    Ok(env.buffer(key.decrypt_record(raw_id, ciphertext)?))
+}
+
+#[bridge_fn]
+fn CallLinkRootKey_Generate(rng: Option<&Rng>) -> CallLinkRootKey {
+    CallLinkRootKey::generate(&mut CallRng::new(rng))
+}
+
+#[bridge_fn]
+fn CallLinkRootKey_FromBytes(bytes: &[u8]) -> Result<CallLinkRootKey> {
+    CallLinkRootKey::new(bytes)
+}
+
+#[bridge_fn]
+fn CallLinkRootKey_Parse(text: String) -> Result<CallLinkRootKey> {
+    CallLinkRootKey::parse(&text)
+}
+
+#[bridge_fn_buffer]
+fn CallLinkRootKey_GetBytes<E: Env>(env: E, key: &CallLinkRootKey) -> Result<E::Buffer> {
+    Ok(env.buffer(key.bytes().to_vec()))
+}
+
+#[bridge_fn]
+fn CallLinkRootKey_ToString(key: &CallLinkRootKey) -> String {
+    key.to_string()
+}
+
+#[bridge_fn_buffer]
+fn CallLinkRootKey_DeriveRoomId<E: Env>(env: E, key: &CallLinkRootKey) -> Result<E::Buffer> {
+    Ok(env.buffer(key.derive_room_id().to_vec()))
+}
+
+#[bridge_fn_buffer]
+fn CallLinkRootKey_DeriveEraKey<E: Env>(
+    env: E,
+    key: &CallLinkRootKey,
+    era_id: String,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(key.derive_era_key(&era_id).to_vec()))
+}
+
+#[bridge_fn_buffer]
+fn GroupCall_DeriveEraKey<E: Env>(
+    env: E,
+    group_master_key: &[u8],
+    era_id: String,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(group_call::derive_group_call_era_key(group_master_key, &era_id)?.to_vec()))
+}
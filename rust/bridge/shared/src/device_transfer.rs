@@ -5,6 +5,7 @@
 
 use ::device_transfer;
 use libsignal_bridge_macros::*;
+use std::time::{Duration, UNIX_EPOCH};
 
 use crate::support::*;
 use crate::*;
@@ -23,7 +24,10 @@ fn DeviceTransfer_GenerateCertificate<T: Env>(
     private_key: &[u8],
     name: String,
     days_to_expire: u32,
+    now: u64,
 ) -> Result<T::Buffer, device_transfer::Error> {
-    let buf = device_transfer::create_self_signed_cert(&private_key, &name, days_to_expire)?;
+    let now = UNIX_EPOCH + Duration::from_millis(now);
+    let buf =
+        device_transfer::create_self_signed_cert_at(&private_key, &name, days_to_expire, now)?;
     Ok(env.buffer(buf))
 }
@@ -0,0 +1,191 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Base64 and hex codecs, for platforms whose own implementations are slow on large inputs.
+//!
+//! Base64 uses the standard alphabet with padding. Long inputs can be converted in pieces with
+//! [`Base64Encoder`] and [`Base64Decoder`], which carry any incomplete group over to the next
+//! piece, so the result is the same as converting the whole input at once. Hex needs no such
+//! help: encoded pieces can simply be concatenated, and any even-length piece can be decoded.
+
+use libsignal_bridge_macros::*;
+use libsignal_protocol::error::{Result, SignalProtocolError};
+
+// Every entry point here is Node-only.
+#[cfg(feature = "node")]
+use crate::support::*;
+#[cfg(feature = "node")]
+use crate::*;
+
+fn invalid_base64(e: base64::DecodeError) -> SignalProtocolError {
+    SignalProtocolError::InvalidArgument(format!("invalid base64: {}", e))
+}
+
+/// Encodes bytes as base64 as they arrive.
+#[derive(Clone, Default)]
+pub struct Base64Encoder {
+    /// Fewer than 3 bytes that have not been encoded yet.
+    pending: Vec<u8>,
+}
+
+impl Base64Encoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Encodes as much of `data` as possible, keeping any incomplete group for later.
+    pub fn update(&mut self, mut data: &[u8]) -> String {
+        let mut encoded = String::new();
+        if !self.pending.is_empty() {
+            let needed = std::cmp::min(3 - self.pending.len(), data.len());
+            self.pending.extend_from_slice(&data[..needed]);
+            data = &data[needed..];
+            if self.pending.len() < 3 {
+                return encoded;
+            }
+            base64::encode_config_buf(&self.pending, base64::STANDARD, &mut encoded);
+            self.pending.clear();
+        }
+        let complete_len = data.len() - data.len() % 3;
+        base64::encode_config_buf(&data[..complete_len], base64::STANDARD, &mut encoded);
+        self.pending.extend_from_slice(&data[complete_len..]);
+        encoded
+    }
+
+    /// Encodes the final, padded group, if any, and resets the encoder.
+    pub fn finalize(&mut self) -> String {
+        let encoded = base64::encode_config(&self.pending, base64::STANDARD);
+        self.pending.clear();
+        encoded
+    }
+}
+
+/// Decodes base64 text as it arrives.
+#[derive(Clone, Default)]
+pub struct Base64Decoder {
+    /// Fewer than 4 characters that have not been decoded yet.
+    pending: String,
+    /// Whether a padded group has been decoded, after which no more input is allowed.
+    saw_padding: bool,
+}
+
+impl Base64Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes as much of `encoded` as possible, keeping any incomplete group for later.
+    pub fn update(&mut self, mut encoded: &str) -> Result<Vec<u8>> {
+        let mut decoded = Vec::new();
+        if encoded.is_empty() {
+            return Ok(decoded);
+        }
+        if !encoded.is_ascii() {
+            return Err(SignalProtocolError::InvalidArgument(
+                "invalid base64: non-ASCII character".into(),
+            ));
+        }
+        if !self.pending.is_empty() {
+            let needed = std::cmp::min(4 - self.pending.len(), encoded.len());
+            self.pending.push_str(&encoded[..needed]);
+            encoded = &encoded[needed..];
+            if self.pending.len() < 4 {
+                return Ok(decoded);
+            }
+            let group = std::mem::take(&mut self.pending);
+            self.decode_complete_groups(&group, &mut decoded)?;
+        }
+        let complete_len = encoded.len() - encoded.len() % 4;
+        self.decode_complete_groups(&encoded[..complete_len], &mut decoded)?;
+        self.check_not_after_padding(&encoded[complete_len..])?;
+        self.pending.push_str(&encoded[complete_len..]);
+        Ok(decoded)
+    }
+
+    /// Decodes any remaining input and resets the decoder.
+    pub fn finalize(&mut self) -> Result<Vec<u8>> {
+        let mut decoded = Vec::new();
+        let pending = std::mem::take(&mut self.pending);
+        self.saw_padding = false;
+        base64::decode_config_buf(&pending, base64::STANDARD, &mut decoded)
+            .map_err(invalid_base64)?;
+        Ok(decoded)
+    }
+
+    fn check_not_after_padding(&self, encoded: &str) -> Result<()> {
+        if self.saw_padding && !encoded.is_empty() {
+            return Err(SignalProtocolError::InvalidArgument(
+                "invalid base64: data after padding".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn decode_complete_groups(&mut self, encoded: &str, decoded: &mut Vec<u8>) -> Result<()> {
+        self.check_not_after_padding(encoded)?;
+        base64::decode_config_buf(encoded, base64::STANDARD, decoded).map_err(invalid_base64)?;
+        self.saw_padding |= encoded.ends_with('=');
+        Ok(())
+    }
+}
+
+bridge_handle!(Base64Decoder, mut = true, ffi = false, jni = false);
+bridge_handle!(Base64Encoder, mut = true, ffi = false, jni = false);
+
+#[bridge_fn(ffi = false, jni = false)]
+fn Base64_Encode(data: &[u8]) -> String {
+    base64::encode_config(data, base64::STANDARD)
+}
+
+#[bridge_fn_buffer(ffi = false, jni = false)]
+fn Base64_Decode<E: Env>(env: E, encoded: String) -> Result<E::Buffer> {
+    Ok(env.buffer(base64::decode_config(&encoded, base64::STANDARD).map_err(invalid_base64)?))
+}
+
+#[bridge_fn(ffi = false, jni = false)]
+fn Base64Encoder_New() -> Base64Encoder {
+    Base64Encoder::new()
+}
+
+#[bridge_fn(ffi = false, jni = false)]
+fn Base64Encoder_Update(encoder: &mut Base64Encoder, data: &[u8]) -> String {
+    encoder.update(data)
+}
+
+#[bridge_fn(ffi = false, jni = false)]
+fn Base64Encoder_Finalize(encoder: &mut Base64Encoder) -> String {
+    encoder.finalize()
+}
+
+#[bridge_fn(ffi = false, jni = false)]
+fn Base64Decoder_New() -> Base64Decoder {
+    Base64Decoder::new()
+}
+
+#[bridge_fn_buffer(ffi = false, jni = false)]
+fn Base64Decoder_Update<E: Env>(
+    env: E,
+    decoder: &mut Base64Decoder,
+    encoded: String,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(decoder.update(&encoded)?))
+}
+
+#[bridge_fn_buffer(ffi = false, jni = false)]
+fn Base64Decoder_Finalize<E: Env>(env: E, decoder: &mut Base64Decoder) -> Result<E::Buffer> {
+    Ok(env.buffer(decoder.finalize()?))
+}
+
+#[bridge_fn(ffi = false, jni = false)]
+fn Hex_Encode(data: &[u8]) -> String {
+    hex::encode(data)
+}
+
+#[bridge_fn_buffer(ffi = false, jni = false)]
+fn Hex_Decode<E: Env>(env: E, encoded: String) -> Result<E::Buffer> {
+    let decoded = hex::decode(&encoded)
+        .map_err(|e| SignalProtocolError::InvalidArgument(format!("invalid hex: {}", e)))?;
+    Ok(env.buffer(decoded))
+}
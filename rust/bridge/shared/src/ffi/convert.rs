@@ -152,9 +152,21 @@ pub trait ResultTypeInfo: Sized {
     fn convert_into(self) -> SignalFfiResult<Self::ResultType>;
 }
 
+/// Converts result values that are returned as an array and a length, like `Vec<Foo>`.
+///
+/// The `bridge_fn` macro uses this for results that are syntactically `Vec<...>`, passing an extra
+/// `out_len` argument after `out`.
+pub trait SizedResultTypeInfo: Sized {
+    /// The FFI form of each element of the array (e.g. `*mut Foo`).
+    type ElementType;
+    /// Converts the data in `self` to an array of the FFI type.
+    fn convert_into(self) -> SignalFfiResult<Box<[Self::ElementType]>>;
+}
+
 impl SizedArgTypeInfo for &[u8] {
     type ArgType = *const c_uchar;
     fn convert_from(input: Self::ArgType, input_len: usize) -> SignalFfiResult<Self> {
+        crate::limits::check_buffer_len(input_len)?;
         if input.is_null() {
             if input_len != 0 {
                 return Err(SignalFfiError::NullPointer);
@@ -167,6 +179,20 @@ impl SizedArgTypeInfo for &[u8] {
     }
 }
 
+/// A null pointer is `None`.
+impl SizedArgTypeInfo for Option<&[u8]> {
+    type ArgType = *const c_uchar;
+    fn convert_from(input: Self::ArgType, input_len: usize) -> SignalFfiResult<Self> {
+        if input.is_null() {
+            if input_len != 0 {
+                return Err(SignalFfiError::NullPointer);
+            }
+            return Ok(None);
+        }
+        Ok(Some(<&[u8]>::convert_from(input, input_len)?))
+    }
+}
+
 impl SizedArgTypeInfo for &mut [u8] {
     type ArgType = *mut c_uchar;
     fn convert_from(input: Self::ArgType, input_len: usize) -> SignalFfiResult<Self> {
@@ -182,6 +208,28 @@ impl SizedArgTypeInfo for &mut [u8] {
     }
 }
 
+/// Converts an array of handle pointers, each of which must be non-null.
+impl<'a, T> SizedArgTypeInfo for Vec<&'a T>
+where
+    &'a T: SimpleArgTypeInfo<ArgType = *const T>,
+{
+    type ArgType = *const *const T;
+    fn convert_from(input: Self::ArgType, input_len: usize) -> SignalFfiResult<Self> {
+        if input.is_null() {
+            if input_len != 0 {
+                return Err(SignalFfiError::NullPointer);
+            }
+            return Ok(Vec::new());
+        }
+
+        let handles = unsafe { std::slice::from_raw_parts(input, input_len) };
+        handles
+            .iter()
+            .map(|&handle| <&'a T>::convert_from(handle))
+            .collect()
+    }
+}
+
 /// `u32::MAX` (`UINT_MAX`, `~0u`) is used to represent `None` here.
 impl SimpleArgTypeInfo for Option<u32> {
     type ArgType = u32;
@@ -194,6 +242,14 @@ impl SimpleArgTypeInfo for Option<u32> {
     }
 }
 
+/// Rejects values too far in the future to be milliseconds since the epoch.
+impl SimpleArgTypeInfo for crate::timestamp::Timestamp {
+    type ArgType = u64;
+    fn convert_from(foreign: u64) -> SignalFfiResult<Self> {
+        Ok(Self::from_epoch_millis(foreign)?)
+    }
+}
+
 /// Converts a non-`NULL` C string to a Rust String.
 impl SimpleArgTypeInfo for String {
     type ArgType = *const c_char;
@@ -251,6 +307,7 @@ macro_rules! store {
 
 store!(IdentityKeyStore);
 store!(PreKeyStore);
+store!(SenderCertificateSource);
 store!(SenderKeyStore);
 store!(SessionStore);
 store!(SignedPreKeyStore);
@@ -276,6 +333,27 @@ impl<T: ResultTypeInfo> ResultTypeInfo for Result<T, signal_crypto::Error> {
     }
 }
 
+impl<T: ResultTypeInfo> ResultTypeInfo for Result<T, message_backup::Error> {
+    type ResultType = T::ResultType;
+    fn convert_into(self) -> SignalFfiResult<Self::ResultType> {
+        T::convert_into(self?)
+    }
+}
+
+impl<T: ResultTypeInfo> ResultTypeInfo for Result<T, keytrans::Error> {
+    type ResultType = T::ResultType;
+    fn convert_into(self) -> SignalFfiResult<Self::ResultType> {
+        T::convert_into(self?)
+    }
+}
+
+impl<T: ResultTypeInfo> ResultTypeInfo for Result<T, registration::Error> {
+    type ResultType = T::ResultType;
+    fn convert_into(self) -> SignalFfiResult<Self::ResultType> {
+        T::convert_into(self?)
+    }
+}
+
 /// Allocates and returns a new Rust-owned C string.
 impl ResultTypeInfo for String {
     type ResultType = *const libc::c_char;
@@ -311,6 +389,15 @@ impl ResultTypeInfo for Option<&str> {
         }
     }
 }
+
+/// Produces milliseconds since the epoch.
+impl ResultTypeInfo for crate::timestamp::Timestamp {
+    type ResultType = u64;
+    fn convert_into(self) -> SignalFfiResult<Self::ResultType> {
+        Ok(self.epoch_millis())
+    }
+}
+
 /// `u32::MAX` (`UINT_MAX`, `~0u`) is used to represent `None` here.
 impl ResultTypeInfo for Option<u32> {
     type ResultType = u32;
@@ -319,6 +406,27 @@ impl ResultTypeInfo for Option<u32> {
     }
 }
 
+/// Produces an array of boxed values, one for each handle.
+impl<T> SizedResultTypeInfo for Vec<T>
+where
+    T: crate::support::BridgeHandle + ResultTypeInfo,
+{
+    type ElementType = T::ResultType;
+    fn convert_into(self) -> SignalFfiResult<Box<[Self::ElementType]>> {
+        self.into_iter().map(T::convert_into).collect()
+    }
+}
+
+impl<T: SizedResultTypeInfo, E> SizedResultTypeInfo for Result<T, E>
+where
+    SignalFfiError: From<E>,
+{
+    type ElementType = T::ElementType;
+    fn convert_into(self) -> SignalFfiResult<Box<[Self::ElementType]>> {
+        T::convert_into(self?)
+    }
+}
+
 /// A dummy type used to implement [`crate::support::Env`].
 pub(crate) struct Env;
 
@@ -333,6 +441,72 @@ impl crate::support::Env for Env {
 /// Implementation of [`bridge_handle`](crate::support::bridge_handle) for FFI.
 macro_rules! ffi_bridge_handle {
     ( $typ:ty as false $(, $($_:tt)*)? ) => {};
+    ( $typ:ty as $ffi_name:ident, shared = true ) => {
+        // Shared handles point to an `Arc<$typ>` rather than directly to the value.
+        impl ffi::SimpleArgTypeInfo for &$typ {
+            type ArgType = *const $typ;
+            #[allow(clippy::not_unsafe_ptr_arg_deref)]
+            fn convert_from(foreign: *const $typ) -> ffi::SignalFfiResult<Self> {
+                let shared = unsafe {
+                    ffi::native_handle_cast(foreign as *const std::sync::Arc<$typ>)
+                }?;
+                Ok(&**shared)
+            }
+        }
+        impl ffi::SimpleArgTypeInfo for Option<&$typ> {
+            type ArgType = *const $typ;
+            fn convert_from(foreign: *const $typ) -> ffi::SignalFfiResult<Self> {
+                if foreign.is_null() {
+                    Ok(None)
+                } else {
+                    <&$typ>::convert_from(foreign).map(Some)
+                }
+            }
+        }
+        impl ffi::ResultTypeInfo for $typ {
+            type ResultType = *mut $typ;
+            fn convert_into(self) -> ffi::SignalFfiResult<Self::ResultType> {
+                Ok(Box::into_raw(Box::new(std::sync::Arc::new(self))) as *mut $typ)
+            }
+        }
+        impl ffi::ResultTypeInfo for Option<$typ> {
+            type ResultType = *mut $typ;
+            fn convert_into(self) -> ffi::SignalFfiResult<Self::ResultType> {
+                match self {
+                    Some(obj) => obj.convert_into(),
+                    None => Ok(std::ptr::null_mut()),
+                }
+            }
+        }
+        ffi_bridge_destroy!($typ as $ffi_name, shared = true);
+        paste! {
+            #[no_mangle]
+            pub unsafe extern "C" fn [<signal_ $ffi_name _clone>](
+                new_obj: *mut *mut $typ,
+                obj: *const $typ,
+            ) -> *mut ffi::SignalFfiError {
+                ffi::run_ffi_safe(concat!("signal_", stringify!($ffi_name), "_clone"), || {
+                    if new_obj.is_null() {
+                        return Err(ffi::SignalFfiError::NullPointer);
+                    }
+                    let shared =
+                        ffi::native_handle_cast::<std::sync::Arc<$typ>>(obj as *const _)?;
+                    *new_obj = Box::into_raw(Box::new(std::sync::Arc::clone(shared))) as *mut $typ;
+                    Ok(())
+                })
+            }
+
+            bridge_registry_entry!(
+                "ffi",
+                concat!("signal_", stringify!($ffi_name), "_clone"),
+                [
+                    libsignal_bridge_macros::ffi_c_type!(*mut *mut $typ),
+                    libsignal_bridge_macros::ffi_c_type!(*const $typ)
+                ] -> "SignalFfiError *",
+                false
+            );
+        }
+    };
     ( $typ:ty as $ffi_name:ident, clone = false ) => {
         impl ffi::SimpleArgTypeInfo for &$typ {
             type ArgType = *const $typ;
@@ -383,16 +557,26 @@ macro_rules! ffi_bridge_handle {
                 new_obj: *mut *mut $typ,
                 obj: *const $typ,
             ) -> *mut ffi::SignalFfiError {
-                ffi::run_ffi_safe(|| {
+                ffi::run_ffi_safe(concat!("signal_", stringify!($ffi_name), "_clone"), || {
                     let obj = ffi::native_handle_cast::<$typ>(obj)?;
                     ffi::box_object::<$typ>(new_obj, Ok(obj.clone()))
                 })
             }
+
+            bridge_registry_entry!(
+                "ffi",
+                concat!("signal_", stringify!($ffi_name), "_clone"),
+                [
+                    libsignal_bridge_macros::ffi_c_type!(*mut *mut $typ),
+                    libsignal_bridge_macros::ffi_c_type!(*const $typ)
+                ] -> "SignalFfiError *",
+                false
+            );
         }
     };
-    ( $typ:ty $(, clone = $_:tt)? ) => {
+    ( $typ:ty $(, clone = $_:tt)? $(, shared = $__:tt)? ) => {
         paste! {
-            ffi_bridge_handle!($typ as [<$typ:snake>] $(, clone = $_)? );
+            ffi_bridge_handle!($typ as [<$typ:snake>] $(, clone = $_)? $(, shared = $__)? );
         }
     };
 }
@@ -432,12 +616,15 @@ trivial!(bool);
 /// Types that implement [`SizedArgTypeInfo`] should only include their base type here.
 /// (For example, `(&[u8]) => (*const libc::c_uchar);`.)
 macro_rules! ffi_arg_type {
+    (bool) => (bool);
     (u8) => (u8);
     (u32) => (u32);
     (u64) => (u64);
     (Option<u32>) => (u32);
+    (Timestamp) => (u64);
     (usize) => (libc::size_t);
     (&[u8]) => (*const libc::c_uchar);
+    (Option<&[u8]>) => (*const libc::c_uchar);
     (&mut [u8]) => (*mut libc::c_uchar);
     (String) => (*const libc::c_char);
     (Option<String>) => (*const libc::c_char);
@@ -447,6 +634,7 @@ macro_rules! ffi_arg_type {
     (& $typ:ty) => (*const $typ);
     (&mut $typ:ty) => (*mut $typ);
     (Option<& $typ:ty>) => (*const $typ);
+    (Vec<& $typ:ty>) => (*const *const $typ);
 }
 
 /// Syntactically translates `bridge_fn` result types to FFI types for `cbindgen`.
@@ -469,11 +657,13 @@ macro_rules! ffi_result_type {
     (u32) => (u32);
     (Option<u32>) => (u32);
     (u64) => (u64);
+    (Timestamp) => (u64);
     (bool) => (bool);
     (&str) => (*const libc::c_char);
     (String) => (*const libc::c_char);
     (Option<String>) => (*const libc::c_char);
     (Option<&str>) => (*const libc::c_char);
     (Option<$typ:ty>) => (*mut $typ);
+    (Vec<$typ:ty>) => (*mut *mut $typ);
     ( $typ:ty ) => (*mut $typ);
 }
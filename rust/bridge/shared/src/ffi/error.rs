@@ -7,19 +7,36 @@ use std::convert::TryFrom;
 use std::fmt;
 
 use device_transfer::Error as DeviceTransferError;
+use keytrans::Error as KeyTransparencyError;
 use libsignal_protocol::*;
+use message_backup::Error as MessageBackupError;
+use registration::Error as RegistrationError;
 use signal_crypto::Error as SignalCryptoError;
 
+use crate::limits::BufferTooLarge;
+use crate::support::describe_panic;
+
 /// The top-level error type (opaquely) returned to C clients when something goes wrong.
 #[derive(Debug)]
 pub enum SignalFfiError {
     Signal(SignalProtocolError),
+    /// A [`SignalProtocolError::WithContext`], split up so that the error code can still be
+    /// determined by matching on the underlying error.
+    WithContext(ErrorContext, Box<SignalFfiError>),
     DeviceTransfer(DeviceTransferError),
     SignalCrypto(SignalCryptoError),
+    MessageBackup(MessageBackupError),
+    KeyTransparency(KeyTransparencyError),
+    Registration(RegistrationError),
     InsufficientOutputSize(usize, usize),
+    BufferTooLarge(BufferTooLarge),
     NullPointer,
     InvalidUtf8String,
-    UnexpectedPanic(std::boxed::Box<dyn std::any::Any + std::marker::Send>),
+    /// A panic in the named entry point.
+    UnexpectedPanic(
+        &'static str,
+        std::boxed::Box<dyn std::any::Any + std::marker::Send>,
+    ),
     InvalidType,
 }
 
@@ -27,30 +44,39 @@ impl fmt::Display for SignalFfiError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             SignalFfiError::Signal(s) => write!(f, "{}", s),
+            SignalFfiError::WithContext(context, e) => write!(f, "{}: {}", context, e),
             SignalFfiError::DeviceTransfer(c) => {
                 write!(f, "Device transfer operation failed: {}", c)
             }
             SignalFfiError::SignalCrypto(c) => {
                 write!(f, "Cryptographic operation failed: {}", c)
             }
+            SignalFfiError::MessageBackup(e) => write!(f, "Message backup failed: {}", e),
+            SignalFfiError::KeyTransparency(e) => write!(f, "Key transparency failed: {}", e),
+            SignalFfiError::Registration(e) => write!(f, "Registration failed: {}", e),
             SignalFfiError::NullPointer => write!(f, "null pointer"),
             SignalFfiError::InvalidType => write!(f, "invalid type"),
             SignalFfiError::InvalidUtf8String => write!(f, "invalid UTF8 string"),
             SignalFfiError::InsufficientOutputSize(n, h) => {
                 write!(f, "needed {} elements only {} provided", n, h)
             }
+            SignalFfiError::BufferTooLarge(e) => write!(f, "{}", e),
 
-            SignalFfiError::UnexpectedPanic(e) => match e.downcast_ref::<&'static str>() {
-                Some(s) => write!(f, "unexpected panic: {}", s),
-                None => write!(f, "unknown unexpected panic"),
-            },
+            SignalFfiError::UnexpectedPanic(function, e) => {
+                write!(f, "unexpected panic in {}: {}", function, describe_panic(e))
+            }
         }
     }
 }
 
 impl From<SignalProtocolError> for SignalFfiError {
     fn from(e: SignalProtocolError) -> SignalFfiError {
-        SignalFfiError::Signal(e)
+        match e {
+            SignalProtocolError::WithContext(context, e) => {
+                SignalFfiError::WithContext(context, Box::new((*e).into()))
+            }
+            e => SignalFfiError::Signal(e),
+        }
     }
 }
 
@@ -66,6 +92,30 @@ impl From<SignalCryptoError> for SignalFfiError {
     }
 }
 
+impl From<MessageBackupError> for SignalFfiError {
+    fn from(e: MessageBackupError) -> SignalFfiError {
+        SignalFfiError::MessageBackup(e)
+    }
+}
+
+impl From<KeyTransparencyError> for SignalFfiError {
+    fn from(e: KeyTransparencyError) -> SignalFfiError {
+        SignalFfiError::KeyTransparency(e)
+    }
+}
+
+impl From<RegistrationError> for SignalFfiError {
+    fn from(e: RegistrationError) -> SignalFfiError {
+        SignalFfiError::Registration(e)
+    }
+}
+
+impl From<BufferTooLarge> for SignalFfiError {
+    fn from(e: BufferTooLarge) -> SignalFfiError {
+        SignalFfiError::BufferTooLarge(e)
+    }
+}
+
 pub type SignalFfiResult<T> = Result<T, SignalFfiError>;
 
 /// Represents an error returned by a callback, following the C conventions that 0 means "success".
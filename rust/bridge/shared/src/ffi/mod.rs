@@ -5,6 +5,7 @@
 
 use libc::{c_uchar, size_t};
 use libsignal_protocol::*;
+use std::cell::Cell;
 use std::ffi::CString;
 
 #[macro_use]
@@ -19,13 +20,32 @@ pub use storage::*;
 
 pub use crate::support::expect_ready;
 
+thread_local! {
+    static CURRENT_ENTRY_POINT: Cell<Option<&'static str>> = Cell::new(None);
+}
+
+/// Returns the name of the entry point currently running on this thread, if any.
+///
+/// Used to attribute allocations to the call that made them.
+pub fn current_entry_point() -> Option<&'static str> {
+    CURRENT_ENTRY_POINT.try_with(Cell::get).ok().flatten()
+}
+
+/// Runs `f`, converting both errors and panics into a [`SignalFfiError`] to return to C.
+///
+/// `function` is the name of the entry point, used to describe any panic.
 pub fn run_ffi_safe<F: FnOnce() -> Result<(), SignalFfiError> + std::panic::UnwindSafe>(
+    function: &'static str,
     f: F,
 ) -> *mut SignalFfiError {
-    let result = match std::panic::catch_unwind(f) {
+    let previous = CURRENT_ENTRY_POINT.with(|current| current.replace(Some(function)));
+    let result = std::panic::catch_unwind(f);
+    CURRENT_ENTRY_POINT.with(|current| current.set(previous));
+
+    let result = match result {
         Ok(Ok(())) => Ok(()),
         Ok(Err(e)) => Err(e),
-        Err(r) => Err(SignalFfiError::UnexpectedPanic(r)),
+        Err(r) => Err(SignalFfiError::UnexpectedPanic(function, r)),
     };
 
     match result {
@@ -48,7 +68,7 @@ pub unsafe fn box_object<T>(
         }
         Err(e) => {
             *p = std::ptr::null_mut();
-            Err(SignalFfiError::Signal(e))
+            Err(e.into())
         }
     }
 }
@@ -80,6 +100,24 @@ pub unsafe fn write_result_to<T: ResultTypeInfo>(
     Ok(())
 }
 
+/// Writes an array result and its length to `out` and `out_len`.
+///
+/// The array should be freed with `signal_free_handle_array` (after taking ownership of each
+/// element).
+pub unsafe fn write_array_result_to<T: SizedResultTypeInfo>(
+    out: *mut *mut T::ElementType,
+    out_len: *mut size_t,
+    value: T,
+) -> SignalFfiResult<()> {
+    if out.is_null() || out_len.is_null() {
+        return Err(SignalFfiError::NullPointer);
+    }
+    let array = value.convert_into()?;
+    *out_len = array.len();
+    *out = Box::into_raw(array) as *mut T::ElementType;
+    Ok(())
+}
+
 pub unsafe fn write_bytearray_to<T: Into<Box<[u8]>>>(
     out: *mut *const c_uchar,
     out_len: *mut size_t,
@@ -98,6 +136,44 @@ pub unsafe fn write_bytearray_to<T: Into<Box<[u8]>>>(
     Ok(())
 }
 
+/// The result of a `bridge_fn_buffer` function, which may be optional.
+pub trait BufferResult {
+    fn into_optional_buffer(self) -> Option<Box<[u8]>>;
+}
+
+impl BufferResult for Box<[u8]> {
+    fn into_optional_buffer(self) -> Option<Box<[u8]>> {
+        Some(self)
+    }
+}
+
+impl BufferResult for Option<Box<[u8]>> {
+    fn into_optional_buffer(self) -> Option<Box<[u8]>> {
+        self
+    }
+}
+
+/// Writes a buffer result to a pointer/length pair.
+///
+/// `None` is written as `NULL` with a length of 0, without producing an error.
+pub unsafe fn write_buffer_result_to<T: BufferResult>(
+    out: *mut *const c_uchar,
+    out_len: *mut size_t,
+    value: T,
+) -> Result<(), SignalFfiError> {
+    match value.into_optional_buffer() {
+        Some(buffer) => write_bytearray_to(out, out_len, buffer),
+        None => {
+            if out.is_null() || out_len.is_null() {
+                return Err(SignalFfiError::NullPointer);
+            }
+            *out = std::ptr::null();
+            *out_len = 0;
+            Ok(())
+        }
+    }
+}
+
 /// Used by [`bridge_handle`](crate::support::bridge_handle).
 ///
 /// Not intended to be invoked directly.
@@ -109,13 +185,81 @@ macro_rules! ffi_bridge_destroy {
             pub unsafe extern "C" fn [<signal_ $ffi_name _destroy>](
                 p: *mut $typ
             ) -> *mut ffi::SignalFfiError {
-                ffi::run_ffi_safe(|| {
+                ffi::run_ffi_safe(concat!("signal_", stringify!($ffi_name), "_destroy"), || {
                     if !p.is_null() {
                         Box::from_raw(p);
                     }
                     Ok(())
                 })
             }
+
+            bridge_registry_entry!(
+                "ffi",
+                concat!("signal_", stringify!($ffi_name), "_destroy"),
+                [libsignal_bridge_macros::ffi_c_type!(*mut $typ)] -> "SignalFfiError *",
+                false
+            );
+        }
+    };
+    ( $typ:ty as $ffi_name:ident, shared = true ) => {
+        paste! {
+            #[cfg(feature = "ffi")]
+            #[no_mangle]
+            pub unsafe extern "C" fn [<signal_ $ffi_name _destroy>](
+                p: *mut $typ
+            ) -> *mut ffi::SignalFfiError {
+                ffi::run_ffi_safe(concat!("signal_", stringify!($ffi_name), "_destroy"), || {
+                    if !p.is_null() {
+                        Box::from_raw(p as *mut std::sync::Arc<$typ>);
+                    }
+                    Ok(())
+                })
+            }
+
+            bridge_registry_entry!(
+                "ffi",
+                concat!("signal_", stringify!($ffi_name), "_destroy"),
+                [libsignal_bridge_macros::ffi_c_type!(*mut $typ)] -> "SignalFfiError *",
+                false
+            );
+        }
+    };
+}
+
+/// Implementation of [`bridge_serialize`](crate::support::bridge_serialize) for FFI.
+macro_rules! ffi_bridge_serialize {
+    ( $typ:ident as false ) => {};
+    ( $typ:ident as $ffi_name:ident ) => {
+        paste! {
+            #[cfg(feature = "ffi")]
+            #[no_mangle]
+            pub unsafe extern "C" fn [<signal_ $ffi_name _serialize>](
+                out: *mut *const libc::c_uchar,
+                out_len: *mut libc::size_t,
+                obj: *const $typ,
+            ) -> *mut ffi::SignalFfiError {
+                ffi::run_ffi_safe(concat!("signal_", stringify!($ffi_name), "_serialize"), || {
+                    let obj = <&$typ as ffi::SimpleArgTypeInfo>::convert_from(obj)?;
+                    let serialized = crate::support::BridgeSerialize::bridge_serialize(obj)?;
+                    ffi::write_bytearray_to(out, out_len, serialized)
+                })
+            }
+
+            bridge_registry_entry!(
+                "ffi",
+                concat!("signal_", stringify!($ffi_name), "_serialize"),
+                [
+                    "const unsigned char **",
+                    "size_t *",
+                    libsignal_bridge_macros::ffi_c_type!(*const $typ)
+                ] -> "SignalFfiError *",
+                false
+            );
+        }
+    };
+    ( $typ:ident ) => {
+        paste! {
+            ffi_bridge_serialize!($typ as [<$typ:snake>]);
         }
     };
 }
@@ -132,7 +276,7 @@ macro_rules! ffi_bridge_deserialize {
                 data: *const libc::c_uchar,
                 data_len: libc::size_t,
             ) -> *mut ffi::SignalFfiError {
-                ffi::run_ffi_safe(|| {
+                ffi::run_ffi_safe(concat!("signal_", stringify!($ffi_name), "_deserialize"), || {
                     if data.is_null() {
                         return Err(ffi::SignalFfiError::NullPointer);
                     }
@@ -140,6 +284,17 @@ macro_rules! ffi_bridge_deserialize {
                     ffi::write_result_to(p, $typ::$fn(data))
                 })
             }
+
+            bridge_registry_entry!(
+                "ffi",
+                concat!("signal_", stringify!($ffi_name), "_deserialize"),
+                [
+                    libsignal_bridge_macros::ffi_c_type!(*mut *mut $typ),
+                    "const unsigned char *",
+                    "size_t"
+                ] -> "SignalFfiError *",
+                false
+            );
         }
     };
     ( $typ:ident::$fn:path ) => {
@@ -30,6 +30,17 @@ type IsTrustedIdentity = extern "C" fn(
     direction: c_uint,
     ctx: *mut c_void,
 ) -> c_int;
+type GetLocalIdentityKey = extern "C" fn(
+    store_ctx: *mut c_void,
+    public_keyp: *mut *mut PublicKey,
+    ctx: *mut c_void,
+) -> c_int;
+type CalculateIdentityAgreement = extern "C" fn(
+    store_ctx: *mut c_void,
+    shared_secret: *mut u8,
+    their_key: *const PublicKey,
+    ctx: *mut c_void,
+) -> c_int;
 
 #[derive(Debug)]
 #[repr(C)]
@@ -47,6 +58,39 @@ pub struct FfiIdentityKeyStoreStruct {
     save_identity: SaveIdentityKey,
     get_identity: GetIdentityKey,
     is_trusted_identity: IsTrustedIdentity,
+    /// Optional; if set along with `calculate_identity_agreement`, session setup uses these instead
+    /// of `get_identity_key_pair`, so the identity private key never has to leave the store.
+    get_local_identity_key: Option<GetLocalIdentityKey>,
+    /// Optional; writes the 32-byte X25519 agreement between the local identity private key and
+    /// `their_key` to `shared_secret`.
+    calculate_identity_agreement: Option<CalculateIdentityAgreement>,
+}
+
+struct FfiIdentityAgreement<'a> {
+    store: &'a FfiIdentityKeyStoreStruct,
+    calculate_identity_agreement: CalculateIdentityAgreement,
+    ctx: *mut c_void,
+}
+
+impl PrivateKeyOps for FfiIdentityAgreement<'_> {
+    fn calculate_agreement(&self, their_key: &PublicKey) -> Result<Box<[u8]>, SignalProtocolError> {
+        let mut shared_secret = [0u8; 32];
+        let result = (self.calculate_identity_agreement)(
+            self.store.ctx,
+            shared_secret.as_mut_ptr(),
+            their_key,
+            self.ctx,
+        );
+
+        if let Some(error) = CallbackError::check(result) {
+            return Err(SignalProtocolError::ApplicationCallbackError(
+                "calculate_identity_agreement",
+                Box::new(error),
+            ));
+        }
+
+        Ok(Box::new(shared_secret))
+    }
 }
 
 #[async_trait(?Send)]
@@ -76,6 +120,45 @@ impl IdentityKeyStore for &FfiIdentityKeyStoreStruct {
         Ok(IdentityKeyPair::new(IdentityKey::new(pub_key), *priv_key))
     }
 
+    async fn get_identity_key_ops<'a>(
+        &'a self,
+        ctx: Context,
+    ) -> Result<IdentityKeyPairOps<'a>, SignalProtocolError> {
+        let (get_local_identity_key, calculate_identity_agreement) = match (
+            self.get_local_identity_key,
+            self.calculate_identity_agreement,
+        ) {
+            (Some(get_key), Some(calculate)) => (get_key, calculate),
+            _ => return Ok(self.get_identity_key_pair(ctx).await?.into()),
+        };
+
+        let ctx = ctx.unwrap_or(std::ptr::null_mut());
+        let mut key = std::ptr::null_mut();
+        let result = get_local_identity_key(self.ctx, &mut key, ctx);
+
+        if let Some(error) = CallbackError::check(result) {
+            return Err(SignalProtocolError::ApplicationCallbackError(
+                "get_local_identity_key",
+                Box::new(error),
+            ));
+        }
+
+        if key.is_null() {
+            return Err(SignalProtocolError::InternalError("No identity key"));
+        }
+
+        let pub_key = unsafe { Box::from_raw(key) };
+
+        Ok(IdentityKeyPairOps::new(
+            IdentityKey::new(*pub_key),
+            Box::new(FfiIdentityAgreement {
+                store: *self,
+                calculate_identity_agreement,
+                ctx,
+            }),
+        ))
+    }
+
     async fn get_local_registration_id(&self, ctx: Context) -> Result<u32, SignalProtocolError> {
         let ctx = ctx.unwrap_or(std::ptr::null_mut());
         let mut id = 0;
@@ -456,3 +539,43 @@ impl SenderKeyStore for &FfiSenderKeyStoreStruct {
         Ok(Some(*record))
     }
 }
+
+type FetchSenderCertificate = extern "C" fn(
+    store_ctx: *mut c_void,
+    certificatep: *mut *mut SenderCertificate,
+    ctx: *mut c_void,
+) -> c_int;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct FfiSenderCertificateSourceStruct {
+    ctx: *mut c_void,
+    fetch_sender_certificate: FetchSenderCertificate,
+}
+
+#[async_trait(?Send)]
+impl SenderCertificateSource for &FfiSenderCertificateSourceStruct {
+    async fn fetch_sender_certificate(
+        &mut self,
+        ctx: Context,
+    ) -> Result<SenderCertificate, SignalProtocolError> {
+        let ctx = ctx.unwrap_or(std::ptr::null_mut());
+        let mut certificate = std::ptr::null_mut();
+        let result = (self.fetch_sender_certificate)(self.ctx, &mut certificate, ctx);
+
+        if let Some(error) = CallbackError::check(result) {
+            return Err(SignalProtocolError::ApplicationCallbackError(
+                "fetch_sender_certificate",
+                Box::new(error),
+            ));
+        }
+
+        if certificate.is_null() {
+            return Err(SignalProtocolError::InternalError("No sender certificate"));
+        }
+
+        let certificate = unsafe { Box::from_raw(certificate) };
+
+        Ok(*certificate)
+    }
+}
@@ -0,0 +1,102 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! A runtime registry of every entry point generated by `bridge_fn`, `bridge_handle!`, and their
+//! variants.
+//!
+//! Wrappers can compare this against their generated bindings at startup to catch a mismatched
+//! native library early, rather than failing on the first call to a missing or changed function.
+//! The registry is reported as a JSON array:
+//!
+//! ```json
+//! [{"bridge":"node","name":"Rng_NewSeeded","args":["Buffer"],"result":"Rng","async":false}]
+//! ```
+//!
+//! Argument and result types are spelled the way each bridge's bindings spell them: C types as
+//! written by cbindgen (including output parameters, which come first), Java types as in
+//! `Native.java`, and TypeScript types as in `Native.d.ts`.
+//!
+//! The registry is only built with the "introspection" feature, which the Node bridge always
+//! enables. Without it, `Native_ListFunctions` fails.
+
+use libsignal_bridge_macros::*;
+use libsignal_protocol::error::Result;
+#[cfg(feature = "introspection")]
+use std::fmt::Write;
+
+#[cfg(feature = "introspection")]
+use crate::support::*;
+use crate::*;
+
+/// Describes a single generated entry point.
+pub struct BridgeFunctionInfo {
+    /// The bridge exposing the entry point: "ffi", "jni", or "node".
+    pub bridge: &'static str,
+    /// The name as seen from the bridge: the C symbol, the Java method, or the Node export.
+    pub name: &'static str,
+    /// The types of the entry point's parameters, in the bridge's language.
+    pub args: &'static [&'static str],
+    /// The result type of the entry point, in the bridge's language.
+    pub result: &'static str,
+    /// Whether the entry point completes asynchronously.
+    pub is_async: bool,
+}
+
+/// Filled in by `bridge_fn`, `bridge_handle!`, and their variants.
+///
+/// Not intended to be extended directly.
+#[cfg(feature = "introspection")]
+#[linkme::distributed_slice]
+pub static BRIDGE_FUNCTIONS: [BridgeFunctionInfo] = [..];
+
+/// Returns every registered entry point, sorted by bridge and then by name.
+#[cfg(feature = "introspection")]
+pub fn registered_functions() -> Vec<&'static BridgeFunctionInfo> {
+    let mut functions: Vec<_> = BRIDGE_FUNCTIONS.iter().collect();
+    functions.sort_by_key(|info| (info.bridge, info.name));
+    functions
+}
+
+#[cfg(feature = "introspection")]
+fn functions_to_json(functions: &[&BridgeFunctionInfo]) -> String {
+    let mut out = String::new();
+    out.push('[');
+    for (i, info) in functions.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str("{\"bridge\":");
+        write_json_string(&mut out, info.bridge);
+        out.push_str(",\"name\":");
+        write_json_string(&mut out, info.name);
+        out.push_str(",\"args\":[");
+        for (j, arg) in info.args.iter().enumerate() {
+            if j > 0 {
+                out.push(',');
+            }
+            write_json_string(&mut out, arg);
+        }
+        out.push_str("],\"result\":");
+        write_json_string(&mut out, info.result);
+        write!(out, ",\"async\":{}}}", info.is_async).expect("writing to a String cannot fail");
+    }
+    out.push(']');
+    out
+}
+
+#[cfg(feature = "introspection")]
+#[bridge_fn(ffi = "list_functions")]
+fn Native_ListFunctions() -> Result<String> {
+    Ok(functions_to_json(&registered_functions()))
+}
+
+#[cfg(not(feature = "introspection"))]
+#[bridge_fn(ffi = "list_functions")]
+fn Native_ListFunctions() -> Result<String> {
+    Err(libsignal_protocol::SignalProtocolError::InvalidState(
+        "Native_ListFunctions",
+        "built without the \"introspection\" feature".to_string(),
+    ))
+}
@@ -162,6 +162,21 @@ impl<'a> SimpleArgTypeInfo<'a> for u64 {
     }
 }
 
+/// Rejects negative values and values too far in the future to be milliseconds since the epoch.
+impl<'a> SimpleArgTypeInfo<'a> for crate::timestamp::Timestamp {
+    type ArgType = jlong;
+    fn convert_from(_env: &JNIEnv, foreign: jlong) -> SignalJniResult<Self> {
+        Ok(Self::from_epoch_millis(jlong_to_u64(foreign)?)?)
+    }
+}
+
+impl<'a> SimpleArgTypeInfo<'a> for bool {
+    type ArgType = jboolean;
+    fn convert_from(_env: &JNIEnv, foreign: jboolean) -> SignalJniResult<Self> {
+        Ok(foreign != JNI_FALSE)
+    }
+}
+
 /// Supports all valid byte values `0..=255`.
 impl<'a> SimpleArgTypeInfo<'a> for u8 {
     type ArgType = jint;
@@ -192,6 +207,8 @@ impl<'storage, 'context: 'storage> ArgTypeInfo<'storage, 'context> for &'storage
     type ArgType = jbyteArray;
     type StoredType = AutoArray<'context, 'context, jbyte>;
     fn borrow(env: &'context JNIEnv, foreign: Self::ArgType) -> SignalJniResult<Self::StoredType> {
+        // Check the length first, so that an oversized array is never copied out of the JVM.
+        crate::limits::check_buffer_len(env.get_array_length(foreign)? as usize)?;
         Ok(env.get_byte_array_elements(foreign, ReleaseMode::NoCopyBack)?)
     }
     fn load_from(
@@ -241,6 +258,32 @@ impl<'storage, 'context: 'storage> ArgTypeInfo<'storage, 'context> for &'storage
     }
 }
 
+/// Converts a `long[]` of native handles, each of which must be non-zero.
+impl<'storage, 'context: 'storage, T> ArgTypeInfo<'storage, 'context> for Vec<&'storage T>
+where
+    &'storage T: SimpleArgTypeInfo<'storage, ArgType = ObjectHandle>,
+{
+    type ArgType = jlongArray;
+    type StoredType = Vec<ObjectHandle>;
+    fn borrow(env: &'context JNIEnv, foreign: Self::ArgType) -> SignalJniResult<Self::StoredType> {
+        if foreign.is_null() {
+            return Err(SignalJniError::NullHandle);
+        }
+        let mut handles = vec![0; env.get_array_length(foreign)? as usize];
+        env.get_long_array_region(foreign, 0, &mut handles)?;
+        Ok(handles)
+    }
+    fn load_from(
+        env: &JNIEnv,
+        stored: &'storage mut Self::StoredType,
+    ) -> SignalJniResult<Vec<&'storage T>> {
+        stored
+            .iter()
+            .map(|&handle| <&'storage T>::convert_from(env, handle))
+            .collect()
+    }
+}
+
 macro_rules! store {
     ($name:ident) => {
         paste! {
@@ -281,6 +324,7 @@ impl<'a> SimpleArgTypeInfo<'a> for Context {
 
 store!(IdentityKeyStore);
 store!(PreKeyStore);
+store!(SenderCertificateSource);
 store!(SenderKeyStore);
 store!(SessionStore);
 store!(SignedPreKeyStore);
@@ -333,6 +377,14 @@ impl ResultTypeInfo for u64 {
     }
 }
 
+/// Produces milliseconds since the epoch, like `System.currentTimeMillis()`.
+impl ResultTypeInfo for crate::timestamp::Timestamp {
+    type ResultType = jlong;
+    fn convert_into(self, env: &JNIEnv) -> SignalJniResult<Self::ResultType> {
+        self.epoch_millis().convert_into(env)
+    }
+}
+
 impl ResultTypeInfo for String {
     type ResultType = jstring;
     fn convert_into(self, env: &JNIEnv) -> SignalJniResult<Self::ResultType> {
@@ -385,6 +437,27 @@ impl<T: ResultTypeInfo> ResultTypeInfo for Result<T, signal_crypto::Error> {
     }
 }
 
+impl<T: ResultTypeInfo> ResultTypeInfo for Result<T, message_backup::Error> {
+    type ResultType = T::ResultType;
+    fn convert_into(self, env: &JNIEnv) -> SignalJniResult<Self::ResultType> {
+        T::convert_into(self?, env)
+    }
+}
+
+impl<T: ResultTypeInfo> ResultTypeInfo for Result<T, keytrans::Error> {
+    type ResultType = T::ResultType;
+    fn convert_into(self, env: &JNIEnv) -> SignalJniResult<Self::ResultType> {
+        T::convert_into(self?, env)
+    }
+}
+
+impl<T: ResultTypeInfo> ResultTypeInfo for Result<T, registration::Error> {
+    type ResultType = T::ResultType;
+    fn convert_into(self, env: &JNIEnv) -> SignalJniResult<Self::ResultType> {
+        T::convert_into(self?, env)
+    }
+}
+
 impl<T: ResultTypeInfo> ResultTypeInfo for SignalJniResult<T> {
     type ResultType = T::ResultType;
     fn convert_into(self, env: &JNIEnv) -> SignalJniResult<Self::ResultType> {
@@ -402,6 +475,42 @@ where
     }
 }
 
+/// Produces a `byte[][]`.
+impl ResultTypeInfo for Vec<Vec<u8>> {
+    type ResultType = jobjectArray;
+    fn convert_into(self, env: &JNIEnv) -> SignalJniResult<Self::ResultType> {
+        let length = jint::try_from(self.len())
+            .map_err(|_| SignalJniError::IntegerOverflow(format!("{} to jint", self.len())))?;
+        let array = env.new_object_array(length, jni_signature!([byte]), JObject::null())?;
+        for (i, buffer) in self.into_iter().enumerate() {
+            let buffer = JObject::from(env.byte_array_from_slice(&buffer)?);
+            env.set_object_array_element(array, i as jint, buffer)?;
+            // Don't run out of local references for large batches.
+            env.delete_local_ref(buffer)?;
+        }
+        Ok(array)
+    }
+}
+
+/// Produces a `long[]` of native handles, one for each value.
+impl<T> ResultTypeInfo for Vec<T>
+where
+    T: crate::support::BridgeHandle + ResultTypeInfo<ResultType = ObjectHandle>,
+{
+    type ResultType = jlongArray;
+    fn convert_into(self, env: &JNIEnv) -> SignalJniResult<Self::ResultType> {
+        let length = jint::try_from(self.len())
+            .map_err(|_| SignalJniError::IntegerOverflow(format!("{} to jint", self.len())))?;
+        let handles = self
+            .into_iter()
+            .map(|value| value.convert_into(env))
+            .collect::<SignalJniResult<Vec<_>>>()?;
+        let array = env.new_long_array(length)?;
+        env.set_long_array_region(array, 0, &handles)?;
+        Ok(array)
+    }
+}
+
 impl ResultTypeInfo for Option<jobject> {
     type ResultType = jobject;
     fn convert_into(self, _env: &jni::JNIEnv) -> SignalJniResult<Self::ResultType> {
@@ -409,6 +518,77 @@ impl ResultTypeInfo for Option<jobject> {
     }
 }
 
+/// Types that can be fields of a `#[derive(BridgeStruct)]` type, stored in a Java field.
+pub trait StructFieldTypeInfo<'a>: SimpleArgTypeInfo<'a> + ResultTypeInfo {
+    /// The JNI type signature of the Java field (e.g. `"I"`).
+    const SIGNATURE: &'static str;
+    /// Unwraps the value of a Java field.
+    fn arg_from_value(value: JValue<'a>) -> SignalJniResult<Self::ArgType>;
+    /// Wraps a converted result, e.g. to pass it to a constructor.
+    fn result_into_value(result: Self::ResultType) -> JValue<'a>;
+}
+
+impl<'a> StructFieldTypeInfo<'a> for bool {
+    const SIGNATURE: &'static str = jni_signature!(boolean);
+    fn arg_from_value(value: JValue<'a>) -> SignalJniResult<jboolean> {
+        Ok(value.z()?.into())
+    }
+    fn result_into_value(result: jboolean) -> JValue<'a> {
+        JValue::Bool(result)
+    }
+}
+
+impl<'a> StructFieldTypeInfo<'a> for u32 {
+    const SIGNATURE: &'static str = jni_signature!(int);
+    fn arg_from_value(value: JValue<'a>) -> SignalJniResult<jint> {
+        Ok(value.i()?)
+    }
+    fn result_into_value(result: jint) -> JValue<'a> {
+        JValue::Int(result)
+    }
+}
+
+impl<'a> StructFieldTypeInfo<'a> for u64 {
+    const SIGNATURE: &'static str = jni_signature!(long);
+    fn arg_from_value(value: JValue<'a>) -> SignalJniResult<jlong> {
+        Ok(value.j()?)
+    }
+    fn result_into_value(result: jlong) -> JValue<'a> {
+        JValue::Long(result)
+    }
+}
+
+impl<'a> StructFieldTypeInfo<'a> for String {
+    const SIGNATURE: &'static str = jni_signature!(java.lang.String);
+    fn arg_from_value(value: JValue<'a>) -> SignalJniResult<JString<'a>> {
+        Ok(value.l()?.into())
+    }
+    fn result_into_value(result: jstring) -> JValue<'a> {
+        JValue::Object(result.into())
+    }
+}
+
+/// Java `null` is `None`.
+impl<'a> StructFieldTypeInfo<'a> for Option<String> {
+    const SIGNATURE: &'static str = jni_signature!(java.lang.String);
+    fn arg_from_value(value: JValue<'a>) -> SignalJniResult<JString<'a>> {
+        Ok(value.l()?.into())
+    }
+    fn result_into_value(result: jstring) -> JValue<'a> {
+        JValue::Object(result.into())
+    }
+}
+
+/// Loads the field `name` of a `#[derive(BridgeStruct)]` argument.
+pub fn get_struct_field<'a, T: StructFieldTypeInfo<'a>>(
+    env: &JNIEnv<'a>,
+    object: JObject<'a>,
+    name: &str,
+) -> SignalJniResult<T> {
+    let value = env.get_field(object, name, T::SIGNATURE)?;
+    T::convert_from(env, T::arg_from_value(value)?)
+}
+
 impl crate::support::Env for &'_ JNIEnv<'_> {
     type Buffer = SignalJniResult<jbyteArray>;
     fn buffer<'a, T: Into<Cow<'a, [u8]>>>(self, input: T) -> Self::Buffer {
@@ -419,6 +599,68 @@ impl crate::support::Env for &'_ JNIEnv<'_> {
 /// Implementation of [`bridge_handle`](crate::support::bridge_handle) for JNI.
 macro_rules! jni_bridge_handle {
     ( $typ:ty as false $(, $($_:tt)*)? ) => {};
+    ( $typ:ty as $jni_name:ident, shared = true ) => {
+        // Shared handles point to an `Arc<$typ>` rather than directly to the value.
+        impl<'a> jni::SimpleArgTypeInfo<'a> for &$typ {
+            type ArgType = jni::ObjectHandle;
+            fn convert_from(
+                _env: &jni::JNIEnv,
+                foreign: Self::ArgType,
+            ) -> jni::SignalJniResult<Self> {
+                let shared = unsafe { jni::native_handle_cast::<std::sync::Arc<$typ>>(foreign) }?;
+                Ok(&**shared)
+            }
+        }
+        impl<'a> jni::SimpleArgTypeInfo<'a> for Option<&$typ> {
+            type ArgType = jni::ObjectHandle;
+            fn convert_from(
+                env: &jni::JNIEnv,
+                foreign: Self::ArgType,
+            ) -> jni::SignalJniResult<Self> {
+                if foreign == 0 {
+                    Ok(None)
+                } else {
+                    <&$typ>::convert_from(env, foreign).map(Some)
+                }
+            }
+        }
+        impl jni::ResultTypeInfo for $typ {
+            type ResultType = jni::ObjectHandle;
+            fn convert_into(self, _env: &jni::JNIEnv) -> jni::SignalJniResult<Self::ResultType> {
+                jni::box_object(Ok(std::sync::Arc::new(self)))
+            }
+        }
+        impl jni::ResultTypeInfo for Option<$typ> {
+            type ResultType = jni::ObjectHandle;
+            fn convert_into(self, env: &jni::JNIEnv) -> jni::SignalJniResult<Self::ResultType> {
+                match self {
+                    Some(obj) => obj.convert_into(env),
+                    None => Ok(0),
+                }
+            }
+        }
+        jni_bridge_destroy!($typ as $jni_name, shared = true);
+        paste! {
+            #[no_mangle]
+            pub unsafe extern "C" fn [<Java_org_signal_client_internal_Native_ $jni_name _1Clone>](
+                env: jni::JNIEnv,
+                _class: jni::JClass,
+                handle: jni::ObjectHandle,
+            ) -> jni::ObjectHandle {
+                jni::run_ffi_safe(&env, concat!(stringify!($jni_name), "_Clone"), || {
+                    let shared = jni::native_handle_cast::<std::sync::Arc<$typ>>(handle)?;
+                    jni::box_object(Ok(std::sync::Arc::clone(shared)))
+                })
+            }
+
+            bridge_registry_entry!(
+                [<signal_bridge_info_jni_ $jni_name _Clone>]: "jni",
+                concat!(stringify!($jni_name), "_Clone"),
+                ["long"] -> "long",
+                false
+            );
+        }
+    };
     ( $typ:ty as $jni_name:ident ) => {
         impl<'a> jni::SimpleArgTypeInfo<'a> for &$typ {
             type ArgType = jni::ObjectHandle;
@@ -468,9 +710,9 @@ macro_rules! jni_bridge_handle {
         }
         jni_bridge_destroy!($typ as $jni_name);
     };
-    ( $typ:ty ) => {
+    ( $typ:ty $(, shared = $_:tt)? ) => {
         paste! {
-            jni_bridge_handle!($typ as $typ);
+            jni_bridge_handle!($typ as $typ $(, shared = $_)?);
         }
     };
 }
@@ -504,6 +746,9 @@ trivial!(());
 /// behavior for references is to assume they're opaque handles to Rust values; the default
 /// behavior for `&mut dyn Foo` is to assume there's a type called `jni::JavaFoo`.
 macro_rules! jni_arg_type {
+    (bool) => {
+        jni::jboolean
+    };
     (u8) => {
         // Note: not a jbyte. It's better to preserve the signedness here.
         jni::jint
@@ -517,6 +762,9 @@ macro_rules! jni_arg_type {
     (u64) => {
         jni::jlong
     };
+    (Timestamp) => {
+        jni::jlong
+    };
     (String) => {
         jni::JString
     };
@@ -547,6 +795,9 @@ macro_rules! jni_arg_type {
     (Option<& $typ:ty>) => {
         jni::ObjectHandle
     };
+    (Vec<& $typ:ty>) => {
+        jni::jlongArray
+    };
 }
 
 /// Syntactically translates `bridge_fn` result types to JNI types for `cbindgen` and
@@ -588,6 +839,9 @@ macro_rules! jni_result_type {
     (u64) => {
         jni::jlong
     };
+    (Timestamp) => {
+        jni::jlong
+    };
     (Option<u32>) => {
         jni::jint
     };
@@ -606,6 +860,12 @@ macro_rules! jni_result_type {
     (Vec<u8>) => {
         jni::jbyteArray
     };
+    (Vec<Vec<u8>>) => {
+        jni::jobjectArray
+    };
+    (Vec<$typ:ty>) => {
+        jni::jlongArray
+    };
     ( $typ:ty ) => {
         jni::ObjectHandle
     };
@@ -8,10 +8,15 @@ use jni::{JNIEnv, JavaVM};
 use std::fmt;
 
 use device_transfer::Error as DeviceTransferError;
+use keytrans::Error as KeyTransparencyError;
 use libsignal_protocol::*;
+use message_backup::Error as MessageBackupError;
+use registration::Error as RegistrationError;
 use signal_crypto::Error as SignalCryptoError;
 
 use super::*;
+use crate::limits::BufferTooLarge;
+use crate::support::describe_panic;
 
 /// The top-level error type for when something goes wrong.
 #[derive(Debug)]
@@ -19,12 +24,20 @@ pub enum SignalJniError {
     Signal(SignalProtocolError),
     DeviceTransfer(DeviceTransferError),
     SignalCrypto(SignalCryptoError),
+    MessageBackup(MessageBackupError),
+    KeyTransparency(KeyTransparencyError),
+    Registration(RegistrationError),
     Jni(jni::errors::Error),
     BadJniParameter(&'static str),
     UnexpectedJniResultType(&'static str, &'static str),
     NullHandle,
     IntegerOverflow(String),
-    UnexpectedPanic(std::boxed::Box<dyn std::any::Any + std::marker::Send>),
+    BufferTooLarge(BufferTooLarge),
+    /// A panic in the named entry point.
+    UnexpectedPanic(
+        &'static str,
+        std::boxed::Box<dyn std::any::Any + std::marker::Send>,
+    ),
 }
 
 impl fmt::Display for SignalJniError {
@@ -33,6 +46,9 @@ impl fmt::Display for SignalJniError {
             SignalJniError::Signal(s) => write!(f, "{}", s),
             SignalJniError::DeviceTransfer(s) => write!(f, "{}", s),
             SignalJniError::SignalCrypto(s) => write!(f, "{}", s),
+            SignalJniError::MessageBackup(s) => write!(f, "{}", s),
+            SignalJniError::KeyTransparency(s) => write!(f, "{}", s),
+            SignalJniError::Registration(s) => write!(f, "{}", s),
             SignalJniError::Jni(s) => write!(f, "JNI error {}", s),
             SignalJniError::NullHandle => write!(f, "null handle"),
             SignalJniError::BadJniParameter(m) => write!(f, "bad parameter type {}", m),
@@ -42,10 +58,10 @@ impl fmt::Display for SignalJniError {
             SignalJniError::IntegerOverflow(m) => {
                 write!(f, "integer overflow during conversion of {}", m)
             }
-            SignalJniError::UnexpectedPanic(e) => match e.downcast_ref::<&'static str>() {
-                Some(s) => write!(f, "unexpected panic: {}", s),
-                None => write!(f, "unknown unexpected panic"),
-            },
+            SignalJniError::BufferTooLarge(e) => write!(f, "{}", e),
+            SignalJniError::UnexpectedPanic(function, e) => {
+                write!(f, "unexpected panic in {}: {}", function, describe_panic(e))
+            }
         }
     }
 }
@@ -68,6 +84,30 @@ impl From<SignalCryptoError> for SignalJniError {
     }
 }
 
+impl From<MessageBackupError> for SignalJniError {
+    fn from(e: MessageBackupError) -> SignalJniError {
+        SignalJniError::MessageBackup(e)
+    }
+}
+
+impl From<KeyTransparencyError> for SignalJniError {
+    fn from(e: KeyTransparencyError) -> SignalJniError {
+        SignalJniError::KeyTransparency(e)
+    }
+}
+
+impl From<RegistrationError> for SignalJniError {
+    fn from(e: RegistrationError) -> SignalJniError {
+        SignalJniError::Registration(e)
+    }
+}
+
+impl From<BufferTooLarge> for SignalJniError {
+    fn from(e: BufferTooLarge) -> SignalJniError {
+        SignalJniError::BufferTooLarge(e)
+    }
+}
+
 impl From<jni::errors::Error> for SignalJniError {
     fn from(e: jni::errors::Error) -> SignalJniError {
         SignalJniError::Jni(e)
@@ -117,6 +157,11 @@ impl ThrownException {
         })
     }
 
+    /// Gets the global reference that keeps the exception alive.
+    pub(crate) fn global_ref(&self) -> &GlobalRef {
+        &self.exception_ref
+    }
+
     pub fn class_name(&self, env: &JNIEnv) -> Result<String, SignalJniError> {
         let class_type = env.get_object_class(self.exception_ref.as_obj())?;
         let class_name = call_method_checked(
@@ -5,17 +5,22 @@
 
 extern crate jni_crate as jni;
 
-use jni::objects::{JThrowable, JValue};
-use jni::sys::jobject;
+use jni::objects::{GlobalRef, JThrowable, JValue};
 
 use device_transfer::Error as DeviceTransferError;
+use keytrans::Error as KeyTransparencyError;
 use libsignal_protocol::*;
+use message_backup::Error as MessageBackupError;
+use registration::Error as RegistrationError;
 use signal_crypto::Error as SignalCryptoError;
+use std::cell::RefCell;
 use std::convert::TryFrom;
 use std::error::Error;
 
 pub(crate) use jni::objects::{JClass, JObject, JString};
-pub(crate) use jni::sys::{jboolean, jbyteArray, jint, jlong, jstring};
+pub(crate) use jni::sys::{
+    jboolean, jbyteArray, jint, jlong, jlongArray, jobject, jobjectArray, jstring,
+};
 pub(crate) use jni::JNIEnv;
 
 /// Converts a function signature to a JNI signature string.
@@ -69,12 +74,200 @@ pub use crate::support::expect_ready;
 /// The type of boxed Rust values, as surfaced in JavaScript.
 pub type ObjectHandle = jlong;
 
+thread_local! {
+    /// The most recent exception thrown by a Java callback during the current `Native` call.
+    ///
+    /// See [`run_ffi_safe`] and [`call_method_checked`].
+    static LAST_CALLBACK_EXCEPTION: RefCell<Option<GlobalRef>> = RefCell::new(None);
+}
+
+/// Creates an `InternalError` for a panic in the `Native` method `function`.
+fn new_internal_error<'a>(
+    env: &JNIEnv<'a>,
+    message: &str,
+    function: &str,
+) -> jni::errors::Result<JObject<'a>> {
+    let args: [JValue; 2] = [
+        env.new_string(message)?.into(),
+        env.new_string(function)?.into(),
+    ];
+    env.new_object(
+        "org/whispersystems/libsignal/InternalError",
+        jni_signature!((java.lang.String, java.lang.String) -> void),
+        &args,
+    )
+}
+
+/// Creates a Java `SignalProtocolAddress` equal to `address`.
+fn new_protocol_address<'a>(
+    env: &JNIEnv<'a>,
+    address: &ProtocolAddress,
+) -> jni::errors::Result<JObject<'a>> {
+    let args: [JValue; 2] = [
+        env.new_string(address.name())?.into(),
+        JValue::from(address.device_id() as jint),
+    ];
+    env.new_object(
+        "org/whispersystems/libsignal/SignalProtocolAddress",
+        jni_signature!((java.lang.String, int) -> void),
+        &args,
+    )
+}
+
+/// Creates an `InvalidRegistrationIdException` carrying `address` and `registration_id`.
+fn new_invalid_registration_id_exception<'a>(
+    env: &JNIEnv<'a>,
+    message: &str,
+    address: &ProtocolAddress,
+    registration_id: u32,
+) -> jni::errors::Result<JObject<'a>> {
+    let args: [JValue; 3] = [
+        new_protocol_address(env, address)?.into(),
+        JValue::from(registration_id as jint),
+        env.new_string(message)?.into(),
+    ];
+    env.new_object(
+        "org/whispersystems/libsignal/InvalidRegistrationIdException",
+        jni_signature!((
+            org.whispersystems.libsignal.SignalProtocolAddress,
+            int,
+            java.lang.String,
+        ) -> void),
+        &args,
+    )
+}
+
+/// Creates a `RateLimitedException`, using a retry delay of -1 if the server didn't give one.
+fn new_rate_limited_exception<'a>(
+    env: &JNIEnv<'a>,
+    message: &str,
+    retry_after_seconds: Option<u64>,
+) -> jni::errors::Result<JObject<'a>> {
+    let retry_after_seconds = retry_after_seconds
+        .and_then(|seconds| jlong::try_from(seconds).ok())
+        .unwrap_or(-1);
+    let args: [JValue; 2] = [
+        env.new_string(message)?.into(),
+        JValue::from(retry_after_seconds),
+    ];
+    env.new_object(
+        "org/signal/libsignal/registration/RateLimitedException",
+        jni_signature!((java.lang.String, long) -> void),
+        &args,
+    )
+}
+
+/// Creates an `InvalidSenderKeySessionException` for the group and sender in `name`.
+fn new_invalid_sender_key_session_exception<'a>(
+    env: &JNIEnv<'a>,
+    message: &str,
+    name: &SenderKeyName,
+) -> jni::errors::Result<JObject<'a>> {
+    let group_id = name.group_id().expect("infallible");
+    let sender = name.sender().expect("infallible");
+    let args: [JValue; 3] = [
+        env.new_string(group_id)?.into(),
+        new_protocol_address(env, &sender)?.into(),
+        env.new_string(message)?.into(),
+    ];
+    env.new_object(
+        "org/whispersystems/libsignal/groups/InvalidSenderKeySessionException",
+        jni_signature!((
+            java.lang.String,
+            org.whispersystems.libsignal.SignalProtocolAddress,
+            java.lang.String,
+        ) -> void),
+        &args,
+    )
+}
+
+/// Creates a `DuplicateMessageException` carrying the chain position of the duplicate.
+fn new_duplicate_message_exception<'a>(
+    env: &JNIEnv<'a>,
+    message: &str,
+    chain_index: u32,
+    counter: u32,
+) -> jni::errors::Result<JObject<'a>> {
+    let args: [JValue; 3] = [
+        env.new_string(message)?.into(),
+        JValue::from(chain_index as jint),
+        JValue::from(counter as jint),
+    ];
+    env.new_object(
+        "org/whispersystems/libsignal/DuplicateMessageException",
+        jni_signature!((java.lang.String, int, int) -> void),
+        &args,
+    )
+}
+
+/// Creates an exception of class `class_name` using its `(String)` constructor.
+fn new_exception<'a>(
+    env: &JNIEnv<'a>,
+    class_name: &str,
+    message: &str,
+) -> jni::errors::Result<JObject<'a>> {
+    let args: [JValue; 1] = [env.new_string(message)?.into()];
+    env.new_object(
+        class_name,
+        jni_signature!((java.lang.String) -> void),
+        &args,
+    )
+}
+
+/// Throws `throwable`, after making `cause` its cause if present.
+///
+/// `message` describes the error being thrown, and is only used for logging failures.
+fn throw_with_cause(
+    env: &JNIEnv,
+    throwable: jni::errors::Result<JObject>,
+    cause: Option<JObject>,
+    message: &str,
+) {
+    let throwable = match throwable {
+        Ok(throwable) => throwable,
+        Err(e) => {
+            log::error!("failed to create exception for {}: {}", message, e);
+            return;
+        }
+    };
+
+    if let Some(cause) = cause {
+        let result = env.call_method(
+            throwable,
+            "initCause",
+            jni_signature!((java.lang.Throwable) -> java.lang.Throwable),
+            &[cause.into()],
+        );
+        // initCause throws if the cause has already been set. Missing the cause isn't worth
+        // losing the exception over, so just log it.
+        if result.is_err() || env.exception_check().unwrap_or(true) {
+            let _ = env.exception_clear();
+            log::warn!(
+                "failed to attach callback exception as cause of {}",
+                message
+            );
+        }
+    }
+
+    if let Err(e) = env.throw(JThrowable::from(throwable)) {
+        log::error!("failed to throw exception for {}: {}", message, e);
+    }
+}
+
 /// Translates errors into Java exceptions.
 ///
 /// Exceptions thrown in callbacks will be rethrown; all other errors will be mapped to an
-/// appropriate Java exception class and thrown.
-fn throw_error(env: &JNIEnv, error: SignalJniError) {
-    // Handle special cases first.
+/// appropriate Java exception class and thrown. If a callback threw during the operation but its
+/// exception is no longer the error being reported, `callback_exception` becomes the `cause` of
+/// the mapped exception, so that the original stack trace is still available.
+fn throw_error(env: &JNIEnv, error: SignalJniError, callback_exception: Option<&GlobalRef>) {
+    // Keep any operation context in the message, but choose the exception by the underlying error.
+    let message = error.to_string();
+    let error = match error {
+        SignalJniError::Signal(e) => SignalJniError::Signal(e.into_root_cause()),
+        e => e,
+    };
+
     let error = match error {
         SignalJniError::Signal(SignalProtocolError::ApplicationCallbackError(
             callback,
@@ -97,46 +290,99 @@ fn throw_error(env: &JNIEnv, error: SignalJniError) {
                 callback, exception,
             ))
         }
+        e => e,
+    };
 
-        SignalJniError::Signal(SignalProtocolError::UntrustedIdentity(ref addr)) => {
-            let result = env.throw_new(
-                "org/whispersystems/libsignal/UntrustedIdentityException",
-                addr.name(),
-            );
-            if let Err(e) = result {
-                log::error!("failed to throw exception for {}: {}", error, e);
-            }
-            return;
-        }
+    let throwable = new_throwable(env, error, &message);
+    throw_with_cause(
+        env,
+        throwable,
+        callback_exception.map(GlobalRef::as_obj),
+        &message,
+    );
+}
+
+/// Creates the exception for `error`, which must already have had any context removed.
+fn new_throwable<'a>(
+    env: &JNIEnv<'a>,
+    error: SignalJniError,
+    message: &str,
+) -> jni::errors::Result<JObject<'a>> {
+    // Handle special cases first.
+    match error {
+        SignalJniError::Signal(SignalProtocolError::UntrustedIdentity(ref addr)) => new_exception(
+            env,
+            "org/whispersystems/libsignal/UntrustedIdentityException",
+            addr.name(),
+        ),
 
         SignalJniError::Signal(SignalProtocolError::FingerprintVersionMismatch(theirs, ours)) => {
-            let throwable = env.new_object(
+            env.new_object(
                 "org/whispersystems/libsignal/fingerprint/FingerprintVersionMismatchException",
                 jni_signature!((int, int) -> void),
                 &[JValue::from(theirs as jint), JValue::from(ours as jint)],
-            );
+            )
+        }
 
-            match throwable {
-                Err(e) => log::error!("failed to create exception for {}: {}", error, e),
-                Ok(throwable) => {
-                    let result = env.throw(JThrowable::from(throwable));
-                    if let Err(e) = result {
-                        log::error!("failed to throw exception for {}: {}", error, e);
-                    }
-                }
-            }
-            return;
+        SignalJniError::Signal(SignalProtocolError::InvalidRegistrationId(
+            ref address,
+            registration_id,
+        )) => new_invalid_registration_id_exception(env, message, address, registration_id),
+
+        SignalJniError::Signal(SignalProtocolError::InvalidSenderKeySession(ref name)) => {
+            new_invalid_sender_key_session_exception(env, message, name)
+        }
+
+        SignalJniError::Signal(SignalProtocolError::DuplicatedMessage(chain_index, counter)) => {
+            new_duplicate_message_exception(env, message, chain_index, counter)
         }
 
+        SignalJniError::Registration(RegistrationError::RateLimited {
+            retry_after_seconds,
+        }) => new_rate_limited_exception(env, message, retry_after_seconds),
+
+        SignalJniError::UnexpectedPanic(function, _) => new_internal_error(env, message, function),
+
+        ref e => new_exception(env, exception_class(e), message),
+    }
+}
+
+/// Creates the exception that would be thrown for `error`, without throwing it.
+///
+/// Used for results that report a failure for each element rather than failing as a whole.
+pub fn error_to_throwable<'a>(
+    env: &JNIEnv<'a>,
+    error: SignalJniError,
+) -> Result<JObject<'a>, SignalJniError> {
+    let message = error.to_string();
+    let error = match error {
+        SignalJniError::Signal(e) => SignalJniError::Signal(e.into_root_cause()),
         e => e,
     };
+    if let SignalJniError::Signal(SignalProtocolError::ApplicationCallbackError(_, exception)) =
+        &error
+    {
+        // Hand back the exception the callback threw, as throw_error would.
+        if let Some(exception) = Error::downcast_ref::<ThrownException>(&**exception) {
+            // The global reference goes away with `error`, so make a local one to return.
+            return Ok(
+                env.new_local_ref::<JObject>(JObject::from(exception.as_obj().into_inner()))?
+            );
+        }
+    }
+    Ok(new_throwable(env, error, &message)?)
+}
 
-    let exception_type = match error {
+/// Chooses the Java exception class for errors that don't need any special fields.
+fn exception_class(error: &SignalJniError) -> &'static str {
+    match error {
         SignalJniError::NullHandle => "java/lang/NullPointerException",
 
         SignalJniError::Signal(SignalProtocolError::InvalidState(_, _))
         | SignalJniError::Signal(SignalProtocolError::NoSenderKeyState)
         | SignalJniError::SignalCrypto(SignalCryptoError::InvalidState)
+        | SignalJniError::MessageBackup(MessageBackupError::InvalidState)
+        | SignalJniError::KeyTransparency(KeyTransparencyError::NotMonitored)
         | SignalJniError::Signal(SignalProtocolError::InvalidSessionStructure) => {
             "java/lang/IllegalStateException"
         }
@@ -144,13 +390,19 @@ fn throw_error(env: &JNIEnv, error: SignalJniError) {
         SignalJniError::Signal(SignalProtocolError::InvalidArgument(_))
         | SignalJniError::SignalCrypto(SignalCryptoError::UnknownAlgorithm(_, _))
         | SignalJniError::SignalCrypto(SignalCryptoError::InvalidInputSize)
-        | SignalJniError::SignalCrypto(SignalCryptoError::InvalidNonceSize) => {
+        | SignalJniError::SignalCrypto(SignalCryptoError::InvalidNonceSize(_, _))
+        | SignalJniError::SignalCrypto(SignalCryptoError::InvalidKdfParameters(_))
+        | SignalJniError::MessageBackup(MessageBackupError::InvalidRecord(_))
+        | SignalJniError::Registration(RegistrationError::InvalidSessionId)
+        | SignalJniError::Registration(RegistrationError::InvalidMasterKeySize(_)) => {
             "java/lang/IllegalArgumentException"
         }
 
-        SignalJniError::UnexpectedPanic(_)
-        | SignalJniError::BadJniParameter(_)
-        | SignalJniError::UnexpectedJniResultType(_, _) => "java/lang/AssertionError",
+        SignalJniError::BufferTooLarge(_) => "org/whispersystems/libsignal/BufferTooLargeException",
+
+        SignalJniError::BadJniParameter(_) | SignalJniError::UnexpectedJniResultType(_, _) => {
+            "java/lang/AssertionError"
+        }
 
         SignalJniError::IntegerOverflow(_)
         | SignalJniError::Jni(_)
@@ -167,7 +419,7 @@ fn throw_error(env: &JNIEnv, error: SignalJniError) {
             "java/lang/RuntimeException"
         }
 
-        SignalJniError::Signal(SignalProtocolError::DuplicatedMessage(_, _)) => {
+        SignalJniError::Signal(SignalProtocolError::SealedSenderReplay) => {
             "org/whispersystems/libsignal/DuplicateMessageException"
         }
 
@@ -181,7 +433,9 @@ fn throw_error(env: &JNIEnv, error: SignalJniError) {
         | SignalJniError::Signal(SignalProtocolError::SignatureValidationFailed)
         | SignalJniError::Signal(SignalProtocolError::BadKeyType(_))
         | SignalJniError::Signal(SignalProtocolError::BadKeyLength(_, _))
-        | SignalJniError::SignalCrypto(SignalCryptoError::InvalidKeySize) => {
+        | SignalJniError::SignalCrypto(SignalCryptoError::InvalidKeySize(_, _))
+        | SignalJniError::SignalCrypto(SignalCryptoError::InvalidKeyEncoding)
+        | SignalJniError::MessageBackup(MessageBackupError::InvalidKeySize(_, _)) => {
             "org/whispersystems/libsignal/InvalidKeyException"
         }
 
@@ -193,12 +447,30 @@ fn throw_error(env: &JNIEnv, error: SignalJniError) {
         | SignalJniError::Signal(SignalProtocolError::CiphertextMessageTooShort(_))
         | SignalJniError::Signal(SignalProtocolError::InvalidCiphertext)
         | SignalJniError::Signal(SignalProtocolError::InvalidProtobufEncoding)
+        | SignalJniError::Signal(SignalProtocolError::RecordLimitExceeded(_, _))
         | SignalJniError::Signal(SignalProtocolError::ProtobufDecodingError(_))
         | SignalJniError::Signal(SignalProtocolError::InvalidSealedSenderMessage(_))
-        | SignalJniError::SignalCrypto(SignalCryptoError::InvalidTag) => {
+        | SignalJniError::SignalCrypto(SignalCryptoError::InvalidTag)
+        | SignalJniError::MessageBackup(_) => {
             "org/whispersystems/libsignal/InvalidMessageException"
         }
 
+        SignalJniError::SignalCrypto(SignalCryptoError::InvalidWrappedKey) => {
+            "org/signal/libsignal/crypto/InvalidWrappedKeyException"
+        }
+
+        SignalJniError::KeyTransparency(_) => {
+            "org/signal/libsignal/keytrans/VerificationFailedException"
+        }
+
+        SignalJniError::Registration(RegistrationError::SessionNotFound) => {
+            "org/signal/libsignal/registration/SessionNotFoundException"
+        }
+
+        SignalJniError::Registration(_) => {
+            "org/signal/libsignal/registration/RegistrationException"
+        }
+
         SignalJniError::Signal(SignalProtocolError::UnrecognizedCiphertextVersion(_))
         | SignalJniError::Signal(SignalProtocolError::UnrecognizedMessageVersion(_))
         | SignalJniError::Signal(SignalProtocolError::UnknownSealedSenderVersion(_)) => {
@@ -213,19 +485,28 @@ fn throw_error(env: &JNIEnv, error: SignalJniError) {
             "org/signal/libsignal/metadata/SelfSendException"
         }
 
+        SignalJniError::Signal(SignalProtocolError::SealedSenderCertificateExpired(_)) => {
+            "org/signal/libsignal/metadata/certificate/ExpiredCertificateException"
+        }
+
         SignalJniError::Signal(SignalProtocolError::UntrustedIdentity(_))
-        | SignalJniError::Signal(SignalProtocolError::FingerprintVersionMismatch(_, _)) => {
+        | SignalJniError::Signal(SignalProtocolError::FingerprintVersionMismatch(_, _))
+        | SignalJniError::Signal(SignalProtocolError::InvalidRegistrationId(_, _))
+        | SignalJniError::Signal(SignalProtocolError::InvalidSenderKeySession(_))
+        | SignalJniError::Signal(SignalProtocolError::DuplicatedMessage(_, _)) => {
             unreachable!("already handled in prior match")
         }
 
+        SignalJniError::Signal(SignalProtocolError::WithContext(_, _)) => {
+            unreachable!("context already removed")
+        }
+
+        SignalJniError::UnexpectedPanic(_, _) => unreachable!("already handled in prior match"),
+
         SignalJniError::Signal(SignalProtocolError::FingerprintIdentifierMismatch)
         | SignalJniError::Signal(SignalProtocolError::FingerprintParsingError) => {
             "org/whispersystems/libsignal/fingerprint/FingerprintParsingException"
         }
-    };
-
-    if let Err(e) = env.throw_new(exception_type, error.to_string()) {
-        log::error!("failed to throw exception for {}: {}", error, e);
     }
 }
 
@@ -262,21 +543,36 @@ impl JniDummyValue for () {
     fn dummy_value() -> Self {}
 }
 
+/// Runs `f`, converting both errors and panics into Java exceptions.
+///
+/// `function` is the name of the `Native` method, used to describe any panic. If a callback threw
+/// an exception while `f` was running, that exception will be the cause of the one thrown here.
 pub fn run_ffi_safe<F: FnOnce() -> Result<R, SignalJniError> + std::panic::UnwindSafe, R>(
     env: &JNIEnv,
+    function: &'static str,
     f: F,
 ) -> R
 where
     R: JniDummyValue,
 {
-    match std::panic::catch_unwind(f) {
+    // A callback may itself call into Rust, so save the outer call's exception rather than
+    // clobbering it.
+    let outer_exception = LAST_CALLBACK_EXCEPTION.with(|slot| slot.borrow_mut().take());
+    let result = std::panic::catch_unwind(f);
+    let callback_exception = LAST_CALLBACK_EXCEPTION.with(|slot| slot.replace(outer_exception));
+
+    match result {
         Ok(Ok(r)) => r,
         Ok(Err(e)) => {
-            throw_error(env, e);
+            throw_error(env, e, callback_exception.as_ref());
             R::dummy_value()
         }
         Err(r) => {
-            throw_error(env, SignalJniError::UnexpectedPanic(r));
+            throw_error(
+                env,
+                SignalJniError::UnexpectedPanic(function, r),
+                callback_exception.as_ref(),
+            );
             R::dummy_value()
         }
     }
@@ -337,6 +633,9 @@ pub fn to_jbytearray<T: AsRef<[u8]>>(
 /// Calls a method and translates any thrown exceptions to
 /// [`SignalProtocolError::ApplicationCallbackError`].
 ///
+/// The exception is also remembered for the rest of the `Native` call, so that it can still be
+/// reported as a cause if the callback's error is replaced by another one.
+///
 /// Wraps [`JNIEnv::call_method`]; all arguments are the same.
 pub fn call_method_checked<'a>(
     env: &JNIEnv<'a>,
@@ -355,11 +654,11 @@ pub fn call_method_checked<'a>(
     } else {
         env.exception_clear()?;
 
-        Err(SignalProtocolError::ApplicationCallbackError(
-            fn_name,
-            Box::new(ThrownException::new(env, throwable)?),
-        )
-        .into())
+        let exception = ThrownException::new(env, throwable)?;
+        LAST_CALLBACK_EXCEPTION
+            .with(|slot| *slot.borrow_mut() = Some(exception.global_ref().clone()));
+
+        Err(SignalProtocolError::ApplicationCallbackError(fn_name, Box::new(exception)).into())
     }
 }
 
@@ -506,10 +805,68 @@ macro_rules! jni_bridge_destroy {
                     let _boxed_value = Box::from_raw(handle as *mut $typ);
                 }
             }
+
+            bridge_registry_entry!(
+                [<signal_bridge_info_jni_ $jni_name _Destroy>]: "jni",
+                concat!(stringify!($jni_name), "_Destroy"),
+                ["long"] -> "void",
+                false
+            );
+        }
+    };
+    ( $typ:ty as $jni_name:ident, shared = true ) => {
+        paste! {
+            #[no_mangle]
+            pub unsafe extern "C" fn [<Java_org_signal_client_internal_Native_ $jni_name _1Destroy>](
+                _env: jni::JNIEnv,
+                _class: jni::JClass,
+                handle: jni::ObjectHandle,
+            ) {
+                if handle != 0 {
+                    let _boxed_value = Box::from_raw(handle as *mut std::sync::Arc<$typ>);
+                }
+            }
+
+            bridge_registry_entry!(
+                [<signal_bridge_info_jni_ $jni_name _Destroy>]: "jni",
+                concat!(stringify!($jni_name), "_Destroy"),
+                ["long"] -> "void",
+                false
+            );
         }
     };
 }
 
+/// Implementation of [`bridge_serialize`](crate::support::bridge_serialize) for JNI.
+macro_rules! jni_bridge_serialize {
+    ( $typ:ident as false ) => {};
+    ( $typ:ident as $jni_name:ident ) => {
+        paste! {
+            #[no_mangle]
+            pub unsafe extern "C" fn [<Java_org_signal_client_internal_Native_ $jni_name _1Serialize>](
+                env: jni::JNIEnv,
+                _class: jni::JClass,
+                obj: jni::ObjectHandle,
+            ) -> jni::jbyteArray {
+                jni::run_ffi_safe(&env, concat!(stringify!($jni_name), "_Serialize"), || {
+                    let obj = <&$typ as jni::SimpleArgTypeInfo>::convert_from(&env, obj)?;
+                    jni::to_jbytearray(&env, crate::support::BridgeSerialize::bridge_serialize(obj))
+                })
+            }
+
+            bridge_registry_entry!(
+                [<signal_bridge_info_jni_ $jni_name _Serialize>]: "jni",
+                concat!(stringify!($jni_name), "_Serialize"),
+                ["long"] -> "byte[]",
+                false
+            );
+        }
+    };
+    ( $typ:ident ) => {
+        jni_bridge_serialize!($typ as $typ);
+    };
+}
+
 /// Implementation of [`bridge_deserialize`](crate::support::bridge_deserialize) for JNI.
 macro_rules! jni_bridge_deserialize {
     ( $typ:ident::$fn:path as false ) => {};
@@ -521,11 +878,18 @@ macro_rules! jni_bridge_deserialize {
                 _class: jni::JClass,
                 data: jni::jbyteArray,
             ) -> jni::ObjectHandle {
-                jni::run_ffi_safe(&env, || {
+                jni::run_ffi_safe(&env, concat!(stringify!($jni_name), "_Deserialize"), || {
                     let data = env.convert_byte_array(data)?;
                     jni::ResultTypeInfo::convert_into($typ::$fn(data.as_ref()), &env)
                 })
             }
+
+            bridge_registry_entry!(
+                [<signal_bridge_info_jni_ $jni_name _Deserialize>]: "jni",
+                concat!(stringify!($jni_name), "_Deserialize"),
+                ["byte[]"] -> "long",
+                false
+            );
         }
     };
     ( $typ:ident::$fn:path ) => {
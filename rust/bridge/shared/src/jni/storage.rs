@@ -11,6 +11,7 @@ pub type JavaPreKeyStore<'a> = JObject<'a>;
 pub type JavaSignedPreKeyStore<'a> = JObject<'a>;
 pub type JavaSessionStore<'a> = JObject<'a>;
 pub type JavaSenderKeyStore<'a> = JObject<'a>;
+pub type JavaSenderCertificateSource<'a> = JObject<'a>;
 
 fn sender_key_name_to_jobject<'a>(
     env: &JNIEnv<'a>,
@@ -33,19 +34,18 @@ fn sender_key_name_to_jobject<'a>(
     Ok(sender_key_name_jobject)
 }
 
+/// Wraps a copy of `address` in a handle-backed SignalProtocolAddress.
+///
+/// The name and device ID are only converted to Java values if the store asks for them.
 fn protocol_address_to_jobject<'a>(
     env: &'a JNIEnv,
     address: &ProtocolAddress,
 ) -> Result<JObject<'a>, SignalJniError> {
-    let address_class = env.find_class("org/whispersystems/libsignal/SignalProtocolAddress")?;
-    let address_ctor_args = [
-        JObject::from(env.new_string(address.name())?).into(),
-        JValue::from(address.device_id().convert_into(env)?),
-    ];
-
-    let address_ctor_sig = jni_signature!((java.lang.String, int) -> void);
-    let address_jobject = env.new_object(address_class, address_ctor_sig, &address_ctor_args)?;
-    Ok(address_jobject)
+    jobject_from_native_handle(
+        env,
+        "org/whispersystems/libsignal/SignalProtocolAddress",
+        box_object::<ProtocolAddress>(Ok(address.clone()))?,
+    )
 }
 
 pub struct JniIdentityKeyStore<'a> {
@@ -208,6 +208,62 @@ impl<'a> JniIdentityKeyStore<'a> {
             Some(k) => Ok(Some(IdentityKey::decode(&k)?)),
         }
     }
+
+    /// Loads the identity key and private key delegate from an `IdentityKeyOpsStore`.
+    ///
+    /// Returns `None` if the store doesn't implement that interface.
+    fn do_get_identity_key_ops(&self) -> Result<Option<IdentityKeyPairOps<'a>>, SignalJniError> {
+        let ops_store_class = self
+            .env
+            .find_class("org/whispersystems/libsignal/state/IdentityKeyOpsStore")?;
+        if !self.env.is_instance_of(self.store, ops_store_class)? {
+            return Ok(None);
+        }
+
+        let callback_sig = jni_signature!(() -> org.whispersystems.libsignal.IdentityKey);
+        let identity_key = match get_object_with_serialization(
+            self.env,
+            self.store,
+            &[],
+            callback_sig,
+            "getIdentityKey",
+        )? {
+            None => {
+                return Err(SignalJniError::Signal(SignalProtocolError::InternalError(
+                    "getIdentityKey returned null",
+                )))
+            }
+            Some(k) => IdentityKey::decode(&k)?,
+        };
+
+        let callback_sig = jni_signature!(() -> org.whispersystems.libsignal.ecc.PrivateKeyOps);
+        let ops = call_method_checked(
+            self.env,
+            self.store,
+            "getIdentityPrivateKeyOps",
+            callback_sig,
+            &[],
+        )?;
+        let ops = match ops {
+            JValue::Object(o) if !o.is_null() => o,
+            JValue::Object(_) => {
+                return Err(SignalJniError::Signal(SignalProtocolError::InternalError(
+                    "getIdentityPrivateKeyOps returned null",
+                )))
+            }
+            _ => {
+                return Err(SignalJniError::UnexpectedJniResultType(
+                    "getIdentityPrivateKeyOps",
+                    ops.type_name(),
+                ))
+            }
+        };
+
+        Ok(Some(IdentityKeyPairOps::new(
+            identity_key,
+            Box::new(JniPrivateKeyOps { env: self.env, ops }),
+        )))
+    }
 }
 
 #[async_trait(?Send)]
@@ -219,6 +275,16 @@ impl<'a> IdentityKeyStore for JniIdentityKeyStore<'a> {
         Ok(self.do_get_identity_key_pair()?)
     }
 
+    async fn get_identity_key_ops<'b>(
+        &'b self,
+        ctx: Context,
+    ) -> Result<IdentityKeyPairOps<'b>, SignalProtocolError> {
+        match self.do_get_identity_key_ops()? {
+            Some(ops) => Ok(ops),
+            None => Ok(self.get_identity_key_pair(ctx).await?.into()),
+        }
+    }
+
     async fn get_local_registration_id(&self, _ctx: Context) -> Result<u32, SignalProtocolError> {
         Ok(self.do_get_local_registration_id()?)
     }
@@ -251,6 +317,52 @@ impl<'a> IdentityKeyStore for JniIdentityKeyStore<'a> {
     }
 }
 
+/// Delegates private key operations to a Java `PrivateKeyOps`, such as one backed by the Android
+/// keystore.
+struct JniPrivateKeyOps<'a> {
+    env: &'a JNIEnv<'a>,
+    ops: JObject<'a>,
+}
+
+impl<'a> JniPrivateKeyOps<'a> {
+    fn do_calculate_agreement(&self, their_key: &PublicKey) -> Result<Box<[u8]>, SignalJniError> {
+        let key_jobject = jobject_from_native_handle(
+            self.env,
+            "org/whispersystems/libsignal/ecc/ECPublicKey",
+            box_object::<PublicKey>(Ok(*their_key))?,
+        )?;
+        let callback_sig = jni_signature!((
+            org.whispersystems.libsignal.ecc.ECPublicKey
+        ) -> [byte]);
+        let result = call_method_checked(
+            self.env,
+            self.ops,
+            "calculateAgreement",
+            callback_sig,
+            &[key_jobject.into()],
+        )?;
+
+        match result {
+            JValue::Object(o) if !o.is_null() => {
+                Ok(self.env.convert_byte_array(*o)?.into_boxed_slice())
+            }
+            JValue::Object(_) => Err(SignalJniError::Signal(SignalProtocolError::InternalError(
+                "calculateAgreement returned null",
+            ))),
+            _ => Err(SignalJniError::UnexpectedJniResultType(
+                "calculateAgreement",
+                result.type_name(),
+            )),
+        }
+    }
+}
+
+impl PrivateKeyOps for JniPrivateKeyOps<'_> {
+    fn calculate_agreement(&self, their_key: &PublicKey) -> Result<Box<[u8]>, SignalProtocolError> {
+        Ok(self.do_calculate_agreement(their_key)?)
+    }
+}
+
 pub struct JniPreKeyStore<'a> {
     env: &'a JNIEnv<'a>,
     store: JObject<'a>,
@@ -268,20 +380,16 @@ impl<'a> JniPreKeyStore<'a> {
 }
 
 impl<'a> JniPreKeyStore<'a> {
-    fn do_get_pre_key(&self, prekey_id: u32) -> Result<PreKeyRecord, SignalJniError> {
+    fn do_get_pre_key(&self, prekey_id: u32) -> Result<Option<PreKeyRecord>, SignalJniError> {
         let callback_sig = jni_signature!((int) -> org.whispersystems.libsignal.state.PreKeyRecord);
         let callback_args = [JValue::from(prekey_id.convert_into(self.env)?)];
-        let pk = get_object_with_native_handle::<PreKeyRecord>(
+        get_object_with_native_handle::<PreKeyRecord>(
             self.env,
             self.store,
             &callback_args,
             callback_sig,
             "loadPreKey",
-        )?;
-        match pk {
-            Some(pk) => Ok(pk),
-            None => Err(SignalJniError::Signal(SignalProtocolError::InvalidPreKeyId)),
-        }
+        )
     }
 
     fn do_save_pre_key(
@@ -333,7 +441,8 @@ impl<'a> PreKeyStore for JniPreKeyStore<'a> {
         prekey_id: u32,
         _ctx: Context,
     ) -> Result<PreKeyRecord, SignalProtocolError> {
-        Ok(self.do_get_pre_key(prekey_id)?)
+        self.do_get_pre_key(prekey_id)?
+            .ok_or(SignalProtocolError::InvalidPreKeyId)
     }
 
     async fn save_pre_key(
@@ -371,24 +480,21 @@ impl<'a> JniSignedPreKeyStore<'a> {
 }
 
 impl<'a> JniSignedPreKeyStore<'a> {
-    fn do_get_signed_pre_key(&self, prekey_id: u32) -> Result<SignedPreKeyRecord, SignalJniError> {
+    fn do_get_signed_pre_key(
+        &self,
+        prekey_id: u32,
+    ) -> Result<Option<SignedPreKeyRecord>, SignalJniError> {
         let callback_sig = jni_signature!((
             int
         ) -> org.whispersystems.libsignal.state.SignedPreKeyRecord);
         let callback_args = [JValue::from(prekey_id.convert_into(self.env)?)];
-        let spk = get_object_with_native_handle::<SignedPreKeyRecord>(
+        get_object_with_native_handle::<SignedPreKeyRecord>(
             self.env,
             self.store,
             &callback_args,
             callback_sig,
             "loadSignedPreKey",
-        )?;
-        match spk {
-            Some(spk) => Ok(spk),
-            None => Err(SignalJniError::Signal(
-                SignalProtocolError::InvalidSignedPreKeyId,
-            )),
-        }
+        )
     }
 
     fn do_save_signed_pre_key(
@@ -427,7 +533,8 @@ impl<'a> SignedPreKeyStore for JniSignedPreKeyStore<'a> {
         prekey_id: u32,
         _ctx: Context,
     ) -> Result<SignedPreKeyRecord, SignalProtocolError> {
-        Ok(self.do_get_signed_pre_key(prekey_id)?)
+        self.do_get_signed_pre_key(prekey_id)?
+            .ok_or(SignalProtocolError::InvalidSignedPreKeyId)
     }
 
     async fn save_signed_pre_key(
@@ -613,3 +720,50 @@ impl<'a> SenderKeyStore for JniSenderKeyStore<'a> {
         Ok(self.do_load_sender_key(sender_key_name)?)
     }
 }
+
+pub struct JniSenderCertificateSource<'a> {
+    env: &'a JNIEnv<'a>,
+    source: JObject<'a>,
+}
+
+impl<'a> JniSenderCertificateSource<'a> {
+    pub fn new(env: &'a JNIEnv, source: JObject<'a>) -> Result<Self, SignalJniError> {
+        check_jobject_type(
+            &env,
+            source,
+            "org/signal/libsignal/metadata/certificate/SenderCertificateSource",
+        )?;
+        Ok(Self { env, source })
+    }
+}
+
+impl<'a> JniSenderCertificateSource<'a> {
+    fn do_fetch_sender_certificate(&mut self) -> Result<SenderCertificate, SignalJniError> {
+        let callback_sig =
+            jni_signature!(() -> org.signal.libsignal.metadata.certificate.SenderCertificate);
+        let certificate = get_object_with_native_handle::<SenderCertificate>(
+            self.env,
+            self.source,
+            &[],
+            callback_sig,
+            "fetchSenderCertificate",
+        )?;
+
+        match certificate {
+            None => Err(SignalJniError::Signal(SignalProtocolError::InternalError(
+                "fetchSenderCertificate returned null",
+            ))),
+            Some(certificate) => Ok(certificate),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl<'a> SenderCertificateSource for JniSenderCertificateSource<'a> {
+    async fn fetch_sender_certificate(
+        &mut self,
+        _ctx: Context,
+    ) -> Result<SenderCertificate, SignalProtocolError> {
+        Ok(self.do_fetch_sender_certificate()?)
+    }
+}
@@ -0,0 +1,177 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use ::keytrans;
+use keytrans::*;
+use libsignal_bridge_macros::*;
+use libsignal_protocol::PublicKey;
+
+use crate::support::*;
+use crate::*;
+
+// The bridges don't hold on to a KeyTransparencyStore between calls. Instead, the app passes in
+// the state it saved from the last verification and saves the updated state that comes back.
+
+pub struct KeyTransparencySearchResult {
+    value: Vec<u8>,
+    tree_head: Vec<u8>,
+    monitoring_data: Vec<u8>,
+}
+
+impl KeyTransparencySearchResult {
+    fn value(&self) -> &[u8] {
+        &self.value
+    }
+
+    fn tree_head(&self) -> &[u8] {
+        &self.tree_head
+    }
+
+    fn monitoring_data(&self) -> &[u8] {
+        &self.monitoring_data
+    }
+}
+
+pub struct KeyTransparencyMonitorResult {
+    unchanged: bool,
+    tree_head: Vec<u8>,
+    monitoring_data: Vec<u8>,
+}
+
+impl KeyTransparencyMonitorResult {
+    fn is_unchanged(&self) -> bool {
+        self.unchanged
+    }
+
+    fn tree_head(&self) -> &[u8] {
+        &self.tree_head
+    }
+
+    fn monitoring_data(&self) -> &[u8] {
+        &self.monitoring_data
+    }
+}
+
+bridge_handle!(KeyTransparencySearchResult, clone = false);
+
+// These getters are written out rather than generated with bridge_get! and
+// bridge_get_bytearray!, which produce a SignalProtocolError-based Result rather than this
+// module's.
+
+#[bridge_fn_buffer]
+fn KeyTransparencySearchResult_GetValue<E: Env>(
+    env: E,
+    result: &KeyTransparencySearchResult,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(result.value()))
+}
+
+#[bridge_fn_buffer]
+fn KeyTransparencySearchResult_GetTreeHead<E: Env>(
+    env: E,
+    result: &KeyTransparencySearchResult,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(result.tree_head()))
+}
+
+#[bridge_fn_buffer]
+fn KeyTransparencySearchResult_GetMonitoringData<E: Env>(
+    env: E,
+    result: &KeyTransparencySearchResult,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(result.monitoring_data()))
+}
+
+bridge_handle!(KeyTransparencyMonitorResult, clone = false);
+
+#[bridge_fn]
+fn KeyTransparencyMonitorResult_IsUnchanged(result: &KeyTransparencyMonitorResult) -> bool {
+    result.is_unchanged()
+}
+
+#[bridge_fn_buffer]
+fn KeyTransparencyMonitorResult_GetTreeHead<E: Env>(
+    env: E,
+    result: &KeyTransparencyMonitorResult,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(result.tree_head()))
+}
+
+#[bridge_fn_buffer]
+fn KeyTransparencyMonitorResult_GetMonitoringData<E: Env>(
+    env: E,
+    result: &KeyTransparencyMonitorResult,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(result.monitoring_data()))
+}
+
+fn store_with_tree_head(last_tree_head: Option<&[u8]>) -> Result<InMemKeyTransparencyStore> {
+    let mut store = InMemKeyTransparencyStore::new();
+    if let Some(last_tree_head) = last_tree_head {
+        store.set_tree_head(&TreeHead::deserialize(last_tree_head)?)?;
+    }
+    Ok(store)
+}
+
+fn saved_tree_head(store: &InMemKeyTransparencyStore) -> Result<Vec<u8>> {
+    Ok(store
+        .tree_head()?
+        .expect("verification stores a tree head")
+        .serialize())
+}
+
+fn saved_monitoring_data(store: &InMemKeyTransparencyStore, search_key: &[u8]) -> Result<Vec<u8>> {
+    Ok(store
+        .monitoring_data(search_key)?
+        .map(|data| data.serialize())
+        .unwrap_or_default())
+}
+
+#[bridge_fn]
+fn KeyTransparency_VerifySearch(
+    log_key: &PublicKey,
+    search_key: &[u8],
+    response: &[u8],
+    last_tree_head: Option<&[u8]>,
+) -> Result<KeyTransparencySearchResult> {
+    let mut store = store_with_tree_head(last_tree_head)?;
+    let value = verify_search(log_key, search_key, response, &mut store)?;
+    Ok(KeyTransparencySearchResult {
+        value,
+        tree_head: saved_tree_head(&store)?,
+        monitoring_data: saved_monitoring_data(&store, search_key)?,
+    })
+}
+
+#[bridge_fn]
+fn KeyTransparency_VerifyMonitor(
+    log_key: &PublicKey,
+    search_key: &[u8],
+    response: &[u8],
+    last_tree_head: Option<&[u8]>,
+    monitoring_data: &[u8],
+) -> Result<KeyTransparencyMonitorResult> {
+    let mut store = store_with_tree_head(last_tree_head)?;
+    store.set_monitoring_data(search_key, &MonitoringData::deserialize(monitoring_data)?)?;
+    let unchanged = verify_monitor(log_key, search_key, response, &mut store)?;
+    Ok(KeyTransparencyMonitorResult {
+        unchanged,
+        tree_head: saved_tree_head(&store)?,
+        monitoring_data: saved_monitoring_data(&store, search_key)?,
+    })
+}
+
+/// Returns the new tree head to save.
+#[bridge_fn_buffer]
+fn KeyTransparency_VerifyDistinguished<E: Env>(
+    env: E,
+    log_key: &PublicKey,
+    response: &[u8],
+    last_tree_head: Option<&[u8]>,
+) -> Result<E::Buffer> {
+    let mut store = store_with_tree_head(last_tree_head)?;
+    let tree_head = verify_distinguished(log_key, response, &mut store)?;
+    Ok(env.buffer(tree_head.serialize()))
+}
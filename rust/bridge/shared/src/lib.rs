@@ -9,6 +9,10 @@
 #[cfg(not(any(feature = "ffi", feature = "jni", feature = "node")))]
 compile_error!("Feature \"ffi\", \"jni\", or \"node\" must be enabled for this crate.");
 
+// Declared first so that the bridge modules can use its macros.
+#[macro_use]
+mod support;
+
 #[cfg(feature = "ffi")]
 #[macro_use]
 pub mod ffi;
@@ -21,11 +25,24 @@ pub mod jni;
 #[macro_use]
 pub mod node;
 
-#[macro_use]
-mod support;
-
+pub mod audit;
+pub mod context;
 pub mod crypto;
+pub mod encoding;
+pub mod introspection;
+pub mod keytrans;
+pub mod limits;
+pub mod message_backup;
 pub mod protocol;
+pub mod registration;
+#[cfg(feature = "advanced-crypto")]
+pub mod ristretto;
+pub mod rng;
+pub mod self_test;
+pub mod service_id;
+pub mod timestamp;
+pub mod trace_logging;
+pub mod version;
 
 // Desktop does not make use of device transfer certificates
 #[cfg(any(feature = "jni", feature = "ffi"))]
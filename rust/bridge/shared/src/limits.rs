@@ -0,0 +1,79 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Sanity limits on the arguments accepted by bridge functions.
+//!
+//! Byte buffers are checked against [`max_buffer_len`] when they are converted from their
+//! platform representation, before any Rust code gets a chance to copy or parse them. This keeps
+//! an unreasonably large plaintext or ciphertext from driving the native layer out of memory.
+
+use libsignal_bridge_macros::*;
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::*;
+
+/// The default value for [`max_buffer_len`]: 256 MiB.
+///
+/// This is far larger than any message or attachment key material the protocol deals with, but
+/// still small enough to be allocated a few times over.
+pub const DEFAULT_MAX_BUFFER_LEN: usize = 256 * 1024 * 1024;
+
+static MAX_BUFFER_LEN: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_BUFFER_LEN);
+
+/// Sets the largest byte buffer, in bytes, that will be accepted as an argument from now on.
+pub fn set_max_buffer_len(limit: usize) {
+    MAX_BUFFER_LEN.store(limit, Ordering::Relaxed);
+}
+
+/// Returns the largest byte buffer, in bytes, that will be accepted as an argument.
+pub fn max_buffer_len() -> usize {
+    MAX_BUFFER_LEN.load(Ordering::Relaxed)
+}
+
+/// A byte buffer argument was longer than [`max_buffer_len`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferTooLarge {
+    /// The length of the rejected buffer.
+    pub len: usize,
+    /// The limit in effect when it was rejected.
+    pub limit: usize,
+}
+
+impl fmt::Display for BufferTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "buffer of {} bytes exceeds the limit of {} bytes",
+            self.len, self.limit
+        )
+    }
+}
+
+impl std::error::Error for BufferTooLarge {}
+
+/// Checks a buffer length against the current [`max_buffer_len`].
+pub fn check_buffer_len(len: usize) -> Result<(), BufferTooLarge> {
+    let limit = max_buffer_len();
+    if len > limit {
+        Err(BufferTooLarge { len, limit })
+    } else {
+        Ok(())
+    }
+}
+
+/// Changes the limit applied by [`check_buffer_len`].
+///
+/// Values that don't fit in a `usize` are clamped.
+#[bridge_fn_void(ffi = "set_max_buffer_length")]
+fn Limits_SetMaxBufferLength(limit: u64) {
+    set_max_buffer_len(usize::try_from(limit).unwrap_or(usize::MAX))
+}
+
+#[bridge_fn(ffi = "get_max_buffer_length")]
+fn Limits_GetMaxBufferLength() -> u64 {
+    max_buffer_len() as u64
+}
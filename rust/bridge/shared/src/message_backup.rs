@@ -0,0 +1,172 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use ::message_backup;
+use libsignal_bridge_macros::*;
+use message_backup::*;
+
+use crate::rng::{CallRng, Rng};
+use crate::support::*;
+use crate::*;
+
+bridge_handle!(FrameWriter, mut = true);
+bridge_handle!(FrameReader, mut = true);
+
+#[bridge_fn]
+fn FrameWriter_New(backup_key: &[u8], compressed: bool, rng: Option<&Rng>) -> Result<FrameWriter> {
+    FrameWriter::new(backup_key, compressed, &mut CallRng::new(rng))
+}
+
+#[bridge_fn_buffer]
+fn FrameWriter_Append<E: Env>(env: E, writer: &mut FrameWriter, frame: &[u8]) -> Result<E::Buffer> {
+    Ok(env.buffer(writer.append(frame)?))
+}
+
+#[bridge_fn_buffer]
+fn FrameWriter_Finish<E: Env>(env: E, writer: &mut FrameWriter) -> Result<E::Buffer> {
+    Ok(env.buffer(writer.finish()?))
+}
+
+#[bridge_fn]
+fn FrameReader_New(backup_key: &[u8]) -> Result<FrameReader> {
+    FrameReader::new(backup_key)
+}
+
+#[bridge_fn_void]
+fn FrameReader_Push(reader: &mut FrameReader, chunk: &[u8]) -> Result<()> {
+    reader.push(chunk)
+}
+
+/// Returns null when more input is needed or the stream has ended.
+#[bridge_fn_buffer]
+fn FrameReader_NextFrame<E: Env>(env: E, reader: &mut FrameReader) -> Result<Option<E::Buffer>> {
+    Ok(reader.next_frame()?.map(|frame| env.buffer(frame)))
+}
+
+#[bridge_fn]
+fn FrameReader_IsFinished(reader: &FrameReader) -> bool {
+    reader.is_finished()
+}
+
+#[bridge_fn_void]
+fn FrameReader_Finish(reader: &mut FrameReader) -> Result<()> {
+    reader.finish()
+}
+
+bridge_handle!(BackupBuilder, clone = false, mut = true);
+
+#[bridge_fn]
+fn BackupBuilder_New(
+    backup_key: &[u8],
+    compressed: bool,
+    backup_time_ms: u64,
+    rng: Option<&Rng>,
+) -> Result<BackupBuilder> {
+    BackupBuilder::new(
+        backup_key,
+        compressed,
+        backup_time_ms,
+        &mut CallRng::new(rng),
+    )
+}
+
+#[bridge_fn_buffer]
+fn BackupBuilder_AddAccountData<E: Env>(
+    env: E,
+    builder: &mut BackupBuilder,
+    profile_key: &[u8],
+    username: Option<String>,
+    given_name: String,
+    family_name: String,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(builder.add_account_data(&AccountData {
+        profile_key: profile_key.to_vec(),
+        username,
+        given_name,
+        family_name,
+    })?))
+}
+
+#[bridge_fn_buffer]
+fn BackupBuilder_AddSelfRecipient<E: Env>(
+    env: E,
+    builder: &mut BackupBuilder,
+    id: u64,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(builder.add_recipient(&Recipient {
+        id,
+        destination: Destination::SelfRecipient,
+    })?))
+}
+
+#[bridge_fn_buffer]
+fn BackupBuilder_AddContact<E: Env>(
+    env: E,
+    builder: &mut BackupBuilder,
+    id: u64,
+    uuid: Option<String>,
+    e164: Option<String>,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(builder.add_recipient(&Recipient {
+        id,
+        destination: Destination::Contact { uuid, e164 },
+    })?))
+}
+
+#[bridge_fn_buffer]
+fn BackupBuilder_AddGroup<E: Env>(
+    env: E,
+    builder: &mut BackupBuilder,
+    id: u64,
+    master_key: &[u8],
+) -> Result<E::Buffer> {
+    Ok(env.buffer(builder.add_recipient(&Recipient {
+        id,
+        destination: Destination::Group {
+            master_key: master_key.to_vec(),
+        },
+    })?))
+}
+
+#[bridge_fn_buffer]
+fn BackupBuilder_AddChat<E: Env>(
+    env: E,
+    builder: &mut BackupBuilder,
+    id: u64,
+    recipient_id: u64,
+    archived: bool,
+    pinned_order: u32,
+    expiration_timer_ms: u64,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(builder.add_chat(&Chat {
+        id,
+        recipient_id,
+        archived,
+        pinned_order,
+        expiration_timer_ms,
+    })?))
+}
+
+#[bridge_fn_buffer]
+fn BackupBuilder_AddChatItem<E: Env>(
+    env: E,
+    builder: &mut BackupBuilder,
+    chat_id: u64,
+    author_id: u64,
+    date_sent: u64,
+    text: String,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(builder.add_chat_item(&ChatItem {
+        chat_id,
+        author_id,
+        date_sent,
+        text,
+    })?))
+}
+
+#[bridge_fn_buffer]
+fn BackupBuilder_Finish<E: Env>(env: E, builder: &mut BackupBuilder) -> Result<E::Buffer> {
+    Ok(env.buffer(builder.finish()?))
+}
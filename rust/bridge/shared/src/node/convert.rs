@@ -11,6 +11,7 @@ use std::convert::{TryFrom, TryInto};
 use std::hash::Hasher;
 use std::ops::{Deref, RangeInclusive};
 use std::slice;
+use std::sync::atomic::{AtomicU8, Ordering};
 
 use super::*;
 
@@ -231,6 +232,25 @@ impl SimpleArgTypeInfo for u64 {
     }
 }
 
+/// Rejects values too far in the future to be milliseconds since the epoch.
+impl SimpleArgTypeInfo for crate::timestamp::Timestamp {
+    type ArgType = JsNumber;
+    fn convert_from(cx: &mut FunctionContext, foreign: Handle<Self::ArgType>) -> NeonResult<Self> {
+        let millis = u64::convert_from(cx, foreign)?;
+        match Self::from_epoch_millis(millis) {
+            Ok(timestamp) => Ok(timestamp),
+            Err(e) => cx.throw_range_error(e.to_string()),
+        }
+    }
+}
+
+impl SimpleArgTypeInfo for bool {
+    type ArgType = JsBoolean;
+    fn convert_from(cx: &mut FunctionContext, foreign: Handle<Self::ArgType>) -> NeonResult<Self> {
+        Ok(foreign.value(cx))
+    }
+}
+
 impl SimpleArgTypeInfo for String {
     type ArgType = JsString;
     fn convert_from(cx: &mut FunctionContext, foreign: Handle<Self::ArgType>) -> NeonResult<Self> {
@@ -238,6 +258,34 @@ impl SimpleArgTypeInfo for String {
     }
 }
 
+/// Loads the property `name` of a `#[derive(BridgeStruct)]` argument.
+///
+/// Throws a `TypeError` naming the property if it is missing or has the wrong type.
+pub fn get_struct_field<T: SimpleArgTypeInfo>(
+    cx: &mut FunctionContext,
+    object: Handle<JsObject>,
+    name: &str,
+) -> NeonResult<T> {
+    let value = object.get(cx, name)?;
+    match value.downcast::<T::ArgType, _>(cx) {
+        Ok(value) => T::convert_from(cx, value),
+        Err(_) => cx.throw_type_error(format!("missing or invalid property '{}'", name)),
+    }
+}
+
+/// Like [`get_struct_field`], but treats a missing, `undefined`, or `null` property as `None`.
+pub fn get_optional_struct_field<T: SimpleArgTypeInfo>(
+    cx: &mut FunctionContext,
+    object: Handle<JsObject>,
+    name: &str,
+) -> NeonResult<Option<T>> {
+    let value = object.get(cx, name)?;
+    if value.downcast::<JsUndefined, _>(cx).is_ok() || value.downcast::<JsNull, _>(cx).is_ok() {
+        return Ok(None);
+    }
+    get_struct_field(cx, object, name).map(Some)
+}
+
 /// Converts `null` to `None`, passing through all other values.
 impl<'storage, 'context: 'storage, T> ArgTypeInfo<'storage, 'context> for Option<T>
 where
@@ -282,25 +330,156 @@ where
     }
 }
 
+/// Converts an array of wrapper objects to a `Vec` of references to the underlying handles.
+impl<'storage, 'context: 'storage, T> ArgTypeInfo<'storage, 'context> for Vec<&'storage T>
+where
+    &'storage T: ArgTypeInfo<'storage, 'context, ArgType = JsObject>,
+{
+    type ArgType = JsArray;
+    type StoredType = Vec<<&'storage T as ArgTypeInfo<'storage, 'context>>::StoredType>;
+    fn borrow(
+        cx: &mut FunctionContext<'context>,
+        foreign: Handle<'context, Self::ArgType>,
+    ) -> NeonResult<Self::StoredType> {
+        let elements = foreign.to_vec(cx)?;
+        elements
+            .into_iter()
+            .map(|element| {
+                let wrapper = element.downcast_or_throw::<JsObject, _>(cx)?;
+                <&'storage T>::borrow(cx, wrapper)
+            })
+            .collect()
+    }
+    fn load_from(stored: &'storage mut Self::StoredType) -> Self {
+        stored.iter_mut().map(<&'storage T>::load_from).collect()
+    }
+}
+
+/// Converts an array of wrapper objects to a `Vec` of references to the underlying handles.
+impl<'storage, T> AsyncArgTypeInfo<'storage> for Vec<&'storage T>
+where
+    &'storage T: AsyncArgTypeInfo<'storage, ArgType = JsObject>,
+{
+    type ArgType = JsArray;
+    type StoredType = Vec<<&'storage T as AsyncArgTypeInfo<'storage>>::StoredType>;
+    fn save_async_arg(
+        cx: &mut FunctionContext,
+        foreign: Handle<Self::ArgType>,
+    ) -> NeonResult<Self::StoredType> {
+        let elements = foreign.to_vec(cx)?;
+        elements
+            .into_iter()
+            .map(|element| {
+                let wrapper = element.downcast_or_throw::<JsObject, _>(cx)?;
+                <&'storage T>::save_async_arg(cx, wrapper)
+            })
+            .collect()
+    }
+    fn load_async_arg(stored: &'storage mut Self::StoredType) -> Self {
+        stored
+            .iter_mut()
+            .map(<&'storage T>::load_async_arg)
+            .collect()
+    }
+}
+
+/// How [`AssumedImmutableBuffer`] and [`PersistentAssumedImmutableBuffer`] guard against a
+/// JavaScript buffer being modified while Rust code is using it.
+///
+/// The policy is process-wide and is captured when each buffer is loaded, so changing it does not
+/// affect buffers that are already in use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum BufferChecksumPolicy {
+    /// Borrow the buffer without checking it at all.
+    Off = 0,
+    /// Checksum the first 1024 bytes, or the whole buffer if debug logging is enabled, and log an
+    /// error on mismatch. This is the default.
+    Prefix = 1,
+    /// Checksum the whole buffer and log an error on mismatch.
+    Full = 2,
+    /// Checksum the whole buffer and panic on mismatch, unless already panicking.
+    FullWithPanic = 3,
+    /// Copy the buffer's contents instead of borrowing them, so modifications have no effect.
+    Copy = 4,
+}
+
+impl TryFrom<u32> for BufferChecksumPolicy {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Off),
+            1 => Ok(Self::Prefix),
+            2 => Ok(Self::Full),
+            3 => Ok(Self::FullWithPanic),
+            4 => Ok(Self::Copy),
+            _ => Err(value),
+        }
+    }
+}
+
+static BUFFER_CHECKSUM_POLICY: AtomicU8 = AtomicU8::new(BufferChecksumPolicy::Prefix as u8);
+
+/// Sets the policy used for buffers loaded from now on.
+pub fn set_buffer_checksum_policy(policy: BufferChecksumPolicy) {
+    BUFFER_CHECKSUM_POLICY.store(policy as u8, Ordering::Relaxed);
+}
+
+/// Returns the policy that will be used for the next buffer loaded.
+pub fn buffer_checksum_policy() -> BufferChecksumPolicy {
+    BufferChecksumPolicy::try_from(u32::from(BUFFER_CHECKSUM_POLICY.load(Ordering::Relaxed)))
+        .expect("only valid policies are stored")
+}
+
 /// Calculates a checksum to verify that a buffer wasn't mutated out from under us.
 ///
-/// By default, this only checks the first 1024 bytes of the buffer, but it will check the entire
-/// buffer if debug logging is enabled (`log::Level::Debug`).
-fn calculate_checksum_for_immutable_buffer(buffer: &[u8]) -> u64 {
-    let mut hasher = DefaultHasher::new();
+/// Returns `None` if `policy` doesn't call for a checksum.
+fn calculate_checksum_for_immutable_buffer(
+    buffer: &[u8],
+    policy: BufferChecksumPolicy,
+) -> Option<u64> {
     const LIMIT: usize = 1024;
-    if log::log_enabled!(log::Level::Debug) || buffer.len() < LIMIT {
-        hasher.write(buffer);
-    } else {
-        hasher.write(&buffer[..LIMIT]);
+    let checked = match policy {
+        BufferChecksumPolicy::Off | BufferChecksumPolicy::Copy => return None,
+        BufferChecksumPolicy::Prefix
+            if !log::log_enabled!(log::Level::Debug) && buffer.len() > LIMIT =>
+        {
+            &buffer[..LIMIT]
+        }
+        BufferChecksumPolicy::Prefix
+        | BufferChecksumPolicy::Full
+        | BufferChecksumPolicy::FullWithPanic => buffer,
+    };
+    let mut hasher = DefaultHasher::new();
+    hasher.write(checked);
+    Some(hasher.finish())
+}
+
+/// Recomputes the checksum for `buffer` and reports a mismatch as `policy` dictates.
+///
+/// This runs from `Drop` and `Finalize`, so it only logs if the thread is already unwinding;
+/// panicking again there would abort the process.
+fn verify_checksum_for_immutable_buffer(
+    buffer: &[u8],
+    policy: BufferChecksumPolicy,
+    hash: Option<u64>,
+) {
+    if hash != calculate_checksum_for_immutable_buffer(buffer, policy) {
+        if policy == BufferChecksumPolicy::FullWithPanic && !std::thread::panicking() {
+            panic!("buffer modified while in use");
+        }
+        log::error!("buffer modified while in use");
     }
-    hasher.finish()
 }
 
 /// A wrapper around `&[u8]` that also stores a checksum, to be validated on Drop.
+///
+/// Under [`BufferChecksumPolicy::Copy`], this holds a copy of the buffer instead.
 pub struct AssumedImmutableBuffer<'a> {
-    buffer: &'a [u8],
-    hash: u64,
+    buffer: Cow<'a, [u8]>,
+    policy: BufferChecksumPolicy,
+    hash: Option<u64>,
 }
 
 impl<'a> AssumedImmutableBuffer<'a> {
@@ -313,27 +492,41 @@ impl<'a> AssumedImmutableBuffer<'a> {
     ///
     /// [napi]: https://nodejs.org/api/n-api.html#n_api_napi_get_buffer_info
     fn new<'b>(cx: &mut impl Context<'b>, handle: Handle<'a, JsBuffer>) -> Self {
+        let policy = buffer_checksum_policy();
         let buffer = cx.borrow(&handle, |buf| {
             if buf.len() == 0 {
-                &[]
+                Cow::Borrowed(&[][..])
+            } else if policy == BufferChecksumPolicy::Copy {
+                Cow::Owned(buf.as_slice().to_vec())
             } else {
-                unsafe { extend_lifetime::<'_, 'a, [u8]>(buf.as_slice()) }
+                Cow::Borrowed(unsafe { extend_lifetime::<'_, 'a, [u8]>(buf.as_slice()) })
             }
         });
-        let hash = calculate_checksum_for_immutable_buffer(buffer);
-        Self { buffer, hash }
+        let hash = calculate_checksum_for_immutable_buffer(&buffer, policy);
+        Self {
+            buffer,
+            policy,
+            hash,
+        }
     }
 }
 
-/// Logs an error (but does not panic) if the buffer's contents have changed.
+/// Reports a change to the buffer's contents according to the policy it was loaded with.
 impl Drop for AssumedImmutableBuffer<'_> {
     fn drop(&mut self) {
-        if self.hash != calculate_checksum_for_immutable_buffer(self.buffer) {
-            log::error!("buffer modified while in use");
-        }
+        verify_checksum_for_immutable_buffer(&self.buffer, self.policy, self.hash);
     }
 }
 
+/// Throws a `BufferTooLargeError` if `buffer` is longer than [`crate::limits::max_buffer_len`].
+fn check_buffer_len<'a>(cx: &mut impl Context<'a>, buffer: Handle<JsBuffer>) -> NeonResult<()> {
+    let len = cx.borrow(&buffer, |buf| buf.len());
+    if let Err(e) = crate::limits::check_buffer_len(len) {
+        e.throw::<JsUndefined>(cx)?;
+    }
+    Ok(())
+}
+
 /// Loads from a JsBuffer, assuming it won't be mutated while in use.
 /// See [`AssumedImmutableBuffer`].
 impl<'storage, 'context: 'storage> ArgTypeInfo<'storage, 'context> for &'storage [u8] {
@@ -343,25 +536,28 @@ impl<'storage, 'context: 'storage> ArgTypeInfo<'storage, 'context> for &'storage
         cx: &mut FunctionContext,
         foreign: Handle<'context, Self::ArgType>,
     ) -> NeonResult<Self::StoredType> {
+        check_buffer_len(cx, foreign)?;
         Ok(AssumedImmutableBuffer::new(cx, foreign))
     }
     fn load_from(stored: &'storage mut Self::StoredType) -> Self {
-        stored.buffer
+        &stored.buffer
     }
 }
 
 /// A wrapper around a persisted JavaScript buffer and a pointer/length pair.
 ///
 /// Like [`AssumedImmutableBuffer`], `PersistentAssumedImmutableBuffer` also stores a checksum,
-/// to be validated on Finalize.
+/// to be validated on Finalize, or a copy of the buffer under [`BufferChecksumPolicy::Copy`].
 ///
 /// A `PersistentAssumedImmutableBuffer` **cannot be dropped**; instead, it must be explicitly
 /// finalized in a JavaScript context, as it contains a [`neon::handle::Root`].
 pub struct PersistentAssumedImmutableBuffer {
     owner: Root<JsBuffer>,
+    copy: Option<Box<[u8]>>,
     buffer_start: *const u8,
     buffer_len: usize,
-    hash: u64,
+    policy: BufferChecksumPolicy,
+    hash: Option<u64>,
 }
 
 impl PersistentAssumedImmutableBuffer {
@@ -375,6 +571,24 @@ impl PersistentAssumedImmutableBuffer {
     /// [napi]: https://nodejs.org/api/n-api.html#n_api_napi_get_buffer_info
     fn new<'a>(cx: &mut impl Context<'a>, buffer: Handle<JsBuffer>) -> Self {
         let owner = buffer.root(cx);
+        let policy = buffer_checksum_policy();
+        if policy == BufferChecksumPolicy::Copy {
+            let copy = cx.borrow(&buffer, |buf| {
+                if buf.len() == 0 {
+                    Box::default()
+                } else {
+                    buf.as_slice().into()
+                }
+            });
+            return Self {
+                owner,
+                copy: Some(copy),
+                buffer_start: std::ptr::null(),
+                buffer_len: 0,
+                policy,
+                hash: None,
+            };
+        }
         let (buffer_start, buffer_len, hash) = cx.borrow(&buffer, |buf| {
             (
                 if buf.len() == 0 {
@@ -383,13 +597,15 @@ impl PersistentAssumedImmutableBuffer {
                     buf.as_slice().as_ptr()
                 },
                 buf.len(),
-                calculate_checksum_for_immutable_buffer(buf.as_slice()),
+                calculate_checksum_for_immutable_buffer(buf.as_slice(), policy),
             )
         });
         Self {
             owner,
+            copy: None,
             buffer_start,
             buffer_len,
+            policy,
             hash,
         }
     }
@@ -398,7 +614,9 @@ impl PersistentAssumedImmutableBuffer {
 impl Deref for PersistentAssumedImmutableBuffer {
     type Target = [u8];
     fn deref(&self) -> &[u8] {
-        if self.buffer_start.is_null() {
+        if let Some(copy) = &self.copy {
+            copy
+        } else if self.buffer_start.is_null() {
             &[]
         } else {
             // See `new()` for the safety guarantee.
@@ -412,12 +630,10 @@ impl Deref for PersistentAssumedImmutableBuffer {
 // while in use, and we know it won't be deallocated (see above).
 unsafe impl Send for PersistentAssumedImmutableBuffer {}
 
-/// Logs an error (but does not panic) if the buffer's contents have changed.
+/// Reports a change to the buffer's contents according to the policy it was loaded with.
 impl Finalize for PersistentAssumedImmutableBuffer {
     fn finalize<'a, C: Context<'a>>(self, cx: &mut C) {
-        if self.hash != calculate_checksum_for_immutable_buffer(&*self) {
-            log::error!("buffer modified while in use");
-        }
+        verify_checksum_for_immutable_buffer(&*self, self.policy, self.hash);
         self.owner.finalize(cx)
     }
 }
@@ -431,6 +647,7 @@ impl<'a> AsyncArgTypeInfo<'a> for &'a [u8] {
         cx: &mut FunctionContext,
         foreign: Handle<Self::ArgType>,
     ) -> NeonResult<Self::StoredType> {
+        check_buffer_len(cx, foreign)?;
         Ok(PersistentAssumedImmutableBuffer::new(cx, foreign))
     }
     fn load_async_arg(stored: &'a mut Self::StoredType) -> Self {
@@ -438,6 +655,71 @@ impl<'a> AsyncArgTypeInfo<'a> for &'a [u8] {
     }
 }
 
+/// Copies the addresses out of an array of `ProtocolAddress` wrapper objects.
+impl<'a> AsyncArgTypeInfo<'a> for &'a [ProtocolAddress] {
+    type ArgType = JsArray;
+    type StoredType = super::DefaultFinalize<Vec<ProtocolAddress>>;
+    fn save_async_arg(
+        cx: &mut FunctionContext,
+        foreign: Handle<Self::ArgType>,
+    ) -> NeonResult<Self::StoredType> {
+        let addresses = foreign
+            .to_vec(cx)?
+            .into_iter()
+            .map(|element| {
+                let wrapper = element.downcast_or_throw::<JsObject, _>(cx)?;
+                let address: Handle<DefaultJsBox<ProtocolAddress>> = wrapper
+                    .get(cx, NATIVE_HANDLE_PROPERTY)?
+                    .downcast_or_throw(cx)?;
+                Ok((***address).clone())
+            })
+            .collect::<NeonResult<Vec<_>>>()?;
+        Ok(super::DefaultFinalize(addresses))
+    }
+    fn load_async_arg(stored: &'a mut Self::StoredType) -> Self {
+        &stored.0
+    }
+}
+
+/// Copies the messages out of an array of `CiphertextMessage` wrapper objects.
+impl<'a> AsyncArgTypeInfo<'a> for &'a [CiphertextMessage] {
+    type ArgType = JsArray;
+    type StoredType = super::DefaultFinalize<Vec<CiphertextMessage>>;
+    fn save_async_arg(
+        cx: &mut FunctionContext,
+        foreign: Handle<Self::ArgType>,
+    ) -> NeonResult<Self::StoredType> {
+        let messages = foreign
+            .to_vec(cx)?
+            .into_iter()
+            .map(|element| {
+                let wrapper = element.downcast_or_throw::<JsObject, _>(cx)?;
+                let message: Handle<DefaultJsBox<CiphertextMessage>> = wrapper
+                    .get(cx, NATIVE_HANDLE_PROPERTY)?
+                    .downcast_or_throw(cx)?;
+                Ok(match &***message {
+                    CiphertextMessage::SignalMessage(m) => {
+                        CiphertextMessage::SignalMessage(m.clone())
+                    }
+                    CiphertextMessage::PreKeySignalMessage(m) => {
+                        CiphertextMessage::PreKeySignalMessage(m.clone())
+                    }
+                    CiphertextMessage::SenderKeyMessage(m) => {
+                        CiphertextMessage::SenderKeyMessage(m.clone())
+                    }
+                    CiphertextMessage::SenderKeyDistributionMessage(m) => {
+                        CiphertextMessage::SenderKeyDistributionMessage(m.clone())
+                    }
+                })
+            })
+            .collect::<NeonResult<Vec<_>>>()?;
+        Ok(super::DefaultFinalize(messages))
+    }
+    fn load_async_arg(stored: &'a mut Self::StoredType) -> Self {
+        &stored.0
+    }
+}
+
 static_assertions::assert_type_eq_all!(libsignal_protocol::Context, Option<*mut std::ffi::c_void>);
 impl<'a> AsyncArgTypeInfo<'a> for *mut std::ffi::c_void {
     type ArgType = JsNull;
@@ -475,10 +757,26 @@ macro_rules! store {
 
 store!(IdentityKeyStore);
 store!(PreKeyStore);
+store!(ReplayCache);
+store!(SenderCertificateSource);
 store!(SenderKeyStore);
-store!(SessionStore);
 store!(SignedPreKeyStore);
 
+// Not part of store! because picking up the store's session cache can fail.
+impl<'a> AsyncArgTypeInfo<'a> for &'a mut dyn libsignal_protocol::SessionStore {
+    type ArgType = JsObject;
+    type StoredType = NodeSessionStore;
+    fn save_async_arg(
+        cx: &mut FunctionContext,
+        foreign: Handle<Self::ArgType>,
+    ) -> NeonResult<Self::StoredType> {
+        NodeSessionStore::new(cx, foreign)
+    }
+    fn load_async_arg(stored: &'a mut Self::StoredType) -> Self {
+        stored
+    }
+}
+
 impl<'a> ResultTypeInfo<'a> for bool {
     type ResultType = JsBoolean;
     fn convert_into(self, cx: &mut impl Context<'a>) -> NeonResult<Handle<'a, Self::ResultType>> {
@@ -503,6 +801,18 @@ impl<'a> ResultTypeInfo<'a> for u64 {
     }
 }
 
+/// Produces a JavaScript `Date`.
+impl<'a> ResultTypeInfo<'a> for crate::timestamp::Timestamp {
+    type ResultType = JsObject;
+    fn convert_into(self, cx: &mut impl Context<'a>) -> NeonResult<Handle<'a, Self::ResultType>> {
+        let millis = self.epoch_millis().convert_into(cx)?;
+        // JsDate requires N-API 5, so go through the global constructor instead.
+        let global = cx.global();
+        let date_constructor: Handle<JsFunction> = global.get(cx, "Date")?.downcast_or_throw(cx)?;
+        date_constructor.construct(cx, vec![millis])
+    }
+}
+
 impl<'a> ResultTypeInfo<'a> for String {
     type ResultType = JsString;
     fn convert_into(self, cx: &mut impl Context<'a>) -> NeonResult<Handle<'a, Self::ResultType>> {
@@ -528,19 +838,92 @@ impl<'a, T: ResultTypeInfo<'a>> ResultTypeInfo<'a> for Option<T> {
     }
 }
 
+/// Moves the bytes into an externally-backed Buffer rather than copying them.
 impl<'a> ResultTypeInfo<'a> for Vec<u8> {
     type ResultType = JsBuffer;
     fn convert_into(self, cx: &mut impl Context<'a>) -> NeonResult<Handle<'a, Self::ResultType>> {
-        let bytes_len = match u32::try_from(self.len()) {
-            Ok(l) => l,
-            Err(_) => return cx.throw_error("Cannot return very large object to JS environment"),
-        };
+        if u32::try_from(self.len()).is_err() {
+            return cx.throw_error("Cannot return very large object to JS environment");
+        }
+        if self.is_empty() {
+            // Don't bother registering a finalizer for nothing.
+            return cx.buffer(0);
+        }
+        Ok(JsBuffer::external(cx, self))
+    }
+}
 
-        let mut buffer = cx.buffer(bytes_len)?;
-        cx.borrow_mut(&mut buffer, |raw_buffer| {
-            raw_buffer.as_mut_slice().copy_from_slice(&self);
-        });
-        Ok(buffer)
+/// Produces an array of Buffers, moving the bytes of each as for `Vec<u8>`.
+impl<'a> ResultTypeInfo<'a> for Vec<Vec<u8>> {
+    type ResultType = JsArray;
+    fn convert_into(self, cx: &mut impl Context<'a>) -> NeonResult<Handle<'a, Self::ResultType>> {
+        let array = cx.empty_array();
+        for (i, buffer) in self.into_iter().enumerate() {
+            let buffer = buffer.convert_into(cx)?;
+            array.set(cx, i as u32, buffer)?;
+        }
+        Ok(array)
+    }
+}
+
+/// Produces an array of boxed values, one for each handle.
+impl<'a, T> ResultTypeInfo<'a> for Vec<T>
+where
+    T: crate::support::BridgeHandle + ResultTypeInfo<'a>,
+{
+    type ResultType = JsArray;
+    fn convert_into(self, cx: &mut impl Context<'a>) -> NeonResult<Handle<'a, Self::ResultType>> {
+        let array = cx.empty_array();
+        for (i, value) in self.into_iter().enumerate() {
+            let value = value.convert_into(cx)?;
+            array.set(cx, i as u32, value)?;
+        }
+        Ok(array)
+    }
+}
+
+/// Produces an array of objects, each with either a `message` handle or an `error`.
+///
+/// Unlike a top-level `Result`, the errors are returned rather than thrown.
+impl<'a> ResultTypeInfo<'a>
+    for Vec<Result<CiphertextMessage, libsignal_protocol::SignalProtocolError>>
+{
+    type ResultType = JsArray;
+    fn convert_into(self, cx: &mut impl Context<'a>) -> NeonResult<Handle<'a, Self::ResultType>> {
+        let array = cx.empty_array();
+        for (i, result) in self.into_iter().enumerate() {
+            let element = cx.empty_object();
+            match result {
+                Ok(message) => {
+                    let message = message.convert_into(cx)?;
+                    element.set(cx, "message", message)?;
+                }
+                Err(err) => {
+                    let error = err.into_js_error(cx)?;
+                    element.set(cx, "error", error)?;
+                }
+            }
+            array.set(cx, i as u32, element)?;
+        }
+        Ok(array)
+    }
+}
+
+/// Produces an array with a Buffer for each success and an Error for each failure.
+///
+/// Unlike a top-level `Result`, the errors are returned rather than thrown.
+impl<'a> ResultTypeInfo<'a> for Vec<Result<Vec<u8>, libsignal_protocol::SignalProtocolError>> {
+    type ResultType = JsArray;
+    fn convert_into(self, cx: &mut impl Context<'a>) -> NeonResult<Handle<'a, Self::ResultType>> {
+        let array = cx.empty_array();
+        for (i, result) in self.into_iter().enumerate() {
+            let element: Handle<JsValue> = match result {
+                Ok(buffer) => buffer.convert_into(cx)?.upcast(),
+                Err(err) => cx.error(err.to_string())?.upcast(),
+            };
+            array.set(cx, i as u32, element)?;
+        }
+        Ok(array)
     }
 }
 
@@ -551,8 +934,7 @@ impl<'a, T: ResultTypeInfo<'a>> ResultTypeInfo<'a>
     fn convert_into(self, cx: &mut impl Context<'a>) -> NeonResult<Handle<'a, Self::ResultType>> {
         match self {
             Ok(value) => value.convert_into(cx),
-            // FIXME: Use a dedicated Error type?
-            Err(err) => cx.throw_error(err.to_string()),
+            Err(err) => err.throw(cx),
         }
     }
 }
@@ -562,8 +944,7 @@ impl<'a, T: ResultTypeInfo<'a>> ResultTypeInfo<'a> for Result<T, device_transfer
     fn convert_into(self, cx: &mut impl Context<'a>) -> NeonResult<Handle<'a, Self::ResultType>> {
         match self {
             Ok(value) => value.convert_into(cx),
-            // FIXME: Use a dedicated Error type?
-            Err(err) => cx.throw_error(err.to_string()),
+            Err(err) => err.throw(cx),
         }
     }
 }
@@ -573,8 +954,37 @@ impl<'a, T: ResultTypeInfo<'a>> ResultTypeInfo<'a> for Result<T, signal_crypto::
     fn convert_into(self, cx: &mut impl Context<'a>) -> NeonResult<Handle<'a, Self::ResultType>> {
         match self {
             Ok(value) => value.convert_into(cx),
-            // FIXME: Use a dedicated Error type?
-            Err(err) => cx.throw_error(err.to_string()),
+            Err(err) => err.throw(cx),
+        }
+    }
+}
+
+impl<'a, T: ResultTypeInfo<'a>> ResultTypeInfo<'a> for Result<T, message_backup::Error> {
+    type ResultType = T::ResultType;
+    fn convert_into(self, cx: &mut impl Context<'a>) -> NeonResult<Handle<'a, Self::ResultType>> {
+        match self {
+            Ok(value) => value.convert_into(cx),
+            Err(err) => err.throw(cx),
+        }
+    }
+}
+
+impl<'a, T: ResultTypeInfo<'a>> ResultTypeInfo<'a> for Result<T, keytrans::Error> {
+    type ResultType = T::ResultType;
+    fn convert_into(self, cx: &mut impl Context<'a>) -> NeonResult<Handle<'a, Self::ResultType>> {
+        match self {
+            Ok(value) => value.convert_into(cx),
+            Err(err) => err.throw(cx),
+        }
+    }
+}
+
+impl<'a, T: ResultTypeInfo<'a>> ResultTypeInfo<'a> for Result<T, registration::Error> {
+    type ResultType = T::ResultType;
+    fn convert_into(self, cx: &mut impl Context<'a>) -> NeonResult<Handle<'a, Self::ResultType>> {
+        match self {
+            Ok(value) => value.convert_into(cx),
+            Err(err) => err.throw(cx),
         }
     }
 }
@@ -698,6 +1108,78 @@ impl<T: Send + Sync + 'static> Finalize for PersistentBoxedValue<T> {
 /// Implementation of [`bridge_handle`](crate::support::bridge_handle) for Node.
 macro_rules! node_bridge_handle {
     ( $typ:ty as false $(, $($_:tt)*)? ) => {};
+    ( $typ:ty as $node_name:ident, shared = true ) => {
+        // Shared handles box an `Arc<$typ>` rather than the value itself.
+        impl<'storage, 'context: 'storage> node::ArgTypeInfo<'storage, 'context>
+        for &'storage $typ {
+            type ArgType = node::JsObject;
+            type StoredType = node::Handle<'context, node::DefaultJsBox<std::sync::Arc<$typ>>>;
+            fn borrow(
+                cx: &mut node::FunctionContext<'context>,
+                foreign: node::Handle<'context, Self::ArgType>,
+            ) -> node::NeonResult<Self::StoredType> {
+                node::Object::get(*foreign, cx, node::NATIVE_HANDLE_PROPERTY)?.downcast_or_throw(cx)
+            }
+            fn load_from(
+                foreign: &'storage mut Self::StoredType,
+            ) -> Self {
+                &*foreign
+            }
+        }
+
+        paste! {
+            #[doc = "ts: interface " $typ " { readonly __type: unique symbol; }"]
+            impl<'a> node::ResultTypeInfo<'a> for $typ {
+                type ResultType = node::JsValue;
+                fn convert_into(
+                    self,
+                    cx: &mut impl node::Context<'a>,
+                ) -> node::NeonResult<node::Handle<'a, Self::ResultType>> {
+                    node::return_boxed_object(cx, Ok(std::sync::Arc::new(self)))
+                }
+            }
+        }
+
+        impl<'storage> node::AsyncArgTypeInfo<'storage> for &'storage $typ {
+            type ArgType = node::JsObject;
+            type StoredType = node::PersistentBoxedValue<node::DefaultFinalize<std::sync::Arc<$typ>>>;
+            fn save_async_arg(
+                cx: &mut node::FunctionContext,
+                foreign: node::Handle<Self::ArgType>,
+            ) -> node::NeonResult<Self::StoredType> {
+                node::PersistentBoxedValue::new(cx, foreign)
+            }
+            fn load_async_arg(
+                stored: &'storage mut Self::StoredType,
+            ) -> Self {
+                &*stored
+            }
+        }
+
+        paste! {
+            #[allow(non_snake_case)]
+            #[doc = "ts: export function " $node_name "_Clone(obj: Wrapper<" $typ ">): " $typ]
+            pub fn [<node_ $node_name _Clone>](
+                mut cx: node::FunctionContext
+            ) -> node::JsResult<node::JsValue> {
+                let wrapper = cx.argument::<node::JsObject>(0)?;
+                let boxed_value: node::Handle<node::DefaultJsBox<std::sync::Arc<$typ>>> =
+                    node::Object::get(*wrapper, &mut cx, node::NATIVE_HANDLE_PROPERTY)?
+                        .downcast_or_throw(&mut cx)?;
+                let shared = std::sync::Arc::clone(&***boxed_value);
+                node::return_boxed_object(&mut cx, Ok(shared))
+            }
+
+            node_register!([<$node_name _Clone>]);
+
+            bridge_registry_entry!(
+                [<signal_bridge_info_node_ $node_name _Clone>]: "node",
+                concat!(stringify!($node_name), "_Clone"),
+                [concat!("Wrapper<", stringify!($typ), ">")] -> stringify!($typ),
+                false
+            );
+        }
+    };
     ( $typ:ty as $node_name:ident ) => {
         impl<'storage, 'context: 'storage> node::ArgTypeInfo<'storage, 'context>
         for &'storage $typ {
@@ -819,9 +1301,67 @@ macro_rules! node_bridge_handle {
             }
         }
     };
-    ( $typ:ty $(, mut = $_:tt)?) => {
+    ( $typ:ty $(, mut = $_:tt)? $(, shared = $__:tt)? ) => {
         paste! {
-            node_bridge_handle!($typ as $typ $(, mut = $_)?);
+            node_bridge_handle!($typ as $typ $(, mut = $_)? $(, shared = $__)?);
+        }
+    };
+}
+
+/// Implementation of [`bridge_handle`](crate::support::bridge_handle)'s transfers between
+/// JavaScript contexts for Node. See [`start_transfer`](node::start_transfer).
+///
+/// Types with `clone = false` can't be copied, so they can only be used in the context (main thread
+/// or worker) that created them.
+macro_rules! node_bridge_transfer {
+    ( $typ:ty as false $(, $($_:tt)*)? ) => {};
+    ( $typ:ty as $node_name:ident, clone = false $(, $($_:tt)*)? ) => {};
+    ( $typ:ty as $node_name:ident $(, clone = true)?, shared = true ) => {
+        // Both contexts refer to the same value.
+        static_assertions::assert_impl_all!($typ: Send, Sync);
+        node_bridge_transfer!($typ as $node_name, std::sync::Arc<$typ>, |value| {
+            std::sync::Arc::clone(value)
+        });
+    };
+    ( $typ:ty as $node_name:ident $(, clone = true)?, mut = true ) => {
+        static_assertions::assert_impl_all!($typ: Send);
+        node_bridge_transfer!($typ as $node_name, std::cell::RefCell<$typ>, |value| {
+            std::cell::RefCell::new(value.borrow().clone())
+        });
+    };
+    ( $typ:ty as $node_name:ident $(, clone = true)? ) => {
+        static_assertions::assert_impl_all!($typ: Send);
+        node_bridge_transfer!($typ as $node_name, $typ, |value| value.clone());
+    };
+    ( $typ:ty as $node_name:ident, $stored:ty, |$value:ident| $copy:expr ) => {
+        paste! {
+            #[allow(non_snake_case)]
+            #[doc = "ts: export function " $node_name "_TransferToWorker(obj: Wrapper<" $typ ">): Transferred<" $typ ">"]
+            pub fn [<node_ $node_name _TransferToWorker>](
+                mut cx: node::FunctionContext
+            ) -> node::JsResult<node::JsValue> {
+                let wrapper = cx.argument::<node::JsObject>(0)?;
+                let boxed_value: node::Handle<node::DefaultJsBox<$stored>> =
+                    node::Object::get(*wrapper, &mut cx, node::NATIVE_HANDLE_PROPERTY)?
+                        .downcast_or_throw(&mut cx)?;
+                let $value: &$stored = &***boxed_value;
+                let token = node::start_transfer($copy);
+                Ok(node::Context::number(&mut cx, token as f64).upcast())
+            }
+
+            node_register!([<$node_name _TransferToWorker>]);
+
+            bridge_registry_entry!(
+                [<signal_bridge_info_node_ $node_name _TransferToWorker>]: "node",
+                concat!(stringify!($node_name), "_TransferToWorker"),
+                [concat!("Wrapper<", stringify!($typ), ">")] -> concat!("Transferred<", stringify!($typ), ">"),
+                false
+            );
+        }
+    };
+    ( $typ:ty $(, clone = $_clone:tt)? $(, mut = $_mut:tt)? $(, shared = $_shared:tt)? ) => {
+        paste! {
+            node_bridge_transfer!($typ as $typ $(, clone = $_clone)? $(, mut = $_mut)? $(, shared = $_shared)?);
         }
     };
 }
@@ -829,24 +1369,28 @@ macro_rules! node_bridge_handle {
 impl<'a> crate::support::Env for &'_ mut FunctionContext<'a> {
     type Buffer = JsResult<'a, JsBuffer>;
     fn buffer<'b, T: Into<Cow<'b, [u8]>>>(self, input: T) -> Self::Buffer {
-        let input = input.into();
+        let input = match input.into() {
+            Cow::Owned(vec) => return vec.convert_into(self),
+            Cow::Borrowed(slice) => slice,
+        };
         let len: u32 = input
             .len()
             .try_into()
             .or_else(|_| self.throw_error("buffer too large to return to JavaScript"))?;
         let mut result = Context::buffer(self, len)?;
-        self.borrow_mut(&mut result, |buf| {
-            buf.as_mut_slice().copy_from_slice(input.as_ref())
-        });
+        self.borrow_mut(&mut result, |buf| buf.as_mut_slice().copy_from_slice(input));
         Ok(result)
     }
 }
 
 /// A dummy type used to implement [`crate::support::Env`] for `async` `bridge_fn`s.
+///
+/// Owned buffers are passed through untouched, and are later moved into an externally-backed
+/// JavaScript Buffer when the promise is settled (see the `ResultTypeInfo` impl for `Vec<u8>`).
+/// Only borrowed buffers are copied, since they can't outlive the future that produced them.
 pub(crate) struct AsyncEnv;
 
 impl crate::support::Env for AsyncEnv {
-    // FIXME: Can we avoid this copy?
     type Buffer = Vec<u8>;
     fn buffer<'b, T: Into<Cow<'b, [u8]>>>(self, input: T) -> Self::Buffer {
         input.into().into_owned()
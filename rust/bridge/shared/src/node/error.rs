@@ -5,7 +5,9 @@
 
 use super::*;
 
+use std::cell::RefCell;
 use std::fmt;
+use std::mem::ManuallyDrop;
 
 /// Represents an error returned by a callback.
 #[derive(Debug)]
@@ -31,3 +33,326 @@ impl std::error::Error for CallbackError {}
 pub fn js_error_to_rust(func: &'static str, err: String) -> SignalProtocolError {
     SignalProtocolError::ApplicationCallbackError(func, Box::new(CallbackError::new(err)))
 }
+
+thread_local! {
+    /// The object passed to [`register_error_classes`], mapping class names to constructors.
+    ///
+    /// The root is never dropped through the thread-local destructor, since that has no
+    /// [`Context`] to drop it with; it is only released when replaced.
+    static ERROR_CLASSES: RefCell<Option<ManuallyDrop<Root<JsObject>>>> = RefCell::new(None);
+}
+
+/// Records the error classes that [`new_js_error`] should construct, keyed by class name.
+pub fn register_error_classes<'a>(cx: &mut impl Context<'a>, classes: Handle<'a, JsObject>) {
+    let classes = ManuallyDrop::new(classes.root(cx));
+    let previous = ERROR_CLASSES.with(|cell| cell.replace(Some(classes)));
+    if let Some(previous) = previous {
+        ManuallyDrop::into_inner(previous).drop(cx);
+    }
+}
+
+/// Creates an instance of the registered error class `class_name` with the given message.
+///
+/// Each of `fields` is then set as a property on the new error. If no class by that name has been
+/// registered, a plain `Error` is created instead, with its `name` set to `class_name`.
+pub fn new_js_error<'a>(
+    cx: &mut impl Context<'a>,
+    class_name: &str,
+    message: &str,
+    fields: &[(&str, Handle<'a, JsValue>)],
+) -> JsResult<'a, JsObject> {
+    let classes = ERROR_CLASSES.with(|cell| cell.borrow().as_ref().map(|root| root.to_inner(cx)));
+    let constructor = match classes {
+        Some(classes) => classes
+            .get(cx, class_name)?
+            .downcast::<JsFunction, _>(cx)
+            .ok(),
+        None => None,
+    };
+    let error = match constructor {
+        Some(constructor) => {
+            let message = cx.string(message);
+            constructor.construct(cx, vec![message])?
+        }
+        None => {
+            let error = cx.error(message)?;
+            let name = cx.string(class_name);
+            error.set(cx, "name", name)?;
+            error
+        }
+    };
+    for (field, value) in fields {
+        error.set(cx, *field, *value)?;
+    }
+    Ok(error)
+}
+
+/// Errors that can be thrown as instances of the classes passed to `registerErrorClasses`.
+pub trait SignalNodeError: Sized + fmt::Display {
+    /// Creates a JavaScript object for this error, by default a plain `Error`.
+    fn into_js_error<'a>(self, cx: &mut impl Context<'a>) -> JsResult<'a, JsObject> {
+        Ok(cx.error(self.to_string())?.upcast())
+    }
+
+    /// Throws this error as created by [`into_js_error`](Self::into_js_error).
+    fn throw<'a, T: Value>(self, cx: &mut impl Context<'a>) -> JsResult<'a, T> {
+        let error = self.into_js_error(cx)?;
+        cx.throw(error)
+    }
+}
+
+impl SignalNodeError for SignalProtocolError {
+    fn into_js_error<'a>(self, cx: &mut impl Context<'a>) -> JsResult<'a, JsObject> {
+        let message = self.to_string();
+        let action = match classify_decryption_failure(&self) {
+            DecryptionFailureAction::Retryable => "Retryable",
+            DecryptionFailureAction::NeedsNewSession => "NeedsNewSession",
+            DecryptionFailureAction::PermanentlyInvalid => "PermanentlyInvalid",
+            DecryptionFailureAction::StoreError => "StoreError",
+        };
+        let error = match self.into_root_cause() {
+            SignalProtocolError::InvalidRegistrationId(address, registration_id) => {
+                let address_name = cx.string(address.name()).upcast();
+                let device_id = cx.number(address.device_id()).upcast();
+                let registration_id = cx.number(registration_id).upcast();
+                new_js_error(
+                    cx,
+                    "InvalidRegistrationIdError",
+                    &message,
+                    &[
+                        ("addressName", address_name),
+                        ("deviceId", device_id),
+                        ("registrationId", registration_id),
+                    ],
+                )?
+            }
+            SignalProtocolError::InvalidSenderKeySession(name) => {
+                let group_id = name.group_id().expect("infallible");
+                let sender = name.sender().expect("infallible");
+                let group_id = cx.string(group_id).upcast();
+                let address_name = cx.string(sender.name()).upcast();
+                let device_id = cx.number(sender.device_id()).upcast();
+                new_js_error(
+                    cx,
+                    "InvalidSenderKeySessionError",
+                    &message,
+                    &[
+                        ("groupId", group_id),
+                        ("addressName", address_name),
+                        ("deviceId", device_id),
+                    ],
+                )?
+            }
+            SignalProtocolError::SealedSenderCertificateExpired(expiration) => {
+                let expiration = cx.number(expiration as f64).upcast();
+                new_js_error(
+                    cx,
+                    "SealedSenderCertificateExpiredError",
+                    &message,
+                    &[("expiration", expiration)],
+                )?
+            }
+            SignalProtocolError::DuplicatedMessage(chain_index, counter) => {
+                let chain_index = cx.number(chain_index).upcast();
+                let counter = cx.number(counter).upcast();
+                new_js_error(
+                    cx,
+                    "DuplicatedMessageError",
+                    &message,
+                    &[("chainIndex", chain_index), ("counter", counter)],
+                )?
+            }
+            SignalProtocolError::SealedSenderReplay => {
+                new_js_error(cx, "SealedSenderReplayError", &message, &[])?
+            }
+            SignalProtocolError::RecordLimitExceeded(limit, count) => {
+                let count = cx.number(count as f64).upcast();
+                let max = cx.number(limit.max() as f64).upcast();
+                new_js_error(
+                    cx,
+                    "RecordLimitExceededError",
+                    &message,
+                    &[("count", count), ("limit", max)],
+                )?
+            }
+            _ => cx.error(message)?.upcast(),
+        };
+        let action = cx.string(action);
+        error.set(cx, "decryptionFailureAction", action)?;
+        Ok(error)
+    }
+}
+
+impl SignalNodeError for signal_crypto::Error {
+    fn into_js_error<'a>(self, cx: &mut impl Context<'a>) -> JsResult<'a, JsObject> {
+        use signal_crypto::Error;
+
+        let message = self.to_string();
+        let error = match self {
+            Error::UnknownAlgorithm(algorithm_type, algorithm) => {
+                let algorithm_type = cx.string(algorithm_type).upcast();
+                let algorithm = cx.string(algorithm).upcast();
+                new_js_error(
+                    cx,
+                    "UnknownAlgorithmError",
+                    &message,
+                    &[("algorithmType", algorithm_type), ("algorithm", algorithm)],
+                )?
+            }
+            Error::InvalidKeySize(expected, actual) => {
+                new_size_error(cx, "InvalidKeySizeError", &message, expected, actual)?
+            }
+            Error::InvalidNonceSize(expected, actual) => {
+                new_size_error(cx, "InvalidNonceSizeError", &message, expected, actual)?
+            }
+            Error::InvalidInputSize => new_js_error(cx, "InvalidInputSizeError", &message, &[])?,
+            Error::InvalidTag => new_js_error(cx, "InvalidTagError", &message, &[])?,
+            Error::InvalidState => new_js_error(cx, "InvalidCryptoStateError", &message, &[])?,
+            Error::InvalidKdfParameters(_) => {
+                new_js_error(cx, "InvalidKdfParametersError", &message, &[])?
+            }
+            Error::InvalidWrappedKey => new_js_error(cx, "InvalidWrappedKeyError", &message, &[])?,
+            Error::InvalidKeyEncoding => {
+                new_js_error(cx, "InvalidKeyEncodingError", &message, &[])?
+            }
+        };
+        Ok(error)
+    }
+}
+
+impl SignalNodeError for crate::limits::BufferTooLarge {
+    fn into_js_error<'a>(self, cx: &mut impl Context<'a>) -> JsResult<'a, JsObject> {
+        let message = self.to_string();
+        let length = cx.number(self.len as f64).upcast();
+        let limit = cx.number(self.limit as f64).upcast();
+        new_js_error(
+            cx,
+            "BufferTooLargeError",
+            &message,
+            &[("length", length), ("limit", limit)],
+        )
+    }
+}
+
+fn new_size_error<'a>(
+    cx: &mut impl Context<'a>,
+    class_name: &str,
+    message: &str,
+    expected: usize,
+    actual: usize,
+) -> JsResult<'a, JsObject> {
+    let expected = cx.number(expected as f64).upcast();
+    let actual = cx.number(actual as f64).upcast();
+    new_js_error(
+        cx,
+        class_name,
+        message,
+        &[("expectedLength", expected), ("actualLength", actual)],
+    )
+}
+
+impl SignalNodeError for device_transfer::Error {
+    fn into_js_error<'a>(self, cx: &mut impl Context<'a>) -> JsResult<'a, JsObject> {
+        let message = self.to_string();
+        let error = match self {
+            device_transfer::Error::KeyDecodingFailed => {
+                new_js_error(cx, "KeyDecodingFailedError", &message, &[])?
+            }
+            device_transfer::Error::InternalError(_) => {
+                new_js_error(cx, "DeviceTransferInternalError", &message, &[])?
+            }
+        };
+        Ok(error)
+    }
+}
+
+impl SignalNodeError for message_backup::Error {
+    fn into_js_error<'a>(self, cx: &mut impl Context<'a>) -> JsResult<'a, JsObject> {
+        use message_backup::Error;
+
+        let message = self.to_string();
+        let error = match self {
+            Error::InvalidKeySize(expected, actual) => {
+                new_size_error(cx, "InvalidKeySizeError", &message, expected, actual)?
+            }
+            Error::Truncated => new_js_error(cx, "BackupTruncatedError", &message, &[])?,
+            Error::InvalidFrameLength(index)
+            | Error::InvalidMac(index)
+            | Error::InvalidCompression(index) => {
+                let index = cx.number(index as f64).upcast();
+                new_js_error(cx, "InvalidBackupError", &message, &[("frameIndex", index)])?
+            }
+            Error::UnknownFormat | Error::TrailingData => {
+                new_js_error(cx, "InvalidBackupError", &message, &[])?
+            }
+            Error::InvalidState => new_js_error(cx, "InvalidCryptoStateError", &message, &[])?,
+            Error::InvalidKdfParameters(_) => {
+                new_js_error(cx, "InvalidKdfParametersError", &message, &[])?
+            }
+            Error::InvalidRecord(_) => new_js_error(cx, "InvalidBackupRecordError", &message, &[])?,
+        };
+        Ok(error)
+    }
+}
+
+impl SignalNodeError for keytrans::Error {
+    fn into_js_error<'a>(self, cx: &mut impl Context<'a>) -> JsResult<'a, JsObject> {
+        use keytrans::Error;
+
+        let message = self.to_string();
+        let error = match self {
+            Error::InvalidResponse(_) | Error::VerificationFailed(_) => {
+                new_js_error(cx, "KeyTransparencyVerificationError", &message, &[])?
+            }
+            Error::NotMonitored => new_js_error(cx, "KeyNotMonitoredError", &message, &[])?,
+        };
+        Ok(error)
+    }
+}
+
+impl SignalNodeError for registration::Error {
+    fn into_js_error<'a>(self, cx: &mut impl Context<'a>) -> JsResult<'a, JsObject> {
+        use registration::Error;
+
+        let message = self.to_string();
+        let error = match self {
+            Error::RateLimited {
+                retry_after_seconds: Some(seconds),
+            } => {
+                let seconds = cx.number(seconds as f64).upcast();
+                new_js_error(
+                    cx,
+                    "RateLimitedError",
+                    &message,
+                    &[("retryAfterSecs", seconds)],
+                )?
+            }
+            Error::RateLimited {
+                retry_after_seconds: None,
+            } => new_js_error(cx, "RateLimitedError", &message, &[])?,
+            Error::SessionNotFound => {
+                new_js_error(cx, "RegistrationSessionNotFoundError", &message, &[])?
+            }
+            Error::InvalidSessionId
+            | Error::InvalidMasterKeySize(_)
+            | Error::InvalidResponse(_)
+            | Error::NotReadyForVerification
+            | Error::RequestRejected(_) => new_js_error(cx, "RegistrationError", &message, &[])?,
+        };
+        Ok(error)
+    }
+}
+
+/// Throws an `InternalError` for a panic in the bridge function `function`.
+///
+/// The error's `functionName` property is set to `function`.
+pub fn throw_panic<'a, T: Value>(
+    cx: &mut impl Context<'a>,
+    function: &str,
+    message: &str,
+) -> JsResult<'a, T> {
+    let message = format!("unexpected panic in {}: {}", function, message);
+    let function = cx.string(function).upcast();
+    let error = new_js_error(cx, "InternalError", &message, &[("functionName", function)])?;
+    cx.throw(error)
+}
@@ -4,7 +4,10 @@
 //
 
 use libsignal_protocol::*;
+use signal_neon_futures::ContextEx;
 use std::convert::TryFrom;
+use std::fmt;
+use std::future::Future;
 use std::ops::Deref;
 
 pub(crate) use neon::context::Context;
@@ -20,6 +23,8 @@ pub use error::*;
 mod storage;
 pub use storage::*;
 
+pub use crate::support::describe_panic;
+
 /// A function pointer referring to a Neon-based Node entry point.
 #[doc(hidden)]
 pub(crate) type JsFn = for<'a> fn(FunctionContext<'a>) -> JsResult<'a, JsValue>;
@@ -97,6 +102,58 @@ pub fn return_string<'a, T: AsRef<str>>(
     }
 }
 
+/// The result of a `fire_and_forget` entry point, which is logged rather than returned.
+pub trait DetachedResult {
+    /// Logs a failure, if any, on behalf of the entry point `name`.
+    fn log_failure(self, name: &str);
+}
+
+impl DetachedResult for () {
+    fn log_failure(self, _name: &str) {}
+}
+
+impl<E: fmt::Display> DetachedResult for Result<(), E> {
+    fn log_failure(self, name: &str) {
+        if let Err(e) = self {
+            log::error!("{} failed: {}", name, e);
+        }
+    }
+}
+
+/// Used to "send" a value from the JavaScript thread back to itself through the event queue.
+struct AssertSendSafe<T>(T);
+unsafe impl<T> Send for AssertSendSafe<T> {}
+
+/// Runs `future` on the JavaScript event queue without creating a Promise for its result.
+///
+/// Used in the implementation of `fire_and_forget` entry points. Errors and panics are logged on
+/// behalf of `name`. The future also produces a cleanup callback, which is run on the JavaScript
+/// thread once the result has been handled to finalize any stored arguments.
+pub fn run_detached<'a, R, F>(
+    cx: &mut FunctionContext<'a>,
+    name: &'static str,
+    future: impl Future<Output = (std::thread::Result<R>, F)> + 'static,
+) where
+    R: DetachedResult,
+    F: for<'b> FnOnce(&mut TaskContext<'b>) + 'static,
+{
+    let queue = cx.queue();
+    cx.run_future_on_queue(async move {
+        let (result, finalize) = future.await;
+        match result {
+            Ok(result) => result.log_failure(name),
+            Err(panic) => log::error!("{} panicked: {}", name, describe_panic(&panic)),
+        }
+        // The future runs on the queue associated with the current context, so `finalize` never
+        // actually leaves the JavaScript thread.
+        let finalize = AssertSendSafe(finalize);
+        queue.send(move |mut cx| {
+            (finalize.0)(&mut cx);
+            Ok(())
+        });
+    });
+}
+
 pub(crate) fn with_buffer_contents<R>(
     cx: &mut FunctionContext,
     buffer: Handle<JsBuffer>,
@@ -122,6 +179,38 @@ macro_rules! node_register {
     };
 }
 
+/// Implementation of [`bridge_serialize`](crate::support::bridge_serialize) for Node.
+macro_rules! node_bridge_serialize {
+    ( $typ:ident as false ) => {};
+    ( $typ:ident as $node_name:ident ) => {
+        paste! {
+            #[allow(non_snake_case)]
+            #[doc = "ts: export function " $node_name "_Serialize(obj: Wrapper<" $typ ">): Buffer"]
+            pub fn [<node_ $node_name _Serialize>](
+                mut cx: node::FunctionContext
+            ) -> node::JsResult<node::JsValue> {
+                let wrapper = cx.argument::<node::JsObject>(0)?;
+                let mut stored = <&$typ as node::ArgTypeInfo>::borrow(&mut cx, wrapper)?;
+                let obj = <&$typ as node::ArgTypeInfo>::load_from(&mut stored);
+                let serialized = crate::support::BridgeSerialize::bridge_serialize(obj);
+                Ok(node::ResultTypeInfo::convert_into(serialized, &mut cx)?.upcast())
+            }
+
+            node_register!([<$node_name _Serialize>]);
+
+            bridge_registry_entry!(
+                [<signal_bridge_info_node_ $node_name _Serialize>]: "node",
+                concat!(stringify!($node_name), "_Serialize"),
+                [concat!("Wrapper<", stringify!($typ), ">")] -> "Buffer",
+                false
+            );
+        }
+    };
+    ( $typ:ident ) => {
+        node_bridge_serialize!($typ as $typ);
+    };
+}
+
 /// Implementation of [`bridge_deserialize`](crate::support::bridge_deserialize) for Node.
 macro_rules! node_bridge_deserialize {
     ( $typ:ident::$fn:path as false ) => {};
@@ -139,9 +228,26 @@ macro_rules! node_bridge_deserialize {
             }
 
             node_register!([<$node_name _Deserialize>]);
+
+            bridge_registry_entry!(
+                [<signal_bridge_info_node_ $node_name _Deserialize>]: "node",
+                concat!(stringify!($node_name), "_Deserialize"),
+                ["Buffer"] -> stringify!($typ),
+                false
+            );
         }
     };
     ( $typ:ident::$fn:path ) => {
         node_bridge_deserialize!($typ::$fn as $typ);
     };
 }
+
+// Declared after the macros they use.
+mod runtime;
+pub use runtime::*;
+
+mod session_cache;
+pub(crate) use session_cache::*;
+
+mod transfer;
+pub use transfer::*;
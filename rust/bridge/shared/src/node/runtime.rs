@@ -0,0 +1,221 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Admission control and shutdown for async entry points.
+//!
+//! Async entry points run on the JavaScript event queue and keep their arguments (including rooted
+//! JavaScript objects, such as stores) until their promises are settled.
+//!
+//! - [`Native_InitRuntime`](node_Native_InitRuntime) limits how many of them may run at once.
+//!   Calls beyond the limit are either rejected or queued until a running operation finishes.
+//!
+//! - If the JavaScript environment is torn down while any of them are still running, they can
+//!   crash trying to use it. [`Native_Shutdown`](node_Native_Shutdown) stops new async operations
+//!   from starting and waits for the existing ones to finish, including finalizing their
+//!   arguments.
+
+use futures::channel::oneshot;
+use futures::future::{self, Either};
+use libsignal_bridge_macros::*;
+use once_cell::sync::Lazy;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::*;
+use crate::node;
+
+#[derive(Default)]
+struct OperationTracker {
+    /// The most operations that may run at once, or 0 for no limit.
+    max_running: usize,
+    /// The most operations that may wait for a slot; only used if `queue_when_busy` is set.
+    max_queued: usize,
+    /// Whether to queue operations beyond `max_running` rather than rejecting them.
+    queue_when_busy: bool,
+    shutting_down: bool,
+    /// Operations that have started and not yet finished, whether running or queued.
+    in_flight: usize,
+    running: usize,
+    /// Operations waiting for a slot, in order. Entries for abandoned operations are skipped.
+    queued: VecDeque<oneshot::Sender<()>>,
+    /// Notified when `in_flight` drops to zero.
+    idle_waiters: Vec<oneshot::Sender<()>>,
+}
+
+impl OperationTracker {
+    fn has_free_slot(&self) -> bool {
+        self.max_running == 0 || self.running < self.max_running
+    }
+
+    /// Hands free slots to queued operations.
+    fn admit_queued(&mut self) {
+        while self.has_free_slot() {
+            match self.queued.pop_front() {
+                Some(waiter) => {
+                    if waiter.send(()).is_ok() {
+                        self.running += 1;
+                    }
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+static TRACKER: Lazy<Mutex<OperationTracker>> = Lazy::new(Default::default);
+
+/// Tracks an async entry point from when it is called until its arguments are finalized.
+///
+/// Generated async entry points hold on to one of these so that they count towards the limits
+/// set by [`Native_InitRuntime`](node_Native_InitRuntime), and so that
+/// [`Native_Shutdown`](node_Native_Shutdown) waits for them.
+pub struct AsyncOperation {
+    /// Set while the operation is queued; completes when it may start running.
+    admission: Option<oneshot::Receiver<()>>,
+}
+
+// The receiver is only used to wait for admission, so a panic cannot leave it in a state that
+// matters to anyone else.
+impl std::panic::UnwindSafe for AsyncOperation {}
+
+impl AsyncOperation {
+    /// Registers a new async operation on behalf of the entry point `name`.
+    ///
+    /// Throws if shutdown has already started, or if the operation can neither run nor be queued.
+    pub fn begin<'a>(cx: &mut impl Context<'a>, name: &str) -> NeonResult<Self> {
+        let mut tracker = TRACKER.lock().expect("not poisoned");
+        if tracker.shutting_down {
+            drop(tracker);
+            return cx.throw_error(format!("cannot call {} after Native_Shutdown", name));
+        }
+        let admission = if tracker.has_free_slot() {
+            tracker.running += 1;
+            None
+        } else {
+            tracker.queued.retain(|waiter| !waiter.is_canceled());
+            if !tracker.queue_when_busy || tracker.queued.len() >= tracker.max_queued {
+                drop(tracker);
+                return cx.throw_error(format!(
+                    "cannot call {}: too many async operations in progress",
+                    name
+                ));
+            }
+            let (sender, receiver) = oneshot::channel();
+            tracker.queued.push_back(sender);
+            Some(receiver)
+        };
+        tracker.in_flight += 1;
+        Ok(Self { admission })
+    }
+
+    /// Waits until the operation may start running.
+    pub async fn admitted(&mut self) {
+        if let Some(admission) = &mut self.admission {
+            // Queued senders are only dropped once they have been sent to or the receiver is gone.
+            let _ = admission.await;
+            self.admission = None;
+        }
+    }
+}
+
+impl Drop for AsyncOperation {
+    fn drop(&mut self) {
+        let mut tracker = TRACKER.lock().expect("not poisoned");
+        let was_running = match self.admission.take() {
+            None => true,
+            // Check (and drop the receiver) while holding the lock, so that a slot can't be handed
+            // to this operation after it has been abandoned.
+            Some(mut admission) => matches!(admission.try_recv(), Ok(Some(()))),
+        };
+        if was_running {
+            tracker.running -= 1;
+            tracker.admit_queued();
+        }
+        tracker.in_flight -= 1;
+        if tracker.in_flight == 0 {
+            for waiter in tracker.idle_waiters.drain(..) {
+                // The receiver may have given up already.
+                let _ = waiter.send(());
+            }
+        }
+    }
+}
+
+/// Limits how many async operations may run at once.
+///
+/// With a `max_in_flight` of 0 (the default), there is no limit. Otherwise, further calls are
+/// rejected, unless `queue_when_busy` is set, in which case up to `max_queued` of them wait for a
+/// running operation to finish. Operations already running or queued are not affected, but queued
+/// operations are started right away if the new limit allows it.
+///
+/// There is no thread count to configure: async operations always run on the JavaScript thread
+/// that started them.
+#[bridge_fn(ffi = false, jni = false)]
+fn Native_InitRuntime(max_in_flight: u32, max_queued: u32, queue_when_busy: bool) {
+    let mut tracker = TRACKER.lock().expect("not poisoned");
+    tracker.max_running = max_in_flight as usize;
+    tracker.max_queued = max_queued as usize;
+    tracker.queue_when_busy = queue_when_busy;
+    tracker.admit_queued();
+}
+
+/// Stops new async operations from starting, and returns a receiver that completes once all
+/// current operations have finished.
+fn begin_shutdown() -> oneshot::Receiver<()> {
+    let (sender, receiver) = oneshot::channel();
+    let mut tracker = TRACKER.lock().expect("not poisoned");
+    tracker.shutting_down = true;
+    if tracker.in_flight == 0 {
+        let _ = sender.send(());
+    } else {
+        tracker.idle_waiters.push(sender);
+    }
+    receiver
+}
+
+/// Completes after `timeout` has elapsed.
+///
+/// The JavaScript event queue has no timers of its own, so this sleeps on a separate thread.
+fn timer(timeout: Duration) -> oneshot::Receiver<()> {
+    let (sender, receiver) = oneshot::channel();
+    std::thread::spawn(move || {
+        std::thread::sleep(timeout);
+        let _ = sender.send(());
+    });
+    receiver
+}
+
+/// Resolves to `true` once all in-flight async operations have finished, or `false` if
+/// `timeoutMs` elapses first.
+///
+/// Async entry points throw if they are called once this has been called, in any JavaScript
+/// context. There is no way to undo it, so it should only be used right before the process exits.
+#[allow(non_snake_case)]
+#[doc = "ts: export function Native_Shutdown(timeoutMs: number): Promise<boolean>"]
+pub fn node_Native_Shutdown(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let timeout_ms = cx.argument::<JsNumber>(0)?.value(&mut cx);
+    if timeout_ms.is_nan() || timeout_ms < 0.0 {
+        return cx.throw_range_error(format!("invalid timeout {}", timeout_ms));
+    }
+    let idle = begin_shutdown();
+    let timed_out = timer(Duration::from_millis(timeout_ms as u64));
+    let promise = signal_neon_futures::promise(
+        &mut cx,
+        std::panic::AssertUnwindSafe(async move {
+            let finished = matches!(future::select(idle, timed_out).await, Either::Left(_));
+            signal_neon_futures::settle_promise(move |cx| Ok(cx.boolean(finished)))
+        }),
+    )?;
+    Ok(promise.upcast())
+}
+
+node_register!(Native_Shutdown);
+bridge_registry_entry!(
+    signal_bridge_info_node_Native_Shutdown: "node",
+    "Native_Shutdown",
+    ["number"] -> "Promise<boolean>",
+    true
+);
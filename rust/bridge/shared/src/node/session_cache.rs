@@ -0,0 +1,162 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Opt-in caches of session records, layered over JavaScript session stores.
+//!
+//! Every session lookup otherwise costs a round trip through the JavaScript event queue. A
+//! JavaScript `SessionStore` opts in by creating a [`SessionCache`] of its own and keeping it in
+//! its `_sessionCache` property. Records are then kept after they are loaded from or stored to
+//! that store, up to a fixed number of addresses, and the least recently used ones are dropped
+//! first. Stores are write-through: the JavaScript store is always updated before the cache is.
+//!
+//! Each cache only ever sees its own store, so two stores with sessions for the same address (say,
+//! for two different local identities) never see each other's records. An app that changes a
+//! store's sessions any other way must invalidate the affected addresses or clear its cache.
+
+use libsignal_bridge_macros::*;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+use super::*;
+use crate::node;
+
+#[derive(Default)]
+struct SessionCacheState {
+    /// The most addresses to keep records for; 0 disables the cache.
+    capacity: usize,
+    /// Bumped whenever an entry is replaced or removed, so that loads that were already in
+    /// progress don't put back stale records.
+    generation: u64,
+    /// Incremented on each use, to find the least recently used entry.
+    clock: u64,
+    entries: HashMap<ProtocolAddress, (SessionRecord, u64)>,
+}
+
+impl SessionCacheState {
+    fn get(&mut self, address: &ProtocolAddress) -> Option<SessionRecord> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.entries.get_mut(address).map(|(record, last_used)| {
+            *last_used = clock;
+            record.clone()
+        })
+    }
+
+    fn insert(&mut self, address: ProtocolAddress, record: SessionRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.clock += 1;
+        self.entries.insert(address, (record, self.clock));
+        self.evict_to(self.capacity);
+    }
+
+    fn evict_to(&mut self, capacity: usize) {
+        while self.entries.len() > capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(address, _)| address.clone())
+                .expect("not empty");
+            self.entries.remove(&oldest);
+        }
+    }
+
+    fn invalidate(&mut self, address: &ProtocolAddress) {
+        self.generation += 1;
+        self.entries.remove(address);
+    }
+
+    fn clear(&mut self) {
+        self.generation += 1;
+        self.entries.clear();
+    }
+}
+
+/// The session records cached for a single JavaScript `SessionStore`.
+pub struct SessionCache(Mutex<SessionCacheState>);
+
+impl SessionCache {
+    fn state(&self) -> MutexGuard<'_, SessionCacheState> {
+        self.0.lock().expect("not poisoned")
+    }
+
+    /// Returns the cached record for `address`, if any.
+    pub(crate) fn get(&self, address: &ProtocolAddress) -> Option<SessionRecord> {
+        self.state().get(address)
+    }
+
+    /// Returns a token to pass to [`insert_loaded`](Self::insert_loaded) once a load from the
+    /// store completes.
+    pub(crate) fn generation(&self) -> u64 {
+        self.state().generation
+    }
+
+    /// Caches a record loaded from the store, unless the cache changed since `generation` was
+    /// obtained.
+    pub(crate) fn insert_loaded(
+        &self,
+        generation: u64,
+        address: ProtocolAddress,
+        record: SessionRecord,
+    ) {
+        let mut state = self.state();
+        if state.generation == generation {
+            state.insert(address, record);
+        }
+    }
+
+    /// Caches a record that has just been written to the store.
+    pub(crate) fn insert_stored(&self, address: ProtocolAddress, record: SessionRecord) {
+        let mut state = self.state();
+        state.generation += 1;
+        state.insert(address, record);
+    }
+
+    /// Drops the cached record for `address`, e.g. after a failed store.
+    pub(crate) fn invalidate(&self, address: &ProtocolAddress) {
+        self.state().invalidate(address)
+    }
+}
+
+// Only ever used from the JavaScript thread that created it, so it is never transferred.
+bridge_handle!(
+    SessionCache,
+    clone = false,
+    shared = true,
+    ffi = false,
+    jni = false
+);
+
+/// Creates a cache with room for `capacity` addresses; 0 leaves it disabled.
+#[bridge_fn(ffi = false, jni = false)]
+fn SessionCache_New(capacity: u32) -> SessionCache {
+    SessionCache(Mutex::new(SessionCacheState {
+        capacity: capacity as usize,
+        ..Default::default()
+    }))
+}
+
+/// Resizes the cache, or disables it if `capacity` is 0. Shrinking the cache drops the least
+/// recently used records.
+#[bridge_fn(ffi = false, jni = false)]
+fn SessionCache_SetCapacity(cache: &SessionCache, capacity: u32) {
+    let mut state = cache.state();
+    state.capacity = capacity as usize;
+    state.evict_to(state.capacity);
+}
+
+/// Drops the cached record for `address`, so that the next lookup goes to the JavaScript store.
+#[bridge_fn(ffi = false, jni = false)]
+fn SessionCache_Invalidate(cache: &SessionCache, address: &ProtocolAddress) {
+    cache.invalidate(address)
+}
+
+/// Drops every cached record.
+#[bridge_fn(ffi = false, jni = false)]
+fn SessionCache_Clear(cache: &SessionCache) {
+    cache.state().clear()
+}
@@ -10,6 +10,25 @@ use signal_neon_futures::*;
 use std::cell::RefCell;
 use std::sync::Arc;
 
+thread_local! {
+    /// Shared by every store created on this thread, so that lookups from concurrent operations can be coalesced.
+    static STORE_BATCHER: RefCell<Option<JsCallBatcher>> = RefCell::new(None);
+}
+
+fn store_batcher(cx: &mut FunctionContext) -> JsCallBatcher {
+    STORE_BATCHER.with(|batcher| {
+        batcher
+            .borrow_mut()
+            .get_or_insert_with(|| {
+                let mut queue = cx.queue();
+                // Don't keep the process alive just for the batcher; each store has its own queue for that.
+                queue.unref(cx);
+                JsCallBatcher::new(queue)
+            })
+            .clone()
+    })
+}
+
 pub struct NodePreKeyStore {
     js_queue: EventQueue,
     store_object: Arc<Root<JsObject>>,
@@ -23,7 +42,7 @@ impl NodePreKeyStore {
         }
     }
 
-    async fn do_get_pre_key(&self, id: u32) -> Result<PreKeyRecord, String> {
+    async fn do_get_pre_key(&self, id: u32) -> Result<Option<PreKeyRecord>, String> {
         let store_object_shared = self.store_object.clone();
         JsFuture::get_promise(&self.js_queue, move |cx| {
             let store_object = store_object_shared.to_inner(cx);
@@ -35,8 +54,14 @@ impl NodePreKeyStore {
         })
         .then(|cx, result| match result {
             Ok(value) => match value.downcast::<DefaultJsBox<PreKeyRecord>, _>(cx) {
-                Ok(obj) => Ok((***obj).clone()),
-                Err(_) => Err("result must be an object".to_owned()),
+                Ok(obj) => Ok(Some((***obj).clone())),
+                Err(_) => {
+                    if value.is_a::<JsNull, _>(cx) || value.is_a::<JsUndefined, _>(cx) {
+                        Ok(None)
+                    } else {
+                        Err("_getPreKey returned unexpected type".into())
+                    }
+                }
             },
             Err(error) => Err(error
                 .to_string(cx)
@@ -109,7 +134,8 @@ impl PreKeyStore for NodePreKeyStore {
     ) -> Result<PreKeyRecord, SignalProtocolError> {
         self.do_get_pre_key(pre_key_id)
             .await
-            .map_err(|s| js_error_to_rust("getPreKey", s))
+            .map_err(|s| js_error_to_rust("getPreKey", s))?
+            .ok_or(SignalProtocolError::InvalidPreKeyId)
     }
 
     async fn save_pre_key(
@@ -147,7 +173,7 @@ impl NodeSignedPreKeyStore {
         }
     }
 
-    async fn do_get_signed_pre_key(&self, id: u32) -> Result<SignedPreKeyRecord, String> {
+    async fn do_get_signed_pre_key(&self, id: u32) -> Result<Option<SignedPreKeyRecord>, String> {
         let store_object_shared = self.store_object.clone();
         JsFuture::get_promise(&self.js_queue, move |cx| {
             let store_object = store_object_shared.to_inner(cx);
@@ -159,8 +185,14 @@ impl NodeSignedPreKeyStore {
         })
         .then(|cx, result| match result {
             Ok(value) => match value.downcast::<DefaultJsBox<SignedPreKeyRecord>, _>(cx) {
-                Ok(obj) => Ok((***obj).clone()),
-                Err(_) => Err("result must be an object".to_owned()),
+                Ok(obj) => Ok(Some((***obj).clone())),
+                Err(_) => {
+                    if value.is_a::<JsNull, _>(cx) || value.is_a::<JsUndefined, _>(cx) {
+                        Ok(None)
+                    } else {
+                        Err("_getSignedPreKey returned unexpected type".into())
+                    }
+                }
             },
             Err(error) => Err(error
                 .to_string(cx)
@@ -219,7 +251,8 @@ impl SignedPreKeyStore for NodeSignedPreKeyStore {
     ) -> Result<SignedPreKeyRecord, SignalProtocolError> {
         self.do_get_signed_pre_key(signed_pre_key_id)
             .await
-            .map_err(|s| js_error_to_rust("getSignedPreKey", s))
+            .map_err(|s| js_error_to_rust("getSignedPreKey", s))?
+            .ok_or(SignalProtocolError::InvalidSignedPreKeyId)
     }
 
     async fn save_signed_pre_key(
@@ -236,44 +269,60 @@ impl SignedPreKeyStore for NodeSignedPreKeyStore {
 
 pub struct NodeSessionStore {
     js_queue: EventQueue,
+    batcher: JsCallBatcher,
     store_object: Arc<Root<JsObject>>,
+    /// The store's own cache, if it has enabled one; see [`SessionCache`].
+    cache: Option<Arc<SessionCache>>,
 }
 
 impl NodeSessionStore {
-    pub(crate) fn new(cx: &mut FunctionContext, store: Handle<JsObject>) -> Self {
-        Self {
+    pub(crate) fn new(cx: &mut FunctionContext, store: Handle<JsObject>) -> NeonResult<Self> {
+        let cache_wrapper = store.get(cx, "_sessionCache")?;
+        let cache = match cache_wrapper.downcast::<JsObject, _>(cx) {
+            Ok(cache_wrapper) => {
+                let cache: Handle<DefaultJsBox<Arc<SessionCache>>> = cache_wrapper
+                    .get(cx, NATIVE_HANDLE_PROPERTY)?
+                    .downcast_or_throw(cx)?;
+                Some(Arc::clone(&***cache))
+            }
+            // Null or undefined.
+            Err(_) => None,
+        };
+        Ok(Self {
             js_queue: cx.queue(),
+            batcher: store_batcher(cx),
             store_object: Arc::new(store.root(cx)),
-        }
+            cache,
+        })
     }
 
     async fn do_get_session(&self, name: ProtocolAddress) -> Result<Option<SessionRecord>, String> {
-        let store_object_shared = self.store_object.clone();
-        JsFuture::get_promise(&self.js_queue, move |cx| {
-            let store_object = store_object_shared.to_inner(cx);
-            let name: Handle<JsValue> = name.convert_into(cx)?;
-            let result = call_method(cx, store_object, "_getSession", vec![name])?;
-            let result = result.downcast_or_throw(cx)?;
-            store_object_shared.finalize(cx);
-            Ok(result)
-        })
-        .then(|cx, result| match result {
-            Ok(value) => match value.downcast::<DefaultJsBox<RefCell<SessionRecord>>, _>(cx) {
-                Ok(obj) => Ok(Some((***obj).borrow().clone())),
-                Err(_) => {
-                    if value.is_a::<JsNull, _>(cx) || value.is_a::<JsUndefined, _>(cx) {
-                        Ok(None)
-                    } else {
-                        Err("_getSession returned unexpected type".into())
+        self.batcher
+            .call(
+                self.store_object.clone(),
+                "_getSession",
+                "_getSessions",
+                move |cx| name.convert_into(cx),
+                |cx, result| match result {
+                    Ok(value) => {
+                        match value.downcast::<DefaultJsBox<RefCell<SessionRecord>>, _>(cx) {
+                            Ok(obj) => Ok(Some((***obj).borrow().clone())),
+                            Err(_) => {
+                                if value.is_a::<JsNull, _>(cx) || value.is_a::<JsUndefined, _>(cx) {
+                                    Ok(None)
+                                } else {
+                                    Err("_getSession returned unexpected type".into())
+                                }
+                            }
+                        }
                     }
-                }
-            },
-            Err(error) => Err(error
-                .to_string(cx)
-                .expect("can convert to string")
-                .value(cx)),
-        })
-        .await
+                    Err(error) => Err(error
+                        .to_string(cx)
+                        .expect("can convert to string")
+                        .value(cx)),
+                },
+            )
+            .await
     }
 
     async fn do_save_session(
@@ -323,9 +372,27 @@ impl SessionStore for NodeSessionStore {
         name: &ProtocolAddress,
         _ctx: libsignal_protocol::Context,
     ) -> Result<Option<SessionRecord>, SignalProtocolError> {
-        self.do_get_session(name.clone())
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => {
+                return self
+                    .do_get_session(name.clone())
+                    .await
+                    .map_err(|s| js_error_to_rust("getSession", s));
+            }
+        };
+        if let Some(record) = cache.get(name) {
+            return Ok(Some(record));
+        }
+        let generation = cache.generation();
+        let record = self
+            .do_get_session(name.clone())
             .await
-            .map_err(|s| js_error_to_rust("getSession", s))
+            .map_err(|s| js_error_to_rust("getSession", s))?;
+        if let Some(record) = &record {
+            cache.insert_loaded(generation, name.clone(), record.clone());
+        }
+        Ok(record)
     }
 
     async fn store_session(
@@ -334,14 +401,21 @@ impl SessionStore for NodeSessionStore {
         record: &SessionRecord,
         _ctx: libsignal_protocol::Context,
     ) -> Result<(), SignalProtocolError> {
-        self.do_save_session(name.clone(), record.clone())
-            .await
-            .map_err(|s| js_error_to_rust("saveSession", s))
+        let result = self.do_save_session(name.clone(), record.clone()).await;
+        if let Some(cache) = &self.cache {
+            match &result {
+                Ok(()) => cache.insert_stored(name.clone(), record.clone()),
+                // The store may or may not have been updated.
+                Err(_) => cache.invalidate(name),
+            }
+        }
+        result.map_err(|s| js_error_to_rust("saveSession", s))
     }
 }
 
 pub struct NodeIdentityKeyStore {
     js_queue: EventQueue,
+    batcher: JsCallBatcher,
     store_object: Arc<Root<JsObject>>,
 }
 
@@ -349,6 +423,7 @@ impl NodeIdentityKeyStore {
     pub(crate) fn new(cx: &mut FunctionContext, store: Handle<JsObject>) -> Self {
         Self {
             js_queue: cx.queue(),
+            batcher: store_batcher(cx),
             store_object: Arc::new(store.root(cx)),
         }
     }
@@ -398,32 +473,30 @@ impl NodeIdentityKeyStore {
     }
 
     async fn do_get_identity(&self, name: ProtocolAddress) -> Result<Option<PublicKey>, String> {
-        let store_object_shared = self.store_object.clone();
-        JsFuture::get_promise(&self.js_queue, move |cx| {
-            let store_object = store_object_shared.to_inner(cx);
-            let name: Handle<JsValue> = name.convert_into(cx)?;
-            let result = call_method(cx, store_object, "_getIdentity", vec![name])?;
-            let result = result.downcast_or_throw(cx)?;
-            store_object_shared.finalize(cx);
-            Ok(result)
-        })
-        .then(|cx, result| match result {
-            Ok(value) => match value.downcast::<DefaultJsBox<PublicKey>, _>(cx) {
-                Ok(obj) => Ok(Some(***obj)),
-                Err(_) => {
-                    if value.is_a::<JsNull, _>(cx) {
-                        Ok(None)
-                    } else {
-                        Err("result must be an object".to_owned())
-                    }
-                }
-            },
-            Err(error) => Err(error
-                .to_string(cx)
-                .expect("can convert to string")
-                .value(cx)),
-        })
-        .await
+        self.batcher
+            .call(
+                self.store_object.clone(),
+                "_getIdentity",
+                "_getIdentities",
+                move |cx| name.convert_into(cx),
+                |cx, result| match result {
+                    Ok(value) => match value.downcast::<DefaultJsBox<PublicKey>, _>(cx) {
+                        Ok(obj) => Ok(Some(***obj)),
+                        Err(_) => {
+                            if value.is_a::<JsNull, _>(cx) {
+                                Ok(None)
+                            } else {
+                                Err("result must be an object".to_owned())
+                            }
+                        }
+                    },
+                    Err(error) => Err(error
+                        .to_string(cx)
+                        .expect("can convert to string")
+                        .value(cx)),
+                },
+            )
+            .await
     }
 
     async fn do_save_identity(
@@ -584,8 +657,8 @@ impl NodeSenderKeyStore {
             Ok(result)
         })
         .then(|cx, result| match result {
-            Ok(value) => match value.downcast::<DefaultJsBox<SenderKeyRecord>, _>(cx) {
-                Ok(obj) => Ok(Some((***obj).clone())),
+            Ok(value) => match value.downcast::<DefaultJsBox<RefCell<SenderKeyRecord>>, _>(cx) {
+                Ok(obj) => Ok(Some((***obj).borrow().clone())),
                 Err(_) => {
                     if value.is_a::<JsNull, _>(cx) {
                         Ok(None)
@@ -660,3 +733,155 @@ impl SenderKeyStore for NodeSenderKeyStore {
             .map_err(|s| js_error_to_rust("saveSenderKey", s))
     }
 }
+
+pub struct NodeReplayCache {
+    js_queue: EventQueue,
+    store_object: Arc<Root<JsObject>>,
+}
+
+impl NodeReplayCache {
+    pub(crate) fn new(cx: &mut FunctionContext, store: Handle<JsObject>) -> Self {
+        Self {
+            js_queue: cx.queue(),
+            store_object: Arc::new(store.root(cx)),
+        }
+    }
+
+    async fn do_contains_message(&self, message_id: [u8; 32], now: u64) -> Result<bool, String> {
+        let store_object_shared = self.store_object.clone();
+        JsFuture::get_promise(&self.js_queue, move |cx| {
+            let store_object = store_object_shared.to_inner(cx);
+            let message_id: Handle<JsValue> = message_id.to_vec().convert_into(cx)?.upcast();
+            let now = cx.number(now as f64).upcast();
+            let result = call_method(cx, store_object, "_containsMessage", vec![message_id, now])?
+                .downcast_or_throw(cx)?;
+            store_object_shared.finalize(cx);
+            Ok(result)
+        })
+        .then(|cx, result| match result {
+            Ok(value) => match value.downcast::<JsBoolean, _>(cx) {
+                Ok(b) => Ok(b.value(cx)),
+                Err(_) => Err("unexpected result from _containsMessage".into()),
+            },
+            Err(error) => Err(error
+                .to_string(cx)
+                .expect("can convert to string")
+                .value(cx)),
+        })
+        .await
+    }
+
+    async fn do_record_message(&self, message_id: [u8; 32], expiration: u64) -> Result<(), String> {
+        let store_object_shared = self.store_object.clone();
+        JsFuture::get_promise(&self.js_queue, move |cx| {
+            let store_object = store_object_shared.to_inner(cx);
+            let message_id: Handle<JsValue> = message_id.to_vec().convert_into(cx)?.upcast();
+            let expiration = cx.number(expiration as f64).upcast();
+            let result = call_method(
+                cx,
+                store_object,
+                "_recordMessage",
+                vec![message_id, expiration],
+            )?
+            .downcast_or_throw(cx)?;
+            store_object_shared.finalize(cx);
+            Ok(result)
+        })
+        .then(|cx, result| match result {
+            Ok(value) => match value.downcast::<JsUndefined, _>(cx) {
+                Ok(_) => Ok(()),
+                Err(_) => Err("unexpected result from _recordMessage".into()),
+            },
+            Err(error) => Err(error
+                .to_string(cx)
+                .expect("can convert to string")
+                .value(cx)),
+        })
+        .await
+    }
+}
+
+impl Finalize for NodeReplayCache {
+    fn finalize<'a, C: Context<'a>>(self, cx: &mut C) {
+        self.store_object.finalize(cx)
+    }
+}
+
+#[async_trait(?Send)]
+impl ReplayCache for NodeReplayCache {
+    async fn contains(
+        &self,
+        message_id: &[u8; 32],
+        now: u64,
+        _ctx: libsignal_protocol::Context,
+    ) -> Result<bool, SignalProtocolError> {
+        self.do_contains_message(*message_id, now)
+            .await
+            .map_err(|s| js_error_to_rust("containsMessage", s))
+    }
+
+    async fn record(
+        &mut self,
+        message_id: &[u8; 32],
+        expiration: u64,
+        _ctx: libsignal_protocol::Context,
+    ) -> Result<(), SignalProtocolError> {
+        self.do_record_message(*message_id, expiration)
+            .await
+            .map_err(|s| js_error_to_rust("recordMessage", s))
+    }
+}
+
+pub struct NodeSenderCertificateSource {
+    js_queue: EventQueue,
+    store_object: Arc<Root<JsObject>>,
+}
+
+impl NodeSenderCertificateSource {
+    pub(crate) fn new(cx: &mut FunctionContext, store: Handle<JsObject>) -> Self {
+        Self {
+            js_queue: cx.queue(),
+            store_object: Arc::new(store.root(cx)),
+        }
+    }
+
+    async fn do_fetch_sender_certificate(&self) -> Result<SenderCertificate, String> {
+        let store_object_shared = self.store_object.clone();
+        JsFuture::get_promise(&self.js_queue, move |cx| {
+            let store_object = store_object_shared.to_inner(cx);
+            let result = call_method(cx, store_object, "_fetchSenderCertificate", vec![])?
+                .downcast_or_throw(cx)?;
+            store_object_shared.finalize(cx);
+            Ok(result)
+        })
+        .then(|cx, result| match result {
+            Ok(value) => match value.downcast::<DefaultJsBox<SenderCertificate>, _>(cx) {
+                Ok(obj) => Ok((***obj).clone()),
+                Err(_) => Err("_fetchSenderCertificate returned unexpected type".into()),
+            },
+            Err(error) => Err(error
+                .to_string(cx)
+                .expect("can convert to string")
+                .value(cx)),
+        })
+        .await
+    }
+}
+
+impl Finalize for NodeSenderCertificateSource {
+    fn finalize<'a, C: Context<'a>>(self, cx: &mut C) {
+        self.store_object.finalize(cx)
+    }
+}
+
+#[async_trait(?Send)]
+impl SenderCertificateSource for NodeSenderCertificateSource {
+    async fn fetch_sender_certificate(
+        &mut self,
+        _ctx: libsignal_protocol::Context,
+    ) -> Result<SenderCertificate, SignalProtocolError> {
+        self.do_fetch_sender_certificate()
+            .await
+            .map_err(|s| js_error_to_rust("fetchSenderCertificate", s))
+    }
+}
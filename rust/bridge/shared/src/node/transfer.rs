@@ -0,0 +1,76 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Moving boxed values between JavaScript contexts.
+//!
+//! The main thread and each worker thread have separate JavaScript heaps, and a [`JsBox`] can only
+//! be used in the context that created it. To use a value in another context, it is copied out of
+//! its box by a `Foo_TransferToWorker` function, which returns a token that can be posted to the
+//! other context. There, [`ReceiveTransferredHandle`](node_ReceiveTransferredHandle) puts the copy
+//! in a new box. Each token can only be received once, and only within [`TRANSFER_TIMEOUT`]; after
+//! that the copy is dropped, so that a token that is never received doesn't keep its value alive.
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::*;
+use crate::node;
+use crate::support::paste;
+
+/// Boxes a transferred value in the receiving context.
+type PendingTransfer =
+    Box<dyn for<'a> FnOnce(&mut FunctionContext<'a>) -> JsResult<'a, JsValue> + Send>;
+
+/// How long a transferred value waits to be received before it is dropped.
+pub const TRANSFER_TIMEOUT: Duration = Duration::from_secs(60);
+
+static PENDING_TRANSFERS: Lazy<Mutex<HashMap<u64, (PendingTransfer, Instant)>>> =
+    Lazy::new(Default::default);
+static NEXT_TRANSFER_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Holds on to `value` until it is received in another context, returning the token to receive it.
+///
+/// Values that have been waiting longer than [`TRANSFER_TIMEOUT`] are dropped here.
+///
+/// `value` should have the same type as the storage of the original box (e.g. an `Arc` for shared
+/// handles), so that the new box can be used in its place.
+pub fn start_transfer<T: 'static + Send>(value: T) -> u64 {
+    let id = NEXT_TRANSFER_ID.fetch_add(1, Ordering::Relaxed);
+    let pending: PendingTransfer = Box::new(move |cx| return_boxed_object(cx, Ok(value)));
+    let now = Instant::now();
+    let mut pending_transfers = PENDING_TRANSFERS.lock().expect("not poisoned");
+    pending_transfers.retain(|_, (_, started)| now.duration_since(*started) < TRANSFER_TIMEOUT);
+    pending_transfers.insert(id, (pending, now));
+    id
+}
+
+#[allow(non_snake_case)]
+#[doc = "ts: export function ReceiveTransferredHandle<T>(token: Transferred<T>): T"]
+pub fn node_ReceiveTransferredHandle(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let token = cx.argument::<JsNumber>(0)?.value(&mut cx);
+    let pending = PENDING_TRANSFERS
+        .lock()
+        .expect("not poisoned")
+        .remove(&(token as u64))
+        .filter(|(_, started)| started.elapsed() < TRANSFER_TIMEOUT);
+    match pending {
+        Some((pending, _)) => pending(&mut cx),
+        None => cx.throw_error(format!(
+            "no handle is waiting to be received for {} (it may have expired)",
+            token
+        )),
+    }
+}
+
+node_register!(ReceiveTransferredHandle);
+bridge_registry_entry!(
+    signal_bridge_info_node_ReceiveTransferredHandle: "node",
+    "ReceiveTransferredHandle",
+    ["Transferred<T>"] -> "T",
+    false
+);
@@ -7,31 +7,51 @@ use libsignal_bridge_macros::*;
 use libsignal_protocol::error::Result;
 use libsignal_protocol::*;
 use static_assertions::const_assert_eq;
-use std::convert::TryFrom;
+use std::convert::{TryFrom, TryInto};
+use std::sync::Mutex;
 
+use crate::rng::{CallRng, Rng};
 use crate::support::*;
+use crate::timestamp::Timestamp;
 use crate::*;
 
+bridge_handle!(AttachmentPointer);
 bridge_handle!(CiphertextMessage, clone = false, jni = false);
+bridge_handle!(Content);
+bridge_handle!(DataMessageBuilder, clone = false, mut = true);
+bridge_handle!(DeferredDecryptionResult, clone = false);
 bridge_handle!(Fingerprint, jni = NumericFingerprintGenerator);
+bridge_handle!(NotificationDecryptionResult, clone = false);
+bridge_handle!(OutgoingEnvelope, serialize = true);
+bridge_handle!(PaddingPolicy);
 bridge_handle!(PreKeyBundle);
 bridge_handle!(PreKeyRecord);
 bridge_handle!(PreKeySignalMessage);
 bridge_handle!(PrivateKey, ffi = privatekey, jni = ECPrivateKey);
 bridge_handle!(ProtocolAddress, ffi = address);
+bridge_handle!(ProvisioningCipher, clone = false);
+bridge_handle!(ProvisioningUuid);
 bridge_handle!(PublicKey, ffi = publickey, jni = ECPublicKey);
 bridge_handle!(SenderCertificate);
+bridge_handle!(SenderCertificateProvider, clone = false);
 bridge_handle!(SenderKeyDistributionMessage);
 bridge_handle!(SenderKeyMessage);
 bridge_handle!(SenderKeyName);
-bridge_handle!(SenderKeyRecord);
+bridge_handle!(SenderKeyRecord, mut = true);
 bridge_handle!(ServerCertificate);
-bridge_handle!(SessionRecord, mut = true);
+bridge_handle!(SessionRecord, serialize = true, mut = true);
 bridge_handle!(SignalMessage, ffi = message);
 bridge_handle!(SignedPreKeyRecord);
 bridge_handle!(UnidentifiedSenderMessage, ffi = false, node = false);
 bridge_handle!(UnidentifiedSenderMessageContent, clone = false);
-bridge_handle!(SealedSenderDecryptionResult, ffi = false, jni = false);
+bridge_handle!(
+    SealedSenderDecryptionResult,
+    clone = false,
+    ffi = false,
+    jni = false
+);
+#[cfg(feature = "node")]
+bridge_handle!(BacklogDecryptor, clone = false, ffi = false, jni = false);
 
 #[bridge_fn_buffer(ffi = false)]
 fn HKDF_DeriveSecrets<E: Env>(
@@ -118,8 +138,8 @@ bridge_get_bytearray!(
 );
 
 #[bridge_fn(ffi = "privatekey_generate", node = "PrivateKey_Generate")]
-fn ECPrivateKey_Generate() -> PrivateKey {
-    let mut rng = rand::rngs::OsRng;
+fn ECPrivateKey_Generate(rng: Option<&Rng>) -> PrivateKey {
+    let mut rng = CallRng::new(rng);
     let keypair = KeyPair::generate(&mut rng);
     keypair.private_key
 }
@@ -130,8 +150,13 @@ fn ECPrivateKey_GetPublicKey(k: &PrivateKey) -> Result<PublicKey> {
 }
 
 #[bridge_fn_buffer(ffi = "privatekey_sign", node = "PrivateKey_Sign")]
-fn ECPrivateKey_Sign<T: Env>(env: T, key: &PrivateKey, message: &[u8]) -> Result<T::Buffer> {
-    let mut rng = rand::rngs::OsRng;
+fn ECPrivateKey_Sign<T: Env>(
+    env: T,
+    key: &PrivateKey,
+    message: &[u8],
+    rng: Option<&Rng>,
+) -> Result<T::Buffer> {
+    let mut rng = CallRng::new(rng);
     let sig = key.calculate_signature(&message, &mut rng)?;
     Ok(env.buffer(sig.into_vec()))
 }
@@ -146,6 +171,22 @@ fn ECPrivateKey_Agree<T: Env>(
     Ok(env.buffer(dh_secret.into_vec()))
 }
 
+#[bridge_fn_buffer(ffi = "publickey_seal", node = "PublicKey_Seal")]
+fn ECPublicKey_Seal<T: Env>(
+    env: T,
+    key: &PublicKey,
+    plaintext: &[u8],
+    rng: Option<&Rng>,
+) -> Result<T::Buffer> {
+    let mut rng = CallRng::new(rng);
+    Ok(env.buffer(key.seal(plaintext, &mut rng)?))
+}
+
+#[bridge_fn_buffer(ffi = "privatekey_open", node = "PrivateKey_Open")]
+fn ECPrivateKey_Open<T: Env>(env: T, key: &PrivateKey, sealed: &[u8]) -> Result<T::Buffer> {
+    Ok(env.buffer(key.open(sealed)?))
+}
+
 #[bridge_fn_buffer(ffi = "identitykeypair_serialize")]
 fn IdentityKeyPair_Serialize<T: Env>(
     env: T,
@@ -156,6 +197,286 @@ fn IdentityKeyPair_Serialize<T: Env>(
     Ok(env.buffer(identity_key_pair.serialize().into_vec()))
 }
 
+#[bridge_fn_buffer]
+fn ProfileKey_DeriveAccessKey<T: Env>(env: T, profile_key: &[u8]) -> Result<T::Buffer> {
+    Ok(env.buffer(derive_access_key(profile_key)?.to_vec()))
+}
+
+#[bridge_fn_buffer]
+fn ProfileKey_BlindServiceId<T: Env>(
+    env: T,
+    profile_key: &[u8],
+    service_id: String,
+) -> Result<T::Buffer> {
+    Ok(env.buffer(blind_service_id(profile_key, &service_id)?.to_vec()))
+}
+
+#[bridge_fn]
+fn PaddingPolicy_None() -> PaddingPolicy {
+    PaddingPolicy::None
+}
+
+#[bridge_fn]
+fn PaddingPolicy_Bucketed(bucket_size: u32) -> Result<PaddingPolicy> {
+    PaddingPolicy::bucketed(bucket_size as usize)
+}
+
+#[bridge_fn]
+fn PaddingPolicy_Padme() -> PaddingPolicy {
+    PaddingPolicy::Padme
+}
+
+#[bridge_fn]
+fn PaddingPolicy_PaddedLength(policy: &PaddingPolicy, len: u32) -> Result<u32> {
+    let padded_len = policy.padded_len(len as usize)?;
+    u32::try_from(padded_len).map_err(|_| {
+        SignalProtocolError::InvalidArgument("padded length does not fit in 32 bits".into())
+    })
+}
+
+#[bridge_fn_buffer]
+fn PaddingPolicy_Pad<E: Env>(env: E, policy: &PaddingPolicy, message: &[u8]) -> Result<E::Buffer> {
+    Ok(env.buffer(policy.pad(message)?))
+}
+
+#[bridge_fn_buffer]
+fn PaddingPolicy_Unpad<E: Env>(env: E, policy: &PaddingPolicy, padded: &[u8]) -> Result<E::Buffer> {
+    Ok(env.buffer(policy.unpad(padded)?))
+}
+
+/// `width`, `height`, and `upload_timestamp` are 0 if unknown.
+#[bridge_fn]
+fn AttachmentPointer_New(
+    cdn_number: u32,
+    cdn_key: String,
+    content_type: String,
+    key: &[u8],
+    digest: &[u8],
+    size: u32,
+    file_name: Option<String>,
+    caption: Option<String>,
+    width: u32,
+    height: u32,
+    upload_timestamp: Timestamp,
+) -> Result<AttachmentPointer> {
+    let mut attachment =
+        AttachmentPointer::new(cdn_number, cdn_key, content_type, key, digest, size)?;
+    if let Some(file_name) = file_name {
+        attachment = attachment.with_file_name(file_name);
+    }
+    if let Some(caption) = caption {
+        attachment = attachment.with_caption(caption);
+    }
+    if width != 0 || height != 0 {
+        attachment = attachment.with_dimensions(width, height);
+    }
+    if upload_timestamp.epoch_millis() != 0 {
+        attachment = attachment.with_upload_timestamp(upload_timestamp.epoch_millis());
+    }
+    Ok(attachment)
+}
+
+#[bridge_fn]
+fn DataMessageBuilder_New(timestamp: Timestamp) -> DataMessageBuilder {
+    DataMessageBuilder::new(timestamp.epoch_millis())
+}
+
+#[bridge_fn_void]
+fn DataMessageBuilder_SetBody(builder: &mut DataMessageBuilder, body: String) -> Result<()> {
+    builder.set_body(body)?;
+    Ok(())
+}
+
+#[bridge_fn_void]
+fn DataMessageBuilder_AddAttachment(
+    builder: &mut DataMessageBuilder,
+    attachment: &AttachmentPointer,
+) -> Result<()> {
+    builder.add_attachment(attachment.clone())?;
+    Ok(())
+}
+
+#[bridge_fn_void]
+fn DataMessageBuilder_SetQuote(
+    builder: &mut DataMessageBuilder,
+    id: Timestamp,
+    author_uuid: String,
+    text: Option<String>,
+) -> Result<()> {
+    builder.set_quote(id.epoch_millis(), author_uuid, text)?;
+    Ok(())
+}
+
+#[bridge_fn_void]
+fn DataMessageBuilder_SetExpireTimer(builder: &mut DataMessageBuilder, seconds: u32) {
+    builder.set_expire_timer(seconds);
+}
+
+#[bridge_fn_void]
+fn DataMessageBuilder_SetProfileKey(
+    builder: &mut DataMessageBuilder,
+    profile_key: &[u8],
+) -> Result<()> {
+    builder.set_profile_key(profile_key)?;
+    Ok(())
+}
+
+#[bridge_fn]
+fn DataMessageBuilder_Build(builder: &DataMessageBuilder) -> Result<Content> {
+    builder.build()
+}
+
+/// `timestamps` holds the acknowledged messages' timestamps as big-endian 64-bit integers;
+/// `receipt_type` is 0 for delivery, 1 for read, and 2 for viewed.
+#[bridge_fn]
+fn Content_NewReceiptMessage(receipt_type: u32, timestamps: &[u8]) -> Result<Content> {
+    let receipt_type = match receipt_type {
+        0 => ReceiptType::Delivery,
+        1 => ReceiptType::Read,
+        2 => ReceiptType::Viewed,
+        _ => {
+            return Err(SignalProtocolError::InvalidArgument(format!(
+                "unknown receipt type {}",
+                receipt_type
+            )))
+        }
+    };
+    if timestamps.len() % 8 != 0 {
+        return Err(SignalProtocolError::InvalidArgument(format!(
+            "timestamp list length {} is not a multiple of 8",
+            timestamps.len()
+        )));
+    }
+    let timestamps = timestamps
+        .chunks(8)
+        .map(|chunk| {
+            let millis = u64::from_be_bytes(chunk.try_into().expect("correct length"));
+            Ok(Timestamp::from_epoch_millis(millis)?.epoch_millis())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Content::receipt(receipt_type, &timestamps)
+}
+
+/// An empty `group_id` means the indicator is for a one-on-one conversation.
+#[bridge_fn]
+fn Content_NewTypingMessage(
+    timestamp: Timestamp,
+    started: bool,
+    group_id: &[u8],
+) -> Result<Content> {
+    let action = if started {
+        TypingAction::Started
+    } else {
+        TypingAction::Stopped
+    };
+    let group_id = if group_id.is_empty() {
+        None
+    } else {
+        Some(group_id)
+    };
+    Content::typing(timestamp.epoch_millis(), action, group_id)
+}
+
+#[bridge_fn_buffer]
+fn Content_GetSerialized<E: Env>(env: E, content: &Content) -> Result<E::Buffer> {
+    Ok(env.buffer(content.serialized()))
+}
+
+#[bridge_fn_buffer]
+fn PaymentAddress_Sign<T: Env>(
+    env: T,
+    identity_public_key: &PublicKey,
+    identity_private_key: &PrivateKey,
+    address: &[u8],
+    rng: Option<&Rng>,
+) -> Result<T::Buffer> {
+    let identity_key_pair = IdentityKeyPair::new(
+        IdentityKey::new(*identity_public_key),
+        *identity_private_key,
+    );
+    let mut rng = CallRng::new(rng);
+    let signature = sign_payment_address(&identity_key_pair, address, &mut rng)?;
+    Ok(env.buffer(signature.into_vec()))
+}
+
+#[bridge_fn]
+fn PaymentAddress_Verify(
+    identity_key: &PublicKey,
+    address: &[u8],
+    signature: &[u8],
+) -> Result<bool> {
+    verify_payment_address(&IdentityKey::new(*identity_key), address, signature)
+}
+
+#[bridge_fn]
+fn ProvisioningCipher_New(rng: Option<&Rng>) -> ProvisioningCipher {
+    ProvisioningCipher::new(&mut CallRng::new(rng))
+}
+
+#[bridge_fn]
+fn ProvisioningCipher_GetPublicKey(cipher: &ProvisioningCipher) -> PublicKey {
+    *cipher.public_key()
+}
+
+#[bridge_fn_buffer]
+fn ProvisioningCipher_Decrypt<E: Env>(
+    env: E,
+    cipher: &ProvisioningCipher,
+    envelope: &[u8],
+) -> Result<E::Buffer> {
+    Ok(env.buffer(cipher.decrypt(envelope)?))
+}
+
+#[bridge_fn_buffer]
+fn ProvisioningCipher_Encrypt<E: Env>(
+    env: E,
+    their_public_key: &PublicKey,
+    message: &[u8],
+    rng: Option<&Rng>,
+) -> Result<E::Buffer> {
+    let mut rng = CallRng::new(rng);
+    Ok(env.buffer(provisioning_encrypt(their_public_key, message, &mut rng)?))
+}
+
+#[bridge_fn]
+fn ProvisioningUuid_New(uuid: String) -> Result<ProvisioningUuid> {
+    ProvisioningUuid::new(uuid)
+}
+
+bridge_deserialize!(ProvisioningUuid::try_from);
+bridge_get!(ProvisioningUuid::uuid -> &str);
+bridge_get_bytearray!(ProvisioningUuid::serialized);
+
+#[bridge_fn]
+fn OutgoingEnvelope_New(
+    destination: &ProtocolAddress,
+    timestamp: u64,
+    content_hint: u32,
+    payload: &[u8],
+) -> OutgoingEnvelope {
+    OutgoingEnvelope::new(
+        destination,
+        timestamp,
+        content_hint.into(),
+        payload.to_vec(),
+    )
+}
+
+impl BridgeSerialize for OutgoingEnvelope {
+    fn bridge_serialize(&self) -> Result<Vec<u8>> {
+        self.serialize()
+    }
+    fn bridge_deserialize(data: &[u8]) -> Result<Self> {
+        Self::deserialize(data)
+    }
+}
+
+bridge_get!(OutgoingEnvelope::destination -> ProtocolAddress);
+bridge_get!(OutgoingEnvelope::timestamp -> u64);
+bridge_get!(OutgoingEnvelope::content_hint -> u32);
+bridge_get_bytearray!(OutgoingEnvelope::payload);
+bridge_get!(OutgoingEnvelope::serialized_version -> u32);
+
 #[bridge_fn(jni = false)]
 fn Fingerprint_New(
     iterations: u32,
@@ -215,14 +536,29 @@ fn ScannableFingerprint_Compare(fprint1: &[u8], fprint2: &[u8]) -> Result<bool>
 
 bridge_deserialize!(SignalMessage::try_from, ffi = message);
 
+impl BridgeSerialize for SignalMessage {
+    fn bridge_serialize(&self) -> Result<Vec<u8>> {
+        Ok(self.serialized().to_vec())
+    }
+    fn bridge_deserialize(data: &[u8]) -> Result<Self> {
+        Self::try_from(data)
+    }
+}
+
+bridge_transport!(SignalMessage);
+
 #[bridge_fn_buffer(ffi = false, node = false)]
-fn SignalMessage_GetSenderRatchetKey<E: Env>(env: E, m: &SignalMessage) -> E::Buffer {
-    env.buffer(m.sender_ratchet_key().serialize().into_vec())
+fn SignalMessage_GetSenderRatchetKey<E: Env>(env: E, m: &SignalMessage) -> Result<E::Buffer> {
+    Ok(env.buffer(m.sender_ratchet_key()?.serialize().into_vec()))
 }
 
 bridge_get_bytearray!(SignalMessage::body, ffi = "message_get_body");
 bridge_get_bytearray!(SignalMessage::serialized, ffi = "message_get_serialized");
 bridge_get!(SignalMessage::counter -> u32, ffi = "message_get_counter");
+bridge_get!(
+    SignalMessage::previous_counter -> u32,
+    ffi = "message_get_previous_counter"
+);
 bridge_get!(SignalMessage::message_version -> u32, ffi = "message_get_message_version");
 
 #[bridge_fn(ffi = "message_new")]
@@ -262,9 +598,13 @@ fn SignalMessage_VerifyMac(
     )
 }
 
-#[bridge_fn(ffi = "message_get_sender_ratchet_key", jni = false, node = false)]
-fn Message_GetSenderRatchetKey(m: &SignalMessage) -> PublicKey {
-    *m.sender_ratchet_key()
+#[bridge_fn(
+    ffi = "message_get_sender_ratchet_key",
+    jni = false,
+    node = "SignalMessage_GetSenderRatchetKey"
+)]
+fn Message_GetSenderRatchetKey(m: &SignalMessage) -> Result<PublicKey> {
+    Ok(*m.sender_ratchet_key()?)
 }
 
 #[bridge_fn]
@@ -288,22 +628,33 @@ fn PreKeySignalMessage_New(
     )
 }
 
-#[bridge_fn(jni = false, node = false)]
+#[bridge_fn(jni = false)]
 fn PreKeySignalMessage_GetBaseKey(m: &PreKeySignalMessage) -> PublicKey {
     *m.base_key()
 }
 
-#[bridge_fn(jni = false, node = false)]
+#[bridge_fn(jni = false)]
 fn PreKeySignalMessage_GetIdentityKey(m: &PreKeySignalMessage) -> PublicKey {
     *m.identity_key().public_key()
 }
 
-#[bridge_fn(jni = false, node = false)]
+#[bridge_fn(jni = false)]
 fn PreKeySignalMessage_GetSignalMessage(m: &PreKeySignalMessage) -> SignalMessage {
     m.message().clone()
 }
 
 bridge_deserialize!(PreKeySignalMessage::try_from);
+
+impl BridgeSerialize for PreKeySignalMessage {
+    fn bridge_serialize(&self) -> Result<Vec<u8>> {
+        Ok(self.serialized().to_vec())
+    }
+    fn bridge_deserialize(data: &[u8]) -> Result<Self> {
+        Self::try_from(data)
+    }
+}
+
+bridge_transport!(PreKeySignalMessage);
 bridge_get_bytearray!(
     PreKeySignalMessage::serialized as Serialize,
     jni = "PreKeySignalMessage_1GetSerialized"
@@ -340,6 +691,17 @@ bridge_get!(PreKeySignalMessage::pre_key_id -> Option<u32>);
 bridge_get!(PreKeySignalMessage::message_version as GetVersion -> u32);
 
 bridge_deserialize!(SenderKeyMessage::try_from);
+
+impl BridgeSerialize for SenderKeyMessage {
+    fn bridge_serialize(&self) -> Result<Vec<u8>> {
+        Ok(self.serialized().to_vec())
+    }
+    fn bridge_deserialize(data: &[u8]) -> Result<Self> {
+        Self::try_from(data)
+    }
+}
+
+bridge_transport!(SenderKeyMessage);
 bridge_get_bytearray!(SenderKeyMessage::ciphertext as GetCipherText);
 bridge_get_bytearray!(
     SenderKeyMessage::serialized as Serialize,
@@ -347,6 +709,7 @@ bridge_get_bytearray!(
 );
 bridge_get!(SenderKeyMessage::key_id -> u32);
 bridge_get!(SenderKeyMessage::iteration -> u32);
+bridge_get!(SenderKeyMessage::message_version -> u32);
 
 #[bridge_fn]
 fn SenderKeyMessage_New(
@@ -354,8 +717,9 @@ fn SenderKeyMessage_New(
     iteration: u32,
     ciphertext: &[u8],
     pk: &PrivateKey,
+    rng: Option<&Rng>,
 ) -> Result<SenderKeyMessage> {
-    let mut csprng = rand::rngs::OsRng;
+    let mut csprng = CallRng::new(rng);
     SenderKeyMessage::new(key_id, iteration, &ciphertext, &mut csprng, pk)
 }
 
@@ -365,6 +729,17 @@ fn SenderKeyMessage_VerifySignature(skm: &SenderKeyMessage, pubkey: &PublicKey)
 }
 
 bridge_deserialize!(SenderKeyDistributionMessage::try_from);
+
+impl BridgeSerialize for SenderKeyDistributionMessage {
+    fn bridge_serialize(&self) -> Result<Vec<u8>> {
+        Ok(self.serialized().to_vec())
+    }
+    fn bridge_deserialize(data: &[u8]) -> Result<Self> {
+        Self::try_from(data)
+    }
+}
+
+bridge_transport!(SenderKeyDistributionMessage);
 bridge_get_bytearray!(SenderKeyDistributionMessage::chain_key);
 
 #[bridge_fn_buffer(
@@ -449,6 +824,30 @@ bridge_get!(PreKeyBundle::signed_pre_key_id -> u32);
 bridge_get!(PreKeyBundle::pre_key_id -> Option<u32>);
 bridge_get!(PreKeyBundle::pre_key_public -> Option<PublicKey>);
 bridge_get!(PreKeyBundle::signed_pre_key_public -> PublicKey);
+bridge_get!(PreKeyBundle::min_session_version -> u32);
+bridge_get!(PreKeyBundle::max_session_version -> u32);
+
+#[bridge_fn]
+fn PreKeyBundle_WithSessionVersions(
+    bundle: &PreKeyBundle,
+    min_version: u32,
+    max_version: u32,
+) -> Result<PreKeyBundle> {
+    bundle
+        .clone()
+        .with_session_versions(min_version, max_version)
+}
+
+impl BridgeSerialize for PreKeyBundle {
+    fn bridge_serialize(&self) -> Result<Vec<u8>> {
+        self.serialize()
+    }
+    fn bridge_deserialize(data: &[u8]) -> Result<Self> {
+        Self::deserialize(data)
+    }
+}
+
+bridge_transport!(PreKeyBundle);
 
 bridge_deserialize!(SignedPreKeyRecord::deserialize);
 bridge_get_bytearray!(SignedPreKeyRecord::signature);
@@ -464,13 +863,13 @@ bridge_get!(SignedPreKeyRecord::private_key -> PrivateKey);
 #[bridge_fn]
 fn SignedPreKeyRecord_New(
     id: u32,
-    timestamp: u64,
+    timestamp: Timestamp,
     pub_key: &PublicKey,
     priv_key: &PrivateKey,
     signature: &[u8],
 ) -> SignedPreKeyRecord {
     let keypair = KeyPair::new(*pub_key, *priv_key);
-    SignedPreKeyRecord::new(id, timestamp, &keypair, &signature)
+    SignedPreKeyRecord::new(id, timestamp.epoch_millis(), &keypair, &signature)
 }
 
 bridge_deserialize!(PreKeyRecord::deserialize);
@@ -481,6 +880,7 @@ bridge_get_bytearray!(
 bridge_get!(PreKeyRecord::id -> u32);
 bridge_get!(PreKeyRecord::public_key -> PublicKey);
 bridge_get!(PreKeyRecord::private_key -> PrivateKey);
+bridge_get!(PreKeyRecord::serialized_version -> u32);
 
 #[bridge_fn]
 fn PreKeyRecord_New(id: u32, pub_key: &PublicKey, priv_key: &PrivateKey) -> PreKeyRecord {
@@ -517,12 +917,46 @@ bridge_get_bytearray!(
     SenderKeyRecord::serialize as Serialize,
     jni = "SenderKeyRecord_1GetSerialized"
 );
+bridge_get!(SenderKeyRecord::serialized_version -> u32);
 
 #[bridge_fn(ffi = "sender_key_record_new_fresh")]
 fn SenderKeyRecord_New() -> SenderKeyRecord {
     SenderKeyRecord::new_empty()
 }
 
+#[bridge_fn_void]
+fn SenderKeyRecord_MarkDistributed(
+    record: &mut SenderKeyRecord,
+    member: &ProtocolAddress,
+) -> Result<()> {
+    record.mark_distributed(member)
+}
+
+#[bridge_fn]
+fn SenderKeyRecord_NeedsDistribution(record: &SenderKeyRecord, member: &ProtocolAddress) -> bool {
+    record.needs_distribution(member)
+}
+
+#[bridge_fn]
+fn SenderKeyRecord_ForgetDistribution(
+    record: &mut SenderKeyRecord,
+    member: &ProtocolAddress,
+) -> bool {
+    record.forget_distribution(member)
+}
+
+/// The most states a [`SenderKeyRecord`] may have when deserialized.
+#[bridge_fn]
+fn SenderKeyRecord_MaxStates() -> u32 {
+    RecordLimit::SenderKeyStates.max() as u32
+}
+
+/// The most skipped-message keys each state of a [`SenderKeyRecord`] may have when deserialized.
+#[bridge_fn]
+fn SenderKeyRecord_MaxMessageKeysPerState() -> u32 {
+    RecordLimit::MessageKeys.max() as u32
+}
+
 bridge_deserialize!(ServerCertificate::deserialize);
 bridge_get_bytearray!(ServerCertificate::serialized);
 bridge_get_bytearray!(ServerCertificate::certificate);
@@ -535,8 +969,9 @@ fn ServerCertificate_New(
     key_id: u32,
     server_key: &PublicKey,
     trust_root: &PrivateKey,
+    rng: Option<&Rng>,
 ) -> Result<ServerCertificate> {
-    let mut rng = rand::rngs::OsRng;
+    let mut rng = CallRng::new(rng);
     ServerCertificate::new(key_id, *server_key, trust_root, &mut rng)
 }
 
@@ -546,17 +981,31 @@ bridge_get_bytearray!(SenderCertificate::certificate);
 bridge_get_bytearray!(SenderCertificate::signature);
 bridge_get!(SenderCertificate::sender_uuid -> &str);
 bridge_get!(SenderCertificate::sender_e164 -> Option<&str>);
-bridge_get!(SenderCertificate::expiration -> u64);
 bridge_get!(SenderCertificate::sender_device_id as GetDeviceId -> u32);
 bridge_get!(SenderCertificate::key -> PublicKey);
 
+#[bridge_fn]
+fn SenderCertificate_GetExpiration(obj: &SenderCertificate) -> Result<Timestamp> {
+    Ok(Timestamp::from_epoch_millis_unchecked(obj.expiration()?))
+}
+
 #[bridge_fn]
 fn SenderCertificate_Validate(
     cert: &SenderCertificate,
     key: &PublicKey,
-    time: u64,
+    time: Timestamp,
+) -> Result<bool> {
+    cert.validate(key, time.epoch_millis())
+}
+
+#[bridge_fn]
+fn SenderCertificate_ValidateWithGracePeriod(
+    cert: &SenderCertificate,
+    key: &PublicKey,
+    time: Timestamp,
+    grace_period: u64,
 ) -> Result<bool> {
-    cert.validate(key, time)
+    cert.validate_with_grace_period(key, time.epoch_millis(), grace_period)
 }
 
 #[bridge_fn]
@@ -570,18 +1019,19 @@ fn SenderCertificate_New(
     sender_e164: Option<String>,
     sender_device_id: u32,
     sender_key: &PublicKey,
-    expiration: u64,
+    expiration: Timestamp,
     signer_cert: &ServerCertificate,
     signer_key: &PrivateKey,
+    rng: Option<&Rng>,
 ) -> Result<SenderCertificate> {
-    let mut rng = rand::rngs::OsRng;
+    let mut rng = CallRng::new(rng);
 
     SenderCertificate::new(
         sender_uuid,
         sender_e164,
         *sender_key,
         sender_device_id,
-        expiration,
+        expiration.epoch_millis(),
         signer_cert.clone(),
         signer_key,
         &mut rng,
@@ -689,11 +1139,45 @@ const_assert_eq!(
     CiphertextMessageType::SenderKeyDistribution as u8
 );
 
+// None of the CiphertextMessage functions are bridged to Java, which has no CiphertextMessage
+// handle. There, CiphertextMessage is an interface implemented by each message class; results are
+// returned as instances of those classes (see ciphertext_message_to_jobject in the JNI crate), and
+// each class has its own deserializing constructor and header accessors.
 #[bridge_fn(jni = false)]
 fn CiphertextMessage_Type(msg: &CiphertextMessage) -> u8 {
     msg.message_type() as u8
 }
 
+#[bridge_fn(jni = false)]
+fn CiphertextMessage_Deserialize(message_type: u8, data: &[u8]) -> Result<CiphertextMessage> {
+    CiphertextMessage::deserialize(CiphertextMessageType::try_from(message_type)?, data)
+}
+
+/// Serialized as the message type followed by the message itself.
+impl BridgeSerialize for CiphertextMessage {
+    fn bridge_serialize(&self) -> Result<Vec<u8>> {
+        let mut result = vec![self.message_type() as u8];
+        result.extend_from_slice(self.serialize());
+        Ok(result)
+    }
+    fn bridge_deserialize(data: &[u8]) -> Result<Self> {
+        match data.split_first() {
+            Some((&message_type, message)) => {
+                Self::deserialize(CiphertextMessageType::try_from(message_type)?, message)
+            }
+            None => Err(SignalProtocolError::InvalidArgument(
+                "empty CiphertextMessage data".to_owned(),
+            )),
+        }
+    }
+}
+
+bridge_transport!(CiphertextMessage);
+
+bridge_get!(CiphertextMessage::message_version -> u32, jni = false);
+bridge_get!(CiphertextMessage::counter -> Option<u32>, jni = false);
+bridge_get!(CiphertextMessage::previous_counter -> Option<u32>, jni = false);
+
 bridge_get_bytearray!(CiphertextMessage::serialize as Serialize, jni = false);
 
 #[bridge_fn(ffi = false, node = false)]
@@ -707,7 +1191,7 @@ fn SessionRecord_FromSingleSessionState(session_state: &[u8]) -> Result<SessionR
 }
 
 // For historical reasons Android assumes this function will return zero if there is no session state
-#[bridge_fn(ffi = false, node = false)]
+#[bridge_fn]
 fn SessionRecord_GetSessionVersion(s: &SessionRecord) -> Result<u32> {
     match s.session_version() {
         Ok(v) => Ok(v),
@@ -721,10 +1205,35 @@ fn SessionRecord_ArchiveCurrentState(session_record: &mut SessionRecord) -> Resu
     session_record.archive_current_state()
 }
 
+/// The most archived states a [`SessionRecord`] may have when deserialized.
+#[bridge_fn]
+fn SessionRecord_MaxArchivedStates() -> u32 {
+    RecordLimit::ArchivedSessionStates.max() as u32
+}
+
+/// The most receiver chains each state of a [`SessionRecord`] may have when deserialized.
+#[bridge_fn]
+fn SessionRecord_MaxReceiverChains() -> u32 {
+    RecordLimit::ReceiverChains.max() as u32
+}
+
+/// The most skipped-message keys each chain of a [`SessionRecord`] may have when deserialized.
+#[bridge_fn]
+fn SessionRecord_MaxMessageKeysPerChain() -> u32 {
+    RecordLimit::MessageKeys.max() as u32
+}
+
 bridge_get!(SessionRecord::has_current_session_state as HasCurrentState -> bool, jni = false);
 
-bridge_deserialize!(SessionRecord::deserialize);
-bridge_get_bytearray!(SessionRecord::serialize as Serialize);
+impl BridgeSerialize for SessionRecord {
+    fn bridge_serialize(&self) -> Result<Vec<u8>> {
+        self.serialize()
+    }
+    fn bridge_deserialize(data: &[u8]) -> Result<Self> {
+        Self::deserialize(data)
+    }
+}
+
 bridge_get_bytearray!(SessionRecord::alice_base_key, ffi = false, node = false);
 bridge_get_bytearray!(
     SessionRecord::local_identity_key_bytes as GetLocalIdentityKeyPublic,
@@ -738,6 +1247,7 @@ bridge_get_optional_bytearray!(
 );
 bridge_get!(SessionRecord::local_registration_id -> u32);
 bridge_get!(SessionRecord::remote_registration_id -> u32);
+bridge_get!(SessionRecord::serialized_version -> u32);
 bridge_get!(SessionRecord::has_sender_chain as HasSenderChain -> bool, ffi = false, node = false);
 
 bridge_get!(SealedSenderDecryptionResult::sender_uuid -> String, ffi = false, jni = false);
@@ -775,6 +1285,7 @@ fn SessionRecord_InitializeAliceSession(
     their_identity_key: &PublicKey,
     their_signed_prekey: &PublicKey,
     their_ratchet_key: &PublicKey,
+    rng: Option<&Rng>,
 ) -> Result<SessionRecord> {
     let our_identity_key_pair = IdentityKeyPair::new(
         IdentityKey::new(*identity_key_public),
@@ -785,7 +1296,7 @@ fn SessionRecord_InitializeAliceSession(
 
     let their_identity_key = IdentityKey::new(*their_identity_key);
 
-    let mut csprng = rand::rngs::OsRng;
+    let mut csprng = CallRng::new(rng);
 
     let parameters = AliceSignalProtocolParameters::new(
         our_identity_key_pair,
@@ -841,9 +1352,10 @@ async fn SessionBuilder_ProcessPreKeyBundle(
     protocol_address: &ProtocolAddress,
     session_store: &mut dyn SessionStore,
     identity_key_store: &mut dyn IdentityKeyStore,
+    rng: Option<&Rng>,
     ctx: Context,
 ) -> Result<()> {
-    let mut csprng = rand::rngs::OsRng;
+    let mut csprng = CallRng::new(rng);
     process_prekey_bundle(
         protocol_address,
         session_store,
@@ -873,6 +1385,65 @@ async fn SessionCipher_EncryptMessage(
     .await
 }
 
+#[bridge_fn(ffi = "encrypt_content", jni = false)]
+async fn SessionCipher_EncryptContent(
+    content: &Content,
+    padding: &PaddingPolicy,
+    protocol_address: &ProtocolAddress,
+    session_store: &mut dyn SessionStore,
+    identity_key_store: &mut dyn IdentityKeyStore,
+    ctx: Context,
+) -> Result<CiphertextMessage> {
+    message_encrypt_content(
+        content,
+        *padding,
+        protocol_address,
+        session_store,
+        identity_key_store,
+        ctx,
+    )
+    .await
+}
+
+#[bridge_fn(ffi = "encrypt_message_with_identity_override", jni = false)]
+async fn SessionCipher_EncryptMessageWithIdentityOverride(
+    ptext: &[u8],
+    protocol_address: &ProtocolAddress,
+    approved_identity_key: &PublicKey,
+    session_store: &mut dyn SessionStore,
+    identity_key_store: &mut dyn IdentityKeyStore,
+    ctx: Context,
+) -> Result<CiphertextMessage> {
+    message_encrypt_with_identity_override(
+        ptext,
+        protocol_address,
+        &IdentityKey::new(*approved_identity_key),
+        session_store,
+        identity_key_store,
+        ctx,
+    )
+    .await
+}
+
+// The FFI and JNI entry points are implemented by hand, since they take arrays of handles.
+#[bridge_fn(ffi = false, jni = false)]
+async fn SessionCipher_EncryptMessageBatch(
+    ptext: &[u8],
+    protocol_addresses: &[ProtocolAddress],
+    session_store: &mut dyn SessionStore,
+    identity_key_store: &mut dyn IdentityKeyStore,
+    ctx: Context,
+) -> Result<Vec<Result<CiphertextMessage>>> {
+    message_encrypt_batch(
+        ptext,
+        protocol_addresses,
+        session_store,
+        identity_key_store,
+        ctx,
+    )
+    .await
+}
+
 #[bridge_fn_buffer(ffi = "decrypt_message")]
 async fn SessionCipher_DecryptSignalMessage<E: Env>(
     env: E,
@@ -880,9 +1451,10 @@ async fn SessionCipher_DecryptSignalMessage<E: Env>(
     protocol_address: &ProtocolAddress,
     session_store: &mut dyn SessionStore,
     identity_key_store: &mut dyn IdentityKeyStore,
+    rng: Option<&Rng>,
     ctx: Context,
 ) -> Result<E::Buffer> {
-    let mut csprng = rand::rngs::OsRng;
+    let mut csprng = CallRng::new(rng);
     let ptext = message_decrypt_signal(
         message,
         protocol_address,
@@ -895,6 +1467,7 @@ async fn SessionCipher_DecryptSignalMessage<E: Env>(
     Ok(env.buffer(ptext))
 }
 
+#[allow(clippy::too_many_arguments)]
 #[bridge_fn_buffer(ffi = "decrypt_pre_key_message")]
 async fn SessionCipher_DecryptPreKeySignalMessage<E: Env>(
     env: E,
@@ -904,9 +1477,10 @@ async fn SessionCipher_DecryptPreKeySignalMessage<E: Env>(
     identity_key_store: &mut dyn IdentityKeyStore,
     prekey_store: &mut dyn PreKeyStore,
     signed_prekey_store: &mut dyn SignedPreKeyStore,
+    rng: Option<&Rng>,
     ctx: Context,
 ) -> Result<E::Buffer> {
-    let mut csprng = rand::rngs::OsRng;
+    let mut csprng = CallRng::new(rng);
     let ptext = message_decrypt_prekey(
         message,
         protocol_address,
@@ -921,6 +1495,98 @@ async fn SessionCipher_DecryptPreKeySignalMessage<E: Env>(
     Ok(env.buffer(ptext))
 }
 
+#[allow(clippy::too_many_arguments)]
+#[bridge_fn_buffer(ffi = "decrypt_message_with_identity_override")]
+async fn SessionCipher_DecryptSignalMessageWithIdentityOverride<E: Env>(
+    env: E,
+    message: &SignalMessage,
+    protocol_address: &ProtocolAddress,
+    approved_identity_key: &PublicKey,
+    session_store: &mut dyn SessionStore,
+    identity_key_store: &mut dyn IdentityKeyStore,
+    rng: Option<&Rng>,
+    ctx: Context,
+) -> Result<E::Buffer> {
+    let mut csprng = CallRng::new(rng);
+    let ptext = message_decrypt_signal_with_identity_override(
+        message,
+        protocol_address,
+        &IdentityKey::new(*approved_identity_key),
+        session_store,
+        identity_key_store,
+        &mut csprng,
+        ctx,
+    )
+    .await?;
+    Ok(env.buffer(ptext))
+}
+
+#[allow(clippy::too_many_arguments)]
+#[bridge_fn_buffer(ffi = "decrypt_pre_key_message_with_identity_override")]
+async fn SessionCipher_DecryptPreKeySignalMessageWithIdentityOverride<E: Env>(
+    env: E,
+    message: &PreKeySignalMessage,
+    protocol_address: &ProtocolAddress,
+    approved_identity_key: &PublicKey,
+    session_store: &mut dyn SessionStore,
+    identity_key_store: &mut dyn IdentityKeyStore,
+    prekey_store: &mut dyn PreKeyStore,
+    signed_prekey_store: &mut dyn SignedPreKeyStore,
+    rng: Option<&Rng>,
+    ctx: Context,
+) -> Result<E::Buffer> {
+    let mut csprng = CallRng::new(rng);
+    let ptext = message_decrypt_prekey_with_identity_override(
+        message,
+        protocol_address,
+        &IdentityKey::new(*approved_identity_key),
+        session_store,
+        identity_key_store,
+        prekey_store,
+        signed_prekey_store,
+        &mut csprng,
+        ctx,
+    )
+    .await?;
+    Ok(env.buffer(ptext))
+}
+
+#[cfg(feature = "node")]
+#[bridge_fn(ffi = false, jni = false)]
+fn BacklogDecryptor_New(threads: u32) -> Result<BacklogDecryptor> {
+    BacklogDecryptor::new(threads as usize)
+}
+
+// Only Node can wait for the thread pool without blocking the calling thread.
+#[cfg(feature = "node")]
+#[allow(clippy::too_many_arguments)]
+#[bridge_fn(ffi = false, jni = false)]
+async fn BacklogDecryptor_Decrypt(
+    decryptor: &BacklogDecryptor,
+    messages: &[CiphertextMessage],
+    protocol_addresses: &[ProtocolAddress],
+    session_store: &mut dyn SessionStore,
+    identity_key_store: &mut dyn IdentityKeyStore,
+    prekey_store: &mut dyn PreKeyStore,
+    signed_prekey_store: &mut dyn SignedPreKeyStore,
+    rng: Option<&Rng>,
+    ctx: Context,
+) -> Result<Vec<Result<Vec<u8>>>> {
+    let mut csprng = CallRng::new(rng);
+    decryptor
+        .decrypt(
+            messages,
+            protocol_addresses,
+            session_store,
+            identity_key_store,
+            prekey_store,
+            signed_prekey_store,
+            &mut csprng,
+            ctx,
+        )
+        .await
+}
+
 #[bridge_fn_buffer(node = "SealedSender_EncryptMessage")]
 async fn SealedSessionCipher_Encrypt<E: Env>(
     env: E,
@@ -929,9 +1595,10 @@ async fn SealedSessionCipher_Encrypt<E: Env>(
     ptext: &[u8],
     session_store: &mut dyn SessionStore,
     identity_key_store: &mut dyn IdentityKeyStore,
+    rng: Option<&Rng>,
     ctx: Context,
 ) -> Result<E::Buffer> {
-    let mut rng = rand::rngs::OsRng;
+    let mut rng = CallRng::new(rng);
     let ctext = sealed_sender_encrypt(
         destination,
         sender_cert,
@@ -945,6 +1612,79 @@ async fn SealedSessionCipher_Encrypt<E: Env>(
     Ok(env.buffer(ctext))
 }
 
+/// A [libsignal_protocol::SenderCertificateProvider] that can be shared between calls.
+///
+/// The lock is only held while reading or updating the cached certificate, not while a new one
+/// is being fetched, so concurrent calls may each fetch one.
+pub struct SenderCertificateProvider(Mutex<libsignal_protocol::SenderCertificateProvider>);
+
+impl SenderCertificateProvider {
+    async fn get(
+        &self,
+        valid_until: Timestamp,
+        certificate_source: &mut dyn SenderCertificateSource,
+        ctx: Context,
+    ) -> Result<SenderCertificate> {
+        let mut provider = self.0.lock().expect("not poisoned").clone();
+        let certificate = provider
+            .get(valid_until.epoch_millis(), certificate_source, ctx)
+            .await?;
+        *self.0.lock().expect("not poisoned") = provider;
+        Ok(certificate)
+    }
+}
+
+#[bridge_fn]
+fn SenderCertificateProvider_New(
+    certificate: Option<&SenderCertificate>,
+    freshness_window: u64,
+) -> SenderCertificateProvider {
+    let mut provider = libsignal_protocol::SenderCertificateProvider::new(freshness_window);
+    if let Some(certificate) = certificate {
+        provider = provider.with_certificate(certificate.clone());
+    }
+    SenderCertificateProvider(Mutex::new(provider))
+}
+
+#[bridge_fn]
+async fn SenderCertificateProvider_Get(
+    provider: &SenderCertificateProvider,
+    valid_until: Timestamp,
+    certificate_source: &mut dyn SenderCertificateSource,
+    ctx: Context,
+) -> Result<SenderCertificate> {
+    provider.get(valid_until, certificate_source, ctx).await
+}
+
+#[allow(clippy::too_many_arguments)]
+#[bridge_fn_buffer(node = "SealedSender_EncryptMessageWithProvider")]
+async fn SealedSessionCipher_EncryptWithProvider<E: Env>(
+    env: E,
+    destination: &ProtocolAddress,
+    provider: &SenderCertificateProvider,
+    certificate_source: &mut dyn SenderCertificateSource,
+    timestamp: Timestamp,
+    ptext: &[u8],
+    session_store: &mut dyn SessionStore,
+    identity_key_store: &mut dyn IdentityKeyStore,
+    rng: Option<&Rng>,
+    ctx: Context,
+) -> Result<E::Buffer> {
+    let sender_cert = provider.get(timestamp, certificate_source, ctx).await?;
+    let mut rng = CallRng::new(rng);
+    let ctext = sealed_sender_encrypt(
+        destination,
+        &sender_cert,
+        ptext,
+        session_store,
+        identity_key_store,
+        ctx,
+        &mut rng,
+    )
+    .await?;
+    Ok(env.buffer(ctext))
+}
+
 #[bridge_fn(node = "SealedSender_DecryptToUsmc")]
 async fn SealedSessionCipher_DecryptToUsmc(
     ctext: &[u8],
@@ -959,7 +1699,8 @@ async fn SealedSessionCipher_DecryptToUsmc(
 async fn SealedSender_DecryptMessage(
     message: &[u8],
     trust_root: &PublicKey,
-    timestamp: u64,
+    timestamp: Timestamp,
+    grace_period: u64,
     local_e164: Option<String>,
     local_uuid: String,
     local_device_id: u32,
@@ -967,11 +1708,79 @@ async fn SealedSender_DecryptMessage(
     identity_store: &mut dyn IdentityKeyStore,
     prekey_store: &mut dyn PreKeyStore,
     signed_prekey_store: &mut dyn SignedPreKeyStore,
+    replay_cache: Option<&mut dyn ReplayCache>,
+    rng: Option<&Rng>,
 ) -> Result<Option<SealedSenderDecryptionResult>> {
-    let result = sealed_sender_decrypt(
+    let result = match replay_cache {
+        Some(replay_cache) => {
+            sealed_sender_decrypt_with_replay_cache(
+                message,
+                trust_root,
+                timestamp.epoch_millis(),
+                grace_period,
+                local_e164,
+                local_uuid,
+                local_device_id,
+                identity_store,
+                session_store,
+                prekey_store,
+                signed_prekey_store,
+                replay_cache,
+                None,
+                &mut CallRng::new(rng),
+            )
+            .await
+        }
+        None => {
+            sealed_sender_decrypt_with_grace_period(
+                message,
+                trust_root,
+                timestamp.epoch_millis(),
+                grace_period,
+                local_e164,
+                local_uuid,
+                local_device_id,
+                identity_store,
+                session_store,
+                prekey_store,
+                signed_prekey_store,
+                None,
+                &mut CallRng::new(rng),
+            )
+            .await
+        }
+    };
+
+    match result {
+        Ok(r) => Ok(Some(r)),
+        Err(e) if matches!(e.root_cause(), SignalProtocolError::SealedSenderSelfSend) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Decrypts a sealed sender message for a push notification without writing to any of the stores.
+///
+/// The store changes are kept in the result, to be passed to `NotificationCommitSet_Apply` later.
+#[allow(clippy::too_many_arguments)]
+#[bridge_fn(ffi = "notification_decrypt")]
+async fn NotificationDecrypt_Decrypt(
+    message: &[u8],
+    trust_root: &PublicKey,
+    timestamp: Timestamp,
+    local_e164: Option<String>,
+    local_uuid: String,
+    local_device_id: u32,
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    prekey_store: &mut dyn PreKeyStore,
+    signed_prekey_store: &mut dyn SignedPreKeyStore,
+    rng: Option<&Rng>,
+    ctx: Context,
+) -> Result<NotificationDecryptionResult> {
+    notification_decrypt(
         message,
         trust_root,
-        timestamp,
+        timestamp.epoch_millis(),
         local_e164,
         local_uuid,
         local_device_id,
@@ -979,24 +1788,184 @@ async fn SealedSender_DecryptMessage(
         session_store,
         prekey_store,
         signed_prekey_store,
-        None,
+        ctx,
+        &mut CallRng::new(rng),
     )
-    .await;
+    .await
+}
 
-    match result {
-        Ok(r) => Ok(Some(r)),
-        Err(SignalProtocolError::SealedSenderSelfSend) => Ok(None),
-        Err(e) => Err(e),
+#[bridge_fn]
+fn NotificationDecryptionResult_GetSenderUuid(result: &NotificationDecryptionResult) -> String {
+    result.message.sender_uuid.clone()
+}
+
+#[bridge_fn]
+fn NotificationDecryptionResult_GetSenderE164(
+    result: &NotificationDecryptionResult,
+) -> Option<String> {
+    result.message.sender_e164.clone()
+}
+
+#[bridge_fn]
+fn NotificationDecryptionResult_GetDeviceId(result: &NotificationDecryptionResult) -> u32 {
+    result.message.device_id
+}
+
+#[bridge_fn_buffer]
+fn NotificationDecryptionResult_GetMessage<E: Env>(
+    env: E,
+    result: &NotificationDecryptionResult,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(result.message.message.as_slice()))
+}
+
+/// The deferred store changes, serialized so they can be handed to another process.
+#[bridge_fn_buffer]
+fn NotificationDecryptionResult_GetCommitSet<E: Env>(
+    env: E,
+    result: &NotificationDecryptionResult,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(result.commit_set.serialize()?))
+}
+
+#[bridge_fn_void]
+async fn NotificationCommitSet_Apply(
+    commit_set: &[u8],
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    prekey_store: &mut dyn PreKeyStore,
+    ctx: Context,
+) -> Result<()> {
+    CommitSet::deserialize(commit_set)?
+        .apply(session_store, identity_store, prekey_store, ctx)
+        .await
+}
+
+/// A decryption made through a [MutationRecorder]'s stores, along with the serialized
+/// [MutationLog] of the store writes it would have made.
+pub struct DeferredDecryptionResult {
+    plaintext: Vec<u8>,
+    mutation_log: Vec<u8>,
+}
+
+impl DeferredDecryptionResult {
+    fn new(plaintext: Vec<u8>, recorder: MutationRecorder) -> Result<Self> {
+        Ok(Self {
+            plaintext,
+            mutation_log: recorder.into_log().serialize()?,
+        })
+    }
+
+    fn plaintext(&self) -> &[u8] {
+        &self.plaintext
     }
+
+    fn mutation_log(&self) -> &[u8] {
+        &self.mutation_log
+    }
+}
+
+bridge_get_bytearray!(DeferredDecryptionResult::plaintext);
+bridge_get_bytearray!(DeferredDecryptionResult::mutation_log);
+
+#[bridge_fn(ffi = "decrypt_message_deferred")]
+async fn SessionCipher_DecryptSignalMessageDeferred(
+    message: &SignalMessage,
+    protocol_address: &ProtocolAddress,
+    session_store: &mut dyn SessionStore,
+    identity_key_store: &mut dyn IdentityKeyStore,
+    rng: Option<&Rng>,
+    ctx: Context,
+) -> Result<DeferredDecryptionResult> {
+    let recorder = MutationRecorder::new();
+    let ptext = message_decrypt_signal(
+        message,
+        protocol_address,
+        &mut recorder.session_store(session_store),
+        &mut recorder.identity_store(identity_key_store),
+        &mut CallRng::new(rng),
+        ctx,
+    )
+    .await?;
+    DeferredDecryptionResult::new(ptext, recorder)
+}
+
+#[allow(clippy::too_many_arguments)]
+#[bridge_fn(ffi = "decrypt_pre_key_message_deferred")]
+async fn SessionCipher_DecryptPreKeySignalMessageDeferred(
+    message: &PreKeySignalMessage,
+    protocol_address: &ProtocolAddress,
+    session_store: &mut dyn SessionStore,
+    identity_key_store: &mut dyn IdentityKeyStore,
+    prekey_store: &mut dyn PreKeyStore,
+    signed_prekey_store: &mut dyn SignedPreKeyStore,
+    rng: Option<&Rng>,
+    ctx: Context,
+) -> Result<DeferredDecryptionResult> {
+    let recorder = MutationRecorder::new();
+    let ptext = message_decrypt_prekey(
+        message,
+        protocol_address,
+        &mut recorder.session_store(session_store),
+        &mut recorder.identity_store(identity_key_store),
+        &mut recorder.pre_key_store(prekey_store),
+        &mut recorder.signed_pre_key_store(signed_prekey_store),
+        &mut CallRng::new(rng),
+        ctx,
+    )
+    .await?;
+    DeferredDecryptionResult::new(ptext, recorder)
+}
+
+#[bridge_fn(ffi = "group_decrypt_message_deferred")]
+async fn GroupCipher_DecryptMessageDeferred(
+    sender_key_name: &SenderKeyName,
+    message: &[u8],
+    store: &mut dyn SenderKeyStore,
+    ctx: Context,
+) -> Result<DeferredDecryptionResult> {
+    let recorder = MutationRecorder::new();
+    let ptext = group_decrypt(
+        message,
+        &mut recorder.sender_key_store(store),
+        sender_key_name,
+        ctx,
+    )
+    .await?;
+    DeferredDecryptionResult::new(ptext, recorder)
+}
+
+/// Replays a mutation log recorded by one of the `*Deferred` functions against the real stores.
+#[bridge_fn_void]
+async fn MutationLog_Apply(
+    mutation_log: &[u8],
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    prekey_store: &mut dyn PreKeyStore,
+    signed_prekey_store: &mut dyn SignedPreKeyStore,
+    sender_key_store: &mut dyn SenderKeyStore,
+    ctx: Context,
+) -> Result<()> {
+    MutationLog::deserialize(mutation_log)?
+        .apply(
+            session_store,
+            identity_store,
+            prekey_store,
+            signed_prekey_store,
+            sender_key_store,
+            ctx,
+        )
+        .await
 }
 
 #[bridge_fn(jni = "GroupSessionBuilder_1CreateSenderKeyDistributionMessage")]
 async fn SenderKeyDistributionMessage_Create(
     sender_key_name: &SenderKeyName,
     store: &mut dyn SenderKeyStore,
+    rng: Option<&Rng>,
     ctx: Context,
 ) -> Result<SenderKeyDistributionMessage> {
-    let mut csprng = rand::rngs::OsRng;
+    let mut csprng = CallRng::new(rng);
     create_sender_key_distribution_message(sender_key_name, store, &mut csprng, ctx).await
 }
 
@@ -1025,9 +1994,10 @@ async fn GroupCipher_EncryptMessage<E: Env>(
     sender_key_name: &SenderKeyName,
     message: &[u8],
     store: &mut dyn SenderKeyStore,
+    rng: Option<&Rng>,
     ctx: Context,
 ) -> Result<E::Buffer> {
-    let mut rng = rand::rngs::OsRng;
+    let mut rng = CallRng::new(rng);
     let ctext = group_encrypt(store, sender_key_name, message, &mut rng, ctx).await?;
     Ok(env.buffer(ctext))
 }
@@ -1043,3 +2013,86 @@ async fn GroupCipher_DecryptMessage<E: Env>(
     let ptext = group_decrypt(message, store, sender_key_name, ctx).await?;
     Ok(env.buffer(ptext))
 }
+
+#[bridge_fn(ffi = "group_chain_position")]
+async fn GroupCipher_ChainPosition(
+    sender_key_name: &SenderKeyName,
+    distribution_id: u32,
+    store: &mut dyn SenderKeyStore,
+    ctx: Context,
+) -> Result<u32> {
+    group_chain_position(store, sender_key_name, distribution_id, ctx).await
+}
+
+#[bridge_fn_void(ffi = "group_fast_forward")]
+async fn GroupCipher_FastForward(
+    sender_key_name: &SenderKeyName,
+    distribution_id: u32,
+    to_iteration: u32,
+    store: &mut dyn SenderKeyStore,
+    ctx: Context,
+) -> Result<()> {
+    group_fast_forward(store, sender_key_name, distribution_id, to_iteration, ctx).await
+}
+
+/// Returns a [`SecuritySnapshot`] of the conversation with `address`, as JSON.
+///
+/// If `sender_key_name` (one of our own) is given, the snapshot also says which of our sender keys
+/// for that group `address` was last sent.
+#[bridge_fn(ffi = "security_snapshot")]
+async fn SecuritySnapshot_Create(
+    address: &ProtocolAddress,
+    sender_key_name: Option<&SenderKeyName>,
+    session_store: &mut dyn SessionStore,
+    identity_key_store: &mut dyn IdentityKeyStore,
+    sender_key_store: &mut dyn SenderKeyStore,
+    ctx: Context,
+) -> Result<String> {
+    let sender_key_names: Vec<SenderKeyName> = sender_key_name.into_iter().cloned().collect();
+    let snapshot = security_snapshot(
+        address,
+        &sender_key_names,
+        session_store,
+        identity_key_store,
+        sender_key_store,
+        ctx,
+    )
+    .await?;
+    Ok(snapshot.to_json())
+}
+
+/// Turns the process-wide decryption counters on or off; see [`set_decryption_metrics_enabled`].
+#[bridge_fn_void]
+fn DecryptionMetrics_SetEnabled(enabled: bool) {
+    set_decryption_metrics_enabled(enabled)
+}
+
+/// Returns the decryption counters, as formatted by [`DecryptionMetrics::report`].
+#[bridge_fn]
+fn DecryptionMetrics_Get() -> String {
+    decryption_metrics().report()
+}
+
+#[bridge_fn_void]
+fn DecryptionMetrics_Reset() {
+    reset_decryption_metrics()
+}
+
+macro_rules! bridge_debug_dump {
+    ($typ:ident $(, $param:ident = $val:tt)*) => {
+        paste! {
+            #[bridge_fn($($param = $val),*)]
+            fn [<$typ _DebugDump>](obj: &$typ, include_secrets: bool) -> Result<String> {
+                obj.debug_dump(include_secrets)
+            }
+        }
+    };
+}
+
+bridge_debug_dump!(PreKeyBundle);
+bridge_debug_dump!(PreKeySignalMessage);
+bridge_debug_dump!(SenderKeyDistributionMessage);
+bridge_debug_dump!(SenderKeyMessage);
+bridge_debug_dump!(SenderKeyRecord);
+bridge_debug_dump!(SessionRecord);
+bridge_debug_dump!(SignalMessage, ffi = "message_debug_dump");
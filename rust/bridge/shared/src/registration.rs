@@ -0,0 +1,249 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use ::registration;
+use libsignal_bridge_macros::*;
+use registration::*;
+use std::convert::TryInto;
+
+use crate::support::*;
+use crate::*;
+
+bridge_handle!(RegistrationRequest, clone = false);
+
+// The getters here are written out rather than generated with bridge_get!, which produces a
+// SignalProtocolError-based Result rather than this module's.
+
+#[bridge_fn]
+fn RegistrationRequest_GetMethod(request: &RegistrationRequest) -> &str {
+    request.method()
+}
+
+#[bridge_fn]
+fn RegistrationRequest_GetPath(request: &RegistrationRequest) -> &str {
+    request.path()
+}
+
+#[bridge_fn_buffer]
+fn RegistrationRequest_GetBody<E: Env>(
+    env: E,
+    request: &RegistrationRequest,
+) -> Result<Option<E::Buffer>> {
+    Ok(request.body().map(|body| env.buffer(body)))
+}
+
+#[bridge_fn]
+fn RegistrationRequest_CreateSession(
+    number: String,
+    push_token: Option<String>,
+    push_token_is_apn: bool,
+    mcc: Option<String>,
+    mnc: Option<String>,
+) -> RegistrationRequest {
+    let push_token = push_token.map(|token| {
+        if push_token_is_apn {
+            PushToken::Apn(token)
+        } else {
+            PushToken::Fcm(token)
+        }
+    });
+    RegistrationRequest::create_session(
+        &number,
+        push_token.as_ref(),
+        mcc.as_deref(),
+        mnc.as_deref(),
+    )
+}
+
+#[bridge_fn]
+fn RegistrationRequest_GetSession(session_id: String) -> Result<RegistrationRequest> {
+    RegistrationRequest::get_session(&session_id)
+}
+
+#[bridge_fn]
+fn RegistrationRequest_UpdateSession(
+    session_id: String,
+    push_challenge: Option<String>,
+    captcha: Option<String>,
+) -> Result<RegistrationRequest> {
+    RegistrationRequest::update_session(&session_id, push_challenge.as_deref(), captcha.as_deref())
+}
+
+#[bridge_fn]
+fn RegistrationRequest_RequestVerificationCode(
+    session_id: String,
+    voice: bool,
+    client: String,
+) -> Result<RegistrationRequest> {
+    let transport = if voice {
+        VerificationTransport::Voice
+    } else {
+        VerificationTransport::Sms
+    };
+    RegistrationRequest::request_verification_code(&session_id, transport, &client)
+}
+
+#[bridge_fn]
+fn RegistrationRequest_SubmitVerificationCode(
+    session_id: String,
+    code: String,
+) -> Result<RegistrationRequest> {
+    RegistrationRequest::submit_verification_code(&session_id, &code)
+}
+
+#[bridge_fn]
+fn RegistrationRequest_RegisterAccount(
+    session_id: String,
+    registration_id: u32,
+    fetches_messages: bool,
+    registration_lock: Option<String>,
+) -> Result<RegistrationRequest> {
+    RegistrationRequest::register_account(
+        &session_id,
+        &AccountAttributes {
+            registration_id,
+            fetches_messages,
+            registration_lock,
+        },
+    )
+}
+
+bridge_handle!(RegistrationSession, clone = false);
+
+#[bridge_fn]
+fn RegistrationSession_GetId(session: &RegistrationSession) -> &str {
+    session.id()
+}
+
+#[bridge_fn]
+fn RegistrationSession_GetNextSmsSeconds(session: &RegistrationSession) -> Option<u32> {
+    session.next_sms_seconds()
+}
+
+#[bridge_fn]
+fn RegistrationSession_GetNextCallSeconds(session: &RegistrationSession) -> Option<u32> {
+    session.next_call_seconds()
+}
+
+#[bridge_fn]
+fn RegistrationSession_GetNextVerificationAttemptSeconds(
+    session: &RegistrationSession,
+) -> Option<u32> {
+    session.next_verification_attempt_seconds()
+}
+
+#[bridge_fn]
+fn RegistrationSession_GetAllowedToRequestCode(session: &RegistrationSession) -> bool {
+    session.allowed_to_request_code()
+}
+
+#[bridge_fn]
+fn RegistrationSession_GetVerified(session: &RegistrationSession) -> bool {
+    session.verified()
+}
+
+#[bridge_fn]
+fn RegistrationSession_FromResponse(
+    status: u32,
+    body: &[u8],
+    retry_after: Option<String>,
+) -> Result<RegistrationSession> {
+    RegistrationSession::from_response(
+        status.try_into().unwrap_or(u16::MAX),
+        body,
+        retry_after.as_deref(),
+    )
+}
+
+#[bridge_fn]
+fn RegistrationSession_RequiresPushChallenge(session: &RegistrationSession) -> bool {
+    session
+        .requested_information()
+        .contains(&Challenge::PushChallenge)
+}
+
+#[bridge_fn]
+fn RegistrationSession_RequiresCaptcha(session: &RegistrationSession) -> bool {
+    session
+        .requested_information()
+        .contains(&Challenge::Captcha)
+}
+
+bridge_handle!(RegistrationOutcome, clone = false);
+
+fn registered_account(outcome: &RegistrationOutcome) -> Option<&RegisteredAccount> {
+    match outcome {
+        RegistrationOutcome::Registered(account) => Some(account),
+        RegistrationOutcome::Locked { .. } => None,
+    }
+}
+
+fn svr_credentials(outcome: &RegistrationOutcome) -> Option<&SvrCredentials> {
+    match outcome {
+        RegistrationOutcome::Registered(_) => None,
+        RegistrationOutcome::Locked {
+            svr_credentials, ..
+        } => svr_credentials.as_ref(),
+    }
+}
+
+#[bridge_fn]
+fn RegistrationOutcome_FromResponse(
+    status: u32,
+    body: &[u8],
+    retry_after: Option<String>,
+) -> Result<RegistrationOutcome> {
+    RegistrationOutcome::from_response(
+        status.try_into().unwrap_or(u16::MAX),
+        body,
+        retry_after.as_deref(),
+    )
+}
+
+#[bridge_fn]
+fn RegistrationOutcome_IsLocked(outcome: &RegistrationOutcome) -> bool {
+    registered_account(outcome).is_none()
+}
+
+#[bridge_fn]
+fn RegistrationOutcome_GetAci(outcome: &RegistrationOutcome) -> Option<&str> {
+    registered_account(outcome).map(|account| account.aci.as_str())
+}
+
+#[bridge_fn]
+fn RegistrationOutcome_GetPni(outcome: &RegistrationOutcome) -> Option<&str> {
+    registered_account(outcome).map(|account| account.pni.as_str())
+}
+
+#[bridge_fn]
+fn RegistrationOutcome_GetNumber(outcome: &RegistrationOutcome) -> Option<&str> {
+    registered_account(outcome).map(|account| account.number.as_str())
+}
+
+/// Returns 0 if the account was registered.
+#[bridge_fn]
+fn RegistrationOutcome_GetLockTimeRemainingMs(outcome: &RegistrationOutcome) -> u64 {
+    match outcome {
+        RegistrationOutcome::Registered(_) => 0,
+        RegistrationOutcome::Locked {
+            time_remaining_ms, ..
+        } => *time_remaining_ms,
+    }
+}
+
+#[bridge_fn]
+fn RegistrationOutcome_GetSvrUsername(outcome: &RegistrationOutcome) -> Option<&str> {
+    svr_credentials(outcome).map(|credentials| credentials.username.as_str())
+}
+
+#[bridge_fn]
+fn RegistrationOutcome_GetSvrPassword(outcome: &RegistrationOutcome) -> Option<&str> {
+    svr_credentials(outcome).map(|credentials| credentials.password.as_str())
+}
+
+#[bridge_fn]
+fn Registration_LockToken(master_key: &[u8]) -> Result<String> {
+    registration_lock_token(master_key)
+}
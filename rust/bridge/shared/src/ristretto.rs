@@ -0,0 +1,63 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Bridged Ristretto group operations, for prototyping new credentials and blinded identifiers.
+//!
+//! Only built with the `advanced-crypto` feature of the bridge crates, and not part of the
+//! published headers or app-facing APIs. Points and scalars cross the bridge in their 32-byte
+//! encodings; inputs that are not canonically encoded are rejected as invalid arguments.
+
+use libsignal_bridge_macros::*;
+use libsignal_protocol::error::{Result, SignalProtocolError};
+use poksho::ristretto::*;
+
+use crate::support::*;
+use crate::*;
+
+fn invalid_point(_: poksho::PokshoError) -> SignalProtocolError {
+    SignalProtocolError::InvalidArgument("invalid Ristretto point".into())
+}
+
+fn invalid_scalar(_: poksho::PokshoError) -> SignalProtocolError {
+    SignalProtocolError::InvalidArgument("invalid Ristretto scalar".into())
+}
+
+#[bridge_fn_buffer]
+fn Ristretto_HashToPoint<E: Env>(env: E, domain: &[u8], input: &[u8]) -> E::Buffer {
+    env.buffer(point_to_bytes(&hash_to_point(domain, input)).to_vec())
+}
+
+#[bridge_fn_buffer]
+fn Ristretto_HashToScalar<E: Env>(env: E, domain: &[u8], input: &[u8]) -> E::Buffer {
+    env.buffer(scalar_to_bytes(&hash_to_scalar(domain, input)).to_vec())
+}
+
+#[bridge_fn_buffer]
+fn Ristretto_PointAdd<E: Env>(env: E, a: &[u8], b: &[u8]) -> Result<E::Buffer> {
+    let a = point_from_bytes(a).map_err(invalid_point)?;
+    let b = point_from_bytes(b).map_err(invalid_point)?;
+    Ok(env.buffer(point_to_bytes(&(a + b)).to_vec()))
+}
+
+#[bridge_fn_buffer]
+fn Ristretto_PointMultiply<E: Env>(env: E, point: &[u8], scalar: &[u8]) -> Result<E::Buffer> {
+    let point = point_from_bytes(point).map_err(invalid_point)?;
+    let scalar = scalar_from_bytes(scalar).map_err(invalid_scalar)?;
+    Ok(env.buffer(point_to_bytes(&(scalar * point)).to_vec()))
+}
+
+/// Computes `value * G + blinding * H`, for generators G and H hashed from `domain`.
+#[bridge_fn_buffer]
+fn Ristretto_PedersenCommit<E: Env>(
+    env: E,
+    domain: &[u8],
+    value: &[u8],
+    blinding: &[u8],
+) -> Result<E::Buffer> {
+    let value = scalar_from_bytes(value).map_err(invalid_scalar)?;
+    let blinding = scalar_from_bytes(blinding).map_err(invalid_scalar)?;
+    let commitment = PedersenGenerators::new(domain).commit(&value, &blinding);
+    Ok(env.buffer(point_to_bytes(&commitment).to_vec()))
+}
@@ -0,0 +1,108 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use libsignal_bridge_macros::*;
+use libsignal_protocol::error::Result;
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use std::convert::TryInto;
+use std::sync::Mutex;
+
+use crate::support::*;
+use crate::*;
+
+enum RngSource {
+    System,
+    Seeded(ChaCha20Rng),
+}
+
+/// A source of randomness that can be passed to any bridged API that generates keys, nonces, or
+/// signatures.
+///
+/// Bridged functions take an optional `Rng`; if none is provided they use the operating system's
+/// RNG, as they always have.
+pub struct Rng(Mutex<RngSource>);
+
+impl Rng {
+    /// Uses the operating system's RNG.
+    pub fn system() -> Self {
+        Self(Mutex::new(RngSource::System))
+    }
+
+    /// Produces a deterministic stream of bytes from `seed`.
+    ///
+    /// This is intended for tests only; anything generated with a seeded RNG is only as secret as
+    /// the seed.
+    pub fn seeded(seed: [u8; 32]) -> Self {
+        Self(Mutex::new(RngSource::Seeded(ChaCha20Rng::from_seed(seed))))
+    }
+
+    fn with_source<T>(&self, f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+        let mut source = self.0.lock().expect("not poisoned");
+        match &mut *source {
+            RngSource::System => f(&mut OsRng),
+            RngSource::Seeded(rng) => f(rng),
+        }
+    }
+}
+
+/// The RNG used by a single bridged call.
+///
+/// The underlying [`Rng`] is locked for each individual request rather than for the lifetime of
+/// the call, so a `CallRng` can be held across an `await`.
+#[derive(Clone, Copy)]
+pub struct CallRng<'a>(Option<&'a Rng>);
+
+impl<'a> CallRng<'a> {
+    pub fn new(rng: Option<&'a Rng>) -> Self {
+        Self(rng)
+    }
+}
+
+impl RngCore for CallRng<'_> {
+    fn next_u32(&mut self) -> u32 {
+        match self.0 {
+            Some(rng) => rng.with_source(|source| source.next_u32()),
+            None => OsRng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self.0 {
+            Some(rng) => rng.with_source(|source| source.next_u64()),
+            None => OsRng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self.0 {
+            Some(rng) => rng.with_source(|source| source.fill_bytes(dest)),
+            None => OsRng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> std::result::Result<(), rand::Error> {
+        match self.0 {
+            Some(rng) => rng.with_source(|source| source.try_fill_bytes(dest)),
+            None => OsRng.try_fill_bytes(dest),
+        }
+    }
+}
+
+// Both sources are cryptographically secure.
+impl CryptoRng for CallRng<'_> {}
+
+bridge_handle!(Rng, shared = true);
+
+#[bridge_fn]
+fn Rng_NewSystem() -> Rng {
+    Rng::system()
+}
+
+#[bridge_fn(len(seed = 32))]
+fn Rng_NewSeeded(seed: &[u8]) -> Result<Rng> {
+    Ok(Rng::seeded(seed.try_into().expect("checked length")))
+}
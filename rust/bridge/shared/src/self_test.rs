@@ -0,0 +1,295 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! A quick known-answer check that the native layer is functioning.
+//!
+//! Each check runs in isolation (a panic in one does not prevent the others from running) and is
+//! timed individually. The result is reported as a JSON object so that every platform can present
+//! it without needing bespoke bridging:
+//!
+//! ```json
+//! {"passed":true,"checks":[{"name":"curve_agreement","passed":true,"duration_us":41,"error":null}]}
+//! ```
+
+use libsignal_bridge_macros::*;
+use libsignal_protocol::*;
+use rand::rngs::OsRng;
+use std::fmt::Write;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::time::Instant;
+
+use crate::support::*;
+use crate::*;
+
+type CheckResult = std::result::Result<(), String>;
+
+struct CheckReport {
+    name: &'static str,
+    duration_us: u128,
+    error: Option<String>,
+}
+
+const CHECKS: &[(&str, fn() -> CheckResult)] = &[
+    ("curve_agreement", check_curve_agreement),
+    ("aes_gcm", check_aes_gcm),
+    ("hkdf", check_hkdf),
+    ("ratchet_exchange", check_ratchet_exchange),
+];
+
+fn from_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("valid hex in test vector"))
+        .collect()
+}
+
+fn expect_equal(what: &str, actual: &[u8], expected: &[u8]) -> CheckResult {
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(format!("{} did not match the expected value", what))
+    }
+}
+
+/// RFC 7748 section 6.1.
+fn check_curve_agreement() -> CheckResult {
+    let alice_private = PrivateKey::deserialize(&from_hex(
+        "77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a",
+    ))
+    .map_err(|e| e.to_string())?;
+    let mut bob_public = vec![0x05];
+    bob_public.extend(from_hex(
+        "de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f",
+    ));
+    let bob_public = PublicKey::deserialize(&bob_public).map_err(|e| e.to_string())?;
+
+    let shared = alice_private
+        .calculate_agreement(&bob_public)
+        .map_err(|e| e.to_string())?;
+    expect_equal(
+        "shared secret",
+        &shared,
+        &from_hex("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742"),
+    )
+}
+
+/// Wycheproof aes_gcm_test.json, tcId 73.
+fn check_aes_gcm() -> CheckResult {
+    let key = from_hex("92ace3e348cd821092cd921aa3546374299ab46209691bc28b8752d17f123c20");
+    let nonce = from_hex("00112233445566778899aabb");
+    let aad = from_hex("00000000ffffffff");
+    let plaintext = from_hex("00010203040506070809");
+    let expected_ciphertext = from_hex("e27abdd2d2a53d2f136b");
+    let expected_tag = from_hex("9a4a2579529301bcfb71c78d4060f52c");
+
+    let mut buf = plaintext.clone();
+    let mut gcm =
+        signal_crypto::Aes256GcmEncryption::new(&key, &nonce, &aad).map_err(|e| e.to_string())?;
+    gcm.encrypt(&mut buf).map_err(|e| e.to_string())?;
+    let tag = gcm.compute_tag().map_err(|e| e.to_string())?;
+    expect_equal("ciphertext", &buf, &expected_ciphertext)?;
+    expect_equal("tag", &tag, &expected_tag)?;
+
+    let mut gcm =
+        signal_crypto::Aes256GcmDecryption::new(&key, &nonce, &aad).map_err(|e| e.to_string())?;
+    gcm.decrypt(&mut buf).map_err(|e| e.to_string())?;
+    gcm.verify_tag(&tag).map_err(|e| e.to_string())?;
+    expect_equal("decrypted plaintext", &buf, &plaintext)
+}
+
+/// RFC 5869 test case 1.
+fn check_hkdf() -> CheckResult {
+    let ikm = [0x0bu8; 22];
+    let salt = from_hex("000102030405060708090a0b0c");
+    let info = from_hex("f0f1f2f3f4f5f6f7f8f9");
+
+    let okm = HKDF::new(3)
+        .and_then(|hkdf| hkdf.derive_salted_secrets(&ikm, &salt, &info, 42))
+        .map_err(|e| e.to_string())?;
+    expect_equal(
+        "output key material",
+        &okm,
+        &from_hex(
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865",
+        ),
+    )
+}
+
+/// Establishes a session between two in-memory stores and exchanges a message in each direction.
+///
+/// The in-memory stores never suspend, so the whole exchange completes synchronously.
+fn check_ratchet_exchange() -> CheckResult {
+    expect_ready(ratchet_exchange()).map_err(|e| e.to_string())
+}
+
+async fn ratchet_exchange() -> std::result::Result<(), SignalProtocolError> {
+    let mut csprng = OsRng;
+    let alice_address = ProtocolAddress::new("+14151111111".to_owned(), 1);
+    let bob_address = ProtocolAddress::new("+14151111112".to_owned(), 1);
+
+    let mut alice_store = InMemSignalProtocolStore::new(IdentityKeyPair::generate(&mut csprng), 1)?;
+    let mut bob_store = InMemSignalProtocolStore::new(IdentityKeyPair::generate(&mut csprng), 2)?;
+
+    let bob_identity = bob_store.get_identity_key_pair(None).await?;
+    let bob_pre_key = KeyPair::generate(&mut csprng);
+    let bob_signed_pre_key = KeyPair::generate(&mut csprng);
+    let bob_signed_pre_key_signature = bob_identity
+        .private_key()
+        .calculate_signature(&bob_signed_pre_key.public_key.serialize(), &mut csprng)?;
+
+    let bundle = PreKeyBundle::new(
+        bob_store.get_local_registration_id(None).await?,
+        1,
+        Some((1, bob_pre_key.public_key)),
+        2,
+        bob_signed_pre_key.public_key,
+        bob_signed_pre_key_signature.to_vec(),
+        *bob_identity.identity_key(),
+    )?;
+    bob_store
+        .save_pre_key(1, &PreKeyRecord::new(1, &bob_pre_key), None)
+        .await?;
+    bob_store
+        .save_signed_pre_key(
+            2,
+            &SignedPreKeyRecord::new(2, 0, &bob_signed_pre_key, &bob_signed_pre_key_signature),
+            None,
+        )
+        .await?;
+
+    process_prekey_bundle(
+        &bob_address,
+        &mut alice_store.session_store,
+        &mut alice_store.identity_store,
+        &bundle,
+        &mut csprng,
+        None,
+    )
+    .await?;
+
+    let request = b"self-test request";
+    let ciphertext = message_encrypt(
+        request,
+        &bob_address,
+        &mut alice_store.session_store,
+        &mut alice_store.identity_store,
+        None,
+    )
+    .await?;
+    let decrypted = message_decrypt(
+        &ciphertext,
+        &alice_address,
+        &mut bob_store.session_store,
+        &mut bob_store.identity_store,
+        &mut bob_store.pre_key_store,
+        &mut bob_store.signed_pre_key_store,
+        &mut csprng,
+        None,
+    )
+    .await?;
+    if decrypted != request {
+        return Err(SignalProtocolError::InvalidMessage(
+            "request decrypted incorrectly",
+        ));
+    }
+
+    let response = b"self-test response";
+    let ciphertext = message_encrypt(
+        response,
+        &alice_address,
+        &mut bob_store.session_store,
+        &mut bob_store.identity_store,
+        None,
+    )
+    .await?;
+    let decrypted = message_decrypt(
+        &ciphertext,
+        &bob_address,
+        &mut alice_store.session_store,
+        &mut alice_store.identity_store,
+        &mut alice_store.pre_key_store,
+        &mut alice_store.signed_pre_key_store,
+        &mut csprng,
+        None,
+    )
+    .await?;
+    if decrypted != response {
+        return Err(SignalProtocolError::InvalidMessage(
+            "response decrypted incorrectly",
+        ));
+    }
+
+    Ok(())
+}
+
+fn run_check(name: &'static str, check: fn() -> CheckResult) -> CheckReport {
+    let start = Instant::now();
+    let outcome = catch_unwind(AssertUnwindSafe(check));
+    let duration_us = start.elapsed().as_micros();
+    let error = match outcome {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e),
+        Err(panic) => Some(format!("panicked: {}", describe_panic(&panic))),
+    };
+    CheckReport {
+        name,
+        duration_us,
+        error,
+    }
+}
+
+fn report_to_json(reports: &[CheckReport]) -> String {
+    let mut out = String::new();
+    write!(
+        out,
+        "{{\"passed\":{},\"checks\":[",
+        reports.iter().all(|r| r.error.is_none())
+    )
+    .expect("writing to a String cannot fail");
+    for (i, report) in reports.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str("{\"name\":");
+        write_json_string(&mut out, report.name);
+        write!(
+            out,
+            ",\"passed\":{},\"duration_us\":{},\"error\":",
+            report.error.is_none(),
+            report.duration_us
+        )
+        .expect("writing to a String cannot fail");
+        match &report.error {
+            Some(error) => write_json_string(&mut out, error),
+            None => out.push_str("null"),
+        }
+        out.push('}');
+    }
+    out.push_str("]}");
+    out
+}
+
+/// Runs the known-answer self-test suite and returns a JSON report of the results.
+///
+/// This never fails; individual check failures are recorded in the report.
+pub fn run_self_test() -> String {
+    let reports: Vec<CheckReport> = CHECKS
+        .iter()
+        .map(|&(name, check)| run_check(name, check))
+        .collect();
+    report_to_json(&reports)
+}
+
+#[bridge_fn(ffi = "self_test")]
+fn Native_SelfTest() -> String {
+    run_self_test()
+}
+
+/// Panics with `message`, so that each platform's tests can check that a panic in the native layer
+/// is reported as an error rather than aborting the process.
+#[bridge_fn_void(ffi = "test_panic")]
+fn Native_TestPanic(message: String) {
+    panic!("{}", message)
+}
@@ -0,0 +1,175 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Sets of service IDs, for intersecting contact discovery results with local contact lists.
+//!
+//! Service IDs cross the bridge in bulk in their fixed-width binary form: a kind byte (0 for an
+//! ACI, 1 for a PNI) followed by the 16 bytes of the UUID. A list of IDs is just these 17-byte
+//! entries concatenated. Sets are kept sorted (ACIs before PNIs, then by UUID), so that a large set
+//! can be read back a page at a time in a stable order.
+
+use libsignal_bridge_macros::*;
+use libsignal_protocol::error::{Result, SignalProtocolError};
+use std::collections::BTreeSet;
+
+use crate::support::*;
+use crate::*;
+
+/// The size of a service ID in its fixed-width binary form.
+pub const SERVICE_ID_FIXED_WIDTH_LEN: usize = 17;
+
+const UUID_LEN: usize = 16;
+
+/// An ACI or PNI, which identify an account and its phone number respectively.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ServiceId {
+    Aci([u8; UUID_LEN]),
+    Pni([u8; UUID_LEN]),
+}
+
+impl ServiceId {
+    pub fn parse_fixed_width_binary(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != SERVICE_ID_FIXED_WIDTH_LEN {
+            return Err(SignalProtocolError::InvalidArgument(format!(
+                "service ID must be {} bytes, not {}",
+                SERVICE_ID_FIXED_WIDTH_LEN,
+                bytes.len()
+            )));
+        }
+        let mut uuid = [0; UUID_LEN];
+        uuid.copy_from_slice(&bytes[1..]);
+        match bytes[0] {
+            0 => Ok(ServiceId::Aci(uuid)),
+            1 => Ok(ServiceId::Pni(uuid)),
+            kind => Err(SignalProtocolError::InvalidArgument(format!(
+                "unknown service ID kind {}",
+                kind
+            ))),
+        }
+    }
+
+    pub fn fixed_width_binary(&self) -> [u8; SERVICE_ID_FIXED_WIDTH_LEN] {
+        let (kind, uuid) = match self {
+            ServiceId::Aci(uuid) => (0, uuid),
+            ServiceId::Pni(uuid) => (1, uuid),
+        };
+        let mut result = [0; SERVICE_ID_FIXED_WIDTH_LEN];
+        result[0] = kind;
+        result[1..].copy_from_slice(uuid);
+        result
+    }
+}
+
+/// A sorted set of service IDs.
+#[derive(Clone, Default)]
+pub struct ServiceIdSet {
+    ids: BTreeSet<ServiceId>,
+}
+
+impl ServiceIdSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn contains(&self, id: &ServiceId) -> bool {
+        self.ids.contains(id)
+    }
+
+    /// Adds every ID in a list of fixed-width binary IDs.
+    ///
+    /// The whole list is checked first, so nothing is added if any entry is invalid.
+    pub fn extend_from_fixed_width_binary(&mut self, list: &[u8]) -> Result<()> {
+        if list.len() % SERVICE_ID_FIXED_WIDTH_LEN != 0 {
+            return Err(SignalProtocolError::InvalidArgument(format!(
+                "service ID list length {} is not a multiple of {}",
+                list.len(),
+                SERVICE_ID_FIXED_WIDTH_LEN
+            )));
+        }
+        let ids = list
+            .chunks(SERVICE_ID_FIXED_WIDTH_LEN)
+            .map(ServiceId::parse_fixed_width_binary)
+            .collect::<Result<Vec<_>>>()?;
+        self.ids.extend(ids);
+        Ok(())
+    }
+
+    pub fn intersection(&self, other: &ServiceIdSet) -> ServiceIdSet {
+        Self {
+            ids: self.ids.intersection(&other.ids).copied().collect(),
+        }
+    }
+
+    /// The IDs in `self` that are not in `other`.
+    pub fn difference(&self, other: &ServiceIdSet) -> ServiceIdSet {
+        Self {
+            ids: self.ids.difference(&other.ids).copied().collect(),
+        }
+    }
+
+    /// Up to `limit` IDs in fixed-width binary form, skipping the first `offset`.
+    pub fn page(&self, offset: usize, limit: usize) -> Vec<u8> {
+        let mut result = Vec::with_capacity(
+            std::cmp::min(limit, self.len().saturating_sub(offset)) * SERVICE_ID_FIXED_WIDTH_LEN,
+        );
+        for id in self.ids.iter().skip(offset).take(limit) {
+            result.extend_from_slice(&id.fixed_width_binary());
+        }
+        result
+    }
+}
+
+bridge_handle!(ServiceIdSet, mut = true);
+
+#[bridge_fn]
+fn ServiceIdSet_New() -> ServiceIdSet {
+    ServiceIdSet::new()
+}
+
+/// Adds a list of fixed-width binary service IDs.
+#[bridge_fn_void]
+fn ServiceIdSet_AddFixedWidthBinary(set: &mut ServiceIdSet, list: &[u8]) -> Result<()> {
+    set.extend_from_fixed_width_binary(list)
+}
+
+#[bridge_fn]
+fn ServiceIdSet_Len(set: &ServiceIdSet) -> u32 {
+    set.len() as u32
+}
+
+#[bridge_fn]
+fn ServiceIdSet_Contains(set: &ServiceIdSet, service_id: &[u8]) -> Result<bool> {
+    Ok(set.contains(&ServiceId::parse_fixed_width_binary(service_id)?))
+}
+
+#[bridge_fn]
+fn ServiceIdSet_Intersection(set: &ServiceIdSet, other: &ServiceIdSet) -> ServiceIdSet {
+    set.intersection(other)
+}
+
+#[bridge_fn]
+fn ServiceIdSet_Difference(set: &ServiceIdSet, other: &ServiceIdSet) -> ServiceIdSet {
+    set.difference(other)
+}
+
+/// Returns up to `limit` IDs in fixed-width binary form, in sorted order, skipping the first
+/// `offset`.
+#[bridge_fn_buffer]
+fn ServiceIdSet_GetPage<E: Env>(
+    env: E,
+    set: &ServiceIdSet,
+    offset: u32,
+    limit: u32,
+) -> Result<E::Buffer> {
+    Ok(env.buffer(set.page(offset as usize, limit as usize)))
+}
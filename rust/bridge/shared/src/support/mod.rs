@@ -5,7 +5,10 @@
 
 use futures::pin_mut;
 use futures::task::noop_waker_ref;
+use std::any::Any;
 use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt::Write;
 use std::future::Future;
 use std::task::{self, Poll};
 
@@ -25,6 +28,53 @@ pub fn expect_ready<F: Future>(future: F) -> F::Output {
     }
 }
 
+/// Extracts the message from a panic caught at a bridge boundary, if there is one.
+pub fn describe_panic(any: &Box<dyn Any + Send>) -> String {
+    if let Some(msg) = any.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = any.downcast_ref::<String>() {
+        msg.to_string()
+    } else {
+        "(break on rust_panic to debug)".to_string()
+    }
+}
+
+/// Appends `s` to `out` as a quoted JSON string.
+pub(crate) fn write_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                write!(out, "\\u{:04x}", c as u32).expect("writing to a String cannot fail")
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Checks an argument length requirement declared with `#[bridge_fn(len(arg = N))]`.
+///
+/// Not intended to be invoked directly.
+pub fn check_argument_length(
+    name: &'static str,
+    actual: usize,
+    expected: usize,
+) -> Result<(), libsignal_protocol::SignalProtocolError> {
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(libsignal_protocol::SignalProtocolError::InvalidArgument(
+            format!("{} must be {} bytes (got {})", name, expected, actual),
+        ))
+    }
+}
+
 /// Used for returning newly-allocated buffers as efficiently as possible.
 ///
 /// Functions marked `#[bridge_fn_buffer]` must have an `Env` as their first parameter.
@@ -33,6 +83,46 @@ pub(crate) trait Env {
     fn buffer<'a, T: Into<Cow<'a, [u8]>>>(self, input: T) -> Self::Buffer;
 }
 
+/// Records a generated entry point in
+/// [`introspection::BRIDGE_FUNCTIONS`](crate::introspection::BRIDGE_FUNCTIONS).
+///
+/// The argument and result types are written in the language of the bridge. The first form names
+/// the registry entry and exports it, which keeps it from being dropped when linking a cdylib (used
+/// for JNI and Node); the second form is anonymous (used for FFI). Either form expands to nothing
+/// unless the "introspection" feature is enabled.
+///
+/// Used by `bridge_fn` and the other bridge macros; not intended to be invoked directly.
+macro_rules! bridge_registry_entry {
+    ($static_name:ident: $bridge:literal, $name:expr, [$($arg:expr),*] -> $result:expr, $is_async:expr) => {
+        #[cfg(feature = "introspection")]
+        #[no_mangle] // necessary because we are linking as a cdylib
+        #[allow(non_upper_case_globals)]
+        #[linkme::distributed_slice(crate::introspection::BRIDGE_FUNCTIONS)]
+        static $static_name: crate::introspection::BridgeFunctionInfo =
+            crate::introspection::BridgeFunctionInfo {
+                bridge: $bridge,
+                name: $name,
+                args: &[$($arg),*],
+                result: $result,
+                is_async: $is_async,
+            };
+    };
+    ($bridge:literal, $name:expr, [$($arg:expr),*] -> $result:expr, $is_async:expr) => {
+        #[cfg(feature = "introspection")]
+        const _: () = {
+            #[linkme::distributed_slice(crate::introspection::BRIDGE_FUNCTIONS)]
+            static ENTRY: crate::introspection::BridgeFunctionInfo =
+                crate::introspection::BridgeFunctionInfo {
+                    bridge: $bridge,
+                    name: $name,
+                    args: &[$($arg),*],
+                    result: $result,
+                    is_async: $is_async,
+                };
+        };
+    };
+}
+
 /// Exposes a Rust type to each of the bridges as a boxed value.
 ///
 /// Full form:
@@ -62,8 +152,19 @@ pub(crate) trait Env {
 /// - "Destroy" functions are generated for FFI and JNI based on the name of the type:
 ///   `signal_foo_destroy` and `Native.Foo_Destroy`.
 ///
-/// - If `clone = true` is passed to `bridge_handle`, a `signal_foo_clone` function will be
-///   generated for the FFI bridge as well. `Foo` must adopt `Clone`.
+/// - Unless `clone = false` is passed to `bridge_handle`, a `signal_foo_clone` function will be
+///   generated for the FFI bridge as well, along with `Foo_TransferToWorker` for Node (see below).
+///   `Foo` must adopt `Clone`.
+///
+/// - If `serialize = true` is passed to `bridge_handle` (before any other arguments), `Foo_Serialize`
+///   and `Foo_Deserialize` functions are generated for all three bridges as well, using
+///   `Foo`'s implementation of [`BridgeSerialize`]. See [`bridge_serialize`].
+///
+/// - If `shared = true` is passed to `bridge_handle`, the value is stored behind an [`Arc`][] and
+///   `signal_foo_clone`, `Native.Foo_Clone`, and `Foo_Clone` functions are generated for all three
+///   bridges. Each clone refers to the same value and must be destroyed independently; `Foo` does
+///   not need to adopt `Clone`, but it must be `Send` and `Sync`. Shared handles cannot be passed
+///   as `&mut Foo`, and `shared` cannot be combined with `clone` or `mut`.
 ///
 /// # Representation
 ///
@@ -84,15 +185,33 @@ pub(crate) trait Env {
 ///   For TypeScript's benefit, each boxed type gets its own unique `interface Foo`, and the
 ///   arguments are of the form `Wrapper<Foo>`.
 ///
+///   A box can only be used in the JavaScript context (main thread or worker) that created it.
+///   `Foo_TransferToWorker` copies the value out and returns a token that can be posted to another
+///   context, where `ReceiveTransferredHandle` puts the copy in a new box. Tokens that aren't
+///   received within a minute expire. For shared handles, the copy refers to the same value. `Foo`
+///   must be `Send` (and `Sync` for shared handles); this is checked at compile time.
+///
+/// A `Vec<Foo>` result is returned as an array of boxed values: a `SignalFoo **` and a length for
+/// FFI (the array itself is freed with `signal_free_handle_array`), a `long[]` for JNI, and an
+/// `Array` for Node.
+///
 /// [`JsBox`]: https://docs.rs/neon/0.7.1-napi/neon/types/struct.JsBox.html
+/// [`Arc`]: std::sync::Arc
 macro_rules! bridge_handle {
-    ($typ:ty $(, clone = $_clone:tt)? $(, mut = $_mut:tt)? $(, ffi = $ffi_name:ident)? $(, jni = $jni_name:ident)? $(, node = $node_name:ident)?) => {
+    ($typ:ident, serialize = true $(, $($rest:tt)*)?) => {
+        bridge_handle!($typ $(, $($rest)*)?);
+        bridge_serialize!($typ $(, $($rest)*)?);
+    };
+    ($typ:ty $(, clone = $_clone:tt)? $(, mut = $_mut:tt)? $(, shared = $_shared:tt)? $(, ffi = $ffi_name:ident)? $(, jni = $jni_name:ident)? $(, node = $node_name:ident)?) => {
+        impl crate::support::BridgeHandle for $typ {}
         #[cfg(feature = "ffi")]
-        ffi_bridge_handle!($typ $(as $ffi_name)? $(, clone = $_clone)?);
+        ffi_bridge_handle!($typ $(as $ffi_name)? $(, clone = $_clone)? $(, shared = $_shared)?);
         #[cfg(feature = "jni")]
-        jni_bridge_handle!($typ $(as $jni_name)?);
+        jni_bridge_handle!($typ $(as $jni_name)? $(, shared = $_shared)?);
+        #[cfg(feature = "node")]
+        node_bridge_handle!($typ $(as $node_name)? $(, mut = $_mut)? $(, shared = $_shared)?);
         #[cfg(feature = "node")]
-        node_bridge_handle!($typ $(as $node_name)? $(, mut = $_mut)?);
+        node_bridge_transfer!($typ $(as $node_name)? $(, clone = $_clone)? $(, mut = $_mut)? $(, shared = $_shared)?);
     };
 }
 
@@ -130,6 +249,108 @@ macro_rules! bridge_deserialize {
     }
 }
 
+/// Implemented by [`bridge_handle`] for every type it exposes.
+///
+/// This lets each bridge convert a `Vec` of handles without overlapping its other `Vec`
+/// conversions (such as `Vec<u8>`).
+pub trait BridgeHandle {}
+
+/// A type whose serialized form is exposed to the bridges as `Foo_Serialize` and
+/// `Foo_Deserialize`.
+///
+/// Use `bridge_handle!(Foo, serialize = true)` to generate the entry points. The same
+/// serialization is used by [`bridge_transport`].
+pub trait BridgeSerialize: Sized {
+    fn bridge_serialize(&self) -> Result<Vec<u8>, libsignal_protocol::SignalProtocolError>;
+    fn bridge_deserialize(data: &[u8]) -> Result<Self, libsignal_protocol::SignalProtocolError>;
+}
+
+/// Exposes serialization and deserialization of a [`BridgeSerialize`] type to the bridges.
+///
+/// This is normally invoked through `bridge_handle!(Foo, serialize = true)`, which passes along the
+/// same `ffi`, `jni`, and `node` type names; other `bridge_handle` arguments are ignored. The
+/// generated functions are the same as those from [`bridge_deserialize`] and from
+/// `bridge_get_bytearray!(Foo::serialize as Serialize)`: `signal_foo_serialize`,
+/// `Native.Foo_Serialize`, and `Foo_Serialize`, plus the corresponding `Deserialize` functions.
+macro_rules! bridge_serialize {
+    ($typ:ident $(, clone = $_clone:tt)? $(, mut = $_mut:tt)? $(, shared = $_shared:tt)? $(, ffi = $ffi_name:ident)? $(, jni = $jni_name:ident)? $(, node = $node_name:ident)?) => {
+        bridge_deserialize!($typ::bridge_deserialize $(, ffi = $ffi_name)? $(, jni = $jni_name)? $(, node = $node_name)?);
+        #[cfg(feature = "ffi")]
+        ffi_bridge_serialize!($typ $(as $ffi_name)?);
+        #[cfg(feature = "jni")]
+        jni_bridge_serialize!($typ $(as $jni_name)?);
+        #[cfg(feature = "node")]
+        node_bridge_serialize!($typ $(as $node_name)?);
+    };
+}
+
+/// Prefixes serialized data with the name of its type, for [`bridge_transport`].
+#[allow(dead_code)] // only used in Node builds
+pub fn encode_for_transport(type_name: &str, data: Vec<u8>) -> Vec<u8> {
+    let name_len = u8::try_from(type_name.len()).expect("type names are short");
+    let mut result = Vec::with_capacity(1 + type_name.len() + data.len());
+    result.push(name_len);
+    result.extend_from_slice(type_name.as_bytes());
+    result.extend(data);
+    result
+}
+
+/// Checks and strips the prefix added by [`encode_for_transport`].
+#[allow(dead_code)] // only used in Node builds
+pub fn decode_from_transport<'a>(
+    type_name: &str,
+    data: &'a [u8],
+) -> Result<&'a [u8], libsignal_protocol::SignalProtocolError> {
+    let (name, rest) = match data.split_first() {
+        Some((&name_len, rest)) if rest.len() >= name_len as usize => {
+            rest.split_at(name_len as usize)
+        }
+        _ => {
+            return Err(libsignal_protocol::SignalProtocolError::InvalidArgument(
+                format!("{} transport data is truncated", type_name),
+            ))
+        }
+    };
+    if name != type_name.as_bytes() {
+        return Err(libsignal_protocol::SignalProtocolError::InvalidArgument(
+            format!(
+                "expected {} transport data, got {}",
+                type_name,
+                String::from_utf8_lossy(name)
+            ),
+        ));
+    }
+    Ok(rest)
+}
+
+/// Lets a [`BridgeSerialize`] handle be sent between processes as bytes.
+///
+/// ```no_run
+/// # #[macro_use] extern crate libsignal_bridge;
+/// bridge_transport!(PreKeyBundle);
+/// ```
+///
+/// This generates `PreKeyBundle_SerializeForTransport` and `PreKeyBundle_DeserializeFromTransport`
+/// for Node, for passing handles over channels that can only carry plain data, such as Electron
+/// IPC with context isolation. The data is the `bridge_serialize` form prefixed with the type name,
+/// so deserializing it as a different type fails instead of producing garbage. It is not meant to
+/// be persisted; use `Foo_Serialize` for that.
+macro_rules! bridge_transport {
+    ($typ:ident) => {
+        paste! {
+            #[bridge_fn(ffi = false, jni = false)]
+            fn [<$typ _SerializeForTransport>](obj: &$typ) -> Result<Vec<u8>> {
+                Ok(encode_for_transport(stringify!($typ), obj.bridge_serialize()?))
+            }
+
+            #[bridge_fn(ffi = false, jni = false)]
+            fn [<$typ _DeserializeFromTransport>](data: &[u8]) -> Result<$typ> {
+                $typ::bridge_deserialize(decode_from_transport(stringify!($typ), data)?)
+            }
+        }
+    };
+}
+
 /// Exposes a buffer-returning getter to the bridges.
 ///
 /// Example:
@@ -266,3 +487,51 @@ macro_rules! bridge_get {
         }
     };
 }
+
+/// Exposes a large result set to the bridges a batch at a time.
+///
+/// ```no_run
+/// # #[macro_use] extern crate libsignal_bridge;
+/// bridge_stream!(SenderKeyIdStream);
+/// ```
+///
+/// This declares a handle type `SenderKeyIdStream` wrapping an iterator of buffers, created with
+/// `SenderKeyIdStream::new(iter)`, so that APIs returning many items don't have to materialize
+/// them all in one array. It also generates:
+///
+/// - `SenderKeyIdStream_Next(stream, batch_size)` for JNI and Node, which returns the next
+///   `batch_size` items (as `byte[][]` and `Buffer[]` respectively). A batch shorter than
+///   `batch_size` means the stream is exhausted.
+///
+/// - `signal_sender_key_id_stream_next_item` for FFI, which returns one item per call and `NULL`
+///   once the stream is exhausted. Since C callers can use the buffer directly, there is no
+///   per-call conversion cost to amortize with batches.
+///
+/// The iterator is only advanced as items are requested, so it should produce them lazily.
+// No bridged API streams its results yet.
+#[allow(unused_macros)]
+macro_rules! bridge_stream {
+    ($typ:ident) => {
+        pub struct $typ(Box<dyn Iterator<Item = Vec<u8>> + Send>);
+
+        impl $typ {
+            pub fn new(items: impl Iterator<Item = Vec<u8>> + Send + 'static) -> Self {
+                Self(Box::new(items))
+            }
+        }
+
+        bridge_handle!($typ, clone = false, mut = true);
+
+        paste! {
+            #[bridge_fn(ffi = false)]
+            fn [<$typ _Next>](stream: &mut $typ, batch_size: u32) -> Vec<Vec<u8>> {
+                stream.0.by_ref().take(batch_size as usize).collect()
+            }
+
+            #[bridge_fn_buffer(jni = false, node = false)]
+            fn [<$typ _NextItem>]<E: Env>(env: E, stream: &mut $typ) -> Result<Option<E::Buffer>> {
+                Ok(stream.0.next().map(|item| env.buffer(item)))
+            }
+        }
+    };
+}
@@ -0,0 +1,83 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Points in time passed across the bridge.
+//!
+//! Every platform represents a timestamp as a plain integer, so a value in seconds can easily be
+//! passed where milliseconds are expected (or vice versa). Bridge functions take a [`Timestamp`]
+//! instead of a bare `u64`; it is always in milliseconds, and values far enough in the future to
+//! have been computed in the wrong unit are rejected during argument conversion.
+//!
+//! Returned timestamps are converted to each platform's usual form: a `Date` in JavaScript, and
+//! milliseconds since the epoch (`long` or `uint64_t`) in Java and Swift.
+
+use libsignal_protocol::SignalProtocolError;
+use std::convert::TryFrom;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A point in time, in milliseconds since the Unix epoch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+    /// The latest accepted timestamp, the last millisecond of the year 9999.
+    ///
+    /// A current time in seconds mistakenly multiplied by 1000 again lands far beyond this.
+    pub const MAX: Timestamp = Timestamp(253_402_300_799_999);
+
+    pub fn from_epoch_millis(millis: u64) -> Result<Self, SignalProtocolError> {
+        if millis > Self::MAX.0 {
+            return Err(SignalProtocolError::InvalidArgument(format!(
+                "timestamp {} ms is implausibly far in the future",
+                millis
+            )));
+        }
+        Ok(Self(millis))
+    }
+
+    pub fn from_epoch_seconds(seconds: u64) -> Result<Self, SignalProtocolError> {
+        match seconds.checked_mul(1000) {
+            Some(millis) => Self::from_epoch_millis(millis),
+            None => Err(SignalProtocolError::InvalidArgument(format!(
+                "timestamp {} s is implausibly far in the future",
+                seconds
+            ))),
+        }
+    }
+
+    /// Wraps a timestamp that came from a stored or received value rather than from an argument.
+    ///
+    /// Such values are not checked, since they are returned as is.
+    pub fn from_epoch_millis_unchecked(millis: u64) -> Self {
+        Self(millis)
+    }
+
+    pub fn epoch_millis(self) -> u64 {
+        self.0
+    }
+}
+
+/// Rejects times before the epoch or after [`Timestamp::MAX`].
+impl TryFrom<SystemTime> for Timestamp {
+    type Error = SignalProtocolError;
+
+    fn try_from(time: SystemTime) -> Result<Self, Self::Error> {
+        let since_epoch = time.duration_since(UNIX_EPOCH).map_err(|_| {
+            SignalProtocolError::InvalidArgument("time is before the Unix epoch".to_string())
+        })?;
+        match u64::try_from(since_epoch.as_millis()) {
+            Ok(millis) => Self::from_epoch_millis(millis),
+            Err(_) => Err(SignalProtocolError::InvalidArgument(
+                "time is implausibly far in the future".to_string(),
+            )),
+        }
+    }
+}
+
+impl From<Timestamp> for SystemTime {
+    fn from(timestamp: Timestamp) -> Self {
+        UNIX_EPOCH + Duration::from_millis(timestamp.0)
+    }
+}
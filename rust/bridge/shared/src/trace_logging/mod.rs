@@ -0,0 +1,31 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Forwards `tracing` output from libsignal-protocol to the `log` crate, and from there to
+//! whatever platform logger the app has installed.
+//!
+//! The subscriber is only built with the bridge's `tracing` feature, which also turns on the
+//! protocol crate's spans. Without it, the entry point is still present but does nothing.
+
+use libsignal_bridge_macros::*;
+
+use crate::*;
+
+#[cfg(feature = "tracing")]
+mod subscriber;
+#[cfg(feature = "tracing")]
+pub use subscriber::LogSubscriber;
+
+/// Installs a [`LogSubscriber`] as the process-wide `tracing` subscriber.
+///
+/// Span and event output then goes wherever log output goes, subject to the same level filter.
+/// Only the first call has any effect.
+#[bridge_fn_void]
+fn Tracing_InstallLogSubscriber() {
+    #[cfg(feature = "tracing")]
+    subscriber::install_log_subscriber();
+    #[cfg(not(feature = "tracing"))]
+    log::info!("built without the tracing feature; not installing a subscriber");
+}
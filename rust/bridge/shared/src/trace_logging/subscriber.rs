@@ -0,0 +1,284 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+extern crate tracing_crate as tracing;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+use crate::context::BridgeContext;
+
+struct SpanData {
+    name: &'static str,
+    level: Level,
+    target: &'static str,
+    fields: String,
+    parent: Option<Id>,
+    opened: Instant,
+    ref_count: usize,
+}
+
+thread_local! {
+    static ENTERED_SPANS: RefCell<Vec<Id>> = RefCell::new(Vec::new());
+}
+
+/// Appends each field as ` name=value`, except for an event's message, which is kept separately.
+#[derive(Default)]
+struct FieldFormatter {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldFormatter {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+}
+
+fn log_level(level: &Level) -> log::Level {
+    match *level {
+        Level::ERROR => log::Level::Error,
+        Level::WARN => log::Level::Warn,
+        Level::INFO => log::Level::Info,
+        Level::DEBUG => log::Level::Debug,
+        Level::TRACE => log::Level::Trace,
+    }
+}
+
+/// A [`Subscriber`] that turns events into log records.
+///
+/// Each record is prefixed with the spans it happened in, outermost first, along with their
+/// fields. Closing a span is logged at the span's own level, with how long it was open, and
+/// counted in the metrics of the current [`BridgeContext`], if any.
+pub struct LogSubscriber {
+    next_id: AtomicU64,
+    spans: Mutex<HashMap<u64, SpanData>>,
+}
+
+impl LogSubscriber {
+    pub fn new() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            spans: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn current_span(&self) -> Option<Id> {
+        ENTERED_SPANS.with(|stack| stack.borrow().last().cloned())
+    }
+
+    /// Formats the chain of spans ending at `id` as `outer{a=1}:inner{b=2}: `.
+    fn span_context(&self, spans: &HashMap<u64, SpanData>, mut id: Option<Id>) -> String {
+        let mut chain = Vec::new();
+        while let Some(span) = id.and_then(|id| spans.get(&id.into_u64())) {
+            chain.push(span);
+            id = span.parent.clone();
+        }
+        let mut context = String::new();
+        for span in chain.iter().rev() {
+            let _ = write!(context, "{}{{{}}}:", span.name, span.fields.trim_start());
+        }
+        if !context.is_empty() {
+            context.push(' ');
+        }
+        context
+    }
+
+    fn log(
+        &self,
+        level: &Level,
+        target: &str,
+        file: Option<&str>,
+        line: Option<u32>,
+        args: fmt::Arguments,
+    ) {
+        log::logger().log(
+            &log::Record::builder()
+                .level(log_level(level))
+                .target(target)
+                .file(file)
+                .line(line)
+                .args(args)
+                .build(),
+        );
+    }
+}
+
+impl Default for LogSubscriber {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Subscriber for LogSubscriber {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        let level = log_level(metadata.level());
+        level <= log::max_level()
+            && log::logger().enabled(
+                &log::Metadata::builder()
+                    .level(level)
+                    .target(metadata.target())
+                    .build(),
+            )
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut formatter = FieldFormatter::default();
+        span.record(&mut formatter);
+        let parent = if span.is_root() {
+            None
+        } else {
+            span.parent().cloned().or_else(|| self.current_span())
+        };
+        let metadata = span.metadata();
+        let data = SpanData {
+            name: metadata.name(),
+            level: *metadata.level(),
+            target: metadata.target(),
+            fields: formatter.fields,
+            parent: parent.clone(),
+            opened: Instant::now(),
+            ref_count: 1,
+        };
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut spans = self.spans.lock().expect("not poisoned");
+        // Keep the parent alive for as long as its child might need it for context.
+        if let Some(parent) = parent.and_then(|parent| spans.get_mut(&parent.into_u64())) {
+            parent.ref_count += 1;
+        }
+        spans.insert(id, data);
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut formatter = FieldFormatter::default();
+        values.record(&mut formatter);
+        if let Some(span) = self
+            .spans
+            .lock()
+            .expect("not poisoned")
+            .get_mut(&span.into_u64())
+        {
+            span.fields.push_str(&formatter.fields);
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut formatter = FieldFormatter::default();
+        event.record(&mut formatter);
+        let parent = if event.is_root() {
+            None
+        } else {
+            event.parent().cloned().or_else(|| self.current_span())
+        };
+        let context = self.span_context(&self.spans.lock().expect("not poisoned"), parent);
+        let metadata = event.metadata();
+        self.log(
+            metadata.level(),
+            metadata.target(),
+            metadata.file(),
+            metadata.line(),
+            format_args!("{}{}{}", context, formatter.message, formatter.fields),
+        );
+    }
+
+    fn enter(&self, span: &Id) {
+        ENTERED_SPANS.with(|stack| stack.borrow_mut().push(span.clone()));
+    }
+
+    fn exit(&self, span: &Id) {
+        ENTERED_SPANS.with(|stack| {
+            let mut stack = stack.borrow_mut();
+            if let Some(position) = stack.iter().rposition(|entered| entered == span) {
+                stack.remove(position);
+            }
+        });
+    }
+
+    fn clone_span(&self, span: &Id) -> Id {
+        if let Some(span) = self
+            .spans
+            .lock()
+            .expect("not poisoned")
+            .get_mut(&span.into_u64())
+        {
+            span.ref_count += 1;
+        }
+        span.clone()
+    }
+
+    fn try_close(&self, span: Id) -> bool {
+        let mut closed = Vec::new();
+        {
+            let mut spans = self.spans.lock().expect("not poisoned");
+            let mut next = Some(span.into_u64());
+            while let Some(id) = next.take() {
+                let data = match spans.get_mut(&id) {
+                    Some(data) => data,
+                    None => break,
+                };
+                data.ref_count -= 1;
+                if data.ref_count > 0 {
+                    break;
+                }
+                let data = spans.remove(&id).expect("just looked up");
+                let context = self.span_context(&spans, data.parent.clone());
+                // Release the reference this span held on its parent.
+                next = data.parent.as_ref().map(Id::into_u64);
+                closed.push((context, data));
+            }
+        }
+
+        let bridge_context = BridgeContext::current();
+        for (context, data) in &closed {
+            if let Some(bridge_context) = &bridge_context {
+                bridge_context.record_operation(data.name, data.opened.elapsed());
+            }
+            self.log(
+                &data.level,
+                data.target,
+                None,
+                None,
+                format_args!(
+                    "{}{}{{{}}}: closed after {}us",
+                    context,
+                    data.name,
+                    data.fields.trim_start(),
+                    data.opened.elapsed().as_micros()
+                ),
+            );
+        }
+        !closed.is_empty()
+    }
+}
+
+/// Installs a [`LogSubscriber`] as the process-wide `tracing` subscriber, unless one already is.
+pub(super) fn install_log_subscriber() {
+    if tracing::subscriber::set_global_default(LogSubscriber::new()).is_err() {
+        log::warn!("a tracing subscriber is already installed; ignoring later call");
+    }
+}
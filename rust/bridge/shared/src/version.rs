@@ -0,0 +1,82 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Lets wrappers check that they were built against a compatible native library.
+//!
+//! The native library and the platform wrappers are not always shipped together, so a wrapper
+//! can use [`Native_RequireAtLeast`] at startup to fail with a clear message instead of a
+//! confusing error on the first call that changed. The full details are reported as JSON:
+//!
+//! ```json
+//! {"version":"0.1.0","abi_revision":2,"features":["node"],"ciphertext_message_version":3,"sealed_sender_version":1}
+//! ```
+
+use libsignal_bridge_macros::*;
+use libsignal_protocol::*;
+use std::fmt::Write;
+
+use crate::support::*;
+use crate::*;
+
+/// Identifies the set of generated entry points and their signatures.
+///
+/// Bump this whenever an entry point is added, removed, or changes its arguments or result.
+pub const ABI_REVISION: u32 = 46;
+
+/// The bridges compiled into this library.
+fn enabled_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "ffi") {
+        features.push("ffi");
+    }
+    if cfg!(feature = "jni") {
+        features.push("jni");
+    }
+    if cfg!(feature = "node") {
+        features.push("node");
+    }
+    features
+}
+
+fn version_info_to_json() -> String {
+    let mut out = String::new();
+    out.push_str("{\"version\":");
+    write_json_string(&mut out, env!("CARGO_PKG_VERSION"));
+    write!(out, ",\"abi_revision\":{},\"features\":[", ABI_REVISION)
+        .expect("writing to a String cannot fail");
+    for (i, feature) in enabled_features().into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_json_string(&mut out, feature);
+    }
+    write!(
+        out,
+        "],\"ciphertext_message_version\":{},\"sealed_sender_version\":{}}}",
+        CIPHERTEXT_MESSAGE_CURRENT_VERSION, SEALED_SENDER_VERSION
+    )
+    .expect("writing to a String cannot fail");
+    out
+}
+
+#[bridge_fn(ffi = "version_info")]
+fn Native_VersionInfo() -> String {
+    version_info_to_json()
+}
+
+/// Fails if this library's [`ABI_REVISION`] is older than `abi_revision`.
+#[bridge_fn_void(ffi = "require_at_least")]
+fn Native_RequireAtLeast(abi_revision: u32) -> Result<(), SignalProtocolError> {
+    if ABI_REVISION < abi_revision {
+        return Err(SignalProtocolError::InvalidState(
+            "Native_RequireAtLeast",
+            format!(
+                "native library ABI revision {} is older than the required revision {}",
+                ABI_REVISION, abi_revision
+            ),
+        ));
+    }
+    Ok(())
+}
@@ -24,7 +24,7 @@ pub enum Aes256 {
 impl Aes256 {
     pub fn new(key: &[u8]) -> Result<Self> {
         if key.len() != 32 {
-            return Err(Error::InvalidKeySize);
+            return Err(Error::InvalidKeySize(32, key.len()));
         }
 
         #[cfg(target_arch = "aarch64")]
@@ -24,7 +24,7 @@ pub struct Aes256Aarch64 {
 impl Aes256Aarch64 {
     pub unsafe fn new(key: &[u8]) -> Result<Aes256Aarch64> {
         if key.len() != 32 {
-            return Err(Error::InvalidKeySize);
+            return Err(Error::InvalidKeySize(32, key.len()));
         }
 
         let rcon: [u32; 10] = [
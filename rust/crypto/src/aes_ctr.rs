@@ -33,7 +33,7 @@ impl Aes256Ctr32 {
 
     pub fn new(aes256: Aes256, nonce: &[u8], init_ctr: u32) -> Result<Self> {
         if nonce.len() != Self::NONCE_SIZE {
-            return Err(Error::InvalidNonceSize);
+            return Err(Error::InvalidNonceSize(Self::NONCE_SIZE, nonce.len()));
         }
 
         let mut ctr = [0u8; PAD_SIZE];
@@ -102,7 +102,7 @@ fn setup_gcm(key: &[u8], nonce: &[u8], associated_data: &[u8]) -> Result<(Aes256
     sizes require special handling
      */
     if nonce.len() != NONCE_SIZE {
-        return Err(Error::InvalidNonceSize);
+        return Err(Error::InvalidNonceSize(NONCE_SIZE, nonce.len()));
     }
 
     let aes256 = Aes256::new(key)?;
@@ -31,7 +31,7 @@ impl Aes256GcmSiv {
 
     fn derive_keys(&self, nonce: &[u8]) -> Result<([u8; AES_KEY_SIZE], [u8; POLYVAL_KEY_SIZE])> {
         if nonce.len() != NONCE_SIZE {
-            return Err(Error::InvalidNonceSize);
+            return Err(Error::InvalidNonceSize(NONCE_SIZE, nonce.len()));
         }
 
         /*
@@ -161,7 +161,7 @@ impl Aes256GcmSiv {
         tag: &[u8],
     ) -> Result<()> {
         if nonce.len() != NONCE_SIZE {
-            return Err(Error::InvalidNonceSize);
+            return Err(Error::InvalidNonceSize(NONCE_SIZE, nonce.len()));
         }
         if tag.len() != TAG_SIZE {
             return Err(Error::InvalidTag);
@@ -0,0 +1,135 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! AES key wrap with padding ([RFC 5649]), for storing derived keys under a master key.
+//!
+//! Unlike an AEAD, key wrap needs no nonce: the wrapped form is deterministic and only eight
+//! bytes longer than the padded key, and any change to it is detected when unwrapping.
+//!
+//! [RFC 5649]: https://tools.ietf.org/html/rfc5649
+
+use crate::{Error, Result};
+
+use cipher::block::{BlockCipher, NewBlockCipher};
+use generic_array::GenericArray;
+use std::convert::TryFrom;
+use subtle::ConstantTimeEq;
+
+const SEMIBLOCK_SIZE: usize = 8;
+const ALTERNATIVE_IV: [u8; 4] = [0xA6, 0x59, 0x59, 0xA6];
+
+/// Wraps and unwraps keys under a 256-bit key-encryption key.
+#[derive(Clone)]
+pub struct Aes256KeyWrap {
+    aes: aes_soft::Aes256,
+}
+
+impl Aes256KeyWrap {
+    pub const KEY_SIZE: usize = 32;
+
+    pub fn new(key: &[u8]) -> Result<Self> {
+        if key.len() != Self::KEY_SIZE {
+            return Err(Error::InvalidKeySize(Self::KEY_SIZE, key.len()));
+        }
+        Ok(Self {
+            aes: aes_soft::Aes256::new(GenericArray::from_slice(key)),
+        })
+    }
+
+    /// Wraps `key`, which must be between 1 and 2^32 - 1 bytes long.
+    ///
+    /// The result is the key's length rounded up to a multiple of 8, plus 8.
+    pub fn wrap(&self, key: &[u8]) -> Result<Vec<u8>> {
+        let mli = u32::try_from(key.len()).map_err(|_| Error::InvalidInputSize)?;
+        if mli == 0 {
+            return Err(Error::InvalidInputSize);
+        }
+
+        let padded_len = (key.len() + SEMIBLOCK_SIZE - 1) / SEMIBLOCK_SIZE * SEMIBLOCK_SIZE;
+        let mut output = Vec::with_capacity(SEMIBLOCK_SIZE + padded_len);
+        output.extend_from_slice(&ALTERNATIVE_IV);
+        output.extend_from_slice(&mli.to_be_bytes());
+        output.extend_from_slice(key);
+        output.resize(SEMIBLOCK_SIZE + padded_len, 0);
+
+        if padded_len == SEMIBLOCK_SIZE {
+            // A single semiblock is encrypted together with the IV as one AES block.
+            self.aes
+                .encrypt_block(GenericArray::from_mut_slice(&mut output));
+            return Ok(output);
+        }
+
+        let n = padded_len / SEMIBLOCK_SIZE;
+        let mut block = [0u8; 16];
+        for j in 0..6 {
+            for i in 1..=n {
+                block[..8].copy_from_slice(&output[..8]);
+                block[8..].copy_from_slice(&output[i * 8..(i + 1) * 8]);
+                self.aes
+                    .encrypt_block(GenericArray::from_mut_slice(&mut block));
+                let t = ((n * j + i) as u64).to_be_bytes();
+                for (a, (b, t)) in output[..8].iter_mut().zip(block[..8].iter().zip(&t)) {
+                    *a = b ^ t;
+                }
+                output[i * 8..(i + 1) * 8].copy_from_slice(&block[8..]);
+            }
+        }
+        Ok(output)
+    }
+
+    /// Recovers a key from the output of [`wrap`](Self::wrap).
+    ///
+    /// Returns [`Error::InvalidWrappedKey`] if `wrapped` was not produced under this key or has
+    /// been modified.
+    pub fn unwrap(&self, wrapped: &[u8]) -> Result<Vec<u8>> {
+        if wrapped.len() < 2 * SEMIBLOCK_SIZE || wrapped.len() % SEMIBLOCK_SIZE != 0 {
+            return Err(Error::InvalidInputSize);
+        }
+
+        let mut output = wrapped.to_vec();
+        let n = wrapped.len() / SEMIBLOCK_SIZE - 1;
+
+        if n == 1 {
+            self.aes
+                .decrypt_block(GenericArray::from_mut_slice(&mut output));
+        } else {
+            let mut block = [0u8; 16];
+            for j in (0..6).rev() {
+                for i in (1..=n).rev() {
+                    let t = ((n * j + i) as u64).to_be_bytes();
+                    for (b, (a, t)) in block[..8].iter_mut().zip(output[..8].iter().zip(&t)) {
+                        *b = a ^ t;
+                    }
+                    block[8..].copy_from_slice(&output[i * 8..(i + 1) * 8]);
+                    self.aes
+                        .decrypt_block(GenericArray::from_mut_slice(&mut block));
+                    output[..8].copy_from_slice(&block[..8]);
+                    output[i * 8..(i + 1) * 8].copy_from_slice(&block[8..]);
+                }
+            }
+        }
+
+        // Check the IV, the length, and the padding together so that a failure doesn't reveal
+        // which part was wrong.
+        let mut mli_bytes = [0u8; 4];
+        mli_bytes.copy_from_slice(&output[4..8]);
+        let mli = u32::from_be_bytes(mli_bytes) as usize;
+        let padded_len = n * SEMIBLOCK_SIZE;
+        let length_ok = mli > padded_len - SEMIBLOCK_SIZE && mli <= padded_len;
+        let pad_start = SEMIBLOCK_SIZE + if length_ok { mli } else { padded_len };
+
+        let mut valid = output[..4].ct_eq(&ALTERNATIVE_IV) & subtle::Choice::from(length_ok as u8);
+        for &b in &output[pad_start..] {
+            valid &= b.ct_eq(&0);
+        }
+        if !bool::from(valid) {
+            return Err(Error::InvalidWrappedKey);
+        }
+
+        output.truncate(SEMIBLOCK_SIZE + mli);
+        output.drain(..SEMIBLOCK_SIZE);
+        Ok(output)
+    }
+}
@@ -0,0 +1,75 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Fixed-algorithm digests over data supplied in pieces, such as a large attachment.
+//!
+//! Unlike [`CryptographicHash`](crate::CryptographicHash), these don't pick their algorithm by
+//! name, so they can't fail. Finalizing any of them resets it, ready for new input.
+
+use sha2::Digest;
+
+/// A CRC-32 (IEEE) checksum. This detects corruption, not tampering.
+#[derive(Clone, Default)]
+pub struct Crc32(crc32fast::Hasher);
+
+impl Crc32 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn digest(input: &[u8]) -> u32 {
+        crc32fast::hash(input)
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.0.update(input)
+    }
+
+    pub fn finalize(&mut self) -> u32 {
+        std::mem::take(&mut self.0).finalize()
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Sha256(sha2::Sha256);
+
+impl Sha256 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn digest(input: &[u8]) -> Vec<u8> {
+        sha2::Sha256::digest(input).to_vec()
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.0.update(input)
+    }
+
+    pub fn finalize(&mut self) -> Vec<u8> {
+        self.0.finalize_reset().to_vec()
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Sha512(sha2::Sha512);
+
+impl Sha512 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn digest(input: &[u8]) -> Vec<u8> {
+        sha2::Sha512::digest(input).to_vec()
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.0.update(input)
+    }
+
+    pub fn finalize(&mut self) -> Vec<u8> {
+        self.0.finalize_reset().to_vec()
+    }
+}
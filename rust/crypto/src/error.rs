@@ -8,11 +8,19 @@ use std::fmt;
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Error {
     UnknownAlgorithm(&'static str, String),
-    InvalidKeySize,
-    InvalidNonceSize,
+    /// The expected and actual sizes, in bytes.
+    InvalidKeySize(usize, usize),
+    /// The expected and actual sizes, in bytes.
+    InvalidNonceSize(usize, usize),
     InvalidInputSize,
     InvalidTag,
     InvalidState,
+    /// Key derivation parameters that the algorithm doesn't allow.
+    InvalidKdfParameters(&'static str),
+    /// A wrapped key failed its integrity check on unwrapping.
+    InvalidWrappedKey,
+    /// A key given as text that isn't in the expected format.
+    InvalidKeyEncoding,
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -21,11 +29,22 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Error::UnknownAlgorithm(typ, named) => write!(f, "unknown {} algorithm {}", typ, named),
-            Error::InvalidKeySize => write!(f, "invalid key size"),
-            Error::InvalidNonceSize => write!(f, "invalid nonce size"),
+            Error::InvalidKeySize(expected, actual) => {
+                write!(f, "invalid key size <{}>, expected <{}>", actual, expected)
+            }
+            Error::InvalidNonceSize(expected, actual) => {
+                write!(
+                    f,
+                    "invalid nonce size <{}>, expected <{}>",
+                    actual, expected
+                )
+            }
             Error::InvalidInputSize => write!(f, "invalid input size"),
             Error::InvalidTag => write!(f, "invalid authentication tag"),
             Error::InvalidState => write!(f, "invalid object state"),
+            Error::InvalidKdfParameters(reason) => write!(f, "invalid KDF parameters: {}", reason),
+            Error::InvalidWrappedKey => write!(f, "wrapped key failed integrity check"),
+            Error::InvalidKeyEncoding => write!(f, "invalid key encoding"),
         }
     }
 }
@@ -37,7 +37,7 @@ fn reverse_bytes(c: &[u8]) -> [u8; 16] {
 impl Ghash {
     pub fn new(key: &[u8]) -> Result<Self> {
         if key.len() != 16 {
-            return Err(Error::InvalidKeySize);
+            return Err(Error::InvalidKeySize(16, key.len()));
         }
         let polyval = Polyval::new(&Polyval::mulx(&reverse_bytes_16(key)))?;
         Ok(Self { polyval })
@@ -0,0 +1,142 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Keys for group calls and call links, as derived by RingRTC integrations.
+//!
+//! - A call link's root key is 16 random bytes, shared as text: each byte becomes two characters
+//!   of the consonant-only alphabet `bcdfghkmnpqrstxz`, in groups of four separated by dashes.
+//! - A call link's room ID is HKDF-SHA256(root key, info = "20230501-Signal-CallLinkRootKey-RoomId").
+//! - The media key for one era of a call (an era being one continuous run of the call, identified
+//!   by the calling server) is HKDF-SHA256(call secret, info = "Signal Group Call Era Key" || era
+//!   ID). The call secret is the group's master key for group calls, and the root key for call
+//!   links.
+//!
+//! All of these use an all-zero salt and produce 32 bytes.
+
+use crate::{Error, Result};
+
+use hmac::{Hmac, Mac, NewMac};
+use rand::{CryptoRng, Rng};
+use sha2::Sha256;
+use std::fmt;
+
+pub const CALL_LINK_ROOT_KEY_SIZE: usize = 16;
+pub const GROUP_MASTER_KEY_SIZE: usize = 32;
+pub const DERIVED_KEY_SIZE: usize = 32;
+
+const ROOM_ID_INFO: &[u8] = b"20230501-Signal-CallLinkRootKey-RoomId";
+const ERA_KEY_INFO: &[u8] = b"Signal Group Call Era Key";
+const ALPHABET: &[u8; 16] = b"bcdfghkmnpqrstxz";
+const CHARS_PER_GROUP: usize = 4;
+
+/// HKDF-SHA256 with an all-zero salt, producing a single block of output.
+fn hkdf_sha256(ikm: &[u8], info: &[&[u8]]) -> [u8; DERIVED_KEY_SIZE] {
+    let mut extract = Hmac::<Sha256>::new_varkey(&[0; 32]).expect("HMAC accepts any key size");
+    extract.update(ikm);
+    let prk = extract.finalize().into_bytes();
+
+    let mut expand = Hmac::<Sha256>::new_varkey(&prk).expect("HMAC accepts any key size");
+    for part in info {
+        expand.update(part);
+    }
+    expand.update(&[1]);
+    let mut result = [0; DERIVED_KEY_SIZE];
+    result.copy_from_slice(&expand.finalize().into_bytes());
+    result
+}
+
+fn derive_era_key(call_secret: &[u8], era_id: &str) -> [u8; DERIVED_KEY_SIZE] {
+    hkdf_sha256(call_secret, &[ERA_KEY_INFO, era_id.as_bytes()])
+}
+
+/// Derives the media key for one era of a group call from the group's master key.
+pub fn derive_group_call_era_key(
+    group_master_key: &[u8],
+    era_id: &str,
+) -> Result<[u8; DERIVED_KEY_SIZE]> {
+    if group_master_key.len() != GROUP_MASTER_KEY_SIZE {
+        return Err(Error::InvalidKeySize(
+            GROUP_MASTER_KEY_SIZE,
+            group_master_key.len(),
+        ));
+    }
+    Ok(derive_era_key(group_master_key, era_id))
+}
+
+/// The secret shared by everyone with a call link.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallLinkRootKey([u8; CALL_LINK_ROOT_KEY_SIZE]);
+
+impl CallLinkRootKey {
+    pub fn new(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != CALL_LINK_ROOT_KEY_SIZE {
+            return Err(Error::InvalidKeySize(CALL_LINK_ROOT_KEY_SIZE, bytes.len()));
+        }
+        let mut result = Self([0; CALL_LINK_ROOT_KEY_SIZE]);
+        result.0.copy_from_slice(bytes);
+        Ok(result)
+    }
+
+    pub fn generate<R: Rng + CryptoRng>(csprng: &mut R) -> Self {
+        let mut result = Self([0; CALL_LINK_ROOT_KEY_SIZE]);
+        csprng.fill_bytes(&mut result.0);
+        result
+    }
+
+    /// Parses the text form of a root key (see the module documentation).
+    ///
+    /// Dashes are ignored, so they don't have to be in their usual places.
+    pub fn parse(text: &str) -> Result<Self> {
+        let digits = text
+            .bytes()
+            .filter(|c| *c != b'-')
+            .map(|c| {
+                ALPHABET
+                    .iter()
+                    .position(|a| *a == c)
+                    .ok_or(Error::InvalidKeyEncoding)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if digits.len() != 2 * CALL_LINK_ROOT_KEY_SIZE {
+            return Err(Error::InvalidKeyEncoding);
+        }
+        let mut result = Self([0; CALL_LINK_ROOT_KEY_SIZE]);
+        for (byte, pair) in result.0.iter_mut().zip(digits.chunks(2)) {
+            *byte = (pair[0] << 4 | pair[1]) as u8;
+        }
+        Ok(result)
+    }
+
+    pub fn bytes(&self) -> &[u8; CALL_LINK_ROOT_KEY_SIZE] {
+        &self.0
+    }
+
+    /// The ID the calling server uses for this call link's room.
+    pub fn derive_room_id(&self) -> [u8; DERIVED_KEY_SIZE] {
+        hkdf_sha256(&self.0, &[ROOM_ID_INFO])
+    }
+
+    /// Derives the media key for one era of a call using this call link.
+    pub fn derive_era_key(&self, era_id: &str) -> [u8; DERIVED_KEY_SIZE] {
+        derive_era_key(&self.0, era_id)
+    }
+}
+
+/// Formats the key in its text form (see the module documentation).
+impl fmt::Display for CallLinkRootKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let chars = self
+            .0
+            .iter()
+            .flat_map(|b| vec![ALPHABET[(b >> 4) as usize], ALPHABET[(b & 0xf) as usize]]);
+        for (i, c) in chars.enumerate() {
+            if i > 0 && i % CHARS_PER_GROUP == 0 {
+                f.write_str("-")?;
+            }
+            write!(f, "{}", c as char)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,227 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Chunked HMAC-SHA256, so that a download can be checked as it arrives.
+//!
+//! The digest is the running MAC taken at every chunk boundary, followed by the MAC of the whole
+//! input if it doesn't end on a boundary (or is empty). Each 32-byte entry therefore
+//! authenticates everything up to the end of its chunk.
+
+use crate::{Error, Result};
+
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+
+pub const MAC_SIZE: usize = 32;
+
+#[derive(Clone)]
+struct ChunkedMac {
+    mac: Hmac<Sha256>,
+    chunk_size: usize,
+    chunk_offset: usize,
+    completed_length: u64,
+}
+
+impl ChunkedMac {
+    fn new(key: &[u8], chunk_size: usize) -> Result<Self> {
+        if chunk_size == 0 {
+            return Err(Error::InvalidInputSize);
+        }
+        Ok(Self {
+            mac: Hmac::<Sha256>::new_varkey(key).expect("HMAC accepts any key length"),
+            chunk_size,
+            chunk_offset: 0,
+            completed_length: 0,
+        })
+    }
+
+    /// Calls `on_chunk` with the running MAC each time a chunk boundary is crossed.
+    fn update(
+        &mut self,
+        mut input: &[u8],
+        mut on_chunk: impl FnMut(&[u8; MAC_SIZE]) -> Result<()>,
+    ) -> Result<()> {
+        while !input.is_empty() {
+            let take = input.len().min(self.chunk_size - self.chunk_offset);
+            let (chunk, rest) = input.split_at(take);
+            self.mac.update(chunk);
+            self.chunk_offset += take;
+            input = rest;
+
+            if self.chunk_offset == self.chunk_size {
+                on_chunk(&self.current_mac())?;
+                self.completed_length += self.chunk_size as u64;
+                self.chunk_offset = 0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Calls `on_chunk` with the MAC of the whole input if the digest needs a trailing entry.
+    fn finalize(&mut self, on_chunk: impl FnOnce(&[u8; MAC_SIZE]) -> Result<()>) -> Result<()> {
+        if self.chunk_offset == 0 && self.completed_length != 0 {
+            return Ok(());
+        }
+        on_chunk(&self.current_mac())?;
+        self.completed_length += self.chunk_offset as u64;
+        self.chunk_offset = 0;
+        Ok(())
+    }
+
+    fn current_mac(&self) -> [u8; MAC_SIZE] {
+        let mut result = [0; MAC_SIZE];
+        result.copy_from_slice(&self.mac.clone().finalize().into_bytes());
+        result
+    }
+}
+
+/// Computes the digest of an input supplied in pieces of any size.
+#[derive(Clone)]
+pub struct IncrementalMac {
+    chunked: ChunkedMac,
+    digest: Vec<u8>,
+}
+
+impl IncrementalMac {
+    pub fn new(key: &[u8], chunk_size: usize) -> Result<Self> {
+        Ok(Self {
+            chunked: ChunkedMac::new(key, chunk_size)?,
+            digest: Vec::new(),
+        })
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        let digest = &mut self.digest;
+        self.chunked
+            .update(input, |mac| {
+                digest.extend_from_slice(mac);
+                Ok(())
+            })
+            .expect("collecting the digest cannot fail");
+    }
+
+    pub fn finalize(mut self) -> Vec<u8> {
+        let digest = &mut self.digest;
+        self.chunked
+            .finalize(|mac| {
+                digest.extend_from_slice(mac);
+                Ok(())
+            })
+            .expect("collecting the digest cannot fail");
+        self.digest
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ValidatorState {
+    Active,
+    Failed,
+    Finished,
+}
+
+/// Checks an input supplied in pieces of any size against a digest from [`IncrementalMac`].
+///
+/// Bytes are only vouched for once the chunk containing them is complete, so
+/// [`validated_length`](Self::validated_length) may lag behind the amount of input supplied.
+#[derive(Clone)]
+pub struct IncrementalMacValidator {
+    chunked: ChunkedMac,
+    expected: Vec<[u8; MAC_SIZE]>,
+    next_expected: usize,
+    state: ValidatorState,
+}
+
+impl IncrementalMacValidator {
+    pub fn new(key: &[u8], chunk_size: usize, digest: &[u8]) -> Result<Self> {
+        if digest.is_empty() || digest.len() % MAC_SIZE != 0 {
+            return Err(Error::InvalidInputSize);
+        }
+        let expected = digest
+            .chunks_exact(MAC_SIZE)
+            .map(|mac| {
+                let mut entry = [0; MAC_SIZE];
+                entry.copy_from_slice(mac);
+                entry
+            })
+            .collect();
+        Ok(Self {
+            chunked: ChunkedMac::new(key, chunk_size)?,
+            expected,
+            next_expected: 0,
+            state: ValidatorState::Active,
+        })
+    }
+
+    /// The number of leading bytes of input known to be authentic.
+    ///
+    /// Once validation has failed, this is the offset at which the invalid chunk begins.
+    pub fn validated_length(&self) -> u64 {
+        self.chunked.completed_length
+    }
+
+    /// Fails with [`Error::InvalidTag`] as soon as a completed chunk doesn't match the digest,
+    /// and with [`Error::InvalidState`] on any use after that or after [`finalize`].
+    ///
+    /// [`finalize`]: Self::finalize
+    pub fn update(&mut self, input: &[u8]) -> Result<()> {
+        self.check_active()?;
+        let expected = &self.expected;
+        let next_expected = &mut self.next_expected;
+        let result = self
+            .chunked
+            .update(input, |mac| Self::check_next(expected, next_expected, mac));
+        self.record(result)
+    }
+
+    /// Checks the end of the input, which fails with [`Error::InvalidTag`] if the input was
+    /// truncated or the final partial chunk doesn't match.
+    pub fn finalize(&mut self) -> Result<()> {
+        self.check_active()?;
+        let expected = &self.expected;
+        let next_expected = &mut self.next_expected;
+        let result = self
+            .chunked
+            .finalize(|mac| Self::check_next(expected, next_expected, mac))
+            .and_then(|()| {
+                if *next_expected == expected.len() {
+                    Ok(())
+                } else {
+                    Err(Error::InvalidTag)
+                }
+            });
+        self.record(result)?;
+        self.state = ValidatorState::Finished;
+        Ok(())
+    }
+
+    fn check_active(&self) -> Result<()> {
+        match self.state {
+            ValidatorState::Active => Ok(()),
+            ValidatorState::Failed | ValidatorState::Finished => Err(Error::InvalidState),
+        }
+    }
+
+    fn check_next(
+        expected: &[[u8; MAC_SIZE]],
+        next_expected: &mut usize,
+        mac: &[u8; MAC_SIZE],
+    ) -> Result<()> {
+        match expected.get(*next_expected) {
+            Some(expected_mac) if bool::from(expected_mac[..].ct_eq(&mac[..])) => {
+                *next_expected += 1;
+                Ok(())
+            }
+            _ => Err(Error::InvalidTag),
+        }
+    }
+
+    fn record(&mut self, result: Result<()>) -> Result<()> {
+        if result.is_err() {
+            self.state = ValidatorState::Failed;
+        }
+        result
+    }
+}
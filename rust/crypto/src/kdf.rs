@@ -0,0 +1,188 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Memory-hard key derivation, for keys derived from PINs and other low-entropy secrets.
+//!
+//! Parameters are explicit and validated up front, so every platform derives the same key from
+//! the same inputs. Each parameter type also has a `calibrate` helper that picks the cost for a
+//! target duration on the current device; store the result alongside the salt, since a key can
+//! only be derived again with exactly the same parameters.
+
+use crate::{Error, Result};
+
+use std::convert::TryFrom;
+use std::time::{Duration, Instant};
+
+const CALIBRATION_PASSWORD: &[u8] = b"calibration password";
+const CALIBRATION_SALT: &[u8] = b"calibration salt";
+const CALIBRATION_OUTPUT_LEN: usize = 32;
+
+/// Parameters for Argon2id (version 1.3), as described in RFC 9106.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Argon2idParams {
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+impl Argon2idParams {
+    /// The shortest salt Argon2 accepts, in bytes.
+    pub const MIN_SALT_LEN: usize = 8;
+    /// The shortest output Argon2 can produce, in bytes.
+    pub const MIN_OUTPUT_LEN: usize = 4;
+
+    pub fn new(memory_kib: u32, iterations: u32, parallelism: u32) -> Result<Self> {
+        if iterations == 0 {
+            return Err(Error::InvalidKdfParameters("iterations must be positive"));
+        }
+        if parallelism == 0 || parallelism > 0x00FF_FFFF {
+            return Err(Error::InvalidKdfParameters(
+                "parallelism must be between 1 and 2^24 - 1",
+            ));
+        }
+        if u64::from(memory_kib) < 8 * u64::from(parallelism) {
+            return Err(Error::InvalidKdfParameters(
+                "memory must be at least 8 KiB per lane",
+            ));
+        }
+        Ok(Self {
+            memory_kib,
+            iterations,
+            parallelism,
+        })
+    }
+
+    /// Picks the number of iterations that takes about `target` on this device, using the given
+    /// amount of memory.
+    ///
+    /// This runs the KDF once, so it takes at least as long as a single pass over `memory_kib`.
+    /// At least one iteration is always used, even if that takes longer than `target`.
+    pub fn calibrate(target: Duration, memory_kib: u32, parallelism: u32) -> Result<Self> {
+        let probe = Self::new(memory_kib, 1, parallelism)?;
+        let start = Instant::now();
+        probe.derive(
+            CALIBRATION_PASSWORD,
+            CALIBRATION_SALT,
+            CALIBRATION_OUTPUT_LEN,
+        )?;
+        let per_iteration = start.elapsed().as_nanos().max(1);
+        let iterations = (target.as_nanos() / per_iteration).max(1);
+        Self::new(
+            memory_kib,
+            iterations.min(u128::from(u32::MAX)) as u32,
+            parallelism,
+        )
+    }
+
+    pub fn memory_kib(&self) -> u32 {
+        self.memory_kib
+    }
+
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    pub fn parallelism(&self) -> u32 {
+        self.parallelism
+    }
+
+    pub fn derive(&self, password: &[u8], salt: &[u8], output_len: usize) -> Result<Vec<u8>> {
+        if salt.len() < Self::MIN_SALT_LEN || output_len < Self::MIN_OUTPUT_LEN {
+            return Err(Error::InvalidInputSize);
+        }
+        let hash_length = u32::try_from(output_len).map_err(|_| Error::InvalidInputSize)?;
+        let config = argon2::Config {
+            variant: argon2::Variant::Argon2id,
+            version: argon2::Version::Version13,
+            mem_cost: self.memory_kib,
+            time_cost: self.iterations,
+            lanes: self.parallelism,
+            thread_mode: argon2::ThreadMode::Sequential,
+            secret: &[],
+            ad: &[],
+            hash_length,
+        };
+        argon2::hash_raw(password, salt, &config)
+            .map_err(|_| Error::InvalidKdfParameters("rejected by Argon2"))
+    }
+}
+
+/// Parameters for scrypt, as described in RFC 7914.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScryptParams {
+    log_n: u8,
+    r: u32,
+    p: u32,
+}
+
+impl ScryptParams {
+    /// The largest cost [`calibrate`](Self::calibrate) will pick: with `r = 8`, 128 MiB of memory.
+    pub const MAX_CALIBRATED_LOG_N: u8 = 20;
+
+    const MIN_CALIBRATED_LOG_N: u8 = 10;
+
+    /// Creates parameters for a cost of `N = 2^log_n`, block size `r`, and parallelism `p`.
+    pub fn new(log_n: u8, r: u32, p: u32) -> Result<Self> {
+        if log_n == 0 || r == 0 || p == 0 {
+            return Err(Error::InvalidKdfParameters(
+                "log_n, r, and p must be positive",
+            ));
+        }
+        if u64::from(log_n) >= 16 * u64::from(r) {
+            return Err(Error::InvalidKdfParameters(
+                "log_n must be less than 16 * r",
+            ));
+        }
+        scrypt::ScryptParams::new(log_n, r, p)
+            .map_err(|_| Error::InvalidKdfParameters("cost too high for r and p"))?;
+        Ok(Self { log_n, r, p })
+    }
+
+    /// Picks the cost that takes about `target` on this device, for the given `r` and `p`.
+    ///
+    /// The cost is a power of two, so the result may take up to twice as long as `target`. It is
+    /// at least `2^10` and at most `2^MAX_CALIBRATED_LOG_N`.
+    pub fn calibrate(target: Duration, r: u32, p: u32) -> Result<Self> {
+        let probe = Self::new(Self::MIN_CALIBRATED_LOG_N, r, p)?;
+        let start = Instant::now();
+        probe.derive(
+            CALIBRATION_PASSWORD,
+            CALIBRATION_SALT,
+            CALIBRATION_OUTPUT_LEN,
+        )?;
+        let probe_nanos = start.elapsed().as_nanos().max(1);
+
+        // Keep N below 2^(16r), as scrypt requires.
+        let max_log_n = u64::from(Self::MAX_CALIBRATED_LOG_N).min(16 * u64::from(r) - 1) as u8;
+        let mut log_n = Self::MIN_CALIBRATED_LOG_N;
+        let mut expected_nanos = probe_nanos;
+        while log_n < max_log_n && expected_nanos < target.as_nanos() {
+            log_n += 1;
+            expected_nanos *= 2;
+        }
+        Self::new(log_n, r, p)
+    }
+
+    pub fn log_n(&self) -> u8 {
+        self.log_n
+    }
+
+    pub fn r(&self) -> u32 {
+        self.r
+    }
+
+    pub fn p(&self) -> u32 {
+        self.p
+    }
+
+    pub fn derive(&self, password: &[u8], salt: &[u8], output_len: usize) -> Result<Vec<u8>> {
+        let params = scrypt::ScryptParams::new(self.log_n, self.r, self.p)
+            .map_err(|_| Error::InvalidKdfParameters("cost too high for r and p"))?;
+        let mut output = vec![0; output_len];
+        scrypt::scrypt(password, salt, &params, &mut output)
+            .map_err(|_| Error::InvalidInputSize)?;
+        Ok(output)
+    }
+}
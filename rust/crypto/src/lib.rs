@@ -7,21 +7,35 @@
 #![cfg_attr(target_arch = "aarch64", feature(aarch64_target_feature))]
 #![deny(clippy::unwrap_used)]
 
+mod digest;
 mod error;
 mod hash;
+mod incremental_mac;
+mod kdf;
 
 mod aes;
 mod aes_ctr;
 mod aes_gcm;
 mod aes_gcm_siv;
+mod aes_kwp;
 mod cpuid;
 mod ghash;
+pub mod group_call;
 mod polyval;
+mod sticker;
+pub mod storage_service;
 
 pub use {
     aes_ctr::Aes256Ctr32,
     aes_gcm::{Aes256GcmDecryption, Aes256GcmEncryption},
     aes_gcm_siv::Aes256GcmSiv,
+    aes_kwp::Aes256KeyWrap,
+    digest::{Crc32, Sha256, Sha512},
     error::{Error, Result},
+    group_call::CallLinkRootKey,
     hash::{CryptographicHash, CryptographicMac},
+    incremental_mac::{IncrementalMac, IncrementalMacValidator},
+    kdf::{Argon2idParams, ScryptParams},
+    sticker::{StickerCipher, StickerDecryption},
+    storage_service::StorageServiceKey,
 };
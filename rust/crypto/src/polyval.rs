@@ -25,7 +25,7 @@ pub enum Polyval {
 impl Polyval {
     pub fn new(key: &[u8]) -> Result<Self> {
         if key.len() != 16 {
-            return Err(Error::InvalidKeySize);
+            return Err(Error::InvalidKeySize(16, key.len()));
         }
 
         #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
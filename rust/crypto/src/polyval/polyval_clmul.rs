@@ -19,7 +19,7 @@ pub struct PolyvalClmul {
 impl PolyvalClmul {
     pub fn new(key: &[u8]) -> Result<Self> {
         if key.len() != 16 {
-            return Err(Error::InvalidKeySize);
+            return Err(Error::InvalidKeySize(16, key.len()));
         }
 
         unsafe {
@@ -26,7 +26,7 @@ unsafe fn swap_halves(x: uint8x16_t) -> uint8x16_t {
 impl PolyvalPmul {
     pub fn new(key: &[u8]) -> Result<Self> {
         if key.len() != 16 {
-            return Err(Error::InvalidKeySize);
+            return Err(Error::InvalidKeySize(16, key.len()));
         }
 
         unsafe {
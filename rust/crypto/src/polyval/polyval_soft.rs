@@ -14,7 +14,7 @@ pub struct PolyvalSoft {
 impl PolyvalSoft {
     pub fn new(key: &[u8]) -> Result<Self> {
         if key.len() != 16 {
-            return Err(Error::InvalidKeySize);
+            return Err(Error::InvalidKeySize(16, key.len()));
         }
 
         Ok(Self {
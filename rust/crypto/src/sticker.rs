@@ -0,0 +1,181 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Decryption of sticker pack manifests and stickers.
+//!
+//! Both are encrypted like attachments: `IV || AES-256-CBC(PKCS#7-padded data) || MAC`, where the
+//! MAC is HMAC-SHA256 over the IV and ciphertext. The AES and MAC keys are the two halves of
+//! HKDF-SHA256(pack key, info = "Sticker Pack").
+
+use crate::{Error, Result};
+
+use cipher::block::{BlockCipher, NewBlockCipher};
+use generic_array::GenericArray;
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+
+pub const PACK_KEY_SIZE: usize = 32;
+
+const BLOCK_SIZE: usize = 16;
+const MAC_SIZE: usize = 32;
+const HKDF_INFO: &[u8] = b"Sticker Pack";
+
+/// The keys for one sticker pack.
+#[derive(Clone)]
+pub struct StickerCipher {
+    aes_key: [u8; 32],
+    mac_key: [u8; 32],
+}
+
+impl StickerCipher {
+    pub fn new(pack_key: &[u8]) -> Result<Self> {
+        if pack_key.len() != PACK_KEY_SIZE {
+            return Err(Error::InvalidKeySize(PACK_KEY_SIZE, pack_key.len()));
+        }
+
+        // HKDF with an all-zero salt; the 64 bytes of output need two expand rounds.
+        let mut extract = hmac_sha256(&[0; 32]);
+        extract.update(pack_key);
+        let prk = extract.finalize().into_bytes();
+
+        let mut expand = hmac_sha256(&prk);
+        expand.update(HKDF_INFO);
+        expand.update(&[1]);
+        let first = expand.finalize().into_bytes();
+
+        let mut expand = hmac_sha256(&prk);
+        expand.update(&first);
+        expand.update(HKDF_INFO);
+        expand.update(&[2]);
+        let second = expand.finalize().into_bytes();
+
+        let mut cipher = Self {
+            aes_key: [0; 32],
+            mac_key: [0; 32],
+        };
+        cipher.aes_key.copy_from_slice(&first);
+        cipher.mac_key.copy_from_slice(&second);
+        Ok(cipher)
+    }
+
+    /// Decrypts a whole manifest or sticker.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut decryption = self.decryption();
+        let mut result = decryption.update(data)?;
+        result.extend(decryption.finalize()?);
+        Ok(result)
+    }
+
+    /// Starts decrypting a manifest or sticker that arrives in pieces.
+    pub fn decryption(&self) -> StickerDecryption {
+        StickerDecryption {
+            aes: aes_soft::Aes256::new(GenericArray::from_slice(&self.aes_key)),
+            mac: hmac_sha256(&self.mac_key),
+            previous_block: None,
+            pending: Vec::new(),
+            finished: false,
+        }
+    }
+}
+
+/// Decrypts a manifest or sticker supplied in pieces of any size.
+///
+/// Plaintext is returned as soon as it can be decrypted, but it is not authenticated until
+/// [`finalize`](Self::finalize) succeeds and must be discarded if it fails.
+#[derive(Clone)]
+pub struct StickerDecryption {
+    aes: aes_soft::Aes256,
+    mac: Hmac<Sha256>,
+    /// The IV, then each ciphertext block in turn, once the IV has arrived.
+    previous_block: Option<[u8; BLOCK_SIZE]>,
+    pending: Vec<u8>,
+    finished: bool,
+}
+
+impl StickerDecryption {
+    pub fn update(&mut self, input: &[u8]) -> Result<Vec<u8>> {
+        if self.finished {
+            return Err(Error::InvalidState);
+        }
+        self.pending.extend_from_slice(input);
+
+        if self.previous_block.is_none() {
+            if self.pending.len() < BLOCK_SIZE {
+                return Ok(Vec::new());
+            }
+            let mut iv = [0; BLOCK_SIZE];
+            iv.copy_from_slice(&self.pending[..BLOCK_SIZE]);
+            self.mac.update(&iv);
+            self.previous_block = Some(iv);
+            self.pending.drain(..BLOCK_SIZE);
+        }
+
+        // Hold back the MAC and the last block, whose padding can't be removed until the end.
+        let ready = self.pending.len().saturating_sub(MAC_SIZE + BLOCK_SIZE);
+        let ready = ready - ready % BLOCK_SIZE;
+        let mut blocks: Vec<u8> = self.pending.drain(..ready).collect();
+        self.mac.update(&blocks);
+        self.decrypt_blocks(&mut blocks);
+        Ok(blocks)
+    }
+
+    /// Checks the MAC and returns the rest of the plaintext.
+    ///
+    /// Fails with [`Error::InvalidTag`] if the MAC doesn't match and [`Error::InvalidInputSize`]
+    /// if the input was not a well-formed encryption.
+    pub fn finalize(&mut self) -> Result<Vec<u8>> {
+        if self.finished {
+            return Err(Error::InvalidState);
+        }
+        self.finished = true;
+
+        if self.previous_block.is_none()
+            || self.pending.len() < MAC_SIZE + BLOCK_SIZE
+            || (self.pending.len() - MAC_SIZE) % BLOCK_SIZE != 0
+        {
+            return Err(Error::InvalidInputSize);
+        }
+
+        let mac_start = self.pending.len() - MAC_SIZE;
+        let mut blocks = self.pending[..mac_start].to_vec();
+        self.mac.update(&blocks);
+        let mac = self.mac.clone().finalize().into_bytes();
+        if !bool::from(mac.as_slice().ct_eq(&self.pending[mac_start..])) {
+            return Err(Error::InvalidTag);
+        }
+
+        self.decrypt_blocks(&mut blocks);
+        let padding = blocks[blocks.len() - 1] as usize;
+        if padding == 0
+            || padding > BLOCK_SIZE
+            || blocks[blocks.len() - padding..]
+                .iter()
+                .any(|&b| b as usize != padding)
+        {
+            return Err(Error::InvalidInputSize);
+        }
+        blocks.truncate(blocks.len() - padding);
+        Ok(blocks)
+    }
+
+    fn decrypt_blocks(&mut self, blocks: &mut [u8]) {
+        let mut previous = self.previous_block.expect("IV has been read");
+        for block in blocks.chunks_exact_mut(BLOCK_SIZE) {
+            let mut ciphertext = [0; BLOCK_SIZE];
+            ciphertext.copy_from_slice(block);
+            self.aes.decrypt_block(GenericArray::from_mut_slice(block));
+            for (b, p) in block.iter_mut().zip(previous.iter()) {
+                *b ^= p;
+            }
+            previous = ciphertext;
+        }
+        self.previous_block = Some(previous);
+    }
+}
+
+fn hmac_sha256(key: &[u8]) -> Hmac<Sha256> {
+    Hmac::<Sha256>::new_varkey(key).expect("HMAC accepts any key length")
+}
@@ -0,0 +1,150 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Encryption of Storage Service manifests and records.
+//!
+//! All keys are derived from the account's storage key, which is itself
+//! HMAC-SHA256(master key, "Storage Service Encryption"):
+//!
+//! - a manifest's key is HMAC-SHA256(storage key, "Manifest_" || decimal version)
+//! - a record's key is HMAC-SHA256(storage key, "Item_" || base64(raw record ID))
+//!
+//! Both are encrypted as `nonce || AES-256-GCM(data) || tag`, with a random 12-byte nonce and no
+//! associated data.
+
+use crate::{Aes256GcmDecryption, Aes256GcmEncryption, Error, Result};
+
+use hmac::{Hmac, Mac, NewMac};
+use rand::{CryptoRng, Rng};
+use sha2::Sha256;
+
+const STORAGE_KEY_INFO: &[u8] = b"Storage Service Encryption";
+const KEY_SIZE: usize = 32;
+const NONCE_SIZE: usize = Aes256GcmEncryption::NONCE_SIZE;
+const TAG_SIZE: usize = Aes256GcmEncryption::TAG_SIZE;
+
+fn hmac_sha256(key: &[u8], input: &[u8]) -> [u8; KEY_SIZE] {
+    let mut mac = Hmac::<Sha256>::new_varkey(key).expect("HMAC accepts any key size");
+    mac.update(input);
+    let mut result = [0; KEY_SIZE];
+    result.copy_from_slice(&mac.finalize().into_bytes());
+    result
+}
+
+/// An account's storage key, from which the key for each manifest and record is derived.
+#[derive(Clone)]
+pub struct StorageServiceKey {
+    storage_key: [u8; KEY_SIZE],
+}
+
+impl StorageServiceKey {
+    pub const KEY_SIZE: usize = KEY_SIZE;
+
+    /// Wraps an existing storage key.
+    pub fn new(storage_key: &[u8]) -> Result<Self> {
+        if storage_key.len() != KEY_SIZE {
+            return Err(Error::InvalidKeySize(KEY_SIZE, storage_key.len()));
+        }
+        let mut result = Self {
+            storage_key: [0; KEY_SIZE],
+        };
+        result.storage_key.copy_from_slice(storage_key);
+        Ok(result)
+    }
+
+    /// Derives the storage key from the account's 32-byte master key.
+    pub fn from_master_key(master_key: &[u8]) -> Result<Self> {
+        if master_key.len() != KEY_SIZE {
+            return Err(Error::InvalidKeySize(KEY_SIZE, master_key.len()));
+        }
+        Ok(Self {
+            storage_key: hmac_sha256(master_key, STORAGE_KEY_INFO),
+        })
+    }
+
+    pub fn storage_key(&self) -> &[u8; KEY_SIZE] {
+        &self.storage_key
+    }
+
+    pub fn manifest_key(&self, version: u64) -> [u8; KEY_SIZE] {
+        hmac_sha256(
+            &self.storage_key,
+            format!("Manifest_{}", version).as_bytes(),
+        )
+    }
+
+    pub fn record_key(&self, raw_id: &[u8]) -> [u8; KEY_SIZE] {
+        hmac_sha256(
+            &self.storage_key,
+            format!("Item_{}", base64::encode(raw_id)).as_bytes(),
+        )
+    }
+
+    pub fn encrypt_manifest<R: Rng + CryptoRng>(
+        &self,
+        version: u64,
+        plaintext: &[u8],
+        csprng: &mut R,
+    ) -> Result<Vec<u8>> {
+        encrypt(&self.manifest_key(version), plaintext, csprng)
+    }
+
+    /// Fails with [`Error::InvalidTag`] if the manifest was encrypted for a different version or
+    /// under a different key, or has been modified.
+    pub fn decrypt_manifest(&self, version: u64, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        decrypt(&self.manifest_key(version), ciphertext)
+    }
+
+    pub fn encrypt_record<R: Rng + CryptoRng>(
+        &self,
+        raw_id: &[u8],
+        plaintext: &[u8],
+        csprng: &mut R,
+    ) -> Result<Vec<u8>> {
+        encrypt(&self.record_key(raw_id), plaintext, csprng)
+    }
+
+    /// Fails with [`Error::InvalidTag`] if the record was encrypted for a different ID or under a
+    /// different key, or has been modified.
+    pub fn decrypt_record(&self, raw_id: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        decrypt(&self.record_key(raw_id), ciphertext)
+    }
+}
+
+/// Encrypts `plaintext` as `nonce || ciphertext || tag` under a manifest or record key.
+pub fn encrypt<R: Rng + CryptoRng>(
+    key: &[u8],
+    plaintext: &[u8],
+    csprng: &mut R,
+) -> Result<Vec<u8>> {
+    let mut nonce = [0; NONCE_SIZE];
+    csprng.fill_bytes(&mut nonce);
+    encrypt_with_nonce(key, &nonce, plaintext)
+}
+
+/// Like [`encrypt`], but with a caller-chosen nonce, which must never be reused with `key`.
+pub fn encrypt_with_nonce(key: &[u8], nonce: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut gcm = Aes256GcmEncryption::new(key, nonce, &[])?;
+    let mut output = Vec::with_capacity(NONCE_SIZE + plaintext.len() + TAG_SIZE);
+    output.extend_from_slice(nonce);
+    output.extend_from_slice(plaintext);
+    gcm.encrypt(&mut output[NONCE_SIZE..])?;
+    output.extend_from_slice(&gcm.compute_tag()?);
+    Ok(output)
+}
+
+/// Decrypts the output of [`encrypt`].
+pub fn decrypt(key: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    if ciphertext.len() < NONCE_SIZE + TAG_SIZE {
+        return Err(Error::InvalidInputSize);
+    }
+    let (nonce, rest) = ciphertext.split_at(NONCE_SIZE);
+    let (body, tag) = rest.split_at(rest.len() - TAG_SIZE);
+    let mut gcm = Aes256GcmDecryption::new(key, nonce, &[])?;
+    let mut plaintext = body.to_vec();
+    gcm.decrypt(&mut plaintext)?;
+    gcm.verify_tag(tag)?;
+    Ok(plaintext)
+}
@@ -0,0 +1,77 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use signal_crypto::{Aes256KeyWrap, Error};
+
+fn kek() -> Vec<u8> {
+    (0..32).collect()
+}
+
+// The keys from RFC 5649 section 6, wrapped under a 256-bit KEK.
+const VECTORS: &[(&str, &str)] = &[
+    ("466f7250617369", "443b17837bb39348610d19202df8a1f9"),
+    (
+        "c37b7e6492584340bed12207808941155068f738",
+        "29b7fa191c2165684374eee9f74595e2a42bace75c425b3053efa26ffe1bb32f",
+    ),
+];
+
+#[test]
+fn aes_kwp_known_answers() -> Result<(), Error> {
+    let kw = Aes256KeyWrap::new(&kek())?;
+    for (key, wrapped) in VECTORS {
+        let key = hex::decode(key).expect("valid hex");
+        let wrapped = hex::decode(wrapped).expect("valid hex");
+        assert_eq!(kw.wrap(&key)?, wrapped);
+        assert_eq!(kw.unwrap(&wrapped)?, key);
+    }
+    Ok(())
+}
+
+#[test]
+fn aes_kwp_round_trip() -> Result<(), Error> {
+    let kw = Aes256KeyWrap::new(&kek())?;
+    for len in 1..=64 {
+        let key = vec![0x5a; len];
+        let wrapped = kw.wrap(&key)?;
+        assert_eq!(wrapped.len(), (len + 7) / 8 * 8 + 8);
+        assert_eq!(kw.unwrap(&wrapped)?, key, "length {}", len);
+    }
+    Ok(())
+}
+
+#[test]
+fn aes_kwp_rejects_tampering() -> Result<(), Error> {
+    let kw = Aes256KeyWrap::new(&kek())?;
+    for (_, wrapped) in VECTORS {
+        let wrapped = hex::decode(wrapped).expect("valid hex");
+        for i in 0..wrapped.len() {
+            let mut tampered = wrapped.clone();
+            tampered[i] ^= 1;
+            assert_eq!(kw.unwrap(&tampered), Err(Error::InvalidWrappedKey));
+        }
+    }
+
+    let other = Aes256KeyWrap::new(&[0xff; 32])?;
+    assert_eq!(
+        other.unwrap(&kw.wrap(b"secret key")?),
+        Err(Error::InvalidWrappedKey)
+    );
+    Ok(())
+}
+
+#[test]
+fn aes_kwp_rejects_bad_sizes() -> Result<(), Error> {
+    assert_eq!(
+        Aes256KeyWrap::new(&[0; 16]).err(),
+        Some(Error::InvalidKeySize(32, 16))
+    );
+
+    let kw = Aes256KeyWrap::new(&kek())?;
+    assert_eq!(kw.wrap(&[]), Err(Error::InvalidInputSize));
+    assert_eq!(kw.unwrap(&[0; 8]), Err(Error::InvalidInputSize));
+    assert_eq!(kw.unwrap(&[0; 17]), Err(Error::InvalidInputSize));
+    Ok(())
+}
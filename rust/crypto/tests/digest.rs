@@ -0,0 +1,45 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use signal_crypto::{Crc32, Sha256, Sha512};
+
+#[test]
+fn crc32_known_answer() {
+    assert_eq!(Crc32::digest(b""), 0);
+    assert_eq!(Crc32::digest(b"123456789"), 0xcbf4_3926);
+
+    let mut crc = Crc32::new();
+    crc.update(b"1234");
+    crc.update(b"56789");
+    assert_eq!(crc.finalize(), 0xcbf4_3926);
+    assert_eq!(crc.finalize(), 0);
+}
+
+#[test]
+fn sha256_known_answer() {
+    let expected = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    assert_eq!(hex::encode(Sha256::digest(b"abc")), expected);
+
+    let mut sha256 = Sha256::new();
+    sha256.update(b"a");
+    sha256.update(b"bc");
+    assert_eq!(hex::encode(sha256.finalize()), expected);
+    assert_eq!(
+        hex::encode(sha256.finalize()),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+}
+
+#[test]
+fn sha512_known_answer() {
+    let expected = "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+                    2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f";
+    assert_eq!(hex::encode(Sha512::digest(b"abc")), expected);
+
+    let mut sha512 = Sha512::new();
+    sha512.update(b"ab");
+    sha512.update(b"c");
+    assert_eq!(hex::encode(sha512.finalize()), expected);
+}
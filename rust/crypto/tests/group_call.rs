@@ -0,0 +1,73 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use rand::rngs::OsRng;
+use signal_crypto::group_call::derive_group_call_era_key;
+use signal_crypto::{CallLinkRootKey, Error};
+
+const ROOT_KEY_TEXT: &str = "bbbc-bdbf-bgbh-bkbm-bnbp-bqbr-bsbt-bxbz";
+
+fn root_key() -> CallLinkRootKey {
+    CallLinkRootKey::new(&(0..16).collect::<Vec<u8>>()).expect("valid size")
+}
+
+#[test]
+fn call_link_root_key_text_form() -> Result<(), Error> {
+    assert_eq!(root_key().to_string(), ROOT_KEY_TEXT);
+    assert_eq!(CallLinkRootKey::parse(ROOT_KEY_TEXT)?, root_key());
+    assert_eq!(
+        CallLinkRootKey::parse(&ROOT_KEY_TEXT.replace("-", ""))?,
+        root_key()
+    );
+
+    let generated = CallLinkRootKey::generate(&mut OsRng);
+    assert_eq!(CallLinkRootKey::parse(&generated.to_string())?, generated);
+    Ok(())
+}
+
+#[test]
+fn call_link_root_key_rejects_bad_text() {
+    // Vowels aren't in the alphabet.
+    assert_eq!(
+        CallLinkRootKey::parse("abbc-bdbf-bgbh-bkbm-bnbp-bqbr-bsbt-bxbz"),
+        Err(Error::InvalidKeyEncoding)
+    );
+    assert_eq!(
+        CallLinkRootKey::parse("bbbc-bdbf-bgbh-bkbm-bnbp-bqbr-bsbt-bx"),
+        Err(Error::InvalidKeyEncoding)
+    );
+    assert_eq!(
+        CallLinkRootKey::new(&[0; 15]),
+        Err(Error::InvalidKeySize(16, 15))
+    );
+}
+
+#[test]
+fn call_link_derivations() {
+    let key = root_key();
+    assert_eq!(
+        hex::encode(key.derive_room_id()),
+        "cd415ffc58771108cdbf029b8d86ee857be167bf28dda87e3e592e3f10eb1c9b"
+    );
+    assert_eq!(
+        hex::encode(key.derive_era_key("era-1")),
+        "ccd3b57831cf1733774ae4a6ae24eee4d88184c21b46aac04e5983a7c2990845"
+    );
+    assert_ne!(key.derive_era_key("era-1"), key.derive_era_key("era-2"));
+}
+
+#[test]
+fn group_call_era_key() -> Result<(), Error> {
+    let master_key: Vec<u8> = (0..32).collect();
+    assert_eq!(
+        hex::encode(derive_group_call_era_key(&master_key, "era-1")?),
+        "ddb3cc5d658418708592e5814e848d25601c9ead04dd40538cdcf82e951a8980"
+    );
+    assert_eq!(
+        derive_group_call_era_key(&master_key[..16], "era-1"),
+        Err(Error::InvalidKeySize(32, 16))
+    );
+    Ok(())
+}
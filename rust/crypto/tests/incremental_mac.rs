@@ -0,0 +1,125 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use signal_crypto::{Error, IncrementalMac, IncrementalMacValidator};
+
+const CHUNK_SIZE: usize = 4;
+
+fn key() -> Vec<u8> {
+    (0..32).collect()
+}
+
+fn digest(data: &[u8]) -> Vec<u8> {
+    let mut mac = IncrementalMac::new(&key(), CHUNK_SIZE).expect("valid chunk size");
+    mac.update(data);
+    mac.finalize()
+}
+
+#[test]
+fn incremental_mac_known_answer() {
+    let data: Vec<u8> = (0..10).collect();
+    assert_eq!(
+        hex::encode(digest(&data)),
+        "52fa805b50abf8dfcab98df509bb333be21ae17d2014a42eb8d477c171e65473\
+         97c6323b0c0ee26c6a637ad3f16be38dd3cff2dbcef3fdf0a61d01110cdc3b17\
+         0e8ff8cf15d36c44df1fb5ada0acaaf9f7e149f805b7533ad50344fd82c1f9b9"
+    );
+    assert_eq!(
+        hex::encode(digest(&[])),
+        "d38b42096d80f45f826b44a9d5607de72496a415d3f4a1a8c88e3bb9da8dc1cb"
+    );
+}
+
+#[test]
+fn incremental_mac_ignores_update_boundaries() {
+    let data: Vec<u8> = (0..10).collect();
+    let mut mac = IncrementalMac::new(&key(), CHUNK_SIZE).expect("valid chunk size");
+    for piece in data.chunks(3) {
+        mac.update(piece);
+    }
+    assert_eq!(mac.finalize(), digest(&data));
+
+    // An input ending on a chunk boundary has no trailing entry.
+    assert_eq!(digest(&data[..8]).len(), 64);
+    assert_eq!(&digest(&data)[..64], &digest(&data[..8])[..]);
+}
+
+#[test]
+fn validator_accepts_any_piece_size() -> Result<(), Error> {
+    let data: Vec<u8> = (0..10).collect();
+    let expected = digest(&data);
+    for piece_size in 1..=data.len() {
+        let mut validator = IncrementalMacValidator::new(&key(), CHUNK_SIZE, &expected)?;
+        let mut supplied = 0;
+        for piece in data.chunks(piece_size) {
+            validator.update(piece)?;
+            supplied += piece.len();
+            assert_eq!(
+                validator.validated_length(),
+                (supplied - supplied % CHUNK_SIZE) as u64
+            );
+        }
+        validator.finalize()?;
+        assert_eq!(validator.validated_length(), data.len() as u64);
+    }
+
+    let mut validator = IncrementalMacValidator::new(&key(), CHUNK_SIZE, &digest(&[]))?;
+    validator.finalize()?;
+    assert_eq!(validator.validated_length(), 0);
+    Ok(())
+}
+
+#[test]
+fn validator_reports_offset_of_corruption() -> Result<(), Error> {
+    let data: Vec<u8> = (0..10).collect();
+    let expected = digest(&data);
+
+    let mut corrupted = data.clone();
+    corrupted[5] ^= 1;
+    let mut validator = IncrementalMacValidator::new(&key(), CHUNK_SIZE, &expected)?;
+    validator.update(&corrupted[..3])?;
+    assert_eq!(validator.update(&corrupted[3..]), Err(Error::InvalidTag));
+    assert_eq!(validator.validated_length(), 4);
+    assert_eq!(validator.update(&data[8..]), Err(Error::InvalidState));
+    assert_eq!(validator.finalize(), Err(Error::InvalidState));
+
+    // Corruption in the final partial chunk is only caught at the end.
+    let mut corrupted = data.clone();
+    corrupted[9] ^= 1;
+    let mut validator = IncrementalMacValidator::new(&key(), CHUNK_SIZE, &expected)?;
+    validator.update(&corrupted)?;
+    assert_eq!(validator.finalize(), Err(Error::InvalidTag));
+    assert_eq!(validator.validated_length(), 8);
+    Ok(())
+}
+
+#[test]
+fn validator_rejects_wrong_length() -> Result<(), Error> {
+    let data: Vec<u8> = (0..10).collect();
+    let expected = digest(&data);
+
+    let mut validator = IncrementalMacValidator::new(&key(), CHUNK_SIZE, &expected)?;
+    validator.update(&data[..8])?;
+    assert_eq!(validator.finalize(), Err(Error::InvalidTag));
+    assert_eq!(validator.validated_length(), 8);
+
+    let mut validator = IncrementalMacValidator::new(&key(), CHUNK_SIZE, &digest(&data[..8]))?;
+    validator.update(&data[..8])?;
+    assert_eq!(validator.update(&data[8..]), Ok(()));
+    assert_eq!(validator.finalize(), Err(Error::InvalidTag));
+
+    let mut validator = IncrementalMacValidator::new(&key(), CHUNK_SIZE, &digest(&data[..4]))?;
+    assert_eq!(validator.update(&data), Err(Error::InvalidTag));
+    assert_eq!(validator.validated_length(), 4);
+    Ok(())
+}
+
+#[test]
+fn validator_rejects_bad_parameters() {
+    assert!(IncrementalMac::new(&key(), 0).is_err());
+    assert!(IncrementalMacValidator::new(&key(), 0, &[0; 32]).is_err());
+    assert!(IncrementalMacValidator::new(&key(), CHUNK_SIZE, &[]).is_err());
+    assert!(IncrementalMacValidator::new(&key(), CHUNK_SIZE, &[0; 33]).is_err());
+}
@@ -0,0 +1,86 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use signal_crypto::{Argon2idParams, Error, ScryptParams};
+use std::time::Duration;
+
+#[test]
+fn argon2id_known_answer() -> Result<(), Error> {
+    // From the reference implementation's test suite.
+    let params = Argon2idParams::new(65536, 2, 1)?;
+    assert_eq!(
+        hex::encode(params.derive(b"password", b"somesalt", 32)?),
+        "09316115d5cf24ed5a15a31a3ba326e5cf32edc24702987c02b6566f61913cf7"
+    );
+    Ok(())
+}
+
+#[test]
+fn argon2id_rejects_invalid_parameters() -> Result<(), Error> {
+    assert!(matches!(
+        Argon2idParams::new(64, 0, 1),
+        Err(Error::InvalidKdfParameters(_))
+    ));
+    assert!(matches!(
+        Argon2idParams::new(64, 1, 0),
+        Err(Error::InvalidKdfParameters(_))
+    ));
+    assert!(matches!(
+        Argon2idParams::new(15, 1, 2),
+        Err(Error::InvalidKdfParameters(_))
+    ));
+
+    let params = Argon2idParams::new(64, 1, 1)?;
+    assert_eq!(
+        params.derive(b"password", b"short", 32),
+        Err(Error::InvalidInputSize)
+    );
+    assert_eq!(
+        params.derive(b"password", b"somesalt", 3),
+        Err(Error::InvalidInputSize)
+    );
+    Ok(())
+}
+
+#[test]
+fn argon2id_calibration() -> Result<(), Error> {
+    let params = Argon2idParams::calibrate(Duration::from_millis(0), 64, 1)?;
+    assert_eq!(params, Argon2idParams::new(64, 1, 1)?);
+
+    let params = Argon2idParams::calibrate(Duration::from_millis(50), 64, 1)?;
+    assert!(params.iterations() > 1);
+    Ok(())
+}
+
+#[test]
+fn scrypt_known_answer() -> Result<(), Error> {
+    // RFC 7914, section 12.
+    let params = ScryptParams::new(10, 8, 16)?;
+    assert_eq!(
+        hex::encode(params.derive(b"password", b"NaCl", 64)?),
+        "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162\
+         2eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640"
+    );
+    Ok(())
+}
+
+#[test]
+fn scrypt_rejects_invalid_parameters() {
+    assert!(matches!(
+        ScryptParams::new(0, 8, 1),
+        Err(Error::InvalidKdfParameters(_))
+    ));
+    assert!(matches!(
+        ScryptParams::new(16, 1, 1),
+        Err(Error::InvalidKdfParameters(_))
+    ));
+}
+
+#[test]
+fn scrypt_calibration() -> Result<(), Error> {
+    let params = ScryptParams::calibrate(Duration::from_millis(0), 8, 1)?;
+    assert_eq!(params, ScryptParams::new(10, 8, 1)?);
+    Ok(())
+}
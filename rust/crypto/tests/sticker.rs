@@ -0,0 +1,87 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use signal_crypto::{Error, StickerCipher};
+
+const PLAINTEXT: &[u8] = b"sticker pack manifest for testing";
+
+fn pack_key() -> Vec<u8> {
+    (0..32).collect()
+}
+
+fn ciphertext() -> Vec<u8> {
+    hex::decode(
+        "6465666768696a6b6c6d6e6f707172731e07a999cd11729bcee7b16cc7aa710d\
+         ce938f78a947d0f8de052db72443aedf6050f41d82c3b5f23bcfdd8fd27469cf\
+         0178d0de4ac48f383508dccd00bb88c54e6a0cbfeb861694da6dfc1870b648d6",
+    )
+    .expect("valid hex")
+}
+
+#[test]
+fn sticker_decrypt_known_answer() -> Result<(), Error> {
+    let cipher = StickerCipher::new(&pack_key())?;
+    assert_eq!(cipher.decrypt(&ciphertext())?, PLAINTEXT);
+    Ok(())
+}
+
+#[test]
+fn sticker_decrypt_in_pieces() -> Result<(), Error> {
+    let cipher = StickerCipher::new(&pack_key())?;
+    let ciphertext = ciphertext();
+    for piece_size in 1..=ciphertext.len() {
+        let mut decryption = cipher.decryption();
+        let mut plaintext = Vec::new();
+        for piece in ciphertext.chunks(piece_size) {
+            plaintext.extend(decryption.update(piece)?);
+        }
+        plaintext.extend(decryption.finalize()?);
+        assert_eq!(plaintext, PLAINTEXT, "piece size {}", piece_size);
+    }
+    Ok(())
+}
+
+#[test]
+fn sticker_decrypt_rejects_tampering() -> Result<(), Error> {
+    let cipher = StickerCipher::new(&pack_key())?;
+    let ciphertext = ciphertext();
+    for i in 0..ciphertext.len() {
+        let mut corrupted = ciphertext.clone();
+        corrupted[i] ^= 1;
+        assert_eq!(cipher.decrypt(&corrupted), Err(Error::InvalidTag));
+    }
+
+    let mut wrong_key = pack_key();
+    wrong_key[0] ^= 1;
+    assert_eq!(
+        StickerCipher::new(&wrong_key)?.decrypt(&ciphertext),
+        Err(Error::InvalidTag)
+    );
+    Ok(())
+}
+
+#[test]
+fn sticker_decrypt_rejects_bad_lengths() -> Result<(), Error> {
+    assert_eq!(
+        StickerCipher::new(&[0; 16]).err(),
+        Some(Error::InvalidKeySize(32, 16))
+    );
+
+    let cipher = StickerCipher::new(&pack_key())?;
+    let ciphertext = ciphertext();
+    for len in [0, 16, 47, ciphertext.len() - 1].iter() {
+        assert_eq!(
+            cipher.decrypt(&ciphertext[..*len]),
+            Err(Error::InvalidInputSize)
+        );
+    }
+
+    let mut decryption = cipher.decryption();
+    decryption.update(&ciphertext)?;
+    decryption.finalize()?;
+    assert_eq!(decryption.update(&[]), Err(Error::InvalidState));
+    assert_eq!(decryption.finalize(), Err(Error::InvalidState));
+    Ok(())
+}
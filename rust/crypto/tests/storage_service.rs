@@ -0,0 +1,97 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use rand::rngs::OsRng;
+use signal_crypto::storage_service::{decrypt, encrypt_with_nonce};
+use signal_crypto::{Error, StorageServiceKey};
+
+const PLAINTEXT: &[u8] = b"storage record for testing";
+
+fn master_key() -> Vec<u8> {
+    (0..32).collect()
+}
+
+fn record_id() -> Vec<u8> {
+    (0..16).collect()
+}
+
+#[test]
+fn storage_service_key_derivation() -> Result<(), Error> {
+    let key = StorageServiceKey::from_master_key(&master_key())?;
+    assert_eq!(
+        hex::encode(key.storage_key()),
+        "d9fd23ee99c148ad879f62609297139e6a36f415b1ad779d23c687b1fa6d36d9"
+    );
+    assert_eq!(
+        hex::encode(key.manifest_key(42)),
+        "6030bc36a437e3bbdf288720487ca3293fe7eb1914819ef235f2532ff85fee6f"
+    );
+    assert_eq!(
+        hex::encode(key.record_key(&record_id())),
+        "93e3f1cee062cb39adbef00589a172248106ff336017c853154062453939294e"
+    );
+
+    let rewrapped = StorageServiceKey::new(key.storage_key())?;
+    assert_eq!(rewrapped.manifest_key(42), key.manifest_key(42));
+    Ok(())
+}
+
+#[test]
+fn storage_service_record_known_answer() -> Result<(), Error> {
+    let key = StorageServiceKey::from_master_key(&master_key())?;
+    let ciphertext = hex::decode(
+        "6465666768696a6b6c6d6e6fe05a971f4a5a0b590212cc8bd2172baaf28790fa\
+         4f7ea8cd1eafe5898c431608052773941c26c5bb025a",
+    )
+    .expect("valid hex");
+    let nonce: Vec<u8> = (100..112).collect();
+    assert_eq!(
+        encrypt_with_nonce(&key.record_key(&record_id()), &nonce, PLAINTEXT)?,
+        ciphertext
+    );
+    assert_eq!(key.decrypt_record(&record_id(), &ciphertext)?, PLAINTEXT);
+    Ok(())
+}
+
+#[test]
+fn storage_service_round_trip() -> Result<(), Error> {
+    let key = StorageServiceKey::from_master_key(&master_key())?;
+
+    let manifest = key.encrypt_manifest(7, PLAINTEXT, &mut OsRng)?;
+    assert_eq!(key.decrypt_manifest(7, &manifest)?, PLAINTEXT);
+    assert_eq!(key.decrypt_manifest(8, &manifest), Err(Error::InvalidTag));
+
+    let record = key.encrypt_record(&record_id(), PLAINTEXT, &mut OsRng)?;
+    assert_eq!(key.decrypt_record(&record_id(), &record)?, PLAINTEXT);
+    assert_eq!(
+        key.decrypt_record(&[1, 2, 3], &record),
+        Err(Error::InvalidTag)
+    );
+    Ok(())
+}
+
+#[test]
+fn storage_service_rejects_bad_input() -> Result<(), Error> {
+    assert_eq!(
+        StorageServiceKey::new(&[0; 31]).err(),
+        Some(Error::InvalidKeySize(32, 31))
+    );
+
+    let key = StorageServiceKey::from_master_key(&master_key())?;
+    let record = key.encrypt_record(&record_id(), PLAINTEXT, &mut OsRng)?;
+    for i in 0..record.len() {
+        let mut corrupted = record.clone();
+        corrupted[i] ^= 1;
+        assert_eq!(
+            key.decrypt_record(&record_id(), &corrupted),
+            Err(Error::InvalidTag)
+        );
+    }
+    assert_eq!(
+        decrypt(&key.record_key(&record_id()), &record[..27]),
+        Err(Error::InvalidInputSize)
+    );
+    Ok(())
+}
@@ -5,12 +5,13 @@
 
 #![deny(unsafe_code)]
 
-use chrono::{Datelike, Duration, Utc};
+use chrono::{DateTime, Datelike, Duration, Utc};
 use picky::key::PrivateKey;
 use picky::x509::name::{DirectoryName, NameAttr};
 use picky::x509::{certificate::CertificateBuilder, date::UTCDate};
 use picky::{hash::HashAlgorithm, signature::SignatureAlgorithm};
 use std::fmt;
+use std::time::SystemTime;
 
 #[derive(Copy, Clone, Debug)]
 pub enum Error {
@@ -39,6 +40,17 @@ pub fn create_self_signed_cert(
     rsa_key_pkcs8: &[u8],
     name: &str,
     days_to_expire: u32,
+) -> Result<Vec<u8>, Error> {
+    create_self_signed_cert_at(rsa_key_pkcs8, name, days_to_expire, SystemTime::now())
+}
+
+/// Like [`create_self_signed_cert`], but valid from the day containing `now` rather than the
+/// current day according to the system clock.
+pub fn create_self_signed_cert_at(
+    rsa_key_pkcs8: &[u8],
+    name: &str,
+    days_to_expire: u32,
+    now: SystemTime,
 ) -> Result<Vec<u8>, Error> {
     let rsa_key = PrivateKey::from_pkcs8(rsa_key_pkcs8).map_err(|_| Error::KeyDecodingFailed)?;
 
@@ -46,7 +58,7 @@ pub fn create_self_signed_cert(
     dn.add_attr(NameAttr::OrganizationName, "Signal Foundation");
     dn.add_attr(NameAttr::OrganizationalUnitName, "Device Transfer");
 
-    let now = Utc::now();
+    let now = DateTime::<Utc>::from(now);
     let expires = now + Duration::days(days_to_expire.into());
 
     let started_at = UTCDate::ymd(now.year() as u16, now.month() as u8, now.day() as u8)
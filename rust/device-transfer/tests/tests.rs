@@ -7,6 +7,7 @@ use device_transfer::*;
 use openssl::pkey::PKey;
 use openssl::rsa::Padding;
 use openssl::x509::X509;
+use std::time::{Duration, UNIX_EPOCH};
 
 #[test]
 fn test_generate_and_parse() -> Result<(), Error> {
@@ -39,3 +40,23 @@ fn test_generate_and_parse() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+fn test_cert_validity_uses_given_time() -> Result<(), Error> {
+    let key = create_rsa_private_key(2048)?;
+    // 2021-03-15T12:00:00Z
+    let now = UNIX_EPOCH + Duration::from_millis(1615809600000);
+    let cert = create_self_signed_cert_at(&key, "test", 10, now)?;
+
+    let openssl_cert = X509::from_der(&cert).expect("OpenSSL can parse our certificate");
+    assert_eq!(
+        openssl_cert.not_before().to_string(),
+        "Mar 15 00:00:00 2021 GMT"
+    );
+    assert_eq!(
+        openssl_cert.not_after().to_string(),
+        "Mar 25 00:00:00 2021 GMT"
+    );
+
+    Ok(())
+}
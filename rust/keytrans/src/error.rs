@@ -0,0 +1,30 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::fmt;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Error {
+    /// The server's response or stored state could not be parsed.
+    InvalidResponse(String),
+    /// A proof or signature did not verify; the log may be misbehaving.
+    VerificationFailed(String),
+    /// A monitor request was made for a search key that was never searched for.
+    NotMonitored,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidResponse(message) => write!(f, "invalid response: {}", message),
+            Error::VerificationFailed(message) => write!(f, "verification failed: {}", message),
+            Error::NotMonitored => write!(f, "search key is not being monitored"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,33 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Client-side verification for a key transparency log.
+//!
+//! The log is an append-only Merkle tree, as in [RFC 9162]. Each of its entries is the root of a
+//! prefix tree that maps every search key (such as an account identifier) to a commitment to its
+//! current value (such as the account's identity key). The log signs each [`TreeHead`] it
+//! publishes.
+//!
+//! A client keeps the last tree head it verified in a [`KeyTransparencyStore`], and checks each
+//! new one for consistency with it, so the log can't show it a history that was rewritten. It
+//! also remembers each key it has looked up, so it can later [monitor](verify_monitor) that the
+//! log still maps the key to the same value.
+//!
+//! [RFC 9162]: https://www.rfc-editor.org/rfc/rfc9162.html
+
+#![deny(clippy::unwrap_used)]
+
+mod error;
+pub mod log;
+pub mod prefix;
+pub mod proto;
+mod store;
+mod tree_head;
+mod verify;
+
+pub use error::{Error, Result};
+pub use store::{InMemKeyTransparencyStore, KeyTransparencyStore, MonitoringData};
+pub use tree_head::TreeHead;
+pub use verify::{verify_distinguished, verify_monitor, verify_search, DISTINGUISHED_KEY};
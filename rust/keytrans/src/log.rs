@@ -0,0 +1,162 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Verification of proofs about the log tree, following [RFC 9162] section 2.1.
+//!
+//! [RFC 9162]: https://www.rfc-editor.org/rfc/rfc9162.html#section-2.1
+
+use crate::{Error, Result};
+
+use sha2::{Digest, Sha256};
+use std::convert::TryInto;
+
+pub const HASH_SIZE: usize = 32;
+
+pub type Hash = [u8; HASH_SIZE];
+
+pub(crate) fn hash_from(bytes: &[u8], what: &str) -> Result<Hash> {
+    bytes.try_into().map_err(|_| {
+        Error::InvalidResponse(format!(
+            "{} has {} bytes, expected {}",
+            what,
+            bytes.len(),
+            HASH_SIZE
+        ))
+    })
+}
+
+pub(crate) fn hashes_from(list: &[Vec<u8>], what: &str) -> Result<Vec<Hash>> {
+    list.iter().map(|bytes| hash_from(bytes, what)).collect()
+}
+
+/// The hash of a leaf holding `data`.
+pub fn leaf_hash(data: &[u8]) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(&[0x00]);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+pub(crate) fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(&[0x01]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+fn failed(message: &str) -> Error {
+    Error::VerificationFailed(message.to_string())
+}
+
+/// Checks that the leaf at `index` has hash `leaf` in the tree of `tree_size` leaves with `root`.
+pub fn verify_inclusion(
+    index: u64,
+    tree_size: u64,
+    leaf: &Hash,
+    proof: &[Hash],
+    root: &Hash,
+) -> Result<()> {
+    if index >= tree_size {
+        return Err(failed("leaf index is outside the tree"));
+    }
+    let mut f = index;
+    let mut s = tree_size - 1;
+    let mut r = *leaf;
+    for p in proof {
+        if s == 0 {
+            return Err(failed("inclusion proof is too long"));
+        }
+        if f & 1 == 1 || f == s {
+            r = node_hash(p, &r);
+            while f & 1 == 0 && f != 0 {
+                f >>= 1;
+                s >>= 1;
+            }
+        } else {
+            r = node_hash(&r, p);
+        }
+        f >>= 1;
+        s >>= 1;
+    }
+    if s != 0 {
+        return Err(failed("inclusion proof is too short"));
+    }
+    if r != *root {
+        return Err(failed("inclusion proof does not match the tree root"));
+    }
+    Ok(())
+}
+
+/// Checks that the tree of `old_size` leaves with `old_root` is a prefix of the tree of `new_size`
+/// leaves with `new_root`.
+pub fn verify_consistency(
+    old_size: u64,
+    new_size: u64,
+    old_root: &Hash,
+    new_root: &Hash,
+    proof: &[Hash],
+) -> Result<()> {
+    if old_size > new_size {
+        return Err(failed("tree shrank"));
+    }
+    if old_size == new_size {
+        if !proof.is_empty() || old_root != new_root {
+            return Err(failed("tree changed without growing"));
+        }
+        return Ok(());
+    }
+    if old_size == 0 {
+        // Every tree is consistent with the empty tree.
+        return if proof.is_empty() {
+            Ok(())
+        } else {
+            Err(failed("consistency proof is too long"))
+        };
+    }
+
+    let mut path = Vec::with_capacity(proof.len() + 1);
+    if old_size.is_power_of_two() {
+        path.push(*old_root);
+    }
+    path.extend_from_slice(proof);
+    let (first, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return Err(failed("consistency proof is empty")),
+    };
+
+    let mut f = old_size - 1;
+    let mut s = new_size - 1;
+    while f & 1 == 1 {
+        f >>= 1;
+        s >>= 1;
+    }
+    let mut fr = *first;
+    let mut sr = *first;
+    for c in rest {
+        if s == 0 {
+            return Err(failed("consistency proof is too long"));
+        }
+        if f & 1 == 1 || f == s {
+            fr = node_hash(c, &fr);
+            sr = node_hash(c, &sr);
+            while f & 1 == 0 && f != 0 {
+                f >>= 1;
+                s >>= 1;
+            }
+        } else {
+            sr = node_hash(&sr, c);
+        }
+        f >>= 1;
+        s >>= 1;
+    }
+    if s != 0 {
+        return Err(failed("consistency proof is too short"));
+    }
+    if fr != *old_root || sr != *new_root {
+        return Err(failed("consistency proof does not match the tree roots"));
+    }
+    Ok(())
+}
@@ -0,0 +1,69 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Verification of proofs about a prefix tree.
+//!
+//! A prefix tree is a sparse binary Merkle tree keyed by the 256-bit index of each search key. A
+//! leaf sits at the shallowest depth where its index differs from every other leaf's, and an empty
+//! subtree hashes to all zeros.
+
+use crate::log::{node_hash, Hash, HASH_SIZE};
+use crate::{Error, Result};
+
+use sha2::{Digest, Sha256};
+
+const INDEX_LABEL: &[u8] = b"Signal Key Transparency Index";
+const COMMITMENT_LABEL: &[u8] = b"Signal Key Transparency Commitment";
+
+/// The position of `search_key` in the prefix tree.
+pub fn search_index(search_key: &[u8]) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(INDEX_LABEL);
+    hasher.update(search_key);
+    hasher.finalize().into()
+}
+
+/// Commits to `value` for `search_key`, without revealing either to those who lack `opening`.
+pub fn commitment(opening: &[u8], search_key: &[u8], value: &[u8]) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(COMMITMENT_LABEL);
+    for part in &[opening, search_key, value] {
+        hasher.update(&(part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+fn leaf_hash(index: &Hash, commitment: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(&[0x00]);
+    hasher.update(index);
+    hasher.update(commitment);
+    hasher.finalize().into()
+}
+
+fn index_bit(index: &Hash, depth: usize) -> bool {
+    index[depth / 8] & (0x80 >> (depth % 8)) != 0
+}
+
+/// Computes the root of a prefix tree in which `index` maps to `commitment`, given the sibling of
+/// each node on the path from that leaf to the root.
+pub fn prefix_root(index: &Hash, commitment: &Hash, copath: &[Hash]) -> Result<Hash> {
+    if copath.len() > HASH_SIZE * 8 {
+        return Err(Error::InvalidResponse(format!(
+            "prefix proof has {} levels",
+            copath.len()
+        )));
+    }
+    let mut node = leaf_hash(index, commitment);
+    for (sibling, depth) in copath.iter().zip((0..copath.len()).rev()) {
+        node = if index_bit(index, depth) {
+            node_hash(sibling, &node)
+        } else {
+            node_hash(&node, sibling)
+        };
+    }
+    Ok(node)
+}
@@ -0,0 +1,93 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use crate::log::{hash_from, Hash};
+use crate::{proto, Error, Result, TreeHead};
+
+use prost::Message;
+use std::collections::HashMap;
+
+/// What a client remembers about a search key in order to monitor it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MonitoringData {
+    /// The key's position in the prefix tree.
+    pub index: Hash,
+    /// The commitment to the key's value that was last verified.
+    pub commitment: Hash,
+    /// The log entry in which it was verified.
+    pub position: u64,
+}
+
+impl MonitoringData {
+    pub fn deserialize(data: &[u8]) -> Result<Self> {
+        let invalid = || Error::InvalidResponse("monitoring data could not be decoded".to_string());
+        let data = proto::MonitoringData::decode(data).map_err(|_| invalid())?;
+        Ok(Self {
+            index: hash_from(&data.index.ok_or_else(invalid)?, "search index")?,
+            commitment: hash_from(&data.commitment.ok_or_else(invalid)?, "commitment")?,
+            position: data.position.ok_or_else(invalid)?,
+        })
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let data = proto::MonitoringData {
+            index: Some(self.index.to_vec()),
+            commitment: Some(self.commitment.to_vec()),
+            position: Some(self.position),
+        };
+        let mut buf = Vec::with_capacity(data.encoded_len());
+        data.encode(&mut buf)
+            .expect("a Vec has room for any message");
+        buf
+    }
+}
+
+/// Persists a client's view of the log between requests.
+///
+/// Unlike the protocol stores this is synchronous, since it is only ever called from Rust; the
+/// bridges hand the state to the application as serialized bytes instead.
+pub trait KeyTransparencyStore {
+    /// The most recent tree head that was verified, if any.
+    fn tree_head(&self) -> Result<Option<TreeHead>>;
+
+    fn set_tree_head(&mut self, head: &TreeHead) -> Result<()>;
+
+    fn monitoring_data(&self, search_key: &[u8]) -> Result<Option<MonitoringData>>;
+
+    fn set_monitoring_data(&mut self, search_key: &[u8], data: &MonitoringData) -> Result<()>;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InMemKeyTransparencyStore {
+    tree_head: Option<TreeHead>,
+    monitoring_data: HashMap<Vec<u8>, MonitoringData>,
+}
+
+impl InMemKeyTransparencyStore {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl KeyTransparencyStore for InMemKeyTransparencyStore {
+    fn tree_head(&self) -> Result<Option<TreeHead>> {
+        Ok(self.tree_head.clone())
+    }
+
+    fn set_tree_head(&mut self, head: &TreeHead) -> Result<()> {
+        self.tree_head = Some(head.clone());
+        Ok(())
+    }
+
+    fn monitoring_data(&self, search_key: &[u8]) -> Result<Option<MonitoringData>> {
+        Ok(self.monitoring_data.get(search_key).cloned())
+    }
+
+    fn set_monitoring_data(&mut self, search_key: &[u8], data: &MonitoringData) -> Result<()> {
+        self.monitoring_data
+            .insert(search_key.to_vec(), data.clone());
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use crate::log::{hash_from, Hash};
+use crate::{proto, Error, Result};
+
+use libsignal_protocol::PublicKey;
+use prost::Message;
+
+const SIGNATURE_LABEL: &[u8] = b"Signal Key Transparency Tree Head";
+
+/// The log's signed statement of its size and root at a point in time.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TreeHead {
+    pub tree_size: u64,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    pub root: Hash,
+    pub signature: Vec<u8>,
+}
+
+impl TreeHead {
+    /// The data covered by [`signature`](Self::signature).
+    pub fn signed_data(tree_size: u64, timestamp: u64, root: &Hash) -> Vec<u8> {
+        let mut data = Vec::with_capacity(SIGNATURE_LABEL.len() + 16 + root.len());
+        data.extend_from_slice(SIGNATURE_LABEL);
+        data.extend_from_slice(&tree_size.to_be_bytes());
+        data.extend_from_slice(&timestamp.to_be_bytes());
+        data.extend_from_slice(root);
+        data
+    }
+
+    pub fn verify_signature(&self, log_key: &PublicKey) -> Result<()> {
+        let signed_data = Self::signed_data(self.tree_size, self.timestamp, &self.root);
+        match log_key.verify_signature(&signed_data, &self.signature) {
+            Ok(true) => Ok(()),
+            _ => Err(Error::VerificationFailed(
+                "tree head signature is invalid".to_string(),
+            )),
+        }
+    }
+
+    pub fn deserialize(data: &[u8]) -> Result<Self> {
+        let head = proto::TreeHead::decode(data)
+            .map_err(|_| Error::InvalidResponse("tree head could not be decoded".to_string()))?;
+        Self::from_proto(head)
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let head = self.to_proto();
+        let mut buf = Vec::with_capacity(head.encoded_len());
+        head.encode(&mut buf)
+            .expect("a Vec has room for any message");
+        buf
+    }
+
+    pub(crate) fn from_proto(head: proto::TreeHead) -> Result<Self> {
+        let missing = |field: &str| Error::InvalidResponse(format!("tree head has no {}", field));
+        Ok(Self {
+            tree_size: head.tree_size.ok_or_else(|| missing("size"))?,
+            timestamp: head.timestamp.ok_or_else(|| missing("timestamp"))?,
+            root: hash_from(&head.root.ok_or_else(|| missing("root"))?, "tree root")?,
+            signature: head.signature.ok_or_else(|| missing("signature"))?,
+        })
+    }
+
+    pub(crate) fn to_proto(&self) -> proto::TreeHead {
+        proto::TreeHead {
+            tree_size: Some(self.tree_size),
+            timestamp: Some(self.timestamp),
+            root: Some(self.root.to_vec()),
+            signature: Some(self.signature.clone()),
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use crate::log::{self, hash_from, hashes_from, Hash};
+use crate::prefix::{self, search_index};
+use crate::{proto, Error, KeyTransparencyStore, MonitoringData, Result, TreeHead};
+
+use libsignal_protocol::PublicKey;
+use prost::Message;
+
+/// The search key whose entry is looked up by [`verify_distinguished`].
+pub const DISTINGUISHED_KEY: &[u8] = b"distinguished";
+
+fn missing(what: &str) -> Error {
+    Error::InvalidResponse(format!("response has no {}", what))
+}
+
+fn decode<M: Message + Default>(response: &[u8]) -> Result<M> {
+    M::decode(response).map_err(|_| Error::InvalidResponse("could not be decoded".to_string()))
+}
+
+/// The facts established by a verified [`proto::EntryProof`].
+struct VerifiedEntry {
+    tree_head: TreeHead,
+    /// The position of the log's last entry.
+    position: u64,
+    commitment: Hash,
+}
+
+/// Verifies that the log's last entry, as of a tree head consistent with the one in `store`, maps
+/// `index` to the returned commitment.
+///
+/// Nothing is written to `store`; that is left to the caller once all its checks have passed.
+fn verify_entry(
+    log_key: &PublicKey,
+    index: &Hash,
+    entry: Option<proto::EntryProof>,
+    store: &dyn KeyTransparencyStore,
+) -> Result<VerifiedEntry> {
+    let entry = entry.ok_or_else(|| missing("entry proof"))?;
+    let tree_head = TreeHead::from_proto(entry.tree_head.ok_or_else(|| missing("tree head"))?)?;
+    tree_head.verify_signature(log_key)?;
+    if tree_head.tree_size == 0 {
+        return Err(Error::VerificationFailed("log is empty".to_string()));
+    }
+
+    if let Some(last) = store.tree_head()? {
+        log::verify_consistency(
+            last.tree_size,
+            tree_head.tree_size,
+            &last.root,
+            &tree_head.root,
+            &hashes_from(&entry.consistency, "consistency proof")?,
+        )?;
+    }
+
+    let prefix_root = hash_from(
+        &entry.prefix_root.ok_or_else(|| missing("prefix root"))?,
+        "prefix root",
+    )?;
+    let position = tree_head.tree_size - 1;
+    log::verify_inclusion(
+        position,
+        tree_head.tree_size,
+        &log::leaf_hash(&prefix_root),
+        &hashes_from(&entry.inclusion, "inclusion proof")?,
+        &tree_head.root,
+    )?;
+
+    let prefix_proof = entry.prefix.ok_or_else(|| missing("prefix proof"))?;
+    let commitment = hash_from(
+        &prefix_proof
+            .commitment
+            .ok_or_else(|| missing("commitment"))?,
+        "commitment",
+    )?;
+    let copath = hashes_from(&prefix_proof.copath, "prefix proof")?;
+    if prefix::prefix_root(index, &commitment, &copath)? != prefix_root {
+        return Err(Error::VerificationFailed(
+            "prefix proof does not match the log entry".to_string(),
+        ));
+    }
+
+    Ok(VerifiedEntry {
+        tree_head,
+        position,
+        commitment,
+    })
+}
+
+/// Verifies the response to a search for `search_key`, returning the value it maps to.
+///
+/// On success the new tree head is saved to `store`, and `search_key` is set up for monitoring.
+pub fn verify_search(
+    log_key: &PublicKey,
+    search_key: &[u8],
+    response: &[u8],
+    store: &mut dyn KeyTransparencyStore,
+) -> Result<Vec<u8>> {
+    let response: proto::SearchResponse = decode(response)?;
+    let index = search_index(search_key);
+    let verified = verify_entry(log_key, &index, response.entry, store)?;
+
+    let opening = response.opening.ok_or_else(|| missing("opening"))?;
+    let value = response.value.ok_or_else(|| missing("value"))?;
+    if prefix::commitment(&opening, search_key, &value) != verified.commitment {
+        return Err(Error::VerificationFailed(
+            "value does not match its commitment".to_string(),
+        ));
+    }
+
+    store.set_tree_head(&verified.tree_head)?;
+    store.set_monitoring_data(
+        search_key,
+        &MonitoringData {
+            index,
+            commitment: verified.commitment,
+            position: verified.position,
+        },
+    )?;
+    Ok(value)
+}
+
+/// Verifies the response to a monitor request for `search_key`, which must have been searched for
+/// before.
+///
+/// Returns whether `search_key` still maps to the value it did when last verified. If it does not,
+/// the caller should search for it again to learn the new value.
+pub fn verify_monitor(
+    log_key: &PublicKey,
+    search_key: &[u8],
+    response: &[u8],
+    store: &mut dyn KeyTransparencyStore,
+) -> Result<bool> {
+    let data = store
+        .monitoring_data(search_key)?
+        .ok_or(Error::NotMonitored)?;
+    let response: proto::MonitorResponse = decode(response)?;
+    let verified = verify_entry(log_key, &data.index, response.entry, store)?;
+    if verified.position < data.position {
+        return Err(Error::VerificationFailed(
+            "monitored entry is older than the one last verified".to_string(),
+        ));
+    }
+
+    store.set_tree_head(&verified.tree_head)?;
+    if verified.commitment != data.commitment {
+        return Ok(false);
+    }
+    store.set_monitoring_data(
+        search_key,
+        &MonitoringData {
+            position: verified.position,
+            ..data
+        },
+    )?;
+    Ok(true)
+}
+
+/// Verifies the response to a search for [`DISTINGUISHED_KEY`], returning the new tree head.
+///
+/// Clients make this request periodically so that their view of the log advances even when they
+/// have nothing else to look up, which keeps every client's view consistent with everyone else's.
+pub fn verify_distinguished(
+    log_key: &PublicKey,
+    response: &[u8],
+    store: &mut dyn KeyTransparencyStore,
+) -> Result<TreeHead> {
+    let response: proto::SearchResponse = decode(response)?;
+    let verified = verify_entry(
+        log_key,
+        &search_index(DISTINGUISHED_KEY),
+        response.entry,
+        store,
+    )?;
+    store.set_tree_head(&verified.tree_head)?;
+    Ok(verified.tree_head)
+}
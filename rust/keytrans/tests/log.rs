@@ -0,0 +1,82 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+mod support;
+
+use keytrans::log::*;
+use keytrans::Error;
+use support::*;
+
+#[test]
+fn inclusion_proofs_verify() {
+    for size in 1..=20 {
+        let leaves = leaves(size);
+        let root = tree_root(&leaves);
+        for index in 0..size {
+            let proof = inclusion_proof(index, &leaves);
+            verify_inclusion(index as u64, size as u64, &leaves[index], &proof, &root)
+                .unwrap_or_else(|e| panic!("size {} index {}: {}", size, index, e));
+        }
+    }
+}
+
+#[test]
+fn bad_inclusion_proofs_fail() {
+    let leaves = leaves(7);
+    let root = tree_root(&leaves);
+    let proof = inclusion_proof(3, &leaves);
+
+    let wrong_leaf = leaf_hash(b"not in the tree");
+    assert!(verify_inclusion(3, 7, &wrong_leaf, &proof, &root).is_err());
+    assert!(verify_inclusion(4, 7, &leaves[3], &proof, &root).is_err());
+    assert!(verify_inclusion(3, 4, &leaves[3], &proof, &root).is_err());
+    assert!(verify_inclusion(7, 7, &leaves[3], &proof, &root).is_err());
+    assert!(verify_inclusion(3, 7, &leaves[3], &proof[1..], &root).is_err());
+
+    let mut long_proof = proof.clone();
+    long_proof.push(root);
+    assert!(matches!(
+        verify_inclusion(3, 7, &leaves[3], &long_proof, &root),
+        Err(Error::VerificationFailed(_))
+    ));
+}
+
+#[test]
+fn consistency_proofs_verify() {
+    let all_leaves = leaves(20);
+    for new_size in 1..=20 {
+        let new_root = tree_root(&all_leaves[..new_size]);
+        for old_size in 0..=new_size {
+            let old_root = tree_root(&all_leaves[..old_size]);
+            let proof = consistency_proof(old_size, &all_leaves[..new_size]);
+            verify_consistency(
+                old_size as u64,
+                new_size as u64,
+                &old_root,
+                &new_root,
+                &proof,
+            )
+            .unwrap_or_else(|e| panic!("sizes {} to {}: {}", old_size, new_size, e));
+        }
+    }
+}
+
+#[test]
+fn bad_consistency_proofs_fail() {
+    let all_leaves = leaves(11);
+    let old_root = tree_root(&all_leaves[..6]);
+    let new_root = tree_root(&all_leaves);
+    let proof = consistency_proof(6, &all_leaves);
+
+    let mut forked = all_leaves.clone();
+    forked[2] = leaf_hash(b"rewritten history");
+    let forked_root = tree_root(&forked);
+    assert!(verify_consistency(6, 11, &old_root, &forked_root, &proof).is_err());
+    assert!(verify_consistency(6, 11, &new_root, &new_root, &proof).is_err());
+    assert!(verify_consistency(5, 11, &old_root, &new_root, &proof).is_err());
+    assert!(verify_consistency(6, 11, &old_root, &new_root, &proof[1..]).is_err());
+    assert!(verify_consistency(11, 6, &new_root, &old_root, &proof).is_err());
+    assert!(verify_consistency(11, 11, &new_root, &old_root, &[]).is_err());
+}
@@ -0,0 +1,272 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! A straightforward log tree and key transparency server, following the definitions in RFC 9162.
+
+#![allow(dead_code)]
+
+use keytrans::log::{leaf_hash, Hash};
+use keytrans::{prefix, proto, TreeHead, DISTINGUISHED_KEY};
+use libsignal_protocol::KeyPair;
+use prost::Message;
+use rand::rngs::OsRng;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+pub fn leaves(count: usize) -> Vec<Hash> {
+    (0..count)
+        .map(|i| leaf_hash(format!("leaf {}", i).as_bytes()))
+        .collect()
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(&[0x01]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// The largest power of two smaller than `n`.
+fn split_point(n: usize) -> usize {
+    let mut k = 1;
+    while k * 2 < n {
+        k *= 2;
+    }
+    k
+}
+
+/// MTH from RFC 9162 section 2.1.1, given the hashes of the leaves.
+pub fn tree_root(leaves: &[Hash]) -> Hash {
+    match leaves.len() {
+        0 => Sha256::digest(&[]).into(),
+        1 => leaves[0],
+        n => {
+            let k = split_point(n);
+            node_hash(&tree_root(&leaves[..k]), &tree_root(&leaves[k..]))
+        }
+    }
+}
+
+/// PATH from RFC 9162 section 2.1.3.1.
+pub fn inclusion_proof(index: usize, leaves: &[Hash]) -> Vec<Hash> {
+    let n = leaves.len();
+    if n <= 1 {
+        return vec![];
+    }
+    let k = split_point(n);
+    if index < k {
+        let mut path = inclusion_proof(index, &leaves[..k]);
+        path.push(tree_root(&leaves[k..]));
+        path
+    } else {
+        let mut path = inclusion_proof(index - k, &leaves[k..]);
+        path.push(tree_root(&leaves[..k]));
+        path
+    }
+}
+
+/// PROOF from RFC 9162 section 2.1.4.1.
+pub fn consistency_proof(old_size: usize, leaves: &[Hash]) -> Vec<Hash> {
+    if old_size == 0 || old_size == leaves.len() {
+        return vec![];
+    }
+    subproof(old_size, leaves, true)
+}
+
+fn subproof(m: usize, leaves: &[Hash], complete: bool) -> Vec<Hash> {
+    let n = leaves.len();
+    if m == n {
+        return if complete {
+            vec![]
+        } else {
+            vec![tree_root(leaves)]
+        };
+    }
+    let k = split_point(n);
+    if m <= k {
+        let mut proof = subproof(m, &leaves[..k], complete);
+        proof.push(tree_root(&leaves[k..]));
+        proof
+    } else {
+        let mut proof = subproof(m - k, &leaves[k..], false);
+        proof.push(tree_root(&leaves[..k]));
+        proof
+    }
+}
+
+const ZERO_HASH: Hash = [0; 32];
+
+fn bit(index: &Hash, depth: usize) -> bool {
+    index[depth / 8] & (0x80 >> (depth % 8)) != 0
+}
+
+fn prefix_leaf_hash(index: &Hash, commitment: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(&[0x00]);
+    hasher.update(index);
+    hasher.update(commitment);
+    hasher.finalize().into()
+}
+
+/// The root of the prefix subtree at `depth` holding `entries`, which share their first `depth`
+/// index bits.
+fn prefix_subtree(depth: usize, entries: &[&(Hash, Hash)]) -> Hash {
+    match entries {
+        [] => ZERO_HASH,
+        [(index, commitment)] => prefix_leaf_hash(index, commitment),
+        _ => {
+            let (right, left): (Vec<_>, Vec<_>) = entries
+                .iter()
+                .copied()
+                .partition(|(index, _)| bit(index, depth));
+            node_hash(
+                &prefix_subtree(depth + 1, &left),
+                &prefix_subtree(depth + 1, &right),
+            )
+        }
+    }
+}
+
+/// The siblings on the path from the leaf for `index` to the root, starting next to the leaf.
+fn prefix_copath(index: &Hash, entries: &[(Hash, Hash)]) -> Vec<Hash> {
+    let mut copath = Vec::new();
+    let mut remaining: Vec<&(Hash, Hash)> = entries.iter().collect();
+    let mut depth = 0;
+    while remaining.len() > 1 {
+        let (same, other): (Vec<_>, Vec<_>) = remaining
+            .into_iter()
+            .partition(|(other_index, _)| bit(other_index, depth) == bit(index, depth));
+        copath.push(prefix_subtree(depth + 1, &other));
+        remaining = same;
+        depth += 1;
+    }
+    copath.reverse();
+    copath
+}
+
+/// An honest key transparency log, which can be made to misbehave by editing its fields.
+pub struct FakeLog {
+    pub key_pair: KeyPair,
+    /// Each search key's opening and value.
+    pub values: BTreeMap<Vec<u8>, (Vec<u8>, Vec<u8>)>,
+    /// The prefix root recorded in each log entry.
+    pub prefix_roots: Vec<Hash>,
+    pub timestamp: u64,
+}
+
+impl FakeLog {
+    pub fn new() -> Self {
+        let mut log = Self {
+            key_pair: KeyPair::generate(&mut OsRng),
+            values: BTreeMap::new(),
+            prefix_roots: Vec::new(),
+            timestamp: 1_614_000_000_000,
+        };
+        log.set(DISTINGUISHED_KEY, b"");
+        log
+    }
+
+    fn entries(&self) -> Vec<(Hash, Hash)> {
+        self.values
+            .iter()
+            .map(|(search_key, (opening, value))| {
+                (
+                    prefix::search_index(search_key),
+                    prefix::commitment(opening, search_key, value),
+                )
+            })
+            .collect()
+    }
+
+    /// Maps `search_key` to `value` in a new log entry.
+    pub fn set(&mut self, search_key: &[u8], value: &[u8]) {
+        let opening = vec![self.prefix_roots.len() as u8; 16];
+        self.values
+            .insert(search_key.to_vec(), (opening, value.to_vec()));
+        let entries = self.entries();
+        let refs: Vec<_> = entries.iter().collect();
+        self.prefix_roots.push(prefix_subtree(0, &refs));
+        self.timestamp += 1000;
+    }
+
+    pub fn size(&self) -> usize {
+        self.prefix_roots.len()
+    }
+
+    fn log_leaves(&self) -> Vec<Hash> {
+        self.prefix_roots
+            .iter()
+            .map(|root| leaf_hash(root))
+            .collect()
+    }
+
+    pub fn tree_head(&self) -> proto::TreeHead {
+        let size = self.size() as u64;
+        let root = tree_root(&self.log_leaves());
+        let signature = self
+            .key_pair
+            .private_key
+            .calculate_signature(
+                &TreeHead::signed_data(size, self.timestamp, &root),
+                &mut OsRng,
+            )
+            .expect("can sign");
+        proto::TreeHead {
+            tree_size: Some(size),
+            timestamp: Some(self.timestamp),
+            root: Some(root.to_vec()),
+            signature: Some(signature.into_vec()),
+        }
+    }
+
+    /// Proves the last entry's commitment for `search_key`, to a client that last saw
+    /// `known_size` entries.
+    fn entry_proof(&self, search_key: &[u8], known_size: usize) -> proto::EntryProof {
+        let leaves = self.log_leaves();
+        let index = prefix::search_index(search_key);
+        let entries = self.entries();
+        let commitment = entries
+            .iter()
+            .find(|(other, _)| *other == index)
+            .expect("search key is in the log")
+            .1;
+        proto::EntryProof {
+            tree_head: Some(self.tree_head()),
+            consistency: to_bytes(consistency_proof(known_size, &leaves)),
+            prefix_root: Some(self.prefix_roots[self.size() - 1].to_vec()),
+            inclusion: to_bytes(inclusion_proof(self.size() - 1, &leaves)),
+            prefix: Some(proto::PrefixProof {
+                copath: to_bytes(prefix_copath(&index, &entries)),
+                commitment: Some(commitment.to_vec()),
+            }),
+        }
+    }
+
+    pub fn search_response(&self, search_key: &[u8], known_size: usize) -> proto::SearchResponse {
+        let (opening, value) = self.values[search_key].clone();
+        proto::SearchResponse {
+            entry: Some(self.entry_proof(search_key, known_size)),
+            opening: Some(opening),
+            value: Some(value),
+        }
+    }
+
+    pub fn monitor_response(&self, search_key: &[u8], known_size: usize) -> proto::MonitorResponse {
+        proto::MonitorResponse {
+            entry: Some(self.entry_proof(search_key, known_size)),
+        }
+    }
+}
+
+fn to_bytes(hashes: Vec<Hash>) -> Vec<Vec<u8>> {
+    hashes.iter().map(|hash| hash.to_vec()).collect()
+}
+
+pub fn encode(message: &impl Message) -> Vec<u8> {
+    let mut buf = Vec::new();
+    message.encode(&mut buf).expect("can encode");
+    buf
+}
@@ -0,0 +1,202 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+mod support;
+
+use keytrans::{
+    verify_distinguished, verify_monitor, verify_search, Error, InMemKeyTransparencyStore,
+    KeyTransparencyStore,
+};
+use libsignal_protocol::KeyPair;
+use rand::rngs::OsRng;
+use support::{encode, FakeLog};
+
+const ALICE: &[u8] = b"alice";
+const BOB: &[u8] = b"bob";
+
+fn log_with_alice() -> FakeLog {
+    let mut log = FakeLog::new();
+    log.set(ALICE, b"alice's key");
+    log.set(BOB, b"bob's key");
+    log
+}
+
+#[test]
+fn search_verifies() -> Result<(), Error> {
+    let log = log_with_alice();
+    let mut store = InMemKeyTransparencyStore::new();
+
+    let response = encode(&log.search_response(ALICE, 0));
+    let value = verify_search(&log.key_pair.public_key, ALICE, &response, &mut store)?;
+    assert_eq!(value, b"alice's key");
+
+    let head = store.tree_head()?.expect("tree head was stored");
+    assert_eq!(head.tree_size, log.size() as u64);
+    let data = store.monitoring_data(ALICE)?.expect("alice is monitored");
+    assert_eq!(data.position, log.size() as u64 - 1);
+    assert!(store.monitoring_data(BOB)?.is_none());
+    Ok(())
+}
+
+#[test]
+fn search_after_growth_verifies() -> Result<(), Error> {
+    let mut log = log_with_alice();
+    let mut store = InMemKeyTransparencyStore::new();
+    let response = encode(&log.search_response(ALICE, 0));
+    verify_search(&log.key_pair.public_key, ALICE, &response, &mut store)?;
+
+    let known_size = log.size();
+    for i in 0..5 {
+        log.set(format!("user {}", i).as_bytes(), b"key");
+    }
+    let response = encode(&log.search_response(BOB, known_size));
+    let value = verify_search(&log.key_pair.public_key, BOB, &response, &mut store)?;
+    assert_eq!(value, b"bob's key");
+    assert_eq!(
+        store.tree_head()?.expect("tree head was stored").tree_size,
+        log.size() as u64
+    );
+    Ok(())
+}
+
+#[test]
+fn forked_history_fails() -> Result<(), Error> {
+    let mut log = log_with_alice();
+    let mut store = InMemKeyTransparencyStore::new();
+    let response = encode(&log.search_response(ALICE, 0));
+    verify_search(&log.key_pair.public_key, ALICE, &response, &mut store)?;
+    let known_size = log.size();
+
+    // Rewrite an entry the client has already seen, then keep going.
+    log.prefix_roots[1] = [0xff; 32];
+    log.set(b"carol", b"carol's key");
+    let response = encode(&log.search_response(BOB, known_size));
+    assert!(matches!(
+        verify_search(&log.key_pair.public_key, BOB, &response, &mut store),
+        Err(Error::VerificationFailed(_))
+    ));
+    assert_eq!(
+        store.tree_head()?.expect("tree head was stored").tree_size,
+        known_size as u64
+    );
+    Ok(())
+}
+
+#[test]
+fn wrong_log_key_fails() {
+    let log = log_with_alice();
+    let mut store = InMemKeyTransparencyStore::new();
+    let other_key = KeyPair::generate(&mut OsRng).public_key;
+
+    let response = encode(&log.search_response(ALICE, 0));
+    assert!(matches!(
+        verify_search(&other_key, ALICE, &response, &mut store),
+        Err(Error::VerificationFailed(_))
+    ));
+    assert!(store.tree_head().expect("in-memory").is_none());
+}
+
+#[test]
+fn tampered_value_fails() {
+    let log = log_with_alice();
+    let mut store = InMemKeyTransparencyStore::new();
+
+    let mut response = log.search_response(ALICE, 0);
+    response.value = Some(b"mallory's key".to_vec());
+    assert!(matches!(
+        verify_search(
+            &log.key_pair.public_key,
+            ALICE,
+            &encode(&response),
+            &mut store
+        ),
+        Err(Error::VerificationFailed(_))
+    ));
+
+    // Answering with another user's entry doesn't work either.
+    let response = encode(&log.search_response(BOB, 0));
+    assert!(matches!(
+        verify_search(&log.key_pair.public_key, ALICE, &response, &mut store),
+        Err(Error::VerificationFailed(_))
+    ));
+}
+
+#[test]
+fn garbage_response_fails() {
+    let log = log_with_alice();
+    let mut store = InMemKeyTransparencyStore::new();
+    assert!(matches!(
+        verify_search(&log.key_pair.public_key, ALICE, &[0xff; 10], &mut store),
+        Err(Error::InvalidResponse(_))
+    ));
+    assert!(matches!(
+        verify_search(&log.key_pair.public_key, ALICE, &[], &mut store),
+        Err(Error::InvalidResponse(_))
+    ));
+}
+
+#[test]
+fn monitor_detects_changes() -> Result<(), Error> {
+    let mut log = log_with_alice();
+    let mut store = InMemKeyTransparencyStore::new();
+    let response = encode(&log.search_response(ALICE, 0));
+    verify_search(&log.key_pair.public_key, ALICE, &response, &mut store)?;
+
+    let known_size = log.size();
+    log.set(b"carol", b"carol's key");
+    let response = encode(&log.monitor_response(ALICE, known_size));
+    assert!(verify_monitor(
+        &log.key_pair.public_key,
+        ALICE,
+        &response,
+        &mut store
+    )?);
+    assert_eq!(
+        store
+            .monitoring_data(ALICE)?
+            .expect("still monitored")
+            .position,
+        log.size() as u64 - 1
+    );
+
+    let known_size = log.size();
+    log.set(ALICE, b"alice's new key");
+    let response = encode(&log.monitor_response(ALICE, known_size));
+    assert!(!verify_monitor(
+        &log.key_pair.public_key,
+        ALICE,
+        &response,
+        &mut store
+    )?);
+    Ok(())
+}
+
+#[test]
+fn monitor_requires_search() {
+    let log = log_with_alice();
+    let mut store = InMemKeyTransparencyStore::new();
+    let response = encode(&log.monitor_response(ALICE, 0));
+    assert!(matches!(
+        verify_monitor(&log.key_pair.public_key, ALICE, &response, &mut store),
+        Err(Error::NotMonitored)
+    ));
+}
+
+#[test]
+fn distinguished_verifies() -> Result<(), Error> {
+    let mut log = log_with_alice();
+    let mut store = InMemKeyTransparencyStore::new();
+    let response = encode(&log.search_response(keytrans::DISTINGUISHED_KEY, 0));
+    let head = verify_distinguished(&log.key_pair.public_key, &response, &mut store)?;
+    assert_eq!(head.tree_size, log.size() as u64);
+
+    let known_size = log.size();
+    log.set(b"carol", b"carol's key");
+    let response = encode(&log.search_response(keytrans::DISTINGUISHED_KEY, known_size));
+    let head = verify_distinguished(&log.key_pair.public_key, &response, &mut store)?;
+    assert_eq!(head.tree_size, log.size() as u64);
+    assert_eq!(store.tree_head()?, Some(head));
+    Ok(())
+}
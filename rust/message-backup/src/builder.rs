@@ -0,0 +1,261 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use crate::proto;
+use crate::{Error, FrameWriter, Result};
+
+use prost::Message;
+use rand::{CryptoRng, Rng};
+use std::collections::HashSet;
+
+/// The version of the record schema written to [`proto::BackupInfo`].
+pub const BACKUP_VERSION: u64 = 1;
+
+const PROFILE_KEY_SIZE: usize = 32;
+const GROUP_MASTER_KEY_SIZE: usize = 32;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AccountData {
+    pub profile_key: Vec<u8>,
+    pub username: Option<String>,
+    pub given_name: String,
+    pub family_name: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Destination {
+    /// The account the backup belongs to.
+    SelfRecipient,
+    /// At least one of `uuid` and `e164` must be present.
+    Contact {
+        uuid: Option<String>,
+        e164: Option<String>,
+    },
+    Group {
+        master_key: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Recipient {
+    /// Identifies the recipient within the backup. Need not match anything outside it.
+    pub id: u64,
+    pub destination: Destination,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Chat {
+    /// Identifies the chat within the backup. Need not match anything outside it.
+    pub id: u64,
+    pub recipient_id: u64,
+    pub archived: bool,
+    /// 0 if the chat is not pinned.
+    pub pinned_order: u32,
+    /// 0 if messages don't expire.
+    pub expiration_timer_ms: u64,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ChatItem {
+    pub chat_id: u64,
+    pub author_id: u64,
+    pub date_sent: u64,
+    pub text: String,
+}
+
+/// Produces a complete backup from structured records.
+///
+/// Records must be added in dependency order: the account data first, then each recipient before
+/// any chat with it, and each chat before its items. Each call returns the bytes to append to the
+/// stream, so an export never needs to be held in memory as a whole; the stream is complete once
+/// the output of [`finish`](Self::finish) has been written.
+///
+/// A record that is malformed or out of order is rejected with [`Error::InvalidRecord`] without
+/// affecting the stream, so the caller may skip it and carry on.
+pub struct BackupBuilder {
+    writer: FrameWriter,
+    pending: Vec<u8>,
+    has_account_data: bool,
+    has_self_recipient: bool,
+    recipients: HashSet<u64>,
+    chats: HashSet<u64>,
+}
+
+impl BackupBuilder {
+    /// Starts a backup taken at `backup_time_ms` (milliseconds since the Unix epoch).
+    pub fn new<R: Rng + CryptoRng>(
+        backup_key: &[u8],
+        compressed: bool,
+        backup_time_ms: u64,
+        csprng: &mut R,
+    ) -> Result<Self> {
+        let mut writer = FrameWriter::new(backup_key, compressed, csprng)?;
+        let info = proto::BackupInfo {
+            version: Some(BACKUP_VERSION),
+            backup_time_ms: Some(backup_time_ms),
+        };
+        let pending = writer.append(&encode(&info))?;
+        Ok(Self {
+            writer,
+            pending,
+            has_account_data: false,
+            has_self_recipient: false,
+            recipients: HashSet::new(),
+            chats: HashSet::new(),
+        })
+    }
+
+    pub fn add_account_data(&mut self, account: &AccountData) -> Result<Vec<u8>> {
+        if self.has_account_data {
+            return Err(invalid("account data was already added"));
+        }
+        if account.profile_key.len() != PROFILE_KEY_SIZE {
+            return Err(invalid(format!(
+                "profile key has {} bytes, expected {}",
+                account.profile_key.len(),
+                PROFILE_KEY_SIZE
+            )));
+        }
+        let out = self.append(proto::frame::Item::Account(proto::AccountData {
+            profile_key: Some(account.profile_key.clone()),
+            username: account.username.clone(),
+            given_name: Some(account.given_name.clone()),
+            family_name: Some(account.family_name.clone()),
+        }))?;
+        self.has_account_data = true;
+        Ok(out)
+    }
+
+    pub fn add_recipient(&mut self, recipient: &Recipient) -> Result<Vec<u8>> {
+        self.require_account_data()?;
+        if self.recipients.contains(&recipient.id) {
+            return Err(invalid(format!("duplicate recipient {}", recipient.id)));
+        }
+        use proto::recipient::Destination as ProtoDestination;
+        let destination = match &recipient.destination {
+            Destination::SelfRecipient => {
+                if self.has_self_recipient {
+                    return Err(invalid("the self recipient was already added"));
+                }
+                ProtoDestination::SelfRecipient(proto::SelfRecipient {})
+            }
+            Destination::Contact { uuid, e164 } => {
+                if uuid.is_none() && e164.is_none() {
+                    return Err(invalid(format!(
+                        "contact {} has neither a UUID nor a phone number",
+                        recipient.id
+                    )));
+                }
+                ProtoDestination::Contact(proto::Contact {
+                    uuid: uuid.clone(),
+                    e164: e164.clone(),
+                })
+            }
+            Destination::Group { master_key } => {
+                if master_key.len() != GROUP_MASTER_KEY_SIZE {
+                    return Err(invalid(format!(
+                        "group {} has a {}-byte master key, expected {}",
+                        recipient.id,
+                        master_key.len(),
+                        GROUP_MASTER_KEY_SIZE
+                    )));
+                }
+                ProtoDestination::Group(proto::Group {
+                    master_key: Some(master_key.clone()),
+                })
+            }
+        };
+        let out = self.append(proto::frame::Item::Recipient(proto::Recipient {
+            id: Some(recipient.id),
+            destination: Some(destination),
+        }))?;
+        if recipient.destination == Destination::SelfRecipient {
+            self.has_self_recipient = true;
+        }
+        self.recipients.insert(recipient.id);
+        Ok(out)
+    }
+
+    pub fn add_chat(&mut self, chat: &Chat) -> Result<Vec<u8>> {
+        self.require_account_data()?;
+        if self.chats.contains(&chat.id) {
+            return Err(invalid(format!("duplicate chat {}", chat.id)));
+        }
+        if !self.recipients.contains(&chat.recipient_id) {
+            return Err(invalid(format!(
+                "chat {} refers to unknown recipient {}",
+                chat.id, chat.recipient_id
+            )));
+        }
+        let out = self.append(proto::frame::Item::Chat(proto::Chat {
+            id: Some(chat.id),
+            recipient_id: Some(chat.recipient_id),
+            archived: Some(chat.archived),
+            pinned_order: Some(chat.pinned_order),
+            expiration_timer_ms: Some(chat.expiration_timer_ms),
+        }))?;
+        self.chats.insert(chat.id);
+        Ok(out)
+    }
+
+    pub fn add_chat_item(&mut self, item: &ChatItem) -> Result<Vec<u8>> {
+        self.require_account_data()?;
+        if !self.chats.contains(&item.chat_id) {
+            return Err(invalid(format!(
+                "chat item refers to unknown chat {}",
+                item.chat_id
+            )));
+        }
+        if !self.recipients.contains(&item.author_id) {
+            return Err(invalid(format!(
+                "chat item refers to unknown author {}",
+                item.author_id
+            )));
+        }
+        self.append(proto::frame::Item::ChatItem(proto::ChatItem {
+            chat_id: Some(item.chat_id),
+            author_id: Some(item.author_id),
+            date_sent: Some(item.date_sent),
+            text: Some(item.text.clone()),
+        }))
+    }
+
+    /// Ends the backup, returning the last bytes of the stream.
+    pub fn finish(&mut self) -> Result<Vec<u8>> {
+        self.require_account_data()?;
+        let last = self.writer.finish()?;
+        let mut out = std::mem::take(&mut self.pending);
+        out.extend(last);
+        Ok(out)
+    }
+
+    fn require_account_data(&self) -> Result<()> {
+        if self.has_account_data {
+            Ok(())
+        } else {
+            Err(invalid("account data must be added first"))
+        }
+    }
+
+    fn append(&mut self, item: proto::frame::Item) -> Result<Vec<u8>> {
+        let frame = proto::Frame { item: Some(item) };
+        let encoded = self.writer.append(&encode(&frame))?;
+        let mut out = std::mem::take(&mut self.pending);
+        out.extend(encoded);
+        Ok(out)
+    }
+}
+
+fn encode(message: &impl Message) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(message.encoded_len());
+    message
+        .encode(&mut buf)
+        .expect("a Vec has room for any message");
+    buf
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidRecord(message.into())
+}
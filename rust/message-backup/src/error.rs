@@ -0,0 +1,52 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::fmt;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Error {
+    /// The expected and actual sizes, in bytes.
+    InvalidKeySize(usize, usize),
+    /// The stream does not start with a supported header.
+    UnknownFormat,
+    /// The stream ended before its final frame.
+    Truncated,
+    /// The index of a frame whose length is invalid or too large.
+    InvalidFrameLength(u64),
+    /// The index of a frame that failed authentication.
+    InvalidMac(u64),
+    /// The index of a frame that could not be decompressed.
+    InvalidCompression(u64),
+    /// Data followed the final frame.
+    TrailingData,
+    /// The reader or writer was used after it finished.
+    InvalidState,
+    /// A record passed to a builder is malformed or out of order.
+    InvalidRecord(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidKeySize(expected, actual) => {
+                write!(f, "invalid key size <{}>, expected <{}>", actual, expected)
+            }
+            Error::UnknownFormat => write!(f, "not a supported backup stream"),
+            Error::Truncated => write!(f, "backup stream is truncated"),
+            Error::InvalidFrameLength(index) => write!(f, "frame {} has an invalid length", index),
+            Error::InvalidMac(index) => write!(f, "frame {} failed authentication", index),
+            Error::InvalidCompression(index) => {
+                write!(f, "frame {} could not be decompressed", index)
+            }
+            Error::TrailingData => write!(f, "unexpected data after the final frame"),
+            Error::InvalidState => write!(f, "invalid object state"),
+            Error::InvalidRecord(message) => write!(f, "invalid backup record: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
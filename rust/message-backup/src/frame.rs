@@ -0,0 +1,401 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use crate::{Error, Result};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use hmac::{Hmac, Mac, NewMac};
+use rand::{CryptoRng, Rng};
+use sha2::Sha256;
+use signal_crypto::Aes256Ctr32;
+use std::convert::TryInto;
+use std::io::{Read, Write};
+
+pub const BACKUP_KEY_SIZE: usize = 32;
+
+/// The largest frame accepted, both as stored and after decompression.
+pub const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+const MAGIC: &[u8; 4] = b"SGBK";
+const VERSION: u8 = 1;
+const FLAG_COMPRESSED: u8 = 0x01;
+const SALT_SIZE: usize = 16;
+const HEADER_SIZE: usize = MAGIC.len() + 2 + SALT_SIZE;
+const MAC_SIZE: usize = 32;
+const MAX_VARINT_SIZE: usize = 10;
+
+const CIPHER_KEY_LABEL: &[u8] = b"Signal Message Backup Frame Encryption";
+const MAC_KEY_LABEL: &[u8] = b"Signal Message Backup Frame Authentication";
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn hmac_sha256(key: &[u8]) -> HmacSha256 {
+    HmacSha256::new_varkey(key).expect("HMAC accepts any key length")
+}
+
+fn backup_key_from(key: &[u8]) -> Result<[u8; BACKUP_KEY_SIZE]> {
+    key.try_into()
+        .map_err(|_| Error::InvalidKeySize(BACKUP_KEY_SIZE, key.len()))
+}
+
+#[derive(Clone)]
+struct FrameKeys {
+    cipher_key: [u8; 32],
+    mac_key: [u8; 32],
+}
+
+impl FrameKeys {
+    fn derive(backup_key: &[u8; BACKUP_KEY_SIZE], salt: &[u8]) -> Self {
+        let derive_key = |label: &[u8]| {
+            let mut mac = hmac_sha256(backup_key);
+            mac.update(label);
+            mac.update(salt);
+            let mut key = [0u8; 32];
+            key.copy_from_slice(&mac.finalize().into_bytes());
+            key
+        };
+        Self {
+            cipher_key: derive_key(CIPHER_KEY_LABEL),
+            mac_key: derive_key(MAC_KEY_LABEL),
+        }
+    }
+
+    /// Encrypts or decrypts the frame at `index` in place.
+    fn apply_keystream(&self, index: u64, buf: &mut [u8]) {
+        let mut nonce = [0u8; Aes256Ctr32::NONCE_SIZE];
+        nonce[Aes256Ctr32::NONCE_SIZE - 8..].copy_from_slice(&index.to_be_bytes());
+        let mut ctr = Aes256Ctr32::from_key(&self.cipher_key, &nonce, 0)
+            .expect("key and nonce have the right sizes");
+        ctr.process(buf).expect("AES-CTR accepts any length");
+    }
+
+    /// The starting point of the MAC chain.
+    fn header_mac(&self, header: &[u8]) -> [u8; MAC_SIZE] {
+        let mut mac = hmac_sha256(&self.mac_key);
+        mac.update(header);
+        let mut result = [0u8; MAC_SIZE];
+        result.copy_from_slice(&mac.finalize().into_bytes());
+        result
+    }
+
+    fn frame_mac(&self, previous: &[u8; MAC_SIZE], prefix: &[u8], ciphertext: &[u8]) -> HmacSha256 {
+        let mut mac = hmac_sha256(&self.mac_key);
+        mac.update(previous);
+        mac.update(prefix);
+        mac.update(ciphertext);
+        mac
+    }
+}
+
+fn encode_varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+enum Varint {
+    Incomplete,
+    Invalid,
+    /// The value and the number of bytes it took up.
+    Value(u64, usize),
+}
+
+fn decode_varint(buf: &[u8]) -> Varint {
+    let mut value = 0u64;
+    for (i, byte) in buf.iter().take(MAX_VARINT_SIZE).enumerate() {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Varint::Value(value, i + 1);
+        }
+    }
+    if buf.len() >= MAX_VARINT_SIZE {
+        Varint::Invalid
+    } else {
+        Varint::Incomplete
+    }
+}
+
+fn compress(frame: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(frame)
+        .expect("writing to a Vec cannot fail");
+    encoder.finish().expect("writing to a Vec cannot fail")
+}
+
+fn decompress(data: &[u8], index: u64) -> Result<Vec<u8>> {
+    let mut frame = Vec::new();
+    GzDecoder::new(data)
+        .take(MAX_FRAME_SIZE as u64 + 1)
+        .read_to_end(&mut frame)
+        .map_err(|_| Error::InvalidCompression(index))?;
+    if frame.len() > MAX_FRAME_SIZE {
+        return Err(Error::InvalidFrameLength(index));
+    }
+    Ok(frame)
+}
+
+/// Produces a backup stream one frame at a time.
+///
+/// The output of each call is meant to be appended to the stream as-is; the header is included in
+/// the output of the first call.
+#[derive(Clone)]
+pub struct FrameWriter {
+    keys: FrameKeys,
+    compressed: bool,
+    pending_header: Option<Vec<u8>>,
+    chain: [u8; MAC_SIZE],
+    index: u64,
+    finished: bool,
+}
+
+impl FrameWriter {
+    /// Starts a new stream, gzip-compressing each frame if `compressed` is set.
+    pub fn new<R: Rng + CryptoRng>(
+        backup_key: &[u8],
+        compressed: bool,
+        csprng: &mut R,
+    ) -> Result<Self> {
+        let backup_key = backup_key_from(backup_key)?;
+        let mut salt = [0u8; SALT_SIZE];
+        csprng.fill_bytes(&mut salt);
+        let keys = FrameKeys::derive(&backup_key, &salt);
+
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        header.extend_from_slice(MAGIC);
+        header.push(VERSION);
+        header.push(if compressed { FLAG_COMPRESSED } else { 0 });
+        header.extend_from_slice(&salt);
+        let chain = keys.header_mac(&header);
+
+        Ok(Self {
+            keys,
+            compressed,
+            pending_header: Some(header),
+            chain,
+            index: 0,
+            finished: false,
+        })
+    }
+
+    /// Encodes `frame`, returning the bytes to append to the stream.
+    pub fn append(&mut self, frame: &[u8]) -> Result<Vec<u8>> {
+        if self.finished {
+            return Err(Error::InvalidState);
+        }
+        if frame.len() > MAX_FRAME_SIZE {
+            return Err(Error::InvalidFrameLength(self.index));
+        }
+        let mut ciphertext = if self.compressed {
+            compress(frame)
+        } else {
+            frame.to_vec()
+        };
+        if ciphertext.len() > MAX_FRAME_SIZE {
+            return Err(Error::InvalidFrameLength(self.index));
+        }
+        self.keys.apply_keystream(self.index, &mut ciphertext);
+        Ok(self.write_frame(&ciphertext, false))
+    }
+
+    /// Ends the stream, returning the bytes to append to it.
+    ///
+    /// A stream that is not finished will be reported as truncated when it is read.
+    pub fn finish(&mut self) -> Result<Vec<u8>> {
+        if self.finished {
+            return Err(Error::InvalidState);
+        }
+        self.finished = true;
+        Ok(self.write_frame(&[], true))
+    }
+
+    fn write_frame(&mut self, ciphertext: &[u8], is_final: bool) -> Vec<u8> {
+        let mut out = self.pending_header.take().unwrap_or_default();
+        out.reserve(MAX_VARINT_SIZE + ciphertext.len() + MAC_SIZE);
+
+        let prefix_start = out.len();
+        encode_varint(((ciphertext.len() as u64) << 1) | is_final as u64, &mut out);
+        let mac = self
+            .keys
+            .frame_mac(&self.chain, &out[prefix_start..], ciphertext)
+            .finalize()
+            .into_bytes();
+        out.extend_from_slice(ciphertext);
+        out.extend_from_slice(&mac);
+
+        self.chain.copy_from_slice(&mac);
+        self.index += 1;
+        out
+    }
+}
+
+#[derive(Clone)]
+enum ReaderState {
+    Header {
+        backup_key: [u8; BACKUP_KEY_SIZE],
+    },
+    Frames {
+        keys: FrameKeys,
+        compressed: bool,
+        chain: [u8; MAC_SIZE],
+        index: u64,
+    },
+    Finished,
+    Failed(Error),
+}
+
+/// Decodes a backup stream fed to it in chunks of any size.
+///
+/// Call [`push`](Self::push) with each chunk of input, then [`next_frame`](Self::next_frame) until
+/// it returns `None`, and repeat until the input is exhausted. [`finish`](Self::finish) then checks
+/// that the stream was complete.
+///
+/// Once an error is reported, the reader reports the same error from then on.
+#[derive(Clone)]
+pub struct FrameReader {
+    buffer: Vec<u8>,
+    state: ReaderState,
+}
+
+impl FrameReader {
+    pub fn new(backup_key: &[u8]) -> Result<Self> {
+        Ok(Self {
+            buffer: Vec::new(),
+            state: ReaderState::Header {
+                backup_key: backup_key_from(backup_key)?,
+            },
+        })
+    }
+
+    /// Adds `chunk` to the input.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<()> {
+        match &self.state {
+            ReaderState::Failed(e) => return Err(e.clone()),
+            ReaderState::Finished if !chunk.is_empty() => {
+                return Err(self.fail(Error::TrailingData));
+            }
+            _ => {}
+        }
+        self.buffer.extend_from_slice(chunk);
+        Ok(())
+    }
+
+    /// Returns the next frame, or `None` if more input is needed or the stream has ended.
+    pub fn next_frame(&mut self) -> Result<Option<Vec<u8>>> {
+        self.read_frame().map_err(|e| self.fail(e))
+    }
+
+    /// Whether the final frame has been read.
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, ReaderState::Finished)
+    }
+
+    /// Checks that the stream ended with its final frame.
+    ///
+    /// This should be called once [`next_frame`](Self::next_frame) has returned `None` for the
+    /// last of the input.
+    pub fn finish(&mut self) -> Result<()> {
+        match &self.state {
+            ReaderState::Finished => Ok(()),
+            ReaderState::Failed(e) => Err(e.clone()),
+            _ => Err(self.fail(Error::Truncated)),
+        }
+    }
+
+    fn fail(&mut self, error: Error) -> Error {
+        self.state = ReaderState::Failed(error.clone());
+        self.buffer = Vec::new();
+        error
+    }
+
+    fn read_frame(&mut self) -> Result<Option<Vec<u8>>> {
+        loop {
+            match &mut self.state {
+                ReaderState::Failed(e) => return Err(e.clone()),
+                ReaderState::Finished => {
+                    if self.buffer.is_empty() {
+                        return Ok(None);
+                    }
+                    return Err(Error::TrailingData);
+                }
+                ReaderState::Header { backup_key } => {
+                    if self.buffer.len() < HEADER_SIZE {
+                        // Reject the wrong kind of input without waiting for a whole header.
+                        let checkable = self.buffer.len().min(MAGIC.len());
+                        if self.buffer[..checkable] != MAGIC[..checkable] {
+                            return Err(Error::UnknownFormat);
+                        }
+                        return Ok(None);
+                    }
+                    let header: Vec<u8> = self.buffer.drain(..HEADER_SIZE).collect();
+                    let flags = header[MAGIC.len() + 1];
+                    if header[..MAGIC.len()] != MAGIC[..]
+                        || header[MAGIC.len()] != VERSION
+                        || flags & !FLAG_COMPRESSED != 0
+                    {
+                        return Err(Error::UnknownFormat);
+                    }
+                    let keys = FrameKeys::derive(backup_key, &header[MAGIC.len() + 2..]);
+                    let chain = keys.header_mac(&header);
+                    self.state = ReaderState::Frames {
+                        keys,
+                        compressed: flags & FLAG_COMPRESSED != 0,
+                        chain,
+                        index: 0,
+                    };
+                }
+                ReaderState::Frames {
+                    keys,
+                    compressed,
+                    chain,
+                    index,
+                } => {
+                    let (tag, prefix_len) = match decode_varint(&self.buffer) {
+                        Varint::Incomplete => return Ok(None),
+                        Varint::Invalid => return Err(Error::InvalidFrameLength(*index)),
+                        Varint::Value(tag, prefix_len) => (tag, prefix_len),
+                    };
+                    let is_final = tag & 1 == 1;
+                    let length = tag >> 1;
+                    if length > MAX_FRAME_SIZE as u64 || (is_final && length != 0) {
+                        return Err(Error::InvalidFrameLength(*index));
+                    }
+                    let frame_size = prefix_len + length as usize + MAC_SIZE;
+                    if self.buffer.len() < frame_size {
+                        return Ok(None);
+                    }
+
+                    let (prefix, rest) = self.buffer.split_at(prefix_len);
+                    let (ciphertext, rest) = rest.split_at(length as usize);
+                    let their_mac = &rest[..MAC_SIZE];
+                    keys.frame_mac(chain, prefix, ciphertext)
+                        .verify(their_mac)
+                        .map_err(|_| Error::InvalidMac(*index))?;
+                    chain.copy_from_slice(their_mac);
+
+                    let mut frame = ciphertext.to_vec();
+                    keys.apply_keystream(*index, &mut frame);
+                    let frame_index = *index;
+                    *index += 1;
+                    let compressed = *compressed;
+                    self.buffer.drain(..frame_size);
+
+                    if is_final {
+                        self.state = ReaderState::Finished;
+                        continue;
+                    }
+                    if compressed {
+                        frame = decompress(&frame, frame_index)?;
+                    }
+                    return Ok(Some(frame));
+                }
+            }
+        }
+    }
+}
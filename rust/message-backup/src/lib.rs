@@ -0,0 +1,42 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Reading and writing the framed container used for message backups.
+//!
+//! A backup stream is a short header followed by a sequence of encrypted frames:
+//!
+//! ```text
+//! header = "SGBK" version:u8 flags:u8 salt:[u8; 16]
+//! frame  = varint(length << 1 | is_final) ciphertext:[u8; length] mac:[u8; 32]
+//! ```
+//!
+//! - The cipher and MAC keys are derived from the 32-byte backup key and the salt, so every stream
+//!   gets its own keys even when the backup key is reused.
+//! - Each frame is encrypted with AES-256-CTR, using the frame's index as the nonce. If bit 0 of
+//!   `flags` is set, each frame is gzip-compressed before it is encrypted.
+//! - Each MAC is HMAC-SHA256 over the previous frame's MAC (or the header, for the first frame),
+//!   the frame's length prefix, and its ciphertext. Frames therefore can't be dropped, reordered,
+//!   or moved between streams without being detected.
+//! - The stream ends with an empty frame marked final. A stream without one was truncated.
+//!
+//! [`FrameReader`] accepts input in chunks of any size, so it can be fed straight from the network
+//! or a file, and reports exactly which frame failed if the stream is corrupted.
+//!
+//! The first frame holds a [`proto::BackupInfo`], and every later frame a [`proto::Frame`].
+//! [`BackupBuilder`] writes these from structured records, checking that they are well-formed and
+//! refer only to records that came before them.
+
+#![deny(clippy::unwrap_used)]
+
+mod builder;
+mod error;
+mod frame;
+pub mod proto;
+
+pub use builder::{
+    AccountData, BackupBuilder, Chat, ChatItem, Destination, Recipient, BACKUP_VERSION,
+};
+pub use error::{Error, Result};
+pub use frame::{FrameReader, FrameWriter, BACKUP_KEY_SIZE, MAX_FRAME_SIZE};
@@ -0,0 +1,8 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! The records stored in a backup's frames, as generated from `backup.proto`.
+
+include!(concat!(env!("OUT_DIR"), "/signal.backup.rs"));
@@ -0,0 +1,168 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use message_backup::proto::{self, frame::Item};
+use message_backup::*;
+use prost::Message;
+use rand::rngs::OsRng;
+
+const BACKUP_KEY: [u8; BACKUP_KEY_SIZE] = [0x42; BACKUP_KEY_SIZE];
+const BACKUP_TIME_MS: u64 = 1_614_000_000_000;
+
+fn account_data() -> AccountData {
+    AccountData {
+        profile_key: vec![1; 32],
+        username: None,
+        given_name: "Alice".to_string(),
+        family_name: "".to_string(),
+    }
+}
+
+fn contact(id: u64) -> Recipient {
+    Recipient {
+        id,
+        destination: Destination::Contact {
+            uuid: Some("9d0652a3-dcc3-4d11-975f-74d61598733f".to_string()),
+            e164: None,
+        },
+    }
+}
+
+fn chat(id: u64, recipient_id: u64) -> Chat {
+    Chat {
+        id,
+        recipient_id,
+        archived: false,
+        pinned_order: 0,
+        expiration_timer_ms: 0,
+    }
+}
+
+fn new_builder() -> Result<BackupBuilder> {
+    BackupBuilder::new(&BACKUP_KEY, true, BACKUP_TIME_MS, &mut OsRng)
+}
+
+#[test]
+fn builder_output_reads_back() -> Result<()> {
+    let mut builder = new_builder()?;
+    let mut stream = Vec::new();
+    stream.extend(builder.add_account_data(&account_data())?);
+    stream.extend(builder.add_recipient(&Recipient {
+        id: 1,
+        destination: Destination::SelfRecipient,
+    })?);
+    stream.extend(builder.add_recipient(&contact(2))?);
+    stream.extend(builder.add_chat(&chat(10, 2))?);
+    stream.extend(builder.add_chat_item(&ChatItem {
+        chat_id: 10,
+        author_id: 1,
+        date_sent: BACKUP_TIME_MS - 1000,
+        text: "hello".to_string(),
+    })?);
+    stream.extend(builder.finish()?);
+
+    let mut reader = FrameReader::new(&BACKUP_KEY)?;
+    reader.push(&stream)?;
+    let info = proto::BackupInfo::decode(&reader.next_frame()?.expect("info frame")[..])
+        .expect("valid BackupInfo");
+    assert_eq!(info.version, Some(BACKUP_VERSION));
+    assert_eq!(info.backup_time_ms, Some(BACKUP_TIME_MS));
+
+    let mut items = Vec::new();
+    while let Some(frame) = reader.next_frame()? {
+        let frame = proto::Frame::decode(&frame[..]).expect("valid Frame");
+        items.push(frame.item.expect("frame has an item"));
+    }
+    reader.finish()?;
+
+    assert_eq!(items.len(), 5);
+    match &items[0] {
+        Item::Account(account) => assert_eq!(account.given_name.as_deref(), Some("Alice")),
+        other => panic!("expected account data, got {:?}", other),
+    }
+    match &items[4] {
+        Item::ChatItem(item) => {
+            assert_eq!(item.chat_id, Some(10));
+            assert_eq!(item.text.as_deref(), Some("hello"));
+        }
+        other => panic!("expected a chat item, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn records_must_come_in_order() -> Result<()> {
+    let mut builder = new_builder()?;
+    assert!(matches!(
+        builder.add_recipient(&contact(1)),
+        Err(Error::InvalidRecord(_))
+    ));
+    assert!(matches!(builder.finish(), Err(Error::InvalidRecord(_))));
+
+    builder.add_account_data(&account_data())?;
+    assert!(matches!(
+        builder.add_account_data(&account_data()),
+        Err(Error::InvalidRecord(_))
+    ));
+    assert!(matches!(
+        builder.add_chat(&chat(10, 1)),
+        Err(Error::InvalidRecord(_))
+    ));
+    builder.add_recipient(&contact(1))?;
+    assert!(matches!(
+        builder.add_recipient(&contact(1)),
+        Err(Error::InvalidRecord(_))
+    ));
+    builder.add_chat(&chat(10, 1))?;
+    assert!(matches!(
+        builder.add_chat_item(&ChatItem {
+            chat_id: 10,
+            author_id: 2,
+            date_sent: 0,
+            text: "from nobody".to_string(),
+        }),
+        Err(Error::InvalidRecord(_))
+    ));
+
+    builder.finish()?;
+    assert_eq!(builder.add_recipient(&contact(3)), Err(Error::InvalidState));
+    Ok(())
+}
+
+#[test]
+fn rejected_records_leave_the_stream_intact() -> Result<()> {
+    let mut builder = new_builder()?;
+    let mut stream = builder.add_account_data(&account_data())?;
+    assert!(builder
+        .add_recipient(&Recipient {
+            id: 1,
+            destination: Destination::Group {
+                master_key: vec![0; 16],
+            },
+        })
+        .is_err());
+    assert!(builder
+        .add_recipient(&Recipient {
+            id: 1,
+            destination: Destination::Contact {
+                uuid: None,
+                e164: None,
+            },
+        })
+        .is_err());
+    stream.extend(builder.add_recipient(&contact(1))?);
+    stream.extend(builder.finish()?);
+
+    let mut reader = FrameReader::new(&BACKUP_KEY)?;
+    reader.push(&stream)?;
+    let mut count = 0;
+    while reader.next_frame()?.is_some() {
+        count += 1;
+    }
+    reader.finish()?;
+    // The info frame, the account data, and the one valid recipient.
+    assert_eq!(count, 3);
+    Ok(())
+}
@@ -0,0 +1,134 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use message_backup::*;
+use rand::rngs::OsRng;
+
+const BACKUP_KEY: [u8; BACKUP_KEY_SIZE] = [0x42; BACKUP_KEY_SIZE];
+
+fn frames() -> Vec<Vec<u8>> {
+    vec![
+        b"account data".to_vec(),
+        vec![],
+        vec![0xab; 100_000],
+        b"last chat item".to_vec(),
+    ]
+}
+
+fn write_stream(compressed: bool) -> Result<Vec<u8>> {
+    let mut writer = FrameWriter::new(&BACKUP_KEY, compressed, &mut OsRng)?;
+    let mut stream = Vec::new();
+    for frame in frames() {
+        stream.extend(writer.append(&frame)?);
+    }
+    stream.extend(writer.finish()?);
+    Ok(stream)
+}
+
+/// Feeds `stream` to a reader `chunk_size` bytes at a time and collects the frames.
+fn read_stream(stream: &[u8], chunk_size: usize) -> Result<Vec<Vec<u8>>> {
+    let mut reader = FrameReader::new(&BACKUP_KEY)?;
+    let mut frames = Vec::new();
+    for chunk in stream.chunks(chunk_size) {
+        reader.push(chunk)?;
+        while let Some(frame) = reader.next_frame()? {
+            frames.push(frame);
+        }
+    }
+    reader.finish()?;
+    Ok(frames)
+}
+
+#[test]
+fn round_trip() -> Result<()> {
+    for &compressed in &[false, true] {
+        let stream = write_stream(compressed)?;
+        for &chunk_size in &[1, 7, 4096, stream.len()] {
+            assert_eq!(read_stream(&stream, chunk_size)?, frames());
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn compression_shrinks_repetitive_frames() -> Result<()> {
+    assert!(write_stream(true)?.len() < write_stream(false)?.len() / 10);
+    Ok(())
+}
+
+#[test]
+fn streams_use_fresh_keys() -> Result<()> {
+    let first = write_stream(false)?;
+    let second = write_stream(false)?;
+    assert_eq!(first.len(), second.len());
+    assert_ne!(first, second);
+    Ok(())
+}
+
+#[test]
+fn truncation_is_detected() -> Result<()> {
+    let stream = write_stream(false)?;
+    for &len in &[0, 10, 30, stream.len() / 2, stream.len() - 1] {
+        assert_eq!(read_stream(&stream[..len], 16), Err(Error::Truncated));
+    }
+    Ok(())
+}
+
+#[test]
+fn corruption_is_reported_with_the_frame_index() -> Result<()> {
+    let mut stream = write_stream(false)?;
+    // The header is 22 bytes, and the first frame holds 12 bytes with a 1-byte prefix and a
+    // 32-byte MAC; the second frame is empty. Flip a bit in the third frame's ciphertext.
+    let third_frame_start = 22 + (1 + 12 + 32) + (1 + 32);
+    stream[third_frame_start + 10] ^= 1;
+
+    let mut reader = FrameReader::new(&BACKUP_KEY)?;
+    reader.push(&stream)?;
+    assert_eq!(reader.next_frame()?, Some(frames()[0].clone()));
+    assert_eq!(reader.next_frame()?, Some(frames()[1].clone()));
+    assert_eq!(reader.next_frame(), Err(Error::InvalidMac(2)));
+    // The error sticks.
+    assert_eq!(reader.next_frame(), Err(Error::InvalidMac(2)));
+    assert_eq!(reader.finish(), Err(Error::InvalidMac(2)));
+    Ok(())
+}
+
+#[test]
+fn wrong_key_fails_the_first_frame() -> Result<()> {
+    let stream = write_stream(false)?;
+    let mut reader = FrameReader::new(&[0x43; BACKUP_KEY_SIZE])?;
+    reader.push(&stream)?;
+    assert_eq!(reader.next_frame(), Err(Error::InvalidMac(0)));
+    Ok(())
+}
+
+#[test]
+fn trailing_data_is_rejected() -> Result<()> {
+    let mut stream = write_stream(false)?;
+    stream.push(0);
+    assert_eq!(read_stream(&stream, 4096), Err(Error::TrailingData));
+    Ok(())
+}
+
+#[test]
+fn other_formats_are_rejected_early() -> Result<()> {
+    let mut reader = FrameReader::new(&BACKUP_KEY)?;
+    reader.push(b"PK")?;
+    assert_eq!(reader.next_frame(), Err(Error::UnknownFormat));
+    Ok(())
+}
+
+#[test]
+fn bad_keys_and_finished_writers() -> Result<()> {
+    assert_eq!(
+        FrameReader::new(&[0; 16]).err(),
+        Some(Error::InvalidKeySize(32, 16))
+    );
+    let mut writer = FrameWriter::new(&BACKUP_KEY, false, &mut OsRng)?;
+    writer.finish()?;
+    assert_eq!(writer.append(b"too late"), Err(Error::InvalidState));
+    assert_eq!(writer.finish(), Err(Error::InvalidState));
+    Ok(())
+}
@@ -12,4 +12,5 @@ pub enum PokshoError {
     BadArgsMissingPointArg,           // Bad arguments were passed to the function
     VerificationFailure,              // Proof verification failed
     ProofCreationVerificationFailure, // Proof verification failed during proof creation, indicating bad inputs or faulty computation
+    BadEncoding,                      // A point or scalar was not canonically encoded
 }
@@ -8,6 +8,8 @@
 pub mod args;
 pub mod errors;
 pub mod proof;
+#[cfg(feature = "advanced-crypto")]
+pub mod ristretto;
 pub mod scalar;
 pub mod shoapi;
 pub mod shohmacsha256;
@@ -0,0 +1,137 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Ristretto group operations for prototyping new credentials and blinded identifiers.
+//!
+//! These are the same building blocks zkgroup is made of, exposed with the `advanced-crypto`
+//! feature. Every hash takes a domain label, so that points and scalars derived for one purpose
+//! can never be confused with those derived for another. Anything built on top of this module
+//! needs its own review before it ships.
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+
+use crate::errors::PokshoError;
+use crate::scalar::{scalar_from_slice_canonical, scalar_from_slice_wide};
+use crate::shoapi::ShoApi;
+use crate::shohmacsha256::ShoHmacSha256;
+
+pub const POINT_LEN: usize = 32;
+pub const SCALAR_LEN: usize = 32;
+
+/// Hashes `input` to a point whose discrete log nobody knows.
+pub fn hash_to_point(domain: &[u8], input: &[u8]) -> RistrettoPoint {
+    let mut uniform_bytes = [0u8; 64];
+    uniform_bytes.copy_from_slice(&ShoHmacSha256::shohash(domain, input, 64));
+    RistrettoPoint::from_uniform_bytes(&uniform_bytes)
+}
+
+/// Hashes `input` to a uniformly distributed scalar.
+pub fn hash_to_scalar(domain: &[u8], input: &[u8]) -> Scalar {
+    scalar_from_slice_wide(&ShoHmacSha256::shohash(domain, input, 64))
+}
+
+/// Parses a compressed point, rejecting any encoding that is not canonical.
+pub fn point_from_bytes(bytes: &[u8]) -> Result<RistrettoPoint, PokshoError> {
+    if bytes.len() != POINT_LEN {
+        return Err(PokshoError::BadEncoding);
+    }
+    CompressedRistretto::from_slice(bytes)
+        .decompress()
+        .ok_or(PokshoError::BadEncoding)
+}
+
+pub fn point_to_bytes(point: &RistrettoPoint) -> [u8; POINT_LEN] {
+    point.compress().to_bytes()
+}
+
+/// Parses a scalar, rejecting any encoding that is not fully reduced.
+pub fn scalar_from_bytes(bytes: &[u8]) -> Result<Scalar, PokshoError> {
+    if bytes.len() != SCALAR_LEN {
+        return Err(PokshoError::BadEncoding);
+    }
+    scalar_from_slice_canonical(bytes).ok_or(PokshoError::BadEncoding)
+}
+
+pub fn scalar_to_bytes(scalar: &Scalar) -> [u8; SCALAR_LEN] {
+    scalar.to_bytes()
+}
+
+/// The generators for Pedersen commitments `value * G + blinding * H` within one domain.
+///
+/// Both generators are hashed from the domain label, so nobody knows the discrete log of one with
+/// respect to the other, and a commitment cannot be opened to two different values.
+#[derive(Clone, Copy, Debug)]
+pub struct PedersenGenerators {
+    g: RistrettoPoint,
+    h: RistrettoPoint,
+}
+
+impl PedersenGenerators {
+    pub fn new(domain: &[u8]) -> Self {
+        Self {
+            g: hash_to_point(domain, b"Pedersen G"),
+            h: hash_to_point(domain, b"Pedersen H"),
+        }
+    }
+
+    pub fn commit(&self, value: &Scalar, blinding: &Scalar) -> RistrettoPoint {
+        value * self.g + blinding * self.h
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashing_is_domain_separated() {
+        let point = hash_to_point(b"domain A", b"input");
+        assert_eq!(point, hash_to_point(b"domain A", b"input"));
+        assert_ne!(point, hash_to_point(b"domain B", b"input"));
+        assert_ne!(point, hash_to_point(b"domain A", b"other input"));
+
+        let scalar = hash_to_scalar(b"domain A", b"input");
+        assert_eq!(scalar, hash_to_scalar(b"domain A", b"input"));
+        assert_ne!(scalar, hash_to_scalar(b"domain B", b"input"));
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_serialization() {
+        let point = hash_to_point(b"domain", b"input");
+        assert_eq!(point_from_bytes(&point_to_bytes(&point)).unwrap(), point);
+        assert!(point_from_bytes(&[0xff; POINT_LEN]).is_err());
+        assert!(point_from_bytes(&point_to_bytes(&point)[1..]).is_err());
+
+        let scalar = hash_to_scalar(b"domain", b"input");
+        assert_eq!(
+            scalar_from_bytes(&scalar_to_bytes(&scalar)).unwrap(),
+            scalar
+        );
+        assert!(scalar_from_bytes(&[0xff; SCALAR_LEN]).is_err());
+        assert!(scalar_from_bytes(&[0; 31]).is_err());
+    }
+
+    #[test]
+    fn test_pedersen_commitments_are_homomorphic() {
+        let generators = PedersenGenerators::new(b"domain");
+        let (a, b) = (Scalar::from(3u64), Scalar::from(4u64));
+        let (r, s) = (
+            hash_to_scalar(b"domain", b"r"),
+            hash_to_scalar(b"domain", b"s"),
+        );
+
+        assert_eq!(
+            generators.commit(&a, &r) + generators.commit(&b, &s),
+            generators.commit(&(a + b), &(r + s))
+        );
+        assert_ne!(generators.commit(&a, &r), generators.commit(&b, &r));
+        assert_ne!(
+            generators.commit(&a, &r),
+            PedersenGenerators::new(b"other domain").commit(&a, &r)
+        );
+    }
+}
@@ -81,10 +81,155 @@ pub fn ratchet_forward_result(c: &mut Criterion) -> Result<(), SignalProtocolErr
     Ok(())
 }
 
+pub fn session_skip_result(c: &mut Criterion) -> Result<(), SignalProtocolError> {
+    let mut group = c.benchmark_group("session skip");
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+    group.warm_up_time(core::time::Duration::from_millis(100));
+
+    let alice_address = ProtocolAddress::new("+14159999999".to_owned(), 1);
+    let bob_address = ProtocolAddress::new("+14158888888".to_owned(), 1);
+
+    // Each decrypt below has to derive and store a message key for every message it skips over.
+    for skipped in [100, 1000].iter() {
+        let skipped = *skipped;
+
+        // Start from a fresh session for each size, so earlier sizes don't add to the gap.
+        let (alice_session_record, bob_session_record) = support::initialize_sessions_v3()?;
+
+        let mut alice_store = support::test_in_memory_protocol_store()?;
+        let mut bob_store = support::test_in_memory_protocol_store()?;
+
+        block_on(alice_store.store_session(&bob_address, &alice_session_record, None))?;
+        block_on(bob_store.store_session(&alice_address, &bob_session_record, None))?;
+
+        for i in 0..skipped {
+            block_on(support::encrypt(
+                &mut alice_store,
+                &bob_address,
+                &format!("lost in transit {}", i),
+            ))?;
+        }
+
+        let alice_ciphertext = block_on(support::encrypt(
+            &mut alice_store,
+            &bob_address,
+            "did you get all that?",
+        ))?;
+
+        group.bench_function(format!("skip {}", skipped), |b| {
+            b.iter(|| {
+                let mut bob_store = bob_store.clone();
+                block_on(support::decrypt(
+                    &mut bob_store,
+                    &alice_address,
+                    &alice_ciphertext,
+                ))
+                .expect("ok");
+            })
+        });
+    }
+
+    Ok(())
+}
+
+pub fn group_decrypt_batched_result(c: &mut Criterion) -> Result<(), SignalProtocolError> {
+    let mut group = c.benchmark_group("group decrypt");
+
+    let mut csprng = rand::rngs::OsRng;
+
+    let sender_address = ProtocolAddress::new("+14159999111".to_owned(), 1);
+    let group_sender =
+        SenderKeyName::new("summer camp planning committee".to_owned(), sender_address)?;
+
+    let mut alice_store = support::test_in_memory_protocol_store()?;
+    let mut bob_store = support::test_in_memory_protocol_store()?;
+
+    let sent_distribution_message = block_on(create_sender_key_distribution_message(
+        &group_sender,
+        &mut alice_store,
+        &mut csprng,
+        None,
+    ))?;
+
+    let recv_distribution_message =
+        SenderKeyDistributionMessage::try_from(sent_distribution_message.serialized())?;
+
+    block_on(process_sender_key_distribution_message(
+        &group_sender,
+        &recv_distribution_message,
+        &mut bob_store,
+        None,
+    ))?;
+
+    let ciphertexts = (0..100)
+        .map(|i| {
+            block_on(group_encrypt(
+                &mut alice_store,
+                &group_sender,
+                format!("caption {}", i).as_bytes(),
+                &mut csprng,
+                None,
+            ))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for count in [10, 100].iter() {
+        let ciphertexts: Vec<&[u8]> = ciphertexts[..*count].iter().map(|c| &c[..]).collect();
+
+        group.bench_function(format!("individually {}", count), |b| {
+            b.iter(|| {
+                let mut bob_store = bob_store.clone();
+                for ciphertext in &ciphertexts {
+                    block_on(group_decrypt(
+                        ciphertext,
+                        &mut bob_store,
+                        &group_sender,
+                        None,
+                    ))
+                    .expect("ok");
+                }
+            })
+        });
+
+        group.bench_function(format!("batched {}", count), |b| {
+            b.iter(|| {
+                let mut bob_store = bob_store.clone();
+                let results = block_on(group_decrypt_batch(
+                    &ciphertexts,
+                    &mut bob_store,
+                    &group_sender,
+                    &mut csprng,
+                    None,
+                ))
+                .expect("ok");
+                assert!(results.iter().all(Result::is_ok));
+            })
+        });
+    }
+
+    group.finish();
+
+    Ok(())
+}
+
 pub fn ratchet_forward(mut c: &mut Criterion) {
     ratchet_forward_result(&mut c).expect("success");
 }
 
-criterion_group!(ratchet, ratchet_forward);
+pub fn session_skip(mut c: &mut Criterion) {
+    session_skip_result(&mut c).expect("success");
+}
+
+pub fn group_decrypt_batched(mut c: &mut Criterion) {
+    group_decrypt_batched_result(&mut c).expect("success");
+}
+
+criterion_group!(
+    ratchet,
+    ratchet_forward,
+    session_skip,
+    group_decrypt_batched
+);
 
 criterion_main!(ratchet);
@@ -0,0 +1,309 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Benchmarks for whole client workloads, as opposed to individual operations.
+//!
+//! To track results across releases, save a named baseline and compare later runs against it:
+//!
+//! ```text
+//! cargo bench --bench workloads -- --save-baseline v0.1.0
+//! cargo bench --bench workloads -- --baseline v0.1.0
+//! ```
+//!
+//! For machine-readable output, pass `--output-format bencher`, which prints one
+//! `test <name> ... bench: <ns> ns/iter (+/- <ns>)` line per benchmark. Criterion also writes
+//! the full statistics for each benchmark as JSON under `target/criterion`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, SamplingMode};
+use futures::executor::block_on;
+use libsignal_protocol::*;
+use rand::rngs::OsRng;
+
+#[path = "../tests/support/mod.rs"]
+mod support;
+
+const GROUP_SIZE: usize = 1000;
+const BACKLOG_SIZES: &[usize] = &[100, 1000];
+
+/// Sending the first message to a new contact: fetching their bundle, processing it, and
+/// encrypting a PreKeySignalMessage.
+pub fn one_to_one_result(c: &mut Criterion) -> Result<(), SignalProtocolError> {
+    let mut csprng = OsRng;
+    let alice_address = ProtocolAddress::new("+14159999999".to_owned(), 1);
+    let bob_address = ProtocolAddress::new("+14158888888".to_owned(), 1);
+
+    let alice_store = support::test_in_memory_protocol_store()?;
+    let mut bob_store = support::test_in_memory_protocol_store()?;
+    let bob_bundle = block_on(support::create_pre_key_bundle(&mut bob_store, &mut csprng))?;
+
+    let mut group = c.benchmark_group("one-to-one");
+
+    group.bench_function("process bundle and send", |b| {
+        b.iter(|| {
+            let mut alice_store = alice_store.clone();
+            block_on(process_prekey_bundle(
+                &bob_address,
+                &mut alice_store.session_store,
+                &mut alice_store.identity_store,
+                &bob_bundle,
+                &mut csprng,
+                None,
+            ))
+            .expect("success");
+            block_on(support::encrypt(
+                &mut alice_store,
+                &bob_address,
+                "a short message",
+            ))
+            .expect("success");
+        })
+    });
+
+    let mut alice_store = alice_store;
+    block_on(process_prekey_bundle(
+        &bob_address,
+        &mut alice_store.session_store,
+        &mut alice_store.identity_store,
+        &bob_bundle,
+        &mut csprng,
+        None,
+    ))?;
+    let first_message = block_on(support::encrypt(
+        &mut alice_store,
+        &bob_address,
+        "a short message",
+    ))?;
+
+    group.bench_function("receive first message", |b| {
+        b.iter(|| {
+            let mut bob_store = bob_store.clone();
+            block_on(support::decrypt(
+                &mut bob_store,
+                &alice_address,
+                &first_message,
+            ))
+            .expect("success");
+        })
+    });
+
+    group.finish();
+    Ok(())
+}
+
+/// Sealed-sender encryption and decryption over an established session.
+pub fn sealed_sender_result(c: &mut Criterion) -> Result<(), SignalProtocolError> {
+    let mut rng = OsRng;
+    let alice_uuid = "9d0652a3-dcc3-4d11-975f-74d61598733f".to_owned();
+    let bob_uuid = "796abedb-ca4e-4f18-8803-1fde5b921f9f".to_owned();
+    let bob_address = ProtocolAddress::new(bob_uuid.clone(), 1);
+
+    let mut alice_store = support::test_in_memory_protocol_store()?;
+    let mut bob_store = support::test_in_memory_protocol_store()?;
+    let bob_bundle = block_on(support::create_pre_key_bundle(&mut bob_store, &mut rng))?;
+    block_on(process_prekey_bundle(
+        &bob_address,
+        &mut alice_store.session_store,
+        &mut alice_store.identity_store,
+        &bob_bundle,
+        &mut rng,
+        None,
+    ))?;
+
+    let trust_root = KeyPair::generate(&mut rng);
+    let server_key = KeyPair::generate(&mut rng);
+    let server_cert =
+        ServerCertificate::new(1, server_key.public_key, &trust_root.private_key, &mut rng)?;
+    let expires = 1605722925;
+    let sender_cert = SenderCertificate::new(
+        alice_uuid,
+        None,
+        *block_on(alice_store.get_identity_key_pair(None))?.public_key(),
+        1,
+        expires,
+        server_cert,
+        &server_key.private_key,
+        &mut rng,
+    )?;
+
+    let mut group = c.benchmark_group("sealed sender");
+
+    group.bench_function("encrypt", |b| {
+        b.iter(|| {
+            block_on(sealed_sender_encrypt(
+                &bob_address,
+                &sender_cert,
+                b"a short message",
+                &mut alice_store.session_store,
+                &mut alice_store.identity_store,
+                None,
+                &mut rng,
+            ))
+            .expect("success");
+        })
+    });
+
+    let ciphertext = block_on(sealed_sender_encrypt(
+        &bob_address,
+        &sender_cert,
+        b"a short message",
+        &mut alice_store.session_store,
+        &mut alice_store.identity_store,
+        None,
+        &mut rng,
+    ))?;
+
+    group.bench_function("decrypt", |b| {
+        b.iter(|| {
+            let mut bob_store = bob_store.clone();
+            block_on(sealed_sender_decrypt(
+                &ciphertext,
+                &trust_root.public_key,
+                expires - 1,
+                None,
+                bob_uuid.clone(),
+                1,
+                &mut bob_store.identity_store,
+                &mut bob_store.session_store,
+                &mut bob_store.pre_key_store,
+                &mut bob_store.signed_pre_key_store,
+                None,
+                &mut rng,
+            ))
+            .expect("success");
+        })
+    });
+
+    group.finish();
+    Ok(())
+}
+
+/// Starting a sender key session in a large group: encrypting the distribution message to every
+/// member over their 1:1 sessions, then the per-message group encryption.
+pub fn sender_key_fan_out_result(c: &mut Criterion) -> Result<(), SignalProtocolError> {
+    let mut csprng = OsRng;
+    let sender_address = ProtocolAddress::new("+14159999111".to_owned(), 1);
+    let group_sender = SenderKeyName::new("a large group".to_owned(), sender_address)?;
+
+    let mut alice_store = support::test_in_memory_protocol_store()?;
+    let mut members = Vec::with_capacity(GROUP_SIZE);
+    for i in 0..GROUP_SIZE {
+        let member_address = ProtocolAddress::new(format!("+1415555{:04}", i), 1);
+        let (alice_session, _) = support::initialize_sessions_v3()?;
+        block_on(alice_store.store_session(&member_address, &alice_session, None))?;
+        members.push(member_address);
+    }
+
+    let distribution_message = block_on(create_sender_key_distribution_message(
+        &group_sender,
+        &mut alice_store,
+        &mut csprng,
+        None,
+    ))?;
+
+    let mut group = c.benchmark_group("sender key fan-out");
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+
+    group.bench_function(BenchmarkId::new("distribute to members", GROUP_SIZE), |b| {
+        b.iter(|| {
+            for member in &members {
+                block_on(message_encrypt(
+                    distribution_message.serialized(),
+                    member,
+                    &mut alice_store.session_store,
+                    &mut alice_store.identity_store,
+                    None,
+                ))
+                .expect("success");
+            }
+        })
+    });
+
+    group.bench_function("group encrypt", |b| {
+        b.iter(|| {
+            block_on(group_encrypt(
+                &mut alice_store,
+                &group_sender,
+                b"a short message",
+                &mut csprng,
+                None,
+            ))
+            .expect("success");
+        })
+    });
+
+    group.finish();
+    Ok(())
+}
+
+/// Coming back online to a backlog of messages from a single contact.
+pub fn backlog_decrypt_result(c: &mut Criterion) -> Result<(), SignalProtocolError> {
+    let (alice_session_record, bob_session_record) = support::initialize_sessions_v3()?;
+
+    let alice_address = ProtocolAddress::new("+14159999999".to_owned(), 1);
+    let bob_address = ProtocolAddress::new("+14158888888".to_owned(), 1);
+
+    let mut alice_store = support::test_in_memory_protocol_store()?;
+    let mut bob_store = support::test_in_memory_protocol_store()?;
+
+    block_on(alice_store.store_session(&bob_address, &alice_session_record, None))?;
+    block_on(bob_store.store_session(&alice_address, &bob_session_record, None))?;
+
+    let mut group = c.benchmark_group("backlog decrypt");
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+
+    for &size in BACKLOG_SIZES {
+        let mut alice_store = alice_store.clone();
+        let backlog = (0..size)
+            .map(|i| {
+                block_on(support::encrypt(
+                    &mut alice_store,
+                    &bob_address,
+                    &format!("message {}", i),
+                ))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        group.bench_with_input(BenchmarkId::from_parameter(size), &backlog, |b, backlog| {
+            b.iter(|| {
+                let mut bob_store = bob_store.clone();
+                for message in backlog {
+                    block_on(support::decrypt(&mut bob_store, &alice_address, message))
+                        .expect("success");
+                }
+            })
+        });
+    }
+
+    group.finish();
+    Ok(())
+}
+
+pub fn one_to_one(mut c: &mut Criterion) {
+    one_to_one_result(&mut c).expect("success");
+}
+
+pub fn sealed_sender(mut c: &mut Criterion) {
+    sealed_sender_result(&mut c).expect("success");
+}
+
+pub fn sender_key_fan_out(mut c: &mut Criterion) {
+    sender_key_fan_out_result(&mut c).expect("success");
+}
+
+pub fn backlog_decrypt(mut c: &mut Criterion) {
+    backlog_decrypt_result(&mut c).expect("success");
+}
+
+criterion_group!(
+    workloads,
+    one_to_one,
+    sealed_sender,
+    sender_key_fan_out,
+    backlog_decrypt
+);
+
+criterion_main!(workloads);
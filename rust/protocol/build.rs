@@ -5,7 +5,9 @@
 
 fn main() {
     let protos = [
+        "src/proto/content.proto",
         "src/proto/fingerprint.proto",
+        "src/proto/provisioning.proto",
         "src/proto/storage.proto",
         "src/proto/sealed_sender.proto",
         "src/proto/wire.proto",
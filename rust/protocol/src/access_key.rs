@@ -0,0 +1,100 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Values derived from a profile key that let others reach its owner without knowing who they are.
+
+use crate::{Result, SignalProtocolError};
+
+use alloc::format;
+
+use aes::cipher::generic_array::GenericArray;
+use aes::{Aes256, BlockCipher, NewBlockCipher};
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha256;
+
+pub const PROFILE_KEY_SIZE: usize = 32;
+pub const ACCESS_KEY_SIZE: usize = 16;
+pub const BLINDED_SERVICE_ID_SIZE: usize = 32;
+
+const BLINDED_SERVICE_ID_LABEL: &[u8] = b"Signal_BlindedServiceId_v1";
+
+fn check_profile_key(profile_key: &[u8]) -> Result<()> {
+    if profile_key.len() != PROFILE_KEY_SIZE {
+        return Err(SignalProtocolError::InvalidArgument(format!(
+            "profile key must be {} bytes (got {})",
+            PROFILE_KEY_SIZE,
+            profile_key.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Derives the unidentified-access key that a sender presents to deliver sealed sender messages.
+///
+/// Clients have always computed this as the first 16 bytes of AES-256-GCM encrypting 16 zero bytes
+/// under the profile key with an all-zero nonce. That is exactly the GCM keystream block for
+/// counter 2, so it is computed here as a single AES block.
+pub fn derive_access_key(profile_key: &[u8]) -> Result<[u8; ACCESS_KEY_SIZE]> {
+    check_profile_key(profile_key)?;
+    let aes = Aes256::new(GenericArray::from_slice(profile_key));
+    let mut block = [0u8; ACCESS_KEY_SIZE];
+    block[ACCESS_KEY_SIZE - 1] = 2;
+    aes.encrypt_block(GenericArray::from_mut_slice(&mut block));
+    Ok(block)
+}
+
+/// Blinds `service_id` under a profile key, so it can be looked up by those who know the key
+/// without revealing it to anyone else.
+///
+/// The result is HMAC-SHA256 over a fixed label and the service ID, keyed with the profile key.
+pub fn blind_service_id(
+    profile_key: &[u8],
+    service_id: &str,
+) -> Result<[u8; BLINDED_SERVICE_ID_SIZE]> {
+    check_profile_key(profile_key)?;
+    let mut mac =
+        Hmac::<Sha256>::new_varkey(profile_key).expect("HMAC-SHA256 should accept any size key");
+    mac.update(BLINDED_SERVICE_ID_LABEL);
+    mac.update(service_id.as_bytes());
+    Ok(mac.finalize().into_bytes().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile_key() -> [u8; PROFILE_KEY_SIZE] {
+        let mut key = [0u8; PROFILE_KEY_SIZE];
+        for (i, b) in key.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        key
+    }
+
+    #[test]
+    fn test_derive_access_key() -> Result<()> {
+        assert_eq!(
+            hex::encode(derive_access_key(&profile_key())?),
+            "0ebcb5deb52c83bd08a8a935182c9199"
+        );
+        assert!(derive_access_key(&[0; 16]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_blind_service_id() -> Result<()> {
+        let service_id = "9d0652a3-dcc3-4d11-975f-74d61598733f";
+        assert_eq!(
+            hex::encode(blind_service_id(&profile_key(), service_id)?),
+            "81be8025bf77f56435df7ab967647c5e0ce8c92232483d053ac1e0fc3fb6e81b"
+        );
+        assert_ne!(
+            blind_service_id(&[1; PROFILE_KEY_SIZE], service_id)?,
+            blind_service_id(&profile_key(), service_id)?
+        );
+        assert!(blind_service_id(&[], service_id).is_err());
+        Ok(())
+    }
+}
@@ -3,9 +3,16 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
-use std::fmt;
+use crate::{Result, SignalProtocolError};
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ProtocolAddress {
     name: String,
     device_id: u32,
@@ -30,3 +37,79 @@ impl fmt::Display for ProtocolAddress {
         write!(f, "{}.{}", self.name, self.device_id)
     }
 }
+
+/// Encodes `addresses` in the compact form accepted by the multi-recipient bridge functions.
+///
+/// Each address is written as a one-byte length, the UTF-8 service ID (the address name), and
+/// the device ID as a big-endian `u32`. Entries are simply concatenated, so clients can build a
+/// list without going through this library at all.
+pub fn serialize_service_id_list(addresses: &[ProtocolAddress]) -> Result<Vec<u8>> {
+    let total_len = addresses
+        .iter()
+        .map(|address| 1 + address.name.len() + 4)
+        .sum();
+    let mut result = Vec::with_capacity(total_len);
+    for address in addresses {
+        let name_len = u8::try_from(address.name.len()).map_err(|_| {
+            SignalProtocolError::InvalidArgument(format!(
+                "service ID for {} is longer than 255 bytes",
+                address
+            ))
+        })?;
+        result.push(name_len);
+        result.extend_from_slice(address.name.as_bytes());
+        result.extend_from_slice(&address.device_id.to_be_bytes());
+    }
+    Ok(result)
+}
+
+/// Decodes a list produced by [`serialize_service_id_list`].
+pub fn parse_service_id_list(mut bytes: &[u8]) -> Result<Vec<ProtocolAddress>> {
+    let mut result = Vec::new();
+    while let Some((&name_len, rest)) = bytes.split_first() {
+        let name_len = usize::from(name_len);
+        if rest.len() < name_len + 4 {
+            return Err(SignalProtocolError::InvalidArgument(
+                "service ID list is truncated".into(),
+            ));
+        }
+        let (name, rest) = rest.split_at(name_len);
+        let (device_id, rest) = rest.split_at(4);
+        let name = core::str::from_utf8(name).map_err(|_| {
+            SignalProtocolError::InvalidArgument("service ID is not valid UTF-8".into())
+        })?;
+        let mut device_id_bytes = [0; 4];
+        device_id_bytes.copy_from_slice(device_id);
+        result.push(ProtocolAddress::new(
+            name.into(),
+            u32::from_be_bytes(device_id_bytes),
+        ));
+        bytes = rest;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_id_list_round_trip() -> Result<()> {
+        let addresses = vec![
+            ProtocolAddress::new("9d0652a3-dcc3-4d11-975f-74d61598733f".into(), 1),
+            ProtocolAddress::new("+14151111111".into(), 0x0102_0304),
+            ProtocolAddress::new("".into(), 7),
+        ];
+        let serialized = serialize_service_id_list(&addresses)?;
+        assert_eq!(&serialized[37..54], b"\x00\x00\x00\x01\x0c+14151111111");
+        assert_eq!(parse_service_id_list(&serialized)?, addresses);
+
+        assert!(parse_service_id_list(&[])?.is_empty());
+        assert!(parse_service_id_list(&serialized[..serialized.len() - 1]).is_err());
+        assert!(parse_service_id_list(&[1, 0xff, 0, 0, 0, 1]).is_err());
+
+        let too_long = ProtocolAddress::new("x".repeat(256), 1);
+        assert!(serialize_service_id_list(&[too_long]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! An optional hook for keeping an audit trail of identity changes and session resets.
+//!
+//! Once an [AuditHook] is installed with [set_audit_hook], the library reports an [AuditEvent]
+//! whenever it
+//!
+//! - saves an identity key that replaces a different one for the same address,
+//! - archives the current session with an address to make way for a new one,
+//! - creates a new sender key for sending to a group, or
+//! - fast-forwards a sender key chain with [group_fast_forward](crate::group_fast_forward).
+//!
+//! Events are reported synchronously, from whichever thread made the change, before the operation
+//! returns. Nothing is reported if the operation fails before making the change. Without the `std`
+//! feature there is no way to install a hook, and no events are reported.
+
+use crate::{Context, IdentityKey, IdentityKeyStore, ProtocolAddress, Result, SenderKeyName};
+
+/// A change worth recording in an audit trail; see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditEvent {
+    /// A new identity key was saved for `address`, replacing a different one.
+    IdentityChanged { address: ProtocolAddress },
+    /// The current session with `address` was archived because a new session was set up.
+    SessionArchived { address: ProtocolAddress },
+    /// A new sender key was created for sending to the group in `sender_key_name`.
+    SenderKeyCreated {
+        sender_key_name: SenderKeyName,
+        key_id: u32,
+    },
+    /// The sender key chain `key_id` from the sender in `sender_key_name` was advanced from
+    /// `from_iteration` to `to_iteration`, skipping the messages in between.
+    SenderKeyFastForwarded {
+        sender_key_name: SenderKeyName,
+        key_id: u32,
+        from_iteration: u32,
+        to_iteration: u32,
+    },
+}
+
+/// Receives [AuditEvent]s once installed with [set_audit_hook].
+pub trait AuditHook: Send + Sync {
+    /// Called for each event.
+    ///
+    /// This runs in the middle of the operation that caused the event, so it should be quick, and
+    /// must not call back into the library.
+    fn record(&self, event: &AuditEvent);
+}
+
+#[cfg(feature = "std")]
+mod registry {
+    use super::{AuditEvent, AuditHook};
+    use once_cell::sync::Lazy;
+    use std::sync::RwLock;
+
+    static AUDIT_HOOK: Lazy<RwLock<Option<Box<dyn AuditHook>>>> = Lazy::new(Default::default);
+
+    /// Installs `hook`, replacing any hook installed earlier, or removes the current hook if
+    /// `hook` is `None`.
+    ///
+    /// Once this returns, the previous hook will not be called again.
+    pub fn set_audit_hook(hook: Option<Box<dyn AuditHook>>) {
+        *AUDIT_HOOK.write().expect("not poisoned") = hook;
+    }
+
+    /// Reports the event built by `event` to the installed hook, if there is one.
+    pub(crate) fn audit(event: impl FnOnce() -> AuditEvent) {
+        if let Some(hook) = AUDIT_HOOK.read().expect("not poisoned").as_ref() {
+            hook.record(&event());
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+pub use registry::set_audit_hook;
+
+#[cfg(feature = "std")]
+pub(crate) use registry::audit;
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn audit(_event: impl FnOnce() -> AuditEvent) {}
+
+/// Saves `identity` for `address`, reporting [AuditEvent::IdentityChanged] if the store says it
+/// replaced a different identity.
+pub(crate) async fn save_identity(
+    identity_store: &mut dyn IdentityKeyStore,
+    address: &ProtocolAddress,
+    identity: &IdentityKey,
+    ctx: Context,
+) -> Result<bool> {
+    let replaced = identity_store.save_identity(address, identity, ctx).await?;
+    if replaced {
+        audit(|| AuditEvent::IdentityChanged {
+            address: address.clone(),
+        });
+    }
+    Ok(replaced)
+}
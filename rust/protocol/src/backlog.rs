@@ -0,0 +1,683 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Parallel decryption of an offline backlog, enabled by the `backlog` feature.
+//!
+//! Messages from different senders never touch the same session, so once everything a sender's
+//! messages could need has been read from the stores, each sender's messages can be decrypted
+//! independently. [BacklogDecryptor::decrypt] works in three steps:
+//!
+//! 1. On the calling task, group the messages by sender, then load each sender's session, ask the
+//!    identity store about every identity key involved, and fetch any pre-keys the messages refer
+//!    to.
+//! 2. On the thread pool, decrypt each sender's messages in their original order against a private
+//!    copy of that state.
+//! 3. Back on the calling task, save identities, store updated sessions, and remove used one-time
+//!    pre-keys.
+//!
+//! The stores are only ever used from the calling task, so they don't need to be thread-safe.
+//! Trust decisions are made against the identity store as it was before the backlog was
+//! processed.
+
+use crate::audit;
+use crate::state::{PreKeyId, SignedPreKeyId};
+use crate::{
+    message_decrypt, CiphertextMessage, Context, Direction, IdentityKey, IdentityKeyPair,
+    IdentityKeyStore, PreKeyRecord, PreKeyStore, ProtocolAddress, Result, SessionRecord,
+    SessionStore, SignalProtocolError, SignedPreKeyRecord, SignedPreKeyStore,
+};
+
+use async_trait::async_trait;
+use futures::channel::oneshot;
+use futures::executor::block_on;
+use rand::rngs::StdRng;
+use rand::{CryptoRng, Rng, SeedableRng};
+use rayon::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Decrypts large numbers of 1:1 messages, handling different senders in parallel.
+pub struct BacklogDecryptor {
+    pool: rayon::ThreadPool,
+}
+
+impl BacklogDecryptor {
+    /// Creates a decryptor with a pool of `threads` threads, or one per CPU if `threads` is 0.
+    pub fn new(threads: usize) -> Result<Self> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|i| format!("libsignal-backlog-{}", i))
+            .panic_handler(|_| log::error!("panic during backlog decryption"))
+            .build()
+            .map_err(|e| {
+                SignalProtocolError::InvalidState("BacklogDecryptor::new", e.to_string())
+            })?;
+        Ok(Self { pool })
+    }
+
+    /// Decrypts `ciphertexts[i]`, which was sent by `remote_addresses[i]`, for every `i`.
+    ///
+    /// Messages from the same address are decrypted in the order given. Each message gets its own
+    /// result, in the same order as `ciphertexts`, and a message that fails to decrypt leaves its
+    /// sender's session as it was, just like [message_decrypt]. The outer error is only used for
+    /// mismatched arguments and for failures of the stores themselves.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn decrypt<R: Rng + CryptoRng>(
+        &self,
+        ciphertexts: &[CiphertextMessage],
+        remote_addresses: &[ProtocolAddress],
+        session_store: &mut dyn SessionStore,
+        identity_store: &mut dyn IdentityKeyStore,
+        pre_key_store: &mut dyn PreKeyStore,
+        signed_pre_key_store: &mut dyn SignedPreKeyStore,
+        csprng: &mut R,
+        ctx: Context,
+    ) -> Result<Vec<Result<Vec<u8>>>> {
+        if ciphertexts.len() != remote_addresses.len() {
+            return Err(SignalProtocolError::InvalidArgument(format!(
+                "{} ciphertexts but {} addresses",
+                ciphertexts.len(),
+                remote_addresses.len()
+            )));
+        }
+
+        let mut results: Vec<Result<Vec<u8>>> = (0..ciphertexts.len())
+            .map(|_| {
+                Err(SignalProtocolError::InternalError(
+                    "backlog message was not decrypted",
+                ))
+            })
+            .collect();
+
+        let mut partitions: Vec<Partition> = Vec::new();
+        let mut partition_indexes: BTreeMap<&ProtocolAddress, usize> = BTreeMap::new();
+        for (i, (ciphertext, remote_address)) in
+            ciphertexts.iter().zip(remote_addresses).enumerate()
+        {
+            let ciphertext = match ciphertext {
+                CiphertextMessage::SignalMessage(m) => CiphertextMessage::SignalMessage(m.clone()),
+                CiphertextMessage::PreKeySignalMessage(m) => {
+                    CiphertextMessage::PreKeySignalMessage(m.clone())
+                }
+                _ => {
+                    results[i] = Err(SignalProtocolError::InvalidArgument(
+                        "BacklogDecryptor cannot decrypt this message type".to_owned(),
+                    ));
+                    continue;
+                }
+            };
+            let index = *partition_indexes.entry(remote_address).or_insert_with(|| {
+                partitions.push(Partition::new(remote_address.clone()));
+                partitions.len() - 1
+            });
+            partitions[index].messages.push((i, ciphertext));
+        }
+
+        let mut fetcher = Fetcher {
+            identity_store,
+            pre_key_store,
+            signed_pre_key_store,
+            local_identity: None,
+            signed_pre_keys: BTreeMap::new(),
+            claimed_pre_keys: BTreeSet::new(),
+            ctx,
+        };
+        for partition in &mut partitions {
+            partition.sessions.record = session_store.load_session(&partition.address, ctx).await?;
+            fetcher.prepare(partition).await?;
+            partition.rng = Some(StdRng::from_rng(&mut *csprng).map_err(|e| {
+                SignalProtocolError::InvalidState("BacklogDecryptor::decrypt", e.to_string())
+            })?);
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        self.pool.spawn(move || {
+            let decrypted: Vec<Partition> =
+                partitions.into_par_iter().map(Partition::decrypt).collect();
+            // If the caller stopped waiting, there's nobody left to tell.
+            let _ = sender.send(decrypted);
+        });
+        let partitions = receiver.await.map_err(|_| {
+            SignalProtocolError::InternalError("backlog decryption did not complete")
+        })?;
+
+        for partition in partitions {
+            let mut last_saved: Option<IdentityKey> = None;
+            for identity in &partition.identities.saved {
+                if last_saved.as_ref() != Some(identity) {
+                    audit::save_identity(fetcher.identity_store, &partition.address, identity, ctx)
+                        .await?;
+                    last_saved = Some(*identity);
+                }
+            }
+            if partition.sessions.updated {
+                if let Some(record) = &partition.sessions.record {
+                    session_store
+                        .store_session(&partition.address, record, ctx)
+                        .await?;
+                }
+            }
+            for pre_key_id in &partition.pre_keys.removed {
+                fetcher
+                    .pre_key_store
+                    .remove_pre_key(*pre_key_id, ctx)
+                    .await?;
+            }
+            for (i, result) in partition.results {
+                results[i] = result;
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+/// Reads what each partition needs from the real stores.
+struct Fetcher<'a> {
+    identity_store: &'a mut dyn IdentityKeyStore,
+    pre_key_store: &'a mut dyn PreKeyStore,
+    signed_pre_key_store: &'a mut dyn SignedPreKeyStore,
+    local_identity: Option<(IdentityKeyPair, u32)>,
+    signed_pre_keys: BTreeMap<SignedPreKeyId, Option<SignedPreKeyRecord>>,
+    /// One-time pre-keys that some sender has already referred to. Only the first sender to refer
+    /// to a pre-key gets to use it.
+    claimed_pre_keys: BTreeSet<PreKeyId>,
+    ctx: Context,
+}
+
+impl Fetcher<'_> {
+    async fn prepare(&mut self, partition: &mut Partition) -> Result<()> {
+        let Partition {
+            address,
+            messages,
+            sessions,
+            identities,
+            pre_keys,
+            signed_pre_keys,
+            ..
+        } = partition;
+
+        let mut identities_to_check = Vec::new();
+        if let Some(record) = &sessions.record {
+            if record.has_current_session_state() {
+                identities_to_check.extend(record.session_state()?.remote_identity_key()?);
+            }
+            for state in record.previous_session_states()? {
+                identities_to_check.extend(state?.remote_identity_key()?);
+            }
+        }
+
+        for (_, ciphertext) in messages.iter() {
+            if let CiphertextMessage::PreKeySignalMessage(message) = ciphertext {
+                identities_to_check.push(*message.identity_key());
+                if identities.local.is_none() {
+                    identities.local = Some(self.local_identity().await?);
+                }
+
+                let signed_pre_key_id = message.signed_pre_key_id();
+                if let Some(record) = self.signed_pre_key(signed_pre_key_id).await? {
+                    signed_pre_keys.records.insert(signed_pre_key_id, record);
+                }
+
+                if let Some(pre_key_id) = message.pre_key_id() {
+                    if let Some(record) = self.claim_pre_key(pre_key_id).await? {
+                        pre_keys.records.insert(pre_key_id, record);
+                    }
+                }
+            }
+        }
+
+        identities_to_check.sort();
+        identities_to_check.dedup();
+        for identity in identities_to_check {
+            let trusted = self
+                .identity_store
+                .is_trusted_identity(address, &identity, Direction::Receiving, self.ctx)
+                .await?;
+            identities.trusted.push((identity, trusted));
+        }
+        Ok(())
+    }
+
+    async fn local_identity(&mut self) -> Result<(IdentityKeyPair, u32)> {
+        if let Some(local_identity) = self.local_identity {
+            return Ok(local_identity);
+        }
+        let local_identity = (
+            self.identity_store.get_identity_key_pair(self.ctx).await?,
+            self.identity_store
+                .get_local_registration_id(self.ctx)
+                .await?,
+        );
+        self.local_identity = Some(local_identity);
+        Ok(local_identity)
+    }
+
+    async fn signed_pre_key(&mut self, id: SignedPreKeyId) -> Result<Option<SignedPreKeyRecord>> {
+        if let Some(record) = self.signed_pre_keys.get(&id) {
+            return Ok(record.clone());
+        }
+        let record = match self
+            .signed_pre_key_store
+            .get_signed_pre_key(id, self.ctx)
+            .await
+        {
+            Ok(record) => Some(record),
+            Err(SignalProtocolError::InvalidSignedPreKeyId) => None,
+            Err(e) => return Err(e),
+        };
+        self.signed_pre_keys.insert(id, record.clone());
+        Ok(record)
+    }
+
+    /// Fetches a one-time pre-key the first time any sender refers to it.
+    async fn claim_pre_key(&mut self, id: PreKeyId) -> Result<Option<PreKeyRecord>> {
+        if !self.claimed_pre_keys.insert(id) {
+            return Ok(None);
+        }
+        match self.pre_key_store.get_pre_key(id, self.ctx).await {
+            Ok(record) => Ok(Some(record)),
+            Err(SignalProtocolError::InvalidPreKeyId) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// One sender's messages, along with everything needed to decrypt them.
+struct Partition {
+    address: ProtocolAddress,
+    messages: Vec<(usize, CiphertextMessage)>,
+    results: Vec<(usize, Result<Vec<u8>>)>,
+    sessions: PartitionSessionStore,
+    identities: PartitionIdentityStore,
+    pre_keys: PartitionPreKeyStore,
+    signed_pre_keys: PartitionSignedPreKeyStore,
+    rng: Option<StdRng>,
+}
+
+impl Partition {
+    fn new(address: ProtocolAddress) -> Self {
+        Self {
+            address,
+            messages: Vec::new(),
+            results: Vec::new(),
+            sessions: PartitionSessionStore {
+                record: None,
+                updated: false,
+            },
+            identities: PartitionIdentityStore {
+                local: None,
+                trusted: Vec::new(),
+                saved: Vec::new(),
+            },
+            pre_keys: PartitionPreKeyStore {
+                records: BTreeMap::new(),
+                removed: Vec::new(),
+            },
+            signed_pre_keys: PartitionSignedPreKeyStore {
+                records: BTreeMap::new(),
+            },
+            rng: None,
+        }
+    }
+
+    fn decrypt(mut self) -> Self {
+        let mut rng = self.rng.take().unwrap_or_else(StdRng::from_entropy);
+        for (i, ciphertext) in core::mem::take(&mut self.messages) {
+            // None of the partition stores ever wait, so this never blocks.
+            let result = block_on(message_decrypt(
+                &ciphertext,
+                &self.address,
+                &mut self.sessions,
+                &mut self.identities,
+                &mut self.pre_keys,
+                &mut self.signed_pre_keys,
+                &mut rng,
+                None,
+            ));
+            self.results.push((i, result));
+        }
+        self
+    }
+}
+
+struct PartitionSessionStore {
+    record: Option<SessionRecord>,
+    updated: bool,
+}
+
+#[async_trait(?Send)]
+impl SessionStore for PartitionSessionStore {
+    async fn load_session(
+        &self,
+        _address: &ProtocolAddress,
+        _ctx: Context,
+    ) -> Result<Option<SessionRecord>> {
+        Ok(self.record.clone())
+    }
+
+    async fn store_session(
+        &mut self,
+        _address: &ProtocolAddress,
+        record: &SessionRecord,
+        _ctx: Context,
+    ) -> Result<()> {
+        self.record = Some(record.clone());
+        self.updated = true;
+        Ok(())
+    }
+}
+
+struct PartitionIdentityStore {
+    local: Option<(IdentityKeyPair, u32)>,
+    trusted: Vec<(IdentityKey, bool)>,
+    saved: Vec<IdentityKey>,
+}
+
+impl PartitionIdentityStore {
+    fn local(&self) -> Result<&(IdentityKeyPair, u32)> {
+        self.local
+            .as_ref()
+            .ok_or(SignalProtocolError::InternalError(
+                "local identity was not fetched for backlog decryption",
+            ))
+    }
+}
+
+#[async_trait(?Send)]
+impl IdentityKeyStore for PartitionIdentityStore {
+    async fn get_identity_key_pair(&self, _ctx: Context) -> Result<IdentityKeyPair> {
+        Ok(self.local()?.0)
+    }
+
+    async fn get_local_registration_id(&self, _ctx: Context) -> Result<u32> {
+        Ok(self.local()?.1)
+    }
+
+    async fn save_identity(
+        &mut self,
+        _address: &ProtocolAddress,
+        identity: &IdentityKey,
+        _ctx: Context,
+    ) -> Result<bool> {
+        let changed = self.saved.last().map_or(false, |last| last != identity);
+        self.saved.push(*identity);
+        Ok(changed)
+    }
+
+    async fn is_trusted_identity(
+        &self,
+        _address: &ProtocolAddress,
+        identity: &IdentityKey,
+        _direction: Direction,
+        _ctx: Context,
+    ) -> Result<bool> {
+        self.trusted
+            .iter()
+            .find(|(candidate, _)| candidate == identity)
+            .map(|(_, trusted)| *trusted)
+            .ok_or(SignalProtocolError::InternalError(
+                "identity was not checked before backlog decryption",
+            ))
+    }
+
+    async fn get_identity(
+        &self,
+        _address: &ProtocolAddress,
+        _ctx: Context,
+    ) -> Result<Option<IdentityKey>> {
+        Ok(self.saved.last().copied())
+    }
+}
+
+struct PartitionPreKeyStore {
+    records: BTreeMap<PreKeyId, PreKeyRecord>,
+    removed: Vec<PreKeyId>,
+}
+
+#[async_trait(?Send)]
+impl PreKeyStore for PartitionPreKeyStore {
+    async fn get_pre_key(&self, prekey_id: PreKeyId, _ctx: Context) -> Result<PreKeyRecord> {
+        Ok(self
+            .records
+            .get(&prekey_id)
+            .ok_or(SignalProtocolError::InvalidPreKeyId)?
+            .clone())
+    }
+
+    async fn save_pre_key(
+        &mut self,
+        _prekey_id: PreKeyId,
+        _record: &PreKeyRecord,
+        _ctx: Context,
+    ) -> Result<()> {
+        Err(SignalProtocolError::InternalError(
+            "pre-keys cannot be saved during backlog decryption",
+        ))
+    }
+
+    async fn remove_pre_key(&mut self, prekey_id: PreKeyId, _ctx: Context) -> Result<()> {
+        self.records.remove(&prekey_id);
+        self.removed.push(prekey_id);
+        Ok(())
+    }
+}
+
+struct PartitionSignedPreKeyStore {
+    records: BTreeMap<SignedPreKeyId, SignedPreKeyRecord>,
+}
+
+#[async_trait(?Send)]
+impl SignedPreKeyStore for PartitionSignedPreKeyStore {
+    async fn get_signed_pre_key(
+        &self,
+        signed_prekey_id: SignedPreKeyId,
+        _ctx: Context,
+    ) -> Result<SignedPreKeyRecord> {
+        Ok(self
+            .records
+            .get(&signed_prekey_id)
+            .ok_or(SignalProtocolError::InvalidSignedPreKeyId)?
+            .clone())
+    }
+
+    async fn save_signed_pre_key(
+        &mut self,
+        _signed_prekey_id: SignedPreKeyId,
+        _record: &SignedPreKeyRecord,
+        _ctx: Context,
+    ) -> Result<()> {
+        Err(SignalProtocolError::InternalError(
+            "signed pre-keys cannot be saved during backlog decryption",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        message_encrypt, process_prekey_bundle, InMemSignalProtocolStore, KeyPair, PreKeyBundle,
+    };
+    use rand::rngs::OsRng;
+
+    fn new_store() -> Result<InMemSignalProtocolStore> {
+        InMemSignalProtocolStore::new(IdentityKeyPair::generate(&mut OsRng), 1)
+    }
+
+    /// Has `sender` start a session with `receiver` using fresh pre-keys, both with ID `pre_key_id`.
+    async fn start_session(
+        sender: &mut InMemSignalProtocolStore,
+        receiver: &mut InMemSignalProtocolStore,
+        receiver_address: &ProtocolAddress,
+        pre_key_id: PreKeyId,
+    ) -> Result<()> {
+        let identity = receiver.get_identity_key_pair(None).await?;
+        let signed_pre_key = KeyPair::generate(&mut OsRng);
+        let signature = identity
+            .private_key()
+            .calculate_signature(&signed_pre_key.public_key.serialize(), &mut OsRng)?;
+        receiver
+            .save_signed_pre_key(
+                pre_key_id,
+                &SignedPreKeyRecord::new(pre_key_id, 0, &signed_pre_key, &signature),
+                None,
+            )
+            .await?;
+        let pre_key = KeyPair::generate(&mut OsRng);
+        receiver
+            .save_pre_key(pre_key_id, &PreKeyRecord::new(pre_key_id, &pre_key), None)
+            .await?;
+
+        let bundle = PreKeyBundle::new(
+            1,
+            1,
+            Some((pre_key_id, pre_key.public_key)),
+            pre_key_id,
+            signed_pre_key.public_key,
+            signature.to_vec(),
+            *identity.identity_key(),
+        )?;
+        process_prekey_bundle(
+            receiver_address,
+            &mut sender.session_store,
+            &mut sender.identity_store,
+            &bundle,
+            &mut OsRng,
+            None,
+        )
+        .await
+    }
+
+    async fn backlog_from_two_senders() -> Result<()> {
+        let alice_address = ProtocolAddress::new("+14151111111".to_owned(), 1);
+        let bob_address = ProtocolAddress::new("+14152222222".to_owned(), 1);
+        let carol_address = ProtocolAddress::new("+14153333333".to_owned(), 1);
+
+        let mut alice_store = new_store()?;
+        let mut bob_store = new_store()?;
+        let mut carol_store = new_store()?;
+
+        start_session(&mut alice_store, &mut bob_store, &bob_address, 1).await?;
+        start_session(&mut carol_store, &mut bob_store, &bob_address, 2).await?;
+
+        let mut ciphertexts = Vec::new();
+        let mut senders = Vec::new();
+        let mut plaintexts = Vec::new();
+        for i in 0..20 {
+            let (store, address) = if i % 3 == 0 {
+                (&mut carol_store, &carol_address)
+            } else {
+                (&mut alice_store, &alice_address)
+            };
+            let plaintext = format!("message {}", i).into_bytes();
+            ciphertexts.push(
+                message_encrypt(
+                    &plaintext,
+                    &bob_address,
+                    &mut store.session_store,
+                    &mut store.identity_store,
+                    None,
+                )
+                .await?,
+            );
+            senders.push(address.clone());
+            plaintexts.push(plaintext);
+        }
+        // Throw in a replay, which should fail without affecting anything else.
+        let replayed = match &ciphertexts[1] {
+            CiphertextMessage::PreKeySignalMessage(m) => {
+                CiphertextMessage::PreKeySignalMessage(m.clone())
+            }
+            _ => panic!("expected a pre-key message"),
+        };
+        ciphertexts.push(replayed);
+        senders.push(alice_address.clone());
+
+        let decryptor = BacklogDecryptor::new(2)?;
+        let results = decryptor
+            .decrypt(
+                &ciphertexts,
+                &senders,
+                &mut bob_store.session_store,
+                &mut bob_store.identity_store,
+                &mut bob_store.pre_key_store,
+                &mut bob_store.signed_pre_key_store,
+                &mut OsRng,
+                None,
+            )
+            .await?;
+
+        assert_eq!(results.len(), plaintexts.len() + 1);
+        for (result, plaintext) in results.iter().zip(&plaintexts) {
+            assert_eq!(result.as_ref().expect("decrypted"), plaintext);
+        }
+        assert!(matches!(
+            results
+                .last()
+                .map(|result| result.as_ref().map_err(|e| e.root_cause())),
+            Some(Err(SignalProtocolError::DuplicatedMessage(_, _)))
+        ));
+
+        assert!(bob_store.get_pre_key(1, None).await.is_err());
+        assert!(bob_store.get_pre_key(2, None).await.is_err());
+        for address in &[&alice_address, &carol_address] {
+            assert!(bob_store.load_session(address, None).await?.is_some());
+            assert!(bob_store.get_identity(address, None).await?.is_some());
+        }
+
+        // The stored sessions are usable for the next message.
+        let next = message_encrypt(
+            b"after the backlog",
+            &bob_address,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            None,
+        )
+        .await?;
+        let results = decryptor
+            .decrypt(
+                &[next],
+                &[alice_address],
+                &mut bob_store.session_store,
+                &mut bob_store.identity_store,
+                &mut bob_store.pre_key_store,
+                &mut bob_store.signed_pre_key_store,
+                &mut OsRng,
+                None,
+            )
+            .await?;
+        assert_eq!(
+            results[0].as_ref().expect("decrypted"),
+            b"after the backlog"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_backlog_from_two_senders() -> Result<()> {
+        block_on(backlog_from_two_senders())
+    }
+
+    #[test]
+    fn test_mismatched_arguments() -> Result<()> {
+        let mut store = new_store()?;
+        let decryptor = BacklogDecryptor::new(1)?;
+        let result = block_on(decryptor.decrypt(
+            &[],
+            &[ProtocolAddress::new("+14151111111".to_owned(), 1)],
+            &mut store.session_store,
+            &mut store.identity_store,
+            &mut store.pre_key_store,
+            &mut store.signed_pre_key_store,
+            &mut OsRng,
+            None,
+        ));
+        assert!(matches!(
+            result,
+            Err(SignalProtocolError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+}
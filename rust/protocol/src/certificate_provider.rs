@@ -0,0 +1,133 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Keeping a sender certificate fresh for sealed sender.
+//!
+//! Sender certificates are short-lived, so a client has to notice when the one it has is about to
+//! expire and fetch a new one from the service. A [SenderCertificateProvider] caches the current
+//! certificate and asks a [SenderCertificateSource] for a new one once the cached certificate is
+//! within a configurable freshness window of expiring. [sealed_sender_encrypt_with_provider] uses
+//! it to pick the certificate for each message.
+
+use crate::{
+    sealed_sender_encrypt, Context, IdentityKeyStore, ProtocolAddress, Result, SenderCertificate,
+    SenderCertificateSource, SessionStore, SignalProtocolError,
+};
+
+use alloc::vec::Vec;
+use rand::{CryptoRng, Rng};
+
+/// Caches a sender certificate, refreshing it before it expires.
+#[derive(Debug, Clone)]
+pub struct SenderCertificateProvider {
+    current: Option<SenderCertificate>,
+    freshness_window: u64,
+}
+
+impl SenderCertificateProvider {
+    /// Creates a provider with no certificate, which refreshes its certificate whenever it is
+    /// within `freshness_window` milliseconds of expiring.
+    pub fn new(freshness_window: u64) -> Self {
+        Self {
+            current: None,
+            freshness_window,
+        }
+    }
+
+    /// Starts the provider off with a certificate the caller already has, such as one saved by a
+    /// previous run.
+    pub fn with_certificate(mut self, certificate: SenderCertificate) -> Self {
+        self.current = Some(certificate);
+        self
+    }
+
+    pub fn freshness_window(&self) -> u64 {
+        self.freshness_window
+    }
+
+    /// The cached certificate, whether or not it is still fresh.
+    pub fn current(&self) -> Option<&SenderCertificate> {
+        self.current.as_ref()
+    }
+
+    /// Returns true if [get](Self::get) would fetch a new certificate for `valid_until`.
+    pub fn needs_refresh(&self, valid_until: u64) -> Result<bool> {
+        match &self.current {
+            Some(certificate) => {
+                Ok(certificate.expiration()? < valid_until.saturating_add(self.freshness_window))
+            }
+            None => Ok(true),
+        }
+    }
+
+    /// Returns a certificate that is still valid at `valid_until`.
+    ///
+    /// If the cached certificate expires within the freshness window of `valid_until`, a new one
+    /// is fetched from `source` first. Should that fail while the cached certificate is still
+    /// valid at `valid_until`, the cached certificate is returned and the refresh is tried again
+    /// on the next call.
+    ///
+    /// Fails with
+    /// [`SealedSenderCertificateExpired`](SignalProtocolError::SealedSenderCertificateExpired) if
+    /// the newly fetched certificate itself expires before `valid_until`.
+    pub async fn get(
+        &mut self,
+        valid_until: u64,
+        source: &mut dyn SenderCertificateSource,
+        ctx: Context,
+    ) -> Result<SenderCertificate> {
+        if !self.needs_refresh(valid_until)? {
+            if let Some(certificate) = &self.current {
+                return Ok(certificate.clone());
+            }
+        }
+
+        let certificate = match source.fetch_sender_certificate(ctx).await {
+            Ok(certificate) => certificate,
+            Err(error) => {
+                return match &self.current {
+                    Some(current) if current.expiration()? >= valid_until => Ok(current.clone()),
+                    _ => Err(error),
+                };
+            }
+        };
+
+        let expiration = certificate.expiration()?;
+        if expiration < valid_until {
+            return Err(SignalProtocolError::SealedSenderCertificateExpired(
+                expiration,
+            ));
+        }
+        self.current = Some(certificate.clone());
+        Ok(certificate)
+    }
+}
+
+/// Like [sealed_sender_encrypt], but takes the sender certificate from `provider`, refreshing it
+/// first if it is within the provider's freshness window as of `timestamp`.
+#[allow(clippy::too_many_arguments)]
+pub async fn sealed_sender_encrypt_with_provider<R: Rng + CryptoRng>(
+    destination: &ProtocolAddress,
+    provider: &mut SenderCertificateProvider,
+    certificate_source: &mut dyn SenderCertificateSource,
+    timestamp: u64,
+    ptext: &[u8],
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    ctx: Context,
+    rng: &mut R,
+) -> Result<Vec<u8>> {
+    let sender_cert = provider.get(timestamp, certificate_source, ctx).await?;
+    sealed_sender_encrypt(
+        destination,
+        &sender_cert,
+        ptext,
+        session_store,
+        identity_store,
+        ctx,
+        rng,
+    )
+    .await
+}
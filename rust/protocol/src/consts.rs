@@ -4,7 +4,17 @@
 //
 
 pub const MAX_FORWARD_JUMPS: usize = 25_000;
+/// The most iterations a sender key chain can be advanced by one call to
+/// [group_fast_forward](crate::group_fast_forward).
+pub const MAX_FAST_FORWARD_JUMPS: usize = 1_000_000;
+/// The most skipped-message keys kept for any one chain.
 pub const MAX_MESSAGE_KEYS: usize = 2000;
+/// The most decrypted-message MACs kept for any one chain, for reporting duplicates.
+pub const MAX_DECRYPTED_MESSAGE_MACS: usize = 100;
+/// The most receiver chains kept in a session state.
 pub const MAX_RECEIVER_CHAINS: usize = 5;
+/// The most archived session states kept in a session record.
 pub const ARCHIVED_STATES_MAX_LENGTH: usize = 40;
+/// The most sender key states kept in a sender key record.
 pub const MAX_SENDER_KEY_STATES: usize = 5;
+pub const MAX_REGISTRATION_ID: u32 = 0x3FFF;
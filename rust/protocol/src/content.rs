@@ -0,0 +1,378 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Building the `Content` messages that are padded and encrypted for each recipient.
+//!
+//! Only the common shapes are covered: data messages (with a body, attachments, and a quote),
+//! typing indicators, and receipts. Each is checked as it is built, so a [`Content`] is always
+//! something the service and other clients will accept.
+
+use crate::proto::content as proto;
+use crate::{
+    message_encrypt, CiphertextMessage, Context, IdentityKeyStore, PaddingPolicy, ProtocolAddress,
+    Result, SessionStore, SignalProtocolError, PROFILE_KEY_SIZE,
+};
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use prost::Message;
+
+/// The longest body sent inline; longer text goes in a "long text" attachment instead.
+pub const MAX_INLINE_BODY_BYTES: usize = 2048;
+/// The most attachments one data message may carry.
+pub const MAX_ATTACHMENTS: usize = 32;
+
+const ATTACHMENT_KEY_SIZE: usize = 64;
+const ATTACHMENT_DIGEST_SIZE: usize = 32;
+
+fn invalid(message: &str) -> SignalProtocolError {
+    SignalProtocolError::InvalidArgument(message.into())
+}
+
+fn check_timestamp(timestamp: u64) -> Result<()> {
+    if timestamp == 0 {
+        return Err(invalid("message timestamp must be set"));
+    }
+    Ok(())
+}
+
+/// A reference to an encrypted attachment that has been uploaded to a CDN.
+#[derive(Clone, Debug)]
+pub struct AttachmentPointer(proto::AttachmentPointer);
+
+impl AttachmentPointer {
+    /// `key` is the attachment's 64-byte AES and HMAC key, and `digest` the SHA-256 of the
+    /// uploaded ciphertext; `size` is the length of the plaintext.
+    pub fn new(
+        cdn_number: u32,
+        cdn_key: String,
+        content_type: String,
+        key: &[u8],
+        digest: &[u8],
+        size: u32,
+    ) -> Result<Self> {
+        if cdn_key.is_empty() {
+            return Err(invalid("attachment CDN key must be set"));
+        }
+        if key.len() != ATTACHMENT_KEY_SIZE {
+            return Err(SignalProtocolError::InvalidArgument(format!(
+                "attachment key must be {} bytes, not {}",
+                ATTACHMENT_KEY_SIZE,
+                key.len()
+            )));
+        }
+        if digest.len() != ATTACHMENT_DIGEST_SIZE {
+            return Err(SignalProtocolError::InvalidArgument(format!(
+                "attachment digest must be {} bytes, not {}",
+                ATTACHMENT_DIGEST_SIZE,
+                digest.len()
+            )));
+        }
+        Ok(Self(proto::AttachmentPointer {
+            cdn_key: Some(cdn_key),
+            cdn_number: Some(cdn_number),
+            content_type: Some(content_type),
+            key: Some(key.to_vec()),
+            size: Some(size),
+            digest: Some(digest.to_vec()),
+            ..Default::default()
+        }))
+    }
+
+    pub fn with_file_name(mut self, file_name: String) -> Self {
+        self.0.file_name = Some(file_name);
+        self
+    }
+
+    /// The dimensions of an image or video, in pixels.
+    pub fn with_dimensions(mut self, width: u32, height: u32) -> Self {
+        self.0.width = Some(width);
+        self.0.height = Some(height);
+        self
+    }
+
+    pub fn with_caption(mut self, caption: String) -> Self {
+        self.0.caption = Some(caption);
+        self
+    }
+
+    /// When the attachment was uploaded, in milliseconds since the epoch.
+    pub fn with_upload_timestamp(mut self, upload_timestamp: u64) -> Self {
+        self.0.upload_timestamp = Some(upload_timestamp);
+        self
+    }
+}
+
+/// Assembles a data message, checking each part as it is added.
+#[derive(Clone, Debug)]
+pub struct DataMessageBuilder(proto::DataMessage);
+
+impl DataMessageBuilder {
+    /// Starts a data message sent at `timestamp`, in milliseconds since the epoch.
+    pub fn new(timestamp: u64) -> Self {
+        Self(proto::DataMessage {
+            timestamp: Some(timestamp),
+            ..Default::default()
+        })
+    }
+
+    pub fn set_body(&mut self, body: String) -> Result<&mut Self> {
+        if body.len() > MAX_INLINE_BODY_BYTES {
+            return Err(SignalProtocolError::InvalidArgument(format!(
+                "message body is {} bytes; bodies over {} bytes must be sent as attachments",
+                body.len(),
+                MAX_INLINE_BODY_BYTES
+            )));
+        }
+        self.0.body = Some(body);
+        Ok(self)
+    }
+
+    pub fn add_attachment(&mut self, attachment: AttachmentPointer) -> Result<&mut Self> {
+        if self.0.attachments.len() >= MAX_ATTACHMENTS {
+            return Err(SignalProtocolError::InvalidArgument(format!(
+                "a message can have at most {} attachments",
+                MAX_ATTACHMENTS
+            )));
+        }
+        self.0.attachments.push(attachment.0);
+        Ok(self)
+    }
+
+    /// Quotes the message sent at `id` (its timestamp) by `author_uuid`.
+    pub fn set_quote(
+        &mut self,
+        id: u64,
+        author_uuid: String,
+        text: Option<String>,
+    ) -> Result<&mut Self> {
+        check_timestamp(id)?;
+        if author_uuid.is_empty() {
+            return Err(invalid("quote author must be set"));
+        }
+        self.0.quote = Some(proto::data_message::Quote {
+            id: Some(id),
+            author_uuid: Some(author_uuid),
+            text,
+        });
+        Ok(self)
+    }
+
+    /// The disappearing message timer, in seconds; 0 turns it off.
+    pub fn set_expire_timer(&mut self, seconds: u32) -> &mut Self {
+        self.0.expire_timer = Some(seconds);
+        self
+    }
+
+    pub fn set_profile_key(&mut self, profile_key: &[u8]) -> Result<&mut Self> {
+        if profile_key.len() != PROFILE_KEY_SIZE {
+            return Err(SignalProtocolError::InvalidArgument(format!(
+                "profile key must be {} bytes, not {}",
+                PROFILE_KEY_SIZE,
+                profile_key.len()
+            )));
+        }
+        self.0.profile_key = Some(profile_key.to_vec());
+        Ok(self)
+    }
+
+    /// Fails if the message has no timestamp, or has neither a body nor any attachments.
+    pub fn build(&self) -> Result<Content> {
+        check_timestamp(self.0.timestamp.unwrap_or(0))?;
+        let has_body = self.0.body.as_ref().map_or(false, |body| !body.is_empty());
+        if !has_body && self.0.attachments.is_empty() {
+            return Err(invalid("data message needs a body or an attachment"));
+        }
+        Ok(Content(proto::Content {
+            data_message: Some(self.0.clone()),
+            ..Default::default()
+        }))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReceiptType {
+    Delivery,
+    Read,
+    Viewed,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TypingAction {
+    Started,
+    Stopped,
+}
+
+/// A message ready to be padded and encrypted.
+#[derive(Clone, Debug)]
+pub struct Content(proto::Content);
+
+impl Content {
+    /// Acknowledges the messages sent at each of `timestamps`.
+    pub fn receipt(receipt_type: ReceiptType, timestamps: &[u64]) -> Result<Self> {
+        if timestamps.is_empty() {
+            return Err(invalid("receipt must acknowledge at least one message"));
+        }
+        for timestamp in timestamps {
+            check_timestamp(*timestamp)?;
+        }
+        let receipt_type = match receipt_type {
+            ReceiptType::Delivery => proto::receipt_message::Type::Delivery,
+            ReceiptType::Read => proto::receipt_message::Type::Read,
+            ReceiptType::Viewed => proto::receipt_message::Type::Viewed,
+        };
+        Ok(Self(proto::Content {
+            receipt_message: Some(proto::ReceiptMessage {
+                r#type: Some(receipt_type.into()),
+                timestamp: timestamps.to_vec(),
+            }),
+            ..Default::default()
+        }))
+    }
+
+    /// A typing indicator, in the group with ID `group_id` if given.
+    pub fn typing(timestamp: u64, action: TypingAction, group_id: Option<&[u8]>) -> Result<Self> {
+        check_timestamp(timestamp)?;
+        if group_id.map_or(false, |id| id.is_empty()) {
+            return Err(invalid("group ID must not be empty"));
+        }
+        let action = match action {
+            TypingAction::Started => proto::typing_message::Action::Started,
+            TypingAction::Stopped => proto::typing_message::Action::Stopped,
+        };
+        Ok(Self(proto::Content {
+            typing_message: Some(proto::TypingMessage {
+                timestamp: Some(timestamp),
+                action: Some(action.into()),
+                group_id: group_id.map(|id| id.to_vec()),
+            }),
+            ..Default::default()
+        }))
+    }
+
+    pub fn serialized(&self) -> Vec<u8> {
+        let mut result = Vec::with_capacity(self.0.encoded_len());
+        self.0
+            .encode(&mut result)
+            .expect("a Vec has room for any message");
+        result
+    }
+}
+
+/// Serializes `content`, pads it according to `padding`, and encrypts it for `remote_address`.
+pub async fn message_encrypt_content(
+    content: &Content,
+    padding: PaddingPolicy,
+    remote_address: &ProtocolAddress,
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    ctx: Context,
+) -> Result<CiphertextMessage> {
+    let padded = padding.pad(&content.serialized())?;
+    message_encrypt(&padded, remote_address, session_store, identity_store, ctx).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attachment() -> Result<AttachmentPointer> {
+        AttachmentPointer::new(
+            2,
+            "cdn-key".into(),
+            "image/jpeg".into(),
+            &[1; 64],
+            &[2; 32],
+            1000,
+        )
+    }
+
+    #[test]
+    fn test_data_message() -> Result<()> {
+        let mut builder = DataMessageBuilder::new(1234);
+        builder
+            .set_body("hello".into())?
+            .add_attachment(attachment()?.with_dimensions(640, 480))?
+            .set_quote(1000, "9d0652a3-dcc3-4d11-975f-74d61598733f".into(), None)?
+            .set_expire_timer(60);
+        let content = builder.build()?;
+
+        let decoded =
+            proto::Content::decode(content.serialized().as_slice()).expect("content round-trips");
+        let data_message = decoded.data_message.expect("is a data message");
+        assert_eq!(data_message.body.as_deref(), Some("hello"));
+        assert_eq!(data_message.timestamp, Some(1234));
+        assert_eq!(data_message.attachments[0].width, Some(640));
+        assert_eq!(data_message.quote.and_then(|quote| quote.id), Some(1000));
+        assert_eq!(data_message.expire_timer, Some(60));
+        Ok(())
+    }
+
+    #[test]
+    fn test_data_message_validation() -> Result<()> {
+        assert!(DataMessageBuilder::new(1234).build().is_err());
+        assert!(DataMessageBuilder::new(0)
+            .set_body("hello".into())?
+            .build()
+            .is_err());
+        assert!(DataMessageBuilder::new(1234)
+            .set_body("x".repeat(MAX_INLINE_BODY_BYTES + 1))
+            .is_err());
+        assert!(DataMessageBuilder::new(1234)
+            .set_profile_key(&[0; 16])
+            .is_err());
+        assert!(
+            AttachmentPointer::new(2, "".into(), "image/jpeg".into(), &[1; 64], &[2; 32], 1)
+                .is_err()
+        );
+        assert!(AttachmentPointer::new(
+            2,
+            "key".into(),
+            "image/jpeg".into(),
+            &[1; 32],
+            &[2; 32],
+            1
+        )
+        .is_err());
+
+        let mut builder = DataMessageBuilder::new(1234);
+        for _ in 0..MAX_ATTACHMENTS {
+            builder.add_attachment(attachment()?)?;
+        }
+        assert!(builder.add_attachment(attachment()?).is_err());
+        assert!(builder.build().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_receipts_and_typing() -> Result<()> {
+        let receipt = Content::receipt(ReceiptType::Read, &[10, 20])?;
+        let decoded = proto::Content::decode(receipt.serialized().as_slice())
+            .expect("content round-trips")
+            .receipt_message
+            .expect("is a receipt");
+        assert_eq!(
+            decoded.r#type,
+            Some(proto::receipt_message::Type::Read.into())
+        );
+        assert_eq!(decoded.timestamp, vec![10, 20]);
+        assert!(Content::receipt(ReceiptType::Delivery, &[]).is_err());
+        assert!(Content::receipt(ReceiptType::Delivery, &[10, 0]).is_err());
+
+        let typing = Content::typing(10, TypingAction::Stopped, Some(&[1, 2, 3]))?;
+        let decoded = proto::Content::decode(typing.serialized().as_slice())
+            .expect("content round-trips")
+            .typing_message
+            .expect("is a typing message");
+        assert_eq!(
+            decoded.action,
+            Some(proto::typing_message::Action::Stopped.into())
+        );
+        assert_eq!(decoded.group_id, Some(vec![1, 2, 3]));
+        assert!(Content::typing(10, TypingAction::Started, Some(&[])).is_err());
+        Ok(())
+    }
+}
@@ -5,6 +5,8 @@
 
 use crate::{error::Result, SignalProtocolError};
 
+use alloc::vec::Vec;
+
 use aes::cipher::stream::{NewStreamCipher, SyncStreamCipher};
 use aes::Aes256;
 use block_modes::{block_padding::Pkcs7, BlockMode, Cbc};
@@ -14,14 +16,23 @@ use sha2::Sha256;
 use subtle::ConstantTimeEq;
 
 pub fn aes_256_ctr_encrypt(ptext: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+    aes_256_ctr_encrypt_with_nonce(ptext, key, &[0u8; 16])
+}
+
+/// Like [aes_256_ctr_encrypt], but starts the counter block at `nonce`, for keys that encrypt
+/// more than one message.
+pub fn aes_256_ctr_encrypt_with_nonce(
+    ptext: &[u8],
+    key: &[u8],
+    nonce: &[u8; 16],
+) -> Result<Vec<u8>> {
     if key.len() != 32 {
         return Err(SignalProtocolError::InvalidCipherCryptographicParameters(
             32, 0,
         ));
     }
 
-    let zero_nonce = [0u8; 16];
-    let mut cipher = Ctr128::<Aes256>::new(key.into(), (&zero_nonce).into());
+    let mut cipher = Ctr128::<Aes256>::new(key.into(), nonce.into());
 
     let mut ctext = ptext.to_vec();
     cipher.apply_keystream(&mut ctext);
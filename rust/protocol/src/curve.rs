@@ -5,16 +5,26 @@
 
 mod curve25519;
 
+use crate::crypto;
+use crate::kdf::HKDF;
+use crate::redact::{nested, secret, Redact};
 use crate::{Result, SignalProtocolError};
 
-use std::cmp::Ordering;
-use std::convert::TryFrom;
-use std::fmt;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::fmt;
 
 use arrayref::array_ref;
 use rand::{CryptoRng, Rng};
 use subtle::ConstantTimeEq;
 
+/// Separates keys derived for sealed boxes from any other use of the same agreement.
+const SEALED_BOX_INFO: &[u8] = b"Signal_SealedBox_X25519_HKDF_SHA256_AES256CTR_HMACSHA256";
+const SEALED_BOX_EPHEMERAL_KEY_SIZE: usize = 33;
+const SEALED_BOX_MAC_SIZE: usize = 32;
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum KeyType {
     Djb,
@@ -113,6 +123,59 @@ impl PublicKey {
         }
     }
 
+    /// Checks several `(message, signature)` pairs made with this key at once.
+    ///
+    /// Returns `true` only if every signature is valid; on `false`, check them individually with
+    /// [`verify_signature`](Self::verify_signature) to find the bad ones. A batch passes exactly
+    /// when every signature would pass `verify_signature`; keeping that guarantee means checking
+    /// each signature's `R` for small-order components, so this costs about as much as checking
+    /// the signatures one by one.
+    pub fn verify_signatures<R: CryptoRng + Rng>(
+        &self,
+        signatures: &[(&[u8], &[u8])],
+        csprng: &mut R,
+    ) -> Result<bool> {
+        match self.key {
+            PublicKeyData::DjbPublicKey(pub_key) => {
+                if signatures
+                    .iter()
+                    .any(|(_, signature)| signature.len() != 64)
+                {
+                    return Ok(false);
+                }
+                let signatures: Vec<(&[u8], &[u8; 64])> = signatures
+                    .iter()
+                    .map(|(message, signature)| (*message, array_ref![signature, 0, 64]))
+                    .collect();
+                Ok(curve25519::KeyPair::verify_signatures(
+                    &pub_key,
+                    &signatures,
+                    csprng,
+                ))
+            }
+        }
+    }
+
+    /// Encrypts `plaintext` so that only the holder of the matching private key can read it.
+    ///
+    /// The result reveals nothing about who sealed it: it is a fresh ephemeral public key, the
+    /// AES-256-CTR ciphertext, and an HMAC-SHA256 over both, with the keys derived from the
+    /// ephemeral agreement. It is 65 bytes longer than `plaintext`. See [`PrivateKey::open`].
+    pub fn seal<R: CryptoRng + Rng>(&self, plaintext: &[u8], csprng: &mut R) -> Result<Vec<u8>> {
+        let ephemeral = KeyPair::generate(csprng);
+        let ephemeral_public = ephemeral.public_key.serialize();
+        let shared_secret = ephemeral.private_key.calculate_agreement(self)?;
+        let (cipher_key, mac_key) = sealed_box_keys(&shared_secret, &ephemeral_public, self)?;
+
+        let mut sealed =
+            Vec::with_capacity(ephemeral_public.len() + plaintext.len() + SEALED_BOX_MAC_SIZE);
+        sealed.extend_from_slice(&ephemeral_public);
+        sealed.extend_from_slice(&crypto::aes_256_ctr_encrypt(plaintext, &cipher_key)?);
+        let mac = crypto::hmac_sha256(&mac_key, &sealed)?;
+        sealed.extend_from_slice(&mac);
+        Ok(sealed)
+    }
+
     fn key_data(&self) -> &[u8] {
         match self.key {
             PublicKeyData::DjbPublicKey(ref k) => k.as_ref(),
@@ -173,7 +236,7 @@ impl fmt::Debug for PublicKey {
     }
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Clone, Copy, Eq, PartialEq)]
 enum PrivateKeyData {
     DjbPrivateKey([u8; 32]),
 }
@@ -242,6 +305,45 @@ impl PrivateKey {
             }
         }
     }
+
+    /// Decrypts the output of [`PublicKey::seal`] for this key's public key.
+    ///
+    /// Returns [`SignalProtocolError::InvalidCiphertext`] if `sealed` was meant for a different
+    /// key or has been modified.
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+        if sealed.len() < SEALED_BOX_EPHEMERAL_KEY_SIZE + SEALED_BOX_MAC_SIZE {
+            return Err(SignalProtocolError::InvalidCiphertext);
+        }
+        let (authenticated, their_mac) = sealed.split_at(sealed.len() - SEALED_BOX_MAC_SIZE);
+        let (ephemeral_public, ctext) = authenticated.split_at(SEALED_BOX_EPHEMERAL_KEY_SIZE);
+
+        let ephemeral = PublicKey::deserialize(ephemeral_public)
+            .map_err(|_| SignalProtocolError::InvalidCiphertext)?;
+        let shared_secret = self.calculate_agreement(&ephemeral)?;
+        let (cipher_key, mac_key) =
+            sealed_box_keys(&shared_secret, ephemeral_public, &self.public_key()?)?;
+
+        let our_mac = crypto::hmac_sha256(&mac_key, authenticated)?;
+        if !bool::from(our_mac[..].ct_eq(their_mac)) {
+            return Err(SignalProtocolError::InvalidCiphertext);
+        }
+        crypto::aes_256_ctr_decrypt(ctext, &cipher_key)
+    }
+}
+
+/// Derives the cipher and MAC keys for a sealed box, bound to both the ephemeral and the
+/// recipient's public key.
+fn sealed_box_keys(
+    shared_secret: &[u8],
+    ephemeral_public: &[u8],
+    recipient: &PublicKey,
+) -> Result<(Box<[u8]>, Box<[u8]>)> {
+    let mut salt = Vec::with_capacity(2 * SEALED_BOX_EPHEMERAL_KEY_SIZE);
+    salt.extend_from_slice(ephemeral_public);
+    salt.extend_from_slice(&recipient.serialize());
+
+    let keys = HKDF::new(3)?.derive_salted_secrets(shared_secret, &salt, SEALED_BOX_INFO, 64)?;
+    Ok((keys[..32].into(), keys[32..].into()))
 }
 
 impl From<PrivateKeyData> for PrivateKey {
@@ -258,7 +360,19 @@ impl TryFrom<&[u8]> for PrivateKey {
     }
 }
 
+impl Redact for PrivateKey {
+    fn fmt_redactable(&self, f: &mut fmt::Formatter, redact: bool) -> fmt::Result {
+        f.debug_struct("PrivateKey")
+            .field("key_type", &self.key_type())
+            .field("key", &secret(&self.serialize(), redact))
+            .finish()
+    }
+}
+
+impl_redacted_debug!(PrivateKey);
+
 #[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KeyPair {
     pub public_key: PublicKey,
     pub private_key: PrivateKey,
@@ -306,6 +420,17 @@ impl KeyPair {
     }
 }
 
+impl Redact for KeyPair {
+    fn fmt_redactable(&self, f: &mut fmt::Formatter, redact: bool) -> fmt::Result {
+        f.debug_struct("KeyPair")
+            .field("public_key", &self.public_key)
+            .field("private_key", &nested(&self.private_key, redact))
+            .finish()
+    }
+}
+
+impl_redacted_debug!(KeyPair);
+
 #[cfg(test)]
 mod tests {
     use rand::rngs::OsRng;
@@ -363,4 +488,30 @@ mod tests {
         assert_eq!(&serialized_public[..], &extra_space_decode?.serialize()[..]);
         Ok(())
     }
+
+    #[test]
+    fn test_sealed_box() -> Result<()> {
+        let mut csprng = OsRng;
+        let key_pair = KeyPair::generate(&mut csprng);
+        let plaintext = b"username link entropy";
+
+        let sealed = key_pair.public_key.seal(plaintext, &mut csprng)?;
+        assert_eq!(sealed.len(), plaintext.len() + 65);
+        assert_eq!(key_pair.private_key.open(&sealed)?, plaintext);
+        assert_ne!(key_pair.public_key.seal(plaintext, &mut csprng)?, sealed);
+
+        for i in 0..sealed.len() {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 0x01;
+            assert!(key_pair.private_key.open(&tampered).is_err());
+        }
+
+        let other = KeyPair::generate(&mut csprng);
+        assert!(other.private_key.open(&sealed).is_err());
+        assert!(key_pair.private_key.open(&sealed[..64]).is_err());
+
+        let empty = key_pair.public_key.seal(&[], &mut csprng)?;
+        assert_eq!(key_pair.private_key.open(&empty)?, b"");
+        Ok(())
+    }
 }
@@ -0,0 +1,533 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Structured, human-readable descriptions of records and messages.
+//!
+//! The output is JSON, intended for support tooling and interop debugging rather than for
+//! storage; its exact layout is not stable. Private keys, chain keys, and other secrets are
+//! redacted unless explicitly requested.
+
+use crate::proto::storage::{session_structure, SessionStructure};
+use crate::utils::hex_encode;
+use crate::{
+    PreKeyBundle, PreKeySignalMessage, Result, SecuritySnapshot, SenderKeyDistributionMessage,
+    SenderKeyMessage, SenderKeyRecord, SessionRecord, SignalMessage,
+};
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+enum DumpValue {
+    Null,
+    Bool(bool),
+    Number(u64),
+    Text(String),
+    Bytes(Vec<u8>),
+    Secret(Vec<u8>),
+    List(Vec<DumpValue>),
+    Object(Vec<(&'static str, DumpValue)>),
+}
+
+impl From<bool> for DumpValue {
+    fn from(value: bool) -> Self {
+        DumpValue::Bool(value)
+    }
+}
+
+impl From<u8> for DumpValue {
+    fn from(value: u8) -> Self {
+        DumpValue::Number(value.into())
+    }
+}
+
+impl From<u32> for DumpValue {
+    fn from(value: u32) -> Self {
+        DumpValue::Number(value.into())
+    }
+}
+
+impl From<u64> for DumpValue {
+    fn from(value: u64) -> Self {
+        DumpValue::Number(value)
+    }
+}
+
+impl From<i32> for DumpValue {
+    fn from(value: i32) -> Self {
+        // Negative values don't appear in any of the structures we dump.
+        DumpValue::Number(value as u64)
+    }
+}
+
+impl From<&str> for DumpValue {
+    fn from(value: &str) -> Self {
+        DumpValue::Text(value.to_string())
+    }
+}
+
+impl<T: Into<DumpValue>> From<Option<T>> for DumpValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(DumpValue::Null, Into::into)
+    }
+}
+
+impl<T: Into<DumpValue>> From<Vec<T>> for DumpValue {
+    fn from(value: Vec<T>) -> Self {
+        DumpValue::List(value.into_iter().map(Into::into).collect())
+    }
+}
+
+fn bytes(value: &[u8]) -> DumpValue {
+    DumpValue::Bytes(value.to_vec())
+}
+
+fn secret(value: &[u8]) -> DumpValue {
+    DumpValue::Secret(value.to_vec())
+}
+
+fn write_json_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                write!(out, "\\u{:04x}", c as u32).expect("writing to a String cannot fail")
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+impl DumpValue {
+    fn write(&self, out: &mut String, indent: usize, include_secrets: bool) {
+        const INDENT: &str = "  ";
+        match self {
+            DumpValue::Null => out.push_str("null"),
+            DumpValue::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            DumpValue::Number(n) => out.push_str(&n.to_string()),
+            DumpValue::Text(s) => write_json_string(out, s),
+            DumpValue::Bytes(b) => write_json_string(out, &hex_encode(b)),
+            DumpValue::Secret(b) if include_secrets => write_json_string(out, &hex_encode(b)),
+            DumpValue::Secret(b) => {
+                write_json_string(out, &format!("<redacted {} bytes>", b.len()))
+            }
+            DumpValue::List(items) if items.is_empty() => out.push_str("[]"),
+            DumpValue::List(items) => {
+                out.push_str("[\n");
+                for (i, item) in items.iter().enumerate() {
+                    out.push_str(&INDENT.repeat(indent + 1));
+                    item.write(out, indent + 1, include_secrets);
+                    out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&INDENT.repeat(indent));
+                out.push(']');
+            }
+            DumpValue::Object(fields) if fields.is_empty() => out.push_str("{}"),
+            DumpValue::Object(fields) => {
+                out.push_str("{\n");
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    out.push_str(&INDENT.repeat(indent + 1));
+                    write_json_string(out, name);
+                    out.push_str(": ");
+                    value.write(out, indent + 1, include_secrets);
+                    out.push_str(if i + 1 < fields.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&INDENT.repeat(indent));
+                out.push('}');
+            }
+        }
+    }
+
+    fn render(&self, include_secrets: bool) -> String {
+        let mut out = String::new();
+        self.write(&mut out, 0, include_secrets);
+        out
+    }
+}
+
+fn dump_chain(chain: &session_structure::Chain) -> DumpValue {
+    let chain_key = chain.chain_key.as_ref().map(|chain_key| {
+        DumpValue::Object(vec![
+            ("index", chain_key.index.into()),
+            ("key", secret(&chain_key.key)),
+        ])
+    });
+    let message_keys = chain
+        .message_keys
+        .iter()
+        .map(|key| {
+            DumpValue::Object(vec![
+                ("index", key.index.into()),
+                ("cipher_key", secret(&key.cipher_key)),
+                ("mac_key", secret(&key.mac_key)),
+                ("iv", secret(&key.iv)),
+            ])
+        })
+        .collect();
+    let decrypted_messages = chain
+        .decrypted_messages
+        .iter()
+        .map(|decrypted| {
+            DumpValue::Object(vec![
+                ("index", decrypted.index.into()),
+                ("plaintext_mac", secret(&decrypted.plaintext_mac)),
+            ])
+        })
+        .collect();
+    DumpValue::Object(vec![
+        ("sender_ratchet_key", bytes(&chain.sender_ratchet_key)),
+        (
+            "sender_ratchet_key_private",
+            secret(&chain.sender_ratchet_key_private),
+        ),
+        ("chain_key", chain_key.into()),
+        ("message_keys", DumpValue::List(message_keys)),
+        ("header_key", secret(&chain.header_key)),
+        ("decrypted_messages", DumpValue::List(decrypted_messages)),
+    ])
+}
+
+fn dump_session(session: &SessionStructure) -> DumpValue {
+    let pending_pre_key = session.pending_pre_key.as_ref().map(|pending| {
+        DumpValue::Object(vec![
+            ("pre_key_id", pending.pre_key_id.into()),
+            ("signed_pre_key_id", pending.signed_pre_key_id.into()),
+            ("base_key", bytes(&pending.base_key)),
+        ])
+    });
+    DumpValue::Object(vec![
+        ("session_version", session.session_version.into()),
+        (
+            "local_identity_public",
+            bytes(&session.local_identity_public),
+        ),
+        (
+            "remote_identity_public",
+            bytes(&session.remote_identity_public),
+        ),
+        ("root_key", secret(&session.root_key)),
+        ("previous_counter", session.previous_counter.into()),
+        (
+            "sender_chain",
+            session.sender_chain.as_ref().map(dump_chain).into(),
+        ),
+        (
+            "receiver_chains",
+            DumpValue::List(session.receiver_chains.iter().map(dump_chain).collect()),
+        ),
+        ("pending_pre_key", pending_pre_key.into()),
+        (
+            "remote_registration_id",
+            session.remote_registration_id.into(),
+        ),
+        (
+            "local_registration_id",
+            session.local_registration_id.into(),
+        ),
+        ("needs_refresh", session.needs_refresh.into()),
+        ("alice_base_key", bytes(&session.alice_base_key)),
+        (
+            "last_ratchet_time",
+            match session.last_ratchet_time {
+                0 => DumpValue::Null,
+                time => time.into(),
+            },
+        ),
+    ])
+}
+
+fn dump_signal_message(message: &SignalMessage) -> DumpValue {
+    DumpValue::Object(vec![
+        ("type", "SignalMessage".into()),
+        ("message_version", message.message_version().into()),
+        // Null if the header is encrypted.
+        (
+            "sender_ratchet_key",
+            message
+                .sender_ratchet_key()
+                .ok()
+                .map(|key| bytes(&key.serialize()))
+                .into(),
+        ),
+        ("counter", message.counter().ok().into()),
+        ("body", bytes(message.body())),
+    ])
+}
+
+impl SessionRecord {
+    /// Describes the current and archived session states as JSON.
+    ///
+    /// Root keys, chain keys, header keys, message keys, and ratchet private keys are redacted
+    /// unless `include_secrets` is set.
+    pub fn debug_dump(&self, include_secrets: bool) -> Result<String> {
+        let current_session = if self.has_current_session_state() {
+            Some(dump_session(&self.session_state()?.into()))
+        } else {
+            None
+        };
+        let previous_sessions = self
+            .previous_session_states()?
+            .map(|state| Ok(dump_session(&state?.into())))
+            .collect::<Result<Vec<DumpValue>>>()?;
+        let dump = DumpValue::Object(vec![
+            ("type", "SessionRecord".into()),
+            ("serialized_version", self.serialized_version().into()),
+            ("current_session", current_session.into()),
+            ("previous_sessions", previous_sessions.into()),
+        ]);
+        Ok(dump.render(include_secrets))
+    }
+}
+
+impl PreKeyBundle {
+    /// Describes the bundle as JSON.
+    ///
+    /// A bundle contains only public information; `include_secrets` is accepted for consistency
+    /// with the other `debug_dump` methods.
+    pub fn debug_dump(&self, include_secrets: bool) -> Result<String> {
+        let dump = DumpValue::Object(vec![
+            ("type", "PreKeyBundle".into()),
+            ("registration_id", self.registration_id()?.into()),
+            ("device_id", self.device_id()?.into()),
+            ("pre_key_id", self.pre_key_id()?.into()),
+            (
+                "pre_key_public",
+                self.pre_key_public()?
+                    .map(|key| bytes(&key.serialize()))
+                    .into(),
+            ),
+            ("signed_pre_key_id", self.signed_pre_key_id()?.into()),
+            (
+                "signed_pre_key_public",
+                bytes(&self.signed_pre_key_public()?.serialize()),
+            ),
+            (
+                "signed_pre_key_signature",
+                bytes(self.signed_pre_key_signature()?),
+            ),
+            ("identity_key", bytes(&self.identity_key()?.serialize())),
+            ("min_session_version", self.min_session_version()?.into()),
+            ("max_session_version", self.max_session_version()?.into()),
+        ]);
+        Ok(dump.render(include_secrets))
+    }
+}
+
+impl SenderKeyRecord {
+    /// Describes each sender key state as JSON.
+    ///
+    /// Chain key seeds, message key seeds, and signing private keys are redacted unless
+    /// `include_secrets` is set.
+    pub fn debug_dump(&self, include_secrets: bool) -> Result<String> {
+        let record = self.as_protobuf()?;
+        let states: Vec<DumpValue> = record
+            .sender_key_states
+            .iter()
+            .map(|state| {
+                let chain_key = state.sender_chain_key.as_ref().map(|chain_key| {
+                    DumpValue::Object(vec![
+                        ("iteration", chain_key.iteration.into()),
+                        ("seed", secret(&chain_key.seed)),
+                    ])
+                });
+                let signing_key = state.sender_signing_key.as_ref().map(|signing_key| {
+                    DumpValue::Object(vec![
+                        ("public", bytes(&signing_key.public)),
+                        ("private", secret(&signing_key.private)),
+                    ])
+                });
+                let message_keys = state
+                    .sender_message_keys
+                    .iter()
+                    .map(|key| {
+                        DumpValue::Object(vec![
+                            ("iteration", key.iteration.into()),
+                            ("seed", secret(&key.seed)),
+                        ])
+                    })
+                    .collect();
+                DumpValue::Object(vec![
+                    ("sender_key_id", state.sender_key_id.into()),
+                    ("sender_chain_key", chain_key.into()),
+                    ("sender_signing_key", signing_key.into()),
+                    ("sender_message_keys", DumpValue::List(message_keys)),
+                ])
+            })
+            .collect();
+        let dump = DumpValue::Object(vec![
+            ("type", "SenderKeyRecord".into()),
+            ("serialized_version", self.serialized_version().into()),
+            ("sender_key_states", states.into()),
+        ]);
+        Ok(dump.render(include_secrets))
+    }
+}
+
+impl SignalMessage {
+    /// Describes the message header and (encrypted) body as JSON.
+    pub fn debug_dump(&self, include_secrets: bool) -> Result<String> {
+        Ok(dump_signal_message(self).render(include_secrets))
+    }
+}
+
+impl PreKeySignalMessage {
+    /// Describes the message header and the embedded [SignalMessage] as JSON.
+    pub fn debug_dump(&self, include_secrets: bool) -> Result<String> {
+        let dump = DumpValue::Object(vec![
+            ("type", "PreKeySignalMessage".into()),
+            ("message_version", self.message_version().into()),
+            ("registration_id", self.registration_id().into()),
+            ("pre_key_id", self.pre_key_id().into()),
+            ("signed_pre_key_id", self.signed_pre_key_id().into()),
+            ("base_key", bytes(&self.base_key().serialize())),
+            ("identity_key", bytes(&self.identity_key().serialize())),
+            ("message", dump_signal_message(self.message())),
+        ]);
+        Ok(dump.render(include_secrets))
+    }
+}
+
+impl SenderKeyMessage {
+    /// Describes the message header and (encrypted) body as JSON.
+    pub fn debug_dump(&self, include_secrets: bool) -> Result<String> {
+        let dump = DumpValue::Object(vec![
+            ("type", "SenderKeyMessage".into()),
+            ("message_version", self.message_version().into()),
+            ("key_id", self.key_id().into()),
+            ("iteration", self.iteration().into()),
+            ("ciphertext", bytes(self.ciphertext())),
+        ]);
+        Ok(dump.render(include_secrets))
+    }
+}
+
+impl SenderKeyDistributionMessage {
+    /// Describes the message as JSON.
+    ///
+    /// The chain key is redacted unless `include_secrets` is set.
+    pub fn debug_dump(&self, include_secrets: bool) -> Result<String> {
+        let dump = DumpValue::Object(vec![
+            ("type", "SenderKeyDistributionMessage".into()),
+            ("message_version", self.message_version().into()),
+            ("id", self.id()?.into()),
+            ("iteration", self.iteration()?.into()),
+            ("chain_key", secret(self.chain_key()?)),
+            ("signing_key", bytes(&self.signing_key()?.serialize())),
+        ]);
+        Ok(dump.render(include_secrets))
+    }
+}
+
+impl SecuritySnapshot {
+    /// Describes the snapshot as JSON, for attaching to a bug report.
+    ///
+    /// There is nothing to redact: the snapshot never holds any secrets.
+    pub fn to_json(&self) -> String {
+        let session = self.session().map(|session| {
+            DumpValue::Object(vec![
+                ("session_version", session.session_version().into()),
+                (
+                    "remote_identity_fingerprint",
+                    session.remote_identity_fingerprint().into(),
+                ),
+                ("last_ratchet_time", session.last_ratchet_time().into()),
+                (
+                    "receiver_chain_count",
+                    (session.receiver_chain_count() as u64).into(),
+                ),
+                (
+                    "has_unacknowledged_pre_key_message",
+                    session.has_unacknowledged_pre_key_message().into(),
+                ),
+                (
+                    "previous_session_count",
+                    (session.previous_session_count() as u64).into(),
+                ),
+            ])
+        });
+        let sender_key_distributions = self
+            .sender_key_distributions()
+            .iter()
+            .map(|distribution| {
+                DumpValue::Object(vec![
+                    ("group_id", distribution.group_id().into()),
+                    ("current_key_id", distribution.current_key_id().into()),
+                    (
+                        "distributed_key_id",
+                        distribution.distributed_key_id().into(),
+                    ),
+                ])
+            })
+            .collect();
+        let dump = DumpValue::Object(vec![
+            ("type", "SecuritySnapshot".into()),
+            ("name", self.address().name().into()),
+            ("device_id", self.address().device_id().into()),
+            ("identity_fingerprint", self.identity_fingerprint().into()),
+            ("session", session.into()),
+            (
+                "sender_key_distributions",
+                DumpValue::List(sender_key_distributions),
+            ),
+        ]);
+        dump.render(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KeyPair;
+
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn test_render() {
+        let value = DumpValue::Object(vec![
+            ("name", "a \"quoted\"\nname".into()),
+            ("public", bytes(&[0xAB, 0xCD])),
+            ("private", secret(&[1, 2, 3])),
+            ("missing", Option::<u32>::None.into()),
+            ("list", vec![1u32, 2].into()),
+        ]);
+        assert_eq!(
+            value.render(false),
+            r#"{
+  "name": "a \"quoted\"\nname",
+  "public": "abcd",
+  "private": "<redacted 3 bytes>",
+  "missing": null,
+  "list": [
+    1,
+    2
+  ]
+}"#
+        );
+        assert!(value.render(true).contains(r#""private": "010203""#));
+    }
+
+    #[test]
+    fn test_sender_key_distribution_message_redaction() -> Result<()> {
+        let signing_key = KeyPair::generate(&mut OsRng).public_key;
+        let chain_key = [0x42; 32];
+        let message = SenderKeyDistributionMessage::new(7, 3, &chain_key, signing_key)?;
+
+        let redacted = message.debug_dump(false)?;
+        assert!(redacted.contains(r#""id": 7"#));
+        assert!(!redacted.contains(&hex::encode(chain_key)));
+
+        let full = message.debug_dump(true)?;
+        assert!(full.contains(&hex::encode(chain_key)));
+        Ok(())
+    }
+}
@@ -3,13 +3,142 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use crate::consts;
 use crate::curve::KeyType;
+use crate::{CiphertextMessageType, ProtocolAddress};
 
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::fmt;
+#[cfg(feature = "std")]
 use std::error::Error;
-use std::fmt;
+#[cfg(feature = "std")]
 use std::panic::UnwindSafe;
 
-pub type Result<T> = std::result::Result<T, SignalProtocolError>;
+pub type Result<T> = core::result::Result<T, SignalProtocolError>;
+
+/// Stands in for `std::error::Error` in `no_std` builds, for errors reported by application
+/// callbacks.
+#[cfg(not(feature = "std"))]
+pub trait Error: fmt::Debug + fmt::Display {}
+
+#[cfg(not(feature = "std"))]
+impl<T: fmt::Debug + fmt::Display + ?Sized> Error for T {}
+
+#[cfg(feature = "std")]
+type CallbackError = Box<dyn Error + Send + UnwindSafe + 'static>;
+#[cfg(not(feature = "std"))]
+type CallbackError = Box<dyn Error + Send + 'static>;
+
+/// Describes the operation that was in progress when an error occurred.
+///
+/// Attached to errors with [`SignalProtocolError::with_context`]; an error that passes through
+/// several operations carries one context per operation, innermost last.
+#[derive(Debug, Clone)]
+pub struct ErrorContext {
+    operation: &'static str,
+    address: Option<ProtocolAddress>,
+    message_type: Option<CiphertextMessageType>,
+}
+
+impl ErrorContext {
+    pub fn new(operation: &'static str) -> Self {
+        Self {
+            operation,
+            address: None,
+            message_type: None,
+        }
+    }
+
+    pub fn with_address(mut self, address: &ProtocolAddress) -> Self {
+        self.address = Some(address.clone());
+        self
+    }
+
+    pub fn with_message_type(mut self, message_type: CiphertextMessageType) -> Self {
+        self.message_type = Some(message_type);
+        self
+    }
+
+    pub fn operation(&self) -> &'static str {
+        self.operation
+    }
+
+    pub fn address(&self) -> Option<&ProtocolAddress> {
+        self.address.as_ref()
+    }
+
+    pub fn message_type(&self) -> Option<CiphertextMessageType> {
+        self.message_type
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.operation)?;
+        match (&self.address, self.message_type) {
+            (None, None) => Ok(()),
+            (Some(address), None) => write!(f, "(address={})", address),
+            (None, Some(message_type)) => write!(f, "(message_type={:?})", message_type),
+            (Some(address), Some(message_type)) => {
+                write!(f, "(address={}, message_type={:?})", address, message_type)
+            }
+        }
+    }
+}
+
+/// A structural limit on a [`SessionRecord`](crate::SessionRecord) or
+/// [`SenderKeyRecord`](crate::SenderKeyRecord).
+///
+/// Records never grow past these limits as they are updated, so a stored record that exceeds one
+/// is corrupt, and is rejected when it is deserialized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordLimit {
+    /// See [`ARCHIVED_STATES_MAX_LENGTH`](crate::ARCHIVED_STATES_MAX_LENGTH).
+    ArchivedSessionStates,
+    /// See [`MAX_RECEIVER_CHAINS`](crate::MAX_RECEIVER_CHAINS).
+    ReceiverChains,
+    /// See [`MAX_MESSAGE_KEYS`](crate::MAX_MESSAGE_KEYS).
+    MessageKeys,
+    /// See [`MAX_DECRYPTED_MESSAGE_MACS`](crate::MAX_DECRYPTED_MESSAGE_MACS).
+    DecryptedMessageMacs,
+    /// See [`MAX_SENDER_KEY_STATES`](crate::MAX_SENDER_KEY_STATES).
+    SenderKeyStates,
+}
+
+impl RecordLimit {
+    /// The largest permitted count.
+    pub fn max(self) -> usize {
+        match self {
+            RecordLimit::ArchivedSessionStates => consts::ARCHIVED_STATES_MAX_LENGTH,
+            RecordLimit::ReceiverChains => consts::MAX_RECEIVER_CHAINS,
+            RecordLimit::MessageKeys => consts::MAX_MESSAGE_KEYS,
+            RecordLimit::DecryptedMessageMacs => consts::MAX_DECRYPTED_MESSAGE_MACS,
+            RecordLimit::SenderKeyStates => consts::MAX_SENDER_KEY_STATES,
+        }
+    }
+
+    /// Fails with [`SignalProtocolError::RecordLimitExceeded`] if `count` is over the limit.
+    pub(crate) fn check(self, count: usize) -> Result<()> {
+        if count > self.max() {
+            return Err(SignalProtocolError::RecordLimitExceeded(self, count));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for RecordLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            RecordLimit::ArchivedSessionStates => "archived session states",
+            RecordLimit::ReceiverChains => "receiver chains",
+            RecordLimit::MessageKeys => "message keys",
+            RecordLimit::DecryptedMessageMacs => "decrypted message MACs",
+            RecordLimit::SenderKeyStates => "sender key states",
+        };
+        write!(f, "{}", description)
+    }
+}
 
 #[derive(Debug)]
 pub enum SignalProtocolError {
@@ -49,27 +178,84 @@ pub enum SignalProtocolError {
     InvalidCiphertext,
 
     NoSenderKeyState,
+    InvalidSenderKeySession(crate::SenderKeyName),
 
     SessionNotFound(String),
     InvalidSessionStructure,
+    RecordLimitExceeded(RecordLimit, usize),
+    InvalidRegistrationId(crate::ProtocolAddress, u32),
 
     DuplicatedMessage(u32, u32),
     InvalidMessage(&'static str),
     InternalError(&'static str),
     FfiBindingError(String),
-    ApplicationCallbackError(&'static str, Box<dyn Error + Send + UnwindSafe + 'static>),
+    ApplicationCallbackError(&'static str, CallbackError),
 
     InvalidSealedSenderMessage(String),
     UnknownSealedSenderVersion(u8),
     SealedSenderSelfSend,
+    SealedSenderCertificateExpired(u64),
+    SealedSenderReplay,
+
+    WithContext(ErrorContext, Box<SignalProtocolError>),
 }
 
+impl SignalProtocolError {
+    /// Records that this error happened while performing `context`'s operation.
+    pub fn with_context(self, context: ErrorContext) -> Self {
+        SignalProtocolError::WithContext(context, Box::new(self))
+    }
+
+    /// Returns the error underneath any contexts, for deciding how to handle it.
+    pub fn root_cause(&self) -> &Self {
+        let mut error = self;
+        while let SignalProtocolError::WithContext(_, inner) = error {
+            error = inner;
+        }
+        error
+    }
+
+    /// Like [`root_cause`](Self::root_cause), but discards the contexts.
+    pub fn into_root_cause(self) -> Self {
+        let mut error = self;
+        while let SignalProtocolError::WithContext(_, inner) = error {
+            error = *inner;
+        }
+        error
+    }
+
+    /// Returns the contexts attached to this error, outermost first.
+    pub fn contexts(&self) -> impl Iterator<Item = &ErrorContext> {
+        let mut error = self;
+        core::iter::from_fn(move || match error {
+            SignalProtocolError::WithContext(context, inner) => {
+                error = inner;
+                Some(context)
+            }
+            _ => None,
+        })
+    }
+}
+
+/// Attaches an [`ErrorContext`] to the error in a [`Result`], if there is one.
+pub(crate) trait ResultExt {
+    fn with_context(self, context: impl FnOnce() -> ErrorContext) -> Self;
+}
+
+impl<T> ResultExt for Result<T> {
+    fn with_context(self, context: impl FnOnce() -> ErrorContext) -> Self {
+        self.map_err(|e| e.with_context(context()))
+    }
+}
+
+#[cfg(feature = "std")]
 impl Error for SignalProtocolError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             SignalProtocolError::ProtobufEncodingError(e) => Some(e),
             SignalProtocolError::ProtobufDecodingError(e) => Some(e),
             SignalProtocolError::ApplicationCallbackError(_, e) => Some(e.as_ref()),
+            SignalProtocolError::WithContext(_, e) => Some(e.as_ref()),
             _ => None,
         }
     }
@@ -162,6 +348,20 @@ impl fmt::Display for SignalProtocolError {
                 write!(f, "session with '{}' not found", who)
             }
             SignalProtocolError::InvalidSessionStructure => write!(f, "invalid session structure"),
+            SignalProtocolError::RecordLimitExceeded(limit, count) => write!(
+                f,
+                "record has {} {}, more than the limit of {}",
+                count,
+                limit,
+                limit.max()
+            ),
+            SignalProtocolError::InvalidRegistrationId(addr, id) => {
+                write!(
+                    f,
+                    "session with {} uses invalid registration ID {}",
+                    addr, id
+                )
+            }
             SignalProtocolError::DuplicatedMessage(i, c) => {
                 write!(f, "message with old counter {} / {}", i, c)
             }
@@ -169,6 +369,9 @@ impl fmt::Display for SignalProtocolError {
             SignalProtocolError::InternalError(m) => write!(f, "internal error {}", m),
             SignalProtocolError::InvalidSenderKeyId => write!(f, "invalid send key id"),
             SignalProtocolError::NoSenderKeyState => write!(f, "no sender key state"),
+            SignalProtocolError::InvalidSenderKeySession(name) => {
+                write!(f, "no sender key session for {}", name)
+            }
             SignalProtocolError::FfiBindingError(m) => {
                 write!(f, "error while invoking an ffi callback: {}", m)
             }
@@ -184,6 +387,108 @@ impl fmt::Display for SignalProtocolError {
             SignalProtocolError::SealedSenderSelfSend => {
                 write!(f, "self send of a sealed sender message")
             }
+            SignalProtocolError::SealedSenderCertificateExpired(expiration) => {
+                write!(f, "sender certificate expired at {}", expiration)
+            }
+            SignalProtocolError::SealedSenderReplay => {
+                write!(f, "sealed sender message was already received")
+            }
+            SignalProtocolError::WithContext(context, e) => write!(f, "{}: {}", context, e),
         }
     }
 }
+
+/// What a client should do about a message that failed to decrypt.
+///
+/// See [`classify_decryption_failure`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecryptionFailureAction {
+    /// The failure is expected to go away; keep the message and try again later.
+    ///
+    /// For example, a message from a sender whose identity changed can be decrypted once the new
+    /// identity has been approved, and one whose sender certificate just expired may be accepted
+    /// with a grace period once the clocks are checked.
+    Retryable,
+    /// The session with the sender is missing or out of sync. The message cannot be recovered,
+    /// but the sender should be asked to start a new session (refetching our pre-key bundle).
+    NeedsNewSession,
+    /// The message is malformed, a duplicate, or otherwise never decryptable; drop it.
+    PermanentlyInvalid,
+    /// One of the application's stores failed; the message itself may be fine.
+    StoreError,
+}
+
+/// Decides what to do about `error`, returned from decrypting an incoming message.
+///
+/// Contexts attached to the error are ignored.
+pub fn classify_decryption_failure(error: &SignalProtocolError) -> DecryptionFailureAction {
+    use DecryptionFailureAction::*;
+    match error.root_cause() {
+        SignalProtocolError::UntrustedIdentity(_)
+        | SignalProtocolError::SealedSenderCertificateExpired(_) => Retryable,
+
+        SignalProtocolError::SessionNotFound(_)
+        | SignalProtocolError::InvalidSessionStructure
+        | SignalProtocolError::RecordLimitExceeded(_, _)
+        | SignalProtocolError::InvalidRegistrationId(_, _)
+        | SignalProtocolError::InvalidPreKeyId
+        | SignalProtocolError::InvalidSignedPreKeyId
+        | SignalProtocolError::InvalidSenderKeyId
+        | SignalProtocolError::NoSenderKeyState
+        | SignalProtocolError::InvalidSenderKeySession(_)
+        | SignalProtocolError::InvalidMessage(_)
+        | SignalProtocolError::InvalidCiphertext
+        | SignalProtocolError::InvalidRootKeyLength(_)
+        | SignalProtocolError::InvalidChainKeyLength(_)
+        | SignalProtocolError::InvalidMacKeyLength(_)
+        | SignalProtocolError::InvalidCipherCryptographicParameters(_, _) => NeedsNewSession,
+
+        SignalProtocolError::InvalidArgument(_)
+        | SignalProtocolError::InvalidState(_, _)
+        | SignalProtocolError::ProtobufDecodingError(_)
+        | SignalProtocolError::ProtobufEncodingError(_)
+        | SignalProtocolError::InvalidProtobufEncoding
+        | SignalProtocolError::CiphertextMessageTooShort(_)
+        | SignalProtocolError::LegacyCiphertextVersion(_)
+        | SignalProtocolError::UnrecognizedCiphertextVersion(_)
+        | SignalProtocolError::UnrecognizedMessageVersion(_)
+        | SignalProtocolError::FingerprintIdentifierMismatch
+        | SignalProtocolError::FingerprintVersionMismatch(_, _)
+        | SignalProtocolError::FingerprintParsingError
+        | SignalProtocolError::NoKeyTypeIdentifier
+        | SignalProtocolError::BadKeyType(_)
+        | SignalProtocolError::BadKeyLength(_, _)
+        | SignalProtocolError::SignatureValidationFailed
+        | SignalProtocolError::DuplicatedMessage(_, _)
+        | SignalProtocolError::InternalError(_)
+        | SignalProtocolError::InvalidSealedSenderMessage(_)
+        | SignalProtocolError::UnknownSealedSenderVersion(_)
+        | SignalProtocolError::SealedSenderSelfSend
+        | SignalProtocolError::SealedSenderReplay => PermanentlyInvalid,
+
+        SignalProtocolError::FfiBindingError(_)
+        | SignalProtocolError::ApplicationCallbackError(_, _) => StoreError,
+
+        SignalProtocolError::WithContext(_, _) => unreachable!("contexts already removed"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_classify_decryption_failure_ignores_context() {
+        let error = SignalProtocolError::SessionNotFound("+14151111111.1".to_string())
+            .with_context(ErrorContext::new("message_decrypt"));
+        assert_eq!(
+            classify_decryption_failure(&error),
+            DecryptionFailureAction::NeedsNewSession
+        );
+        assert_eq!(
+            classify_decryption_failure(&SignalProtocolError::DuplicatedMessage(1, 2)),
+            DecryptionFailureAction::PermanentlyInvalid
+        );
+    }
+}
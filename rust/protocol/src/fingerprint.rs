@@ -5,9 +5,14 @@
 
 use crate::proto;
 use crate::{IdentityKey, Result, SignalProtocolError};
+
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
 use prost::Message;
 use sha2::{digest::Digest, Sha512};
-use std::fmt;
 use subtle::ConstantTimeEq;
 
 #[derive(Debug, Clone)]
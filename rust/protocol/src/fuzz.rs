@@ -0,0 +1,158 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Fuzzing entry points, compiled only with `--cfg fuzzing` (as set by `cargo fuzz`).
+//!
+//! Each function takes arbitrary bytes, parses them as one of the crate's untrusted input formats,
+//! and exercises the accessors of anything that parses successfully. Parse failures are expected
+//! and ignored; the only bugs these can find are panics, hangs, and excessive allocation. Fuzz
+//! targets should call these directly so that the harness code lives next to the parsers.
+//!
+//! The signatures here are stable: adding coverage to an entry point is fine, but renaming or
+//! removing one will break downstream fuzz targets.
+
+use crate::{
+    CiphertextMessage, CiphertextMessageType, PreKeySignalMessage, ScannableFingerprint,
+    SenderCertificate, SenderKeyDistributionMessage, SenderKeyMessage, SessionRecord,
+    SignalMessage, UnidentifiedSenderMessage, UnidentifiedSenderMessageContent,
+};
+
+use core::convert::TryFrom;
+
+fn exercise_signal_message(message: &SignalMessage) {
+    let _ = message.message_version();
+    let _ = message.sender_ratchet_key().map(|key| key.serialize());
+    let _ = message.counter();
+    let _ = message.previous_counter();
+    let _ = message.body();
+}
+
+/// Parses a `SignalMessage`, including its version byte and MAC.
+pub fn signal_message(data: &[u8]) {
+    if let Ok(message) = SignalMessage::try_from(data) {
+        exercise_signal_message(&message);
+    }
+}
+
+/// Parses a `PreKeySignalMessage` and the `SignalMessage` embedded in it.
+pub fn pre_key_signal_message(data: &[u8]) {
+    if let Ok(message) = PreKeySignalMessage::try_from(data) {
+        let _ = message.message_version();
+        let _ = message.registration_id();
+        let _ = message.pre_key_id();
+        let _ = message.signed_pre_key_id();
+        let _ = message.base_key().serialize();
+        let _ = message.identity_key().serialize();
+        exercise_signal_message(message.message());
+    }
+}
+
+/// Parses the group messaging formats, `SenderKeyMessage` and `SenderKeyDistributionMessage`.
+pub fn sender_key_messages(data: &[u8]) {
+    if let Ok(message) = SenderKeyMessage::try_from(data) {
+        let _ = message.key_id();
+        let _ = message.iteration();
+        let _ = message.ciphertext();
+    }
+    if let Ok(message) = SenderKeyDistributionMessage::try_from(data) {
+        let _ = message.id();
+        let _ = message.iteration();
+        let _ = message.chain_key();
+        let _ = message.signing_key();
+    }
+}
+
+/// Parses `data` as every [CiphertextMessageType], using the first byte to pick the type.
+pub fn ciphertext_message(data: &[u8]) {
+    let (type_byte, rest) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    let message_type = match CiphertextMessageType::try_from(*type_byte) {
+        Ok(message_type) => message_type,
+        Err(_) => return,
+    };
+    if let Ok(message) = CiphertextMessage::deserialize(message_type, rest) {
+        let _ = message.message_type();
+        let _ = message.message_version();
+        let _ = message.serialize();
+    }
+}
+
+/// Parses a serialized `SessionRecord`, walks its current state, and checks that it survives a
+/// round trip through `serialize`.
+pub fn session_record(data: &[u8]) {
+    let record = match SessionRecord::deserialize(data) {
+        Ok(record) => record,
+        Err(_) => return,
+    };
+    let _ = record.has_current_session_state();
+    let _ = record.session_version();
+    let _ = record.local_registration_id();
+    let _ = record.remote_registration_id();
+    let _ = record.local_identity_key_bytes();
+    let _ = record.remote_identity_key_bytes();
+    let _ = record.has_sender_chain();
+    let _ = record.alice_base_key();
+    let _ = record.get_sender_chain_key_bytes();
+
+    if let Ok(serialized) = record.serialize() {
+        SessionRecord::deserialize(&serialized)
+            .expect("a serialized SessionRecord can be deserialized");
+    }
+}
+
+/// Parses an outer sealed-sender envelope.
+pub fn sealed_sender_message(data: &[u8]) {
+    if let Ok(message) = UnidentifiedSenderMessage::deserialize(data) {
+        let _ = message.version();
+        let _ = message.ephemeral_public();
+        let _ = message.encrypted_static();
+        let _ = message.encrypted_message();
+    }
+}
+
+/// Parses the decrypted contents of a sealed-sender envelope, including the sender certificate
+/// and the server certificate nested inside it.
+pub fn sealed_sender_message_content(data: &[u8]) {
+    if let Ok(content) = UnidentifiedSenderMessageContent::deserialize(data) {
+        let _ = content.msg_type();
+        let _ = content.contents();
+        if let Ok(sender) = content.sender() {
+            exercise_sender_certificate(sender);
+        }
+    }
+}
+
+/// Parses a `SenderCertificate` on its own.
+pub fn sender_certificate(data: &[u8]) {
+    if let Ok(certificate) = SenderCertificate::deserialize(data) {
+        exercise_sender_certificate(&certificate);
+    }
+}
+
+fn exercise_sender_certificate(certificate: &SenderCertificate) {
+    let _ = certificate.sender_uuid();
+    let _ = certificate.sender_e164();
+    let _ = certificate.sender_device_id();
+    let _ = certificate.expiration();
+    let _ = certificate.key();
+    if let Ok(signer) = certificate.signer() {
+        let _ = signer.key_id();
+        if let Ok(key) = signer.public_key() {
+            let _ = certificate.validate(&key, 0);
+        }
+    }
+}
+
+/// Parses the payload of a scannable safety-number QR code and compares it against itself.
+pub fn scannable_fingerprint(data: &[u8]) {
+    if let Ok(fingerprint) = ScannableFingerprint::deserialize(data) {
+        let _ = fingerprint.compare(data);
+        if let Ok(serialized) = fingerprint.serialize() {
+            let _ = fingerprint.compare(&serialized);
+        }
+    }
+}
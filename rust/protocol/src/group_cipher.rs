@@ -3,18 +3,47 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use crate::audit;
 use crate::consts;
 use crate::crypto;
 
+use crate::error::ResultExt;
+use crate::metrics;
 use crate::{
-    Context, KeyPair, Result, SenderKeyDistributionMessage, SenderKeyMessage, SenderKeyName,
-    SenderKeyRecord, SenderKeyStore, SignalProtocolError,
+    AuditEvent, CiphertextMessageType, Context, ErrorContext, KeyPair, Result,
+    SenderKeyDistributionMessage, SenderKeyMessage, SenderKeyName, SenderKeyRecord, SenderKeyStore,
+    SignalProtocolError,
 };
 
 use crate::sender_keys::{SenderKeyState, SenderMessageKey};
 
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
 use rand::{CryptoRng, Rng};
-use std::convert::TryFrom;
+
+fn sender_key_context(operation: &'static str, sender_key_id: &SenderKeyName) -> ErrorContext {
+    let context = ErrorContext::new(operation).with_message_type(CiphertextMessageType::SenderKey);
+    match sender_key_id.sender() {
+        Ok(sender) => context.with_address(&sender),
+        Err(_) => context,
+    }
+}
+
+/// Reports a missing sender key record or state as a missing session for `sender_key_id`.
+fn no_session(sender_key_id: &SenderKeyName) -> SignalProtocolError {
+    SignalProtocolError::InvalidSenderKeySession(sender_key_id.clone())
+}
+
+fn or_no_session(
+    sender_key_id: &SenderKeyName,
+) -> impl Fn(SignalProtocolError) -> SignalProtocolError + '_ {
+    move |e| match e {
+        SignalProtocolError::NoSenderKeyState => no_session(sender_key_id),
+        e => e,
+    }
+}
 
 pub async fn group_encrypt<R: Rng + CryptoRng>(
     sender_key_store: &mut dyn SenderKeyStore,
@@ -23,35 +52,41 @@ pub async fn group_encrypt<R: Rng + CryptoRng>(
     csprng: &mut R,
     ctx: Context,
 ) -> Result<Vec<u8>> {
-    let mut record = sender_key_store
-        .load_sender_key(&sender_key_id, ctx)
-        .await?
-        .ok_or(SignalProtocolError::InvalidSenderKeyId)?;
+    let result: Result<_> = async {
+        let mut record = sender_key_store
+            .load_sender_key(&sender_key_id, ctx)
+            .await?
+            .ok_or_else(|| no_session(sender_key_id))?;
 
-    let sender_key_state = record.sender_key_state()?;
+        let sender_key_state = record
+            .sender_key_state()
+            .map_err(or_no_session(sender_key_id))?;
 
-    let sender_key = sender_key_state.sender_chain_key()?.sender_message_key()?;
+        let sender_key = sender_key_state.sender_chain_key()?.sender_message_key()?;
 
-    let ciphertext =
-        crypto::aes_256_cbc_encrypt(plaintext, &sender_key.cipher_key()?, &sender_key.iv()?)?;
+        let ciphertext =
+            crypto::aes_256_cbc_encrypt(plaintext, &sender_key.cipher_key()?, &sender_key.iv()?)?;
 
-    let signing_key = sender_key_state.signing_key_private()?;
+        let signing_key = sender_key_state.signing_key_private()?;
 
-    let skm = SenderKeyMessage::new(
-        sender_key_state.sender_key_id()?,
-        sender_key.iteration()?,
-        &ciphertext,
-        csprng,
-        &signing_key,
-    )?;
+        let skm = SenderKeyMessage::new(
+            sender_key_state.sender_key_id()?,
+            sender_key.iteration()?,
+            &ciphertext,
+            csprng,
+            &signing_key,
+        )?;
 
-    sender_key_state.set_sender_chain_key(sender_key_state.sender_chain_key()?.next()?)?;
+        sender_key_state.set_sender_chain_key(sender_key_state.sender_chain_key()?.next()?)?;
 
-    sender_key_store
-        .store_sender_key(sender_key_id, &record, ctx)
-        .await?;
+        sender_key_store
+            .store_sender_key(sender_key_id, &record, ctx)
+            .await?;
 
-    Ok(skm.serialized().to_vec())
+        Ok(skm.serialized().to_vec())
+    }
+    .await;
+    result.with_context(|| sender_key_context("group_encrypt", sender_key_id))
 }
 
 fn get_sender_key(state: &mut SenderKeyState, iteration: u32) -> Result<SenderMessageKey> {
@@ -92,33 +127,240 @@ pub async fn group_decrypt(
     sender_key_id: &SenderKeyName,
     ctx: Context,
 ) -> Result<Vec<u8>> {
+    let result: Result<_> = async {
+        let mut record = sender_key_store
+            .load_sender_key(&sender_key_id, ctx)
+            .await?
+            .ok_or_else(|| no_session(sender_key_id))?;
+
+        let skm = SenderKeyMessage::try_from(skm_bytes)?;
+
+        let mut sender_key_state = record
+            .sender_key_state_for_keyid(skm.key_id())
+            .map_err(or_no_session(sender_key_id))?;
+
+        let signing_key = sender_key_state.signing_key_public()?;
+        if !skm.verify_signature(&signing_key)? {
+            return Err(SignalProtocolError::SignatureValidationFailed);
+        }
+
+        let sender_key = get_sender_key(&mut sender_key_state, skm.iteration())?;
+
+        let plaintext = crypto::aes_256_cbc_decrypt(
+            skm.ciphertext(),
+            &sender_key.cipher_key()?,
+            &sender_key.iv()?,
+        )?;
+
+        sender_key_store
+            .store_sender_key(sender_key_id, &record, ctx)
+            .await?;
+
+        Ok(plaintext)
+    }
+    .await;
+    metrics::record_decryption(&result);
+    result.with_context(|| sender_key_context("group_decrypt", sender_key_id))
+}
+
+/// Decrypts several messages from the same sender, loading and storing its sender key once.
+///
+/// Messages that share a sender key chain have their signatures checked together in a single
+/// batch; if that fails, each of them is checked on its own so that only the bad ones are
+/// rejected. The results are in the same order as `skm_bytes`, and the record is stored only if
+/// at least one message was decrypted.
+pub async fn group_decrypt_batch<R: Rng + CryptoRng>(
+    skm_bytes: &[&[u8]],
+    sender_key_store: &mut dyn SenderKeyStore,
+    sender_key_id: &SenderKeyName,
+    csprng: &mut R,
+    ctx: Context,
+) -> Result<Vec<Result<Vec<u8>>>> {
+    let result = decrypt_batch(skm_bytes, sender_key_store, sender_key_id, csprng, ctx).await;
+    match &result {
+        Ok(results) => results.iter().for_each(metrics::record_decryption),
+        // None of the messages were decrypted.
+        Err(_) => skm_bytes
+            .iter()
+            .for_each(|_| metrics::record_decryption(&result)),
+    }
+    result
+}
+
+async fn decrypt_batch<R: Rng + CryptoRng>(
+    skm_bytes: &[&[u8]],
+    sender_key_store: &mut dyn SenderKeyStore,
+    sender_key_id: &SenderKeyName,
+    csprng: &mut R,
+    ctx: Context,
+) -> Result<Vec<Result<Vec<u8>>>> {
     let mut record = sender_key_store
         .load_sender_key(&sender_key_id, ctx)
         .await?
-        .ok_or(SignalProtocolError::InvalidSenderKeyId)?;
+        .ok_or_else(|| no_session(sender_key_id))?;
 
-    let skm = SenderKeyMessage::try_from(skm_bytes)?;
+    let skms: Vec<Result<SenderKeyMessage>> = skm_bytes
+        .iter()
+        .map(|bytes| SenderKeyMessage::try_from(*bytes))
+        .collect();
 
-    let mut sender_key_state = record.sender_key_state_for_keyid(skm.key_id())?;
+    let mut by_key_id: BTreeMap<u32, Vec<&SenderKeyMessage>> = BTreeMap::new();
+    for skm in skms.iter().flatten() {
+        by_key_id.entry(skm.key_id()).or_default().push(skm);
+    }
 
-    let signing_key = sender_key_state.signing_key_public()?;
-    if !skm.verify_signature(&signing_key)? {
-        return Err(SignalProtocolError::SignatureValidationFailed);
+    let mut verified_key_ids = BTreeSet::new();
+    for (key_id, messages) in by_key_id {
+        if messages.len() < 2 {
+            continue;
+        }
+        let signing_key = match record
+            .sender_key_state_for_keyid(key_id)
+            .and_then(|state| state.signing_key_public())
+        {
+            Ok(signing_key) => signing_key,
+            // Leave it to the individual checks below to report the error for each message.
+            Err(_) => continue,
+        };
+        if SenderKeyMessage::verify_signatures(&messages, &signing_key, csprng)? {
+            verified_key_ids.insert(key_id);
+        }
     }
 
-    let sender_key = get_sender_key(&mut sender_key_state, skm.iteration())?;
+    let mut any_decrypted = false;
+    let results = skms
+        .into_iter()
+        .map(|skm| {
+            let skm = skm?;
+            let sender_key_state = record
+                .sender_key_state_for_keyid(skm.key_id())
+                .map_err(or_no_session(sender_key_id))?;
+
+            if !verified_key_ids.contains(&skm.key_id()) {
+                let signing_key = sender_key_state.signing_key_public()?;
+                if !skm.verify_signature(&signing_key)? {
+                    return Err(SignalProtocolError::SignatureValidationFailed);
+                }
+            }
+
+            let sender_key = get_sender_key(sender_key_state, skm.iteration())?;
+
+            let plaintext = crypto::aes_256_cbc_decrypt(
+                skm.ciphertext(),
+                &sender_key.cipher_key()?,
+                &sender_key.iv()?,
+            )?;
+            any_decrypted = true;
+            Ok(plaintext)
+        })
+        .collect();
+
+    if any_decrypted {
+        sender_key_store
+            .store_sender_key(sender_key_id, &record, ctx)
+            .await?;
+    }
 
-    let plaintext = crypto::aes_256_cbc_decrypt(
-        skm.ciphertext(),
-        &sender_key.cipher_key()?,
-        &sender_key.iv()?,
-    )?;
+    Ok(results)
+}
 
-    sender_key_store
-        .store_sender_key(sender_key_id, &record, ctx)
-        .await?;
+/// Returns the next iteration expected on the chain for `distribution_id` from `sender_key_id`.
+///
+/// A message whose iteration is too far past this position is rejected by [`group_decrypt`]; once
+/// the sender's position has been confirmed some other way, [`group_fast_forward`] can catch up.
+pub async fn group_chain_position(
+    sender_key_store: &mut dyn SenderKeyStore,
+    sender_key_id: &SenderKeyName,
+    distribution_id: u32,
+    ctx: Context,
+) -> Result<u32> {
+    let result: Result<_> = async {
+        let mut record = sender_key_store
+            .load_sender_key(&sender_key_id, ctx)
+            .await?
+            .ok_or_else(|| no_session(sender_key_id))?;
+
+        let sender_key_state = record
+            .sender_key_state_for_keyid(distribution_id)
+            .map_err(or_no_session(sender_key_id))?;
+        sender_key_state.sender_chain_key()?.iteration()
+    }
+    .await;
+    result.with_context(|| sender_key_context("group_chain_position", sender_key_id))
+}
+
+/// Advances the chain for `distribution_id` from `sender_key_id` so that `to_iteration` is the next
+/// iteration expected.
+///
+/// Unlike [`group_decrypt`], this does not keep the keys it skips over, so messages from the
+/// skipped iterations can no longer be decrypted. Only call this once the sender's position has
+/// been verified out of band. Moving the chain backwards is an error, as is moving it forward by
+/// more than [`MAX_FAST_FORWARD_JUMPS`](crate::MAX_FAST_FORWARD_JUMPS) iterations at once.
+///
+/// Reports [`AuditEvent::SenderKeyFastForwarded`] once the new position has been stored.
+pub async fn group_fast_forward(
+    sender_key_store: &mut dyn SenderKeyStore,
+    sender_key_id: &SenderKeyName,
+    distribution_id: u32,
+    to_iteration: u32,
+    ctx: Context,
+) -> Result<()> {
+    let result: Result<_> = async {
+        let mut record = sender_key_store
+            .load_sender_key(&sender_key_id, ctx)
+            .await?
+            .ok_or_else(|| no_session(sender_key_id))?;
+
+        let sender_key_state = record
+            .sender_key_state_for_keyid(distribution_id)
+            .map_err(or_no_session(sender_key_id))?;
+
+        let mut sender_chain_key = sender_key_state.sender_chain_key()?;
+        let from_iteration = sender_chain_key.iteration()?;
+        if to_iteration < from_iteration {
+            return Err(SignalProtocolError::InvalidState(
+                "group_fast_forward",
+                format!(
+                    "chain is already at iteration {}, past {}",
+                    from_iteration, to_iteration
+                ),
+            ));
+        }
+        if (to_iteration - from_iteration) as usize > consts::MAX_FAST_FORWARD_JUMPS {
+            return Err(SignalProtocolError::InvalidArgument(format!(
+                "cannot fast-forward more than {} iterations at once (from {} to {})",
+                consts::MAX_FAST_FORWARD_JUMPS,
+                from_iteration,
+                to_iteration
+            )));
+        }
 
-    Ok(plaintext)
+        while sender_chain_key.iteration()? < to_iteration {
+            sender_chain_key = sender_chain_key.next()?;
+        }
+        sender_key_state.set_sender_chain_key(sender_chain_key)?;
+
+        sender_key_store
+            .store_sender_key(sender_key_id, &record, ctx)
+            .await?;
+
+        log::warn!(
+            "Fast-forwarded sender key {} from {} from iteration {} to {}",
+            distribution_id,
+            sender_key_id,
+            from_iteration,
+            to_iteration
+        );
+        audit::audit(|| AuditEvent::SenderKeyFastForwarded {
+            sender_key_name: sender_key_id.clone(),
+            key_id: distribution_id,
+            from_iteration,
+            to_iteration,
+        });
+        Ok(())
+    }
+    .await;
+    result.with_context(|| sender_key_context("group_fast_forward", sender_key_id))
 }
 
 pub async fn process_sender_key_distribution_message(
@@ -172,6 +414,10 @@ pub async fn create_sender_key_distribution_message<R: Rng + CryptoRng>(
         sender_key_store
             .store_sender_key(sender_key_name, &sender_key_record, ctx)
             .await?;
+        audit::audit(|| AuditEvent::SenderKeyCreated {
+            sender_key_name: sender_key_name.clone(),
+            key_id: sender_key_id,
+        });
     }
 
     let state = sender_key_record.sender_key_state()?;
@@ -4,10 +4,14 @@
 //
 
 use crate::proto;
+use crate::redact::{nested, Redact};
 use crate::{KeyPair, PrivateKey, PublicKey, Result, SignalProtocolError};
 
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
 use rand::{CryptoRng, Rng};
-use std::convert::TryFrom;
 
 use prost::Message;
 
@@ -133,6 +137,71 @@ impl From<KeyPair> for IdentityKeyPair {
     }
 }
 
+/// Operations that use a private key without needing to read it.
+///
+/// Implement this to keep the local identity private key somewhere it can't be read out, such as
+/// a hardware keystore. Session setup only needs agreement; it never signs with the identity key.
+pub trait PrivateKeyOps {
+    /// Calculates the shared secret between this private key and `their_key`, as
+    /// [`PrivateKey::calculate_agreement`] does.
+    fn calculate_agreement(&self, their_key: &PublicKey) -> Result<Box<[u8]>>;
+}
+
+impl PrivateKeyOps for PrivateKey {
+    fn calculate_agreement(&self, their_key: &PublicKey) -> Result<Box<[u8]>> {
+        PrivateKey::calculate_agreement(self, their_key)
+    }
+}
+
+/// An identity key along with a way to use its private key, which may not be directly available.
+///
+/// See [`IdentityKeyStore::get_identity_key_ops`](crate::IdentityKeyStore::get_identity_key_ops).
+pub struct IdentityKeyPairOps<'a> {
+    identity_key: IdentityKey,
+    private_key: Box<dyn PrivateKeyOps + 'a>,
+}
+
+impl<'a> IdentityKeyPairOps<'a> {
+    pub fn new(identity_key: IdentityKey, private_key: Box<dyn PrivateKeyOps + 'a>) -> Self {
+        Self {
+            identity_key,
+            private_key,
+        }
+    }
+
+    #[inline]
+    pub fn identity_key(&self) -> &IdentityKey {
+        &self.identity_key
+    }
+
+    #[inline]
+    pub fn public_key(&self) -> &PublicKey {
+        self.identity_key.public_key()
+    }
+
+    #[inline]
+    pub fn private_key(&self) -> &dyn PrivateKeyOps {
+        &*self.private_key
+    }
+}
+
+impl From<IdentityKeyPair> for IdentityKeyPairOps<'_> {
+    fn from(pair: IdentityKeyPair) -> Self {
+        Self::new(pair.identity_key, Box::new(pair.private_key))
+    }
+}
+
+impl Redact for IdentityKeyPair {
+    fn fmt_redactable(&self, f: &mut fmt::Formatter, redact: bool) -> fmt::Result {
+        f.debug_struct("IdentityKeyPair")
+            .field("identity_key", &self.identity_key)
+            .field("private_key", &nested(&self.private_key, redact))
+            .finish()
+    }
+}
+
+impl_redacted_debug!(IdentityKeyPair);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -5,6 +5,9 @@
 
 use crate::{Result, SignalProtocolError};
 
+use alloc::boxed::Box;
+use alloc::vec;
+
 use hmac::{Hmac, Mac, NewMac};
 use sha2::Sha256;
 
@@ -24,6 +27,11 @@ impl HKDF {
             3 => Ok(HKDF {
                 iteration_start_offset: 1,
             }),
+            // Header-encrypted sessions derive keys the same way as version 3.
+            #[cfg(feature = "header-encryption")]
+            4 => Ok(HKDF {
+                iteration_start_offset: 1,
+            }),
             _ => Err(SignalProtocolError::UnrecognizedMessageVersion(
                 message_version,
             )),
@@ -51,8 +59,38 @@ impl HKDF {
         info: &[u8],
         output_length: usize,
     ) -> Result<Box<[u8]>> {
+        let mut output = vec![0u8; output_length].into_boxed_slice();
+        self.derive_salted_secrets_into(input_key_material, salt, info, &mut output)?;
+        Ok(output)
+    }
+
+    /// Like [derive_secrets](Self::derive_secrets), but fills `output` instead of allocating.
+    pub(crate) fn derive_secrets_into(
+        self,
+        input_key_material: &[u8],
+        info: &[u8],
+        output: &mut [u8],
+    ) -> Result<()> {
+        self.derive_salted_secrets_into(
+            input_key_material,
+            &[0u8; Self::HASH_OUTPUT_SIZE],
+            info,
+            output,
+        )
+    }
+
+    /// Like [derive_salted_secrets](Self::derive_salted_secrets), but fills `output` instead of
+    /// allocating.
+    pub(crate) fn derive_salted_secrets_into(
+        self,
+        input_key_material: &[u8],
+        salt: &[u8],
+        info: &[u8],
+        output: &mut [u8],
+    ) -> Result<()> {
         let prk = self.extract(salt, input_key_material)?;
-        self.expand(&prk, info, output_length)
+        self.expand(&prk, info, output);
+        Ok(())
     }
 
     fn extract(
@@ -63,29 +101,20 @@ impl HKDF {
         crate::crypto::hmac_sha256(salt, input_key_material)
     }
 
-    fn expand(
-        self,
-        prk: &[u8; Self::HASH_OUTPUT_SIZE],
-        info: &[u8],
-        output_length: usize,
-    ) -> Result<Box<[u8]>> {
-        let iterations = (output_length + Self::HASH_OUTPUT_SIZE - 1) / Self::HASH_OUTPUT_SIZE;
-        let mut result = Vec::<u8>::with_capacity(iterations * Self::HASH_OUTPUT_SIZE);
+    fn expand(self, prk: &[u8; Self::HASH_OUTPUT_SIZE], info: &[u8], output: &mut [u8]) {
         let mut mac =
             Hmac::<Sha256>::new_varkey(prk).expect("HMAC-SHA256 should accept any size key");
+        let mut previous_block = [0u8; Self::HASH_OUTPUT_SIZE];
 
-        for i in 0..iterations {
-            if result.len() >= Self::HASH_OUTPUT_SIZE {
-                mac.update(&result[(result.len() - Self::HASH_OUTPUT_SIZE)..]);
+        for (i, block) in output.chunks_mut(Self::HASH_OUTPUT_SIZE).enumerate() {
+            if i > 0 {
+                mac.update(&previous_block);
             }
             mac.update(info);
             mac.update(&[(i as u8) + self.iteration_start_offset]);
-            let d = mac.finalize_reset().into_bytes();
-            result.extend_from_slice(&d[..]);
+            previous_block = mac.finalize_reset().into_bytes().into();
+            block.copy_from_slice(&previous_block[..block.len()]);
         }
-
-        result.truncate(output_length);
-        Ok(result.into_boxed_slice())
     }
 }
 
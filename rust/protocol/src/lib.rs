@@ -3,64 +3,158 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+//! An implementation of the Signal Protocol.
+//!
+//! With default features disabled, the crate builds as `no_std` and only requires `alloc`. The
+//! ratchet, key agreement, and message formats are all available in that configuration. Anything
+//! that needs randomness takes a caller-provided [rand::CryptoRng], and anything that depends on
+//! the current time takes it as an argument, so there are no implicit std-only sources of either.
+//! (With `std`, sessions do note when their ratchet last stepped, but only for diagnostics.)
+//! The in-memory stores need the `std` feature.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![warn(clippy::unwrap_used)]
 #![deny(unsafe_code)]
 
+extern crate alloc;
+
+// Declared first so that its macro is available to the other modules.
+#[macro_use]
+mod redact;
+
+mod access_key;
 mod address;
+mod audit;
+#[cfg(feature = "backlog")]
+mod backlog;
+mod certificate_provider;
 mod consts;
+mod content;
 mod crypto;
 mod curve;
+mod debug_dump;
 pub mod error;
 mod fingerprint;
+#[cfg(fuzzing)]
+pub mod fuzz;
 mod group_cipher;
 mod identity_key;
 mod kdf;
+mod metrics;
+mod notification;
+mod padding;
+mod payment_address;
 mod proto;
 mod protocol;
+mod provisioning;
 mod ratchet;
 mod sealed_sender;
 mod sender_keys;
+#[cfg(feature = "serde")]
+mod serde_support;
 mod session;
 mod session_cipher;
+#[cfg(feature = "simulation")]
+pub mod simulation;
+mod snapshot;
 mod state;
 mod storage;
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
+#[cfg(feature = "tracing")]
+mod trace;
 mod utils;
 
 use error::Result;
 
 pub use {
-    address::ProtocolAddress,
+    access_key::{
+        blind_service_id, derive_access_key, ACCESS_KEY_SIZE, BLINDED_SERVICE_ID_SIZE,
+        PROFILE_KEY_SIZE,
+    },
+    address::{parse_service_id_list, serialize_service_id_list, ProtocolAddress},
+    audit::{AuditEvent, AuditHook},
+    certificate_provider::{sealed_sender_encrypt_with_provider, SenderCertificateProvider},
+    consts::{
+        ARCHIVED_STATES_MAX_LENGTH, MAX_DECRYPTED_MESSAGE_MACS, MAX_FAST_FORWARD_JUMPS,
+        MAX_MESSAGE_KEYS, MAX_RECEIVER_CHAINS, MAX_SENDER_KEY_STATES,
+    },
+    content::{
+        message_encrypt_content, AttachmentPointer, Content, DataMessageBuilder, ReceiptType,
+        TypingAction, MAX_ATTACHMENTS, MAX_INLINE_BODY_BYTES,
+    },
     curve::{KeyPair, PrivateKey, PublicKey},
-    error::SignalProtocolError,
+    error::{
+        classify_decryption_failure, DecryptionFailureAction, ErrorContext, RecordLimit,
+        SignalProtocolError,
+    },
     fingerprint::{DisplayableFingerprint, Fingerprint, ScannableFingerprint},
     group_cipher::{
-        create_sender_key_distribution_message, group_decrypt, group_encrypt,
+        create_sender_key_distribution_message, group_chain_position, group_decrypt,
+        group_decrypt_batch, group_encrypt, group_fast_forward,
         process_sender_key_distribution_message,
     },
-    identity_key::{IdentityKey, IdentityKeyPair},
+    identity_key::{IdentityKey, IdentityKeyPair, IdentityKeyPairOps, PrivateKeyOps},
     kdf::HKDF,
+    metrics::{
+        decryption_metrics, decryption_metrics_enabled, reset_decryption_metrics,
+        set_decryption_metrics_enabled, DecryptionMetrics, DecryptionResultClass,
+    },
+    notification::{notification_decrypt, CommitSet, NotificationDecryptionResult},
+    padding::PaddingPolicy,
+    payment_address::{sign_payment_address, verify_payment_address},
     protocol::{
         CiphertextMessage, CiphertextMessageType, PreKeySignalMessage,
         SenderKeyDistributionMessage, SenderKeyMessage, SignalMessage,
+        CIPHERTEXT_MESSAGE_CURRENT_VERSION, HEADER_ENCRYPTED_MESSAGE_VERSION,
     },
+    provisioning::{provisioning_encrypt, ProvisioningCipher, ProvisioningUuid},
     ratchet::{
         initialize_alice_session_record, initialize_bob_session_record,
         AliceSignalProtocolParameters, BobSignalProtocolParameters,
     },
+    redact::{Redact, Redactable},
     sealed_sender::{
-        sealed_sender_decrypt, sealed_sender_decrypt_to_usmc, sealed_sender_encrypt,
-        SealedSenderDecryptionResult, SenderCertificate, ServerCertificate,
-        UnidentifiedSenderMessage, UnidentifiedSenderMessageContent,
+        sealed_sender_decrypt, sealed_sender_decrypt_to_usmc,
+        sealed_sender_decrypt_with_grace_period, sealed_sender_decrypt_with_replay_cache,
+        sealed_sender_encrypt, SealedSenderDecryptionResult, SenderCertificate, ServerCertificate,
+        UnidentifiedSenderMessage, UnidentifiedSenderMessageContent, SEALED_SENDER_VERSION,
+    },
+    sender_keys::{SenderKeyDistributionTracker, SenderKeyName, SenderKeyRecord},
+    session::{
+        negotiate_session_version, process_prekey, process_prekey_bundle, MAX_SESSION_VERSION,
+        MIN_SESSION_VERSION,
     },
-    sender_keys::{SenderKeyName, SenderKeyRecord},
-    session::{process_prekey, process_prekey_bundle},
     session_cipher::{
-        message_decrypt, message_decrypt_prekey, message_decrypt_signal, message_encrypt,
+        message_decrypt, message_decrypt_prekey, message_decrypt_prekey_with_identity_override,
+        message_decrypt_returning_duplicates, message_decrypt_signal,
+        message_decrypt_signal_with_identity_override, message_decrypt_with_identity_override,
+        message_encrypt, message_encrypt_batch, message_encrypt_with_identity_override,
+        DecryptionOutcome,
+    },
+    snapshot::{
+        security_snapshot, SecuritySnapshot, SenderKeyDistributionSnapshot, SessionSnapshot,
+    },
+    state::{
+        ContentHint, OutgoingEnvelope, PreKeyBundle, PreKeyRecord, SessionRecord,
+        SignedPreKeyRecord,
     },
-    state::{PreKeyBundle, PreKeyRecord, SessionRecord, SignedPreKeyRecord},
     storage::{
-        Context, Direction, IdentityKeyStore, InMemIdentityKeyStore, InMemPreKeyStore,
-        InMemSenderKeyStore, InMemSessionStore, InMemSignalProtocolStore, InMemSignedPreKeyStore,
-        PreKeyStore, ProtocolStore, SenderKeyStore, SessionStore, SignedPreKeyStore,
+        Context, DeferredIdentityKeyStore, DeferredPreKeyStore, DeferredSenderKeyStore,
+        DeferredSessionStore, DeferredSignedPreKeyStore, Direction, IdentityKeyStore, MutationLog,
+        MutationRecorder, PreKeyStore, ProtocolStore, ReplayCache, SenderCertificateSource,
+        SenderKeyStore, SessionStore, SignedPreKeyStore,
     },
 };
+
+#[cfg(feature = "backlog")]
+pub use backlog::BacklogDecryptor;
+
+#[cfg(feature = "std")]
+pub use audit::set_audit_hook;
+
+#[cfg(feature = "std")]
+pub use storage::{
+    InMemIdentityKeyStore, InMemPreKeyStore, InMemReplayCache, InMemSenderKeyStore,
+    InMemSessionStore, InMemSignalProtocolStore, InMemSignedPreKeyStore,
+};
@@ -0,0 +1,194 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Process-wide counters of decryption results, for aggregate telemetry.
+//!
+//! Counting is off until [set_decryption_metrics_enabled] is called. Once it is on, every
+//! decryption of an incoming message (1:1, sealed sender, or group) adds one to the counter for
+//! its [DecryptionResultClass]. Only the class is recorded: no addresses, message contents, or
+//! timing, so the counts can be reported without revealing anything about individual messages.
+//!
+//! A sealed sender message that fails after it has been unsealed is counted by the 1:1 decryption
+//! of its contents, so each message is still only counted once.
+
+use crate::{Result, SignalProtocolError};
+
+use alloc::string::String;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// The kinds of decryption result that are counted separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DecryptionResultClass {
+    Success,
+    /// [`UntrustedIdentity`](SignalProtocolError::UntrustedIdentity).
+    UntrustedIdentity,
+    /// There was no session, sender key, or pre-key for the message.
+    NoSession,
+    /// The message failed its MAC or signature check.
+    InvalidMac,
+    /// [`DuplicatedMessage`](SignalProtocolError::DuplicatedMessage).
+    Duplicate,
+    /// The message was malformed, or used an unsupported version.
+    InvalidMessage,
+    /// The sealed sender envelope or sender certificate was rejected.
+    InvalidSealedSender,
+    /// One of the application's stores failed.
+    StoreError,
+    Other,
+}
+
+impl DecryptionResultClass {
+    /// Every class, in the order they are reported.
+    pub const ALL: [Self; 9] = [
+        Self::Success,
+        Self::UntrustedIdentity,
+        Self::NoSession,
+        Self::InvalidMac,
+        Self::Duplicate,
+        Self::InvalidMessage,
+        Self::InvalidSealedSender,
+        Self::StoreError,
+        Self::Other,
+    ];
+
+    /// The class of a failed decryption. Contexts attached to the error are ignored.
+    pub fn of_error(error: &SignalProtocolError) -> Self {
+        match error.root_cause() {
+            SignalProtocolError::UntrustedIdentity(_) => Self::UntrustedIdentity,
+
+            SignalProtocolError::SessionNotFound(_)
+            | SignalProtocolError::NoSenderKeyState
+            | SignalProtocolError::InvalidSenderKeySession(_)
+            | SignalProtocolError::InvalidPreKeyId
+            | SignalProtocolError::InvalidSignedPreKeyId
+            | SignalProtocolError::InvalidSenderKeyId => Self::NoSession,
+
+            SignalProtocolError::InvalidCiphertext
+            | SignalProtocolError::SignatureValidationFailed => Self::InvalidMac,
+
+            SignalProtocolError::DuplicatedMessage(_, _) => Self::Duplicate,
+
+            SignalProtocolError::InvalidMessage(_)
+            | SignalProtocolError::ProtobufDecodingError(_)
+            | SignalProtocolError::InvalidProtobufEncoding
+            | SignalProtocolError::CiphertextMessageTooShort(_)
+            | SignalProtocolError::LegacyCiphertextVersion(_)
+            | SignalProtocolError::UnrecognizedCiphertextVersion(_)
+            | SignalProtocolError::UnrecognizedMessageVersion(_) => Self::InvalidMessage,
+
+            SignalProtocolError::InvalidSealedSenderMessage(_)
+            | SignalProtocolError::UnknownSealedSenderVersion(_)
+            | SignalProtocolError::SealedSenderCertificateExpired(_)
+            | SignalProtocolError::SealedSenderSelfSend
+            | SignalProtocolError::SealedSenderReplay => Self::InvalidSealedSender,
+
+            SignalProtocolError::FfiBindingError(_)
+            | SignalProtocolError::ApplicationCallbackError(_, _) => Self::StoreError,
+
+            _ => Self::Other,
+        }
+    }
+
+    /// The class of a decryption's result.
+    pub fn of<T>(result: &Result<T>) -> Self {
+        match result {
+            Ok(_) => Self::Success,
+            Err(error) => Self::of_error(error),
+        }
+    }
+
+    /// The name used for this class in [DecryptionMetrics::report].
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::UntrustedIdentity => "untrusted_identity",
+            Self::NoSession => "no_session",
+            Self::InvalidMac => "invalid_mac",
+            Self::Duplicate => "duplicate",
+            Self::InvalidMessage => "invalid_message",
+            Self::InvalidSealedSender => "invalid_sealed_sender",
+            Self::StoreError => "store_error",
+            Self::Other => "other",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static COUNTS: [AtomicU64; 9] = [
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+];
+
+/// Turns counting on or off. Counts already recorded are kept either way.
+pub fn set_decryption_metrics_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn decryption_metrics_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Returns the counts recorded since the last [reset_decryption_metrics].
+pub fn decryption_metrics() -> DecryptionMetrics {
+    let mut counts = [0; 9];
+    for (count, counter) in counts.iter_mut().zip(COUNTS.iter()) {
+        *count = counter.load(Ordering::Relaxed);
+    }
+    DecryptionMetrics { counts }
+}
+
+/// Sets every count back to zero.
+pub fn reset_decryption_metrics() {
+    for counter in COUNTS.iter() {
+        counter.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Counts `result`, if counting is enabled.
+pub(crate) fn record_decryption<T>(result: &Result<T>) {
+    if decryption_metrics_enabled() {
+        COUNTS[DecryptionResultClass::of(result).index()].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// A snapshot of the decryption counts; see [decryption_metrics].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DecryptionMetrics {
+    counts: [u64; 9],
+}
+
+impl DecryptionMetrics {
+    pub fn count(&self, class: DecryptionResultClass) -> u64 {
+        self.counts[class.index()]
+    }
+
+    /// The number of decryptions counted, whatever their result.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Formats the counts as one `name count=N` line per class, in the order of
+    /// [DecryptionResultClass::ALL]. Classes with no decryptions are included.
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        for class in DecryptionResultClass::ALL.iter() {
+            let _ = writeln!(report, "{} count={}", class.name(), self.count(*class));
+        }
+        report
+    }
+}
@@ -0,0 +1,267 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Decrypting a sealed sender message for a push notification, without writing to any store.
+//!
+//! A notification extension typically runs in a separate process with tight memory and time
+//! limits, and may not be allowed to write to the app's database at all. [notification_decrypt]
+//! only reads from the stores it is given; the session, identity, and pre-key changes that a
+//! normal decryption would make are collected in a [CommitSet] instead. The extension serializes
+//! the commit set and hands it to the main app, which applies it with [CommitSet::apply].
+//!
+//! If the main app has changed one of the affected sessions in the meantime (say, by decrypting
+//! another message from the same sender), the commit set is stale and is rejected as a whole. The
+//! message is then simply decrypted again the normal way.
+
+use crate::audit;
+use crate::proto::storage::{notification_commit_set_structure, NotificationCommitSetStructure};
+use crate::state::PreKeyId;
+use crate::storage::Mutation;
+use crate::{
+    sealed_sender_decrypt, Context, IdentityKey, IdentityKeyStore, MutationLog, MutationRecorder,
+    PreKeyStore, ProtocolAddress, PublicKey, Result, SealedSenderDecryptionResult, SessionRecord,
+    SessionStore, SignalProtocolError, SignedPreKeyStore,
+};
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use prost::Message;
+use rand::{CryptoRng, Rng};
+use sha2::{Digest, Sha256};
+
+/// The result of [notification_decrypt].
+pub struct NotificationDecryptionResult {
+    pub message: SealedSenderDecryptionResult,
+    /// The store changes the decryption would have made.
+    pub commit_set: CommitSet,
+}
+
+/// Store changes deferred by [notification_decrypt], to be applied later with [CommitSet::apply].
+#[derive(Clone, Default)]
+pub struct CommitSet {
+    sessions: Vec<SessionUpdate>,
+    identities: Vec<(ProtocolAddress, IdentityKey)>,
+    removed_pre_keys: Vec<PreKeyId>,
+}
+
+#[derive(Clone)]
+struct SessionUpdate {
+    address: ProtocolAddress,
+    /// The digest of the record this update was derived from, if there was one.
+    base_digest: Option<[u8; 32]>,
+    record: SessionRecord,
+}
+
+fn session_digest(record: &SessionRecord) -> Result<[u8; 32]> {
+    Ok(Sha256::digest(&record.serialize()?).into())
+}
+
+impl CommitSet {
+    /// Keeps only the last write to each session and identity, which is all the main app needs.
+    fn from_log(log: MutationLog) -> Result<Self> {
+        let mut commit_set = Self::default();
+        for mutation in log.into_mutations() {
+            match mutation {
+                Mutation::StoreSession {
+                    address,
+                    base_digest,
+                    record,
+                } => match commit_set
+                    .sessions
+                    .iter_mut()
+                    .find(|update| update.address == address)
+                {
+                    Some(update) => update.record = record,
+                    None => commit_set.sessions.push(SessionUpdate {
+                        address,
+                        base_digest,
+                        record,
+                    }),
+                },
+                Mutation::SaveIdentity { address, identity } => {
+                    commit_set.identities.retain(|(a, _)| *a != address);
+                    commit_set.identities.push((address, identity));
+                }
+                Mutation::RemovePreKey { id } => {
+                    if !commit_set.removed_pre_keys.contains(&id) {
+                        commit_set.removed_pre_keys.push(id);
+                    }
+                }
+                Mutation::SavePreKey { .. } => return Err(read_only("pre-keys")),
+                Mutation::SaveSignedPreKey { .. } => return Err(read_only("signed pre-keys")),
+                Mutation::StoreSenderKey { .. } => return Err(read_only("sender keys")),
+            }
+        }
+        Ok(commit_set)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty() && self.identities.is_empty() && self.removed_pre_keys.is_empty()
+    }
+
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        let structure = NotificationCommitSetStructure {
+            sessions: self
+                .sessions
+                .iter()
+                .map(|update| {
+                    Ok(notification_commit_set_structure::SessionUpdate {
+                        name: update.address.name().into(),
+                        device_id: update.address.device_id(),
+                        base_digest: update
+                            .base_digest
+                            .map(|digest| digest.to_vec())
+                            .unwrap_or_default(),
+                        record: update.record.serialize()?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            identities: self
+                .identities
+                .iter()
+                .map(
+                    |(address, identity)| notification_commit_set_structure::IdentityUpdate {
+                        name: address.name().into(),
+                        device_id: address.device_id(),
+                        identity_key: identity.serialize().into_vec(),
+                    },
+                )
+                .collect(),
+            removed_pre_keys: self.removed_pre_keys.clone(),
+        };
+        let mut result = Vec::with_capacity(structure.encoded_len());
+        structure.encode(&mut result)?;
+        Ok(result)
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<Self> {
+        let structure = NotificationCommitSetStructure::decode(bytes)?;
+        let sessions = structure
+            .sessions
+            .into_iter()
+            .map(|update| {
+                let base_digest = match update.base_digest.len() {
+                    0 => None,
+                    32 => {
+                        let mut digest = [0; 32];
+                        digest.copy_from_slice(&update.base_digest);
+                        Some(digest)
+                    }
+                    _ => return Err(SignalProtocolError::InvalidProtobufEncoding),
+                };
+                Ok(SessionUpdate {
+                    address: ProtocolAddress::new(update.name, update.device_id),
+                    base_digest,
+                    record: SessionRecord::deserialize(&update.record)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        let identities = structure
+            .identities
+            .into_iter()
+            .map(|update| {
+                Ok((
+                    ProtocolAddress::new(update.name, update.device_id),
+                    IdentityKey::decode(&update.identity_key)?,
+                ))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            sessions,
+            identities,
+            removed_pre_keys: structure.removed_pre_keys,
+        })
+    }
+
+    /// Makes the deferred store changes.
+    ///
+    /// Fails with [`InvalidState`](SignalProtocolError::InvalidState), without changing anything,
+    /// if any of the affected sessions has changed since the notification was decrypted.
+    pub async fn apply(
+        &self,
+        session_store: &mut dyn SessionStore,
+        identity_store: &mut dyn IdentityKeyStore,
+        pre_key_store: &mut dyn PreKeyStore,
+        ctx: Context,
+    ) -> Result<()> {
+        for update in &self.sessions {
+            let current_digest = match session_store.load_session(&update.address, ctx).await? {
+                Some(record) => Some(session_digest(&record)?),
+                None => None,
+            };
+            if current_digest != update.base_digest {
+                return Err(SignalProtocolError::InvalidState(
+                    "CommitSet::apply",
+                    format!(
+                        "session with {} changed after the notification was decrypted",
+                        update.address
+                    ),
+                ));
+            }
+        }
+
+        for (address, identity) in &self.identities {
+            audit::save_identity(identity_store, address, identity, ctx).await?;
+        }
+        for update in &self.sessions {
+            session_store
+                .store_session(&update.address, &update.record, ctx)
+                .await?;
+        }
+        for pre_key_id in &self.removed_pre_keys {
+            pre_key_store.remove_pre_key(*pre_key_id, ctx).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Decrypts a sealed sender message like [sealed_sender_decrypt], but only reads from the stores.
+///
+/// The store changes are returned as a [CommitSet] instead of being made.
+#[allow(clippy::too_many_arguments)]
+pub async fn notification_decrypt<R: Rng + CryptoRng>(
+    ciphertext: &[u8],
+    trust_root: &PublicKey,
+    timestamp: u64,
+    local_e164: Option<String>,
+    local_uuid: String,
+    local_device_id: u32,
+    identity_store: &dyn IdentityKeyStore,
+    session_store: &dyn SessionStore,
+    pre_key_store: &dyn PreKeyStore,
+    signed_pre_key_store: &dyn SignedPreKeyStore,
+    ctx: Context,
+    rng: &mut R,
+) -> Result<NotificationDecryptionResult> {
+    let recorder = MutationRecorder::new();
+    let message = sealed_sender_decrypt(
+        ciphertext,
+        trust_root,
+        timestamp,
+        local_e164,
+        local_uuid,
+        local_device_id,
+        &mut recorder.identity_store(identity_store),
+        &mut recorder.session_store(session_store),
+        &mut recorder.pre_key_store(pre_key_store),
+        &mut recorder.signed_pre_key_store(signed_pre_key_store),
+        ctx,
+        rng,
+    )
+    .await?;
+
+    Ok(NotificationDecryptionResult {
+        message,
+        commit_set: CommitSet::from_log(recorder.into_log())?,
+    })
+}
+
+fn read_only(what: &str) -> SignalProtocolError {
+    SignalProtocolError::InvalidState(
+        "notification_decrypt",
+        format!("cannot save {} while decrypting a notification", what),
+    )
+}
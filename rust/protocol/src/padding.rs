@@ -0,0 +1,136 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Padding plaintexts before encryption, so that ciphertext lengths reveal less about content.
+//!
+//! A padded message is the original message, a single `0x80` terminator byte, and as many zero
+//! bytes as the policy calls for. The terminator makes the padding unambiguous to remove, so a
+//! recipient can unpad without knowing which policy the sender used.
+
+use crate::{Result, SignalProtocolError};
+
+use alloc::borrow::ToOwned;
+use alloc::vec::Vec;
+
+const TERMINATOR: u8 = 0x80;
+
+/// How far to pad a message before encrypting it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaddingPolicy {
+    /// Messages are sent as-is, with no terminator.
+    None,
+    /// Messages (plus terminator) are padded up to the next multiple of the bucket size.
+    Bucketed { bucket_size: usize },
+    /// Messages (plus terminator) are padded with the Padmé scheme, which limits the overhead to
+    /// about 12% while leaking only O(log log n) bits of the length.
+    Padme,
+}
+
+impl PaddingPolicy {
+    pub fn bucketed(bucket_size: usize) -> Result<Self> {
+        if bucket_size == 0 {
+            return Err(SignalProtocolError::InvalidArgument(
+                "padding bucket size must be positive".into(),
+            ));
+        }
+        Ok(PaddingPolicy::Bucketed { bucket_size })
+    }
+
+    /// The length of a message of `len` bytes after [`pad`](Self::pad).
+    pub fn padded_len(&self, len: usize) -> Result<usize> {
+        let overflow = || SignalProtocolError::InvalidArgument("message is too long to pad".into());
+        match *self {
+            PaddingPolicy::None => Ok(len),
+            PaddingPolicy::Bucketed { bucket_size } => {
+                let with_terminator = len.checked_add(1).ok_or_else(overflow)?;
+                let buckets = (with_terminator - 1) / bucket_size + 1;
+                buckets.checked_mul(bucket_size).ok_or_else(overflow)
+            }
+            PaddingPolicy::Padme => {
+                let with_terminator = len.checked_add(1).ok_or_else(overflow)?;
+                padme_len(with_terminator).ok_or_else(overflow)
+            }
+        }
+    }
+
+    pub fn pad(&self, message: &[u8]) -> Result<Vec<u8>> {
+        if *self == PaddingPolicy::None {
+            return Ok(message.to_owned());
+        }
+        let padded_len = self.padded_len(message.len())?;
+        let mut padded = Vec::with_capacity(padded_len);
+        padded.extend_from_slice(message);
+        padded.push(TERMINATOR);
+        padded.resize(padded_len, 0);
+        Ok(padded)
+    }
+
+    /// Removes the padding added by [`pad`](Self::pad) under any policy other than
+    /// [`PaddingPolicy::None`], for which the message is returned unchanged.
+    pub fn unpad<'a>(&self, padded: &'a [u8]) -> Result<&'a [u8]> {
+        if *self == PaddingPolicy::None {
+            return Ok(padded);
+        }
+        let terminator_index = padded
+            .iter()
+            .rposition(|&b| b != 0)
+            .filter(|&i| padded[i] == TERMINATOR)
+            .ok_or(SignalProtocolError::InvalidMessage("padding is malformed"))?;
+        Ok(&padded[..terminator_index])
+    }
+}
+
+/// Rounds `len` up so that only the top `floor(log2(floor(log2(len)))) + 1` bits can be set.
+fn padme_len(len: usize) -> Option<usize> {
+    if len < 2 {
+        return Some(len);
+    }
+    let exponent = usize::max_value().count_ones() - 1 - len.leading_zeros();
+    let significant_bits = 32 - exponent.leading_zeros();
+    let mask = (1usize << (exponent - significant_bits)) - 1;
+    len.checked_add(mask).map(|n| n & !mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_padded_lengths() -> Result<()> {
+        let bucketed = PaddingPolicy::bucketed(160)?;
+        assert_eq!(bucketed.padded_len(0)?, 160);
+        assert_eq!(bucketed.padded_len(159)?, 160);
+        assert_eq!(bucketed.padded_len(160)?, 320);
+        assert!(PaddingPolicy::bucketed(0).is_err());
+
+        assert_eq!(PaddingPolicy::Padme.padded_len(0)?, 1);
+        assert_eq!(PaddingPolicy::Padme.padded_len(8)?, 10);
+        assert_eq!(PaddingPolicy::Padme.padded_len(999)?, 1024);
+        assert_eq!(PaddingPolicy::Padme.padded_len(1000)?, 1024);
+        assert_eq!(PaddingPolicy::Padme.padded_len(1024)?, 1088);
+        assert!(PaddingPolicy::Padme.padded_len(usize::MAX).is_err());
+
+        assert_eq!(PaddingPolicy::None.padded_len(17)?, 17);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pad_round_trip() -> Result<()> {
+        let message = b"padded message\0\0";
+        for policy in &[
+            PaddingPolicy::None,
+            PaddingPolicy::bucketed(160)?,
+            PaddingPolicy::Padme,
+        ] {
+            let padded = policy.pad(message)?;
+            assert_eq!(padded.len(), policy.padded_len(message.len())?);
+            assert_eq!(policy.unpad(&padded)?, message);
+        }
+
+        assert!(PaddingPolicy::Padme.unpad(&[1, 2, 0, 0]).is_err());
+        assert!(PaddingPolicy::Padme.unpad(&[0, 0]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Signatures binding a payment address published in a profile to the account's identity key.
+//!
+//! The address is signed with a fixed prefix so that the signature can't be mistaken for one the
+//! identity key made over anything else.
+
+use crate::{IdentityKey, IdentityKeyPair, Result};
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use rand::{CryptoRng, Rng};
+
+const SIGNATURE_CONTEXT: &[u8] = b"Signal_PaymentAddress_Signature_20210401";
+
+fn signed_message(address: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(SIGNATURE_CONTEXT.len() + address.len());
+    message.extend_from_slice(SIGNATURE_CONTEXT);
+    message.extend_from_slice(address);
+    message
+}
+
+pub fn sign_payment_address<R: CryptoRng + Rng>(
+    identity_key_pair: &IdentityKeyPair,
+    address: &[u8],
+    csprng: &mut R,
+) -> Result<Box<[u8]>> {
+    identity_key_pair
+        .private_key()
+        .calculate_signature(&signed_message(address), csprng)
+}
+
+pub fn verify_payment_address(
+    identity_key: &IdentityKey,
+    address: &[u8],
+    signature: &[u8],
+) -> Result<bool> {
+    identity_key
+        .public_key()
+        .verify_signature(&signed_message(address), signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn test_payment_address_round_trip() -> Result<()> {
+        let identity_key_pair = IdentityKeyPair::generate(&mut OsRng);
+        let address = b"mobilecoin public address";
+        let signature = sign_payment_address(&identity_key_pair, address, &mut OsRng)?;
+
+        let identity_key = identity_key_pair.identity_key();
+        assert!(verify_payment_address(identity_key, address, &signature)?);
+        assert!(!verify_payment_address(
+            identity_key,
+            b"another address",
+            &signature
+        )?);
+
+        let other_identity = IdentityKeyPair::generate(&mut OsRng);
+        assert!(!verify_payment_address(
+            other_identity.identity_key(),
+            address,
+            &signature
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_payment_address_signature_is_domain_separated() -> Result<()> {
+        let identity_key_pair = IdentityKeyPair::generate(&mut OsRng);
+        let address = b"mobilecoin public address";
+
+        let plain_signature = identity_key_pair
+            .private_key()
+            .calculate_signature(address, &mut OsRng)?;
+        assert!(!verify_payment_address(
+            identity_key_pair.identity_key(),
+            address,
+            &plain_signature
+        )?);
+        Ok(())
+    }
+}
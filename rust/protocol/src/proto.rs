@@ -3,7 +3,13 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+pub mod content;
 pub mod fingerprint;
+pub mod provisioning;
 pub mod sealed_sender;
 pub mod storage;
 pub mod wire;
+
+pub(crate) mod unknown_fields;
+#[cfg_attr(not(feature = "no-prost"), allow(dead_code))]
+pub(crate) mod wire_codec;
@@ -4,3 +4,17 @@
 //
 
 include!(concat!(env!("OUT_DIR"), "/signal.proto.storage.rs"));
+
+/// Reads and writes the same data as [RecordStructure], but leaves the sessions encoded.
+///
+/// A message field has the same wire format as a bytes field, so `current_session` and each
+/// element of `previous_sessions` is exactly the encoding of one `SessionStructure`.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LazyRecordStructure {
+    #[prost(bytes = "vec", optional, tag = "1")]
+    pub current_session: ::core::option::Option<::alloc::vec::Vec<u8>>,
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub previous_sessions: ::alloc::vec::Vec<::alloc::vec::Vec<u8>>,
+    #[prost(uint32, tag = "3")]
+    pub version: u32,
+}
@@ -0,0 +1,230 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Preservation of protobuf fields that this version of the library does not understand.
+//!
+//! prost discards unknown fields when decoding, which means that a record written by a newer
+//! client and re-saved by an older one would lose whatever the newer client added. The record
+//! types keep the raw encoding of any unrecognized fields and append it when serializing, so such
+//! data survives a round trip. This is done for the records themselves and for the long-lived
+//! messages nested inside them: session states, their ratchet chains, and sender key states.
+//! Messages that are replaced wholesale as the ratchet advances (chain keys, message keys) are
+//! rebuilt from scratch, so anything a newer client stored in them would be stale anyway.
+
+use crate::{Result, SignalProtocolError};
+
+use alloc::vec::Vec;
+use prost::encoding::{encode_key, encode_varint, WireType};
+use prost::Message;
+
+/// The version stamped into newly-serialized records.
+///
+/// Version 0 is implied for records written before versions were recorded.
+pub(crate) const CURRENT_RECORD_VERSION: u32 = 1;
+
+pub(super) const WIRE_TYPE_VARINT: u64 = 0;
+const WIRE_TYPE_FIXED64: u64 = 1;
+pub(super) const WIRE_TYPE_LENGTH_DELIMITED: u64 = 2;
+const WIRE_TYPE_START_GROUP: u64 = 3;
+pub(super) const WIRE_TYPE_END_GROUP: u64 = 4;
+const WIRE_TYPE_FIXED32: u64 = 5;
+
+const MAX_GROUP_DEPTH: usize = 100;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct UnknownFields(Vec<u8>);
+
+impl UnknownFields {
+    /// Collects the raw encoding of every top-level field in `buf` whose tag is not in
+    /// `known_tags`.
+    pub(crate) fn extract(buf: &[u8], known_tags: &[u32]) -> Result<Self> {
+        let mut unknown = Vec::new();
+        let mut offset = 0;
+        while offset < buf.len() {
+            let start = offset;
+            let (tag, wire_type) = read_key(buf, &mut offset)?;
+            if wire_type == WIRE_TYPE_END_GROUP {
+                return Err(SignalProtocolError::InvalidProtobufEncoding);
+            }
+            skip_field_value(buf, &mut offset, tag, wire_type, 0)?;
+            if !known_tags.contains(&tag) {
+                unknown.extend_from_slice(&buf[start..offset]);
+            }
+        }
+        Ok(Self(unknown))
+    }
+
+    /// Like [`extract`](Self::extract), but for each embedded message in field `tag` of `buf`.
+    ///
+    /// Returns the encoding of each embedded message along with its unknown fields, in the order
+    /// they appear, which is also the order prost decodes a repeated field in.
+    pub(crate) fn extract_embedded<'a>(
+        buf: &'a [u8],
+        tag: u32,
+        known_tags: &[u32],
+    ) -> Result<Vec<(&'a [u8], Self)>> {
+        let mut embedded = Vec::new();
+        let mut offset = 0;
+        while offset < buf.len() {
+            let (field_tag, wire_type) = read_key(buf, &mut offset)?;
+            if field_tag == tag && wire_type == WIRE_TYPE_LENGTH_DELIMITED {
+                let len = read_varint(buf, &mut offset)?;
+                let start = offset;
+                skip_bytes(buf, &mut offset, len)?;
+                let message = &buf[start..offset];
+                embedded.push((message, Self::extract(message, known_tags)?));
+            } else {
+                if wire_type == WIRE_TYPE_END_GROUP {
+                    return Err(SignalProtocolError::InvalidProtobufEncoding);
+                }
+                skip_field_value(buf, &mut offset, field_tag, wire_type, 0)?;
+            }
+        }
+        Ok(embedded)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Appends the preserved fields to an already-encoded message.
+    ///
+    /// Protobuf messages may be concatenated, so this produces a valid encoding as long as none
+    /// of the preserved tags are also used by the message itself.
+    pub(crate) fn append_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.0);
+    }
+
+    /// Encodes `message` as field `tag` of an enclosing message, with the preserved fields
+    /// appended to it.
+    pub(crate) fn encode_embedded(&self, tag: u32, message: &impl Message, buf: &mut Vec<u8>) {
+        encode_key(tag, WireType::LengthDelimited, buf);
+        encode_varint((message.encoded_len() + self.0.len()) as u64, buf);
+        message
+            .encode(buf)
+            .expect("Vec<u8> provides capacity as needed");
+        self.append_to(buf);
+    }
+}
+
+/// Chooses the format version to stamp into a re-serialized record.
+///
+/// A record written by a newer client keeps its version, since its unknown fields are preserved.
+pub(crate) fn record_version_for_serialize(serialized_version: u32) -> u32 {
+    serialized_version.max(CURRENT_RECORD_VERSION)
+}
+
+pub(super) fn read_varint(buf: &[u8], offset: &mut usize) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *buf
+            .get(*offset)
+            .ok_or(SignalProtocolError::InvalidProtobufEncoding)?;
+        *offset += 1;
+        value |= u64::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(SignalProtocolError::InvalidProtobufEncoding)
+}
+
+pub(super) fn read_key(buf: &[u8], offset: &mut usize) -> Result<(u32, u64)> {
+    let key = read_varint(buf, offset)?;
+    let tag = key >> 3;
+    if tag == 0 || tag > u64::from(u32::MAX) {
+        return Err(SignalProtocolError::InvalidProtobufEncoding);
+    }
+    Ok((tag as u32, key & 0x7))
+}
+
+fn skip_bytes(buf: &[u8], offset: &mut usize, len: u64) -> Result<()> {
+    let remaining = (buf.len() - *offset) as u64;
+    if len > remaining {
+        return Err(SignalProtocolError::InvalidProtobufEncoding);
+    }
+    *offset += len as usize;
+    Ok(())
+}
+
+pub(super) fn skip_field_value(
+    buf: &[u8],
+    offset: &mut usize,
+    tag: u32,
+    wire_type: u64,
+    depth: usize,
+) -> Result<()> {
+    match wire_type {
+        WIRE_TYPE_VARINT => read_varint(buf, offset).map(|_| ()),
+        WIRE_TYPE_FIXED64 => skip_bytes(buf, offset, 8),
+        WIRE_TYPE_LENGTH_DELIMITED => {
+            let len = read_varint(buf, offset)?;
+            skip_bytes(buf, offset, len)
+        }
+        WIRE_TYPE_START_GROUP => {
+            if depth >= MAX_GROUP_DEPTH {
+                return Err(SignalProtocolError::InvalidProtobufEncoding);
+            }
+            loop {
+                let (inner_tag, inner_wire_type) = read_key(buf, offset)?;
+                if inner_wire_type == WIRE_TYPE_END_GROUP {
+                    if inner_tag != tag {
+                        return Err(SignalProtocolError::InvalidProtobufEncoding);
+                    }
+                    return Ok(());
+                }
+                skip_field_value(buf, offset, inner_tag, inner_wire_type, depth + 1)?;
+            }
+        }
+        WIRE_TYPE_FIXED32 => skip_bytes(buf, offset, 4),
+        _ => Err(SignalProtocolError::InvalidProtobufEncoding),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_unknown_fields() -> Result<()> {
+        // field 1 (varint 5), field 9 (bytes "hi"), field 2 (fixed32), field 10 (varint 300)
+        let encoded = [
+            0x08, 0x05, 0x4A, 0x02, b'h', b'i', 0x15, 1, 2, 3, 4, 0x50, 0xAC, 0x02,
+        ];
+        let unknown = UnknownFields::extract(&encoded, &[1, 2])?;
+        assert_eq!(
+            unknown,
+            UnknownFields(vec![0x4A, 0x02, b'h', b'i', 0x50, 0xAC, 0x02])
+        );
+
+        let mut buf = vec![0x08, 0x05];
+        unknown.append_to(&mut buf);
+        assert_eq!(UnknownFields::extract(&buf, &[1])?, unknown);
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_rejects_truncated_input() {
+        assert!(UnknownFields::extract(&[0x4A, 0x05, b'h'], &[]).is_err());
+        assert!(UnknownFields::extract(&[0x08], &[]).is_err());
+        assert!(UnknownFields::extract(&[0x0C], &[]).is_err());
+    }
+
+    #[test]
+    fn test_embedded_unknown_fields_round_trip() -> Result<()> {
+        // field 1 (varint 7), field 3 (embedded message: field 1 (varint 5), field 9 (bytes "hi"))
+        let encoded = [0x08, 0x07, 0x1A, 0x06, 0x08, 0x05, 0x4A, 0x02, b'h', b'i'];
+        let embedded = UnknownFields::extract_embedded(&encoded, 3, &[1])?;
+        assert_eq!(embedded.len(), 1);
+        let (message, unknown) = &embedded[0];
+        assert_eq!(*message, &encoded[4..]);
+        assert_eq!(*unknown, UnknownFields(vec![0x4A, 0x02, b'h', b'i']));
+
+        let mut buf = vec![0x08, 0x07];
+        unknown.encode_embedded(3, &5u32, &mut buf);
+        assert_eq!(buf, encoded);
+        Ok(())
+    }
+}
@@ -0,0 +1,303 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! A hand-written encoder and decoder for the messages in `wire.proto`.
+//!
+//! When the `no-prost` feature is enabled, the message types in [crate::protocol] use this codec
+//! instead of the prost-generated code in [super::wire]. The structs here have the same fields as
+//! their generated counterparts and produce byte-identical output: fields are written in tag
+//! order, and a field is written whenever it is present, even if its value is zero or empty.
+//!
+//! Decoding follows the usual protobuf rules: unknown fields are skipped, and if a field appears
+//! more than once the last occurrence wins.
+
+use super::unknown_fields::{
+    read_key, read_varint, skip_field_value, WIRE_TYPE_END_GROUP, WIRE_TYPE_LENGTH_DELIMITED,
+    WIRE_TYPE_VARINT,
+};
+use crate::{Result, SignalProtocolError};
+
+use alloc::vec::Vec;
+
+trait FieldValue: Sized {
+    const WIRE_TYPE: u64;
+
+    fn encoded_len(&self) -> usize;
+    fn encode(&self, buf: &mut &mut [u8]);
+    fn decode(buf: &[u8], offset: &mut usize) -> Result<Self>;
+}
+
+fn varint_len(value: u64) -> usize {
+    let bits = 64 - (value | 1).leading_zeros() as usize;
+    (bits + 6) / 7
+}
+
+fn put_bytes(buf: &mut &mut [u8], bytes: &[u8]) {
+    let (head, tail) = core::mem::take(buf).split_at_mut(bytes.len());
+    head.copy_from_slice(bytes);
+    *buf = tail;
+}
+
+fn put_varint(buf: &mut &mut [u8], mut value: u64) {
+    while value >= 0x80 {
+        put_bytes(buf, &[(value as u8 & 0x7F) | 0x80]);
+        value >>= 7;
+    }
+    put_bytes(buf, &[value as u8]);
+}
+
+fn key(tag: u32, wire_type: u64) -> u64 {
+    (u64::from(tag) << 3) | wire_type
+}
+
+impl FieldValue for u32 {
+    const WIRE_TYPE: u64 = WIRE_TYPE_VARINT;
+
+    fn encoded_len(&self) -> usize {
+        varint_len(u64::from(*self))
+    }
+
+    fn encode(&self, buf: &mut &mut [u8]) {
+        put_varint(buf, u64::from(*self))
+    }
+
+    fn decode(buf: &[u8], offset: &mut usize) -> Result<Self> {
+        // Like other protobuf implementations, silently truncate oversized values.
+        Ok(read_varint(buf, offset)? as u32)
+    }
+}
+
+impl FieldValue for Vec<u8> {
+    const WIRE_TYPE: u64 = WIRE_TYPE_LENGTH_DELIMITED;
+
+    fn encoded_len(&self) -> usize {
+        varint_len(self.len() as u64) + self.len()
+    }
+
+    fn encode(&self, buf: &mut &mut [u8]) {
+        put_varint(buf, self.len() as u64);
+        put_bytes(buf, self);
+    }
+
+    fn decode(buf: &[u8], offset: &mut usize) -> Result<Self> {
+        let len = read_varint(buf, offset)?;
+        if len > (buf.len() - *offset) as u64 {
+            return Err(SignalProtocolError::InvalidProtobufEncoding);
+        }
+        let value = buf[*offset..*offset + len as usize].to_vec();
+        *offset += len as usize;
+        Ok(value)
+    }
+}
+
+/// Defines a message struct along with its codec.
+///
+/// Fields must be listed in increasing tag order, which is the order they are encoded in.
+macro_rules! wire_message {
+    ($name:ident { $($field:ident: $ty:ty = $tag:literal),* $(,)? }) => {
+        #[derive(Clone, PartialEq, Eq, Debug, Default)]
+        pub struct $name {
+            $(pub $field: Option<$ty>,)*
+        }
+
+        impl $name {
+            pub fn encoded_len(&self) -> usize {
+                let mut len = 0;
+                $(
+                    if let Some(value) = &self.$field {
+                        len += varint_len(key($tag, <$ty as FieldValue>::WIRE_TYPE));
+                        len += value.encoded_len();
+                    }
+                )*
+                len
+            }
+
+            /// Writes the message to the front of `buf`, advancing it past the written bytes.
+            pub fn encode(&self, buf: &mut &mut [u8]) -> Result<()> {
+                if buf.len() < self.encoded_len() {
+                    return Err(SignalProtocolError::InvalidProtobufEncoding);
+                }
+                $(
+                    if let Some(value) = &self.$field {
+                        put_varint(buf, key($tag, <$ty as FieldValue>::WIRE_TYPE));
+                        value.encode(buf);
+                    }
+                )*
+                Ok(())
+            }
+
+            pub fn decode(buf: &[u8]) -> Result<Self> {
+                let mut result = Self::default();
+                let mut offset = 0;
+                while offset < buf.len() {
+                    let (tag, wire_type) = read_key(buf, &mut offset)?;
+                    match tag {
+                        $(
+                            $tag => {
+                                if wire_type != <$ty as FieldValue>::WIRE_TYPE {
+                                    return Err(SignalProtocolError::InvalidProtobufEncoding);
+                                }
+                                result.$field = Some(FieldValue::decode(buf, &mut offset)?);
+                            }
+                        )*
+                        _ => {
+                            if wire_type == WIRE_TYPE_END_GROUP {
+                                return Err(SignalProtocolError::InvalidProtobufEncoding);
+                            }
+                            skip_field_value(buf, &mut offset, tag, wire_type, 0)?;
+                        }
+                    }
+                }
+                Ok(result)
+            }
+        }
+    };
+}
+
+wire_message!(SignalMessage {
+    ratchet_key: Vec<u8> = 1,
+    counter: u32 = 2,
+    previous_counter: u32 = 3,
+    ciphertext: Vec<u8> = 4,
+    encrypted_header: Vec<u8> = 5,
+});
+
+wire_message!(PreKeySignalMessage {
+    pre_key_id: u32 = 1,
+    base_key: Vec<u8> = 2,
+    identity_key: Vec<u8> = 3,
+    message: Vec<u8> = 4,
+    registration_id: u32 = 5,
+    signed_pre_key_id: u32 = 6,
+});
+
+wire_message!(SenderKeyMessage {
+    id: u32 = 1,
+    iteration: u32 = 2,
+    ciphertext: Vec<u8> = 3,
+});
+
+wire_message!(SenderKeyDistributionMessage {
+    id: u32 = 1,
+    iteration: u32 = 2,
+    chain_key: Vec<u8> = 3,
+    signing_key: Vec<u8> = 4,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::wire;
+
+    use prost::Message;
+
+    #[test]
+    fn test_varint_len() {
+        assert_eq!(varint_len(0), 1);
+        assert_eq!(varint_len(0x7F), 1);
+        assert_eq!(varint_len(0x80), 2);
+        assert_eq!(varint_len(u64::from(u32::MAX)), 5);
+        assert_eq!(varint_len(u64::MAX), 10);
+    }
+
+    #[test]
+    fn test_matches_prost_encoding() -> Result<()> {
+        let inner = wire::SignalMessage {
+            ratchet_key: Some(vec![5; 33]),
+            counter: Some(0),
+            previous_counter: Some(u32::MAX),
+            ciphertext: Some(vec![9; 300]),
+            encrypted_header: None,
+        };
+        let mut expected = Vec::new();
+        inner.encode(&mut expected)?;
+
+        let ours = SignalMessage::decode(&expected)?;
+        assert_eq!(ours.ratchet_key, inner.ratchet_key);
+        assert_eq!(ours.counter, inner.counter);
+        assert_eq!(ours.previous_counter, inner.previous_counter);
+        assert_eq!(ours.ciphertext, inner.ciphertext);
+        assert_eq!(ours.encrypted_header, None);
+        let mut encoded = vec![0u8; ours.encoded_len()];
+        ours.encode(&mut &mut encoded[..])?;
+        assert_eq!(encoded, expected);
+
+        let outer = wire::PreKeySignalMessage {
+            registration_id: Some(12345),
+            pre_key_id: None,
+            signed_pre_key_id: Some(7),
+            base_key: Some(vec![1; 33]),
+            identity_key: Some(vec![]),
+            message: Some(expected),
+        };
+        let mut expected = Vec::new();
+        outer.encode(&mut expected)?;
+
+        let ours = PreKeySignalMessage {
+            registration_id: outer.registration_id,
+            pre_key_id: outer.pre_key_id,
+            signed_pre_key_id: outer.signed_pre_key_id,
+            base_key: outer.base_key.clone(),
+            identity_key: outer.identity_key.clone(),
+            message: outer.message.clone(),
+        };
+        assert_eq!(ours.encoded_len(), outer.encoded_len());
+        let mut encoded = vec![0u8; ours.encoded_len()];
+        ours.encode(&mut &mut encoded[..])?;
+        assert_eq!(encoded, expected);
+        assert_eq!(PreKeySignalMessage::decode(&expected)?, ours);
+
+        let distribution = wire::SenderKeyDistributionMessage {
+            id: Some(1),
+            iteration: Some(200),
+            chain_key: Some(vec![2; 32]),
+            signing_key: None,
+        };
+        let mut expected = Vec::new();
+        distribution.encode(&mut expected)?;
+        let ours = SenderKeyDistributionMessage::decode(&expected)?;
+        assert_eq!(ours.chain_key, distribution.chain_key);
+        assert_eq!(ours.signing_key, None);
+        let mut encoded = vec![0u8; ours.encoded_len()];
+        ours.encode(&mut &mut encoded[..])?;
+        assert_eq!(encoded, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_skips_unknown_fields() -> Result<()> {
+        // field 1 (varint 5), field 9 (bytes "hi"), field 2 (varint 1), field 1 (varint 6)
+        let encoded = [0x08, 0x05, 0x4A, 0x02, b'h', b'i', 0x10, 0x01, 0x08, 0x06];
+        let message = SenderKeyMessage::decode(&encoded)?;
+        assert_eq!(
+            message,
+            SenderKeyMessage {
+                id: Some(6),
+                iteration: Some(1),
+                ciphertext: None,
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid_input() {
+        // wrong wire type for `id`
+        assert!(SenderKeyMessage::decode(&[0x0A, 0x00]).is_err());
+        // truncated ciphertext
+        assert!(SenderKeyMessage::decode(&[0x1A, 0x05, 1, 2]).is_err());
+        // unmatched end group
+        assert!(SenderKeyMessage::decode(&[0x4C]).is_err());
+
+        let message = SenderKeyMessage {
+            id: Some(1),
+            iteration: None,
+            ciphertext: Some(vec![1, 2, 3]),
+        };
+        let mut too_small = vec![0u8; message.encoded_len() - 1];
+        assert!(message.encode(&mut &mut too_small[..]).is_err());
+    }
+}
@@ -3,12 +3,22 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
-use crate::proto;
+#[cfg(not(feature = "no-prost"))]
+use crate::proto::wire;
+#[cfg(feature = "no-prost")]
+use crate::proto::wire_codec as wire;
+use crate::ratchet::HeaderKey;
+use crate::utils::hex_encode;
 use crate::{IdentityKey, PrivateKey, PublicKey, Result, SignalProtocolError};
 
-use std::convert::TryFrom;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
 
 use hmac::{Hmac, Mac, NewMac};
+#[cfg(not(feature = "no-prost"))]
 use prost::Message;
 use rand::{CryptoRng, Rng};
 use sha2::Sha256;
@@ -16,6 +26,14 @@ use subtle::ConstantTimeEq;
 
 pub const CIPHERTEXT_MESSAGE_CURRENT_VERSION: u8 = 3;
 
+/// The session version, and ciphertext version, of sessions that encrypt message headers.
+///
+/// Encrypting the header hides each message's ratchet key and counters from anyone without the
+/// session state, at the cost of trial-decrypting headers on receipt. Such sessions are only
+/// established with the `header-encryption` feature, and only when the peer's pre-key bundle
+/// advertises support for this version.
+pub const HEADER_ENCRYPTED_MESSAGE_VERSION: u8 = 4;
+
 pub enum CiphertextMessage {
     SignalMessage(SignalMessage),
     PreKeySignalMessage(PreKeySignalMessage),
@@ -31,7 +49,47 @@ pub enum CiphertextMessageType {
     SenderKeyDistribution = 5,
 }
 
+impl TryFrom<u8> for CiphertextMessageType {
+    type Error = SignalProtocolError;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            2 => Ok(CiphertextMessageType::Whisper),
+            3 => Ok(CiphertextMessageType::PreKey),
+            4 => Ok(CiphertextMessageType::SenderKey),
+            5 => Ok(CiphertextMessageType::SenderKeyDistribution),
+            _ => Err(SignalProtocolError::InvalidArgument(format!(
+                "unknown ciphertext message type {}",
+                value
+            ))),
+        }
+    }
+}
+
 impl CiphertextMessage {
+    /// Parses a serialized message of the given type.
+    ///
+    /// This only decodes the message; no session state is consulted, and MACs and signatures
+    /// are not checked.
+    pub fn deserialize(message_type: CiphertextMessageType, serialized: &[u8]) -> Result<Self> {
+        Ok(match message_type {
+            CiphertextMessageType::Whisper => {
+                CiphertextMessage::SignalMessage(SignalMessage::try_from(serialized)?)
+            }
+            CiphertextMessageType::PreKey => {
+                CiphertextMessage::PreKeySignalMessage(PreKeySignalMessage::try_from(serialized)?)
+            }
+            CiphertextMessageType::SenderKey => {
+                CiphertextMessage::SenderKeyMessage(SenderKeyMessage::try_from(serialized)?)
+            }
+            CiphertextMessageType::SenderKeyDistribution => {
+                CiphertextMessage::SenderKeyDistributionMessage(
+                    SenderKeyDistributionMessage::try_from(serialized)?,
+                )
+            }
+        })
+    }
+
     pub fn message_type(&self) -> CiphertextMessageType {
         match self {
             CiphertextMessage::SignalMessage(_) => CiphertextMessageType::Whisper,
@@ -51,15 +109,91 @@ impl CiphertextMessage {
             CiphertextMessage::SenderKeyDistributionMessage(x) => x.serialized(),
         }
     }
+
+    pub fn message_version(&self) -> u8 {
+        match self {
+            CiphertextMessage::SignalMessage(x) => x.message_version(),
+            CiphertextMessage::PreKeySignalMessage(x) => x.message_version(),
+            CiphertextMessage::SenderKeyMessage(x) => x.message_version(),
+            CiphertextMessage::SenderKeyDistributionMessage(x) => x.message_version(),
+        }
+    }
+
+    /// The session message inside this message, if it was sent as part of a 1:1 session.
+    fn session_message(&self) -> Option<&SignalMessage> {
+        match self {
+            CiphertextMessage::SignalMessage(x) => Some(x),
+            CiphertextMessage::PreKeySignalMessage(x) => Some(x.message()),
+            CiphertextMessage::SenderKeyMessage(_)
+            | CiphertextMessage::SenderKeyDistributionMessage(_) => None,
+        }
+    }
+
+    /// The index of this message in the sender's current chain.
+    ///
+    /// Returns `None` for sender key messages, which don't belong to a session, and for received
+    /// messages whose header is still encrypted.
+    pub fn counter(&self) -> Option<u32> {
+        self.session_message()?.counter().ok()
+    }
+
+    /// The length of the sender's previous chain when this message was sent.
+    ///
+    /// Returns `None` for sender key messages, which don't belong to a session, and for received
+    /// messages whose header is still encrypted.
+    pub fn previous_counter(&self) -> Option<u32> {
+        self.session_message()?.previous_counter().ok()
+    }
 }
 
+/// The part of a [`SignalMessage`] that places it in the sender's ratchet.
 #[derive(Debug, Clone)]
-pub struct SignalMessage {
-    message_version: u8,
+struct MessageHeader {
     sender_ratchet_key: PublicKey,
     counter: u32,
-    #[allow(dead_code)]
     previous_counter: u32,
+}
+
+impl MessageHeader {
+    /// A serialized ratchet key followed by both counters, big-endian.
+    ///
+    /// Encrypted headers use this fixed-size encoding rather than protobuf so that their length
+    /// doesn't reveal the size of the counters.
+    const ENCODED_LENGTH: usize = 33 + 4 + 4;
+
+    fn encode(&self) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(Self::ENCODED_LENGTH);
+        encoded.extend_from_slice(&self.sender_ratchet_key.serialize());
+        encoded.extend_from_slice(&self.counter.to_be_bytes());
+        encoded.extend_from_slice(&self.previous_counter.to_be_bytes());
+        encoded
+    }
+
+    fn decode(encoded: &[u8]) -> Result<Self> {
+        if encoded.len() != Self::ENCODED_LENGTH {
+            return Err(SignalProtocolError::InvalidMessage(
+                "encrypted header has the wrong length",
+            ));
+        }
+        let (key, counters) = encoded.split_at(33);
+        let mut counter = [0u8; 4];
+        let mut previous_counter = [0u8; 4];
+        counter.copy_from_slice(&counters[..4]);
+        previous_counter.copy_from_slice(&counters[4..]);
+        Ok(Self {
+            sender_ratchet_key: PublicKey::deserialize(key)?,
+            counter: u32::from_be_bytes(counter),
+            previous_counter: u32::from_be_bytes(previous_counter),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SignalMessage {
+    message_version: u8,
+    /// `None` until an encrypted header has been opened.
+    header: Option<MessageHeader>,
+    encrypted_header: Option<Box<[u8]>>,
     ciphertext: Box<[u8]>,
     serialized: Box<[u8]>,
 }
@@ -77,14 +211,66 @@ impl SignalMessage {
         sender_identity_key: &IdentityKey,
         receiver_identity_key: &IdentityKey,
     ) -> Result<Self> {
-        let message = proto::wire::SignalMessage {
-            ratchet_key: Some(sender_ratchet_key.serialize().into_vec()),
-            counter: Some(counter),
-            previous_counter: Some(previous_counter),
-            ciphertext: Some(Vec::<u8>::from(&ciphertext[..])),
+        Self::new_with_header_key(
+            message_version,
+            mac_key,
+            None,
+            sender_ratchet_key,
+            counter,
+            previous_counter,
+            ciphertext,
+            sender_identity_key,
+            receiver_identity_key,
+        )
+    }
+
+    /// Like [`new`](Self::new), but seals the header under `header_key` if one is given.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new_with_header_key(
+        message_version: u8,
+        mac_key: &[u8],
+        header_key: Option<&HeaderKey>,
+        sender_ratchet_key: PublicKey,
+        counter: u32,
+        previous_counter: u32,
+        ciphertext: &[u8],
+        sender_identity_key: &IdentityKey,
+        receiver_identity_key: &IdentityKey,
+    ) -> Result<Self> {
+        let header = MessageHeader {
+            sender_ratchet_key,
+            counter,
+            previous_counter,
+        };
+        let (message, ciphertext_version, encrypted_header) = match header_key {
+            None => (
+                wire::SignalMessage {
+                    ratchet_key: Some(sender_ratchet_key.serialize().into_vec()),
+                    counter: Some(counter),
+                    previous_counter: Some(previous_counter),
+                    ciphertext: Some(Vec::<u8>::from(&ciphertext[..])),
+                    encrypted_header: None,
+                },
+                CIPHERTEXT_MESSAGE_CURRENT_VERSION,
+                None,
+            ),
+            Some(header_key) => {
+                let encrypted_header = header_key.seal(&header.encode())?;
+                (
+                    wire::SignalMessage {
+                        ratchet_key: None,
+                        counter: None,
+                        previous_counter: None,
+                        ciphertext: Some(Vec::<u8>::from(&ciphertext[..])),
+                        encrypted_header: Some(encrypted_header.clone()),
+                    },
+                    HEADER_ENCRYPTED_MESSAGE_VERSION,
+                    Some(encrypted_header.into_boxed_slice()),
+                )
+            }
         };
         let mut serialized = vec![0u8; 1 + message.encoded_len() + Self::MAC_LENGTH];
-        serialized[0] = ((message_version & 0xF) << 4) | CIPHERTEXT_MESSAGE_CURRENT_VERSION;
+        serialized[0] = ((message_version & 0xF) << 4) | ciphertext_version;
         message.encode(&mut &mut serialized[1..message.encoded_len() + 1])?;
         let msg_len_for_mac = serialized.len() - Self::MAC_LENGTH;
         let mac = Self::compute_mac(
@@ -97,9 +283,8 @@ impl SignalMessage {
         let serialized = serialized.into_boxed_slice();
         Ok(Self {
             message_version,
-            sender_ratchet_key,
-            counter,
-            previous_counter,
+            header: Some(header),
+            encrypted_header,
             ciphertext: ciphertext.into(),
             serialized,
         })
@@ -110,14 +295,39 @@ impl SignalMessage {
         self.message_version
     }
 
+    /// Whether the ratchet key and counters were sent encrypted.
+    ///
+    /// A message parsed from its serialized form doesn't know its header fields until the header
+    /// has been decrypted; a message built locally always does.
+    #[inline]
+    pub fn has_encrypted_header(&self) -> bool {
+        self.encrypted_header.is_some()
+    }
+
+    fn header(&self) -> Result<&MessageHeader> {
+        self.header
+            .as_ref()
+            .ok_or(SignalProtocolError::InvalidMessage(
+                "message header is encrypted",
+            ))
+    }
+
+    /// Fails with [`SignalProtocolError::InvalidMessage`] if the header is still encrypted.
     #[inline]
-    pub fn sender_ratchet_key(&self) -> &PublicKey {
-        &self.sender_ratchet_key
+    pub fn sender_ratchet_key(&self) -> Result<&PublicKey> {
+        Ok(&self.header()?.sender_ratchet_key)
     }
 
+    /// Fails with [`SignalProtocolError::InvalidMessage`] if the header is still encrypted.
     #[inline]
-    pub fn counter(&self) -> u32 {
-        self.counter
+    pub fn counter(&self) -> Result<u32> {
+        Ok(self.header()?.counter)
+    }
+
+    /// Fails with [`SignalProtocolError::InvalidMessage`] if the header is still encrypted.
+    #[inline]
+    pub fn previous_counter(&self) -> Result<u32> {
+        Ok(self.header()?.previous_counter)
     }
 
     #[inline]
@@ -130,6 +340,22 @@ impl SignalMessage {
         &*self.ciphertext
     }
 
+    /// Returns a copy of this message with its header decrypted, or `None` if the header was not
+    /// sealed under `header_key` (including if it was not encrypted at all).
+    pub(crate) fn open_header(&self, header_key: &HeaderKey) -> Result<Option<Self>> {
+        let encrypted_header = match &self.encrypted_header {
+            Some(encrypted_header) => encrypted_header,
+            None => return Ok(None),
+        };
+        match header_key.open(encrypted_header)? {
+            Some(header) => Ok(Some(Self {
+                header: Some(MessageHeader::decode(&header)?),
+                ..self.clone()
+            })),
+            None => Ok(None),
+        }
+    }
+
     pub fn verify_mac(
         &self,
         sender_identity_key: &IdentityKey,
@@ -147,8 +373,8 @@ impl SignalMessage {
         if !result {
             log::error!(
                 "Bad Mac! Their Mac: {} Our Mac: {}",
-                hex::encode(their_mac),
-                hex::encode(our_mac)
+                hex_encode(their_mac),
+                hex_encode(our_mac)
             );
         }
         Ok(result)
@@ -199,23 +425,46 @@ impl TryFrom<&[u8]> for SignalMessage {
                 ciphertext_version,
             ));
         }
-        if ciphertext_version > CIPHERTEXT_MESSAGE_CURRENT_VERSION {
+        let max_ciphertext_version = if cfg!(feature = "header-encryption") {
+            HEADER_ENCRYPTED_MESSAGE_VERSION
+        } else {
+            CIPHERTEXT_MESSAGE_CURRENT_VERSION
+        };
+        if ciphertext_version > max_ciphertext_version {
             return Err(SignalProtocolError::UnrecognizedCiphertextVersion(
                 ciphertext_version,
             ));
         }
 
         let proto_structure =
-            proto::wire::SignalMessage::decode(&value[1..value.len() - SignalMessage::MAC_LENGTH])?;
-
-        let sender_ratchet_key = proto_structure
-            .ratchet_key
-            .ok_or(SignalProtocolError::InvalidProtobufEncoding)?;
-        let sender_ratchet_key = PublicKey::deserialize(&sender_ratchet_key)?;
-        let counter = proto_structure
-            .counter
-            .ok_or(SignalProtocolError::InvalidProtobufEncoding)?;
-        let previous_counter = proto_structure.previous_counter.unwrap_or(0);
+            wire::SignalMessage::decode(&value[1..value.len() - SignalMessage::MAC_LENGTH])?;
+
+        let (header, encrypted_header) = if ciphertext_version == HEADER_ENCRYPTED_MESSAGE_VERSION {
+            let encrypted_header = proto_structure
+                .encrypted_header
+                .ok_or(SignalProtocolError::InvalidProtobufEncoding)?;
+            if encrypted_header.len() != HeaderKey::sealed_len(MessageHeader::ENCODED_LENGTH) {
+                return Err(SignalProtocolError::InvalidMessage(
+                    "encrypted header has the wrong length",
+                ));
+            }
+            (None, Some(encrypted_header.into_boxed_slice()))
+        } else {
+            let sender_ratchet_key = proto_structure
+                .ratchet_key
+                .ok_or(SignalProtocolError::InvalidProtobufEncoding)?;
+            let sender_ratchet_key = PublicKey::deserialize(&sender_ratchet_key)?;
+            let counter = proto_structure
+                .counter
+                .ok_or(SignalProtocolError::InvalidProtobufEncoding)?;
+            let previous_counter = proto_structure.previous_counter.unwrap_or(0);
+            let header = MessageHeader {
+                sender_ratchet_key,
+                counter,
+                previous_counter,
+            };
+            (Some(header), None)
+        };
         let ciphertext = proto_structure
             .ciphertext
             .ok_or(SignalProtocolError::InvalidProtobufEncoding)?
@@ -223,9 +472,8 @@ impl TryFrom<&[u8]> for SignalMessage {
 
         Ok(SignalMessage {
             message_version,
-            sender_ratchet_key,
-            counter,
-            previous_counter,
+            header,
+            encrypted_header,
             ciphertext,
             serialized: Box::from(value),
         })
@@ -254,7 +502,7 @@ impl PreKeySignalMessage {
         identity_key: IdentityKey,
         message: SignalMessage,
     ) -> Result<Self> {
-        let proto_message = proto::wire::PreKeySignalMessage {
+        let proto_message = wire::PreKeySignalMessage {
             registration_id: Some(registration_id),
             pre_key_id,
             signed_pre_key_id: Some(signed_pre_key_id),
@@ -345,7 +593,7 @@ impl TryFrom<&[u8]> for PreKeySignalMessage {
             ));
         }
 
-        let proto_structure = proto::wire::PreKeySignalMessage::decode(&value[1..])?;
+        let proto_structure = wire::PreKeySignalMessage::decode(&value[1..])?;
 
         let base_key = proto_structure
             .base_key
@@ -394,7 +642,7 @@ impl SenderKeyMessage {
         csprng: &mut R,
         signature_key: &PrivateKey,
     ) -> Result<Self> {
-        let proto_message = proto::wire::SenderKeyMessage {
+        let proto_message = wire::SenderKeyMessage {
             id: Some(key_id),
             iteration: Some(iteration),
             ciphertext: Some(ciphertext.to_vec()),
@@ -425,6 +673,25 @@ impl SenderKeyMessage {
         Ok(valid)
     }
 
+    /// Checks the signatures on several messages from the same sender key at once.
+    ///
+    /// Returns `true` only if every signature is valid. See [`PublicKey::verify_signatures`].
+    pub fn verify_signatures<R: CryptoRng + Rng>(
+        messages: &[&Self],
+        signature_key: &PublicKey,
+        csprng: &mut R,
+    ) -> Result<bool> {
+        let signatures: Vec<(&[u8], &[u8])> = messages
+            .iter()
+            .map(|message| {
+                message
+                    .serialized
+                    .split_at(message.serialized.len() - Self::SIGNATURE_LEN)
+            })
+            .collect();
+        signature_key.verify_signatures(&signatures, csprng)
+    }
+
     #[inline]
     pub fn message_version(&self) -> u8 {
         self.message_version
@@ -477,7 +744,7 @@ impl TryFrom<&[u8]> for SenderKeyMessage {
             ));
         }
         let proto_structure =
-            proto::wire::SenderKeyMessage::decode(&value[1..value.len() - Self::SIGNATURE_LEN])?;
+            wire::SenderKeyMessage::decode(&value[1..value.len() - Self::SIGNATURE_LEN])?;
 
         let key_id = proto_structure
             .id
@@ -512,7 +779,7 @@ pub struct SenderKeyDistributionMessage {
 
 impl SenderKeyDistributionMessage {
     pub fn new(id: u32, iteration: u32, chain_key: &[u8], signing_key: PublicKey) -> Result<Self> {
-        let proto_message = proto::wire::SenderKeyDistributionMessage {
+        let proto_message = wire::SenderKeyDistributionMessage {
             id: Some(id),
             iteration: Some(iteration),
             chain_key: Some(chain_key.to_vec()),
@@ -592,7 +859,7 @@ impl TryFrom<&[u8]> for SenderKeyDistributionMessage {
             ));
         }
 
-        let proto_structure = proto::wire::SenderKeyDistributionMessage::decode(&value[1..])?;
+        let proto_structure = wire::SenderKeyDistributionMessage::decode(&value[1..])?;
 
         let id = proto_structure
             .id
@@ -627,7 +894,7 @@ impl TryFrom<&[u8]> for SenderKeyDistributionMessage {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::KeyPair;
+    use crate::{KeyPair, HKDF};
 
     use rand::rngs::OsRng;
     use rand::{CryptoRng, Rng};
@@ -662,9 +929,16 @@ mod tests {
 
     fn assert_signal_message_equals(m1: &SignalMessage, m2: &SignalMessage) {
         assert_eq!(m1.message_version, m2.message_version);
-        assert_eq!(m1.sender_ratchet_key, m2.sender_ratchet_key);
-        assert_eq!(m1.counter, m2.counter);
-        assert_eq!(m1.previous_counter, m2.previous_counter);
+        assert_eq!(
+            m1.sender_ratchet_key().ok(),
+            m2.sender_ratchet_key().ok()
+        );
+        assert_eq!(m1.counter().ok(), m2.counter().ok());
+        assert_eq!(
+            m1.previous_counter().ok(),
+            m2.previous_counter().ok()
+        );
+        assert_eq!(m1.encrypted_header, m2.encrypted_header);
         assert_eq!(m1.ciphertext, m2.ciphertext);
         assert_eq!(m1.serialized, m2.serialized);
     }
@@ -679,6 +953,52 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_signal_message_encrypted_header() -> Result<()> {
+        let mut csprng = OsRng;
+        let header_key = HeaderKey::new(HKDF::new(3)?, &[7u8; 32])?;
+        let sender_ratchet_key = KeyPair::generate(&mut csprng).public_key;
+        let identity_key: IdentityKey = KeyPair::generate(&mut csprng).public_key.into();
+        let message = SignalMessage::new_with_header_key(
+            HEADER_ENCRYPTED_MESSAGE_VERSION,
+            &[1u8; 32],
+            Some(&header_key),
+            sender_ratchet_key,
+            42,
+            41,
+            b"ciphertext",
+            &identity_key,
+            &identity_key,
+        )?;
+        assert!(message.has_encrypted_header());
+        assert_eq!(message.counter()?, 42);
+        let serialized_key = sender_ratchet_key.serialize();
+        assert!(!message
+            .serialized()
+            .windows(serialized_key.len())
+            .any(|window| window == &serialized_key[..]));
+
+        #[cfg(feature = "header-encryption")]
+        {
+            let parsed = SignalMessage::try_from(message.serialized())?;
+            assert!(parsed.has_encrypted_header());
+            assert!(parsed.counter().is_err());
+
+            let other_key = HeaderKey::new(HKDF::new(3)?, &[8u8; 32])?;
+            assert!(parsed.open_header(&other_key)?.is_none());
+            let opened = parsed
+                .open_header(&header_key)?
+                .expect("sealed under this key");
+            assert_signal_message_equals(&message, &opened);
+        }
+        #[cfg(not(feature = "header-encryption"))]
+        assert!(matches!(
+            SignalMessage::try_from(message.serialized()),
+            Err(SignalProtocolError::UnrecognizedCiphertextVersion(4))
+        ));
+        Ok(())
+    }
+
     #[test]
     fn test_pre_key_signal_message_serialize_deserialize() -> Result<()> {
         let mut csprng = OsRng;
@@ -764,4 +1084,32 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_ciphertext_message_deserialize_without_state() -> Result<()> {
+        let mut csprng = OsRng;
+        let message = create_signal_message(&mut csprng)?;
+
+        let message_type = CiphertextMessageType::try_from(2u8)?;
+        assert_eq!(message_type, CiphertextMessageType::Whisper);
+        let parsed = CiphertextMessage::deserialize(message_type, message.serialized())?;
+        assert_eq!(parsed.message_type(), CiphertextMessageType::Whisper);
+        assert_eq!(parsed.message_version(), 3);
+        match parsed {
+            CiphertextMessage::SignalMessage(parsed) => {
+                assert_eq!(parsed.counter()?, 42);
+                assert_eq!(parsed.previous_counter()?, 41);
+                assert_signal_message_equals(&message, &parsed);
+            }
+            _ => panic!("wrong message type"),
+        }
+
+        assert!(CiphertextMessage::deserialize(
+            CiphertextMessageType::SenderKey,
+            message.serialized()
+        )
+        .is_err());
+        assert!(CiphertextMessageType::try_from(1u8).is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,241 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! The envelope used to hand account details from a primary device to a newly linked one.
+//!
+//! The new device generates an ephemeral key pair and shows its public key to the primary, along
+//! with the provisioning UUID the server assigned to its connection. The primary encrypts a
+//! provisioning message to that key with a fresh ephemeral key of its own: the body of the
+//! envelope is `version || iv || AES-256-CBC(message) || HMAC-SHA256(version || iv || ciphertext)`,
+//! with the AES and MAC keys derived from the ECDH shared secret.
+
+use crate::crypto;
+use crate::proto::provisioning;
+use crate::{KeyPair, PublicKey, Result, SignalProtocolError, HKDF};
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use prost::Message;
+use rand::{CryptoRng, Rng};
+use subtle::ConstantTimeEq;
+
+const PROVISIONING_VERSION: u8 = 1;
+const KDF_INFO: &[u8] = b"TextSecure Provisioning Message";
+const IV_SIZE: usize = 16;
+const MAC_SIZE: usize = 32;
+
+fn derive_keys(shared_secret: &[u8]) -> Result<([u8; 32], [u8; 32])> {
+    let derived = HKDF::new(3)?.derive_secrets(shared_secret, KDF_INFO, 64)?;
+    let mut cipher_key = [0; 32];
+    let mut mac_key = [0; 32];
+    cipher_key.copy_from_slice(&derived[..32]);
+    mac_key.copy_from_slice(&derived[32..]);
+    Ok((cipher_key, mac_key))
+}
+
+/// Encrypts `message` for the linked device that showed `their_public_key`, as the primary
+/// device does.
+///
+/// Returns a serialized `ProvisionEnvelope`.
+pub fn provisioning_encrypt<R: CryptoRng + Rng>(
+    their_public_key: &PublicKey,
+    message: &[u8],
+    csprng: &mut R,
+) -> Result<Vec<u8>> {
+    let our_key_pair = KeyPair::generate(csprng);
+    let shared_secret = our_key_pair.calculate_agreement(their_public_key)?;
+    let (cipher_key, mac_key) = derive_keys(&shared_secret)?;
+
+    let mut iv = [0u8; IV_SIZE];
+    csprng.fill_bytes(&mut iv);
+    let ciphertext = crypto::aes_256_cbc_encrypt(message, &cipher_key, &iv)?;
+
+    let mut body = Vec::with_capacity(1 + IV_SIZE + ciphertext.len() + MAC_SIZE);
+    body.push(PROVISIONING_VERSION);
+    body.extend_from_slice(&iv);
+    body.extend_from_slice(&ciphertext);
+    let mac = crypto::hmac_sha256(&mac_key, &body)?;
+    body.extend_from_slice(&mac);
+
+    let envelope = provisioning::ProvisionEnvelope {
+        public_key: Some(our_key_pair.public_key.serialize().into_vec()),
+        body: Some(body),
+    };
+    let mut serialized = Vec::with_capacity(envelope.encoded_len());
+    envelope.encode(&mut serialized)?;
+    Ok(serialized)
+}
+
+/// The linked device's side of provisioning.
+///
+/// The key pair is only needed until the provisioning message arrives and should not be kept
+/// afterwards.
+#[derive(Clone, Debug)]
+pub struct ProvisioningCipher {
+    key_pair: KeyPair,
+}
+
+impl ProvisioningCipher {
+    pub fn new<R: CryptoRng + Rng>(csprng: &mut R) -> Self {
+        Self::from_key_pair(KeyPair::generate(csprng))
+    }
+
+    pub fn from_key_pair(key_pair: KeyPair) -> Self {
+        Self { key_pair }
+    }
+
+    /// The key to show to the primary device.
+    pub fn public_key(&self) -> &PublicKey {
+        &self.key_pair.public_key
+    }
+
+    /// Decrypts a serialized `ProvisionEnvelope` produced by [`provisioning_encrypt`].
+    pub fn decrypt(&self, envelope: &[u8]) -> Result<Vec<u8>> {
+        let envelope = provisioning::ProvisionEnvelope::decode(envelope)?;
+        let their_public_key = PublicKey::deserialize(
+            &envelope
+                .public_key
+                .ok_or(SignalProtocolError::InvalidProtobufEncoding)?,
+        )?;
+        let body = envelope
+            .body
+            .ok_or(SignalProtocolError::InvalidProtobufEncoding)?;
+
+        if body.len() < 1 + IV_SIZE + MAC_SIZE {
+            return Err(SignalProtocolError::CiphertextMessageTooShort(body.len()));
+        }
+        if body[0] != PROVISIONING_VERSION {
+            return Err(SignalProtocolError::UnrecognizedCiphertextVersion(body[0]));
+        }
+
+        let shared_secret = self.key_pair.calculate_agreement(&their_public_key)?;
+        let (cipher_key, mac_key) = derive_keys(&shared_secret)?;
+
+        let (authenticated, their_mac) = body.split_at(body.len() - MAC_SIZE);
+        let our_mac = crypto::hmac_sha256(&mac_key, authenticated)?;
+        if !bool::from(our_mac[..].ct_eq(their_mac)) {
+            return Err(SignalProtocolError::InvalidCiphertext);
+        }
+
+        let iv = &authenticated[1..1 + IV_SIZE];
+        crypto::aes_256_cbc_decrypt(&authenticated[1 + IV_SIZE..], &cipher_key, iv)
+    }
+}
+
+/// The first message the server sends on a provisioning connection, giving the address the
+/// primary device should send the provisioning message to.
+#[derive(Clone, Debug)]
+pub struct ProvisioningUuid {
+    uuid: String,
+    serialized: Vec<u8>,
+}
+
+impl ProvisioningUuid {
+    pub fn new(uuid: String) -> Result<Self> {
+        let message = provisioning::ProvisioningUuid {
+            uuid: Some(uuid.clone()),
+        };
+        let mut serialized = Vec::with_capacity(message.encoded_len());
+        message.encode(&mut serialized)?;
+        Ok(Self { uuid, serialized })
+    }
+
+    pub fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    pub fn serialized(&self) -> &[u8] {
+        &self.serialized
+    }
+}
+
+impl TryFrom<&[u8]> for ProvisioningUuid {
+    type Error = SignalProtocolError;
+
+    fn try_from(value: &[u8]) -> Result<Self> {
+        let message = provisioning::ProvisioningUuid::decode(value)?;
+        let uuid = message
+            .uuid
+            .ok_or(SignalProtocolError::InvalidProtobufEncoding)?;
+        Ok(Self {
+            uuid,
+            serialized: value.to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn test_provisioning_round_trip() -> Result<()> {
+        let linked = ProvisioningCipher::new(&mut OsRng);
+        let message = b"account details for the new device";
+
+        let envelope = provisioning_encrypt(linked.public_key(), message, &mut OsRng)?;
+        assert_eq!(linked.decrypt(&envelope)?, message);
+
+        let other = ProvisioningCipher::new(&mut OsRng);
+        assert!(matches!(
+            other.decrypt(&envelope),
+            Err(SignalProtocolError::InvalidCiphertext)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_provisioning_rejects_tampering() -> Result<()> {
+        let linked = ProvisioningCipher::new(&mut OsRng);
+        let envelope = provisioning_encrypt(linked.public_key(), b"message", &mut OsRng)?;
+        let decoded =
+            provisioning::ProvisionEnvelope::decode(envelope.as_slice()).expect("just encoded");
+
+        let reencode = |body: Vec<u8>| {
+            let tampered = provisioning::ProvisionEnvelope {
+                public_key: decoded.public_key.clone(),
+                body: Some(body),
+            };
+            let mut serialized = Vec::new();
+            tampered.encode(&mut serialized).expect("can encode");
+            serialized
+        };
+
+        let body = decoded.body.clone().expect("present");
+        let mut flipped = body.clone();
+        flipped[20] ^= 1;
+        assert!(matches!(
+            linked.decrypt(&reencode(flipped)),
+            Err(SignalProtocolError::InvalidCiphertext)
+        ));
+
+        let mut wrong_version = body.clone();
+        wrong_version[0] = 2;
+        assert!(matches!(
+            linked.decrypt(&reencode(wrong_version)),
+            Err(SignalProtocolError::UnrecognizedCiphertextVersion(2))
+        ));
+
+        assert!(matches!(
+            linked.decrypt(&reencode(body[..40].to_vec())),
+            Err(SignalProtocolError::CiphertextMessageTooShort(40))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_provisioning_uuid_round_trip() -> Result<()> {
+        let uuid = ProvisioningUuid::new("b7f8d8a0-5f1e-4b5e-9a5c-2e3c5d7f9a1b".into())?;
+        let parsed = ProvisioningUuid::try_from(uuid.serialized())?;
+        assert_eq!(parsed.uuid(), uuid.uuid());
+
+        assert!(ProvisioningUuid::try_from(&[][..]).is_err());
+        Ok(())
+    }
+}
@@ -6,23 +6,34 @@
 mod keys;
 mod params;
 
-pub use self::keys::{ChainKey, MessageKeys, RootKey};
+pub use self::keys::{ChainKey, HeaderKey, MessageKeys, RootKey};
 pub use self::params::{AliceSignalProtocolParameters, BobSignalProtocolParameters};
 use crate::proto::storage::SessionStructure;
-use crate::protocol::CIPHERTEXT_MESSAGE_CURRENT_VERSION;
 use crate::state::SessionState;
-use crate::{KeyPair, Result, SessionRecord};
+use crate::{KeyPair, Result, SessionRecord, HEADER_ENCRYPTED_MESSAGE_VERSION};
 use rand::{CryptoRng, Rng};
 
-fn derive_keys(secret_input: &[u8]) -> Result<(RootKey, ChainKey)> {
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Returns the initial root key, the initial chain key, and the header key for that chain.
+///
+/// The header key is only used in header-encrypted sessions. It comes from the same HKDF output
+/// as the other two keys, which are unaffected by deriving it.
+fn derive_keys(secret_input: &[u8]) -> Result<(RootKey, ChainKey, HeaderKey)> {
     let kdf = crate::kdf::HKDF::new(3)?;
 
-    let secrets = kdf.derive_secrets(secret_input, b"WhisperText", 64)?;
+    let secrets = kdf.derive_secrets(secret_input, b"WhisperText", 96)?;
 
     let root_key = RootKey::new(kdf, &secrets[0..32])?;
     let chain_key = ChainKey::new(kdf, &secrets[32..64], 0)?;
+    let header_key = HeaderKey::new(kdf, &secrets[64..96])?;
+
+    Ok((root_key, chain_key, header_key))
+}
 
-    Ok((root_key, chain_key))
+fn uses_header_encryption(session_version: u32) -> bool {
+    session_version == u32::from(HEADER_ENCRYPTED_MESSAGE_VERSION)
 }
 
 pub(crate) fn initialize_alice_session<R: Rng + CryptoRng>(
@@ -59,7 +70,7 @@ pub(crate) fn initialize_alice_session<R: Rng + CryptoRng>(
             .extend_from_slice(&our_base_private_key.calculate_agreement(their_one_time_prekey)?);
     }
 
-    let (root_key, chain_key) = derive_keys(&secrets)?;
+    let (root_key, chain_key, header_key) = derive_keys(&secrets)?;
 
     let (sending_chain_root_key, sending_chain_chain_key) = root_key.create_chain(
         parameters.their_ratchet_key(),
@@ -67,7 +78,7 @@ pub(crate) fn initialize_alice_session<R: Rng + CryptoRng>(
     )?;
 
     let session = SessionStructure {
-        session_version: CIPHERTEXT_MESSAGE_CURRENT_VERSION as u32,
+        session_version: parameters.session_version(),
         local_identity_public: local_identity.public_key().serialize().to_vec(),
         remote_identity_public: parameters.their_identity_key().serialize().to_vec(),
         root_key: sending_chain_root_key.key().to_vec(),
@@ -79,12 +90,28 @@ pub(crate) fn initialize_alice_session<R: Rng + CryptoRng>(
         local_registration_id: 0,
         needs_refresh: false,
         alice_base_key: vec![],
+        last_ratchet_time: 0,
     };
 
     let mut session = SessionState::new(session);
 
-    session.add_receiver_chain(parameters.their_ratchet_key(), &chain_key)?;
-    session.set_sender_chain(&sending_ratchet_key, &sending_chain_chain_key)?;
+    let (receiver_header_key, sender_header_key) =
+        if uses_header_encryption(parameters.session_version()) {
+            (Some(header_key), Some(root_key.header_key()?))
+        } else {
+            (None, None)
+        };
+
+    session.add_receiver_chain(
+        parameters.their_ratchet_key(),
+        &chain_key,
+        receiver_header_key.as_ref(),
+    )?;
+    session.set_sender_chain(
+        &sending_ratchet_key,
+        &sending_chain_chain_key,
+        sender_header_key.as_ref(),
+    )?;
 
     Ok(session)
 }
@@ -127,10 +154,10 @@ pub(crate) fn initialize_bob_session(
         );
     }
 
-    let (root_key, chain_key) = derive_keys(&secrets)?;
+    let (root_key, chain_key, header_key) = derive_keys(&secrets)?;
 
     let session = SessionStructure {
-        session_version: CIPHERTEXT_MESSAGE_CURRENT_VERSION as u32,
+        session_version: parameters.session_version(),
         local_identity_public: local_identity.public_key().serialize().to_vec(),
         remote_identity_public: parameters.their_identity_key().serialize().to_vec(),
         root_key: root_key.key().to_vec(),
@@ -142,11 +169,17 @@ pub(crate) fn initialize_bob_session(
         local_registration_id: 0,
         needs_refresh: false,
         alice_base_key: vec![],
+        last_ratchet_time: 0,
     };
 
     let mut session = SessionState::new(session);
 
-    session.set_sender_chain(&parameters.our_ratchet_key_pair(), &chain_key)?;
+    let header_key = if uses_header_encryption(parameters.session_version()) {
+        Some(&header_key)
+    } else {
+        None
+    };
+    session.set_sender_chain(&parameters.our_ratchet_key_pair(), &chain_key, header_key)?;
 
     Ok(session)
 }
@@ -6,8 +6,12 @@
 use arrayref::array_ref;
 
 use crate::crypto;
+use crate::redact::{secret, Redact};
 use crate::{PrivateKey, PublicKey, Result, SignalProtocolError, HKDF};
-use std::fmt;
+
+use alloc::vec::Vec;
+use core::fmt;
+use subtle::ConstantTimeEq;
 
 pub struct MessageKeys {
     cipher_key: [u8; 32],
@@ -18,7 +22,8 @@ pub struct MessageKeys {
 
 impl MessageKeys {
     pub fn derive_keys(input_key_material: &[u8], kdf: HKDF, counter: u32) -> Result<Self> {
-        let okm = kdf.derive_secrets(input_key_material, b"WhisperMessageKeys", 80)?;
+        let mut okm = [0u8; 80];
+        kdf.derive_secrets_into(input_key_material, b"WhisperMessageKeys", &mut okm)?;
         Ok(MessageKeys {
             cipher_key: *array_ref![okm, 0, 32],
             mac_key: *array_ref![okm, 32, 32],
@@ -67,7 +72,7 @@ impl MessageKeys {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct ChainKey {
     kdf: HKDF,
     key: [u8; 32],
@@ -121,7 +126,7 @@ impl ChainKey {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct RootKey {
     kdf: HKDF,
     key: [u8; 32],
@@ -148,11 +153,12 @@ impl RootKey {
         our_ratchet_key: &PrivateKey,
     ) -> Result<(RootKey, ChainKey)> {
         let shared_secret = our_ratchet_key.calculate_agreement(their_ratchet_key)?;
-        let derived_secret_bytes = self.kdf.derive_salted_secrets(
+        let mut derived_secret_bytes = [0u8; 64];
+        self.kdf.derive_salted_secrets_into(
             shared_secret.as_ref(),
             &self.key,
             b"WhisperRatchet",
-            64,
+            &mut derived_secret_bytes,
         )?;
         Ok((
             RootKey {
@@ -166,14 +172,145 @@ impl RootKey {
             },
         ))
     }
+
+    /// The key for message headers in chains created from this root key.
+    ///
+    /// Both parties derive a chain from the same root key, so the receiver can compute the header
+    /// key for the sender's next chain before it has seen the new ratchet key.
+    pub fn header_key(&self) -> Result<HeaderKey> {
+        let mut key = [0u8; 32];
+        self.kdf
+            .derive_secrets_into(&self.key, b"WhisperHeaderKey", &mut key)?;
+        Ok(HeaderKey { kdf: self.kdf, key })
+    }
+}
+
+/// Encrypts the headers of messages in one chain of a header-encrypted session.
+///
+/// Headers are sealed deterministically, SIV-style: the truncated MAC of the header is also the
+/// nonce for AES-CTR. No two headers in a chain are the same, because their counters differ, so
+/// the nonce never repeats under one key.
+#[derive(Clone)]
+pub struct HeaderKey {
+    kdf: HKDF,
+    key: [u8; 32],
+}
+
+impl HeaderKey {
+    const TAG_LENGTH: usize = 16;
+
+    pub fn new(kdf: HKDF, key: &[u8]) -> Result<Self> {
+        if key.len() != 32 {
+            return Err(SignalProtocolError::InvalidChainKeyLength(key.len()));
+        }
+        Ok(Self {
+            kdf,
+            key: *array_ref![key, 0, 32],
+        })
+    }
+
+    #[inline]
+    pub fn key(&self) -> &[u8; 32] {
+        &self.key
+    }
+
+    /// The length of a sealed header of `len` bytes.
+    pub const fn sealed_len(len: usize) -> usize {
+        Self::TAG_LENGTH + len
+    }
+
+    pub fn seal(&self, header: &[u8]) -> Result<Vec<u8>> {
+        let (cipher_key, mac_key) = self.derive_keys()?;
+        let tag = crypto::hmac_sha256(&mac_key, header)?;
+        let nonce = array_ref![tag, 0, 16];
+        let mut sealed = Vec::with_capacity(Self::sealed_len(header.len()));
+        sealed.extend_from_slice(nonce);
+        sealed.extend(crypto::aes_256_ctr_encrypt_with_nonce(
+            header,
+            &cipher_key,
+            nonce,
+        )?);
+        Ok(sealed)
+    }
+
+    /// Recovers a header sealed with [`seal`](Self::seal).
+    ///
+    /// Returns `None` if `sealed` was not produced under this key, which is expected while trying
+    /// each receiver chain in turn.
+    pub fn open(&self, sealed: &[u8]) -> Result<Option<Vec<u8>>> {
+        if sealed.len() < Self::TAG_LENGTH {
+            return Ok(None);
+        }
+        let (cipher_key, mac_key) = self.derive_keys()?;
+        let nonce = array_ref![sealed, 0, 16];
+        let header = crypto::aes_256_ctr_encrypt_with_nonce(
+            &sealed[Self::TAG_LENGTH..],
+            &cipher_key,
+            nonce,
+        )?;
+        let tag = crypto::hmac_sha256(&mac_key, &header)?;
+        if bool::from(tag[..Self::TAG_LENGTH].ct_eq(nonce)) {
+            Ok(Some(header))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn derive_keys(&self) -> Result<([u8; 32], [u8; 32])> {
+        let mut okm = [0u8; 64];
+        self.kdf
+            .derive_secrets_into(&self.key, b"WhisperHeaderKeys", &mut okm)?;
+        Ok((*array_ref![okm, 0, 32], *array_ref![okm, 32, 32]))
+    }
 }
 
 impl fmt::Display for RootKey {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", hex::encode(self.key))
+        fmt::Debug::fmt(&secret(&self.key, true), f)
+    }
+}
+
+impl Redact for MessageKeys {
+    fn fmt_redactable(&self, f: &mut fmt::Formatter, redact: bool) -> fmt::Result {
+        f.debug_struct("MessageKeys")
+            .field("cipher_key", &secret(&self.cipher_key, redact))
+            .field("mac_key", &secret(&self.mac_key, redact))
+            .field("iv", &secret(&self.iv, redact))
+            .field("counter", &self.counter)
+            .finish()
+    }
+}
+
+impl Redact for ChainKey {
+    fn fmt_redactable(&self, f: &mut fmt::Formatter, redact: bool) -> fmt::Result {
+        f.debug_struct("ChainKey")
+            .field("kdf", &self.kdf)
+            .field("key", &secret(&self.key, redact))
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+impl Redact for RootKey {
+    fn fmt_redactable(&self, f: &mut fmt::Formatter, redact: bool) -> fmt::Result {
+        f.debug_struct("RootKey")
+            .field("kdf", &self.kdf)
+            .field("key", &secret(&self.key, redact))
+            .finish()
+    }
+}
+
+impl Redact for HeaderKey {
+    fn fmt_redactable(&self, f: &mut fmt::Formatter, redact: bool) -> fmt::Result {
+        f.debug_struct("HeaderKey")
+            .field("kdf", &self.kdf)
+            .field("key", &secret(&self.key, redact))
+            .finish()
     }
 }
 
+impl_redacted_debug!(MessageKeys, ChainKey, RootKey, HeaderKey);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -296,4 +433,27 @@ mod tests {
         assert_eq!(&next_chain, next_chain_key.key());
         Ok(())
     }
+
+    #[test]
+    fn test_header_key_seal_open() -> Result<()> {
+        let root_key = RootKey::new(HKDF::new(3)?, &[0x42; 32])?;
+        let header_key = root_key.header_key()?;
+        let other_key = RootKey::new(HKDF::new(3)?, &[0x43; 32])?.header_key()?;
+
+        let sealed = header_key.seal(b"header")?;
+        assert_eq!(sealed.len(), HeaderKey::sealed_len(6));
+        assert_eq!(header_key.open(&sealed)?.as_deref(), Some(&b"header"[..]));
+        assert_eq!(other_key.open(&sealed)?, None);
+
+        // Sealing is deterministic, but different headers get different nonces.
+        assert_eq!(header_key.seal(b"header")?, sealed);
+        assert_ne!(header_key.seal(b"headex")?[..16], sealed[..16]);
+
+        for i in 0..sealed.len() {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 1;
+            assert_eq!(header_key.open(&tampered)?, None);
+        }
+        Ok(())
+    }
 }
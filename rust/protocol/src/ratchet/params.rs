@@ -3,21 +3,25 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
-use crate::{IdentityKey, IdentityKeyPair, KeyPair, PublicKey};
+use crate::{
+    IdentityKey, IdentityKeyPairOps, KeyPair, PublicKey, CIPHERTEXT_MESSAGE_CURRENT_VERSION,
+};
 
-pub struct AliceSignalProtocolParameters {
-    our_identity_key_pair: IdentityKeyPair,
+pub struct AliceSignalProtocolParameters<'a> {
+    our_identity_key_pair: IdentityKeyPairOps<'a>,
     our_base_key_pair: KeyPair,
 
     their_identity_key: IdentityKey,
     their_signed_pre_key: PublicKey,
     their_one_time_pre_key: Option<PublicKey>,
     their_ratchet_key: PublicKey,
+
+    session_version: u32,
 }
 
-impl AliceSignalProtocolParameters {
+impl<'a> AliceSignalProtocolParameters<'a> {
     pub fn new(
-        our_identity_key_pair: IdentityKeyPair,
+        our_identity_key_pair: impl Into<IdentityKeyPairOps<'a>>,
         our_base_key_pair: KeyPair,
         their_identity_key: IdentityKey,
         their_signed_pre_key: PublicKey,
@@ -25,17 +29,23 @@ impl AliceSignalProtocolParameters {
         their_ratchet_key: PublicKey,
     ) -> Self {
         Self {
-            our_identity_key_pair,
+            our_identity_key_pair: our_identity_key_pair.into(),
             our_base_key_pair,
             their_identity_key,
             their_signed_pre_key,
             their_one_time_pre_key,
             their_ratchet_key,
+            session_version: CIPHERTEXT_MESSAGE_CURRENT_VERSION.into(),
         }
     }
 
+    /// Records the session version negotiated with the other party, instead of the current one.
+    pub fn set_session_version(&mut self, session_version: u32) {
+        self.session_version = session_version;
+    }
+
     #[inline]
-    pub fn our_identity_key_pair(&self) -> &IdentityKeyPair {
+    pub fn our_identity_key_pair(&self) -> &IdentityKeyPairOps<'a> {
         &self.our_identity_key_pair
     }
 
@@ -63,21 +73,28 @@ impl AliceSignalProtocolParameters {
     pub fn their_ratchet_key(&self) -> &PublicKey {
         &self.their_ratchet_key
     }
+
+    #[inline]
+    pub fn session_version(&self) -> u32 {
+        self.session_version
+    }
 }
 
-pub struct BobSignalProtocolParameters {
-    our_identity_key_pair: IdentityKeyPair,
+pub struct BobSignalProtocolParameters<'a> {
+    our_identity_key_pair: IdentityKeyPairOps<'a>,
     our_signed_pre_key_pair: KeyPair,
     our_one_time_pre_key_pair: Option<KeyPair>,
     our_ratchet_key_pair: KeyPair,
 
     their_identity_key: IdentityKey,
     their_base_key: PublicKey,
+
+    session_version: u32,
 }
 
-impl BobSignalProtocolParameters {
+impl<'a> BobSignalProtocolParameters<'a> {
     pub fn new(
-        our_identity_key_pair: IdentityKeyPair,
+        our_identity_key_pair: impl Into<IdentityKeyPairOps<'a>>,
         our_signed_pre_key_pair: KeyPair,
         our_one_time_pre_key_pair: Option<KeyPair>,
         our_ratchet_key_pair: KeyPair,
@@ -85,17 +102,23 @@ impl BobSignalProtocolParameters {
         their_base_key: PublicKey,
     ) -> Self {
         Self {
-            our_identity_key_pair,
+            our_identity_key_pair: our_identity_key_pair.into(),
             our_signed_pre_key_pair,
             our_one_time_pre_key_pair,
             our_ratchet_key_pair,
             their_identity_key,
             their_base_key,
+            session_version: CIPHERTEXT_MESSAGE_CURRENT_VERSION.into(),
         }
     }
 
+    /// Records the session version chosen by the other party, instead of the current one.
+    pub fn set_session_version(&mut self, session_version: u32) {
+        self.session_version = session_version;
+    }
+
     #[inline]
-    pub fn our_identity_key_pair(&self) -> &IdentityKeyPair {
+    pub fn our_identity_key_pair(&self) -> &IdentityKeyPairOps<'a> {
         &self.our_identity_key_pair
     }
 
@@ -123,4 +146,9 @@ impl BobSignalProtocolParameters {
     pub fn their_base_key(&self) -> &PublicKey {
         &self.their_base_key
     }
+
+    #[inline]
+    pub fn session_version(&self) -> u32 {
+        self.session_version
+    }
 }
@@ -0,0 +1,117 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Keeping key material out of `Debug` output.
+//!
+//! Types that hold private keys, chain keys, or other secrets implement `Debug` through
+//! [`Redact`], which prints each secret as its length plus a short SHA-256 fingerprint. That is
+//! enough to tell two keys apart in a log without revealing either. Because the redaction happens
+//! in `Debug` itself, it also covers panic messages and errors that the bridges turn into strings.
+//!
+//! To see the actual bytes, as a test might, wrap the value with [`Redact::dangerous_unredacted`].
+
+use crate::utils::hex_encode;
+
+use core::fmt;
+use sha2::{Digest, Sha256};
+
+/// A type whose `Debug` output redacts the secrets it contains.
+pub trait Redact {
+    /// Writes the `Debug` representation of `self`, with secrets redacted if `redact` is set.
+    fn fmt_redactable(&self, f: &mut fmt::Formatter, redact: bool) -> fmt::Result;
+
+    /// Returns a wrapper whose `Debug` output includes every secret in full.
+    ///
+    /// The result must never end up in a log; it exists for tests and local debugging.
+    fn dangerous_unredacted(&self) -> Redactable<'_, Self> {
+        Redactable {
+            value: self,
+            redact: false,
+        }
+    }
+}
+
+/// Formats a [`Redact`] value with or without its secrets.
+pub struct Redactable<'a, T: ?Sized> {
+    value: &'a T,
+    redact: bool,
+}
+
+impl<T: Redact + ?Sized> fmt::Debug for Redactable<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.value.fmt_redactable(f, self.redact)
+    }
+}
+
+/// Formats `value` as a field of a type that is itself being formatted with `redact`.
+pub(crate) fn nested<T: Redact + ?Sized>(value: &T, redact: bool) -> Redactable<'_, T> {
+    Redactable { value, redact }
+}
+
+/// Formats secret bytes as `<redacted N bytes, sha256:0123abcd>`, or as hex if `redact` is unset.
+pub(crate) struct Secret<'a> {
+    bytes: &'a [u8],
+    redact: bool,
+}
+
+pub(crate) fn secret(bytes: &[u8], redact: bool) -> Secret<'_> {
+    Secret { bytes, redact }
+}
+
+impl fmt::Debug for Secret<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.redact {
+            return f.write_str(&hex_encode(self.bytes));
+        }
+        let digest = Sha256::digest(self.bytes);
+        write!(
+            f,
+            "<redacted {} bytes, sha256:{}>",
+            self.bytes.len(),
+            hex_encode(&digest[..4])
+        )
+    }
+}
+
+/// Implements `Debug` for types that implement [`Redact`], redacting their secrets.
+macro_rules! impl_redacted_debug {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl core::fmt::Debug for $ty {
+                fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                    crate::redact::Redact::fmt_redactable(self, f, true)
+                }
+            }
+        )+
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{IdentityKeyPair, KeyPair, PreKeyRecord};
+    use alloc::format;
+    use rand::rngs::OsRng;
+
+    use super::*;
+
+    #[test]
+    fn test_private_keys_are_redacted() {
+        let key_pair = KeyPair::generate(&mut OsRng);
+        let private_key = hex_encode(&key_pair.private_key.serialize());
+
+        let redacted = format!("{:?}", key_pair);
+        assert!(!redacted.contains(&private_key));
+        assert!(redacted.contains("<redacted 32 bytes, sha256:"));
+        assert!(format!("{:?}", key_pair.dangerous_unredacted()).contains(&private_key));
+
+        let record = PreKeyRecord::new(7, &key_pair);
+        assert!(!format!("{:?}", record).contains(&private_key));
+        assert!(format!("{:?}", record.dangerous_unredacted()).contains(&private_key));
+
+        let identity = IdentityKeyPair::generate(&mut OsRng);
+        let identity_private = hex_encode(&identity.private_key().serialize());
+        assert!(!format!("{:?}", identity).contains(&identity_private));
+    }
+}
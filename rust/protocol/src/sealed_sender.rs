@@ -4,17 +4,29 @@
 //
 
 use crate::{
-    message_encrypt, CiphertextMessageType, Context, IdentityKeyStore, KeyPair,
-    PreKeySignalMessage, PreKeyStore, PrivateKey, ProtocolAddress, PublicKey, Result, SessionStore,
-    SignalMessage, SignalProtocolError, SignedPreKeyStore, HKDF,
+    message_encrypt, CiphertextMessageType, Context, ErrorContext, IdentityKeyStore, KeyPair,
+    PreKeySignalMessage, PreKeyStore, PrivateKey, ProtocolAddress, PublicKey, ReplayCache, Result,
+    SessionStore, SignalMessage, SignalProtocolError, SignedPreKeyStore, HKDF,
 };
 
 use crate::crypto;
+use crate::error::ResultExt;
+use crate::metrics;
 use crate::proto;
+use crate::redact::{secret, Redact};
 use crate::session_cipher;
+
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
 use prost::Message;
 use rand::{CryptoRng, Rng};
-use std::convert::TryFrom;
+use sha2::{Digest, Sha256};
 use subtle::ConstantTimeEq;
 
 #[derive(Debug, Clone)]
@@ -256,7 +268,31 @@ impl SenderCertificate {
         })
     }
 
+    /// Checks that this certificate was issued under `trust_root` and has not expired as of
+    /// `validation_time`.
+    ///
+    /// An expired certificate is reported as `Ok(false)`, the same as a forged one; use
+    /// [`validate_with_grace_period`](Self::validate_with_grace_period) to tell them apart.
     pub fn validate(&self, trust_root: &PublicKey, validation_time: u64) -> Result<bool> {
+        match self.validate_with_grace_period(trust_root, validation_time, 0) {
+            Err(SignalProtocolError::SealedSenderCertificateExpired(_)) => Ok(false),
+            result => result,
+        }
+    }
+
+    /// Like [`validate`](Self::validate), but accepts the certificate for `grace_period` past its
+    /// expiration, to tolerate clock skew between the sender and the receiver.
+    ///
+    /// A certificate that is correctly signed but expired even with the grace period produces a
+    /// [`SealedSenderCertificateExpired`](SignalProtocolError::SealedSenderCertificateExpired)
+    /// error rather than `Ok(false)`, so the caller can refresh certificates instead of treating
+    /// it as forged.
+    pub fn validate_with_grace_period(
+        &self,
+        trust_root: &PublicKey,
+        validation_time: u64,
+        grace_period: u64,
+    ) -> Result<bool> {
         if !self.signer.validate(&trust_root)? {
             return Ok(false);
         }
@@ -269,8 +305,10 @@ impl SenderCertificate {
             return Ok(false);
         }
 
-        if validation_time > self.expiration {
-            return Ok(false);
+        if validation_time > self.expiration.saturating_add(grace_period) {
+            return Err(SignalProtocolError::SealedSenderCertificateExpired(
+                self.expiration,
+            ));
         }
 
         Ok(true)
@@ -405,7 +443,7 @@ pub struct UnidentifiedSenderMessage {
     serialized: Vec<u8>,
 }
 
-const SEALED_SENDER_VERSION: u8 = 1;
+pub const SEALED_SENDER_VERSION: u8 = 1;
 
 impl UnidentifiedSenderMessage {
     pub fn deserialize(data: &[u8]) -> Result<Self> {
@@ -564,6 +602,14 @@ impl StaticKeys {
     }
 }
 
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip(destination, sender_cert, ptext, session_store, identity_store, ctx, rng),
+        fields(destination = %destination)
+    )
+)]
 pub async fn sealed_sender_encrypt<R: Rng + CryptoRng>(
     destination: &ProtocolAddress,
     sender_cert: &SenderCertificate,
@@ -573,102 +619,121 @@ pub async fn sealed_sender_encrypt<R: Rng + CryptoRng>(
     ctx: Context,
     rng: &mut R,
 ) -> Result<Vec<u8>> {
-    let message = message_encrypt(ptext, destination, session_store, identity_store, ctx).await?;
-
-    let our_identity = identity_store.get_identity_key_pair(ctx).await?;
-    let their_identity = identity_store
-        .get_identity(destination, ctx)
-        .await?
-        .ok_or_else(|| SignalProtocolError::SessionNotFound(format!("{}", destination)))?;
-
-    let ephemeral = KeyPair::generate(rng);
-
-    let eph_keys = EphemeralKeys::calculate(
-        their_identity.public_key(),
-        &ephemeral.public_key,
-        &ephemeral.private_key,
-        true,
-    )?;
-
-    let static_key_ctext = crypto::aes256_ctr_hmacsha256_encrypt(
-        &our_identity.public_key().serialize(),
-        &eph_keys.cipher_key()?,
-        &eph_keys.mac_key()?,
-    )?;
-
-    let static_keys = StaticKeys::calculate(
-        their_identity.public_key(),
-        our_identity.private_key(),
-        eph_keys.chain_key()?,
-        &static_key_ctext,
-    )?;
-
-    let usmc = UnidentifiedSenderMessageContent::new(
-        message.message_type(),
-        sender_cert.clone(),
-        message.serialize().to_vec(),
-    )?;
-    let message_data = crypto::aes256_ctr_hmacsha256_encrypt(
-        usmc.serialized()?,
-        &static_keys.cipher_key()?,
-        &static_keys.mac_key()?,
-    )?;
-
-    Ok(
-        UnidentifiedSenderMessage::new(ephemeral.public_key, static_key_ctext, message_data)?
-            .serialized()?
-            .to_vec(),
-    )
+    let result: Result<_> = async {
+        let message =
+            message_encrypt(ptext, destination, session_store, identity_store, ctx).await?;
+
+        // message_encrypt has its own span for the calls it makes.
+        #[cfg(feature = "tracing")]
+        let identity_store = &mut crate::trace::TracedIdentityKeyStore(identity_store);
+
+        let our_identity = identity_store.get_identity_key_pair(ctx).await?;
+        let their_identity = identity_store
+            .get_identity(destination, ctx)
+            .await?
+            .ok_or_else(|| SignalProtocolError::SessionNotFound(format!("{}", destination)))?;
+
+        let ephemeral = KeyPair::generate(rng);
+
+        let eph_keys = EphemeralKeys::calculate(
+            their_identity.public_key(),
+            &ephemeral.public_key,
+            &ephemeral.private_key,
+            true,
+        )?;
+
+        let static_key_ctext = crypto::aes256_ctr_hmacsha256_encrypt(
+            &our_identity.public_key().serialize(),
+            &eph_keys.cipher_key()?,
+            &eph_keys.mac_key()?,
+        )?;
+
+        let static_keys = StaticKeys::calculate(
+            their_identity.public_key(),
+            our_identity.private_key(),
+            eph_keys.chain_key()?,
+            &static_key_ctext,
+        )?;
+
+        let usmc = UnidentifiedSenderMessageContent::new(
+            message.message_type(),
+            sender_cert.clone(),
+            message.serialize().to_vec(),
+        )?;
+        let message_data = crypto::aes256_ctr_hmacsha256_encrypt(
+            usmc.serialized()?,
+            &static_keys.cipher_key()?,
+            &static_keys.mac_key()?,
+        )?;
+
+        Ok(
+            UnidentifiedSenderMessage::new(ephemeral.public_key, static_key_ctext, message_data)?
+                .serialized()?
+                .to_vec(),
+        )
+    }
+    .await;
+    result.with_context(|| ErrorContext::new("sealed_sender_encrypt").with_address(destination))
 }
 
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip(ciphertext, identity_store, ctx))
+)]
 pub async fn sealed_sender_decrypt_to_usmc(
     ciphertext: &[u8],
     identity_store: &mut dyn IdentityKeyStore,
     ctx: Context,
 ) -> Result<UnidentifiedSenderMessageContent> {
-    let our_identity = identity_store.get_identity_key_pair(ctx).await?;
-    let usm = UnidentifiedSenderMessage::deserialize(ciphertext)?;
-
-    let eph_keys = EphemeralKeys::calculate(
-        &usm.ephemeral_public()?,
-        &our_identity.public_key(),
-        &our_identity.private_key(),
-        false,
-    )?;
-
-    let static_key_bytes = crypto::aes256_ctr_hmacsha256_decrypt(
-        usm.encrypted_static()?,
-        &eph_keys.cipher_key()?,
-        &eph_keys.mac_key()?,
-    )?;
-
-    let static_key = PublicKey::try_from(&static_key_bytes[..])?;
-
-    let static_keys = StaticKeys::calculate(
-        &static_key,
-        our_identity.private_key(),
-        eph_keys.chain_key()?,
-        usm.encrypted_static()?,
-    )?;
-
-    let message_bytes = crypto::aes256_ctr_hmacsha256_decrypt(
-        usm.encrypted_message()?,
-        &static_keys.cipher_key()?,
-        &static_keys.mac_key()?,
-    )?;
-
-    let usmc = UnidentifiedSenderMessageContent::deserialize(&message_bytes)?;
-
-    if !bool::from(static_key_bytes.ct_eq(&usmc.sender()?.key()?.serialize())) {
-        return Err(SignalProtocolError::InvalidSealedSenderMessage(
-            "sender certificate key does not match message key".to_string(),
-        ));
-    }
-
-    Ok(usmc)
+    #[cfg(feature = "tracing")]
+    let identity_store = &mut crate::trace::TracedIdentityKeyStore(identity_store);
+
+    let result: Result<_> = async {
+        let our_identity = identity_store.get_identity_key_pair(ctx).await?;
+        let usm = UnidentifiedSenderMessage::deserialize(ciphertext)?;
+
+        let eph_keys = EphemeralKeys::calculate(
+            &usm.ephemeral_public()?,
+            &our_identity.public_key(),
+            &our_identity.private_key(),
+            false,
+        )?;
+
+        let static_key_bytes = crypto::aes256_ctr_hmacsha256_decrypt(
+            usm.encrypted_static()?,
+            &eph_keys.cipher_key()?,
+            &eph_keys.mac_key()?,
+        )?;
+
+        let static_key = PublicKey::try_from(&static_key_bytes[..])?;
+
+        let static_keys = StaticKeys::calculate(
+            &static_key,
+            our_identity.private_key(),
+            eph_keys.chain_key()?,
+            usm.encrypted_static()?,
+        )?;
+
+        let message_bytes = crypto::aes256_ctr_hmacsha256_decrypt(
+            usm.encrypted_message()?,
+            &static_keys.cipher_key()?,
+            &static_keys.mac_key()?,
+        )?;
+
+        let usmc = UnidentifiedSenderMessageContent::deserialize(&message_bytes)?;
+
+        if !bool::from(static_key_bytes.ct_eq(&usmc.sender()?.key()?.serialize())) {
+            return Err(SignalProtocolError::InvalidSealedSenderMessage(
+                "sender certificate key does not match message key".to_string(),
+            ));
+        }
+
+        Ok(usmc)
+    }
+    .await;
+    result.with_context(|| ErrorContext::new("sealed_sender_decrypt_to_usmc"))
 }
 
-#[derive(Debug)]
 pub struct SealedSenderDecryptionResult {
     pub sender_uuid: String,
     pub sender_e164: Option<String>,
@@ -694,8 +759,41 @@ impl SealedSenderDecryptionResult {
     }
 }
 
+impl Redact for SealedSenderDecryptionResult {
+    fn fmt_redactable(&self, f: &mut fmt::Formatter, redact: bool) -> fmt::Result {
+        f.debug_struct("SealedSenderDecryptionResult")
+            .field("sender_uuid", &self.sender_uuid)
+            .field("sender_e164", &self.sender_e164)
+            .field("device_id", &self.device_id)
+            .field("message", &secret(&self.message, redact))
+            .finish()
+    }
+}
+
+impl_redacted_debug!(SealedSenderDecryptionResult);
+
 #[allow(clippy::too_many_arguments)]
-pub async fn sealed_sender_decrypt(
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip(
+            ciphertext,
+            trust_root,
+            timestamp,
+            local_e164,
+            local_uuid,
+            local_device_id,
+            identity_store,
+            session_store,
+            pre_key_store,
+            signed_pre_key_store,
+            ctx,
+            rng
+        )
+    )
+)]
+pub async fn sealed_sender_decrypt<R: Rng + CryptoRng>(
     ciphertext: &[u8],
     trust_root: &PublicKey,
     timestamp: u64,
@@ -707,73 +805,260 @@ pub async fn sealed_sender_decrypt(
     pre_key_store: &mut dyn PreKeyStore,
     signed_pre_key_store: &mut dyn SignedPreKeyStore,
     ctx: Context,
+    rng: &mut R,
 ) -> Result<SealedSenderDecryptionResult> {
-    let usmc = sealed_sender_decrypt_to_usmc(ciphertext, identity_store, ctx).await?;
-
-    if !usmc.sender()?.validate(trust_root, timestamp)? {
-        return Err(SignalProtocolError::InvalidSealedSenderMessage(
-            "trust root validation failed".to_string(),
-        ));
-    }
+    decrypt(
+        ciphertext,
+        trust_root,
+        timestamp,
+        0,
+        local_e164,
+        local_uuid,
+        local_device_id,
+        identity_store,
+        session_store,
+        pre_key_store,
+        signed_pre_key_store,
+        None,
+        ctx,
+        rng,
+    )
+    .await
+}
 
-    let is_local_uuid = local_uuid == usmc.sender()?.sender_uuid()?;
+/// Like [`sealed_sender_decrypt`], but accepts a sender certificate for `grace_period` past its
+/// expiration.
+///
+/// See [`SenderCertificate::validate_with_grace_period`].
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip(
+            ciphertext,
+            trust_root,
+            timestamp,
+            grace_period,
+            local_e164,
+            local_uuid,
+            local_device_id,
+            identity_store,
+            session_store,
+            pre_key_store,
+            signed_pre_key_store,
+            ctx,
+            rng
+        )
+    )
+)]
+pub async fn sealed_sender_decrypt_with_grace_period<R: Rng + CryptoRng>(
+    ciphertext: &[u8],
+    trust_root: &PublicKey,
+    timestamp: u64,
+    grace_period: u64,
+    local_e164: Option<String>,
+    local_uuid: String,
+    local_device_id: u32,
+    identity_store: &mut dyn IdentityKeyStore,
+    session_store: &mut dyn SessionStore,
+    pre_key_store: &mut dyn PreKeyStore,
+    signed_pre_key_store: &mut dyn SignedPreKeyStore,
+    ctx: Context,
+    rng: &mut R,
+) -> Result<SealedSenderDecryptionResult> {
+    decrypt(
+        ciphertext,
+        trust_root,
+        timestamp,
+        grace_period,
+        local_e164,
+        local_uuid,
+        local_device_id,
+        identity_store,
+        session_store,
+        pre_key_store,
+        signed_pre_key_store,
+        None,
+        ctx,
+        rng,
+    )
+    .await
+}
 
-    let is_local_e164 = match (local_e164, usmc.sender()?.sender_e164()?) {
-        (Some(l), Some(s)) => l == s,
-        (_, _) => false,
-    };
+/// Like [`sealed_sender_decrypt_with_grace_period`], but rejects a message that has already been
+/// received with [`SealedSenderReplay`](SignalProtocolError::SealedSenderReplay).
+///
+/// `replay_cache` is consulted once the sender certificate has been validated, before the message
+/// reaches the sender's session, and the message is recorded there only once it has been
+/// successfully decrypted. Entries are kept until the sender certificate (plus `grace_period`)
+/// expires.
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip(
+            ciphertext,
+            trust_root,
+            timestamp,
+            grace_period,
+            local_e164,
+            local_uuid,
+            local_device_id,
+            identity_store,
+            session_store,
+            pre_key_store,
+            signed_pre_key_store,
+            replay_cache,
+            ctx,
+            rng
+        )
+    )
+)]
+pub async fn sealed_sender_decrypt_with_replay_cache<R: Rng + CryptoRng>(
+    ciphertext: &[u8],
+    trust_root: &PublicKey,
+    timestamp: u64,
+    grace_period: u64,
+    local_e164: Option<String>,
+    local_uuid: String,
+    local_device_id: u32,
+    identity_store: &mut dyn IdentityKeyStore,
+    session_store: &mut dyn SessionStore,
+    pre_key_store: &mut dyn PreKeyStore,
+    signed_pre_key_store: &mut dyn SignedPreKeyStore,
+    replay_cache: &mut dyn ReplayCache,
+    ctx: Context,
+    rng: &mut R,
+) -> Result<SealedSenderDecryptionResult> {
+    decrypt(
+        ciphertext,
+        trust_root,
+        timestamp,
+        grace_period,
+        local_e164,
+        local_uuid,
+        local_device_id,
+        identity_store,
+        session_store,
+        pre_key_store,
+        signed_pre_key_store,
+        Some(replay_cache),
+        ctx,
+        rng,
+    )
+    .await
+}
 
-    if (is_local_e164 || is_local_uuid) && usmc.sender()?.sender_device_id()? == local_device_id {
-        return Err(SignalProtocolError::SealedSenderSelfSend);
-    }
+#[allow(clippy::too_many_arguments)]
+async fn decrypt<R: Rng + CryptoRng>(
+    ciphertext: &[u8],
+    trust_root: &PublicKey,
+    timestamp: u64,
+    grace_period: u64,
+    local_e164: Option<String>,
+    local_uuid: String,
+    local_device_id: u32,
+    identity_store: &mut dyn IdentityKeyStore,
+    session_store: &mut dyn SessionStore,
+    pre_key_store: &mut dyn PreKeyStore,
+    signed_pre_key_store: &mut dyn SignedPreKeyStore,
+    mut replay_cache: Option<&mut dyn ReplayCache>,
+    ctx: Context,
+    rng: &mut R,
+) -> Result<SealedSenderDecryptionResult> {
+    // Set once the contents are handed to session_cipher, which counts the result itself.
+    let mut counted = false;
+    let result: Result<_> = async {
+        let usmc = sealed_sender_decrypt_to_usmc(ciphertext, identity_store, ctx).await?;
+
+        if !usmc
+            .sender()?
+            .validate_with_grace_period(trust_root, timestamp, grace_period)?
+        {
+            return Err(SignalProtocolError::InvalidSealedSenderMessage(
+                "trust root validation failed".to_string(),
+            ));
+        }
 
-    let mut rng = rand::rngs::OsRng;
+        let is_local_uuid = local_uuid == usmc.sender()?.sender_uuid()?;
 
-    let remote_address = ProtocolAddress::new(
-        usmc.sender()?.sender_uuid()?.to_string(),
-        usmc.sender()?.sender_device_id()?,
-    );
+        let is_local_e164 = match (local_e164, usmc.sender()?.sender_e164()?) {
+            (Some(l), Some(s)) => l == s,
+            (_, _) => false,
+        };
 
-    let message = match usmc.msg_type()? {
-        CiphertextMessageType::Whisper => {
-            let ctext = SignalMessage::try_from(usmc.contents()?)?;
-            session_cipher::message_decrypt_signal(
-                &ctext,
-                &remote_address,
-                session_store,
-                identity_store,
-                &mut rng,
-                ctx,
-            )
-            .await?
+        if (is_local_e164 || is_local_uuid) && usmc.sender()?.sender_device_id()? == local_device_id
+        {
+            return Err(SignalProtocolError::SealedSenderSelfSend);
         }
-        CiphertextMessageType::PreKey => {
-            let ctext = PreKeySignalMessage::try_from(usmc.contents()?)?;
-            session_cipher::message_decrypt_prekey(
-                &ctext,
-                &remote_address,
-                session_store,
-                identity_store,
-                pre_key_store,
-                signed_pre_key_store,
-                &mut rng,
-                ctx,
-            )
-            .await?
+
+        let message_id: [u8; 32] = Sha256::digest(ciphertext).into();
+        if let Some(replay_cache) = replay_cache.as_mut() {
+            if replay_cache.contains(&message_id, timestamp, ctx).await? {
+                return Err(SignalProtocolError::SealedSenderReplay);
+            }
         }
-        _ => {
-            return Err(SignalProtocolError::InvalidSealedSenderMessage(
-                "Unknown message type".to_owned(),
-            ))
+
+        let remote_address = ProtocolAddress::new(
+            usmc.sender()?.sender_uuid()?.to_string(),
+            usmc.sender()?.sender_device_id()?,
+        );
+
+        let message = match usmc.msg_type()? {
+            CiphertextMessageType::Whisper => {
+                let ctext = SignalMessage::try_from(usmc.contents()?)?;
+                counted = true;
+                session_cipher::message_decrypt_signal(
+                    &ctext,
+                    &remote_address,
+                    session_store,
+                    identity_store,
+                    rng,
+                    ctx,
+                )
+                .await?
+            }
+            CiphertextMessageType::PreKey => {
+                let ctext = PreKeySignalMessage::try_from(usmc.contents()?)?;
+                counted = true;
+                session_cipher::message_decrypt_prekey(
+                    &ctext,
+                    &remote_address,
+                    session_store,
+                    identity_store,
+                    pre_key_store,
+                    signed_pre_key_store,
+                    rng,
+                    ctx,
+                )
+                .await?
+            }
+            _ => {
+                return Err(SignalProtocolError::InvalidSealedSenderMessage(
+                    "Unknown message type".to_owned(),
+                ))
+            }
+        };
+
+        if let Some(replay_cache) = replay_cache {
+            let expiration = usmc.sender()?.expiration()?.saturating_add(grace_period);
+            replay_cache.record(&message_id, expiration, ctx).await?;
         }
-    };
 
-    Ok(SealedSenderDecryptionResult {
-        sender_uuid: usmc.sender()?.sender_uuid()?.to_string(),
-        sender_e164: usmc.sender()?.sender_e164()?.map(|s| s.to_string()),
-        device_id: usmc.sender()?.sender_device_id()?,
-        message,
-    })
+        Ok(SealedSenderDecryptionResult {
+            sender_uuid: usmc.sender()?.sender_uuid()?.to_string(),
+            sender_e164: usmc.sender()?.sender_e164()?.map(|s| s.to_string()),
+            device_id: usmc.sender()?.sender_device_id()?,
+            message,
+        })
+    }
+    .await;
+    if !counted {
+        metrics::record_decryption(&result);
+    }
+    result.with_context(|| ErrorContext::new("sealed_sender_decrypt"))
 }
 
 #[test]
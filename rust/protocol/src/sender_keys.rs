@@ -6,13 +6,25 @@
 use crate::consts;
 use crate::crypto::hmac_sha256;
 use crate::proto::storage as storage_proto;
-use crate::{PrivateKey, ProtocolAddress, PublicKey, Result, SignalProtocolError, HKDF};
-
+use crate::proto::unknown_fields::{
+    record_version_for_serialize, UnknownFields, CURRENT_RECORD_VERSION,
+};
+use crate::redact::{nested, secret, Redact};
+use crate::utils::hex_encode;
+use crate::{
+    PrivateKey, ProtocolAddress, PublicKey, RecordLimit, Result, SignalProtocolError, HKDF,
+};
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
 use prost::Message;
-use std::collections::VecDeque;
-use std::convert::TryFrom;
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SenderKeyName {
     group_id: String,
     sender: ProtocolAddress,
@@ -40,7 +52,13 @@ impl SenderKeyName {
     }
 }
 
-#[derive(Debug, Clone)]
+impl fmt::Display for SenderKeyName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} in group {}", self.sender, self.group_id)
+    }
+}
+
+#[derive(Clone)]
 pub struct SenderMessageKey {
     iteration: u32,
     iv: Vec<u8>,
@@ -51,7 +69,8 @@ pub struct SenderMessageKey {
 impl SenderMessageKey {
     pub fn new(iteration: u32, seed: Vec<u8>) -> Result<Self> {
         let hkdf = HKDF::new(3)?;
-        let derived = hkdf.derive_secrets(&seed, b"WhisperGroup", 48)?;
+        let mut derived = [0u8; 48];
+        hkdf.derive_secrets_into(&seed, b"WhisperGroup", &mut derived)?;
         Ok(Self {
             iteration,
             seed,
@@ -94,7 +113,7 @@ impl SenderMessageKey {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct SenderChainKey {
     iteration: u32,
     chain_key: Vec<u8>,
@@ -146,12 +165,15 @@ impl SenderChainKey {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct SenderKeyState {
     state: storage_proto::SenderKeyStateStructure,
+    unknown_fields: UnknownFields,
 }
 
 impl SenderKeyState {
+    const KNOWN_FIELD_TAGS: &'static [u32] = &[1, 2, 3, 4];
+
     pub fn new(
         id: u32,
         iteration: u32,
@@ -176,21 +198,34 @@ impl SenderKeyState {
             sender_message_keys: vec![],
         };
 
-        Ok(Self { state })
+        Ok(Self::from_protobuf(state))
     }
 
     pub fn deserialize(buf: &[u8]) -> Result<Self> {
         let state = storage_proto::SenderKeyStateStructure::decode(buf)?;
-        Ok(Self { state })
+        let state = Self {
+            state,
+            unknown_fields: UnknownFields::extract(buf, Self::KNOWN_FIELD_TAGS)?,
+        };
+        state.check_record_limits()?;
+        Ok(state)
+    }
+
+    fn check_record_limits(&self) -> Result<()> {
+        RecordLimit::MessageKeys.check(self.state.sender_message_keys.len())
     }
 
     pub fn from_protobuf(state: storage_proto::SenderKeyStateStructure) -> Self {
-        Self { state }
+        Self {
+            state,
+            unknown_fields: UnknownFields::default(),
+        }
     }
 
     pub fn serialize(&self) -> Result<Vec<u8>> {
         let mut buf = vec![];
         self.state.encode(&mut buf)?;
+        self.unknown_fields.append_to(&mut buf);
         Ok(buf)
     }
 
@@ -269,26 +304,122 @@ impl SenderKeyState {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Tracks which group members have been sent which of our sender keys.
+///
+/// A member needs a new SenderKeyDistributionMessage whenever the sender key they hold is not the
+/// current one, and the sender key must be replaced when a member who holds it leaves the group.
+#[derive(Clone, Debug, Default)]
+pub struct SenderKeyDistributionTracker {
+    distributed: BTreeMap<ProtocolAddress, u32>,
+}
+
+impl SenderKeyDistributionTracker {
+    fn from_protobuf(distributions: Vec<storage_proto::SenderKeyDistributionStructure>) -> Self {
+        let mut distributed = BTreeMap::new();
+        for distribution in distributions {
+            distributed.insert(
+                ProtocolAddress::new(distribution.name, distribution.device_id),
+                distribution.sender_key_id,
+            );
+        }
+        Self { distributed }
+    }
+
+    fn as_protobuf(&self) -> Vec<storage_proto::SenderKeyDistributionStructure> {
+        self.distributed
+            .iter()
+            .map(
+                |(member, sender_key_id)| storage_proto::SenderKeyDistributionStructure {
+                    name: member.name().to_string(),
+                    device_id: member.device_id(),
+                    sender_key_id: *sender_key_id,
+                },
+            )
+            .collect()
+    }
+
+    /// Records that `member` has been sent the sender key with ID `sender_key_id`.
+    pub fn mark_distributed(&mut self, member: &ProtocolAddress, sender_key_id: u32) {
+        self.distributed.insert(member.clone(), sender_key_id);
+    }
+
+    /// The ID of the sender key `member` was last sent, if any.
+    pub fn distributed_key_id(&self, member: &ProtocolAddress) -> Option<u32> {
+        self.distributed.get(member).copied()
+    }
+
+    /// Stops tracking `member`, returning the ID of the sender key they were last sent.
+    pub fn forget(&mut self, member: &ProtocolAddress) -> Option<u32> {
+        self.distributed.remove(member)
+    }
+
+    /// Every tracked member with the ID of the sender key they were last sent.
+    pub fn members(&self) -> impl Iterator<Item = (&ProtocolAddress, u32)> {
+        self.distributed
+            .iter()
+            .map(|(member, sender_key_id)| (member, *sender_key_id))
+    }
+
+    pub fn clear(&mut self) {
+        self.distributed.clear()
+    }
+}
+
+#[derive(Clone)]
 pub struct SenderKeyRecord {
     states: VecDeque<SenderKeyState>,
+    distribution_tracker: SenderKeyDistributionTracker,
+    serialized_version: u32,
+    unknown_fields: UnknownFields,
 }
 
 impl SenderKeyRecord {
+    const KNOWN_FIELD_TAGS: &'static [u32] = &[1, 2, 3];
+    const SENDER_KEY_STATES_TAG: u32 = 1;
+
     pub fn new_empty() -> Self {
         Self {
             states: VecDeque::new(),
+            distribution_tracker: SenderKeyDistributionTracker::default(),
+            serialized_version: CURRENT_RECORD_VERSION,
+            unknown_fields: UnknownFields::default(),
         }
     }
 
     pub fn deserialize(buf: &[u8]) -> Result<SenderKeyRecord> {
         let skr = storage_proto::SenderKeyRecordStructure::decode(buf)?;
-
+        let unknown_fields = UnknownFields::extract(buf, Self::KNOWN_FIELD_TAGS)?;
+
+        RecordLimit::SenderKeyStates.check(skr.sender_key_states.len())?;
+        let state_unknown_fields = UnknownFields::extract_embedded(
+            buf,
+            Self::SENDER_KEY_STATES_TAG,
+            SenderKeyState::KNOWN_FIELD_TAGS,
+        )?;
         let mut states = VecDeque::with_capacity(skr.sender_key_states.len());
-        for state in skr.sender_key_states {
-            states.push_back(SenderKeyState::from_protobuf(state))
+        for (state, (_, unknown_fields)) in
+            skr.sender_key_states.into_iter().zip(state_unknown_fields)
+        {
+            let state = SenderKeyState {
+                state,
+                unknown_fields,
+            };
+            state.check_record_limits()?;
+            states.push_back(state)
         }
-        Ok(Self { states })
+        Ok(Self {
+            states,
+            distribution_tracker: SenderKeyDistributionTracker::from_protobuf(skr.distributions),
+            serialized_version: skr.version,
+            unknown_fields,
+        })
+    }
+
+    /// The format version of the data this record was deserialized from.
+    ///
+    /// Records written before versions were recorded report 0.
+    pub fn serialized_version(&self) -> u32 {
+        self.serialized_version
     }
 
     pub fn is_empty(&self) -> Result<bool> {
@@ -351,6 +482,48 @@ impl SenderKeyRecord {
         )
     }
 
+    pub fn distribution_tracker(&self) -> &SenderKeyDistributionTracker {
+        &self.distribution_tracker
+    }
+
+    pub fn distribution_tracker_mut(&mut self) -> &mut SenderKeyDistributionTracker {
+        &mut self.distribution_tracker
+    }
+
+    fn current_sender_key_id(&self) -> Option<u32> {
+        self.states.front().map(|state| state.state.sender_key_id)
+    }
+
+    /// Records that `member` has been sent the current sender key.
+    pub fn mark_distributed(&mut self, member: &ProtocolAddress) -> Result<()> {
+        let sender_key_id = self
+            .current_sender_key_id()
+            .ok_or(SignalProtocolError::NoSenderKeyState)?;
+        self.distribution_tracker
+            .mark_distributed(member, sender_key_id);
+        Ok(())
+    }
+
+    /// Whether `member` has yet to be sent the current sender key.
+    pub fn needs_distribution(&self, member: &ProtocolAddress) -> bool {
+        match self.current_sender_key_id() {
+            Some(sender_key_id) => {
+                self.distribution_tracker.distributed_key_id(member) != Some(sender_key_id)
+            }
+            None => true,
+        }
+    }
+
+    /// Stops tracking a member who has left the group.
+    ///
+    /// Returns true if they hold the current sender key, in which case it should be replaced
+    /// before the next message is sent to the group.
+    pub fn forget_distribution(&mut self, member: &ProtocolAddress) -> bool {
+        let current = self.current_sender_key_id();
+        let forgotten = self.distribution_tracker.forget(member);
+        current.is_some() && forgotten == current
+    }
+
     pub fn as_protobuf(&self) -> Result<storage_proto::SenderKeyRecordStructure> {
         let mut states = Vec::with_capacity(self.states.len());
         for state in &self.states {
@@ -359,12 +532,97 @@ impl SenderKeyRecord {
 
         Ok(storage_proto::SenderKeyRecordStructure {
             sender_key_states: states,
+            version: record_version_for_serialize(self.serialized_version),
+            distributions: self.distribution_tracker.as_protobuf(),
         })
     }
 
     pub fn serialize(&self) -> Result<Vec<u8>> {
         let mut buf = vec![];
-        self.as_protobuf()?.encode(&mut buf)?;
+        let mut record = self.as_protobuf()?;
+        let states = core::mem::take(&mut record.sender_key_states);
+        record.encode(&mut buf)?;
+        for (state, structure) in self.states.iter().zip(&states) {
+            state
+                .unknown_fields
+                .encode_embedded(Self::SENDER_KEY_STATES_TAG, structure, &mut buf);
+        }
+        self.unknown_fields.append_to(&mut buf);
         Ok(buf)
     }
 }
+
+impl Redact for SenderMessageKey {
+    fn fmt_redactable(&self, f: &mut fmt::Formatter, redact: bool) -> fmt::Result {
+        f.debug_struct("SenderMessageKey")
+            .field("iteration", &self.iteration)
+            .field("iv", &secret(&self.iv, redact))
+            .field("cipher_key", &secret(&self.cipher_key, redact))
+            .field("seed", &secret(&self.seed, redact))
+            .finish()
+    }
+}
+
+impl Redact for SenderChainKey {
+    fn fmt_redactable(&self, f: &mut fmt::Formatter, redact: bool) -> fmt::Result {
+        f.debug_struct("SenderChainKey")
+            .field("iteration", &self.iteration)
+            .field("chain_key", &secret(&self.chain_key, redact))
+            .finish()
+    }
+}
+
+impl Redact for SenderKeyState {
+    fn fmt_redactable(&self, f: &mut fmt::Formatter, redact: bool) -> fmt::Result {
+        if !redact {
+            return f
+                .debug_struct("SenderKeyState")
+                .field("state", &self.state)
+                .finish();
+        }
+        let chain_key = self.state.sender_chain_key.as_ref();
+        let signing_key = self.state.sender_signing_key.as_ref();
+        f.debug_struct("SenderKeyState")
+            .field("sender_key_id", &self.state.sender_key_id)
+            .field("iteration", &chain_key.map(|key| key.iteration))
+            .field("seed", &chain_key.map(|key| secret(&key.seed, true)))
+            .field(
+                "signing_key_public",
+                &signing_key.map(|key| hex_encode(&key.public)),
+            )
+            .field(
+                "signing_key_private",
+                &signing_key.map(|key| secret(&key.private, true)),
+            )
+            .field("message_keys", &self.state.sender_message_keys.len())
+            .finish()
+    }
+}
+
+impl Redact for SenderKeyRecord {
+    fn fmt_redactable(&self, f: &mut fmt::Formatter, redact: bool) -> fmt::Result {
+        f.debug_struct("SenderKeyRecord")
+            .field(
+                "states",
+                &self
+                    .states
+                    .iter()
+                    .map(|state| nested(state, redact))
+                    .collect::<Vec<_>>(),
+            )
+            .field(
+                "distributed_to",
+                &self.distribution_tracker.distributed.len(),
+            )
+            .field("serialized_version", &self.serialized_version)
+            .field("unknown_fields", &self.unknown_fields)
+            .finish()
+    }
+}
+
+impl_redacted_debug!(
+    SenderMessageKey,
+    SenderChainKey,
+    SenderKeyState,
+    SenderKeyRecord
+);
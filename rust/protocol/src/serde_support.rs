@@ -0,0 +1,160 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! serde support for the public key and record types, enabled by the `serde` feature.
+//!
+//! Records and keys are represented using their existing serialized form as a byte string, so
+//! a value saved through serde can also be read with the matching `deserialize` method (and
+//! vice versa). Plain aggregates like [ProtocolAddress] derive their implementations instead.
+
+use crate::{
+    IdentityKey, IdentityKeyPair, PreKeyRecord, PrivateKey, PublicKey, SenderKeyRecord,
+    SessionRecord, SignedPreKeyRecord,
+};
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Error as _, Serialize, Serializer};
+
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a byte string")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(v)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut result = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            result.push(byte);
+        }
+        Ok(result)
+    }
+}
+
+fn deserialize_bytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    deserializer.deserialize_byte_buf(BytesVisitor)
+}
+
+macro_rules! serde_as_bytes {
+    ($typ:ty, |$this:ident| $to_bytes:expr, $from_bytes:expr) => {
+        impl Serialize for $typ {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let $this = self;
+                let bytes = $to_bytes.map_err(S::Error::custom)?;
+                serializer.serialize_bytes(&bytes)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $typ {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let bytes = deserialize_bytes(deserializer)?;
+                $from_bytes(&bytes[..]).map_err(de::Error::custom)
+            }
+        }
+    };
+}
+
+serde_as_bytes!(
+    PublicKey,
+    |key| crate::Result::Ok(key.serialize()),
+    PublicKey::deserialize
+);
+serde_as_bytes!(
+    PrivateKey,
+    |key| crate::Result::Ok(key.serialize()),
+    PrivateKey::deserialize
+);
+serde_as_bytes!(
+    IdentityKey,
+    |key| crate::Result::Ok(key.serialize()),
+    IdentityKey::decode
+);
+serde_as_bytes!(
+    IdentityKeyPair,
+    |pair| crate::Result::Ok(pair.serialize()),
+    IdentityKeyPair::try_from
+);
+serde_as_bytes!(
+    SessionRecord,
+    |record| record.serialize(),
+    SessionRecord::deserialize
+);
+serde_as_bytes!(
+    PreKeyRecord,
+    |record| record.serialize(),
+    PreKeyRecord::deserialize
+);
+serde_as_bytes!(
+    SignedPreKeyRecord,
+    |record| record.serialize(),
+    SignedPreKeyRecord::deserialize
+);
+serde_as_bytes!(
+    SenderKeyRecord,
+    |record| record.serialize(),
+    SenderKeyRecord::deserialize
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{KeyPair, ProtocolAddress, SenderKeyName};
+
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn test_key_round_trip() {
+        let key_pair = KeyPair::generate(&mut OsRng);
+        let json = serde_json::to_string(&key_pair).expect("can serialize");
+        let decoded: KeyPair = serde_json::from_str(&json).expect("can deserialize");
+        assert_eq!(decoded.public_key, key_pair.public_key);
+        assert_eq!(
+            decoded.private_key.serialize(),
+            key_pair.private_key.serialize()
+        );
+
+        let pair = IdentityKeyPair::generate(&mut OsRng);
+        let json = serde_json::to_string(&pair).expect("can serialize");
+        let decoded: IdentityKeyPair = serde_json::from_str(&json).expect("can deserialize");
+        assert_eq!(decoded.serialize(), pair.serialize());
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let key_pair = KeyPair::generate(&mut OsRng);
+        let record = PreKeyRecord::new(23, &key_pair);
+        let json = serde_json::to_string(&record).expect("can serialize");
+        let decoded: PreKeyRecord = serde_json::from_str(&json).expect("can deserialize");
+        assert_eq!(
+            decoded.serialize().expect("can serialize"),
+            record.serialize().expect("can serialize")
+        );
+
+        assert!(serde_json::from_str::<SessionRecord>("[1, 2, 3]").is_err());
+    }
+
+    #[test]
+    fn test_field_names() {
+        let name = SenderKeyName::new("group".to_string(), ProtocolAddress::new("+1".into(), 2))
+            .expect("valid");
+        assert_eq!(
+            serde_json::to_string(&name).expect("can serialize"),
+            r#"{"group_id":"group","sender":{"name":"+1","device_id":2}}"#
+        );
+    }
+}
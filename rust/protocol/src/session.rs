@@ -4,15 +4,21 @@
 //
 
 use crate::{
-    Context, Direction, IdentityKeyStore, KeyPair, PreKeyBundle, PreKeySignalMessage, PreKeyStore,
-    ProtocolAddress, Result, SessionRecord, SessionStore, SignalProtocolError, SignedPreKeyStore,
+    AuditEvent, Context, Direction, ErrorContext, IdentityKey, IdentityKeyStore, KeyPair,
+    PreKeyBundle, PreKeySignalMessage, PreKeyStore, ProtocolAddress, Result, SessionRecord,
+    SessionStore, SignalProtocolError, SignedPreKeyStore, CIPHERTEXT_MESSAGE_CURRENT_VERSION,
 };
 
+use crate::audit;
+use crate::consts::MAX_REGISTRATION_ID;
+use crate::error::ResultExt;
 use crate::ratchet;
 use crate::ratchet::{AliceSignalProtocolParameters, BobSignalProtocolParameters};
 use crate::state::PreKeyId;
 use rand::{CryptoRng, Rng};
 
+use alloc::string::ToString;
+
 /*
 These functions are on SessionBuilder in Java
 
@@ -22,6 +28,62 @@ its reference to the various data stores, instead the functions are
 free standing.
  */
 
+/// Registration IDs are 14 bits; anything larger means the peer's state is corrupt.
+fn check_registration_id(remote_address: &ProtocolAddress, registration_id: u32) -> Result<()> {
+    if registration_id > MAX_REGISTRATION_ID {
+        return Err(SignalProtocolError::InvalidRegistrationId(
+            remote_address.clone(),
+            registration_id,
+        ));
+    }
+    Ok(())
+}
+
+/// The oldest session version this library can establish.
+pub const MIN_SESSION_VERSION: u32 = CIPHERTEXT_MESSAGE_CURRENT_VERSION as u32;
+/// The newest session version this library can establish.
+#[cfg(not(feature = "header-encryption"))]
+pub const MAX_SESSION_VERSION: u32 = CIPHERTEXT_MESSAGE_CURRENT_VERSION as u32;
+/// The newest session version this library can establish.
+#[cfg(feature = "header-encryption")]
+pub const MAX_SESSION_VERSION: u32 = crate::HEADER_ENCRYPTED_MESSAGE_VERSION as u32;
+
+/// Picks the newest session version supported both locally and by the owner of `bundle`.
+pub fn negotiate_session_version(bundle: &PreKeyBundle) -> Result<u32> {
+    let their_max = bundle.max_session_version()?;
+    if their_max < MIN_SESSION_VERSION {
+        return Err(SignalProtocolError::LegacyCiphertextVersion(
+            their_max as u8,
+        ));
+    }
+    let their_min = bundle.min_session_version()?;
+    if their_min > MAX_SESSION_VERSION {
+        return Err(SignalProtocolError::UnrecognizedMessageVersion(their_min));
+    }
+    Ok(their_max.min(MAX_SESSION_VERSION))
+}
+
+/// Asks `identity_store` whether `identity_key` is trusted for `remote_address`.
+///
+/// If `identity_key` is `approved_identity`, which the caller has explicitly decided to trust, the
+/// store is not consulted.
+pub(crate) async fn is_trusted_identity(
+    identity_store: &mut dyn IdentityKeyStore,
+    remote_address: &ProtocolAddress,
+    identity_key: &IdentityKey,
+    direction: Direction,
+    approved_identity: Option<&IdentityKey>,
+    ctx: Context,
+) -> Result<bool> {
+    if approved_identity == Some(identity_key) {
+        log::info!("Trusting approved identity for {}", remote_address);
+        return Ok(true);
+    }
+    identity_store
+        .is_trusted_identity(remote_address, identity_key, direction, ctx)
+        .await
+}
+
 pub async fn process_prekey(
     message: &PreKeySignalMessage,
     remote_address: &ProtocolAddress,
@@ -31,16 +93,45 @@ pub async fn process_prekey(
     signed_prekey_store: &mut dyn SignedPreKeyStore,
     ctx: Context,
 ) -> Result<Option<PreKeyId>> {
+    process_prekey_with_approved_identity(
+        message,
+        remote_address,
+        session_record,
+        identity_store,
+        pre_key_store,
+        signed_prekey_store,
+        None,
+        ctx,
+    )
+    .await
+}
+
+/// Like [`process_prekey`], but trusts `approved_identity` regardless of what the identity store
+/// says about it.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn process_prekey_with_approved_identity(
+    message: &PreKeySignalMessage,
+    remote_address: &ProtocolAddress,
+    session_record: &mut SessionRecord,
+    identity_store: &mut dyn IdentityKeyStore,
+    pre_key_store: &mut dyn PreKeyStore,
+    signed_prekey_store: &mut dyn SignedPreKeyStore,
+    approved_identity: Option<&IdentityKey>,
+    ctx: Context,
+) -> Result<Option<PreKeyId>> {
+    check_registration_id(remote_address, message.registration_id())?;
+
     let their_identity_key = message.identity_key();
 
-    if !identity_store
-        .is_trusted_identity(
-            &remote_address,
-            their_identity_key,
-            Direction::Receiving,
-            ctx,
-        )
-        .await?
+    if !is_trusted_identity(
+        identity_store,
+        &remote_address,
+        their_identity_key,
+        Direction::Receiving,
+        approved_identity,
+        ctx,
+    )
+    .await?
     {
         return Err(SignalProtocolError::UntrustedIdentity(
             remote_address.clone(),
@@ -49,6 +140,7 @@ pub async fn process_prekey(
 
     let unsigned_pre_key_id = process_prekey_v3(
         message,
+        remote_address,
         session_record,
         signed_prekey_store,
         pre_key_store,
@@ -57,15 +149,29 @@ pub async fn process_prekey(
     )
     .await?;
 
-    identity_store
-        .save_identity(&remote_address, their_identity_key, ctx)
-        .await?;
+    audit::save_identity(identity_store, &remote_address, their_identity_key, ctx).await?;
 
     Ok(unsigned_pre_key_id)
 }
 
+/// Archives the current session in `session_record`, if there is one, reporting it to the audit
+/// hook.
+fn archive_current_state(
+    remote_address: &ProtocolAddress,
+    session_record: &mut SessionRecord,
+) -> Result<()> {
+    if session_record.has_current_session_state() {
+        session_record.archive_current_state()?;
+        audit::audit(|| AuditEvent::SessionArchived {
+            address: remote_address.clone(),
+        });
+    }
+    Ok(())
+}
+
 async fn process_prekey_v3(
     message: &PreKeySignalMessage,
+    remote_address: &ProtocolAddress,
     session_record: &mut SessionRecord,
     signed_prekey_store: &mut dyn SignedPreKeyStore,
     pre_key_store: &mut dyn PreKeyStore,
@@ -97,16 +203,17 @@ async fn process_prekey_v3(
         None
     };
 
-    let parameters = BobSignalProtocolParameters::new(
-        identity_store.get_identity_key_pair(ctx).await?,
+    let mut parameters = BobSignalProtocolParameters::new(
+        identity_store.get_identity_key_ops(ctx).await?,
         our_signed_pre_key_pair, // signed pre key
         our_one_time_pre_key_pair,
         our_signed_pre_key_pair, // ratchet key
         *message.identity_key(),
         *message.base_key(),
     );
+    parameters.set_session_version(message.message_version().into());
 
-    session_record.archive_current_state()?;
+    archive_current_state(remote_address, session_record)?;
 
     let mut new_session = ratchet::initialize_bob_session(&parameters)?;
 
@@ -119,6 +226,14 @@ async fn process_prekey_v3(
     Ok(message.pre_key_id())
 }
 
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip(remote_address, session_store, identity_store, bundle, csprng, ctx),
+        fields(remote_address = %remote_address)
+    )
+)]
 pub async fn process_prekey_bundle<R: Rng + CryptoRng>(
     remote_address: &ProtocolAddress,
     session_store: &mut dyn SessionStore,
@@ -127,73 +242,89 @@ pub async fn process_prekey_bundle<R: Rng + CryptoRng>(
     mut csprng: &mut R,
     ctx: Context,
 ) -> Result<()> {
-    let their_identity_key = bundle.identity_key()?;
-
-    if !identity_store
-        .is_trusted_identity(&remote_address, their_identity_key, Direction::Sending, ctx)
-        .await?
-    {
-        return Err(SignalProtocolError::UntrustedIdentity(
-            remote_address.clone(),
-        ));
+    #[cfg(feature = "tracing")]
+    let session_store = &mut crate::trace::TracedSessionStore(session_store);
+    #[cfg(feature = "tracing")]
+    let identity_store = &mut crate::trace::TracedIdentityKeyStore(identity_store);
+
+    let result: Result<_> = async {
+        check_registration_id(remote_address, bundle.registration_id()?)?;
+        let session_version = negotiate_session_version(bundle)?;
+
+        let their_identity_key = bundle.identity_key()?;
+
+        if !identity_store
+            .is_trusted_identity(&remote_address, their_identity_key, Direction::Sending, ctx)
+            .await?
+        {
+            return Err(SignalProtocolError::UntrustedIdentity(
+                remote_address.clone(),
+            ));
+        }
+
+        if !their_identity_key.public_key().verify_signature(
+            &bundle.signed_pre_key_public()?.serialize(),
+            bundle.signed_pre_key_signature()?,
+        )? {
+            return Err(SignalProtocolError::SignatureValidationFailed);
+        }
+
+        let mut session_record = session_store
+            .load_session(&remote_address, ctx)
+            .await?
+            .unwrap_or_else(SessionRecord::new_fresh);
+
+        let our_base_key_pair = KeyPair::generate(&mut csprng);
+        let their_signed_prekey = bundle.signed_pre_key_public()?;
+
+        let their_one_time_prekey = bundle.pre_key_public()?;
+        let their_one_time_prekey_id = bundle.pre_key_id()?;
+
+        // The identity key ops borrow the store, so they must be released before the store is
+        // updated below.
+        let mut session = {
+            let our_identity_key_pair = identity_store.get_identity_key_ops(ctx).await?;
+
+            let mut parameters = AliceSignalProtocolParameters::new(
+                our_identity_key_pair,
+                our_base_key_pair,
+                *their_identity_key,
+                their_signed_prekey,
+                their_one_time_prekey,
+                their_signed_prekey,
+            );
+            parameters.set_session_version(session_version);
+
+            ratchet::initialize_alice_session(&parameters, csprng)?
+        };
+
+        log::info!(
+            "set_unacknowledged_pre_key_message for: {} with preKeyId: {}",
+            remote_address,
+            their_one_time_prekey_id.map_or_else(|| "<none>".to_string(), |id| id.to_string())
+        );
+
+        session.set_unacknowledged_pre_key_message(
+            their_one_time_prekey_id,
+            bundle.signed_pre_key_id()?,
+            &our_base_key_pair.public_key,
+        )?;
+
+        session.set_local_registration_id(identity_store.get_local_registration_id(ctx).await?)?;
+        session.set_remote_registration_id(bundle.registration_id()?)?;
+        session.set_alice_base_key(&our_base_key_pair.public_key.serialize())?;
+
+        audit::save_identity(identity_store, &remote_address, their_identity_key, ctx).await?;
+
+        archive_current_state(&remote_address, &mut session_record)?;
+        session_record.promote_state(session)?;
+
+        session_store
+            .store_session(&remote_address, &session_record, ctx)
+            .await?;
+
+        Ok(())
     }
-
-    if !their_identity_key.public_key().verify_signature(
-        &bundle.signed_pre_key_public()?.serialize(),
-        bundle.signed_pre_key_signature()?,
-    )? {
-        return Err(SignalProtocolError::SignatureValidationFailed);
-    }
-
-    let mut session_record = session_store
-        .load_session(&remote_address, ctx)
-        .await?
-        .unwrap_or_else(SessionRecord::new_fresh);
-
-    let our_base_key_pair = KeyPair::generate(&mut csprng);
-    let their_signed_prekey = bundle.signed_pre_key_public()?;
-
-    let their_one_time_prekey = bundle.pre_key_public()?;
-    let their_one_time_prekey_id = bundle.pre_key_id()?;
-
-    let our_identity_key_pair = identity_store.get_identity_key_pair(ctx).await?;
-
-    let parameters = AliceSignalProtocolParameters::new(
-        our_identity_key_pair,
-        our_base_key_pair,
-        *their_identity_key,
-        their_signed_prekey,
-        their_one_time_prekey,
-        their_signed_prekey,
-    );
-
-    let mut session = ratchet::initialize_alice_session(&parameters, csprng)?;
-
-    log::info!(
-        "set_unacknowledged_pre_key_message for: {} with preKeyId: {}",
-        remote_address,
-        their_one_time_prekey_id.map_or_else(|| "<none>".to_string(), |id| id.to_string())
-    );
-
-    session.set_unacknowledged_pre_key_message(
-        their_one_time_prekey_id,
-        bundle.signed_pre_key_id()?,
-        &our_base_key_pair.public_key,
-    )?;
-
-    session.set_local_registration_id(identity_store.get_local_registration_id(ctx).await?)?;
-    session.set_remote_registration_id(bundle.registration_id()?)?;
-    session.set_alice_base_key(&our_base_key_pair.public_key.serialize())?;
-
-    identity_store
-        .save_identity(&remote_address, their_identity_key, ctx)
-        .await?;
-
-    session_record.promote_state(session)?;
-
-    session_store
-        .store_session(&remote_address, &session_record, ctx)
-        .await?;
-
-    Ok(())
+    .await;
+    result.with_context(|| ErrorContext::new("process_prekey_bundle").with_address(remote_address))
 }
@@ -4,19 +4,38 @@
 //
 
 use crate::{
-    CiphertextMessage, Context, Direction, IdentityKeyStore, KeyPair, PreKeySignalMessage,
-    PreKeyStore, ProtocolAddress, PublicKey, Result, SessionRecord, SessionStore, SignalMessage,
-    SignalProtocolError, SignedPreKeyStore,
+    CiphertextMessage, CiphertextMessageType, Context, Direction, ErrorContext, IdentityKey,
+    IdentityKeyStore, KeyPair, PreKeySignalMessage, PreKeyStore, ProtocolAddress, PublicKey,
+    Result, SessionRecord, SessionStore, SignalMessage, SignalProtocolError, SignedPreKeyStore,
 };
 
-use crate::consts::MAX_FORWARD_JUMPS;
+use crate::audit;
+use crate::consts::{MAX_FORWARD_JUMPS, MAX_MESSAGE_KEYS};
 use crate::crypto;
+use crate::error::ResultExt;
+use crate::metrics;
 use crate::ratchet::{ChainKey, MessageKeys};
 use crate::session;
 use crate::state::SessionState;
+use crate::utils::hex_encode;
+
+use alloc::borrow::ToOwned;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
 
 use rand::{CryptoRng, Rng};
 
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip(ptext, remote_address, session_store, identity_store, ctx),
+        fields(remote_address = %remote_address)
+    )
+)]
 pub async fn message_encrypt(
     ptext: &[u8],
     remote_address: &ProtocolAddress,
@@ -24,10 +43,221 @@ pub async fn message_encrypt(
     identity_store: &mut dyn IdentityKeyStore,
     ctx: Context,
 ) -> Result<CiphertextMessage> {
-    let mut session_record = session_store
-        .load_session(&remote_address, ctx)
-        .await?
-        .ok_or_else(|| SignalProtocolError::SessionNotFound(format!("{}", remote_address)))?;
+    encrypt(
+        ptext,
+        remote_address,
+        session_store,
+        identity_store,
+        None,
+        ctx,
+    )
+    .await
+}
+
+/// Like [`message_encrypt`], but trusts `approved_identity` even if `identity_store` doesn't.
+///
+/// Use this after an [`UntrustedIdentity`](SignalProtocolError::UntrustedIdentity) error once
+/// the user has accepted the new identity. If the session's identity is `approved_identity`, it is
+/// saved to `identity_store` as part of the operation; otherwise the store decides as usual.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip(ptext, remote_address, approved_identity, session_store, identity_store, ctx),
+        fields(remote_address = %remote_address)
+    )
+)]
+pub async fn message_encrypt_with_identity_override(
+    ptext: &[u8],
+    remote_address: &ProtocolAddress,
+    approved_identity: &IdentityKey,
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    ctx: Context,
+) -> Result<CiphertextMessage> {
+    encrypt(
+        ptext,
+        remote_address,
+        session_store,
+        identity_store,
+        Some(approved_identity),
+        ctx,
+    )
+    .await
+}
+
+async fn encrypt(
+    ptext: &[u8],
+    remote_address: &ProtocolAddress,
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    approved_identity: Option<&IdentityKey>,
+    ctx: Context,
+) -> Result<CiphertextMessage> {
+    #[cfg(feature = "tracing")]
+    let session_store = &mut crate::trace::TracedSessionStore(session_store);
+    #[cfg(feature = "tracing")]
+    let identity_store = &mut crate::trace::TracedIdentityKeyStore(identity_store);
+
+    let result: Result<_> = async {
+        let mut session_record = session_store
+            .load_session(&remote_address, ctx)
+            .await?
+            .ok_or_else(|| SignalProtocolError::SessionNotFound(format!("{}", remote_address)))?;
+
+        let message = encrypt_with_session_record(ptext, remote_address, &mut session_record)?;
+
+        // XXX why is this check after everything else?!!
+        let their_identity_key = check_identity_for_sending(
+            remote_address,
+            &session_record,
+            identity_store,
+            approved_identity,
+            ctx,
+        )
+        .await?;
+
+        // XXX this could be combined with the above call to the identity store (in a new API)
+        audit::save_identity(identity_store, &remote_address, &their_identity_key, ctx).await?;
+
+        session_store
+            .store_session(&remote_address, &session_record, ctx)
+            .await?;
+        Ok(message)
+    }
+    .await;
+    result.with_context(|| ErrorContext::new("message_encrypt").with_address(remote_address))
+}
+
+/// Encrypts `ptext` for each of `remote_addresses`, returning a result for each in the same order.
+///
+/// A failure for one recipient (such as a missing session or an untrusted identity) does not
+/// affect the others; the caller can leave that recipient out and still send to everyone else.
+/// Each session is loaded from `session_store` and stored back at most once, even if its address
+/// appears more than once. If any message for an address fails, no session is stored for it, and
+/// every message for that address is reported as a failure.
+///
+/// Only errors from storing the updated sessions and identities fail the batch as a whole.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip(ptext, remote_addresses, session_store, identity_store, ctx),
+        fields(recipients = remote_addresses.len())
+    )
+)]
+pub async fn message_encrypt_batch(
+    ptext: &[u8],
+    remote_addresses: &[ProtocolAddress],
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    ctx: Context,
+) -> Result<Vec<Result<CiphertextMessage>>> {
+    #[cfg(feature = "tracing")]
+    let session_store = &mut crate::trace::TracedSessionStore(session_store);
+    #[cfg(feature = "tracing")]
+    let identity_store = &mut crate::trace::TracedIdentityKeyStore(identity_store);
+
+    // `None` marks an address that has failed.
+    let mut session_records: BTreeMap<&ProtocolAddress, Option<SessionRecord>> = BTreeMap::new();
+    let mut results = Vec::with_capacity(remote_addresses.len());
+
+    for remote_address in remote_addresses {
+        if !session_records.contains_key(remote_address) {
+            let loaded = session_store
+                .load_session(remote_address, ctx)
+                .await
+                .and_then(|session_record| {
+                    session_record.ok_or_else(|| {
+                        SignalProtocolError::SessionNotFound(format!("{}", remote_address))
+                    })
+                });
+            match loaded {
+                Ok(session_record) => {
+                    session_records.insert(remote_address, Some(session_record));
+                }
+                Err(e) => {
+                    session_records.insert(remote_address, None);
+                    results.push(Err(e));
+                    continue;
+                }
+            }
+        }
+        let result = match session_records
+            .get_mut(remote_address)
+            .expect("loaded above")
+        {
+            Some(session_record) => {
+                encrypt_with_session_record(ptext, remote_address, session_record)
+            }
+            None => Err(batch_sibling_failed(remote_address)),
+        };
+        if result.is_err() {
+            session_records.insert(remote_address, None);
+        }
+        results.push(result);
+    }
+
+    let mut their_identity_keys = Vec::with_capacity(session_records.len());
+    for (remote_address, session_record) in session_records.iter_mut() {
+        if let Some(record) = session_record {
+            match check_identity_for_sending(remote_address, record, identity_store, None, ctx)
+                .await
+            {
+                Ok(their_identity_key) => their_identity_keys.push(their_identity_key),
+                Err(e) => {
+                    *session_record = None;
+                    // Report the identity failure on the first message for this address.
+                    let first = remote_addresses
+                        .iter()
+                        .position(|address| address == *remote_address)
+                        .expect("address came from the batch");
+                    results[first] = Err(e);
+                }
+            }
+        }
+    }
+
+    // Messages that were encrypted for an address that failed later must not be sent.
+    for (remote_address, result) in remote_addresses.iter().zip(results.iter_mut()) {
+        if result.is_ok() && session_records[remote_address].is_none() {
+            *result = Err(batch_sibling_failed(remote_address));
+        }
+    }
+
+    let successful_sessions = session_records
+        .iter()
+        .filter_map(|(address, record)| record.as_ref().map(|record| (address, record)));
+    for ((remote_address, session_record), their_identity_key) in
+        successful_sessions.zip(their_identity_keys)
+    {
+        audit::save_identity(identity_store, remote_address, &their_identity_key, ctx).await?;
+        session_store
+            .store_session(remote_address, session_record, ctx)
+            .await?;
+    }
+
+    Ok(results)
+}
+
+/// The error reported for a message in a batch when another message to the same address failed.
+fn batch_sibling_failed(remote_address: &ProtocolAddress) -> SignalProtocolError {
+    SignalProtocolError::InvalidState(
+        "message_encrypt_batch",
+        format!(
+            "not sent because another message to {} could not be encrypted",
+            remote_address
+        ),
+    )
+}
+
+/// Encrypts a single message with the current session in `session_record`, advancing its sending
+/// chain. The caller is responsible for storing the updated record.
+fn encrypt_with_session_record(
+    ptext: &[u8],
+    remote_address: &ProtocolAddress,
+    session_record: &mut SessionRecord,
+) -> Result<CiphertextMessage> {
     let session_state = session_record.session_state_mut()?;
 
     let chain_key = session_state.get_sender_chain_key()?;
@@ -37,6 +267,7 @@ pub async fn message_encrypt(
     let sender_ephemeral = session_state.sender_ratchet_key()?;
     let previous_counter = session_state.previous_counter()?;
     let session_version = session_state.session_version()? as u8;
+    let header_key = session_state.sender_header_key()?;
 
     let local_identity_key = session_state.local_identity_key()?;
     let their_identity_key = session_state
@@ -56,9 +287,10 @@ pub async fn message_encrypt(
                 .map_or_else(|| "<none>".to_string(), |id| id.to_string())
         );
 
-        let message = SignalMessage::new(
+        let message = SignalMessage::new_with_header_key(
             session_version,
             message_keys.mac_key(),
+            header_key.as_ref(),
             sender_ephemeral,
             chain_key.index(),
             previous_counter,
@@ -77,9 +309,10 @@ pub async fn message_encrypt(
             message,
         )?)
     } else {
-        CiphertextMessage::SignalMessage(SignalMessage::new(
+        CiphertextMessage::SignalMessage(SignalMessage::new_with_header_key(
             session_version,
             message_keys.mac_key(),
+            header_key.as_ref(),
             sender_ephemeral,
             chain_key.index(),
             previous_counter,
@@ -91,22 +324,38 @@ pub async fn message_encrypt(
 
     session_state.set_sender_chain_key(&chain_key.next_chain_key()?)?;
 
-    // XXX why is this check after everything else?!!
-    if !identity_store
-        .is_trusted_identity(
-            &remote_address,
-            &their_identity_key,
-            Direction::Sending,
-            ctx,
-        )
-        .await?
+    Ok(message)
+}
+
+/// Checks that the remote identity in `session_record` is trusted for sending, and returns it.
+async fn check_identity_for_sending(
+    remote_address: &ProtocolAddress,
+    session_record: &SessionRecord,
+    identity_store: &mut dyn IdentityKeyStore,
+    approved_identity: Option<&IdentityKey>,
+    ctx: Context,
+) -> Result<IdentityKey> {
+    let their_identity_key = session_record
+        .session_state()?
+        .remote_identity_key()?
+        .ok_or(SignalProtocolError::InvalidSessionStructure)?;
+
+    if !session::is_trusted_identity(
+        identity_store,
+        &remote_address,
+        &their_identity_key,
+        Direction::Sending,
+        approved_identity,
+        ctx,
+    )
+    .await?
     {
         log::warn!(
             "Identity key {} is not trusted for remote address {}",
             their_identity_key
                 .public_key()
                 .public_key_bytes()
-                .map_or_else(|e| format!("<error: {}>", e), hex::encode),
+                .map_or_else(|e| format!("<error: {}>", e), hex_encode),
             remote_address,
         );
         return Err(SignalProtocolError::UntrustedIdentity(
@@ -114,17 +363,26 @@ pub async fn message_encrypt(
         ));
     }
 
-    // XXX this could be combined with the above call to the identity store (in a new API)
-    identity_store
-        .save_identity(&remote_address, &their_identity_key, ctx)
-        .await?;
-
-    session_store
-        .store_session(&remote_address, &session_record, ctx)
-        .await?;
-    Ok(message)
+    Ok(their_identity_key)
 }
 
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip(
+            ciphertext,
+            remote_address,
+            session_store,
+            identity_store,
+            pre_key_store,
+            signed_pre_key_store,
+            csprng,
+            ctx,
+        ),
+        fields(remote_address = %remote_address)
+    )
+)]
 pub async fn message_decrypt<R: Rng + CryptoRng>(
     ciphertext: &CiphertextMessage,
     remote_address: &ProtocolAddress,
@@ -134,27 +392,241 @@ pub async fn message_decrypt<R: Rng + CryptoRng>(
     signed_pre_key_store: &mut dyn SignedPreKeyStore,
     csprng: &mut R,
     ctx: Context,
+) -> Result<Vec<u8>> {
+    decrypt(
+        ciphertext,
+        remote_address,
+        session_store,
+        identity_store,
+        pre_key_store,
+        signed_pre_key_store,
+        None,
+        false,
+        csprng,
+        ctx,
+    )
+    .await
+}
+
+/// Like [`message_decrypt`], but trusts `approved_identity` even if `identity_store` doesn't.
+///
+/// Use this after an [`UntrustedIdentity`](SignalProtocolError::UntrustedIdentity) error once
+/// the user has accepted the new identity. If the sender's identity is `approved_identity`, it is
+/// saved to `identity_store` as part of the operation; otherwise the store decides as usual.
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip(
+            ciphertext,
+            remote_address,
+            approved_identity,
+            session_store,
+            identity_store,
+            pre_key_store,
+            signed_pre_key_store,
+            csprng,
+            ctx,
+        ),
+        fields(remote_address = %remote_address)
+    )
+)]
+pub async fn message_decrypt_with_identity_override<R: Rng + CryptoRng>(
+    ciphertext: &CiphertextMessage,
+    remote_address: &ProtocolAddress,
+    approved_identity: &IdentityKey,
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    pre_key_store: &mut dyn PreKeyStore,
+    signed_pre_key_store: &mut dyn SignedPreKeyStore,
+    csprng: &mut R,
+    ctx: Context,
+) -> Result<Vec<u8>> {
+    decrypt(
+        ciphertext,
+        remote_address,
+        session_store,
+        identity_store,
+        pre_key_store,
+        signed_pre_key_store,
+        Some(approved_identity),
+        false,
+        csprng,
+        ctx,
+    )
+    .await
+}
+
+/// The result of [`message_decrypt_returning_duplicates`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecryptionOutcome {
+    /// The message was decrypted for the first time.
+    ///
+    /// `plaintext_mac` identifies the plaintext; a later duplicate of the same message reports
+    /// the same value. It is keyed by the message's own message key, so it can't be recomputed
+    /// once that key has been used up.
+    Decrypted {
+        plaintext: Vec<u8>,
+        plaintext_mac: [u8; 32],
+    },
+    /// The message had already been decrypted.
+    ///
+    /// `plaintext_mac` is the value reported when it was first decrypted, if the session still
+    /// remembers it, so the earlier copy can be found without keeping every ciphertext.
+    Duplicate { plaintext_mac: Option<[u8; 32]> },
+}
+
+/// Like [`message_decrypt`], but reports a message that was already decrypted as a
+/// [`DecryptionOutcome::Duplicate`] rather than a
+/// [`DuplicatedMessage`](SignalProtocolError::DuplicatedMessage) error.
+///
+/// Only messages decrypted through this function can be matched up with their duplicates; the
+/// other decryption functions don't record anything about the messages they decrypt.
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip(
+            ciphertext,
+            remote_address,
+            session_store,
+            identity_store,
+            pre_key_store,
+            signed_pre_key_store,
+            csprng,
+            ctx,
+        ),
+        fields(remote_address = %remote_address)
+    )
+)]
+pub async fn message_decrypt_returning_duplicates<R: Rng + CryptoRng>(
+    ciphertext: &CiphertextMessage,
+    remote_address: &ProtocolAddress,
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    pre_key_store: &mut dyn PreKeyStore,
+    signed_pre_key_store: &mut dyn SignedPreKeyStore,
+    csprng: &mut R,
+    ctx: Context,
+) -> Result<DecryptionOutcome> {
+    let message = match ciphertext {
+        CiphertextMessage::SignalMessage(message) => message,
+        CiphertextMessage::PreKeySignalMessage(message) => message.message(),
+        _ => {
+            return Err(SignalProtocolError::InvalidArgument(
+                "SessionCipher::decrypt cannot decrypt this message type".to_owned(),
+            ))
+        }
+    };
+    let result = decrypt(
+        ciphertext,
+        remote_address,
+        session_store,
+        identity_store,
+        pre_key_store,
+        signed_pre_key_store,
+        None,
+        true,
+        csprng,
+        ctx,
+    )
+    .await;
+
+    match result {
+        Ok(plaintext) => {
+            let plaintext_mac = match session_store.load_session(remote_address, ctx).await? {
+                Some(record) => find_decrypted_message_mac(&record, message)?,
+                None => None,
+            }
+            .ok_or_else(|| {
+                SignalProtocolError::InvalidState(
+                    "message_decrypt_returning_duplicates",
+                    "decrypted message was not recorded".to_string(),
+                )
+            })?;
+            Ok(DecryptionOutcome::Decrypted {
+                plaintext,
+                plaintext_mac,
+            })
+        }
+        Err(e) if matches!(e.root_cause(), SignalProtocolError::DuplicatedMessage(_, _)) => {
+            let plaintext_mac = match session_store.load_session(remote_address, ctx).await? {
+                Some(record) => find_decrypted_message_mac(&record, message)?,
+                None => None,
+            };
+            Ok(DecryptionOutcome::Duplicate { plaintext_mac })
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Looks for the MAC recorded when `ciphertext` was decrypted, in the record's current session
+/// state. Archived states don't keep them.
+fn find_decrypted_message_mac(
+    record: &SessionRecord,
+    ciphertext: &SignalMessage,
+) -> Result<Option<[u8; 32]>> {
+    let state = match record.session_state() {
+        Ok(state) => state,
+        Err(_) => return Ok(None),
+    };
+    let opened;
+    let ciphertext = if state.uses_header_encryption()? {
+        match open_header(state, ciphertext) {
+            Ok(header) => {
+                opened = header;
+                &opened
+            }
+            Err(_) => return Ok(None),
+        }
+    } else {
+        ciphertext
+    };
+    match (ciphertext.sender_ratchet_key(), ciphertext.counter()) {
+        (Ok(their_ephemeral), Ok(counter)) => state.decrypted_message_mac(their_ephemeral, counter),
+        _ => Ok(None),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn decrypt<R: Rng + CryptoRng>(
+    ciphertext: &CiphertextMessage,
+    remote_address: &ProtocolAddress,
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    pre_key_store: &mut dyn PreKeyStore,
+    signed_pre_key_store: &mut dyn SignedPreKeyStore,
+    approved_identity: Option<&IdentityKey>,
+    record_for_duplicates: bool,
+    csprng: &mut R,
+    ctx: Context,
 ) -> Result<Vec<u8>> {
     match ciphertext {
         CiphertextMessage::SignalMessage(m) => {
-            message_decrypt_signal(
+            decrypt_signal(
                 m,
                 remote_address,
                 session_store,
                 identity_store,
+                approved_identity,
+                record_for_duplicates,
                 csprng,
                 ctx,
             )
             .await
         }
         CiphertextMessage::PreKeySignalMessage(m) => {
-            message_decrypt_prekey(
+            decrypt_prekey(
                 m,
                 remote_address,
                 session_store,
                 identity_store,
                 pre_key_store,
                 signed_pre_key_store,
+                approved_identity,
+                record_for_duplicates,
                 csprng,
                 ctx,
             )
@@ -166,6 +638,23 @@ pub async fn message_decrypt<R: Rng + CryptoRng>(
     }
 }
 
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip(
+            ciphertext,
+            remote_address,
+            session_store,
+            identity_store,
+            pre_key_store,
+            signed_pre_key_store,
+            csprng,
+            ctx,
+        ),
+        fields(remote_address = %remote_address)
+    )
+)]
 pub async fn message_decrypt_prekey<R: Rng + CryptoRng>(
     ciphertext: &PreKeySignalMessage,
     remote_address: &ProtocolAddress,
@@ -176,40 +665,127 @@ pub async fn message_decrypt_prekey<R: Rng + CryptoRng>(
     csprng: &mut R,
     ctx: Context,
 ) -> Result<Vec<u8>> {
-    let mut session_record = session_store
-        .load_session(&remote_address, ctx)
-        .await?
-        .unwrap_or_else(SessionRecord::new_fresh);
-
-    let pre_key_id = session::process_prekey(
+    decrypt_prekey(
         ciphertext,
-        &remote_address,
-        &mut session_record,
+        remote_address,
+        session_store,
         identity_store,
         pre_key_store,
         signed_pre_key_store,
+        None,
+        false,
+        csprng,
         ctx,
     )
-    .await?;
+    .await
+}
 
-    let ptext = decrypt_message_with_record(
-        &remote_address,
-        &mut session_record,
-        ciphertext.message(),
+/// Like [`message_decrypt_prekey`], but trusts `approved_identity` even if `identity_store`
+/// doesn't.
+///
+/// See [`message_decrypt_with_identity_override`].
+#[allow(clippy::too_many_arguments)]
+pub async fn message_decrypt_prekey_with_identity_override<R: Rng + CryptoRng>(
+    ciphertext: &PreKeySignalMessage,
+    remote_address: &ProtocolAddress,
+    approved_identity: &IdentityKey,
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    pre_key_store: &mut dyn PreKeyStore,
+    signed_pre_key_store: &mut dyn SignedPreKeyStore,
+    csprng: &mut R,
+    ctx: Context,
+) -> Result<Vec<u8>> {
+    decrypt_prekey(
+        ciphertext,
+        remote_address,
+        session_store,
+        identity_store,
+        pre_key_store,
+        signed_pre_key_store,
+        Some(approved_identity),
+        false,
         csprng,
-    )?;
+        ctx,
+    )
+    .await
+}
 
-    session_store
-        .store_session(&remote_address, &session_record, ctx)
+#[allow(clippy::too_many_arguments)]
+async fn decrypt_prekey<R: Rng + CryptoRng>(
+    ciphertext: &PreKeySignalMessage,
+    remote_address: &ProtocolAddress,
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    pre_key_store: &mut dyn PreKeyStore,
+    signed_pre_key_store: &mut dyn SignedPreKeyStore,
+    approved_identity: Option<&IdentityKey>,
+    record_for_duplicates: bool,
+    csprng: &mut R,
+    ctx: Context,
+) -> Result<Vec<u8>> {
+    #[cfg(feature = "tracing")]
+    let session_store = &mut crate::trace::TracedSessionStore(session_store);
+    #[cfg(feature = "tracing")]
+    let identity_store = &mut crate::trace::TracedIdentityKeyStore(identity_store);
+    #[cfg(feature = "tracing")]
+    let pre_key_store = &mut crate::trace::TracedPreKeyStore(pre_key_store);
+    #[cfg(feature = "tracing")]
+    let signed_pre_key_store = &mut crate::trace::TracedSignedPreKeyStore(signed_pre_key_store);
+
+    let result: Result<_> = async {
+        let mut session_record = session_store
+            .load_session(&remote_address, ctx)
+            .await?
+            .unwrap_or_else(SessionRecord::new_fresh);
+
+        let pre_key_id = session::process_prekey_with_approved_identity(
+            ciphertext,
+            &remote_address,
+            &mut session_record,
+            identity_store,
+            pre_key_store,
+            signed_pre_key_store,
+            approved_identity,
+            ctx,
+        )
         .await?;
 
-    if let Some(pre_key_id) = pre_key_id {
-        pre_key_store.remove_pre_key(pre_key_id, ctx).await?;
-    }
+        let ptext = decrypt_message_with_record(
+            &remote_address,
+            &mut session_record,
+            ciphertext.message(),
+            record_for_duplicates,
+            csprng,
+        )?;
 
-    Ok(ptext)
+        session_store
+            .store_session(&remote_address, &session_record, ctx)
+            .await?;
+
+        if let Some(pre_key_id) = pre_key_id {
+            pre_key_store.remove_pre_key(pre_key_id, ctx).await?;
+        }
+
+        Ok(ptext)
+    }
+    .await;
+    metrics::record_decryption(&result);
+    result.with_context(|| {
+        ErrorContext::new("message_decrypt_prekey")
+            .with_address(remote_address)
+            .with_message_type(CiphertextMessageType::PreKey)
+    })
 }
 
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip(ciphertext, remote_address, session_store, identity_store, csprng, ctx),
+        fields(remote_address = %remote_address)
+    )
+)]
 pub async fn message_decrypt_signal<R: Rng + CryptoRng>(
     ciphertext: &SignalMessage,
     remote_address: &ProtocolAddress,
@@ -218,51 +794,119 @@ pub async fn message_decrypt_signal<R: Rng + CryptoRng>(
     csprng: &mut R,
     ctx: Context,
 ) -> Result<Vec<u8>> {
-    let mut session_record = session_store
-        .load_session(&remote_address, ctx)
-        .await?
-        .ok_or_else(|| SignalProtocolError::SessionNotFound(format!("{}", remote_address)))?;
+    decrypt_signal(
+        ciphertext,
+        remote_address,
+        session_store,
+        identity_store,
+        None,
+        false,
+        csprng,
+        ctx,
+    )
+    .await
+}
 
-    let ptext =
-        decrypt_message_with_record(&remote_address, &mut session_record, ciphertext, csprng)?;
+/// Like [`message_decrypt_signal`], but trusts `approved_identity` even if `identity_store`
+/// doesn't.
+///
+/// See [`message_decrypt_with_identity_override`].
+pub async fn message_decrypt_signal_with_identity_override<R: Rng + CryptoRng>(
+    ciphertext: &SignalMessage,
+    remote_address: &ProtocolAddress,
+    approved_identity: &IdentityKey,
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    csprng: &mut R,
+    ctx: Context,
+) -> Result<Vec<u8>> {
+    decrypt_signal(
+        ciphertext,
+        remote_address,
+        session_store,
+        identity_store,
+        Some(approved_identity),
+        false,
+        csprng,
+        ctx,
+    )
+    .await
+}
 
-    // Why are we performing this check after decryption instead of before?
-    let their_identity_key = session_record
-        .session_state()?
-        .remote_identity_key()?
-        .ok_or(SignalProtocolError::InvalidSessionStructure)?;
+#[allow(clippy::too_many_arguments)]
+async fn decrypt_signal<R: Rng + CryptoRng>(
+    ciphertext: &SignalMessage,
+    remote_address: &ProtocolAddress,
+    session_store: &mut dyn SessionStore,
+    identity_store: &mut dyn IdentityKeyStore,
+    approved_identity: Option<&IdentityKey>,
+    record_for_duplicates: bool,
+    csprng: &mut R,
+    ctx: Context,
+) -> Result<Vec<u8>> {
+    #[cfg(feature = "tracing")]
+    let session_store = &mut crate::trace::TracedSessionStore(session_store);
+    #[cfg(feature = "tracing")]
+    let identity_store = &mut crate::trace::TracedIdentityKeyStore(identity_store);
+
+    let result: Result<_> = async {
+        let mut session_record = session_store
+            .load_session(&remote_address, ctx)
+            .await?
+            .ok_or_else(|| SignalProtocolError::SessionNotFound(format!("{}", remote_address)))?;
+
+        let ptext = decrypt_message_with_record(
+            &remote_address,
+            &mut session_record,
+            ciphertext,
+            record_for_duplicates,
+            csprng,
+        )?;
+
+        // Why are we performing this check after decryption instead of before?
+        let their_identity_key = session_record
+            .session_state()?
+            .remote_identity_key()?
+            .ok_or(SignalProtocolError::InvalidSessionStructure)?;
 
-    if !identity_store
-        .is_trusted_identity(
+        if !session::is_trusted_identity(
+            identity_store,
             &remote_address,
             &their_identity_key,
             Direction::Receiving,
+            approved_identity,
             ctx,
         )
         .await?
-    {
-        log::warn!(
-            "Identity key {} is not trusted for remote address {}",
-            their_identity_key
-                .public_key()
-                .public_key_bytes()
-                .map_or_else(|e| format!("<error: {}>", e), hex::encode),
-            remote_address,
-        );
-        return Err(SignalProtocolError::UntrustedIdentity(
-            remote_address.clone(),
-        ));
-    }
+        {
+            log::warn!(
+                "Identity key {} is not trusted for remote address {}",
+                their_identity_key
+                    .public_key()
+                    .public_key_bytes()
+                    .map_or_else(|e| format!("<error: {}>", e), hex_encode),
+                remote_address,
+            );
+            return Err(SignalProtocolError::UntrustedIdentity(
+                remote_address.clone(),
+            ));
+        }
 
-    identity_store
-        .save_identity(&remote_address, &their_identity_key, ctx)
-        .await?;
+        audit::save_identity(identity_store, &remote_address, &their_identity_key, ctx).await?;
 
-    session_store
-        .store_session(&remote_address, &session_record, ctx)
-        .await?;
+        session_store
+            .store_session(&remote_address, &session_record, ctx)
+            .await?;
 
-    Ok(ptext)
+        Ok(ptext)
+    }
+    .await;
+    metrics::record_decryption(&result);
+    result.with_context(|| {
+        ErrorContext::new("message_decrypt_signal")
+            .with_address(remote_address)
+            .with_message_type(CiphertextMessageType::Whisper)
+    })
 }
 
 fn create_decryption_failure_log(
@@ -277,16 +921,21 @@ fn create_decryption_failure_log(
         "Message from {}:{} failed to decrypt; sender ratchet public key {} message counter {}",
         remote_address.name(),
         remote_address.device_id(),
-        hex::encode(ciphertext.sender_ratchet_key().public_key_bytes()?),
-        ciphertext.counter()
+        ciphertext
+            .sender_ratchet_key()
+            .and_then(|key| key.public_key_bytes())
+            .map_or_else(|e| format!("<error: {}>", e), hex_encode),
+        ciphertext
+            .counter()
+            .map_or_else(|e| format!("<error: {}>", e), |counter| counter.to_string())
     ));
 
-    for (idx, (state, err)) in std::iter::once(record.session_state()?)
+    for (idx, (state, err)) in core::iter::once(Ok(record.session_state()?.clone()))
         .chain(record.previous_session_states()?)
         .zip(errs)
         .enumerate()
     {
-        let chains = state.all_receiver_chain_logging_info()?;
+        let chains = state?.all_receiver_chain_logging_info()?;
         lines.push(format!(
             "Candidate session {} failed with '{}', had {} receiver chains",
             idx,
@@ -302,7 +951,7 @@ fn create_decryption_failure_log(
 
             lines.push(format!(
                 "Receiver chain with sender ratchet public key {} chain key index {}",
-                hex::encode(chain.0),
+                hex_encode(chain.0),
                 chain_idx
             ));
         }
@@ -315,6 +964,7 @@ fn decrypt_message_with_record<R: Rng + CryptoRng>(
     remote_address: &ProtocolAddress,
     record: &mut SessionRecord,
     ciphertext: &SignalMessage,
+    record_for_duplicates: bool,
     csprng: &mut R,
 ) -> Result<Vec<u8>> {
     let log_decryption_failure = |state: &SessionState, error: &SignalProtocolError| {
@@ -323,9 +973,11 @@ fn decrypt_message_with_record<R: Rng + CryptoRng>(
              Session loaded for {}. Local session has base key: {} and counter: {}. {}",
             ciphertext
                 .sender_ratchet_key()
-                .public_key_bytes()
-                .map_or_else(|e| format!("<error: {}>", e), hex::encode),
-            ciphertext.counter(),
+                .and_then(|key| key.public_key_bytes())
+                .map_or_else(|e| format!("<error: {}>", e), hex_encode),
+            ciphertext
+                .counter()
+                .map_or_else(|e| format!("<error: {}>", e), |counter| counter.to_string()),
             remote_address,
             state
                 .sender_ratchet_key_for_logging()
@@ -339,14 +991,19 @@ fn decrypt_message_with_record<R: Rng + CryptoRng>(
 
     if let Ok(current_state) = record.session_state() {
         let mut current_state = current_state.clone();
-        let result =
-            decrypt_message_with_state(&mut current_state, ciphertext, remote_address, csprng);
+        let result = decrypt_message_with_state(
+            &mut current_state,
+            ciphertext,
+            remote_address,
+            record_for_duplicates,
+            csprng,
+        );
 
         match result {
             Ok(ptext) => {
                 log::debug!(
                     "successfully decrypted with current session state (base key {})",
-                    hex::encode(
+                    hex_encode(
                         current_state
                             .sender_ratchet_key_for_logging()
                             .expect("successful decrypt always has a valid base key")
@@ -369,15 +1026,22 @@ fn decrypt_message_with_record<R: Rng + CryptoRng>(
     let mut updated_session = None;
 
     for (idx, previous) in record.previous_session_states()?.enumerate() {
+        let previous = previous?;
         let mut updated = previous.clone();
 
-        let result = decrypt_message_with_state(&mut updated, ciphertext, remote_address, csprng);
+        let result = decrypt_message_with_state(
+            &mut updated,
+            ciphertext,
+            remote_address,
+            record_for_duplicates,
+            csprng,
+        );
 
         match result {
             Ok(ptext) => {
                 log::info!(
                     "successfully decrypted with PREVIOUS session state (base key {})",
-                    hex::encode(
+                    hex_encode(
                         previous
                             .sender_ratchet_key_for_logging()
                             .expect("successful decrypt always has a valid base key")
@@ -400,12 +1064,7 @@ fn decrypt_message_with_record<R: Rng + CryptoRng>(
         record.promote_old_session(idx, updated_session)?;
         Ok(ptext)
     } else {
-        let previous_state_count = || {
-            record.previous_session_states().map_or_else(
-                |e| format!("<error: {}>", e),
-                |states| states.count().to_string(),
-            )
-        };
+        let previous_state_count = record.previous_session_count();
 
         if let Ok(current_state) = record.session_state() {
             log::error!(
@@ -413,13 +1072,13 @@ fn decrypt_message_with_record<R: Rng + CryptoRng>(
                 remote_address,
                 current_state.sender_ratchet_key_for_logging()
                 .unwrap_or_else(|e| format!("<error: {}>", e)),
-                previous_state_count(),
+                previous_state_count,
             );
         } else {
             log::error!(
                 "No valid session for recipient: {}, (no current session state), number of previous states: {}",
                 remote_address,
-                previous_state_count(),
+                previous_state_count,
             );
         }
         log::error!(
@@ -436,6 +1095,7 @@ fn decrypt_message_with_state<R: Rng + CryptoRng>(
     state: &mut SessionState,
     ciphertext: &SignalMessage,
     remote_address: &ProtocolAddress,
+    record_for_duplicates: bool,
     csprng: &mut R,
 ) -> Result<Vec<u8>> {
     if !state.has_sender_chain()? {
@@ -451,8 +1111,16 @@ fn decrypt_message_with_state<R: Rng + CryptoRng>(
         ));
     }
 
-    let their_ephemeral = ciphertext.sender_ratchet_key();
-    let counter = ciphertext.counter();
+    let opened;
+    let ciphertext = if state.uses_header_encryption()? {
+        opened = open_header(state, ciphertext)?;
+        &opened
+    } else {
+        ciphertext
+    };
+
+    let their_ephemeral = ciphertext.sender_ratchet_key()?;
+    let counter = ciphertext.counter()?;
     let chain_key = get_or_create_chain_key(state, their_ephemeral, remote_address, csprng)?;
     let message_keys =
         get_or_create_message_key(state, their_ephemeral, remote_address, &chain_key, counter)?;
@@ -478,10 +1146,41 @@ fn decrypt_message_with_state<R: Rng + CryptoRng>(
     )?;
 
     state.clear_unacknowledged_pre_key_message()?;
+    if record_for_duplicates {
+        let plaintext_mac = plaintext_mac(&message_keys, &ptext)?;
+        state.record_decrypted_message(their_ephemeral, counter, &plaintext_mac)?;
+    }
 
     Ok(ptext)
 }
 
+/// Identifies a decrypted plaintext for [`DecryptionOutcome`].
+///
+/// This is keyed by a key derived from the message's own MAC key, rather than being a plain hash,
+/// so that someone who later reads the session store can't confirm a guess at the plaintext.
+fn plaintext_mac(message_keys: &MessageKeys, ptext: &[u8]) -> Result<[u8; 32]> {
+    let key = crypto::hmac_sha256(message_keys.mac_key(), b"Signal_DecryptedMessageMAC")?;
+    crypto::hmac_sha256(&key, ptext)
+}
+
+/// Decrypts the header of a message in a header-encrypted session, trying the key for each
+/// receiver chain and then the key for the next chain the sender will create.
+fn open_header(state: &SessionState, ciphertext: &SignalMessage) -> Result<SignalMessage> {
+    if !ciphertext.has_encrypted_header() {
+        return Err(SignalProtocolError::InvalidMessage(
+            "header-encrypted session received a message with a plaintext header",
+        ));
+    }
+    for header_key in state.receiver_header_keys()? {
+        if let Some(opened) = ciphertext.open_header(&header_key)? {
+            return Ok(opened);
+        }
+    }
+    Err(SignalProtocolError::InvalidMessage(
+        "message header does not decrypt with any receiver chain",
+    ))
+}
+
 fn get_or_create_chain_key<R: Rng + CryptoRng>(
     state: &mut SessionState,
     their_ephemeral: &PublicKey,
@@ -503,8 +1202,22 @@ fn get_or_create_chain_key<R: Rng + CryptoRng>(
         .0
         .create_chain(their_ephemeral, &our_new_ephemeral.private_key)?;
 
+    // Each chain's headers are sealed with a key derived from the root key it was created from.
+    let (receiver_header_key, sender_header_key) = if state.uses_header_encryption()? {
+        (
+            Some(root_key.header_key()?),
+            Some(receiver_chain.0.header_key()?),
+        )
+    } else {
+        (None, None)
+    };
+
     state.set_root_key(&sender_chain.0)?;
-    state.add_receiver_chain(their_ephemeral, &receiver_chain.1)?;
+    state.add_receiver_chain(
+        their_ephemeral,
+        &receiver_chain.1,
+        receiver_header_key.as_ref(),
+    )?;
 
     let current_index = state.get_sender_chain_key()?.index();
     let previous_index = if current_index > 0 {
@@ -513,7 +1226,11 @@ fn get_or_create_chain_key<R: Rng + CryptoRng>(
         0
     };
     state.set_previous_counter(previous_index)?;
-    state.set_sender_chain(&our_new_ephemeral, &sender_chain.1)?;
+    state.set_sender_chain(
+        &our_new_ephemeral,
+        &sender_chain.1,
+        sender_header_key.as_ref(),
+    )?;
 
     Ok(receiver_chain.1)
 }
@@ -568,13 +1285,21 @@ fn get_or_create_message_key(
         }
     }
 
+    // Only the most recent MAX_MESSAGE_KEYS skipped keys are kept, so there's no point holding
+    // on to the older ones while stepping (possibly a very long way, for a session with self).
     let mut chain_key = chain_key.clone();
+    let mut skipped_message_keys = VecDeque::with_capacity(jump.min(MAX_MESSAGE_KEYS));
 
     while chain_key.index() < counter {
-        let message_keys = chain_key.message_keys()?;
-        state.set_message_keys(their_ephemeral, &message_keys)?;
+        if skipped_message_keys.len() == MAX_MESSAGE_KEYS {
+            skipped_message_keys.pop_front();
+        }
+        skipped_message_keys.push_back(chain_key.message_keys()?);
         chain_key = chain_key.next_chain_key()?;
     }
+    if !skipped_message_keys.is_empty() {
+        state.set_message_keys(their_ephemeral, skipped_message_keys.make_contiguous())?;
+    }
 
     state.set_receiver_chain_key(their_ephemeral, &chain_key.next_chain_key()?)?;
     Ok(chain_key.message_keys()?)
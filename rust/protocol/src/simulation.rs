@@ -0,0 +1,369 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Randomized session simulation, enabled by the `simulation` feature.
+//!
+//! [run] sets up a number of devices with in-memory stores, establishes sessions between every
+//! pair, and then drives them through a random interleaving of sends and deliveries. The network
+//! between them can drop messages, deliver them out of order, and replay messages that were
+//! already delivered. Throughout, the simulation checks that:
+//!
+//! - every message that is delivered decrypts to exactly what was sent, and
+//! - a message that has already been decrypted can never be decrypted again, because its message
+//!   key must have been deleted.
+//!
+//! Any violation is reported as an error. All randomness, including the protocol's own, comes
+//! from the RNG passed to [run], so a failing run can be reproduced from its seed.
+
+use crate::{
+    message_decrypt, message_encrypt, process_prekey_bundle, CiphertextMessage, IdentityKeyPair,
+    IdentityKeyStore, InMemSignalProtocolStore, KeyPair, PreKeyBundle, PreKeyRecord, PreKeyStore,
+    ProtocolAddress, Result, SessionStore, SignalProtocolError, SignedPreKeyRecord,
+    SignedPreKeyStore,
+};
+
+use futures::executor::block_on;
+use rand::{CryptoRng, Rng};
+
+const SIGNED_PRE_KEY_ID: u32 = 1;
+
+/// Knobs for a simulation run.
+#[derive(Clone, Debug)]
+pub struct SimulationConfig {
+    /// The number of devices exchanging messages. Must be at least 2.
+    pub device_count: usize,
+    /// The number of send or delivery events to simulate before draining the network.
+    pub steps: usize,
+    /// The probability that a sent message is lost.
+    pub loss_rate: f64,
+    /// How far out of order messages may be delivered. A message is always delivered before
+    /// `reorder_window` further messages have been sent; 1 means messages arrive in order.
+    ///
+    /// The protocol only keeps a limited number of old receiving chains, so very large windows
+    /// can make delayed messages legitimately undecryptable.
+    pub reorder_window: usize,
+    /// The probability, at each step, of replaying a message that was already delivered.
+    pub replay_rate: f64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            device_count: 2,
+            steps: 1000,
+            loss_rate: 0.1,
+            reorder_window: 4,
+            replay_rate: 0.05,
+        }
+    }
+}
+
+impl SimulationConfig {
+    fn validate(&self) -> Result<()> {
+        let invalid = |msg: &str| Err(SignalProtocolError::InvalidArgument(msg.to_string()));
+        if self.device_count < 2 {
+            return invalid("simulation needs at least two devices");
+        }
+        if self.reorder_window == 0 {
+            return invalid("reorder window must be at least 1");
+        }
+        if !(0.0..=1.0).contains(&self.loss_rate) || !(0.0..=1.0).contains(&self.replay_rate) {
+            return invalid("rates must be between 0 and 1");
+        }
+        Ok(())
+    }
+}
+
+/// Counts of what happened during a run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SimulationReport {
+    pub sent: usize,
+    pub dropped: usize,
+    pub delivered: usize,
+    pub reordered: usize,
+    pub replays_rejected: usize,
+}
+
+struct Device {
+    address: ProtocolAddress,
+    store: InMemSignalProtocolStore,
+}
+
+struct InFlight {
+    sender: usize,
+    recipient: usize,
+    sequence: usize,
+    plaintext: Vec<u8>,
+    ciphertext: CiphertextMessage,
+}
+
+struct Simulation<'a, R> {
+    config: &'a SimulationConfig,
+    rng: &'a mut R,
+    devices: Vec<Device>,
+    in_flight: Vec<InFlight>,
+    delivered: Vec<InFlight>,
+    report: SimulationReport,
+}
+
+fn violation(message: String) -> SignalProtocolError {
+    SignalProtocolError::InvalidState("simulation", message)
+}
+
+impl<'a, R: Rng + CryptoRng> Simulation<'a, R> {
+    async fn new(config: &'a SimulationConfig, rng: &'a mut R) -> Result<Simulation<'a, R>> {
+        let mut devices = Vec::with_capacity(config.device_count);
+        for i in 0..config.device_count {
+            let identity = IdentityKeyPair::generate(rng);
+            devices.push(Device {
+                address: ProtocolAddress::new(format!("device-{}", i), 1),
+                store: InMemSignalProtocolStore::new(identity, i as u32 + 1)?,
+            });
+        }
+
+        let mut simulation = Self {
+            config,
+            rng,
+            devices,
+            in_flight: Vec::new(),
+            delivered: Vec::new(),
+            report: SimulationReport::default(),
+        };
+        simulation.establish_sessions().await?;
+        Ok(simulation)
+    }
+
+    /// Has each device process a pre-key bundle from every higher-numbered device.
+    ///
+    /// The higher-numbered device can only send once it has received a message in return, which
+    /// avoids simultaneous session initiation.
+    async fn establish_sessions(&mut self) -> Result<()> {
+        for responder in 1..self.devices.len() {
+            let identity = self.devices[responder]
+                .store
+                .identity_store
+                .get_identity_key_pair(None)
+                .await?;
+            let signed_pre_key = KeyPair::generate(self.rng);
+            let signature = identity
+                .private_key()
+                .calculate_signature(&signed_pre_key.public_key.serialize(), self.rng)?;
+            self.devices[responder]
+                .store
+                .save_signed_pre_key(
+                    SIGNED_PRE_KEY_ID,
+                    &SignedPreKeyRecord::new(SIGNED_PRE_KEY_ID, 0, &signed_pre_key, &signature),
+                    None,
+                )
+                .await?;
+
+            for initiator in 0..responder {
+                let pre_key_id = initiator as u32 + 1;
+                let pre_key = KeyPair::generate(self.rng);
+                self.devices[responder]
+                    .store
+                    .save_pre_key(pre_key_id, &PreKeyRecord::new(pre_key_id, &pre_key), None)
+                    .await?;
+
+                let bundle = PreKeyBundle::new(
+                    responder as u32 + 1,
+                    1,
+                    Some((pre_key_id, pre_key.public_key)),
+                    SIGNED_PRE_KEY_ID,
+                    signed_pre_key.public_key,
+                    signature.to_vec(),
+                    *identity.identity_key(),
+                )?;
+                let responder_address = self.devices[responder].address.clone();
+                let initiator_store = &mut self.devices[initiator].store;
+                process_prekey_bundle(
+                    &responder_address,
+                    &mut initiator_store.session_store,
+                    &mut initiator_store.identity_store,
+                    &bundle,
+                    self.rng,
+                    None,
+                )
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn has_session(&self, from: usize, to: usize) -> Result<bool> {
+        Ok(self.devices[from]
+            .store
+            .session_store
+            .load_session(&self.devices[to].address, None)
+            .await?
+            .is_some())
+    }
+
+    async fn send(&mut self) -> Result<()> {
+        let count = self.devices.len();
+        let mut sender = self.rng.gen_range(0, count);
+        let mut recipient = (sender + self.rng.gen_range(1, count)) % count;
+        if !self.has_session(sender, recipient).await? {
+            // Only the initiator has a session until the first message is received.
+            core::mem::swap(&mut sender, &mut recipient);
+        }
+
+        let sequence = self.report.sent;
+        let plaintext = format!("message {} from {} to {}", sequence, sender, recipient);
+        let recipient_address = self.devices[recipient].address.clone();
+        let sender_store = &mut self.devices[sender].store;
+        let ciphertext = message_encrypt(
+            plaintext.as_bytes(),
+            &recipient_address,
+            &mut sender_store.session_store,
+            &mut sender_store.identity_store,
+            None,
+        )
+        .await?;
+        self.report.sent += 1;
+
+        if self.rng.gen_bool(self.config.loss_rate) {
+            self.report.dropped += 1;
+        } else {
+            self.in_flight.push(InFlight {
+                sender,
+                recipient,
+                sequence,
+                plaintext: plaintext.into_bytes(),
+                ciphertext,
+            });
+        }
+        Ok(())
+    }
+
+    async fn decrypt(&mut self, message: &InFlight) -> Result<Vec<u8>> {
+        let sender_address = self.devices[message.sender].address.clone();
+        let store = &mut self.devices[message.recipient].store;
+        message_decrypt(
+            &message.ciphertext,
+            &sender_address,
+            &mut store.session_store,
+            &mut store.identity_store,
+            &mut store.pre_key_store,
+            &mut store.signed_pre_key_store,
+            self.rng,
+            None,
+        )
+        .await
+    }
+
+    async fn deliver(&mut self) -> Result<()> {
+        let oldest = &self.in_flight[0];
+        let index = if oldest.sequence + self.config.reorder_window <= self.report.sent {
+            0
+        } else {
+            let window = self.config.reorder_window.min(self.in_flight.len());
+            self.rng.gen_range(0, window)
+        };
+        if index != 0 {
+            self.report.reordered += 1;
+        }
+
+        let message = self.in_flight.remove(index);
+        let plaintext = self.decrypt(&message).await.map_err(|e| {
+            violation(format!(
+                "message {} from device {} to device {} failed to decrypt: {}",
+                message.sequence, message.sender, message.recipient, e
+            ))
+        })?;
+        if plaintext != message.plaintext {
+            return Err(violation(format!(
+                "message {} decrypted to the wrong plaintext",
+                message.sequence
+            )));
+        }
+        self.report.delivered += 1;
+        self.delivered.push(message);
+        Ok(())
+    }
+
+    async fn replay(&mut self) -> Result<()> {
+        let index = self.rng.gen_range(0, self.delivered.len());
+        let message = self.delivered.swap_remove(index);
+        if self.decrypt(&message).await.is_ok() {
+            return Err(violation(format!(
+                "message {} was decrypted a second time",
+                message.sequence
+            )));
+        }
+        self.report.replays_rejected += 1;
+        self.delivered.push(message);
+        Ok(())
+    }
+
+    async fn run(mut self) -> Result<SimulationReport> {
+        for _ in 0..self.config.steps {
+            if !self.delivered.is_empty() && self.rng.gen_bool(self.config.replay_rate) {
+                self.replay().await?;
+            } else if self.in_flight.is_empty() || self.rng.gen() {
+                self.send().await?;
+            } else {
+                self.deliver().await?;
+            }
+        }
+        while !self.in_flight.is_empty() {
+            self.deliver().await?;
+        }
+        Ok(self.report)
+    }
+}
+
+/// Runs a simulation with the given configuration, taking all randomness from `rng`.
+pub fn run<R: Rng + CryptoRng>(config: &SimulationConfig, rng: &mut R) -> Result<SimulationReport> {
+    config.validate()?;
+    block_on(async move {
+        let simulation = Simulation::new(config, rng).await?;
+        simulation.run().await
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn test_two_devices() -> Result<()> {
+        let report = run(&SimulationConfig::default(), &mut OsRng)?;
+        assert_eq!(report.sent, report.delivered + report.dropped);
+        assert!(report.delivered > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_many_devices_lossy_network() -> Result<()> {
+        let config = SimulationConfig {
+            device_count: 4,
+            steps: 500,
+            loss_rate: 0.3,
+            reorder_window: 3,
+            replay_rate: 0.1,
+        };
+        let report = run(&config, &mut OsRng)?;
+        assert_eq!(report.sent, report.delivered + report.dropped);
+        assert!(report.replays_rejected > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let config = SimulationConfig {
+            device_count: 1,
+            ..SimulationConfig::default()
+        };
+        assert!(run(&config, &mut OsRng).is_err());
+
+        let config = SimulationConfig {
+            loss_rate: 1.5,
+            ..SimulationConfig::default()
+        };
+        assert!(run(&config, &mut OsRng).is_err());
+    }
+}
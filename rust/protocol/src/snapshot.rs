@@ -0,0 +1,189 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Summaries of the security state of a conversation, for attaching to bug reports.
+//!
+//! A [SecuritySnapshot] only ever contains public information: identity keys are reduced to short
+//! fingerprints, and no ratchet, chain, or message keys are read at all. It is not signed, so it
+//! says nothing about who produced it.
+
+use crate::utils::hex_encode;
+use crate::{
+    Context, IdentityKey, IdentityKeyStore, ProtocolAddress, Result, SenderKeyName, SenderKeyStore,
+    SessionStore,
+};
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use sha2::{Digest, Sha256};
+
+/// A short, stable fingerprint of an identity key: the first 8 bytes of the SHA-256 digest of
+/// its serialized form, as hex.
+///
+/// Two snapshots (say, one from each side of a conversation) show the same fingerprint exactly
+/// when they saw the same identity key.
+fn identity_key_fingerprint(identity_key: &IdentityKey) -> String {
+    let digest = Sha256::digest(&identity_key.serialize());
+    hex_encode(&digest[..8])
+}
+
+/// The parts of the current session with an address that are useful for debugging.
+#[derive(Debug, Clone)]
+pub struct SessionSnapshot {
+    session_version: u32,
+    remote_identity_fingerprint: Option<String>,
+    last_ratchet_time: Option<u64>,
+    receiver_chain_count: usize,
+    has_unacknowledged_pre_key_message: bool,
+    previous_session_count: usize,
+}
+
+impl SessionSnapshot {
+    pub fn session_version(&self) -> u32 {
+        self.session_version
+    }
+
+    /// The fingerprint of the identity key the session was set up with.
+    pub fn remote_identity_fingerprint(&self) -> Option<&str> {
+        self.remote_identity_fingerprint.as_deref()
+    }
+
+    /// When the ratchet last stepped, in milliseconds since the epoch.
+    ///
+    /// Only known for sessions that have stepped since this was first recorded.
+    pub fn last_ratchet_time(&self) -> Option<u64> {
+        self.last_ratchet_time
+    }
+
+    pub fn receiver_chain_count(&self) -> usize {
+        self.receiver_chain_count
+    }
+
+    /// Whether we are still attaching the PreKey to every message, because the other side has
+    /// not replied yet.
+    pub fn has_unacknowledged_pre_key_message(&self) -> bool {
+        self.has_unacknowledged_pre_key_message
+    }
+
+    pub fn previous_session_count(&self) -> usize {
+        self.previous_session_count
+    }
+}
+
+/// Where an address stands with one of our sender keys.
+#[derive(Debug, Clone)]
+pub struct SenderKeyDistributionSnapshot {
+    group_id: String,
+    current_key_id: Option<u32>,
+    distributed_key_id: Option<u32>,
+}
+
+impl SenderKeyDistributionSnapshot {
+    pub fn group_id(&self) -> &str {
+        &self.group_id
+    }
+
+    /// The ID of the sender key we currently send to the group with, if we have one.
+    pub fn current_key_id(&self) -> Option<u32> {
+        self.current_key_id
+    }
+
+    /// The ID of the sender key the address was last sent, if any.
+    pub fn distributed_key_id(&self) -> Option<u32> {
+        self.distributed_key_id
+    }
+}
+
+/// A summary of the security state of a conversation with one address; see [security_snapshot].
+#[derive(Debug, Clone)]
+pub struct SecuritySnapshot {
+    address: ProtocolAddress,
+    identity_fingerprint: Option<String>,
+    session: Option<SessionSnapshot>,
+    sender_key_distributions: Vec<SenderKeyDistributionSnapshot>,
+}
+
+impl SecuritySnapshot {
+    pub fn address(&self) -> &ProtocolAddress {
+        &self.address
+    }
+
+    /// The fingerprint of the identity key saved for the address, if any.
+    pub fn identity_fingerprint(&self) -> Option<&str> {
+        self.identity_fingerprint.as_deref()
+    }
+
+    /// The current session with the address, if there is one.
+    pub fn session(&self) -> Option<&SessionSnapshot> {
+        self.session.as_ref()
+    }
+
+    pub fn sender_key_distributions(&self) -> &[SenderKeyDistributionSnapshot] {
+        &self.sender_key_distributions
+    }
+}
+
+/// Summarizes the security state of the conversation with `address`.
+///
+/// `sender_key_names` are our own sender keys for the groups the address is in; each one that has
+/// a record is reported with the ID of the key the address was last sent.
+pub async fn security_snapshot(
+    address: &ProtocolAddress,
+    sender_key_names: &[SenderKeyName],
+    session_store: &dyn SessionStore,
+    identity_store: &dyn IdentityKeyStore,
+    sender_key_store: &mut dyn SenderKeyStore,
+    ctx: Context,
+) -> Result<SecuritySnapshot> {
+    let identity_fingerprint = identity_store
+        .get_identity(address, ctx)
+        .await?
+        .map(|identity_key| identity_key_fingerprint(&identity_key));
+
+    let session = match session_store.load_session(address, ctx).await? {
+        Some(record) if record.has_current_session_state() => {
+            let state = record.session_state()?;
+            Some(SessionSnapshot {
+                session_version: state.session_version()?,
+                remote_identity_fingerprint: state
+                    .remote_identity_key()?
+                    .map(|identity_key| identity_key_fingerprint(&identity_key)),
+                last_ratchet_time: state.last_ratchet_time(),
+                receiver_chain_count: state.receiver_chain_count(),
+                has_unacknowledged_pre_key_message: state
+                    .unacknowledged_pre_key_message_items()?
+                    .is_some(),
+                previous_session_count: record.previous_session_count(),
+            })
+        }
+        _ => None,
+    };
+
+    let mut sender_key_distributions = Vec::new();
+    for sender_key_name in sender_key_names {
+        if let Some(mut record) = sender_key_store
+            .load_sender_key(sender_key_name, ctx)
+            .await?
+        {
+            let current_key_id = if record.is_empty()? {
+                None
+            } else {
+                Some(record.sender_key_state()?.sender_key_id()?)
+            };
+            sender_key_distributions.push(SenderKeyDistributionSnapshot {
+                group_id: sender_key_name.group_id()?,
+                current_key_id,
+                distributed_key_id: record.distribution_tracker().distributed_key_id(address),
+            });
+        }
+    }
+
+    Ok(SecuritySnapshot {
+        address: address.clone(),
+        identity_fingerprint,
+        session,
+        sender_key_distributions,
+    })
+}
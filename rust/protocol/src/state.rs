@@ -4,11 +4,13 @@
 //
 
 mod bundle;
+mod outgoing_envelope;
 mod prekey;
 mod session;
 mod signed_prekey;
 
 pub use bundle::PreKeyBundle;
+pub use outgoing_envelope::{ContentHint, OutgoingEnvelope};
 pub use prekey::{PreKeyId, PreKeyRecord};
 pub use session::SessionRecord;
 pub(crate) use session::SessionState;
@@ -3,8 +3,16 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use crate::proto::storage::PreKeyBundleStructure;
 use crate::state::{PreKeyId, SignedPreKeyId};
-use crate::{IdentityKey, PublicKey, Result};
+use crate::{
+    IdentityKey, PublicKey, Result, SignalProtocolError, CIPHERTEXT_MESSAGE_CURRENT_VERSION,
+};
+use prost::Message;
+
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
 
 #[derive(Debug, Clone)]
 pub struct PreKeyBundle {
@@ -16,6 +24,8 @@ pub struct PreKeyBundle {
     signed_pre_key_public: PublicKey,
     signed_pre_key_signature: Vec<u8>,
     identity_key: IdentityKey,
+    min_session_version: u32,
+    max_session_version: u32,
 }
 
 impl PreKeyBundle {
@@ -42,6 +52,26 @@ impl PreKeyBundle {
             signed_pre_key_public,
             signed_pre_key_signature,
             identity_key,
+            min_session_version: CIPHERTEXT_MESSAGE_CURRENT_VERSION.into(),
+            max_session_version: CIPHERTEXT_MESSAGE_CURRENT_VERSION.into(),
+        })
+    }
+
+    /// Advertises that the bundle's owner can establish sessions with any version from `min` to
+    /// `max`, inclusive.
+    ///
+    /// Bundles advertise only the current session version unless this is called.
+    pub fn with_session_versions(self, min: u32, max: u32) -> Result<Self> {
+        if min == 0 || min > max {
+            return Err(SignalProtocolError::InvalidArgument(format!(
+                "invalid session version range {}..={}",
+                min, max
+            )));
+        }
+        Ok(Self {
+            min_session_version: min,
+            max_session_version: max,
+            ..self
         })
     }
 
@@ -76,4 +106,60 @@ impl PreKeyBundle {
     pub fn identity_key(&self) -> Result<&IdentityKey> {
         Ok(&self.identity_key)
     }
+
+    pub fn min_session_version(&self) -> Result<u32> {
+        Ok(self.min_session_version)
+    }
+
+    pub fn max_session_version(&self) -> Result<u32> {
+        Ok(self.max_session_version)
+    }
+
+    /// Reconstructs a bundle from the output of [`serialize`](Self::serialize).
+    pub fn deserialize(data: &[u8]) -> Result<Self> {
+        let bundle = PreKeyBundleStructure::decode(data)?;
+        let pre_key = if bundle.pre_key_public.is_empty() {
+            None
+        } else {
+            Some((
+                bundle.pre_key_id,
+                PublicKey::deserialize(&bundle.pre_key_public)?,
+            ))
+        };
+        Self::new(
+            bundle.registration_id,
+            bundle.device_id,
+            pre_key,
+            bundle.signed_pre_key_id,
+            PublicKey::deserialize(&bundle.signed_pre_key_public)?,
+            bundle.signed_pre_key_signature,
+            IdentityKey::decode(&bundle.identity_key)?,
+        )?
+        .with_session_versions(bundle.min_session_version, bundle.max_session_version)
+    }
+
+    /// Encodes the bundle so that it can be handed to another process.
+    ///
+    /// This is not a format the server understands; bundles fetched from the server should still
+    /// be assembled with [`new`](Self::new).
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        let bundle = PreKeyBundleStructure {
+            registration_id: self.registration_id,
+            device_id: self.device_id,
+            pre_key_id: self.pre_key_id.unwrap_or_default(),
+            pre_key_public: self
+                .pre_key_public
+                .map(|key| key.serialize().into_vec())
+                .unwrap_or_default(),
+            signed_pre_key_id: self.signed_pre_key_id,
+            signed_pre_key_public: self.signed_pre_key_public.serialize().into_vec(),
+            signed_pre_key_signature: self.signed_pre_key_signature.clone(),
+            identity_key: self.identity_key.serialize().into_vec(),
+            min_session_version: self.min_session_version,
+            max_session_version: self.max_session_version,
+        };
+        let mut buf = vec![];
+        bundle.encode(&mut buf)?;
+        Ok(buf)
+    }
 }
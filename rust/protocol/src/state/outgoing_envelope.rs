@@ -0,0 +1,166 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use crate::proto::storage::OutgoingEnvelopeStructure;
+use crate::proto::unknown_fields::{UnknownFields, CURRENT_RECORD_VERSION};
+use crate::{ProtocolAddress, Result};
+use prost::Message;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// How a recipient should treat a message it fails to decrypt.
+///
+/// Values this version of the library doesn't know about are kept as [`ContentHint::Unknown`] so
+/// that they survive a round trip.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContentHint {
+    /// The message should be shown to the user as an error.
+    Default,
+    /// The sender will resend the message if the recipient asks.
+    Resendable,
+    /// The message is not visible to the user, so a failure need not be shown.
+    Implicit,
+    Unknown(u32),
+}
+
+impl From<u32> for ContentHint {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => ContentHint::Default,
+            1 => ContentHint::Resendable,
+            2 => ContentHint::Implicit,
+            x => ContentHint::Unknown(x),
+        }
+    }
+}
+
+impl From<ContentHint> for u32 {
+    fn from(hint: ContentHint) -> Self {
+        match hint {
+            ContentHint::Default => 0,
+            ContentHint::Resendable => 1,
+            ContentHint::Implicit => 2,
+            ContentHint::Unknown(x) => x,
+        }
+    }
+}
+
+/// An already-encrypted message waiting in a client's outgoing queue.
+///
+/// The payload is opaque to this type; it is normally a serialized sealed sender message. Keeping
+/// the destination, timestamp, and content hint alongside it means a queued send can be retried
+/// after the app restarts without re-encrypting anything.
+#[derive(Clone, Debug)]
+pub struct OutgoingEnvelope {
+    envelope: OutgoingEnvelopeStructure,
+    unknown_fields: UnknownFields,
+}
+
+impl OutgoingEnvelope {
+    const KNOWN_FIELD_TAGS: &'static [u32] = &[1, 2, 3, 4, 5, 6];
+
+    pub fn new(
+        destination: &ProtocolAddress,
+        timestamp: u64,
+        content_hint: ContentHint,
+        payload: Vec<u8>,
+    ) -> Self {
+        Self {
+            envelope: OutgoingEnvelopeStructure {
+                destination_name: destination.name().into(),
+                destination_device_id: destination.device_id(),
+                timestamp,
+                content_hint: content_hint.into(),
+                payload,
+                version: CURRENT_RECORD_VERSION,
+            },
+            unknown_fields: UnknownFields::default(),
+        }
+    }
+
+    pub fn deserialize(data: &[u8]) -> Result<Self> {
+        Ok(Self {
+            envelope: OutgoingEnvelopeStructure::decode(data)?,
+            unknown_fields: UnknownFields::extract(data, Self::KNOWN_FIELD_TAGS)?,
+        })
+    }
+
+    /// The format version of the data this envelope was deserialized from.
+    pub fn serialized_version(&self) -> u32 {
+        self.envelope.version
+    }
+
+    pub fn destination(&self) -> ProtocolAddress {
+        ProtocolAddress::new(
+            self.envelope.destination_name.clone(),
+            self.envelope.destination_device_id,
+        )
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.envelope.timestamp
+    }
+
+    pub fn content_hint(&self) -> ContentHint {
+        self.envelope.content_hint.into()
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.envelope.payload
+    }
+
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        let mut buf = vec![];
+        let envelope = OutgoingEnvelopeStructure {
+            version: CURRENT_RECORD_VERSION,
+            ..self.envelope.clone()
+        };
+        envelope.encode(&mut buf)?;
+        self.unknown_fields.append_to(&mut buf);
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outgoing_envelope_round_trip() -> Result<()> {
+        let destination = ProtocolAddress::new("+14151111111".into(), 2);
+        let envelope = OutgoingEnvelope::new(
+            &destination,
+            1_600_000_000_000,
+            ContentHint::Resendable,
+            b"sealed sender ciphertext".to_vec(),
+        );
+
+        let parsed = OutgoingEnvelope::deserialize(&envelope.serialize()?)?;
+        assert_eq!(parsed.destination(), destination);
+        assert_eq!(parsed.timestamp(), 1_600_000_000_000);
+        assert_eq!(parsed.content_hint(), ContentHint::Resendable);
+        assert_eq!(parsed.payload(), b"sealed sender ciphertext");
+        assert_eq!(parsed.serialized_version(), CURRENT_RECORD_VERSION);
+        Ok(())
+    }
+
+    #[test]
+    fn test_outgoing_envelope_preserves_newer_data() -> Result<()> {
+        let destination = ProtocolAddress::new("+14151111111".into(), 1);
+        let envelope = OutgoingEnvelope::new(&destination, 5, ContentHint::Unknown(7), vec![1]);
+
+        let mut serialized = envelope.serialize()?;
+        // Field 15, length-delimited: as if written by a newer client.
+        serialized.extend_from_slice(&[0x7a, 0x02, 0xab, 0xcd]);
+
+        let parsed = OutgoingEnvelope::deserialize(&serialized)?;
+        assert_eq!(parsed.content_hint(), ContentHint::Unknown(7));
+        assert_eq!(parsed.serialize()?, serialized);
+
+        assert!(OutgoingEnvelope::deserialize(&[0x0a, 0x05]).is_err());
+        Ok(())
+    }
+}
@@ -4,17 +4,29 @@
 //
 
 use crate::proto::storage::PreKeyRecordStructure;
+use crate::proto::unknown_fields::{
+    record_version_for_serialize, UnknownFields, CURRENT_RECORD_VERSION,
+};
+use crate::redact::{secret, Redact};
+use crate::utils::hex_encode;
 use crate::{KeyPair, PrivateKey, PublicKey, Result};
 use prost::Message;
 
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
 pub type PreKeyId = u32;
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct PreKeyRecord {
     pre_key: PreKeyRecordStructure,
+    unknown_fields: UnknownFields,
 }
 
 impl PreKeyRecord {
+    const KNOWN_FIELD_TAGS: &'static [u32] = &[1, 2, 3, 4];
+
     pub fn new(id: PreKeyId, key: &KeyPair) -> Self {
         let public_key = key.public_key.serialize().to_vec();
         let private_key = key.private_key.serialize().to_vec();
@@ -23,16 +35,26 @@ impl PreKeyRecord {
                 id,
                 public_key,
                 private_key,
+                version: CURRENT_RECORD_VERSION,
             },
+            unknown_fields: UnknownFields::default(),
         }
     }
 
     pub fn deserialize(data: &[u8]) -> Result<Self> {
         Ok(Self {
             pre_key: PreKeyRecordStructure::decode(data)?,
+            unknown_fields: UnknownFields::extract(data, Self::KNOWN_FIELD_TAGS)?,
         })
     }
 
+    /// The format version of the data this record was deserialized from.
+    ///
+    /// Records written before versions were recorded report 0.
+    pub fn serialized_version(&self) -> u32 {
+        self.pre_key.version
+    }
+
     pub fn id(&self) -> Result<PreKeyId> {
         Ok(self.pre_key.id)
     }
@@ -51,7 +73,25 @@ impl PreKeyRecord {
 
     pub fn serialize(&self) -> Result<Vec<u8>> {
         let mut buf = vec![];
-        self.pre_key.encode(&mut buf)?;
+        let pre_key = PreKeyRecordStructure {
+            version: record_version_for_serialize(self.pre_key.version),
+            ..self.pre_key.clone()
+        };
+        pre_key.encode(&mut buf)?;
+        self.unknown_fields.append_to(&mut buf);
         Ok(buf)
     }
 }
+
+impl Redact for PreKeyRecord {
+    fn fmt_redactable(&self, f: &mut fmt::Formatter, redact: bool) -> fmt::Result {
+        f.debug_struct("PreKeyRecord")
+            .field("id", &self.pre_key.id)
+            .field("public_key", &hex_encode(&self.pre_key.public_key))
+            .field("private_key", &secret(&self.pre_key.private_key, redact))
+            .field("unknown_fields", &self.unknown_fields)
+            .finish()
+    }
+}
+
+impl_redacted_debug!(PreKeyRecord);
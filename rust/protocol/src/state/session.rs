@@ -3,16 +3,30 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
-use crate::ratchet::{ChainKey, MessageKeys, RootKey};
-use crate::{IdentityKey, KeyPair, PrivateKey, PublicKey, Result, SignalProtocolError, HKDF};
+use crate::ratchet::{ChainKey, HeaderKey, MessageKeys, RootKey};
+use crate::{
+    IdentityKey, KeyPair, PrivateKey, PublicKey, RecordLimit, Result, SignalProtocolError,
+    HEADER_ENCRYPTED_MESSAGE_VERSION, HKDF,
+};
 
 use crate::consts;
 use crate::proto::storage::session_structure;
-use crate::proto::storage::{RecordStructure, SessionStructure};
+use crate::proto::storage::{LazyRecordStructure, SessionStructure};
+use crate::proto::unknown_fields::{
+    record_version_for_serialize, UnknownFields, CURRENT_RECORD_VERSION,
+};
+use crate::redact::{nested, secret, Redact};
 use crate::state::{PreKeyId, SignedPreKeyId};
+use crate::utils;
 use prost::Message;
 
-use std::collections::VecDeque;
+use alloc::borrow::ToOwned;
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
 
 #[derive(Debug, Clone)]
 pub(crate) struct UnacknowledgedPreKeyMessageItems {
@@ -47,14 +61,93 @@ impl UnacknowledgedPreKeyMessageItems {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub(crate) struct SessionState {
     session: SessionStructure,
+    unknown_fields: UnknownFields,
+    /// Unknown fields of the sender and receiver chains, keyed by their sender ratchet key.
+    ///
+    /// Chains without unknown fields are not listed.
+    chain_unknown_fields: Vec<(Vec<u8>, UnknownFields)>,
 }
 
 impl SessionState {
+    const KNOWN_FIELD_TAGS: &'static [u32] = &[1, 2, 3, 4, 5, 6, 7, 9, 10, 11, 12, 13, 14];
+    const SENDER_CHAIN_TAG: u32 = 6;
+    const RECEIVER_CHAINS_TAG: u32 = 7;
+    const KNOWN_CHAIN_FIELD_TAGS: &'static [u32] = &[1, 2, 3, 4, 5, 6];
+
     pub(crate) fn new(session: SessionStructure) -> Self {
-        Self { session }
+        Self {
+            session,
+            unknown_fields: UnknownFields::default(),
+            chain_unknown_fields: Vec::new(),
+        }
+    }
+
+    /// Decodes a state, rejecting it if it is larger than a state can legitimately grow.
+    fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut state = Self::new(SessionStructure::decode(bytes)?);
+        state.check_record_limits()?;
+
+        state.unknown_fields = UnknownFields::extract(bytes, Self::KNOWN_FIELD_TAGS)?;
+        for tag in &[Self::SENDER_CHAIN_TAG, Self::RECEIVER_CHAINS_TAG] {
+            for (chain, unknown_fields) in
+                UnknownFields::extract_embedded(bytes, *tag, Self::KNOWN_CHAIN_FIELD_TAGS)?
+            {
+                if !unknown_fields.is_empty() {
+                    let chain = session_structure::Chain::decode(chain)?;
+                    state
+                        .chain_unknown_fields
+                        .push((chain.sender_ratchet_key, unknown_fields));
+                }
+            }
+        }
+        Ok(state)
+    }
+
+    /// Encodes the state, including any fields that were not understood when it was decoded.
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<()> {
+        if self.chain_unknown_fields.is_empty() {
+            self.session.encode(buf)?;
+            self.unknown_fields.append_to(buf);
+            return Ok(());
+        }
+
+        let chain_unknown_fields = |chain: &session_structure::Chain| {
+            self.chain_unknown_fields
+                .iter()
+                .find(|(key, _)| *key == chain.sender_ratchet_key)
+                .map(|(_, unknown_fields)| unknown_fields.clone())
+                .unwrap_or_default()
+        };
+
+        let mut session = self.session.clone();
+        let sender_chain = session.sender_chain.take();
+        let receiver_chains = core::mem::take(&mut session.receiver_chains);
+        session.encode(buf)?;
+        if let Some(chain) = &sender_chain {
+            chain_unknown_fields(chain).encode_embedded(Self::SENDER_CHAIN_TAG, chain, buf);
+        }
+        for chain in &receiver_chains {
+            chain_unknown_fields(chain).encode_embedded(Self::RECEIVER_CHAINS_TAG, chain, buf);
+        }
+        self.unknown_fields.append_to(buf);
+        Ok(())
+    }
+
+    fn check_record_limits(&self) -> Result<()> {
+        RecordLimit::ReceiverChains.check(self.session.receiver_chains.len())?;
+        for chain in self
+            .session
+            .sender_chain
+            .iter()
+            .chain(&self.session.receiver_chains)
+        {
+            RecordLimit::MessageKeys.check(chain.message_keys.len())?;
+            RecordLimit::DecryptedMessageMacs.check(chain.decrypted_messages.len())?;
+        }
+        Ok(())
     }
 
     pub(crate) fn alice_base_key(&self) -> Result<&[u8]> {
@@ -75,6 +168,10 @@ impl SessionState {
         }
     }
 
+    pub(crate) fn uses_header_encryption(&self) -> Result<bool> {
+        Ok(self.session_version()? == u32::from(HEADER_ENCRYPTED_MESSAGE_VERSION))
+    }
+
     pub(crate) fn remote_identity_key(&self) -> Result<Option<IdentityKey>> {
         match self.session.remote_identity_public.len() {
             0 => Ok(None),
@@ -138,7 +235,7 @@ impl SessionState {
     pub(crate) fn sender_ratchet_key_for_logging(&self) -> Result<String> {
         self.sender_ratchet_key()?
             .public_key_bytes()
-            .map(hex::encode)
+            .map(utils::hex_encode)
     }
 
     pub(crate) fn sender_ratchet_private_key(&self) -> Result<PrivateKey> {
@@ -152,6 +249,35 @@ impl SessionState {
         Ok(self.session.sender_chain.is_some())
     }
 
+    fn chain_header_key(&self, chain: &session_structure::Chain) -> Result<Option<HeaderKey>> {
+        if chain.header_key.is_empty() {
+            return Ok(None);
+        }
+        if chain.header_key.len() != 32 {
+            return Err(SignalProtocolError::InvalidProtobufEncoding);
+        }
+        let hkdf = HKDF::new(self.session_version()?)?;
+        Ok(Some(HeaderKey::new(hkdf, &chain.header_key)?))
+    }
+
+    pub(crate) fn sender_header_key(&self) -> Result<Option<HeaderKey>> {
+        match self.session.sender_chain {
+            None => Ok(None),
+            Some(ref c) => self.chain_header_key(c),
+        }
+    }
+
+    /// The keys a header from the remote party may be sealed under: that of each receiver chain,
+    /// newest first, followed by the key for the next chain they will create.
+    pub(crate) fn receiver_header_keys(&self) -> Result<Vec<HeaderKey>> {
+        let mut keys = Vec::with_capacity(self.session.receiver_chains.len() + 1);
+        for chain in self.session.receiver_chains.iter().rev() {
+            keys.extend(self.chain_header_key(chain)?);
+        }
+        keys.push(self.root_key()?.header_key()?);
+        Ok(keys)
+    }
+
     pub(crate) fn all_receiver_chain_logging_info(&self) -> Result<Vec<(Vec<u8>, Option<u32>)>> {
         let mut results = vec![];
         for chain in self.session.receiver_chains.iter() {
@@ -167,10 +293,7 @@ impl SessionState {
         Ok(results)
     }
 
-    pub(crate) fn get_receiver_chain(
-        &self,
-        sender: &PublicKey,
-    ) -> Result<Option<(session_structure::Chain, usize)>> {
+    fn get_receiver_chain_index(&self, sender: &PublicKey) -> Result<Option<usize>> {
         let sender_bytes = sender.serialize();
 
         for (idx, chain) in self.session.receiver_chains.iter().enumerate() {
@@ -182,7 +305,7 @@ impl SessionState {
             let this_point = PublicKey::deserialize(&chain.sender_ratchet_key)?.serialize();
 
             if this_point == sender_bytes {
-                return Ok(Some((chain.clone(), idx)));
+                return Ok(Some(idx));
             }
         }
 
@@ -190,9 +313,9 @@ impl SessionState {
     }
 
     pub(crate) fn get_receiver_chain_key(&self, sender: &PublicKey) -> Result<Option<ChainKey>> {
-        match self.get_receiver_chain(sender)? {
+        match self.get_receiver_chain_index(sender)? {
             None => Ok(None),
-            Some((chain, _)) => match chain.chain_key {
+            Some(chain_idx) => match &self.session.receiver_chains[chain_idx].chain_key {
                 None => Err(SignalProtocolError::InvalidProtobufEncoding),
                 Some(c) => {
                     if c.key.len() != 32 {
@@ -209,6 +332,7 @@ impl SessionState {
         &mut self,
         sender: &PublicKey,
         chain_key: &ChainKey,
+        header_key: Option<&HeaderKey>,
     ) -> Result<()> {
         let chain_key = session_structure::chain::ChainKey {
             index: chain_key.index(),
@@ -220,6 +344,8 @@ impl SessionState {
             sender_ratchet_key_private: vec![],
             chain_key: Some(chain_key),
             message_keys: vec![],
+            header_key: header_key.map_or_else(Vec::new, |k| k.key().to_vec()),
+            decrypted_messages: vec![],
         };
 
         self.session.receiver_chains.push(chain);
@@ -241,6 +367,7 @@ impl SessionState {
         &mut self,
         sender: &KeyPair,
         next_chain_key: &ChainKey,
+        header_key: Option<&HeaderKey>,
     ) -> Result<()> {
         let chain_key = session_structure::chain::ChainKey {
             index: next_chain_key.index(),
@@ -252,13 +379,28 @@ impl SessionState {
             sender_ratchet_key_private: sender.private_key.serialize().to_vec(),
             chain_key: Some(chain_key),
             message_keys: vec![],
+            header_key: header_key.map_or_else(Vec::new, |k| k.key().to_vec()),
+            decrypted_messages: vec![],
         };
 
         self.session.sender_chain = Some(new_chain);
+        self.session.last_ratchet_time = utils::diagnostic_time_millis().unwrap_or(0);
 
         Ok(())
     }
 
+    /// When the sender chain was last replaced, in milliseconds since the epoch, if known.
+    pub(crate) fn last_ratchet_time(&self) -> Option<u64> {
+        match self.session.last_ratchet_time {
+            0 => None,
+            time => Some(time),
+        }
+    }
+
+    pub(crate) fn receiver_chain_count(&self) -> usize {
+        self.session.receiver_chains.len()
+    }
+
     pub(crate) fn get_sender_chain_key(&self) -> Result<ChainKey> {
         let sender_chain = self.session.sender_chain.as_ref().ok_or_else(|| {
             SignalProtocolError::InvalidState("get_sender_chain_key", "No chain".to_owned())
@@ -290,6 +432,8 @@ impl SessionState {
                 sender_ratchet_key_private: vec![],
                 chain_key: Some(chain_key),
                 message_keys: vec![],
+                header_key: vec![],
+                decrypted_messages: vec![],
             },
             Some(mut c) => {
                 c.chain_key = Some(chain_key);
@@ -307,14 +451,11 @@ impl SessionState {
         sender: &PublicKey,
         counter: u32,
     ) -> Result<Option<MessageKeys>> {
-        if let Some(mut chain_and_index) = self.get_receiver_chain(sender)? {
-            let message_key_idx = chain_and_index
-                .0
-                .message_keys
-                .iter()
-                .position(|m| m.index == counter);
+        if let Some(chain_idx) = self.get_receiver_chain_index(sender)? {
+            let chain = &mut self.session.receiver_chains[chain_idx];
+            let message_key_idx = chain.message_keys.iter().position(|m| m.index == counter);
             if let Some(position) = message_key_idx {
-                let message_key = chain_and_index.0.message_keys.remove(position);
+                let message_key = chain.message_keys.remove(position);
 
                 let keys = MessageKeys::new(
                     &message_key.cipher_key,
@@ -323,8 +464,6 @@ impl SessionState {
                     counter,
                 )?;
 
-                // Update with message key removed
-                self.session.receiver_chains[chain_and_index.1] = chain_and_index.0;
                 return Ok(Some(keys));
             }
         }
@@ -332,33 +471,95 @@ impl SessionState {
         Ok(None)
     }
 
+    /// Saves the keys for skipped messages, given in the order they were derived.
+    ///
+    /// The receiver chain is looked up once and updated in place, so skipping many messages at
+    /// once doesn't copy the chain's existing keys for each new one.
     pub(crate) fn set_message_keys(
         &mut self,
         sender: &PublicKey,
-        message_keys: &MessageKeys,
+        message_keys: &[MessageKeys],
     ) -> Result<()> {
-        let new_keys = session_structure::chain::MessageKey {
-            cipher_key: message_keys.cipher_key().to_vec(),
-            mac_key: message_keys.mac_key().to_vec(),
-            iv: message_keys.iv().to_vec(),
-            index: message_keys.counter(),
-        };
+        let chain_idx = self.get_receiver_chain_index(sender)?.ok_or_else(|| {
+            SignalProtocolError::InvalidState("set_message_keys", "No receiver".to_string())
+        })?;
+        let chain = &mut self.session.receiver_chains[chain_idx];
+
+        // The most recent keys are kept first, and only the most recent MAX_MESSAGE_KEYS are kept.
+        let new_keys = message_keys
+            .iter()
+            .rev()
+            .take(consts::MAX_MESSAGE_KEYS)
+            .map(|message_keys| session_structure::chain::MessageKey {
+                cipher_key: message_keys.cipher_key().to_vec(),
+                mac_key: message_keys.mac_key().to_vec(),
+                iv: message_keys.iv().to_vec(),
+                index: message_keys.counter(),
+            });
+        chain.message_keys.splice(0..0, new_keys);
+        chain.message_keys.truncate(consts::MAX_MESSAGE_KEYS);
+        Ok(())
+    }
 
-        if let Some(chain_and_index) = self.get_receiver_chain(sender)? {
-            let mut updated_chain = chain_and_index.0;
-            updated_chain.message_keys.insert(0, new_keys);
+    /// Remembers the MAC of the plaintext of message `counter` in `sender`'s chain.
+    ///
+    /// Only the most recent [`MAX_DECRYPTED_MESSAGE_MACS`](consts::MAX_DECRYPTED_MESSAGE_MACS)
+    /// are kept.
+    pub(crate) fn record_decrypted_message(
+        &mut self,
+        sender: &PublicKey,
+        counter: u32,
+        plaintext_mac: &[u8; 32],
+    ) -> Result<()> {
+        let chain_idx = self.get_receiver_chain_index(sender)?.ok_or_else(|| {
+            SignalProtocolError::InvalidState("record_decrypted_message", "No receiver".to_string())
+        })?;
+        let chain = &mut self.session.receiver_chains[chain_idx];
+        chain.decrypted_messages.insert(
+            0,
+            session_structure::chain::DecryptedMessage {
+                index: counter,
+                plaintext_mac: plaintext_mac.to_vec(),
+            },
+        );
+        chain
+            .decrypted_messages
+            .truncate(consts::MAX_DECRYPTED_MESSAGE_MACS);
+        Ok(())
+    }
 
-            if updated_chain.message_keys.len() > consts::MAX_MESSAGE_KEYS {
-                updated_chain.message_keys.pop();
+    /// The MAC recorded by [`record_decrypted_message`](Self::record_decrypted_message), if it
+    /// is still kept.
+    pub(crate) fn decrypted_message_mac(
+        &self,
+        sender: &PublicKey,
+        counter: u32,
+    ) -> Result<Option<[u8; 32]>> {
+        let chain = match self.get_receiver_chain_index(sender)? {
+            Some(chain_idx) => &self.session.receiver_chains[chain_idx],
+            None => return Ok(None),
+        };
+        let decrypted = chain
+            .decrypted_messages
+            .iter()
+            .find(|decrypted| decrypted.index == counter);
+        match decrypted {
+            Some(decrypted) => {
+                let mut mac = [0u8; 32];
+                if decrypted.plaintext_mac.len() != mac.len() {
+                    return Err(SignalProtocolError::InvalidSessionStructure);
+                }
+                mac.copy_from_slice(&decrypted.plaintext_mac);
+                Ok(Some(mac))
             }
+            None => Ok(None),
+        }
+    }
 
-            self.session.receiver_chains[chain_and_index.1] = updated_chain;
-            Ok(())
-        } else {
-            Err(SignalProtocolError::InvalidState(
-                "set_message_keys",
-                "No receiver".to_string(),
-            ))
+    /// Forgets every recorded plaintext MAC, so that an archived state doesn't keep them.
+    pub(crate) fn clear_decrypted_messages(&mut self) {
+        for chain in &mut self.session.receiver_chains {
+            chain.decrypted_messages.clear();
         }
     }
 
@@ -367,14 +568,12 @@ impl SessionState {
         sender: &PublicKey,
         chain_key: &ChainKey,
     ) -> Result<()> {
-        if let Some(chain_and_index) = self.get_receiver_chain(sender)? {
-            let mut updated_chain = chain_and_index.0;
-            updated_chain.chain_key = Some(session_structure::chain::ChainKey {
-                index: chain_key.index(),
-                key: chain_key.key().to_vec(),
-            });
-
-            self.session.receiver_chains[chain_and_index.1] = updated_chain;
+        if let Some(chain_idx) = self.get_receiver_chain_index(sender)? {
+            self.session.receiver_chains[chain_idx].chain_key =
+                Some(session_structure::chain::ChainKey {
+                    index: chain_key.index(),
+                    key: chain_key.key().to_vec(),
+                });
             return Ok(());
         }
 
@@ -458,17 +657,24 @@ impl From<&SessionState> for SessionStructure {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct SessionRecord {
     current_session: Option<SessionState>,
-    previous_sessions: VecDeque<SessionState>,
+    /// Archived states are kept encoded, since most messages are decrypted with the current state.
+    previous_sessions: VecDeque<Vec<u8>>,
+    serialized_version: u32,
+    unknown_fields: UnknownFields,
 }
 
 impl SessionRecord {
+    const KNOWN_FIELD_TAGS: &'static [u32] = &[1, 2, 3];
+
     pub fn new_fresh() -> Self {
         Self {
             current_session: None,
             previous_sessions: VecDeque::new(),
+            serialized_version: CURRENT_RECORD_VERSION,
+            unknown_fields: UnknownFields::default(),
         }
     }
 
@@ -476,31 +682,48 @@ impl SessionRecord {
         Self {
             current_session: Some(state),
             previous_sessions: VecDeque::new(),
+            serialized_version: CURRENT_RECORD_VERSION,
+            unknown_fields: UnknownFields::default(),
         }
     }
 
     pub fn deserialize(bytes: &[u8]) -> Result<Self> {
-        let record = RecordStructure::decode(bytes)?;
+        let record = LazyRecordStructure::decode(bytes)?;
+        let unknown_fields = UnknownFields::extract(bytes, Self::KNOWN_FIELD_TAGS)?;
 
-        let mut previous = VecDeque::with_capacity(record.previous_sessions.len());
-        for s in record.previous_sessions {
-            previous.push_back(s.into());
-        }
+        // Archived states are checked when they are decoded.
+        RecordLimit::ArchivedSessionStates.check(record.previous_sessions.len())?;
+        let current_session = record
+            .current_session
+            .map(|session| SessionState::decode(&session))
+            .transpose()?;
 
         Ok(Self {
-            current_session: record.current_session.map(|s| s.into()),
-            previous_sessions: previous,
+            current_session,
+            previous_sessions: record.previous_sessions.into(),
+            serialized_version: record.version,
+            unknown_fields,
         })
     }
 
     pub fn from_single_session_state(bytes: &[u8]) -> Result<Self> {
-        let session = SessionState::new(SessionStructure::decode(bytes)?);
+        let session = SessionState::decode(bytes)?;
         Ok(Self {
             current_session: Some(session),
             previous_sessions: VecDeque::new(),
+            serialized_version: 0,
+            unknown_fields: UnknownFields::default(),
         })
     }
 
+    /// The format version of the data this record was deserialized from.
+    ///
+    /// Records written before versions were recorded report 0. Records that were created
+    /// locally report the version that [`serialize`](Self::serialize) will produce.
+    pub fn serialized_version(&self) -> u32 {
+        self.serialized_version
+    }
+
     pub(crate) fn has_session_state(&self, version: u32, alice_base_key: &[u8]) -> Result<bool> {
         if let Some(current_session) = &self.current_session {
             if current_session.session_version()? == version
@@ -510,7 +733,8 @@ impl SessionRecord {
             }
         }
 
-        for previous in &self.previous_sessions {
+        for previous in self.previous_session_states()? {
+            let previous = previous?;
             if previous.session_version()? == version
                 && alice_base_key == previous.alice_base_key()?
             {
@@ -552,8 +776,18 @@ impl SessionRecord {
         Ok(())
     }
 
-    pub(crate) fn previous_session_states(&self) -> Result<impl Iterator<Item = &SessionState>> {
-        Ok(self.previous_sessions.iter())
+    /// Decodes the archived states one at a time, most recent first.
+    pub(crate) fn previous_session_states(
+        &self,
+    ) -> Result<impl Iterator<Item = Result<SessionState>> + '_> {
+        Ok(self
+            .previous_sessions
+            .iter()
+            .map(|bytes| SessionState::decode(bytes.as_slice())))
+    }
+
+    pub(crate) fn previous_session_count(&self) -> usize {
+        self.previous_sessions.len()
     }
 
     pub(crate) fn promote_old_session(
@@ -574,9 +808,11 @@ impl SessionRecord {
     }
 
     pub fn archive_current_state(&mut self) -> Result<()> {
-        if self.current_session.is_some() {
-            self.previous_sessions
-                .push_front(self.current_session.take().expect("Checked is_some"));
+        if let Some(mut current_session) = self.current_session.take() {
+            current_session.clear_decrypted_messages();
+            let mut encoded = vec![];
+            current_session.encode(&mut encoded)?;
+            self.previous_sessions.push_front(encoded);
             if self.previous_sessions.len() > consts::ARCHIVED_STATES_MAX_LENGTH {
                 self.previous_sessions.pop_back();
             }
@@ -590,11 +826,21 @@ impl SessionRecord {
     pub fn serialize(&self) -> Result<Vec<u8>> {
         let mut buf = vec![];
 
-        let record = RecordStructure {
-            current_session: self.current_session.as_ref().map(|s| s.into()),
-            previous_sessions: self.previous_sessions.iter().map(|s| s.into()).collect(),
+        let current_session = match &self.current_session {
+            Some(session) => {
+                let mut encoded = vec![];
+                session.encode(&mut encoded)?;
+                Some(encoded)
+            }
+            None => None,
+        };
+        let record = LazyRecordStructure {
+            current_session,
+            previous_sessions: self.previous_sessions.iter().cloned().collect(),
+            version: record_version_for_serialize(self.serialized_version),
         };
         record.encode(&mut buf)?;
+        self.unknown_fields.append_to(&mut buf);
         Ok(buf)
     }
 
@@ -637,3 +883,191 @@ impl SessionRecord {
         self.session_state()?.get_sender_chain_key_bytes()
     }
 }
+
+impl Redact for SessionState {
+    fn fmt_redactable(&self, f: &mut fmt::Formatter, redact: bool) -> fmt::Result {
+        if !redact {
+            return f
+                .debug_struct("SessionState")
+                .field("session", &self.session)
+                .finish();
+        }
+        let session = &self.session;
+        f.debug_struct("SessionState")
+            .field("session_version", &session.session_version)
+            .field("local_registration_id", &session.local_registration_id)
+            .field("remote_registration_id", &session.remote_registration_id)
+            .field("root_key", &secret(&session.root_key, true))
+            .field("previous_counter", &session.previous_counter)
+            .field("has_sender_chain", &session.sender_chain.is_some())
+            .field("receiver_chains", &session.receiver_chains.len())
+            .field("has_pending_pre_key", &session.pending_pre_key.is_some())
+            .finish()
+    }
+}
+
+impl Redact for SessionRecord {
+    fn fmt_redactable(&self, f: &mut fmt::Formatter, redact: bool) -> fmt::Result {
+        f.debug_struct("SessionRecord")
+            .field(
+                "current_session",
+                &self
+                    .current_session
+                    .as_ref()
+                    .map(|session| nested(session, redact)),
+            )
+            .field("previous_sessions", &self.previous_sessions.len())
+            .field("serialized_version", &self.serialized_version)
+            .field("unknown_fields", &self.unknown_fields)
+            .finish()
+    }
+}
+
+impl_redacted_debug!(SessionState, SessionRecord);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::storage::RecordStructure;
+
+    fn state_with_base_key(base_key: u8) -> SessionState {
+        SessionState::new(SessionStructure {
+            session_version: 3,
+            alice_base_key: vec![base_key; 33],
+            ..SessionStructure::default()
+        })
+    }
+
+    #[test]
+    fn test_serialize_preserves_nested_unknown_fields() -> Result<()> {
+        // field 15, length-delimited, "future"
+        let unknown_field = [0x7A, 0x06, b'f', b'u', b't', b'u', b'r', b'e'];
+        let unknown_fields = UnknownFields::extract(&unknown_field, &[])?;
+
+        let mut state = vec![];
+        SessionStructure {
+            session_version: 3,
+            alice_base_key: vec![1; 33],
+            ..SessionStructure::default()
+        }
+        .encode(&mut state)?;
+        let chain = session_structure::Chain {
+            sender_ratchet_key: vec![2; 33],
+            ..session_structure::Chain::default()
+        };
+        unknown_fields.encode_embedded(SessionState::RECEIVER_CHAINS_TAG, &chain, &mut state);
+        state.extend_from_slice(&unknown_field);
+
+        let mut bytes = vec![];
+        LazyRecordStructure {
+            current_session: Some(state.clone()),
+            previous_sessions: vec![],
+            version: CURRENT_RECORD_VERSION + 1,
+        }
+        .encode(&mut bytes)?;
+
+        let mut record = SessionRecord::deserialize(&bytes)?;
+        assert_eq!(record.serialized_version(), CURRENT_RECORD_VERSION + 1);
+        assert_eq!(record.serialize()?, bytes);
+
+        record.archive_current_state()?;
+        assert_eq!(record.previous_sessions[0], state);
+        Ok(())
+    }
+
+    #[test]
+    fn test_archived_states_round_trip() -> Result<()> {
+        let mut record = SessionRecord::new(state_with_base_key(1));
+        record.promote_state(state_with_base_key(2))?;
+        record.promote_state(state_with_base_key(3))?;
+        assert_eq!(record.previous_session_count(), 2);
+
+        let serialized = record.serialize()?;
+
+        // Decoding every archived state and encoding them again gives the same bytes.
+        let mut eager = vec![];
+        RecordStructure::decode(serialized.as_slice())?.encode(&mut eager)?;
+        assert_eq!(serialized, eager);
+
+        let record = SessionRecord::deserialize(&serialized)?;
+        assert_eq!(record.serialize()?, serialized);
+
+        let previous_base_keys = record
+            .previous_session_states()?
+            .map(|state| Ok(state?.alice_base_key()?.to_vec()))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(previous_base_keys, vec![vec![2; 33], vec![1; 33]]);
+        assert!(record.has_session_state(3, &[1; 33])?);
+        assert!(!record.has_session_state(3, &[4; 33])?);
+        Ok(())
+    }
+
+    fn encode_record(record: RecordStructure) -> Result<Vec<u8>> {
+        let mut bytes = vec![];
+        record.encode(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    #[test]
+    fn test_deserialize_enforces_record_limits() -> Result<()> {
+        let too_many_archived_states = encode_record(RecordStructure {
+            current_session: None,
+            previous_sessions: vec![
+                SessionStructure::default();
+                consts::ARCHIVED_STATES_MAX_LENGTH + 1
+            ],
+            version: CURRENT_RECORD_VERSION,
+        })?;
+        assert!(matches!(
+            SessionRecord::deserialize(&too_many_archived_states),
+            Err(SignalProtocolError::RecordLimitExceeded(
+                RecordLimit::ArchivedSessionStates,
+                41
+            ))
+        ));
+
+        let too_many_receiver_chains = encode_record(RecordStructure {
+            current_session: Some(SessionStructure {
+                receiver_chains: vec![
+                    session_structure::Chain::default();
+                    consts::MAX_RECEIVER_CHAINS + 1
+                ],
+                ..SessionStructure::default()
+            }),
+            previous_sessions: vec![],
+            version: CURRENT_RECORD_VERSION,
+        })?;
+        assert!(matches!(
+            SessionRecord::deserialize(&too_many_receiver_chains),
+            Err(SignalProtocolError::RecordLimitExceeded(
+                RecordLimit::ReceiverChains,
+                6
+            ))
+        ));
+
+        // Archived states are only checked once they are decoded.
+        let too_many_message_keys = encode_record(RecordStructure {
+            current_session: None,
+            previous_sessions: vec![SessionStructure {
+                receiver_chains: vec![session_structure::Chain {
+                    message_keys: vec![
+                        session_structure::chain::MessageKey::default();
+                        consts::MAX_MESSAGE_KEYS + 1
+                    ],
+                    ..session_structure::Chain::default()
+                }],
+                ..SessionStructure::default()
+            }],
+            version: CURRENT_RECORD_VERSION,
+        })?;
+        let record = SessionRecord::deserialize(&too_many_message_keys)?;
+        assert!(matches!(
+            record.previous_session_states()?.next(),
+            Some(Err(SignalProtocolError::RecordLimitExceeded(
+                RecordLimit::MessageKeys,
+                2001
+            )))
+        ));
+        Ok(())
+    }
+}
@@ -4,12 +4,18 @@
 //
 
 use crate::proto::storage::SignedPreKeyRecordStructure;
+use crate::redact::{secret, Redact};
+use crate::utils::hex_encode;
 use crate::{KeyPair, PrivateKey, PublicKey, Result};
 use prost::Message;
 
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
 pub type SignedPreKeyId = u32;
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct SignedPreKeyRecord {
     signed_pre_key: SignedPreKeyRecordStructure,
 }
@@ -69,3 +75,20 @@ impl SignedPreKeyRecord {
         Ok(buf)
     }
 }
+
+impl Redact for SignedPreKeyRecord {
+    fn fmt_redactable(&self, f: &mut fmt::Formatter, redact: bool) -> fmt::Result {
+        f.debug_struct("SignedPreKeyRecord")
+            .field("id", &self.signed_pre_key.id)
+            .field("timestamp", &self.signed_pre_key.timestamp)
+            .field("public_key", &hex_encode(&self.signed_pre_key.public_key))
+            .field(
+                "private_key",
+                &secret(&self.signed_pre_key.private_key, redact),
+            )
+            .field("signature", &hex_encode(&self.signed_pre_key.signature))
+            .finish()
+    }
+}
+
+impl_redacted_debug!(SignedPreKeyRecord);
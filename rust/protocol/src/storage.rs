@@ -3,16 +3,23 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+mod deferred;
+#[cfg(feature = "std")]
 mod inmem;
 mod traits;
 
-pub use {
-    inmem::{
-        InMemIdentityKeyStore, InMemPreKeyStore, InMemSenderKeyStore, InMemSessionStore,
-        InMemSignalProtocolStore, InMemSignedPreKeyStore,
-    },
-    traits::{
-        Context, Direction, IdentityKeyStore, PreKeyStore, ProtocolStore, SenderKeyStore,
-        SessionStore, SignedPreKeyStore,
-    },
+pub(crate) use deferred::Mutation;
+pub use deferred::{
+    DeferredIdentityKeyStore, DeferredPreKeyStore, DeferredSenderKeyStore, DeferredSessionStore,
+    DeferredSignedPreKeyStore, MutationLog, MutationRecorder,
+};
+
+#[cfg(feature = "std")]
+pub use inmem::{
+    InMemIdentityKeyStore, InMemPreKeyStore, InMemReplayCache, InMemSenderKeyStore,
+    InMemSessionStore, InMemSignalProtocolStore, InMemSignedPreKeyStore,
+};
+pub use traits::{
+    Context, Direction, IdentityKeyStore, PreKeyStore, ProtocolStore, ReplayCache,
+    SenderCertificateSource, SenderKeyStore, SessionStore, SignedPreKeyStore,
 };
@@ -0,0 +1,634 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Store wrappers that record writes in a [MutationLog] instead of making them.
+//!
+//! A process that can read the stores but must not write to them, such as a notification extension
+//! or a worker, wraps each store it needs with a [MutationRecorder] and runs the operation as
+//! usual. Reads through the wrappers see the writes recorded before them, so the operation behaves
+//! exactly as it would against writable stores. Afterwards the log is serialized and handed to the
+//! primary process, which replays it with [MutationLog::apply].
+//!
+//! Session and sender key writes remember a digest of the record they replace. If the primary
+//! process has changed one of those records in the meantime, the log is stale and is rejected as a
+//! whole; the operation should then be run again against the current stores.
+
+use super::traits::{
+    Context, Direction, IdentityKeyStore, PreKeyStore, SenderKeyStore, SessionStore,
+    SignedPreKeyStore,
+};
+use crate::proto::storage::mutation_log_structure::{self, mutation::Kind};
+use crate::proto::storage::MutationLogStructure;
+use crate::state::{PreKeyId, SignedPreKeyId};
+use crate::{
+    IdentityKey, IdentityKeyPair, IdentityKeyPairOps, PreKeyRecord, ProtocolAddress, Result,
+    SenderKeyName, SenderKeyRecord, SessionRecord, SignalProtocolError, SignedPreKeyRecord,
+};
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec::Vec;
+use async_trait::async_trait;
+use core::cell::RefCell;
+use prost::Message;
+use sha2::{Digest, Sha256};
+
+/// Store writes captured by a [MutationRecorder], in the order they were made.
+#[derive(Clone, Default)]
+pub struct MutationLog {
+    mutations: Vec<Mutation>,
+}
+
+#[derive(Clone)]
+pub(crate) enum Mutation {
+    SaveIdentity {
+        address: ProtocolAddress,
+        identity: IdentityKey,
+    },
+    StoreSession {
+        address: ProtocolAddress,
+        /// The digest of the record this write replaces, if there was one.
+        base_digest: Option<[u8; 32]>,
+        record: SessionRecord,
+    },
+    SavePreKey {
+        id: PreKeyId,
+        record: PreKeyRecord,
+    },
+    RemovePreKey {
+        id: PreKeyId,
+    },
+    SaveSignedPreKey {
+        id: SignedPreKeyId,
+        record: SignedPreKeyRecord,
+    },
+    StoreSenderKey {
+        name: SenderKeyName,
+        /// The digest of the record this write replaces, if there was one.
+        base_digest: Option<[u8; 32]>,
+        record: SenderKeyRecord,
+    },
+}
+
+fn record_digest(serialized: &[u8]) -> [u8; 32] {
+    Sha256::digest(serialized).into()
+}
+
+fn encode_digest(digest: Option<[u8; 32]>) -> Vec<u8> {
+    digest.map(|digest| digest.to_vec()).unwrap_or_default()
+}
+
+fn decode_digest(bytes: Vec<u8>) -> Result<Option<[u8; 32]>> {
+    match bytes.len() {
+        0 => Ok(None),
+        32 => {
+            let mut digest = [0; 32];
+            digest.copy_from_slice(&bytes);
+            Ok(Some(digest))
+        }
+        _ => Err(SignalProtocolError::InvalidProtobufEncoding),
+    }
+}
+
+fn stale(what: &str) -> SignalProtocolError {
+    SignalProtocolError::InvalidState(
+        "MutationLog::apply",
+        format!("{} changed after the mutation log was recorded", what),
+    )
+}
+
+impl MutationLog {
+    pub fn is_empty(&self) -> bool {
+        self.mutations.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.mutations.len()
+    }
+
+    pub(crate) fn into_mutations(self) -> Vec<Mutation> {
+        self.mutations
+    }
+
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        use mutation_log_structure::mutation;
+
+        let structure = MutationLogStructure {
+            mutations: self
+                .mutations
+                .iter()
+                .map(|m| {
+                    let kind = match m {
+                        Mutation::SaveIdentity { address, identity } => {
+                            Kind::SaveIdentity(mutation::SaveIdentity {
+                                name: address.name().into(),
+                                device_id: address.device_id(),
+                                identity_key: identity.serialize().into_vec(),
+                            })
+                        }
+                        Mutation::StoreSession {
+                            address,
+                            base_digest,
+                            record,
+                        } => Kind::StoreSession(mutation::StoreSession {
+                            name: address.name().into(),
+                            device_id: address.device_id(),
+                            base_digest: encode_digest(*base_digest),
+                            record: record.serialize()?,
+                        }),
+                        Mutation::SavePreKey { id, record } => {
+                            Kind::SavePreKey(mutation::SavePreKey {
+                                id: *id,
+                                record: record.serialize()?,
+                            })
+                        }
+                        Mutation::RemovePreKey { id } => {
+                            Kind::RemovePreKey(mutation::RemovePreKey { id: *id })
+                        }
+                        Mutation::SaveSignedPreKey { id, record } => {
+                            Kind::SaveSignedPreKey(mutation::SaveSignedPreKey {
+                                id: *id,
+                                record: record.serialize()?,
+                            })
+                        }
+                        Mutation::StoreSenderKey {
+                            name,
+                            base_digest,
+                            record,
+                        } => Kind::StoreSenderKey(mutation::StoreSenderKey {
+                            group_id: name.group_id()?,
+                            sender_name: name.sender_name()?,
+                            sender_device_id: name.sender_device_id()?,
+                            base_digest: encode_digest(*base_digest),
+                            record: record.serialize()?,
+                        }),
+                    };
+                    Ok(mutation_log_structure::Mutation { kind: Some(kind) })
+                })
+                .collect::<Result<_>>()?,
+        };
+        let mut result = Vec::with_capacity(structure.encoded_len());
+        structure.encode(&mut result)?;
+        Ok(result)
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<Self> {
+        let structure = MutationLogStructure::decode(bytes)?;
+        let mutations = structure
+            .mutations
+            .into_iter()
+            .map(|m| {
+                Ok(
+                    match m.kind.ok_or(SignalProtocolError::InvalidProtobufEncoding)? {
+                        Kind::SaveIdentity(m) => Mutation::SaveIdentity {
+                            address: ProtocolAddress::new(m.name, m.device_id),
+                            identity: IdentityKey::decode(&m.identity_key)?,
+                        },
+                        Kind::StoreSession(m) => Mutation::StoreSession {
+                            address: ProtocolAddress::new(m.name, m.device_id),
+                            base_digest: decode_digest(m.base_digest)?,
+                            record: SessionRecord::deserialize(&m.record)?,
+                        },
+                        Kind::SavePreKey(m) => Mutation::SavePreKey {
+                            id: m.id,
+                            record: PreKeyRecord::deserialize(&m.record)?,
+                        },
+                        Kind::RemovePreKey(m) => Mutation::RemovePreKey { id: m.id },
+                        Kind::SaveSignedPreKey(m) => Mutation::SaveSignedPreKey {
+                            id: m.id,
+                            record: SignedPreKeyRecord::deserialize(&m.record)?,
+                        },
+                        Kind::StoreSenderKey(m) => Mutation::StoreSenderKey {
+                            name: SenderKeyName::new(
+                                m.group_id,
+                                ProtocolAddress::new(m.sender_name, m.sender_device_id),
+                            )?,
+                            base_digest: decode_digest(m.base_digest)?,
+                            record: SenderKeyRecord::deserialize(&m.record)?,
+                        },
+                    },
+                )
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { mutations })
+    }
+
+    /// Replays the recorded writes against the real stores, in order.
+    ///
+    /// Before writing anything, checks that every session and sender key the log replaces is still
+    /// the one it was recorded against, and fails with
+    /// [`InvalidState`](SignalProtocolError::InvalidState) if not. A store error partway through
+    /// can still leave some writes made; stores that need all-or-nothing behavior should apply the
+    /// log inside their own transaction, using `ctx` to find it.
+    pub async fn apply(
+        &self,
+        session_store: &mut dyn SessionStore,
+        identity_store: &mut dyn IdentityKeyStore,
+        pre_key_store: &mut dyn PreKeyStore,
+        signed_pre_key_store: &mut dyn SignedPreKeyStore,
+        sender_key_store: &mut dyn SenderKeyStore,
+        ctx: Context,
+    ) -> Result<()> {
+        // Only the first write to each record is checked; later ones build on it.
+        let mut checked_sessions: Vec<&ProtocolAddress> = Vec::new();
+        let mut checked_sender_keys: Vec<&SenderKeyName> = Vec::new();
+        for m in &self.mutations {
+            match m {
+                Mutation::StoreSession {
+                    address,
+                    base_digest,
+                    ..
+                } if !checked_sessions.contains(&address) => {
+                    let current = match session_store.load_session(address, ctx).await? {
+                        Some(record) => Some(record_digest(&record.serialize()?)),
+                        None => None,
+                    };
+                    if current != *base_digest {
+                        return Err(stale(&format!("session with {}", address)));
+                    }
+                    checked_sessions.push(address);
+                }
+                Mutation::StoreSenderKey {
+                    name, base_digest, ..
+                } if !checked_sender_keys.contains(&name) => {
+                    let current = match sender_key_store.load_sender_key(name, ctx).await? {
+                        Some(record) => Some(record_digest(&record.serialize()?)),
+                        None => None,
+                    };
+                    if current != *base_digest {
+                        return Err(stale(&format!("sender key for {}", name)));
+                    }
+                    checked_sender_keys.push(name);
+                }
+                _ => {}
+            }
+        }
+
+        for m in &self.mutations {
+            match m {
+                Mutation::SaveIdentity { address, identity } => {
+                    identity_store.save_identity(address, identity, ctx).await?;
+                }
+                Mutation::StoreSession {
+                    address, record, ..
+                } => {
+                    session_store.store_session(address, record, ctx).await?;
+                }
+                Mutation::SavePreKey { id, record } => {
+                    pre_key_store.save_pre_key(*id, record, ctx).await?;
+                }
+                Mutation::RemovePreKey { id } => {
+                    pre_key_store.remove_pre_key(*id, ctx).await?;
+                }
+                Mutation::SaveSignedPreKey { id, record } => {
+                    signed_pre_key_store
+                        .save_signed_pre_key(*id, record, ctx)
+                        .await?;
+                }
+                Mutation::StoreSenderKey { name, record, .. } => {
+                    sender_key_store.store_sender_key(name, record, ctx).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn session(&self, address: &ProtocolAddress) -> Option<&SessionRecord> {
+        self.mutations.iter().rev().find_map(|m| match m {
+            Mutation::StoreSession {
+                address: a, record, ..
+            } if a == address => Some(record),
+            _ => None,
+        })
+    }
+
+    fn identity(&self, address: &ProtocolAddress) -> Option<&IdentityKey> {
+        self.mutations.iter().rev().find_map(|m| match m {
+            Mutation::SaveIdentity {
+                address: a,
+                identity,
+            } if a == address => Some(identity),
+            _ => None,
+        })
+    }
+
+    /// `Some(None)` if the pre-key has been removed.
+    fn pre_key(&self, id: PreKeyId) -> Option<Option<&PreKeyRecord>> {
+        self.mutations.iter().rev().find_map(|m| match m {
+            Mutation::SavePreKey { id: i, record } if *i == id => Some(Some(record)),
+            Mutation::RemovePreKey { id: i } if *i == id => Some(None),
+            _ => None,
+        })
+    }
+
+    fn signed_pre_key(&self, id: SignedPreKeyId) -> Option<&SignedPreKeyRecord> {
+        self.mutations.iter().rev().find_map(|m| match m {
+            Mutation::SaveSignedPreKey { id: i, record } if *i == id => Some(record),
+            _ => None,
+        })
+    }
+
+    fn sender_key(&self, name: &SenderKeyName) -> Option<&SenderKeyRecord> {
+        self.mutations.iter().rev().find_map(|m| match m {
+            Mutation::StoreSenderKey {
+                name: n, record, ..
+            } if n == name => Some(record),
+            _ => None,
+        })
+    }
+}
+
+/// Hands out store wrappers that record their writes into a shared [MutationLog].
+///
+/// Wrappers from the same recorder see each other's writes, and the log keeps them in the order
+/// they were made across all stores.
+#[derive(Default)]
+pub struct MutationRecorder {
+    log: RefCell<MutationLog>,
+}
+
+impl MutationRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn session_store<'a>(&'a self, store: &'a dyn SessionStore) -> DeferredSessionStore<'a> {
+        DeferredSessionStore {
+            store,
+            log: &self.log,
+        }
+    }
+
+    pub fn identity_store<'a>(
+        &'a self,
+        store: &'a dyn IdentityKeyStore,
+    ) -> DeferredIdentityKeyStore<'a> {
+        DeferredIdentityKeyStore {
+            store,
+            log: &self.log,
+        }
+    }
+
+    pub fn pre_key_store<'a>(&'a self, store: &'a dyn PreKeyStore) -> DeferredPreKeyStore<'a> {
+        DeferredPreKeyStore {
+            store,
+            log: &self.log,
+        }
+    }
+
+    pub fn signed_pre_key_store<'a>(
+        &'a self,
+        store: &'a dyn SignedPreKeyStore,
+    ) -> DeferredSignedPreKeyStore<'a> {
+        DeferredSignedPreKeyStore {
+            store,
+            log: &self.log,
+        }
+    }
+
+    /// Takes `store` mutably only because [SenderKeyStore::load_sender_key] does; it is never
+    /// written to.
+    pub fn sender_key_store<'a>(
+        &'a self,
+        store: &'a mut dyn SenderKeyStore,
+    ) -> DeferredSenderKeyStore<'a> {
+        DeferredSenderKeyStore {
+            store,
+            log: &self.log,
+        }
+    }
+
+    /// The writes recorded so far, leaving the log empty.
+    pub fn take_log(&self) -> MutationLog {
+        self.log.replace(MutationLog::default())
+    }
+
+    pub fn into_log(self) -> MutationLog {
+        self.log.into_inner()
+    }
+}
+
+pub struct DeferredSessionStore<'a> {
+    store: &'a dyn SessionStore,
+    log: &'a RefCell<MutationLog>,
+}
+
+#[async_trait(?Send)]
+impl<'s> SessionStore for DeferredSessionStore<'s> {
+    async fn load_session(
+        &self,
+        address: &ProtocolAddress,
+        ctx: Context,
+    ) -> Result<Option<SessionRecord>> {
+        let recorded = self.log.borrow().session(address).cloned();
+        match recorded {
+            Some(record) => Ok(Some(record)),
+            None => self.store.load_session(address, ctx).await,
+        }
+    }
+
+    async fn store_session(
+        &mut self,
+        address: &ProtocolAddress,
+        record: &SessionRecord,
+        ctx: Context,
+    ) -> Result<()> {
+        let base_digest = match self.load_session(address, ctx).await? {
+            Some(base) => Some(record_digest(&base.serialize()?)),
+            None => None,
+        };
+        self.log
+            .borrow_mut()
+            .mutations
+            .push(Mutation::StoreSession {
+                address: address.clone(),
+                base_digest,
+                record: record.clone(),
+            });
+        Ok(())
+    }
+}
+
+pub struct DeferredIdentityKeyStore<'a> {
+    store: &'a dyn IdentityKeyStore,
+    log: &'a RefCell<MutationLog>,
+}
+
+#[async_trait(?Send)]
+impl<'s> IdentityKeyStore for DeferredIdentityKeyStore<'s> {
+    async fn get_identity_key_pair(&self, ctx: Context) -> Result<IdentityKeyPair> {
+        self.store.get_identity_key_pair(ctx).await
+    }
+
+    async fn get_identity_key_ops<'a>(&'a self, ctx: Context) -> Result<IdentityKeyPairOps<'a>> {
+        self.store.get_identity_key_ops(ctx).await
+    }
+
+    async fn get_local_registration_id(&self, ctx: Context) -> Result<u32> {
+        self.store.get_local_registration_id(ctx).await
+    }
+
+    async fn save_identity(
+        &mut self,
+        address: &ProtocolAddress,
+        identity: &IdentityKey,
+        ctx: Context,
+    ) -> Result<bool> {
+        let replaced = match self.get_identity(address, ctx).await? {
+            Some(existing) => existing != *identity,
+            None => false,
+        };
+        self.log
+            .borrow_mut()
+            .mutations
+            .push(Mutation::SaveIdentity {
+                address: address.clone(),
+                identity: *identity,
+            });
+        Ok(replaced)
+    }
+
+    async fn is_trusted_identity(
+        &self,
+        address: &ProtocolAddress,
+        identity: &IdentityKey,
+        direction: Direction,
+        ctx: Context,
+    ) -> Result<bool> {
+        if self.log.borrow().identity(address) == Some(identity) {
+            return Ok(true);
+        }
+        self.store
+            .is_trusted_identity(address, identity, direction, ctx)
+            .await
+    }
+
+    async fn get_identity(
+        &self,
+        address: &ProtocolAddress,
+        ctx: Context,
+    ) -> Result<Option<IdentityKey>> {
+        let recorded = self.log.borrow().identity(address).copied();
+        match recorded {
+            Some(identity) => Ok(Some(identity)),
+            None => self.store.get_identity(address, ctx).await,
+        }
+    }
+}
+
+pub struct DeferredPreKeyStore<'a> {
+    store: &'a dyn PreKeyStore,
+    log: &'a RefCell<MutationLog>,
+}
+
+#[async_trait(?Send)]
+impl<'s> PreKeyStore for DeferredPreKeyStore<'s> {
+    async fn get_pre_key(&self, prekey_id: PreKeyId, ctx: Context) -> Result<PreKeyRecord> {
+        let recorded = self.log.borrow().pre_key(prekey_id).map(|r| r.cloned());
+        match recorded {
+            Some(Some(record)) => Ok(record),
+            Some(None) => Err(SignalProtocolError::InvalidPreKeyId),
+            None => self.store.get_pre_key(prekey_id, ctx).await,
+        }
+    }
+
+    async fn save_pre_key(
+        &mut self,
+        prekey_id: PreKeyId,
+        record: &PreKeyRecord,
+        _ctx: Context,
+    ) -> Result<()> {
+        self.log.borrow_mut().mutations.push(Mutation::SavePreKey {
+            id: prekey_id,
+            record: record.clone(),
+        });
+        Ok(())
+    }
+
+    async fn remove_pre_key(&mut self, prekey_id: PreKeyId, _ctx: Context) -> Result<()> {
+        self.log
+            .borrow_mut()
+            .mutations
+            .push(Mutation::RemovePreKey { id: prekey_id });
+        Ok(())
+    }
+}
+
+pub struct DeferredSignedPreKeyStore<'a> {
+    store: &'a dyn SignedPreKeyStore,
+    log: &'a RefCell<MutationLog>,
+}
+
+#[async_trait(?Send)]
+impl<'s> SignedPreKeyStore for DeferredSignedPreKeyStore<'s> {
+    async fn get_signed_pre_key(
+        &self,
+        signed_prekey_id: SignedPreKeyId,
+        ctx: Context,
+    ) -> Result<SignedPreKeyRecord> {
+        let recorded = self.log.borrow().signed_pre_key(signed_prekey_id).cloned();
+        match recorded {
+            Some(record) => Ok(record),
+            None => self.store.get_signed_pre_key(signed_prekey_id, ctx).await,
+        }
+    }
+
+    async fn save_signed_pre_key(
+        &mut self,
+        signed_prekey_id: SignedPreKeyId,
+        record: &SignedPreKeyRecord,
+        _ctx: Context,
+    ) -> Result<()> {
+        self.log
+            .borrow_mut()
+            .mutations
+            .push(Mutation::SaveSignedPreKey {
+                id: signed_prekey_id,
+                record: record.clone(),
+            });
+        Ok(())
+    }
+}
+
+pub struct DeferredSenderKeyStore<'a> {
+    store: &'a mut dyn SenderKeyStore,
+    log: &'a RefCell<MutationLog>,
+}
+
+#[async_trait(?Send)]
+impl<'s> SenderKeyStore for DeferredSenderKeyStore<'s> {
+    async fn store_sender_key(
+        &mut self,
+        sender_key_name: &SenderKeyName,
+        record: &SenderKeyRecord,
+        ctx: Context,
+    ) -> Result<()> {
+        let base_digest = match self.load_sender_key(sender_key_name, ctx).await? {
+            Some(base) => Some(record_digest(&base.serialize()?)),
+            None => None,
+        };
+        self.log
+            .borrow_mut()
+            .mutations
+            .push(Mutation::StoreSenderKey {
+                name: sender_key_name.clone(),
+                base_digest,
+                record: record.clone(),
+            });
+        Ok(())
+    }
+
+    async fn load_sender_key(
+        &mut self,
+        sender_key_name: &SenderKeyName,
+        ctx: Context,
+    ) -> Result<Option<SenderKeyRecord>> {
+        let recorded = self.log.borrow().sender_key(sender_key_name).cloned();
+        match recorded {
+            Some(record) => Ok(Some(record)),
+            None => self.store.load_sender_key(sender_key_name, ctx).await,
+        }
+    }
+}
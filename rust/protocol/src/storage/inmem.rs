@@ -265,6 +265,51 @@ impl traits::SenderKeyStore for InMemSenderKeyStore {
     }
 }
 
+#[derive(Clone)]
+pub struct InMemReplayCache {
+    expirations: HashMap<[u8; 32], u64>,
+}
+
+impl InMemReplayCache {
+    pub fn new() -> Self {
+        Self {
+            expirations: HashMap::new(),
+        }
+    }
+
+    /// Forgets every message whose entry expired before `now`.
+    pub fn remove_expired(&mut self, now: u64) {
+        self.expirations
+            .retain(|_, &mut expiration| expiration >= now);
+    }
+}
+
+impl Default for InMemReplayCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait(?Send)]
+impl traits::ReplayCache for InMemReplayCache {
+    async fn contains(&self, message_id: &[u8; 32], now: u64, _ctx: Context) -> Result<bool> {
+        Ok(self
+            .expirations
+            .get(message_id)
+            .map_or(false, |&expiration| expiration >= now))
+    }
+
+    async fn record(
+        &mut self,
+        message_id: &[u8; 32],
+        expiration: u64,
+        _ctx: Context,
+    ) -> Result<()> {
+        self.expirations.insert(*message_id, expiration);
+        Ok(())
+    }
+}
+
 #[derive(Clone)]
 pub struct InMemSignalProtocolStore {
     pub session_store: InMemSessionStore,
@@ -3,15 +3,16 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use alloc::boxed::Box;
 use async_trait::async_trait;
 
 use crate::state::{PreKeyId, SignedPreKeyId};
 use crate::{
-    IdentityKey, IdentityKeyPair, PreKeyRecord, ProtocolAddress, Result, SenderKeyName,
-    SenderKeyRecord, SessionRecord, SignedPreKeyRecord,
+    IdentityKey, IdentityKeyPair, IdentityKeyPairOps, PreKeyRecord, ProtocolAddress, Result,
+    SenderCertificate, SenderKeyName, SenderKeyRecord, SessionRecord, SignedPreKeyRecord,
 };
 
-pub type Context = Option<*mut std::ffi::c_void>;
+pub type Context = Option<*mut core::ffi::c_void>;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Direction {
@@ -23,6 +24,15 @@ pub enum Direction {
 pub trait IdentityKeyStore {
     async fn get_identity_key_pair(&self, ctx: Context) -> Result<IdentityKeyPair>;
 
+    /// Returns the local identity key, along with a way to use its private key.
+    ///
+    /// Session setup uses this instead of [`get_identity_key_pair`](Self::get_identity_key_pair),
+    /// which the default implementation calls. Override it to keep the private key somewhere it
+    /// can't be read out, such as a hardware keystore.
+    async fn get_identity_key_ops<'a>(&'a self, ctx: Context) -> Result<IdentityKeyPairOps<'a>> {
+        Ok(self.get_identity_key_pair(ctx).await?.into())
+    }
+
     async fn get_local_registration_id(&self, ctx: Context) -> Result<u32>;
 
     async fn save_identity(
@@ -109,4 +119,25 @@ pub trait SenderKeyStore {
     ) -> Result<Option<SenderKeyRecord>>;
 }
 
+/// Remembers which sealed sender messages have already been received, so that a replayed copy can
+/// be rejected before it reaches the session.
+///
+/// Each message is identified by the SHA-256 digest of its sealed sender ciphertext. An entry only
+/// needs to be kept until `expiration`, after which the message's sender certificate no longer
+/// validates and a replay would be rejected anyway.
+#[async_trait(?Send)]
+pub trait ReplayCache {
+    /// Returns true if `message_id` has been recorded and has not expired as of `now`.
+    async fn contains(&self, message_id: &[u8; 32], now: u64, ctx: Context) -> Result<bool>;
+
+    async fn record(&mut self, message_id: &[u8; 32], expiration: u64, ctx: Context) -> Result<()>;
+}
+
+/// Fetches a new sender certificate from the service when a
+/// [SenderCertificateProvider](crate::SenderCertificateProvider) needs one.
+#[async_trait(?Send)]
+pub trait SenderCertificateSource {
+    async fn fetch_sender_certificate(&mut self, ctx: Context) -> Result<SenderCertificate>;
+}
+
 pub trait ProtocolStore: SessionStore + PreKeyStore + SignedPreKeyStore + IdentityKeyStore {}
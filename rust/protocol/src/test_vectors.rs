@@ -0,0 +1,426 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Deterministic protocol transcripts for cross-platform test suites, enabled by the
+//! `test-vectors` feature.
+//!
+//! [generate] runs a complete conversation between two in-memory parties using an RNG seeded from
+//! a fixed 32-byte seed: an X3DH handshake, a sequence of ratchet messages in both directions, and
+//! a sealed-sender envelope. Every key and ciphertext in the conversation is recorded as hex in a
+//! [TestVectors] value, which serializes to JSON.
+//!
+//! [TestVectors::verify] replays the conversation from the recorded seed and checks that every
+//! recorded value is reproduced exactly, so a JSON file checked into another repository can be
+//! re-validated against any version of this crate.
+
+use crate::utils::hex_encode;
+use crate::{
+    message_decrypt, message_encrypt, process_prekey_bundle, sealed_sender_decrypt,
+    sealed_sender_encrypt, IdentityKeyPair, IdentityKeyStore, InMemSignalProtocolStore, KeyPair,
+    PreKeyBundle, PreKeyRecord, PreKeyStore, ProtocolAddress, Result, SenderCertificate,
+    ServerCertificate, SignalProtocolError, SignedPreKeyRecord, SignedPreKeyStore,
+};
+
+use futures::executor::block_on;
+use rand::{CryptoRng, Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
+
+/// The version of the [TestVectors] format produced by this crate.
+///
+/// This is bumped whenever the generated transcript changes, so that consumers can tell a stale
+/// vector file from a protocol regression.
+pub const TEST_VECTORS_VERSION: u32 = 1;
+
+const ALICE_UUID: &str = "9d0652a3-dcc3-4d11-975f-74d61598733f";
+const BOB_UUID: &str = "796abedb-ca4e-4f18-8803-1fde5b921f9f";
+const DEVICE_ID: u32 = 1;
+const ALICE_REGISTRATION_ID: u32 = 1111;
+const BOB_REGISTRATION_ID: u32 = 2222;
+const PRE_KEY_ID: u32 = 31337;
+const SIGNED_PRE_KEY_ID: u32 = 22;
+const SIGNED_PRE_KEY_TIMESTAMP: u64 = 1_600_000_000_000;
+const SENDER_CERTIFICATE_EXPIRATION: u64 = 1_700_000_000_000;
+
+/// The order and plaintexts of the messages in the ratchet sequence, after the initial X3DH
+/// message from Alice.
+const RATCHET_MESSAGES: &[(Party, &str)] = &[
+    (Party::Bob, "hi alice"),
+    (Party::Bob, "are you there?"),
+    (Party::Alice, "yes"),
+    (Party::Alice, "sorry, was away"),
+    (Party::Alice, "what's up?"),
+    (Party::Bob, "nothing much"),
+    (Party::Alice, "ok"),
+];
+
+const X3DH_MESSAGE: &str = "hello bob";
+const SEALED_SENDER_MESSAGE: &str = "sealed hello";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Party {
+    Alice,
+    Bob,
+}
+
+/// The keys and first message of an X3DH handshake from Alice to Bob.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct X3dhVector {
+    pub alice_identity_key_pair: String,
+    pub bob_identity_key_pair: String,
+    pub bob_pre_key: String,
+    pub bob_signed_pre_key: String,
+    pub plaintext: String,
+    pub pre_key_signal_message: String,
+}
+
+/// A single message in the ratchet sequence that follows the handshake.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RatchetStep {
+    pub sender: Party,
+    pub message_type: u8,
+    pub plaintext: String,
+    pub ciphertext: String,
+}
+
+/// A sealed-sender envelope from Alice to Bob, sent over the established session.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedSenderVector {
+    pub trust_root: String,
+    pub server_certificate: String,
+    pub sender_certificate: String,
+    pub timestamp: u64,
+    pub plaintext: String,
+    pub ciphertext: String,
+}
+
+/// A complete transcript generated from a single seed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestVectors {
+    pub version: u32,
+    pub seed: String,
+    pub x3dh: X3dhVector,
+    pub ratchet: Vec<RatchetStep>,
+    pub sealed_sender: SealedSenderVector,
+}
+
+impl TestVectors {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("test vectors can always be serialized")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| {
+            SignalProtocolError::InvalidArgument(format!("invalid test vectors: {}", e))
+        })
+    }
+
+    /// Regenerates the transcript from the recorded seed and checks that it matches.
+    pub fn verify(&self) -> Result<()> {
+        if self.version != TEST_VECTORS_VERSION {
+            return Err(SignalProtocolError::InvalidArgument(format!(
+                "test vectors have version {}, expected {}",
+                self.version, TEST_VECTORS_VERSION
+            )));
+        }
+
+        let expected = generate(parse_seed(&self.seed)?)?;
+
+        let mismatch = |field: &str| {
+            Err(SignalProtocolError::InvalidState(
+                "verify_test_vectors",
+                format!("{} does not match", field),
+            ))
+        };
+        if self.x3dh != expected.x3dh {
+            return mismatch("x3dh");
+        }
+        if self.ratchet.len() != expected.ratchet.len() {
+            return mismatch("ratchet length");
+        }
+        for (i, (step, expected_step)) in self.ratchet.iter().zip(&expected.ratchet).enumerate() {
+            if step != expected_step {
+                return mismatch(&format!("ratchet step {}", i));
+            }
+        }
+        if self.sealed_sender != expected.sealed_sender {
+            return mismatch("sealed_sender");
+        }
+        Ok(())
+    }
+}
+
+fn parse_seed(seed: &str) -> Result<[u8; 32]> {
+    let invalid = || SignalProtocolError::InvalidArgument("seed must be 64 hex digits".to_string());
+    if seed.len() != 64 || !seed.is_ascii() {
+        return Err(invalid());
+    }
+    let mut result = [0u8; 32];
+    for (i, byte) in result.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&seed[2 * i..2 * i + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(result)
+}
+
+struct Conversation {
+    alice: InMemSignalProtocolStore,
+    bob: InMemSignalProtocolStore,
+    alice_address: ProtocolAddress,
+    bob_address: ProtocolAddress,
+}
+
+impl Conversation {
+    fn stores(
+        &mut self,
+        sender: Party,
+    ) -> (
+        &mut InMemSignalProtocolStore,
+        &mut InMemSignalProtocolStore,
+        &ProtocolAddress,
+        &ProtocolAddress,
+    ) {
+        match sender {
+            Party::Alice => (
+                &mut self.alice,
+                &mut self.bob,
+                &self.bob_address,
+                &self.alice_address,
+            ),
+            Party::Bob => (
+                &mut self.bob,
+                &mut self.alice,
+                &self.alice_address,
+                &self.bob_address,
+            ),
+        }
+    }
+
+    async fn send<R: Rng + CryptoRng>(
+        &mut self,
+        sender: Party,
+        plaintext: &str,
+        rng: &mut R,
+    ) -> Result<RatchetStep> {
+        let (from, to, to_address, from_address) = self.stores(sender);
+        let ciphertext = message_encrypt(
+            plaintext.as_bytes(),
+            to_address,
+            &mut from.session_store,
+            &mut from.identity_store,
+            None,
+        )
+        .await?;
+        let decrypted = message_decrypt(
+            &ciphertext,
+            from_address,
+            &mut to.session_store,
+            &mut to.identity_store,
+            &mut to.pre_key_store,
+            &mut to.signed_pre_key_store,
+            rng,
+            None,
+        )
+        .await?;
+        if decrypted != plaintext.as_bytes() {
+            return Err(SignalProtocolError::InternalError(
+                "test vector message did not round-trip",
+            ));
+        }
+        Ok(RatchetStep {
+            sender,
+            message_type: ciphertext.message_type() as u8,
+            plaintext: hex_encode(plaintext),
+            ciphertext: hex_encode(ciphertext.serialize()),
+        })
+    }
+}
+
+async fn x3dh<R: Rng + CryptoRng>(rng: &mut R) -> Result<(Conversation, X3dhVector)> {
+    let alice_identity = IdentityKeyPair::generate(rng);
+    let bob_identity = IdentityKeyPair::generate(rng);
+    let mut bob = InMemSignalProtocolStore::new(bob_identity, BOB_REGISTRATION_ID)?;
+
+    let pre_key = KeyPair::generate(rng);
+    let signed_pre_key = KeyPair::generate(rng);
+    let signature = bob_identity
+        .private_key()
+        .calculate_signature(&signed_pre_key.public_key.serialize(), rng)?;
+    let pre_key_record = PreKeyRecord::new(PRE_KEY_ID, &pre_key);
+    let signed_pre_key_record = SignedPreKeyRecord::new(
+        SIGNED_PRE_KEY_ID,
+        SIGNED_PRE_KEY_TIMESTAMP,
+        &signed_pre_key,
+        &signature,
+    );
+    bob.save_pre_key(PRE_KEY_ID, &pre_key_record, None).await?;
+    bob.save_signed_pre_key(SIGNED_PRE_KEY_ID, &signed_pre_key_record, None)
+        .await?;
+
+    let bundle = PreKeyBundle::new(
+        BOB_REGISTRATION_ID,
+        DEVICE_ID,
+        Some((PRE_KEY_ID, pre_key.public_key)),
+        SIGNED_PRE_KEY_ID,
+        signed_pre_key.public_key,
+        signature.to_vec(),
+        *bob_identity.identity_key(),
+    )?;
+
+    let mut conversation = Conversation {
+        alice: InMemSignalProtocolStore::new(alice_identity, ALICE_REGISTRATION_ID)?,
+        bob,
+        alice_address: ProtocolAddress::new(ALICE_UUID.to_string(), DEVICE_ID),
+        bob_address: ProtocolAddress::new(BOB_UUID.to_string(), DEVICE_ID),
+    };
+    process_prekey_bundle(
+        &conversation.bob_address,
+        &mut conversation.alice.session_store,
+        &mut conversation.alice.identity_store,
+        &bundle,
+        rng,
+        None,
+    )
+    .await?;
+
+    let first_message = conversation.send(Party::Alice, X3DH_MESSAGE, rng).await?;
+    let vector = X3dhVector {
+        alice_identity_key_pair: hex_encode(alice_identity.serialize()),
+        bob_identity_key_pair: hex_encode(bob_identity.serialize()),
+        bob_pre_key: hex_encode(pre_key_record.serialize()?),
+        bob_signed_pre_key: hex_encode(signed_pre_key_record.serialize()?),
+        plaintext: first_message.plaintext,
+        pre_key_signal_message: first_message.ciphertext,
+    };
+    Ok((conversation, vector))
+}
+
+async fn sealed_sender<R: Rng + CryptoRng>(
+    conversation: &mut Conversation,
+    rng: &mut R,
+) -> Result<SealedSenderVector> {
+    let trust_root = KeyPair::generate(rng);
+    let server_key = KeyPair::generate(rng);
+    let server_certificate =
+        ServerCertificate::new(1, server_key.public_key, &trust_root.private_key, rng)?;
+    let alice_identity = conversation.alice.get_identity_key_pair(None).await?;
+    let sender_certificate = SenderCertificate::new(
+        ALICE_UUID.to_string(),
+        None,
+        *alice_identity.public_key(),
+        DEVICE_ID,
+        SENDER_CERTIFICATE_EXPIRATION,
+        server_certificate.clone(),
+        &server_key.private_key,
+        rng,
+    )?;
+
+    let ciphertext = sealed_sender_encrypt(
+        &conversation.bob_address,
+        &sender_certificate,
+        SEALED_SENDER_MESSAGE.as_bytes(),
+        &mut conversation.alice.session_store,
+        &mut conversation.alice.identity_store,
+        None,
+        rng,
+    )
+    .await?;
+
+    let timestamp = SENDER_CERTIFICATE_EXPIRATION - 1;
+    let bob = &mut conversation.bob;
+    let decrypted = sealed_sender_decrypt(
+        &ciphertext,
+        &trust_root.public_key,
+        timestamp,
+        None,
+        BOB_UUID.to_string(),
+        DEVICE_ID,
+        &mut bob.identity_store,
+        &mut bob.session_store,
+        &mut bob.pre_key_store,
+        &mut bob.signed_pre_key_store,
+        None,
+        rng,
+    )
+    .await?;
+    if decrypted.message()? != SEALED_SENDER_MESSAGE.as_bytes() {
+        return Err(SignalProtocolError::InternalError(
+            "test vector message did not round-trip",
+        ));
+    }
+
+    Ok(SealedSenderVector {
+        trust_root: hex_encode(trust_root.public_key.serialize()),
+        server_certificate: hex_encode(server_certificate.serialized()?),
+        sender_certificate: hex_encode(sender_certificate.serialized()?),
+        timestamp,
+        plaintext: hex_encode(SEALED_SENDER_MESSAGE),
+        ciphertext: hex_encode(ciphertext),
+    })
+}
+
+async fn transcript<R: Rng + CryptoRng>(seed: [u8; 32], rng: &mut R) -> Result<TestVectors> {
+    let (mut conversation, x3dh) = x3dh(rng).await?;
+
+    let mut ratchet = Vec::with_capacity(RATCHET_MESSAGES.len());
+    for (sender, plaintext) in RATCHET_MESSAGES {
+        ratchet.push(conversation.send(*sender, plaintext, rng).await?);
+    }
+
+    let sealed_sender = sealed_sender(&mut conversation, rng).await?;
+
+    Ok(TestVectors {
+        version: TEST_VECTORS_VERSION,
+        seed: hex_encode(seed),
+        x3dh,
+        ratchet,
+        sealed_sender,
+    })
+}
+
+/// Generates the transcript for `seed`.
+///
+/// The same seed always produces the same transcript for a given [TEST_VECTORS_VERSION].
+pub fn generate(seed: [u8; 32]) -> Result<TestVectors> {
+    block_on(transcript(seed, &mut ChaCha20Rng::from_seed(seed)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generation_is_deterministic() -> Result<()> {
+        let vectors = generate([1; 32])?;
+        assert_eq!(vectors, generate([1; 32])?);
+        assert_ne!(vectors.x3dh, generate([2; 32])?.x3dh);
+        assert_eq!(vectors.ratchet.len(), RATCHET_MESSAGES.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_round_trip_and_verify() -> Result<()> {
+        let vectors = generate([42; 32])?;
+        let decoded = TestVectors::from_json(&vectors.to_json())?;
+        assert_eq!(decoded, vectors);
+        decoded.verify()
+    }
+
+    #[test]
+    fn test_verify_detects_tampering() -> Result<()> {
+        let mut vectors = generate([42; 32])?;
+        vectors.ratchet[3].plaintext = hex_encode("tampered");
+        assert!(vectors.verify().is_err());
+
+        let mut vectors = generate([42; 32])?;
+        vectors.version += 1;
+        assert!(vectors.verify().is_err());
+
+        let mut vectors = generate([42; 32])?;
+        vectors.seed.truncate(10);
+        assert!(vectors.verify().is_err());
+        Ok(())
+    }
+}
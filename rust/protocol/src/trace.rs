@@ -0,0 +1,295 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Support for the optional `tracing` instrumentation.
+//!
+//! The top-level operations open a span each (see their `instrument` attributes). Within those
+//! spans, every store callback is reported as a `TRACE`-level event naming the store and method;
+//! with the `std` feature the event also carries `elapsed_us`, the time the callback took. The
+//! store wrappers here are what emit those events, and the operations swap them in for the stores
+//! they were given.
+
+use alloc::boxed::Box;
+use async_trait::async_trait;
+use core::future::Future;
+
+use crate::state::{PreKeyId, SignedPreKeyId};
+use crate::{
+    Context, Direction, IdentityKey, IdentityKeyPair, IdentityKeyPairOps, IdentityKeyStore,
+    PreKeyRecord, PreKeyStore, ProtocolAddress, Result, SessionRecord, SessionStore,
+    SignedPreKeyRecord, SignedPreKeyStore,
+};
+
+/// Awaits `call`, then reports it as a store callback.
+async fn store_call<F: Future>(store: &'static str, method: &'static str, call: F) -> F::Output {
+    #[cfg(feature = "std")]
+    {
+        let start = std::time::Instant::now();
+        let result = call.await;
+        let elapsed_us = start.elapsed().as_micros() as u64;
+        tracing::trace!(store, method, elapsed_us, "store callback");
+        result
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        let result = call.await;
+        tracing::trace!(store, method, "store callback");
+        result
+    }
+}
+
+pub(crate) struct TracedSessionStore<'a>(pub(crate) &'a mut dyn SessionStore);
+
+#[async_trait(?Send)]
+impl SessionStore for TracedSessionStore<'_> {
+    async fn load_session(
+        &self,
+        address: &ProtocolAddress,
+        ctx: Context,
+    ) -> Result<Option<SessionRecord>> {
+        store_call("session", "load_session", self.0.load_session(address, ctx)).await
+    }
+
+    async fn store_session(
+        &mut self,
+        address: &ProtocolAddress,
+        record: &SessionRecord,
+        ctx: Context,
+    ) -> Result<()> {
+        store_call(
+            "session",
+            "store_session",
+            self.0.store_session(address, record, ctx),
+        )
+        .await
+    }
+}
+
+pub(crate) struct TracedIdentityKeyStore<'a>(pub(crate) &'a mut dyn IdentityKeyStore);
+
+#[async_trait(?Send)]
+impl IdentityKeyStore for TracedIdentityKeyStore<'_> {
+    async fn get_identity_key_pair(&self, ctx: Context) -> Result<IdentityKeyPair> {
+        store_call(
+            "identity",
+            "get_identity_key_pair",
+            self.0.get_identity_key_pair(ctx),
+        )
+        .await
+    }
+
+    async fn get_identity_key_ops<'a>(&'a self, ctx: Context) -> Result<IdentityKeyPairOps<'a>> {
+        store_call(
+            "identity",
+            "get_identity_key_ops",
+            self.0.get_identity_key_ops(ctx),
+        )
+        .await
+    }
+
+    async fn get_local_registration_id(&self, ctx: Context) -> Result<u32> {
+        store_call(
+            "identity",
+            "get_local_registration_id",
+            self.0.get_local_registration_id(ctx),
+        )
+        .await
+    }
+
+    async fn save_identity(
+        &mut self,
+        address: &ProtocolAddress,
+        identity: &IdentityKey,
+        ctx: Context,
+    ) -> Result<bool> {
+        store_call(
+            "identity",
+            "save_identity",
+            self.0.save_identity(address, identity, ctx),
+        )
+        .await
+    }
+
+    async fn is_trusted_identity(
+        &self,
+        address: &ProtocolAddress,
+        identity: &IdentityKey,
+        direction: Direction,
+        ctx: Context,
+    ) -> Result<bool> {
+        store_call(
+            "identity",
+            "is_trusted_identity",
+            self.0
+                .is_trusted_identity(address, identity, direction, ctx),
+        )
+        .await
+    }
+
+    async fn get_identity(
+        &self,
+        address: &ProtocolAddress,
+        ctx: Context,
+    ) -> Result<Option<IdentityKey>> {
+        store_call(
+            "identity",
+            "get_identity",
+            self.0.get_identity(address, ctx),
+        )
+        .await
+    }
+}
+
+pub(crate) struct TracedPreKeyStore<'a>(pub(crate) &'a mut dyn PreKeyStore);
+
+#[async_trait(?Send)]
+impl PreKeyStore for TracedPreKeyStore<'_> {
+    async fn get_pre_key(&self, prekey_id: PreKeyId, ctx: Context) -> Result<PreKeyRecord> {
+        store_call("pre_key", "get_pre_key", self.0.get_pre_key(prekey_id, ctx)).await
+    }
+
+    async fn save_pre_key(
+        &mut self,
+        prekey_id: PreKeyId,
+        record: &PreKeyRecord,
+        ctx: Context,
+    ) -> Result<()> {
+        store_call(
+            "pre_key",
+            "save_pre_key",
+            self.0.save_pre_key(prekey_id, record, ctx),
+        )
+        .await
+    }
+
+    async fn remove_pre_key(&mut self, prekey_id: PreKeyId, ctx: Context) -> Result<()> {
+        store_call(
+            "pre_key",
+            "remove_pre_key",
+            self.0.remove_pre_key(prekey_id, ctx),
+        )
+        .await
+    }
+}
+
+pub(crate) struct TracedSignedPreKeyStore<'a>(pub(crate) &'a mut dyn SignedPreKeyStore);
+
+#[async_trait(?Send)]
+impl SignedPreKeyStore for TracedSignedPreKeyStore<'_> {
+    async fn get_signed_pre_key(
+        &self,
+        signed_prekey_id: SignedPreKeyId,
+        ctx: Context,
+    ) -> Result<SignedPreKeyRecord> {
+        store_call(
+            "signed_pre_key",
+            "get_signed_pre_key",
+            self.0.get_signed_pre_key(signed_prekey_id, ctx),
+        )
+        .await
+    }
+
+    async fn save_signed_pre_key(
+        &mut self,
+        signed_prekey_id: SignedPreKeyId,
+        record: &SignedPreKeyRecord,
+        ctx: Context,
+    ) -> Result<()> {
+        store_call(
+            "signed_pre_key",
+            "save_signed_pre_key",
+            self.0.save_signed_pre_key(signed_prekey_id, record, ctx),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{message_encrypt, InMemSignalProtocolStore, SignalProtocolError};
+    use futures::executor::block_on;
+    use rand::rngs::OsRng;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// Records the names of the spans opened and the `method` of each event.
+    #[derive(Default, Clone)]
+    struct RecordingSubscriber {
+        spans: Arc<Mutex<Vec<&'static str>>>,
+        methods: Arc<Mutex<Vec<String>>>,
+    }
+
+    struct MethodVisitor<'a>(&'a mut Vec<String>);
+
+    impl Visit for MethodVisitor<'_> {
+        fn record_debug(&mut self, _field: &Field, _value: &dyn core::fmt::Debug) {}
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "method" {
+                self.0.push(value.to_owned());
+            }
+        }
+    }
+
+    impl Subscriber for RecordingSubscriber {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut spans = self.spans.lock().expect("not poisoned");
+            spans.push(span.metadata().name());
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            event.record(&mut MethodVisitor(
+                &mut self.methods.lock().expect("not poisoned"),
+            ));
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[test]
+    fn test_store_callbacks_are_traced() {
+        let mut store = InMemSignalProtocolStore::new(IdentityKeyPair::generate(&mut OsRng), 1)
+            .expect("can create store");
+        let address = ProtocolAddress::new("+14151111111".to_owned(), 1);
+
+        let subscriber = RecordingSubscriber::default();
+        let result = tracing::subscriber::with_default(subscriber.clone(), || {
+            block_on(message_encrypt(
+                b"hello",
+                &address,
+                &mut store.session_store,
+                &mut store.identity_store,
+                None,
+            ))
+        });
+
+        assert!(matches!(
+            result.map_err(SignalProtocolError::into_root_cause),
+            Err(SignalProtocolError::SessionNotFound(_))
+        ));
+        assert_eq!(
+            *subscriber.spans.lock().expect("not poisoned"),
+            ["message_encrypt"]
+        );
+        assert_eq!(
+            *subscriber.methods.lock().expect("not poisoned"),
+            ["load_session"]
+        );
+    }
+}
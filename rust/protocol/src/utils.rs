@@ -3,7 +3,9 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
-use std::cmp::Ordering;
+use alloc::string::String;
+use core::cmp::Ordering;
+use core::fmt::Write;
 
 fn expand_top_bit(a: u8) -> u8 {
     //if (a >> 7) == 1 { 0xFF } else { 0 }
@@ -81,6 +83,34 @@ pub(crate) fn constant_time_cmp(x: &[u8], y: &[u8]) -> Ordering {
     }
 }
 
+/// Formats bytes as lowercase hex, for use in log and error messages.
+pub(crate) fn hex_encode<T: AsRef<[u8]>>(data: T) -> String {
+    let data = data.as_ref();
+    let mut result = String::with_capacity(data.len() * 2);
+    for b in data {
+        write!(result, "{:02x}", b).expect("writing to a String cannot fail");
+    }
+    result
+}
+
+/// Milliseconds since the epoch, if there is a clock to ask (that is, with `std`).
+///
+/// This is only for annotating records to help with debugging. Anything whose behavior depends on
+/// the time takes it as an argument instead.
+pub(crate) fn diagnostic_time_millis() -> Option<u64> {
+    #[cfg(feature = "std")]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()
+            .map(|elapsed| elapsed.as_millis() as u64)
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -108,6 +138,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_hex_encode() {
+        assert_eq!(hex_encode(b""), "");
+        assert_eq!(hex_encode([0x00u8, 0x0f, 0xa0, 0xff]), "000fa0ff");
+
+        let data: Vec<u8> = (0..=255).collect();
+        assert_eq!(hex_encode(&data), hex::encode(&data));
+    }
+
     #[test]
     fn test_ct_is_zero() {
         assert_eq!(ct_is_zero(0), 0xFF);
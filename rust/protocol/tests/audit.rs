@@ -0,0 +1,173 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+mod support;
+
+use futures::executor::block_on;
+use libsignal_protocol::*;
+use rand::rngs::OsRng;
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex};
+use support::*;
+
+struct RecordingHook(Arc<Mutex<Vec<AuditEvent>>>);
+
+impl AuditHook for RecordingHook {
+    fn record(&self, event: &AuditEvent) {
+        self.0.lock().expect("not poisoned").push(event.clone());
+    }
+}
+
+// The hook is process-wide, so everything that installs one is kept in this one test.
+#[test]
+fn test_audit_hook() -> Result<(), SignalProtocolError> {
+    block_on(async {
+        let mut csprng = OsRng;
+
+        let alice_address = ProtocolAddress::new("+14151111111".to_owned(), 1);
+        let bob_address = ProtocolAddress::new("+14151111112".to_owned(), 1);
+
+        let mut alice_store = support::test_in_memory_protocol_store()?;
+        let mut bob_store = support::test_in_memory_protocol_store()?;
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        set_audit_hook(Some(Box::new(RecordingHook(events.clone()))));
+        let take_events = || std::mem::take(&mut *events.lock().expect("not poisoned"));
+
+        // Setting up the first session with Bob replaces nothing.
+        let bob_pre_key_bundle = create_pre_key_bundle(&mut bob_store, &mut csprng).await?;
+        process_prekey_bundle(
+            &bob_address,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            &bob_pre_key_bundle,
+            &mut csprng,
+            None,
+        )
+        .await?;
+        assert_eq!(take_events(), vec![]);
+
+        // Setting up another one archives the first.
+        let bob_pre_key_bundle = create_pre_key_bundle(&mut bob_store, &mut csprng).await?;
+        process_prekey_bundle(
+            &bob_address,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            &bob_pre_key_bundle,
+            &mut csprng,
+            None,
+        )
+        .await?;
+        assert_eq!(
+            take_events(),
+            vec![AuditEvent::SessionArchived {
+                address: bob_address.clone()
+            }]
+        );
+
+        // Bob reinstalls, and Alice accepts his new identity.
+        let mut new_bob_store = support::test_in_memory_protocol_store()?;
+        let alice_pre_key_bundle = create_pre_key_bundle(&mut alice_store, &mut csprng).await?;
+        process_prekey_bundle(
+            &alice_address,
+            &mut new_bob_store.session_store,
+            &mut new_bob_store.identity_store,
+            &alice_pre_key_bundle,
+            &mut csprng,
+            None,
+        )
+        .await?;
+        assert_eq!(take_events(), vec![]);
+
+        let outgoing = encrypt(&mut new_bob_store, &alice_address, "it's me").await?;
+        let new_bob_identity = *new_bob_store
+            .get_identity_key_pair(None)
+            .await?
+            .identity_key();
+        message_decrypt_with_identity_override(
+            &outgoing,
+            &bob_address,
+            &new_bob_identity,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            &mut alice_store.pre_key_store,
+            &mut alice_store.signed_pre_key_store,
+            &mut csprng,
+            None,
+        )
+        .await?;
+        assert_eq!(
+            take_events(),
+            vec![
+                AuditEvent::SessionArchived {
+                    address: bob_address.clone()
+                },
+                AuditEvent::IdentityChanged {
+                    address: bob_address.clone()
+                },
+            ]
+        );
+
+        // Only the first distribution message for a group creates a sender key.
+        let group_sender = SenderKeyName::new("group".to_owned(), alice_address.clone())?;
+        let distribution_message = create_sender_key_distribution_message(
+            &group_sender,
+            &mut alice_store,
+            &mut csprng,
+            None,
+        )
+        .await?;
+        create_sender_key_distribution_message(&group_sender, &mut alice_store, &mut csprng, None)
+            .await?;
+        assert_eq!(
+            take_events(),
+            vec![AuditEvent::SenderKeyCreated {
+                sender_key_name: group_sender.clone(),
+                key_id: distribution_message.id()?,
+            }]
+        );
+
+        // Fast-forwarding a received sender key chain is reported along with where it moved.
+        let recv_distribution_message =
+            SenderKeyDistributionMessage::try_from(distribution_message.serialized())?;
+        process_sender_key_distribution_message(
+            &group_sender,
+            &recv_distribution_message,
+            &mut bob_store,
+            None,
+        )
+        .await?;
+        group_fast_forward(
+            &mut bob_store,
+            &group_sender,
+            recv_distribution_message.id()?,
+            10,
+            None,
+        )
+        .await?;
+        assert_eq!(
+            take_events(),
+            vec![AuditEvent::SenderKeyFastForwarded {
+                sender_key_name: group_sender.clone(),
+                key_id: recv_distribution_message.id()?,
+                from_iteration: 0,
+                to_iteration: 10,
+            }]
+        );
+
+        set_audit_hook(None);
+        let other_group_sender = SenderKeyName::new("other".to_owned(), alice_address.clone())?;
+        create_sender_key_distribution_message(
+            &other_group_sender,
+            &mut alice_store,
+            &mut csprng,
+            None,
+        )
+        .await?;
+        assert_eq!(take_events(), vec![]);
+
+        Ok(())
+    })
+}
@@ -136,7 +136,10 @@ fn group_no_recv_session() -> Result<(), SignalProtocolError> {
         let bob_plaintext =
             group_decrypt(&alice_ciphertext, &mut bob_store, &group_sender, None).await;
 
-        assert!(bob_plaintext.is_err());
+        assert!(matches!(
+            bob_plaintext.map_err(SignalProtocolError::into_root_cause),
+            Err(SignalProtocolError::InvalidSenderKeySession(name)) if name == group_sender
+        ));
 
         Ok(())
     })
@@ -312,7 +315,9 @@ fn group_basic_ratchet() -> Result<(), SignalProtocolError> {
         );
 
         assert!(matches!(
-            group_decrypt(&alice_ciphertext1, &mut bob_store, &group_sender, None).await,
+            group_decrypt(&alice_ciphertext1, &mut bob_store, &group_sender, None)
+                .await
+                .map_err(SignalProtocolError::into_root_cause),
             Err(SignalProtocolError::DuplicatedMessage(1, 0))
         ));
 
@@ -522,6 +527,119 @@ fn group_too_far_in_the_future() -> Result<(), SignalProtocolError> {
                 .is_err()
         );
 
+        let distribution_id = recv_distribution_message.id()?;
+        assert_eq!(
+            group_chain_position(&mut bob_store, &group_sender, distribution_id, None).await?,
+            0
+        );
+        group_fast_forward(&mut bob_store, &group_sender, distribution_id, 25001, None).await?;
+
+        let bob_plaintext =
+            group_decrypt(&alice_ciphertext, &mut bob_store, &group_sender, None).await?;
+        assert_eq!(
+            String::from_utf8(bob_plaintext).expect("valid utf8"),
+            "you got the plan?"
+        );
+
+        Ok(())
+    })
+}
+
+#[test]
+fn group_fast_forward_skips_messages() -> Result<(), SignalProtocolError> {
+    block_on(async {
+        let mut csprng = OsRng;
+
+        let sender_address = ProtocolAddress::new("+14159999111".to_owned(), 1);
+        let group_sender =
+            SenderKeyName::new("summer camp planning committee".to_owned(), sender_address)?;
+
+        let mut alice_store = test_in_memory_protocol_store()?;
+        let mut bob_store = test_in_memory_protocol_store()?;
+
+        let sent_distribution_message = create_sender_key_distribution_message(
+            &group_sender,
+            &mut alice_store,
+            &mut csprng,
+            None,
+        )
+        .await?;
+
+        let recv_distribution_message =
+            SenderKeyDistributionMessage::try_from(sent_distribution_message.serialized())?;
+
+        process_sender_key_distribution_message(
+            &group_sender,
+            &recv_distribution_message,
+            &mut bob_store,
+            None,
+        )
+        .await?;
+
+        let mut ciphertexts = Vec::with_capacity(5);
+        for i in 0..ciphertexts.capacity() {
+            ciphertexts.push(
+                group_encrypt(
+                    &mut alice_store,
+                    &group_sender,
+                    format!("nefarious plotting {}", i).as_bytes(),
+                    &mut csprng,
+                    None,
+                )
+                .await?,
+            );
+        }
+
+        let distribution_id = recv_distribution_message.id()?;
+        group_fast_forward(&mut bob_store, &group_sender, distribution_id, 3, None).await?;
+        assert_eq!(
+            group_chain_position(&mut bob_store, &group_sender, distribution_id, None).await?,
+            3
+        );
+
+        // The skipped messages are gone for good...
+        assert!(
+            group_decrypt(&ciphertexts[1], &mut bob_store, &group_sender, None)
+                .await
+                .is_err()
+        );
+        // ...but the rest of the chain carries on.
+        let bob_plaintext =
+            group_decrypt(&ciphertexts[4], &mut bob_store, &group_sender, None).await?;
+        assert_eq!(
+            String::from_utf8(bob_plaintext).expect("valid utf8"),
+            "nefarious plotting 4"
+        );
+
+        assert!(matches!(
+            group_fast_forward(&mut bob_store, &group_sender, distribution_id, 2, None)
+                .await
+                .map_err(|e| e.into_root_cause()),
+            Err(SignalProtocolError::InvalidState("group_fast_forward", _))
+        ));
+        let too_far = 5 + MAX_FAST_FORWARD_JUMPS as u32 + 1;
+        assert!(matches!(
+            group_fast_forward(
+                &mut bob_store,
+                &group_sender,
+                distribution_id,
+                too_far,
+                None
+            )
+            .await
+            .map_err(|e| e.into_root_cause()),
+            Err(SignalProtocolError::InvalidArgument(_))
+        ));
+        assert_eq!(
+            group_chain_position(&mut bob_store, &group_sender, distribution_id, None).await?,
+            5
+        );
+        assert!(
+            group_chain_position(&mut bob_store, &group_sender, distribution_id + 1, None)
+                .await
+                .is_err()
+        );
+
         Ok(())
     })
 }
@@ -601,3 +719,163 @@ fn group_message_key_limit() -> Result<(), SignalProtocolError> {
         Ok(())
     })
 }
+
+#[test]
+fn group_decrypt_in_batch() -> Result<(), SignalProtocolError> {
+    block_on(async {
+        let mut csprng = OsRng;
+
+        let sender_address = ProtocolAddress::new("+14159999111".to_owned(), 1);
+        let group_sender =
+            SenderKeyName::new("summer camp planning committee".to_owned(), sender_address)?;
+
+        let mut alice_store = test_in_memory_protocol_store()?;
+        let mut bob_store = test_in_memory_protocol_store()?;
+
+        let sent_distribution_message = create_sender_key_distribution_message(
+            &group_sender,
+            &mut alice_store,
+            &mut csprng,
+            None,
+        )
+        .await?;
+
+        let recv_distribution_message =
+            SenderKeyDistributionMessage::try_from(sent_distribution_message.serialized())?;
+
+        process_sender_key_distribution_message(
+            &group_sender,
+            &recv_distribution_message,
+            &mut bob_store,
+            None,
+        )
+        .await?;
+
+        let mut ciphertexts = Vec::new();
+        for i in 0..10 {
+            ciphertexts.push(
+                group_encrypt(
+                    &mut alice_store,
+                    &group_sender,
+                    format!("nefarious plotting {}", i).as_bytes(),
+                    &mut csprng,
+                    None,
+                )
+                .await?,
+            );
+        }
+
+        let refs: Vec<&[u8]> = ciphertexts.iter().map(|c| &c[..]).collect();
+        let plaintexts =
+            group_decrypt_batch(&refs[..5], &mut bob_store, &group_sender, &mut csprng, None)
+                .await?;
+        for (i, plaintext) in plaintexts.into_iter().enumerate() {
+            assert_eq!(
+                String::from_utf8(plaintext?).expect("valid utf8"),
+                format!("nefarious plotting {}", i)
+            );
+        }
+
+        // A bad signature in the batch only rejects that message, and so does a duplicate.
+        let mut corrupted = ciphertexts[7].clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 0x01;
+        let refs: Vec<&[u8]> = vec![
+            &ciphertexts[5][..],
+            &ciphertexts[6][..],
+            &corrupted[..],
+            &ciphertexts[8][..],
+            &ciphertexts[2][..],
+            &b"not a message"[..],
+        ];
+        let results =
+            group_decrypt_batch(&refs, &mut bob_store, &group_sender, &mut csprng, None).await?;
+        assert_eq!(results.len(), 6);
+        assert_eq!(
+            results[0].as_ref().expect("decrypted"),
+            b"nefarious plotting 5"
+        );
+        assert_eq!(
+            results[1].as_ref().expect("decrypted"),
+            b"nefarious plotting 6"
+        );
+        assert!(matches!(
+            results[2],
+            Err(SignalProtocolError::SignatureValidationFailed)
+        ));
+        assert_eq!(
+            results[3].as_ref().expect("decrypted"),
+            b"nefarious plotting 8"
+        );
+        assert!(matches!(
+            results[4],
+            Err(SignalProtocolError::DuplicatedMessage(_, 2))
+        ));
+        assert!(results[5].is_err());
+
+        // The skipped key for message 7 was kept, and the updated record was stored.
+        let plaintext = group_decrypt(&ciphertexts[7], &mut bob_store, &group_sender, None).await?;
+        assert_eq!(
+            String::from_utf8(plaintext).expect("valid utf8"),
+            "nefarious plotting 7"
+        );
+
+        Ok(())
+    })
+}
+
+#[test]
+fn group_distribution_tracking() -> Result<(), SignalProtocolError> {
+    block_on(async {
+        let mut csprng = OsRng;
+
+        let sender_address = ProtocolAddress::new("+14159999111".to_owned(), 1);
+        let group_sender =
+            SenderKeyName::new("summer camp planning committee".to_owned(), sender_address)?;
+        let bob = ProtocolAddress::new("+14159999222".to_owned(), 1);
+        let carol = ProtocolAddress::new("+14159999333".to_owned(), 2);
+
+        let mut alice_store = test_in_memory_protocol_store()?;
+        create_sender_key_distribution_message(&group_sender, &mut alice_store, &mut csprng, None)
+            .await?;
+        let mut record = alice_store
+            .load_sender_key(&group_sender, None)
+            .await?
+            .expect("created");
+
+        assert!(record.needs_distribution(&bob));
+        record.mark_distributed(&bob)?;
+        assert!(!record.needs_distribution(&bob));
+        assert!(record.needs_distribution(&carol));
+
+        // The tracker is stored with the record.
+        let mut record = SenderKeyRecord::deserialize(&record.serialize()?)?;
+        assert!(!record.needs_distribution(&bob));
+        record.mark_distributed(&carol)?;
+        assert_eq!(record.distribution_tracker().members().count(), 2);
+
+        // Removing a member who holds the current key means it must be replaced.
+        assert!(record.forget_distribution(&carol));
+        assert!(!record.forget_distribution(&carol));
+
+        // Everyone needs the replacement key.
+        let signing_key = KeyPair::generate(&mut csprng);
+        record.set_sender_key_state(
+            csprng.gen(),
+            0,
+            &csprng.gen::<[u8; 32]>(),
+            signing_key.public_key,
+            Some(signing_key.private_key),
+        )?;
+        assert!(record.needs_distribution(&bob));
+        assert!(!record.forget_distribution(&bob));
+
+        assert!(SenderKeyRecord::new_empty().needs_distribution(&bob));
+        assert!(matches!(
+            SenderKeyRecord::new_empty().mark_distributed(&bob),
+            Err(SignalProtocolError::NoSenderKeyState)
+        ));
+
+        Ok(())
+    })
+}
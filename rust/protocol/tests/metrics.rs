@@ -0,0 +1,89 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+mod support;
+
+use futures::executor::block_on;
+use libsignal_protocol::*;
+use rand::rngs::OsRng;
+use support::*;
+
+// The counters are process-wide, so everything that touches them is kept in this one test.
+#[test]
+fn test_decryption_metrics() -> Result<(), SignalProtocolError> {
+    block_on(async {
+        let mut csprng = OsRng;
+
+        let alice_address = ProtocolAddress::new("+14151111111".to_owned(), 1);
+        let bob_address = ProtocolAddress::new("+14151111112".to_owned(), 1);
+
+        let mut alice_store = support::test_in_memory_protocol_store()?;
+        let mut bob_store = support::test_in_memory_protocol_store()?;
+        let mut carol_store = support::test_in_memory_protocol_store()?;
+
+        let bob_pre_key_bundle = create_pre_key_bundle(&mut bob_store, &mut csprng).await?;
+        process_prekey_bundle(
+            &bob_address,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            &bob_pre_key_bundle,
+            &mut csprng,
+            None,
+        )
+        .await?;
+
+        reset_decryption_metrics();
+        assert!(!decryption_metrics_enabled());
+
+        let outgoing = encrypt(&mut alice_store, &bob_address, "hi bob").await?;
+        assert!(decrypt(&mut carol_store, &alice_address, &outgoing)
+            .await
+            .is_err());
+        assert_eq!(decryption_metrics().total(), 0);
+
+        set_decryption_metrics_enabled(true);
+
+        assert_eq!(
+            decrypt(&mut bob_store, &alice_address, &outgoing).await?,
+            b"hi bob"
+        );
+
+        let reply = encrypt(&mut bob_store, &alice_address, "hi alice").await?;
+        decrypt(&mut alice_store, &bob_address, &reply).await?;
+        assert!(matches!(
+            decrypt(&mut alice_store, &bob_address, &reply)
+                .await
+                .unwrap_err()
+                .into_root_cause(),
+            SignalProtocolError::DuplicatedMessage(_, _)
+        ));
+
+        let outgoing = encrypt(&mut alice_store, &bob_address, "hi again").await?;
+        assert_eq!(outgoing.message_type(), CiphertextMessageType::Whisper);
+        assert!(decrypt(&mut carol_store, &alice_address, &outgoing)
+            .await
+            .is_err());
+
+        let metrics = decryption_metrics();
+        assert_eq!(metrics.count(DecryptionResultClass::Success), 2);
+        assert_eq!(metrics.count(DecryptionResultClass::Duplicate), 1);
+        assert_eq!(metrics.count(DecryptionResultClass::NoSession), 1);
+        assert_eq!(metrics.total(), 4);
+        assert!(metrics.report().contains("success count=2\n"));
+
+        set_decryption_metrics_enabled(false);
+        decrypt(&mut bob_store, &alice_address, &outgoing).await?;
+        assert_eq!(decryption_metrics(), metrics);
+
+        reset_decryption_metrics();
+        assert_eq!(decryption_metrics(), DecryptionMetrics::default());
+        assert_eq!(
+            decryption_metrics().report().lines().count(),
+            DecryptionResultClass::ALL.len()
+        );
+
+        Ok(())
+    })
+}
@@ -5,6 +5,7 @@
 
 mod support;
 
+use async_trait::async_trait;
 use futures::executor::block_on;
 use libsignal_protocol::*;
 use rand::rngs::OsRng;
@@ -107,6 +108,15 @@ fn test_sender_cert() -> Result<(), SignalProtocolError> {
         false
     ); // expired
 
+    assert_eq!(
+        sender_cert.validate_with_grace_period(&trust_root.public_key, expires + 10, 10)?,
+        true
+    );
+    assert!(matches!(
+        sender_cert.validate_with_grace_period(&trust_root.public_key, expires + 11, 10),
+        Err(SignalProtocolError::SealedSenderCertificateExpired(expiration)) if expiration == expires
+    ));
+
     let mut sender_cert_data = sender_cert.serialized()?.to_vec();
     let sender_cert_bits = sender_cert_data.len() * 8;
 
@@ -211,6 +221,7 @@ fn test_sealed_sender() -> Result<(), SignalProtocolError> {
             &mut bob_store.pre_key_store,
             &mut bob_store.signed_pre_key_store,
             None,
+            &mut rng,
         )
         .await?;
 
@@ -244,11 +255,14 @@ fn test_sealed_sender() -> Result<(), SignalProtocolError> {
             &mut bob_store.pre_key_store,
             &mut bob_store.signed_pre_key_store,
             None,
+            &mut rng,
         )
         .await;
 
-        match bob_ptext {
-            Err(SignalProtocolError::InvalidSealedSenderMessage(_)) => { /* ok */ }
+        match bob_ptext.map_err(SignalProtocolError::into_root_cause) {
+            Err(SignalProtocolError::SealedSenderCertificateExpired(expiration)) => {
+                assert_eq!(expiration, expires);
+            }
             Err(err) => {
                 panic!("Unexpected error {}", err)
             }
@@ -257,6 +271,27 @@ fn test_sealed_sender() -> Result<(), SignalProtocolError> {
             }
         }
 
+        // The same message is accepted within a grace period.
+
+        let bob_ptext = sealed_sender_decrypt_with_grace_period(
+            &alice_ctext,
+            &trust_root.public_key,
+            expires + 11,
+            20,
+            Some(bob_e164.clone()),
+            bob_uuid.clone(),
+            bob_device_id,
+            &mut bob_store.identity_store,
+            &mut bob_store.session_store,
+            &mut bob_store.pre_key_store,
+            &mut bob_store.signed_pre_key_store,
+            None,
+            &mut rng,
+        )
+        .await?;
+
+        assert_eq!(bob_ptext.message, alice_ptext);
+
         // Now test but try to verify using some other trust root
 
         let alice_ctext = sealed_sender_encrypt(
@@ -284,10 +319,11 @@ fn test_sealed_sender() -> Result<(), SignalProtocolError> {
             &mut bob_store.pre_key_store,
             &mut bob_store.signed_pre_key_store,
             None,
+            &mut rng,
         )
         .await;
 
-        match bob_ptext {
+        match bob_ptext.map_err(SignalProtocolError::into_root_cause) {
             Err(SignalProtocolError::InvalidSealedSenderMessage(_)) => { /* ok */ }
             Err(err) => {
                 panic!("Unexpected error {}", err)
@@ -300,3 +336,518 @@ fn test_sealed_sender() -> Result<(), SignalProtocolError> {
         Ok(())
     })
 }
+
+#[test]
+fn test_sealed_sender_replay() -> Result<(), SignalProtocolError> {
+    block_on(async {
+        let mut rng = OsRng;
+
+        let alice_device_id = 23;
+        let bob_device_id = 42;
+
+        let alice_uuid = "9d0652a3-dcc3-4d11-975f-74d61598733f".to_string();
+        let bob_uuid = "796abedb-ca4e-4f18-8803-1fde5b921f9f".to_string();
+
+        let bob_uuid_address = ProtocolAddress::new(bob_uuid.clone(), bob_device_id);
+
+        let mut alice_store = support::test_in_memory_protocol_store()?;
+        let mut bob_store = support::test_in_memory_protocol_store()?;
+        let mut bob_replay_cache = InMemReplayCache::new();
+
+        let alice_pubkey = *alice_store.get_identity_key_pair(None).await?.public_key();
+
+        let bob_pre_key_bundle = create_pre_key_bundle(&mut bob_store, &mut rng).await?;
+
+        process_prekey_bundle(
+            &bob_uuid_address,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            &bob_pre_key_bundle,
+            &mut rng,
+            None,
+        )
+        .await?;
+
+        let trust_root = KeyPair::generate(&mut rng);
+        let server_key = KeyPair::generate(&mut rng);
+
+        let server_cert =
+            ServerCertificate::new(1, server_key.public_key, &trust_root.private_key, &mut rng)?;
+
+        let expires = 1605722925;
+
+        let sender_cert = SenderCertificate::new(
+            alice_uuid.clone(),
+            None,
+            alice_pubkey,
+            alice_device_id,
+            expires,
+            server_cert,
+            &server_key.private_key,
+            &mut rng,
+        )?;
+
+        let alice_ptext = vec![1, 2, 3, 23, 99];
+        let alice_ctext = sealed_sender_encrypt(
+            &bob_uuid_address,
+            &sender_cert,
+            &alice_ptext,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            None,
+            &mut rng,
+        )
+        .await?;
+
+        let bob_ptext = sealed_sender_decrypt_with_replay_cache(
+            &alice_ctext,
+            &trust_root.public_key,
+            expires - 1,
+            0,
+            None,
+            bob_uuid.clone(),
+            bob_device_id,
+            &mut bob_store.identity_store,
+            &mut bob_store.session_store,
+            &mut bob_store.pre_key_store,
+            &mut bob_store.signed_pre_key_store,
+            &mut bob_replay_cache,
+            None,
+            &mut rng,
+        )
+        .await?;
+
+        assert_eq!(bob_ptext.message, alice_ptext);
+
+        // The replayed copy is caught before it reaches the session.
+
+        let bob_ptext = sealed_sender_decrypt_with_replay_cache(
+            &alice_ctext,
+            &trust_root.public_key,
+            expires - 1,
+            0,
+            None,
+            bob_uuid.clone(),
+            bob_device_id,
+            &mut bob_store.identity_store,
+            &mut bob_store.session_store,
+            &mut bob_store.pre_key_store,
+            &mut bob_store.signed_pre_key_store,
+            &mut bob_replay_cache,
+            None,
+            &mut rng,
+        )
+        .await;
+
+        match bob_ptext.map_err(SignalProtocolError::into_root_cause) {
+            Err(SignalProtocolError::SealedSenderReplay) => { /* ok */ }
+            Err(err) => {
+                panic!("Unexpected error {}", err)
+            }
+            Ok(_) => {
+                panic!("Shouldn't have decrypted")
+            }
+        }
+
+        // A new message from the same sender is unaffected.
+
+        let alice_ctext = sealed_sender_encrypt(
+            &bob_uuid_address,
+            &sender_cert,
+            &alice_ptext,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            None,
+            &mut rng,
+        )
+        .await?;
+
+        let bob_ptext = sealed_sender_decrypt_with_replay_cache(
+            &alice_ctext,
+            &trust_root.public_key,
+            expires - 1,
+            0,
+            None,
+            bob_uuid.clone(),
+            bob_device_id,
+            &mut bob_store.identity_store,
+            &mut bob_store.session_store,
+            &mut bob_store.pre_key_store,
+            &mut bob_store.signed_pre_key_store,
+            &mut bob_replay_cache,
+            None,
+            &mut rng,
+        )
+        .await?;
+
+        assert_eq!(bob_ptext.message, alice_ptext);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn test_notification_decrypt() -> Result<(), SignalProtocolError> {
+    block_on(async {
+        let mut rng = OsRng;
+
+        let alice_device_id = 23;
+        let bob_device_id = 42;
+
+        let alice_uuid = "9d0652a3-dcc3-4d11-975f-74d61598733f".to_string();
+        let bob_uuid = "796abedb-ca4e-4f18-8803-1fde5b921f9f".to_string();
+
+        let alice_uuid_address = ProtocolAddress::new(alice_uuid.clone(), alice_device_id);
+        let bob_uuid_address = ProtocolAddress::new(bob_uuid.clone(), bob_device_id);
+
+        let mut alice_store = support::test_in_memory_protocol_store()?;
+        let mut bob_store = support::test_in_memory_protocol_store()?;
+
+        let alice_pubkey = *alice_store.get_identity_key_pair(None).await?.public_key();
+
+        let bob_pre_key_bundle = create_pre_key_bundle(&mut bob_store, &mut rng).await?;
+        let bob_pre_key_id = bob_pre_key_bundle
+            .pre_key_id()?
+            .expect("has a one-time pre-key");
+
+        process_prekey_bundle(
+            &bob_uuid_address,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            &bob_pre_key_bundle,
+            &mut rng,
+            None,
+        )
+        .await?;
+
+        let trust_root = KeyPair::generate(&mut rng);
+        let server_key = KeyPair::generate(&mut rng);
+
+        let server_cert =
+            ServerCertificate::new(1, server_key.public_key, &trust_root.private_key, &mut rng)?;
+
+        let expires = 1605722925;
+
+        let sender_cert = SenderCertificate::new(
+            alice_uuid.clone(),
+            None,
+            alice_pubkey,
+            alice_device_id,
+            expires,
+            server_cert,
+            &server_key.private_key,
+            &mut rng,
+        )?;
+
+        let alice_ctext = sealed_sender_encrypt(
+            &bob_uuid_address,
+            &sender_cert,
+            b"first",
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            None,
+            &mut rng,
+        )
+        .await?;
+        let decrypted = notification_decrypt(
+            &alice_ctext,
+            &trust_root.public_key,
+            expires - 1,
+            None,
+            bob_uuid.clone(),
+            bob_device_id,
+            &bob_store.identity_store,
+            &bob_store.session_store,
+            &bob_store.pre_key_store,
+            &bob_store.signed_pre_key_store,
+            None,
+            &mut rng,
+        )
+        .await?;
+
+        assert_eq!(decrypted.message.message, b"first");
+        assert_eq!(decrypted.message.sender_uuid, alice_uuid);
+
+        // Nothing was written to Bob's stores...
+        assert!(bob_store
+            .load_session(&alice_uuid_address, None)
+            .await?
+            .is_none());
+        assert!(bob_store
+            .get_identity(&alice_uuid_address, None)
+            .await?
+            .is_none());
+        assert!(bob_store.get_pre_key(bob_pre_key_id, None).await.is_ok());
+
+        // ...until the commit set is applied, after a round trip through its serialized form.
+        assert!(!decrypted.commit_set.is_empty());
+        let commit_set = CommitSet::deserialize(&decrypted.commit_set.serialize()?)?;
+        commit_set
+            .apply(
+                &mut bob_store.session_store,
+                &mut bob_store.identity_store,
+                &mut bob_store.pre_key_store,
+                None,
+            )
+            .await?;
+
+        assert!(bob_store
+            .load_session(&alice_uuid_address, None)
+            .await?
+            .is_some());
+        assert_eq!(
+            bob_store.get_identity(&alice_uuid_address, None).await?,
+            Some(IdentityKey::new(alice_pubkey))
+        );
+        assert!(bob_store.get_pre_key(bob_pre_key_id, None).await.is_err());
+
+        // A commit set for a session that has since moved on is rejected.
+        let alice_ctext = sealed_sender_encrypt(
+            &bob_uuid_address,
+            &sender_cert,
+            b"second",
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            None,
+            &mut rng,
+        )
+        .await?;
+        let stale = notification_decrypt(
+            &alice_ctext,
+            &trust_root.public_key,
+            expires - 1,
+            None,
+            bob_uuid.clone(),
+            bob_device_id,
+            &bob_store.identity_store,
+            &bob_store.session_store,
+            &bob_store.pre_key_store,
+            &bob_store.signed_pre_key_store,
+            None,
+            &mut rng,
+        )
+        .await?;
+        assert_eq!(stale.message.message, b"second");
+
+        let alice_ctext = sealed_sender_encrypt(
+            &bob_uuid_address,
+            &sender_cert,
+            b"third",
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            None,
+            &mut rng,
+        )
+        .await?;
+        let bob_ptext = sealed_sender_decrypt(
+            &alice_ctext,
+            &trust_root.public_key,
+            expires - 1,
+            None,
+            bob_uuid.clone(),
+            bob_device_id,
+            &mut bob_store.identity_store,
+            &mut bob_store.session_store,
+            &mut bob_store.pre_key_store,
+            &mut bob_store.signed_pre_key_store,
+            None,
+            &mut rng,
+        )
+        .await?;
+        assert_eq!(bob_ptext.message, b"third");
+
+        let result = stale
+            .commit_set
+            .apply(
+                &mut bob_store.session_store,
+                &mut bob_store.identity_store,
+                &mut bob_store.pre_key_store,
+                None,
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(SignalProtocolError::InvalidState("CommitSet::apply", _))
+        ));
+
+        Ok(())
+    })
+}
+
+struct QueuedCertificateSource {
+    certificates: Vec<SenderCertificate>,
+    fetches: usize,
+}
+
+#[async_trait(?Send)]
+impl SenderCertificateSource for QueuedCertificateSource {
+    async fn fetch_sender_certificate(
+        &mut self,
+        _ctx: Context,
+    ) -> Result<SenderCertificate, SignalProtocolError> {
+        self.fetches += 1;
+        if self.certificates.is_empty() {
+            return Err(SignalProtocolError::InvalidState(
+                "fetch_sender_certificate",
+                "service unavailable".to_string(),
+            ));
+        }
+        Ok(self.certificates.remove(0))
+    }
+}
+
+#[test]
+fn test_sender_certificate_provider() -> Result<(), SignalProtocolError> {
+    block_on(async {
+        let mut rng = OsRng;
+
+        let alice_device_id = 23;
+        let bob_device_id = 42;
+
+        let alice_uuid = "9d0652a3-dcc3-4d11-975f-74d61598733f".to_string();
+        let bob_uuid = "796abedb-ca4e-4f18-8803-1fde5b921f9f".to_string();
+
+        let bob_uuid_address = ProtocolAddress::new(bob_uuid.clone(), bob_device_id);
+
+        let mut alice_store = support::test_in_memory_protocol_store()?;
+        let mut bob_store = support::test_in_memory_protocol_store()?;
+
+        let alice_pubkey = *alice_store.get_identity_key_pair(None).await?.public_key();
+
+        let bob_pre_key_bundle = create_pre_key_bundle(&mut bob_store, &mut rng).await?;
+
+        process_prekey_bundle(
+            &bob_uuid_address,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            &bob_pre_key_bundle,
+            &mut rng,
+            None,
+        )
+        .await?;
+
+        let trust_root = KeyPair::generate(&mut rng);
+        let server_key = KeyPair::generate(&mut rng);
+
+        let server_cert =
+            ServerCertificate::new(1, server_key.public_key, &trust_root.private_key, &mut rng)?;
+
+        let sender_cert = |expires| {
+            SenderCertificate::new(
+                alice_uuid.clone(),
+                None,
+                alice_pubkey,
+                alice_device_id,
+                expires,
+                server_cert.clone(),
+                &server_key.private_key,
+                &mut OsRng,
+            )
+        };
+
+        let mut provider =
+            SenderCertificateProvider::new(1000).with_certificate(sender_cert(10000)?);
+        let mut source = QueuedCertificateSource {
+            certificates: vec![sender_cert(20000)?],
+            fetches: 0,
+        };
+
+        // Well before expiration, the cached certificate is used as is.
+
+        let alice_ptext = vec![1, 2, 3, 23, 99];
+        let alice_ctext = sealed_sender_encrypt_with_provider(
+            &bob_uuid_address,
+            &mut provider,
+            &mut source,
+            5000,
+            &alice_ptext,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            None,
+            &mut rng,
+        )
+        .await?;
+        assert_eq!(source.fetches, 0);
+
+        let bob_ptext = sealed_sender_decrypt(
+            &alice_ctext,
+            &trust_root.public_key,
+            5000,
+            None,
+            bob_uuid.clone(),
+            bob_device_id,
+            &mut bob_store.identity_store,
+            &mut bob_store.session_store,
+            &mut bob_store.pre_key_store,
+            &mut bob_store.signed_pre_key_store,
+            None,
+            &mut rng,
+        )
+        .await?;
+        assert_eq!(bob_ptext.message, alice_ptext);
+
+        // Within the freshness window, a new certificate is fetched first.
+
+        let alice_ctext = sealed_sender_encrypt_with_provider(
+            &bob_uuid_address,
+            &mut provider,
+            &mut source,
+            9500,
+            &alice_ptext,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            None,
+            &mut rng,
+        )
+        .await?;
+        assert_eq!(source.fetches, 1);
+        assert_eq!(provider.current().expect("cached").expiration()?, 20000);
+
+        let bob_ptext = sealed_sender_decrypt(
+            &alice_ctext,
+            &trust_root.public_key,
+            15000,
+            None,
+            bob_uuid.clone(),
+            bob_device_id,
+            &mut bob_store.identity_store,
+            &mut bob_store.session_store,
+            &mut bob_store.pre_key_store,
+            &mut bob_store.signed_pre_key_store,
+            None,
+            &mut rng,
+        )
+        .await?;
+        assert_eq!(bob_ptext.message, alice_ptext);
+
+        // A failed refresh falls back to the cached certificate while it is still valid...
+
+        let cert = provider.get(19500, &mut source, None).await?;
+        assert_eq!(cert.expiration()?, 20000);
+        assert_eq!(source.fetches, 2);
+
+        // ...but not after it has expired.
+
+        match provider
+            .get(20500, &mut source, None)
+            .await
+            .map_err(SignalProtocolError::into_root_cause)
+        {
+            Err(SignalProtocolError::InvalidState("fetch_sender_certificate", _)) => { /* ok */ }
+            Err(err) => panic!("Unexpected error {}", err),
+            Ok(_) => panic!("Shouldn't have returned an expired certificate"),
+        }
+
+        // A fetched certificate that has already expired is rejected.
+
+        source.certificates.push(sender_cert(100)?);
+        match provider.get(20500, &mut source, None).await {
+            Err(SignalProtocolError::SealedSenderCertificateExpired(100)) => { /* ok */ }
+            Err(err) => panic!("Unexpected error {}", err),
+            Ok(_) => panic!("Shouldn't have returned an expired certificate"),
+        }
+        assert_eq!(provider.current().expect("cached").expiration()?, 20000);
+
+        Ok(())
+    })
+}
@@ -201,7 +201,8 @@ fn test_basic_prekey_v3() -> Result<(), SignalProtocolError> {
         assert!(matches!(
             decrypt(&mut bob_store, &alice_address, &outgoing_message)
                 .await
-                .unwrap_err(),
+                .unwrap_err()
+                .into_root_cause(),
             SignalProtocolError::UntrustedIdentity(a) if a == alice_address
         ));
 
@@ -913,13 +914,92 @@ fn message_key_limits() -> Result<(), SignalProtocolError> {
             .await
             .unwrap_err();
         assert!(matches!(
-            err,
+            err.root_cause(),
             SignalProtocolError::DuplicatedMessage(2300, 5)
         ));
         Ok(())
     })
 }
 
+#[test]
+fn encrypt_batch() -> Result<(), SignalProtocolError> {
+    block_on(async {
+        let alice_address = ProtocolAddress::new("+14159999999".to_owned(), 1);
+        let bob_device_1 = ProtocolAddress::new("+14158888888".to_owned(), 1);
+        let bob_device_2 = ProtocolAddress::new("+14158888888".to_owned(), 2);
+        let carol_address = ProtocolAddress::new("+14157777777".to_owned(), 1);
+
+        let mut alice_store = support::test_in_memory_protocol_store()?;
+        let mut bob_stores = Vec::new();
+        for bob_address in &[&bob_device_1, &bob_device_2] {
+            let (alice_session_record, bob_session_record) = initialize_sessions_v3()?;
+            let mut bob_store = support::test_in_memory_protocol_store()?;
+            alice_store
+                .store_session(bob_address, &alice_session_record, None)
+                .await?;
+            bob_store
+                .store_session(&alice_address, &bob_session_record, None)
+                .await?;
+            bob_stores.push(bob_store);
+        }
+
+        let recipients = [
+            bob_device_1.clone(),
+            bob_device_2.clone(),
+            bob_device_1.clone(),
+        ];
+        let messages = message_encrypt_batch(
+            b"hello",
+            &recipients,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            None,
+        )
+        .await?;
+        assert_eq!(messages.len(), recipients.len());
+
+        let counters: Vec<_> = messages
+            .iter()
+            .map(|message| message.as_ref().expect("encrypted").counter())
+            .collect();
+        assert_eq!(counters, [Some(0), Some(0), Some(1)]);
+
+        // The repeated address gets two distinct messages from the same session.
+        for (message, store_index) in messages.iter().zip(&[0, 1, 0]) {
+            let message = message.as_ref().expect("encrypted");
+            let plaintext = decrypt(&mut bob_stores[*store_index], &alice_address, message).await?;
+            assert_eq!(plaintext, b"hello");
+        }
+
+        // A missing session only fails that recipient; the others still advance.
+        let mut results = message_encrypt_batch(
+            b"hello again",
+            &[bob_device_1.clone(), carol_address],
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            None,
+        )
+        .await?;
+        assert_eq!(results.len(), 2);
+        assert!(matches!(
+            results.pop().expect("two results"),
+            Err(SignalProtocolError::SessionNotFound(_))
+        ));
+        let message = results.pop().expect("two results")?;
+        let plaintext = decrypt(&mut bob_stores[0], &alice_address, &message).await?;
+        assert_eq!(plaintext, b"hello again");
+
+        let message = encrypt(&mut alice_store, &bob_device_1, "hello once more").await?;
+        if let CiphertextMessage::SignalMessage(message) = &message {
+            assert_eq!(message.counter()?, 3);
+        } else {
+            panic!("unexpected message type");
+        }
+
+        Ok(())
+    })
+}
+
 #[allow(clippy::needless_range_loop)]
 fn run_session_interaction(
     alice_session: SessionRecord,
@@ -1994,3 +2074,726 @@ fn simultaneous_initiate_lost_message_repeated_messages() -> Result<(), SignalPr
         Ok(())
     })
 }
+
+#[test]
+fn record_serialization_preserves_unknown_fields() -> Result<(), SignalProtocolError> {
+    // field 15, length-delimited, "future"
+    let unknown_field = [0x7A, 0x06, b'f', b'u', b't', b'u', b'r', b'e'];
+
+    let mut session_bytes = SessionRecord::new_fresh().serialize()?;
+    session_bytes.extend_from_slice(&unknown_field);
+    let session = SessionRecord::deserialize(&session_bytes)?;
+    assert_eq!(session.serialized_version(), 1);
+    assert!(session.serialize()?.ends_with(&unknown_field));
+
+    let key_pair = KeyPair::generate(&mut OsRng);
+    let mut pre_key_bytes = PreKeyRecord::new(7, &key_pair).serialize()?;
+    pre_key_bytes.extend_from_slice(&unknown_field);
+    let pre_key = PreKeyRecord::deserialize(&pre_key_bytes)?;
+    assert_eq!(pre_key.serialized_version(), 1);
+    assert_eq!(pre_key.id()?, 7);
+    assert!(pre_key.serialize()?.ends_with(&unknown_field));
+
+    let mut sender_key_bytes = SenderKeyRecord::new_empty().serialize()?;
+    sender_key_bytes.extend_from_slice(&unknown_field);
+    let sender_key = SenderKeyRecord::deserialize(&sender_key_bytes)?;
+    assert_eq!(sender_key.serialized_version(), 1);
+    assert!(sender_key.serialize()?.ends_with(&unknown_field));
+
+    let legacy = SessionRecord::deserialize(&[])?;
+    assert_eq!(legacy.serialized_version(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn sender_key_serialization_preserves_nested_unknown_fields() -> Result<(), SignalProtocolError> {
+    // field 15, length-delimited, "future"
+    let unknown_field = [0x7A, 0x06, b'f', b'u', b't', b'u', b'r', b'e'];
+
+    let signing_key = KeyPair::generate(&mut OsRng);
+    let mut record = SenderKeyRecord::new_empty();
+    record.add_sender_key_state(
+        42,
+        0,
+        &[1; 32],
+        signing_key.public_key,
+        Some(signing_key.private_key),
+    )?;
+    let mut state_bytes = record.sender_key_state()?.serialize()?;
+    state_bytes.extend_from_slice(&unknown_field);
+
+    // field 2 (a version newer than this library writes), field 1 (the state)
+    let mut record_bytes = vec![0x10, 0x63, 0x0A, state_bytes.len() as u8];
+    record_bytes.extend_from_slice(&state_bytes);
+
+    let mut record = SenderKeyRecord::deserialize(&record_bytes)?;
+    assert_eq!(record.serialized_version(), 0x63);
+    assert_eq!(record.serialize()?, record_bytes);
+    assert_eq!(record.sender_key_state()?.serialize()?, state_bytes);
+
+    Ok(())
+}
+
+#[test]
+fn decrypt_errors_carry_operation_context() -> Result<(), SignalProtocolError> {
+    block_on(async {
+        let (alice_session, _bob_session) = initialize_sessions_v3()?;
+
+        let alice_address = ProtocolAddress::new("+14159999999".to_owned(), 1);
+        let bob_address = ProtocolAddress::new("+14158888888".to_owned(), 1);
+
+        let mut alice_store = support::test_in_memory_protocol_store()?;
+        let mut bob_store = support::test_in_memory_protocol_store()?;
+
+        alice_store
+            .store_session(&bob_address, &alice_session, None)
+            .await?;
+        let message = encrypt(&mut alice_store, &bob_address, "hello").await?;
+
+        // Bob never stored his half of the session.
+        let err = decrypt(&mut bob_store, &alice_address, &message)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err.root_cause(),
+            SignalProtocolError::SessionNotFound(_)
+        ));
+        let contexts: Vec<&ErrorContext> = err.contexts().collect();
+        assert_eq!(contexts.len(), 1);
+        assert_eq!(contexts[0].operation(), "message_decrypt_signal");
+        assert_eq!(contexts[0].address(), Some(&alice_address));
+        assert_eq!(
+            contexts[0].message_type(),
+            Some(CiphertextMessageType::Whisper)
+        );
+        assert_eq!(
+            err.to_string(),
+            "message_decrypt_signal(address=+14159999999.1, message_type=Whisper): \
+             session with '+14159999999.1' not found"
+        );
+
+        Ok(())
+    })
+}
+
+#[test]
+fn prekey_bundle_with_invalid_registration_id() -> Result<(), SignalProtocolError> {
+    block_on(async {
+        let mut csprng = OsRng;
+        let bob_address = ProtocolAddress::new("+14151111112".to_owned(), 1);
+
+        let mut alice_store = support::test_in_memory_protocol_store()?;
+        let bob_store = support::test_in_memory_protocol_store()?;
+
+        let bob_signed_pre_key_pair = KeyPair::generate(&mut csprng);
+        let bob_signed_pre_key_signature = bob_store
+            .get_identity_key_pair(None)
+            .await?
+            .private_key()
+            .calculate_signature(&bob_signed_pre_key_pair.public_key.serialize(), &mut csprng)?;
+
+        let bob_pre_key_bundle = PreKeyBundle::new(
+            0x4000, // registration id
+            1,      // device id
+            None,   // pre key
+            22,     // signed pre key id
+            bob_signed_pre_key_pair.public_key,
+            bob_signed_pre_key_signature.to_vec(),
+            *bob_store.get_identity_key_pair(None).await?.identity_key(),
+        )?;
+
+        let result = process_prekey_bundle(
+            &bob_address,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            &bob_pre_key_bundle,
+            &mut csprng,
+            None,
+        )
+        .await;
+
+        assert!(matches!(
+            result.map_err(SignalProtocolError::into_root_cause),
+            Err(SignalProtocolError::InvalidRegistrationId(address, 0x4000)) if address == bob_address
+        ));
+        assert!(alice_store
+            .load_session(&bob_address, None)
+            .await?
+            .is_none());
+
+        Ok(())
+    })
+}
+
+#[test]
+fn prekey_bundle_session_version_negotiation() -> Result<(), SignalProtocolError> {
+    block_on(async {
+        let mut csprng = OsRng;
+        let bob_address = ProtocolAddress::new("+14151111112".to_owned(), 1);
+
+        let bob_store = support::test_in_memory_protocol_store()?;
+
+        let bob_signed_pre_key_pair = KeyPair::generate(&mut csprng);
+        let bob_signed_pre_key_signature = bob_store
+            .get_identity_key_pair(None)
+            .await?
+            .private_key()
+            .calculate_signature(&bob_signed_pre_key_pair.public_key.serialize(), &mut csprng)?;
+
+        let bob_pre_key_bundle = PreKeyBundle::new(
+            bob_store.get_local_registration_id(None).await?,
+            1,    // device id
+            None, // pre key
+            22,   // signed pre key id
+            bob_signed_pre_key_pair.public_key,
+            bob_signed_pre_key_signature.to_vec(),
+            *bob_store.get_identity_key_pair(None).await?.identity_key(),
+        )?;
+        assert_eq!(bob_pre_key_bundle.min_session_version()?, 3);
+        assert_eq!(bob_pre_key_bundle.max_session_version()?, 3);
+        assert!(bob_pre_key_bundle
+            .clone()
+            .with_session_versions(4, 3)
+            .is_err());
+
+        // A peer that also supports a future version still gets the newest one we support.
+        let mut alice_store = support::test_in_memory_protocol_store()?;
+        process_prekey_bundle(
+            &bob_address,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            &bob_pre_key_bundle.clone().with_session_versions(2, 9)?,
+            &mut csprng,
+            None,
+        )
+        .await?;
+        assert_eq!(
+            alice_store
+                .load_session(&bob_address, None)
+                .await?
+                .expect("session found")
+                .session_version()?,
+            MAX_SESSION_VERSION
+        );
+
+        // Version 4 is only supported with header encryption.
+        #[cfg(not(feature = "header-encryption"))]
+        let too_new = (4, 5);
+        #[cfg(feature = "header-encryption")]
+        let too_new = (5, 6);
+
+        for &(min, max) in &[(1, 2), too_new] {
+            let mut alice_store = support::test_in_memory_protocol_store()?;
+            let result = process_prekey_bundle(
+                &bob_address,
+                &mut alice_store.session_store,
+                &mut alice_store.identity_store,
+                &bob_pre_key_bundle.clone().with_session_versions(min, max)?,
+                &mut csprng,
+                None,
+            )
+            .await;
+
+            match result.map_err(SignalProtocolError::into_root_cause) {
+                Err(SignalProtocolError::LegacyCiphertextVersion(2)) if min == 1 => {}
+                Err(SignalProtocolError::UnrecognizedMessageVersion(v)) if v == too_new.0 => {}
+                other => panic!("unexpected result for {}..={}: {:?}", min, max, other),
+            }
+            assert!(alice_store
+                .load_session(&bob_address, None)
+                .await?
+                .is_none());
+        }
+
+        Ok(())
+    })
+}
+
+#[test]
+#[cfg(feature = "header-encryption")]
+fn header_encrypted_session() -> Result<(), SignalProtocolError> {
+    block_on(async {
+        let mut csprng = OsRng;
+        let alice_address = ProtocolAddress::new("+14151111111".to_owned(), 1);
+        let bob_address = ProtocolAddress::new("+14151111112".to_owned(), 1);
+
+        let mut alice_store = support::test_in_memory_protocol_store()?;
+        let mut bob_store = support::test_in_memory_protocol_store()?;
+
+        let bob_pre_key_bundle = create_pre_key_bundle(&mut bob_store, &mut csprng)
+            .await?
+            .with_session_versions(3, HEADER_ENCRYPTED_MESSAGE_VERSION.into())?;
+        process_prekey_bundle(
+            &bob_address,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            &bob_pre_key_bundle,
+            &mut csprng,
+            None,
+        )
+        .await?;
+
+        let alice_message = encrypt(&mut alice_store, &bob_address, "hidden header").await?;
+        let signal_message = match &alice_message {
+            CiphertextMessage::PreKeySignalMessage(message) => message.message(),
+            _ => panic!("unexpected message type"),
+        };
+        assert_eq!(
+            signal_message.message_version(),
+            HEADER_ENCRYPTED_MESSAGE_VERSION
+        );
+        assert!(signal_message.has_encrypted_header());
+        // The sender knows its own header...
+        assert_eq!(signal_message.counter()?, 0);
+        assert_eq!(alice_message.counter(), Some(0));
+        // ...but the recipient can't read it until the message is decrypted.
+        let received = CiphertextMessage::deserialize(
+            alice_message.message_type(),
+            alice_message.serialize(),
+        )?;
+        assert!(matches!(
+            &received,
+            CiphertextMessage::PreKeySignalMessage(message)
+                if message.message().counter().is_err()
+        ));
+        assert_eq!(received.counter(), None);
+
+        assert_eq!(
+            decrypt(&mut bob_store, &alice_address, &alice_message).await?,
+            b"hidden header"
+        );
+        assert_eq!(
+            bob_store
+                .load_session(&alice_address, None)
+                .await?
+                .expect("session found")
+                .session_version()?,
+            u32::from(HEADER_ENCRYPTED_MESSAGE_VERSION)
+        );
+
+        let bob_message = encrypt(&mut bob_store, &alice_address, "reply").await?;
+        assert_eq!(
+            decrypt(&mut alice_store, &bob_address, &bob_message).await?,
+            b"reply"
+        );
+
+        // Covers new chains in both directions and out-of-order delivery into an old chain.
+        run_interaction(
+            &mut alice_store,
+            &alice_address,
+            &mut bob_store,
+            &bob_address,
+        )
+        .await?;
+
+        // Bundles that don't advertise the new version still get plaintext headers.
+        let mut carol_store = support::test_in_memory_protocol_store()?;
+        let bundle = create_pre_key_bundle(&mut bob_store, &mut csprng).await?;
+        process_prekey_bundle(
+            &bob_address,
+            &mut carol_store.session_store,
+            &mut carol_store.identity_store,
+            &bundle,
+            &mut csprng,
+            None,
+        )
+        .await?;
+        let carol_message = encrypt(&mut carol_store, &bob_address, "plain").await?;
+        assert_eq!(carol_message.counter(), Some(0));
+
+        Ok(())
+    })
+}
+
+#[test]
+fn decrypt_with_identity_override() -> Result<(), SignalProtocolError> {
+    block_on(async {
+        let mut csprng = OsRng;
+        let alice_address = ProtocolAddress::new("+14151111111".to_owned(), 1);
+        let bob_address = ProtocolAddress::new("+14151111112".to_owned(), 1);
+
+        let mut alice_store = support::test_in_memory_protocol_store()?;
+        let mut bob_store = support::test_in_memory_protocol_store()?;
+
+        let bob_pre_key_bundle = create_pre_key_bundle(&mut bob_store, &mut csprng).await?;
+        process_prekey_bundle(
+            &bob_address,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            &bob_pre_key_bundle,
+            &mut csprng,
+            None,
+        )
+        .await?;
+
+        // Bob remembers a different identity for Alice.
+        let old_identity = *IdentityKeyPair::generate(&mut csprng).identity_key();
+        bob_store
+            .save_identity(&alice_address, &old_identity, None)
+            .await?;
+
+        let alice_identity = *alice_store
+            .get_identity_key_pair(None)
+            .await?
+            .identity_key();
+        let outgoing_message = encrypt(&mut alice_store, &bob_address, "hi bob").await?;
+
+        assert!(matches!(
+            decrypt(&mut bob_store, &alice_address, &outgoing_message)
+                .await
+                .unwrap_err()
+                .into_root_cause(),
+            SignalProtocolError::UntrustedIdentity(a) if a == alice_address
+        ));
+
+        // Approving some other identity doesn't help.
+        let other_identity = *IdentityKeyPair::generate(&mut csprng).identity_key();
+        assert!(matches!(
+            message_decrypt_with_identity_override(
+                &outgoing_message,
+                &alice_address,
+                &other_identity,
+                &mut bob_store.session_store,
+                &mut bob_store.identity_store,
+                &mut bob_store.pre_key_store,
+                &mut bob_store.signed_pre_key_store,
+                &mut csprng,
+                None,
+            )
+            .await
+            .unwrap_err()
+            .into_root_cause(),
+            SignalProtocolError::UntrustedIdentity(a) if a == alice_address
+        ));
+
+        let decrypted = message_decrypt_with_identity_override(
+            &outgoing_message,
+            &alice_address,
+            &alice_identity,
+            &mut bob_store.session_store,
+            &mut bob_store.identity_store,
+            &mut bob_store.pre_key_store,
+            &mut bob_store.signed_pre_key_store,
+            &mut csprng,
+            None,
+        )
+        .await?;
+        assert_eq!(decrypted, b"hi bob");
+        assert_eq!(
+            bob_store.get_identity(&alice_address, None).await?,
+            Some(alice_identity)
+        );
+
+        Ok(())
+    })
+}
+
+#[test]
+fn decrypt_returning_duplicates() -> Result<(), SignalProtocolError> {
+    block_on(async {
+        let mut csprng = OsRng;
+        let alice_address = ProtocolAddress::new("+14151111111".to_owned(), 1);
+        let bob_address = ProtocolAddress::new("+14151111112".to_owned(), 1);
+
+        let mut alice_store = support::test_in_memory_protocol_store()?;
+        let mut bob_store = support::test_in_memory_protocol_store()?;
+
+        let bob_pre_key_bundle = create_pre_key_bundle(&mut bob_store, &mut csprng).await?;
+        process_prekey_bundle(
+            &bob_address,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            &bob_pre_key_bundle,
+            &mut csprng,
+            None,
+        )
+        .await?;
+
+        let outgoing_message = encrypt(&mut alice_store, &bob_address, "hi bob").await?;
+        assert_eq!(
+            decrypt(&mut bob_store, &alice_address, &outgoing_message).await?,
+            b"hi bob"
+        );
+
+        let bob_reply = encrypt(&mut bob_store, &alice_address, "hi alice").await?;
+        assert_eq!(bob_reply.message_type(), CiphertextMessageType::Whisper);
+
+        let plaintext_mac = match message_decrypt_returning_duplicates(
+            &bob_reply,
+            &bob_address,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            &mut alice_store.pre_key_store,
+            &mut alice_store.signed_pre_key_store,
+            &mut csprng,
+            None,
+        )
+        .await?
+        {
+            DecryptionOutcome::Decrypted {
+                plaintext,
+                plaintext_mac,
+            } => {
+                assert_eq!(plaintext, b"hi alice");
+                plaintext_mac
+            }
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        };
+
+        // The replayed copy reports the same MAC as the copy that was already delivered.
+        assert_eq!(
+            message_decrypt_returning_duplicates(
+                &bob_reply,
+                &bob_address,
+                &mut alice_store.session_store,
+                &mut alice_store.identity_store,
+                &mut alice_store.pre_key_store,
+                &mut alice_store.signed_pre_key_store,
+                &mut csprng,
+                None,
+            )
+            .await?,
+            DecryptionOutcome::Duplicate {
+                plaintext_mac: Some(plaintext_mac)
+            }
+        );
+
+        // A message decrypted without asking for duplicate reporting left nothing behind.
+        assert_eq!(
+            message_decrypt_returning_duplicates(
+                &outgoing_message,
+                &alice_address,
+                &mut bob_store.session_store,
+                &mut bob_store.identity_store,
+                &mut bob_store.pre_key_store,
+                &mut bob_store.signed_pre_key_store,
+                &mut csprng,
+                None,
+            )
+            .await?,
+            DecryptionOutcome::Duplicate {
+                plaintext_mac: None
+            }
+        );
+
+        Ok(())
+    })
+}
+
+#[test]
+fn deferred_stores_record_and_replay() -> Result<(), SignalProtocolError> {
+    block_on(async {
+        let mut csprng = OsRng;
+        let alice_address = ProtocolAddress::new("+14151111111".to_owned(), 1);
+        let bob_address = ProtocolAddress::new("+14151111112".to_owned(), 1);
+
+        let mut alice_store = support::test_in_memory_protocol_store()?;
+        let mut bob_store = support::test_in_memory_protocol_store()?;
+
+        let bob_pre_key_bundle = create_pre_key_bundle(&mut bob_store, &mut csprng).await?;
+        process_prekey_bundle(
+            &bob_address,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            &bob_pre_key_bundle,
+            &mut csprng,
+            None,
+        )
+        .await?;
+
+        let first_message = encrypt(&mut alice_store, &bob_address, "first").await?;
+
+        let recorder = MutationRecorder::new();
+        let plaintext = message_decrypt(
+            &first_message,
+            &alice_address,
+            &mut recorder.session_store(&bob_store.session_store),
+            &mut recorder.identity_store(&bob_store.identity_store),
+            &mut recorder.pre_key_store(&bob_store.pre_key_store),
+            &mut recorder.signed_pre_key_store(&bob_store.signed_pre_key_store),
+            &mut csprng,
+            None,
+        )
+        .await?;
+        assert_eq!(plaintext, b"first");
+
+        // Nothing has been written yet.
+        assert!(bob_store
+            .load_session(&alice_address, None)
+            .await?
+            .is_none());
+        assert!(bob_store
+            .get_identity(&alice_address, None)
+            .await?
+            .is_none());
+
+        let log = MutationLog::deserialize(&recorder.into_log().serialize()?)?;
+        assert!(!log.is_empty());
+        log.apply(
+            &mut bob_store.session_store,
+            &mut bob_store.identity_store,
+            &mut bob_store.pre_key_store,
+            &mut bob_store.signed_pre_key_store,
+            &mut bob_store.sender_key_store,
+            None,
+        )
+        .await?;
+
+        assert!(bob_store
+            .load_session(&alice_address, None)
+            .await?
+            .is_some());
+        assert!(bob_store
+            .get_identity(&alice_address, None)
+            .await?
+            .is_some());
+        let pre_key_id = bob_pre_key_bundle
+            .pre_key_id()?
+            .expect("has a one-time pre-key");
+        assert!(bob_store.get_pre_key(pre_key_id, None).await.is_err());
+
+        // The replayed session picks up where the deferred decryption left off.
+        let second_message = encrypt(&mut alice_store, &bob_address, "second").await?;
+        assert_eq!(
+            decrypt(&mut bob_store, &alice_address, &second_message).await?,
+            b"second"
+        );
+
+        // A log recorded against a session that has since moved on is rejected.
+        let third_message = encrypt(&mut alice_store, &bob_address, "third").await?;
+        let recorder = MutationRecorder::new();
+        message_decrypt(
+            &third_message,
+            &alice_address,
+            &mut recorder.session_store(&bob_store.session_store),
+            &mut recorder.identity_store(&bob_store.identity_store),
+            &mut recorder.pre_key_store(&bob_store.pre_key_store),
+            &mut recorder.signed_pre_key_store(&bob_store.signed_pre_key_store),
+            &mut csprng,
+            None,
+        )
+        .await?;
+        let stale_log = recorder.into_log();
+
+        decrypt(&mut bob_store, &alice_address, &third_message).await?;
+        assert!(matches!(
+            stale_log
+                .apply(
+                    &mut bob_store.session_store,
+                    &mut bob_store.identity_store,
+                    &mut bob_store.pre_key_store,
+                    &mut bob_store.signed_pre_key_store,
+                    &mut bob_store.sender_key_store,
+                    None,
+                )
+                .await,
+            Err(SignalProtocolError::InvalidState(..))
+        ));
+
+        Ok(())
+    })
+}
+
+#[test]
+fn security_snapshot_reports_public_state_only() -> Result<(), SignalProtocolError> {
+    block_on(async {
+        let mut csprng = OsRng;
+        let alice_address = ProtocolAddress::new("+14151111111".to_owned(), 1);
+        let bob_address = ProtocolAddress::new("+14151111112".to_owned(), 1);
+
+        let mut alice_store = support::test_in_memory_protocol_store()?;
+        let mut bob_store = support::test_in_memory_protocol_store()?;
+
+        let group_sender = SenderKeyName::new("group".to_owned(), alice_address.clone())?;
+        let other_group_sender = SenderKeyName::new("other".to_owned(), alice_address.clone())?;
+        let groups = [group_sender.clone(), other_group_sender];
+
+        // Nothing is known about Bob yet.
+        let snapshot = security_snapshot(
+            &bob_address,
+            &groups,
+            &alice_store.session_store,
+            &alice_store.identity_store,
+            &mut alice_store.sender_key_store,
+            None,
+        )
+        .await?;
+        assert_eq!(snapshot.identity_fingerprint(), None);
+        assert!(snapshot.session().is_none());
+        assert!(snapshot.sender_key_distributions().is_empty());
+
+        let bob_pre_key_bundle = create_pre_key_bundle(&mut bob_store, &mut csprng).await?;
+        process_prekey_bundle(
+            &bob_address,
+            &mut alice_store.session_store,
+            &mut alice_store.identity_store,
+            &bob_pre_key_bundle,
+            &mut csprng,
+            None,
+        )
+        .await?;
+        let distribution_message = create_sender_key_distribution_message(
+            &group_sender,
+            &mut alice_store,
+            &mut csprng,
+            None,
+        )
+        .await?;
+        let mut record = alice_store
+            .load_sender_key(&group_sender, None)
+            .await?
+            .expect("created");
+        record.mark_distributed(&bob_address)?;
+        alice_store
+            .store_sender_key(&group_sender, &record, None)
+            .await?;
+
+        let snapshot = security_snapshot(
+            &bob_address,
+            &groups,
+            &alice_store.session_store,
+            &alice_store.identity_store,
+            &mut alice_store.sender_key_store,
+            None,
+        )
+        .await?;
+
+        let session = snapshot.session().expect("has a session");
+        assert_eq!(session.session_version(), 3);
+        assert!(session.has_unacknowledged_pre_key_message());
+        assert!(session.last_ratchet_time().is_some());
+        assert_eq!(session.previous_session_count(), 0);
+        assert!(snapshot.identity_fingerprint().is_some());
+        assert_eq!(
+            snapshot.identity_fingerprint(),
+            session.remote_identity_fingerprint()
+        );
+
+        let distributions = snapshot.sender_key_distributions();
+        assert_eq!(distributions.len(), 1);
+        assert_eq!(distributions[0].group_id(), "group");
+        assert_eq!(
+            distributions[0].current_key_id(),
+            Some(distribution_message.id()?)
+        );
+        assert_eq!(
+            distributions[0].distributed_key_id(),
+            distributions[0].current_key_id()
+        );
+
+        let json = snapshot.to_json();
+        assert!(json.contains(r#""name": "+14151111112""#));
+        assert!(json.contains(&format!(
+            r#""identity_fingerprint": "{}""#,
+            snapshot.identity_fingerprint().expect("present")
+        )));
+        // No key material appears, redacted or otherwise.
+        assert!(!json.contains("redacted"));
+        let bob_identity = bob_store.get_identity_key_pair(None).await?;
+        assert!(!json.contains(&hex::encode(bob_identity.public_key().serialize())));
+
+        Ok(())
+    })
+}
@@ -0,0 +1,51 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::fmt;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Error {
+    /// A session ID that can't be used in a request path.
+    InvalidSessionId,
+    /// A master key of the wrong length.
+    InvalidMasterKeySize(usize),
+    /// The response body could not be parsed.
+    InvalidResponse(String),
+    /// The server refused the request until the given number of seconds has passed, if it said.
+    RateLimited { retry_after_seconds: Option<u64> },
+    /// The session has expired or never existed; a new one must be created.
+    SessionNotFound,
+    /// A verification code was submitted before one was sent, or after the session was verified.
+    NotReadyForVerification,
+    /// The server rejected the request with this HTTP status.
+    RequestRejected(u16),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidSessionId => write!(f, "invalid session ID"),
+            Error::InvalidMasterKeySize(size) => {
+                write!(f, "master key must be 32 bytes, not {}", size)
+            }
+            Error::InvalidResponse(message) => write!(f, "invalid response: {}", message),
+            Error::RateLimited {
+                retry_after_seconds: Some(seconds),
+            } => write!(f, "rate limited; retry after {} seconds", seconds),
+            Error::RateLimited {
+                retry_after_seconds: None,
+            } => write!(f, "rate limited"),
+            Error::SessionNotFound => write!(f, "registration session not found"),
+            Error::NotReadyForVerification => {
+                write!(f, "session is not ready for a verification code")
+            }
+            Error::RequestRejected(status) => write!(f, "request rejected with status {}", status),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,29 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! The client side of account registration.
+//!
+//! Registering goes through a verification session: the app creates a session for a phone number,
+//! answers any challenges the server asks for, requests a verification code, and submits it. Once
+//! the session is verified the account can be registered, which may require a registration lock
+//! token if the account has a PIN.
+//!
+//! This crate builds each [`RegistrationRequest`] and parses each response, leaving only the HTTP
+//! transport to the app. [`RegistrationSession::next_step`] says which request comes next.
+
+#![deny(clippy::unwrap_used)]
+
+mod error;
+mod lock;
+mod request;
+mod response;
+
+pub use error::{Error, Result};
+pub use lock::{registration_lock_token, MASTER_KEY_SIZE};
+pub use request::{AccountAttributes, PushToken, RegistrationRequest, VerificationTransport};
+pub use response::{
+    Challenge, NextStep, RegisteredAccount, RegistrationOutcome, RegistrationSession,
+    SvrCredentials,
+};
@@ -0,0 +1,25 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha256;
+
+use crate::{Error, Result};
+
+pub const MASTER_KEY_SIZE: usize = 32;
+
+/// Derives the registration lock token that proves knowledge of the account's master key.
+///
+/// The master key is the one restored from secure value recovery using the user's PIN. The token
+/// goes in [`AccountAttributes::registration_lock`](crate::AccountAttributes) when registering an
+/// account that is [locked](crate::RegistrationOutcome::Locked).
+pub fn registration_lock_token(master_key: &[u8]) -> Result<String> {
+    if master_key.len() != MASTER_KEY_SIZE {
+        return Err(Error::InvalidMasterKeySize(master_key.len()));
+    }
+    let mut mac = Hmac::<Sha256>::new_varkey(master_key).expect("HMAC accepts any key length");
+    mac.update(b"Registration Lock");
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
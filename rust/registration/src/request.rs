@@ -0,0 +1,179 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use serde_json::{json, Map, Value};
+
+use crate::{Error, Result};
+
+/// A push token the server can use to send a push challenge to this device.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PushToken {
+    Fcm(String),
+    Apn(String),
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum VerificationTransport {
+    Sms,
+    Voice,
+}
+
+/// The account attributes sent when registering.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AccountAttributes {
+    pub registration_id: u32,
+    /// True if the device polls for messages instead of receiving pushes.
+    pub fetches_messages: bool,
+    /// See [`registration_lock_token`](crate::registration_lock_token).
+    pub registration_lock: Option<String>,
+}
+
+/// An HTTP request for the app to send to the chat server.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RegistrationRequest {
+    method: &'static str,
+    path: String,
+    /// A JSON body, if the request has one.
+    body: Option<Vec<u8>>,
+}
+
+const SESSION_PATH: &str = "/v1/verification/session";
+
+/// Session IDs come from the server, but are checked before being put in a path anyway.
+fn session_path(session_id: &str) -> Result<String> {
+    let is_url_safe = |c: char| c.is_ascii_alphanumeric() || "-_=".contains(c);
+    if session_id.is_empty() || !session_id.chars().all(is_url_safe) {
+        return Err(Error::InvalidSessionId);
+    }
+    Ok(format!("{}/{}", SESSION_PATH, session_id))
+}
+
+fn insert_push_token(body: &mut Map<String, Value>, push_token: Option<&PushToken>) {
+    let (token, token_type) = match push_token {
+        Some(PushToken::Fcm(token)) => (token, "fcm"),
+        Some(PushToken::Apn(token)) => (token, "apn"),
+        None => return,
+    };
+    body.insert("pushToken".to_string(), json!(token));
+    body.insert("pushTokenType".to_string(), json!(token_type));
+}
+
+impl RegistrationRequest {
+    fn new(method: &'static str, path: String, body: Option<Value>) -> Self {
+        Self {
+            method,
+            path,
+            body: body.map(|body| body.to_string().into_bytes()),
+        }
+    }
+
+    /// Starts a verification session for `number`, in E.164 format.
+    pub fn create_session(
+        number: &str,
+        push_token: Option<&PushToken>,
+        mcc: Option<&str>,
+        mnc: Option<&str>,
+    ) -> Self {
+        let mut body = Map::new();
+        body.insert("number".to_string(), json!(number));
+        insert_push_token(&mut body, push_token);
+        if let Some(mcc) = mcc {
+            body.insert("mcc".to_string(), json!(mcc));
+        }
+        if let Some(mnc) = mnc {
+            body.insert("mnc".to_string(), json!(mnc));
+        }
+        Self::new("POST", SESSION_PATH.to_string(), Some(Value::Object(body)))
+    }
+
+    pub fn get_session(session_id: &str) -> Result<Self> {
+        Ok(Self::new("GET", session_path(session_id)?, None))
+    }
+
+    /// Answers the challenges in the session's
+    /// [`requested_information`](crate::RegistrationSession::requested_information).
+    pub fn update_session(
+        session_id: &str,
+        push_challenge: Option<&str>,
+        captcha: Option<&str>,
+    ) -> Result<Self> {
+        let mut body = Map::new();
+        if let Some(push_challenge) = push_challenge {
+            body.insert("pushChallenge".to_string(), json!(push_challenge));
+        }
+        if let Some(captcha) = captcha {
+            body.insert("captcha".to_string(), json!(captcha));
+        }
+        Ok(Self::new(
+            "PATCH",
+            session_path(session_id)?,
+            Some(Value::Object(body)),
+        ))
+    }
+
+    /// Asks the server to send a verification code; `client` names the app's platform.
+    pub fn request_verification_code(
+        session_id: &str,
+        transport: VerificationTransport,
+        client: &str,
+    ) -> Result<Self> {
+        let transport = match transport {
+            VerificationTransport::Sms => "sms",
+            VerificationTransport::Voice => "voice",
+        };
+        Ok(Self::new(
+            "POST",
+            format!("{}/code", session_path(session_id)?),
+            Some(json!({ "transport": transport, "client": client })),
+        ))
+    }
+
+    pub fn submit_verification_code(session_id: &str, code: &str) -> Result<Self> {
+        Ok(Self::new(
+            "PUT",
+            format!("{}/code", session_path(session_id)?),
+            Some(json!({ "code": code })),
+        ))
+    }
+
+    /// Registers an account using a verified session.
+    pub fn register_account(session_id: &str, attributes: &AccountAttributes) -> Result<Self> {
+        // Checked here too, even though the ID doesn't end up in the path.
+        session_path(session_id)?;
+        let mut account_attributes = Map::new();
+        account_attributes.insert(
+            "registrationId".to_string(),
+            json!(attributes.registration_id),
+        );
+        account_attributes.insert(
+            "fetchesMessages".to_string(),
+            json!(attributes.fetches_messages),
+        );
+        if let Some(registration_lock) = &attributes.registration_lock {
+            account_attributes.insert("registrationLock".to_string(), json!(registration_lock));
+        }
+        Ok(Self::new(
+            "POST",
+            "/v1/registration".to_string(),
+            Some(json!({
+                "sessionId": session_id,
+                "accountAttributes": account_attributes,
+                "skipDeviceTransfer": true,
+            })),
+        ))
+    }
+
+    pub fn method(&self) -> &str {
+        self.method
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn body(&self) -> Option<&[u8]> {
+        self.body.as_deref()
+    }
+}
@@ -0,0 +1,215 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::{Error, Result};
+
+/// Maps the error statuses shared by all registration endpoints.
+fn check_status(status: u16, retry_after: Option<&str>) -> Result<()> {
+    match status {
+        200..=299 => Ok(()),
+        404 => Err(Error::SessionNotFound),
+        409 => Err(Error::NotReadyForVerification),
+        429 => Err(Error::RateLimited {
+            retry_after_seconds: retry_after.and_then(|value| value.trim().parse().ok()),
+        }),
+        _ => Err(Error::RequestRejected(status)),
+    }
+}
+
+fn parse<T: DeserializeOwned>(body: &[u8]) -> Result<T> {
+    serde_json::from_slice(body).map_err(|e| Error::InvalidResponse(e.to_string()))
+}
+
+/// Something the server wants before it will send a verification code.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Challenge {
+    /// The challenge token sent to the session's push token.
+    PushChallenge,
+    Captcha,
+}
+
+/// What the app should do next with a session.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum NextStep {
+    /// Answer the challenges with
+    /// [`RegistrationRequest::update_session`](crate::RegistrationRequest::update_session).
+    SolveChallenges(Vec<Challenge>),
+    RequestVerificationCode,
+    SubmitVerificationCode,
+    RegisterAccount,
+    /// No request can succeed yet; the session's wait times say when to try again.
+    Wait,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionJson {
+    id: String,
+    next_sms: Option<u32>,
+    next_call: Option<u32>,
+    next_verification_attempt: Option<u32>,
+    allowed_to_request_code: bool,
+    #[serde(default)]
+    requested_information: Vec<String>,
+    verified: bool,
+}
+
+/// The server's view of a verification session, as of the last response.
+///
+/// Wait times are in seconds from when the response was received. A wait time of `None` means
+/// the action isn't available at all.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RegistrationSession {
+    id: String,
+    next_sms_seconds: Option<u32>,
+    next_call_seconds: Option<u32>,
+    next_verification_attempt_seconds: Option<u32>,
+    allowed_to_request_code: bool,
+    requested_information: Vec<Challenge>,
+    verified: bool,
+}
+
+impl RegistrationSession {
+    /// Parses the response to any of the session requests.
+    ///
+    /// `retry_after` is the value of the response's `Retry-After` header, if any.
+    pub fn from_response(status: u16, body: &[u8], retry_after: Option<&str>) -> Result<Self> {
+        check_status(status, retry_after)?;
+        let session: SessionJson = parse(body)?;
+        Ok(Self {
+            id: session.id,
+            next_sms_seconds: session.next_sms,
+            next_call_seconds: session.next_call,
+            next_verification_attempt_seconds: session.next_verification_attempt,
+            allowed_to_request_code: session.allowed_to_request_code,
+            // Challenges added by newer servers can't be answered, so they're left out.
+            requested_information: session
+                .requested_information
+                .iter()
+                .filter_map(|name| match name.as_str() {
+                    "pushChallenge" => Some(Challenge::PushChallenge),
+                    "captcha" => Some(Challenge::Captcha),
+                    _ => None,
+                })
+                .collect(),
+            verified: session.verified,
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn next_sms_seconds(&self) -> Option<u32> {
+        self.next_sms_seconds
+    }
+
+    pub fn next_call_seconds(&self) -> Option<u32> {
+        self.next_call_seconds
+    }
+
+    pub fn next_verification_attempt_seconds(&self) -> Option<u32> {
+        self.next_verification_attempt_seconds
+    }
+
+    pub fn allowed_to_request_code(&self) -> bool {
+        self.allowed_to_request_code
+    }
+
+    pub fn requested_information(&self) -> &[Challenge] {
+        &self.requested_information
+    }
+
+    pub fn verified(&self) -> bool {
+        self.verified
+    }
+
+    pub fn next_step(&self) -> NextStep {
+        if self.verified {
+            NextStep::RegisterAccount
+        } else if self.next_verification_attempt_seconds.is_some() {
+            NextStep::SubmitVerificationCode
+        } else if !self.requested_information.is_empty() {
+            NextStep::SolveChallenges(self.requested_information.clone())
+        } else if self.allowed_to_request_code {
+            NextStep::RequestVerificationCode
+        } else {
+            NextStep::Wait
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RegisteredAccount {
+    pub aci: String,
+    pub pni: String,
+    pub number: String,
+}
+
+/// Credentials for restoring the master key from secure value recovery.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SvrCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RegistrationOutcome {
+    Registered(RegisteredAccount),
+    /// The account has a registration lock. Registering again with the token derived from the
+    /// master key will succeed; after the time remaining the lock expires anyway.
+    Locked {
+        time_remaining_ms: u64,
+        svr_credentials: Option<SvrCredentials>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccountJson {
+    uuid: String,
+    pni: String,
+    number: String,
+}
+
+#[derive(Deserialize)]
+struct SvrCredentialsJson {
+    username: String,
+    password: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LockedJson {
+    time_remaining: u64,
+    svr2_credentials: Option<SvrCredentialsJson>,
+}
+
+impl RegistrationOutcome {
+    /// Parses the response to
+    /// [`RegistrationRequest::register_account`](crate::RegistrationRequest::register_account).
+    pub fn from_response(status: u16, body: &[u8], retry_after: Option<&str>) -> Result<Self> {
+        if status == 423 {
+            let locked: LockedJson = parse(body)?;
+            return Ok(RegistrationOutcome::Locked {
+                time_remaining_ms: locked.time_remaining,
+                svr_credentials: locked.svr2_credentials.map(|credentials| SvrCredentials {
+                    username: credentials.username,
+                    password: credentials.password,
+                }),
+            });
+        }
+        check_status(status, retry_after)?;
+        let account: AccountJson = parse(body)?;
+        Ok(RegistrationOutcome::Registered(RegisteredAccount {
+            aci: account.uuid,
+            pni: account.pni,
+            number: account.number,
+        }))
+    }
+}
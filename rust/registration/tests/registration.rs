@@ -0,0 +1,238 @@
+//
+// Copyright 2021 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use registration::*;
+use serde_json::{json, Value};
+
+fn body_json(request: &RegistrationRequest) -> Value {
+    serde_json::from_slice(request.body().expect("has a body")).expect("valid JSON")
+}
+
+#[test]
+fn session_requests() -> Result<()> {
+    let request = RegistrationRequest::create_session(
+        "+14155550100",
+        Some(&PushToken::Fcm("token".to_string())),
+        Some("310"),
+        None,
+    );
+    assert_eq!(request.method(), "POST");
+    assert_eq!(request.path(), "/v1/verification/session");
+    assert_eq!(
+        body_json(&request),
+        json!({
+            "number": "+14155550100",
+            "pushToken": "token",
+            "pushTokenType": "fcm",
+            "mcc": "310",
+        })
+    );
+
+    let request = RegistrationRequest::get_session("abc-123")?;
+    assert_eq!(request.method(), "GET");
+    assert_eq!(request.path(), "/v1/verification/session/abc-123");
+    assert_eq!(request.body(), None);
+
+    let request = RegistrationRequest::update_session("abc-123", None, Some("captcha-token"))?;
+    assert_eq!(request.method(), "PATCH");
+    assert_eq!(body_json(&request), json!({ "captcha": "captcha-token" }));
+
+    let request = RegistrationRequest::request_verification_code(
+        "abc-123",
+        VerificationTransport::Voice,
+        "ios",
+    )?;
+    assert_eq!(request.path(), "/v1/verification/session/abc-123/code");
+    assert_eq!(
+        body_json(&request),
+        json!({ "transport": "voice", "client": "ios" })
+    );
+
+    let request = RegistrationRequest::submit_verification_code("abc-123", "123456")?;
+    assert_eq!(request.method(), "PUT");
+    assert_eq!(body_json(&request), json!({ "code": "123456" }));
+    Ok(())
+}
+
+#[test]
+fn bad_session_ids_are_rejected() {
+    for id in &["", "../v1/accounts", "a/b", "a?b=c", "a b"] {
+        assert_eq!(
+            RegistrationRequest::get_session(id),
+            Err(Error::InvalidSessionId)
+        );
+        assert_eq!(
+            RegistrationRequest::submit_verification_code(id, "123456"),
+            Err(Error::InvalidSessionId)
+        );
+    }
+}
+
+#[test]
+fn register_request() -> Result<()> {
+    let attributes = AccountAttributes {
+        registration_id: 1234,
+        fetches_messages: false,
+        registration_lock: Some("lock".to_string()),
+    };
+    let request = RegistrationRequest::register_account("abc-123", &attributes)?;
+    assert_eq!(request.method(), "POST");
+    assert_eq!(request.path(), "/v1/registration");
+    assert_eq!(
+        body_json(&request),
+        json!({
+            "sessionId": "abc-123",
+            "accountAttributes": {
+                "registrationId": 1234,
+                "fetchesMessages": false,
+                "registrationLock": "lock",
+            },
+            "skipDeviceTransfer": true,
+        })
+    );
+    Ok(())
+}
+
+fn session_body(value: Value) -> Vec<u8> {
+    let mut session = json!({
+        "id": "abc-123",
+        "nextSms": null,
+        "nextCall": null,
+        "nextVerificationAttempt": null,
+        "allowedToRequestCode": false,
+        "requestedInformation": [],
+        "verified": false,
+    });
+    for (key, value) in value.as_object().expect("object") {
+        session[key] = value.clone();
+    }
+    session.to_string().into_bytes()
+}
+
+#[test]
+fn session_steps() -> Result<()> {
+    let session = RegistrationSession::from_response(
+        200,
+        &session_body(json!({ "requestedInformation": ["pushChallenge", "somethingNew"] })),
+        None,
+    )?;
+    assert_eq!(session.id(), "abc-123");
+    assert_eq!(session.requested_information(), &[Challenge::PushChallenge]);
+    assert_eq!(
+        session.next_step(),
+        NextStep::SolveChallenges(vec![Challenge::PushChallenge])
+    );
+
+    let session = RegistrationSession::from_response(
+        200,
+        &session_body(json!({ "allowedToRequestCode": true, "nextSms": 0, "nextCall": 60 })),
+        None,
+    )?;
+    assert_eq!(session.next_sms_seconds(), Some(0));
+    assert_eq!(session.next_call_seconds(), Some(60));
+    assert_eq!(session.next_step(), NextStep::RequestVerificationCode);
+
+    let session = RegistrationSession::from_response(
+        200,
+        &session_body(json!({ "nextVerificationAttempt": 0 })),
+        None,
+    )?;
+    assert_eq!(session.next_step(), NextStep::SubmitVerificationCode);
+
+    let session =
+        RegistrationSession::from_response(200, &session_body(json!({ "verified": true })), None)?;
+    assert!(session.verified());
+    assert_eq!(session.next_step(), NextStep::RegisterAccount);
+
+    let session = RegistrationSession::from_response(200, &session_body(json!({})), None)?;
+    assert_eq!(session.next_step(), NextStep::Wait);
+    Ok(())
+}
+
+#[test]
+fn session_errors() {
+    let body = session_body(json!({}));
+    assert_eq!(
+        RegistrationSession::from_response(429, &body, Some("30")),
+        Err(Error::RateLimited {
+            retry_after_seconds: Some(30)
+        })
+    );
+    assert_eq!(
+        RegistrationSession::from_response(429, &[], None),
+        Err(Error::RateLimited {
+            retry_after_seconds: None
+        })
+    );
+    assert_eq!(
+        RegistrationSession::from_response(404, &[], None),
+        Err(Error::SessionNotFound)
+    );
+    assert_eq!(
+        RegistrationSession::from_response(409, &body, None),
+        Err(Error::NotReadyForVerification)
+    );
+    assert_eq!(
+        RegistrationSession::from_response(400, &[], None),
+        Err(Error::RequestRejected(400))
+    );
+    assert!(matches!(
+        RegistrationSession::from_response(200, b"{}", None),
+        Err(Error::InvalidResponse(_))
+    ));
+}
+
+#[test]
+fn registration_outcomes() -> Result<()> {
+    let body = json!({
+        "uuid": "aci",
+        "pni": "pni",
+        "number": "+14155550100",
+        "storageCapable": true,
+    });
+    assert_eq!(
+        RegistrationOutcome::from_response(200, body.to_string().as_bytes(), None)?,
+        RegistrationOutcome::Registered(RegisteredAccount {
+            aci: "aci".to_string(),
+            pni: "pni".to_string(),
+            number: "+14155550100".to_string(),
+        })
+    );
+
+    let body = json!({
+        "timeRemaining": 86_400_000u64,
+        "svr2Credentials": { "username": "user", "password": "pass" },
+    });
+    assert_eq!(
+        RegistrationOutcome::from_response(423, body.to_string().as_bytes(), None)?,
+        RegistrationOutcome::Locked {
+            time_remaining_ms: 86_400_000,
+            svr_credentials: Some(SvrCredentials {
+                username: "user".to_string(),
+                password: "pass".to_string(),
+            }),
+        }
+    );
+
+    assert_eq!(
+        RegistrationOutcome::from_response(403, &[], None),
+        Err(Error::RequestRejected(403))
+    );
+    Ok(())
+}
+
+#[test]
+fn lock_token() -> Result<()> {
+    let master_key: Vec<u8> = (0..32).collect();
+    assert_eq!(
+        registration_lock_token(&master_key)?,
+        "0c5f865eea5eebe9ef4fa8c4d99cb91de301bebb5b3b91bf775da1a57c725975"
+    );
+    assert_eq!(
+        registration_lock_token(&master_key[1..]),
+        Err(Error::InvalidMasterKeySize(31))
+    );
+    Ok(())
+}
@@ -0,0 +1,46 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+import Foundation
+
+/// AES key wrap with padding (RFC 5649), for storing keys encrypted under a 256-bit master key.
+public class Aes256KeyWrap: ClonableHandleOwner {
+    public init<Bytes: ContiguousBytes>(key: Bytes) throws {
+        let handle: OpaquePointer? = try key.withUnsafeBytes {
+            var result: OpaquePointer?
+            try checkError(signal_aes256_key_wrap_new(&result, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count))
+            return result
+        }
+        super.init(owned: handle!)
+    }
+
+    internal override class func cloneNativeHandle(_ newHandle: inout OpaquePointer?, currentHandle: OpaquePointer?) -> SignalFfiErrorRef? {
+        return signal_aes256_key_wrap_clone(&newHandle, currentHandle)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_aes256_key_wrap_destroy(handle)
+    }
+
+    /// Wraps a non-empty key. The result is 8 to 15 bytes longer than the key.
+    public func wrap<Bytes: ContiguousBytes>(_ key: Bytes) throws -> [UInt8] {
+        return try key.withUnsafeBytes { keyBytes in
+            try invokeFnReturningArray {
+                signal_aes256_key_wrap_wrap($0, $1, nativeHandle, keyBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), keyBytes.count)
+            }
+        }
+    }
+
+    /// Throws `SignalError.invalidWrappedKey` if the data was wrapped under a different key or has
+    /// been modified.
+    public func unwrap<Bytes: ContiguousBytes>(_ wrapped: Bytes) throws -> [UInt8] {
+        return try wrapped.withUnsafeBytes { wrappedBytes in
+            try invokeFnReturningArray {
+                signal_aes256_key_wrap_unwrap($0, $1, nativeHandle, wrappedBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), wrappedBytes.count)
+            }
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+
+/// A change to identity, session, or sender key state that an app may want to keep a record of.
+public enum AuditEvent {
+    /// A new identity key was saved for `address`, replacing a different one.
+    case identityChanged(ProtocolAddress)
+    /// The current session with `address` was archived to make way for a new one.
+    case sessionArchived(ProtocolAddress)
+    /// A new sender key chain was created for sending to `groupId`.
+    case senderKeyCreated(groupId: String, sender: ProtocolAddress)
+    /// A sender key chain from `sender` for `groupId` was fast-forwarded, skipping some messages.
+    case senderKeyFastForwarded(groupId: String, sender: ProtocolAddress)
+}
+
+/// Receives every `AuditEvent` while installed with `setAuditHook(_:)`.
+///
+/// `record(_:)` may be called from any thread, and must not call back into the library.
+public protocol AuditHook: AnyObject {
+    func record(_ event: AuditEvent)
+}
+
+private func ffiShimRecordAuditEvent(ctx: UnsafeMutableRawPointer?,
+                                     kind: SignalAuditEventKind,
+                                     addressName: UnsafePointer<CChar>?,
+                                     deviceId: UInt32,
+                                     groupId: UnsafePointer<CChar>?) {
+    let hook = Unmanaged<AnyObject>.fromOpaque(ctx!).takeUnretainedValue() as! AuditHook
+    let address: ProtocolAddress
+    do {
+        address = try ProtocolAddress(name: String(cString: addressName!), deviceId: deviceId)
+    } catch {
+        return
+    }
+
+    switch kind {
+    case SignalAuditEventKind_IdentityChanged:
+        hook.record(.identityChanged(address))
+    case SignalAuditEventKind_SessionArchived:
+        hook.record(.sessionArchived(address))
+    case SignalAuditEventKind_SenderKeyCreated:
+        hook.record(.senderKeyCreated(groupId: String(cString: groupId!), sender: address))
+    case SignalAuditEventKind_SenderKeyFastForwarded:
+        hook.record(.senderKeyFastForwarded(groupId: String(cString: groupId!), sender: address))
+    default:
+        break
+    }
+}
+
+private func ffiShimDestroyAuditHook(ctx: UnsafeMutableRawPointer?) {
+    Unmanaged<AnyObject>.fromOpaque(ctx!).release()
+}
+
+/// Installs `hook` for the whole process, replacing any hook installed earlier.
+///
+/// The hook is kept alive until it is replaced or removed with `clearAuditHook()`.
+public func setAuditHook(_ hook: AuditHook) {
+    let ctx = Unmanaged<AnyObject>.passRetained(hook).toOpaque()
+    signal_set_audit_hook(SignalFfiAuditHook(ctx: ctx,
+                                             record: ffiShimRecordAuditEvent,
+                                             destroy: ffiShimDestroyAuditHook))
+}
+
+public func clearAuditHook() {
+    signal_clear_audit_hook()
+}
@@ -0,0 +1,57 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+
+/// Per-account state, for apps that use libsignal on behalf of several accounts at once.
+///
+/// While a context is entered on a thread (see `withEntered(_:)`), log output from that thread is
+/// prefixed with the context's label and filtered by its level, and traced operations are counted
+/// in its metrics.
+public class BridgeContext: ClonableHandleOwner {
+    public init(label: String) throws {
+        var handle: OpaquePointer?
+        try checkError(signal_bridge_context_new(&handle, label))
+        super.init(owned: handle!)
+    }
+
+    internal override class func cloneNativeHandle(_ newHandle: inout OpaquePointer?, currentHandle: OpaquePointer?) -> SignalFfiErrorRef? {
+        return signal_bridge_context_clone(&newHandle, currentHandle)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_bridge_context_destroy(handle)
+    }
+
+    /// Sets the most verbose level logged while this context is entered: 0 for none, then 1
+    /// (errors) through 5 (trace).
+    ///
+    /// This can only make logging quieter than the level passed to `signal_init_logger`.
+    public func setMaxLogLevel(_ level: UInt32) {
+        failOnError(signal_bridge_context_set_max_log_level(nativeHandle, level))
+    }
+
+    /// Runs `body` with this context entered on the current thread.
+    public func withEntered<Result>(_ body: () throws -> Result) throws -> Result {
+        try checkError(signal_bridge_context_enter(nativeHandle))
+        defer { failOnError(signal_bridge_context_exit(nativeHandle)) }
+        return try body()
+    }
+
+    /// One `name count=N total_us=T` line per traced operation run while this context was entered.
+    ///
+    /// Operations are only recorded once `signal_tracing_install_log_subscriber` has been called.
+    public var metrics: String {
+        return failOnError {
+            try invokeFnReturningString {
+                signal_bridge_context_get_metrics($0, nativeHandle)
+            }
+        }
+    }
+
+    public func resetMetrics() {
+        failOnError(signal_bridge_context_reset_metrics(nativeHandle))
+    }
+}
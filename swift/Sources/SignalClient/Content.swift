@@ -0,0 +1,145 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+import Foundation
+
+/// A reference to an encrypted attachment that has been uploaded to a CDN.
+public class AttachmentPointer: ClonableHandleOwner {
+    /// `key` is the attachment's 64-byte AES and HMAC key, and `digest` the SHA-256 of the uploaded
+    /// ciphertext; `size` is the length of the plaintext. `width`, `height`, and `uploadTimestamp`
+    /// are 0 if unknown.
+    public init<KeyBytes: ContiguousBytes, DigestBytes: ContiguousBytes>(cdnNumber: UInt32,
+                                                                      cdnKey: String,
+                                                                      contentType: String,
+                                                                      key: KeyBytes,
+                                                                      digest: DigestBytes,
+                                                                      size: UInt32,
+                                                                      fileName: String? = nil,
+                                                                      caption: String? = nil,
+                                                                      width: UInt32 = 0,
+                                                                      height: UInt32 = 0,
+                                                                      uploadTimestamp: UInt64 = 0) throws {
+        var result: OpaquePointer?
+        try key.withUnsafeBytes { keyBytes in
+            try digest.withUnsafeBytes { digestBytes in
+                try checkError(signal_attachment_pointer_new(&result,
+                                                             cdnNumber,
+                                                             cdnKey,
+                                                             contentType,
+                                                             keyBytes.baseAddress?.assumingMemoryBound(to: UInt8.self),
+                                                             keyBytes.count,
+                                                             digestBytes.baseAddress?.assumingMemoryBound(to: UInt8.self),
+                                                             digestBytes.count,
+                                                             size,
+                                                             fileName,
+                                                             caption,
+                                                             width,
+                                                             height,
+                                                             uploadTimestamp))
+            }
+        }
+        super.init(owned: result!)
+    }
+
+    internal override class func cloneNativeHandle(_ newHandle: inout OpaquePointer?, currentHandle: OpaquePointer?) -> SignalFfiErrorRef? {
+        return signal_attachment_pointer_clone(&newHandle, currentHandle)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_attachment_pointer_destroy(handle)
+    }
+}
+
+/// Assembles a data message, checking each part as it is added.
+public class DataMessageBuilder: ClonableHandleOwner {
+    public init(timestamp: UInt64) throws {
+        var result: OpaquePointer?
+        try checkError(signal_data_message_builder_new(&result, timestamp))
+        super.init(owned: result!)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_data_message_builder_destroy(handle)
+    }
+
+    /// Throws if `body` is longer than the limit for inline text.
+    public func setBody(_ body: String) throws {
+        try checkError(signal_data_message_builder_set_body(nativeHandle, body))
+    }
+
+    public func addAttachment(_ attachment: AttachmentPointer) throws {
+        try checkError(signal_data_message_builder_add_attachment(nativeHandle, attachment.nativeHandle))
+    }
+
+    /// Quotes the message sent at `id` (its timestamp) by `authorUuid`.
+    public func setQuote(id: UInt64, authorUuid: String, text: String?) throws {
+        try checkError(signal_data_message_builder_set_quote(nativeHandle, id, authorUuid, text))
+    }
+
+    /// The disappearing message timer, in seconds; 0 turns it off.
+    public func setExpireTimer(_ seconds: UInt32) {
+        failOnError(signal_data_message_builder_set_expire_timer(nativeHandle, seconds))
+    }
+
+    public func setProfileKey<Bytes: ContiguousBytes>(_ profileKey: Bytes) throws {
+        try profileKey.withUnsafeBytes {
+            try checkError(signal_data_message_builder_set_profile_key(nativeHandle, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count))
+        }
+    }
+
+    /// Throws if the message has neither a body nor any attachments.
+    public func build() throws -> Content {
+        var result: OpaquePointer?
+        try checkError(signal_data_message_builder_build(&result, nativeHandle))
+        return Content(owned: result!)
+    }
+}
+
+/// A message ready to be padded and encrypted, such as with
+/// `signalEncrypt(content:for:sessionStore:identityStore:padding:context:)`.
+public class Content: ClonableHandleOwner {
+    public enum ReceiptType: UInt32 {
+        case delivery = 0
+        case read = 1
+        case viewed = 2
+    }
+
+    /// Acknowledges the messages sent at each of `timestamps`.
+    public static func receipt(_ type: ReceiptType, timestamps: [UInt64]) throws -> Content {
+        let packed = timestamps.flatMap { withUnsafeBytes(of: $0.bigEndian, Array.init) }
+        var result: OpaquePointer?
+        try checkError(signal_content_new_receipt_message(&result, type.rawValue, packed, packed.count))
+        return Content(owned: result!)
+    }
+
+    /// A typing indicator, in the group with ID `groupId` if given.
+    public static func typing(timestamp: UInt64, started: Bool, groupId: [UInt8]? = nil) throws -> Content {
+        let groupId = groupId ?? []
+        var result: OpaquePointer?
+        try checkError(signal_content_new_typing_message(&result, timestamp, started, groupId, groupId.count))
+        return Content(owned: result!)
+    }
+
+    internal override init(owned handle: OpaquePointer) {
+        super.init(owned: handle)
+    }
+
+    internal override class func cloneNativeHandle(_ newHandle: inout OpaquePointer?, currentHandle: OpaquePointer?) -> SignalFfiErrorRef? {
+        return signal_content_clone(&newHandle, currentHandle)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_content_destroy(handle)
+    }
+
+    public func serialize() -> [UInt8] {
+        return failOnError {
+            try invokeFnReturningArray {
+                signal_content_get_serialized($0, $1, nativeHandle)
+            }
+        }
+    }
+}
@@ -23,6 +23,17 @@ public protocol IdentityKeyStore: AnyObject {
     func identity(for address: ProtocolAddress, context: StoreContext) throws -> IdentityKey?
 }
 
+/// An identity key store that can keep its private key out of process memory, e.g. in the Secure
+/// Enclave.
+///
+/// Session setup uses these methods instead of `identityKeyPair(context:)` when the store conforms
+/// to this protocol. Other operations still use the full key pair.
+public protocol IdentityKeyOpsStore: IdentityKeyStore {
+    func identityKey(context: StoreContext) throws -> IdentityKey
+    /// Returns the 32-byte X25519 agreement between the local identity private key and `theirKey`.
+    func calculateIdentityAgreement(with theirKey: PublicKey, context: StoreContext) throws -> [UInt8]
+}
+
 public protocol PreKeyStore: AnyObject {
     func loadPreKey(id: UInt32, context: StoreContext) throws -> PreKeyRecord
     func storePreKey(_ record: PreKeyRecord, id: UInt32, context: StoreContext) throws
@@ -43,3 +54,8 @@ public protocol SenderKeyStore: AnyObject {
     func storeSenderKey(name: SenderKeyName, record: SenderKeyRecord, context: StoreContext) throws
     func loadSenderKey(name: SenderKeyName, context: StoreContext) throws -> SenderKeyRecord?
 }
+
+/// Fetches a new sender certificate from the service when a `SenderCertificateProvider` needs one.
+public protocol SenderCertificateSource: AnyObject {
+    func fetchSenderCertificate(context: StoreContext) throws -> SenderCertificate
+}
@@ -0,0 +1,30 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+
+/// Turns the process-wide decryption counters on or off.
+///
+/// While counting is on, every decryption of an incoming message (1:1, sealed sender, or group) is
+/// counted by the class of its result, such as `success`, `untrusted_identity`, `no_session`, or
+/// `invalid_mac`. Nothing else about the message is recorded. Counts already recorded are kept
+/// when counting is turned off.
+public func setDecryptionMetricsEnabled(_ enabled: Bool) {
+    failOnError(signal_decryption_metrics_set_enabled(enabled))
+}
+
+/// One `name count=N` line per result class, including classes with no decryptions, in a fixed
+/// order.
+public var decryptionMetrics: String {
+    return failOnError {
+        try invokeFnReturningString {
+            signal_decryption_metrics_get($0)
+        }
+    }
+}
+
+public func resetDecryptionMetrics() {
+    failOnError(signal_decryption_metrics_reset())
+}
@@ -23,14 +23,17 @@ public struct DeviceTransferKey {
         return self.privateKey
     }
 
-    public func generateCertificate(_ name: String, _ daysTilExpire: Int) -> [UInt8] {
+    /// Generates a certificate valid from the day containing `now`, which defaults to the current
+    /// time according to the system clock.
+    public func generateCertificate(_ name: String, _ daysTilExpire: Int, now: Date = Date()) -> [UInt8] {
+        let nowMillis = UInt64(now.timeIntervalSince1970 * 1000)
         return privateKey.withUnsafeBytes { privateKeyBytes in
             failOnError {
                 try invokeFnReturningArray {
                     signal_device_transfer_generate_certificate($0, $1,
                                                                 privateKeyBytes.baseAddress?.assumingMemoryBound(to: UInt8.self),
                                                                 privateKeyBytes.count,
-                                                                name, UInt32(daysTilExpire))
+                                                                name, UInt32(daysTilExpire), nowMillis)
                 }
             }
         }
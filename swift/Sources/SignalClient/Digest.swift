@@ -0,0 +1,135 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+import Foundation
+
+/// A CRC-32 (IEEE) checksum over data supplied in pieces of any size.
+///
+/// This detects corruption, not tampering.
+public class Crc32: ClonableHandleOwner {
+    public init() {
+        var result: OpaquePointer?
+        failOnError(signal_crc32_new(&result))
+        super.init(owned: result!)
+    }
+
+    public static func digest<Bytes: ContiguousBytes>(_ data: Bytes) -> UInt32 {
+        return failOnError {
+            try data.withUnsafeBytes { dataBytes in
+                try invokeFnReturningInteger {
+                    signal_crc32_digest($0, dataBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), dataBytes.count)
+                }
+            }
+        }
+    }
+
+    internal override class func cloneNativeHandle(_ newHandle: inout OpaquePointer?, currentHandle: OpaquePointer?) -> SignalFfiErrorRef? {
+        return signal_crc32_clone(&newHandle, currentHandle)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_crc32_destroy(handle)
+    }
+
+    public func update<Bytes: ContiguousBytes>(_ data: Bytes) {
+        data.withUnsafeBytes {
+            failOnError(signal_crc32_update(nativeHandle, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count))
+        }
+    }
+
+    /// Returns the checksum of everything passed to `update(_:)`, and starts over.
+    public func finalize() -> UInt32 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_crc32_finalize($0, nativeHandle)
+            }
+        }
+    }
+}
+
+/// A SHA-256 digest over data supplied in pieces of any size.
+public class Sha256: ClonableHandleOwner {
+    public init() {
+        var result: OpaquePointer?
+        failOnError(signal_sha256_new(&result))
+        super.init(owned: result!)
+    }
+
+    public static func digest<Bytes: ContiguousBytes>(_ data: Bytes) -> [UInt8] {
+        return failOnError {
+            try data.withUnsafeBytes { dataBytes in
+                try invokeFnReturningArray {
+                    signal_sha256_digest($0, $1, dataBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), dataBytes.count)
+                }
+            }
+        }
+    }
+
+    internal override class func cloneNativeHandle(_ newHandle: inout OpaquePointer?, currentHandle: OpaquePointer?) -> SignalFfiErrorRef? {
+        return signal_sha256_clone(&newHandle, currentHandle)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_sha256_destroy(handle)
+    }
+
+    public func update<Bytes: ContiguousBytes>(_ data: Bytes) {
+        data.withUnsafeBytes {
+            failOnError(signal_sha256_update(nativeHandle, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count))
+        }
+    }
+
+    /// Returns the digest of everything passed to `update(_:)`, and starts over.
+    public func finalize() -> [UInt8] {
+        return failOnError {
+            try invokeFnReturningArray {
+                signal_sha256_finalize($0, $1, nativeHandle)
+            }
+        }
+    }
+}
+
+/// A SHA-512 digest over data supplied in pieces of any size.
+public class Sha512: ClonableHandleOwner {
+    public init() {
+        var result: OpaquePointer?
+        failOnError(signal_sha512_new(&result))
+        super.init(owned: result!)
+    }
+
+    public static func digest<Bytes: ContiguousBytes>(_ data: Bytes) -> [UInt8] {
+        return failOnError {
+            try data.withUnsafeBytes { dataBytes in
+                try invokeFnReturningArray {
+                    signal_sha512_digest($0, $1, dataBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), dataBytes.count)
+                }
+            }
+        }
+    }
+
+    internal override class func cloneNativeHandle(_ newHandle: inout OpaquePointer?, currentHandle: OpaquePointer?) -> SignalFfiErrorRef? {
+        return signal_sha512_clone(&newHandle, currentHandle)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_sha512_destroy(handle)
+    }
+
+    public func update<Bytes: ContiguousBytes>(_ data: Bytes) {
+        data.withUnsafeBytes {
+            failOnError(signal_sha512_update(nativeHandle, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count))
+        }
+    }
+
+    /// Returns the digest of everything passed to `update(_:)`, and starts over.
+    public func finalize() -> [UInt8] {
+        return failOnError {
+            try invokeFnReturningArray {
+                signal_sha512_finalize($0, $1, nativeHandle)
+            }
+        }
+    }
+}
@@ -17,6 +17,7 @@ public enum SignalError: Error {
     case invalidType(String)
     case invalidUtf8String(String)
     case insufficientOutputSize(String)
+    case bufferTooLarge(String)
     case protobufError(String)
     case invalidCiphertext(String)
     case legacyCiphertextVersion(String)
@@ -25,15 +26,26 @@ public enum SignalError: Error {
     case invalidMessage(String)
     case invalidKey(String)
     case invalidSignature(String)
+    case invalidWrappedKey(String)
     case fingerprintIdentifierMismatch(String)
     case fingerprintVersionMismatch(String)
     case fingerprintParsingError(String)
     case sealedSenderSelfSend(String)
+    case sealedSenderCertificateExpired(String)
+    case sealedSenderReplay(String)
     case untrustedIdentity(String)
     case invalidKeyIdentifier(String)
     case sessionNotFound(String)
+    case invalidRegistrationId(address: ProtocolAddress, message: String)
+    case invalidSenderKeySession(sender: ProtocolAddress, message: String)
     case duplicatedMessage(String)
     case callbackError(String)
+    case truncatedBackup(String)
+    case invalidBackup(String)
+    case keyTransparencyVerificationFailed(String)
+    case rateLimited(String)
+    case registrationSessionNotFound(String)
+    case registrationFailed(String)
     case unknown(UInt32, String)
 }
 
@@ -47,7 +59,7 @@ internal func checkError(_ error: SignalFfiErrorRef?) throws {
     let errStr = try! invokeFnReturningString {
         signal_error_get_message(error, $0)
     }
-    signal_error_free(error)
+    defer { signal_error_free(error) }
 
     switch SignalErrorCode(errType) {
     case SignalErrorCode_InvalidState:
@@ -64,6 +76,8 @@ internal func checkError(_ error: SignalFfiErrorRef?) throws {
         throw SignalError.invalidUtf8String(errStr)
     case SignalErrorCode_InsufficientOutputSize:
         throw SignalError.insufficientOutputSize(errStr)
+    case SignalErrorCode_BufferTooLarge:
+        throw SignalError.bufferTooLarge(errStr)
     case SignalErrorCode_ProtobufError:
         throw SignalError.protobufError(errStr)
     case SignalErrorCode_InvalidCiphertext:
@@ -80,10 +94,16 @@ internal func checkError(_ error: SignalFfiErrorRef?) throws {
         throw SignalError.fingerprintParsingError(errStr)
     case SignalErrorCode_SealedSenderSelfSend:
         throw SignalError.sealedSenderSelfSend(errStr)
+    case SignalErrorCode_SealedSenderCertificateExpired:
+        throw SignalError.sealedSenderCertificateExpired(errStr)
+    case SignalErrorCode_SealedSenderReplay:
+        throw SignalError.sealedSenderReplay(errStr)
     case SignalErrorCode_InvalidKey:
         throw SignalError.invalidKey(errStr)
     case SignalErrorCode_InvalidSignature:
         throw SignalError.invalidSignature(errStr)
+    case SignalErrorCode_InvalidWrappedKey:
+        throw SignalError.invalidWrappedKey(errStr)
     case SignalErrorCode_FingerprintIdentifierMismatch:
         throw SignalError.fingerprintIdentifierMismatch(errStr)
     case SignalErrorCode_FingerprintVersionMismatch:
@@ -94,15 +114,38 @@ internal func checkError(_ error: SignalFfiErrorRef?) throws {
         throw SignalError.invalidKeyIdentifier(errStr)
     case SignalErrorCode_SessionNotFound:
         throw SignalError.sessionNotFound(errStr)
+    case SignalErrorCode_InvalidRegistrationId:
+        throw SignalError.invalidRegistrationId(address: errorAddress(error), message: errStr)
+    case SignalErrorCode_InvalidSenderKeySession:
+        throw SignalError.invalidSenderKeySession(sender: errorAddress(error), message: errStr)
     case SignalErrorCode_DuplicatedMessage:
         throw SignalError.duplicatedMessage(errStr)
     case SignalErrorCode_CallbackError:
         throw SignalError.callbackError(errStr)
+    case SignalErrorCode_TruncatedBackup:
+        throw SignalError.truncatedBackup(errStr)
+    case SignalErrorCode_InvalidBackup:
+        throw SignalError.invalidBackup(errStr)
+    case SignalErrorCode_KeyTransparencyVerificationFailed:
+        throw SignalError.keyTransparencyVerificationFailed(errStr)
+    case SignalErrorCode_RateLimited:
+        throw SignalError.rateLimited(errStr)
+    case SignalErrorCode_RegistrationSessionNotFound:
+        throw SignalError.registrationSessionNotFound(errStr)
+    case SignalErrorCode_RegistrationFailed:
+        throw SignalError.registrationFailed(errStr)
     default:
         throw SignalError.unknown(errType, errStr)
     }
 }
 
+/// Extracts the address carried by `error`, which must be of a kind that has one.
+private func errorAddress(_ error: SignalFfiErrorRef) -> ProtocolAddress {
+    var handle: OpaquePointer?
+    failOnError(signal_error_get_address(error, &handle))
+    return ProtocolAddress(owned: handle!)
+}
+
 internal func failOnError(_ error: SignalFfiErrorRef?) {
     failOnError { try checkError(error) }
 }
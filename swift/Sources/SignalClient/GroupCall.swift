@@ -0,0 +1,89 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+import Foundation
+
+/// The secret shared by everyone with a call link.
+///
+/// Its text form (`description`) is what goes in the link itself.
+public class CallLinkRootKey: ClonableHandleOwner, CustomStringConvertible {
+    public static func generate() -> CallLinkRootKey {
+        var handle: OpaquePointer?
+        failOnError(signal_call_link_root_key_generate(&handle, nil))
+        return CallLinkRootKey(owned: handle!)
+    }
+
+    public init<Bytes: ContiguousBytes>(bytes: Bytes) throws {
+        let handle: OpaquePointer? = try bytes.withUnsafeBytes {
+            var result: OpaquePointer?
+            try checkError(signal_call_link_root_key_from_bytes(&result, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count))
+            return result
+        }
+        super.init(owned: handle!)
+    }
+
+    /// Throws `SignalError.invalidKey` if `text` isn't a root key in its text form.
+    public init(_ text: String) throws {
+        var handle: OpaquePointer?
+        try checkError(signal_call_link_root_key_parse(&handle, text))
+        super.init(owned: handle!)
+    }
+
+    internal override init(owned handle: OpaquePointer) {
+        super.init(owned: handle)
+    }
+
+    internal override class func cloneNativeHandle(_ newHandle: inout OpaquePointer?, currentHandle: OpaquePointer?) -> SignalFfiErrorRef? {
+        return signal_call_link_root_key_clone(&newHandle, currentHandle)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_call_link_root_key_destroy(handle)
+    }
+
+    public var bytes: [UInt8] {
+        return failOnError {
+            try invokeFnReturningArray {
+                signal_call_link_root_key_get_bytes($0, $1, nativeHandle)
+            }
+        }
+    }
+
+    public var description: String {
+        return failOnError {
+            try invokeFnReturningString {
+                signal_call_link_root_key_to_string($0, nativeHandle)
+            }
+        }
+    }
+
+    /// The ID the calling server uses for this call link's room.
+    public func deriveRoomId() -> [UInt8] {
+        return failOnError {
+            try invokeFnReturningArray {
+                signal_call_link_root_key_derive_room_id($0, $1, nativeHandle)
+            }
+        }
+    }
+
+    /// Derives the media key for one era of a call using this call link.
+    public func deriveEraKey(eraId: String) -> [UInt8] {
+        return failOnError {
+            try invokeFnReturningArray {
+                signal_call_link_root_key_derive_era_key($0, $1, nativeHandle, eraId)
+            }
+        }
+    }
+}
+
+/// Derives the media key for one era of a group call from the group's 32-byte master key.
+public func groupCallEraKey<MasterKey: ContiguousBytes>(groupMasterKey: MasterKey, eraId: String) throws -> [UInt8] {
+    return try groupMasterKey.withUnsafeBytes { masterKeyBytes in
+        try invokeFnReturningArray {
+            signal_group_call_derive_era_key($0, $1, masterKeyBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), masterKeyBytes.count, eraId)
+        }
+    }
+}
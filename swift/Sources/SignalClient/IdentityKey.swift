@@ -20,6 +20,18 @@ public struct IdentityKey: Equatable {
     public func serialize() -> [UInt8] {
         return publicKey.serialize()
     }
+
+    /// Checks that a payment address from a profile was signed by this identity key.
+    public func verifyPaymentAddress<AddressBytes, SignatureBytes>(_ address: AddressBytes, signature: SignatureBytes) throws -> Bool
+    where AddressBytes: ContiguousBytes, SignatureBytes: ContiguousBytes {
+        var result: Bool = false
+        try address.withUnsafeBytes { addressBytes in
+            try signature.withUnsafeBytes { signatureBytes in
+                try checkError(signal_payment_address_verify(&result, publicKey.nativeHandle, addressBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), addressBytes.count, signatureBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), signatureBytes.count))
+            }
+        }
+        return result
+    }
 }
 
 public struct IdentityKeyPair {
@@ -56,6 +68,17 @@ public struct IdentityKeyPair {
         }
     }
 
+    /// Signs a payment address to be published in this account's profile.
+    public func signPaymentAddress<Bytes: ContiguousBytes>(_ address: Bytes) -> [UInt8] {
+        return address.withUnsafeBytes { addressBytes in
+            failOnError {
+                try invokeFnReturningArray {
+                    signal_payment_address_sign($0, $1, publicKey.nativeHandle, privateKey.nativeHandle, addressBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), addressBytes.count, nil)
+                }
+            }
+        }
+    }
+
     public var identityKey: IdentityKey {
         return IdentityKey(publicKey: publicKey)
     }
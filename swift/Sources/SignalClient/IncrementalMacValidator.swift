@@ -0,0 +1,60 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+import Foundation
+
+/// Checks a download against its chunked HMAC-SHA256 digest as the data arrives.
+///
+/// Data may be passed to `update(_:)` in pieces of any size. Only the first `validatedLength`
+/// bytes are known to be authentic and safe to use; once validation fails, that is the offset of
+/// the chunk that failed.
+public class IncrementalMacValidator: ClonableHandleOwner {
+    public init<Key: ContiguousBytes, Digest: ContiguousBytes>(key: Key, chunkSize: UInt32, digest: Digest) throws {
+        let handle: OpaquePointer? = try key.withUnsafeBytes { keyBytes in
+            try digest.withUnsafeBytes { digestBytes in
+                var result: OpaquePointer?
+                try checkError(signal_incremental_mac_validator_new(&result,
+                                                                    keyBytes.baseAddress?.assumingMemoryBound(to: UInt8.self),
+                                                                    keyBytes.count,
+                                                                    chunkSize,
+                                                                    digestBytes.baseAddress?.assumingMemoryBound(to: UInt8.self),
+                                                                    digestBytes.count))
+                return result
+            }
+        }
+        super.init(owned: handle!)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_incremental_mac_validator_destroy(handle)
+    }
+
+    /// Returns false as soon as a completed chunk doesn't match the digest.
+    ///
+    /// Throws `SignalError.invalidState` if validation has already failed or been finalized.
+    public func update<Bytes: ContiguousBytes>(_ data: Bytes) throws -> Bool {
+        var result: Bool = false
+        try data.withUnsafeBytes {
+            try checkError(signal_incremental_mac_validator_update(&result, nativeHandle, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count))
+        }
+        return result
+    }
+
+    /// Returns false if the data was truncated or its final chunk doesn't match the digest.
+    public func finalize() throws -> Bool {
+        var result: Bool = false
+        try checkError(signal_incremental_mac_validator_finalize(&result, nativeHandle))
+        return result
+    }
+
+    public var validatedLength: UInt64 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_incremental_mac_validator_get_validated_length($0, nativeHandle)
+            }
+        }
+    }
+}
@@ -29,3 +29,130 @@ where InputBytes: ContiguousBytes, SaltBytes: ContiguousBytes, InfoBytes: Contig
 
     return output
 }
+
+/// Parameters for Argon2id (version 1.3), for deriving keys from PINs and other low-entropy secrets.
+///
+/// A key can only be derived again with exactly the same parameters, so store them alongside the salt.
+public class Argon2idParams: ClonableHandleOwner {
+    public convenience init(memoryKib: UInt32, iterations: UInt32, parallelism: UInt32) throws {
+        var result: OpaquePointer?
+        try checkError(signal_argon2id_params_new(&result, memoryKib, iterations, parallelism))
+        self.init(owned: result!)
+    }
+
+    /// Runs the KDF once to pick the iterations that take about `targetDurationMs` on this device.
+    public static func calibrate(targetDurationMs: UInt32, memoryKib: UInt32, parallelism: UInt32) throws -> Argon2idParams {
+        var result: OpaquePointer?
+        try checkError(signal_argon2id_params_calibrate(&result, targetDurationMs, memoryKib, parallelism))
+        return Argon2idParams(owned: result!)
+    }
+
+    internal override class func cloneNativeHandle(_ newHandle: inout OpaquePointer?, currentHandle: OpaquePointer?) -> SignalFfiErrorRef? {
+        return signal_argon2id_params_clone(&newHandle, currentHandle)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_argon2id_params_destroy(handle)
+    }
+
+    public var memoryKib: UInt32 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_argon2id_params_get_memory_kib($0, nativeHandle)
+            }
+        }
+    }
+
+    public var iterations: UInt32 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_argon2id_params_get_iterations($0, nativeHandle)
+            }
+        }
+    }
+
+    public var parallelism: UInt32 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_argon2id_params_get_parallelism($0, nativeHandle)
+            }
+        }
+    }
+
+    public func deriveKey<Password: ContiguousBytes, Salt: ContiguousBytes>(password: Password, salt: Salt, outputLength: UInt32) throws -> [UInt8] {
+        return try password.withUnsafeBytes { passwordBytes in
+            try salt.withUnsafeBytes { saltBytes in
+                try invokeFnReturningArray {
+                    signal_argon2id_params_derive($0, $1, nativeHandle,
+                                                  passwordBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), passwordBytes.count,
+                                                  saltBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), saltBytes.count,
+                                                  outputLength)
+                }
+            }
+        }
+    }
+}
+
+/// Parameters for scrypt, for deriving keys from PINs and other low-entropy secrets.
+///
+/// A key can only be derived again with exactly the same parameters, so store them alongside the salt.
+public class ScryptParams: ClonableHandleOwner {
+    /// Creates parameters for a cost of `N = 2^logN`, block size `r`, and parallelism `p`.
+    public convenience init(logN: UInt8, r: UInt32, p: UInt32) throws {
+        var result: OpaquePointer?
+        try checkError(signal_scrypt_params_new(&result, logN, r, p))
+        self.init(owned: result!)
+    }
+
+    /// Runs the KDF once to pick the cost that takes about `targetDurationMs` on this device.
+    public static func calibrate(targetDurationMs: UInt32, r: UInt32, p: UInt32) throws -> ScryptParams {
+        var result: OpaquePointer?
+        try checkError(signal_scrypt_params_calibrate(&result, targetDurationMs, r, p))
+        return ScryptParams(owned: result!)
+    }
+
+    internal override class func cloneNativeHandle(_ newHandle: inout OpaquePointer?, currentHandle: OpaquePointer?) -> SignalFfiErrorRef? {
+        return signal_scrypt_params_clone(&newHandle, currentHandle)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_scrypt_params_destroy(handle)
+    }
+
+    public var logN: UInt8 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_scrypt_params_get_log_n($0, nativeHandle)
+            }
+        }
+    }
+
+    public var r: UInt32 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_scrypt_params_get_r($0, nativeHandle)
+            }
+        }
+    }
+
+    public var p: UInt32 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_scrypt_params_get_p($0, nativeHandle)
+            }
+        }
+    }
+
+    public func deriveKey<Password: ContiguousBytes, Salt: ContiguousBytes>(password: Password, salt: Salt, outputLength: UInt32) throws -> [UInt8] {
+        return try password.withUnsafeBytes { passwordBytes in
+            try salt.withUnsafeBytes { saltBytes in
+                try invokeFnReturningArray {
+                    signal_scrypt_params_derive($0, $1, nativeHandle,
+                                                passwordBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), passwordBytes.count,
+                                                saltBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), saltBytes.count,
+                                                outputLength)
+                }
+            }
+        }
+    }
+}
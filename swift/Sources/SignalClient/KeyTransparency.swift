@@ -0,0 +1,151 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+import Foundation
+
+public class KeyTransparencySearchResult: ClonableHandleOwner {
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_key_transparency_search_result_destroy(handle)
+    }
+
+    public var value: [UInt8] {
+        return failOnError {
+            try invokeFnReturningArray {
+                signal_key_transparency_search_result_get_value($0, $1, nativeHandle)
+            }
+        }
+    }
+
+    /// The tree head to save and pass to the next verification.
+    public var treeHead: [UInt8] {
+        return failOnError {
+            try invokeFnReturningArray {
+                signal_key_transparency_search_result_get_tree_head($0, $1, nativeHandle)
+            }
+        }
+    }
+
+    /// The state to save and pass to `keyTransparencyVerifyMonitor` for this search key.
+    public var monitoringData: [UInt8] {
+        return failOnError {
+            try invokeFnReturningArray {
+                signal_key_transparency_search_result_get_monitoring_data($0, $1, nativeHandle)
+            }
+        }
+    }
+}
+
+public class KeyTransparencyMonitorResult: ClonableHandleOwner {
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_key_transparency_monitor_result_destroy(handle)
+    }
+
+    /// If false, the search key's value has changed and it should be searched for again.
+    public var isUnchanged: Bool {
+        var result: Bool = false
+        failOnError(signal_key_transparency_monitor_result_is_unchanged(&result, nativeHandle))
+        return result
+    }
+
+    public var treeHead: [UInt8] {
+        return failOnError {
+            try invokeFnReturningArray {
+                signal_key_transparency_monitor_result_get_tree_head($0, $1, nativeHandle)
+            }
+        }
+    }
+
+    public var monitoringData: [UInt8] {
+        return failOnError {
+            try invokeFnReturningArray {
+                signal_key_transparency_monitor_result_get_monitoring_data($0, $1, nativeHandle)
+            }
+        }
+    }
+}
+
+private func withOptionalBytes<Result>(_ bytes: [UInt8]?, _ body: (UnsafePointer<UInt8>?, Int) throws -> Result) rethrows -> Result {
+    guard let bytes = bytes else {
+        return try body(nil, 0)
+    }
+    return try bytes.withUnsafeBufferPointer { try body($0.baseAddress, $0.count) }
+}
+
+/// Verifies a key transparency log's response to a search for `searchKey`.
+///
+/// `lastTreeHead` is the tree head saved from the previous verification, if any. Throws
+/// `SignalError.keyTransparencyVerificationFailed` if the response doesn't check out.
+public func keyTransparencyVerifySearch<SearchKey: ContiguousBytes, Response: ContiguousBytes>(
+    logKey: PublicKey,
+    searchKey: SearchKey,
+    response: Response,
+    lastTreeHead: [UInt8]?
+) throws -> KeyTransparencySearchResult {
+    var result: OpaquePointer?
+    try searchKey.withUnsafeBytes { searchKeyBytes in
+        try response.withUnsafeBytes { responseBytes in
+            try withOptionalBytes(lastTreeHead) { lastTreeHead, lastTreeHeadLen in
+                try checkError(signal_key_transparency_verify_search(&result,
+                                                                     logKey.nativeHandle,
+                                                                     searchKeyBytes.baseAddress?.assumingMemoryBound(to: UInt8.self),
+                                                                     searchKeyBytes.count,
+                                                                     responseBytes.baseAddress?.assumingMemoryBound(to: UInt8.self),
+                                                                     responseBytes.count,
+                                                                     lastTreeHead,
+                                                                     lastTreeHeadLen))
+            }
+        }
+    }
+    return KeyTransparencySearchResult(owned: result!)
+}
+
+public func keyTransparencyVerifyMonitor<SearchKey: ContiguousBytes, Response: ContiguousBytes>(
+    logKey: PublicKey,
+    searchKey: SearchKey,
+    response: Response,
+    lastTreeHead: [UInt8]?,
+    monitoringData: [UInt8]
+) throws -> KeyTransparencyMonitorResult {
+    var result: OpaquePointer?
+    try searchKey.withUnsafeBytes { searchKeyBytes in
+        try response.withUnsafeBytes { responseBytes in
+            try withOptionalBytes(lastTreeHead) { lastTreeHead, lastTreeHeadLen in
+                try checkError(signal_key_transparency_verify_monitor(&result,
+                                                                      logKey.nativeHandle,
+                                                                      searchKeyBytes.baseAddress?.assumingMemoryBound(to: UInt8.self),
+                                                                      searchKeyBytes.count,
+                                                                      responseBytes.baseAddress?.assumingMemoryBound(to: UInt8.self),
+                                                                      responseBytes.count,
+                                                                      lastTreeHead,
+                                                                      lastTreeHeadLen,
+                                                                      monitoringData,
+                                                                      monitoringData.count))
+            }
+        }
+    }
+    return KeyTransparencyMonitorResult(owned: result!)
+}
+
+/// Returns the new tree head to save.
+public func keyTransparencyVerifyDistinguished<Response: ContiguousBytes>(
+    logKey: PublicKey,
+    response: Response,
+    lastTreeHead: [UInt8]?
+) throws -> [UInt8] {
+    return try response.withUnsafeBytes { responseBytes in
+        try withOptionalBytes(lastTreeHead) { lastTreeHead, lastTreeHeadLen in
+            try invokeFnReturningArray {
+                signal_key_transparency_verify_distinguished($0,
+                                                             $1,
+                                                             logKey.nativeHandle,
+                                                             responseBytes.baseAddress?.assumingMemoryBound(to: UInt8.self),
+                                                             responseBytes.count,
+                                                             lastTreeHead,
+                                                             lastTreeHeadLen)
+            }
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+
+/// The largest byte buffer, in bytes, that libsignal will accept as an argument.
+///
+/// Longer buffers are rejected with `SignalError.bufferTooLarge` before they are read.
+/// The limit applies process-wide; the default is 256 MiB.
+public var maxBufferLength: UInt64 {
+    get {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_get_max_buffer_length($0)
+            }
+        }
+    }
+    set {
+        failOnError(signal_set_max_buffer_length(newValue))
+    }
+}
@@ -0,0 +1,155 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+import Foundation
+
+/// Encrypts frames into a message backup stream.
+///
+/// Each call returns the bytes to append to the stream; the first one also carries the stream
+/// header. The stream is incomplete until the output of `finish()` has been written.
+public class BackupFrameWriter: ClonableHandleOwner {
+    public init<Bytes: ContiguousBytes>(backupKey: Bytes, compressed: Bool) throws {
+        let handle: OpaquePointer? = try backupKey.withUnsafeBytes {
+            var result: OpaquePointer?
+            try checkError(signal_frame_writer_new(&result, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count, compressed, nil))
+            return result
+        }
+        super.init(owned: handle!)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_frame_writer_destroy(handle)
+    }
+
+    public func append<Bytes: ContiguousBytes>(_ frame: Bytes) throws -> [UInt8] {
+        return try frame.withUnsafeBytes { frameBytes in
+            try invokeFnReturningArray {
+                signal_frame_writer_append($0, $1, nativeHandle, frameBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), frameBytes.count)
+            }
+        }
+    }
+
+    public func finish() throws -> [UInt8] {
+        return try invokeFnReturningArray {
+            signal_frame_writer_finish($0, $1, nativeHandle)
+        }
+    }
+}
+
+/// Decrypts frames from a message backup stream that arrives in chunks of any size.
+///
+/// Once a frame fails to decrypt, every later call throws the same error.
+public class BackupFrameReader: ClonableHandleOwner {
+    public init<Bytes: ContiguousBytes>(backupKey: Bytes) throws {
+        let handle: OpaquePointer? = try backupKey.withUnsafeBytes {
+            var result: OpaquePointer?
+            try checkError(signal_frame_reader_new(&result, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count))
+            return result
+        }
+        super.init(owned: handle!)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_frame_reader_destroy(handle)
+    }
+
+    public func push<Bytes: ContiguousBytes>(_ chunk: Bytes) throws {
+        try chunk.withUnsafeBytes {
+            try checkError(signal_frame_reader_push(nativeHandle, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count))
+        }
+    }
+
+    /// Returns the next complete frame, or `nil` if more input is needed or the stream has ended.
+    public func nextFrame() throws -> [UInt8]? {
+        return try invokeFnReturningOptionalArray {
+            signal_frame_reader_next_frame($0, $1, nativeHandle)
+        }
+    }
+
+    public var isFinished: Bool {
+        var result: Bool = false
+        failOnError(signal_frame_reader_is_finished(&result, nativeHandle))
+        return result
+    }
+
+    /// Throws `SignalError.truncatedBackup` if the stream ended early.
+    public func finish() throws {
+        try checkError(signal_frame_reader_finish(nativeHandle))
+    }
+}
+
+/// Produces a complete, encrypted message backup from structured records.
+///
+/// Records must be added in dependency order: the account data first, then each recipient before
+/// any chat with it, and each chat before its items. Each call returns the bytes to append to the
+/// backup; the backup is complete once the output of `finish()` has been written.
+///
+/// A record that is malformed or out of order is rejected with `SignalError.invalidArgument`
+/// without affecting the backup, so the caller may skip it and carry on.
+public class BackupBuilder: ClonableHandleOwner {
+    public init<Bytes: ContiguousBytes>(backupKey: Bytes, compressed: Bool, backupTime: Date) throws {
+        let backupTimeMs = UInt64(backupTime.timeIntervalSince1970 * 1000)
+        let handle: OpaquePointer? = try backupKey.withUnsafeBytes {
+            var result: OpaquePointer?
+            try checkError(signal_backup_builder_new(&result, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count, compressed, backupTimeMs, nil))
+            return result
+        }
+        super.init(owned: handle!)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_backup_builder_destroy(handle)
+    }
+
+    public func addAccountData<Bytes: ContiguousBytes>(profileKey: Bytes, username: String?, givenName: String, familyName: String) throws -> [UInt8] {
+        return try profileKey.withUnsafeBytes { profileKeyBytes in
+            try invokeFnReturningArray {
+                signal_backup_builder_add_account_data($0, $1, nativeHandle, profileKeyBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), profileKeyBytes.count, username, givenName, familyName)
+            }
+        }
+    }
+
+    /// Adds the recipient representing the account the backup belongs to.
+    public func addSelfRecipient(id: UInt64) throws -> [UInt8] {
+        return try invokeFnReturningArray {
+            signal_backup_builder_add_self_recipient($0, $1, nativeHandle, id)
+        }
+    }
+
+    /// At least one of `uuid` and `e164` must be present.
+    public func addContact(id: UInt64, uuid: UUID?, e164: String?) throws -> [UInt8] {
+        return try invokeFnReturningArray {
+            signal_backup_builder_add_contact($0, $1, nativeHandle, id, uuid?.uuidString.lowercased(), e164)
+        }
+    }
+
+    public func addGroup<Bytes: ContiguousBytes>(id: UInt64, masterKey: Bytes) throws -> [UInt8] {
+        return try masterKey.withUnsafeBytes { masterKeyBytes in
+            try invokeFnReturningArray {
+                signal_backup_builder_add_group($0, $1, nativeHandle, id, masterKeyBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), masterKeyBytes.count)
+            }
+        }
+    }
+
+    /// `pinnedOrder` is 0 if the chat is not pinned, and `expirationTimerMs` is 0 if messages don't expire.
+    public func addChat(id: UInt64, recipientId: UInt64, archived: Bool = false, pinnedOrder: UInt32 = 0, expirationTimerMs: UInt64 = 0) throws -> [UInt8] {
+        return try invokeFnReturningArray {
+            signal_backup_builder_add_chat($0, $1, nativeHandle, id, recipientId, archived, pinnedOrder, expirationTimerMs)
+        }
+    }
+
+    public func addChatItem(chatId: UInt64, authorId: UInt64, dateSent: UInt64, text: String) throws -> [UInt8] {
+        return try invokeFnReturningArray {
+            signal_backup_builder_add_chat_item($0, $1, nativeHandle, chatId, authorId, dateSent, text)
+        }
+    }
+
+    public func finish() throws -> [UInt8] {
+        return try invokeFnReturningArray {
+            signal_backup_builder_finish($0, $1, nativeHandle)
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+import Foundation
+
+/// The result of a decryption that only read from the stores.
+public struct DeferredDecryptionResult {
+    public var plaintext: [UInt8]
+    /// The store writes the decryption would have made, to be replayed with
+    /// `applyMutationLog(_:sessionStore:identityStore:preKeyStore:signedPreKeyStore:senderKeyStore:context:)`
+    /// by a process that can write to the stores.
+    public var mutationLog: [UInt8]
+
+    fileprivate init(consuming handle: OpaquePointer?) throws {
+        defer {
+            failOnError(signal_deferred_decryption_result_destroy(handle))
+        }
+        plaintext = try invokeFnReturningArray {
+            signal_deferred_decryption_result_get_plaintext($0, $1, handle)
+        }
+        mutationLog = try invokeFnReturningArray {
+            signal_deferred_decryption_result_get_mutation_log($0, $1, handle)
+        }
+    }
+}
+
+/// Like `signalDecrypt(message:from:sessionStore:identityStore:context:)`, but never writes to the
+/// stores.
+public func signalDecryptDeferred(message: SignalMessage,
+                                  from address: ProtocolAddress,
+                                  sessionStore: SessionStore,
+                                  identityStore: IdentityKeyStore,
+                                  context: StoreContext) throws -> DeferredDecryptionResult {
+    var handle: OpaquePointer?
+    try context.withOpaquePointer { context in
+        try withSessionStore(sessionStore) { ffiSessionStore in
+            try withIdentityKeyStore(identityStore) { ffiIdentityStore in
+                try checkError(signal_decrypt_message_deferred(&handle, message.nativeHandle, address.nativeHandle, ffiSessionStore, ffiIdentityStore, nil, context))
+            }
+        }
+    }
+    return try DeferredDecryptionResult(consuming: handle)
+}
+
+/// Like `signalDecryptPreKey(message:from:sessionStore:identityStore:preKeyStore:signedPreKeyStore:context:)`,
+/// but never writes to the stores.
+public func signalDecryptPreKeyDeferred(message: PreKeySignalMessage,
+                                        from address: ProtocolAddress,
+                                        sessionStore: SessionStore,
+                                        identityStore: IdentityKeyStore,
+                                        preKeyStore: PreKeyStore,
+                                        signedPreKeyStore: SignedPreKeyStore,
+                                        context: StoreContext) throws -> DeferredDecryptionResult {
+    var handle: OpaquePointer?
+    try context.withOpaquePointer { context in
+        try withSessionStore(sessionStore) { ffiSessionStore in
+            try withIdentityKeyStore(identityStore) { ffiIdentityStore in
+                try withPreKeyStore(preKeyStore) { ffiPreKeyStore in
+                    try withSignedPreKeyStore(signedPreKeyStore) { ffiSignedPreKeyStore in
+                        try checkError(signal_decrypt_pre_key_message_deferred(&handle, message.nativeHandle, address.nativeHandle, ffiSessionStore, ffiIdentityStore, ffiPreKeyStore, ffiSignedPreKeyStore, nil, context))
+                    }
+                }
+            }
+        }
+    }
+    return try DeferredDecryptionResult(consuming: handle)
+}
+
+/// Like `groupDecrypt(groupId:message:store:context:)`, but never writes to the store.
+public func groupDecryptDeferred<Bytes: ContiguousBytes>(groupId: SenderKeyName,
+                                                         message: Bytes,
+                                                         store: SenderKeyStore,
+                                                         context: StoreContext) throws -> DeferredDecryptionResult {
+    var handle: OpaquePointer?
+    try context.withOpaquePointer { context in
+        try message.withUnsafeBytes { messageBytes in
+            try withSenderKeyStore(store) { ffiStore in
+                try checkError(signal_group_decrypt_message_deferred(&handle, groupId.nativeHandle, messageBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), messageBytes.count, ffiStore, context))
+            }
+        }
+    }
+    return try DeferredDecryptionResult(consuming: handle)
+}
+
+/// Makes the store writes recorded in a `DeferredDecryptionResult`'s `mutationLog`, in order.
+///
+/// Throws `SignalError.invalidState` without writing anything if a session or sender key the log
+/// replaces has changed since it was recorded; the message should then be decrypted again.
+public func applyMutationLog<Bytes: ContiguousBytes>(_ mutationLog: Bytes,
+                                                     sessionStore: SessionStore,
+                                                     identityStore: IdentityKeyStore,
+                                                     preKeyStore: PreKeyStore,
+                                                     signedPreKeyStore: SignedPreKeyStore,
+                                                     senderKeyStore: SenderKeyStore,
+                                                     context: StoreContext) throws {
+    try mutationLog.withUnsafeBytes { mutationLogBytes in
+        try context.withOpaquePointer { context in
+            try withSessionStore(sessionStore) { ffiSessionStore in
+                try withIdentityKeyStore(identityStore) { ffiIdentityStore in
+                    try withPreKeyStore(preKeyStore) { ffiPreKeyStore in
+                        try withSignedPreKeyStore(signedPreKeyStore) { ffiSignedPreKeyStore in
+                            try withSenderKeyStore(senderKeyStore) { ffiSenderKeyStore in
+                                try checkError(signal_mutation_log_apply(
+                                    mutationLogBytes.baseAddress?.assumingMemoryBound(to: UInt8.self),
+                                    mutationLogBytes.count,
+                                    ffiSessionStore,
+                                    ffiIdentityStore,
+                                    ffiPreKeyStore,
+                                    ffiSignedPreKeyStore,
+                                    ffiSenderKeyStore,
+                                    context))
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
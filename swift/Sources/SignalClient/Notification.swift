@@ -0,0 +1,109 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+import Foundation
+
+public struct NotificationDecryptionResult {
+    public var message: [UInt8]
+    public var sender: SealedSenderAddress
+    /// The store changes a normal decryption would have made, to be passed to
+    /// `applyNotificationCommitSet(_:sessionStore:identityStore:preKeyStore:context:)` by the main
+    /// app.
+    public var commitSet: [UInt8]
+}
+
+/// Decrypts a sealed sender message like `sealedSenderDecrypt`, but never writes to the stores.
+///
+/// Meant for notification service extensions, which may not be able to write to the app's
+/// database. The store changes are returned in the result's `commitSet` instead.
+public func notificationDecrypt<Bytes: ContiguousBytes>(message: Bytes,
+                                                        from localAddress: SealedSenderAddress,
+                                                        trustRoot: PublicKey,
+                                                        timestamp: UInt64,
+                                                        sessionStore: SessionStore,
+                                                        identityStore: IdentityKeyStore,
+                                                        preKeyStore: PreKeyStore,
+                                                        signedPreKeyStore: SignedPreKeyStore,
+                                                        context: StoreContext) throws -> NotificationDecryptionResult {
+    var handle: OpaquePointer?
+    try message.withUnsafeBytes { messageBytes in
+        try context.withOpaquePointer { context in
+            try withSessionStore(sessionStore) { ffiSessionStore in
+                try withIdentityKeyStore(identityStore) { ffiIdentityStore in
+                    try withPreKeyStore(preKeyStore) { ffiPreKeyStore in
+                        try withSignedPreKeyStore(signedPreKeyStore) { ffiSignedPreKeyStore in
+                            try checkError(signal_notification_decrypt(
+                                &handle,
+                                messageBytes.baseAddress?.assumingMemoryBound(to: UInt8.self),
+                                messageBytes.count,
+                                trustRoot.nativeHandle,
+                                timestamp,
+                                localAddress.e164,
+                                localAddress.uuidString,
+                                localAddress.deviceId,
+                                ffiSessionStore,
+                                ffiIdentityStore,
+                                ffiPreKeyStore,
+                                ffiSignedPreKeyStore,
+                                nil,
+                                context))
+                        }
+                    }
+                }
+            }
+        }
+    }
+    defer {
+        failOnError(signal_notification_decryption_result_destroy(handle))
+    }
+
+    let sender = try SealedSenderAddress(
+        e164: try invokeFnReturningOptionalString {
+            signal_notification_decryption_result_get_sender_e164($0, handle)
+        },
+        uuidString: try invokeFnReturningString {
+            signal_notification_decryption_result_get_sender_uuid($0, handle)
+        },
+        deviceId: try invokeFnReturningInteger {
+            signal_notification_decryption_result_get_device_id($0, handle)
+        })
+    return NotificationDecryptionResult(
+        message: try invokeFnReturningArray {
+            signal_notification_decryption_result_get_message($0, $1, handle)
+        },
+        sender: sender,
+        commitSet: try invokeFnReturningArray {
+            signal_notification_decryption_result_get_commit_set($0, $1, handle)
+        })
+}
+
+/// Makes the store changes deferred by `notificationDecrypt`.
+///
+/// Throws `SignalError.invalidState` without changing anything if one of the affected sessions has
+/// changed since the notification was decrypted; the message should then be decrypted again.
+public func applyNotificationCommitSet<Bytes: ContiguousBytes>(_ commitSet: Bytes,
+                                                               sessionStore: SessionStore,
+                                                               identityStore: IdentityKeyStore,
+                                                               preKeyStore: PreKeyStore,
+                                                               context: StoreContext) throws {
+    try commitSet.withUnsafeBytes { commitSetBytes in
+        try context.withOpaquePointer { context in
+            try withSessionStore(sessionStore) { ffiSessionStore in
+                try withIdentityKeyStore(identityStore) { ffiIdentityStore in
+                    try withPreKeyStore(preKeyStore) { ffiPreKeyStore in
+                        try checkError(signal_notification_commit_set_apply(
+                            commitSetBytes.baseAddress?.assumingMemoryBound(to: UInt8.self),
+                            commitSetBytes.count,
+                            ffiSessionStore,
+                            ffiIdentityStore,
+                            ffiPreKeyStore,
+                            context))
+                    }
+                }
+            }
+        }
+    }
+}
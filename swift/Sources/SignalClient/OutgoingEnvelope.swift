@@ -0,0 +1,106 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+import Foundation
+
+/// How a recipient should treat a message it fails to decrypt.
+public struct ContentHint: RawRepresentable, Hashable {
+    public var rawValue: UInt32
+
+    public init(rawValue: UInt32) {
+        self.rawValue = rawValue
+    }
+
+    /// The message should be shown to the user as an error.
+    public static let `default` = ContentHint(rawValue: 0)
+    /// The sender will resend the message if the recipient asks.
+    public static let resendable = ContentHint(rawValue: 1)
+    /// The message is not visible to the user, so a failure need not be shown.
+    public static let implicit = ContentHint(rawValue: 2)
+}
+
+/// An already-encrypted message waiting to be sent, normally a sealed sender message.
+///
+/// Serialize queued envelopes to keep them across restarts; the format is shared by every platform.
+public class OutgoingEnvelope: ClonableHandleOwner {
+    public init<Bytes: ContiguousBytes>(destination: ProtocolAddress,
+                                        timestamp: UInt64,
+                                        contentHint: ContentHint,
+                                        payload: Bytes) {
+        let handle: OpaquePointer? = payload.withUnsafeBytes {
+            var result: OpaquePointer?
+            failOnError(signal_outgoing_envelope_new(&result, destination.nativeHandle, timestamp, contentHint.rawValue,
+                                                     $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count))
+            return result
+        }
+        super.init(owned: handle!)
+    }
+
+    public init<Bytes: ContiguousBytes>(bytes: Bytes) throws {
+        let handle: OpaquePointer? = try bytes.withUnsafeBytes {
+            var result: OpaquePointer?
+            try checkError(signal_outgoing_envelope_deserialize(&result, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count))
+            return result
+        }
+        super.init(owned: handle!)
+    }
+
+    internal override class func cloneNativeHandle(_ newHandle: inout OpaquePointer?, currentHandle: OpaquePointer?) -> SignalFfiErrorRef? {
+        return signal_outgoing_envelope_clone(&newHandle, currentHandle)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_outgoing_envelope_destroy(handle)
+    }
+
+    public var destination: ProtocolAddress {
+        var handle: OpaquePointer?
+        failOnError(signal_outgoing_envelope_get_destination(&handle, nativeHandle))
+        return ProtocolAddress(owned: handle!)
+    }
+
+    public var timestamp: UInt64 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_outgoing_envelope_get_timestamp($0, nativeHandle)
+            }
+        }
+    }
+
+    public var contentHint: ContentHint {
+        let rawValue: UInt32 = failOnError {
+            try invokeFnReturningInteger {
+                signal_outgoing_envelope_get_content_hint($0, nativeHandle)
+            }
+        }
+        return ContentHint(rawValue: rawValue)
+    }
+
+    public var payload: [UInt8] {
+        return failOnError {
+            try invokeFnReturningArray {
+                signal_outgoing_envelope_get_payload($0, $1, nativeHandle)
+            }
+        }
+    }
+
+    /// The format version of the data this envelope was deserialized from.
+    public var serializedVersion: UInt32 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_outgoing_envelope_get_serialized_version($0, nativeHandle)
+            }
+        }
+    }
+
+    public func serialize() -> [UInt8] {
+        return failOnError {
+            try invokeFnReturningArray {
+                signal_outgoing_envelope_serialize($0, $1, nativeHandle)
+            }
+        }
+    }
+}
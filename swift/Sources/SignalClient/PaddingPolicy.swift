@@ -0,0 +1,64 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+import Foundation
+
+/// How far to pad a plaintext before encrypting it, so that ciphertext lengths reveal less.
+///
+/// A padded message is the original message, a 0x80 terminator byte, and zeros, so any policy other
+/// than `none` can remove padding added under any other.
+public class PaddingPolicy: ClonableHandleOwner {
+    /// Messages are sent as-is.
+    public static var none: PaddingPolicy {
+        var result: OpaquePointer?
+        failOnError(signal_padding_policy_none(&result))
+        return PaddingPolicy(owned: result!)
+    }
+
+    /// Messages are padded with the Padmé scheme, which adds at most about 12%.
+    public static var padme: PaddingPolicy {
+        var result: OpaquePointer?
+        failOnError(signal_padding_policy_padme(&result))
+        return PaddingPolicy(owned: result!)
+    }
+
+    /// Messages are padded up to the next multiple of `bucketSize`.
+    public static func bucketed(_ bucketSize: UInt32) throws -> PaddingPolicy {
+        var result: OpaquePointer?
+        try checkError(signal_padding_policy_bucketed(&result, bucketSize))
+        return PaddingPolicy(owned: result!)
+    }
+
+    internal override class func cloneNativeHandle(_ newHandle: inout OpaquePointer?, currentHandle: OpaquePointer?) -> SignalFfiErrorRef? {
+        return signal_padding_policy_clone(&newHandle, currentHandle)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_padding_policy_destroy(handle)
+    }
+
+    public func paddedLength(_ length: UInt32) throws -> UInt32 {
+        return try invokeFnReturningInteger {
+            signal_padding_policy_padded_length($0, nativeHandle, length)
+        }
+    }
+
+    public func pad<Bytes: ContiguousBytes>(_ message: Bytes) throws -> [UInt8] {
+        return try message.withUnsafeBytes { messageBytes in
+            try invokeFnReturningArray {
+                signal_padding_policy_pad($0, $1, nativeHandle, messageBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), messageBytes.count)
+            }
+        }
+    }
+
+    public func unpad<Bytes: ContiguousBytes>(_ paddedMessage: Bytes) throws -> [UInt8] {
+        return try paddedMessage.withUnsafeBytes { paddedBytes in
+            try invokeFnReturningArray {
+                signal_padding_policy_unpad($0, $1, nativeHandle, paddedBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), paddedBytes.count)
+            }
+        }
+    }
+}
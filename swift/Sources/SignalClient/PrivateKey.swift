@@ -22,7 +22,7 @@ public class PrivateKey: ClonableHandleOwner {
 
     public static func generate() -> PrivateKey {
         var handle: OpaquePointer?
-        failOnError(signal_privatekey_generate(&handle))
+        failOnError(signal_privatekey_generate(&handle, nil))
         return PrivateKey(owned: handle!)
     }
 
@@ -46,7 +46,7 @@ public class PrivateKey: ClonableHandleOwner {
         return message.withUnsafeBytes { messageBytes in
             failOnError {
                 try invokeFnReturningArray {
-                    signal_privatekey_sign($0, $1, nativeHandle, messageBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), messageBytes.count)
+                    signal_privatekey_sign($0, $1, nativeHandle, messageBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), messageBytes.count, nil)
                 }
             }
         }
@@ -60,6 +60,18 @@ public class PrivateKey: ClonableHandleOwner {
         }
     }
 
+    /// Decrypts a message from `PublicKey.seal(_:)`.
+    ///
+    /// Throws `SignalError.invalidCiphertext` if the message was sealed for a different key or has
+    /// been modified.
+    public func open<Bytes: ContiguousBytes>(_ sealed: Bytes) throws -> [UInt8] {
+        return try sealed.withUnsafeBytes { sealedBytes in
+            try invokeFnReturningArray {
+                signal_privatekey_open($0, $1, nativeHandle, sealedBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), sealedBytes.count)
+            }
+        }
+    }
+
     public var publicKey: PublicKey {
         return failOnError {
             try invokeFnReturningPublicKey {
@@ -11,6 +11,20 @@ public func signalEncrypt<Bytes: ContiguousBytes>(message: Bytes,
                                                   sessionStore: SessionStore,
                                                   identityStore: IdentityKeyStore,
                                                   context: StoreContext) throws -> CiphertextMessage {
+    return try signalEncrypt(message: message, for: address, sessionStore: sessionStore, identityStore: identityStore, padding: nil, context: context)
+}
+
+/// Like `signalEncrypt(message:for:sessionStore:identityStore:context:)`, but pads `message` according
+/// to `padding` first.
+public func signalEncrypt<Bytes: ContiguousBytes>(message: Bytes,
+                                                  for address: ProtocolAddress,
+                                                  sessionStore: SessionStore,
+                                                  identityStore: IdentityKeyStore,
+                                                  padding: PaddingPolicy?,
+                                                  context: StoreContext) throws -> CiphertextMessage {
+    if let padding = padding {
+        return try signalEncrypt(message: padding.pad(message), for: address, sessionStore: sessionStore, identityStore: identityStore, padding: nil, context: context)
+    }
     return try message.withUnsafeBytes { messageBytes in
         try context.withOpaquePointer { context in
             try withSessionStore(sessionStore) { ffiSessionStore in
@@ -24,8 +38,124 @@ public func signalEncrypt<Bytes: ContiguousBytes>(message: Bytes,
     }
 }
 
+/// Serializes `content`, pads it according to `padding`, and encrypts it for `address`.
+public func signalEncrypt(content: Content,
+                          for address: ProtocolAddress,
+                          sessionStore: SessionStore,
+                          identityStore: IdentityKeyStore,
+                          padding: PaddingPolicy,
+                          context: StoreContext) throws -> CiphertextMessage {
+    return try context.withOpaquePointer { context in
+        try withSessionStore(sessionStore) { ffiSessionStore in
+            try withIdentityKeyStore(identityStore) { ffiIdentityStore in
+                try invokeFnReturningCiphertextMessage {
+                    signal_encrypt_content($0, content.nativeHandle, padding.nativeHandle, address.nativeHandle, ffiSessionStore, ffiIdentityStore, context)
+                }
+            }
+        }
+    }
+}
+
+/// Like `signalEncrypt(message:for:sessionStore:identityStore:context:)`, but trusts `approvedIdentity`
+/// even if `identityStore` doesn't.
+///
+/// Use this to retry after an untrusted identity error once the user has accepted the recipient's new
+/// identity. If the session's identity is `approvedIdentity`, it is saved to `identityStore` as part of
+/// the same operation; otherwise the store decides as usual.
+public func signalEncrypt<Bytes: ContiguousBytes>(message: Bytes,
+                                                  for address: ProtocolAddress,
+                                                  trusting approvedIdentity: IdentityKey,
+                                                  sessionStore: SessionStore,
+                                                  identityStore: IdentityKeyStore,
+                                                  context: StoreContext) throws -> CiphertextMessage {
+    return try message.withUnsafeBytes { messageBytes in
+        try context.withOpaquePointer { context in
+            try withSessionStore(sessionStore) { ffiSessionStore in
+                try withIdentityKeyStore(identityStore) { ffiIdentityStore in
+                    try invokeFnReturningCiphertextMessage {
+                        signal_encrypt_message_with_identity_override($0, messageBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), messageBytes.count, address.nativeHandle, approvedIdentity.publicKey.nativeHandle, ffiSessionStore, ffiIdentityStore, context)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Encrypts `message` for each of `addresses`, returning one result per address in the same order.
+///
+/// A recipient that cannot be encrypted for (for example, because it has no session or an untrusted
+/// identity) gets a failed result without affecting the others. This only throws if the batch as a
+/// whole could not be processed.
+public func signalEncrypt<Bytes: ContiguousBytes>(message: Bytes,
+                                                  for addresses: [ProtocolAddress],
+                                                  sessionStore: SessionStore,
+                                                  identityStore: IdentityKeyStore,
+                                                  context: StoreContext) throws -> [Result<CiphertextMessage, Error>] {
+    var handles = [OpaquePointer?](repeating: nil, count: addresses.count)
+    var errors = [SignalFfiErrorRef?](repeating: nil, count: addresses.count)
+    try withExtendedLifetime(addresses) {
+        let addressHandles = addresses.map { $0.nativeHandle }
+        try message.withUnsafeBytes { messageBytes in
+            try context.withOpaquePointer { context in
+                try withSessionStore(sessionStore) { ffiSessionStore in
+                    try withIdentityKeyStore(identityStore) { ffiIdentityStore in
+                        try checkError(signal_encrypt_message_batch(&handles, &errors, messageBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), messageBytes.count, addressHandles, addressHandles.count, ffiSessionStore, ffiIdentityStore, context))
+                    }
+                }
+            }
+        }
+    }
+    return zip(handles, errors).map { handle, error in
+        Result {
+            try checkError(error)
+            return CiphertextMessage(owned: handle)
+        }
+    }
+}
+
+public func signalDecrypt(message: SignalMessage,
+                          from address: ProtocolAddress,
+                          sessionStore: SessionStore,
+                          identityStore: IdentityKeyStore,
+                          context: StoreContext) throws -> [UInt8] {
+    return try context.withOpaquePointer { context in
+        try withSessionStore(sessionStore) { ffiSessionStore in
+            try withIdentityKeyStore(identityStore) { ffiIdentityStore in
+                try invokeFnReturningArray {
+                    signal_decrypt_message($0, $1, message.nativeHandle, address.nativeHandle, ffiSessionStore, ffiIdentityStore, nil, context)
+                }
+            }
+        }
+    }
+}
+
+public func signalDecryptPreKey(message: PreKeySignalMessage,
+                                from: ProtocolAddress,
+                                sessionStore: SessionStore,
+                                identityStore: IdentityKeyStore,
+                                preKeyStore: PreKeyStore,
+                                signedPreKeyStore: SignedPreKeyStore,
+                                context: StoreContext) throws -> [UInt8] {
+    return try context.withOpaquePointer { context in
+        try withSessionStore(sessionStore) { ffiSessionStore in
+            try withIdentityKeyStore(identityStore) { ffiIdentityStore in
+                try withPreKeyStore(preKeyStore) { ffiPreKeyStore in
+                    try withSignedPreKeyStore(signedPreKeyStore) { ffiSignedPreKeyStore in
+                        try invokeFnReturningArray {
+                            signal_decrypt_pre_key_message($0, $1, message.nativeHandle, from.nativeHandle, ffiSessionStore, ffiIdentityStore, ffiPreKeyStore, ffiSignedPreKeyStore, nil, context)
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Like `signalDecrypt(message:from:sessionStore:identityStore:context:)`, but trusts `approvedIdentity`
+/// even if `identityStore` doesn't.
 public func signalDecrypt(message: SignalMessage,
                           from address: ProtocolAddress,
+                          trusting approvedIdentity: IdentityKey,
                           sessionStore: SessionStore,
                           identityStore: IdentityKeyStore,
                           context: StoreContext) throws -> [UInt8] {
@@ -33,15 +163,18 @@ public func signalDecrypt(message: SignalMessage,
         try withSessionStore(sessionStore) { ffiSessionStore in
             try withIdentityKeyStore(identityStore) { ffiIdentityStore in
                 try invokeFnReturningArray {
-                    signal_decrypt_message($0, $1, message.nativeHandle, address.nativeHandle, ffiSessionStore, ffiIdentityStore, context)
+                    signal_decrypt_message_with_identity_override($0, $1, message.nativeHandle, address.nativeHandle, approvedIdentity.publicKey.nativeHandle, ffiSessionStore, ffiIdentityStore, nil, context)
                 }
             }
         }
     }
 }
 
+/// Like `signalDecryptPreKey(message:from:sessionStore:identityStore:preKeyStore:signedPreKeyStore:context:)`,
+/// but trusts `approvedIdentity` even if `identityStore` doesn't.
 public func signalDecryptPreKey(message: PreKeySignalMessage,
                                 from: ProtocolAddress,
+                                trusting approvedIdentity: IdentityKey,
                                 sessionStore: SessionStore,
                                 identityStore: IdentityKeyStore,
                                 preKeyStore: PreKeyStore,
@@ -53,7 +186,7 @@ public func signalDecryptPreKey(message: PreKeySignalMessage,
                 try withPreKeyStore(preKeyStore) { ffiPreKeyStore in
                     try withSignedPreKeyStore(signedPreKeyStore) { ffiSignedPreKeyStore in
                         try invokeFnReturningArray {
-                            signal_decrypt_pre_key_message($0, $1, message.nativeHandle, from.nativeHandle, ffiSessionStore, ffiIdentityStore, ffiPreKeyStore, ffiSignedPreKeyStore, context)
+                            signal_decrypt_pre_key_message_with_identity_override($0, $1, message.nativeHandle, from.nativeHandle, approvedIdentity.publicKey.nativeHandle, ffiSessionStore, ffiIdentityStore, ffiPreKeyStore, ffiSignedPreKeyStore, nil, context)
                         }
                     }
                 }
@@ -70,7 +203,7 @@ public func processPreKeyBundle(_ bundle: PreKeyBundle,
     return try context.withOpaquePointer { context in
         try withSessionStore(sessionStore) { ffiSessionStore in
             try withIdentityKeyStore(identityStore) { ffiIdentityStore in
-                try checkError(signal_process_prekey_bundle(bundle.nativeHandle, address.nativeHandle, ffiSessionStore, ffiIdentityStore, context))
+                try checkError(signal_process_prekey_bundle(bundle.nativeHandle, address.nativeHandle, ffiSessionStore, ffiIdentityStore, nil, context))
             }
         }
     }
@@ -80,11 +213,23 @@ public func groupEncrypt<Bytes: ContiguousBytes>(groupId: SenderKeyName,
                                                  message: Bytes,
                                                  store: SenderKeyStore,
                                                  context: StoreContext) throws -> [UInt8] {
+    return try groupEncrypt(groupId: groupId, message: message, store: store, padding: nil, context: context)
+}
+
+/// Like `groupEncrypt(groupId:message:store:context:)`, but pads `message` according to `padding` first.
+public func groupEncrypt<Bytes: ContiguousBytes>(groupId: SenderKeyName,
+                                                 message: Bytes,
+                                                 store: SenderKeyStore,
+                                                 padding: PaddingPolicy?,
+                                                 context: StoreContext) throws -> [UInt8] {
+    if let padding = padding {
+        return try groupEncrypt(groupId: groupId, message: padding.pad(message), store: store, padding: nil, context: context)
+    }
     return try context.withOpaquePointer { context in
         try message.withUnsafeBytes { messageBytes in
             try withSenderKeyStore(store) { ffiStore in
                 try invokeFnReturningArray {
-                    signal_group_encrypt_message($0, $1, groupId.nativeHandle, messageBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), messageBytes.count, ffiStore, context)
+                    signal_group_encrypt_message($0, $1, groupId.nativeHandle, messageBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), messageBytes.count, ffiStore, nil, context)
                 }
             }
         }
@@ -106,6 +251,62 @@ public func groupDecrypt<Bytes: ContiguousBytes>(groupId: SenderKeyName,
     }
 }
 
+/// Returns the next iteration expected on the sender key chain `distributionId`.
+public func groupChainPosition(groupId: SenderKeyName,
+                               distributionId: UInt32,
+                               store: SenderKeyStore,
+                               context: StoreContext) throws -> UInt32 {
+    return try context.withOpaquePointer { context in
+        try withSenderKeyStore(store) { ffiStore in
+            try invokeFnReturningInteger {
+                signal_group_chain_position($0, groupId.nativeHandle, distributionId, ffiStore, context)
+            }
+        }
+    }
+}
+
+/// Advances the sender key chain `distributionId` so that `iteration` is the next one expected.
+///
+/// Messages from the skipped iterations can no longer be decrypted, so only use this once the
+/// sender's position has been verified out of band. The chain can move forward by at most 1,000,000
+/// iterations at once.
+public func groupFastForward(groupId: SenderKeyName,
+                             distributionId: UInt32,
+                             to iteration: UInt32,
+                             store: SenderKeyStore,
+                             context: StoreContext) throws {
+    try context.withOpaquePointer { context in
+        try withSenderKeyStore(store) { ffiStore in
+            try checkError(signal_group_fast_forward(groupId.nativeHandle, distributionId, iteration, ffiStore, context))
+        }
+    }
+}
+
+/// Summarizes the security state of the conversation with `address` as JSON, for attaching to a
+/// bug report.
+///
+/// The summary includes a fingerprint of the saved identity key, the session version, and when
+/// the ratchet last stepped, but never any secrets. If `senderKeyName` (one of our own) is given,
+/// it also says which of our sender keys for that group `address` was last sent.
+public func securitySnapshot(for address: ProtocolAddress,
+                             senderKeyName: SenderKeyName?,
+                             sessionStore: SessionStore,
+                             identityStore: IdentityKeyStore,
+                             senderKeyStore: SenderKeyStore,
+                             context: StoreContext) throws -> String {
+    return try context.withOpaquePointer { context in
+        try withSessionStore(sessionStore) { ffiSessionStore in
+            try withIdentityKeyStore(identityStore) { ffiIdentityStore in
+                try withSenderKeyStore(senderKeyStore) { ffiSenderKeyStore in
+                    try invokeFnReturningString {
+                        signal_security_snapshot($0, address.nativeHandle, senderKeyName?.nativeHandle, ffiSessionStore, ffiIdentityStore, ffiSenderKeyStore, context)
+                    }
+                }
+            }
+        }
+    }
+}
+
 public func processSenderKeyDistributionMessage(sender: SenderKeyName,
                                                 message: SenderKeyDistributionMessage,
                                                 store: SenderKeyStore,
@@ -0,0 +1,96 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+import Foundation
+
+/// The linked device's side of device provisioning.
+///
+/// The linked device shows `publicKey` to the primary device, which encrypts the provisioning
+/// message with `ProvisioningCipher.encrypt(_:for:)`. The cipher should be discarded once that
+/// message has been decrypted.
+public class ProvisioningCipher: ClonableHandleOwner {
+    public init() {
+        var result: OpaquePointer?
+        failOnError(signal_provisioning_cipher_new(&result, nil))
+        super.init(owned: result!)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_provisioning_cipher_destroy(handle)
+    }
+
+    /// Encrypts `message` for the linked device that showed `theirPublicKey`, returning a
+    /// serialized ProvisionEnvelope.
+    public static func encrypt<Bytes: ContiguousBytes>(_ message: Bytes, for theirPublicKey: PublicKey) -> [UInt8] {
+        return message.withUnsafeBytes { messageBytes in
+            failOnError {
+                try invokeFnReturningArray {
+                    signal_provisioning_cipher_encrypt($0, $1, theirPublicKey.nativeHandle, messageBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), messageBytes.count, nil)
+                }
+            }
+        }
+    }
+
+    public var publicKey: PublicKey {
+        return failOnError {
+            try invokeFnReturningPublicKey {
+                signal_provisioning_cipher_get_public_key($0, nativeHandle)
+            }
+        }
+    }
+
+    /// Throws `SignalError.invalidCiphertext` if the envelope was not encrypted for this cipher or
+    /// has been tampered with.
+    public func decrypt<Bytes: ContiguousBytes>(_ envelope: Bytes) throws -> [UInt8] {
+        return try envelope.withUnsafeBytes { envelopeBytes in
+            try invokeFnReturningArray {
+                signal_provisioning_cipher_decrypt($0, $1, nativeHandle, envelopeBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), envelopeBytes.count)
+            }
+        }
+    }
+}
+
+/// The first message the server sends on a provisioning connection.
+public class ProvisioningUuid: ClonableHandleOwner {
+    public init(uuid: String) throws {
+        var result: OpaquePointer?
+        try checkError(signal_provisioning_uuid_new(&result, uuid))
+        super.init(owned: result!)
+    }
+
+    public init<Bytes: ContiguousBytes>(bytes: Bytes) throws {
+        let handle: OpaquePointer? = try bytes.withUnsafeBytes {
+            var result: OpaquePointer?
+            try checkError(signal_provisioning_uuid_deserialize(&result, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count))
+            return result
+        }
+        super.init(owned: handle!)
+    }
+
+    internal override class func cloneNativeHandle(_ newHandle: inout OpaquePointer?, currentHandle: OpaquePointer?) -> SignalFfiErrorRef? {
+        return signal_provisioning_uuid_clone(&newHandle, currentHandle)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_provisioning_uuid_destroy(handle)
+    }
+
+    public var uuid: String {
+        return failOnError {
+            try invokeFnReturningString {
+                signal_provisioning_uuid_get_uuid($0, nativeHandle)
+            }
+        }
+    }
+
+    public func serialize() -> [UInt8] {
+        return failOnError {
+            try invokeFnReturningArray {
+                signal_provisioning_uuid_get_serialized($0, $1, nativeHandle)
+            }
+        }
+    }
+}
@@ -59,6 +59,18 @@ public class PublicKey: ClonableHandleOwner {
         return result
     }
 
+    /// Encrypts a message that only the holder of the matching private key can read, without
+    /// identifying the sender. See `PrivateKey.open(_:)`.
+    public func seal<Bytes: ContiguousBytes>(_ plaintext: Bytes) -> [UInt8] {
+        return plaintext.withUnsafeBytes { plaintextBytes in
+            failOnError {
+                try invokeFnReturningArray {
+                    signal_publickey_seal($0, $1, nativeHandle, plaintextBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), plaintextBytes.count, nil)
+                }
+            }
+        }
+    }
+
     public func compare(_ other: PublicKey) -> Int32 {
         var result: Int32 = 0
         failOnError(signal_publickey_compare(&result, nativeHandle, other.nativeHandle))
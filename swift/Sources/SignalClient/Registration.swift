@@ -0,0 +1,243 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+import Foundation
+
+public enum PushTokenType {
+    case fcm
+    case apn
+}
+
+public enum VerificationTransport {
+    case sms
+    case voice
+}
+
+/// An HTTP request to the registration service, to be sent by the caller.
+public class RegistrationRequest: ClonableHandleOwner {
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_registration_request_destroy(handle)
+    }
+
+    public static func createSession(number: String, pushToken: String?, pushTokenType: PushTokenType, mcc: String?, mnc: String?) -> RegistrationRequest {
+        var result: OpaquePointer?
+        failOnError(signal_registration_request_create_session(&result, number, pushToken, pushTokenType == .apn, mcc, mnc))
+        return RegistrationRequest(owned: result!)
+    }
+
+    public static func getSession(sessionId: String) throws -> RegistrationRequest {
+        var result: OpaquePointer?
+        try checkError(signal_registration_request_get_session(&result, sessionId))
+        return RegistrationRequest(owned: result!)
+    }
+
+    /// At least one of `pushChallenge` and `captcha` should be present.
+    public static func updateSession(sessionId: String, pushChallenge: String?, captcha: String?) throws -> RegistrationRequest {
+        var result: OpaquePointer?
+        try checkError(signal_registration_request_update_session(&result, sessionId, pushChallenge, captcha))
+        return RegistrationRequest(owned: result!)
+    }
+
+    public static func requestVerificationCode(sessionId: String, transport: VerificationTransport, client: String) throws -> RegistrationRequest {
+        var result: OpaquePointer?
+        try checkError(signal_registration_request_request_verification_code(&result, sessionId, transport == .voice, client))
+        return RegistrationRequest(owned: result!)
+    }
+
+    public static func submitVerificationCode(sessionId: String, code: String) throws -> RegistrationRequest {
+        var result: OpaquePointer?
+        try checkError(signal_registration_request_submit_verification_code(&result, sessionId, code))
+        return RegistrationRequest(owned: result!)
+    }
+
+    /// `registrationLock` should come from `registrationLockToken(masterKey:)` if the account has
+    /// registration lock enabled.
+    public static func registerAccount(sessionId: String, registrationId: UInt32, fetchesMessages: Bool, registrationLock: String?) throws -> RegistrationRequest {
+        var result: OpaquePointer?
+        try checkError(signal_registration_request_register_account(&result, sessionId, registrationId, fetchesMessages, registrationLock))
+        return RegistrationRequest(owned: result!)
+    }
+
+    public var method: String {
+        return failOnError {
+            try invokeFnReturningString {
+                signal_registration_request_get_method($0, nativeHandle)
+            }
+        }
+    }
+
+    public var path: String {
+        return failOnError {
+            try invokeFnReturningString {
+                signal_registration_request_get_path($0, nativeHandle)
+            }
+        }
+    }
+
+    /// The JSON body to send, if the request has one.
+    public var body: [UInt8]? {
+        return failOnError {
+            try invokeFnReturningOptionalArray {
+                signal_registration_request_get_body($0, $1, nativeHandle)
+            }
+        }
+    }
+}
+
+/// The state of a verification session, parsed from the registration service's response.
+public class RegistrationSession: ClonableHandleOwner {
+    /// Throws `SignalError.rateLimited` or `SignalError.registrationSessionNotFound` for those
+    /// responses, and `SignalError.registrationFailed` for any other unsuccessful response.
+    public init<Bytes: ContiguousBytes>(status: UInt32, body: Bytes, retryAfter: String?) throws {
+        let handle: OpaquePointer? = try body.withUnsafeBytes {
+            var result: OpaquePointer?
+            try checkError(signal_registration_session_from_response(&result, status, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count, retryAfter))
+            return result
+        }
+        super.init(owned: handle!)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_registration_session_destroy(handle)
+    }
+
+    public var id: String {
+        return failOnError {
+            try invokeFnReturningString {
+                signal_registration_session_get_id($0, nativeHandle)
+            }
+        }
+    }
+
+    private func optionalSeconds(_ fn: (UnsafeMutablePointer<UInt32>?, OpaquePointer?) -> SignalFfiErrorRef?) -> UInt32? {
+        let seconds = failOnError {
+            try invokeFnReturningInteger {
+                fn($0, nativeHandle)
+            }
+        }
+        return seconds == .max ? nil : seconds
+    }
+
+    public var nextSmsSeconds: UInt32? {
+        return optionalSeconds(signal_registration_session_get_next_sms_seconds)
+    }
+
+    public var nextCallSeconds: UInt32? {
+        return optionalSeconds(signal_registration_session_get_next_call_seconds)
+    }
+
+    public var nextVerificationAttemptSeconds: UInt32? {
+        return optionalSeconds(signal_registration_session_get_next_verification_attempt_seconds)
+    }
+
+    public var allowedToRequestCode: Bool {
+        var result: Bool = false
+        failOnError(signal_registration_session_get_allowed_to_request_code(&result, nativeHandle))
+        return result
+    }
+
+    public var requiresPushChallenge: Bool {
+        var result: Bool = false
+        failOnError(signal_registration_session_requires_push_challenge(&result, nativeHandle))
+        return result
+    }
+
+    public var requiresCaptcha: Bool {
+        var result: Bool = false
+        failOnError(signal_registration_session_requires_captcha(&result, nativeHandle))
+        return result
+    }
+
+    public var verified: Bool {
+        var result: Bool = false
+        failOnError(signal_registration_session_get_verified(&result, nativeHandle))
+        return result
+    }
+}
+
+/// The result of a registration attempt.
+///
+/// If the account is protected by registration lock, `isLocked` is true and only
+/// `lockTimeRemainingMs` and the SVR credentials are available; otherwise only the account
+/// details are.
+public class RegistrationOutcome: ClonableHandleOwner {
+    public init<Bytes: ContiguousBytes>(status: UInt32, body: Bytes, retryAfter: String?) throws {
+        let handle: OpaquePointer? = try body.withUnsafeBytes {
+            var result: OpaquePointer?
+            try checkError(signal_registration_outcome_from_response(&result, status, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count, retryAfter))
+            return result
+        }
+        super.init(owned: handle!)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_registration_outcome_destroy(handle)
+    }
+
+    public var isLocked: Bool {
+        var result: Bool = false
+        failOnError(signal_registration_outcome_is_locked(&result, nativeHandle))
+        return result
+    }
+
+    public var aci: String? {
+        return failOnError {
+            try invokeFnReturningOptionalString {
+                signal_registration_outcome_get_aci($0, nativeHandle)
+            }
+        }
+    }
+
+    public var pni: String? {
+        return failOnError {
+            try invokeFnReturningOptionalString {
+                signal_registration_outcome_get_pni($0, nativeHandle)
+            }
+        }
+    }
+
+    public var number: String? {
+        return failOnError {
+            try invokeFnReturningOptionalString {
+                signal_registration_outcome_get_number($0, nativeHandle)
+            }
+        }
+    }
+
+    public var lockTimeRemainingMs: UInt64 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_registration_outcome_get_lock_time_remaining_ms($0, nativeHandle)
+            }
+        }
+    }
+
+    public var svrUsername: String? {
+        return failOnError {
+            try invokeFnReturningOptionalString {
+                signal_registration_outcome_get_svr_username($0, nativeHandle)
+            }
+        }
+    }
+
+    public var svrPassword: String? {
+        return failOnError {
+            try invokeFnReturningOptionalString {
+                signal_registration_outcome_get_svr_password($0, nativeHandle)
+            }
+        }
+    }
+}
+
+/// Derives the registration lock token to send when registering an account with registration
+/// lock enabled.
+public func registrationLockToken<MasterKey: ContiguousBytes>(masterKey: MasterKey) throws -> String {
+    return try masterKey.withUnsafeBytes { masterKeyBytes in
+        try invokeFnReturningString {
+            signal_registration_lock_token($0, masterKeyBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), masterKeyBytes.count)
+        }
+    }
+}
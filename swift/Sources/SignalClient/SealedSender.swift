@@ -19,7 +19,7 @@ public class ServerCertificate: ClonableHandleOwner {
     // For testing
     public init(keyId: UInt32, publicKey: PublicKey, trustRoot: PrivateKey) throws {
         var result: OpaquePointer?
-        try checkError(signal_server_certificate_new(&result, keyId, publicKey.nativeHandle, trustRoot.nativeHandle))
+        try checkError(signal_server_certificate_new(&result, keyId, publicKey.nativeHandle, trustRoot.nativeHandle, nil))
         super.init(owned: result!)
     }
 
@@ -96,7 +96,8 @@ public class SenderCertificate: ClonableHandleOwner {
                                                      publicKey.nativeHandle,
                                                      expiration,
                                                      signerCertificate.nativeHandle,
-                                                     signerKey.nativeHandle))
+                                                     signerKey.nativeHandle,
+                                                     nil))
         super.init(owned: result!)
     }
 
@@ -187,6 +188,20 @@ public class SenderCertificate: ClonableHandleOwner {
         try checkError(signal_sender_certificate_validate(&result, nativeHandle, trustRoot.nativeHandle, time))
         return result
     }
+
+    /// Accepts the certificate for `gracePeriod` past its expiration.
+    ///
+    /// A certificate that is still expired throws `SignalError.sealedSenderCertificateExpired`
+    /// instead of returning `false`.
+    public func validate(trustRoot: PublicKey, time: UInt64, gracePeriod: UInt64) throws -> Bool {
+        var result: Bool = false
+        try checkError(signal_sender_certificate_validate_with_grace_period(&result,
+                                                                             nativeHandle,
+                                                                             trustRoot.nativeHandle,
+                                                                             time,
+                                                                             gracePeriod))
+        return result
+    }
 }
 
 public func sealedSenderEncrypt<Bytes: ContiguousBytes>(message: Bytes,
@@ -204,7 +219,38 @@ public func sealedSenderEncrypt<Bytes: ContiguousBytes>(message: Bytes,
                                                              address.nativeHandle, senderCert.nativeHandle,
                                                              messageBytes.baseAddress?.assumingMemoryBound(to: UInt8.self),
                                                              messageBytes.count,
-                                                             ffiSessionStore, ffiIdentityStore, context)
+                                                             ffiSessionStore, ffiIdentityStore, nil, context)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Like `sealedSenderEncrypt(message:for:from:sessionStore:identityStore:context:)`, but takes the
+/// sender certificate from `provider`, refreshing it first if it is close to expiring as of
+/// `timestamp` (in milliseconds since the epoch).
+public func sealedSenderEncrypt<Bytes: ContiguousBytes>(message: Bytes,
+                                                        for address: ProtocolAddress,
+                                                        from provider: SenderCertificateProvider,
+                                                        timestamp: UInt64,
+                                                        sessionStore: SessionStore,
+                                                        identityStore: IdentityKeyStore,
+                                                        context: StoreContext) throws -> [UInt8] {
+    return try message.withUnsafeBytes { messageBytes in
+        try context.withOpaquePointer { context in
+            try withSenderCertificateSource(provider.source) { ffiSource in
+                try withSessionStore(sessionStore) { ffiSessionStore in
+                    try withIdentityKeyStore(identityStore) { ffiIdentityStore in
+                        try invokeFnReturningArray {
+                            signal_sealed_session_cipher_encrypt_with_provider($0, $1,
+                                                                               address.nativeHandle,
+                                                                               provider.nativeHandle, ffiSource,
+                                                                               timestamp,
+                                                                               messageBytes.baseAddress?.assumingMemoryBound(to: UInt8.self),
+                                                                               messageBytes.count,
+                                                                               ffiSessionStore, ffiIdentityStore, nil, context)
+                        }
                     }
                 }
             }
@@ -212,6 +258,39 @@ public func sealedSenderEncrypt<Bytes: ContiguousBytes>(message: Bytes,
     }
 }
 
+/// Caches a sender certificate, fetching a new one from `source` before it expires.
+public class SenderCertificateProvider: ClonableHandleOwner {
+    public let source: SenderCertificateSource
+
+    /// Creates a provider that refreshes its certificate once it is within `freshnessWindow`
+    /// milliseconds of expiring, optionally starting with a certificate saved earlier.
+    public init(source: SenderCertificateSource, freshnessWindow: UInt64, certificate: SenderCertificate? = nil) {
+        var result: OpaquePointer?
+        failOnError(signal_sender_certificate_provider_new(&result, certificate?.nativeHandle, freshnessWindow))
+        self.source = source
+        super.init(owned: result!)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_sender_certificate_provider_destroy(handle)
+    }
+
+    /// Returns a certificate that is still valid at `validUntil` (in milliseconds since the epoch).
+    ///
+    /// If the cached certificate expires within the freshness window, a new one is fetched from
+    /// `source` first. If that fails, the cached certificate is still returned as long as it is
+    /// valid at `validUntil`.
+    public func get(validUntil: UInt64, context: StoreContext) throws -> SenderCertificate {
+        var result: OpaquePointer?
+        try context.withOpaquePointer { context in
+            try withSenderCertificateSource(source) { ffiSource in
+                try checkError(signal_sender_certificate_provider_get(&result, nativeHandle, validUntil, ffiSource, context))
+            }
+        }
+        return SenderCertificate(owned: result!)
+    }
+}
+
 public class UnidentifiedSenderMessageContent: ClonableHandleOwner {
     public init<Bytes: ContiguousBytes>(message: Bytes,
                                         identityStore: IdentityKeyStore,
@@ -315,6 +394,7 @@ public func sealedSenderDecrypt<Bytes: ContiguousBytes>(message: Bytes,
                                     ffiIdentityStore,
                                     ffiPreKeyStore,
                                     ffiSignedPreKeyStore,
+                                    nil,
                                     context)
                             }
                         }
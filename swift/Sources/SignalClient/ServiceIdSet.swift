@@ -0,0 +1,79 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+import Foundation
+
+/// A sorted set of service IDs, kept in native memory so that large sets (such as contact
+/// discovery results) can be compared without copying them back and forth.
+///
+/// IDs are passed in their fixed-width binary form: a kind byte (0 for an ACI, 1 for a PNI)
+/// followed by the 16 bytes of the UUID. Lists of IDs are these 17-byte entries concatenated.
+public class ServiceIdSet: ClonableHandleOwner {
+    public static let fixedWidthLength = 17
+
+    public init() {
+        var handle: OpaquePointer?
+        failOnError(signal_service_id_set_new(&handle))
+        super.init(owned: handle!)
+    }
+
+    internal override init(owned handle: OpaquePointer) {
+        super.init(owned: handle)
+    }
+
+    internal override class func cloneNativeHandle(_ newHandle: inout OpaquePointer?, currentHandle: OpaquePointer?) -> SignalFfiErrorRef? {
+        return signal_service_id_set_clone(&newHandle, currentHandle)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_service_id_set_destroy(handle)
+    }
+
+    /// Adds a list of fixed-width binary IDs. Nothing is added if any entry is invalid.
+    public func add<Bytes: ContiguousBytes>(fixedWidthBinary list: Bytes) throws {
+        try list.withUnsafeBytes {
+            try checkError(signal_service_id_set_add_fixed_width_binary(nativeHandle, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count))
+        }
+    }
+
+    public var count: Int {
+        return Int(failOnError {
+            try invokeFnReturningInteger {
+                signal_service_id_set_len($0, nativeHandle)
+            }
+        })
+    }
+
+    public func contains<Bytes: ContiguousBytes>(fixedWidthBinary serviceId: Bytes) throws -> Bool {
+        var result: Bool = false
+        try serviceId.withUnsafeBytes {
+            try checkError(signal_service_id_set_contains(&result, nativeHandle, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count))
+        }
+        return result
+    }
+
+    public func intersection(_ other: ServiceIdSet) -> ServiceIdSet {
+        var handle: OpaquePointer?
+        failOnError(signal_service_id_set_intersection(&handle, nativeHandle, other.nativeHandle))
+        return ServiceIdSet(owned: handle!)
+    }
+
+    /// The IDs in this set that are not in `other`.
+    public func subtracting(_ other: ServiceIdSet) -> ServiceIdSet {
+        var handle: OpaquePointer?
+        failOnError(signal_service_id_set_difference(&handle, nativeHandle, other.nativeHandle))
+        return ServiceIdSet(owned: handle!)
+    }
+
+    /// Up to `limit` IDs in fixed-width binary form, in sorted order, skipping the first `offset`.
+    public func page(offset: UInt32, limit: UInt32) -> [UInt8] {
+        return failOnError {
+            try invokeFnReturningArray {
+                signal_service_id_set_get_page($0, $1, nativeHandle, offset, limit)
+            }
+        }
+    }
+}
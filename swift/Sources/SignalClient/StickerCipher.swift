@@ -0,0 +1,64 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+import Foundation
+
+/// Decrypts the manifest and stickers of a sticker pack, using keys derived from the pack key.
+public class StickerCipher: ClonableHandleOwner {
+    public init<Bytes: ContiguousBytes>(packKey: Bytes) throws {
+        let handle: OpaquePointer? = try packKey.withUnsafeBytes {
+            var result: OpaquePointer?
+            try checkError(signal_sticker_cipher_new(&result, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count))
+            return result
+        }
+        super.init(owned: handle!)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_sticker_cipher_destroy(handle)
+    }
+
+    /// Throws `SignalError.invalidCiphertext` if the data has been tampered with.
+    public func decrypt<Bytes: ContiguousBytes>(_ data: Bytes) throws -> [UInt8] {
+        return try data.withUnsafeBytes { dataBytes in
+            try invokeFnReturningArray {
+                signal_sticker_cipher_decrypt($0, $1, nativeHandle, dataBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), dataBytes.count)
+            }
+        }
+    }
+
+    /// Starts decrypting a manifest or sticker that arrives in pieces.
+    public func newDecryption() -> StickerDecryption {
+        var result: OpaquePointer?
+        failOnError(signal_sticker_decryption_new(&result, nativeHandle))
+        return StickerDecryption(owned: result!)
+    }
+}
+
+/// Decrypts a manifest or sticker supplied in pieces of any size.
+///
+/// Plaintext is returned as soon as it can be decrypted, but it is not authenticated until
+/// `finalize()` succeeds and must be discarded if it throws.
+public class StickerDecryption: ClonableHandleOwner {
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_sticker_decryption_destroy(handle)
+    }
+
+    public func update<Bytes: ContiguousBytes>(_ data: Bytes) throws -> [UInt8] {
+        return try data.withUnsafeBytes { dataBytes in
+            try invokeFnReturningArray {
+                signal_sticker_decryption_update($0, $1, nativeHandle, dataBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), dataBytes.count)
+            }
+        }
+    }
+
+    /// Checks the MAC and returns the rest of the plaintext.
+    public func finalize() throws -> [UInt8] {
+        return try invokeFnReturningArray {
+            signal_sticker_decryption_finalize($0, $1, nativeHandle)
+        }
+    }
+}
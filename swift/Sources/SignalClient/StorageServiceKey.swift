@@ -0,0 +1,106 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+import Foundation
+
+/// An account's Storage Service key, from which the key for each manifest and record is derived.
+///
+/// Manifests and records are encrypted as `nonce || AES-256-GCM(data) || tag`.
+public class StorageServiceKey: ClonableHandleOwner {
+    /// Wraps an existing 32-byte storage key.
+    public init<Bytes: ContiguousBytes>(storageKey: Bytes) throws {
+        let handle: OpaquePointer? = try storageKey.withUnsafeBytes {
+            var result: OpaquePointer?
+            try checkError(signal_storage_service_key_new(&result, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count))
+            return result
+        }
+        super.init(owned: handle!)
+    }
+
+    /// Derives the storage key from the account's 32-byte master key.
+    public init<Bytes: ContiguousBytes>(masterKey: Bytes) throws {
+        let handle: OpaquePointer? = try masterKey.withUnsafeBytes {
+            var result: OpaquePointer?
+            try checkError(signal_storage_service_key_from_master_key(&result, $0.baseAddress?.assumingMemoryBound(to: UInt8.self), $0.count))
+            return result
+        }
+        super.init(owned: handle!)
+    }
+
+    internal override class func cloneNativeHandle(_ newHandle: inout OpaquePointer?, currentHandle: OpaquePointer?) -> SignalFfiErrorRef? {
+        return signal_storage_service_key_clone(&newHandle, currentHandle)
+    }
+
+    internal override class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_storage_service_key_destroy(handle)
+    }
+
+    public var storageKey: [UInt8] {
+        return failOnError {
+            try invokeFnReturningArray {
+                signal_storage_service_key_get_storage_key($0, $1, nativeHandle)
+            }
+        }
+    }
+
+    public func manifestKey(version: UInt64) -> [UInt8] {
+        return failOnError {
+            try invokeFnReturningArray {
+                signal_storage_service_key_manifest_key($0, $1, nativeHandle, version)
+            }
+        }
+    }
+
+    public func recordKey<Bytes: ContiguousBytes>(rawId: Bytes) -> [UInt8] {
+        return rawId.withUnsafeBytes { rawIdBytes in
+            failOnError {
+                try invokeFnReturningArray {
+                    signal_storage_service_key_record_key($0, $1, nativeHandle, rawIdBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), rawIdBytes.count)
+                }
+            }
+        }
+    }
+
+    public func encryptManifest<Bytes: ContiguousBytes>(version: UInt64, _ plaintext: Bytes) throws -> [UInt8] {
+        return try plaintext.withUnsafeBytes { plaintextBytes in
+            try invokeFnReturningArray {
+                signal_storage_service_key_encrypt_manifest($0, $1, nativeHandle, version, plaintextBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), plaintextBytes.count, nil)
+            }
+        }
+    }
+
+    /// Throws `SignalError.invalidCiphertext` if the manifest was encrypted for a different version
+    /// or under a different key, or has been modified.
+    public func decryptManifest<Bytes: ContiguousBytes>(version: UInt64, _ ciphertext: Bytes) throws -> [UInt8] {
+        return try ciphertext.withUnsafeBytes { ciphertextBytes in
+            try invokeFnReturningArray {
+                signal_storage_service_key_decrypt_manifest($0, $1, nativeHandle, version, ciphertextBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), ciphertextBytes.count)
+            }
+        }
+    }
+
+    public func encryptRecord<RawId: ContiguousBytes, Bytes: ContiguousBytes>(rawId: RawId, _ plaintext: Bytes) throws -> [UInt8] {
+        return try rawId.withUnsafeBytes { rawIdBytes in
+            try plaintext.withUnsafeBytes { plaintextBytes in
+                try invokeFnReturningArray {
+                    signal_storage_service_key_encrypt_record($0, $1, nativeHandle, rawIdBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), rawIdBytes.count, plaintextBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), plaintextBytes.count, nil)
+                }
+            }
+        }
+    }
+
+    /// Throws `SignalError.invalidCiphertext` if the record was encrypted for a different ID or
+    /// under a different key, or has been modified.
+    public func decryptRecord<RawId: ContiguousBytes, Bytes: ContiguousBytes>(rawId: RawId, _ ciphertext: Bytes) throws -> [UInt8] {
+        return try rawId.withUnsafeBytes { rawIdBytes in
+            try ciphertext.withUnsafeBytes { ciphertextBytes in
+                try invokeFnReturningArray {
+                    signal_storage_service_key_decrypt_record($0, $1, nativeHandle, rawIdBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), rawIdBytes.count, ciphertextBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), ciphertextBytes.count)
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//
+// Copyright 2021 Signal Messenger, LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import SignalFfi
+import Foundation
+
+/// Derives the 16-byte unidentified-access key that senders present to deliver sealed sender
+/// messages to the owner of `profileKey`.
+public func deriveAccessKey<Bytes: ContiguousBytes>(profileKey: Bytes) throws -> [UInt8] {
+    return try profileKey.withUnsafeBytes { profileKeyBytes in
+        try invokeFnReturningArray {
+            signal_profile_key_derive_access_key($0, $1, profileKeyBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), profileKeyBytes.count)
+        }
+    }
+}
+
+/// Blinds `serviceId` under a profile key, giving a 32-byte identifier that only those who know
+/// the key can link back to it.
+public func blindServiceId<Bytes: ContiguousBytes>(_ serviceId: String, profileKey: Bytes) throws -> [UInt8] {
+    return try profileKey.withUnsafeBytes { profileKeyBytes in
+        try invokeFnReturningArray {
+            signal_profile_key_blind_service_id($0, $1, profileKeyBytes.baseAddress?.assumingMemoryBound(to: UInt8.self), profileKeyBytes.count, serviceId)
+        }
+    }
+}
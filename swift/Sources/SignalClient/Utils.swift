@@ -33,6 +33,18 @@ internal func invokeFnReturningArray(fn: (UnsafeMutablePointer<UnsafePointer<UIn
     return result
 }
 
+internal func invokeFnReturningOptionalArray(fn: (UnsafeMutablePointer<UnsafePointer<UInt8>?>?, UnsafeMutablePointer<Int>?) -> SignalFfiErrorRef?) throws -> [UInt8]? {
+    var output: UnsafePointer<UInt8>?
+    var output_len = 0
+    try checkError(fn(&output, &output_len))
+    if output == nil {
+        return nil
+    }
+    let result = Array(UnsafeBufferPointer(start: output, count: output_len))
+    signal_free_buffer(output, output_len)
+    return result
+}
+
 internal func invokeFnReturningInteger<Result: FixedWidthInteger>(fn: (UnsafeMutablePointer<Result>?) -> SignalFfiErrorRef?) throws -> Result {
     var output: Result = 0
     try checkError(fn(&output))
@@ -193,6 +205,39 @@ internal func withIdentityKeyStore<Result>(_ store: IdentityKeyStore, _ body: (U
         }
     }
 
+    func ffiShimGetLocalIdentityKey(store_ctx: UnsafeMutableRawPointer?,
+                                    public_keyp: UnsafeMutablePointer<OpaquePointer?>?,
+                                    ctx: UnsafeMutableRawPointer?) -> Int32 {
+        let storeContext = store_ctx!.assumingMemoryBound(to: ErrorHandlingContext<IdentityKeyStore>.self)
+        return storeContext.pointee.catchCallbackErrors { store in
+            let context = ctx!.assumingMemoryBound(to: StoreContext.self).pointee
+            var publicKey = try (store as! IdentityKeyOpsStore).identityKey(context: context).publicKey
+            public_keyp!.pointee = try cloneOrTakeHandle(from: &publicKey)
+            return 0
+        }
+    }
+
+    func ffiShimCalculateIdentityAgreement(store_ctx: UnsafeMutableRawPointer?,
+                                           shared_secret: UnsafeMutablePointer<UInt8>?,
+                                           their_key: OpaquePointer?,
+                                           ctx: UnsafeMutableRawPointer?) -> Int32 {
+        let storeContext = store_ctx!.assumingMemoryBound(to: ErrorHandlingContext<IdentityKeyStore>.self)
+        return storeContext.pointee.catchCallbackErrors { store in
+            let context = ctx!.assumingMemoryBound(to: StoreContext.self).pointee
+            var theirKey = PublicKey(borrowing: their_key)
+            defer { cloneOrForgetAsNeeded(&theirKey) }
+            let agreement = try (store as! IdentityKeyOpsStore).calculateIdentityAgreement(with: theirKey, context: context)
+            guard agreement.count == 32 else {
+                throw SignalError.invalidKey("identity agreement must be 32 bytes, got \(agreement.count)")
+            }
+            shared_secret!.assign(from: agreement, count: agreement.count)
+            return 0
+        }
+    }
+
+    // Only offer the delegate callbacks if the store can handle them.
+    let supportsOps = store is IdentityKeyOpsStore
+
     return try rethrowCallbackErrors(store) {
         var ffiStore = SignalIdentityKeyStore(
             ctx: $0,
@@ -200,7 +245,9 @@ internal func withIdentityKeyStore<Result>(_ store: IdentityKeyStore, _ body: (U
             get_local_registration_id: ffiShimGetLocalRegistrationId,
             save_identity: ffiShimSaveIdentity,
             get_identity: ffiShimGetIdentity,
-            is_trusted_identity: ffiShimIsTrustedIdentity)
+            is_trusted_identity: ffiShimIsTrustedIdentity,
+            get_local_identity_key: supportsOps ? ffiShimGetLocalIdentityKey : nil,
+            calculate_identity_agreement: supportsOps ? ffiShimCalculateIdentityAgreement : nil)
         return try body(&ffiStore)
     }
 }
@@ -378,3 +425,24 @@ internal func withSenderKeyStore<Result>(_ store: SenderKeyStore, _ body: (Unsaf
         return try body(&ffiStore)
     }
 }
+
+internal func withSenderCertificateSource<Result>(_ source: SenderCertificateSource, _ body: (UnsafePointer<SignalSenderCertificateSource>) throws -> Result) rethrows -> Result {
+    func ffiShimFetchSenderCertificate(store_ctx: UnsafeMutableRawPointer?,
+                                       certificatep: UnsafeMutablePointer<OpaquePointer?>?,
+                                       ctx: UnsafeMutableRawPointer?) -> Int32 {
+        let storeContext = store_ctx!.assumingMemoryBound(to: ErrorHandlingContext<SenderCertificateSource>.self)
+        return storeContext.pointee.catchCallbackErrors { source in
+            let context = ctx!.assumingMemoryBound(to: StoreContext.self).pointee
+            var certificate = try source.fetchSenderCertificate(context: context)
+            certificatep!.pointee = try cloneOrTakeHandle(from: &certificate)
+            return 0
+        }
+    }
+
+    return try rethrowCallbackErrors(source) {
+        var ffiSource = SignalSenderCertificateSource(
+            ctx: $0,
+            fetch_sender_certificate: ffiShimFetchSenderCertificate)
+        return try body(&ffiSource)
+    }
+}
@@ -56,4 +56,32 @@ public class CiphertextMessage {
         }
         return MessageType(rawValue: rawValue)
     }
+
+    public var messageVersion: UInt32 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_ciphertext_message_get_message_version($0, handle)
+            }
+        }
+    }
+
+    /// The position of this message in the sender's current chain, or `nil` for sender key messages.
+    public var counter: UInt32? {
+        let counter = failOnError {
+            try invokeFnReturningInteger {
+                signal_ciphertext_message_get_counter($0, handle)
+            }
+        }
+        return counter == 0xFFFFFFFF ? nil : counter
+    }
+
+    /// The length of the sender's previous chain, or `nil` for sender key messages.
+    public var previousCounter: UInt32? {
+        let previousCounter = failOnError {
+            try invokeFnReturningInteger {
+                signal_ciphertext_message_get_previous_counter($0, handle)
+            }
+        }
+        return previousCounter == 0xFFFFFFFF ? nil : previousCounter
+    }
 }
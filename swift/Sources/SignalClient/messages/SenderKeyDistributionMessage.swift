@@ -21,7 +21,7 @@ public class SenderKeyDistributionMessage {
         try context.withOpaquePointer { context in
             try withSenderKeyStore(store) {
                 try checkError(signal_sender_key_distribution_message_create(&handle, name.nativeHandle,
-                                                                             $0, context))
+                                                                             $0, nil, context))
             }
         }
     }
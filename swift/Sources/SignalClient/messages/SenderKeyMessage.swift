@@ -24,7 +24,8 @@ public class SenderKeyMessage {
                                                          iteration,
                                                          $0.baseAddress?.assumingMemoryBound(to: UInt8.self),
                                                          $0.count,
-                                                         privateKey.nativeHandle))
+                                                         privateKey.nativeHandle,
+                                                         nil))
             return result
         }
     }
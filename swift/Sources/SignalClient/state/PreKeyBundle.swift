@@ -17,6 +17,10 @@ public class PreKeyBundle {
         return handle
     }
 
+    private init(owned handle: OpaquePointer?) {
+        self.handle = handle
+    }
+
     // with a prekey
     public init<Bytes: ContiguousBytes>(registrationId: UInt32,
                                         deviceId: UInt32,
@@ -135,4 +139,31 @@ public class PreKeyBundle {
             }
         }
     }
+
+    /// The oldest session version the bundle's owner advertises support for.
+    public var minSessionVersion: UInt32 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_pre_key_bundle_get_min_session_version($0, handle)
+            }
+        }
+    }
+
+    /// The newest session version the bundle's owner advertises support for.
+    public var maxSessionVersion: UInt32 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_pre_key_bundle_get_max_session_version($0, handle)
+            }
+        }
+    }
+
+    /// Returns a copy of this bundle advertising support for session versions `min` through `max`.
+    ///
+    /// A session established from the bundle uses the newest version supported by both sides.
+    public func withSessionVersions(min: UInt32, max: UInt32) throws -> PreKeyBundle {
+        var result: OpaquePointer?
+        try checkError(signal_pre_key_bundle_with_session_versions(&result, handle, min, max))
+        return PreKeyBundle(owned: result)
+    }
 }
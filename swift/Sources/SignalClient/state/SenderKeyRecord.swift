@@ -38,6 +38,24 @@ public class SenderKeyRecord: ClonableHandleOwner {
         super.init(owned: handle!)
     }
 
+    /// The most states a record may have when deserialized.
+    public static var maxStates: UInt32 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_sender_key_record_max_states($0)
+            }
+        }
+    }
+
+    /// The most skipped-message keys each state in a record may have.
+    public static var maxMessageKeysPerState: UInt32 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_sender_key_record_max_message_keys_per_state($0)
+            }
+        }
+    }
+
     public func serialize() -> [UInt8] {
         return failOnError {
             try invokeFnReturningArray {
@@ -45,4 +63,26 @@ public class SenderKeyRecord: ClonableHandleOwner {
             }
         }
     }
+
+    /// Records that `member` has been sent a distribution message for the current sender key.
+    public func markDistributed(to member: ProtocolAddress) throws {
+        try checkError(signal_sender_key_record_mark_distributed(nativeHandle, member.nativeHandle))
+    }
+
+    /// Returns true if `member` has not been sent the current sender key.
+    public func needsDistribution(to member: ProtocolAddress) -> Bool {
+        var result: Bool = false
+        failOnError(signal_sender_key_record_needs_distribution(&result, nativeHandle, member.nativeHandle))
+        return result
+    }
+
+    /// Stops tracking a member who has left the group.
+    ///
+    /// Returns true if the member holds the current sender key, which should then be replaced
+    /// before the next message is sent to the group.
+    public func forgetDistribution(to member: ProtocolAddress) -> Bool {
+        var result: Bool = false
+        failOnError(signal_sender_key_record_forget_distribution(&result, nativeHandle, member.nativeHandle))
+        return result
+    }
 }
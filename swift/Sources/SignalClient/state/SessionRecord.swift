@@ -28,6 +28,33 @@ public class SessionRecord: ClonableHandleOwner {
         super.init(borrowing: handle)
     }
 
+    /// The most archived states a record may have when deserialized.
+    public static var maxArchivedStates: UInt32 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_session_record_max_archived_states($0)
+            }
+        }
+    }
+
+    /// The most receiver chains each state in a record may have.
+    public static var maxReceiverChains: UInt32 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_session_record_max_receiver_chains($0)
+            }
+        }
+    }
+
+    /// The most skipped-message keys each chain in a record may have.
+    public static var maxMessageKeysPerChain: UInt32 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_session_record_max_message_keys_per_chain($0)
+            }
+        }
+    }
+
     public func serialize() -> [UInt8] {
         return failOnError {
             try invokeFnReturningArray {
@@ -46,6 +73,15 @@ public class SessionRecord: ClonableHandleOwner {
         failOnError(signal_session_record_archive_current_state(nativeHandle))
     }
 
+    /// The version of the current session, or 0 if there is no current session.
+    public var sessionVersion: UInt32 {
+        return failOnError {
+            try invokeFnReturningInteger {
+                signal_session_record_get_session_version($0, nativeHandle)
+            }
+        }
+    }
+
     public func remoteRegistrationId() throws -> UInt32 {
         return try invokeFnReturningInteger {
             signal_session_record_get_remote_registration_id($0, nativeHandle)
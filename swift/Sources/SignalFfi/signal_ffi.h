@@ -26,6 +26,13 @@ typedef enum {
   SignalDirection_Receiving = 1,
 } SignalDirection;
 
+typedef enum {
+  SignalAuditEventKind_IdentityChanged = 1,
+  SignalAuditEventKind_SessionArchived,
+  SignalAuditEventKind_SenderKeyCreated,
+  SignalAuditEventKind_SenderKeyFastForwarded,
+} SignalAuditEventKind;
+
 typedef enum {
   SignalLogLevel_Error = 1,
   SignalLogLevel_Warn,
@@ -43,6 +50,7 @@ typedef enum {
   SignalErrorCode_InvalidType = 6,
   SignalErrorCode_InvalidUtf8String = 7,
   SignalErrorCode_InsufficientOutputSize = 8,
+  SignalErrorCode_BufferTooLarge = 9,
   SignalErrorCode_ProtobufError = 10,
   SignalErrorCode_InvalidCiphertext = 20,
   SignalErrorCode_LegacyCiphertextVersion = 21,
@@ -50,18 +58,36 @@ typedef enum {
   SignalErrorCode_UnrecognizedMessageVersion = 23,
   SignalErrorCode_InvalidMessage = 30,
   SignalErrorCode_SealedSenderSelfSend = 31,
+  SignalErrorCode_SealedSenderCertificateExpired = 32,
+  SignalErrorCode_SealedSenderReplay = 33,
   SignalErrorCode_InvalidKey = 40,
   SignalErrorCode_InvalidSignature = 41,
+  SignalErrorCode_InvalidWrappedKey = 42,
   SignalErrorCode_FingerprintIdentifierMismatch = 50,
   SignalErrorCode_FingerprintVersionMismatch = 51,
   SignalErrorCode_FingerprintParsingError = 52,
   SignalErrorCode_UntrustedIdentity = 60,
   SignalErrorCode_InvalidKeyIdentifier = 70,
   SignalErrorCode_SessionNotFound = 80,
+  SignalErrorCode_InvalidRegistrationId = 81,
+  SignalErrorCode_InvalidSenderKeySession = 82,
   SignalErrorCode_DuplicatedMessage = 90,
   SignalErrorCode_CallbackError = 100,
+  SignalErrorCode_TruncatedBackup = 110,
+  SignalErrorCode_InvalidBackup = 111,
+  SignalErrorCode_KeyTransparencyVerificationFailed = 120,
+  SignalErrorCode_RateLimited = 130,
+  SignalErrorCode_RegistrationSessionNotFound = 131,
+  SignalErrorCode_RegistrationFailed = 132,
 } SignalErrorCode;
 
+typedef enum {
+  SignalDecryptionFailureAction_Retryable = 1,
+  SignalDecryptionFailureAction_NeedsNewSession = 2,
+  SignalDecryptionFailureAction_PermanentlyInvalid = 3,
+  SignalDecryptionFailureAction_StoreError = 4,
+} SignalDecryptionFailureAction;
+
 typedef struct SignalAes256Ctr32 SignalAes256Ctr32;
 
 typedef struct SignalAes256GcmDecryption SignalAes256GcmDecryption;
@@ -70,10 +96,46 @@ typedef struct SignalAes256GcmEncryption SignalAes256GcmEncryption;
 
 typedef struct SignalAes256GcmSiv SignalAes256GcmSiv;
 
+typedef struct SignalAes256KeyWrap SignalAes256KeyWrap;
+
+typedef struct SignalArgon2idParams SignalArgon2idParams;
+
+typedef struct SignalAttachmentPointer SignalAttachmentPointer;
+
+typedef struct SignalBackupBuilder SignalBackupBuilder;
+
+typedef struct SignalBridgeContext SignalBridgeContext;
+
+typedef struct SignalCallLinkRootKey SignalCallLinkRootKey;
+
 typedef struct SignalCiphertextMessage SignalCiphertextMessage;
 
+typedef struct SignalContent SignalContent;
+
+typedef struct SignalCrc32 SignalCrc32;
+
+typedef struct SignalDataMessageBuilder SignalDataMessageBuilder;
+
+typedef struct SignalDeferredDecryptionResult SignalDeferredDecryptionResult;
+
 typedef struct SignalFingerprint SignalFingerprint;
 
+typedef struct SignalFrameReader SignalFrameReader;
+
+typedef struct SignalFrameWriter SignalFrameWriter;
+
+typedef struct SignalIncrementalMacValidator SignalIncrementalMacValidator;
+
+typedef struct SignalKeyTransparencyMonitorResult SignalKeyTransparencyMonitorResult;
+
+typedef struct SignalKeyTransparencySearchResult SignalKeyTransparencySearchResult;
+
+typedef struct SignalNotificationDecryptionResult SignalNotificationDecryptionResult;
+
+typedef struct SignalOutgoingEnvelope SignalOutgoingEnvelope;
+
+typedef struct SignalPaddingPolicy SignalPaddingPolicy;
+
 typedef struct SignalPreKeyBundle SignalPreKeyBundle;
 
 typedef struct SignalPreKeyRecord SignalPreKeyRecord;
@@ -84,10 +146,26 @@ typedef struct SignalPrivateKey SignalPrivateKey;
 
 typedef struct SignalProtocolAddress SignalProtocolAddress;
 
+typedef struct SignalProvisioningCipher SignalProvisioningCipher;
+
+typedef struct SignalProvisioningUuid SignalProvisioningUuid;
+
 typedef struct SignalPublicKey SignalPublicKey;
 
+typedef struct SignalRegistrationOutcome SignalRegistrationOutcome;
+
+typedef struct SignalRegistrationRequest SignalRegistrationRequest;
+
+typedef struct SignalRegistrationSession SignalRegistrationSession;
+
+typedef struct SignalRng SignalRng;
+
+typedef struct SignalScryptParams SignalScryptParams;
+
 typedef struct SignalSenderCertificate SignalSenderCertificate;
 
+typedef struct SignalSenderCertificateProvider SignalSenderCertificateProvider;
+
 typedef struct SignalSenderKeyDistributionMessage SignalSenderKeyDistributionMessage;
 
 typedef struct SignalSenderKeyMessage SignalSenderKeyMessage;
@@ -98,8 +176,20 @@ typedef struct SignalSenderKeyRecord SignalSenderKeyRecord;
 
 typedef struct SignalServerCertificate SignalServerCertificate;
 
+typedef struct SignalServiceIdSet SignalServiceIdSet;
+
 typedef struct SignalSessionRecord SignalSessionRecord;
 
+typedef struct SignalSha256 SignalSha256;
+
+typedef struct SignalSha512 SignalSha512;
+
+typedef struct SignalStickerCipher SignalStickerCipher;
+
+typedef struct SignalStickerDecryption SignalStickerDecryption;
+
+typedef struct SignalStorageServiceKey SignalStorageServiceKey;
+
 /**
  * The top-level error type (opaquely) returned to C clients when something goes wrong.
  */
@@ -131,6 +221,10 @@ typedef int (*SignalGetIdentityKey)(void *store_ctx, SignalPublicKey **public_ke
 
 typedef int (*SignalIsTrustedIdentity)(void *store_ctx, const SignalProtocolAddress *address, const SignalPublicKey *public_key, unsigned int direction, void *ctx);
 
+typedef int (*SignalGetLocalIdentityKey)(void *store_ctx, SignalPublicKey **public_keyp, void *ctx);
+
+typedef int (*SignalCalculateIdentityAgreement)(void *store_ctx, uint8_t *shared_secret, const SignalPublicKey *their_key, void *ctx);
+
 typedef struct {
   void *ctx;
   SignalGetIdentityKeyPair get_identity_key_pair;
@@ -138,6 +232,16 @@ typedef struct {
   SignalSaveIdentityKey save_identity;
   SignalGetIdentityKey get_identity;
   SignalIsTrustedIdentity is_trusted_identity;
+  /**
+   * Optional; if set along with `calculate_identity_agreement`, session setup uses these instead
+   * of `get_identity_key_pair`, so the identity private key never has to leave the store.
+   */
+  SignalGetLocalIdentityKey get_local_identity_key;
+  /**
+   * Optional; writes the 32-byte X25519 agreement between the local identity private key and
+   * `their_key` to `shared_secret`.
+   */
+  SignalCalculateIdentityAgreement calculate_identity_agreement;
 } SignalIdentityKeyStore;
 
 typedef int (*SignalLoadPreKey)(void *store_ctx, SignalPreKeyRecord **recordp, uint32_t id, void *ctx);
@@ -163,6 +267,22 @@ typedef struct {
   SignalStoreSignedPreKey store_signed_pre_key;
 } SignalSignedPreKeyStore;
 
+typedef void (*SignalAuditCallback)(void *ctx, SignalAuditEventKind kind, const char *address_name, uint32_t device_id, const char *group_id);
+
+typedef void (*SignalAuditDestroyCallback)(void *ctx);
+
+/**
+ * An audit hook implemented by the app.
+ *
+ * `record` may be called from any thread. `destroy` is called once the hook has been replaced
+ * and will not be called again.
+ */
+typedef struct {
+  void *ctx;
+  SignalAuditCallback record;
+  SignalAuditDestroyCallback destroy;
+} SignalFfiAuditHook;
+
 typedef bool (*SignalLogEnabledCallback)(const char *target, SignalLogLevel level);
 
 typedef void (*SignalLogCallback)(const char *target, SignalLogLevel level, const char *file, uint32_t line, const char *message);
@@ -185,16 +305,40 @@ typedef struct {
   SignalStoreSenderKey store_sender_key;
 } SignalSenderKeyStore;
 
+typedef int (*SignalFetchSenderCertificate)(void *store_ctx, SignalSenderCertificate **certificatep, void *ctx);
+
+typedef struct {
+  void *ctx;
+  SignalFetchSenderCertificate fetch_sender_certificate;
+} SignalSenderCertificateSource;
+
 void signal_print_ptr(const void *p);
 
 void signal_free_string(const char *buf);
 
 void signal_free_buffer(const unsigned char *buf, size_t buf_len);
 
+/**
+ * Frees an array of handles returned by a function, but not the handles themselves.
+ */
+void signal_free_handle_array(void **handles, size_t handles_len);
+
 SignalFfiError *signal_error_get_message(const SignalFfiError *err, const char **out);
 
 uint32_t signal_error_get_type(const SignalFfiError *err);
 
+/**
+ * Classifies an error returned while decrypting a message as a `SignalDecryptionFailureAction`.
+ *
+ * Errors that don't come from the protocol layer are treated as `PermanentlyInvalid`.
+ */
+uint32_t signal_error_get_decryption_failure_action(const SignalFfiError *err);
+
+/**
+ * Returns the address carried by an `InvalidRegistrationId` or `InvalidSenderKeySession` error.
+ */
+SignalFfiError *signal_error_get_address(const SignalFfiError *err, SignalProtocolAddress **out);
+
 void signal_error_free(SignalFfiError *err);
 
 SignalFfiError *signal_identitykeypair_deserialize(SignalPrivateKey **private_key,
@@ -218,10 +362,58 @@ SignalFfiError *signal_sealed_session_cipher_decrypt(const unsigned char **out,
                                                      const SignalIdentityKeyStore *identity_store,
                                                      const SignalPreKeyStore *prekey_store,
                                                      const SignalSignedPreKeyStore *signed_prekey_store,
+                                                     const SignalRng *rng,
                                                      void *ctx);
 
+SignalFfiError *signal_encrypt_message_batch(SignalCiphertextMessage **out,
+                                             SignalFfiError **errors,
+                                             const unsigned char *ptext,
+                                             size_t ptext_len,
+                                             const SignalProtocolAddress *const *protocol_addresses,
+                                             size_t protocol_addresses_len,
+                                             const SignalSessionStore *session_store,
+                                             const SignalIdentityKeyStore *identity_key_store,
+                                             void *ctx);
+
+/**
+ * Installs `hook` to be told about identity changes, session resets, and new sender keys,
+ * replacing any hook installed earlier.
+ */
+void signal_set_audit_hook(SignalFfiAuditHook hook);
+
+/**
+ * Removes the current audit hook, if any.
+ */
+void signal_clear_audit_hook(void);
+
 void signal_init_logger(SignalLogLevel max_level, SignalFfiLogger logger);
 
+/**
+ * Reports allocation statistics as JSON.
+ *
+ * Allocations are attributed to the entry point that was running when they were made.
+ */
+SignalFfiError *signal_memory_stats(const char **out);
+
+SignalFfiError *signal_bridge_context_destroy(SignalBridgeContext *p);
+
+SignalFfiError *signal_bridge_context_clone(SignalBridgeContext **new_obj,
+                                            const SignalBridgeContext *obj);
+
+SignalFfiError *signal_bridge_context_new(SignalBridgeContext **out, const char *label);
+
+SignalFfiError *signal_bridge_context_set_max_log_level(const SignalBridgeContext *context,
+                                                        uint32_t level);
+
+SignalFfiError *signal_bridge_context_enter(const SignalBridgeContext *context);
+
+SignalFfiError *signal_bridge_context_exit(const SignalBridgeContext *context);
+
+SignalFfiError *signal_bridge_context_get_metrics(const char **out,
+                                                  const SignalBridgeContext *context);
+
+SignalFfiError *signal_bridge_context_reset_metrics(const SignalBridgeContext *context);
+
 SignalFfiError *signal_aes256_gcm_siv_destroy(SignalAes256GcmSiv *p);
 
 SignalFfiError *signal_aes256_ctr32_destroy(SignalAes256Ctr32 *p);
@@ -239,6 +431,49 @@ SignalFfiError *signal_aes256_gcm_decryption_destroy(SignalAes256GcmDecryption *
 SignalFfiError *signal_aes256_gcm_decryption_clone(SignalAes256GcmDecryption **new_obj,
                                                    const SignalAes256GcmDecryption *obj);
 
+SignalFfiError *signal_aes256_key_wrap_destroy(SignalAes256KeyWrap *p);
+
+SignalFfiError *signal_aes256_key_wrap_clone(SignalAes256KeyWrap **new_obj,
+                                             const SignalAes256KeyWrap *obj);
+
+SignalFfiError *signal_storage_service_key_destroy(SignalStorageServiceKey *p);
+
+SignalFfiError *signal_storage_service_key_clone(SignalStorageServiceKey **new_obj,
+                                                 const SignalStorageServiceKey *obj);
+
+SignalFfiError *signal_call_link_root_key_destroy(SignalCallLinkRootKey *p);
+
+SignalFfiError *signal_call_link_root_key_clone(SignalCallLinkRootKey **new_obj,
+                                                const SignalCallLinkRootKey *obj);
+
+SignalFfiError *signal_argon2id_params_destroy(SignalArgon2idParams *p);
+
+SignalFfiError *signal_argon2id_params_clone(SignalArgon2idParams **new_obj,
+                                             const SignalArgon2idParams *obj);
+
+SignalFfiError *signal_crc32_destroy(SignalCrc32 *p);
+
+SignalFfiError *signal_crc32_clone(SignalCrc32 **new_obj, const SignalCrc32 *obj);
+
+SignalFfiError *signal_incremental_mac_validator_destroy(SignalIncrementalMacValidator *p);
+
+SignalFfiError *signal_scrypt_params_destroy(SignalScryptParams *p);
+
+SignalFfiError *signal_scrypt_params_clone(SignalScryptParams **new_obj,
+                                           const SignalScryptParams *obj);
+
+SignalFfiError *signal_sha256_destroy(SignalSha256 *p);
+
+SignalFfiError *signal_sha256_clone(SignalSha256 **new_obj, const SignalSha256 *obj);
+
+SignalFfiError *signal_sha512_destroy(SignalSha512 *p);
+
+SignalFfiError *signal_sha512_clone(SignalSha512 **new_obj, const SignalSha512 *obj);
+
+SignalFfiError *signal_sticker_cipher_destroy(SignalStickerCipher *p);
+
+SignalFfiError *signal_sticker_decryption_destroy(SignalStickerDecryption *p);
+
 SignalFfiError *signal_aes256_ctr32_new(SignalAes256Ctr32 **out,
                                         const unsigned char *key,
                                         size_t key_len,
@@ -313,12 +548,447 @@ SignalFfiError *signal_aes256_gcm_siv_decrypt(const unsigned char **out,
                                               const unsigned char *associated_data,
                                               size_t associated_data_len);
 
+SignalFfiError *signal_crc32_new(SignalCrc32 **out);
+
+SignalFfiError *signal_crc32_update(SignalCrc32 *crc, const unsigned char *input, size_t input_len);
+
+SignalFfiError *signal_crc32_finalize(uint32_t *out, SignalCrc32 *crc);
+
+SignalFfiError *signal_crc32_digest(uint32_t *out, const unsigned char *input, size_t input_len);
+
+SignalFfiError *signal_incremental_mac_validator_new(SignalIncrementalMacValidator **out,
+                                                     const unsigned char *key,
+                                                     size_t key_len,
+                                                     uint32_t chunk_size,
+                                                     const unsigned char *digest,
+                                                     size_t digest_len);
+
+SignalFfiError *signal_incremental_mac_validator_update(bool *out,
+                                                        SignalIncrementalMacValidator *validator,
+                                                        const unsigned char *data,
+                                                        size_t data_len);
+
+SignalFfiError *signal_incremental_mac_validator_finalize(bool *out,
+                                                          SignalIncrementalMacValidator *validator);
+
+SignalFfiError *signal_incremental_mac_validator_get_validated_length(uint64_t *out,
+                                                                      const SignalIncrementalMacValidator *obj);
+
+SignalFfiError *signal_sticker_cipher_new(SignalStickerCipher **out,
+                                          const unsigned char *pack_key,
+                                          size_t pack_key_len);
+
+SignalFfiError *signal_sticker_cipher_decrypt(const unsigned char **out,
+                                              size_t *out_len,
+                                              const SignalStickerCipher *cipher,
+                                              const unsigned char *data,
+                                              size_t data_len);
+
+SignalFfiError *signal_sticker_decryption_new(SignalStickerDecryption **out,
+                                              const SignalStickerCipher *cipher);
+
+SignalFfiError *signal_sticker_decryption_update(const unsigned char **out,
+                                                 size_t *out_len,
+                                                 SignalStickerDecryption *decryption,
+                                                 const unsigned char *data,
+                                                 size_t data_len);
+
+SignalFfiError *signal_sticker_decryption_finalize(const unsigned char **out,
+                                                   size_t *out_len,
+                                                   SignalStickerDecryption *decryption);
+
+SignalFfiError *signal_sha256_new(SignalSha256 **out);
+
+SignalFfiError *signal_sha256_update(SignalSha256 *sha256,
+                                     const unsigned char *input,
+                                     size_t input_len);
+
+SignalFfiError *signal_sha256_finalize(const unsigned char **out,
+                                       size_t *out_len,
+                                       SignalSha256 *sha256);
+
+SignalFfiError *signal_sha256_digest(const unsigned char **out,
+                                     size_t *out_len,
+                                     const unsigned char *input,
+                                     size_t input_len);
+
+SignalFfiError *signal_sha512_new(SignalSha512 **out);
+
+SignalFfiError *signal_sha512_update(SignalSha512 *sha512,
+                                     const unsigned char *input,
+                                     size_t input_len);
+
+SignalFfiError *signal_sha512_finalize(const unsigned char **out,
+                                       size_t *out_len,
+                                       SignalSha512 *sha512);
+
+SignalFfiError *signal_sha512_digest(const unsigned char **out,
+                                     size_t *out_len,
+                                     const unsigned char *input,
+                                     size_t input_len);
+
+SignalFfiError *signal_argon2id_params_new(SignalArgon2idParams **out,
+                                           uint32_t memory_kib,
+                                           uint32_t iterations,
+                                           uint32_t parallelism);
+
+/**
+ * Runs the KDF once to pick the iterations that take about `target_duration_ms` on this device.
+ */
+SignalFfiError *signal_argon2id_params_calibrate(SignalArgon2idParams **out,
+                                                 uint32_t target_duration_ms,
+                                                 uint32_t memory_kib,
+                                                 uint32_t parallelism);
+
+SignalFfiError *signal_argon2id_params_get_memory_kib(uint32_t *out,
+                                                      const SignalArgon2idParams *obj);
+
+SignalFfiError *signal_argon2id_params_get_iterations(uint32_t *out,
+                                                      const SignalArgon2idParams *obj);
+
+SignalFfiError *signal_argon2id_params_get_parallelism(uint32_t *out,
+                                                       const SignalArgon2idParams *obj);
+
+SignalFfiError *signal_argon2id_params_derive(const unsigned char **out,
+                                              size_t *out_len,
+                                              const SignalArgon2idParams *params,
+                                              const unsigned char *password,
+                                              size_t password_len,
+                                              const unsigned char *salt,
+                                              size_t salt_len,
+                                              uint32_t output_length);
+
+SignalFfiError *signal_scrypt_params_new(SignalScryptParams **out,
+                                         uint8_t log_n,
+                                         uint32_t r,
+                                         uint32_t p);
+
+/**
+ * Runs the KDF once to pick the cost that takes about `target_duration_ms` on this device.
+ */
+SignalFfiError *signal_scrypt_params_calibrate(SignalScryptParams **out,
+                                               uint32_t target_duration_ms,
+                                               uint32_t r,
+                                               uint32_t p);
+
+SignalFfiError *signal_scrypt_params_get_log_n(uint8_t *out, const SignalScryptParams *obj);
+
+SignalFfiError *signal_scrypt_params_get_r(uint32_t *out, const SignalScryptParams *obj);
+
+SignalFfiError *signal_scrypt_params_get_p(uint32_t *out, const SignalScryptParams *obj);
+
+SignalFfiError *signal_scrypt_params_derive(const unsigned char **out,
+                                            size_t *out_len,
+                                            const SignalScryptParams *params,
+                                            const unsigned char *password,
+                                            size_t password_len,
+                                            const unsigned char *salt,
+                                            size_t salt_len,
+                                            uint32_t output_length);
+
+SignalFfiError *signal_aes256_key_wrap_new(SignalAes256KeyWrap **out,
+                                           const unsigned char *key,
+                                           size_t key_len);
+
+SignalFfiError *signal_aes256_key_wrap_wrap(const unsigned char **out,
+                                            size_t *out_len,
+                                            const SignalAes256KeyWrap *kw,
+                                            const unsigned char *key,
+                                            size_t key_len);
+
+SignalFfiError *signal_aes256_key_wrap_unwrap(const unsigned char **out,
+                                              size_t *out_len,
+                                              const SignalAes256KeyWrap *kw,
+                                              const unsigned char *wrapped,
+                                              size_t wrapped_len);
+
+SignalFfiError *signal_storage_service_key_new(SignalStorageServiceKey **out,
+                                               const unsigned char *storage_key,
+                                               size_t storage_key_len);
+
+SignalFfiError *signal_storage_service_key_from_master_key(SignalStorageServiceKey **out,
+                                                           const unsigned char *master_key,
+                                                           size_t master_key_len);
+
+SignalFfiError *signal_storage_service_key_get_storage_key(const unsigned char **out,
+                                                           size_t *out_len,
+                                                           const SignalStorageServiceKey *key);
+
+SignalFfiError *signal_storage_service_key_manifest_key(const unsigned char **out,
+                                                        size_t *out_len,
+                                                        const SignalStorageServiceKey *key,
+                                                        uint64_t version);
+
+SignalFfiError *signal_storage_service_key_record_key(const unsigned char **out,
+                                                      size_t *out_len,
+                                                      const SignalStorageServiceKey *key,
+                                                      const unsigned char *raw_id,
+                                                      size_t raw_id_len);
+
+SignalFfiError *signal_storage_service_key_encrypt_manifest(const unsigned char **out,
+                                                            size_t *out_len,
+                                                            const SignalStorageServiceKey *key,
+                                                            uint64_t version,
+                                                            const unsigned char *plaintext,
+                                                            size_t plaintext_len,
+                                                            const SignalRng *rng);
+
+SignalFfiError *signal_storage_service_key_decrypt_manifest(const unsigned char **out,
+                                                            size_t *out_len,
+                                                            const SignalStorageServiceKey *key,
+                                                            uint64_t version,
+                                                            const unsigned char *ciphertext,
+                                                            size_t ciphertext_len);
+
+SignalFfiError *signal_storage_service_key_encrypt_record(const unsigned char **out,
+                                                          size_t *out_len,
+                                                          const SignalStorageServiceKey *key,
+                                                          const unsigned char *raw_id,
+                                                          size_t raw_id_len,
+                                                          const unsigned char *plaintext,
+                                                          size_t plaintext_len,
+                                                          const SignalRng *rng);
+
+SignalFfiError *signal_storage_service_key_decrypt_record(const unsigned char **out,
+                                                          size_t *out_len,
+                                                          const SignalStorageServiceKey *key,
+                                                          const unsigned char *raw_id,
+                                                          size_t raw_id_len,
+                                                          const unsigned char *ciphertext,
+                                                          size_t ciphertext_len);
+
+SignalFfiError *signal_call_link_root_key_generate(SignalCallLinkRootKey **out,
+                                                   const SignalRng *rng);
+
+SignalFfiError *signal_call_link_root_key_from_bytes(SignalCallLinkRootKey **out,
+                                                     const unsigned char *bytes,
+                                                     size_t bytes_len);
+
+SignalFfiError *signal_call_link_root_key_parse(SignalCallLinkRootKey **out, const char *text);
+
+SignalFfiError *signal_call_link_root_key_get_bytes(const unsigned char **out,
+                                                    size_t *out_len,
+                                                    const SignalCallLinkRootKey *key);
+
+SignalFfiError *signal_call_link_root_key_to_string(const char **out,
+                                                    const SignalCallLinkRootKey *key);
+
+SignalFfiError *signal_call_link_root_key_derive_room_id(const unsigned char **out,
+                                                         size_t *out_len,
+                                                         const SignalCallLinkRootKey *key);
+
+SignalFfiError *signal_call_link_root_key_derive_era_key(const unsigned char **out,
+                                                         size_t *out_len,
+                                                         const SignalCallLinkRootKey *key,
+                                                         const char *era_id);
+
+SignalFfiError *signal_group_call_derive_era_key(const unsigned char **out,
+                                                 size_t *out_len,
+                                                 const unsigned char *group_master_key,
+                                                 size_t group_master_key_len,
+                                                 const char *era_id);
+
+SignalFfiError *signal_list_functions(const char **out);
+
+SignalFfiError *signal_key_transparency_search_result_destroy(SignalKeyTransparencySearchResult *p);
+
+SignalFfiError *signal_key_transparency_search_result_get_value(const unsigned char **out,
+                                                                size_t *out_len,
+                                                                const SignalKeyTransparencySearchResult *obj);
+
+SignalFfiError *signal_key_transparency_search_result_get_tree_head(const unsigned char **out,
+                                                                    size_t *out_len,
+                                                                    const SignalKeyTransparencySearchResult *obj);
+
+SignalFfiError *signal_key_transparency_search_result_get_monitoring_data(const unsigned char **out,
+                                                                          size_t *out_len,
+                                                                          const SignalKeyTransparencySearchResult *obj);
+
+SignalFfiError *signal_key_transparency_monitor_result_destroy(SignalKeyTransparencyMonitorResult *p);
+
+SignalFfiError *signal_key_transparency_monitor_result_is_unchanged(bool *out,
+                                                                    const SignalKeyTransparencyMonitorResult *obj);
+
+SignalFfiError *signal_key_transparency_monitor_result_get_tree_head(const unsigned char **out,
+                                                                     size_t *out_len,
+                                                                     const SignalKeyTransparencyMonitorResult *obj);
+
+SignalFfiError *signal_key_transparency_monitor_result_get_monitoring_data(const unsigned char **out,
+                                                                           size_t *out_len,
+                                                                           const SignalKeyTransparencyMonitorResult *obj);
+
+SignalFfiError *signal_key_transparency_verify_search(SignalKeyTransparencySearchResult **out,
+                                                      const SignalPublicKey *log_key,
+                                                      const unsigned char *search_key,
+                                                      size_t search_key_len,
+                                                      const unsigned char *response,
+                                                      size_t response_len,
+                                                      const unsigned char *last_tree_head,
+                                                      size_t last_tree_head_len);
+
+SignalFfiError *signal_key_transparency_verify_monitor(SignalKeyTransparencyMonitorResult **out,
+                                                       const SignalPublicKey *log_key,
+                                                       const unsigned char *search_key,
+                                                       size_t search_key_len,
+                                                       const unsigned char *response,
+                                                       size_t response_len,
+                                                       const unsigned char *last_tree_head,
+                                                       size_t last_tree_head_len,
+                                                       const unsigned char *monitoring_data,
+                                                       size_t monitoring_data_len);
+
+SignalFfiError *signal_key_transparency_verify_distinguished(const unsigned char **out,
+                                                             size_t *out_len,
+                                                             const SignalPublicKey *log_key,
+                                                             const unsigned char *response,
+                                                             size_t response_len,
+                                                             const unsigned char *last_tree_head,
+                                                             size_t last_tree_head_len);
+
+SignalFfiError *signal_set_max_buffer_length(uint64_t limit);
+
+SignalFfiError *signal_get_max_buffer_length(uint64_t *out);
+
+SignalFfiError *signal_frame_writer_destroy(SignalFrameWriter *p);
+
+SignalFfiError *signal_frame_writer_clone(SignalFrameWriter **new_obj, const SignalFrameWriter *obj);
+
+SignalFfiError *signal_frame_reader_destroy(SignalFrameReader *p);
+
+SignalFfiError *signal_frame_reader_clone(SignalFrameReader **new_obj, const SignalFrameReader *obj);
+
+SignalFfiError *signal_frame_writer_new(SignalFrameWriter **out,
+                                        const unsigned char *backup_key,
+                                        size_t backup_key_len,
+                                        bool compressed,
+                                        const SignalRng *rng);
+
+SignalFfiError *signal_frame_writer_append(const unsigned char **out,
+                                           size_t *out_len,
+                                           SignalFrameWriter *writer,
+                                           const unsigned char *frame,
+                                           size_t frame_len);
+
+SignalFfiError *signal_frame_writer_finish(const unsigned char **out,
+                                           size_t *out_len,
+                                           SignalFrameWriter *writer);
+
+SignalFfiError *signal_frame_reader_new(SignalFrameReader **out,
+                                        const unsigned char *backup_key,
+                                        size_t backup_key_len);
+
+SignalFfiError *signal_frame_reader_push(SignalFrameReader *reader,
+                                         const unsigned char *chunk,
+                                         size_t chunk_len);
+
+/**
+ * Returns null when more input is needed or the stream has ended.
+ */
+SignalFfiError *signal_frame_reader_next_frame(const unsigned char **out,
+                                               size_t *out_len,
+                                               SignalFrameReader *reader);
+
+SignalFfiError *signal_frame_reader_is_finished(bool *out, const SignalFrameReader *reader);
+
+SignalFfiError *signal_frame_reader_finish(SignalFrameReader *reader);
+
+SignalFfiError *signal_backup_builder_destroy(SignalBackupBuilder *p);
+
+SignalFfiError *signal_backup_builder_new(SignalBackupBuilder **out,
+                                          const unsigned char *backup_key,
+                                          size_t backup_key_len,
+                                          bool compressed,
+                                          uint64_t backup_time_ms,
+                                          const SignalRng *rng);
+
+SignalFfiError *signal_backup_builder_add_account_data(const unsigned char **out,
+                                                       size_t *out_len,
+                                                       SignalBackupBuilder *builder,
+                                                       const unsigned char *profile_key,
+                                                       size_t profile_key_len,
+                                                       const char *username,
+                                                       const char *given_name,
+                                                       const char *family_name);
+
+SignalFfiError *signal_backup_builder_add_self_recipient(const unsigned char **out,
+                                                         size_t *out_len,
+                                                         SignalBackupBuilder *builder,
+                                                         uint64_t id);
+
+SignalFfiError *signal_backup_builder_add_contact(const unsigned char **out,
+                                                  size_t *out_len,
+                                                  SignalBackupBuilder *builder,
+                                                  uint64_t id,
+                                                  const char *uuid,
+                                                  const char *e164);
+
+SignalFfiError *signal_backup_builder_add_group(const unsigned char **out,
+                                                size_t *out_len,
+                                                SignalBackupBuilder *builder,
+                                                uint64_t id,
+                                                const unsigned char *master_key,
+                                                size_t master_key_len);
+
+SignalFfiError *signal_backup_builder_add_chat(const unsigned char **out,
+                                               size_t *out_len,
+                                               SignalBackupBuilder *builder,
+                                               uint64_t id,
+                                               uint64_t recipient_id,
+                                               bool archived,
+                                               uint32_t pinned_order,
+                                               uint64_t expiration_timer_ms);
+
+SignalFfiError *signal_backup_builder_add_chat_item(const unsigned char **out,
+                                                    size_t *out_len,
+                                                    SignalBackupBuilder *builder,
+                                                    uint64_t chat_id,
+                                                    uint64_t author_id,
+                                                    uint64_t date_sent,
+                                                    const char *text);
+
+SignalFfiError *signal_backup_builder_finish(const unsigned char **out,
+                                             size_t *out_len,
+                                             SignalBackupBuilder *builder);
+
+SignalFfiError *signal_attachment_pointer_destroy(SignalAttachmentPointer *p);
+
+SignalFfiError *signal_attachment_pointer_clone(SignalAttachmentPointer **new_obj,
+                                                const SignalAttachmentPointer *obj);
+
 SignalFfiError *signal_ciphertext_message_destroy(SignalCiphertextMessage *p);
 
+SignalFfiError *signal_content_destroy(SignalContent *p);
+
+SignalFfiError *signal_content_clone(SignalContent **new_obj, const SignalContent *obj);
+
+SignalFfiError *signal_data_message_builder_destroy(SignalDataMessageBuilder *p);
+
+SignalFfiError *signal_deferred_decryption_result_destroy(SignalDeferredDecryptionResult *p);
+
 SignalFfiError *signal_fingerprint_destroy(SignalFingerprint *p);
 
 SignalFfiError *signal_fingerprint_clone(SignalFingerprint **new_obj, const SignalFingerprint *obj);
 
+SignalFfiError *signal_notification_decryption_result_destroy(SignalNotificationDecryptionResult *p);
+
+SignalFfiError *signal_outgoing_envelope_destroy(SignalOutgoingEnvelope *p);
+
+SignalFfiError *signal_outgoing_envelope_clone(SignalOutgoingEnvelope **new_obj,
+                                               const SignalOutgoingEnvelope *obj);
+
+SignalFfiError *signal_outgoing_envelope_deserialize(SignalOutgoingEnvelope **p,
+                                                     const unsigned char *data,
+                                                     size_t data_len);
+
+SignalFfiError *signal_outgoing_envelope_serialize(const unsigned char **out,
+                                                   size_t *out_len,
+                                                   const SignalOutgoingEnvelope *obj);
+
+SignalFfiError *signal_padding_policy_destroy(SignalPaddingPolicy *p);
+
+SignalFfiError *signal_padding_policy_clone(SignalPaddingPolicy **new_obj,
+                                            const SignalPaddingPolicy *obj);
+
 SignalFfiError *signal_pre_key_bundle_destroy(SignalPreKeyBundle *p);
 
 SignalFfiError *signal_pre_key_bundle_clone(SignalPreKeyBundle **new_obj,
@@ -343,6 +1013,13 @@ SignalFfiError *signal_address_destroy(SignalProtocolAddress *p);
 SignalFfiError *signal_address_clone(SignalProtocolAddress **new_obj,
                                      const SignalProtocolAddress *obj);
 
+SignalFfiError *signal_provisioning_cipher_destroy(SignalProvisioningCipher *p);
+
+SignalFfiError *signal_provisioning_uuid_destroy(SignalProvisioningUuid *p);
+
+SignalFfiError *signal_provisioning_uuid_clone(SignalProvisioningUuid **new_obj,
+                                               const SignalProvisioningUuid *obj);
+
 SignalFfiError *signal_publickey_destroy(SignalPublicKey *p);
 
 SignalFfiError *signal_publickey_clone(SignalPublicKey **new_obj, const SignalPublicKey *obj);
@@ -352,6 +1029,8 @@ SignalFfiError *signal_sender_certificate_destroy(SignalSenderCertificate *p);
 SignalFfiError *signal_sender_certificate_clone(SignalSenderCertificate **new_obj,
                                                 const SignalSenderCertificate *obj);
 
+SignalFfiError *signal_sender_certificate_provider_destroy(SignalSenderCertificateProvider *p);
+
 SignalFfiError *signal_sender_key_distribution_message_destroy(SignalSenderKeyDistributionMessage *p);
 
 SignalFfiError *signal_sender_key_distribution_message_clone(SignalSenderKeyDistributionMessage **new_obj,
@@ -382,6 +1061,14 @@ SignalFfiError *signal_session_record_destroy(SignalSessionRecord *p);
 SignalFfiError *signal_session_record_clone(SignalSessionRecord **new_obj,
                                             const SignalSessionRecord *obj);
 
+SignalFfiError *signal_session_record_deserialize(SignalSessionRecord **p,
+                                                  const unsigned char *data,
+                                                  size_t data_len);
+
+SignalFfiError *signal_session_record_serialize(const unsigned char **out,
+                                                size_t *out_len,
+                                                const SignalSessionRecord *obj);
+
 SignalFfiError *signal_message_destroy(SignalMessage *p);
 
 SignalFfiError *signal_message_clone(SignalMessage **new_obj, const SignalMessage *obj);
@@ -442,7 +1129,7 @@ SignalFfiError *signal_privatekey_serialize(const unsigned char **out,
                                             size_t *out_len,
                                             const SignalPrivateKey *obj);
 
-SignalFfiError *signal_privatekey_generate(SignalPrivateKey **out);
+SignalFfiError *signal_privatekey_generate(SignalPrivateKey **out, const SignalRng *rng);
 
 SignalFfiError *signal_privatekey_get_public_key(SignalPublicKey **out, const SignalPrivateKey *k);
 
@@ -450,18 +1137,188 @@ SignalFfiError *signal_privatekey_sign(const unsigned char **out,
                                        size_t *out_len,
                                        const SignalPrivateKey *key,
                                        const unsigned char *message,
-                                       size_t message_len);
+                                       size_t message_len,
+                                       const SignalRng *rng);
 
 SignalFfiError *signal_privatekey_agree(const unsigned char **out,
                                         size_t *out_len,
                                         const SignalPrivateKey *private_key,
                                         const SignalPublicKey *public_key);
 
+SignalFfiError *signal_publickey_seal(const unsigned char **out,
+                                      size_t *out_len,
+                                      const SignalPublicKey *key,
+                                      const unsigned char *plaintext,
+                                      size_t plaintext_len,
+                                      const SignalRng *rng);
+
+SignalFfiError *signal_privatekey_open(const unsigned char **out,
+                                       size_t *out_len,
+                                       const SignalPrivateKey *key,
+                                       const unsigned char *sealed,
+                                       size_t sealed_len);
+
 SignalFfiError *signal_identitykeypair_serialize(const unsigned char **out,
                                                  size_t *out_len,
                                                  const SignalPublicKey *public_key,
                                                  const SignalPrivateKey *private_key);
 
+SignalFfiError *signal_profile_key_derive_access_key(const unsigned char **out,
+                                                     size_t *out_len,
+                                                     const unsigned char *profile_key,
+                                                     size_t profile_key_len);
+
+SignalFfiError *signal_profile_key_blind_service_id(const unsigned char **out,
+                                                    size_t *out_len,
+                                                    const unsigned char *profile_key,
+                                                    size_t profile_key_len,
+                                                    const char *service_id);
+
+SignalFfiError *signal_padding_policy_none(SignalPaddingPolicy **out);
+
+SignalFfiError *signal_padding_policy_bucketed(SignalPaddingPolicy **out, uint32_t bucket_size);
+
+SignalFfiError *signal_padding_policy_padme(SignalPaddingPolicy **out);
+
+SignalFfiError *signal_padding_policy_padded_length(uint32_t *out,
+                                                    const SignalPaddingPolicy *policy,
+                                                    uint32_t len);
+
+SignalFfiError *signal_padding_policy_pad(const unsigned char **out,
+                                          size_t *out_len,
+                                          const SignalPaddingPolicy *policy,
+                                          const unsigned char *message,
+                                          size_t message_len);
+
+SignalFfiError *signal_padding_policy_unpad(const unsigned char **out,
+                                            size_t *out_len,
+                                            const SignalPaddingPolicy *policy,
+                                            const unsigned char *padded,
+                                            size_t padded_len);
+
+SignalFfiError *signal_attachment_pointer_new(SignalAttachmentPointer **out,
+                                             uint32_t cdn_number,
+                                             const char *cdn_key,
+                                             const char *content_type,
+                                             const unsigned char *key,
+                                             size_t key_len,
+                                             const unsigned char *digest,
+                                             size_t digest_len,
+                                             uint32_t size,
+                                             const char *file_name,
+                                             const char *caption,
+                                             uint32_t width,
+                                             uint32_t height,
+                                             uint64_t upload_timestamp);
+
+SignalFfiError *signal_data_message_builder_new(SignalDataMessageBuilder **out, uint64_t timestamp);
+
+SignalFfiError *signal_data_message_builder_set_body(SignalDataMessageBuilder *builder,
+                                                     const char *body);
+
+SignalFfiError *signal_data_message_builder_add_attachment(SignalDataMessageBuilder *builder,
+                                                           const SignalAttachmentPointer *attachment);
+
+SignalFfiError *signal_data_message_builder_set_quote(SignalDataMessageBuilder *builder,
+                                                      uint64_t id,
+                                                      const char *author_uuid,
+                                                      const char *text);
+
+SignalFfiError *signal_data_message_builder_set_expire_timer(SignalDataMessageBuilder *builder,
+                                                             uint32_t seconds);
+
+SignalFfiError *signal_data_message_builder_set_profile_key(SignalDataMessageBuilder *builder,
+                                                            const unsigned char *profile_key,
+                                                            size_t profile_key_len);
+
+SignalFfiError *signal_data_message_builder_build(SignalContent **out,
+                                                  const SignalDataMessageBuilder *builder);
+
+SignalFfiError *signal_content_new_receipt_message(SignalContent **out,
+                                                   uint32_t receipt_type,
+                                                   const unsigned char *timestamps,
+                                                   size_t timestamps_len);
+
+SignalFfiError *signal_content_new_typing_message(SignalContent **out,
+                                                  uint64_t timestamp,
+                                                  bool started,
+                                                  const unsigned char *group_id,
+                                                  size_t group_id_len);
+
+SignalFfiError *signal_content_get_serialized(const unsigned char **out,
+                                              size_t *out_len,
+                                              const SignalContent *content);
+
+SignalFfiError *signal_payment_address_sign(const unsigned char **out,
+                                            size_t *out_len,
+                                            const SignalPublicKey *identity_public_key,
+                                            const SignalPrivateKey *identity_private_key,
+                                            const unsigned char *address,
+                                            size_t address_len,
+                                            const SignalRng *rng);
+
+SignalFfiError *signal_payment_address_verify(bool *out,
+                                              const SignalPublicKey *identity_key,
+                                              const unsigned char *address,
+                                              size_t address_len,
+                                              const unsigned char *signature,
+                                              size_t signature_len);
+
+SignalFfiError *signal_provisioning_cipher_new(SignalProvisioningCipher **out,
+                                               const SignalRng *rng);
+
+SignalFfiError *signal_provisioning_cipher_get_public_key(SignalPublicKey **out,
+                                                          const SignalProvisioningCipher *cipher);
+
+SignalFfiError *signal_provisioning_cipher_decrypt(const unsigned char **out,
+                                                   size_t *out_len,
+                                                   const SignalProvisioningCipher *cipher,
+                                                   const unsigned char *envelope,
+                                                   size_t envelope_len);
+
+SignalFfiError *signal_provisioning_cipher_encrypt(const unsigned char **out,
+                                                   size_t *out_len,
+                                                   const SignalPublicKey *their_public_key,
+                                                   const unsigned char *message,
+                                                   size_t message_len,
+                                                   const SignalRng *rng);
+
+SignalFfiError *signal_provisioning_uuid_new(SignalProvisioningUuid **out, const char *uuid);
+
+SignalFfiError *signal_provisioning_uuid_deserialize(SignalProvisioningUuid **p,
+                                                     const unsigned char *data,
+                                                     size_t data_len);
+
+SignalFfiError *signal_provisioning_uuid_get_uuid(const char **out,
+                                                  const SignalProvisioningUuid *obj);
+
+SignalFfiError *signal_provisioning_uuid_get_serialized(const unsigned char **out,
+                                                        size_t *out_len,
+                                                        const SignalProvisioningUuid *obj);
+
+SignalFfiError *signal_outgoing_envelope_new(SignalOutgoingEnvelope **out,
+                                             const SignalProtocolAddress *destination,
+                                             uint64_t timestamp,
+                                             uint32_t content_hint,
+                                             const unsigned char *payload,
+                                             size_t payload_len);
+
+SignalFfiError *signal_outgoing_envelope_get_destination(SignalProtocolAddress **out,
+                                                         const SignalOutgoingEnvelope *obj);
+
+SignalFfiError *signal_outgoing_envelope_get_timestamp(uint64_t *out,
+                                                       const SignalOutgoingEnvelope *obj);
+
+SignalFfiError *signal_outgoing_envelope_get_content_hint(uint32_t *out,
+                                                          const SignalOutgoingEnvelope *obj);
+
+SignalFfiError *signal_outgoing_envelope_get_payload(const unsigned char **out,
+                                                     size_t *out_len,
+                                                     const SignalOutgoingEnvelope *obj);
+
+SignalFfiError *signal_outgoing_envelope_get_serialized_version(uint32_t *out,
+                                                                const SignalOutgoingEnvelope *obj);
+
 SignalFfiError *signal_fingerprint_new(SignalFingerprint **out,
                                        uint32_t iterations,
                                        uint32_t version,
@@ -498,6 +1355,8 @@ SignalFfiError *signal_message_get_serialized(const unsigned char **out,
 
 SignalFfiError *signal_message_get_counter(uint32_t *out, const SignalMessage *obj);
 
+SignalFfiError *signal_message_get_previous_counter(uint32_t *out, const SignalMessage *obj);
+
 SignalFfiError *signal_message_get_message_version(uint32_t *out, const SignalMessage *obj);
 
 SignalFfiError *signal_message_new(SignalMessage **out,
@@ -578,12 +1437,16 @@ SignalFfiError *signal_sender_key_message_get_key_id(uint32_t *out,
 SignalFfiError *signal_sender_key_message_get_iteration(uint32_t *out,
                                                         const SignalSenderKeyMessage *obj);
 
+SignalFfiError *signal_sender_key_message_get_message_version(uint32_t *out,
+                                                              const SignalSenderKeyMessage *obj);
+
 SignalFfiError *signal_sender_key_message_new(SignalSenderKeyMessage **out,
                                               uint32_t key_id,
                                               uint32_t iteration,
                                               const unsigned char *ciphertext,
                                               size_t ciphertext_len,
-                                              const SignalPrivateKey *pk);
+                                              const SignalPrivateKey *pk,
+                                              const SignalRng *rng);
 
 SignalFfiError *signal_sender_key_message_verify_signature(bool *out,
                                                            const SignalSenderKeyMessage *skm,
@@ -651,6 +1514,17 @@ SignalFfiError *signal_pre_key_bundle_get_pre_key_public(SignalPublicKey **out,
 SignalFfiError *signal_pre_key_bundle_get_signed_pre_key_public(SignalPublicKey **out,
                                                                 const SignalPreKeyBundle *obj);
 
+SignalFfiError *signal_pre_key_bundle_get_min_session_version(uint32_t *out,
+                                                              const SignalPreKeyBundle *obj);
+
+SignalFfiError *signal_pre_key_bundle_get_max_session_version(uint32_t *out,
+                                                              const SignalPreKeyBundle *obj);
+
+SignalFfiError *signal_pre_key_bundle_with_session_versions(SignalPreKeyBundle **out,
+                                                            const SignalPreKeyBundle *bundle,
+                                                            uint32_t min_version,
+                                                            uint32_t max_version);
+
 SignalFfiError *signal_signed_pre_key_record_deserialize(SignalSignedPreKeyRecord **p,
                                                          const unsigned char *data,
                                                          size_t data_len);
@@ -699,6 +1573,9 @@ SignalFfiError *signal_pre_key_record_get_public_key(SignalPublicKey **out,
 SignalFfiError *signal_pre_key_record_get_private_key(SignalPrivateKey **out,
                                                       const SignalPreKeyRecord *obj);
 
+SignalFfiError *signal_pre_key_record_get_serialized_version(uint32_t *out,
+                                                             const SignalPreKeyRecord *obj);
+
 SignalFfiError *signal_pre_key_record_new(SignalPreKeyRecord **out,
                                           uint32_t id,
                                           const SignalPublicKey *pub_key,
@@ -726,8 +1603,26 @@ SignalFfiError *signal_sender_key_record_serialize(const unsigned char **out,
                                                    size_t *out_len,
                                                    const SignalSenderKeyRecord *obj);
 
+SignalFfiError *signal_sender_key_record_get_serialized_version(uint32_t *out,
+                                                                const SignalSenderKeyRecord *obj);
+
 SignalFfiError *signal_sender_key_record_new_fresh(SignalSenderKeyRecord **out);
 
+SignalFfiError *signal_sender_key_record_mark_distributed(SignalSenderKeyRecord *record,
+                                                          const SignalProtocolAddress *member);
+
+SignalFfiError *signal_sender_key_record_needs_distribution(bool *out,
+                                                            const SignalSenderKeyRecord *record,
+                                                            const SignalProtocolAddress *member);
+
+SignalFfiError *signal_sender_key_record_forget_distribution(bool *out,
+                                                             SignalSenderKeyRecord *record,
+                                                             const SignalProtocolAddress *member);
+
+SignalFfiError *signal_sender_key_record_max_states(uint32_t *out);
+
+SignalFfiError *signal_sender_key_record_max_message_keys_per_state(uint32_t *out);
+
 SignalFfiError *signal_server_certificate_deserialize(SignalServerCertificate **p,
                                                       const unsigned char *data,
                                                       size_t data_len);
@@ -753,7 +1648,8 @@ SignalFfiError *signal_server_certificate_get_key(SignalPublicKey **out,
 SignalFfiError *signal_server_certificate_new(SignalServerCertificate **out,
                                               uint32_t key_id,
                                               const SignalPublicKey *server_key,
-                                              const SignalPrivateKey *trust_root);
+                                              const SignalPrivateKey *trust_root,
+                                              const SignalRng *rng);
 
 SignalFfiError *signal_sender_certificate_deserialize(SignalSenderCertificate **p,
                                                       const unsigned char *data,
@@ -791,6 +1687,12 @@ SignalFfiError *signal_sender_certificate_validate(bool *out,
                                                    const SignalPublicKey *key,
                                                    uint64_t time);
 
+SignalFfiError *signal_sender_certificate_validate_with_grace_period(bool *out,
+                                                                     const SignalSenderCertificate *cert,
+                                                                     const SignalPublicKey *key,
+                                                                     uint64_t time,
+                                                                     uint64_t grace_period);
+
 SignalFfiError *signal_sender_certificate_get_server_certificate(SignalServerCertificate **out,
                                                                  const SignalSenderCertificate *cert);
 
@@ -801,7 +1703,8 @@ SignalFfiError *signal_sender_certificate_new(SignalSenderCertificate **out,
                                               const SignalPublicKey *sender_key,
                                               uint64_t expiration,
                                               const SignalServerCertificate *signer_cert,
-                                              const SignalPrivateKey *signer_key);
+                                              const SignalPrivateKey *signer_key,
+                                              const SignalRng *rng);
 
 SignalFfiError *signal_unidentified_sender_message_content_deserialize(SignalUnidentifiedSenderMessageContent **p,
                                                                        const unsigned char *data,
@@ -827,17 +1730,32 @@ SignalFfiError *signal_ciphertext_message_serialize(const unsigned char **out,
                                                     size_t *out_len,
                                                     const SignalCiphertextMessage *obj);
 
+SignalFfiError *signal_ciphertext_message_deserialize(SignalCiphertextMessage **out,
+                                                      uint8_t message_type,
+                                                      const unsigned char *data,
+                                                      size_t data_len);
+
+SignalFfiError *signal_ciphertext_message_get_message_version(uint32_t *out,
+                                                              const SignalCiphertextMessage *obj);
+
+SignalFfiError *signal_ciphertext_message_get_counter(uint32_t *out,
+                                                      const SignalCiphertextMessage *obj);
+
+SignalFfiError *signal_ciphertext_message_get_previous_counter(uint32_t *out,
+                                                               const SignalCiphertextMessage *obj);
+
+SignalFfiError *signal_session_record_get_session_version(uint32_t *out,
+                                                          const SignalSessionRecord *s);
+
 SignalFfiError *signal_session_record_archive_current_state(SignalSessionRecord *session_record);
 
-SignalFfiError *signal_session_record_has_current_state(bool *out, const SignalSessionRecord *obj);
+SignalFfiError *signal_session_record_max_archived_states(uint32_t *out);
 
-SignalFfiError *signal_session_record_deserialize(SignalSessionRecord **p,
-                                                  const unsigned char *data,
-                                                  size_t data_len);
+SignalFfiError *signal_session_record_max_receiver_chains(uint32_t *out);
 
-SignalFfiError *signal_session_record_serialize(const unsigned char **out,
-                                                size_t *out_len,
-                                                const SignalSessionRecord *obj);
+SignalFfiError *signal_session_record_max_message_keys_per_chain(uint32_t *out);
+
+SignalFfiError *signal_session_record_has_current_state(bool *out, const SignalSessionRecord *obj);
 
 SignalFfiError *signal_session_record_get_local_registration_id(uint32_t *out,
                                                                 const SignalSessionRecord *obj);
@@ -845,10 +1763,14 @@ SignalFfiError *signal_session_record_get_local_registration_id(uint32_t *out,
 SignalFfiError *signal_session_record_get_remote_registration_id(uint32_t *out,
                                                                  const SignalSessionRecord *obj);
 
+SignalFfiError *signal_session_record_get_serialized_version(uint32_t *out,
+                                                             const SignalSessionRecord *obj);
+
 SignalFfiError *signal_process_prekey_bundle(const SignalPreKeyBundle *bundle,
                                              const SignalProtocolAddress *protocol_address,
                                              const SignalSessionStore *session_store,
                                              const SignalIdentityKeyStore *identity_key_store,
+                                             const SignalRng *rng,
                                              void *ctx);
 
 SignalFfiError *signal_encrypt_message(SignalCiphertextMessage **out,
@@ -859,12 +1781,30 @@ SignalFfiError *signal_encrypt_message(SignalCiphertextMessage **out,
                                        const SignalIdentityKeyStore *identity_key_store,
                                        void *ctx);
 
+SignalFfiError *signal_encrypt_content(SignalCiphertextMessage **out,
+                                       const SignalContent *content,
+                                       const SignalPaddingPolicy *padding,
+                                       const SignalProtocolAddress *protocol_address,
+                                       const SignalSessionStore *session_store,
+                                       const SignalIdentityKeyStore *identity_key_store,
+                                       void *ctx);
+
+SignalFfiError *signal_encrypt_message_with_identity_override(SignalCiphertextMessage **out,
+                                                              const unsigned char *ptext,
+                                                              size_t ptext_len,
+                                                              const SignalProtocolAddress *protocol_address,
+                                                              const SignalPublicKey *approved_identity_key,
+                                                              const SignalSessionStore *session_store,
+                                                              const SignalIdentityKeyStore *identity_key_store,
+                                                              void *ctx);
+
 SignalFfiError *signal_decrypt_message(const unsigned char **out,
                                        size_t *out_len,
                                        const SignalMessage *message,
                                        const SignalProtocolAddress *protocol_address,
                                        const SignalSessionStore *session_store,
                                        const SignalIdentityKeyStore *identity_key_store,
+                                       const SignalRng *rng,
                                        void *ctx);
 
 SignalFfiError *signal_decrypt_pre_key_message(const unsigned char **out,
@@ -875,8 +1815,31 @@ SignalFfiError *signal_decrypt_pre_key_message(const unsigned char **out,
                                                const SignalIdentityKeyStore *identity_key_store,
                                                const SignalPreKeyStore *prekey_store,
                                                const SignalSignedPreKeyStore *signed_prekey_store,
+                                               const SignalRng *rng,
                                                void *ctx);
 
+SignalFfiError *signal_decrypt_message_with_identity_override(const unsigned char **out,
+                                                              size_t *out_len,
+                                                              const SignalMessage *message,
+                                                              const SignalProtocolAddress *protocol_address,
+                                                              const SignalPublicKey *approved_identity_key,
+                                                              const SignalSessionStore *session_store,
+                                                              const SignalIdentityKeyStore *identity_key_store,
+                                                              const SignalRng *rng,
+                                                              void *ctx);
+
+SignalFfiError *signal_decrypt_pre_key_message_with_identity_override(const unsigned char **out,
+                                                                      size_t *out_len,
+                                                                      const SignalPreKeySignalMessage *message,
+                                                                      const SignalProtocolAddress *protocol_address,
+                                                                      const SignalPublicKey *approved_identity_key,
+                                                                      const SignalSessionStore *session_store,
+                                                                      const SignalIdentityKeyStore *identity_key_store,
+                                                                      const SignalPreKeyStore *prekey_store,
+                                                                      const SignalSignedPreKeyStore *signed_prekey_store,
+                                                                      const SignalRng *rng,
+                                                                      void *ctx);
+
 SignalFfiError *signal_sealed_session_cipher_encrypt(const unsigned char **out,
                                                      size_t *out_len,
                                                      const SignalProtocolAddress *destination,
@@ -885,17 +1848,123 @@ SignalFfiError *signal_sealed_session_cipher_encrypt(const unsigned char **out,
                                                      size_t ptext_len,
                                                      const SignalSessionStore *session_store,
                                                      const SignalIdentityKeyStore *identity_key_store,
+                                                     const SignalRng *rng,
                                                      void *ctx);
 
+SignalFfiError *signal_sender_certificate_provider_new(SignalSenderCertificateProvider **out,
+                                                       const SignalSenderCertificate *certificate,
+                                                       uint64_t freshness_window);
+
+SignalFfiError *signal_sender_certificate_provider_get(SignalSenderCertificate **out,
+                                                       const SignalSenderCertificateProvider *provider,
+                                                       uint64_t valid_until,
+                                                       const SignalSenderCertificateSource *certificate_source,
+                                                       void *ctx);
+
+SignalFfiError *signal_sealed_session_cipher_encrypt_with_provider(const unsigned char **out,
+                                                                   size_t *out_len,
+                                                                   const SignalProtocolAddress *destination,
+                                                                   const SignalSenderCertificateProvider *provider,
+                                                                   const SignalSenderCertificateSource *certificate_source,
+                                                                   uint64_t timestamp,
+                                                                   const unsigned char *ptext,
+                                                                   size_t ptext_len,
+                                                                   const SignalSessionStore *session_store,
+                                                                   const SignalIdentityKeyStore *identity_key_store,
+                                                                   const SignalRng *rng,
+                                                                   void *ctx);
+
 SignalFfiError *signal_sealed_session_cipher_decrypt_to_usmc(SignalUnidentifiedSenderMessageContent **out,
                                                              const unsigned char *ctext,
                                                              size_t ctext_len,
                                                              const SignalIdentityKeyStore *identity_store,
                                                              void *ctx);
 
+SignalFfiError *signal_notification_decrypt(SignalNotificationDecryptionResult **out,
+                                           const unsigned char *message,
+                                           size_t message_len,
+                                           const SignalPublicKey *trust_root,
+                                           uint64_t timestamp,
+                                           const char *local_e164,
+                                           const char *local_uuid,
+                                           uint32_t local_device_id,
+                                           const SignalSessionStore *session_store,
+                                           const SignalIdentityKeyStore *identity_store,
+                                           const SignalPreKeyStore *prekey_store,
+                                           const SignalSignedPreKeyStore *signed_prekey_store,
+                                           const SignalRng *rng,
+                                           void *ctx);
+
+SignalFfiError *signal_notification_decryption_result_get_sender_uuid(const char **out,
+                                                                      const SignalNotificationDecryptionResult *result);
+
+SignalFfiError *signal_notification_decryption_result_get_sender_e164(const char **out,
+                                                                      const SignalNotificationDecryptionResult *result);
+
+SignalFfiError *signal_notification_decryption_result_get_device_id(uint32_t *out,
+                                                                    const SignalNotificationDecryptionResult *result);
+
+SignalFfiError *signal_notification_decryption_result_get_message(const unsigned char **out,
+                                                                  size_t *out_len,
+                                                                  const SignalNotificationDecryptionResult *result);
+
+SignalFfiError *signal_notification_decryption_result_get_commit_set(const unsigned char **out,
+                                                                     size_t *out_len,
+                                                                     const SignalNotificationDecryptionResult *result);
+
+SignalFfiError *signal_notification_commit_set_apply(const unsigned char *commit_set,
+                                                     size_t commit_set_len,
+                                                     const SignalSessionStore *session_store,
+                                                     const SignalIdentityKeyStore *identity_store,
+                                                     const SignalPreKeyStore *prekey_store,
+                                                     void *ctx);
+
+SignalFfiError *signal_deferred_decryption_result_get_plaintext(const unsigned char **out,
+                                                                size_t *out_len,
+                                                                const SignalDeferredDecryptionResult *obj);
+
+SignalFfiError *signal_deferred_decryption_result_get_mutation_log(const unsigned char **out,
+                                                                   size_t *out_len,
+                                                                   const SignalDeferredDecryptionResult *obj);
+
+SignalFfiError *signal_decrypt_message_deferred(SignalDeferredDecryptionResult **out,
+                                                const SignalMessage *message,
+                                                const SignalProtocolAddress *protocol_address,
+                                                const SignalSessionStore *session_store,
+                                                const SignalIdentityKeyStore *identity_key_store,
+                                                const SignalRng *rng,
+                                                void *ctx);
+
+SignalFfiError *signal_decrypt_pre_key_message_deferred(SignalDeferredDecryptionResult **out,
+                                                        const SignalPreKeySignalMessage *message,
+                                                        const SignalProtocolAddress *protocol_address,
+                                                        const SignalSessionStore *session_store,
+                                                        const SignalIdentityKeyStore *identity_key_store,
+                                                        const SignalPreKeyStore *prekey_store,
+                                                        const SignalSignedPreKeyStore *signed_prekey_store,
+                                                        const SignalRng *rng,
+                                                        void *ctx);
+
+SignalFfiError *signal_group_decrypt_message_deferred(SignalDeferredDecryptionResult **out,
+                                                      const SignalSenderKeyName *sender_key_name,
+                                                      const unsigned char *message,
+                                                      size_t message_len,
+                                                      const SignalSenderKeyStore *store,
+                                                      void *ctx);
+
+SignalFfiError *signal_mutation_log_apply(const unsigned char *mutation_log,
+                                          size_t mutation_log_len,
+                                          const SignalSessionStore *session_store,
+                                          const SignalIdentityKeyStore *identity_store,
+                                          const SignalPreKeyStore *prekey_store,
+                                          const SignalSignedPreKeyStore *signed_prekey_store,
+                                          const SignalSenderKeyStore *sender_key_store,
+                                          void *ctx);
+
 SignalFfiError *signal_sender_key_distribution_message_create(SignalSenderKeyDistributionMessage **out,
                                                               const SignalSenderKeyName *sender_key_name,
                                                               const SignalSenderKeyStore *store,
+                                                              const SignalRng *rng,
                                                               void *ctx);
 
 SignalFfiError *signal_process_sender_key_distribution_message(const SignalSenderKeyName *sender_key_name,
@@ -909,6 +1978,7 @@ SignalFfiError *signal_group_encrypt_message(const unsigned char **out,
                                              const unsigned char *message,
                                              size_t message_len,
                                              const SignalSenderKeyStore *store,
+                                             const SignalRng *rng,
                                              void *ctx);
 
 SignalFfiError *signal_group_decrypt_message(const unsigned char **out,
@@ -919,6 +1989,217 @@ SignalFfiError *signal_group_decrypt_message(const unsigned char **out,
                                              const SignalSenderKeyStore *store,
                                              void *ctx);
 
+SignalFfiError *signal_group_chain_position(uint32_t *out,
+                                            const SignalSenderKeyName *sender_key_name,
+                                            uint32_t distribution_id,
+                                            const SignalSenderKeyStore *store,
+                                            void *ctx);
+
+SignalFfiError *signal_group_fast_forward(const SignalSenderKeyName *sender_key_name,
+                                          uint32_t distribution_id,
+                                          uint32_t to_iteration,
+                                          const SignalSenderKeyStore *store,
+                                          void *ctx);
+
+SignalFfiError *signal_security_snapshot(const char **out,
+                                         const SignalProtocolAddress *address,
+                                         const SignalSenderKeyName *sender_key_name,
+                                         const SignalSessionStore *session_store,
+                                         const SignalIdentityKeyStore *identity_key_store,
+                                         const SignalSenderKeyStore *sender_key_store,
+                                         void *ctx);
+
+SignalFfiError *signal_decryption_metrics_set_enabled(bool enabled);
+
+SignalFfiError *signal_decryption_metrics_get(const char **out);
+
+SignalFfiError *signal_decryption_metrics_reset(void);
+
+SignalFfiError *signal_pre_key_bundle_debug_dump(const char **out,
+                                                 const SignalPreKeyBundle *obj,
+                                                 bool include_secrets);
+
+SignalFfiError *signal_pre_key_signal_message_debug_dump(const char **out,
+                                                         const SignalPreKeySignalMessage *obj,
+                                                         bool include_secrets);
+
+SignalFfiError *signal_sender_key_distribution_message_debug_dump(const char **out,
+                                                                  const SignalSenderKeyDistributionMessage *obj,
+                                                                  bool include_secrets);
+
+SignalFfiError *signal_sender_key_message_debug_dump(const char **out,
+                                                     const SignalSenderKeyMessage *obj,
+                                                     bool include_secrets);
+
+SignalFfiError *signal_sender_key_record_debug_dump(const char **out,
+                                                    const SignalSenderKeyRecord *obj,
+                                                    bool include_secrets);
+
+SignalFfiError *signal_session_record_debug_dump(const char **out,
+                                                 const SignalSessionRecord *obj,
+                                                 bool include_secrets);
+
+SignalFfiError *signal_message_debug_dump(const char **out,
+                                          const SignalMessage *obj,
+                                          bool include_secrets);
+
+SignalFfiError *signal_registration_request_destroy(SignalRegistrationRequest *p);
+
+SignalFfiError *signal_registration_request_get_method(const char **out,
+                                                       const SignalRegistrationRequest *obj);
+
+SignalFfiError *signal_registration_request_get_path(const char **out,
+                                                     const SignalRegistrationRequest *obj);
+
+SignalFfiError *signal_registration_request_get_body(const unsigned char **out,
+                                                     size_t *out_len,
+                                                     const SignalRegistrationRequest *obj);
+
+SignalFfiError *signal_registration_request_create_session(SignalRegistrationRequest **out,
+                                                           const char *number,
+                                                           const char *push_token,
+                                                           bool push_token_is_apn,
+                                                           const char *mcc,
+                                                           const char *mnc);
+
+SignalFfiError *signal_registration_request_get_session(SignalRegistrationRequest **out,
+                                                        const char *session_id);
+
+SignalFfiError *signal_registration_request_update_session(SignalRegistrationRequest **out,
+                                                           const char *session_id,
+                                                           const char *push_challenge,
+                                                           const char *captcha);
+
+SignalFfiError *signal_registration_request_request_verification_code(SignalRegistrationRequest **out,
+                                                                      const char *session_id,
+                                                                      bool voice,
+                                                                      const char *client);
+
+SignalFfiError *signal_registration_request_submit_verification_code(SignalRegistrationRequest **out,
+                                                                     const char *session_id,
+                                                                     const char *code);
+
+SignalFfiError *signal_registration_request_register_account(SignalRegistrationRequest **out,
+                                                             const char *session_id,
+                                                             uint32_t registration_id,
+                                                             bool fetches_messages,
+                                                             const char *registration_lock);
+
+SignalFfiError *signal_registration_session_destroy(SignalRegistrationSession *p);
+
+SignalFfiError *signal_registration_session_get_id(const char **out,
+                                                   const SignalRegistrationSession *obj);
+
+SignalFfiError *signal_registration_session_get_next_sms_seconds(uint32_t *out,
+                                                                 const SignalRegistrationSession *obj);
+
+SignalFfiError *signal_registration_session_get_next_call_seconds(uint32_t *out,
+                                                                  const SignalRegistrationSession *obj);
+
+SignalFfiError *signal_registration_session_get_next_verification_attempt_seconds(uint32_t *out,
+                                                                                  const SignalRegistrationSession *obj);
+
+SignalFfiError *signal_registration_session_get_allowed_to_request_code(bool *out,
+                                                                        const SignalRegistrationSession *obj);
+
+SignalFfiError *signal_registration_session_get_verified(bool *out,
+                                                         const SignalRegistrationSession *obj);
+
+SignalFfiError *signal_registration_session_from_response(SignalRegistrationSession **out,
+                                                          uint32_t status,
+                                                          const unsigned char *body,
+                                                          size_t body_len,
+                                                          const char *retry_after);
+
+SignalFfiError *signal_registration_session_requires_push_challenge(bool *out,
+                                                                    const SignalRegistrationSession *session);
+
+SignalFfiError *signal_registration_session_requires_captcha(bool *out,
+                                                             const SignalRegistrationSession *session);
+
+SignalFfiError *signal_registration_outcome_destroy(SignalRegistrationOutcome *p);
+
+SignalFfiError *signal_registration_outcome_from_response(SignalRegistrationOutcome **out,
+                                                          uint32_t status,
+                                                          const unsigned char *body,
+                                                          size_t body_len,
+                                                          const char *retry_after);
+
+SignalFfiError *signal_registration_outcome_is_locked(bool *out,
+                                                      const SignalRegistrationOutcome *outcome);
+
+SignalFfiError *signal_registration_outcome_get_aci(const char **out,
+                                                    const SignalRegistrationOutcome *outcome);
+
+SignalFfiError *signal_registration_outcome_get_pni(const char **out,
+                                                    const SignalRegistrationOutcome *outcome);
+
+SignalFfiError *signal_registration_outcome_get_number(const char **out,
+                                                       const SignalRegistrationOutcome *outcome);
+
+SignalFfiError *signal_registration_outcome_get_lock_time_remaining_ms(uint64_t *out,
+                                                                       const SignalRegistrationOutcome *outcome);
+
+SignalFfiError *signal_registration_outcome_get_svr_username(const char **out,
+                                                             const SignalRegistrationOutcome *outcome);
+
+SignalFfiError *signal_registration_outcome_get_svr_password(const char **out,
+                                                             const SignalRegistrationOutcome *outcome);
+
+SignalFfiError *signal_registration_lock_token(const char **out,
+                                               const unsigned char *master_key,
+                                               size_t master_key_len);
+
+SignalFfiError *signal_rng_destroy(SignalRng *p);
+
+SignalFfiError *signal_rng_clone(SignalRng **new_obj, const SignalRng *obj);
+
+SignalFfiError *signal_rng_new_system(SignalRng **out);
+
+SignalFfiError *signal_rng_new_seeded(SignalRng **out, const unsigned char *seed, size_t seed_len);
+
+SignalFfiError *signal_self_test(const char **out);
+
+SignalFfiError *signal_test_panic(const char *message);
+
+SignalFfiError *signal_service_id_set_destroy(SignalServiceIdSet *p);
+
+SignalFfiError *signal_service_id_set_clone(SignalServiceIdSet **new_obj,
+                                            const SignalServiceIdSet *obj);
+
+SignalFfiError *signal_service_id_set_new(SignalServiceIdSet **out);
+
+SignalFfiError *signal_service_id_set_add_fixed_width_binary(SignalServiceIdSet *set,
+                                                             const unsigned char *list,
+                                                             size_t list_len);
+
+SignalFfiError *signal_service_id_set_len(uint32_t *out, const SignalServiceIdSet *set);
+
+SignalFfiError *signal_service_id_set_contains(bool *out,
+                                               const SignalServiceIdSet *set,
+                                               const unsigned char *service_id,
+                                               size_t service_id_len);
+
+SignalFfiError *signal_service_id_set_intersection(SignalServiceIdSet **out,
+                                                   const SignalServiceIdSet *set,
+                                                   const SignalServiceIdSet *other);
+
+SignalFfiError *signal_service_id_set_difference(SignalServiceIdSet **out,
+                                                 const SignalServiceIdSet *set,
+                                                 const SignalServiceIdSet *other);
+
+SignalFfiError *signal_service_id_set_get_page(const unsigned char **out,
+                                               size_t *out_len,
+                                               const SignalServiceIdSet *set,
+                                               uint32_t offset,
+                                               uint32_t limit);
+
+SignalFfiError *signal_tracing_install_log_subscriber(void);
+
+SignalFfiError *signal_version_info(const char **out);
+
+SignalFfiError *signal_require_at_least(uint32_t abi_revision);
+
 SignalFfiError *signal_device_transfer_generate_private_key(const unsigned char **out,
                                                             size_t *out_len);
 
@@ -927,6 +2208,7 @@ SignalFfiError *signal_device_transfer_generate_certificate(const unsigned char
                                                             const unsigned char *private_key,
                                                             size_t private_key_len,
                                                             const char *name,
-                                                            uint32_t days_to_expire);
+                                                            uint32_t days_to_expire,
+                                                            uint64_t now);
 
 #endif /* SIGNAL_FFI_H_ */
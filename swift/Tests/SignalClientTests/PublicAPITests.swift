@@ -4,7 +4,8 @@
 //
 
 import XCTest
-import SignalClient
+import SignalFfi
+@testable import SignalClient
 
 class PublicAPITests: TestCaseBase {
     func testHkdfSimple() {
@@ -72,6 +73,132 @@ class PublicAPITests: TestCaseBase {
         XCTAssertThrowsError(try gcm_siv.decrypt(ctext, ad, nonce))
     }
 
+    func testIncrementalMacValidator() throws {
+        let key = [UInt8](repeating: 1, count: 32)
+        let validator = try IncrementalMacValidator(key: key, chunkSize: 4, digest: [UInt8](repeating: 0, count: 64))
+        // Nothing is checked until a chunk is complete.
+        XCTAssert(try validator.update([1, 2, 3]))
+        XCTAssertEqual(validator.validatedLength, 0)
+        XCTAssertFalse(try validator.update([4, 5]))
+        XCTAssertEqual(validator.validatedLength, 0)
+        XCTAssertThrowsError(try validator.finalize())
+
+        XCTAssertThrowsError(try IncrementalMacValidator(key: key, chunkSize: 4, digest: [0, 1, 2]))
+    }
+
+    func testAes256KeyWrap() throws {
+        let kw = try Aes256KeyWrap(key: [UInt8](0..<32))
+        let wrapped = try kw.wrap([0x46, 0x6f, 0x72, 0x50, 0x61, 0x73, 0x69])
+        XCTAssertEqual(wrapped, [0x44, 0x3b, 0x17, 0x83, 0x7b, 0xb3, 0x93, 0x48,
+                                 0x61, 0x0d, 0x19, 0x20, 0x2d, 0xf8, 0xa1, 0xf9])
+        XCTAssertEqual(try kw.unwrap(wrapped), [0x46, 0x6f, 0x72, 0x50, 0x61, 0x73, 0x69])
+
+        var tampered = wrapped
+        tampered[0] ^= 1
+        XCTAssertThrowsError(try kw.unwrap(tampered)) {
+            guard case SignalError.invalidWrappedKey(_) = $0 else {
+                XCTFail("unexpected error: \($0)")
+                return
+            }
+        }
+    }
+
+    func testStorageServiceKey() throws {
+        let key = try StorageServiceKey(masterKey: [UInt8](0..<32))
+        XCTAssertEqual(key.storageKey, [0xd9, 0xfd, 0x23, 0xee, 0x99, 0xc1, 0x48, 0xad,
+                                        0x87, 0x9f, 0x62, 0x60, 0x92, 0x97, 0x13, 0x9e,
+                                        0x6a, 0x36, 0xf4, 0x15, 0xb1, 0xad, 0x77, 0x9d,
+                                        0x23, 0xc6, 0x87, 0xb1, 0xfa, 0x6d, 0x36, 0xd9])
+        let rewrapped = try StorageServiceKey(storageKey: key.storageKey)
+        XCTAssertEqual(rewrapped.manifestKey(version: 42), key.manifestKey(version: 42))
+
+        let plaintext = Array("storage record for testing".utf8)
+        let manifest = try key.encryptManifest(version: 7, plaintext)
+        XCTAssertEqual(try key.decryptManifest(version: 7, manifest), plaintext)
+        XCTAssertThrowsError(try key.decryptManifest(version: 8, manifest)) {
+            guard case SignalError.invalidCiphertext(_) = $0 else {
+                XCTFail("unexpected error: \($0)")
+                return
+            }
+        }
+
+        let rawId = [UInt8](0..<16)
+        let record = try key.encryptRecord(rawId: rawId, plaintext)
+        XCTAssertEqual(try key.decryptRecord(rawId: rawId, record), plaintext)
+        XCTAssertThrowsError(try key.decryptRecord(rawId: [1, 2, 3], record))
+    }
+
+    func testCallLinkRootKey() throws {
+        let key = try CallLinkRootKey(bytes: [UInt8](0..<16))
+        XCTAssertEqual(key.description, "bbbc-bdbf-bgbh-bkbm-bnbp-bqbr-bsbt-bxbz")
+        XCTAssertEqual(try CallLinkRootKey(key.description).bytes, key.bytes)
+        XCTAssertEqual(key.deriveRoomId(), [0xcd, 0x41, 0x5f, 0xfc, 0x58, 0x77, 0x11, 0x08,
+                                            0xcd, 0xbf, 0x02, 0x9b, 0x8d, 0x86, 0xee, 0x85,
+                                            0x7b, 0xe1, 0x67, 0xbf, 0x28, 0xdd, 0xa8, 0x7e,
+                                            0x3e, 0x59, 0x2e, 0x3f, 0x10, 0xeb, 0x1c, 0x9b])
+        XCTAssertNotEqual(key.deriveEraKey(eraId: "era-1"), key.deriveEraKey(eraId: "era-2"))
+
+        let generated = CallLinkRootKey.generate()
+        XCTAssertEqual(try CallLinkRootKey(generated.description).bytes, generated.bytes)
+
+        XCTAssertThrowsError(try CallLinkRootKey("abbc-bdbf-bgbh-bkbm-bnbp-bqbr-bsbt-bxbz")) {
+            guard case SignalError.invalidKey(_) = $0 else {
+                XCTFail("unexpected error: \($0)")
+                return
+            }
+        }
+    }
+
+    func testGroupCallEraKey() throws {
+        let eraKey = try groupCallEraKey(groupMasterKey: [UInt8](0..<32), eraId: "era-1")
+        XCTAssertEqual(eraKey, [0xdd, 0xb3, 0xcc, 0x5d, 0x65, 0x84, 0x18, 0x70,
+                                0x85, 0x92, 0xe5, 0x81, 0x4e, 0x84, 0x8d, 0x25,
+                                0x60, 0x1c, 0x9e, 0xad, 0x04, 0xdd, 0x40, 0x53,
+                                0x8c, 0xdc, 0xf8, 0x2e, 0x95, 0x1a, 0x89, 0x80])
+        XCTAssertThrowsError(try groupCallEraKey(groupMasterKey: [UInt8](0..<16), eraId: "era-1"))
+    }
+
+    func testServiceIdSet() throws {
+        func serviceId(kind: UInt8, _ last: UInt8) -> [UInt8] {
+            return [kind] + [UInt8](repeating: 0, count: 15) + [last]
+        }
+        let aci1 = serviceId(kind: 0, 1)
+        let aci2 = serviceId(kind: 0, 2)
+        let pni1 = serviceId(kind: 1, 1)
+
+        let contacts = ServiceIdSet()
+        try contacts.add(fixedWidthBinary: pni1 + aci2 + aci1)
+        XCTAssertEqual(contacts.count, 3)
+        XCTAssertTrue(try contacts.contains(fixedWidthBinary: aci2))
+        XCTAssertEqual(contacts.page(offset: 0, limit: 2), aci1 + aci2)
+        XCTAssertEqual(contacts.page(offset: 2, limit: 2), pni1)
+
+        let discovered = ServiceIdSet()
+        try discovered.add(fixedWidthBinary: aci2 + pni1)
+        XCTAssertEqual(contacts.intersection(discovered).page(offset: 0, limit: 10), aci2 + pni1)
+        XCTAssertEqual(contacts.subtracting(discovered).page(offset: 0, limit: 10), aci1)
+
+        XCTAssertThrowsError(try contacts.add(fixedWidthBinary: serviceId(kind: 2, 3)))
+        XCTAssertThrowsError(try contacts.add(fixedWidthBinary: aci1.dropLast()))
+        XCTAssertEqual(contacts.count, 3)
+    }
+
+    func testStickerCipher() throws {
+        let cipher = try StickerCipher(packKey: [UInt8](0..<32))
+        XCTAssertThrowsError(try cipher.decrypt([UInt8](repeating: 0, count: 96))) {
+            guard case SignalError.invalidCiphertext(_) = $0 else {
+                XCTFail("unexpected error: \($0)")
+                return
+            }
+        }
+        XCTAssertThrowsError(try StickerCipher(packKey: [UInt8](repeating: 0, count: 16)))
+
+        // Plaintext is withheld until the final block and MAC could have arrived.
+        let decryption = cipher.newDecryption()
+        XCTAssertEqual(try decryption.update([UInt8](repeating: 0, count: 64)), [])
+        XCTAssertThrowsError(try decryption.finalize())
+    }
+
     func testAddress() {
         let addr = try! ProtocolAddress(name: "addr1", deviceId: 5)
         XCTAssertEqual(addr.name, "addr1")
@@ -120,6 +247,157 @@ class PublicAPITests: TestCaseBase {
         XCTAssertEqual(shared_secret1, shared_secret2)
     }
 
+    func testUnidentifiedAccess() throws {
+        let profileKey = [UInt8](0..<32)
+        XCTAssertEqual(try deriveAccessKey(profileKey: profileKey),
+                       [0x0e, 0xbc, 0xb5, 0xde, 0xb5, 0x2c, 0x83, 0xbd, 0x08, 0xa8, 0xa9, 0x35, 0x18, 0x2c, 0x91, 0x99])
+        XCTAssertEqual(try blindServiceId("9d0652a3-dcc3-4d11-975f-74d61598733f", profileKey: profileKey).count, 32)
+        XCTAssertThrowsError(try deriveAccessKey(profileKey: [UInt8](repeating: 0, count: 16)))
+    }
+
+    func testSealedBox() throws {
+        let sk = PrivateKey.generate()
+        let message: [UInt8] = [1, 2, 3]
+
+        var sealed = sk.publicKey.seal(message)
+        XCTAssertEqual(sealed.count, message.count + 65)
+        XCTAssertEqual(try sk.open(sealed), message)
+        XCTAssertThrowsError(try PrivateKey.generate().open(sealed))
+
+        sealed[40] ^= 1
+        XCTAssertThrowsError(try sk.open(sealed)) {
+            guard case SignalError.invalidCiphertext(_) = $0 else {
+                XCTFail("unexpected error: \($0)")
+                return
+            }
+        }
+    }
+
+    func testPaymentAddress() {
+        let identityKeyPair = IdentityKeyPair.generate()
+        let address = Array("mobilecoin public address".utf8)
+
+        let signature = identityKeyPair.signPaymentAddress(address)
+        let identityKey = identityKeyPair.identityKey
+        XCTAssertTrue(try! identityKey.verifyPaymentAddress(address, signature: signature))
+        XCTAssertFalse(try! identityKey.verifyPaymentAddress(Array("another address".utf8), signature: signature))
+
+        let plainSignature = identityKeyPair.privateKey.generateSignature(message: address)
+        XCTAssertFalse(try! identityKey.verifyPaymentAddress(address, signature: plainSignature))
+    }
+
+    func testProvisioning() throws {
+        let linkedDevice = ProvisioningCipher()
+        let message = Array("account details for the new device".utf8)
+
+        let envelope = ProvisioningCipher.encrypt(message, for: linkedDevice.publicKey)
+        XCTAssertEqual(try linkedDevice.decrypt(envelope), message)
+        XCTAssertThrowsError(try ProvisioningCipher().decrypt(envelope)) {
+            guard case SignalError.invalidCiphertext(_) = $0 else {
+                XCTFail("unexpected error: \($0)")
+                return
+            }
+        }
+
+        let uuid = try ProvisioningUuid(uuid: "b7f8d8a0-5f1e-4b5e-9a5c-2e3c5d7f9a1b")
+        XCTAssertEqual(try ProvisioningUuid(bytes: uuid.serialize()).uuid, uuid.uuid)
+    }
+
+    func testOutgoingEnvelope() throws {
+        let destination = try ProtocolAddress(name: "+14151111111", deviceId: 2)
+        let payload = Array("sealed sender ciphertext".utf8)
+        let envelope = OutgoingEnvelope(destination: destination,
+                                        timestamp: 1_600_000_000_000,
+                                        contentHint: .resendable,
+                                        payload: payload)
+
+        let parsed = try OutgoingEnvelope(bytes: envelope.serialize())
+        XCTAssertEqual(parsed.destination.name, "+14151111111")
+        XCTAssertEqual(parsed.destination.deviceId, 2)
+        XCTAssertEqual(parsed.timestamp, 1_600_000_000_000)
+        XCTAssertEqual(parsed.contentHint, .resendable)
+        XCTAssertEqual(parsed.payload, payload)
+        XCTAssertEqual(parsed.serializedVersion, 1)
+
+        XCTAssertThrowsError(try OutgoingEnvelope(bytes: [0x0a, 0x05]))
+    }
+
+    func testDigests() {
+        XCTAssertEqual(Crc32.digest(Array("123456789".utf8)), 0xcbf43926)
+        let crc = Crc32()
+        crc.update(Array("1234".utf8))
+        crc.update(Array("56789".utf8))
+        XCTAssertEqual(crc.finalize(), 0xcbf43926)
+
+        let sha256: [UInt8] = [0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae, 0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61, 0xf2, 0x00, 0x15, 0xad]
+        XCTAssertEqual(Sha256.digest(Array("abc".utf8)), sha256)
+        let hash = Sha256()
+        hash.update(Array("a".utf8))
+        hash.update(Array("bc".utf8))
+        XCTAssertEqual(hash.finalize(), sha256)
+
+        XCTAssertEqual(Sha512.digest([UInt8]()).prefix(4), [0xcf, 0x83, 0xe1, 0x35])
+    }
+
+    func testArgon2id() throws {
+        let params = try Argon2idParams(memoryKib: 65536, iterations: 2, parallelism: 1)
+        XCTAssertEqual(params.memoryKib, 65536)
+        XCTAssertEqual(params.iterations, 2)
+        XCTAssertEqual(params.parallelism, 1)
+
+        let key = try params.deriveKey(password: Array("password".utf8), salt: Array("somesalt".utf8), outputLength: 32)
+        let expected: [UInt8] = [0x09, 0x31, 0x61, 0x15, 0xd5, 0xcf, 0x24, 0xed, 0x5a, 0x15, 0xa3, 0x1a, 0x3b, 0xa3, 0x26, 0xe5, 0xcf, 0x32, 0xed, 0xc2, 0x47, 0x02, 0x98, 0x7c, 0x02, 0xb6, 0x56, 0x6f, 0x61, 0x91, 0x3c, 0xf7]
+        XCTAssertEqual(key, expected)
+
+        XCTAssertThrowsError(try Argon2idParams(memoryKib: 4, iterations: 1, parallelism: 1))
+        XCTAssertEqual(try Argon2idParams.calibrate(targetDurationMs: 0, memoryKib: 64, parallelism: 1).iterations, 1)
+    }
+
+    func testScrypt() throws {
+        let params = try ScryptParams(logN: 10, r: 8, p: 16)
+        let key = try params.deriveKey(password: Array("password".utf8), salt: Array("NaCl".utf8), outputLength: 64)
+        XCTAssertEqual(key.prefix(8), [0xfd, 0xba, 0xbe, 0x1c, 0x9d, 0x34, 0x72, 0x00])
+
+        XCTAssertThrowsError(try ScryptParams(logN: 0, r: 8, p: 1))
+        XCTAssertEqual(try ScryptParams.calibrate(targetDurationMs: 0, r: 8, p: 1).logN, 10)
+    }
+
+    func testPaddingPolicy() throws {
+        let bucketed = try PaddingPolicy.bucketed(160)
+        XCTAssertEqual(try bucketed.paddedLength(159), 160)
+        XCTAssertEqual(try bucketed.paddedLength(160), 320)
+        XCTAssertEqual(try PaddingPolicy.padme.paddedLength(1000), 1024)
+        XCTAssertThrowsError(try PaddingPolicy.bucketed(0))
+
+        let message = Array("padded message".utf8)
+        for policy in [PaddingPolicy.none, bucketed, PaddingPolicy.padme] {
+            let padded = try policy.pad(message)
+            XCTAssertEqual(UInt32(padded.count), try policy.paddedLength(UInt32(message.count)))
+            XCTAssertEqual(try policy.unpad(padded), message)
+        }
+        XCTAssertThrowsError(try PaddingPolicy.padme.unpad([1, 2, 0, 0]))
+    }
+
+    func testContent() throws {
+        let attachment = try AttachmentPointer(cdnNumber: 2, cdnKey: "cdn-key", contentType: "image/jpeg", key: [UInt8](repeating: 1, count: 64), digest: [UInt8](repeating: 2, count: 32), size: 1000, width: 640, height: 480)
+        XCTAssertThrowsError(try AttachmentPointer(cdnNumber: 2, cdnKey: "cdn-key", contentType: "image/jpeg", key: [1, 2, 3], digest: [UInt8](repeating: 2, count: 32), size: 1000))
+
+        let builder = try DataMessageBuilder(timestamp: 1234)
+        XCTAssertThrowsError(try builder.build())
+        XCTAssertThrowsError(try builder.setBody(String(repeating: "x", count: 2049)))
+        XCTAssertThrowsError(try builder.setProfileKey([1, 2, 3]))
+        try builder.setBody("hello")
+        try builder.addAttachment(attachment)
+        try builder.setQuote(id: 1000, authorUuid: "9d0652a3-dcc3-4d11-975f-74d61598733f", text: nil)
+        builder.setExpireTimer(60)
+        XCTAssertFalse(try builder.build().serialize().isEmpty)
+
+        XCTAssertFalse(try Content.receipt(.read, timestamps: [1000, 1001]).serialize().isEmpty)
+        XCTAssertThrowsError(try Content.receipt(.read, timestamps: []))
+        XCTAssertFalse(try Content.typing(timestamp: 1234, started: true, groupId: [1, 2, 3]).serialize().isEmpty)
+        XCTAssertThrowsError(try Content.typing(timestamp: 0, started: false))
+    }
+
     func testFingerprint() {
 
         let ALICE_IDENTITY: [UInt8] = [0x05, 0x06, 0x86, 0x3b, 0xc6, 0x6d, 0x02, 0xb4, 0x0d, 0x27, 0xb8, 0xd4, 0x9c, 0xa7, 0xc0, 0x9e, 0x92, 0x39, 0x23, 0x6f, 0x9d, 0x7d, 0x25, 0xd6, 0xfc, 0xca, 0x5c, 0xe1, 0x3c, 0x70, 0x64, 0xd8, 0x68]
@@ -254,6 +532,30 @@ class PublicAPITests: TestCaseBase {
         XCTAssertEqual(b_ptext, [1, 2, 3])
     }
 
+    func testSenderKeyDistributionTracking() throws {
+        let sender = try ProtocolAddress(name: "+14159999111", deviceId: 4)
+        let group_id = try SenderKeyName(groupName: "summer camp", sender: sender)
+        let bob = try ProtocolAddress(name: "+14159999222", deviceId: 1)
+        let carol = try ProtocolAddress(name: "+14159999333", deviceId: 2)
+
+        let a_store = InMemorySignalProtocolStore()
+        _ = try SenderKeyDistributionMessage(name: group_id, store: a_store, context: NullContext())
+        let record = try a_store.loadSenderKey(name: group_id, context: NullContext())!
+
+        XCTAssertTrue(record.needsDistribution(to: bob))
+        try record.markDistributed(to: bob)
+        try record.markDistributed(to: carol)
+        XCTAssertFalse(record.needsDistribution(to: bob))
+
+        let reloaded = try SenderKeyRecord(bytes: record.serialize())
+        XCTAssertFalse(reloaded.needsDistribution(to: carol))
+        XCTAssertTrue(reloaded.forgetDistribution(to: carol))
+        XCTAssertTrue(reloaded.needsDistribution(to: carol))
+        XCTAssertFalse(reloaded.forgetDistribution(to: carol))
+
+        XCTAssertThrowsError(try SenderKeyRecord().markDistributed(to: bob))
+    }
+
     func testSenderCertificates() {
         let senderCertBits: [UInt8] = [
             0x0a, 0xcd, 0x01, 0x0a, 0x0c, 0x2b, 0x31, 0x34, 0x31, 0x35, 0x32, 0x32, 0x32, 0x32, 0x32, 0x32, 0x32, 0x10, 0x2a, 0x19,
@@ -315,6 +617,34 @@ class PublicAPITests: TestCaseBase {
         testRoundTrip(signedPreKeyRecord, serialize: { $0.serialize() }, deserialize: { try .init(bytes: $0) })
     }
 
+    func testImplausibleTimestamp() {
+        let keyPair = IdentityKeyPair.generate()
+        // A timestamp in milliseconds mistakenly scaled by 1000 again.
+        let timestamp = UInt64(Date().timeIntervalSince1970 * 1000) * 1000
+        XCTAssertThrowsError(try SignedPreKeyRecord(
+            id: 77,
+            timestamp: timestamp,
+            privateKey: keyPair.privateKey,
+            signature: keyPair.privateKey.generateSignature(message: keyPair.publicKey.serialize())
+        )) { error in
+            guard case SignalError.invalidArgument(_) = error else {
+                XCTFail("unexpected error: \(error)")
+                return
+            }
+        }
+    }
+
+    func testPanicIsReportedAsError() {
+        XCTAssertThrowsError(try checkError(signal_test_panic("contained panic"))) { error in
+            guard case SignalError.internalError(let message) = error else {
+                XCTFail("unexpected error: \(error)")
+                return
+            }
+            XCTAssert(message.contains("signal_test_panic"), message)
+            XCTAssert(message.contains("contained panic"), message)
+        }
+    }
+
     func testDeviceTransferKey() {
         let deviceKey = DeviceTransferKey.generate()
 
@@ -329,6 +659,108 @@ class PublicAPITests: TestCaseBase {
         let cert = deviceKey.generateCertificate("name", 30)
         XCTAssert(cert.count > 0)
         XCTAssertEqual(cert[0], 0x30)
+
+        let backdatedCert = deviceKey.generateCertificate("name", 30, now: Date(timeIntervalSince1970: 1615809600))
+        XCTAssert(backdatedCert.count > 0)
+        XCTAssertEqual(backdatedCert[0], 0x30)
+    }
+
+    func testBackupFrames() throws {
+        let key = [UInt8](repeating: 1, count: 32)
+        let frames: [[UInt8]] = [Array("account data".utf8), [], [UInt8](repeating: 7, count: 10000)]
+
+        let writer = try BackupFrameWriter(backupKey: key, compressed: true)
+        var stream = try frames.flatMap { try writer.append($0) }
+        stream += try writer.finish()
+
+        func readAll(_ input: ArraySlice<UInt8>) throws -> [[UInt8]] {
+            let reader = try BackupFrameReader(backupKey: key)
+            var result: [[UInt8]] = []
+            for offset in stride(from: input.startIndex, to: input.endIndex, by: 100) {
+                try reader.push(Array(input[offset..<min(offset + 100, input.endIndex)]))
+                while let frame = try reader.nextFrame() {
+                    result.append(frame)
+                }
+            }
+            try reader.finish()
+            XCTAssert(reader.isFinished)
+            return result
+        }
+
+        XCTAssertEqual(try readAll(stream[...]), frames)
+        XCTAssertThrowsError(try readAll(stream.dropLast())) { error in
+            guard case SignalError.truncatedBackup(_) = error else {
+                XCTFail("unexpected error: \(error)")
+                return
+            }
+        }
+    }
+
+    func testBackupBuilder() throws {
+        let key = [UInt8](repeating: 1, count: 32)
+        let builder = try BackupBuilder(backupKey: key, compressed: true, backupTime: Date())
+        var stream = try builder.addAccountData(profileKey: [UInt8](repeating: 0, count: 32), username: nil, givenName: "Alice", familyName: "")
+        stream += try builder.addSelfRecipient(id: 1)
+        stream += try builder.addContact(id: 2, uuid: UUID(), e164: nil)
+        stream += try builder.addChat(id: 10, recipientId: 2)
+        stream += try builder.addChatItem(chatId: 10, authorId: 1, dateSent: 1, text: "hello")
+        XCTAssertThrowsError(try builder.addChat(id: 11, recipientId: 3))
+        stream += try builder.finish()
+
+        let reader = try BackupFrameReader(backupKey: key)
+        try reader.push(stream)
+        var frameCount = 0
+        while try reader.nextFrame() != nil {
+            frameCount += 1
+        }
+        try reader.finish()
+        // The backup info frame, followed by one frame per record.
+        XCTAssertEqual(frameCount, 6)
+    }
+
+    func testKeyTransparencyRejectsBadResponses() throws {
+        let logKey = PrivateKey.generate().publicKey
+        for response in [[], [0xff, 0xff]] as [[UInt8]] {
+            XCTAssertThrowsError(try keyTransparencyVerifySearch(logKey: logKey, searchKey: Array("alice".utf8), response: response, lastTreeHead: nil)) {
+                guard case SignalError.keyTransparencyVerificationFailed(_) = $0 else {
+                    XCTFail("unexpected error: \($0)")
+                    return
+                }
+            }
+            XCTAssertThrowsError(try keyTransparencyVerifyDistinguished(logKey: logKey, response: response, lastTreeHead: nil))
+        }
+    }
+
+    func testRegistration() throws {
+        let request = try RegistrationRequest.getSession(sessionId: "abc")
+        XCTAssertEqual(request.method, "GET")
+        XCTAssertEqual(request.path, "/v1/verification/session/abc")
+        XCTAssertNil(request.body)
+        XCTAssertThrowsError(try RegistrationRequest.getSession(sessionId: "../abc"))
+
+        let body = Array(#"{"id":"abc","nextSms":30,"nextCall":null,"nextVerificationAttempt":null,"allowedToRequestCode":true,"requestedInformation":["captcha"],"verified":false}"#.utf8)
+        let session = try RegistrationSession(status: 200, body: body, retryAfter: nil)
+        XCTAssertEqual(session.id, "abc")
+        XCTAssertEqual(session.nextSmsSeconds, 30)
+        XCTAssertNil(session.nextCallSeconds)
+        XCTAssert(session.requiresCaptcha)
+        XCTAssertFalse(session.requiresPushChallenge)
+
+        XCTAssertThrowsError(try RegistrationSession(status: 429, body: [], retryAfter: "60")) {
+            guard case SignalError.rateLimited(_) = $0 else {
+                XCTFail("unexpected error: \($0)")
+                return
+            }
+        }
+
+        let locked = Array(#"{"timeRemaining":1000,"svr2Credentials":{"username":"user","password":"pass"}}"#.utf8)
+        let outcome = try RegistrationOutcome(status: 423, body: locked, retryAfter: nil)
+        XCTAssert(outcome.isLocked)
+        XCTAssertNil(outcome.aci)
+        XCTAssertEqual(outcome.lockTimeRemainingMs, 1000)
+        XCTAssertEqual(outcome.svrUsername, "user")
+
+        XCTAssertEqual(try registrationLockToken(masterKey: [UInt8](repeating: 0, count: 32)).count, 64)
     }
 
     static var allTests: [(String, (PublicAPITests) -> () throws -> Void)] {
@@ -336,12 +768,36 @@ class PublicAPITests: TestCaseBase {
             ("testAddreses", testAddress),
             ("testFingerprint", testFingerprint),
             ("testPkOperations", testPkOperations),
+            ("testUnidentifiedAccess", testUnidentifiedAccess),
+            ("testSealedBox", testSealedBox),
+            ("testPaymentAddress", testPaymentAddress),
+            ("testProvisioning", testProvisioning),
+            ("testOutgoingEnvelope", testOutgoingEnvelope),
+            ("testPaddingPolicy", testPaddingPolicy),
+            ("testContent", testContent),
+            ("testDigests", testDigests),
+            ("testArgon2id", testArgon2id),
+            ("testScrypt", testScrypt),
             ("testHkdfSimple", testHkdfSimple),
             ("testHkdfUsingRFCExample", testHkdfUsingRFCExample),
             ("testAesGcmSiv", testAesGcmSiv),
+            ("testIncrementalMacValidator", testIncrementalMacValidator),
+            ("testAes256KeyWrap", testAes256KeyWrap),
+            ("testStorageServiceKey", testStorageServiceKey),
+            ("testCallLinkRootKey", testCallLinkRootKey),
+            ("testGroupCallEraKey", testGroupCallEraKey),
+            ("testServiceIdSet", testServiceIdSet),
+            ("testStickerCipher", testStickerCipher),
             ("testGroupCipher", testGroupCipher),
+            ("testSenderKeyDistributionTracking", testSenderKeyDistributionTracking),
             ("testSenderCertifications", testSenderCertificates),
             ("testSerializationRoundTrip", testSerializationRoundTrip),
+            ("testImplausibleTimestamp", testImplausibleTimestamp),
+            ("testPanicIsReportedAsError", testPanicIsReportedAsError),
+            ("testBackupFrames", testBackupFrames),
+            ("testBackupBuilder", testBackupBuilder),
+            ("testKeyTransparencyRejectsBadResponses", testKeyTransparencyRejectsBadResponses),
+            ("testRegistration", testRegistration),
         ]
     }
 }
@@ -76,6 +76,9 @@ class SessionTests: TestCaseBase {
                                          context: NullContext())
 
         XCTAssertEqual(ctext_a.messageType, .preKey)
+        XCTAssertEqual(ctext_a.counter, 0)
+        XCTAssertEqual(ctext_a.previousCounter, 0)
+        XCTAssertEqual(ctext_a.messageVersion, 3)
 
         let ctext_b = try! PreKeySignalMessage(bytes: ctext_a.serialize())
 
@@ -111,6 +114,104 @@ class SessionTests: TestCaseBase {
         XCTAssertEqual(ptext2_a, ptext2_b)
     }
 
+    func testSessionCipherContent() throws {
+        let alice_address = try! ProtocolAddress(name: "+14151111111", deviceId: 1)
+        let bob_address = try! ProtocolAddress(name: "+14151111112", deviceId: 1)
+
+        let alice_store = InMemorySignalProtocolStore()
+        let bob_store = InMemorySignalProtocolStore()
+
+        initializeSessions(alice_store: alice_store, bob_store: bob_store, bob_address: bob_address)
+
+        let builder = try DataMessageBuilder(timestamp: 1234)
+        try builder.setBody("hello")
+        let content = try builder.build()
+
+        let ctext_a = try signalEncrypt(content: content,
+                                        for: bob_address,
+                                        sessionStore: alice_store,
+                                        identityStore: alice_store,
+                                        padding: PaddingPolicy.padme,
+                                        context: NullContext())
+        XCTAssertEqual(ctext_a.messageType, .preKey)
+
+        let padded = try signalDecryptPreKey(message: PreKeySignalMessage(bytes: ctext_a.serialize()),
+                                             from: alice_address,
+                                             sessionStore: bob_store,
+                                             identityStore: bob_store,
+                                             preKeyStore: bob_store,
+                                             signedPreKeyStore: bob_store,
+                                             context: NullContext())
+        XCTAssertEqual(try PaddingPolicy.padme.unpad(padded), content.serialize())
+    }
+
+    func testSessionCipherBatch() {
+        let alice_address = try! ProtocolAddress(name: "+14151111111", deviceId: 1)
+        let bob_address = try! ProtocolAddress(name: "+14151111112", deviceId: 1)
+
+        let alice_store = InMemorySignalProtocolStore()
+        let bob_store = InMemorySignalProtocolStore()
+
+        initializeSessions(alice_store: alice_store, bob_store: bob_store, bob_address: bob_address)
+
+        let ptext_a: [UInt8] = [8, 6, 7, 5, 3, 0, 9]
+
+        let ctexts_a = try! signalEncrypt(message: ptext_a,
+                                          for: [bob_address, bob_address],
+                                          sessionStore: alice_store,
+                                          identityStore: alice_store,
+                                          context: NullContext()).map { try! $0.get() }
+
+        XCTAssertEqual(ctexts_a.count, 2)
+        XCTAssertNotEqual(ctexts_a[0].serialize(), ctexts_a[1].serialize())
+
+        for ctext_a in ctexts_a {
+            XCTAssertEqual(ctext_a.messageType, .preKey)
+
+            let ptext_b = try! signalDecryptPreKey(message: PreKeySignalMessage(bytes: ctext_a.serialize()),
+                                                   from: alice_address,
+                                                   sessionStore: bob_store,
+                                                   identityStore: bob_store,
+                                                   preKeyStore: bob_store,
+                                                   signedPreKeyStore: bob_store,
+                                                   context: NullContext())
+
+            XCTAssertEqual(ptext_a, ptext_b)
+        }
+    }
+
+    func testSessionCipherBatchIsolatesFailures() {
+        let alice_address = try! ProtocolAddress(name: "+14151111111", deviceId: 1)
+        let bob_address = try! ProtocolAddress(name: "+14151111112", deviceId: 1)
+        let unknown_address = try! ProtocolAddress(name: "+14151111113", deviceId: 1)
+
+        let alice_store = InMemorySignalProtocolStore()
+        let bob_store = InMemorySignalProtocolStore()
+
+        initializeSessions(alice_store: alice_store, bob_store: bob_store, bob_address: bob_address)
+
+        let ptext_a: [UInt8] = [8, 6, 7, 5, 3, 0, 9]
+
+        let results = try! signalEncrypt(message: ptext_a,
+                                         for: [unknown_address, bob_address],
+                                         sessionStore: alice_store,
+                                         identityStore: alice_store,
+                                         context: NullContext())
+
+        XCTAssertEqual(results.count, 2)
+        XCTAssertThrowsError(try results[0].get())
+
+        let ctext_a = try! results[1].get()
+        let ptext_b = try! signalDecryptPreKey(message: PreKeySignalMessage(bytes: ctext_a.serialize()),
+                                               from: alice_address,
+                                               sessionStore: bob_store,
+                                               identityStore: bob_store,
+                                               preKeyStore: bob_store,
+                                               signedPreKeyStore: bob_store,
+                                               context: NullContext())
+        XCTAssertEqual(ptext_a, ptext_b)
+    }
+
     func testSessionCipherWithBadStore() {
         let alice_address = try! ProtocolAddress(name: "+14151111111", deviceId: 1)
         let bob_address = try! ProtocolAddress(name: "+14151111112", deviceId: 1)
@@ -148,6 +249,38 @@ class SessionTests: TestCaseBase {
         }
     }
 
+    func testSessionSetupWithIdentityOpsStore() {
+        let alice_address = try! ProtocolAddress(name: "+14151111111", deviceId: 1)
+        let bob_address = try! ProtocolAddress(name: "+14151111112", deviceId: 1)
+
+        let alice_store = IdentityOpsStore()
+        let bob_store = IdentityOpsStore()
+
+        initializeSessions(alice_store: alice_store, bob_store: bob_store, bob_address: bob_address)
+        XCTAssertEqual(alice_store.agreementCount, 1)
+
+        let ptext_a: [UInt8] = [8, 6, 7, 5, 3, 0, 9]
+
+        let ctext_a = try! signalEncrypt(message: ptext_a,
+                                         for: bob_address,
+                                         sessionStore: alice_store,
+                                         identityStore: alice_store,
+                                         context: NullContext())
+
+        let ctext_b = try! PreKeySignalMessage(bytes: ctext_a.serialize())
+
+        let ptext_b = try! signalDecryptPreKey(message: ctext_b,
+                                               from: alice_address,
+                                               sessionStore: bob_store,
+                                               identityStore: bob_store,
+                                               preKeyStore: bob_store,
+                                               signedPreKeyStore: bob_store,
+                                               context: NullContext())
+
+        XCTAssertEqual(ptext_a, ptext_b)
+        XCTAssertEqual(bob_store.agreementCount, 1)
+    }
+
     func testSealedSenderSession() throws {
         let alice_address = try! ProtocolAddress(name: "9d0652a3-dcc3-4d11-975f-74d61598733f", deviceId: 1)
         let bob_address = try! ProtocolAddress(name: "6838237D-02F6-4098-B110-698253D15961", deviceId: 1)
@@ -169,6 +302,15 @@ class SessionTests: TestCaseBase {
                                                  signerCertificate: server_cert,
                                                  signerKey: server_keys.privateKey)
 
+        XCTAssertFalse(try sender_cert.validate(trustRoot: trust_root.publicKey, time: 31338))
+        XCTAssertTrue(try sender_cert.validate(trustRoot: trust_root.publicKey, time: 31338, gracePeriod: 1))
+        do {
+            _ = try sender_cert.validate(trustRoot: trust_root.publicKey, time: 31339, gracePeriod: 1)
+            XCTFail("should have thrown")
+        } catch SignalError.sealedSenderCertificateExpired(_) {
+            // good
+        }
+
         let message = Array("2020 vision".utf8)
         let ciphertext = try sealedSenderEncrypt(message: message,
                                                  for: bob_address,
@@ -192,6 +334,176 @@ class SessionTests: TestCaseBase {
         XCTAssertEqual(plaintext.sender, sender_addr)
     }
 
+    func testSealedSenderCertificateProvider() throws {
+        class QueuedCertificateSource: SenderCertificateSource {
+            var certificates: [SenderCertificate]
+            var fetches = 0
+
+            init(_ certificates: [SenderCertificate]) {
+                self.certificates = certificates
+            }
+
+            func fetchSenderCertificate(context: StoreContext) throws -> SenderCertificate {
+                fetches += 1
+                return certificates.removeFirst()
+            }
+        }
+
+        let alice_address = try! ProtocolAddress(name: "9d0652a3-dcc3-4d11-975f-74d61598733f", deviceId: 1)
+        let bob_address = try! ProtocolAddress(name: "6838237D-02F6-4098-B110-698253D15961", deviceId: 1)
+
+        let alice_store = InMemorySignalProtocolStore()
+        let bob_store = InMemorySignalProtocolStore()
+
+        initializeSessions(alice_store: alice_store, bob_store: bob_store, bob_address: bob_address)
+
+        let trust_root = IdentityKeyPair.generate()
+        let server_keys = IdentityKeyPair.generate()
+        let server_cert = try! ServerCertificate(keyId: 1, publicKey: server_keys.publicKey, trustRoot: trust_root.privateKey)
+        let sender_addr = try! SealedSenderAddress(e164: nil,
+                                                   uuidString: alice_address.name,
+                                                   deviceId: 1)
+        func makeSenderCert(expiration: UInt64) -> SenderCertificate {
+            return try! SenderCertificate(sender: sender_addr,
+                                          publicKey: alice_store.identityKeyPair(context: NullContext()).publicKey,
+                                          expiration: expiration,
+                                          signerCertificate: server_cert,
+                                          signerKey: server_keys.privateKey)
+        }
+
+        let source = QueuedCertificateSource([makeSenderCert(expiration: 20000)])
+        let provider = SenderCertificateProvider(source: source,
+                                                 freshnessWindow: 1000,
+                                                 certificate: makeSenderCert(expiration: 10000))
+
+        XCTAssertEqual(try provider.get(validUntil: 5000, context: NullContext()).expiration, 10000)
+        XCTAssertEqual(source.fetches, 0)
+
+        // Within the freshness window, encrypting fetches a new certificate first.
+        let message = Array("2020 vision".utf8)
+        let ciphertext = try sealedSenderEncrypt(message: message,
+                                                 for: bob_address,
+                                                 from: provider,
+                                                 timestamp: 9500,
+                                                 sessionStore: alice_store,
+                                                 identityStore: alice_store,
+                                                 context: NullContext())
+        XCTAssertEqual(source.fetches, 1)
+
+        let recipient_addr = try! SealedSenderAddress(e164: nil, uuidString: bob_address.name, deviceId: 1)
+        let plaintext = try sealedSenderDecrypt(message: ciphertext,
+                                                from: recipient_addr,
+                                                trustRoot: trust_root.publicKey,
+                                                timestamp: 15000,
+                                                sessionStore: bob_store,
+                                                identityStore: bob_store,
+                                                preKeyStore: bob_store,
+                                                signedPreKeyStore: bob_store,
+                                                context: NullContext())
+
+        XCTAssertEqual(plaintext.message, message)
+        XCTAssertEqual(plaintext.sender, sender_addr)
+        XCTAssertEqual(try provider.get(validUntil: 15000, context: NullContext()).expiration, 20000)
+        XCTAssertEqual(source.fetches, 1)
+    }
+
+    func testNotificationDecrypt() throws {
+        let alice_address = try! ProtocolAddress(name: "9d0652a3-dcc3-4d11-975f-74d61598733f", deviceId: 1)
+        let bob_address = try! ProtocolAddress(name: "6838237D-02F6-4098-B110-698253D15961", deviceId: 1)
+
+        let alice_store = InMemorySignalProtocolStore()
+        let bob_store = InMemorySignalProtocolStore()
+
+        initializeSessions(alice_store: alice_store, bob_store: bob_store, bob_address: bob_address)
+
+        let trust_root = IdentityKeyPair.generate()
+        let server_keys = IdentityKeyPair.generate()
+        let server_cert = try! ServerCertificate(keyId: 1, publicKey: server_keys.publicKey, trustRoot: trust_root.privateKey)
+        let sender_addr = try! SealedSenderAddress(e164: nil,
+                                                   uuidString: alice_address.name,
+                                                   deviceId: 1)
+        let sender_cert = try! SenderCertificate(sender: sender_addr,
+                                                 publicKey: alice_store.identityKeyPair(context: NullContext()).publicKey,
+                                                 expiration: 31337,
+                                                 signerCertificate: server_cert,
+                                                 signerKey: server_keys.privateKey)
+
+        let message = Array("2020 vision".utf8)
+        let ciphertext = try sealedSenderEncrypt(message: message,
+                                                 for: bob_address,
+                                                 from: sender_cert,
+                                                 sessionStore: alice_store,
+                                                 identityStore: alice_store,
+                                                 context: NullContext())
+
+        let recipient_addr = try! SealedSenderAddress(e164: nil, uuidString: bob_address.name, deviceId: 1)
+        let result = try notificationDecrypt(message: ciphertext,
+                                             from: recipient_addr,
+                                             trustRoot: trust_root.publicKey,
+                                             timestamp: 31335,
+                                             sessionStore: bob_store,
+                                             identityStore: bob_store,
+                                             preKeyStore: bob_store,
+                                             signedPreKeyStore: bob_store,
+                                             context: NullContext())
+
+        XCTAssertEqual(result.message, message)
+        XCTAssertEqual(result.sender, sender_addr)
+        XCTAssertNil(try bob_store.loadSession(for: alice_address, context: NullContext()))
+
+        try applyNotificationCommitSet(result.commitSet,
+                                       sessionStore: bob_store,
+                                       identityStore: bob_store,
+                                       preKeyStore: bob_store,
+                                       context: NullContext())
+        XCTAssertNotNil(try bob_store.loadSession(for: alice_address, context: NullContext()))
+    }
+
+    func testDeferredDecrypt() throws {
+        let alice_address = try! ProtocolAddress(name: "+14151111111", deviceId: 1)
+        let bob_address = try! ProtocolAddress(name: "+14151111112", deviceId: 1)
+
+        let alice_store = InMemorySignalProtocolStore()
+        let bob_store = InMemorySignalProtocolStore()
+
+        initializeSessions(alice_store: alice_store, bob_store: bob_store, bob_address: bob_address)
+
+        let ptext_a: [UInt8] = [8, 6, 7, 5, 3, 0, 9]
+        let ctext_a = try signalEncrypt(message: ptext_a,
+                                        for: bob_address,
+                                        sessionStore: alice_store,
+                                        identityStore: alice_store,
+                                        context: NullContext())
+
+        let result = try signalDecryptPreKeyDeferred(message: PreKeySignalMessage(bytes: ctext_a.serialize()),
+                                                     from: alice_address,
+                                                     sessionStore: bob_store,
+                                                     identityStore: bob_store,
+                                                     preKeyStore: bob_store,
+                                                     signedPreKeyStore: bob_store,
+                                                     context: NullContext())
+        XCTAssertEqual(result.plaintext, ptext_a)
+        XCTAssertNil(try bob_store.loadSession(for: alice_address, context: NullContext()))
+
+        try applyMutationLog(result.mutationLog,
+                             sessionStore: bob_store,
+                             identityStore: bob_store,
+                             preKeyStore: bob_store,
+                             signedPreKeyStore: bob_store,
+                             senderKeyStore: bob_store,
+                             context: NullContext())
+        XCTAssertNotNil(try bob_store.loadSession(for: alice_address, context: NullContext()))
+
+        // Replaying the same log again is rejected, since the session has moved on.
+        XCTAssertThrowsError(try applyMutationLog(result.mutationLog,
+                                                  sessionStore: bob_store,
+                                                  identityStore: bob_store,
+                                                  preKeyStore: bob_store,
+                                                  signedPreKeyStore: bob_store,
+                                                  senderKeyStore: bob_store,
+                                                  context: NullContext()))
+    }
+
     func testArchiveSession() throws {
         let bob_address = try! ProtocolAddress(name: "+14151111112", deviceId: 1)
 
@@ -210,12 +522,65 @@ class SessionTests: TestCaseBase {
         XCTAssertFalse(session.hasCurrentState)
     }
 
+    func testSessionVersionNegotiation() throws {
+        let bob_address = try! ProtocolAddress(name: "+14151111112", deviceId: 1)
+
+        let alice_store = InMemorySignalProtocolStore()
+        let bob_store = InMemorySignalProtocolStore()
+
+        let bob_signed_pre_key = PrivateKey.generate()
+        let bob_identity_key_pair = try! bob_store.identityKeyPair(context: NullContext())
+        let bob_signed_pre_key_signature = bob_identity_key_pair.privateKey.generateSignature(message: bob_signed_pre_key.publicKey.serialize())
+
+        let bob_bundle = try! PreKeyBundle(registrationId: bob_store.localRegistrationId(context: NullContext()),
+                                           deviceId: 9,
+                                           signedPrekeyId: 3006,
+                                           signedPrekey: bob_signed_pre_key.publicKey,
+                                           signedPrekeySignature: bob_signed_pre_key_signature,
+                                           identity: bob_identity_key_pair.identityKey)
+        XCTAssertEqual(bob_bundle.minSessionVersion, 3)
+        XCTAssertEqual(bob_bundle.maxSessionVersion, 3)
+        XCTAssertThrowsError(try bob_bundle.withSessionVersions(min: 4, max: 3))
+
+        // A peer that also supports a newer version gets the newest one we support.
+        let newer_bundle = try bob_bundle.withSessionVersions(min: 3, max: 4)
+        XCTAssertEqual(newer_bundle.minSessionVersion, 3)
+        XCTAssertEqual(newer_bundle.maxSessionVersion, 4)
+
+        try processPreKeyBundle(newer_bundle,
+                                for: bob_address,
+                                sessionStore: alice_store,
+                                identityStore: alice_store,
+                                context: NullContext())
+        let session: SessionRecord! = try alice_store.loadSession(for: bob_address, context: NullContext())
+        XCTAssertEqual(session.sessionVersion, 3)
+        session.archiveCurrentState()
+        XCTAssertEqual(session.sessionVersion, 0)
+
+        // A peer that only supports newer versions is rejected.
+        let too_new_store = InMemorySignalProtocolStore()
+        XCTAssertThrowsError(try processPreKeyBundle(bob_bundle.withSessionVersions(min: 4, max: 5),
+                                                     for: bob_address,
+                                                     sessionStore: too_new_store,
+                                                     identityStore: too_new_store,
+                                                     context: NullContext()))
+        XCTAssertNil(try too_new_store.loadSession(for: bob_address, context: NullContext()))
+    }
+
     static var allTests: [(String, (SessionTests) -> () throws -> Void)] {
         return [
             ("testSessionCipher", testSessionCipher),
+            ("testSessionCipherContent", testSessionCipherContent),
+            ("testSessionCipherBatch", testSessionCipherBatch),
+            ("testSessionCipherBatchIsolatesFailures", testSessionCipherBatchIsolatesFailures),
             ("testSessionCipherWithBadStore", testSessionCipherWithBadStore),
+            ("testSessionSetupWithIdentityOpsStore", testSessionSetupWithIdentityOpsStore),
             ("testSealedSenderSession", testSealedSenderSession),
+            ("testSealedSenderCertificateProvider", testSealedSenderCertificateProvider),
+            ("testNotificationDecrypt", testNotificationDecrypt),
+            ("testDeferredDecrypt", testDeferredDecrypt),
             ("testArchiveSession", testArchiveSession),
+            ("testSessionVersionNegotiation", testSessionVersionNegotiation),
         ]
     }
 }
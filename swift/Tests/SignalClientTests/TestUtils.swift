@@ -13,3 +13,16 @@ class BadStore: InMemorySignalProtocolStore {
         throw Error.badness
     }
 }
+
+class IdentityOpsStore: InMemorySignalProtocolStore, IdentityKeyOpsStore {
+    var agreementCount = 0
+
+    func identityKey(context: StoreContext) throws -> IdentityKey {
+        return try identityKeyPair(context: context).identityKey
+    }
+
+    func calculateIdentityAgreement(with theirKey: PublicKey, context: StoreContext) throws -> [UInt8] {
+        agreementCount += 1
+        return try identityKeyPair(context: context).privateKey.keyAgreement(with: theirKey)
+    }
+}